@@ -8,6 +8,9 @@ import * as IDL from './idl/ontora_ai.json'; // Adjust path to your generated ID
 // Define the program ID (replace with your deployed program ID)
 const PROGRAM_ID = new PublicKey('YourProgramIdHere'); // Replace with actual program ID after deployment
 
+// Version of this client SDK; the program reports the oldest version it supports
+const CLIENT_VERSION: [number, number, number] = [1, 0, 0];
+
 // Define network (use 'devnet', 'testnet', or 'mainnet-beta')
 const NETWORK = WalletAdapterNetwork.Devnet;
 const RPC_ENDPOINT = clusterApiUrl(NETWORK);
@@ -20,9 +23,20 @@ interface OntoraAIClient {
   userAccount: PublicKey | null;
 }
 
+// Compare two [major, minor, patch] versions (negative if a < b)
+function compareVersions(a: number[], b: number[]): number {
+  for (let i = 0; i < 3; i++) {
+    if (a[i] !== b[i]) {
+      return a[i] - b[i];
+    }
+  }
+  return 0;
+}
+
 // Class to handle Ontora AI contract interactions
 class OntoraAI {
   private client: OntoraAIClient;
+  private versionChecked = false;
 
   constructor() {
     this.client = {
@@ -76,6 +90,23 @@ class OntoraAI {
     return this.client.wallet !== null && this.client.wallet.connected;
   }
 
+  // Refuse to submit transactions to a program that no longer supports this client
+  async checkProgramVersion(): Promise<void> {
+    if (this.versionChecked) {
+      return;
+    }
+
+    const info = await this.client.program.methods.getVersion().view();
+    const minClientVersion: number[] = info.minClientVersion;
+    if (compareVersions(CLIENT_VERSION, minClientVersion) < 0) {
+      throw new Error(
+        `Client version ${CLIENT_VERSION.join('.')} is older than the minimum ${minClientVersion.join('.')} ` +
+          `required by program version ${info.programVersion.join('.')}`
+      );
+    }
+    this.versionChecked = true;
+  }
+
   // Disconnect wallet
   async disconnect(): Promise<void> {
     if (this.client.wallet) {
@@ -93,6 +124,7 @@ class OntoraAI {
     }
 
     try {
+      await this.checkProgramVersion();
       const userAccountKeypair = Keypair.generate();
       const tx = await this.client.program.methods
         .initializeUser()
@@ -119,6 +151,8 @@ class OntoraAI {
     }
 
     try {
+      await this.checkProgramVersion();
+
       // Convert amount to BN (BigNumber) for on-chain precision
       const stakeAmount = new BN(amount);
 
@@ -159,6 +193,8 @@ class OntoraAI {
     }
 
     try {
+      await this.checkProgramVersion();
+
       // Derive PDA for user's stake account
       const [stakeAccountPda] = await PublicKey.findProgramAddress(
         [Buffer.from('stake'), this.client.userAccount!.toBuffer(), Buffer.from(agentId)],
@@ -196,6 +232,7 @@ class OntoraAI {
     }
 
     try {
+      await this.checkProgramVersion();
       const proposalKeypair = Keypair.generate();
       const votingDurationBN = new BN(votingDuration);
 
@@ -224,6 +261,8 @@ class OntoraAI {
     }
 
     try {
+      await this.checkProgramVersion();
+
       // Derive PDA for proposal account
       const [proposalPda] = await PublicKey.findProgramAddress(
         [Buffer.from('proposal'), Buffer.from(proposalId)],
@@ -254,6 +293,23 @@ class OntoraAI {
     }
   }
 
+  // List the agents a user has an open stake on. Each stake is marked by a
+  // StakeIndexEntry PDA, so this is a getProgramAccounts scan filtered on the
+  // entry's user field (right after the 8-byte discriminator).
+  async getStakedAgents(user: PublicKey = this.client.userAccount!): Promise<
+    { agentId: BN; stakedAmount: BN; stakedAt: BN; address: PublicKey }[]
+  > {
+    const entries = await this.client.program.account.stakeIndexEntry.all([
+      { memcmp: { offset: 8, bytes: user.toBase58() } },
+    ]);
+    return entries.map(({ publicKey, account }) => ({
+      agentId: account.agentId as BN,
+      stakedAmount: account.stakedAmount as BN,
+      stakedAt: account.stakedAt as BN,
+      address: publicKey,
+    }));
+  }
+
   // Fetch account data (example for stake account)
   async getStakeAccount(agentId: string): Promise<any> {
     if (!this.isConnected()) {
@@ -1,5 +1,6 @@
 import { PublicKey, SystemProgram, TransactionInstruction, LAMPORTS_PER_SOL } from '@solana/web3.js';
 import { BN, Program, Idl, utils as anchorUtils } from '@project-serum/anchor';
+import { IdlCoder } from '@project-serum/anchor/dist/cjs/coder/borsh/idl';
 import { TOKEN_PROGRAM_ID, getAssociatedTokenAddress, ASSOCIATED_TOKEN_PROGRAM_ID } from '@solana/spl-token';
 import * as borsh from 'borsh';
 import { Buffer } from 'buffer';
@@ -49,6 +50,15 @@ export class PdaUtils {
     return pda;
   }
 
+  // Generate PDA for the index entry marking a user's stake on an agent
+  static async getStakeIndexPda(user: PublicKey, agentId: BN, programId: PublicKey): Promise<PublicKey> {
+    const [pda, _] = await PublicKey.findProgramAddress(
+      [Buffer.from('stake-index'), user.toBuffer(), agentId.toArrayLike(Buffer, 'le', 8)],
+      programId
+    );
+    return pda;
+  }
+
   // Generate PDA for a reward pool
   static async getRewardPoolPda(programId: PublicKey): Promise<PublicKey> {
     const [pda, _] = await PublicKey.findProgramAddress(
@@ -345,3 +355,50 @@ export class GeneralUtils {
     return new BN(str);
   }
 }
+
+// A program event decoded from a "Program data:" log line
+export interface DecodedEvent {
+  name: string;
+  schemaVersion: number; // First byte of every event payload
+  data: any;
+}
+
+// Utility for decoding program events. Event layouts are append-only, so an
+// event emitted by a newer program may carry fields this client's IDL doesn't
+// know yet; those trailing bytes are ignored instead of failing the decode.
+export class EventUtils {
+  static readonly LOG_PREFIX = 'Program data: ';
+
+  // Decode one log line, or return null if it isn't an event in `idl`
+  static decodeEvent(idl: Idl, logLine: string): DecodedEvent | null {
+    if (!logLine.startsWith(EventUtils.LOG_PREFIX)) {
+      return null;
+    }
+    const payload = Buffer.from(logLine.slice(EventUtils.LOG_PREFIX.length), 'base64');
+    if (payload.length < 9) {
+      return null;
+    }
+
+    const discriminator = payload.subarray(0, 8);
+    for (const event of idl.events ?? []) {
+      const expected = Buffer.from(anchorUtils.sha256.hash(`event:${event.name}`), 'hex').subarray(0, 8);
+      if (!expected.equals(discriminator)) {
+        continue;
+      }
+      // Layout decoding reads only the fields it knows and leaves the rest
+      const layout = IdlCoder.typeDefLayout(
+        { name: event.name, type: { kind: 'struct', fields: event.fields as any } },
+        idl.types
+      );
+      return { name: event.name, schemaVersion: payload[8], data: layout.decode(payload.subarray(8)) };
+    }
+    return null;
+  }
+
+  // Decode every event in a transaction's logs
+  static decodeEvents(idl: Idl, logs: string[]): DecodedEvent[] {
+    return logs
+      .map((line) => EventUtils.decodeEvent(idl, line))
+      .filter((event): event is DecodedEvent => event !== null);
+  }
+}
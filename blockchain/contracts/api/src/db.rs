@@ -0,0 +1,307 @@
+//! Queries over the indexer's tables. They run unchanged on SQLite and
+//! Postgres: sums are cast back to BIGINT, which Postgres would widen.
+
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use ontora_client::{Activity, WalletEvent};
+use ontora_indexer::EVENT_TABLES;
+use solana_sdk::signature::Signature;
+use sqlx::any::{AnyArguments, AnyRow};
+use sqlx::query::Query;
+use sqlx::{Any, AnyPool, Row};
+
+use crate::error::ApiError;
+use crate::model::{Agent, Distribution, Position, Price, Proposal, ProposalStatus, RewardPool};
+
+// A value bound to a query assembled from optional filters
+enum Param {
+    Int(i64),
+    Text(String),
+}
+
+fn bind_all<'q>(mut query: Query<'q, Any, AnyArguments<'q>>, params: Vec<Param>) -> Query<'q, Any, AnyArguments<'q>> {
+    for param in params {
+        query = match param {
+            Param::Int(value) => query.bind(value),
+            Param::Text(value) => query.bind(value),
+        };
+    }
+    query
+}
+
+/// The newest slot any indexed row came from; 0 for an empty database.
+pub async fn indexed_slot(pool: &AnyPool) -> Result<i64, ApiError> {
+    let latest: Vec<String> =
+        EVENT_TABLES.iter().map(|table| format!("SELECT MAX(slot) AS slot FROM {table}")).collect();
+    let sql = format!("SELECT MAX(slot) FROM ({}) latest", latest.join(" UNION ALL "));
+    let slot: Option<i64> = sqlx::query_scalar(&sql).fetch_one(pool).await?;
+    Ok(slot.unwrap_or(0))
+}
+
+/// Which agents `agents` returns, in agent id order.
+#[derive(Clone, Debug, Default)]
+pub struct AgentFilter {
+    /// Only agents with a larger id.
+    pub after: Option<i64>,
+    pub agent_id: Option<i64>,
+    pub category: Option<String>,
+    pub verified: Option<bool>,
+    pub limit: i64,
+}
+
+fn agent(row: &AnyRow) -> Result<Agent, ApiError> {
+    let metadata: String = row.try_get("metadata")?;
+    let metadata: serde_json::Value = serde_json::from_str(&metadata).unwrap_or_default();
+    Ok(Agent {
+        agent_id: row.try_get("agent_id")?,
+        owner: row.try_get("owner")?,
+        name: metadata["name"].as_str().map(str::to_string),
+        description: metadata["description"].as_str().map(str::to_string),
+        performance_score: metadata["performance_score"].as_i64(),
+        category: row.try_get("category")?,
+        verified: row.try_get::<i64, _>("verified")? != 0,
+        total_staked: row.try_get("total_staked")?,
+        registered_at: row.try_get("registered_at")?,
+        updated_slot: row.try_get("updated_slot")?,
+    })
+}
+
+/// Agents with at least one indexed event, described by the latest one.
+pub async fn agents(pool: &AnyPool, filter: &AgentFilter) -> Result<Vec<Agent>, ApiError> {
+    let mut conditions = Vec::new();
+    let mut params = Vec::new();
+    if let Some(after) = filter.after {
+        params.push(Param::Int(after));
+        conditions.push(format!("g.agent_id > ${}", params.len()));
+    }
+    if let Some(agent_id) = filter.agent_id {
+        params.push(Param::Int(agent_id));
+        conditions.push(format!("g.agent_id = ${}", params.len()));
+    }
+    if let Some(category) = &filter.category {
+        params.push(Param::Text(category.clone()));
+        conditions.push(format!("p.category = ${}", params.len()));
+    }
+    if let Some(verified) = filter.verified {
+        params.push(Param::Int(verified as i64));
+        conditions.push(format!("COALESCE(p.verified, 0) = ${}", params.len()));
+    }
+    params.push(Param::Int(filter.limit));
+    let limit = params.len();
+    let conditions = if conditions.is_empty() { String::new() } else { format!("WHERE {}", conditions.join(" AND ")) };
+
+    let sql = format!(
+        "SELECT g.agent_id, g.registered_at, g.updated_slot, \
+             (SELECT latest.owner FROM agents latest WHERE latest.agent_id = g.agent_id \
+              ORDER BY latest.slot DESC, latest.event_index DESC LIMIT 1) AS owner, \
+             (SELECT latest.metadata FROM agents latest WHERE latest.agent_id = g.agent_id \
+              ORDER BY latest.slot DESC, latest.event_index DESC LIMIT 1) AS metadata, \
+             (SELECT CAST(COALESCE(SUM(CASE WHEN s.kind = 'deposit' THEN s.amount ELSE -s.amount END), 0) AS BIGINT) \
+              FROM stakes s WHERE s.agent_id = g.agent_id) AS total_staked, \
+             p.category, COALESCE(p.verified, 0) AS verified \
+         FROM (SELECT agent_id, MIN(timestamp) AS registered_at, MAX(slot) AS updated_slot \
+               FROM agents GROUP BY agent_id) g \
+         LEFT JOIN agent_profiles p ON p.agent_id = g.agent_id \
+         {conditions} ORDER BY g.agent_id LIMIT ${limit}"
+    );
+    let rows = bind_all(sqlx::query(&sql), params).fetch_all(pool).await?;
+    rows.iter().map(agent).collect()
+}
+
+/// Every agent `user` has staked on or claimed from, by agent id.
+pub async fn positions(pool: &AnyPool, user: &str) -> Result<BTreeMap<i64, Position>, ApiError> {
+    let mut positions: BTreeMap<i64, Position> = BTreeMap::new();
+    let stakes = sqlx::query(
+        "SELECT agent_id, CAST(SUM(CASE WHEN kind = 'deposit' THEN amount ELSE -amount END) AS BIGINT) AS staked, \
+         MAX(slot) AS slot FROM stakes WHERE user_pubkey = $1 GROUP BY agent_id",
+    )
+    .bind(user)
+    .fetch_all(pool)
+    .await?;
+    for row in stakes {
+        let agent_id: i64 = row.try_get("agent_id")?;
+        let position = positions.entry(agent_id).or_insert(Position { agent_id, ..Position::default() });
+        position.staked = row.try_get("staked")?;
+        position.updated_slot = position.updated_slot.max(row.try_get("slot")?);
+    }
+    let claims = sqlx::query(
+        "SELECT agent_id, CAST(SUM(amount) AS BIGINT) AS claimed, MAX(slot) AS slot \
+         FROM claims WHERE user_pubkey = $1 GROUP BY agent_id",
+    )
+    .bind(user)
+    .fetch_all(pool)
+    .await?;
+    for row in claims {
+        let agent_id: i64 = row.try_get("agent_id")?;
+        let position = positions.entry(agent_id).or_insert(Position { agent_id, ..Position::default() });
+        position.rewards_claimed = row.try_get("claimed")?;
+        position.updated_slot = position.updated_slot.max(row.try_get("slot")?);
+    }
+    Ok(positions)
+}
+
+/// Every indexed stake, unstake, claim and vote of `user`'s, oldest first.
+pub async fn wallet_events(pool: &AnyPool, user: &str) -> Result<Vec<WalletEvent>, ApiError> {
+    let rows = sqlx::query(
+        "SELECT slot, signature, event_index, kind, agent_id AS id, amount, NULL AS vote_option, timestamp \
+         FROM stakes WHERE user_pubkey = $1 \
+         UNION ALL SELECT slot, signature, event_index, 'claim' AS kind, agent_id AS id, amount, \
+             NULL AS vote_option, timestamp FROM claims WHERE user_pubkey = $1 \
+         UNION ALL SELECT slot, signature, event_index, 'vote' AS kind, proposal_id AS id, vote_weight AS amount, \
+             vote_option, timestamp FROM votes WHERE voter = $1 \
+         ORDER BY slot, event_index",
+    )
+    .bind(user)
+    .fetch_all(pool)
+    .await?;
+    rows.iter()
+        .map(|row| {
+            let id = row.try_get::<i64, _>("id")? as u64;
+            let amount = row.try_get::<i64, _>("amount")? as u64;
+            let activity = match row.try_get::<String, _>("kind")?.as_str() {
+                "deposit" => Activity::Stake { agent_id: id, amount },
+                "withdrawal" => Activity::Unstake { agent_id: id, amount },
+                "claim" => Activity::Claim { agent_id: id, amount },
+                _ => {
+                    let option = row.try_get::<Option<i64>, _>("vote_option")?.unwrap_or_default() as u8;
+                    Activity::Vote { proposal_id: id, option, weight: amount }
+                }
+            };
+            let signature: String = row.try_get("signature")?;
+            Ok(WalletEvent {
+                signature: Signature::from_str(&signature).map_err(|error| sqlx::Error::Decode(Box::new(error)))?,
+                index: row.try_get::<i64, _>("event_index")? as usize,
+                timestamp: row.try_get("timestamp")?,
+                activity,
+            })
+        })
+        .collect()
+}
+
+/// Proposals in id order. `now` decides which unfinalized ones have ended.
+pub async fn proposals(
+    pool: &AnyPool,
+    after: Option<i64>,
+    status: Option<ProposalStatus>,
+    now: i64,
+    limit: i64,
+) -> Result<Vec<Proposal>, ApiError> {
+    let mut conditions = vec!["c.kind = 'created'".to_string()];
+    let mut params = Vec::new();
+    if let Some(after) = after {
+        params.push(Param::Int(after));
+        conditions.push(format!("c.proposal_id > ${}", params.len()));
+    }
+    match status {
+        Some(ProposalStatus::Finalized) => conditions.push("f.proposal_id IS NOT NULL".to_string()),
+        Some(status) => {
+            params.push(Param::Int(now));
+            let comparison = if status == ProposalStatus::Active { ">" } else { "<=" };
+            conditions.push(format!(
+                "f.proposal_id IS NULL AND c.timestamp + c.voting_duration {comparison} ${}",
+                params.len()
+            ));
+        }
+        None => {}
+    }
+    params.push(Param::Int(limit));
+
+    let sql = format!(
+        "SELECT c.proposal_id, c.creator, c.title, c.timestamp AS created_at, \
+             c.timestamp + c.voting_duration AS voting_ends_at, f.result, f.vote_summary, \
+             (SELECT COUNT(*) FROM votes v WHERE v.proposal_id = c.proposal_id) AS votes_cast, \
+             (SELECT CAST(COALESCE(SUM(v.vote_weight), 0) AS BIGINT) FROM votes v \
+              WHERE v.proposal_id = c.proposal_id) AS vote_weight, \
+             (SELECT MAX(v.slot) FROM votes v WHERE v.proposal_id = c.proposal_id) AS vote_slot, \
+             COALESCE(f.slot, c.slot) AS slot \
+         FROM proposals c \
+         LEFT JOIN proposals f ON f.proposal_id = c.proposal_id AND f.kind = 'finalized' \
+         WHERE {} ORDER BY c.proposal_id LIMIT ${}",
+        conditions.join(" AND "),
+        params.len()
+    );
+    let rows = bind_all(sqlx::query(&sql), params).fetch_all(pool).await?;
+    rows.iter()
+        .map(|row| {
+            let result: Option<i64> = row.try_get("result")?;
+            let voting_ends_at: i64 = row.try_get("voting_ends_at")?;
+            let status = match result {
+                Some(_) => ProposalStatus::Finalized,
+                None if voting_ends_at > now => ProposalStatus::Active,
+                None => ProposalStatus::Ended,
+            };
+            let slot: i64 = row.try_get("slot")?;
+            let vote_slot: Option<i64> = row.try_get("vote_slot")?;
+            Ok(Proposal {
+                proposal_id: row.try_get("proposal_id")?,
+                creator: row.try_get::<Option<String>, _>("creator")?.unwrap_or_default(),
+                title: row.try_get::<Option<String>, _>("title")?.unwrap_or_default(),
+                status,
+                created_at: row.try_get("created_at")?,
+                voting_ends_at,
+                votes_cast: row.try_get("votes_cast")?,
+                vote_weight: row.try_get("vote_weight")?,
+                result,
+                vote_summary: row.try_get("vote_summary")?,
+                updated_slot: slot.max(vote_slot.unwrap_or(0)),
+            })
+        })
+        .collect()
+}
+
+pub async fn reward_pool(pool: &AnyPool) -> Result<RewardPool, ApiError> {
+    let totals = sqlx::query(
+        "SELECT CAST(COALESCE(SUM(total_amount), 0) AS BIGINT) AS distributed, COUNT(*) AS distributions \
+         FROM reward_distributions",
+    )
+    .fetch_one(pool)
+    .await?;
+    let total_claimed: i64 = sqlx::query_scalar("SELECT CAST(COALESCE(SUM(amount), 0) AS BIGINT) FROM claims")
+        .fetch_one(pool)
+        .await?;
+    let last = sqlx::query(
+        "SELECT slot, timestamp, total_amount, eligible_count FROM reward_distributions \
+         ORDER BY slot DESC, event_index DESC LIMIT 1",
+    )
+    .fetch_optional(pool)
+    .await?;
+    let last_distribution = match last {
+        Some(row) => Some(Distribution {
+            slot: row.try_get("slot")?,
+            timestamp: row.try_get("timestamp")?,
+            total_amount: row.try_get("total_amount")?,
+            eligible_count: row.try_get("eligible_count")?,
+        }),
+        None => None,
+    };
+    Ok(RewardPool {
+        total_distributed: totals.try_get("distributed")?,
+        total_claimed,
+        distributions: totals.try_get("distributions")?,
+        last_distribution,
+    })
+}
+
+/// The latest price recorded for `symbol`, as the oracle registry spells it.
+pub async fn latest_price(pool: &AnyPool, symbol: &str) -> Result<Option<Price>, ApiError> {
+    let row = sqlx::query(
+        "SELECT symbol, feed, price, confidence, round, timestamp, slot FROM price_updates \
+         WHERE symbol = $1 ORDER BY slot DESC, event_index DESC LIMIT 1",
+    )
+    .bind(symbol)
+    .fetch_optional(pool)
+    .await?;
+    let Some(row) = row else {
+        return Ok(None);
+    };
+    Ok(Some(Price {
+        symbol: row.try_get("symbol")?,
+        feed: row.try_get("feed")?,
+        price: row.try_get("price")?,
+        confidence: row.try_get("confidence")?,
+        round: row.try_get("round")?,
+        timestamp: row.try_get("timestamp")?,
+        updated_slot: row.try_get("slot")?,
+    }))
+}
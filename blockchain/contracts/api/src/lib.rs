@@ -0,0 +1,67 @@
+//! REST API over the indexer database, so frontends query indexed platform
+//! state instead of scanning program accounts over RPC. The only writes are to
+//! notification subscriptions, which the indexer service delivers.
+//!
+//!     let pool = ontora_api::connect("sqlite://indexer.db").await?;
+//!     axum::serve(listener, ontora_api::router(pool)).await?;
+//!
+//! Every response is JSON in camelCase and carries `slot`, the newest slot the
+//! indexer has written, so clients can judge how fresh it is. Lists come a page
+//! at a time: pass a page's `nextCursor` as `cursor` to get the one after it.
+//!
+//!     GET /agents?category=&verified=&cursor=&limit=
+//!     GET /agents/:id
+//!     GET /users/:pubkey/positions?cursor=&limit=
+//!     GET /users/:pubkey/timeline?cursor=&limit=
+//!     GET /proposals?status=active|ended|finalized&cursor=&limit=
+//!     GET /rewards/pool
+//!     GET /prices/:symbol
+//!     POST /subscriptions
+//!     GET /subscriptions?wallet=&cursor=&limit=
+//!     GET|PUT|DELETE /subscriptions/:id
+//!
+//! The server does not authenticate callers: run it behind a gateway that
+//! does before exposing the subscription endpoints.
+
+pub mod db;
+pub mod error;
+pub mod model;
+pub mod routes;
+
+use axum::routing::get;
+use axum::Router;
+use ontora_indexer::store::MIGRATOR;
+use sqlx::any::AnyPoolOptions;
+use sqlx::AnyPool;
+
+pub use error::ApiError;
+pub use model::{
+    Agent, Distribution, Item, NewSubscription, Page, Position, Price, Proposal, ProposalStatus, RewardPool,
+    Subscription, SubscriptionRule, TimelineEntry,
+};
+
+/// Connects to the indexer database at `url` (`sqlite:` or `postgres:`),
+/// creating its schema if the indexer has not yet.
+pub async fn connect(url: &str) -> Result<AnyPool, ApiError> {
+    sqlx::any::install_default_drivers();
+    let pool = AnyPoolOptions::new().connect(url).await?;
+    MIGRATOR.run(&pool).await?;
+    Ok(pool)
+}
+
+pub fn router(pool: AnyPool) -> Router {
+    Router::new()
+        .route("/agents", get(routes::list_agents))
+        .route("/agents/:id", get(routes::get_agent))
+        .route("/users/:pubkey/positions", get(routes::user_positions))
+        .route("/users/:pubkey/timeline", get(routes::user_timeline))
+        .route("/proposals", get(routes::list_proposals))
+        .route("/rewards/pool", get(routes::reward_pool))
+        .route("/prices/:symbol", get(routes::latest_price))
+        .route("/subscriptions", get(routes::list_subscriptions).post(routes::create_subscription))
+        .route(
+            "/subscriptions/:id",
+            get(routes::get_subscription).put(routes::update_subscription).delete(routes::delete_subscription),
+        )
+        .with_state(pool)
+}
@@ -0,0 +1,33 @@
+// main.rs
+// Serves the indexer database over HTTP. Point it at the database the indexer writes
+// (INDEXER_DATABASE_URL); it creates the schema if missing and otherwise only reads.
+//
+//     ontora-api --database-url sqlite://indexer.db --listen 0.0.0.0:8081
+
+use std::net::SocketAddr;
+
+use clap::Parser;
+use tokio::net::TcpListener;
+
+/// Serve indexed platform state as JSON.
+#[derive(Debug, Parser)]
+#[command(name = "ontora-api", version)]
+struct Cli {
+    /// SQLite or Postgres database the indexer writes to
+    #[arg(long, env = "INDEXER_DATABASE_URL")]
+    database_url: String,
+    /// Address to listen on
+    #[arg(long, env = "ONTORA_API_LISTEN", default_value = "127.0.0.1:8081")]
+    listen: SocketAddr,
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let cli = Cli::parse();
+    let pool = ontora_api::connect(&cli.database_url).await?;
+    let listener = TcpListener::bind(cli.listen).await?;
+    // Only the listen address: the database URL may carry credentials
+    println!("serving on http://{}", listener.local_addr()?);
+    axum::serve(listener, ontora_api::router(pool)).await?;
+    Ok(())
+}
@@ -0,0 +1,173 @@
+//! Response bodies. Fields are camelCase; amounts are in base units, and
+//! timestamps are Unix seconds as the program recorded them.
+
+use serde::{Deserialize, Serialize};
+
+/// A single resource, with the newest slot the indexer has written so
+/// clients can judge how fresh it is.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Item<T> {
+    pub data: T,
+    pub slot: i64,
+}
+
+/// One page of a list. `next_cursor` is passed back as `cursor` for the page
+/// after it, and is null on the last page.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Page<T> {
+    pub data: Vec<T>,
+    pub next_cursor: Option<String>,
+    pub slot: i64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Agent {
+    pub agent_id: i64,
+    pub owner: String,
+    /// From the metadata of the agent's latest event; null when it is not
+    /// the program's JSON.
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub performance_score: Option<i64>,
+    /// Operator-maintained, not recorded on chain.
+    pub category: Option<String>,
+    pub verified: bool,
+    pub total_staked: i64,
+    pub registered_at: i64,
+    /// Slot of the agent's latest event.
+    pub updated_slot: i64,
+}
+
+/// A user's stake and claimed rewards on one agent.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Position {
+    pub agent_id: i64,
+    pub staked: i64,
+    pub rewards_claimed: i64,
+    pub updated_slot: i64,
+}
+
+/// One of a wallet's events, with its stake across every agent and its
+/// claimed rewards after it.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimelineEntry {
+    /// Position in the wallet's timeline, oldest first.
+    pub sequence: i64,
+    /// `stake`, `unstake`, `claim` or `vote`.
+    pub kind: String,
+    pub agent_id: Option<i64>,
+    pub proposal_id: Option<i64>,
+    pub vote_option: Option<i64>,
+    /// Tokens moved, or the vote's weight.
+    pub amount: i64,
+    pub staked: i64,
+    pub claimed: i64,
+    /// Set when events are missing before this one.
+    pub gap: Option<String>,
+    pub signature: String,
+    pub event_index: i64,
+    pub timestamp: i64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ProposalStatus {
+    /// Open for votes.
+    Active,
+    /// Voting closed, not finalized yet.
+    Ended,
+    Finalized,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Proposal {
+    pub proposal_id: i64,
+    pub creator: String,
+    pub title: String,
+    pub status: ProposalStatus,
+    pub created_at: i64,
+    /// When voting closes, not counting time the proposal spent suspended.
+    pub voting_ends_at: i64,
+    pub votes_cast: i64,
+    pub vote_weight: i64,
+    /// Set once finalized.
+    pub result: Option<i64>,
+    pub vote_summary: Option<String>,
+    pub updated_slot: i64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Distribution {
+    pub slot: i64,
+    pub timestamp: i64,
+    pub total_amount: i64,
+    pub eligible_count: i64,
+}
+
+/// Reward totals over every indexed distribution and claim.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RewardPool {
+    pub total_distributed: i64,
+    pub total_claimed: i64,
+    pub distributions: i64,
+    pub last_distribution: Option<Distribution>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Price {
+    pub symbol: String,
+    pub feed: String,
+    /// Raw price, scaled by the feed's decimals.
+    pub price: i64,
+    pub confidence: i64,
+    pub round: i64,
+    pub timestamp: i64,
+    pub updated_slot: i64,
+}
+
+/// A wallet's notification rule. `secret` signs its webhook deliveries and is
+/// only returned when the subscription is created.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Subscription {
+    pub id: String,
+    pub wallet: String,
+    pub event_types: Vec<String>,
+    /// Smallest amount that notifies, for events that carry an amount.
+    pub min_amount: Option<u64>,
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+    pub created_at: i64,
+}
+
+/// Body of `POST /subscriptions`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewSubscription {
+    pub wallet: String,
+    pub event_types: Vec<String>,
+    #[serde(default)]
+    pub min_amount: Option<u64>,
+    pub url: String,
+}
+
+/// Body of `PUT /subscriptions/:id`, replacing the rule. The wallet and
+/// secret stay.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubscriptionRule {
+    pub event_types: Vec<String>,
+    #[serde(default)]
+    pub min_amount: Option<u64>,
+    pub url: String,
+}
@@ -0,0 +1,288 @@
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::Json;
+use ontora_client::{Activity, Timeline};
+use ontora_indexer::{notify, NOTIFICATION_EVENTS};
+use rand::distributions::{Alphanumeric, DistString};
+use serde::Deserialize;
+use solana_sdk::pubkey::Pubkey;
+use sqlx::AnyPool;
+
+use crate::db::{self, AgentFilter};
+use crate::error::ApiError;
+use crate::model::{
+    Agent, Item, NewSubscription, Page, Position, Price, Proposal, ProposalStatus, RewardPool, Subscription,
+    SubscriptionRule, TimelineEntry,
+};
+
+fn now() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs() as i64)
+}
+
+/// Page size when a request does not ask for one.
+pub const DEFAULT_PAGE_SIZE: i64 = 50;
+pub const MAX_PAGE_SIZE: i64 = 200;
+
+fn page_size(limit: Option<i64>) -> Result<i64, ApiError> {
+    match limit {
+        None => Ok(DEFAULT_PAGE_SIZE),
+        Some(limit) if (1..=MAX_PAGE_SIZE).contains(&limit) => Ok(limit),
+        Some(limit) => Err(ApiError::BadRequest(format!("limit {limit} is not between 1 and {MAX_PAGE_SIZE}"))),
+    }
+}
+
+// Cursors are the id of the last item on the previous page
+fn after(cursor: Option<&str>) -> Result<Option<i64>, ApiError> {
+    cursor
+        .map(|cursor| cursor.parse().map_err(|_| ApiError::BadRequest(format!("invalid cursor {cursor:?}"))))
+        .transpose()
+}
+
+// Turns up to `limit + 1` rows into a page, with a cursor when there is more
+fn page<T, K: ToString>(mut rows: Vec<T>, limit: i64, id: impl Fn(&T) -> K, slot: i64) -> Page<T> {
+    let mut next_cursor = None;
+    if rows.len() as i64 > limit {
+        rows.truncate(limit as usize);
+        next_cursor = rows.last().map(|row| id(row).to_string());
+    }
+    Page { data: rows, next_cursor, slot }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AgentQuery {
+    pub category: Option<String>,
+    pub verified: Option<bool>,
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+}
+
+pub async fn list_agents(
+    State(pool): State<AnyPool>,
+    Query(query): Query<AgentQuery>,
+) -> Result<Json<Page<Agent>>, ApiError> {
+    let limit = page_size(query.limit)?;
+    let filter = AgentFilter {
+        after: after(query.cursor.as_deref())?,
+        agent_id: None,
+        category: query.category,
+        verified: query.verified,
+        limit: limit + 1,
+    };
+    let slot = db::indexed_slot(&pool).await?;
+    let agents = db::agents(&pool, &filter).await?;
+    Ok(Json(page(agents, limit, |agent| agent.agent_id, slot)))
+}
+
+pub async fn get_agent(State(pool): State<AnyPool>, Path(agent_id): Path<i64>) -> Result<Json<Item<Agent>>, ApiError> {
+    let filter = AgentFilter { agent_id: Some(agent_id), limit: 1, ..AgentFilter::default() };
+    let slot = db::indexed_slot(&pool).await?;
+    let agent = db::agents(&pool, &filter).await?.pop();
+    let agent = agent.ok_or_else(|| ApiError::NotFound(format!("agent {agent_id}")))?;
+    Ok(Json(Item { data: agent, slot }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PageQuery {
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+}
+
+fn pubkey(key: &str) -> Result<Pubkey, ApiError> {
+    Pubkey::from_str(key).map_err(|_| ApiError::BadRequest(format!("invalid public key {key:?}")))
+}
+
+pub async fn user_positions(
+    State(pool): State<AnyPool>,
+    Path(user): Path<String>,
+    Query(query): Query<PageQuery>,
+) -> Result<Json<Page<Position>>, ApiError> {
+    let user = pubkey(&user)?;
+    let limit = page_size(query.limit)?;
+    let after = after(query.cursor.as_deref())?.unwrap_or(i64::MIN);
+    let slot = db::indexed_slot(&pool).await?;
+    let positions = db::positions(&pool, &user.to_string()).await?;
+    let positions = positions.range(after.saturating_add(1)..).take(limit as usize + 1).map(|(_, position)| position);
+    Ok(Json(page(positions.cloned().collect(), limit, |position| position.agent_id, slot)))
+}
+
+/// The wallet's indexed events with its running balances. Balances are run
+/// over the whole timeline, so each page's carry on from the page before.
+pub async fn user_timeline(
+    State(pool): State<AnyPool>,
+    Path(user): Path<String>,
+    Query(query): Query<PageQuery>,
+) -> Result<Json<Page<TimelineEntry>>, ApiError> {
+    let user = pubkey(&user)?;
+    let limit = page_size(query.limit)?;
+    let after = after(query.cursor.as_deref())?.unwrap_or(-1);
+    let slot = db::indexed_slot(&pool).await?;
+    let timeline = Timeline::build(db::wallet_events(&pool, &user.to_string()).await?);
+    let entries = timeline.entries.iter().enumerate().skip(after.saturating_add(1).max(0) as usize);
+    let entries = entries.take(limit as usize + 1).map(|(sequence, entry)| {
+        let mut item = TimelineEntry {
+            sequence: sequence as i64,
+            kind: entry.event.activity.name().to_string(),
+            staked: entry.staked as i64,
+            claimed: entry.claimed as i64,
+            gap: entry.gap.map(|gap| gap.to_string()),
+            signature: entry.event.signature.to_string(),
+            event_index: entry.event.index as i64,
+            timestamp: entry.event.timestamp,
+            ..TimelineEntry::default()
+        };
+        match entry.event.activity {
+            Activity::Stake { agent_id, amount }
+            | Activity::Unstake { agent_id, amount }
+            | Activity::Claim { agent_id, amount } => {
+                (item.agent_id, item.amount) = (Some(agent_id as i64), amount as i64);
+            }
+            Activity::Vote { proposal_id, option, weight } => {
+                (item.proposal_id, item.vote_option, item.amount) =
+                    (Some(proposal_id as i64), Some(option as i64), weight as i64);
+            }
+        }
+        item
+    });
+    Ok(Json(page(entries.collect(), limit, |entry| entry.sequence, slot)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ProposalQuery {
+    pub status: Option<ProposalStatus>,
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+}
+
+pub async fn list_proposals(
+    State(pool): State<AnyPool>,
+    Query(query): Query<ProposalQuery>,
+) -> Result<Json<Page<Proposal>>, ApiError> {
+    let limit = page_size(query.limit)?;
+    let slot = db::indexed_slot(&pool).await?;
+    let proposals = db::proposals(&pool, after(query.cursor.as_deref())?, query.status, now(), limit + 1).await?;
+    Ok(Json(page(proposals, limit, |proposal| proposal.proposal_id, slot)))
+}
+
+pub async fn reward_pool(State(pool): State<AnyPool>) -> Result<Json<Item<RewardPool>>, ApiError> {
+    let slot = db::indexed_slot(&pool).await?;
+    Ok(Json(Item { data: db::reward_pool(&pool).await?, slot }))
+}
+
+/// `symbol` is matched the way the oracle registry normalizes it, and may
+/// separate its pair with `-` to keep the `/` out of the path: `sol-usd`
+/// finds `SOL/USD`.
+pub async fn latest_price(
+    State(pool): State<AnyPool>,
+    Path(symbol): Path<String>,
+) -> Result<Json<Item<Price>>, ApiError> {
+    let normalized = symbol.trim().to_ascii_uppercase().replace('-', "/");
+    let slot = db::indexed_slot(&pool).await?;
+    let price = db::latest_price(&pool, &normalized).await?;
+    let price = price.ok_or_else(|| ApiError::NotFound(format!("price for {symbol}")))?;
+    Ok(Json(Item { data: price, slot }))
+}
+
+fn subscription(subscription: notify::Subscription) -> Subscription {
+    Subscription {
+        id: subscription.id,
+        wallet: subscription.wallet.to_string(),
+        event_types: subscription.event_types,
+        min_amount: subscription.min_amount,
+        url: subscription.url,
+        secret: None,
+        created_at: subscription.created_at,
+    }
+}
+
+fn check_rule(event_types: &[String], url: &str) -> Result<(), ApiError> {
+    if event_types.is_empty() {
+        return Err(ApiError::BadRequest("eventTypes is empty".to_string()));
+    }
+    if let Some(unknown) = event_types.iter().find(|name| !NOTIFICATION_EVENTS.contains(&name.as_str())) {
+        let known = NOTIFICATION_EVENTS.join(", ");
+        return Err(ApiError::BadRequest(format!("unknown event type {unknown:?}, expected one of {known}")));
+    }
+    if !url.starts_with("https://") && !url.starts_with("http://") {
+        return Err(ApiError::BadRequest(format!("url {url:?} is not http or https")));
+    }
+    Ok(())
+}
+
+/// Creates a subscription with a new id and signing secret. The secret is
+/// in this response only.
+pub async fn create_subscription(
+    State(pool): State<AnyPool>,
+    Json(request): Json<NewSubscription>,
+) -> Result<(StatusCode, Json<Item<Subscription>>), ApiError> {
+    let wallet = pubkey(&request.wallet)?;
+    check_rule(&request.event_types, &request.url)?;
+    let created = notify::Subscription {
+        id: Alphanumeric.sample_string(&mut rand::thread_rng(), 16),
+        wallet,
+        event_types: request.event_types,
+        min_amount: request.min_amount,
+        url: request.url,
+        secret: Alphanumeric.sample_string(&mut rand::thread_rng(), 32),
+        created_at: now(),
+    };
+    notify::insert_subscription(&pool, &created).await?;
+    let slot = db::indexed_slot(&pool).await?;
+    let secret = Some(created.secret.clone());
+    Ok((StatusCode::CREATED, Json(Item { data: Subscription { secret, ..subscription(created) }, slot })))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SubscriptionQuery {
+    pub wallet: Option<String>,
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+}
+
+pub async fn list_subscriptions(
+    State(pool): State<AnyPool>,
+    Query(query): Query<SubscriptionQuery>,
+) -> Result<Json<Page<Subscription>>, ApiError> {
+    let wallet = query.wallet.as_deref().map(pubkey).transpose()?;
+    let limit = page_size(query.limit)?;
+    let slot = db::indexed_slot(&pool).await?;
+    let subscriptions = notify::list_subscriptions(&pool, wallet.as_ref(), query.cursor.as_deref(), limit + 1).await?;
+    let subscriptions = subscriptions.into_iter().map(subscription).collect();
+    Ok(Json(page(subscriptions, limit, |subscription: &Subscription| subscription.id.clone(), slot)))
+}
+
+pub async fn get_subscription(
+    State(pool): State<AnyPool>,
+    Path(id): Path<String>,
+) -> Result<Json<Item<Subscription>>, ApiError> {
+    let slot = db::indexed_slot(&pool).await?;
+    let found = notify::get_subscription(&pool, &id).await?;
+    let found = found.ok_or_else(|| ApiError::NotFound(format!("subscription {id}")))?;
+    Ok(Json(Item { data: subscription(found), slot }))
+}
+
+pub async fn update_subscription(
+    State(pool): State<AnyPool>,
+    Path(id): Path<String>,
+    Json(rule): Json<SubscriptionRule>,
+) -> Result<Json<Item<Subscription>>, ApiError> {
+    check_rule(&rule.event_types, &rule.url)?;
+    let found = notify::get_subscription(&pool, &id).await?;
+    let found = found.ok_or_else(|| ApiError::NotFound(format!("subscription {id}")))?;
+    let updated =
+        notify::Subscription { event_types: rule.event_types, min_amount: rule.min_amount, url: rule.url, ..found };
+    if !notify::update_subscription(&pool, &updated).await? {
+        return Err(ApiError::NotFound(format!("subscription {id}")));
+    }
+    let slot = db::indexed_slot(&pool).await?;
+    Ok(Json(Item { data: subscription(updated), slot }))
+}
+
+pub async fn delete_subscription(State(pool): State<AnyPool>, Path(id): Path<String>) -> Result<StatusCode, ApiError> {
+    if !notify::delete_subscription(&pool, &id).await? {
+        return Err(ApiError::NotFound(format!("subscription {id}")));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,461 @@
+// test_api.rs
+// Integration tests for the REST API. A SQLite file is seeded through the indexer's
+// `SqlSink`, the server is started over it on a local port, and each endpoint's JSON is
+// checked field by field, along with its pagination and the freshness slot.
+
+use ontora_ai::events::{
+    AgentRegistered, AgentUpdated, ProposalCreated, ProposalFinalized, RewardClaimed, RewardDistributed,
+    StakeDeposited, StakeWithdrawn, VoteCast,
+};
+use ontora_indexer::{EventRecord, EventSink, OntoraEvent, PriceUpdate, SqlSink};
+use serde_json::{json, Value};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use tempfile::TempDir;
+use tokio::runtime::Runtime;
+
+const TIMESTAMP: i64 = 1_700_000_000;
+// Highest slot seeded below
+const INDEXED_SLOT: i64 = 20;
+
+fn record(slot: u64, event: OntoraEvent) -> EventRecord {
+    EventRecord { slot, signature: Signature::new_unique(), index: 0, event }
+}
+
+fn agent(slot: u64, agent_id: u64, owner: Pubkey, name: &str) -> EventRecord {
+    let metadata = json!({ "name": name, "description": "an agent", "performance_score": agent_id * 10 }).to_string();
+    let event = match slot {
+        10 => OntoraEvent::AgentRegistered(AgentRegistered {
+            schema_version: AgentRegistered::SCHEMA_VERSION,
+            agent_id,
+            owner,
+            timestamp: TIMESTAMP,
+            metadata,
+        }),
+        _ => OntoraEvent::AgentUpdated(AgentUpdated {
+            schema_version: AgentUpdated::SCHEMA_VERSION,
+            agent_id,
+            owner,
+            timestamp: TIMESTAMP + 60,
+            new_metadata: metadata,
+        }),
+    };
+    record(slot, event)
+}
+
+fn deposit(slot: u64, user: Pubkey, agent_id: u64, amount: u64) -> EventRecord {
+    let event = StakeDeposited {
+        schema_version: StakeDeposited::SCHEMA_VERSION,
+        user,
+        agent_id,
+        amount,
+        timestamp: TIMESTAMP,
+        staking_duration: 0,
+    };
+    record(slot, OntoraEvent::StakeDeposited(event))
+}
+
+fn withdrawal(slot: u64, user: Pubkey, agent_id: u64, amount: u64) -> EventRecord {
+    let event =
+        StakeWithdrawn { schema_version: StakeWithdrawn::SCHEMA_VERSION, user, agent_id, amount, timestamp: TIMESTAMP };
+    record(slot, OntoraEvent::StakeWithdrawn(event))
+}
+
+fn claim(slot: u64, user: Pubkey, agent_id: u64, reward_amount: u64) -> EventRecord {
+    let event = RewardClaimed {
+        schema_version: RewardClaimed::SCHEMA_VERSION,
+        user,
+        agent_id,
+        reward_amount,
+        timestamp: TIMESTAMP,
+    };
+    record(slot, OntoraEvent::RewardClaimed(event))
+}
+
+fn proposal(slot: u64, proposal_id: u64, creator: Pubkey, voting_duration: u64) -> EventRecord {
+    let event = ProposalCreated {
+        schema_version: ProposalCreated::SCHEMA_VERSION,
+        proposal_id,
+        creator,
+        timestamp: TIMESTAMP,
+        title: format!("Proposal {proposal_id}"),
+        voting_duration,
+    };
+    record(slot, OntoraEvent::ProposalCreated(event))
+}
+
+fn vote(slot: u64, voter: Pubkey, vote_weight: u64) -> EventRecord {
+    let event = VoteCast {
+        schema_version: VoteCast::SCHEMA_VERSION,
+        proposal_id: 0,
+        voter,
+        timestamp: TIMESTAMP,
+        vote_option: 0,
+        vote_weight,
+    };
+    record(slot, OntoraEvent::VoteCast(event))
+}
+
+fn distribution(slot: u64, total_amount: u64, eligible_count: u64) -> EventRecord {
+    let event = RewardDistributed {
+        schema_version: RewardDistributed::SCHEMA_VERSION,
+        authority: Pubkey::new_unique(),
+        timestamp: TIMESTAMP + slot as i64,
+        total_amount,
+        eligible_count,
+    };
+    record(slot, OntoraEvent::RewardDistributed(event))
+}
+
+fn price(slot: u64, price: i64) -> PriceUpdate {
+    PriceUpdate {
+        slot,
+        signature: Signature::new_unique(),
+        index: 0,
+        symbol: "SOL/USD".to_string(),
+        feed: Pubkey::new_unique(),
+        price,
+        confidence: 50,
+        round: slot,
+        timestamp: TIMESTAMP + slot as i64,
+    }
+}
+
+// The server over a seeded database, and the user whose positions were seeded
+struct Api {
+    runtime: Runtime,
+    base_url: String,
+    user: Pubkey,
+    _dir: TempDir,
+}
+
+impl Api {
+    fn start() -> Self {
+        let dir = TempDir::new().unwrap();
+        let url = format!("sqlite://{}?mode=rwc", dir.path().join("indexer.db").display());
+        let (owner, user, other) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+
+        let mut sink = SqlSink::connect(&url).unwrap();
+        let records = [
+            agent(10, 1, owner, "alpha"),
+            agent(10, 2, owner, "beta"),
+            agent(10, 3, owner, "gamma"),
+            agent(11, 1, owner, "alpha v2"),
+            deposit(12, user, 1, 500),
+            deposit(12, user, 2, 300),
+            withdrawal(13, user, 1, 200),
+            deposit(13, other, 1, 1_000),
+            claim(14, user, 1, 25),
+            claim(14, user, 3, 5),
+            // Voting stays open well past the test run
+            proposal(15, 0, owner, 400_000_000),
+            proposal(15, 1, owner, 3_600),
+            proposal(15, 2, owner, 3_600),
+            vote(16, user, 500),
+            vote(16, other, 200),
+            distribution(17, 1_000, 3),
+            distribution(18, 500, 2),
+        ];
+        for record in &records {
+            sink.apply(record);
+        }
+        let finalized = ProposalFinalized {
+            schema_version: ProposalFinalized::SCHEMA_VERSION,
+            proposal_id: 2,
+            timestamp: TIMESTAMP + 3_600,
+            result: 1,
+            vote_summary: "no votes".to_string(),
+            reason: 0,
+        };
+        sink.apply(&record(16, OntoraEvent::ProposalFinalized(finalized)));
+        sink.record_price(&price(19, 14_200)).unwrap();
+        sink.record_price(&price(INDEXED_SLOT as u64, 14_350)).unwrap();
+        drop(sink);
+
+        let runtime = Runtime::new().unwrap();
+        let base_url = runtime.block_on(async {
+            let pool = ontora_api::connect(&url).await.unwrap();
+            for (agent_id, category, verified) in [(1, "trading", 1), (2, "research", 0)] {
+                sqlx::query("INSERT INTO agent_profiles (agent_id, category, verified) VALUES ($1, $2, $3)")
+                    .bind(agent_id as i64)
+                    .bind(category)
+                    .bind(verified as i64)
+                    .execute(&pool)
+                    .await
+                    .unwrap();
+            }
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let address = listener.local_addr().unwrap();
+            tokio::spawn(async move { axum::serve(listener, ontora_api::router(pool)).await });
+            format!("http://{address}")
+        });
+        Api { runtime, base_url, user, _dir: dir }
+    }
+
+    // Status and JSON body of `path`; null for bodies axum rejected in plain text
+    fn get(&self, path: &str) -> (u16, Value) {
+        self.runtime.block_on(async {
+            let response = reqwest::get(format!("{}{}", self.base_url, path)).await.unwrap();
+            (response.status().as_u16(), response.json().await.unwrap_or(Value::Null))
+        })
+    }
+
+    // Status and JSON body of a `method` request to `path`, with `body` as JSON
+    fn send(&self, method: reqwest::Method, path: &str, body: Option<Value>) -> (u16, Value) {
+        self.runtime.block_on(async {
+            let mut request = reqwest::Client::new().request(method, format!("{}{}", self.base_url, path));
+            if let Some(body) = body {
+                request = request.json(&body);
+            }
+            let response = request.send().await.unwrap();
+            (response.status().as_u16(), response.json().await.unwrap_or(Value::Null))
+        })
+    }
+}
+
+fn ids(page: &Value, field: &str) -> Vec<i64> {
+    page["data"].as_array().unwrap().iter().map(|item| item[field].as_i64().unwrap()).collect()
+}
+
+// Test that agents are listed in id order with their latest metadata, filtered by
+// category and verification, a page at a time
+#[test]
+fn test_lists_agents() {
+    let api = Api::start();
+
+    let (status, page) = api.get("/agents");
+    assert_eq!(status, 200);
+    assert_eq!(page["slot"], INDEXED_SLOT);
+    assert_eq!(page["nextCursor"], Value::Null);
+    assert_eq!(ids(&page, "agentId"), [1, 2, 3]);
+    let first = &page["data"][0];
+    let mut fields: Vec<&str> = first.as_object().unwrap().keys().map(String::as_str).collect();
+    fields.sort();
+    assert_eq!(
+        fields,
+        [
+            "agentId", "category", "description", "name", "owner", "performanceScore", "registeredAt", "totalStaked",
+            "updatedSlot", "verified"
+        ]
+    );
+    assert_eq!(first["name"], "alpha v2");
+    assert_eq!(first["totalStaked"], 1_300);
+    assert_eq!(first["category"], "trading");
+    assert_eq!(first["verified"], true);
+    assert_eq!(first["updatedSlot"], 11);
+    assert_eq!(page["data"][2]["category"], Value::Null);
+
+    assert_eq!(ids(&api.get("/agents?verified=true").1, "agentId"), [1]);
+    assert_eq!(ids(&api.get("/agents?verified=false").1, "agentId"), [2, 3]);
+    assert_eq!(ids(&api.get("/agents?category=research").1, "agentId"), [2]);
+
+    let (_, first_page) = api.get("/agents?limit=2");
+    assert_eq!(ids(&first_page, "agentId"), [1, 2]);
+    assert_eq!(first_page["nextCursor"], "2");
+    let (_, last_page) = api.get("/agents?limit=2&cursor=2");
+    assert_eq!(ids(&last_page, "agentId"), [3]);
+    assert_eq!(last_page["nextCursor"], Value::Null);
+}
+
+// Test that one agent is returned by id, and an unknown id is a 404
+#[test]
+fn test_gets_agent() {
+    let api = Api::start();
+
+    let (status, item) = api.get("/agents/2");
+    assert_eq!(status, 200);
+    assert_eq!(item["slot"], INDEXED_SLOT);
+    assert_eq!(item["data"]["agentId"], 2);
+    assert_eq!(item["data"]["name"], "beta");
+    assert_eq!(item["data"]["performanceScore"], 20);
+    assert_eq!(item["data"]["totalStaked"], 300);
+
+    let (status, error) = api.get("/agents/99");
+    assert_eq!(status, 404);
+    assert_eq!(error, json!({ "error": "agent 99 not found" }));
+}
+
+// Test that a user's positions net deposits against withdrawals and add up claims
+#[test]
+fn test_lists_user_positions() {
+    let api = Api::start();
+
+    let (status, page) = api.get(&format!("/users/{}/positions", api.user));
+    assert_eq!(status, 200);
+    assert_eq!(page["slot"], INDEXED_SLOT);
+    assert_eq!(
+        page["data"],
+        json!([
+            { "agentId": 1, "staked": 300, "rewardsClaimed": 25, "updatedSlot": 14 },
+            { "agentId": 2, "staked": 300, "rewardsClaimed": 0, "updatedSlot": 12 },
+            { "agentId": 3, "staked": 0, "rewardsClaimed": 5, "updatedSlot": 14 },
+        ])
+    );
+
+    let (_, first_page) = api.get(&format!("/users/{}/positions?limit=1", api.user));
+    assert_eq!(ids(&first_page, "agentId"), [1]);
+    let cursor = first_page["nextCursor"].as_str().unwrap();
+    let (_, rest) = api.get(&format!("/users/{}/positions?cursor={cursor}", api.user));
+    assert_eq!(ids(&rest, "agentId"), [2, 3]);
+
+    let (_, none) = api.get(&format!("/users/{}/positions", Pubkey::new_unique()));
+    assert_eq!(none["data"], json!([]));
+}
+
+// Test that a user's timeline runs the stake and claimed rewards over their events in order
+#[test]
+fn test_lists_user_timeline() {
+    let api = Api::start();
+
+    let (status, page) = api.get(&format!("/users/{}/timeline", api.user));
+    assert_eq!(status, 200);
+    assert_eq!(page["slot"], INDEXED_SLOT);
+    assert_eq!(ids(&page, "sequence"), [0, 1, 2, 3, 4, 5]);
+    let column = |field: &str| -> Vec<Value> {
+        page["data"].as_array().unwrap().iter().map(|entry| entry[field].clone()).collect()
+    };
+    assert_eq!(column("kind"), ["stake", "stake", "unstake", "claim", "claim", "vote"]);
+    assert_eq!(column("staked")[1..], [800, 600, 600, 600, 600]);
+    assert_eq!(column("claimed"), [0, 0, 0, 25, 30, 30]);
+    assert!(column("gap").iter().all(Value::is_null));
+    let vote = &page["data"][5];
+    assert_eq!((&vote["proposalId"], &vote["voteOption"], &vote["amount"]), (&json!(0), &json!(0), &json!(500)));
+    assert_eq!((&vote["agentId"], &page["data"][2]["agentId"]), (&Value::Null, &json!(1)));
+
+    let (_, first_page) = api.get(&format!("/users/{}/timeline?limit=4", api.user));
+    assert_eq!(first_page["nextCursor"], "3");
+    let (_, rest) = api.get(&format!("/users/{}/timeline?cursor=3", api.user));
+    assert_eq!(ids(&rest, "sequence"), [4, 5]);
+    assert_eq!(rest["data"][0]["claimed"], 30);
+
+    let (_, none) = api.get(&format!("/users/{}/timeline", Pubkey::new_unique()));
+    assert_eq!(none["data"], json!([]));
+}
+
+// Test that proposals report their status and tally, and filter on it
+#[test]
+fn test_lists_proposals_by_status() {
+    let api = Api::start();
+
+    let (status, page) = api.get("/proposals?status=active");
+    assert_eq!(status, 200);
+    assert_eq!(page["slot"], INDEXED_SLOT);
+    assert_eq!(ids(&page, "proposalId"), [0]);
+    let active = &page["data"][0];
+    assert_eq!(active["status"], "active");
+    assert_eq!(active["title"], "Proposal 0");
+    assert_eq!(active["votesCast"], 2);
+    assert_eq!(active["voteWeight"], 700);
+    assert_eq!(active["votingEndsAt"], TIMESTAMP + 400_000_000);
+    assert_eq!(active["result"], Value::Null);
+    assert_eq!(active["updatedSlot"], 16);
+
+    assert_eq!(ids(&api.get("/proposals?status=ended").1, "proposalId"), [1]);
+    let (_, finalized) = api.get("/proposals?status=finalized");
+    assert_eq!(ids(&finalized, "proposalId"), [2]);
+    assert_eq!(finalized["data"][0]["result"], 1);
+    assert_eq!(finalized["data"][0]["voteSummary"], "no votes");
+    assert_eq!(ids(&api.get("/proposals").1, "proposalId"), [0, 1, 2]);
+}
+
+// Test the reward pool totals and the latest price of a symbol
+#[test]
+fn test_reward_pool_and_prices() {
+    let api = Api::start();
+
+    let (status, pool) = api.get("/rewards/pool");
+    assert_eq!(status, 200);
+    assert_eq!(
+        pool,
+        json!({
+            "data": {
+                "totalDistributed": 1_500,
+                "totalClaimed": 30,
+                "distributions": 2,
+                "lastDistribution": { "slot": 18, "timestamp": TIMESTAMP + 18, "totalAmount": 500, "eligibleCount": 2 },
+            },
+            "slot": INDEXED_SLOT,
+        })
+    );
+
+    for symbol in ["SOL%2FUSD", "sol-usd"] {
+        let (status, price) = api.get(&format!("/prices/{symbol}"));
+        assert_eq!(status, 200);
+        assert_eq!(price["slot"], INDEXED_SLOT);
+        assert_eq!(price["data"]["symbol"], "SOL/USD");
+        assert_eq!(price["data"]["price"], 14_350);
+        assert_eq!(price["data"]["round"], INDEXED_SLOT);
+    }
+    assert_eq!(api.get("/prices/BTC-USD").0, 404);
+}
+
+// Test that malformed parameters are rejected with a message
+#[test]
+fn test_rejects_bad_requests() {
+    let api = Api::start();
+
+    let (status, error) = api.get("/users/not-a-key/positions");
+    assert_eq!(status, 400);
+    assert_eq!(error, json!({ "error": "invalid public key \"not-a-key\"" }));
+    assert_eq!(api.get("/agents?cursor=abc").0, 400);
+    assert_eq!(api.get("/agents?limit=0").0, 400);
+    assert_eq!(api.get(&format!("/agents?limit={}", ontora_api::routes::MAX_PAGE_SIZE + 1)).0, 400);
+    assert_eq!(api.get("/proposals?status=pending").0, 400);
+}
+
+// Test that subscriptions are created with a secret shown once, listed by wallet,
+// updated, deleted, and validated
+#[test]
+fn test_manages_subscriptions() {
+    let api = Api::start();
+    let wallet = api.user.to_string();
+    let new = json!({
+        "wallet": wallet,
+        "eventTypes": ["RewardClaimed"],
+        "minAmount": 100,
+        "url": "https://example.com/hook",
+    });
+    let (status, created) = api.send(reqwest::Method::POST, "/subscriptions", Some(new.clone()));
+    assert_eq!(status, 201);
+    let id = created["data"]["id"].as_str().unwrap().to_string();
+    assert_eq!(created["data"]["secret"].as_str().unwrap().len(), 32);
+    assert_eq!(created["data"]["minAmount"], 100);
+    api.send(reqwest::Method::POST, "/subscriptions", Some(new.clone()));
+    let other = Pubkey::new_unique().to_string();
+    let other = json!({ "wallet": other, "eventTypes": ["StakeDeposited"], "url": "http://localhost/" });
+    assert_eq!(api.send(reqwest::Method::POST, "/subscriptions", Some(other)).0, 201);
+
+    let (status, fetched) = api.get(&format!("/subscriptions/{id}"));
+    assert_eq!(status, 200);
+    assert_eq!(fetched["data"]["wallet"], wallet);
+    assert_eq!(fetched["data"]["secret"], Value::Null);
+    assert_eq!(fetched["slot"], INDEXED_SLOT);
+
+    let (_, first) = api.get(&format!("/subscriptions?wallet={wallet}&limit=1"));
+    assert_eq!(first["data"].as_array().unwrap().len(), 1);
+    let cursor = first["nextCursor"].as_str().unwrap();
+    let (_, second) = api.get(&format!("/subscriptions?wallet={wallet}&limit=1&cursor={cursor}"));
+    assert_eq!(second["data"].as_array().unwrap().len(), 1);
+    assert_eq!(second["nextCursor"], Value::Null);
+    assert_eq!(api.get("/subscriptions").1["data"].as_array().unwrap().len(), 3);
+
+    let rule = json!({ "eventTypes": ["RewardClaimed", "ProposalFinalized"], "url": "https://example.com/v2" });
+    let (status, updated) = api.send(reqwest::Method::PUT, &format!("/subscriptions/{id}"), Some(rule.clone()));
+    assert_eq!(status, 200);
+    assert_eq!(updated["data"]["minAmount"], Value::Null);
+    assert_eq!(api.get(&format!("/subscriptions/{id}")).1["data"]["url"], "https://example.com/v2");
+
+    assert_eq!(api.send(reqwest::Method::DELETE, &format!("/subscriptions/{id}"), None).0, 204);
+    assert_eq!(api.get(&format!("/subscriptions/{id}")).0, 404);
+    assert_eq!(api.send(reqwest::Method::DELETE, &format!("/subscriptions/{id}"), None).0, 404);
+    assert_eq!(api.send(reqwest::Method::PUT, &format!("/subscriptions/{id}"), Some(rule)).0, 404);
+
+    for invalid in [
+        json!({ "wallet": "not-a-key", "eventTypes": ["RewardClaimed"], "url": "https://example.com/" }),
+        json!({ "wallet": wallet, "eventTypes": [], "url": "https://example.com/" }),
+        json!({ "wallet": wallet, "eventTypes": ["AgentSlashed"], "url": "https://example.com/" }),
+        json!({ "wallet": wallet, "eventTypes": ["RewardClaimed"], "url": "ftp://example.com/" }),
+    ] {
+        assert_eq!(api.send(reqwest::Method::POST, "/subscriptions", Some(invalid)).0, 400);
+    }
+}
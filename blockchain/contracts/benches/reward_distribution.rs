@@ -0,0 +1,191 @@
+// reward_distribution.rs
+// Criterion benchmarks of one epoch's reward distribution to 10, 100 and 1000 staked
+// positions, compared across strategies: one `distribute_rewards` transaction per agent,
+// and `distribute_rewards_batch` at several batch sizes. The positions are legacy agents
+// set up with the svm scenario builder, so the measured paths are the real instructions
+// on the in-process harness. Criterion times the transactions; each case's compute units
+// per position, transactions and wall-clock time are also written to
+// target/reward-distribution/<commit>.json, for comparing one commit's numbers with another's.
+// Stakes on the unified agents need no distribution at all: the reward-per-share accumulator
+// on the platform config accrues lazily and each stake change settles it, so it has no case here.
+//
+// The harness executes the compiled program, so build it first:
+//     cargo build-sbf
+//     cargo bench --bench reward_distribution
+// `--quick` runs 10 positions with short measurements, as a smoke test for CI:
+//     cargo bench --bench reward_distribution -- --quick
+
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::pubkey::Pubkey;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use serde_json::{json, Value};
+use solana_sdk::compute_budget::ComputeBudgetInstruction;
+
+#[path = "../tests/svm/mod.rs"]
+mod svm;
+#[path = "../tests/test_setup.rs"]
+mod test_setup;
+use svm::*;
+
+const POSITIONS: [usize; 3] = [10, 100, 1_000];
+const QUICK_POSITIONS: [usize; 1] = [10];
+// Agents per `distribute_rewards_batch`; 20 keeps a legacy transaction under the packet size
+const BATCH_SIZES: [usize; 3] = [5, 10, 20];
+const STAKE_AMOUNT: u64 = 1_000_000_000;
+// Enough for every epoch criterion distributes, whatever the emission
+const REWARD_POOL: u64 = 1_000_000_000_000_000_000;
+const BATCH_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+#[derive(Clone, Copy, Debug)]
+enum Strategy {
+    PerAgent,
+    Batch(usize),
+}
+
+impl Strategy {
+    fn name(self) -> &'static str {
+        match self {
+            Strategy::PerAgent => "per_agent",
+            Strategy::Batch(_) => "batch",
+        }
+    }
+
+    fn id(self, positions: usize) -> BenchmarkId {
+        match self {
+            Strategy::PerAgent => BenchmarkId::new("per_agent", positions),
+            Strategy::Batch(size) => BenchmarkId::new(format!("batch_{}", size), positions),
+        }
+    }
+}
+
+// What one epoch's distribution took
+#[derive(Default)]
+struct Cost {
+    transactions: u64,
+    compute_units: u64,
+    elapsed: Duration,
+}
+
+// A platform with `positions` legacy agents, each staking STAKE_AMOUNT
+fn world(positions: usize) -> (ScenarioWorld, Vec<Pubkey>) {
+    let mut scenario = Scenario::new().with_reward_pool(REWARD_POOL);
+    for index in 0..positions {
+        let name = format!("staker-{}", index);
+        scenario = scenario.user(&name).with_tokens(STAKE_AMOUNT).stake_legacy(&name, STAKE_AMOUNT);
+    }
+    let world = scenario.run();
+    let mut agents: Vec<Pubkey> =
+        world.users.values().map(|user| user.legacy_agent.expect("every user staked").0).collect();
+    // The batch instruction takes its agents in ascending address order
+    agents.sort();
+    (world, agents)
+}
+
+// Send `instructions` as the crank, adding the transaction to `cost`
+fn send(world: &mut ScenarioWorld, instructions: &[Instruction], cost: &mut Cost) {
+    let start = Instant::now();
+    let result = send_transaction(&mut world.ctx, instructions, &[&world.platform.admin.keypair]);
+    cost.elapsed += start.elapsed();
+    let meta = result.unwrap_or_else(|failed| panic!("{:?}\n{}", failed.err, failed.meta.logs.join("\n")));
+    cost.transactions += 1;
+    cost.compute_units += meta.compute_units_consumed;
+}
+
+// Pay the next epoch to every agent
+fn distribute(world: &mut ScenarioWorld, agents: &[Pubkey], strategy: Strategy) -> Cost {
+    let platform = &world.platform;
+    let (platform_config, reward_pool, crank) = (platform.platform_config, platform.reward_pool, platform.admin.pubkey);
+    let mut cost = Cost::default();
+    warp_seconds(&mut world.ctx, REWARD_EPOCH);
+    match strategy {
+        Strategy::PerAgent => {
+            for agent in agents {
+                let instruction = program_instruction(
+                    ontora_ai::accounts::DistributeRewards { platform_config, reward_pool, ai_agent: *agent, crank },
+                    ontora_ai::instruction::DistributeRewards {},
+                );
+                send(world, &[instruction], &mut cost);
+            }
+        }
+        Strategy::Batch(size) => {
+            let batches = agents.chunks(size).count();
+            for (index, batch) in agents.chunks(size).enumerate() {
+                let mut instruction = program_instruction(
+                    ontora_ai::accounts::DistributeRewardsBatch { platform_config, reward_pool, crank },
+                    ontora_ai::instruction::DistributeRewardsBatch { close_epoch: index + 1 == batches },
+                );
+                instruction.accounts.extend(batch.iter().map(|agent| AccountMeta::new(*agent, false)));
+                let limit = ComputeBudgetInstruction::set_compute_unit_limit(BATCH_COMPUTE_UNIT_LIMIT);
+                send(world, &[limit, instruction], &mut cost);
+            }
+        }
+    }
+    cost
+}
+
+// The commit the numbers belong to, from git or, failing that, CI
+fn commit() -> String {
+    Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .or_else(|| std::env::var("GITHUB_SHA").ok())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+fn write_report(quick: bool, cases: Vec<Value>) {
+    let commit = commit();
+    let written_at = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs());
+    let report = json!({ "commit": commit, "quick": quick, "written_at": written_at, "cases": cases });
+    let dir = PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/target/reward-distribution"));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(format!("{}.json", commit));
+    fs::write(&path, serde_json::to_string_pretty(&report).unwrap()).unwrap();
+    println!("reward distribution report: {}", path.display());
+}
+
+fn bench_reward_distribution(c: &mut Criterion) {
+    let quick = std::env::args().any(|arg| arg == "--quick");
+    let positions: &[usize] = if quick { &QUICK_POSITIONS } else { &POSITIONS };
+    let strategies: Vec<Strategy> =
+        [Strategy::PerAgent].into_iter().chain(BATCH_SIZES.map(Strategy::Batch)).collect();
+
+    let mut group = c.benchmark_group("reward_distribution");
+    group.sample_size(10);
+    let mut cases = Vec::new();
+    for &count in positions {
+        for &strategy in &strategies {
+            let (mut world, agents) = world(count);
+            let cost = distribute(&mut world, &agents, strategy);
+            cases.push(json!({
+                "strategy": strategy.name(),
+                "batch_size": match strategy {
+                    Strategy::PerAgent => 1,
+                    Strategy::Batch(size) => size,
+                },
+                "positions": count,
+                "transactions": cost.transactions,
+                "compute_units": cost.compute_units,
+                "compute_units_per_position": cost.compute_units / count as u64,
+                "wall_clock_ms": cost.elapsed.as_secs_f64() * 1_000.0,
+            }));
+            group.bench_function(strategy.id(count), |b| {
+                b.iter_custom(|iterations| {
+                    (0..iterations).map(|_| distribute(&mut world, &agents, strategy).elapsed).sum()
+                })
+            });
+        }
+    }
+    group.finish();
+    write_report(quick, cases);
+}
+
+criterion_group!(benches, bench_reward_distribution);
+criterion_main!(benches);
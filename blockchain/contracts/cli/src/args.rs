@@ -0,0 +1,493 @@
+use std::path::PathBuf;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use ontora_ai::state::{
+    ProposalAction, PAUSE_ALL, PAUSE_CLAIMS, PAUSE_DEPOSITS, PAUSE_GOVERNANCE, PAUSE_ORACLE_UPDATES, PAUSE_WITHDRAWALS,
+};
+use ontora_client::{ComputeLimit, PriorityFee, SignerSource};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::migrate::DEFAULT_CHECKPOINT;
+use crate::profile::DEFAULT_PROFILE;
+
+pub const DEFAULT_RPC_URL: &str = "http://127.0.0.1:8899";
+pub const DEVNET_RPC_URL: &str = "https://api.devnet.solana.com";
+
+/// Operate the Ontora AI program: staking, rewards and governance.
+#[derive(Debug, Parser)]
+#[command(name = "ontora", version)]
+pub struct Cli {
+    /// Keypair that signs and pays for transactions [default: the profile's, or ~/.config/solana/id.json]
+    #[arg(long, short = 'k', env = "ONTORA_KEYPAIR", global = true)]
+    pub keypair: Option<PathBuf>,
+    /// Signer instead of a keypair file: `ledger://?key=<account>`, or the URL of a remote signer
+    #[arg(long, env = "ONTORA_SIGNER", global = true, conflicts_with = "keypair")]
+    pub signer: Option<SignerSource>,
+    /// RPC endpoint of the cluster [default: the profile's, or http://127.0.0.1:8899]
+    #[arg(long, short = 'u', env = "ONTORA_RPC_URL", global = true)]
+    pub url: Option<String>,
+    /// Profile holding the defaults `ontora bootstrap` set up
+    #[arg(long, env = "ONTORA_PROFILE", default_value = DEFAULT_PROFILE, global = true)]
+    pub profile: PathBuf,
+    /// Print JSON instead of tables
+    #[arg(long, global = true)]
+    pub json: bool,
+    /// Send transactions after their simulated summary; without it, commands only simulate
+    #[arg(long, short = 'y', global = true)]
+    pub yes: bool,
+    /// Compute-unit price: `none`, micro-lamports per unit, or a percentile of recent fees such as `p75`
+    #[arg(long, env = "ONTORA_PRIORITY_FEE", default_value = "none", global = true)]
+    pub priority_fee: PriorityFee,
+    /// Compute-unit limit: `table` for the per-instruction budgets, `simulate`, or a number of units
+    #[arg(long, default_value = "table", global = true)]
+    pub compute_limit: ComputeLimit,
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Set up a working platform on a test cluster and write its addresses to the profile
+    Bootstrap(BootstrapArgs),
+    /// Stake tokens on an agent
+    Stake(StakeArgs),
+    /// Withdraw stake from an agent
+    Unstake(StakeArgs),
+    /// Claim the rewards accumulated by your legacy agent
+    Claim(ClaimArgs),
+    /// Register, update and list agents
+    #[command(subcommand)]
+    Agent(AgentCommand),
+    /// Create, vote on and finalize governance proposals
+    #[command(subcommand)]
+    Proposal(ProposalCommand),
+    /// Manage the legacy reward pool
+    #[command(subcommand)]
+    Pool(PoolCommand),
+    /// Inspect and update the platform configuration
+    #[command(subcommand)]
+    Config(ConfigCommand),
+    /// Pause and unpause operation groups
+    #[command(subcommand)]
+    Pause(PauseCommand),
+    /// Move tokens out of the protocol treasury
+    #[command(subcommand)]
+    Treasury(TreasuryCommand),
+    /// Print the instruction in a `--multisig` blob with its arguments and accounts named
+    DecodeInstruction(DecodeInstructionArgs),
+    /// Print what each instruction of a serialized transaction does, for review before signing
+    DecodeTx(DecodeTxArgs),
+    /// Recount a proposal's vote from its transactions and print a report, signed by the keypair, of any discrepancy
+    VerifyProposal(VerifyProposalArgs),
+    /// Move every staked legacy agent onto the unified agent layout (admin), resuming from the checkpoint
+    MigrateAgents(MigrateAgentsArgs),
+    /// Export or review a wallet's staking history
+    #[command(subcommand)]
+    History(HistoryCommand),
+}
+
+impl Cli {
+    /// `--url`, else the cluster `bootstrap` was pointed at, else the profile's URL.
+    pub fn rpc_url(&self, profile_url: Option<&str>) -> String {
+        if let Some(url) = &self.url {
+            return url.clone();
+        }
+        match &self.command {
+            Command::Bootstrap(args) => args.cluster.url().to_string(),
+            _ => profile_url.unwrap_or(DEFAULT_RPC_URL).to_string(),
+        }
+    }
+}
+
+impl Command {
+    /// Whether the command sends a transaction, and so needs the keypair.
+    pub fn signs(&self) -> bool {
+        !matches!(
+            self,
+            Command::Agent(AgentCommand::List(_))
+                | Command::Config(ConfigCommand::Show)
+                | Command::History(_)
+                | Command::DecodeInstruction(_)
+                | Command::DecodeTx(_)
+        )
+    }
+}
+
+/// A cluster `bootstrap` may set up; it refuses mainnet.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum TestCluster {
+    Devnet,
+    Localnet,
+}
+
+impl TestCluster {
+    pub fn name(self) -> &'static str {
+        match self {
+            TestCluster::Devnet => "devnet",
+            TestCluster::Localnet => "localnet",
+        }
+    }
+
+    pub fn url(self) -> &'static str {
+        match self {
+            TestCluster::Devnet => DEVNET_RPC_URL,
+            TestCluster::Localnet => DEFAULT_RPC_URL,
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct BootstrapArgs {
+    #[arg(long, value_enum, default_value = "devnet")]
+    pub cluster: TestCluster,
+    /// Airdrop requests to retry after the faucet's rate limit, with a doubling delay
+    #[arg(long, default_value_t = 5)]
+    pub faucet_retries: u32,
+}
+
+#[derive(Debug, Args)]
+pub struct StakeArgs {
+    /// Owner of the agent
+    #[arg(long)]
+    pub agent_owner: Pubkey,
+    #[arg(long)]
+    pub agent_id: u64,
+    /// Amount in base units of the stake mint
+    #[arg(long)]
+    pub amount: u64,
+    /// Stake mint [default: the profile's]
+    #[arg(long)]
+    pub mint: Option<Pubkey>,
+}
+
+#[derive(Debug, Args)]
+pub struct ClaimArgs {
+    /// Reward mint [default: the profile's]
+    #[arg(long)]
+    pub mint: Option<Pubkey>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum AgentCommand {
+    /// Register an agent owned by the keypair
+    Register(AgentArgs),
+    /// Replace the name and description of an agent owned by the keypair
+    Update(AgentArgs),
+    /// List registered agents
+    List(ListAgentsArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct AgentArgs {
+    /// Agent id, unique per owner
+    #[arg(long)]
+    pub id: u64,
+    #[arg(long)]
+    pub name: String,
+    #[arg(long)]
+    pub description: String,
+}
+
+#[derive(Debug, Args)]
+pub struct ListAgentsArgs {
+    /// Only list agents of this owner
+    #[arg(long)]
+    pub owner: Option<Pubkey>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ProposalCommand {
+    /// Open a proposal under the next proposal id
+    Create(CreateProposalArgs),
+    /// Vote on a proposal
+    Vote(VoteArgs),
+    /// Tally a proposal whose voting period has ended
+    Finalize(FinalizeArgs),
+    /// Apply an approved proposal's change to the platform configuration
+    Execute(ExecuteArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct CreateProposalArgs {
+    #[arg(long)]
+    pub title: String,
+    #[arg(long)]
+    pub description: String,
+    /// Voting period in seconds
+    #[arg(long, default_value_t = 86_400)]
+    pub voting_duration: u64,
+    /// Ballot option; repeat once per option
+    #[arg(long = "option", required = true)]
+    pub options: Vec<String>,
+    /// Reward rate in basis points to set once the proposal passes; at most one change per proposal
+    #[arg(long, conflicts_with_all = ["set_min_stake_amount", "set_epoch_duration"])]
+    pub set_reward_rate_bps: Option<u64>,
+    /// Smallest stake to set once the proposal passes, in base units of the stake mint
+    #[arg(long, conflicts_with = "set_epoch_duration")]
+    pub set_min_stake_amount: Option<u64>,
+    /// Epoch length in seconds to set once the proposal passes
+    #[arg(long)]
+    pub set_epoch_duration: Option<i64>,
+}
+
+impl CreateProposalArgs {
+    /// The change the proposal makes once executed; none without a `--set-*` flag.
+    pub fn action(&self) -> ProposalAction {
+        match (self.set_reward_rate_bps, self.set_min_stake_amount, self.set_epoch_duration) {
+            (Some(bps), _, _) => ProposalAction::UpdateRewardRate { bps },
+            (_, Some(amount), _) => ProposalAction::UpdateMinStake { amount },
+            (_, _, Some(seconds)) => ProposalAction::UpdateEpochDuration { seconds },
+            _ => ProposalAction::NoOp,
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct VoteArgs {
+    /// Proposal id
+    #[arg(long)]
+    pub id: u64,
+    /// Index of the chosen option
+    #[arg(long)]
+    pub option: u8,
+}
+
+#[derive(Debug, Args)]
+pub struct FinalizeArgs {
+    /// Proposal id
+    #[arg(long)]
+    pub id: u64,
+}
+
+#[derive(Debug, Args)]
+pub struct ExecuteArgs {
+    /// Proposal id
+    #[arg(long)]
+    pub id: u64,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum PoolCommand {
+    /// Add tokens from the keypair to the reward pool, initializing it if needed
+    Fund(FundPoolArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct FundPoolArgs {
+    /// Reward mint [default: the profile's]
+    #[arg(long)]
+    pub mint: Option<Pubkey>,
+    /// Amount in base units of the reward mint
+    #[arg(long)]
+    pub amount: u64,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ConfigCommand {
+    /// Print the platform configuration
+    Show,
+    /// Change platform parameters (admin); those left out keep their current values
+    Update(UpdateConfigArgs),
+}
+
+/// Where an admin command's authority lives. With `--multisig`, the command
+/// prints its instruction as a blob for the multisig's vault to sign instead of
+/// sending it, and with `--yes` opens a Squads proposal for it, created by the keypair.
+#[derive(Debug, Args)]
+pub struct MultisigArgs {
+    /// Squads multisig whose vault holds the authority
+    #[arg(long)]
+    pub multisig: Option<Pubkey>,
+    /// Index of that vault
+    #[arg(long, default_value_t = 0)]
+    pub vault_index: u8,
+    /// Memo stored with the Squads transaction
+    #[arg(long)]
+    pub memo: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct UpdateConfigArgs {
+    #[arg(long)]
+    pub reward_rate_bps: Option<u64>,
+    /// Smallest stake, in base units of the stake mint
+    #[arg(long)]
+    pub min_stake_amount: Option<u64>,
+    /// Epoch length in seconds
+    #[arg(long)]
+    pub epoch_duration: Option<i64>,
+    /// Seconds a legacy agent's stake stays locked after it changes
+    #[arg(long)]
+    pub cooldown_seconds: Option<i64>,
+    /// Rewards shared among the legacy agents each epoch, in base units of the reward mint
+    #[arg(long)]
+    pub epoch_emission: Option<u64>,
+    /// Most an agent may hold in stake, in base units of the stake mint (0 = unlimited)
+    #[arg(long)]
+    pub max_stake_per_agent: Option<u64>,
+    /// Seconds claimed legacy rewards vest over before they can be released (0 = paid at once)
+    #[arg(long)]
+    pub vesting_seconds: Option<i64>,
+    /// Stake that counts as one vote on a proposal, in base units of the stake mint
+    #[arg(long)]
+    pub vote_unit: Option<u64>,
+    /// Share of the stake that must vote for a proposal to pass, in basis points
+    #[arg(long)]
+    pub quorum_bps: Option<u16>,
+    /// Share of the votes a proposal's winning option needs to pass, in basis points
+    #[arg(long)]
+    pub approval_threshold_bps: Option<u16>,
+    /// Note recorded in the update's event
+    #[arg(long, default_value = "")]
+    pub note: String,
+    #[command(flatten)]
+    pub multisig: MultisigArgs,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum PauseCommand {
+    /// Pause operation groups (pauser)
+    Set(PauseArgs),
+    /// Unpause operation groups (admin)
+    Clear(PauseArgs),
+}
+
+/// A group of operations the program pauses together.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum PauseGroup {
+    Deposits,
+    Withdrawals,
+    Claims,
+    Governance,
+    OracleUpdates,
+    All,
+}
+
+impl PauseGroup {
+    /// The group's bits in the pause bitfield.
+    pub fn flags(self) -> u8 {
+        match self {
+            PauseGroup::Deposits => PAUSE_DEPOSITS,
+            PauseGroup::Withdrawals => PAUSE_WITHDRAWALS,
+            PauseGroup::Claims => PAUSE_CLAIMS,
+            PauseGroup::Governance => PAUSE_GOVERNANCE,
+            PauseGroup::OracleUpdates => PAUSE_ORACLE_UPDATES,
+            PauseGroup::All => PAUSE_ALL,
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct PauseArgs {
+    /// Operation groups, comma separated
+    #[arg(long, value_enum, value_delimiter = ',', required = true)]
+    pub groups: Vec<PauseGroup>,
+    #[command(flatten)]
+    pub multisig: MultisigArgs,
+}
+
+impl PauseArgs {
+    pub fn flags(&self) -> u8 {
+        self.groups.iter().fold(0, |flags, group| flags | group.flags())
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum TreasuryCommand {
+    /// Transfer tokens from the treasury vault (treasury governance)
+    Transfer(TreasuryTransferArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct TreasuryTransferArgs {
+    #[arg(long)]
+    pub mint: Pubkey,
+    /// Amount in base units of the mint
+    #[arg(long)]
+    pub amount: u64,
+    /// Wallet whose associated token account receives the tokens
+    #[arg(long)]
+    pub recipient: Pubkey,
+    #[command(flatten)]
+    pub multisig: MultisigArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct DecodeInstructionArgs {
+    /// Instruction blob printed by a `--multisig` command, base58 or base64
+    pub blob: String,
+}
+
+#[derive(Debug, Args)]
+pub struct DecodeTxArgs {
+    /// The transaction, bincode-serialized and base64-encoded as wallets and RPC nodes exchange it
+    pub transaction: String,
+    /// Decimals of the staked mint, to print token amounts in whole tokens rather than base units
+    #[arg(long)]
+    pub decimals: Option<u8>,
+}
+
+#[derive(Debug, Args)]
+pub struct VerifyProposalArgs {
+    /// Proposal id
+    pub id: u64,
+}
+
+#[derive(Debug, Args)]
+pub struct MigrateAgentsArgs {
+    /// Stake mint [default: the profile's]
+    #[arg(long)]
+    pub mint: Option<Pubkey>,
+    /// File recording the agents migrated so far; an interrupted run resumes from it
+    #[arg(long, default_value = DEFAULT_CHECKPOINT)]
+    pub checkpoint: PathBuf,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum HistoryCommand {
+    /// Write every stake, unstake, claim and fee of a wallet in one year, for tax reporting
+    Export(ExportHistoryArgs),
+    /// List every stake, unstake, claim and vote of a wallet with its running balances, flagging missing events
+    Timeline(TimelineArgs),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+#[derive(Debug, Args)]
+pub struct ExportHistoryArgs {
+    #[arg(long)]
+    pub wallet: Pubkey,
+    /// Calendar year, in UTC
+    #[arg(long, value_parser = clap::value_parser!(i32).range(1970..=9999))]
+    pub year: i32,
+    #[arg(long, value_enum, default_value = "csv")]
+    pub format: ExportFormat,
+    /// Stake and reward mint, whose decimals amounts are normalized by [default: the profile's]
+    #[arg(long)]
+    pub mint: Option<Pubkey>,
+    /// Indexer database to read instead of the wallet's transactions on the cluster
+    #[arg(long, env = "INDEXER_DATABASE_URL")]
+    pub database_url: Option<String>,
+    /// Price feed symbol claims are valued in USD with, such as `ONT/USD`; needs the database
+    #[arg(long)]
+    pub price_symbol: Option<String>,
+    /// Decimals the feed's prices are scaled by
+    #[arg(long, default_value_t = 8)]
+    pub price_decimals: u8,
+    /// File to write [default: ontora-history-<wallet>-<year>.<format>]
+    #[arg(long, short = 'o')]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct TimelineArgs {
+    #[arg(long)]
+    pub wallet: Pubkey,
+    /// Indexer database whose events are merged with the wallet's transactions on the cluster
+    #[arg(long, env = "INDEXER_DATABASE_URL")]
+    pub database_url: Option<String>,
+}
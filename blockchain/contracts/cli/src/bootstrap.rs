@@ -0,0 +1,300 @@
+//! `ontora bootstrap`: a working platform on devnet or a local validator in one
+//! command. The keypair is funded from the faucet and becomes the admin, the
+//! mint authority, the owner of a demo agent and its first staker; what was
+//! created is written to the profile. Each step first checks whether its
+//! result already exists, so a rerun skips what an earlier run finished.
+
+use std::time::Duration;
+
+use anchor_lang::solana_program::program_pack::Pack;
+use ontora_ai::state::{PlatformConfig, UserStake};
+use ontora_client::signer::OntoraSigner;
+use ontora_client::*;
+use serde_json::{json, Value};
+use solana_sdk::instruction::Instruction;
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::rent::Rent;
+use solana_sdk::signature::Keypair;
+use solana_sdk::system_instruction;
+use spl_associated_token_account::get_associated_token_address;
+
+use crate::args::BootstrapArgs;
+use crate::commands::Session;
+use crate::error::CliError;
+use crate::output::Output;
+use crate::profile::{Addresses, Profile};
+
+/// The demo agent's id under the keypair.
+pub const DEMO_AGENT_ID: u64 = 1;
+/// Below this balance the keypair is topped up from the faucet...
+pub const MIN_BALANCE: u64 = LAMPORTS_PER_SOL;
+/// ...to this one.
+pub const TARGET_BALANCE: u64 = 2 * LAMPORTS_PER_SOL;
+// Devnet's faucet hands out at most one SOL per request
+const AIRDROP_LAMPORTS: u64 = LAMPORTS_PER_SOL;
+const FAUCET_BACKOFF: Duration = Duration::from_secs(1);
+
+pub const MINT_DECIMALS: u8 = 9;
+const TOKEN: u64 = 10u64.pow(MINT_DECIMALS as u32);
+/// Tokens minted to the keypair, in base units.
+pub const MINTED: u64 = 1_000_000 * TOKEN;
+/// Tokens the reward pool starts with.
+pub const INITIAL_REWARDS: u64 = 10_000 * TOKEN;
+/// The demo stake, unless the platform's minimum is higher.
+pub const DEMO_STAKE: u64 = 10 * TOKEN;
+
+// Defaults the platform is initialized with
+const REWARD_RATE_BPS: u64 = 500;
+const MIN_STAKE_AMOUNT: u64 = TOKEN;
+const EPOCH_DURATION: i64 = 86_400;
+const COOLDOWN_SECONDS: i64 = 86_400;
+const EPOCH_EMISSION: u64 = 10 * TOKEN;
+
+/// How a bootstrap step went.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Step {
+    /// Run now.
+    Done,
+    /// Its result was already there.
+    Skipped,
+    /// Would run, given `--yes`.
+    Pending,
+}
+
+impl Step {
+    fn name(self) -> &'static str {
+        match self {
+            Step::Done => "done",
+            Step::Skipped => "skipped",
+            Step::Pending => "pending",
+        }
+    }
+}
+
+fn exists(session: &mut Session, address: &Pubkey) -> Result<bool, CliError> {
+    Ok(session.cluster.account(address)?.is_some())
+}
+
+fn balance(session: &mut Session, address: &Pubkey) -> Result<u64, CliError> {
+    Ok(session.cluster.account(address)?.map_or(0, |account| account.lamports))
+}
+
+// Airdrop, waiting out the faucet's rate limit up to `retries` times
+fn airdrop(session: &mut Session, to: &Pubkey, retries: u32) -> Result<(), CliError> {
+    let mut attempt = 0;
+    loop {
+        match session.cluster.airdrop(to, AIRDROP_LAMPORTS) {
+            Err(CliError::RateLimited(_)) if attempt < retries => {
+                std::thread::sleep(FAUCET_BACKOFF * 2u32.pow(attempt.min(8)));
+                attempt += 1;
+            }
+            result => return result.map(|_| ()),
+        }
+    }
+}
+
+fn fund(session: &mut Session, owner: &Pubkey, args: &BootstrapArgs) -> Result<Step, CliError> {
+    if balance(session, owner)? >= MIN_BALANCE {
+        return Ok(Step::Skipped);
+    }
+    if !session.yes {
+        return Ok(Step::Pending);
+    }
+    while balance(session, owner)? < TARGET_BALANCE {
+        airdrop(session, owner, args.faucet_retries)?;
+    }
+    Ok(Step::Done)
+}
+
+// The profile's mint if it still exists, else a new one with `MINTED` tokens for the keypair
+fn mint(
+    session: &mut Session,
+    signer: &dyn OntoraSigner,
+    known: Option<Pubkey>,
+) -> Result<(Step, Option<Pubkey>), CliError> {
+    if let Some(mint) = known {
+        if session.cluster.account(&mint)?.is_some_and(|account| account.owner == spl_token::ID) {
+            return Ok((Step::Skipped, Some(mint)));
+        }
+    }
+    if !session.yes {
+        return Ok((Step::Pending, None));
+    }
+    let owner = signer.pubkey();
+    let mint = Keypair::new();
+    let instructions = [
+        system_instruction::create_account(
+            &owner,
+            &mint.pubkey(),
+            Rent::default().minimum_balance(spl_token::state::Mint::LEN),
+            spl_token::state::Mint::LEN as u64,
+            &spl_token::ID,
+        ),
+        spl_token::instruction::initialize_mint(&spl_token::ID, &mint.pubkey(), &owner, None, MINT_DECIMALS)
+            .expect("the token program id is valid"),
+        create_associated_token_account_idempotent(&owner, &owner, &mint.pubkey(), &spl_token::ID),
+        spl_token::instruction::mint_to(
+            &spl_token::ID,
+            &mint.pubkey(),
+            &get_associated_token_address(&owner, &mint.pubkey()),
+            &owner,
+            &[],
+            MINTED,
+        )
+        .expect("the token program id is valid"),
+    ];
+    let signers: [&dyn OntoraSigner; 2] = [signer, &mint];
+    session.cluster.send(&instructions, &signers)?;
+    Ok((Step::Done, Some(mint.pubkey())))
+}
+
+// Runs `instructions` unless `done`, or only reports them without `--yes`
+fn step(
+    session: &mut Session,
+    signer: &dyn OntoraSigner,
+    done: bool,
+    instructions: impl FnOnce(&mut Session) -> Result<Vec<Instruction>, CliError>,
+) -> Result<Step, CliError> {
+    if done {
+        return Ok(Step::Skipped);
+    }
+    if !session.yes {
+        return Ok(Step::Pending);
+    }
+    let instructions = instructions(session)?;
+    session.cluster.send(&instructions, &[signer])?;
+    Ok(Step::Done)
+}
+
+/// Runs `ontora bootstrap`.
+pub fn bootstrap(session: &mut Session, args: &BootstrapArgs) -> Result<Output, CliError> {
+    let signer = session.signer()?;
+    let owner = signer.pubkey();
+    let mut profile = Profile::load(session.profile)?.unwrap_or_default();
+    let platform_config = pda::platform_config().0;
+    let reward_pool = pda::reward_pool().0;
+    let agent = pda::ai_agent(&owner, DEMO_AGENT_ID).0;
+    let user_stake = pda::user_stake(&owner).0;
+
+    let mut steps = vec![("airdrop", fund(session, &owner, args)?)];
+    let (status, mint) = mint(session, signer, profile.addresses.mint)?;
+    steps.push(("mint", status));
+
+    let done = exists(session, &platform_config)?;
+    let status = step(session, signer, done, |_| {
+        Ok(vec![build_initialize_platform(InitializePlatformParams {
+            admin: owner,
+            reward_rate_bps: REWARD_RATE_BPS,
+            min_stake_amount: MIN_STAKE_AMOUNT,
+            epoch_duration: EPOCH_DURATION,
+            cooldown_seconds: COOLDOWN_SECONDS,
+            epoch_emission: EPOCH_EMISSION,
+        })])
+    })?;
+    steps.push(("platform_config", status));
+
+    let vaults = mint.map(|_| (pda::staking_vault().0, pda::reward_vault().0));
+    let status = match mint.zip(vaults) {
+        Some((stake_mint, (platform_vault, reward_vault))) => {
+            let done = exists(session, &platform_vault)? && exists(session, &reward_vault)?;
+            step(session, signer, done, |_| {
+                Ok(vec![build_initialize_vaults(InitializeVaultsParams { admin: owner, stake_mint })])
+            })?
+        }
+        None => Step::Pending,
+    };
+    steps.push(("vaults", status));
+
+    let done = exists(session, &reward_pool)?;
+    let status = match mint.zip(vaults) {
+        Some((mint, (_, reward_vault))) => step(session, signer, done, |_| {
+            let transfer = spl_token::instruction::transfer(
+                &spl_token::ID,
+                &get_associated_token_address(&owner, &mint),
+                &reward_vault,
+                &owner,
+                &[],
+                INITIAL_REWARDS,
+            )
+            .expect("the token program id is valid");
+            let initialize = build_initialize_reward_pool(InitializeRewardPoolParams {
+                payer: owner,
+                initial_rewards: INITIAL_REWARDS,
+            });
+            Ok(vec![initialize, transfer])
+        })?,
+        None if done => Step::Skipped,
+        None => Step::Pending,
+    };
+    steps.push(("reward_pool", status));
+
+    let done = exists(session, &agent)?;
+    let status = step(session, signer, done, |_| {
+        Ok(vec![build_register_agent(RegisterAgentParams {
+            owner,
+            agent_id: DEMO_AGENT_ID,
+            name: "Demo agent".to_string(),
+            description: "Registered by ontora bootstrap".to_string(),
+        })])
+    })?;
+    steps.push(("demo_agent", status));
+
+    let staked = match session.cluster.account(&user_stake)? {
+        Some(account) => decode_account::<UserStake>(&user_stake, &account.data)?.staked_amount > 0,
+        None => false,
+    };
+    let status = match mint.zip(vaults) {
+        Some((mint, (platform_vault, _))) => step(session, signer, staked, |session| {
+            let config: PlatformConfig = session
+                .cluster
+                .account(&platform_config)?
+                .ok_or(ClientError::AccountNotFound(platform_config))
+                .and_then(|account| decode_account(&platform_config, &account.data))?;
+            Ok(vec![build_stake_on_agent(StakeOnAgentParams {
+                user: owner,
+                user_token_account: get_associated_token_address(&owner, &mint),
+                platform_vault,
+                agent_owner: owner,
+                agent_id: DEMO_AGENT_ID,
+                amount: DEMO_STAKE.max(config.min_stake_amount),
+                beneficiary: None,
+            })])
+        })?,
+        None if staked => Step::Skipped,
+        None => Step::Pending,
+    };
+    steps.push(("stake", status));
+
+    let mut record: Vec<(&str, Value)> = steps.iter().map(|(name, status)| (*name, json!(status.name()))).collect();
+    if !session.yes {
+        record.push(("next", json!("rerun with --yes to run the pending steps and write the profile")));
+        return Ok(Output::record(record));
+    }
+
+    profile.cluster = Some(args.cluster.name().to_string());
+    profile.url = Some(session.rpc_url.to_string());
+    if let Some(keypair) = session.keypair {
+        profile.keypair = Some(keypair.to_path_buf());
+    }
+    profile.addresses = Addresses {
+        mint,
+        token_account: mint.map(|mint| get_associated_token_address(&owner, &mint)),
+        platform_config: Some(platform_config),
+        platform_vault: vaults.map(|(platform_vault, _)| platform_vault),
+        reward_pool: Some(reward_pool),
+        reward_vault: vaults.map(|(_, reward_vault)| reward_vault),
+        agent: Some(agent),
+        agent_id: Some(DEMO_AGENT_ID),
+        user_stake: Some(user_stake),
+    };
+    profile.store(session.profile)?;
+    record.extend([
+        ("profile", json!(session.profile.display().to_string())),
+        ("cluster", json!(args.cluster.name())),
+        ("mint", json!(mint.map(|mint| mint.to_string()))),
+        ("agent", json!(agent.to_string())),
+        ("user_stake", json!(user_stake.to_string())),
+    ]);
+    Ok(Output::record(record))
+}
@@ -0,0 +1,162 @@
+use std::str::FromStr;
+
+use ontora_client::signer::OntoraSigner;
+use ontora_client::{
+    decode_events, ComputeLimit, OntoraEvent, PriorityFee, RetryPolicy, SendOutcome, TxBuilder, TxSummary,
+};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient};
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig, RpcTransactionConfig};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::account::Account;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_transaction_status::UiTransactionEncoding;
+
+use crate::error::CliError;
+
+// Largest page getSignaturesForAddress returns
+const SIGNATURE_PAGE: usize = 1_000;
+
+/// A successful transaction and the events the program emitted in it.
+#[derive(Clone, Debug)]
+pub struct EventTransaction {
+    pub signature: Signature,
+    pub events: Vec<OntoraEvent>,
+}
+
+/// What the commands need from a cluster. `RpcCluster` talks to a validator;
+/// the integration tests implement it over program-test.
+pub trait Cluster {
+    fn account(&mut self, address: &Pubkey) -> Result<Option<Account>, CliError>;
+    /// Every account owned by the program whose data starts with `discriminator`.
+    fn program_accounts(&mut self, discriminator: &[u8; 8]) -> Result<Vec<(Pubkey, Account)>, CliError>;
+    /// What sending `instructions`, paid by `payer`, would do.
+    fn simulate(&mut self, instructions: &[Instruction], payer: &Pubkey) -> Result<TxSummary, CliError>;
+    /// Sends `instructions`, paid by the first signer, and waits until confirmed.
+    fn send(&mut self, instructions: &[Instruction], signers: &[&dyn OntoraSigner]) -> Result<Signature, CliError>;
+    /// The transactions of `address` in which the program emitted events, back
+    /// to at least the unix time `since`, newest first.
+    fn event_transactions(&mut self, address: &Pubkey, since: i64) -> Result<Vec<EventTransaction>, CliError>;
+    /// Requests `lamports` from the cluster's faucet and waits until they arrive.
+    /// A refusal for the faucet's rate limit is `CliError::RateLimited`.
+    fn airdrop(&mut self, to: &Pubkey, lamports: u64) -> Result<Signature, CliError>;
+}
+
+fn parse_signature(text: &str) -> Result<Signature, CliError> {
+    Signature::from_str(text).map_err(|error| CliError::Cluster(format!("invalid signature {}: {}", text, error)))
+}
+
+pub struct RpcCluster {
+    rpc: RpcClient,
+    compute_limit: ComputeLimit,
+    priority_fee: PriorityFee,
+}
+
+impl RpcCluster {
+    pub fn new(url: &str, compute_limit: ComputeLimit, priority_fee: PriorityFee) -> Self {
+        RpcCluster {
+            rpc: RpcClient::new_with_commitment(url.to_string(), CommitmentConfig::confirmed()),
+            compute_limit,
+            priority_fee,
+        }
+    }
+
+    fn builder(&self, instructions: &[Instruction], payer: &Pubkey) -> TxBuilder {
+        TxBuilder::new(*payer)
+            .instructions(instructions.iter().cloned())
+            .compute_limit(self.compute_limit)
+            .priority_fee(self.priority_fee)
+    }
+}
+
+impl Cluster for RpcCluster {
+    fn account(&mut self, address: &Pubkey) -> Result<Option<Account>, CliError> {
+        Ok(self.rpc.get_account_with_commitment(address, self.rpc.commitment())?.value)
+    }
+
+    fn program_accounts(&mut self, discriminator: &[u8; 8]) -> Result<Vec<(Pubkey, Account)>, CliError> {
+        let config = RpcProgramAccountsConfig {
+            filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_raw_bytes(0, discriminator.to_vec()))]),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                ..RpcAccountInfoConfig::default()
+            },
+            ..RpcProgramAccountsConfig::default()
+        };
+        Ok(self.rpc.get_program_accounts_with_config(&ontora_ai::ID, config)?)
+    }
+
+    fn simulate(&mut self, instructions: &[Instruction], payer: &Pubkey) -> Result<TxSummary, CliError> {
+        Ok(self.builder(instructions, payer).simulate_and_summarize(&self.rpc)?)
+    }
+
+    fn send(&mut self, instructions: &[Instruction], signers: &[&dyn OntoraSigner]) -> Result<Signature, CliError> {
+        let builder = self.builder(instructions, &signers[0].pubkey());
+        match builder.send_with_retry(&self.rpc, signers, &RetryPolicy::default())? {
+            SendOutcome::Confirmed(signature) | SendOutcome::AlreadyProcessed(signature) => Ok(signature),
+            SendOutcome::Expired => Err(CliError::Cluster("transaction expired before it landed".to_string())),
+            SendOutcome::FailedOnChain(error) => Err(CliError::Cluster(format!("transaction failed: {}", error))),
+        }
+    }
+
+    fn event_transactions(&mut self, address: &Pubkey, since: i64) -> Result<Vec<EventTransaction>, CliError> {
+        let config = RpcTransactionConfig {
+            encoding: Some(UiTransactionEncoding::Json),
+            commitment: Some(self.rpc.commitment()),
+            max_supported_transaction_version: Some(0),
+        };
+        let mut transactions = Vec::new();
+        let mut before = None;
+        loop {
+            let page = self.rpc.get_signatures_for_address_with_config(
+                address,
+                GetConfirmedSignaturesForAddress2Config {
+                    before,
+                    until: None,
+                    limit: Some(SIGNATURE_PAGE),
+                    commitment: Some(self.rpc.commitment()),
+                },
+            )?;
+            for status in &page {
+                if status.block_time.is_some_and(|block_time| block_time < since) {
+                    return Ok(transactions);
+                }
+                if status.err.is_some() {
+                    continue;
+                }
+                let signature = parse_signature(&status.signature)?;
+                let transaction = self.rpc.get_transaction_with_config(&signature, config)?;
+                let logs = transaction
+                    .transaction
+                    .meta
+                    .and_then(|meta| Option::<Vec<String>>::from(meta.log_messages))
+                    .unwrap_or_default();
+                let events = decode_events(&logs);
+                if !events.is_empty() {
+                    transactions.push(EventTransaction { signature, events });
+                }
+            }
+            match page.last() {
+                Some(last) if page.len() == SIGNATURE_PAGE => before = Some(parse_signature(&last.signature)?),
+                _ => return Ok(transactions),
+            }
+        }
+    }
+
+    fn airdrop(&mut self, to: &Pubkey, lamports: u64) -> Result<Signature, CliError> {
+        let signature = self.rpc.request_airdrop(to, lamports).map_err(|error| {
+            // The faucet answers 429, or an internal error naming its limit
+            let text = error.to_string();
+            if text.contains("429") || text.to_lowercase().contains("limit") {
+                CliError::RateLimited(text)
+            } else {
+                CliError::Rpc(error)
+            }
+        })?;
+        self.rpc.poll_for_signature_with_commitment(&signature, self.rpc.commitment())?;
+        Ok(signature)
+    }
+}
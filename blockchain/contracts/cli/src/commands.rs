@@ -0,0 +1,957 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::{AccountDeserialize, Discriminator, ZeroCopy};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::DateTime;
+use ontora_ai::state::{
+    AiAgent, PlatformConfig, Proposal, StakeIndexEntry, UserStake, PROPOSAL_ACTIVE, PROPOSAL_APPROVED,
+    PROPOSAL_EXECUTED, PROPOSAL_REJECTED, PROPOSAL_SUSPENDED,
+};
+use ontora_ai::AIAgent;
+use ontora_client::signer::OntoraSigner;
+use ontora_client::*;
+use serde_json::{json, Value};
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use spl_associated_token_account::get_associated_token_address;
+use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+
+use crate::args::*;
+use crate::bootstrap;
+use crate::cluster::Cluster;
+use crate::decode::{arg_json, decode_admin_instruction, decode_blob, encode_blob};
+use crate::error::CliError;
+use crate::history;
+use crate::migrate::{self, Checkpoint};
+use crate::output::{cell, explorer_url, Output, Table};
+use crate::profile::Profile;
+use crate::squads;
+use crate::tally;
+
+/// The cluster a command runs against and the signer it signs with.
+pub struct Session<'a> {
+    pub cluster: &'a mut dyn Cluster,
+    pub signer: Option<&'a dyn OntoraSigner>,
+    /// The keypair file the signer was read from, if it was.
+    pub keypair: Option<&'a Path>,
+    pub rpc_url: &'a str,
+    /// The profile defaults are read from, and `bootstrap` writes.
+    pub profile: &'a Path,
+    /// Send transactions after simulating them, rather than only simulating.
+    pub yes: bool,
+}
+
+impl<'a> Session<'a> {
+    pub(crate) fn signer(&self) -> Result<&'a dyn OntoraSigner, CliError> {
+        self.signer.ok_or(CliError::NoSigner)
+    }
+
+    // `--mint`, or else the profile's
+    fn mint(&self, mint: Option<Pubkey>) -> Result<Pubkey, CliError> {
+        match mint {
+            Some(mint) => Ok(mint),
+            None => Profile::load(self.profile)?.and_then(|profile| profile.addresses.mint).ok_or(CliError::NoMint),
+        }
+    }
+
+    fn raw_account(&mut self, address: &Pubkey) -> Result<Option<Vec<u8>>, CliError> {
+        match self.cluster.account(address)? {
+            Some(account) if account.owner != ontora_ai::ID => {
+                Err(ClientError::WrongOwner(*address, account.owner).into())
+            }
+            account => Ok(account.map(|account| account.data)),
+        }
+    }
+
+    fn account<T: AccountDeserialize>(&mut self, address: &Pubkey) -> Result<T, CliError> {
+        let data = self.raw_account(address)?.ok_or(ClientError::AccountNotFound(*address))?;
+        Ok(decode_account(address, &data)?)
+    }
+
+    fn zero_copy<T: ZeroCopy>(&mut self, address: &Pubkey) -> Result<T, CliError> {
+        let data = self.raw_account(address)?.ok_or(ClientError::AccountNotFound(*address))?;
+        Ok(decode_zero_copy(address, &data)?)
+    }
+
+    // Simulates `instructions`, then sends them signed by `signer` if `--yes` was given;
+    // without it, the summary is what the command prints
+    fn send(&mut self, instructions: &[Instruction], signer: &dyn OntoraSigner) -> Result<Sent, CliError> {
+        let summary = self.cluster.simulate(instructions, &signer.pubkey())?;
+        if let Some(error) = &summary.error {
+            return Err(CliError::WouldFail(format!("{}\n{}", error, summary.logs.join("\n"))));
+        }
+        if !self.yes {
+            let mut record = vec![("sent", json!(false)), ("next", json!("rerun with --yes to send"))];
+            record.extend(summary_fields(&summary));
+            return Ok(Sent::Preview(Output::record(record)));
+        }
+        let signature = self.cluster.send(instructions, &[signer])?;
+        Ok(Sent::Confirmed(signature, summary))
+    }
+
+    // The output of a confirmed transaction: its signature, explorer link, `fields`,
+    // and what its simulation expected it to do
+    fn confirmed(&self, signature: Signature, summary: &TxSummary, fields: Vec<(&str, Value)>) -> Output {
+        let mut record = vec![
+            ("signature", json!(signature.to_string())),
+            ("explorer", json!(explorer_url(&signature, self.rpc_url))),
+        ];
+        record.extend(fields);
+        record.extend(summary_fields(summary));
+        Output::record(record)
+    }
+}
+
+// What `Session::send` came to
+enum Sent {
+    Confirmed(Signature, TxSummary),
+    /// Simulated only, for want of `--yes`.
+    Preview(Output),
+}
+
+// Sends through `Session::send`; a transaction that was only simulated returns its
+// preview from the calling command
+macro_rules! send_or_preview {
+    ($session:expr, $instructions:expr, $signer:expr) => {
+        match $session.send($instructions, $signer)? {
+            Sent::Confirmed(signature, summary) => (signature, summary),
+            Sent::Preview(output) => return Ok(output),
+        }
+    };
+}
+
+// The fields a simulation summary prints as
+fn summary_fields(summary: &TxSummary) -> Vec<(&'static str, Value)> {
+    let events: Vec<&str> = summary.events.iter().map(OntoraEvent::name).collect();
+    let tokens: Vec<Value> = summary
+        .token_deltas
+        .iter()
+        .map(|delta| {
+            json!({
+                "account": delta.account.to_string(),
+                "owner": delta.owner.to_string(),
+                "mint": delta.mint.to_string(),
+                "before": delta.before,
+                "after": delta.after,
+            })
+        })
+        .collect();
+    let stakes: Vec<Value> = summary
+        .stake_deltas
+        .iter()
+        .map(|delta| {
+            json!({
+                "address": delta.address.to_string(),
+                "staked_before": delta.staked_before,
+                "staked_after": delta.staked_after,
+                "rewards_before": delta.rewards_before,
+                "rewards_after": delta.rewards_after,
+            })
+        })
+        .collect();
+    let reward_pool = summary.reward_pool.as_ref().map(|delta| {
+        json!({ "total_rewards_before": delta.total_rewards_before, "total_rewards_after": delta.total_rewards_after })
+    });
+    vec![
+        ("fee_lamports", json!(summary.fee)),
+        ("compute_units", json!(summary.units_consumed)),
+        ("events", json!(events)),
+        ("token_changes", json!(tokens)),
+        ("stake_changes", json!(stakes)),
+        ("reward_pool_change", json!(reward_pool)),
+    ]
+}
+
+/// Runs `command` and returns what it prints.
+pub fn run(command: &Command, session: &mut Session) -> Result<Output, CliError> {
+    match command {
+        Command::Bootstrap(args) => bootstrap::bootstrap(session, args),
+        Command::Stake(args) => stake(session, args),
+        Command::Unstake(args) => unstake(session, args),
+        Command::Claim(args) => claim(session, args),
+        Command::Agent(AgentCommand::Register(args)) => register_agent(session, args),
+        Command::Agent(AgentCommand::Update(args)) => update_agent(session, args),
+        Command::Agent(AgentCommand::List(args)) => list_agents(session, args),
+        Command::Proposal(ProposalCommand::Create(args)) => create_proposal(session, args),
+        Command::Proposal(ProposalCommand::Vote(args)) => vote(session, args),
+        Command::Proposal(ProposalCommand::Finalize(args)) => finalize_proposal(session, args),
+        Command::Proposal(ProposalCommand::Execute(args)) => execute_proposal(session, args),
+        Command::Pool(PoolCommand::Fund(args)) => fund_pool(session, args),
+        Command::Config(ConfigCommand::Show) => show_config(session),
+        Command::Config(ConfigCommand::Update(args)) => update_config(session, args),
+        Command::Pause(PauseCommand::Set(args)) => set_pause(session, args),
+        Command::Pause(PauseCommand::Clear(args)) => clear_pause(session, args),
+        Command::Treasury(TreasuryCommand::Transfer(args)) => treasury_transfer(session, args),
+        Command::DecodeInstruction(args) => decode_instruction(args),
+        Command::DecodeTx(args) => decode_tx(session, args),
+        Command::VerifyProposal(args) => verify_proposal(session, args),
+        Command::MigrateAgents(args) => migrate_agents(session, args),
+        Command::History(HistoryCommand::Export(args)) => export_history(session, args),
+        Command::History(HistoryCommand::Timeline(args)) => history_timeline(session, args),
+    }
+}
+
+fn stake(session: &mut Session, args: &StakeArgs) -> Result<Output, CliError> {
+    let user = session.signer()?;
+    let mint = session.mint(args.mint)?;
+    let instruction = build_stake_on_agent(StakeOnAgentParams {
+        user: user.pubkey(),
+        user_token_account: get_associated_token_address(&user.pubkey(), &mint),
+        platform_vault: pda::staking_vault().0,
+        agent_owner: args.agent_owner,
+        agent_id: args.agent_id,
+        amount: args.amount,
+        beneficiary: None,
+    });
+    let (signature, summary) = send_or_preview!(session, &[instruction], user);
+    let position: UserStake = session.account(&pda::user_stake(&user.pubkey()).0)?;
+    Ok(session.confirmed(
+        signature,
+        &summary,
+        vec![
+            ("agent", json!(pda::ai_agent(&args.agent_owner, args.agent_id).0.to_string())),
+            ("amount", json!(args.amount)),
+            ("staked_amount", json!(position.staked_amount)),
+        ],
+    ))
+}
+
+fn unstake(session: &mut Session, args: &StakeArgs) -> Result<Output, CliError> {
+    let user = session.signer()?;
+    let mint = session.mint(args.mint)?;
+    let instruction = build_unstake_from_agent(UnstakeFromAgentParams {
+        user: user.pubkey(),
+        user_token_account: get_associated_token_address(&user.pubkey(), &mint),
+        platform_vault: pda::staking_vault().0,
+        stake_mint: mint,
+        agent_owner: args.agent_owner,
+        agent_id: args.agent_id,
+        amount: args.amount,
+    });
+    let (signature, summary) = send_or_preview!(session, &[instruction], user);
+    let position: UserStake = session.account(&pda::user_stake(&user.pubkey()).0)?;
+    Ok(session.confirmed(
+        signature,
+        &summary,
+        vec![
+            ("agent", json!(pda::ai_agent(&args.agent_owner, args.agent_id).0.to_string())),
+            ("amount", json!(args.amount)),
+            ("staked_amount", json!(position.staked_amount)),
+        ],
+    ))
+}
+
+fn claim(session: &mut Session, args: &ClaimArgs) -> Result<Output, CliError> {
+    let user = session.signer()?;
+    let agent_address = pda::legacy_ai_agent(&user.pubkey()).0;
+    let agent: AIAgent = session.account(&agent_address)?;
+    let mint = session.mint(args.mint)?;
+    let user_token_account = get_associated_token_address(&user.pubkey(), &mint);
+    let instructions = [
+        create_associated_token_account_idempotent(&user.pubkey(), &user.pubkey(), &mint, &spl_token::ID),
+        build_claim_rewards(ClaimRewardsParams {
+            user: user.pubkey(),
+            user_token_account,
+            reward_vault: pda::reward_vault().0,
+            reward_mint: mint,
+        }),
+    ];
+    let (signature, summary) = send_or_preview!(session, &instructions, user);
+    Ok(session.confirmed(
+        signature,
+        &summary,
+        vec![
+            ("agent", json!(agent_address.to_string())),
+            // Before the claim fee, which goes to the treasury
+            ("claimed", json!(agent.accumulated_rewards)),
+            ("token_account", json!(user_token_account.to_string())),
+        ],
+    ))
+}
+
+fn register_agent(session: &mut Session, args: &AgentArgs) -> Result<Output, CliError> {
+    let owner = session.signer()?;
+    let instruction = build_register_agent(RegisterAgentParams {
+        owner: owner.pubkey(),
+        agent_id: args.id,
+        name: args.name.clone(),
+        description: args.description.clone(),
+    });
+    let (signature, summary) = send_or_preview!(session, &[instruction], owner);
+    Ok(session.confirmed(
+        signature,
+        &summary,
+        vec![
+            ("agent", json!(pda::ai_agent(&owner.pubkey(), args.id).0.to_string())),
+            ("agent_id", json!(args.id)),
+            ("name", json!(args.name)),
+        ],
+    ))
+}
+
+fn update_agent(session: &mut Session, args: &AgentArgs) -> Result<Output, CliError> {
+    let owner = session.signer()?;
+    let instruction = build_update_agent_metadata(UpdateAgentMetadataParams {
+        owner: owner.pubkey(),
+        agent_id: args.id,
+        name: args.name.clone(),
+        description: args.description.clone(),
+    });
+    let (signature, summary) = send_or_preview!(session, &[instruction], owner);
+    Ok(session.confirmed(
+        signature,
+        &summary,
+        vec![
+            ("agent", json!(pda::ai_agent(&owner.pubkey(), args.id).0.to_string())),
+            ("agent_id", json!(args.id)),
+            ("name", json!(args.name)),
+        ],
+    ))
+}
+
+fn list_agents(session: &mut Session, args: &ListAgentsArgs) -> Result<Output, CliError> {
+    let mut agents = Vec::new();
+    for (address, account) in session.cluster.program_accounts(&AiAgent::DISCRIMINATOR)? {
+        let agent: AiAgent = decode_account(&address, &account.data)?;
+        if args.owner.is_some_and(|owner| owner != agent.owner) {
+            continue;
+        }
+        agents.push((address, agent));
+    }
+    agents.sort_by_key(|(_, agent)| (agent.owner, agent.agent_id));
+
+    let mut table = Table::new(&["ADDRESS", "OWNER", "ID", "NAME", "SCORE", "STAKED"]);
+    let mut json = Vec::new();
+    for (address, agent) in &agents {
+        let entry = json!({
+            "address": address.to_string(),
+            "owner": agent.owner.to_string(),
+            "agent_id": agent.agent_id,
+            "name": agent.name,
+            "description": agent.description,
+            "performance_score": agent.performance_score,
+            "staked_amount": agent.staked_amount,
+        });
+        table.row(
+            ["address", "owner", "agent_id", "name", "performance_score", "staked_amount"]
+                .iter()
+                .map(|field| cell(&entry[field]))
+                .collect(),
+        );
+        json.push(entry);
+    }
+    Ok(Output { json: Value::Array(json), table })
+}
+
+fn create_proposal(session: &mut Session, args: &CreateProposalArgs) -> Result<Output, CliError> {
+    let creator = session.signer()?;
+    let config: PlatformConfig = session.account(&pda::platform_config().0)?;
+    let proposal_id = config.proposal_count;
+    let instruction = build_create_governance_proposal(CreateGovernanceProposalParams {
+        creator: creator.pubkey(),
+        proposal_id,
+        title: args.title.clone(),
+        description: args.description.clone(),
+        voting_duration: args.voting_duration,
+        options: args.options.clone(),
+        action: args.action(),
+    });
+    let (signature, summary) = send_or_preview!(session, &[instruction], creator);
+    let proposal: Proposal = session.zero_copy(&pda::proposal(proposal_id).0)?;
+    Ok(session.confirmed(
+        signature,
+        &summary,
+        vec![
+            ("proposal", json!(pda::proposal(proposal_id).0.to_string())),
+            ("proposal_id", json!(proposal_id)),
+            ("end_time", json!(proposal.end_time)),
+        ],
+    ))
+}
+
+fn vote(session: &mut Session, args: &VoteArgs) -> Result<Output, CliError> {
+    let voter = session.signer()?;
+    let instruction = build_cast_vote(CastVoteParams {
+        voter: voter.pubkey(),
+        proposal_id: args.id,
+        vote_option: args.option,
+    });
+    let (signature, summary) = send_or_preview!(session, &[instruction], voter);
+    let proposal: Proposal = session.zero_copy(&pda::proposal(args.id).0)?;
+    Ok(session.confirmed(
+        signature,
+        &summary,
+        vec![
+            ("proposal_id", json!(args.id)),
+            ("option", json!(proposal.option(args.option as usize))),
+            ("tallies", json!(proposal.tallies())),
+        ],
+    ))
+}
+
+fn finalize_proposal(session: &mut Session, args: &FinalizeArgs) -> Result<Output, CliError> {
+    let caller = session.signer()?;
+    let instruction = build_finalize_proposal(FinalizeProposalParams { caller: caller.pubkey(), proposal_id: args.id });
+    let (signature, summary) = send_or_preview!(session, &[instruction], caller);
+    let proposal: Proposal = session.zero_copy(&pda::proposal(args.id).0)?;
+    Ok(session.confirmed(
+        signature,
+        &summary,
+        vec![
+            ("proposal_id", json!(args.id)),
+            ("status", json!(status_name(proposal.status))),
+            ("tallies", json!(proposal.tallies())),
+        ],
+    ))
+}
+
+fn execute_proposal(session: &mut Session, args: &ExecuteArgs) -> Result<Output, CliError> {
+    let executor = session.signer()?;
+    let instruction =
+        build_execute_proposal(ExecuteProposalParams { executor: executor.pubkey(), proposal_id: args.id });
+    let (signature, summary) = send_or_preview!(session, &[instruction], executor);
+    let proposal: Proposal = session.zero_copy(&pda::proposal(args.id).0)?;
+    let config: PlatformConfig = session.account(&pda::platform_config().0)?;
+    Ok(session.confirmed(
+        signature,
+        &summary,
+        vec![
+            ("proposal_id", json!(args.id)),
+            ("status", json!(status_name(proposal.status))),
+            ("reward_rate_bps", json!(config.reward_rate_bps)),
+            ("min_stake_amount", json!(config.min_stake_amount)),
+            ("epoch_duration", json!(config.epoch_duration)),
+        ],
+    ))
+}
+
+fn fund_pool(session: &mut Session, args: &FundPoolArgs) -> Result<Output, CliError> {
+    let funder = session.signer()?;
+    let mint = session.mint(args.mint)?;
+    let reward_pool = pda::reward_pool().0;
+    let reward_vault = pda::reward_vault().0;
+    let funder_token_account = get_associated_token_address(&funder.pubkey(), &mint);
+    let mut instructions = Vec::new();
+    let initialize = session.raw_account(&reward_pool)?.is_none();
+    if initialize {
+        instructions.push(build_initialize_reward_pool(InitializeRewardPoolParams {
+            payer: funder.pubkey(),
+            initial_rewards: args.amount,
+        }));
+    }
+    if initialize {
+        // Initializing records the total, so the tokens only have to reach the vault
+        instructions.push(
+            spl_token::instruction::transfer(
+                &spl_token::ID,
+                &funder_token_account,
+                &reward_vault,
+                &funder.pubkey(),
+                &[],
+                args.amount,
+            )
+            .expect("the token program id is valid"),
+        );
+    } else {
+        instructions.push(build_fund_reward_pool(FundRewardPoolParams {
+            funder: funder.pubkey(),
+            funder_token_account,
+            reward_mint: mint,
+            amount: args.amount,
+        }));
+    }
+    let (signature, summary) = send_or_preview!(session, &instructions, funder);
+    Ok(session.confirmed(
+        signature,
+        &summary,
+        vec![
+            ("reward_pool", json!(reward_pool.to_string())),
+            ("reward_vault", json!(reward_vault.to_string())),
+            ("amount", json!(args.amount)),
+            ("initialized", json!(initialize)),
+        ],
+    ))
+}
+
+fn show_config(session: &mut Session) -> Result<Output, CliError> {
+    let address = pda::platform_config().0;
+    let config: PlatformConfig = session.account(&address)?;
+    let [major, minor, patch] = config.program_version;
+    Ok(Output::record(vec![
+        ("address", json!(address.to_string())),
+        ("admin", json!(config.admin.to_string())),
+        ("pending_admin", json!(config.pending_admin.to_string())),
+        ("reward_rate_bps", json!(config.reward_rate_bps)),
+        ("min_stake_amount", json!(config.min_stake_amount)),
+        ("epoch_duration", json!(config.epoch_duration)),
+        ("cooldown_seconds", json!(config.cooldown_seconds)),
+        ("epoch_emission", json!(config.epoch_emission)),
+        ("max_stake_per_agent", json!(config.max_stake_per_agent)),
+        ("vesting_seconds", json!(config.vesting_seconds)),
+        ("vote_unit", json!(config.vote_unit)),
+        ("quorum_bps", json!(config.quorum_bps)),
+        ("approval_threshold_bps", json!(config.approval_threshold_bps)),
+        ("total_staked", json!(config.total_staked)),
+        ("total_agents", json!(config.total_agents)),
+        ("pause_flags", json!(config.pause_flags)),
+        ("pauser", json!(config.pauser.to_string())),
+        ("crank", json!(config.roles.crank.to_string())),
+        ("oracle", json!(config.roles.oracle.to_string())),
+        ("treasury", json!(config.roles.treasury.to_string())),
+        ("stake_mint", json!(config.stake_mint.to_string())),
+        ("staking_vault", json!(config.staking_vault.to_string())),
+        ("reward_vault", json!(config.reward_vault.to_string())),
+        ("governance_enabled", json!(config.governance_enabled)),
+        ("proposal_count", json!(config.proposal_count)),
+        ("active_proposal_count", json!(config.active_proposal_count)),
+        ("version", json!(config.version)),
+        ("program_version", json!(format!("{}.{}.{}", major, minor, patch))),
+    ]))
+}
+
+// The key an admin command's instruction names as its authority: the multisig's
+// vault with `--multisig`, the signer otherwise
+fn authority(session: &Session, args: &MultisigArgs) -> Result<Pubkey, CliError> {
+    match args.multisig {
+        Some(multisig) => Ok(squads::vault(&multisig, args.vault_index).0),
+        None => Ok(session.signer()?.pubkey()),
+    }
+}
+
+// Prints `instruction` as a blob for the multisig's co-signers, and with `--yes`
+// stores it as a vault transaction of the multisig and opens its proposal
+fn propose(
+    session: &mut Session,
+    multisig: Pubkey,
+    args: &MultisigArgs,
+    instruction: Instruction,
+) -> Result<Output, CliError> {
+    let decoded = decode_admin_instruction(&instruction)?;
+    let blob = encode_blob(&instruction);
+    let mut record = vec![
+        ("multisig", json!(multisig.to_string())),
+        ("vault", json!(squads::vault(&multisig, args.vault_index).0.to_string())),
+        ("instruction", json!(decoded.name)),
+        ("base58", json!(blob.base58)),
+        ("base64", json!(blob.base64)),
+    ];
+    if !session.yes {
+        record.push(("next", json!("rerun with --yes to open a Squads proposal for it")));
+        return Ok(Output::record(record));
+    }
+    let creator = session.signer()?;
+    let account = session.cluster.account(&multisig)?.ok_or(ClientError::AccountNotFound(multisig))?;
+    if account.owner != squads::ID {
+        return Err(ClientError::WrongOwner(multisig, account.owner).into());
+    }
+    let transaction_index = squads::next_transaction_index(&multisig, &account.data)?;
+    let params = squads::ProposeParams {
+        multisig,
+        vault_index: args.vault_index,
+        transaction_index,
+        creator: creator.pubkey(),
+        memo: args.memo.clone(),
+    };
+    let instructions = squads::propose(params, &[instruction])?;
+    let (signature, summary) = send_or_preview!(session, &instructions, creator);
+    record.extend([
+        ("transaction_index", json!(transaction_index)),
+        ("transaction", json!(squads::transaction(&multisig, transaction_index).0.to_string())),
+        ("proposal", json!(squads::proposal(&multisig, transaction_index).0.to_string())),
+    ]);
+    Ok(session.confirmed(signature, &summary, record))
+}
+
+fn update_config(session: &mut Session, args: &UpdateConfigArgs) -> Result<Output, CliError> {
+    let admin = authority(session, &args.multisig)?;
+    let address = pda::platform_config().0;
+    let config: PlatformConfig = session.account(&address)?;
+    let instruction = build_update_platform_config(UpdatePlatformConfigParams {
+        admin,
+        reward_rate_bps: args.reward_rate_bps.unwrap_or(config.reward_rate_bps),
+        min_stake_amount: args.min_stake_amount.unwrap_or(config.min_stake_amount),
+        epoch_duration: args.epoch_duration.unwrap_or(config.epoch_duration),
+        cooldown_seconds: args.cooldown_seconds.unwrap_or(config.cooldown_seconds),
+        epoch_emission: args.epoch_emission.unwrap_or(config.epoch_emission),
+        max_stake_per_agent: args.max_stake_per_agent.unwrap_or(config.max_stake_per_agent),
+        vesting_seconds: args.vesting_seconds.unwrap_or(config.vesting_seconds),
+        vote_unit: args.vote_unit.unwrap_or(config.vote_unit),
+        quorum_bps: args.quorum_bps.unwrap_or(config.quorum_bps),
+        approval_threshold_bps: args.approval_threshold_bps.unwrap_or(config.approval_threshold_bps),
+        update_metadata: args.note.clone(),
+    });
+    if let Some(multisig) = args.multisig.multisig {
+        return propose(session, multisig, &args.multisig, instruction);
+    }
+    let signer = session.signer()?;
+    let (signature, summary) = send_or_preview!(session, &[instruction], signer);
+    let config: PlatformConfig = session.account(&address)?;
+    Ok(session.confirmed(
+        signature,
+        &summary,
+        vec![
+            ("reward_rate_bps", json!(config.reward_rate_bps)),
+            ("min_stake_amount", json!(config.min_stake_amount)),
+            ("epoch_duration", json!(config.epoch_duration)),
+            ("cooldown_seconds", json!(config.cooldown_seconds)),
+            ("epoch_emission", json!(config.epoch_emission)),
+            ("max_stake_per_agent", json!(config.max_stake_per_agent)),
+            ("vesting_seconds", json!(config.vesting_seconds)),
+            ("vote_unit", json!(config.vote_unit)),
+            ("quorum_bps", json!(config.quorum_bps)),
+            ("approval_threshold_bps", json!(config.approval_threshold_bps)),
+        ],
+    ))
+}
+
+fn set_pause(session: &mut Session, args: &PauseArgs) -> Result<Output, CliError> {
+    let authority = authority(session, &args.multisig)?;
+    let instruction = build_set_pause_flags(SetPauseFlagsParams { authority, flags: args.flags() });
+    if let Some(multisig) = args.multisig.multisig {
+        return propose(session, multisig, &args.multisig, instruction);
+    }
+    let signer = session.signer()?;
+    let (signature, summary) = send_or_preview!(session, &[instruction], signer);
+    let config: PlatformConfig = session.account(&pda::platform_config().0)?;
+    Ok(session.confirmed(signature, &summary, pause_fields(config.pause_flags)))
+}
+
+fn clear_pause(session: &mut Session, args: &PauseArgs) -> Result<Output, CliError> {
+    let admin = authority(session, &args.multisig)?;
+    let instruction = build_clear_pause_flags(ClearPauseFlagsParams { admin, flags: args.flags() });
+    if let Some(multisig) = args.multisig.multisig {
+        return propose(session, multisig, &args.multisig, instruction);
+    }
+    let signer = session.signer()?;
+    let (signature, summary) = send_or_preview!(session, &[instruction], signer);
+    let config: PlatformConfig = session.account(&pda::platform_config().0)?;
+    Ok(session.confirmed(signature, &summary, pause_fields(config.pause_flags)))
+}
+
+fn pause_fields(flags: u8) -> Vec<(&'static str, Value)> {
+    vec![("pause_flags", json!(flags)), ("paused", json!(pause_groups(flags)))]
+}
+
+fn treasury_transfer(session: &mut Session, args: &TreasuryTransferArgs) -> Result<Output, CliError> {
+    let governance = authority(session, &args.multisig)?;
+    let recipient = get_associated_token_address(&args.recipient, &args.mint);
+    let instruction = build_treasury_transfer(TreasuryTransferParams {
+        governance,
+        mint: args.mint,
+        recipient,
+        amount: args.amount,
+    });
+    if let Some(multisig) = args.multisig.multisig {
+        return propose(session, multisig, &args.multisig, instruction);
+    }
+    let signer = session.signer()?;
+    let (signature, summary) = send_or_preview!(session, &[instruction], signer);
+    Ok(session.confirmed(
+        signature,
+        &summary,
+        vec![("amount", json!(args.amount)), ("recipient", json!(recipient.to_string()))],
+    ))
+}
+
+fn decode_instruction(args: &DecodeInstructionArgs) -> Result<Output, CliError> {
+    let instruction = decode_blob(&args.blob)?;
+    let decoded = decode_admin_instruction(&instruction)?;
+    let mut record = vec![("program", json!(instruction.program_id.to_string())), ("instruction", json!(decoded.name))];
+    record.extend(decoded.args.iter().map(|(name, value)| (*name, arg_json(value))));
+    record.push(("accounts", json!(accounts_json(&decoded.accounts))));
+    Ok(Output::record(record))
+}
+
+fn accounts_json(accounts: &[(&str, AccountMeta)]) -> Vec<Value> {
+    accounts
+        .iter()
+        .map(|(name, meta)| {
+            json!({
+                "name": name,
+                "address": meta.pubkey.to_string(),
+                "signer": meta.is_signer,
+                "writable": meta.is_writable,
+            })
+        })
+        .collect()
+}
+
+fn decode_tx(session: &mut Session, args: &DecodeTxArgs) -> Result<Output, CliError> {
+    let transaction = parse_transaction(&args.transaction)?;
+    let mut lookup_tables = Vec::new();
+    for lookup in transaction.message.address_table_lookups().unwrap_or_default() {
+        let address = lookup.account_key;
+        let account = session.cluster.account(&address)?.ok_or(ClientError::AccountNotFound(address))?;
+        lookup_tables.push(decode_lookup_table(&address, &account.data)?);
+    }
+    let decoded = decode_transaction(&transaction, &lookup_tables)?;
+
+    let mut table = Table::new(&["#", "INSTRUCTION"]);
+    let mut instructions = Vec::new();
+    for (index, instruction) in decoded.instructions.iter().enumerate() {
+        table.row(vec![index.to_string(), instruction.summary(args.decimals)]);
+        let mut entry = json!({ "program": instruction.program_id().to_string() });
+        match instruction {
+            ReviewedInstruction::Decoded(call) => {
+                let fields = call.args.iter().map(|(name, value)| (name.to_string(), arg_json(value)));
+                entry["instruction"] = json!(call.name);
+                entry["args"] = Value::Object(fields.collect());
+                entry["accounts"] = json!(accounts_json(&call.accounts));
+            }
+            ReviewedInstruction::Undecoded { instruction, reason } => {
+                entry["error"] = json!(reason);
+                entry["data"] = json!(STANDARD.encode(&instruction.data));
+            }
+            ReviewedInstruction::Other(instruction) => {
+                entry["data"] = json!(STANDARD.encode(&instruction.data));
+            }
+        }
+        instructions.push(entry);
+    }
+    let json = json!({ "fee_payer": decoded.fee_payer.to_string(), "instructions": instructions });
+    Ok(Output { json, table })
+}
+
+// The report is signed over the compact JSON of its other fields, keys sorted
+fn verify_proposal(session: &mut Session, args: &VerifyProposalArgs) -> Result<Output, CliError> {
+    let verifier = session.signer()?;
+    let address = pda::proposal(args.id).0;
+    let proposal: Proposal = session.zero_copy(&address)?;
+    let transactions = session.cluster.event_transactions(&address, proposal.start_time)?;
+    let history = tally::vote_history(args.id, &transactions);
+    let recounted = tally::recount(proposal.option_count as usize, &history.ballots);
+    let config: PlatformConfig = session.account(&pda::platform_config().0)?;
+    let discrepancies = tally::discrepancies(&proposal, &history, proposal.effective_vote_unit(&config));
+    let reported = history.finalized.as_ref().and_then(|event| tally::parse_vote_summary(&event.vote_summary));
+    let voters: BTreeSet<Pubkey> = history.ballots.iter().map(|ballot| ballot.voter).collect();
+
+    let report = vec![
+        ("proposal", json!(address.to_string())),
+        ("proposal_id", json!(args.id)),
+        ("status", json!(status_name(proposal.status))),
+        ("votes", json!(history.ballots.len())),
+        ("voters", json!(voters.len())),
+        ("recounted", json!(recounted)),
+        ("on_chain", json!(proposal.tallies())),
+        ("finalized", json!(reported)),
+        ("verified", json!(discrepancies.is_empty())),
+        ("discrepancies", json!(discrepancies)),
+    ];
+    let message = serde_json::to_vec(&Output::record(report.clone()).json).expect("JSON values always serialize");
+    let signature = verifier.sign_message(&message)?;
+    let mut record = report;
+    record.push(("verifier", json!(verifier.pubkey().to_string())));
+    record.push(("signature", json!(signature.to_string())));
+    Ok(Output::record(record))
+}
+
+fn migrate_agents(session: &mut Session, args: &MigrateAgentsArgs) -> Result<Output, CliError> {
+    let admin = session.signer()?;
+    let mint = session.mint(args.mint)?;
+    let mut checkpoint = Checkpoint::load(&args.checkpoint)?;
+    let mut legacy_agents = Vec::new();
+    for (address, account) in session.cluster.program_accounts(&AIAgent::DISCRIMINATOR)? {
+        legacy_agents.push((address, decode_account::<AIAgent>(&address, &account.data)?));
+    }
+
+    let platform_vault = pda::staking_vault().0;
+    let mut migrations = Vec::new();
+    let mut skipped = Vec::new();
+    for (address, agent) in migrate::pending_agents(&legacy_agents, &checkpoint) {
+        let staking_vault = pda::legacy_staking_vault(&agent.owner, &mint).0;
+        let held = match session.cluster.account(&staking_vault)? {
+            Some(account) => spl_token::state::Account::unpack(&account.data).map(|vault| vault.amount).ok(),
+            None => None,
+        };
+        if !matches!(held, Some(amount) if amount >= agent.staked_amount) {
+            skipped.push(json!({
+                "agent": address.to_string(),
+                "owner": agent.owner.to_string(),
+                "staking_vault": staking_vault.to_string(),
+                "staked_amount": agent.staked_amount,
+                "held": held,
+            }));
+            continue;
+        }
+        let instruction = build_migrate_agent_v1(MigrateAgentV1Params {
+            admin: admin.pubkey(),
+            owner: agent.owner,
+            stake_mint: mint,
+            staking_vault,
+            platform_vault,
+        });
+        migrations.push((address, instruction));
+    }
+
+    let already_migrated = checkpoint.migrated.len();
+    let pending = migrations.len();
+    let batches = migrate::plan_batches(migrations, &admin.pubkey());
+    let mut signatures = Vec::new();
+    for batch in &batches {
+        // Without --yes every batch is still simulated, so a failing one shows up
+        if let Sent::Confirmed(signature, _) = session.send(&batch.instructions, admin)? {
+            checkpoint.record(&batch.agents);
+            checkpoint.store(&args.checkpoint)?;
+            signatures.push(json!(signature.to_string()));
+        }
+    }
+
+    let mut record = vec![
+        ("sent", json!(session.yes)),
+        ("pending", json!(pending)),
+        ("transactions", json!(batches.len())),
+        ("already_migrated", json!(already_migrated)),
+        ("skipped", json!(skipped)),
+        ("checkpoint", json!(args.checkpoint.display().to_string())),
+    ];
+    if session.yes {
+        record.push(("migrated", json!(checkpoint.migrated.len() - already_migrated)));
+        record.push(("signatures", json!(signatures)));
+    } else {
+        record.push(("next", json!("rerun with --yes to send")));
+    }
+    Ok(Output::record(record))
+}
+
+fn mint_decimals(session: &mut Session, mint: &Pubkey) -> Result<u8, CliError> {
+    let account = session.cluster.account(mint)?.ok_or(ClientError::AccountNotFound(*mint))?;
+    let mint_state = spl_token::state::Mint::unpack(&account.data)
+        .map_err(|error| CliError::Cluster(format!("{} is not a token mint: {}", mint, error)))?;
+    Ok(mint_state.decimals)
+}
+
+fn export_history(session: &mut Session, args: &ExportHistoryArgs) -> Result<Output, CliError> {
+    let range = history::year_range(args.year).expect("the year argument is range checked");
+    let mint = session.mint(args.mint)?;
+    let (source, mut entries) = match &args.database_url {
+        Some(url) => {
+            let entries =
+                history::from_database(url, &args.wallet, &mint, args.price_symbol.as_deref(), range.clone())?;
+            ("indexer", entries)
+        }
+        None => {
+            let mut entries = Vec::new();
+            for transaction in session.cluster.event_transactions(&args.wallet, range.start)? {
+                let found = history::entries(&args.wallet, &mint, &transaction.signature, &transaction.events);
+                entries.extend(found.into_iter().filter(|entry| range.contains(&entry.timestamp)));
+            }
+            ("cluster", entries)
+        }
+    };
+    history::sort(&mut entries);
+
+    let mut decimals = BTreeMap::new();
+    for entry in &entries {
+        if !decimals.contains_key(&entry.mint) {
+            decimals.insert(entry.mint, mint_decimals(session, &entry.mint)?);
+        }
+    }
+    let rows: Vec<Value> =
+        entries.iter().map(|entry| history::fields(entry, &decimals, args.price_decimals)).collect();
+    let (extension, text) = match args.format {
+        ExportFormat::Csv => ("csv", history::to_csv(&rows)),
+        ExportFormat::Json => {
+            ("json", serde_json::to_string_pretty(&rows).expect("JSON values always serialize") + "\n")
+        }
+    };
+    let path = args
+        .output
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(format!("ontora-history-{}-{}.{}", args.wallet, args.year, extension)));
+    std::fs::write(&path, text)?;
+    Ok(Output::record(vec![
+        ("file", json!(path.display().to_string())),
+        ("wallet", json!(args.wallet.to_string())),
+        ("year", json!(args.year)),
+        ("source", json!(source)),
+        ("entries", json!(rows.len())),
+    ]))
+}
+
+// The events of `args.wallet` from the cluster and, given one, the indexer
+// database, as a timeline checked against the wallet's stake index entries
+fn history_timeline(session: &mut Session, args: &TimelineArgs) -> Result<Output, CliError> {
+    let mut events = match &args.database_url {
+        Some(url) => history::wallet_events_from_database(url, &args.wallet)?,
+        None => Vec::new(),
+    };
+    for transaction in session.cluster.event_transactions(&args.wallet, i64::MIN)?.iter().rev() {
+        events.extend(wallet_events(&args.wallet, &transaction.signature, &transaction.events));
+    }
+    let timeline = Timeline::build(events);
+    let mut entries = Vec::new();
+    for (address, account) in session.cluster.program_accounts(&StakeIndexEntry::DISCRIMINATOR)? {
+        let entry: StakeIndexEntry = decode_account(&address, &account.data)?;
+        if entry.user == args.wallet {
+            entries.push((address, entry));
+        }
+    }
+    let unreconciled = timeline.reconcile(&on_chain_stakes(&entries));
+
+    let mut table = Table::new(&["DATE", "TYPE", "TARGET", "AMOUNT", "STAKED", "CLAIMED", "SIGNATURE", "GAP"]);
+    let mut json = Vec::new();
+    for entry in &timeline.entries {
+        let date = DateTime::from_timestamp(entry.event.timestamp, 0).map(|date| date.format("%Y-%m-%d %H:%M:%S"));
+        let (target, amount) = match entry.event.activity {
+            Activity::Stake { agent_id, amount }
+            | Activity::Unstake { agent_id, amount }
+            | Activity::Claim { agent_id, amount } => (format!("agent {}", agent_id), amount),
+            Activity::Vote { proposal_id, option, weight } => {
+                (format!("proposal {} option {}", proposal_id, option), weight)
+            }
+        };
+        let gap = entry.gap.map(|gap| gap.to_string());
+        table.row(vec![
+            date.map(|date| date.to_string()).unwrap_or_default(),
+            entry.event.activity.name().to_string(),
+            target.clone(),
+            amount.to_string(),
+            entry.staked.to_string(),
+            entry.claimed.to_string(),
+            entry.event.signature.to_string(),
+            gap.clone().unwrap_or_default(),
+        ]);
+        json.push(json!({
+            "timestamp": entry.event.timestamp,
+            "type": entry.event.activity.name(),
+            "target": target,
+            "amount": amount,
+            "staked": entry.staked,
+            "claimed": entry.claimed,
+            "signature": entry.event.signature.to_string(),
+            "event_index": entry.event.index,
+            "gap": gap,
+        }));
+    }
+    let mut gaps: Vec<String> = timeline.gaps().iter().map(ToString::to_string).collect();
+    for gap in &unreconciled {
+        let mut row = vec![String::new(); 8];
+        (row[1], row[7]) = ("gap".to_string(), gap.to_string());
+        table.row(row);
+        gaps.push(gap.to_string());
+    }
+    let json = json!({
+        "wallet": args.wallet.to_string(),
+        "staked": timeline.staked(),
+        "on_chain_staked": entries.iter().map(|(_, entry)| entry.staked_amount).sum::<u64>(),
+        "claimed": timeline.claimed(),
+        "gaps": gaps,
+        "entries": json,
+    });
+    Ok(Output { json, table })
+}
+
+pub(crate) fn status_name(status: u8) -> &'static str {
+    match status {
+        PROPOSAL_ACTIVE => "active",
+        PROPOSAL_APPROVED => "approved",
+        PROPOSAL_REJECTED => "rejected",
+        PROPOSAL_SUSPENDED => "suspended",
+        PROPOSAL_EXECUTED => "executed",
+        _ => "unknown",
+    }
+}
@@ -0,0 +1,85 @@
+//! Instruction blobs: the admin instruction a `--multisig` command builds,
+//! bincode-serialized and printed as base58 and base64 for co-signers, and
+//! decoded back into named arguments and accounts for reviewing them with the
+//! client's decoder.
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use bincode::Options;
+use ontora_client::{decode_instruction, ArgValue, ClientError, DecodedInstruction};
+use serde_json::{json, Value};
+use solana_sdk::instruction::Instruction;
+
+use crate::error::CliError;
+
+/// An instruction as the base58 and base64 text of its bincode encoding.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Blob {
+    pub base58: String,
+    pub base64: String,
+}
+
+pub fn encode_blob(instruction: &Instruction) -> Blob {
+    let bytes = bincode::serialize(instruction).expect("instructions always serialize");
+    Blob { base58: bs58::encode(&bytes).into_string(), base64: STANDARD.encode(&bytes) }
+}
+
+fn from_bincode(bytes: &[u8]) -> Option<Instruction> {
+    bincode::DefaultOptions::new().with_fixint_encoding().reject_trailing_bytes().deserialize(bytes).ok()
+}
+
+/// The instruction in a blob of either encoding. Base58 is tried first: its
+/// alphabet lacks `+`, `/`, `=`, `0`, `O`, `I` and `l`, so most base64 text
+/// fails to decode as it, and what does decode is not a whole instruction.
+pub fn decode_blob(blob: &str) -> Result<Instruction, CliError> {
+    let blob = blob.trim();
+    if let Some(instruction) = bs58::decode(blob).into_vec().ok().and_then(|bytes| from_bincode(&bytes)) {
+        return Ok(instruction);
+    }
+    let bytes = STANDARD
+        .decode(blob)
+        .map_err(|_| CliError::Instruction("the blob is neither base58 nor base64".to_string()))?;
+    from_bincode(&bytes).ok_or_else(|| CliError::Instruction("the blob does not hold an instruction".to_string()))
+}
+
+// The instructions a multisig vault may hold the admin, pauser or treasury
+// governance key for
+const ADMIN_INSTRUCTIONS: [&str; 11] = [
+    "update_platform_config",
+    "migrate_platform_config",
+    "set_governance_enabled",
+    "propose_admin",
+    "cancel_admin_transfer",
+    "update_fee_schedule",
+    "treasury_transfer",
+    "set_pause_flags",
+    "clear_pause_flags",
+    "set_role",
+    "set_governance_realm",
+];
+
+/// Decodes one of the program's admin instructions: those a multisig vault
+/// may hold the admin, pauser or treasury governance key for.
+pub fn decode_admin_instruction(instruction: &Instruction) -> Result<DecodedInstruction, CliError> {
+    let decoded = decode_instruction(instruction).map_err(|error| match error {
+        ClientError::Instruction(reason) => CliError::Instruction(reason),
+        error => CliError::Instruction(error.to_string()),
+    })?;
+    if !ADMIN_INSTRUCTIONS.contains(&decoded.name) {
+        return Err(CliError::Instruction(format!("{} is not one of the program's admin instructions", decoded.name)));
+    }
+    Ok(decoded)
+}
+
+/// A decoded argument as JSON; token amounts stay in base units.
+pub fn arg_json(value: &ArgValue) -> Value {
+    match value {
+        ArgValue::Unsigned(value) | ArgValue::Tokens(value) => json!(value),
+        ArgValue::Signed(value) => json!(value),
+        ArgValue::Bool(value) => json!(value),
+        ArgValue::Text(text) => json!(text),
+        ArgValue::Key(key) => json!(key.to_string()),
+        ArgValue::Label(label) => json!(label),
+        ArgValue::List(values) => Value::Array(values.iter().map(arg_json).collect()),
+    }
+}
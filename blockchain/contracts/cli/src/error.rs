@@ -0,0 +1,30 @@
+use thiserror::Error;
+
+/// Errors from running a command.
+#[derive(Debug, Error)]
+pub enum CliError {
+    #[error("RPC request failed: {0}")]
+    Rpc(#[from] solana_client::client_error::ClientError),
+    #[error("cluster request failed: {0}")]
+    Cluster(String),
+    #[error(transparent)]
+    Client(#[from] ontora_client::ClientError),
+    #[error("transaction would fail: {0}")]
+    WouldFail(String),
+    #[error("cannot decode instruction: {0}")]
+    Instruction(String),
+    #[error("this command signs a transaction; pass --keypair or --signer")]
+    NoSigner,
+    #[error("no mint given; pass --mint, or run `ontora bootstrap` to write one to the profile")]
+    NoMint,
+    #[error("profile {0}")]
+    Profile(String),
+    #[error("faucet rate limit reached: {0}")]
+    RateLimited(String),
+    #[error("checkpoint {0}")]
+    Checkpoint(String),
+    #[error("indexer database: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
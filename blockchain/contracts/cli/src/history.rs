@@ -0,0 +1,323 @@
+//! A wallet's staking history for one year, for tax reporting: every stake,
+//! unstake, reward claim and protocol fee, with amounts normalized by their
+//! mint's decimals.
+//!
+//! The history comes from the indexer database when there is one, which also
+//! holds the price archive claims are valued with. Otherwise it is decoded
+//! from the wallet's transactions, without prices.
+//!
+//! The same sources feed `history timeline`, every event of the wallet's with
+//! its running balances (see `ontora_client::history`).
+
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::str::FromStr;
+
+use chrono::{DateTime, NaiveDate};
+use ontora_client::{Activity, OntoraEvent, WalletEvent};
+use serde_json::{json, Value};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use sqlx::any::AnyRow;
+use sqlx::Row;
+
+use crate::error::CliError;
+
+/// Columns of the CSV export, in order.
+pub const CSV_COLUMNS: [&str; 9] =
+    ["date", "timestamp", "type", "agent_id", "amount", "raw_amount", "mint", "signature", "usd_value"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum HistoryKind {
+    Stake,
+    Unstake,
+    Claim,
+    /// A protocol fee the transaction paid into the treasury.
+    Fee,
+}
+
+impl HistoryKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            HistoryKind::Stake => "stake",
+            HistoryKind::Unstake => "unstake",
+            HistoryKind::Claim => "claim",
+            HistoryKind::Fee => "fee",
+        }
+    }
+}
+
+/// One movement of the wallet's tokens.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HistoryEntry {
+    pub timestamp: i64,
+    pub signature: Signature,
+    /// Position of the event among those the transaction emitted.
+    pub index: usize,
+    pub kind: HistoryKind,
+    /// None for fees.
+    pub agent_id: Option<u64>,
+    pub mint: Pubkey,
+    /// In base units of `mint`.
+    pub amount: u64,
+    /// The archived feed price when a claim was made, if there is one.
+    pub price: Option<i64>,
+}
+
+/// Unix seconds from the start of `year` (UTC) to the start of the next.
+pub fn year_range(year: i32) -> Option<Range<i64>> {
+    let start = NaiveDate::from_ymd_opt(year, 1, 1)?.and_hms_opt(0, 0, 0)?.and_utc().timestamp();
+    let end = NaiveDate::from_ymd_opt(year.checked_add(1)?, 1, 1)?.and_hms_opt(0, 0, 0)?.and_utc().timestamp();
+    Some(start..end)
+}
+
+/// The entries for `wallet` among the events of one transaction. `mint` is
+/// the platform's stake and reward mint, which the events do not name. The
+/// treasury deposits of a transaction that staked, unstaked or claimed for
+/// `wallet` are its fees.
+pub fn entries(wallet: &Pubkey, mint: &Pubkey, signature: &Signature, events: &[OntoraEvent]) -> Vec<HistoryEntry> {
+    let mut entries = Vec::new();
+    for (index, event) in events.iter().enumerate() {
+        let (timestamp, kind, agent_id, amount) = match event {
+            OntoraEvent::StakeDeposited(event) if event.user == *wallet => {
+                (event.timestamp, HistoryKind::Stake, event.agent_id, event.amount)
+            }
+            OntoraEvent::StakeWithdrawn(event) if event.user == *wallet => {
+                (event.timestamp, HistoryKind::Unstake, event.agent_id, event.amount)
+            }
+            OntoraEvent::RewardClaimed(event) if event.user == *wallet => {
+                (event.timestamp, HistoryKind::Claim, event.agent_id, event.reward_amount)
+            }
+            _ => continue,
+        };
+        let (signature, mint, agent_id) = (*signature, *mint, Some(agent_id));
+        entries.push(HistoryEntry { timestamp, signature, index, kind, agent_id, mint, amount, price: None });
+    }
+    if entries.is_empty() {
+        return entries;
+    }
+    for (index, event) in events.iter().enumerate() {
+        if let OntoraEvent::TreasuryDeposit(event) = event {
+            entries.push(HistoryEntry {
+                timestamp: event.timestamp,
+                signature: *signature,
+                index,
+                kind: HistoryKind::Fee,
+                agent_id: None,
+                mint: event.mint,
+                amount: event.amount,
+                price: None,
+            });
+        }
+    }
+    entries
+}
+
+fn entry(row: &AnyRow, kind: HistoryKind, mint: Option<&Pubkey>) -> Result<HistoryEntry, CliError> {
+    let signature: String = row.try_get("signature")?;
+    let mint = match mint {
+        Some(mint) => *mint,
+        None => {
+            let mint: String = row.try_get("mint")?;
+            Pubkey::from_str(&mint).map_err(|error| sqlx::Error::Decode(Box::new(error)))?
+        }
+    };
+    let agent_id: Option<i64> = row.try_get("agent_id")?;
+    Ok(HistoryEntry {
+        timestamp: row.try_get("timestamp")?,
+        signature: Signature::from_str(&signature).map_err(|error| sqlx::Error::Decode(Box::new(error)))?,
+        index: row.try_get::<i64, _>("event_index")? as usize,
+        kind,
+        agent_id: agent_id.map(|agent_id| agent_id as u64),
+        mint,
+        amount: row.try_get::<i64, _>("amount")? as u64,
+        price: row.try_get("price")?,
+    })
+}
+
+/// `wallet`'s entries in `range` from the indexer database at `url`. Claims
+/// are priced with the latest `price_symbol` update archived at or before
+/// them.
+pub fn from_database(
+    url: &str,
+    wallet: &Pubkey,
+    mint: &Pubkey,
+    price_symbol: Option<&str>,
+    range: Range<i64>,
+) -> Result<Vec<HistoryEntry>, CliError> {
+    sqlx::any::install_default_drivers();
+    let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build()?;
+    runtime.block_on(async {
+        let pool = sqlx::AnyPool::connect(url).await?;
+        let wallet = wallet.to_string();
+        let mut entries = Vec::new();
+
+        let stakes = sqlx::query(
+            "SELECT signature, event_index, kind, agent_id, amount, timestamp, NULL AS price FROM stakes \
+             WHERE user_pubkey = $1 AND timestamp >= $2 AND timestamp < $3",
+        )
+        .bind(wallet.clone())
+        .bind(range.start)
+        .bind(range.end)
+        .fetch_all(&pool)
+        .await?;
+        for row in &stakes {
+            let kind = match row.try_get::<String, _>("kind")?.as_str() {
+                "deposit" => HistoryKind::Stake,
+                _ => HistoryKind::Unstake,
+            };
+            entries.push(entry(row, kind, Some(mint))?);
+        }
+
+        let claims = sqlx::query(
+            "SELECT c.signature, c.event_index, c.agent_id, c.amount, c.timestamp, \
+                 (SELECT p.price FROM price_updates p WHERE p.symbol = $4 AND p.timestamp <= c.timestamp \
+                  ORDER BY p.timestamp DESC, p.slot DESC, p.event_index DESC LIMIT 1) AS price \
+             FROM claims c WHERE c.user_pubkey = $1 AND c.timestamp >= $2 AND c.timestamp < $3",
+        )
+        .bind(wallet.clone())
+        .bind(range.start)
+        .bind(range.end)
+        .bind(price_symbol.unwrap_or_default().to_string())
+        .fetch_all(&pool)
+        .await?;
+        for row in &claims {
+            entries.push(entry(row, HistoryKind::Claim, Some(mint))?);
+        }
+
+        let fees = sqlx::query(
+            "SELECT signature, event_index, NULL AS agent_id, mint, amount, timestamp, NULL AS price \
+             FROM treasury_deposits WHERE timestamp >= $2 AND timestamp < $3 AND signature IN \
+                 (SELECT signature FROM stakes WHERE user_pubkey = $1 \
+                  UNION SELECT signature FROM claims WHERE user_pubkey = $1)",
+        )
+        .bind(wallet)
+        .bind(range.start)
+        .bind(range.end)
+        .fetch_all(&pool)
+        .await?;
+        for row in &fees {
+            entries.push(entry(row, HistoryKind::Fee, None)?);
+        }
+        Ok::<_, CliError>(entries)
+    })
+}
+
+fn wallet_event(row: &AnyRow, activity: Activity) -> Result<WalletEvent, CliError> {
+    let signature: String = row.try_get("signature")?;
+    Ok(WalletEvent {
+        signature: Signature::from_str(&signature).map_err(|error| sqlx::Error::Decode(Box::new(error)))?,
+        index: row.try_get::<i64, _>("event_index")? as usize,
+        timestamp: row.try_get("timestamp")?,
+        activity,
+    })
+}
+
+/// Every indexed event of `wallet`'s in the database at `url`, oldest first.
+pub fn wallet_events_from_database(url: &str, wallet: &Pubkey) -> Result<Vec<WalletEvent>, CliError> {
+    sqlx::any::install_default_drivers();
+    let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build()?;
+    runtime.block_on(async {
+        let pool = sqlx::AnyPool::connect(url).await?;
+        let rows = sqlx::query(
+            "SELECT slot, signature, event_index, kind, agent_id AS id, amount, NULL AS vote_option, timestamp \
+             FROM stakes WHERE user_pubkey = $1 \
+             UNION ALL SELECT slot, signature, event_index, 'claim' AS kind, agent_id AS id, amount, \
+                 NULL AS vote_option, timestamp FROM claims WHERE user_pubkey = $1 \
+             UNION ALL SELECT slot, signature, event_index, 'vote' AS kind, proposal_id AS id, vote_weight AS amount, \
+                 vote_option, timestamp FROM votes WHERE voter = $1 \
+             ORDER BY slot, event_index",
+        )
+        .bind(wallet.to_string())
+        .fetch_all(&pool)
+        .await?;
+        let mut events = Vec::new();
+        for row in &rows {
+            let id = row.try_get::<i64, _>("id")? as u64;
+            let amount = row.try_get::<i64, _>("amount")? as u64;
+            let activity = match row.try_get::<String, _>("kind")?.as_str() {
+                "deposit" => Activity::Stake { agent_id: id, amount },
+                "withdrawal" => Activity::Unstake { agent_id: id, amount },
+                "claim" => Activity::Claim { agent_id: id, amount },
+                _ => {
+                    let option = row.try_get::<Option<i64>, _>("vote_option")?.unwrap_or_default() as u8;
+                    Activity::Vote { proposal_id: id, option, weight: amount }
+                }
+            };
+            events.push(wallet_event(row, activity)?);
+        }
+        Ok::<_, CliError>(events)
+    })
+}
+
+/// `raw` base units of a mint with `decimals`, as a decimal number with every
+/// decimal place.
+pub fn format_amount(raw: u128, decimals: u8) -> String {
+    let scale = 10u128.pow(decimals as u32);
+    match decimals {
+        0 => raw.to_string(),
+        _ => format!("{}.{:0width$}", raw / scale, raw % scale, width = decimals as usize),
+    }
+}
+
+/// The USD value, to the cent, of `amount` base units of a mint with
+/// `decimals` at `price` scaled by `price_decimals`. None for negative
+/// prices and values too large to compute.
+pub fn usd_value(amount: u64, decimals: u8, price: i64, price_decimals: u8) -> Option<String> {
+    let price = u128::try_from(price).ok()?;
+    let scale = 10u128.checked_pow(decimals as u32 + price_decimals as u32)?;
+    let value = (amount as u128).checked_mul(price)?.checked_mul(100)?;
+    // Rounded half up
+    let cents = value.checked_add(scale / 2)? / scale;
+    Some(format_amount(cents, 2))
+}
+
+/// Sorts `entries` into the order they are exported in: by time, then by
+/// transaction and event, so the same history always exports the same way.
+pub fn sort(entries: &mut [HistoryEntry]) {
+    entries.sort_by_key(|entry| (entry.timestamp, entry.signature.to_string(), entry.index));
+}
+
+/// The exported fields of `entry`, keyed by the CSV columns. `decimals` holds
+/// every mint's decimals.
+pub fn fields(entry: &HistoryEntry, decimals: &BTreeMap<Pubkey, u8>, price_decimals: u8) -> Value {
+    let mint_decimals = decimals.get(&entry.mint).copied().unwrap_or(0);
+    let date = DateTime::from_timestamp(entry.timestamp, 0).map(|date| date.format("%Y-%m-%dT%H:%M:%SZ").to_string());
+    let usd_value = entry.price.and_then(|price| usd_value(entry.amount, mint_decimals, price, price_decimals));
+    json!({
+        "date": date.unwrap_or_default(),
+        "timestamp": entry.timestamp,
+        "type": entry.kind.as_str(),
+        "agent_id": entry.agent_id,
+        "amount": format_amount(entry.amount as u128, mint_decimals),
+        "raw_amount": entry.amount.to_string(),
+        "mint": entry.mint.to_string(),
+        "signature": entry.signature.to_string(),
+        "usd_value": usd_value,
+    })
+}
+
+// A CSV field, quoted when it holds a separator, quote or line break
+fn csv_field(value: &Value) -> String {
+    let text = match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    };
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text
+    }
+}
+
+/// `rows` of `fields` as CSV with a header line, each line ending in `\n`.
+pub fn to_csv(rows: &[Value]) -> String {
+    let mut csv = CSV_COLUMNS.join(",") + "\n";
+    for row in rows {
+        let fields: Vec<String> = CSV_COLUMNS.iter().map(|column| csv_field(&row[column])).collect();
+        csv.push_str(&fields.join(","));
+        csv.push('\n');
+    }
+    csv
+}
@@ -0,0 +1,53 @@
+//! The `ontora` operator CLI: staking, rewards, agents, governance and the
+//! reward pool, with table or `--json` output. Signing commands print a
+//! simulated summary of the transaction, and send it only with `--yes`.
+//!
+//!     ontora --keypair ops.json --yes stake --agent-owner <OWNER> --agent-id 1 --amount 1000000000 --mint <MINT>
+//!     ontora --json config show
+//!     ontora history export --wallet <WALLET> --year 2024 --mint <MINT> --format csv
+//!     ontora history timeline --wallet <WALLET>
+//!     ontora --yes bootstrap --cluster devnet
+//!     ontora --keypair auditor.json verify-proposal 4
+//!     ontora --keypair admin.json --yes migrate-agents --checkpoint migrate.txt
+//!
+//! `bootstrap` sets up a platform, mint, demo agent and stake on a test
+//! cluster and writes their addresses to `ontora.toml`, which later commands
+//! read for the `--url`, `--keypair` and `--mint` they are not given.
+//!
+//! The admin commands (`config update`, `pause`, `treasury transfer`) take
+//! `--multisig <ADDRESS>` for an authority held by a Squads vault: they print
+//! the instruction as a blob co-signers can check with `decode-instruction`,
+//! and with `--yes` open a Squads proposal for it. `decode-tx` prints what each
+//! instruction of a serialized transaction does before anyone signs it.
+//!
+//! `verify-proposal` recounts a proposal's vote from the `VoteCast` events of
+//! its transactions and reports, signed, where the result on chain differs.
+//!
+//! `migrate-agents` moves every staked legacy agent onto the unified layout,
+//! several per transaction, and records each confirmed transaction in a
+//! checkpoint file so an interrupted run picks up where it stopped.
+//!
+//! `history timeline` lists a wallet's events with its running stake and
+//! claimed rewards, and flags where events are missing: an unstake of more
+//! than was staked, or a final stake other than the chain holds.
+
+pub mod args;
+pub mod bootstrap;
+pub mod cluster;
+pub mod commands;
+pub mod decode;
+pub mod error;
+pub mod history;
+pub mod migrate;
+pub mod output;
+pub mod profile;
+pub mod squads;
+pub mod tally;
+
+pub use args::Cli;
+pub use cluster::{Cluster, EventTransaction, RpcCluster};
+pub use commands::{run, Session};
+pub use decode::{arg_json, decode_admin_instruction, decode_blob, encode_blob, Blob};
+pub use error::CliError;
+pub use output::{explorer_url, Output, Table};
+pub use profile::{Addresses, Profile, DEFAULT_PROFILE};
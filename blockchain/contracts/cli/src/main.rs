@@ -0,0 +1,49 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::Parser;
+use ontora_client::{load_signer, SignerSource};
+
+use ontora_cli::{run, Cli, CliError, Profile, RpcCluster, Session};
+
+// Where the Solana CLI keeps its default keypair
+fn default_keypair_path() -> PathBuf {
+    let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+    PathBuf::from(home).join(".config/solana/id.json")
+}
+
+fn execute(cli: &Cli) -> Result<String, CliError> {
+    let profile = Profile::load(&cli.profile)?.unwrap_or_default();
+    let keypair = cli.keypair.clone().or(profile.keypair).unwrap_or_else(default_keypair_path);
+    let signer = if cli.command.signs() {
+        let source = cli.signer.clone().unwrap_or_else(|| SignerSource::Keypair(keypair.clone()));
+        Some(load_signer(&source)?)
+    } else {
+        None
+    };
+    let url = cli.rpc_url(profile.url.as_deref());
+    let mut cluster = RpcCluster::new(&url, cli.compute_limit, cli.priority_fee);
+    let mut session = Session {
+        cluster: &mut cluster,
+        signer: signer.as_deref(),
+        keypair: cli.signer.is_none().then_some(keypair.as_path()),
+        rpc_url: &url,
+        profile: &cli.profile,
+        yes: cli.yes,
+    };
+    Ok(run(&cli.command, &mut session)?.render(cli.json))
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match execute(&cli) {
+        Ok(output) => {
+            println!("{}", output);
+            ExitCode::SUCCESS
+        }
+        Err(error) => {
+            eprintln!("error: {}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,124 @@
+//! Planning and progress for `ontora migrate-agents`, which moves legacy
+//! `AIAgent`s and their stake onto the unified agent layout with
+//! `migrate_agent_v1`.
+//!
+//! Each transaction carries as many migrations as fit the packet and the
+//! compute units a transaction may request. Every confirmed transaction is
+//! recorded in a checkpoint file before the next is sent, so a run that is
+//! interrupted resumes where it stopped without sending a migration twice.
+
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::str::FromStr;
+
+use ontora_ai::AIAgent;
+use ontora_client::{instruction_budget, MAX_COMPUTE_UNIT_LIMIT};
+use solana_sdk::compute_budget::ComputeBudgetInstruction;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::message::Message;
+use solana_sdk::packet::PACKET_DATA_SIZE;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::transaction::Transaction;
+
+use crate::error::CliError;
+
+/// Where `migrate-agents` records its progress unless told otherwise.
+pub const DEFAULT_CHECKPOINT: &str = "ontora-migrate-agents.txt";
+
+/// Legacy agents a run has migrated. The file holds one agent address per line.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Checkpoint {
+    pub migrated: BTreeSet<Pubkey>,
+}
+
+impl Checkpoint {
+    /// Reads the progress stored at `path`; nothing stored is a fresh start.
+    pub fn load(path: &Path) -> Result<Self, CliError> {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(Checkpoint::default()),
+            Err(error) => return Err(error.into()),
+        };
+        let invalid = |line: &str| CliError::Checkpoint(format!("{}: agent {:?}", path.display(), line));
+        let migrated = contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(|line| Pubkey::from_str(line).map_err(|_| invalid(line)))
+            .collect::<Result<_, _>>()?;
+        Ok(Checkpoint { migrated })
+    }
+
+    pub fn record(&mut self, agents: &[Pubkey]) {
+        self.migrated.extend(agents.iter().copied());
+    }
+
+    /// Writes the progress to `path`, through a temporary file renamed over
+    /// it, so a crash mid-write can't lose the agents already recorded.
+    pub fn store(&self, path: &Path) -> Result<(), CliError> {
+        let contents: String = self.migrated.iter().map(|agent| format!("{}\n", agent)).collect();
+        let temporary = path.with_extension("tmp");
+        std::fs::write(&temporary, contents)?;
+        std::fs::rename(&temporary, path)?;
+        Ok(())
+    }
+}
+
+/// Legacy agents with stake left to migrate, other than those in `checkpoint`,
+/// in ascending address order. An agent without stake was unstaked or already
+/// migrated, and has nothing to move.
+pub fn pending_agents(agents: &[(Pubkey, AIAgent)], checkpoint: &Checkpoint) -> Vec<(Pubkey, AIAgent)> {
+    let mut pending: Vec<(Pubkey, AIAgent)> = agents
+        .iter()
+        .filter(|(address, agent)| agent.is_active && agent.staked_amount > 0 && !checkpoint.migrated.contains(address))
+        .cloned()
+        .collect();
+    pending.sort_by_key(|(address, _)| *address);
+    pending
+}
+
+/// Migrations sent together in one transaction.
+#[derive(Clone, Debug, Default)]
+pub struct Batch {
+    /// The legacy agents migrated, in the order of `instructions`.
+    pub agents: Vec<Pubkey>,
+    pub instructions: Vec<Instruction>,
+}
+
+// Whether `instructions`, with a compute-unit limit and price prepended,
+// fit in one transaction paid by `payer`
+fn fits(instructions: &[Instruction], payer: &Pubkey) -> bool {
+    let units: u64 = instructions.iter().map(|instruction| instruction_budget(instruction) as u64).sum();
+    if units > MAX_COMPUTE_UNIT_LIMIT as u64 {
+        return false;
+    }
+    let mut all = vec![
+        ComputeBudgetInstruction::set_compute_unit_limit(MAX_COMPUTE_UNIT_LIMIT),
+        ComputeBudgetInstruction::set_compute_unit_price(u64::MAX),
+    ];
+    all.extend(instructions.iter().cloned());
+    let transaction = Transaction::new_unsigned(Message::new(&all, Some(payer)));
+    bincode::serialized_size(&transaction).is_ok_and(|size| size as usize <= PACKET_DATA_SIZE)
+}
+
+/// Splits `migrations`, each a legacy agent and the instruction migrating it,
+/// into transactions paid by `payer`, each as large as the packet size and
+/// the compute units a transaction may request allow.
+pub fn plan_batches(migrations: Vec<(Pubkey, Instruction)>, payer: &Pubkey) -> Vec<Batch> {
+    let mut batches = Vec::new();
+    let mut current = Batch::default();
+    for (agent, instruction) in migrations {
+        let mut grown = current.instructions.clone();
+        grown.push(instruction.clone());
+        // A batch always takes at least one migration
+        if !current.agents.is_empty() && !fits(&grown, payer) {
+            batches.push(std::mem::take(&mut current));
+        }
+        current.agents.push(agent);
+        current.instructions.push(instruction);
+    }
+    if !current.agents.is_empty() {
+        batches.push(current);
+    }
+    batches
+}
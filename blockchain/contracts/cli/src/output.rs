@@ -0,0 +1,95 @@
+use serde_json::{Map, Value};
+use solana_sdk::signature::Signature;
+
+/// What a command prints: the same result as JSON and as a table.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Output {
+    pub json: Value,
+    pub table: Table,
+}
+
+impl Output {
+    /// A single record, shown as a FIELD/VALUE table.
+    pub fn record(fields: Vec<(&str, Value)>) -> Self {
+        let mut table = Table::new(&["FIELD", "VALUE"]);
+        let mut json = Map::new();
+        for (name, value) in fields {
+            table.row(vec![name.to_string(), cell(&value)]);
+            json.insert(name.to_string(), value);
+        }
+        Output { json: Value::Object(json), table }
+    }
+
+    pub fn render(&self, json: bool) -> String {
+        if json {
+            serde_json::to_string_pretty(&self.json).expect("JSON values always serialize")
+        } else {
+            self.table.render()
+        }
+    }
+}
+
+/// Rows of cells under a header, rendered with left-aligned columns.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Table {
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub fn new(headers: &[&str]) -> Self {
+        Table { headers: headers.iter().map(|header| header.to_string()).collect(), rows: Vec::new() }
+    }
+
+    pub fn row(&mut self, cells: Vec<String>) {
+        self.rows.push(cells);
+    }
+
+    pub fn render(&self) -> String {
+        let mut widths: Vec<usize> = self.headers.iter().map(|header| header.chars().count()).collect();
+        for row in &self.rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+        let line = |cells: &[String]| {
+            let padded: Vec<String> =
+                cells.iter().zip(&widths).map(|(cell, width)| format!("{:<width$}", cell, width = width)).collect();
+            padded.join("  ").trim_end().to_string()
+        };
+        let mut lines = vec![line(&self.headers)];
+        lines.extend(self.rows.iter().map(|row| line(row)));
+        lines.join("\n")
+    }
+}
+
+/// A JSON value as a table cell: strings without their quotes.
+pub fn cell(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Solana Explorer link to a transaction on the cluster behind `rpc_url`.
+pub fn explorer_url(signature: &Signature, rpc_url: &str) -> String {
+    let cluster = if rpc_url.contains("mainnet-beta") {
+        String::new()
+    } else if rpc_url.contains("devnet") {
+        "?cluster=devnet".to_string()
+    } else if rpc_url.contains("testnet") {
+        "?cluster=testnet".to_string()
+    } else {
+        format!("?cluster=custom&customUrl={}", percent_encode(rpc_url))
+    };
+    format!("https://explorer.solana.com/tx/{}{}", signature, cluster)
+}
+
+fn percent_encode(text: &str) -> String {
+    text.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (byte as char).to_string(),
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
@@ -0,0 +1,91 @@
+//! `ontora.toml` profiles: the cluster, keypair and addresses `ontora bootstrap`
+//! set up, read by later commands for the `--url`, `--keypair` and `--mint`
+//! they are not given.
+//!
+//!     cluster = "devnet"
+//!     url = "https://api.devnet.solana.com"
+//!     keypair = "/home/dev/.config/solana/id.json"
+//!
+//!     [addresses]
+//!     mint = "…"
+//!     agent = "…"
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::error::CliError;
+
+/// Where profiles are read from and written to without `--profile`.
+pub const DEFAULT_PROFILE: &str = "ontora.toml";
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Profile {
+    pub cluster: Option<String>,
+    pub url: Option<String>,
+    pub keypair: Option<PathBuf>,
+    #[serde(default)]
+    pub addresses: Addresses,
+}
+
+/// What bootstrap created or found, as base58 addresses.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Addresses {
+    #[serde(default, with = "optional_pubkey", skip_serializing_if = "Option::is_none")]
+    pub mint: Option<Pubkey>,
+    /// The keypair's token account for the mint.
+    #[serde(default, with = "optional_pubkey", skip_serializing_if = "Option::is_none")]
+    pub token_account: Option<Pubkey>,
+    #[serde(default, with = "optional_pubkey", skip_serializing_if = "Option::is_none")]
+    pub platform_config: Option<Pubkey>,
+    #[serde(default, with = "optional_pubkey", skip_serializing_if = "Option::is_none")]
+    pub platform_vault: Option<Pubkey>,
+    #[serde(default, with = "optional_pubkey", skip_serializing_if = "Option::is_none")]
+    pub reward_pool: Option<Pubkey>,
+    #[serde(default, with = "optional_pubkey", skip_serializing_if = "Option::is_none")]
+    pub reward_vault: Option<Pubkey>,
+    #[serde(default, with = "optional_pubkey", skip_serializing_if = "Option::is_none")]
+    pub agent: Option<Pubkey>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent_id: Option<u64>,
+    #[serde(default, with = "optional_pubkey", skip_serializing_if = "Option::is_none")]
+    pub user_stake: Option<Pubkey>,
+}
+
+impl Profile {
+    /// The profile at `path`, or `None` if there is no file there.
+    pub fn load(path: &Path) -> Result<Option<Profile>, CliError> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(error.into()),
+        };
+        toml::from_str(&text).map(Some).map_err(|error| CliError::Profile(format!("{}: {}", path.display(), error)))
+    }
+
+    pub fn store(&self, path: &Path) -> Result<(), CliError> {
+        let text = toml::to_string(self).expect("profiles always serialize");
+        Ok(std::fs::write(path, text)?)
+    }
+}
+
+// Pubkeys as base58 strings rather than byte arrays
+mod optional_pubkey {
+    use std::str::FromStr;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+    use solana_sdk::pubkey::Pubkey;
+
+    pub fn serialize<S: Serializer>(key: &Option<Pubkey>, serializer: S) -> Result<S::Ok, S::Error> {
+        match key {
+            Some(key) => serializer.serialize_str(&key.to_string()),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Pubkey>, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        Pubkey::from_str(&text).map(Some).map_err(serde::de::Error::custom)
+    }
+}
@@ -0,0 +1,145 @@
+//! Just enough of the Squads v4 multisig program to propose an instruction for
+//! a multisig vault to sign: the vault and proposal addresses, and the
+//! `vault_transaction_create` and `proposal_create` instructions, built by hand
+//! so the CLI does not depend on the Squads crates. Co-signers approve and
+//! execute the proposal in the Squads app.
+
+use anchor_lang::solana_program::hash::hash;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::message::Message;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::system_program;
+
+use crate::error::CliError;
+
+/// The Squads v4 program.
+pub const ID: Pubkey = solana_sdk::pubkey!("SQDS4ep65T869zMMBKyuUq6aD6EgTu8psMjkvj52pCf");
+
+const SEED_PREFIX: &[u8] = b"multisig";
+
+// Where `transaction_index` sits in a Multisig account: after the discriminator,
+// create_key, config_authority, threshold (u16) and time_lock (u32)
+const TRANSACTION_INDEX_OFFSET: usize = 8 + 32 + 32 + 2 + 4;
+
+/// The vault of `multisig` that signs as the admin.
+pub fn vault(multisig: &Pubkey, vault_index: u8) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[SEED_PREFIX, multisig.as_ref(), b"vault", &[vault_index]], &ID)
+}
+
+/// The vault transaction stored under `transaction_index`.
+pub fn transaction(multisig: &Pubkey, transaction_index: u64) -> (Pubkey, u8) {
+    let index = transaction_index.to_le_bytes();
+    Pubkey::find_program_address(&[SEED_PREFIX, multisig.as_ref(), b"transaction", &index], &ID)
+}
+
+/// The proposal co-signers approve the vault transaction through.
+pub fn proposal(multisig: &Pubkey, transaction_index: u64) -> (Pubkey, u8) {
+    let index = transaction_index.to_le_bytes();
+    Pubkey::find_program_address(&[SEED_PREFIX, multisig.as_ref(), b"transaction", &index, b"proposal"], &ID)
+}
+
+/// The index the next transaction of a multisig is created under, from the
+/// multisig account's data.
+pub fn next_transaction_index(multisig: &Pubkey, data: &[u8]) -> Result<u64, CliError> {
+    let bytes = data
+        .get(TRANSACTION_INDEX_OFFSET..TRANSACTION_INDEX_OFFSET + 8)
+        .ok_or_else(|| CliError::Cluster(format!("{} is not a Squads multisig", multisig)))?;
+    let index = u64::from_le_bytes(bytes.try_into().expect("the slice is eight bytes"));
+    Ok(index + 1)
+}
+
+fn discriminator(name: &str) -> [u8; 8] {
+    let mut discriminator = [0; 8];
+    discriminator.copy_from_slice(&hash(format!("global:{}", name).as_bytes()).to_bytes()[..8]);
+    discriminator
+}
+
+/// `instructions` as the Squads `TransactionMessage` the vault executes: a
+/// legacy message paid by the vault, with u8-prefixed vectors, except for
+/// instruction data, which is u16-prefixed.
+pub fn transaction_message(instructions: &[Instruction], vault: &Pubkey) -> Result<Vec<u8>, CliError> {
+    let message = Message::new(instructions, Some(vault));
+    let too_large = || CliError::Instruction("the instruction is too large for a Squads transaction".to_string());
+    let header = message.header;
+    let signers = header.num_required_signatures;
+    let writable_signers = signers - header.num_readonly_signed_accounts;
+    let writable_non_signers = message.account_keys.len() as u8 - signers - header.num_readonly_unsigned_accounts;
+    let mut bytes = vec![signers, writable_signers, writable_non_signers];
+    bytes.push(u8::try_from(message.account_keys.len()).map_err(|_| too_large())?);
+    for key in &message.account_keys {
+        bytes.extend_from_slice(key.as_ref());
+    }
+    bytes.push(u8::try_from(message.instructions.len()).map_err(|_| too_large())?);
+    for instruction in &message.instructions {
+        bytes.push(instruction.program_id_index);
+        bytes.push(u8::try_from(instruction.accounts.len()).map_err(|_| too_large())?);
+        bytes.extend_from_slice(&instruction.accounts);
+        bytes.extend_from_slice(&u16::try_from(instruction.data.len()).map_err(|_| too_large())?.to_le_bytes());
+        bytes.extend_from_slice(&instruction.data);
+    }
+    // No address lookup tables
+    bytes.push(0);
+    Ok(bytes)
+}
+
+/// Parameters of [`propose`].
+#[derive(Clone, Debug)]
+pub struct ProposeParams {
+    pub multisig: Pubkey,
+    pub vault_index: u8,
+    /// The multisig's next transaction index, from [`next_transaction_index`].
+    pub transaction_index: u64,
+    /// A member allowed to initiate transactions, who also pays the rent.
+    pub creator: Pubkey,
+    pub memo: Option<String>,
+}
+
+/// The instructions that store `instructions` as a vault transaction of the
+/// multisig and open its proposal for co-signers to vote on.
+pub fn propose(params: ProposeParams, instructions: &[Instruction]) -> Result<[Instruction; 2], CliError> {
+    let vault = vault(&params.multisig, params.vault_index).0;
+    let message = transaction_message(instructions, &vault)?;
+
+    let mut data = discriminator("vault_transaction_create").to_vec();
+    data.push(params.vault_index);
+    // No ephemeral signers
+    data.push(0);
+    data.extend_from_slice(&(message.len() as u32).to_le_bytes());
+    data.extend_from_slice(&message);
+    match &params.memo {
+        Some(memo) => {
+            data.push(1);
+            data.extend_from_slice(&(memo.len() as u32).to_le_bytes());
+            data.extend_from_slice(memo.as_bytes());
+        }
+        None => data.push(0),
+    }
+    let create_transaction = Instruction {
+        program_id: ID,
+        accounts: vec![
+            AccountMeta::new(params.multisig, false),
+            AccountMeta::new(transaction(&params.multisig, params.transaction_index).0, false),
+            AccountMeta::new_readonly(params.creator, true),
+            AccountMeta::new(params.creator, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data,
+    };
+
+    let mut data = discriminator("proposal_create").to_vec();
+    data.extend_from_slice(&params.transaction_index.to_le_bytes());
+    // Open for voting straight away rather than as a draft
+    data.push(0);
+    let create_proposal = Instruction {
+        program_id: ID,
+        accounts: vec![
+            AccountMeta::new_readonly(params.multisig, false),
+            AccountMeta::new(proposal(&params.multisig, params.transaction_index).0, false),
+            AccountMeta::new_readonly(params.creator, true),
+            AccountMeta::new(params.creator, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ],
+        data,
+    };
+    Ok([create_transaction, create_proposal])
+}
@@ -0,0 +1,153 @@
+//! An independent recount of a proposal's vote, for `ontora verify-proposal`.
+//!
+//! The program keeps no record per voter, so the recount replays the
+//! `VoteCast` events of the proposal's transactions in the order they were
+//! cast. Under the program's rules each vote adds its weight to the option it
+//! chose, a voter voting again included, and a finalized proposal is approved
+//! when the votes reach its quorum and a single leading option reaches its
+//! approval threshold. The recount is compared with the tallies on the
+//! proposal account and with those its `ProposalFinalized` event reported.
+
+use ontora_ai::events::ProposalFinalized;
+use ontora_ai::state::{Proposal, MAX_PROPOSAL_OPTIONS, PROPOSAL_APPROVED, PROPOSAL_REJECTED};
+use ontora_client::OntoraEvent;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+
+use crate::cluster::EventTransaction;
+use crate::commands::status_name;
+
+/// One vote as the program emitted it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Ballot {
+    pub signature: Signature,
+    pub voter: Pubkey,
+    pub timestamp: i64,
+    pub option: u8,
+    pub weight: u64,
+}
+
+/// What a proposal's transactions say about its vote.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct VoteHistory {
+    /// Oldest first.
+    pub ballots: Vec<Ballot>,
+    pub finalized: Option<ProposalFinalized>,
+}
+
+/// The vote on `proposal_id` among `transactions`, given newest first as
+/// `Cluster::event_transactions` returns them.
+pub fn vote_history(proposal_id: u64, transactions: &[EventTransaction]) -> VoteHistory {
+    let mut history = VoteHistory::default();
+    for transaction in transactions.iter().rev() {
+        for event in &transaction.events {
+            match event {
+                OntoraEvent::VoteCast(vote) if vote.proposal_id == proposal_id => history.ballots.push(Ballot {
+                    signature: transaction.signature,
+                    voter: vote.voter,
+                    timestamp: vote.timestamp,
+                    option: vote.vote_option,
+                    weight: vote.vote_weight,
+                }),
+                OntoraEvent::ProposalFinalized(finalized) if finalized.proposal_id == proposal_id => {
+                    history.finalized = Some(finalized.clone());
+                }
+                _ => {}
+            }
+        }
+    }
+    // Transactions of one slot share a timestamp, so the sort keeps their order
+    history.ballots.sort_by_key(|ballot| ballot.timestamp);
+    history
+}
+
+/// The tallies of `option_count` options after replaying `ballots`. Ballots
+/// for options the proposal does not have are left out, as the program
+/// refuses them.
+pub fn recount(option_count: usize, ballots: &[Ballot]) -> Vec<u64> {
+    let mut tallies = vec![0u64; option_count];
+    for ballot in ballots {
+        if let Some(tally) = tallies.get_mut(ballot.option as usize) {
+            *tally = tally.saturating_add(ballot.weight);
+        }
+    }
+    tallies
+}
+
+/// The status `finalize_proposal` gives `proposal` once its tallies are
+/// `tallies`, weighing each vote at `vote_unit` of stake against its quorum.
+pub fn expected_status(proposal: &Proposal, tallies: &[u64], vote_unit: u64) -> u8 {
+    let mut recounted = *proposal;
+    recounted.votes = [0; MAX_PROPOSAL_OPTIONS];
+    recounted.votes[..tallies.len()].copy_from_slice(tallies);
+    recounted.outcome(vote_unit).0
+}
+
+/// The tallies in a `ProposalFinalized` vote summary, which the program
+/// writes as a list such as `[3, 0]`.
+pub fn parse_vote_summary(summary: &str) -> Option<Vec<u64>> {
+    let inner = summary.trim().strip_prefix('[')?.strip_suffix(']')?;
+    if inner.trim().is_empty() {
+        return Some(Vec::new());
+    }
+    inner.split(',').map(|votes| votes.trim().parse().ok()).collect()
+}
+
+// Differences between `recounted` and the tallies a `source` holds
+fn compare_tallies(source: &str, recounted: &[u64], tallies: &[u64], found: &mut Vec<String>) {
+    if tallies.len() != recounted.len() {
+        found.push(format!("{} has {} options, the proposal {}", source, tallies.len(), recounted.len()));
+        return;
+    }
+    for (option, (recounted, held)) in recounted.iter().zip(tallies).enumerate() {
+        if recounted != held {
+            found.push(format!("option {}: recounted {}, {} holds {}", option, recounted, source, held));
+        }
+    }
+}
+
+/// Every way `proposal` and its finalization disagree with the recount of
+/// `history`, at the proposal's `vote_unit`, described for the report. Empty
+/// when the result checks out.
+pub fn discrepancies(proposal: &Proposal, history: &VoteHistory, vote_unit: u64) -> Vec<String> {
+    let mut found = Vec::new();
+    let option_count = proposal.option_count as usize;
+    for ballot in history.ballots.iter().filter(|ballot| ballot.option as usize >= option_count) {
+        found.push(format!(
+            "vote by {} in {} chose option {}, which the proposal does not have",
+            ballot.voter, ballot.signature, ballot.option
+        ));
+    }
+    let recounted = recount(option_count, &history.ballots);
+    compare_tallies("the proposal account", &recounted, proposal.tallies(), &mut found);
+
+    let finalized = matches!(proposal.status, PROPOSAL_APPROVED | PROPOSAL_REJECTED);
+    let expected = expected_status(proposal, &recounted, vote_unit);
+    if finalized && proposal.status != expected {
+        found.push(format!(
+            "status: the recount gives {}, the proposal account holds {}",
+            status_name(expected),
+            status_name(proposal.status)
+        ));
+    }
+    match &history.finalized {
+        Some(event) => {
+            match parse_vote_summary(&event.vote_summary) {
+                Some(tallies) => compare_tallies("ProposalFinalized", &recounted, &tallies, &mut found),
+                None => found.push(format!("ProposalFinalized vote summary is unreadable: {}", event.vote_summary)),
+            }
+            if event.result != proposal.status {
+                found.push(format!(
+                    "status: ProposalFinalized reported {}, the proposal account holds {}",
+                    status_name(event.result),
+                    status_name(proposal.status)
+                ));
+            }
+        }
+        None if finalized => {
+            found.push("the proposal is finalized but no ProposalFinalized event was found".to_string());
+        }
+        None => {}
+    }
+    found
+}
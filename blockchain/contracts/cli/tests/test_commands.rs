@@ -0,0 +1,902 @@
+// test_commands.rs
+// Integration tests for the `ontora` CLI: each subcommand is parsed from its command
+// line and run against the program under program-test, through a `Cluster` backed by
+// the banks client. Outputs are checked through their JSON form, and the accounts the
+// command changed are read back.
+
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use anchor_lang::solana_program::program_pack::Pack;
+use base64::Engine;
+use clap::Parser;
+use ontora_ai::state::{
+    AiAgent, FeeType, PlatformConfig, Role, UserStake, DEFAULT_APPROVAL_THRESHOLD_BPS, DEFAULT_QUORUM_BPS,
+    DEFAULT_VOTE_UNIT, PAUSE_CLAIMS, PAUSE_DEPOSITS,
+};
+use ontora_ai::{AIAgent, RewardPool};
+use ontora_cli::*;
+use ontora_client::*;
+use serde_json::{json, Value};
+use solana_program_test::*;
+use solana_sdk::account::Account;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::message::{Message, VersionedMessage};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature, Signer};
+use solana_sdk::system_instruction;
+use solana_sdk::transaction::VersionedTransaction;
+use solana_sdk::sysvar::clock::Clock;
+use tokio::runtime::Runtime;
+
+const LAMPORTS: u64 = 10_000_000_000;
+const MINT_DECIMALS: u8 = 9;
+const TOKENS: u64 = 10_000_000_000;
+// Rewards the legacy agents share each epoch
+const EPOCH_EMISSION: u64 = 100_000_000;
+const RPC_URL: &str = "http://127.0.0.1:8899";
+
+// A `Cluster` over program-test. Banks has no getProgramAccounts, so program accounts
+// are found among the accounts the sent transactions referenced, and no transaction
+// history, so that is kept of the transactions sent through it.
+struct ProgramTestCluster {
+    runtime: Runtime,
+    ctx: ProgramTestContext,
+    referenced: BTreeSet<Pubkey>,
+    // Each sent transaction's signature, accounts and logs, oldest first
+    sent: Vec<(Signature, BTreeSet<Pubkey>, Vec<String>)>,
+    // Airdrops to refuse for the faucet's rate limit before granting any
+    rate_limited: u32,
+    airdrops: u32,
+}
+
+impl Cluster for ProgramTestCluster {
+    fn account(&mut self, address: &Pubkey) -> Result<Option<Account>, CliError> {
+        self.runtime
+            .block_on(self.ctx.banks_client.get_account(*address))
+            .map_err(|error| CliError::Cluster(error.to_string()))
+    }
+
+    fn program_accounts(&mut self, discriminator: &[u8; 8]) -> Result<Vec<(Pubkey, Account)>, CliError> {
+        let mut accounts = Vec::new();
+        for address in self.referenced.clone() {
+            if let Some(account) = self.account(&address)? {
+                if account.owner == ontora_ai::ID && account.data.starts_with(discriminator) {
+                    accounts.push((address, account));
+                }
+            }
+        }
+        Ok(accounts)
+    }
+
+    // Banks reports no account states from a simulation, so these summaries have no deltas
+    fn simulate(&mut self, instructions: &[Instruction], payer: &Pubkey) -> Result<TxSummary, CliError> {
+        let blockhash = self
+            .runtime
+            .block_on(self.ctx.get_new_latest_blockhash())
+            .map_err(|error| CliError::Cluster(error.to_string()))?;
+        let message = VersionedMessage::Legacy(Message::new_with_blockhash(instructions, Some(payer), &blockhash));
+        let signatures = vec![Signature::default(); message.header().num_required_signatures as usize];
+        let transaction = VersionedTransaction { signatures, message: message.clone() };
+        let simulated = self
+            .runtime
+            .block_on(self.ctx.banks_client.simulate_transaction(transaction))
+            .map_err(|error| CliError::Cluster(error.to_string()))?;
+        let (logs, units_consumed) = simulated
+            .simulation_details
+            .map_or((Vec::new(), None), |details| (details.logs, Some(details.units_consumed)));
+        let error = simulated.result.and_then(Result::err);
+        Ok(summarize(&message, &[], &[], Simulation { error, logs, units_consumed, accounts: Vec::new() })?)
+    }
+
+    fn send(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&dyn signer::OntoraSigner],
+    ) -> Result<Signature, CliError> {
+        let accounts: BTreeSet<Pubkey> =
+            instructions.iter().flat_map(|instruction| instruction.accounts.iter().map(|meta| meta.pubkey)).collect();
+        self.referenced.extend(&accounts);
+        let blockhash = self
+            .runtime
+            .block_on(self.ctx.get_new_latest_blockhash())
+            .map_err(|error| CliError::Cluster(error.to_string()))?;
+        let payer = signer::OntoraSigner::pubkey(signers[0]);
+        let message = Message::new_with_blockhash(instructions, Some(&payer), &blockhash);
+        let tx = sign_transaction(VersionedMessage::Legacy(message), signers)?;
+        let signature = tx.signatures[0];
+        let processed = self
+            .runtime
+            .block_on(self.ctx.banks_client.process_transaction_with_metadata(tx))
+            .map_err(|error| CliError::Cluster(error.to_string()))?;
+        processed.result.map_err(|error| CliError::Cluster(error.to_string()))?;
+        let logs = processed.metadata.map(|metadata| metadata.log_messages).unwrap_or_default();
+        self.sent.push((signature, accounts, logs));
+        Ok(signature)
+    }
+
+    fn event_transactions(&mut self, address: &Pubkey, _since: i64) -> Result<Vec<EventTransaction>, CliError> {
+        let transactions = self.sent.iter().rev().filter(|(_, accounts, _)| accounts.contains(address));
+        let transactions = transactions
+            .map(|(signature, _, logs)| EventTransaction { signature: *signature, events: decode_events(logs) });
+        Ok(transactions.filter(|transaction| !transaction.events.is_empty()).collect())
+    }
+
+    // Program-test has no faucet; its payer stands in for one
+    fn airdrop(&mut self, to: &Pubkey, lamports: u64) -> Result<Signature, CliError> {
+        self.airdrops += 1;
+        if self.rate_limited > 0 {
+            self.rate_limited -= 1;
+            return Err(CliError::RateLimited("429 Too Many Requests".to_string()));
+        }
+        let payer = self.ctx.payer.insecure_clone();
+        self.send(&[system_instruction::transfer(&payer.pubkey(), to, lamports)], &[&payer])
+    }
+}
+
+impl ProgramTestCluster {
+    // Start the program, with nothing initialized
+    fn launch() -> Self {
+        let runtime = Runtime::new().unwrap();
+        let test = ProgramTest::new("ontora_ai", ontora_ai::ID, processor!(ontora_ai::entry));
+        let ctx = runtime.block_on(test.start_with_context());
+        ProgramTestCluster { runtime, ctx, referenced: BTreeSet::new(), sent: Vec::new(), rate_limited: 0, airdrops: 0 }
+    }
+
+    // Start the program and initialize the platform, with every role held by `admin`
+    fn start() -> (Self, Keypair) {
+        let mut cluster = ProgramTestCluster::launch();
+
+        let admin = cluster.new_user();
+        let mut instructions = vec![build_initialize_platform(InitializePlatformParams {
+            admin: admin.pubkey(),
+            reward_rate_bps: 100,
+            min_stake_amount: 1_000_000,
+            epoch_duration: 86_400,
+            cooldown_seconds: 86_400,
+            epoch_emission: EPOCH_EMISSION,
+        })];
+        for role in [Role::Crank, Role::Oracle, Role::Pauser, Role::Treasury] {
+            instructions.push(build_set_role(SetRoleParams { admin: admin.pubkey(), role, holder: admin.pubkey() }));
+        }
+        instructions.push(build_initialize_treasury(InitializeTreasuryParams {
+            authority: admin.pubkey(),
+            governance: admin.pubkey(),
+        }));
+        instructions.push(build_initialize_fee_schedule(InitializeFeeScheduleParams {
+            admin: admin.pubkey(),
+            governance: admin.pubkey(),
+            rates_bps: [0; FeeType::COUNT],
+        }));
+        cluster.send(&instructions, &[&admin]).unwrap();
+        (cluster, admin)
+    }
+
+    fn new_user(&mut self) -> Keypair {
+        let user = Keypair::new();
+        let payer = self.ctx.payer.insecure_clone();
+        let transfer = system_instruction::transfer(&payer.pubkey(), &user.pubkey(), LAMPORTS);
+        self.send(&[transfer], &[&payer]).unwrap();
+        user
+    }
+
+    // Create a mint controlled by `authority`, with a funded token account for each holder
+    // and an empty one for each vault owner, and the platform's vaults for it
+    fn mint(&mut self, authority: &Keypair, holders: &[Pubkey], vault_owners: &[Pubkey]) -> Pubkey {
+        let mint = Keypair::new();
+        let rent = self.runtime.block_on(self.ctx.banks_client.get_rent()).unwrap();
+        let mut instructions = vec![
+            system_instruction::create_account(
+                &authority.pubkey(),
+                &mint.pubkey(),
+                rent.minimum_balance(spl_token::state::Mint::LEN),
+                spl_token::state::Mint::LEN as u64,
+                &spl_token::ID,
+            ),
+            spl_token::instruction::initialize_mint(
+                &spl_token::ID,
+                &mint.pubkey(),
+                &authority.pubkey(),
+                None,
+                MINT_DECIMALS,
+            )
+            .unwrap(),
+        ];
+        for owner in holders.iter().chain(vault_owners) {
+            instructions.push(spl_associated_token_account::instruction::create_associated_token_account(
+                &authority.pubkey(),
+                owner,
+                &mint.pubkey(),
+                &spl_token::ID,
+            ));
+        }
+        for holder in holders {
+            let account = spl_associated_token_account::get_associated_token_address(holder, &mint.pubkey());
+            instructions.push(
+                spl_token::instruction::mint_to(&spl_token::ID, &mint.pubkey(), &account, &authority.pubkey(), &[], TOKENS)
+                    .unwrap(),
+            );
+        }
+        instructions.push(build_initialize_vaults(InitializeVaultsParams {
+            admin: authority.pubkey(),
+            stake_mint: mint.pubkey(),
+        }));
+        self.send(&instructions, &[authority, &mint]).unwrap();
+        mint.pubkey()
+    }
+
+    fn token_balance(&mut self, owner: &Pubkey, mint: &Pubkey) -> u64 {
+        self.vault_balance(&spl_associated_token_account::get_associated_token_address(owner, mint))
+    }
+
+    fn vault_balance(&mut self, address: &Pubkey) -> u64 {
+        let account = self.account(address).unwrap().expect("token account exists");
+        spl_token::state::Account::unpack(&account.data).unwrap().amount
+    }
+
+    fn decode<T: anchor_lang::AccountDeserialize>(&mut self, address: &Pubkey) -> T {
+        let account = self.account(address).unwrap().expect("account exists");
+        decode_account(address, &account.data).unwrap()
+    }
+
+    fn warp_seconds(&mut self, seconds: i64) {
+        let mut clock: Clock = self.runtime.block_on(self.ctx.banks_client.get_sysvar()).unwrap();
+        self.ctx.warp_to_slot(clock.slot + 1).unwrap();
+        clock.slot += 1;
+        clock.unix_timestamp += seconds;
+        self.ctx.set_sysvar(&clock);
+    }
+
+    // Run `ontora --json --yes <args>` signed by `signer`
+    fn ontora(&mut self, signer: &Keypair, args: &[&str]) -> Result<Value, CliError> {
+        self.ontora_with(signer, &[&["--yes"], args].concat())
+    }
+
+    // Run `ontora --json <args>` signed by `signer`
+    fn ontora_with(&mut self, signer: &Keypair, args: &[&str]) -> Result<Value, CliError> {
+        let cli = Cli::try_parse_from(["ontora", "--json", "--url", RPC_URL].iter().chain(args)).unwrap();
+        let url = cli.rpc_url(None);
+        let mut session = Session {
+            cluster: self,
+            signer: Some(signer),
+            keypair: None,
+            rpc_url: &url,
+            profile: &cli.profile,
+            yes: cli.yes,
+        };
+        let output = run(&cli.command, &mut session)?;
+        assert_eq!(output.render(true), serde_json::to_string_pretty(&output.json).unwrap());
+        Ok(output.json)
+    }
+}
+
+// A session with no signer that only simulates
+fn read_only_session(cluster: &mut ProgramTestCluster) -> Session {
+    Session { cluster, signer: None, keypair: None, rpc_url: RPC_URL, profile: Path::new(DEFAULT_PROFILE), yes: false }
+}
+
+// Test `config show`, as JSON and as a table
+#[test]
+fn test_config_show() {
+    let (mut cluster, admin) = ProgramTestCluster::start();
+    let config = cluster.ontora(&admin, &["config", "show"]).unwrap();
+    assert_eq!(config["address"], pda::platform_config().0.to_string());
+    assert_eq!(config["admin"], admin.pubkey().to_string());
+    assert_eq!((config["reward_rate_bps"].as_u64(), config["epoch_duration"].as_i64()), (Some(100), Some(86_400)));
+    assert_eq!(config["cooldown_seconds"].as_i64(), Some(86_400));
+    assert_eq!(config["epoch_emission"].as_u64(), Some(EPOCH_EMISSION));
+    assert_eq!(config["max_stake_per_agent"].as_u64(), Some(0));
+    assert_eq!(config["vesting_seconds"].as_i64(), Some(0));
+    assert_eq!(config["vote_unit"].as_u64(), Some(DEFAULT_VOTE_UNIT));
+    let governance = (config["quorum_bps"].as_u64(), config["approval_threshold_bps"].as_u64());
+    assert_eq!(governance, (Some(DEFAULT_QUORUM_BPS.into()), Some(DEFAULT_APPROVAL_THRESHOLD_BPS.into())));
+    assert_eq!(config["total_agents"].as_u64(), Some(0));
+    // No mint has been given vaults yet
+    assert_eq!(config["staking_vault"], Pubkey::default().to_string());
+    assert_eq!(config["governance_enabled"], true);
+
+    let cli = Cli::try_parse_from(["ontora", "config", "show"]).unwrap();
+    assert!(!cli.command.signs());
+    let mut session = read_only_session(&mut cluster);
+    let table = run(&cli.command, &mut session).unwrap().render(false);
+    let lines: Vec<&str> = table.lines().collect();
+    assert_eq!(lines[0].split_whitespace().collect::<Vec<_>>(), ["FIELD", "VALUE"]);
+    assert!(lines.iter().any(|line| line.starts_with("admin") && line.ends_with(&admin.pubkey().to_string())));
+}
+
+// Test `agent register`, `agent update` and `agent list`
+#[test]
+fn test_agent_commands() {
+    let (mut cluster, admin) = ProgramTestCluster::start();
+    let (first, second) = (cluster.new_user(), cluster.new_user());
+
+    let registered = cluster
+        .ontora(&first, &["agent", "register", "--id", "1", "--name", "Atlas", "--description", "Market maker"])
+        .unwrap();
+    assert_eq!(registered["agent"], pda::ai_agent(&first.pubkey(), 1).0.to_string());
+    assert!(registered["explorer"].as_str().unwrap().contains(registered["signature"].as_str().unwrap()));
+    cluster.ontora(&second, &["agent", "register", "--id", "1", "--name", "Borealis", "--description", "Scout"]).unwrap();
+    cluster
+        .ontora(&first, &["agent", "update", "--id", "1", "--name", "Atlas II", "--description", "Arbitrage"])
+        .unwrap();
+    let agent: AiAgent = cluster.decode(&pda::ai_agent(&first.pubkey(), 1).0);
+    assert_eq!((agent.name.as_str(), agent.description.as_str()), ("Atlas II", "Arbitrage"));
+
+    let all = cluster.ontora(&admin, &["agent", "list"]).unwrap();
+    let mut names: Vec<&str> = all.as_array().unwrap().iter().map(|agent| agent["name"].as_str().unwrap()).collect();
+    names.sort();
+    assert_eq!(names, ["Atlas II", "Borealis"]);
+    let owner = second.pubkey().to_string();
+    let mine = cluster.ontora(&admin, &["agent", "list", "--owner", &owner]).unwrap();
+    assert_eq!(mine.as_array().unwrap().len(), 1);
+    assert_eq!(mine[0]["owner"], owner);
+}
+
+// Test `stake` and `unstake`
+#[test]
+fn test_stake_and_unstake() {
+    let (mut cluster, admin) = ProgramTestCluster::start();
+    let (owner, user) = (cluster.new_user(), cluster.new_user());
+    let mint = cluster.mint(&admin, &[user.pubkey()], &[]);
+    cluster.ontora(&owner, &["agent", "register", "--id", "2", "--name", "Atlas", "--description", "Maker"]).unwrap();
+
+    let (owner_arg, mint_arg) = (owner.pubkey().to_string(), mint.to_string());
+    let target = ["--agent-owner", owner_arg.as_str(), "--agent-id", "2", "--mint", mint_arg.as_str()];
+    let staked = cluster.ontora(&user, &[&["stake", "--amount", "3000000000"], &target[..]].concat()).unwrap();
+    assert_eq!(staked["staked_amount"], 3_000_000_000u64);
+    assert_eq!(cluster.token_balance(&user.pubkey(), &mint), TOKENS - 3_000_000_000);
+
+    let unstaked = cluster.ontora(&user, &[&["unstake", "--amount", "1000000000"], &target[..]].concat()).unwrap();
+    assert_eq!(unstaked["staked_amount"], 2_000_000_000u64);
+    let position: UserStake = cluster.decode(&pda::user_stake(&user.pubkey()).0);
+    assert_eq!(position.staked_amount, 2_000_000_000);
+    assert_eq!(cluster.token_balance(&user.pubkey(), &mint), TOKENS - 2_000_000_000);
+}
+
+// Test that without --yes a signing command prints the simulated summary and sends
+// nothing, and that with it the same summary comes back beside the signature
+#[test]
+fn test_summary_requires_yes() {
+    let (mut cluster, admin) = ProgramTestCluster::start();
+    let (owner, user) = (cluster.new_user(), cluster.new_user());
+    let mint = cluster.mint(&admin, &[user.pubkey()], &[]);
+    cluster.ontora(&owner, &["agent", "register", "--id", "2", "--name", "Atlas", "--description", "Maker"]).unwrap();
+
+    let (owner_arg, mint_arg) = (owner.pubkey().to_string(), mint.to_string());
+    let target = ["--agent-owner", owner_arg.as_str(), "--agent-id", "2", "--mint", mint_arg.as_str()];
+    let stake = [&["stake", "--amount", "3000000000"], &target[..]].concat();
+    let preview = cluster.ontora_with(&user, &stake).unwrap();
+    assert_eq!(preview["sent"], false);
+    assert!(preview["events"].as_array().unwrap().contains(&Value::from("StakeDeposited")));
+    assert_eq!(preview["fee_lamports"], 5_000);
+    assert!(preview["compute_units"].as_u64().unwrap() > 0);
+    assert_eq!(cluster.token_balance(&user.pubkey(), &mint), TOKENS);
+    assert!(cluster.account(&pda::user_stake(&user.pubkey()).0).unwrap().is_none());
+
+    let staked = cluster.ontora(&user, &stake).unwrap();
+    assert!(staked["signature"].is_string());
+    for field in ["events", "fee_lamports", "token_changes"] {
+        assert_eq!(staked[field], preview[field], "{}", field);
+    }
+    assert_eq!(cluster.token_balance(&user.pubkey(), &mint), TOKENS - 3_000_000_000);
+
+    // A transaction that would fail is refused before it is sent, --yes or not
+    let overdrawn = [&["unstake", "--amount", "9000000000"], &target[..]].concat();
+    assert!(matches!(cluster.ontora_with(&user, &overdrawn), Err(CliError::WouldFail(_))));
+    assert!(matches!(cluster.ontora(&user, &overdrawn), Err(CliError::WouldFail(_))));
+}
+
+// Test `pool fund`, which tops up the recorded total once the pool exists, and `claim`
+#[test]
+fn test_pool_fund_and_claim() {
+    let (mut cluster, admin) = ProgramTestCluster::start();
+    let user = cluster.new_user();
+    let agent = pda::legacy_ai_agent(&user.pubkey()).0;
+    let mint = cluster.mint(&admin, &[admin.pubkey(), user.pubkey()], &[agent]);
+    let mint_arg = mint.to_string();
+
+    let funded = cluster.ontora(&admin, &["pool", "fund", "--mint", &mint_arg, "--amount", "4000000000"]).unwrap();
+    assert_eq!(funded["initialized"], true);
+    let topped_up = cluster.ontora(&admin, &["pool", "fund", "--mint", &mint_arg, "--amount", "1000000000"]).unwrap();
+    assert_eq!(topped_up["initialized"], false);
+    assert_eq!(cluster.vault_balance(&pda::reward_vault().0), 5_000_000_000);
+    assert_eq!(cluster.decode::<RewardPool>(&pda::reward_pool().0).total_rewards, 5_000_000_000);
+
+    let stake = 2_000_000_000;
+    let register = build_register_ai_agent(RegisterAiAgentParams {
+        user: user.pubkey(),
+        user_token_account: spl_associated_token_account::get_associated_token_address(&user.pubkey(), &mint),
+        staking_vault: spl_associated_token_account::get_associated_token_address(&agent, &mint),
+        stake_amount: stake,
+    });
+    cluster.send(&[register], &[&user]).unwrap();
+    cluster.warp_seconds(604_800);
+    let distribute = build_distribute_rewards(DistributeRewardsParams { crank: admin.pubkey(), agent_owner: user.pubkey() });
+    cluster.send(&[distribute], &[&admin]).unwrap();
+    // The only staker takes the whole epoch emission
+    assert_eq!(cluster.decode::<AIAgent>(&agent).accumulated_rewards, EPOCH_EMISSION);
+
+    let claimed = cluster.ontora(&user, &["claim", "--mint", &mint_arg]).unwrap();
+    assert_eq!(claimed["claimed"], EPOCH_EMISSION);
+    assert_eq!(cluster.token_balance(&user.pubkey(), &mint), TOKENS - stake + EPOCH_EMISSION);
+    assert_eq!(cluster.decode::<AIAgent>(&agent).accumulated_rewards, 0);
+}
+
+// Test `proposal create` with a config change, `proposal vote`, weighted by the voter's stake,
+// `proposal finalize` and `proposal execute`
+#[test]
+fn test_proposal_commands() {
+    let (mut cluster, admin) = ProgramTestCluster::start();
+    let (owner, voter) = (cluster.new_user(), cluster.new_user());
+    let mint = cluster.mint(&admin, &[voter.pubkey()], &[]);
+    cluster.ontora(&owner, &["agent", "register", "--id", "2", "--name", "Atlas", "--description", "Maker"]).unwrap();
+    let (owner_arg, mint_arg) = (owner.pubkey().to_string(), mint.to_string());
+    let target = ["--agent-owner", owner_arg.as_str(), "--agent-id", "2", "--mint", mint_arg.as_str()];
+    cluster.ontora(&voter, &[&["stake", "--amount", "3000000000"], &target[..]].concat()).unwrap();
+    let updated = cluster.ontora(&admin, &["config", "update", "--vote-unit", "1000000000"]).unwrap();
+    assert_eq!(updated["vote_unit"], 1_000_000_000);
+    // Stake votes only on proposals opened after it
+    cluster.warp_seconds(1);
+
+    let created = cluster
+        .ontora(
+            &admin,
+            &[
+                "proposal",
+                "create",
+                "--title",
+                "Raise reward rate",
+                "--description",
+                "Raise the reward rate to 6%",
+                "--voting-duration",
+                "3600",
+                "--option",
+                "Yes",
+                "--option",
+                "No",
+                "--set-reward-rate-bps",
+                "600",
+            ],
+        )
+        .unwrap();
+    assert_eq!(created["proposal_id"], 0);
+    assert_eq!(created["proposal"], pda::proposal(0).0.to_string());
+
+    let voted = cluster.ontora(&voter, &["proposal", "vote", "--id", "0", "--option", "0"]).unwrap();
+    assert_eq!(voted["option"], "Yes");
+    // Three tokens staked at one token per vote
+    assert_eq!(voted["tallies"], serde_json::json!([3, 0]));
+
+    // Voting is still open
+    let early = cluster.ontora(&voter, &["proposal", "finalize", "--id", "0"]);
+    assert!(matches!(early, Err(CliError::WouldFail(_))));
+
+    cluster.warp_seconds(3_601);
+    let finalized = cluster.ontora(&voter, &["proposal", "finalize", "--id", "0"]).unwrap();
+    assert_eq!(finalized["status"], "approved");
+
+    // Anyone may apply the passed proposal, once
+    let executed = cluster.ontora(&owner, &["proposal", "execute", "--id", "0"]).unwrap();
+    assert_eq!(executed["status"], "executed");
+    assert_eq!(executed["reward_rate_bps"], 600);
+    let config: PlatformConfig = cluster.decode(&pda::platform_config().0);
+    assert_eq!(config.reward_rate_bps, 600);
+    let again = cluster.ontora(&owner, &["proposal", "execute", "--id", "0"]);
+    assert!(matches!(again, Err(CliError::WouldFail(_))));
+    // A proposal makes one change at most
+    let create = ["ontora", "proposal", "create", "--title", "Rates", "--description", "Both", "--option", "Yes"];
+    let two_changes = [&create[..], &["--set-reward-rate-bps", "600", "--set-epoch-duration", "7200"]].concat();
+    assert!(Cli::try_parse_from(two_changes).is_err());
+}
+
+// Test `verify-proposal` on a proposal with a large staker's vote and a voter who voted
+// again for another option: the recount matches the proposal account and the finalized
+// tallies, the report is signed by the keypair, and a vote left out of the replay shows
+// up as a discrepancy
+#[test]
+fn test_verify_proposal() {
+    let (mut cluster, admin) = ProgramTestCluster::start();
+    let (owner, staker, voter) = (cluster.new_user(), cluster.new_user(), cluster.new_user());
+    let mint = cluster.mint(&admin, &[staker.pubkey(), voter.pubkey()], &[]);
+    cluster.ontora(&owner, &["agent", "register", "--id", "2", "--name", "Atlas", "--description", "Maker"]).unwrap();
+    let (owner_arg, mint_arg) = (owner.pubkey().to_string(), mint.to_string());
+    let target = ["--agent-owner", owner_arg.as_str(), "--agent-id", "2", "--mint", mint_arg.as_str()];
+    cluster.ontora(&staker, &[&["stake", "--amount", "3000000000"], &target[..]].concat()).unwrap();
+    cluster.ontora(&voter, &[&["stake", "--amount", "1000000"], &target[..]].concat()).unwrap();
+    cluster.ontora(&admin, &["config", "update", "--vote-unit", "1000000"]).unwrap();
+    cluster.warp_seconds(1);
+    let create = ["proposal", "create", "--title", "Fees", "--description", "Cut fees", "--voting-duration", "3600"];
+    cluster.ontora(&admin, &[&create[..], &["--option", "Yes", "--option", "No"]].concat()).unwrap();
+
+    cluster.ontora(&staker, &["proposal", "vote", "--id", "0", "--option", "0"]).unwrap();
+    cluster.ontora(&voter, &["proposal", "vote", "--id", "0", "--option", "0"]).unwrap();
+    cluster.warp_seconds(60);
+    cluster.ontora(&voter, &["proposal", "vote", "--id", "0", "--option", "1"]).unwrap();
+    cluster.warp_seconds(3_600);
+    cluster.ontora(&admin, &["proposal", "finalize", "--id", "0"]).unwrap();
+
+    let report = cluster.ontora(&voter, &["verify-proposal", "0"]).unwrap();
+    assert_eq!(report["discrepancies"], json!([]));
+    assert_eq!(report["verified"], true);
+    assert_eq!((report["votes"].as_u64(), report["voters"].as_u64()), (Some(3), Some(2)));
+    // The staker's 3 tokens at 0.001 tokens per vote, and one vote on each side from the other voter
+    assert_eq!(report["recounted"], json!([3_001, 1]));
+    assert_eq!(report["on_chain"], report["recounted"]);
+    assert_eq!(report["finalized"], report["recounted"]);
+    assert_eq!(report["status"], "approved");
+
+    let mut signed = report.as_object().unwrap().clone();
+    let signature: Signature = signed.remove("signature").unwrap().as_str().unwrap().parse().unwrap();
+    assert_eq!(signed.remove("verifier").unwrap(), voter.pubkey().to_string());
+    let message = serde_json::to_vec(&Value::Object(signed)).unwrap();
+    assert!(signature.verify(voter.pubkey().as_ref(), &message));
+
+    // Without the changed vote's second ballot the recount no longer matches
+    let address = pda::proposal(0).0;
+    let data = cluster.account(&address).unwrap().unwrap().data;
+    let proposal: ontora_ai::state::Proposal = decode_zero_copy(&address, &data).unwrap();
+    let mut history = tally::vote_history(0, &cluster.event_transactions(&address, 0).unwrap());
+    let vote_unit = proposal.vote_unit;
+    assert_eq!(tally::discrepancies(&proposal, &history, vote_unit), Vec::<String>::new());
+    history.ballots.pop();
+    assert_eq!(
+        tally::discrepancies(&proposal, &history, vote_unit),
+        [
+            "option 1: recounted 0, the proposal account holds 1",
+            "option 1: recounted 0, ProposalFinalized holds 1",
+        ]
+    );
+    assert_eq!(tally::parse_vote_summary("[3001, 1]"), Some(vec![3_001, 1]));
+    assert_eq!(tally::parse_vote_summary("3001, 1"), None);
+}
+
+// Test `migrate-agents`: a preview sends nothing, agents in the checkpoint are not sent
+// again, and each migrated agent's stake moves onto its new agent and into the platform vault
+#[test]
+fn test_migrate_agents() {
+    let (mut cluster, admin) = ProgramTestCluster::start();
+    let (alice, bob) = (cluster.new_user(), cluster.new_user());
+    let legacy: Vec<Pubkey> = [&alice, &bob].iter().map(|user| pda::legacy_ai_agent(&user.pubkey()).0).collect();
+    let mint = cluster.mint(&admin, &[alice.pubkey(), bob.pubkey()], &legacy);
+    for (user, stake) in [(&alice, 2_000_000_000), (&bob, 3_000_000_000)] {
+        let register = build_register_ai_agent(RegisterAiAgentParams {
+            user: user.pubkey(),
+            user_token_account: spl_associated_token_account::get_associated_token_address(&user.pubkey(), &mint),
+            staking_vault: pda::legacy_staking_vault(&user.pubkey(), &mint).0,
+            stake_amount: stake,
+        });
+        cluster.send(&[register], &[user]).unwrap();
+    }
+    let dir = tempfile::tempdir().unwrap();
+    let checkpoint = dir.path().join("migrate.txt");
+    let (mint_arg, checkpoint_arg) = (mint.to_string(), checkpoint.display().to_string());
+    let args = ["migrate-agents", "--mint", mint_arg.as_str(), "--checkpoint", checkpoint_arg.as_str()];
+
+    let preview = cluster.ontora_with(&admin, &args).unwrap();
+    assert_eq!((preview["sent"].as_bool(), preview["pending"].as_u64()), (Some(false), Some(2)));
+    assert_eq!(preview["transactions"], 1);
+    assert!(!checkpoint.exists());
+
+    // A run interrupted after migrating bob's agent left it in the checkpoint
+    std::fs::write(&checkpoint, format!("{}\n", legacy[1])).unwrap();
+    let resumed = cluster.ontora(&admin, &args).unwrap();
+    assert_eq!((resumed["pending"].as_u64(), resumed["already_migrated"].as_u64()), (Some(1), Some(1)));
+    assert_eq!(resumed["migrated"], 1);
+    assert_eq!(migrate::Checkpoint::load(&checkpoint).unwrap().migrated, legacy.iter().copied().collect());
+    let agent: AiAgent = cluster.decode(&pda::ai_agent(&alice.pubkey(), ontora_ai::MIGRATED_AGENT_ID).0);
+    assert_eq!((agent.owner, agent.staked_amount), (alice.pubkey(), 2_000_000_000));
+    assert_eq!(cluster.decode::<UserStake>(&pda::user_stake(&alice.pubkey()).0).staked_amount, 2_000_000_000);
+    assert!(!cluster.decode::<AIAgent>(&legacy[0]).is_active);
+    assert!(cluster.decode::<AIAgent>(&legacy[1]).is_active);
+
+    // A fresh run migrates bob's agent; alice's has no stake left to move
+    std::fs::remove_file(&checkpoint).unwrap();
+    let rest = cluster.ontora(&admin, &args).unwrap();
+    assert_eq!((rest["pending"].as_u64(), rest["migrated"].as_u64()), (Some(1), Some(1)));
+    assert_eq!(cluster.vault_balance(&pda::staking_vault().0), 5_000_000_000);
+    assert_eq!(cluster.token_balance(&legacy[1], &mint), 0);
+    assert_eq!(cluster.decode::<PlatformConfig>(&pda::platform_config().0).total_staked, 5_000_000_000);
+    let done = cluster.ontora(&admin, &args).unwrap();
+    assert_eq!((done["pending"].as_u64(), done["transactions"].as_u64()), (Some(0), Some(0)));
+}
+
+// Test `history export` from the cluster's transactions: the user's stake and unstake,
+// in order, with amounts normalized by the mint's decimals
+#[test]
+fn test_history_export_from_cluster() {
+    let (mut cluster, admin) = ProgramTestCluster::start();
+    let (owner, user) = (cluster.new_user(), cluster.new_user());
+    let mint = cluster.mint(&admin, &[user.pubkey()], &[]);
+    cluster.ontora(&owner, &["agent", "register", "--id", "2", "--name", "Atlas", "--description", "Maker"]).unwrap();
+    let (owner_arg, mint_arg) = (owner.pubkey().to_string(), mint.to_string());
+    let target = ["--agent-owner", owner_arg.as_str(), "--agent-id", "2", "--mint", mint_arg.as_str()];
+    let staked = cluster.ontora(&user, &[&["stake", "--amount", "3000000000"], &target[..]].concat()).unwrap();
+    cluster.warp_seconds(60);
+    let unstaked = cluster.ontora(&user, &[&["unstake", "--amount", "1500000000"], &target[..]].concat()).unwrap();
+
+    let clock: Clock = cluster.runtime.block_on(cluster.ctx.banks_client.get_sysvar()).unwrap();
+    let year = (1970..=9999).find(|year| history::year_range(*year).unwrap().contains(&clock.unix_timestamp)).unwrap();
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("history.csv");
+    let (wallet, output) = (user.pubkey().to_string(), path.display().to_string());
+    let (this_year, last_year) = (year.to_string(), (year - 1).to_string());
+    let export = ["history", "export", "--wallet", wallet.as_str(), "--mint", mint_arg.as_str()];
+    let export = [&export[..], &["--output", output.as_str()]].concat();
+    let exported = cluster.ontora_with(&user, &[&export[..], &["--year", this_year.as_str()]].concat()).unwrap();
+    assert_eq!((exported["source"].as_str(), exported["entries"].as_u64()), (Some("cluster"), Some(2)));
+
+    let csv = std::fs::read_to_string(&path).unwrap();
+    let lines: Vec<Vec<&str>> = csv.lines().map(|line| line.split(',').collect()).collect();
+    assert_eq!(lines[0], history::CSV_COLUMNS);
+    assert_eq!(lines.len(), 3);
+    for (line, (kind, amount, sent)) in
+        lines[1..].iter().zip([("stake", "3.000000000", &staked), ("unstake", "1.500000000", &unstaked)])
+    {
+        assert_eq!((line[2], line[3], line[4]), (kind, "2", amount));
+        assert_eq!((line[6], line[7], line[8]), (mint_arg.as_str(), sent["signature"].as_str().unwrap(), ""));
+    }
+    assert!(lines[1][1] < lines[2][1]);
+
+    // Nothing in the year before
+    let exported = cluster.ontora_with(&user, &[&export[..], &["--year", last_year.as_str()]].concat()).unwrap();
+    assert_eq!(exported["entries"], 0);
+}
+
+// Test `history timeline` over the stake events the program emitted, which reconcile with
+// the stake index entries on chain
+#[test]
+fn test_history_timeline() {
+    let (mut cluster, admin) = ProgramTestCluster::start();
+    let (owner, user) = (cluster.new_user(), cluster.new_user());
+    let mint = cluster.mint(&admin, &[user.pubkey()], &[]);
+    for (id, name) in [("1", "Atlas"), ("2", "Borealis")] {
+        cluster.ontora(&owner, &["agent", "register", "--id", id, "--name", name, "--description", "Maker"]).unwrap();
+    }
+    let (owner_arg, mint_arg) = (owner.pubkey().to_string(), mint.to_string());
+    let [first, second] =
+        ["1", "2"].map(|id| ["--agent-owner", owner_arg.as_str(), "--agent-id", id, "--mint", mint_arg.as_str()]);
+    let staked = cluster.ontora(&user, &[&["stake", "--amount", "3000000000"], &first[..]].concat()).unwrap();
+    cluster.warp_seconds(60);
+    cluster.ontora(&user, &[&["stake", "--amount", "2000000000"], &second[..]].concat()).unwrap();
+    cluster.warp_seconds(60);
+    cluster.ontora(&user, &[&["unstake", "--amount", "1000000000"], &first[..]].concat()).unwrap();
+
+    let wallet = user.pubkey().to_string();
+    let timeline = cluster.ontora_with(&user, &["history", "timeline", "--wallet", wallet.as_str()]).unwrap();
+    let entries = timeline["entries"].as_array().unwrap();
+    let summary: Vec<(&str, &str, u64, u64)> = entries
+        .iter()
+        .map(|entry| {
+            let (kind, target) = (entry["type"].as_str().unwrap(), entry["target"].as_str().unwrap());
+            (kind, target, entry["amount"].as_u64().unwrap(), entry["staked"].as_u64().unwrap())
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            ("stake", "agent 1", 3_000_000_000, 3_000_000_000),
+            ("stake", "agent 2", 2_000_000_000, 5_000_000_000),
+            ("unstake", "agent 1", 1_000_000_000, 4_000_000_000),
+        ]
+    );
+    assert_eq!(entries[0]["signature"], staked["signature"]);
+    assert!(entries.windows(2).all(|pair| pair[0]["timestamp"].as_i64() < pair[1]["timestamp"].as_i64()));
+    assert_eq!(timeline["gaps"], json!([]));
+    let position: UserStake = cluster.decode(&pda::user_stake(&user.pubkey()).0);
+    assert_eq!(position.staked_amount, 4_000_000_000);
+    assert_eq!(timeline["staked"], position.staked_amount);
+    assert_eq!(timeline["on_chain_staked"], position.staked_amount);
+    assert_eq!(timeline["claimed"], 0);
+}
+
+// Test `config update`, which keeps the parameters left out, and `pause set` and
+// `pause clear`, signed by the admin, who holds the pauser role
+#[test]
+fn test_admin_commands() {
+    let (mut cluster, admin) = ProgramTestCluster::start();
+    let updated = cluster.ontora(&admin, &["config", "update", "--reward-rate-bps", "250", "--note", "Q3"]).unwrap();
+    assert_eq!(updated["reward_rate_bps"], 250);
+    assert_eq!(updated["min_stake_amount"], 1_000_000);
+    let config: PlatformConfig = cluster.decode(&pda::platform_config().0);
+    assert_eq!((config.reward_rate_bps, config.epoch_duration), (250, 86_400));
+
+    let paused = cluster.ontora(&admin, &["pause", "set", "--groups", "deposits,claims"]).unwrap();
+    assert_eq!(paused["pause_flags"], PAUSE_DEPOSITS | PAUSE_CLAIMS);
+    assert_eq!(paused["paused"], json!(["deposits", "claims"]));
+    let cleared = cluster.ontora(&admin, &["pause", "clear", "--groups", "claims"]).unwrap();
+    assert_eq!(cleared["paused"], json!(["deposits"]));
+
+    let user = cluster.new_user();
+    assert!(matches!(cluster.ontora(&user, &["pause", "set", "--groups", "all"]), Err(CliError::WouldFail(_))));
+    assert!(Cli::try_parse_from(["ontora", "pause", "set"]).is_err());
+}
+
+// Test that with --multisig an admin command sends nothing and prints a blob of its
+// instruction with the multisig's vault as the authority, which `decode-instruction`
+// renders for co-signers without a keypair
+#[test]
+fn test_multisig_blob_and_decode_instruction() {
+    let (mut cluster, admin) = ProgramTestCluster::start();
+    let multisig = Pubkey::new_unique();
+    let vault = squads::vault(&multisig, 1).0;
+    let multisig_args = ["--multisig", &multisig.to_string(), "--vault-index", "1"].map(String::from);
+    let multisig_args: Vec<&str> = multisig_args.iter().map(String::as_str).collect();
+
+    let update = ["config", "update", "--epoch-duration", "3600", "--cooldown-seconds", "0", "--epoch-emission", "5"];
+    let update = [&update[..], &multisig_args[..]].concat();
+    let printed = cluster.ontora_with(&admin, &update).unwrap();
+    assert_eq!(printed["vault"], vault.to_string());
+    assert_eq!(printed["instruction"], "update_platform_config");
+    assert!(printed.get("signature").is_none());
+    let config: PlatformConfig = cluster.decode(&pda::platform_config().0);
+    assert_eq!(config.epoch_duration, 86_400);
+
+    let blob = printed["base58"].as_str().unwrap();
+    let instruction = decode_blob(blob).unwrap();
+    assert_eq!(instruction, decode_blob(printed["base64"].as_str().unwrap()).unwrap());
+    assert_eq!(instruction.accounts[1], AccountMeta::new_readonly(vault, true));
+
+    let cli = Cli::try_parse_from(["ontora", "decode-instruction", blob]).unwrap();
+    assert!(!cli.command.signs());
+    let mut session = read_only_session(&mut cluster);
+    let decoded = run(&cli.command, &mut session).unwrap().json;
+    assert_eq!(decoded["instruction"], "update_platform_config");
+    // The parameters left out are filled in from the current config
+    assert_eq!((decoded["epoch_duration"].as_i64(), decoded["reward_rate_bps"].as_u64()), (Some(3_600), Some(100)));
+    assert_eq!((decoded["cooldown_seconds"].as_i64(), decoded["epoch_emission"].as_u64()), (Some(0), Some(5)));
+    assert_eq!(decoded["max_stake_per_agent"].as_u64(), Some(0));
+    assert_eq!(decoded["vesting_seconds"].as_i64(), Some(0));
+    assert_eq!(decoded["vote_unit"].as_u64(), Some(DEFAULT_VOTE_UNIT));
+    assert_eq!(decoded["quorum_bps"].as_u64(), Some(DEFAULT_QUORUM_BPS.into()));
+    let admin_account = json!({ "name": "admin", "address": vault.to_string(), "signer": true, "writable": false });
+    assert_eq!(decoded["accounts"][1], admin_account);
+
+    // With --yes the proposal goes to the multisig, which must exist
+    let pause = [&["pause", "set", "--groups", "all"], &multisig_args[..]].concat();
+    let missing = cluster.ontora(&admin, &pause);
+    assert!(matches!(missing, Err(CliError::Client(ClientError::AccountNotFound(address))) if address == multisig));
+    let garbage = Cli::try_parse_from(["ontora", "decode-instruction", "not a blob"]).unwrap();
+    let mut session = read_only_session(&mut cluster);
+    assert!(matches!(run(&garbage.command, &mut session), Err(CliError::Instruction(_))));
+}
+
+// Test `decode-tx`: a serialized transaction is printed an instruction per row,
+// with the compute budget instruction kept by program id
+#[test]
+fn test_decode_tx() {
+    let (mut cluster, admin) = ProgramTestCluster::start();
+    let stake = build_stake_on_agent(StakeOnAgentParams {
+        user: admin.pubkey(),
+        user_token_account: Pubkey::new_unique(),
+        platform_vault: Pubkey::new_unique(),
+        agent_owner: admin.pubkey(),
+        agent_id: 7,
+        amount: 1_500_000_000,
+        beneficiary: None,
+    });
+    let limit = solana_sdk::compute_budget::ComputeBudgetInstruction::set_compute_unit_limit(200_000);
+    let message = Message::new(&[limit, stake], Some(&admin.pubkey()));
+    let transaction = VersionedTransaction {
+        signatures: vec![Signature::default()],
+        message: VersionedMessage::Legacy(message),
+    };
+    let base64 = base64::engine::general_purpose::STANDARD.encode(bincode::serialize(&transaction).unwrap());
+
+    let cli = Cli::try_parse_from(["ontora", "decode-tx", &base64, "--decimals", "9"]).unwrap();
+    assert!(!cli.command.signs());
+    let mut session = read_only_session(&mut cluster);
+    let output = run(&cli.command, &mut session).unwrap();
+    assert_eq!(output.json["fee_payer"], admin.pubkey().to_string());
+    let instructions = output.json["instructions"].as_array().unwrap();
+    assert_eq!(instructions[0]["program"], solana_sdk::compute_budget::ID.to_string());
+    assert!(instructions[0].get("instruction").is_none());
+    assert_eq!(instructions[1]["instruction"], "stake_on_agent");
+    let args = json!({ "agent_id": 7, "amount": 1_500_000_000u64, "beneficiary": admin.pubkey().to_string() });
+    assert_eq!(instructions[1]["args"], args);
+    assert_eq!(instructions[1]["accounts"][4]["address"], admin.pubkey().to_string());
+    assert!(output.table.rows[1][1].starts_with("stake_on_agent: agent_id=7, amount=1.5 TOKENS, beneficiary="));
+
+    let garbage = Cli::try_parse_from(["ontora", "decode-tx", "not a transaction"]).unwrap();
+    let mut session = read_only_session(&mut cluster);
+    let result = run(&garbage.command, &mut session);
+    assert!(matches!(result, Err(CliError::Client(ClientError::Instruction(_)))));
+}
+
+// Test the explorer links and that signing commands refuse to run without a keypair
+#[test]
+fn test_explorer_links_and_missing_signer() {
+    let signature = Signature::new_unique();
+    assert_eq!(
+        explorer_url(&signature, "https://api.mainnet-beta.solana.com"),
+        format!("https://explorer.solana.com/tx/{}", signature)
+    );
+    assert!(explorer_url(&signature, "https://api.devnet.solana.com").ends_with("?cluster=devnet"));
+    assert!(explorer_url(&signature, RPC_URL).ends_with("?cluster=custom&customUrl=http%3A%2F%2F127.0.0.1%3A8899"));
+
+    let (mut cluster, _) = ProgramTestCluster::start();
+    let cli = Cli::try_parse_from(["ontora", "proposal", "finalize", "--id", "0"]).unwrap();
+    assert!(cli.command.signs());
+    let mut session = read_only_session(&mut cluster);
+    assert!(matches!(run(&cli.command, &mut session), Err(CliError::NoSigner)));
+}
+
+// Test the compute-budget flags and their defaults
+#[test]
+fn test_compute_budget_flags() {
+    let cli = Cli::try_parse_from(["ontora", "config", "show"]).unwrap();
+    assert_eq!((cli.compute_limit, cli.priority_fee), (ComputeLimit::Table, PriorityFee::None));
+
+    let args = ["ontora", "--compute-limit", "simulate", "--priority-fee", "p75", "config", "show"];
+    let cli = Cli::try_parse_from(args).unwrap();
+    assert_eq!((cli.compute_limit, cli.priority_fee), (ComputeLimit::Simulate, PriorityFee::Percentile(75)));
+
+    let args = ["ontora", "config", "show", "--compute-limit", "90000", "--priority-fee", "5000"];
+    let cli = Cli::try_parse_from(args).unwrap();
+    assert_eq!((cli.compute_limit, cli.priority_fee), (ComputeLimit::Fixed(90_000), PriorityFee::Fixed(5_000)));
+
+    assert!(Cli::try_parse_from(["ontora", "--priority-fee", "p101", "config", "show"]).is_err());
+    assert!(Cli::try_parse_from(["ontora", "--compute-limit", "1400001", "config", "show"]).is_err());
+}
+
+// Test `bootstrap` on a fresh cluster: without --yes it only lists its steps; with it,
+// it waits out the faucet's rate limit, sets up every account and writes them to the
+// profile, and a rerun skips every step. Later commands read the mint from the profile
+#[test]
+fn test_bootstrap_writes_profile() {
+    const STEPS: [&str; 7] =
+        ["airdrop", "mint", "platform_config", "vaults", "reward_pool", "demo_agent", "stake"];
+    let mut cluster = ProgramTestCluster::launch();
+    cluster.rate_limited = 1;
+    let user = Keypair::new();
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("ontora.toml");
+    let profile_arg = path.to_str().unwrap();
+    let bootstrap_args = ["--profile", profile_arg, "bootstrap", "--cluster", "localnet"];
+
+    let plan = cluster.ontora_with(&user, &bootstrap_args).unwrap();
+    assert!(STEPS.iter().all(|step| plan[step] == "pending"), "{}", plan);
+    assert!(!path.exists());
+    assert_eq!(cluster.airdrops, 0);
+
+    let done = cluster.ontora(&user, &bootstrap_args).unwrap();
+    assert!(STEPS.iter().all(|step| done[step] == "done"), "{}", done);
+    // One refused for the rate limit, then one SOL at a time up to two
+    assert_eq!(cluster.airdrops, 3);
+
+    let profile = Profile::load(&path).unwrap().expect("bootstrap wrote the profile");
+    assert_eq!((profile.cluster.as_deref(), profile.url.as_deref()), (Some("localnet"), Some(RPC_URL)));
+    let addresses = &profile.addresses;
+    let mint = addresses.mint.unwrap();
+    assert_eq!(done["mint"], mint.to_string());
+    let ata = spl_associated_token_account::get_associated_token_address;
+    assert_eq!(addresses.token_account, Some(ata(&user.pubkey(), &mint)));
+    assert_eq!(addresses.platform_config, Some(pda::platform_config().0));
+    assert_eq!(addresses.platform_vault, Some(pda::staking_vault().0));
+    assert_eq!(addresses.reward_pool, Some(pda::reward_pool().0));
+    assert_eq!(addresses.reward_vault, Some(pda::reward_vault().0));
+    assert_eq!(addresses.agent, Some(pda::ai_agent(&user.pubkey(), bootstrap::DEMO_AGENT_ID).0));
+    assert_eq!(addresses.agent_id, Some(bootstrap::DEMO_AGENT_ID));
+    assert_eq!(addresses.user_stake, Some(pda::user_stake(&user.pubkey()).0));
+    assert!(std::fs::read_to_string(&path).unwrap().contains(&format!("mint = \"{}\"", mint)));
+
+    let config: PlatformConfig = cluster.decode(&pda::platform_config().0);
+    assert_eq!(config.admin, user.pubkey());
+    let position: UserStake = cluster.decode(&pda::user_stake(&user.pubkey()).0);
+    assert_eq!(position.staked_amount, bootstrap::DEMO_STAKE);
+    let spent = bootstrap::INITIAL_REWARDS + bootstrap::DEMO_STAKE;
+    assert_eq!(cluster.token_balance(&user.pubkey(), &mint), bootstrap::MINTED - spent);
+    assert_eq!(cluster.vault_balance(&pda::reward_vault().0), bootstrap::INITIAL_REWARDS);
+
+    let again = cluster.ontora(&user, &bootstrap_args).unwrap();
+    assert!(STEPS.iter().all(|step| again[step] == "skipped"), "{}", again);
+    assert_eq!(cluster.airdrops, 3);
+    assert_eq!(Profile::load(&path).unwrap().unwrap(), profile);
+
+    let owner = user.pubkey().to_string();
+    let unstake = ["--profile", profile_arg, "unstake", "--agent-owner", &owner, "--agent-id", "1", "--amount", "1000"];
+    let unstaked = cluster.ontora(&user, &unstake).unwrap();
+    assert_eq!(unstaked["staked_amount"], bootstrap::DEMO_STAKE - 1_000);
+    let elsewhere = dir.path().join("missing.toml");
+    let unstake = [&["--profile", elsewhere.to_str().unwrap()], &unstake[2..]].concat();
+    assert!(matches!(cluster.ontora(&user, &unstake), Err(CliError::NoMint)));
+}
@@ -0,0 +1,177 @@
+// test_history.rs
+// Tests for the staking history export. A synthetic set of transactions is turned into
+// the wallet's entries and rendered as CSV, compared with the snapshot in
+// tests/golden/history_2024.csv; regenerate it after an intended format change with
+//     UPDATE_HISTORY_GOLDENS=1 cargo test --test test_history
+// The indexer database source is checked against a SQLite file seeded through `SqlSink`.
+
+use std::collections::BTreeMap;
+
+use ontora_ai::events::{RewardClaimed, StakeDeposited, StakeWithdrawn, TreasuryDeposit};
+use ontora_ai::state::FeeSource;
+use ontora_cli::history::{self, HistoryKind};
+use ontora_client::OntoraEvent;
+use ontora_indexer::{EventRecord, EventSink, PriceUpdate, SqlSink};
+use serde_json::Value;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use tempfile::TempDir;
+
+const GOLDEN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden/history_2024.csv");
+
+const WALLET: Pubkey = Pubkey::new_from_array([1; 32]);
+const OTHER: Pubkey = Pubkey::new_from_array([2; 32]);
+const MINT: Pubkey = Pubkey::new_from_array([3; 32]);
+const DECIMALS: u8 = 9;
+// ONT/USD scaled by 10^8: $1.50
+const PRICE: i64 = 150_000_000;
+
+// 2024-03-01T00:00:00Z, 2024-06-01T12:00:00Z, 2024-10-01T00:00:00Z and 2025-01-01T00:00:00Z
+const MARCH: i64 = 1_709_251_200;
+const JUNE: i64 = 1_717_243_200;
+const OCTOBER: i64 = 1_727_740_800;
+const NEXT_YEAR: i64 = 1_735_689_600;
+
+fn deposit(user: Pubkey, amount: u64, timestamp: i64) -> OntoraEvent {
+    OntoraEvent::StakeDeposited(StakeDeposited {
+        schema_version: StakeDeposited::SCHEMA_VERSION,
+        user,
+        agent_id: 7,
+        amount,
+        timestamp,
+        staking_duration: 86_400,
+    })
+}
+
+fn withdrawal(user: Pubkey, amount: u64, timestamp: i64) -> OntoraEvent {
+    OntoraEvent::StakeWithdrawn(StakeWithdrawn {
+        schema_version: StakeWithdrawn::SCHEMA_VERSION,
+        user,
+        agent_id: 7,
+        amount,
+        timestamp,
+    })
+}
+
+fn claim(user: Pubkey, reward_amount: u64, timestamp: i64) -> OntoraEvent {
+    OntoraEvent::RewardClaimed(RewardClaimed {
+        schema_version: RewardClaimed::SCHEMA_VERSION,
+        user,
+        agent_id: 7,
+        reward_amount,
+        timestamp,
+    })
+}
+
+fn fee(source: FeeSource, amount: u64, timestamp: i64) -> OntoraEvent {
+    OntoraEvent::TreasuryDeposit(TreasuryDeposit {
+        schema_version: TreasuryDeposit::SCHEMA_VERSION,
+        source,
+        mint: MINT,
+        amount,
+        timestamp,
+    })
+}
+
+// Transactions touching the wallet and another user across two years, out of order
+fn transactions() -> Vec<(Signature, Vec<OntoraEvent>)> {
+    vec![
+        (
+            Signature::from([12; 64]),
+            vec![claim(WALLET, 123_456_789, OCTOBER), fee(FeeSource::Claim, 1_234_567, OCTOBER)],
+        ),
+        (Signature::from([10; 64]), vec![deposit(WALLET, 2_500_000_000, MARCH)]),
+        (Signature::from([14; 64]), vec![deposit(WALLET, 1_000_000_000, NEXT_YEAR)]),
+        (Signature::from([13; 64]), vec![claim(OTHER, 99, JUNE), fee(FeeSource::Claim, 1, JUNE)]),
+        (
+            Signature::from([11; 64]),
+            vec![withdrawal(WALLET, 1_000_000_000, JUNE), fee(FeeSource::Unstake, 5_000_000, JUNE)],
+        ),
+    ]
+}
+
+fn render(entries: &[history::HistoryEntry]) -> String {
+    let decimals = BTreeMap::from([(MINT, DECIMALS)]);
+    let rows: Vec<Value> = entries.iter().map(|entry| history::fields(entry, &decimals, 8)).collect();
+    history::to_csv(&rows)
+}
+
+// Test that the wallet's 2024 entries, sorted and priced, render as the snapshot
+#[test]
+fn test_csv_export_matches_golden() {
+    let range = history::year_range(2024).unwrap();
+    assert_eq!(range, 1_704_067_200..NEXT_YEAR);
+    let mut entries = Vec::new();
+    for (signature, events) in transactions() {
+        let found = history::entries(&WALLET, &MINT, &signature, &events);
+        entries.extend(found.into_iter().filter(|entry| range.contains(&entry.timestamp)));
+    }
+    history::sort(&mut entries);
+    for entry in entries.iter_mut().filter(|entry| entry.kind == HistoryKind::Claim) {
+        entry.price = Some(PRICE);
+    }
+
+    let rendered = render(&entries);
+    if std::env::var_os("UPDATE_HISTORY_GOLDENS").is_some() {
+        std::fs::write(GOLDEN_PATH, &rendered).unwrap();
+        return;
+    }
+    let golden = std::fs::read_to_string(GOLDEN_PATH).unwrap_or_default();
+    assert_eq!(rendered, golden, "history CSV changed; if intended, regenerate with UPDATE_HISTORY_GOLDENS=1");
+}
+
+// Test amount normalization and USD rounding
+#[test]
+fn test_amounts_and_usd_values() {
+    assert_eq!(history::format_amount(1, 9), "0.000000001");
+    assert_eq!(history::format_amount(12_345, 0), "12345");
+    assert_eq!(history::format_amount(u64::MAX as u128, 6), "18446744073709.551615");
+    // 0.005 tokens at $1.00 rounds up to a cent; 0.004 rounds down
+    assert_eq!(history::usd_value(5_000_000, 9, 100_000_000, 8).as_deref(), Some("0.01"));
+    assert_eq!(history::usd_value(4_000_000, 9, 100_000_000, 8).as_deref(), Some("0.00"));
+    assert_eq!(history::usd_value(2_000_000_000, 9, 14_250_000_000, 8).as_deref(), Some("285.00"));
+    assert_eq!(history::usd_value(1, 0, -1, 8), None);
+}
+
+// Test the database source: the wallet's stakes, claims and fees in the year, each
+// claim priced with the latest archived price at or before it
+#[test]
+fn test_reads_indexer_database() {
+    let dir = TempDir::new().unwrap();
+    let url = format!("sqlite://{}?mode=rwc", dir.path().join("indexer.db").display());
+    let mut sink = SqlSink::connect(&url).unwrap();
+    for (slot, (signature, events)) in transactions().into_iter().enumerate() {
+        for (index, event) in events.into_iter().enumerate() {
+            sink.apply(&EventRecord { slot: slot as u64, signature, index, event });
+        }
+    }
+    let prices = [(1, 140_000_000, MARCH), (2, PRICE, OCTOBER - 60), (3, 170_000_000, OCTOBER + 60)];
+    for (round, price, timestamp) in prices {
+        sink.record_price(&PriceUpdate {
+            slot: 100 + round,
+            signature: Signature::from([round as u8; 64]),
+            index: 0,
+            symbol: "ONT/USD".to_string(),
+            feed: Pubkey::new_unique(),
+            price,
+            confidence: 0,
+            round,
+            timestamp,
+        })
+        .unwrap();
+    }
+    drop(sink);
+
+    let range = history::year_range(2024).unwrap();
+    let mut entries = history::from_database(&url, &WALLET, &MINT, Some("ONT/USD"), range.clone()).unwrap();
+    history::sort(&mut entries);
+    let kinds: Vec<HistoryKind> = entries.iter().map(|entry| entry.kind).collect();
+    let expected = [HistoryKind::Stake, HistoryKind::Unstake, HistoryKind::Fee, HistoryKind::Claim, HistoryKind::Fee];
+    assert_eq!(kinds, expected);
+    assert_eq!(entries[3].price, Some(PRICE));
+    assert!(entries.iter().filter(|entry| entry.kind != HistoryKind::Claim).all(|entry| entry.price.is_none()));
+    assert_eq!(render(&entries), std::fs::read_to_string(GOLDEN_PATH).unwrap());
+
+    let unpriced = history::from_database(&url, &WALLET, &MINT, None, range).unwrap();
+    assert!(unpriced.iter().all(|entry| entry.price.is_none()));
+}
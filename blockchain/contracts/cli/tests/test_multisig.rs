@@ -0,0 +1,239 @@
+// test_multisig.rs
+// Tests for the `--multisig` flow: every admin instruction survives the trip through
+// a base58 and a base64 blob and decodes back to its named arguments and accounts,
+// and the Squads instructions that propose one lay out its vault transaction as the
+// Squads program reads it.
+
+use ontora_ai::state::{Role, PAUSE_ALL, PAUSE_GOVERNANCE, PAUSE_WITHDRAWALS};
+use ontora_cli::*;
+use ontora_client::*;
+use serde_json::{json, Value};
+use solana_sdk::hash::hash;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::system_program;
+
+fn key(seed: u8) -> Pubkey {
+    Pubkey::new_from_array([seed; 32])
+}
+
+// Encode `instruction` both ways, check that each blob decodes back to it, and
+// return its decoded form
+fn round_trip(instruction: &Instruction) -> DecodedInstruction {
+    let blob = encode_blob(instruction);
+    assert_eq!(decode_blob(&blob.base58).unwrap(), *instruction);
+    assert_eq!(decode_blob(&blob.base64).unwrap(), *instruction);
+    assert_eq!(decode_blob(&format!("  {}\n", blob.base64)).unwrap(), *instruction);
+    let decoded = decode_admin_instruction(instruction).unwrap();
+    let metas: Vec<AccountMeta> = decoded.accounts.iter().map(|(_, meta)| meta.clone()).collect();
+    assert_eq!(metas, instruction.accounts);
+    decoded
+}
+
+fn account_names(decoded: &DecodedInstruction) -> Vec<&'static str> {
+    decoded.accounts.iter().map(|(name, _)| *name).collect()
+}
+
+fn args(decoded: &DecodedInstruction) -> Value {
+    Value::Object(decoded.args.iter().map(|(name, value)| (name.to_string(), arg_json(value))).collect())
+}
+
+// Test the round trip of every admin instruction, with the arguments and account
+// names a reviewer sees
+#[test]
+fn test_admin_instructions_round_trip() {
+    let (admin, mint, recipient) = (key(1), key(2), key(3));
+
+    let update = build_update_platform_config(UpdatePlatformConfigParams {
+        admin,
+        reward_rate_bps: 250,
+        min_stake_amount: 5_000_000,
+        epoch_duration: 3_600,
+        cooldown_seconds: 86_400,
+        epoch_emission: 1_000_000,
+        max_stake_per_agent: 50_000_000,
+        vesting_seconds: 604_800,
+        vote_unit: 5_000_000,
+        quorum_bps: 1_500,
+        approval_threshold_bps: 5_500,
+        update_metadata: "Q3 rates".to_string(),
+    });
+    let decoded = round_trip(&update);
+    assert_eq!(decoded.name, "update_platform_config");
+    let expected = json!({
+        "reward_rate_bps": 250,
+        "min_stake_amount": 5_000_000,
+        "epoch_duration": 3_600,
+        "cooldown_seconds": 86_400,
+        "epoch_emission": 1_000_000,
+        "max_stake_per_agent": 50_000_000,
+        "vesting_seconds": 604_800,
+        "vote_unit": 5_000_000,
+        "quorum_bps": 1_500,
+        "approval_threshold_bps": 5_500,
+        "update_metadata": "Q3 rates",
+    });
+    assert_eq!(args(&decoded), expected);
+    assert_eq!(account_names(&decoded), ["platform_config", "admin"]);
+    assert_eq!(decoded.accounts[1].1, AccountMeta::new_readonly(admin, true));
+    let decoded = round_trip(&with_governance_realm(update));
+    assert_eq!(account_names(&decoded), ["platform_config", "admin", "governance_realm"]);
+
+    let migrate = build_migrate_platform_config(MigratePlatformConfigParams { admin });
+    let decoded = round_trip(&migrate);
+    assert_eq!((decoded.name, args(&decoded)), ("migrate_platform_config", json!({})));
+    assert_eq!(account_names(&decoded), ["platform_config", "admin", "system_program"]);
+
+    let governance = build_set_governance_enabled(SetGovernanceEnabledParams {
+        admin,
+        enabled: false,
+        active_proposal_ids: vec![4, 7],
+    });
+    let decoded = round_trip(&governance);
+    assert_eq!((decoded.name, args(&decoded)), ("set_governance_enabled", json!({ "enabled": false })));
+    assert_eq!(account_names(&decoded), ["platform_config", "admin", "proposal", "proposal"]);
+    assert_eq!(decoded.accounts[3].1.pubkey, pda::proposal(7).0);
+
+    let propose = build_propose_admin(ProposeAdminParams { admin, new_admin: key(9) });
+    let decoded = round_trip(&propose);
+    assert_eq!((decoded.name, args(&decoded)), ("propose_admin", json!({ "new_admin": key(9).to_string() })));
+
+    let cancel = build_cancel_admin_transfer(CancelAdminTransferParams { admin });
+    assert_eq!(round_trip(&cancel).name, "cancel_admin_transfer");
+
+    let fees = build_update_fee_schedule(UpdateFeeScheduleParams { authority: admin, rates_bps: [10, 20, 30, 500] });
+    let decoded = round_trip(&fees);
+    assert_eq!((decoded.name, args(&decoded)), ("update_fee_schedule", json!({ "rates_bps": [10, 20, 30, 500] })));
+    assert_eq!(account_names(&decoded), ["platform_config", "fee_schedule", "authority"]);
+
+    let transfer = build_treasury_transfer(TreasuryTransferParams { governance: admin, mint, recipient, amount: 42 });
+    let decoded = round_trip(&transfer);
+    assert_eq!((decoded.name, args(&decoded)), ("treasury_transfer", json!({ "amount": 42 })));
+    let accounts = ["treasury", "mint", "treasury_vault", "recipient", "governance", "token_program"];
+    assert_eq!(account_names(&decoded), accounts);
+    assert_eq!(decoded.accounts[3].1.pubkey, recipient);
+    let decoded = round_trip(&with_governance_realm(transfer));
+    assert_eq!(decoded.accounts[6], ("governance_realm", AccountMeta::new_readonly(pda::governance_realm().0, false)));
+
+    let flags = PAUSE_WITHDRAWALS | PAUSE_GOVERNANCE;
+    let pause = build_set_pause_flags(SetPauseFlagsParams { authority: admin, flags });
+    let decoded = round_trip(&pause);
+    assert_eq!(decoded.name, "set_pause_flags");
+    assert_eq!(args(&decoded), json!({ "flags": 10, "groups": ["withdrawals", "governance"] }));
+    assert_eq!(account_names(&decoded), ["platform_config", "authority"]);
+
+    let unpause = build_clear_pause_flags(ClearPauseFlagsParams { admin, flags: PAUSE_ALL });
+    let decoded = round_trip(&unpause);
+    assert_eq!(decoded.name, "clear_pause_flags");
+    let groups = ["deposits", "withdrawals", "claims", "governance", "oracle-updates"];
+    assert_eq!(args(&decoded), json!({ "flags": PAUSE_ALL, "groups": groups }));
+
+    let role = build_set_role(SetRoleParams { admin, role: Role::Pauser, holder: key(5) });
+    let decoded = round_trip(&role);
+    assert_eq!(decoded.name, "set_role");
+    assert_eq!(args(&decoded), json!({ "role": "pauser", "holder": key(5).to_string() }));
+
+    let realm = build_set_governance_realm(SetGovernanceRealmParams { admin, realm: key(6), governance: key(7) });
+    let decoded = round_trip(&realm);
+    assert_eq!(decoded.name, "set_governance_realm");
+    assert_eq!(args(&decoded), json!({ "realm": key(6).to_string(), "governance": key(7).to_string() }));
+    let accounts = ["platform_config", "governance_realm", "governance", "admin", "system_program"];
+    assert_eq!(account_names(&decoded), accounts);
+}
+
+// Test that blobs of anything but an admin instruction are refused
+#[test]
+fn test_decode_rejects_other_instructions() {
+    let stake = build_stake_on_agent(StakeOnAgentParams {
+        user: key(1),
+        user_token_account: key(2),
+        platform_vault: key(3),
+        agent_owner: key(4),
+        agent_id: 1,
+        amount: 10,
+        beneficiary: None,
+    });
+    let blob = encode_blob(&stake);
+    assert!(matches!(decode_admin_instruction(&decode_blob(&blob.base58).unwrap()), Err(CliError::Instruction(_))));
+
+    let transfer = solana_sdk::system_instruction::transfer(&key(1), &key(2), 10);
+    assert!(matches!(decode_admin_instruction(&transfer), Err(CliError::Instruction(_))));
+    let role = build_set_role(SetRoleParams { admin: key(1), role: Role::Crank, holder: key(2) });
+    let mut truncated = role.clone();
+    truncated.data.truncate(20);
+    assert!(matches!(decode_admin_instruction(&truncated), Err(CliError::Instruction(_))));
+    let mut short = role;
+    short.accounts.pop();
+    assert!(matches!(decode_admin_instruction(&short), Err(CliError::Instruction(_))));
+    assert!(matches!(decode_blob("3mJr7AoUXx2Wqd"), Err(CliError::Instruction(_))));
+}
+
+// Test the Squads instructions proposing a pause: the vault transaction holds the
+// pause as a message paid by the vault, and the proposal follows the multisig's
+// last transaction index
+#[test]
+fn test_squads_proposal_layout() {
+    let (multisig, creator) = (key(20), key(21));
+    let vault = squads::vault(&multisig, 0).0;
+    assert_ne!(vault, squads::vault(&multisig, 1).0);
+    let pause = build_set_pause_flags(SetPauseFlagsParams { authority: vault, flags: PAUSE_ALL });
+
+    // A multisig account whose last transaction is number 6
+    let mut data = vec![0; 8 + 32 + 32 + 2 + 4];
+    data.extend_from_slice(&6u64.to_le_bytes());
+    data.extend_from_slice(&[0; 40]);
+    let transaction_index = squads::next_transaction_index(&multisig, &data).unwrap();
+    assert_eq!(transaction_index, 7);
+    assert!(squads::next_transaction_index(&multisig, &data[..80]).is_err());
+
+    let params = squads::ProposeParams {
+        multisig,
+        vault_index: 0,
+        transaction_index,
+        creator,
+        memo: Some("pause all".to_string()),
+    };
+    let [create_transaction, create_proposal] = squads::propose(params, &[pause.clone()]).unwrap();
+    assert_eq!((create_transaction.program_id, create_proposal.program_id), (squads::ID, squads::ID));
+    let transaction = squads::transaction(&multisig, 7).0;
+    assert_eq!(
+        create_transaction.accounts,
+        [
+            AccountMeta::new(multisig, false),
+            AccountMeta::new(transaction, false),
+            AccountMeta::new_readonly(creator, true),
+            AccountMeta::new(creator, true),
+            AccountMeta::new_readonly(system_program::ID, false),
+        ]
+    );
+    assert_eq!(create_proposal.accounts[1], AccountMeta::new(squads::proposal(&multisig, 7).0, false));
+
+    let data = &create_transaction.data;
+    assert_eq!(data[..8], hash(b"global:vault_transaction_create").to_bytes()[..8]);
+    // vault_index, ephemeral_signers, the message's length, the message, then the memo
+    assert_eq!(data[8..10], [0, 0]);
+    let length = u32::from_le_bytes(data[10..14].try_into().unwrap()) as usize;
+    let message = &data[14..14 + length];
+    assert_eq!(message, squads::transaction_message(&[pause.clone()], &vault).unwrap());
+    let memo = &data[14 + length..];
+    assert_eq!(memo[..5], [1, 9, 0, 0, 0]);
+    assert_eq!(&memo[5..], b"pause all");
+
+    // One writable signer (the vault), one writable account (the platform config),
+    // then the program; one instruction over them carrying the pause's data
+    assert_eq!(message[..4], [1, 1, 1, 3]);
+    let keys: Vec<&[u8]> = message[4..4 + 3 * 32].chunks(32).collect();
+    assert_eq!(keys, [vault.as_ref(), pda::platform_config().0.as_ref(), ontora_ai::ID.as_ref()]);
+    let instructions = &message[4 + 3 * 32..];
+    assert_eq!(instructions[0], 1);
+    // Program index, the platform config and vault as accounts, the data's u16 length
+    assert_eq!(instructions[1..5], [2, 2, 1, 0]);
+    assert_eq!(instructions[5..7], (pause.data.len() as u16).to_le_bytes());
+    assert_eq!(instructions[7..7 + pause.data.len()], pause.data[..]);
+    // No address lookup tables
+    assert_eq!(instructions[7 + pause.data.len()..], [0]);
+
+    let proposal_data = &create_proposal.data;
+    assert_eq!(proposal_data[..8], hash(b"global:proposal_create").to_bytes()[..8]);
+    assert_eq!(proposal_data[8..], [7, 0, 0, 0, 0, 0, 0, 0, 0]);
+}
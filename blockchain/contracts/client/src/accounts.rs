@@ -0,0 +1,118 @@
+//! Typed account fetches. Each checks the account's owner and Anchor
+//! discriminator before decoding, so a wrong address fails loudly instead of
+//! returning garbage.
+
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::{AccountDeserialize, ZeroCopy};
+use ontora_ai::state::{
+    AdminActionProposal, AdminMultisig, AiAgent, FeeSchedule, GovernanceRealm, Metadata, PlatformConfig, Proposal,
+    StakeIndexEntry, Treasury, UserStake,
+};
+use ontora_ai::{AIAgent, GovernanceProposal, PendingWithdrawal, RewardPool};
+use solana_client::rpc_client::RpcClient;
+
+use crate::error::ClientError;
+use crate::pda;
+
+/// Decode a Borsh account from its raw data, discriminator included.
+pub fn decode_account<T: AccountDeserialize>(address: &Pubkey, data: &[u8]) -> Result<T, ClientError> {
+    T::try_deserialize(&mut &data[..]).map_err(|error| ClientError::Decode(*address, error.to_string()))
+}
+
+/// Decode a zero-copy account from its raw data, discriminator included.
+pub fn decode_zero_copy<T: ZeroCopy>(address: &Pubkey, data: &[u8]) -> Result<T, ClientError> {
+    let body = data
+        .strip_prefix(&T::discriminator()[..])
+        .ok_or_else(|| ClientError::Decode(*address, "discriminator mismatch".to_string()))?;
+    let size = std::mem::size_of::<T>();
+    if body.len() < size {
+        return Err(ClientError::Decode(*address, format!("{} bytes, expected {}", body.len(), size)));
+    }
+    Ok(bytemuck::pod_read_unaligned(&body[..size]))
+}
+
+fn account_data(rpc: &RpcClient, address: &Pubkey) -> Result<Vec<u8>, ClientError> {
+    let account = rpc
+        .get_account_with_commitment(address, rpc.commitment())?
+        .value
+        .ok_or(ClientError::AccountNotFound(*address))?;
+    check_owner(address, &account.owner)?;
+    Ok(account.data)
+}
+
+pub(crate) fn check_owner(address: &Pubkey, owner: &Pubkey) -> Result<(), ClientError> {
+    if *owner != ontora_ai::ID {
+        return Err(ClientError::WrongOwner(*address, *owner));
+    }
+    Ok(())
+}
+
+/// Fetch and decode any Borsh account of the program.
+pub fn fetch_account<T: AccountDeserialize>(rpc: &RpcClient, address: &Pubkey) -> Result<T, ClientError> {
+    decode_account(address, &account_data(rpc, address)?)
+}
+
+/// Fetch and decode any zero-copy account of the program.
+pub fn fetch_zero_copy<T: ZeroCopy>(rpc: &RpcClient, address: &Pubkey) -> Result<T, ClientError> {
+    decode_zero_copy(address, &account_data(rpc, address)?)
+}
+
+pub fn fetch_platform_config(rpc: &RpcClient) -> Result<PlatformConfig, ClientError> {
+    fetch_account(rpc, &pda::platform_config().0)
+}
+
+pub fn fetch_fee_schedule(rpc: &RpcClient) -> Result<FeeSchedule, ClientError> {
+    fetch_account(rpc, &pda::fee_schedule().0)
+}
+
+pub fn fetch_treasury(rpc: &RpcClient) -> Result<Treasury, ClientError> {
+    fetch_account(rpc, &pda::treasury().0)
+}
+
+pub fn fetch_governance_realm(rpc: &RpcClient) -> Result<GovernanceRealm, ClientError> {
+    fetch_account(rpc, &pda::governance_realm().0)
+}
+
+pub fn fetch_reward_pool(rpc: &RpcClient) -> Result<RewardPool, ClientError> {
+    fetch_account(rpc, &pda::reward_pool().0)
+}
+
+pub fn fetch_ai_agent(rpc: &RpcClient, owner: &Pubkey, agent_id: u64) -> Result<AiAgent, ClientError> {
+    fetch_account(rpc, &pda::ai_agent(owner, agent_id).0)
+}
+
+pub fn fetch_legacy_ai_agent(rpc: &RpcClient, owner: &Pubkey) -> Result<AIAgent, ClientError> {
+    fetch_account(rpc, &pda::legacy_ai_agent(owner).0)
+}
+
+pub fn fetch_pending_withdrawal(rpc: &RpcClient, owner: &Pubkey) -> Result<PendingWithdrawal, ClientError> {
+    fetch_account(rpc, &pda::pending_withdrawal(owner).0)
+}
+
+pub fn fetch_user_stake(rpc: &RpcClient, user: &Pubkey) -> Result<UserStake, ClientError> {
+    fetch_account(rpc, &pda::user_stake(user).0)
+}
+
+pub fn fetch_stake_index(rpc: &RpcClient, user: &Pubkey, agent_id: u64) -> Result<StakeIndexEntry, ClientError> {
+    fetch_account(rpc, &pda::stake_index(user, agent_id).0)
+}
+
+pub fn fetch_proposal(rpc: &RpcClient, proposal_id: u64) -> Result<Proposal, ClientError> {
+    fetch_zero_copy(rpc, &pda::proposal(proposal_id).0)
+}
+
+pub fn fetch_legacy_proposal(rpc: &RpcClient, creator: &Pubkey) -> Result<GovernanceProposal, ClientError> {
+    fetch_account(rpc, &pda::legacy_proposal(creator).0)
+}
+
+pub fn fetch_metadata(rpc: &RpcClient, authority: &Pubkey, entity_id: u64) -> Result<Metadata, ClientError> {
+    fetch_account(rpc, &pda::metadata(authority, entity_id).0)
+}
+
+pub fn fetch_admin_multisig(rpc: &RpcClient) -> Result<AdminMultisig, ClientError> {
+    fetch_account(rpc, &pda::admin_multisig().0)
+}
+
+pub fn fetch_admin_action(rpc: &RpcClient, action_id: u64) -> Result<AdminActionProposal, ClientError> {
+    fetch_account(rpc, &pda::admin_action(action_id).0)
+}
@@ -0,0 +1,864 @@
+//! Transactions decoded for review. Each instruction addressed to the program
+//! is matched by its Anchor discriminator, its arguments are deserialized and
+//! its accounts named by position as in the program's account structs;
+//! instructions for other programs, and ours that do not decode, are kept with
+//! their program ids.
+//!
+//!     let transaction = parse_transaction(&base64)?;
+//!     let decoded = decode_transaction(&transaction, &[fetch_lookup_table(&rpc, &table)?])?;
+//!     println!("{}", decoded.report(Some(9)));
+//!
+//! prints a line per instruction:
+//!
+//!     #0 stake_on_agent: agent_id=7, amount=1.5 TOKENS, beneficiary=…, platform_config=…, user=…, platform_vault=…
+
+use anchor_lang::{AnchorDeserialize, Discriminator};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use ontora_ai::instruction as ix;
+use ontora_ai::state::{
+    AdminAction, ProposalAction, Role, PAUSE_CLAIMS, PAUSE_DEPOSITS, PAUSE_GOVERNANCE, PAUSE_ORACLE_UPDATES,
+    PAUSE_WITHDRAWALS,
+};
+use solana_sdk::address_lookup_table::AddressLookupTableAccount;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::message::{MessageHeader, VersionedMessage};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::transaction::VersionedTransaction;
+
+use crate::error::ClientError;
+
+/// A decoded instruction argument.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ArgValue {
+    Unsigned(u64),
+    Signed(i64),
+    Bool(bool),
+    Text(String),
+    Key(Pubkey),
+    /// Token base units.
+    Tokens(u64),
+    /// An enum variant or flag name.
+    Label(&'static str),
+    List(Vec<ArgValue>),
+}
+
+impl ArgValue {
+    /// The value as printed in a report; token amounts are scaled by `decimals`
+    /// when the mint's are known, and left in base units otherwise.
+    pub fn render(&self, decimals: Option<u8>) -> String {
+        match self {
+            ArgValue::Unsigned(value) => value.to_string(),
+            ArgValue::Signed(value) => value.to_string(),
+            ArgValue::Bool(value) => value.to_string(),
+            ArgValue::Text(text) => format!("{:?}", text),
+            ArgValue::Key(key) => key.to_string(),
+            ArgValue::Tokens(amount) => match decimals {
+                Some(decimals) => format!("{} TOKENS", ui_amount(*amount, decimals)),
+                None => amount.to_string(),
+            },
+            ArgValue::Label(label) => label.to_string(),
+            ArgValue::List(values) => {
+                let values: Vec<String> = values.iter().map(|value| value.render(decimals)).collect();
+                format!("[{}]", values.join(", "))
+            }
+        }
+    }
+}
+
+// `amount` base units as a decimal with `decimals` places, trailing zeros dropped
+fn ui_amount(amount: u64, decimals: u8) -> String {
+    let scale = 10u128.pow(decimals as u32);
+    let (whole, fraction) = (amount as u128 / scale, amount as u128 % scale);
+    if fraction == 0 {
+        return whole.to_string();
+    }
+    let fraction = format!("{:0width$}", fraction, width = decimals as usize);
+    format!("{}.{}", whole, fraction.trim_end_matches('0'))
+}
+
+/// One of the program's instructions with its arguments and accounts named as
+/// in the program.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DecodedInstruction {
+    pub name: &'static str,
+    pub args: Vec<(&'static str, ArgValue)>,
+    pub accounts: Vec<(&'static str, AccountMeta)>,
+}
+
+/// An instruction of a transaction under review.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ReviewedInstruction {
+    Decoded(DecodedInstruction),
+    /// Addressed to the program but not one of its instructions, or malformed.
+    Undecoded { instruction: Instruction, reason: String },
+    /// Addressed to another program.
+    Other(Instruction),
+}
+
+/// A transaction's instructions, in order, as [`decode_transaction`] read them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DecodedTransaction {
+    pub fee_payer: Pubkey,
+    pub instructions: Vec<ReviewedInstruction>,
+}
+
+impl ReviewedInstruction {
+    pub fn program_id(&self) -> Pubkey {
+        match self {
+            ReviewedInstruction::Decoded(_) => ontora_ai::ID,
+            ReviewedInstruction::Undecoded { instruction, .. } | ReviewedInstruction::Other(instruction) => {
+                instruction.program_id
+            }
+        }
+    }
+
+    /// The instruction as one line of a report. Program accounts
+    /// (`system_program`, `token_program`) are left out; the program fixes
+    /// them. An optional account left out shows as `none`: Anchor passes the
+    /// program id in its place.
+    pub fn summary(&self, decimals: Option<u8>) -> String {
+        match self {
+            ReviewedInstruction::Decoded(decoded) => {
+                let args = decoded.args.iter().map(|(name, value)| format!("{}={}", name, value.render(decimals)));
+                let accounts = decoded.accounts.iter().filter(|(name, _)| !name.ends_with("_program"));
+                let accounts = accounts.map(|(name, meta)| {
+                    if meta.pubkey == ontora_ai::ID {
+                        format!("{}=none", name)
+                    } else {
+                        format!("{}={}", name, meta.pubkey)
+                    }
+                });
+                let fields: Vec<String> = args.chain(accounts).collect();
+                if fields.is_empty() {
+                    decoded.name.to_string()
+                } else {
+                    format!("{}: {}", decoded.name, fields.join(", "))
+                }
+            }
+            ReviewedInstruction::Undecoded { instruction, reason } => {
+                format!("program {}: not decoded, {}", instruction.program_id, reason)
+            }
+            ReviewedInstruction::Other(instruction) => format!(
+                "program {}: {} accounts, {} bytes of data",
+                instruction.program_id,
+                instruction.accounts.len(),
+                instruction.data.len()
+            ),
+        }
+    }
+}
+
+impl DecodedTransaction {
+    /// The fee payer, then each instruction's summary.
+    pub fn report(&self, decimals: Option<u8>) -> String {
+        let mut lines = vec![format!("fee payer: {}", self.fee_payer)];
+        for (index, instruction) in self.instructions.iter().enumerate() {
+            lines.push(format!("#{} {}", index, instruction.summary(decimals)));
+        }
+        lines.join("\n")
+    }
+}
+
+const PAUSE_GROUPS: [(u8, &str); 5] = [
+    (PAUSE_DEPOSITS, "deposits"),
+    (PAUSE_WITHDRAWALS, "withdrawals"),
+    (PAUSE_CLAIMS, "claims"),
+    (PAUSE_GOVERNANCE, "governance"),
+    (PAUSE_ORACLE_UPDATES, "oracle-updates"),
+];
+
+/// Names of the operation groups set in a pause bitfield.
+pub fn pause_groups(flags: u8) -> Vec<&'static str> {
+    PAUSE_GROUPS.iter().filter(|(bit, _)| flags & bit != 0).map(|(_, name)| *name).collect()
+}
+
+pub fn role_name(role: Role) -> &'static str {
+    match role {
+        Role::Crank => "crank",
+        Role::Oracle => "oracle",
+        Role::Pauser => "pauser",
+        Role::Treasury => "treasury",
+    }
+}
+
+fn pause_args(flags: u8) -> Vec<(&'static str, ArgValue)> {
+    let groups = pause_groups(flags).into_iter().map(ArgValue::Label).collect();
+    vec![("flags", ArgValue::Unsigned(flags as u64)), ("groups", ArgValue::List(groups))]
+}
+
+fn keys(keys: &[Pubkey]) -> ArgValue {
+    ArgValue::List(keys.iter().copied().map(ArgValue::Key).collect())
+}
+
+fn rates(rates_bps: &[u16]) -> ArgValue {
+    ArgValue::List(rates_bps.iter().map(|rate| ArgValue::Unsigned(*rate as u64)).collect())
+}
+
+// The action's variant, then its fields
+fn admin_action_args(action: AdminAction) -> Vec<(&'static str, ArgValue)> {
+    match action {
+        AdminAction::UpdateConfig { reward_rate_bps, min_stake_amount, epoch_duration } => vec![
+            ("action", ArgValue::Label("update_config")),
+            ("reward_rate_bps", ArgValue::Unsigned(reward_rate_bps)),
+            ("min_stake_amount", ArgValue::Tokens(min_stake_amount)),
+            ("epoch_duration", ArgValue::Signed(epoch_duration)),
+        ],
+        AdminAction::SetPauseFlags { pause_flags } => {
+            let mut args = vec![("action", ArgValue::Label("set_pause_flags"))];
+            args.extend(pause_args(pause_flags));
+            args
+        }
+        AdminAction::SetRole { role, holder } => vec![
+            ("action", ArgValue::Label("set_role")),
+            ("role", ArgValue::Label(role_name(role))),
+            ("holder", ArgValue::Key(holder)),
+        ],
+        AdminAction::ProposeAdmin { new_admin } => {
+            vec![("action", ArgValue::Label("propose_admin")), ("new_admin", ArgValue::Key(new_admin))]
+        }
+        AdminAction::UpdateParams { params } => vec![
+            ("action", ArgValue::Label("update_params")),
+            ("reward_rate_bps", ArgValue::Unsigned(params.reward_rate_bps)),
+            ("min_stake_amount", ArgValue::Tokens(params.min_stake_amount)),
+            ("epoch_duration", ArgValue::Signed(params.epoch_duration)),
+            ("cooldown_seconds", ArgValue::Signed(params.cooldown_seconds)),
+            ("epoch_emission", ArgValue::Tokens(params.epoch_emission)),
+            ("max_stake_per_agent", ArgValue::Tokens(params.max_stake_per_agent)),
+            ("vesting_seconds", ArgValue::Signed(params.vesting_seconds)),
+            ("vote_unit", ArgValue::Tokens(params.vote_unit)),
+            ("quorum_bps", ArgValue::Unsigned(params.quorum_bps as u64)),
+            ("approval_threshold_bps", ArgValue::Unsigned(params.approval_threshold_bps as u64)),
+        ],
+        AdminAction::InitializeVaults { stake_mint } => {
+            vec![("action", ArgValue::Label("initialize_vaults")), ("stake_mint", ArgValue::Key(stake_mint))]
+        }
+    }
+}
+
+// The action's variant, then its value
+fn proposal_action_args(action: ProposalAction) -> Vec<(&'static str, ArgValue)> {
+    match action {
+        ProposalAction::NoOp => vec![("action", ArgValue::Label("no_op"))],
+        ProposalAction::UpdateRewardRate { bps } => {
+            vec![("action", ArgValue::Label("update_reward_rate")), ("bps", ArgValue::Unsigned(bps))]
+        }
+        ProposalAction::UpdateMinStake { amount } => {
+            vec![("action", ArgValue::Label("update_min_stake")), ("amount", ArgValue::Tokens(amount))]
+        }
+        ProposalAction::UpdateEpochDuration { seconds } => {
+            vec![("action", ArgValue::Label("update_epoch_duration")), ("seconds", ArgValue::Signed(seconds))]
+        }
+    }
+}
+
+fn args<T: AnchorDeserialize>(name: &str, mut body: &[u8]) -> Result<T, ClientError> {
+    T::deserialize(&mut body).map_err(|error| ClientError::Instruction(format!("{} arguments: {}", name, error)))
+}
+
+// An instruction's name, its named accounts, the name of any accounts after
+// those, and its arguments
+type Parts = (&'static str, &'static [&'static str], &'static str, Vec<(&'static str, ArgValue)>);
+
+const REMAINING: &str = "remaining";
+
+fn parts(discriminator: &[u8], body: &[u8]) -> Result<Parts, ClientError> {
+    use ArgValue::*;
+
+    if discriminator == ix::InitializeRewardPool::DISCRIMINATOR {
+        let name = "initialize_reward_pool";
+        let ix::InitializeRewardPool { initial_rewards } = args(name, body)?;
+        let accounts = &["reward_pool", "user", "system_program"];
+        return Ok((name, accounts, REMAINING, vec![("initial_rewards", Tokens(initial_rewards))]));
+    }
+    if discriminator == ix::FundRewardPool::DISCRIMINATOR {
+        let name = "fund_reward_pool";
+        let ix::FundRewardPool { amount } = args(name, body)?;
+        let accounts = &[
+            "platform_config",
+            "reward_pool",
+            "reward_vault",
+            "reward_mint",
+            "funder",
+            "funder_token_account",
+            "token_program",
+        ];
+        return Ok((name, accounts, REMAINING, vec![("amount", Tokens(amount))]));
+    }
+    if discriminator == ix::RegisterAiAgent::DISCRIMINATOR {
+        let name = "register_ai_agent";
+        let ix::RegisterAiAgent { stake_amount } = args(name, body)?;
+        let accounts = &[
+            "platform_config",
+            "ai_agent",
+            "user",
+            "user_token_account",
+            "staking_vault",
+            "token_program",
+            "system_program",
+        ];
+        return Ok((name, accounts, REMAINING, vec![("stake_amount", Tokens(stake_amount))]));
+    }
+    if discriminator == ix::IncreaseStake::DISCRIMINATOR {
+        let name = "increase_stake";
+        let ix::IncreaseStake { amount } = args(name, body)?;
+        let accounts =
+            &["platform_config", "ai_agent", "user", "user_token_account", "staking_vault", "token_program"];
+        return Ok((name, accounts, REMAINING, vec![("amount", Tokens(amount))]));
+    }
+    if discriminator == ix::UnstakeAiAgent::DISCRIMINATOR {
+        let accounts = &[
+            "platform_config",
+            "ai_agent",
+            "pending_withdrawal",
+            "user",
+            "user_token_account",
+            "staking_vault",
+            "stake_mint",
+            "fee_schedule",
+            "treasury",
+            "treasury_vault",
+            "token_program",
+            "system_program",
+        ];
+        return Ok(("unstake_ai_agent", accounts, REMAINING, Vec::new()));
+    }
+    if discriminator == ix::UnstakePartial::DISCRIMINATOR {
+        let name = "unstake_partial";
+        let ix::UnstakePartial { amount } = args(name, body)?;
+        let accounts = &[
+            "platform_config",
+            "ai_agent",
+            "pending_withdrawal",
+            "user",
+            "user_token_account",
+            "staking_vault",
+            "stake_mint",
+            "fee_schedule",
+            "treasury",
+            "treasury_vault",
+            "token_program",
+            "system_program",
+        ];
+        return Ok((name, accounts, REMAINING, vec![("amount", Tokens(amount))]));
+    }
+    if discriminator == ix::EmergencyUnstake::DISCRIMINATOR {
+        let accounts = &[
+            "platform_config",
+            "ai_agent",
+            "user",
+            "user_token_account",
+            "staking_vault",
+            "stake_mint",
+            "reward_pool",
+            "fee_schedule",
+            "reward_vault",
+            "token_program",
+        ];
+        return Ok(("emergency_unstake", accounts, REMAINING, Vec::new()));
+    }
+    if discriminator == ix::RequestUnstake::DISCRIMINATOR {
+        let name = "request_unstake";
+        let ix::RequestUnstake { amount } = args(name, body)?;
+        let accounts = &["platform_config", "ai_agent", "pending_withdrawal", "user", "system_program"];
+        return Ok((name, accounts, REMAINING, vec![("amount", Tokens(amount))]));
+    }
+    if discriminator == ix::ClaimUnstake::DISCRIMINATOR {
+        let accounts = &[
+            "platform_config",
+            "ai_agent",
+            "pending_withdrawal",
+            "user",
+            "user_token_account",
+            "staking_vault",
+            "stake_mint",
+            "fee_schedule",
+            "treasury",
+            "treasury_vault",
+            "token_program",
+            "system_program",
+        ];
+        return Ok(("claim_unstake", accounts, REMAINING, Vec::new()));
+    }
+    if discriminator == ix::CloseAgent::DISCRIMINATOR {
+        let accounts = &["platform_config", "ai_agent", "pending_withdrawal", "owner"];
+        return Ok(("close_agent", accounts, REMAINING, Vec::new()));
+    }
+    if discriminator == ix::DistributeRewards::DISCRIMINATOR {
+        let accounts = &["platform_config", "reward_pool", "ai_agent", "crank"];
+        return Ok(("distribute_rewards", accounts, REMAINING, Vec::new()));
+    }
+    if discriminator == ix::DistributeRewardsBatch::DISCRIMINATOR {
+        let name = "distribute_rewards_batch";
+        let ix::DistributeRewardsBatch { close_epoch } = args(name, body)?;
+        let accounts = &["platform_config", "reward_pool", "crank"];
+        return Ok((name, accounts, "ai_agent", vec![("close_epoch", Bool(close_epoch))]));
+    }
+    if discriminator == ix::ClaimRewards::DISCRIMINATOR {
+        let accounts = &[
+            "platform_config",
+            "ai_agent",
+            "reward_pool",
+            "user",
+            "user_token_account",
+            "reward_vault",
+            "reward_mint",
+            "fee_schedule",
+            "treasury",
+            "treasury_vault",
+            "reward_vesting",
+            "token_program",
+            "system_program",
+        ];
+        return Ok(("claim_rewards", accounts, REMAINING, Vec::new()));
+    }
+    if discriminator == ix::ReleaseVested::DISCRIMINATOR {
+        let accounts = &[
+            "platform_config",
+            "reward_vesting",
+            "reward_pool",
+            "user",
+            "user_token_account",
+            "reward_vault",
+            "reward_mint",
+            "token_program",
+        ];
+        return Ok(("release_vested", accounts, REMAINING, Vec::new()));
+    }
+    if discriminator == ix::CreateProposal::DISCRIMINATOR {
+        let name = "create_proposal";
+        let ix::CreateProposal { description, duration } = args(name, body)?;
+        let accounts = &["platform_config", "proposal", "user", "system_program"];
+        let fields = vec![("description", Text(description)), ("duration", Signed(duration))];
+        return Ok((name, accounts, REMAINING, fields));
+    }
+    if discriminator == ix::VoteProposal::DISCRIMINATOR {
+        let name = "vote_proposal";
+        let ix::VoteProposal { in_favor } = args(name, body)?;
+        let accounts = &["platform_config", "proposal", "ai_agent", "user"];
+        return Ok((name, accounts, REMAINING, vec![("in_favor", Bool(in_favor))]));
+    }
+    if discriminator == ix::InitializePlatform::DISCRIMINATOR {
+        let name = "initialize_platform";
+        let init: ix::InitializePlatform = args(name, body)?;
+        let fields = vec![
+            ("reward_rate_bps", Unsigned(init.reward_rate_bps)),
+            ("min_stake_amount", Tokens(init.min_stake_amount)),
+            ("epoch_duration", Signed(init.epoch_duration)),
+            ("cooldown_seconds", Signed(init.cooldown_seconds)),
+            ("epoch_emission", Tokens(init.epoch_emission)),
+        ];
+        return Ok((name, &["platform_config", "admin", "system_program"], REMAINING, fields));
+    }
+    if discriminator == ix::InitializeVaults::DISCRIMINATOR {
+        let accounts = &[
+            "platform_config",
+            "reward_pool",
+            "stake_mint",
+            "staking_vault",
+            "reward_vault",
+            "admin",
+            "token_program",
+            "system_program",
+        ];
+        return Ok(("initialize_vaults", accounts, REMAINING, Vec::new()));
+    }
+    if discriminator == ix::UpdatePlatformConfig::DISCRIMINATOR {
+        let name = "update_platform_config";
+        let ix::UpdatePlatformConfig { params, update_metadata } = args(name, body)?;
+        let fields = vec![
+            ("reward_rate_bps", Unsigned(params.reward_rate_bps)),
+            ("min_stake_amount", Tokens(params.min_stake_amount)),
+            ("epoch_duration", Signed(params.epoch_duration)),
+            ("cooldown_seconds", Signed(params.cooldown_seconds)),
+            ("epoch_emission", Tokens(params.epoch_emission)),
+            ("max_stake_per_agent", Tokens(params.max_stake_per_agent)),
+            ("vesting_seconds", Signed(params.vesting_seconds)),
+            ("vote_unit", Tokens(params.vote_unit)),
+            ("quorum_bps", Unsigned(params.quorum_bps as u64)),
+            ("approval_threshold_bps", Unsigned(params.approval_threshold_bps as u64)),
+            ("update_metadata", Text(update_metadata)),
+        ];
+        return Ok((name, &["platform_config", "admin"], "governance_realm", fields));
+    }
+    if discriminator == ix::MigratePlatformConfig::DISCRIMINATOR {
+        let accounts = &["platform_config", "admin", "system_program"];
+        return Ok(("migrate_platform_config", accounts, REMAINING, Vec::new()));
+    }
+    if discriminator == ix::MigrateAccount::DISCRIMINATOR {
+        return Ok(("migrate_account", &["account", "payer", "system_program"], REMAINING, Vec::new()));
+    }
+    if discriminator == ix::MigrateAgentV1::DISCRIMINATOR {
+        let accounts = &[
+            "platform_config",
+            "legacy_agent",
+            "ai_agent",
+            "user_stake",
+            "stake_index",
+            "staking_vault",
+            "platform_vault",
+            "stake_mint",
+            "admin",
+            "token_program",
+            "system_program",
+        ];
+        return Ok(("migrate_agent_v1", accounts, REMAINING, Vec::new()));
+    }
+    if discriminator == ix::GetVersion::DISCRIMINATOR {
+        return Ok(("get_version", &[], REMAINING, Vec::new()));
+    }
+    if discriminator == ix::CreateGovernanceProposal::DISCRIMINATOR {
+        let name = "create_governance_proposal";
+        let create: ix::CreateGovernanceProposal = args(name, body)?;
+        let mut fields = vec![
+            ("title", Text(create.title)),
+            ("description", Text(create.description)),
+            ("voting_duration", Unsigned(create.voting_duration)),
+            ("options", List(create.options.into_iter().map(Text).collect())),
+        ];
+        fields.extend(proposal_action_args(create.action));
+        let accounts = &["creator", "platform_config", "proposal", "system_program"];
+        return Ok((name, accounts, REMAINING, fields));
+    }
+    if discriminator == ix::CastVote::DISCRIMINATOR {
+        let name = "cast_vote";
+        let ix::CastVote { proposal_id, vote_option } = args(name, body)?;
+        let fields = vec![("proposal_id", Unsigned(proposal_id)), ("vote_option", Unsigned(vote_option as u64))];
+        let accounts = &["voter", "platform_config", "proposal", "user_stake", "vote_record", "system_program"];
+        return Ok((name, accounts, REMAINING, fields));
+    }
+    if discriminator == ix::FinalizeProposal::DISCRIMINATOR {
+        let name = "finalize_proposal";
+        let ix::FinalizeProposal { proposal_id } = args(name, body)?;
+        let accounts = &["caller", "platform_config", "proposal", "system_program"];
+        return Ok((name, accounts, REMAINING, vec![("proposal_id", Unsigned(proposal_id))]));
+    }
+    if discriminator == ix::ExecuteProposal::DISCRIMINATOR {
+        let accounts = &["executor", "platform_config", "proposal"];
+        return Ok(("execute_proposal", accounts, REMAINING, Vec::new()));
+    }
+    if discriminator == ix::SetGovernanceEnabled::DISCRIMINATOR {
+        let name = "set_governance_enabled";
+        let ix::SetGovernanceEnabled { enabled } = args(name, body)?;
+        return Ok((name, &["platform_config", "admin"], "proposal", vec![("enabled", Bool(enabled))]));
+    }
+    if discriminator == ix::ProposeAdmin::DISCRIMINATOR {
+        let name = "propose_admin";
+        let ix::ProposeAdmin { new_admin } = args(name, body)?;
+        return Ok((name, &["platform_config", "admin"], REMAINING, vec![("new_admin", Key(new_admin))]));
+    }
+    if discriminator == ix::AcceptAdmin::DISCRIMINATOR {
+        return Ok(("accept_admin", &["platform_config", "new_admin"], REMAINING, Vec::new()));
+    }
+    if discriminator == ix::CancelAdminTransfer::DISCRIMINATOR {
+        return Ok(("cancel_admin_transfer", &["platform_config", "admin"], REMAINING, Vec::new()));
+    }
+    if discriminator == ix::InitializeTreasury::DISCRIMINATOR {
+        let name = "initialize_treasury";
+        let ix::InitializeTreasury { governance } = args(name, body)?;
+        let accounts = &["platform_config", "treasury", "authority", "system_program"];
+        return Ok((name, accounts, REMAINING, vec![("governance", Key(governance))]));
+    }
+    if discriminator == ix::InitializeFeeSchedule::DISCRIMINATOR {
+        let name = "initialize_fee_schedule";
+        let ix::InitializeFeeSchedule { governance, rates_bps } = args(name, body)?;
+        let accounts = &["platform_config", "fee_schedule", "admin", "system_program"];
+        return Ok((name, accounts, REMAINING, vec![("governance", Key(governance)), ("rates_bps", rates(&rates_bps))]));
+    }
+    if discriminator == ix::UpdateFeeSchedule::DISCRIMINATOR {
+        let name = "update_fee_schedule";
+        let ix::UpdateFeeSchedule { rates_bps } = args(name, body)?;
+        let accounts = &["platform_config", "fee_schedule", "authority"];
+        return Ok((name, accounts, REMAINING, vec![("rates_bps", rates(&rates_bps))]));
+    }
+    if discriminator == ix::TreasuryBalance::DISCRIMINATOR {
+        return Ok(("treasury_balance", &["treasury", "treasury_vault"], REMAINING, Vec::new()));
+    }
+    if discriminator == ix::TreasuryTransfer::DISCRIMINATOR {
+        let name = "treasury_transfer";
+        let ix::TreasuryTransfer { amount } = args(name, body)?;
+        let accounts = &["treasury", "mint", "treasury_vault", "recipient", "governance", "token_program"];
+        return Ok((name, accounts, "governance_realm", vec![("amount", Tokens(amount))]));
+    }
+    if discriminator == ix::SetPauseFlags::DISCRIMINATOR {
+        let name = "set_pause_flags";
+        let ix::SetPauseFlags { flags } = args(name, body)?;
+        return Ok((name, &["platform_config", "authority"], REMAINING, pause_args(flags)));
+    }
+    if discriminator == ix::ClearPauseFlags::DISCRIMINATOR {
+        let name = "clear_pause_flags";
+        let ix::ClearPauseFlags { flags } = args(name, body)?;
+        return Ok((name, &["platform_config", "admin"], REMAINING, pause_args(flags)));
+    }
+    if discriminator == ix::SetRole::DISCRIMINATOR {
+        let name = "set_role";
+        let ix::SetRole { role, holder } = args(name, body)?;
+        let fields = vec![("role", Label(role_name(role))), ("holder", Key(holder))];
+        return Ok((name, &["platform_config", "admin"], "governance_realm", fields));
+    }
+    if discriminator == ix::RenounceRole::DISCRIMINATOR {
+        let name = "renounce_role";
+        let ix::RenounceRole { role } = args(name, body)?;
+        return Ok((name, &["platform_config", "holder"], REMAINING, vec![("role", Label(role_name(role)))]));
+    }
+    if discriminator == ix::UpdatePerformanceScore::DISCRIMINATOR {
+        let name = "update_performance_score";
+        let ix::UpdatePerformanceScore { score } = args(name, body)?;
+        let accounts = &["platform_config", "ai_agent", "oracle"];
+        return Ok((name, accounts, REMAINING, vec![("score", Unsigned(score))]));
+    }
+    if discriminator == ix::RegisterAgent::DISCRIMINATOR {
+        let name = "register_agent";
+        let ix::RegisterAgent { agent_id, name: agent_name, description } = args(name, body)?;
+        let fields = vec![
+            ("agent_id", Unsigned(agent_id)),
+            ("name", Text(agent_name)),
+            ("description", Text(description)),
+        ];
+        return Ok((name, &["ai_agent", "owner", "system_program"], REMAINING, fields));
+    }
+    if discriminator == ix::UpdateAgentMetadata::DISCRIMINATOR {
+        let name = "update_agent_metadata";
+        let ix::UpdateAgentMetadata { agent_id, name: agent_name, description } = args(name, body)?;
+        let fields = vec![
+            ("agent_id", Unsigned(agent_id)),
+            ("name", Text(agent_name)),
+            ("description", Text(description)),
+        ];
+        return Ok((name, &["ai_agent", "owner"], REMAINING, fields));
+    }
+    if discriminator == ix::StakeOnAgent::DISCRIMINATOR {
+        let name = "stake_on_agent";
+        let ix::StakeOnAgent { agent_id, amount, beneficiary } = args(name, body)?;
+        let accounts = &[
+            "platform_config",
+            "ai_agent",
+            "user_stake",
+            "stake_index",
+            "user",
+            "user_token_account",
+            "platform_vault",
+            "token_program",
+            "system_program",
+        ];
+        let fields =
+            vec![("agent_id", Unsigned(agent_id)), ("amount", Tokens(amount)), ("beneficiary", Key(beneficiary))];
+        return Ok((name, accounts, REMAINING, fields));
+    }
+    if discriminator == ix::UnstakeFromAgent::DISCRIMINATOR {
+        let name = "unstake_from_agent";
+        let ix::UnstakeFromAgent { agent_id, amount } = args(name, body)?;
+        let accounts = &[
+            "platform_config",
+            "ai_agent",
+            "user_stake",
+            "stake_index",
+            "user",
+            "user_token_account",
+            "platform_vault",
+            "stake_mint",
+            "fee_schedule",
+            "treasury",
+            "treasury_vault",
+            "token_program",
+            "system_program",
+        ];
+        return Ok((name, accounts, REMAINING, vec![("agent_id", Unsigned(agent_id)), ("amount", Tokens(amount))]));
+    }
+    if discriminator == ix::ClaimStakeRewards::DISCRIMINATOR {
+        let accounts = &[
+            "platform_config",
+            "user_stake",
+            "user",
+            "user_token_account",
+            "platform_vault",
+            "reward_mint",
+            "fee_schedule",
+            "treasury",
+            "treasury_vault",
+            "token_program",
+            "system_program",
+        ];
+        return Ok(("claim_stake_rewards", accounts, REMAINING, Vec::new()));
+    }
+    if discriminator == ix::CreateMetadata::DISCRIMINATOR {
+        let name = "create_metadata";
+        let ix::CreateMetadata { entity_id, data } = args(name, body)?;
+        let fields = vec![("entity_id", Unsigned(entity_id)), ("data", Text(data))];
+        return Ok((name, &["metadata", "authority", "system_program"], REMAINING, fields));
+    }
+    if discriminator == ix::UpdateMetadata::DISCRIMINATOR {
+        let name = "update_metadata";
+        let ix::UpdateMetadata { entity_id, data } = args(name, body)?;
+        let fields = vec![("entity_id", Unsigned(entity_id)), ("data", Text(data))];
+        return Ok((name, &["metadata", "authority", "system_program"], REMAINING, fields));
+    }
+    if discriminator == ix::InitializeAdminMultisig::DISCRIMINATOR {
+        let name = "initialize_admin_multisig";
+        let ix::InitializeAdminMultisig { signers, threshold } = args(name, body)?;
+        let fields = vec![("signers", keys(&signers)), ("threshold", Unsigned(threshold as u64))];
+        let accounts = &["platform_config", "admin_multisig", "admin", "system_program"];
+        return Ok((name, accounts, REMAINING, fields));
+    }
+    if discriminator == ix::ProposeAdminAction::DISCRIMINATOR {
+        let name = "propose_admin_action";
+        let ix::ProposeAdminAction { action } = args(name, body)?;
+        let accounts = &["admin_multisig", "proposal", "proposer", "system_program"];
+        return Ok((name, accounts, REMAINING, admin_action_args(action)));
+    }
+    if discriminator == ix::ApproveAdminAction::DISCRIMINATOR {
+        let accounts = &["admin_multisig", "proposal", "signer"];
+        return Ok(("approve_admin_action", accounts, REMAINING, Vec::new()));
+    }
+    if discriminator == ix::ExecuteAdminAction::DISCRIMINATOR {
+        let accounts = &["platform_config", "admin_multisig", "proposal", "executor"];
+        return Ok(("execute_admin_action", accounts, REMAINING, Vec::new()));
+    }
+    if discriminator == ix::ExecuteInitializeVaults::DISCRIMINATOR {
+        let accounts = &[
+            "platform_config",
+            "admin_multisig",
+            "proposal",
+            "reward_pool",
+            "stake_mint",
+            "staking_vault",
+            "reward_vault",
+            "executor",
+            "token_program",
+            "system_program",
+        ];
+        return Ok(("execute_initialize_vaults", accounts, REMAINING, Vec::new()));
+    }
+    if discriminator == ix::SetGovernanceRealm::DISCRIMINATOR {
+        let name = "set_governance_realm";
+        let ix::SetGovernanceRealm { realm, governance } = args(name, body)?;
+        let accounts = &["platform_config", "governance_realm", "governance", "admin", "system_program"];
+        return Ok((name, accounts, REMAINING, vec![("realm", Key(realm)), ("governance", Key(governance))]));
+    }
+    Err(ClientError::Instruction(format!("unknown discriminator {:02x?}", discriminator)))
+}
+
+/// Decodes one of the program's instructions.
+pub fn decode_instruction(instruction: &Instruction) -> Result<DecodedInstruction, ClientError> {
+    if instruction.program_id != ontora_ai::ID {
+        return Err(ClientError::Instruction(format!("not an Ontora AI instruction: {}", instruction.program_id)));
+    }
+    let discriminator = instruction
+        .data
+        .get(..8)
+        .ok_or_else(|| ClientError::Instruction("the data is shorter than a discriminator".to_string()))?;
+    let (name, accounts, remaining, args) = parts(discriminator, &instruction.data[8..])?;
+    if instruction.accounts.len() < accounts.len() {
+        return Err(ClientError::Instruction(format!(
+            "{} takes {} accounts, the instruction has {}",
+            name,
+            accounts.len(),
+            instruction.accounts.len()
+        )));
+    }
+    let names = accounts.iter().copied().chain(std::iter::repeat(remaining));
+    Ok(DecodedInstruction { name, args, accounts: names.zip(instruction.accounts.iter().cloned()).collect() })
+}
+
+/// A bincode-serialized transaction, as base64 text.
+pub fn parse_transaction(base64: &str) -> Result<VersionedTransaction, ClientError> {
+    let bytes = STANDARD
+        .decode(base64.trim())
+        .map_err(|error| ClientError::Instruction(format!("the transaction is not base64: {}", error)))?;
+    bincode::deserialize(&bytes)
+        .map_err(|error| ClientError::Instruction(format!("the bytes are not a transaction: {}", error)))
+}
+
+// Whether the account at `index` of the message's static keys is writable
+fn static_writable(header: &MessageHeader, index: usize, static_keys: usize) -> bool {
+    let signed = header.num_required_signatures as usize;
+    if index < signed {
+        index < signed - header.num_readonly_signed_accounts as usize
+    } else {
+        index < static_keys - header.num_readonly_unsigned_accounts as usize
+    }
+}
+
+/// A message's instructions with their accounts resolved: the static keys,
+/// then the addresses loaded from `lookup_tables`, writable ones first, as the
+/// runtime orders them.
+pub fn message_instructions(
+    message: &VersionedMessage,
+    lookup_tables: &[AddressLookupTableAccount],
+) -> Result<Vec<Instruction>, ClientError> {
+    let header = message.header();
+    let static_keys = message.static_account_keys();
+    let mut keys: Vec<AccountMeta> = static_keys
+        .iter()
+        .enumerate()
+        .map(|(index, key)| AccountMeta {
+            pubkey: *key,
+            is_signer: index < header.num_required_signatures as usize,
+            is_writable: static_writable(header, index, static_keys.len()),
+        })
+        .collect();
+    let lookups = message.address_table_lookups().unwrap_or_default();
+    let mut readonly = Vec::new();
+    for lookup in lookups {
+        let table = lookup_tables
+            .iter()
+            .find(|table| table.key == lookup.account_key)
+            .ok_or_else(|| ClientError::Instruction(format!("lookup table {} was not given", lookup.account_key)))?;
+        let load = |index: &u8| {
+            table.addresses.get(*index as usize).copied().ok_or_else(|| {
+                ClientError::Instruction(format!("lookup table {} has no address {}", table.key, index))
+            })
+        };
+        for index in &lookup.writable_indexes {
+            keys.push(AccountMeta::new(load(index)?, false));
+        }
+        for index in &lookup.readonly_indexes {
+            readonly.push(AccountMeta::new_readonly(load(index)?, false));
+        }
+    }
+    keys.extend(readonly);
+
+    let key = |index: u8| {
+        keys.get(index as usize)
+            .cloned()
+            .ok_or_else(|| ClientError::Instruction(format!("the message has no account {}", index)))
+    };
+    message
+        .instructions()
+        .iter()
+        .map(|compiled| {
+            Ok(Instruction {
+                program_id: key(compiled.program_id_index)?.pubkey,
+                accounts: compiled.accounts.iter().map(|index| key(*index)).collect::<Result<_, _>>()?,
+                data: compiled.data.clone(),
+            })
+        })
+        .collect()
+}
+
+/// Decodes every instruction of `transaction`, resolving accounts it loads
+/// through `lookup_tables`.
+pub fn decode_transaction(
+    transaction: &VersionedTransaction,
+    lookup_tables: &[AddressLookupTableAccount],
+) -> Result<DecodedTransaction, ClientError> {
+    let fee_payer = *transaction
+        .message
+        .static_account_keys()
+        .first()
+        .ok_or_else(|| ClientError::Instruction("the message has no accounts".to_string()))?;
+    let instructions = message_instructions(&transaction.message, lookup_tables)?
+        .into_iter()
+        .map(|instruction| {
+            if instruction.program_id != ontora_ai::ID {
+                return ReviewedInstruction::Other(instruction);
+            }
+            match decode_instruction(&instruction) {
+                Ok(decoded) => ReviewedInstruction::Decoded(decoded),
+                Err(ClientError::Instruction(reason)) => ReviewedInstruction::Undecoded { instruction, reason },
+                Err(error) => ReviewedInstruction::Undecoded { reason: error.to_string(), instruction },
+            }
+        })
+        .collect();
+    Ok(DecodedTransaction { fee_payer, instructions })
+}
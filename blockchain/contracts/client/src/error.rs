@@ -0,0 +1,31 @@
+use anchor_lang::solana_program::pubkey::Pubkey;
+use thiserror::Error;
+
+/// Errors from fetching and decoding program accounts and sending transactions.
+#[derive(Debug, Error)]
+pub enum ClientError {
+    #[error("RPC request failed: {0}")]
+    Rpc(#[from] solana_client::client_error::ClientError),
+    #[error("account {0} does not exist")]
+    AccountNotFound(Pubkey),
+    #[error("account {0} is owned by {1}, not the Ontora program")]
+    WrongOwner(Pubkey, Pubkey),
+    #[error("account {0} could not be decoded: {1}")]
+    Decode(Pubkey, String),
+    #[error("cannot decode instruction: {0}")]
+    Instruction(String),
+    #[error("transaction could not be built: {0}")]
+    Build(String),
+    #[error("signing failed: {0}")]
+    Signer(String),
+    #[error("simulation failed: {0}")]
+    Simulation(String),
+    #[error("block engine request failed: {0}")]
+    Bundle(String),
+    #[error("account subscription failed: {0}")]
+    Subscription(String),
+    #[error("airdrop list is invalid: {0}")]
+    Airdrop(String),
+    #[error("{0} overflows u64")]
+    Overflow(&'static str),
+}
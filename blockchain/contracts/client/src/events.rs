@@ -0,0 +1,196 @@
+//! Typed events from transaction logs. The program emits each event as a
+//! base64 `Program data:` line holding its Anchor discriminator and Borsh
+//! body; these decode them into `OntoraEvent` by discriminator.
+//!
+//!     let events = decode_rpc_logs(&notification.value);
+//!     let events = decode_transaction_events(&rpc.get_transaction(&signature, encoding)?);
+
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::{AnchorDeserialize, Discriminator};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use ontora_ai::events::*;
+use solana_client::rpc_response::RpcTransactionLogs;
+use solana_transaction_status::option_serializer::OptionSerializer;
+use solana_transaction_status::EncodedConfirmedTransactionWithStatusMeta;
+use thiserror::Error;
+
+/// Errors from decoding the program's events out of transaction logs.
+#[derive(Debug, Error, PartialEq)]
+pub enum DecodeError {
+    #[error("program data is not valid base64: {0}")]
+    Base64(String),
+    #[error("{name} event could not be decoded: {reason}")]
+    Event { name: &'static str, reason: String },
+}
+
+/// Declares `OntoraEvent` with one variant per event in events.rs, and decodes
+/// them by their Anchor discriminator.
+macro_rules! ontora_events {
+    ($($event:ident,)*) => {
+        /// An event emitted by the program.
+        #[derive(Clone, Debug, PartialEq)]
+        pub enum OntoraEvent {
+            $($event($event),)*
+        }
+
+        impl OntoraEvent {
+            /// Names of every event, in events.rs order.
+            pub const NAMES: &'static [&'static str] = &[$(stringify!($event),)*];
+
+            /// Discriminators of every event, in the order of `NAMES`.
+            pub const DISCRIMINATORS: &'static [[u8; 8]] = &[$($event::DISCRIMINATOR,)*];
+
+            /// Name of the event struct this variant holds.
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $(OntoraEvent::$event(_) => stringify!($event),)*
+                }
+            }
+
+            /// The `Program data` bytes the program logs for this event.
+            pub fn data(&self) -> Vec<u8> {
+                match self {
+                    $(OntoraEvent::$event(event) => anchor_lang::Event::data(event),)*
+                }
+            }
+
+            /// Decodes one event from its `Program data` bytes: the 8-byte
+            /// discriminator followed by the Borsh body. Trailing bytes are
+            /// ignored, so fields appended by a newer schema version do not break
+            /// an older indexer. `None` for a discriminator no event here carries.
+            pub fn decode(data: &[u8]) -> Option<Result<Self, DecodeError>> {
+                let discriminator = data.get(..8)?;
+                let mut body = &data[8..];
+                $(
+                    if discriminator == &$event::discriminator()[..] {
+                        let event = <$event as AnchorDeserialize>::deserialize(&mut body)
+                            .map(OntoraEvent::$event)
+                            .map_err(|error| DecodeError::Event {
+                                name: stringify!($event),
+                                reason: error.to_string(),
+                            });
+                        return Some(event);
+                    }
+                )*
+                None
+            }
+        }
+    };
+}
+
+ontora_events! {
+    PlatformInitialized,
+    PlatformUpdated,
+    AgentRegistered,
+    AgentUpdated,
+    StakeDeposited,
+    StakeWithdrawn,
+    RewardClaimed,
+    ProposalCreated,
+    VoteCast,
+    ProposalFinalized,
+    ProposalExecuted,
+    RewardDistributed,
+    AdminTransferProposed,
+    AdminTransferAccepted,
+    AdminTransferCancelled,
+    AdminMultisigInitialized,
+    AdminActionProposed,
+    AdminActionApproved,
+    AdminActionExecuted,
+    PlatformConfigMigrated,
+    AccountMigrated,
+    RoleUpdated,
+    PerformanceScoreUpdated,
+    GovernanceSuspended,
+    GovernanceResumed,
+    PauseFlagsUpdated,
+    FeeScheduleUpdated,
+    TreasuryDeposit,
+    TreasuryWithdrawal,
+    GovernanceRealmSet,
+    RewardPoolFunded,
+    PenaltyCharged,
+    UnstakeRequested,
+}
+
+// The encoded `Program data` payloads written while `program_id` was the
+// innermost running program, in log order
+fn program_data<'a>(program_id: &Pubkey, logs: &'a [String]) -> Vec<&'a str> {
+    let program = program_id.to_string();
+    let mut invocations: Vec<&str> = Vec::new();
+    let mut payloads = Vec::new();
+    for line in logs {
+        let Some(rest) = line.strip_prefix("Program ") else {
+            continue;
+        };
+        if let Some(data) = rest.strip_prefix("data: ") {
+            if invocations.last() == Some(&program.as_str()) {
+                payloads.push(data.split_whitespace().next().unwrap_or_default());
+            }
+            continue;
+        }
+        if rest.starts_with("log: ") || rest.starts_with("return: ") {
+            continue;
+        }
+        let mut words = rest.split_whitespace();
+        match (words.next(), words.next()) {
+            (Some(id), Some("invoke")) => invocations.push(id),
+            (Some(_), Some("success" | "failed:")) => {
+                invocations.pop();
+            }
+            _ => {}
+        }
+    }
+    payloads
+}
+
+/// Decodes, in order, the events `program_id` emitted in one transaction's logs.
+///
+/// Only `Program data` lines written while `program_id` is the innermost
+/// running program count, so a program calling into it (or called by it) cannot
+/// pass off look-alike data as its events. Unknown discriminators are skipped;
+/// malformed data is an error, for callers that must not miss an event.
+pub fn parse_logs(program_id: &Pubkey, logs: &[String]) -> Result<Vec<OntoraEvent>, DecodeError> {
+    let mut events = Vec::new();
+    for encoded in program_data(program_id, logs) {
+        let bytes = STANDARD.decode(encoded).map_err(|error| DecodeError::Base64(error.to_string()))?;
+        if let Some(event) = OntoraEvent::decode(&bytes) {
+            events.push(event?);
+        }
+    }
+    Ok(events)
+}
+
+/// Decodes, in order, the events the program emitted in one transaction's
+/// logs, skipping what can't be decoded: unknown discriminators, and lines
+/// the RPC truncated or cut short.
+pub fn decode_events(logs: &[String]) -> Vec<OntoraEvent> {
+    program_data(&ontora_ai::ID, logs)
+        .into_iter()
+        .filter_map(|encoded| STANDARD.decode(encoded).ok())
+        .filter_map(|bytes| OntoraEvent::decode(&bytes)?.ok())
+        .collect()
+}
+
+/// The events of a `logsSubscribe` notification. A failed transaction's
+/// events never took effect, so it has none.
+pub fn decode_rpc_logs(logs: &RpcTransactionLogs) -> Vec<OntoraEvent> {
+    match logs.err {
+        Some(_) => Vec::new(),
+        None => decode_events(&logs.logs),
+    }
+}
+
+/// The events of a confirmed transaction, as `getTransaction` returns it.
+/// None if it failed or was fetched without its log messages.
+pub fn decode_transaction_events(transaction: &EncodedConfirmedTransactionWithStatusMeta) -> Vec<OntoraEvent> {
+    match &transaction.transaction.meta {
+        Some(meta) if meta.err.is_none() => match &meta.log_messages {
+            OptionSerializer::Some(logs) => decode_events(logs),
+            _ => Vec::new(),
+        },
+        _ => Vec::new(),
+    }
+}
@@ -0,0 +1,195 @@
+//! A wallet's activity as one timeline: its stakes, unstakes, reward claims
+//! and votes in the order they happened, each with the wallet's stake and
+//! claimed rewards after it. Events can come from any mix of sources (the
+//! indexer database, the wallet's transactions); one emitted by the same
+//! transaction at the same position is kept once.
+//!
+//! Events missing from the sources show up as gaps: an unstake that takes
+//! more from an agent than the timeline had staked on it, or a final stake
+//! that differs from the wallet's stake index entries on chain.
+//!
+//!     let timeline = Timeline::build(wallet_events(&wallet, &signature, &events));
+//!     let gaps = timeline.reconcile(&on_chain_stakes(&list_stakes_by_user(&rpc, &wallet)?));
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use anchor_lang::solana_program::pubkey::Pubkey;
+use ontora_ai::state::StakeIndexEntry;
+use solana_sdk::signature::Signature;
+
+use crate::events::OntoraEvent;
+
+/// What the wallet did.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Activity {
+    Stake { agent_id: u64, amount: u64 },
+    Unstake { agent_id: u64, amount: u64 },
+    Claim { agent_id: u64, amount: u64 },
+    Vote { proposal_id: u64, option: u8, weight: u64 },
+}
+
+impl Activity {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Activity::Stake { .. } => "stake",
+            Activity::Unstake { .. } => "unstake",
+            Activity::Claim { .. } => "claim",
+            Activity::Vote { .. } => "vote",
+        }
+    }
+}
+
+/// One of the wallet's events and where it was emitted.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WalletEvent {
+    pub signature: Signature,
+    /// Position of the event among those the transaction emitted.
+    pub index: usize,
+    pub timestamp: i64,
+    pub activity: Activity,
+}
+
+/// The events of one transaction that concern `wallet`.
+pub fn wallet_events(wallet: &Pubkey, signature: &Signature, events: &[OntoraEvent]) -> Vec<WalletEvent> {
+    let mut found = Vec::new();
+    for (index, event) in events.iter().enumerate() {
+        let (timestamp, activity) = match event {
+            OntoraEvent::StakeDeposited(event) if event.user == *wallet => {
+                (event.timestamp, Activity::Stake { agent_id: event.agent_id, amount: event.amount })
+            }
+            OntoraEvent::StakeWithdrawn(event) if event.user == *wallet => {
+                (event.timestamp, Activity::Unstake { agent_id: event.agent_id, amount: event.amount })
+            }
+            OntoraEvent::RewardClaimed(event) if event.user == *wallet => {
+                (event.timestamp, Activity::Claim { agent_id: event.agent_id, amount: event.reward_amount })
+            }
+            OntoraEvent::VoteCast(event) if event.voter == *wallet => {
+                let (proposal_id, option, weight) = (event.proposal_id, event.vote_option, event.vote_weight);
+                (event.timestamp, Activity::Vote { proposal_id, option, weight })
+            }
+            _ => continue,
+        };
+        found.push(WalletEvent { signature: *signature, index, timestamp, activity });
+    }
+    found
+}
+
+/// Where the events and the chain disagree.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Gap {
+    /// An unstake took `missing` more from the agent than the events before
+    /// it had staked there, so deposits are missing.
+    MissingDeposits { agent_id: u64, missing: u64 },
+    /// The events leave `timeline` staked on the agent; the chain holds
+    /// `on_chain`.
+    Unreconciled { agent_id: u64, timeline: u64, on_chain: u64 },
+}
+
+impl fmt::Display for Gap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Gap::MissingDeposits { agent_id, missing } => {
+                write!(f, "{} more unstaked from agent {} than the events staked", missing, agent_id)
+            }
+            Gap::Unreconciled { agent_id, timeline, on_chain } => {
+                write!(f, "agent {}: the events leave {} staked, the chain holds {}", agent_id, timeline, on_chain)
+            }
+        }
+    }
+}
+
+/// An event with the wallet's balances after it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TimelineEntry {
+    pub event: WalletEvent,
+    /// Staked across every agent.
+    pub staked: u64,
+    /// Rewards claimed so far.
+    pub claimed: u64,
+    pub gap: Option<Gap>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Timeline {
+    /// Oldest first.
+    pub entries: Vec<TimelineEntry>,
+    /// Staked on each agent after the last entry; agents unstaked in full are
+    /// left out.
+    pub staked_by_agent: BTreeMap<u64, u64>,
+}
+
+impl Timeline {
+    /// Orders `events`, given oldest first within each source, by time and
+    /// runs the balances over them. Events of the same time keep the order
+    /// they were given in.
+    pub fn build(events: impl IntoIterator<Item = WalletEvent>) -> Self {
+        let mut seen = BTreeSet::new();
+        let mut events: Vec<WalletEvent> =
+            events.into_iter().filter(|event| seen.insert((event.signature, event.index))).collect();
+        events.sort_by_key(|event| event.timestamp);
+
+        let mut timeline = Timeline::default();
+        let mut claimed = 0u64;
+        for event in events {
+            let mut gap = None;
+            match event.activity {
+                Activity::Stake { agent_id, amount } => {
+                    let staked = timeline.staked_by_agent.entry(agent_id).or_default();
+                    *staked = staked.saturating_add(amount);
+                }
+                Activity::Unstake { agent_id, amount } => {
+                    let staked = timeline.staked_by_agent.get(&agent_id).copied().unwrap_or(0);
+                    if amount > staked {
+                        gap = Some(Gap::MissingDeposits { agent_id, missing: amount - staked });
+                    }
+                    match staked.saturating_sub(amount) {
+                        0 => timeline.staked_by_agent.remove(&agent_id),
+                        left => timeline.staked_by_agent.insert(agent_id, left),
+                    };
+                }
+                Activity::Claim { amount, .. } => claimed = claimed.saturating_add(amount),
+                Activity::Vote { .. } => {}
+            }
+            let staked = timeline.staked();
+            timeline.entries.push(TimelineEntry { event, staked, claimed, gap });
+        }
+        timeline
+    }
+
+    pub fn staked(&self) -> u64 {
+        self.staked_by_agent.values().fold(0u64, |total, staked| total.saturating_add(*staked))
+    }
+
+    pub fn claimed(&self) -> u64 {
+        self.entries.last().map_or(0, |entry| entry.claimed)
+    }
+
+    /// Gaps found between the events.
+    pub fn gaps(&self) -> Vec<Gap> {
+        self.entries.iter().filter_map(|entry| entry.gap).collect()
+    }
+
+    /// Where the final stake on each agent differs from `on_chain`, the
+    /// wallet's stake by agent as its stake index entries hold it.
+    pub fn reconcile(&self, on_chain: &BTreeMap<u64, u64>) -> Vec<Gap> {
+        let agents: BTreeSet<u64> = self.staked_by_agent.keys().chain(on_chain.keys()).copied().collect();
+        agents
+            .into_iter()
+            .filter_map(|agent_id| {
+                let timeline = self.staked_by_agent.get(&agent_id).copied().unwrap_or(0);
+                let on_chain = on_chain.get(&agent_id).copied().unwrap_or(0);
+                (timeline != on_chain).then_some(Gap::Unreconciled { agent_id, timeline, on_chain })
+            })
+            .collect()
+    }
+}
+
+/// Stake by agent in a wallet's stake index entries.
+pub fn on_chain_stakes(entries: &[(Pubkey, StakeIndexEntry)]) -> BTreeMap<u64, u64> {
+    entries
+        .iter()
+        .filter(|(_, entry)| entry.staked_amount > 0)
+        .map(|(_, entry)| (entry.agent_id, entry.staked_amount))
+        .collect()
+}
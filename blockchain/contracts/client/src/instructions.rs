@@ -0,0 +1,1326 @@
+//! One builder per program instruction. Each takes the signers, the accounts that
+//! cannot be derived, and the instruction arguments; every PDA is derived from
+//! [`crate::pda`], so a reordered or renamed account only needs a change here.
+
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::solana_program::system_program;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use ontora_ai::state::{AdminAction, ConfigParams, FeeType, ProposalAction, Role};
+use ontora_ai::MIGRATED_AGENT_ID;
+
+use crate::pda;
+
+fn program_instruction(accounts: impl ToAccountMetas, args: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: ontora_ai::ID,
+        accounts: accounts.to_account_metas(None),
+        data: args.data(),
+    }
+}
+
+/// Arguments of [`build_initialize_reward_pool`].
+#[derive(Clone, Debug)]
+pub struct InitializeRewardPoolParams {
+    pub payer: Pubkey,
+    pub initial_rewards: u64,
+}
+
+/// Create the legacy reward pool.
+pub fn build_initialize_reward_pool(params: InitializeRewardPoolParams) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::InitializeRewardPool {
+            reward_pool: pda::reward_pool().0,
+            user: params.payer,
+            system_program: system_program::ID,
+        },
+        ontora_ai::instruction::InitializeRewardPool { initial_rewards: params.initial_rewards },
+    )
+}
+
+/// Arguments of [`build_fund_reward_pool`].
+#[derive(Clone, Debug)]
+pub struct FundRewardPoolParams {
+    pub funder: Pubkey,
+    pub funder_token_account: Pubkey,
+    pub reward_mint: Pubkey,
+    pub amount: u64,
+}
+
+/// Move `amount` from the funder's token account into the reward vault and
+/// add it to the pool's total.
+pub fn build_fund_reward_pool(params: FundRewardPoolParams) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::FundRewardPool {
+            platform_config: pda::platform_config().0,
+            reward_pool: pda::reward_pool().0,
+            reward_vault: pda::reward_vault().0,
+            reward_mint: params.reward_mint,
+            funder: params.funder,
+            funder_token_account: params.funder_token_account,
+            token_program: spl_token::ID,
+        },
+        ontora_ai::instruction::FundRewardPool { amount: params.amount },
+    )
+}
+
+/// Arguments of [`build_register_ai_agent`].
+#[derive(Clone, Debug)]
+pub struct RegisterAiAgentParams {
+    pub user: Pubkey,
+    pub user_token_account: Pubkey,
+    pub staking_vault: Pubkey,
+    pub stake_amount: u64,
+}
+
+/// Register the user's legacy agent with an initial stake.
+pub fn build_register_ai_agent(params: RegisterAiAgentParams) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::RegisterAIAgent {
+            platform_config: pda::platform_config().0,
+            ai_agent: pda::legacy_ai_agent(&params.user).0,
+            user: params.user,
+            user_token_account: params.user_token_account,
+            staking_vault: params.staking_vault,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        },
+        ontora_ai::instruction::RegisterAiAgent { stake_amount: params.stake_amount },
+    )
+}
+
+/// Arguments of [`build_increase_stake`].
+#[derive(Clone, Debug)]
+pub struct IncreaseStakeParams {
+    pub user: Pubkey,
+    pub user_token_account: Pubkey,
+    pub staking_vault: Pubkey,
+    pub amount: u64,
+}
+
+/// Add `amount` to the stake of the user's legacy agent.
+pub fn build_increase_stake(params: IncreaseStakeParams) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::IncreaseStake {
+            platform_config: pda::platform_config().0,
+            ai_agent: pda::legacy_ai_agent(&params.user).0,
+            user: params.user,
+            user_token_account: params.user_token_account,
+            staking_vault: params.staking_vault,
+            token_program: spl_token::ID,
+        },
+        ontora_ai::instruction::IncreaseStake { amount: params.amount },
+    )
+}
+
+/// Arguments of [`build_unstake_ai_agent`].
+#[derive(Clone, Debug)]
+pub struct UnstakeAiAgentParams {
+    pub user: Pubkey,
+    pub user_token_account: Pubkey,
+    pub staking_vault: Pubkey,
+    pub stake_mint: Pubkey,
+}
+
+/// Withdraw the whole stake of the user's legacy agent.
+pub fn build_unstake_ai_agent(params: UnstakeAiAgentParams) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::UnstakeAIAgent {
+            platform_config: pda::platform_config().0,
+            ai_agent: pda::legacy_ai_agent(&params.user).0,
+            pending_withdrawal: pda::pending_withdrawal(&params.user).0,
+            user: params.user,
+            user_token_account: params.user_token_account,
+            staking_vault: params.staking_vault,
+            stake_mint: params.stake_mint,
+            fee_schedule: pda::fee_schedule().0,
+            treasury: pda::treasury().0,
+            treasury_vault: pda::treasury_vault(&params.stake_mint).0,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        },
+        ontora_ai::instruction::UnstakeAiAgent {},
+    )
+}
+
+/// Arguments of [`build_unstake_partial`].
+#[derive(Clone, Debug)]
+pub struct UnstakePartialParams {
+    pub user: Pubkey,
+    pub user_token_account: Pubkey,
+    pub staking_vault: Pubkey,
+    pub stake_mint: Pubkey,
+    pub amount: u64,
+}
+
+/// Withdraw `amount` of the user's legacy agent's stake.
+pub fn build_unstake_partial(params: UnstakePartialParams) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::UnstakeAIAgent {
+            platform_config: pda::platform_config().0,
+            ai_agent: pda::legacy_ai_agent(&params.user).0,
+            pending_withdrawal: pda::pending_withdrawal(&params.user).0,
+            user: params.user,
+            user_token_account: params.user_token_account,
+            staking_vault: params.staking_vault,
+            stake_mint: params.stake_mint,
+            fee_schedule: pda::fee_schedule().0,
+            treasury: pda::treasury().0,
+            treasury_vault: pda::treasury_vault(&params.stake_mint).0,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        },
+        ontora_ai::instruction::UnstakePartial { amount: params.amount },
+    )
+}
+
+/// Arguments of [`build_emergency_unstake`].
+#[derive(Clone, Debug)]
+pub struct EmergencyUnstakeParams {
+    pub user: Pubkey,
+    pub user_token_account: Pubkey,
+    pub staking_vault: Pubkey,
+    pub stake_mint: Pubkey,
+}
+
+/// Withdraw the user's whole legacy stake before the cooldown ends, leaving
+/// the fee schedule's emergency unstake penalty in the reward vault.
+pub fn build_emergency_unstake(params: EmergencyUnstakeParams) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::EmergencyUnstake {
+            platform_config: pda::platform_config().0,
+            ai_agent: pda::legacy_ai_agent(&params.user).0,
+            user: params.user,
+            user_token_account: params.user_token_account,
+            staking_vault: params.staking_vault,
+            stake_mint: params.stake_mint,
+            reward_pool: pda::reward_pool().0,
+            fee_schedule: pda::fee_schedule().0,
+            reward_vault: pda::reward_vault().0,
+            token_program: spl_token::ID,
+        },
+        ontora_ai::instruction::EmergencyUnstake {},
+    )
+}
+
+/// Arguments of [`build_request_unstake`].
+#[derive(Clone, Debug)]
+pub struct RequestUnstakeParams {
+    pub user: Pubkey,
+    pub amount: u64,
+}
+
+/// Take `amount` out of the user's legacy stake, to claim with
+/// [`build_claim_unstake`] once the cooldown ends.
+pub fn build_request_unstake(params: RequestUnstakeParams) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::RequestUnstake {
+            platform_config: pda::platform_config().0,
+            ai_agent: pda::legacy_ai_agent(&params.user).0,
+            pending_withdrawal: pda::pending_withdrawal(&params.user).0,
+            user: params.user,
+            system_program: system_program::ID,
+        },
+        ontora_ai::instruction::RequestUnstake { amount: params.amount },
+    )
+}
+
+/// Arguments of [`build_claim_unstake`].
+#[derive(Clone, Debug)]
+pub struct ClaimUnstakeParams {
+    pub user: Pubkey,
+    pub user_token_account: Pubkey,
+    pub staking_vault: Pubkey,
+    pub stake_mint: Pubkey,
+}
+
+/// Pay the user's requested withdrawal, less the unstake fee.
+pub fn build_claim_unstake(params: ClaimUnstakeParams) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::ClaimUnstake {
+            platform_config: pda::platform_config().0,
+            ai_agent: pda::legacy_ai_agent(&params.user).0,
+            pending_withdrawal: pda::pending_withdrawal(&params.user).0,
+            user: params.user,
+            user_token_account: params.user_token_account,
+            staking_vault: params.staking_vault,
+            stake_mint: params.stake_mint,
+            fee_schedule: pda::fee_schedule().0,
+            treasury: pda::treasury().0,
+            treasury_vault: pda::treasury_vault(&params.stake_mint).0,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        },
+        ontora_ai::instruction::ClaimUnstake {},
+    )
+}
+
+/// Arguments of [`build_close_agent`].
+#[derive(Clone, Debug)]
+pub struct CloseAgentParams {
+    pub owner: Pubkey,
+}
+
+/// Close the owner's emptied legacy agent, refunding its rent.
+pub fn build_close_agent(params: CloseAgentParams) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::CloseAgent {
+            platform_config: pda::platform_config().0,
+            ai_agent: pda::legacy_ai_agent(&params.owner).0,
+            pending_withdrawal: pda::pending_withdrawal(&params.owner).0,
+            owner: params.owner,
+        },
+        ontora_ai::instruction::CloseAgent {},
+    )
+}
+
+/// Arguments of [`build_distribute_rewards`].
+#[derive(Clone, Debug)]
+pub struct DistributeRewardsParams {
+    pub crank: Pubkey,
+    pub agent_owner: Pubkey,
+}
+
+/// Credit the epoch's rewards to a legacy agent (crank role).
+pub fn build_distribute_rewards(params: DistributeRewardsParams) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::DistributeRewards {
+            platform_config: pda::platform_config().0,
+            reward_pool: pda::reward_pool().0,
+            ai_agent: pda::legacy_ai_agent(&params.agent_owner).0,
+            crank: params.crank,
+        },
+        ontora_ai::instruction::DistributeRewards {},
+    )
+}
+
+/// Arguments of [`build_distribute_rewards_batch`].
+#[derive(Clone, Debug)]
+pub struct DistributeRewardsBatchParams {
+    pub crank: Pubkey,
+    /// Addresses of the legacy agents to pay, in any order.
+    pub agents: Vec<Pubkey>,
+    /// Close the epoch after this batch; set on an epoch's last batch only.
+    pub close_epoch: bool,
+}
+
+/// Credit the epoch's rewards to a batch of legacy agents (crank role). The
+/// agents are passed sorted and deduplicated, as the program requires.
+pub fn build_distribute_rewards_batch(params: DistributeRewardsBatchParams) -> Instruction {
+    let mut instruction = program_instruction(
+        ontora_ai::accounts::DistributeRewardsBatch {
+            platform_config: pda::platform_config().0,
+            reward_pool: pda::reward_pool().0,
+            crank: params.crank,
+        },
+        ontora_ai::instruction::DistributeRewardsBatch { close_epoch: params.close_epoch },
+    );
+    let mut agents = params.agents;
+    agents.sort_unstable();
+    agents.dedup();
+    instruction.accounts.extend(agents.into_iter().map(|agent| AccountMeta::new(agent, false)));
+    instruction
+}
+
+/// Arguments of [`build_claim_rewards`].
+#[derive(Clone, Debug)]
+pub struct ClaimRewardsParams {
+    pub user: Pubkey,
+    pub user_token_account: Pubkey,
+    pub reward_vault: Pubkey,
+    pub reward_mint: Pubkey,
+}
+
+/// Pay out the rewards accumulated by the user's legacy agent. While the
+/// platform's `vesting_seconds` is set, they join the user's vesting schedule
+/// instead, and only what has vested on it is paid.
+pub fn build_claim_rewards(params: ClaimRewardsParams) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::ClaimRewards {
+            platform_config: pda::platform_config().0,
+            ai_agent: pda::legacy_ai_agent(&params.user).0,
+            reward_pool: pda::reward_pool().0,
+            user: params.user,
+            user_token_account: params.user_token_account,
+            reward_vault: params.reward_vault,
+            reward_mint: params.reward_mint,
+            fee_schedule: pda::fee_schedule().0,
+            treasury: pda::treasury().0,
+            treasury_vault: pda::treasury_vault(&params.reward_mint).0,
+            reward_vesting: pda::reward_vesting(&params.user).0,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        },
+        ontora_ai::instruction::ClaimRewards {},
+    )
+}
+
+/// Arguments of [`build_release_vested`].
+#[derive(Clone, Debug)]
+pub struct ReleaseVestedParams {
+    pub user: Pubkey,
+    pub user_token_account: Pubkey,
+    pub reward_vault: Pubkey,
+    pub reward_mint: Pubkey,
+}
+
+/// Pay out the claimed rewards vested on the user's schedule since the last
+/// release.
+pub fn build_release_vested(params: ReleaseVestedParams) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::ReleaseVested {
+            platform_config: pda::platform_config().0,
+            reward_vesting: pda::reward_vesting(&params.user).0,
+            reward_pool: pda::reward_pool().0,
+            user: params.user,
+            user_token_account: params.user_token_account,
+            reward_vault: params.reward_vault,
+            reward_mint: params.reward_mint,
+            token_program: spl_token::ID,
+        },
+        ontora_ai::instruction::ReleaseVested {},
+    )
+}
+
+/// Arguments of [`build_create_proposal`].
+#[derive(Clone, Debug)]
+pub struct CreateProposalParams {
+    pub user: Pubkey,
+    pub description: String,
+    pub duration: i64,
+}
+
+/// Open the user's legacy yes/no proposal.
+pub fn build_create_proposal(params: CreateProposalParams) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::CreateProposal {
+            platform_config: pda::platform_config().0,
+            proposal: pda::legacy_proposal(&params.user).0,
+            user: params.user,
+            system_program: system_program::ID,
+        },
+        ontora_ai::instruction::CreateProposal {
+            description: params.description,
+            duration: params.duration,
+        },
+    )
+}
+
+/// Arguments of [`build_vote_proposal`].
+#[derive(Clone, Debug)]
+pub struct VoteProposalParams {
+    pub user: Pubkey,
+    pub proposal_creator: Pubkey,
+    pub in_favor: bool,
+}
+
+/// Vote on a legacy proposal with the stake of the user's legacy agent.
+pub fn build_vote_proposal(params: VoteProposalParams) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::VoteProposal {
+            platform_config: pda::platform_config().0,
+            proposal: pda::legacy_proposal(&params.proposal_creator).0,
+            ai_agent: pda::legacy_ai_agent(&params.user).0,
+            user: params.user,
+        },
+        ontora_ai::instruction::VoteProposal { in_favor: params.in_favor },
+    )
+}
+
+/// Arguments of [`build_initialize_platform`].
+#[derive(Clone, Debug)]
+pub struct InitializePlatformParams {
+    pub admin: Pubkey,
+    pub reward_rate_bps: u64,
+    pub min_stake_amount: u64,
+    pub epoch_duration: i64,
+    pub cooldown_seconds: i64,
+    pub epoch_emission: u64,
+}
+
+/// Create the platform configuration with `admin` as its first admin.
+pub fn build_initialize_platform(params: InitializePlatformParams) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::InitializePlatform {
+            platform_config: pda::platform_config().0,
+            admin: params.admin,
+            system_program: system_program::ID,
+        },
+        ontora_ai::instruction::InitializePlatform {
+            reward_rate_bps: params.reward_rate_bps,
+            min_stake_amount: params.min_stake_amount,
+            epoch_duration: params.epoch_duration,
+            cooldown_seconds: params.cooldown_seconds,
+            epoch_emission: params.epoch_emission,
+        },
+    )
+}
+
+/// Arguments of [`build_initialize_vaults`].
+#[derive(Clone, Debug)]
+pub struct InitializeVaultsParams {
+    pub admin: Pubkey,
+    pub stake_mint: Pubkey,
+}
+
+/// Create the staking and reward vaults for `stake_mint` (admin only).
+pub fn build_initialize_vaults(params: InitializeVaultsParams) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::InitializeVaults {
+            platform_config: pda::platform_config().0,
+            reward_pool: pda::reward_pool().0,
+            stake_mint: params.stake_mint,
+            staking_vault: pda::staking_vault().0,
+            reward_vault: pda::reward_vault().0,
+            admin: params.admin,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        },
+        ontora_ai::instruction::InitializeVaults {},
+    )
+}
+
+/// Arguments of [`build_update_platform_config`].
+#[derive(Clone, Debug)]
+pub struct UpdatePlatformConfigParams {
+    pub admin: Pubkey,
+    pub reward_rate_bps: u64,
+    pub min_stake_amount: u64,
+    pub epoch_duration: i64,
+    pub cooldown_seconds: i64,
+    pub epoch_emission: u64,
+    /// Stake cap per agent (0 = unlimited).
+    pub max_stake_per_agent: u64,
+    /// Seconds claimed legacy rewards vest over (0 = paid at once).
+    pub vesting_seconds: i64,
+    /// Stake that counts as one vote.
+    pub vote_unit: u64,
+    /// Share of the stake that must vote for a proposal to pass, in basis points.
+    pub quorum_bps: u16,
+    /// Share of the votes a proposal's winning option needs, in basis points.
+    pub approval_threshold_bps: u16,
+    pub update_metadata: String,
+}
+
+/// Update the platform parameters (admin only).
+pub fn build_update_platform_config(params: UpdatePlatformConfigParams) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::UpdatePlatformConfig {
+            platform_config: pda::platform_config().0,
+            admin: params.admin,
+        },
+        ontora_ai::instruction::UpdatePlatformConfig {
+            params: ConfigParams {
+                reward_rate_bps: params.reward_rate_bps,
+                min_stake_amount: params.min_stake_amount,
+                epoch_duration: params.epoch_duration,
+                cooldown_seconds: params.cooldown_seconds,
+                epoch_emission: params.epoch_emission,
+                max_stake_per_agent: params.max_stake_per_agent,
+                vesting_seconds: params.vesting_seconds,
+                vote_unit: params.vote_unit,
+                quorum_bps: params.quorum_bps,
+                approval_threshold_bps: params.approval_threshold_bps,
+            },
+            update_metadata: params.update_metadata,
+        },
+    )
+}
+
+/// Arguments of [`build_migrate_platform_config`].
+#[derive(Clone, Debug)]
+pub struct MigratePlatformConfigParams {
+    pub admin: Pubkey,
+}
+
+/// Grow an older platform config to the current layout.
+pub fn build_migrate_platform_config(params: MigratePlatformConfigParams) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::MigratePlatformConfig {
+            platform_config: pda::platform_config().0,
+            admin: params.admin,
+            system_program: system_program::ID,
+        },
+        ontora_ai::instruction::MigratePlatformConfig {},
+    )
+}
+
+/// Arguments of [`build_migrate_account`].
+#[derive(Clone, Debug)]
+pub struct MigrateAccountParams {
+    pub account: Pubkey,
+    pub payer: Pubkey,
+}
+
+/// Upgrade any versioned account to its current layout.
+pub fn build_migrate_account(params: MigrateAccountParams) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::MigrateAccount {
+            account: params.account,
+            payer: params.payer,
+            system_program: system_program::ID,
+        },
+        ontora_ai::instruction::MigrateAccount {},
+    )
+}
+
+/// Arguments of [`build_migrate_agent_v1`].
+#[derive(Clone, Debug)]
+pub struct MigrateAgentV1Params {
+    pub admin: Pubkey,
+    /// Owner of the legacy agent.
+    pub owner: Pubkey,
+    pub stake_mint: Pubkey,
+    /// The legacy agent's vault, which its stake moves out of.
+    pub staking_vault: Pubkey,
+    pub platform_vault: Pubkey,
+}
+
+/// Move `owner`'s legacy agent and its stake onto the unified agent layout.
+pub fn build_migrate_agent_v1(params: MigrateAgentV1Params) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::MigrateAgentV1 {
+            platform_config: pda::platform_config().0,
+            legacy_agent: pda::legacy_ai_agent(&params.owner).0,
+            ai_agent: pda::ai_agent(&params.owner, MIGRATED_AGENT_ID).0,
+            user_stake: pda::user_stake(&params.owner).0,
+            stake_index: pda::stake_index(&params.owner, MIGRATED_AGENT_ID).0,
+            staking_vault: params.staking_vault,
+            platform_vault: params.platform_vault,
+            stake_mint: params.stake_mint,
+            admin: params.admin,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        },
+        ontora_ai::instruction::MigrateAgentV1 {},
+    )
+}
+
+/// Report the program version through return data.
+pub fn build_get_version() -> Instruction {
+    program_instruction(ontora_ai::accounts::GetVersion {}, ontora_ai::instruction::GetVersion {})
+}
+
+/// Arguments of [`build_create_governance_proposal`].
+#[derive(Clone, Debug)]
+pub struct CreateGovernanceProposalParams {
+    pub creator: Pubkey,
+    /// The platform config's current `proposal_count`, which becomes the proposal's id
+    pub proposal_id: u64,
+    pub title: String,
+    pub description: String,
+    pub voting_duration: u64,
+    pub options: Vec<String>,
+    /// What [`build_execute_proposal`] applies to the config once the proposal passes
+    pub action: ProposalAction,
+}
+
+/// Open a multi-option governance proposal.
+pub fn build_create_governance_proposal(params: CreateGovernanceProposalParams) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::CreateGovernanceProposal {
+            creator: params.creator,
+            platform_config: pda::platform_config().0,
+            proposal: pda::proposal(params.proposal_id).0,
+            system_program: system_program::ID,
+        },
+        ontora_ai::instruction::CreateGovernanceProposal {
+            title: params.title,
+            description: params.description,
+            voting_duration: params.voting_duration,
+            options: params.options,
+            action: params.action,
+        },
+    )
+}
+
+/// Arguments of [`build_cast_vote`].
+#[derive(Clone, Debug)]
+pub struct CastVoteParams {
+    pub voter: Pubkey,
+    pub proposal_id: u64,
+    pub vote_option: u8,
+}
+
+/// Vote for one option of a governance proposal, with one vote per vote unit
+/// of the voter's stake.
+pub fn build_cast_vote(params: CastVoteParams) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::CastVote {
+            voter: params.voter,
+            platform_config: pda::platform_config().0,
+            proposal: pda::proposal(params.proposal_id).0,
+            user_stake: pda::user_stake(&params.voter).0,
+            vote_record: pda::proposal_vote(params.proposal_id, &params.voter).0,
+            system_program: system_program::ID,
+        },
+        ontora_ai::instruction::CastVote {
+            proposal_id: params.proposal_id,
+            vote_option: params.vote_option,
+        },
+    )
+}
+
+/// Arguments of [`build_finalize_proposal`].
+#[derive(Clone, Debug)]
+pub struct FinalizeProposalParams {
+    pub caller: Pubkey,
+    pub proposal_id: u64,
+}
+
+/// Close voting on a governance proposal whose period has ended.
+pub fn build_finalize_proposal(params: FinalizeProposalParams) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::FinalizeProposal {
+            caller: params.caller,
+            platform_config: pda::platform_config().0,
+            proposal: pda::proposal(params.proposal_id).0,
+            system_program: system_program::ID,
+        },
+        ontora_ai::instruction::FinalizeProposal { proposal_id: params.proposal_id },
+    )
+}
+
+/// Arguments of [`build_execute_proposal`].
+#[derive(Clone, Debug)]
+pub struct ExecuteProposalParams {
+    pub executor: Pubkey,
+    pub proposal_id: u64,
+}
+
+/// Apply an approved governance proposal's action to the platform config
+/// (anyone may, once).
+pub fn build_execute_proposal(params: ExecuteProposalParams) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::ExecuteProposal {
+            executor: params.executor,
+            platform_config: pda::platform_config().0,
+            proposal: pda::proposal(params.proposal_id).0,
+        },
+        ontora_ai::instruction::ExecuteProposal {},
+    )
+}
+
+/// Arguments of [`build_set_governance_enabled`].
+#[derive(Clone, Debug)]
+pub struct SetGovernanceEnabledParams {
+    pub admin: Pubkey,
+    pub enabled: bool,
+    /// Ids of every unfinalized proposal, which the program suspends or resumes
+    pub active_proposal_ids: Vec<u64>,
+}
+
+/// Enable or disable governance (admin only).
+pub fn build_set_governance_enabled(params: SetGovernanceEnabledParams) -> Instruction {
+    let mut instruction = program_instruction(
+        ontora_ai::accounts::SetGovernanceEnabled {
+            platform_config: pda::platform_config().0,
+            admin: params.admin,
+        },
+        ontora_ai::instruction::SetGovernanceEnabled { enabled: params.enabled },
+    );
+    instruction.accounts.extend(
+        params.active_proposal_ids.iter().map(|id| AccountMeta::new(pda::proposal(*id).0, false)),
+    );
+    instruction
+}
+
+/// Arguments of [`build_propose_admin`].
+#[derive(Clone, Debug)]
+pub struct ProposeAdminParams {
+    pub admin: Pubkey,
+    pub new_admin: Pubkey,
+}
+
+/// Nominate a new platform admin.
+pub fn build_propose_admin(params: ProposeAdminParams) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::ProposeAdmin {
+            platform_config: pda::platform_config().0,
+            admin: params.admin,
+        },
+        ontora_ai::instruction::ProposeAdmin { new_admin: params.new_admin },
+    )
+}
+
+/// Arguments of [`build_accept_admin`].
+#[derive(Clone, Debug)]
+pub struct AcceptAdminParams {
+    pub new_admin: Pubkey,
+}
+
+/// Accept a pending admin nomination.
+pub fn build_accept_admin(params: AcceptAdminParams) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::AcceptAdmin {
+            platform_config: pda::platform_config().0,
+            new_admin: params.new_admin,
+        },
+        ontora_ai::instruction::AcceptAdmin {},
+    )
+}
+
+/// Arguments of [`build_cancel_admin_transfer`].
+#[derive(Clone, Debug)]
+pub struct CancelAdminTransferParams {
+    pub admin: Pubkey,
+}
+
+/// Cancel a pending admin nomination.
+pub fn build_cancel_admin_transfer(params: CancelAdminTransferParams) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::CancelAdminTransfer {
+            platform_config: pda::platform_config().0,
+            admin: params.admin,
+        },
+        ontora_ai::instruction::CancelAdminTransfer {},
+    )
+}
+
+/// Arguments of [`build_initialize_treasury`].
+#[derive(Clone, Debug)]
+pub struct InitializeTreasuryParams {
+    pub authority: Pubkey,
+    pub governance: Pubkey,
+}
+
+/// Create the protocol treasury (treasury role).
+pub fn build_initialize_treasury(params: InitializeTreasuryParams) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::InitializeTreasury {
+            platform_config: pda::platform_config().0,
+            treasury: pda::treasury().0,
+            authority: params.authority,
+            system_program: system_program::ID,
+        },
+        ontora_ai::instruction::InitializeTreasury { governance: params.governance },
+    )
+}
+
+/// Arguments of [`build_initialize_fee_schedule`].
+#[derive(Clone, Debug)]
+pub struct InitializeFeeScheduleParams {
+    pub admin: Pubkey,
+    pub governance: Pubkey,
+    pub rates_bps: [u16; FeeType::COUNT],
+}
+
+/// Create the fee schedule (admin only).
+pub fn build_initialize_fee_schedule(params: InitializeFeeScheduleParams) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::InitializeFeeSchedule {
+            platform_config: pda::platform_config().0,
+            fee_schedule: pda::fee_schedule().0,
+            admin: params.admin,
+            system_program: system_program::ID,
+        },
+        ontora_ai::instruction::InitializeFeeSchedule {
+            governance: params.governance,
+            rates_bps: params.rates_bps,
+        },
+    )
+}
+
+/// Arguments of [`build_update_fee_schedule`].
+#[derive(Clone, Debug)]
+pub struct UpdateFeeScheduleParams {
+    pub authority: Pubkey,
+    pub rates_bps: [u16; FeeType::COUNT],
+}
+
+/// Stage new fee rates for the next epoch (admin or governance).
+pub fn build_update_fee_schedule(params: UpdateFeeScheduleParams) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::UpdateFeeSchedule {
+            platform_config: pda::platform_config().0,
+            fee_schedule: pda::fee_schedule().0,
+            authority: params.authority,
+        },
+        ontora_ai::instruction::UpdateFeeSchedule { rates_bps: params.rates_bps },
+    )
+}
+
+/// Arguments of [`build_treasury_balance`].
+#[derive(Clone, Debug)]
+pub struct TreasuryBalanceParams {
+    pub mint: Pubkey,
+}
+
+/// Report the treasury balance for one mint through return data.
+pub fn build_treasury_balance(params: TreasuryBalanceParams) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::TreasuryBalance {
+            treasury: pda::treasury().0,
+            treasury_vault: pda::treasury_vault(&params.mint).0,
+        },
+        ontora_ai::instruction::TreasuryBalance {},
+    )
+}
+
+/// Arguments of [`build_treasury_transfer`].
+#[derive(Clone, Debug)]
+pub struct TreasuryTransferParams {
+    pub governance: Pubkey,
+    pub mint: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+}
+
+/// Move tokens out of the treasury (governance only).
+pub fn build_treasury_transfer(params: TreasuryTransferParams) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::TreasuryTransfer {
+            treasury: pda::treasury().0,
+            mint: params.mint,
+            treasury_vault: pda::treasury_vault(&params.mint).0,
+            recipient: params.recipient,
+            governance: params.governance,
+            token_program: spl_token::ID,
+        },
+        ontora_ai::instruction::TreasuryTransfer { amount: params.amount },
+    )
+}
+
+/// Arguments of [`build_set_pause_flags`].
+#[derive(Clone, Debug)]
+pub struct SetPauseFlagsParams {
+    pub authority: Pubkey,
+    pub flags: u8,
+}
+
+/// Pause operation groups (pauser role).
+pub fn build_set_pause_flags(params: SetPauseFlagsParams) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::SetPauseFlags {
+            platform_config: pda::platform_config().0,
+            authority: params.authority,
+        },
+        ontora_ai::instruction::SetPauseFlags { flags: params.flags },
+    )
+}
+
+/// Arguments of [`build_clear_pause_flags`].
+#[derive(Clone, Debug)]
+pub struct ClearPauseFlagsParams {
+    pub admin: Pubkey,
+    pub flags: u8,
+}
+
+/// Unpause operation groups (admin only).
+pub fn build_clear_pause_flags(params: ClearPauseFlagsParams) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::ClearPauseFlags {
+            platform_config: pda::platform_config().0,
+            admin: params.admin,
+        },
+        ontora_ai::instruction::ClearPauseFlags { flags: params.flags },
+    )
+}
+
+/// Arguments of [`build_set_role`].
+#[derive(Clone, Debug)]
+pub struct SetRoleParams {
+    pub admin: Pubkey,
+    pub role: Role,
+    pub holder: Pubkey,
+}
+
+/// Assign a delegated role (admin only).
+pub fn build_set_role(params: SetRoleParams) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::SetRole {
+            platform_config: pda::platform_config().0,
+            admin: params.admin,
+        },
+        ontora_ai::instruction::SetRole {
+            role: params.role,
+            holder: params.holder,
+        },
+    )
+}
+
+/// Arguments of [`build_renounce_role`].
+#[derive(Clone, Debug)]
+pub struct RenounceRoleParams {
+    pub holder: Pubkey,
+    pub role: Role,
+}
+
+/// Give up a delegated role (current holder only).
+pub fn build_renounce_role(params: RenounceRoleParams) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::RenounceRole {
+            platform_config: pda::platform_config().0,
+            holder: params.holder,
+        },
+        ontora_ai::instruction::RenounceRole { role: params.role },
+    )
+}
+
+/// Arguments of [`build_update_performance_score`].
+#[derive(Clone, Debug)]
+pub struct UpdatePerformanceScoreParams {
+    pub oracle: Pubkey,
+    pub agent_owner: Pubkey,
+    pub agent_id: u64,
+    pub score: u64,
+}
+
+/// Post an agent's performance score (oracle role).
+pub fn build_update_performance_score(params: UpdatePerformanceScoreParams) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::UpdatePerformanceScore {
+            platform_config: pda::platform_config().0,
+            ai_agent: pda::ai_agent(&params.agent_owner, params.agent_id).0,
+            oracle: params.oracle,
+        },
+        ontora_ai::instruction::UpdatePerformanceScore { score: params.score },
+    )
+}
+
+/// Arguments of [`build_register_agent`].
+#[derive(Clone, Debug)]
+pub struct RegisterAgentParams {
+    pub owner: Pubkey,
+    pub agent_id: u64,
+    pub name: String,
+    pub description: String,
+}
+
+/// Register an agent under its owner and id.
+pub fn build_register_agent(params: RegisterAgentParams) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::RegisterAiAgent {
+            ai_agent: pda::ai_agent(&params.owner, params.agent_id).0,
+            owner: params.owner,
+            system_program: system_program::ID,
+        },
+        ontora_ai::instruction::RegisterAgent {
+            agent_id: params.agent_id,
+            name: params.name,
+            description: params.description,
+        },
+    )
+}
+
+/// Arguments of [`build_update_agent_metadata`].
+#[derive(Clone, Debug)]
+pub struct UpdateAgentMetadataParams {
+    pub owner: Pubkey,
+    pub agent_id: u64,
+    pub name: String,
+    pub description: String,
+}
+
+/// Update an agent's name and description (owner only).
+pub fn build_update_agent_metadata(params: UpdateAgentMetadataParams) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::UpdateAgentMetadata {
+            ai_agent: pda::ai_agent(&params.owner, params.agent_id).0,
+            owner: params.owner,
+        },
+        ontora_ai::instruction::UpdateAgentMetadata {
+            agent_id: params.agent_id,
+            name: params.name,
+            description: params.description,
+        },
+    )
+}
+
+/// Arguments of [`build_stake_on_agent`].
+#[derive(Clone, Debug)]
+pub struct StakeOnAgentParams {
+    pub user: Pubkey,
+    pub user_token_account: Pubkey,
+    pub platform_vault: Pubkey,
+    pub agent_owner: Pubkey,
+    pub agent_id: u64,
+    pub amount: u64,
+    /// Owner of the position, if not `user`, who funds it.
+    pub beneficiary: Option<Pubkey>,
+}
+
+/// Stake tokens on an agent.
+pub fn build_stake_on_agent(params: StakeOnAgentParams) -> Instruction {
+    let beneficiary = params.beneficiary.unwrap_or(params.user);
+    program_instruction(
+        ontora_ai::accounts::StakeOnAgent {
+            platform_config: pda::platform_config().0,
+            ai_agent: pda::ai_agent(&params.agent_owner, params.agent_id).0,
+            user_stake: pda::user_stake(&beneficiary).0,
+            stake_index: pda::stake_index(&beneficiary, params.agent_id).0,
+            user: params.user,
+            user_token_account: params.user_token_account,
+            platform_vault: params.platform_vault,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        },
+        ontora_ai::instruction::StakeOnAgent {
+            agent_id: params.agent_id,
+            amount: params.amount,
+            beneficiary,
+        },
+    )
+}
+
+/// Arguments of [`build_unstake_from_agent`].
+#[derive(Clone, Debug)]
+pub struct UnstakeFromAgentParams {
+    pub user: Pubkey,
+    pub user_token_account: Pubkey,
+    pub platform_vault: Pubkey,
+    pub stake_mint: Pubkey,
+    pub agent_owner: Pubkey,
+    pub agent_id: u64,
+    pub amount: u64,
+}
+
+/// Withdraw tokens staked on an agent.
+pub fn build_unstake_from_agent(params: UnstakeFromAgentParams) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::UnstakeFromAgent {
+            platform_config: pda::platform_config().0,
+            ai_agent: pda::ai_agent(&params.agent_owner, params.agent_id).0,
+            user_stake: pda::user_stake(&params.user).0,
+            stake_index: pda::stake_index(&params.user, params.agent_id).0,
+            user: params.user,
+            user_token_account: params.user_token_account,
+            platform_vault: params.platform_vault,
+            stake_mint: params.stake_mint,
+            fee_schedule: pda::fee_schedule().0,
+            treasury: pda::treasury().0,
+            treasury_vault: pda::treasury_vault(&params.stake_mint).0,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        },
+        ontora_ai::instruction::UnstakeFromAgent {
+            agent_id: params.agent_id,
+            amount: params.amount,
+        },
+    )
+}
+
+/// Arguments of [`build_claim_stake_rewards`].
+#[derive(Clone, Debug)]
+pub struct ClaimStakeRewardsParams {
+    pub user: Pubkey,
+    pub user_token_account: Pubkey,
+    pub platform_vault: Pubkey,
+    pub reward_mint: Pubkey,
+}
+
+/// Pay out the rewards accumulated by the user's stake, which the user must
+/// own: a stake funded for a beneficiary is claimed by the beneficiary.
+pub fn build_claim_stake_rewards(params: ClaimStakeRewardsParams) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::ClaimStakeRewards {
+            platform_config: pda::platform_config().0,
+            user_stake: pda::user_stake(&params.user).0,
+            user: params.user,
+            user_token_account: params.user_token_account,
+            platform_vault: params.platform_vault,
+            reward_mint: params.reward_mint,
+            fee_schedule: pda::fee_schedule().0,
+            treasury: pda::treasury().0,
+            treasury_vault: pda::treasury_vault(&params.reward_mint).0,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        },
+        ontora_ai::instruction::ClaimStakeRewards {},
+    )
+}
+
+/// Arguments of [`build_create_metadata`].
+#[derive(Clone, Debug)]
+pub struct CreateMetadataParams {
+    pub authority: Pubkey,
+    pub entity_id: u64,
+    pub data: String,
+}
+
+/// Create an entity's metadata account.
+pub fn build_create_metadata(params: CreateMetadataParams) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::CreateMetadata {
+            metadata: pda::metadata(&params.authority, params.entity_id).0,
+            authority: params.authority,
+            system_program: system_program::ID,
+        },
+        ontora_ai::instruction::CreateMetadata {
+            entity_id: params.entity_id,
+            data: params.data,
+        },
+    )
+}
+
+/// Arguments of [`build_update_metadata`].
+#[derive(Clone, Debug)]
+pub struct UpdateMetadataParams {
+    pub authority: Pubkey,
+    pub entity_id: u64,
+    pub data: String,
+}
+
+/// Replace an entity's metadata.
+pub fn build_update_metadata(params: UpdateMetadataParams) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::UpdateMetadata {
+            metadata: pda::metadata(&params.authority, params.entity_id).0,
+            authority: params.authority,
+            system_program: system_program::ID,
+        },
+        ontora_ai::instruction::UpdateMetadata {
+            entity_id: params.entity_id,
+            data: params.data,
+        },
+    )
+}
+
+/// Arguments of [`build_initialize_admin_multisig`].
+#[derive(Clone, Debug)]
+pub struct InitializeAdminMultisigParams {
+    pub admin: Pubkey,
+    pub signers: Vec<Pubkey>,
+    pub threshold: u8,
+}
+
+/// Hand the admin role to a threshold multisig.
+pub fn build_initialize_admin_multisig(params: InitializeAdminMultisigParams) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::InitializeAdminMultisig {
+            platform_config: pda::platform_config().0,
+            admin_multisig: pda::admin_multisig().0,
+            admin: params.admin,
+            system_program: system_program::ID,
+        },
+        ontora_ai::instruction::InitializeAdminMultisig {
+            signers: params.signers,
+            threshold: params.threshold,
+        },
+    )
+}
+
+/// Arguments of [`build_propose_admin_action`].
+#[derive(Clone, Debug)]
+pub struct ProposeAdminActionParams {
+    pub proposer: Pubkey,
+    /// The multisig's current `proposal_count`, which becomes the action's id
+    pub action_id: u64,
+    pub action: AdminAction,
+}
+
+/// Propose an admin action for multisig approval.
+pub fn build_propose_admin_action(params: ProposeAdminActionParams) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::ProposeAdminAction {
+            admin_multisig: pda::admin_multisig().0,
+            proposal: pda::admin_action(params.action_id).0,
+            proposer: params.proposer,
+            system_program: system_program::ID,
+        },
+        ontora_ai::instruction::ProposeAdminAction { action: params.action },
+    )
+}
+
+/// Arguments of [`build_approve_admin_action`].
+#[derive(Clone, Debug)]
+pub struct ApproveAdminActionParams {
+    pub signer: Pubkey,
+    pub action_id: u64,
+}
+
+/// Approve a pending admin action.
+pub fn build_approve_admin_action(params: ApproveAdminActionParams) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::ApproveAdminAction {
+            admin_multisig: pda::admin_multisig().0,
+            proposal: pda::admin_action(params.action_id).0,
+            signer: params.signer,
+        },
+        ontora_ai::instruction::ApproveAdminAction {},
+    )
+}
+
+/// Arguments of [`build_execute_admin_action`].
+#[derive(Clone, Debug)]
+pub struct ExecuteAdminActionParams {
+    pub executor: Pubkey,
+    pub action_id: u64,
+}
+
+/// Execute an admin action that reached the approval threshold.
+pub fn build_execute_admin_action(params: ExecuteAdminActionParams) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::ExecuteAdminAction {
+            platform_config: pda::platform_config().0,
+            admin_multisig: pda::admin_multisig().0,
+            proposal: pda::admin_action(params.action_id).0,
+            executor: params.executor,
+        },
+        ontora_ai::instruction::ExecuteAdminAction {},
+    )
+}
+
+/// Arguments of [`build_execute_initialize_vaults`].
+#[derive(Clone, Debug)]
+pub struct ExecuteInitializeVaultsParams {
+    pub executor: Pubkey,
+    pub action_id: u64,
+    pub stake_mint: Pubkey,
+}
+
+/// Create the staking and reward vaults named by an approved
+/// `InitializeVaults` admin action. The executor pays for them.
+pub fn build_execute_initialize_vaults(params: ExecuteInitializeVaultsParams) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::ExecuteInitializeVaults {
+            platform_config: pda::platform_config().0,
+            admin_multisig: pda::admin_multisig().0,
+            proposal: pda::admin_action(params.action_id).0,
+            reward_pool: pda::reward_pool().0,
+            stake_mint: params.stake_mint,
+            staking_vault: pda::staking_vault().0,
+            reward_vault: pda::reward_vault().0,
+            executor: params.executor,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        },
+        ontora_ai::instruction::ExecuteInitializeVaults {},
+    )
+}
+
+/// Arguments of [`build_set_governance_realm`].
+#[derive(Clone, Debug)]
+pub struct SetGovernanceRealmParams {
+    pub admin: Pubkey,
+    pub realm: Pubkey,
+    /// The spl-governance governance account, which must belong to `realm`.
+    pub governance: Pubkey,
+}
+
+/// Let an spl-governance governance execute admin instructions through its
+/// native treasury (admin only).
+pub fn build_set_governance_realm(params: SetGovernanceRealmParams) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::SetGovernanceRealm {
+            platform_config: pda::platform_config().0,
+            governance_realm: pda::governance_realm().0,
+            governance: params.governance,
+            admin: params.admin,
+            system_program: system_program::ID,
+        },
+        ontora_ai::instruction::SetGovernanceRealm {
+            realm: params.realm,
+            governance: params.governance,
+        },
+    )
+}
+
+/// Adapt an update_platform_config, set_role or treasury_transfer instruction,
+/// built with the governance's native treasury as its admin or governance
+/// signer, for execution by a governance proposal: appends the governance
+/// realm account the program checks that signer against.
+pub fn with_governance_realm(mut instruction: Instruction) -> Instruction {
+    instruction.accounts.push(AccountMeta::new_readonly(pda::governance_realm().0, false));
+    instruction
+}
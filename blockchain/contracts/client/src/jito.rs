@@ -0,0 +1,322 @@
+//! Jito bundles, with the `jito` feature. Transactions that must land together
+//! and early, such as an epoch's reward distribution batches, go to a Jito
+//! block engine as one bundle: the transactions in order, then a tip
+//! transaction paying a Jito tip account. A bundle lands whole or not at all,
+//! so the tip is only paid when every transaction before it landed.
+//!
+//! When the block engine cannot be reached, the tip would overrun the budget,
+//! or the bundle expires unlanded, the transactions go out one at a time
+//! through `send_and_confirm_with_retry` instead.
+//!
+//!     let engine = JitoClient::new("https://mainnet.block-engine.jito.wtf");
+//!     let mut budget = TipBudget::new(TipStrategy::Percentile(50), 100_000, 1_000_000);
+//!     let outcome = send_bundle_with_fallback(&rpc, &engine, &builders, &[&crank], &mut budget, &policy)?;
+
+use std::str::FromStr;
+use std::time::Duration;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use reqwest::header::CONTENT_TYPE;
+use serde_json::{json, Value};
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
+use solana_sdk::pubkey;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::system_instruction;
+use solana_sdk::transaction::VersionedTransaction;
+
+use crate::error::ClientError;
+use crate::send::{send_and_confirm_with_retry, wait, RetryPolicy, SendOutcome, SendRpc};
+use crate::signer::OntoraSigner;
+use crate::transaction::{ComputeLimit, TxBuilder};
+
+/// Most transactions a block engine accepts in one bundle, the tip's included.
+pub const MAX_BUNDLE_TRANSACTIONS: usize = 5;
+/// Compute units requested by a tip transaction: the transfer and the limit instruction.
+pub const TIP_TRANSACTION_UNITS: u32 = 450;
+/// Where Jito's tip floor, the tips recently paid by landed bundles, is published.
+pub const DEFAULT_TIP_FLOOR_URL: &str = "https://bundles.jito.wtf/api/v1/bundles/tip_floor";
+const BLOCK_ENGINE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The accounts block engines take tips at; any one of them will do.
+pub const JITO_TIP_ACCOUNTS: [Pubkey; 8] = [
+    pubkey!("96gYZGLnJYVFmbjzopPSU6QiEV5fGqZNyN9nmNhvrZU5"),
+    pubkey!("HFqU5x63VTqvQss8hp11i4wVV8bD44PvwucfZ2bU7gRe"),
+    pubkey!("Cw8CFyM9FkoMi7K7Crf6HNQqf4uEMzpKw6QNghXLvLkY"),
+    pubkey!("ADaUMid9yfUytqMBgopwjb2DTLSokTSzL1zt6iGPaS49"),
+    pubkey!("DfXygSm4jCyNCybVYYK6DwvWqjKee8pbDmJGcLWNDXjh"),
+    pubkey!("ADuUkR4vqLUMWXxW9gh6D6L8pMSawimctcNZ5pGwDcEt"),
+    pubkey!("DttWaMuVvTiduZRnguLF7jNxTgiMBZ1hyAumKUiL2KRL"),
+    pubkey!("3AVi9Tg9Uo68tJfuvoKvqKNWKkC5wPdSSdeBnizKZ6jT"),
+];
+
+// Percentiles the tip floor reports, with their fields
+const TIP_FLOOR_PERCENTILES: [(u8, &str); 5] = [
+    (25, "landed_tips_25th_percentile"),
+    (50, "landed_tips_50th_percentile"),
+    (75, "landed_tips_75th_percentile"),
+    (95, "landed_tips_95th_percentile"),
+    (99, "landed_tips_99th_percentile"),
+];
+
+/// How much a bundle tips, in lamports.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TipStrategy {
+    Fixed(u64),
+    /// This much for every transaction in the bundle, the tip's excluded.
+    PerTransaction(u64),
+    /// This percentile (0-100) of the tips recently paid by landed bundles.
+    Percentile(u8),
+}
+
+impl FromStr for TipStrategy {
+    type Err = String;
+
+    /// Lamports such as `10000`, lamports per transaction such as `2500/tx`,
+    /// or a percentile of the tip floor such as `p50`.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if let Some(percentile) = value.strip_prefix('p') {
+            return match percentile.parse() {
+                Ok(percentile) if percentile <= 100 => Ok(TipStrategy::Percentile(percentile)),
+                _ => Err(format!("percentile `{}` is not between p0 and p100", value)),
+            };
+        }
+        let parsed = match value.strip_suffix("/tx") {
+            Some(lamports) => lamports.parse().map(TipStrategy::PerTransaction),
+            None => value.parse().map(TipStrategy::Fixed),
+        };
+        parsed.map_err(|_| format!("`{}` is not lamports, lamports per transaction such as `2500/tx`, or `p50`", value))
+    }
+}
+
+/// What a run may spend on tips.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TipBudget {
+    pub strategy: TipStrategy,
+    /// Most one bundle tips; a larger tip is cut to this.
+    pub max_tip: u64,
+    /// Lamports left for the bundles still to send.
+    pub remaining: u64,
+}
+
+impl TipBudget {
+    pub fn new(strategy: TipStrategy, max_tip: u64, total: u64) -> Self {
+        TipBudget { strategy, max_tip, remaining: total }
+    }
+
+    /// The tip for a bundle of `transactions` transactions, before the budget.
+    pub fn tip(&self, engine: &impl BlockEngine, transactions: usize) -> Result<u64, ClientError> {
+        let tip = match self.strategy {
+            TipStrategy::Fixed(lamports) => lamports,
+            TipStrategy::PerTransaction(lamports) => lamports.saturating_mul(transactions as u64),
+            TipStrategy::Percentile(percentile) => engine.tip_floor(percentile)?,
+        };
+        Ok(tip.min(self.max_tip))
+    }
+}
+
+/// The block engine calls a bundle makes, so tests can stand in for one.
+pub trait BlockEngine {
+    /// Submits `transactions` as one bundle, in order; answers the bundle id.
+    fn send_bundle(&self, transactions: &[VersionedTransaction]) -> Result<String, ClientError>;
+    /// The `percentile` of tips recently paid by landed bundles, in lamports.
+    fn tip_floor(&self, percentile: u8) -> Result<u64, ClientError>;
+}
+
+/// A Jito block engine reached over its JSON-RPC HTTP API.
+#[derive(Clone, Debug)]
+pub struct JitoClient {
+    url: String,
+    tip_floor_url: String,
+    client: reqwest::blocking::Client,
+}
+
+impl JitoClient {
+    /// The block engine at `url`, such as `https://mainnet.block-engine.jito.wtf`.
+    pub fn new(url: &str) -> Self {
+        let client = reqwest::blocking::Client::builder()
+            .timeout(BLOCK_ENGINE_TIMEOUT)
+            .build()
+            .expect("the HTTP client's settings are valid");
+        JitoClient {
+            url: url.trim_end_matches('/').to_string(),
+            tip_floor_url: DEFAULT_TIP_FLOOR_URL.to_string(),
+            client,
+        }
+    }
+
+    /// Reads the tip floor from `url` instead of Jito's public endpoint.
+    pub fn tip_floor_url(mut self, url: &str) -> Self {
+        self.tip_floor_url = url.to_string();
+        self
+    }
+
+    fn json(&self, request: reqwest::blocking::RequestBuilder) -> Result<Value, ClientError> {
+        let bundle_error = |error: reqwest::Error| ClientError::Bundle(error.to_string());
+        let response = request.send().map_err(bundle_error)?;
+        let status = response.status();
+        let text = response.text().map_err(bundle_error)?;
+        if !status.is_success() {
+            return Err(ClientError::Bundle(format!("block engine answered {}: {}", status, text.trim())));
+        }
+        serde_json::from_str(&text).map_err(|_| ClientError::Bundle(format!("block engine answered {:?}", text)))
+    }
+}
+
+impl BlockEngine for JitoClient {
+    fn send_bundle(&self, transactions: &[VersionedTransaction]) -> Result<String, ClientError> {
+        let encoded: Vec<String> = transactions
+            .iter()
+            .map(|transaction| STANDARD.encode(bincode::serialize(transaction).expect("transactions serialize")))
+            .collect();
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "sendBundle",
+            "params": [encoded, { "encoding": "base64" }],
+        });
+        let request = self
+            .client
+            .post(format!("{}/api/v1/bundles", self.url))
+            .header(CONTENT_TYPE, "application/json")
+            .body(body.to_string());
+        let response = self.json(request)?;
+        if let Some(error) = response.get("error") {
+            return Err(ClientError::Bundle(format!("bundle rejected: {}", error["message"])));
+        }
+        response["result"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| ClientError::Bundle(format!("no bundle id in {}", response)))
+    }
+
+    fn tip_floor(&self, percentile: u8) -> Result<u64, ClientError> {
+        let response = self.json(self.client.get(&self.tip_floor_url))?;
+        // The nearest reported percentile at or above the one asked for
+        let (_, field) = TIP_FLOOR_PERCENTILES
+            .iter()
+            .find(|(reported, _)| *reported >= percentile)
+            .unwrap_or(&TIP_FLOOR_PERCENTILES[TIP_FLOOR_PERCENTILES.len() - 1]);
+        let sol = response[0][*field]
+            .as_f64()
+            .ok_or_else(|| ClientError::Bundle(format!("no {} in the tip floor", field)))?;
+        Ok((sol * LAMPORTS_PER_SOL as f64).ceil() as u64)
+    }
+}
+
+/// Why transactions went out over RPC instead of as a bundle.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Fallback {
+    /// The block engine could not be reached, or refused the bundle.
+    Unavailable(String),
+    /// The bundle's tip is more than the budget has left.
+    BudgetExhausted { tip: u64, remaining: u64 },
+    /// The bundle was accepted but its blockhash expired before it landed.
+    Expired,
+}
+
+/// How a group of transactions was sent.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Route {
+    /// As a bundle that landed, tipping `tip` lamports to `tip_account`.
+    Bundle { id: String, tip: u64, tip_account: Pubkey },
+    /// One transaction at a time over RPC.
+    Rpc(Fallback),
+}
+
+/// The outcome of `send_bundle_with_fallback`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BundleOutcome {
+    pub route: Route,
+    /// One outcome per transaction, in order. Over RPC, sending stops at the
+    /// first transaction that did not land, so later ones have none.
+    pub outcomes: Vec<SendOutcome>,
+}
+
+// The tip account for a bundle, spread over the accounts by the bundle's first signature
+fn tip_account(first: &Signature) -> Pubkey {
+    JITO_TIP_ACCOUNTS[first.as_ref()[0] as usize % JITO_TIP_ACCOUNTS.len()]
+}
+
+// Sends the builders' transactions as one bundle; the reason to fall back if it
+// could not be sent or did not land
+fn send_bundle<S: OntoraSigner + ?Sized>(
+    rpc: &impl SendRpc,
+    engine: &impl BlockEngine,
+    builders: &[TxBuilder],
+    signers: &[&S],
+    budget: &mut TipBudget,
+    policy: &RetryPolicy,
+) -> Result<Result<BundleOutcome, Fallback>, ClientError> {
+    let tip = match budget.tip(engine, builders.len()) {
+        Ok(tip) => tip,
+        Err(error) => return Ok(Err(Fallback::Unavailable(error.to_string()))),
+    };
+    if tip > budget.remaining {
+        return Ok(Err(Fallback::BudgetExhausted { tip, remaining: budget.remaining }));
+    }
+
+    let (blockhash, last_valid_block_height) = rpc.latest_blockhash()?;
+    let mut transactions = builders
+        .iter()
+        .map(|builder| builder.transaction(rpc, signers, blockhash))
+        .collect::<Result<Vec<_>, _>>()?;
+    let tipper = signers[0].pubkey();
+    let tip_account = tip_account(&transactions[0].signatures[0]);
+    let tip_builder = TxBuilder::new(tipper)
+        .instruction(system_instruction::transfer(&tipper, &tip_account, tip))
+        .compute_limit(ComputeLimit::Fixed(TIP_TRANSACTION_UNITS));
+    transactions.push(tip_builder.transaction(rpc, &signers[..1], blockhash)?);
+
+    let id = match engine.send_bundle(&transactions) {
+        Ok(id) => id,
+        Err(error) => return Ok(Err(Fallback::Unavailable(error.to_string()))),
+    };
+    // The tip lands only with every transaction before it
+    let tip_signature = transactions[transactions.len() - 1].signatures[0];
+    match wait(rpc, &[tip_signature], last_valid_block_height, policy)? {
+        Some(SendOutcome::Confirmed(_)) => {
+            budget.remaining -= tip;
+            let outcomes = transactions[..builders.len()]
+                .iter()
+                .map(|transaction| SendOutcome::Confirmed(transaction.signatures[0]))
+                .collect();
+            Ok(Ok(BundleOutcome { route: Route::Bundle { id, tip, tip_account }, outcomes }))
+        }
+        Some(outcome) => Err(ClientError::Bundle(format!("bundle {} tip transaction: {:?}", id, outcome))),
+        None => Ok(Err(Fallback::Expired)),
+    }
+}
+
+/// Send the builders' transactions, in order, as one Jito bundle tipped from
+/// the first signer, which must sign them all; or, when the bundle can't be
+/// sent or does not land, one at a time over RPC. At most
+/// `MAX_BUNDLE_TRANSACTIONS - 1` builders fit, leaving room for the tip.
+pub fn send_bundle_with_fallback<S: OntoraSigner + ?Sized>(
+    rpc: &impl SendRpc,
+    engine: &impl BlockEngine,
+    builders: &[TxBuilder],
+    signers: &[&S],
+    budget: &mut TipBudget,
+    policy: &RetryPolicy,
+) -> Result<BundleOutcome, ClientError> {
+    if builders.is_empty() || builders.len() >= MAX_BUNDLE_TRANSACTIONS {
+        let message = format!("a bundle holds 1 to {} transactions besides its tip", MAX_BUNDLE_TRANSACTIONS - 1);
+        return Err(ClientError::Build(message));
+    }
+    let fallback = match send_bundle(rpc, engine, builders, signers, budget, policy)? {
+        Ok(outcome) => return Ok(outcome),
+        Err(fallback) => fallback,
+    };
+    let mut outcomes = Vec::new();
+    for builder in builders {
+        let outcome = send_and_confirm_with_retry(rpc, builder, signers, policy)?;
+        let landed = matches!(outcome, SendOutcome::Confirmed(_) | SendOutcome::AlreadyProcessed(_));
+        outcomes.push(outcome);
+        if !landed {
+            break;
+        }
+    }
+    Ok(BundleOutcome { route: Route::Rpc(fallback), outcomes })
+}
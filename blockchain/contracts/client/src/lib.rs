@@ -0,0 +1,73 @@
+//! Typed Rust client for the Ontora AI program: one instruction builder per
+//! instruction, the program's own PDA derivations, typed account fetches,
+//! filtered account listings, paged agent and proposal listings as async
+//! streams, typed events decoded from transaction logs, off-chain position
+//! math, a transaction builder that sets the compute budget and resolves
+//! accounts through address lookup tables, signers from keypairs, remote
+//! services and Ledgers, pre-flight summaries of what a transaction would do,
+//! a decoder naming a transaction's instructions for review, a send path that
+//! retries until the transaction lands or expires, a pool of rate-limited RPC
+//! endpoints that fails over between them, typed account subscriptions that
+//! survive websocket disconnects, merkle trees and proofs for airdrop lists, a
+//! wallet's activity timeline with running balances, and, with the `jito`
+//! feature, Jito bundles that fall back to that send path.
+//!
+//!     let instruction = build_stake_on_agent(StakeOnAgentParams { .. });
+//!     let stake = fetch_user_stake(&rpc, &user)?;
+//!     let stake = fetch_user_stake(&RpcPool::new(endpoints, commitment, PoolPolicy::default()), &user)?;
+//!     let agents = list_agents_by_owner(&rpc, &owner)?;
+//!     let proposals = ProposalPager::new(&nonblocking_rpc, ProposalFilter::default()).into_stream();
+//!     let position = fetch_position_summary(&rpc, &user)?;
+//!     let mut stake = subscribe_user_stake(&WsAccountPubsub::new(ws_url, nonblocking_rpc), &user);
+//!     let report = decode_transaction(&parse_transaction(&base64)?, &[])?.report(Some(9));
+//!     let events = decode_transaction_events(&rpc.get_transaction(&signature, encoding)?);
+//!     let timeline = Timeline::build(wallet_events(&wallet, &signature, &events));
+//!     let summary = TxBuilder::new(user).instruction(instruction.clone()).simulate_and_summarize(&rpc)?;
+//!     let signature = TxBuilder::new(user).instruction(instruction).send(&rpc, &[&keypair])?;
+//!     let outcome = send_and_confirm_with_retry(&rpc, &builder, &[&keypair], &RetryPolicy::default())?;
+//!     let outcome = send_bundle_with_fallback(&rpc, &engine, &builders, &[&keypair], &mut budget, &policy)?;
+
+pub mod accounts;
+pub mod decode;
+pub mod error;
+pub mod events;
+pub mod history;
+pub mod instructions;
+#[cfg(feature = "jito")]
+pub mod jito;
+pub mod lookup_table;
+pub mod merkle;
+pub mod pager;
+pub mod position;
+pub mod queries;
+pub mod rpc_pool;
+pub mod send;
+pub mod signer;
+pub mod simulate;
+pub mod subscribe;
+pub mod transaction;
+
+pub use accounts::*;
+pub use decode::*;
+pub use error::ClientError;
+pub use events::*;
+pub use history::*;
+pub use instructions::*;
+#[cfg(feature = "jito")]
+pub use jito::*;
+pub use lookup_table::*;
+pub use merkle::*;
+pub use pager::*;
+pub use position::*;
+pub use queries::*;
+pub use rpc_pool::*;
+pub use send::*;
+// Not `OntoraSigner`; see the `signer` module
+#[cfg(feature = "ledger")]
+pub use signer::LedgerSigner;
+pub use signer::{load_signer, sign_transaction, PartialTransaction, RemoteSigner, SignerSource};
+pub use simulate::*;
+pub use subscribe::*;
+pub use transaction::*;
+/// PDA derivations, shared with the program so the seeds cannot drift.
+pub use ontora_ai::pda;
@@ -0,0 +1,92 @@
+//! Address lookup tables for large batches. A v0 transaction names a table's
+//! accounts by one-byte index instead of 32-byte key, so a batch touching a
+//! few dozen agents still fits in one packet. Tables hold the platform's
+//! static accounts and the agent PDAs a caller uses often; pass them to
+//! `TxBuilder::lookup_table`.
+//!
+//!     let (create, table) = build_create_lookup_table(&authority, &payer, recent_slot);
+//!     let extends = build_extend_lookup_table(&table, &authority, &payer, &platform_lookup_addresses(&[mint]));
+//!     let builder = TxBuilder::new(user).lookup_table(fetch_lookup_table(&rpc, &table)?);
+
+use anchor_lang::solana_program::system_program;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::address_lookup_table::instruction::{create_lookup_table, extend_lookup_table};
+use solana_sdk::address_lookup_table::state::AddressLookupTable;
+use solana_sdk::address_lookup_table::{self, AddressLookupTableAccount};
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::error::ClientError;
+use crate::pda;
+
+/// Most addresses one extend instruction carries and still fits in a transaction.
+pub const MAX_EXTEND_ADDRESSES: usize = 30;
+
+/// The platform's static accounts, its staking and reward vaults, and for
+/// each of `mints` the mint and the treasury vault.
+pub fn platform_lookup_addresses(mints: &[Pubkey]) -> Vec<Pubkey> {
+    let mut addresses = vec![
+        pda::platform_config().0,
+        pda::fee_schedule().0,
+        pda::treasury().0,
+        pda::reward_pool().0,
+        pda::staking_vault().0,
+        pda::reward_vault().0,
+        spl_token::ID,
+        system_program::ID,
+    ];
+    for mint in mints {
+        addresses.push(*mint);
+        addresses.push(pda::treasury_vault(mint).0);
+    }
+    addresses
+}
+
+/// The PDAs of `agents`, each given as (owner, agent id).
+pub fn agent_lookup_addresses(agents: &[(Pubkey, u64)]) -> Vec<Pubkey> {
+    agents.iter().map(|(owner, agent_id)| pda::ai_agent(owner, *agent_id).0).collect()
+}
+
+/// `user`'s stake account and stake index entries on the agents `agent_ids`.
+pub fn stake_lookup_addresses(user: &Pubkey, agent_ids: &[u64]) -> Vec<Pubkey> {
+    let entries = agent_ids.iter().map(|agent_id| pda::stake_index(user, *agent_id).0);
+    std::iter::once(pda::user_stake(user).0).chain(entries).collect()
+}
+
+/// Create a lookup table owned by `authority`, and return it with its
+/// address. `recent_slot` must be a slot the cluster still has a hash for.
+pub fn build_create_lookup_table(authority: &Pubkey, payer: &Pubkey, recent_slot: u64) -> (Instruction, Pubkey) {
+    create_lookup_table(*authority, *payer, recent_slot)
+}
+
+/// Append `addresses` to `table`, one instruction per `MAX_EXTEND_ADDRESSES`;
+/// send each in its own transaction. New entries resolve from the next slot.
+pub fn build_extend_lookup_table(
+    table: &Pubkey,
+    authority: &Pubkey,
+    payer: &Pubkey,
+    addresses: &[Pubkey],
+) -> Vec<Instruction> {
+    addresses
+        .chunks(MAX_EXTEND_ADDRESSES)
+        .map(|chunk| extend_lookup_table(*table, *authority, Some(*payer), chunk.to_vec()))
+        .collect()
+}
+
+/// Decode a lookup table account from its raw data.
+pub fn decode_lookup_table(address: &Pubkey, data: &[u8]) -> Result<AddressLookupTableAccount, ClientError> {
+    let table =
+        AddressLookupTable::deserialize(data).map_err(|error| ClientError::Decode(*address, error.to_string()))?;
+    Ok(AddressLookupTableAccount { key: *address, addresses: table.addresses.to_vec() })
+}
+
+pub fn fetch_lookup_table(rpc: &RpcClient, address: &Pubkey) -> Result<AddressLookupTableAccount, ClientError> {
+    let account = rpc
+        .get_account_with_commitment(address, rpc.commitment())?
+        .value
+        .ok_or(ClientError::AccountNotFound(*address))?;
+    if account.owner != address_lookup_table::program::ID {
+        return Err(ClientError::Decode(*address, format!("owned by {}, not a lookup table", account.owner)));
+    }
+    decode_lookup_table(address, &account.data)
+}
@@ -0,0 +1,160 @@
+//! Merkle trees over airdrop lists of (wallet, amount). A leaf hashes a
+//! domain byte, the wallet and the amount as little-endian u64; a node hashes
+//! another domain byte and its two children in ascending order, so a proof is
+//! just the sibling hashes from leaf to root. A node without a sibling is
+//! carried up unchanged.
+//!
+//!     let tree = AirdropTree::new(parse_airdrop_csv(&std::fs::read_to_string("airdrop.csv")?)?)?;
+//!     std::fs::write("proofs.json", tree.proofs_json().to_string())?;
+//!     let proof = find_proof(&serde_json::from_str(&proofs)?, &wallet)?;
+
+use std::collections::BTreeSet;
+use std::str::FromStr;
+
+use anchor_lang::solana_program::hash::{hashv, Hash};
+use anchor_lang::solana_program::pubkey::Pubkey;
+use serde_json::{json, Value};
+
+use crate::error::ClientError;
+
+/// Domain byte of a leaf, so no leaf can pass for a node.
+pub const LEAF_PREFIX: u8 = 0;
+pub const NODE_PREFIX: u8 = 1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AirdropEntry {
+    pub wallet: Pubkey,
+    pub amount: u64,
+}
+
+pub fn leaf_hash(entry: &AirdropEntry) -> Hash {
+    hashv(&[&[LEAF_PREFIX], entry.wallet.as_ref(), &entry.amount.to_le_bytes()])
+}
+
+pub fn node_hash(left: &Hash, right: &Hash) -> Hash {
+    let (first, second) = if left <= right { (left, right) } else { (right, left) };
+    hashv(&[&[NODE_PREFIX], first.as_ref(), second.as_ref()])
+}
+
+/// Whether `proof` leads from `entry`'s leaf to `root`.
+pub fn verify_proof(root: &Hash, entry: &AirdropEntry, proof: &[Hash]) -> bool {
+    proof.iter().fold(leaf_hash(entry), |node, sibling| node_hash(&node, sibling)) == *root
+}
+
+fn invalid(message: String) -> ClientError {
+    ClientError::Airdrop(message)
+}
+
+/// The entries of a CSV with one `wallet,amount` per line. A first line that
+/// is not an entry is taken for a header; blank lines are skipped.
+pub fn parse_airdrop_csv(csv: &str) -> Result<Vec<AirdropEntry>, ClientError> {
+    let mut entries = Vec::new();
+    for (number, line) in csv.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        let entry = match fields.as_slice() {
+            [wallet, amount] => Pubkey::from_str(wallet)
+                .ok()
+                .zip(amount.parse().ok())
+                .map(|(wallet, amount)| AirdropEntry { wallet, amount }),
+            _ => None,
+        };
+        match entry {
+            Some(entry) => entries.push(entry),
+            None if number == 0 => {}
+            None => return Err(invalid(format!("line {}: expected wallet,amount, found {:?}", number + 1, line))),
+        }
+    }
+    Ok(entries)
+}
+
+/// A tree over an airdrop list, in the list's order.
+#[derive(Clone, Debug)]
+pub struct AirdropTree {
+    entries: Vec<AirdropEntry>,
+    // Leaves first, the root last
+    levels: Vec<Vec<Hash>>,
+}
+
+impl AirdropTree {
+    /// Builds the tree. Each wallet may appear once, as the program pays it
+    /// once.
+    pub fn new(entries: Vec<AirdropEntry>) -> Result<Self, ClientError> {
+        if entries.is_empty() {
+            return Err(invalid("the list is empty".to_string()));
+        }
+        let mut wallets = BTreeSet::new();
+        if let Some(entry) = entries.iter().find(|entry| !wallets.insert(entry.wallet)) {
+            return Err(invalid(format!("{} is listed twice", entry.wallet)));
+        }
+        let mut levels = vec![entries.iter().map(leaf_hash).collect::<Vec<_>>()];
+        while levels.last().is_some_and(|level| level.len() > 1) {
+            let level = levels.last().expect("levels start with the leaves");
+            let parents = level
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => node_hash(left, right),
+                    [single] => *single,
+                    _ => unreachable!("chunks of two"),
+                })
+                .collect();
+            levels.push(parents);
+        }
+        Ok(AirdropTree { entries, levels })
+    }
+
+    pub fn root(&self) -> Hash {
+        self.levels.last().expect("levels start with the leaves")[0]
+    }
+
+    pub fn entries(&self) -> &[AirdropEntry] {
+        &self.entries
+    }
+
+    /// Sibling hashes from the `index`th entry's leaf to the root.
+    pub fn proof(&self, index: usize) -> Vec<Hash> {
+        let mut proof = Vec::new();
+        let mut position = index;
+        for level in &self.levels[..self.levels.len() - 1] {
+            if let Some(sibling) = level.get(position ^ 1) {
+                proof.push(*sibling);
+            }
+            position /= 2;
+        }
+        proof
+    }
+
+    /// The proofs file: the root, and every entry with its proof, hashes in
+    /// base58.
+    pub fn proofs_json(&self) -> Value {
+        let entries: Vec<Value> = self
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| {
+                let proof: Vec<String> = self.proof(index).iter().map(Hash::to_string).collect();
+                json!({ "wallet": entry.wallet.to_string(), "amount": entry.amount, "proof": proof })
+            })
+            .collect();
+        json!({ "root": self.root().to_string(), "entries": entries })
+    }
+}
+
+/// `wallet`'s entry and proof in a proofs file.
+pub fn find_proof(proofs: &Value, wallet: &Pubkey) -> Result<(AirdropEntry, Vec<Hash>), ClientError> {
+    let wallet_name = wallet.to_string();
+    let entry = proofs["entries"]
+        .as_array()
+        .ok_or_else(|| invalid("the proofs file has no entries".to_string()))?
+        .iter()
+        .find(|entry| entry["wallet"].as_str() == Some(wallet_name.as_str()))
+        .ok_or_else(|| invalid(format!("{} is not in the proofs file", wallet)))?;
+    let amount = entry["amount"].as_u64().ok_or_else(|| invalid(format!("{} has no amount", wallet)))?;
+    let proof = entry["proof"]
+        .as_array()
+        .ok_or_else(|| invalid(format!("{} has no proof", wallet)))?
+        .iter()
+        .map(|hash| hash.as_str().and_then(|hash| Hash::from_str(hash).ok()))
+        .collect::<Option<Vec<Hash>>>()
+        .ok_or_else(|| invalid(format!("{} has a malformed proof", wallet)))?;
+    Ok((AirdropEntry { wallet: *wallet, amount }, proof))
+}
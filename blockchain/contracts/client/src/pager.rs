@@ -0,0 +1,238 @@
+//! Paged listings of agents and proposals, for clusters with more of them
+//! than one getProgramAccounts response returns before a public RPC times it
+//! out. A pager scans the matching addresses once with an empty data slice,
+//! then hydrates them in address order, a page of getMultipleAccounts calls
+//! at a time, as an async `Stream` or page by page.
+//!
+//! Pages are cut by address rather than offset, so accounts that change
+//! mid-pagination cannot shift an account into a second page: each is yielded
+//! at most once. One closed since the scan is skipped, and one whose data no
+//! longer matches the filters (a proposal finalized while the pager walks the
+//! active ones) is dropped. Accounts created after the scan are not listed;
+//! a pager started after `cursor()` picks them up.
+//!
+//!     let agents = AgentPager::new(&rpc, AgentFilter { owner: Some(owner) }).page_size(50).into_stream();
+//!     let mut agents = pin!(agents);
+//!     while let Some((address, agent)) = agents.try_next().await? { .. }
+
+use std::collections::VecDeque;
+use std::future::Future;
+
+use anchor_lang::solana_program::pubkey::Pubkey;
+use futures::stream::{self, Stream, TryStreamExt};
+use ontora_ai::state::{AiAgent, Proposal};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::account::Account;
+
+use crate::accounts::{decode_account, decode_zero_copy};
+use crate::error::ClientError;
+use crate::queries::{address_scan_config, discriminator_filter, MULTIPLE_ACCOUNTS_PAGE};
+
+/// Accounts this many at a time unless `page_size` says otherwise.
+pub const DEFAULT_PAGE_SIZE: usize = 100;
+
+/// Where a pager reads accounts from; implemented by the async `RpcClient`.
+pub trait AccountSource {
+    /// Addresses of the program's accounts matching `filters`, without their data.
+    fn matching_addresses(
+        &self,
+        filters: Vec<RpcFilterType>,
+    ) -> impl Future<Output = Result<Vec<Pubkey>, ClientError>> + Send;
+
+    /// The accounts at `addresses`, in order, None for those that do not exist.
+    /// Never asked for more than getMultipleAccounts accepts at once.
+    fn multiple_accounts(
+        &self,
+        addresses: &[Pubkey],
+    ) -> impl Future<Output = Result<Vec<Option<Account>>, ClientError>> + Send;
+}
+
+impl AccountSource for RpcClient {
+    async fn matching_addresses(&self, filters: Vec<RpcFilterType>) -> Result<Vec<Pubkey>, ClientError> {
+        let config = address_scan_config(filters, self.commitment());
+        let accounts = self.get_program_accounts_with_config(&ontora_ai::ID, config).await?;
+        Ok(accounts.into_iter().map(|(address, _)| address).collect())
+    }
+
+    async fn multiple_accounts(&self, addresses: &[Pubkey]) -> Result<Vec<Option<Account>>, ClientError> {
+        Ok(self.get_multiple_accounts_with_commitment(addresses, self.commitment()).await?.value)
+    }
+}
+
+/// Agents to list. Categories are kept off chain, in the indexer's
+/// `agent_profiles` table, so they cannot be filtered on here.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AgentFilter {
+    pub owner: Option<Pubkey>,
+}
+
+impl AgentFilter {
+    /// The getProgramAccounts filters selecting these agents.
+    pub fn filters(&self) -> Vec<RpcFilterType> {
+        let mut filters = vec![discriminator_filter::<AiAgent>()];
+        if let Some(owner) = self.owner {
+            let owner = owner.to_bytes().to_vec();
+            filters.push(RpcFilterType::Memcmp(Memcmp::new_raw_bytes(AiAgent::OWNER_OFFSET, owner)));
+        }
+        filters
+    }
+}
+
+/// Proposals to list.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProposalFilter {
+    /// One of the `PROPOSAL_*` status values.
+    pub status: Option<u8>,
+    pub creator: Option<Pubkey>,
+}
+
+impl ProposalFilter {
+    /// The getProgramAccounts filters selecting these proposals.
+    pub fn filters(&self) -> Vec<RpcFilterType> {
+        let mut filters = vec![discriminator_filter::<Proposal>()];
+        if let Some(status) = self.status {
+            filters.push(RpcFilterType::Memcmp(Memcmp::new_raw_bytes(Proposal::STATUS_OFFSET, vec![status])));
+        }
+        if let Some(creator) = self.creator {
+            let creator = creator.to_bytes().to_vec();
+            filters.push(RpcFilterType::Memcmp(Memcmp::new_raw_bytes(Proposal::CREATOR_OFFSET, creator)));
+        }
+        filters
+    }
+}
+
+// Whether `data` still passes `filters`, as the RPC node would check them
+fn matches(filters: &[RpcFilterType], data: &[u8]) -> bool {
+    filters.iter().all(|filter| match filter {
+        RpcFilterType::Memcmp(memcmp) => memcmp.bytes_match(data),
+        RpcFilterType::DataSize(size) => data.len() as u64 == *size,
+        _ => true,
+    })
+}
+
+/// Walks the program accounts matching a set of filters in address order.
+pub struct Pager<'a, S, T> {
+    source: &'a S,
+    filters: Vec<RpcFilterType>,
+    decode: fn(&Pubkey, &[u8]) -> Result<T, ClientError>,
+    page_size: usize,
+    cursor: Option<Pubkey>,
+    scanned: bool,
+    // Scanned addresses not yet paged through, in order
+    pending: VecDeque<Pubkey>,
+}
+
+/// Pages of agents, with their addresses.
+pub type AgentPager<'a, S> = Pager<'a, S, AiAgent>;
+
+/// Pages of proposals, with their addresses.
+pub type ProposalPager<'a, S> = Pager<'a, S, Proposal>;
+
+impl<'a, S: AccountSource> Pager<'a, S, AiAgent> {
+    pub fn new(source: &'a S, filter: AgentFilter) -> Self {
+        Pager::with_filters(source, filter.filters(), decode_account::<AiAgent>)
+    }
+}
+
+impl<'a, S: AccountSource> Pager<'a, S, Proposal> {
+    pub fn new(source: &'a S, filter: ProposalFilter) -> Self {
+        Pager::with_filters(source, filter.filters(), decode_zero_copy::<Proposal>)
+    }
+}
+
+impl<'a, S: AccountSource, T> Pager<'a, S, T> {
+    /// A pager over any program accounts matching `filters`, each decoded with `decode`.
+    pub fn with_filters(
+        source: &'a S,
+        filters: Vec<RpcFilterType>,
+        decode: fn(&Pubkey, &[u8]) -> Result<T, ClientError>,
+    ) -> Self {
+        Pager {
+            source,
+            filters,
+            decode,
+            page_size: DEFAULT_PAGE_SIZE,
+            cursor: None,
+            scanned: false,
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Hydrates `page_size` addresses per page (at least one).
+    pub fn page_size(mut self, page_size: usize) -> Self {
+        self.page_size = page_size.max(1);
+        self
+    }
+
+    /// Resumes after `cursor`, the `cursor()` of an earlier pager.
+    pub fn start_after(mut self, cursor: Pubkey) -> Self {
+        self.cursor = Some(cursor);
+        self
+    }
+
+    /// The last address paged through; a pager started after it continues
+    /// where this one stopped.
+    pub fn cursor(&self) -> Option<Pubkey> {
+        self.cursor
+    }
+
+    async fn scan(&mut self) -> Result<(), ClientError> {
+        if self.scanned {
+            return Ok(());
+        }
+        let mut addresses = self.source.matching_addresses(self.filters.clone()).await?;
+        addresses.sort_unstable();
+        addresses.dedup();
+        // None sorts first, so without a cursor every address is kept
+        addresses.retain(|address| Some(*address) > self.cursor);
+        self.pending = addresses.into();
+        self.scanned = true;
+        Ok(())
+    }
+
+    /// The next page of matching accounts, in address order, or None once
+    /// every scanned address has been paged through. A page that fails to
+    /// load is retried by the next call.
+    pub async fn next_page(&mut self) -> Result<Option<Vec<(Pubkey, T)>>, ClientError> {
+        self.scan().await?;
+        loop {
+            if self.pending.is_empty() {
+                return Ok(None);
+            }
+            let page: Vec<Pubkey> = self.pending.iter().take(self.page_size).copied().collect();
+            let mut accounts = Vec::with_capacity(page.len());
+            for chunk in page.chunks(MULTIPLE_ACCOUNTS_PAGE) {
+                let fetched = self.source.multiple_accounts(chunk).await?;
+                for (address, account) in chunk.iter().zip(fetched) {
+                    // Closed, or changed to no longer match, since the scan
+                    let Some(account) = account else {
+                        continue;
+                    };
+                    if account.owner != ontora_ai::ID || !matches(&self.filters, &account.data) {
+                        continue;
+                    }
+                    accounts.push((*address, (self.decode)(address, &account.data)?));
+                }
+            }
+            self.pending.drain(..page.len());
+            self.cursor = page.last().copied();
+            if !accounts.is_empty() {
+                return Ok(Some(accounts));
+            }
+        }
+    }
+
+    /// Every matching account, one at a time, loading a page whenever the
+    /// last is used up. The stream ends after the first error.
+    pub fn into_stream(self) -> impl Stream<Item = Result<(Pubkey, T), ClientError>> + 'a
+    where
+        T: 'a,
+    {
+        let pages = stream::try_unfold(self, |mut pager| async move {
+            let page = pager.next_page().await?;
+            Ok(page.map(|page| (stream::iter(page.into_iter().map(Ok::<_, ClientError>)), pager)))
+        });
+        pages.try_flatten()
+    }
+}
@@ -0,0 +1,128 @@
+//! Off-chain position math for wallets: what a user can claim now and how long
+//! until their stake unlocks, without simulating a transaction. Every figure is
+//! computed by the program's own methods over `ontora_ai::math`, so it matches
+//! what the program would pay to the lamport.
+
+use anchor_lang::solana_program::clock::Clock;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::solana_program::sysvar;
+use anchor_lang::AccountDeserialize;
+use ontora_ai::state::{FeeSchedule, FeeType, PlatformConfig, UserStake};
+use ontora_ai::AIAgent;
+use solana_client::rpc_client::RpcClient;
+
+use crate::accounts::{check_owner, decode_account};
+use crate::error::ClientError;
+use crate::pda;
+
+/// Rewards accrued on `user_stake` at `now`, before the claim fee: the
+/// accumulated balance plus one reward per whole epoch since the last claim.
+/// `None` if the amount overflows u64, which the program refuses too.
+pub fn pending_rewards(user_stake: &UserStake, platform_config: &PlatformConfig, now: i64) -> Option<u64> {
+    user_stake.pending_rewards(platform_config, now).ok()
+}
+
+/// Seconds until the stake in a legacy agent can be withdrawn under the
+/// platform's `cooldown_seconds`; 0 once it can.
+pub fn cooldown_remaining(agent: &AIAgent, platform_config: &PlatformConfig, now: i64) -> i64 {
+    agent.cooldown_remaining(platform_config, now)
+}
+
+/// What claiming a legacy agent's rewards at `now` pays, as (net, fee). Fee
+/// changes that have taken effect by `now` apply, as they do on chain.
+pub fn claimable_rewards(agent: &AIAgent, fee_schedule: &FeeSchedule, now: i64) -> Option<(u64, u64)> {
+    let mut fee_schedule = fee_schedule.clone();
+    fee_schedule.settle(now);
+    fee_schedule.apply_fee(agent.accumulated_rewards, FeeType::Claim).ok()
+}
+
+/// A user's staking position at one cluster time. Amounts for a stake the
+/// user does not hold are zero.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PositionSummary {
+    /// Cluster time the figures are computed for.
+    pub now: i64,
+    /// Total staked across agents, from the user's `UserStake`.
+    pub staked_amount: u64,
+    /// Rewards accrued on the `UserStake`, before the claim fee.
+    pub pending_rewards: u64,
+    /// Stake in the user's legacy agent.
+    pub agent_staked_amount: u64,
+    /// What claiming the legacy agent's rewards pays the user now.
+    pub claimable_rewards: u64,
+    /// The treasury's share of that claim.
+    pub claim_fee: u64,
+    /// Seconds until the legacy agent stake can be withdrawn.
+    pub cooldown_remaining: i64,
+}
+
+impl PositionSummary {
+    /// Summarize already decoded accounts, for callers that fetch them themselves.
+    pub fn compute(
+        platform_config: &PlatformConfig,
+        fee_schedule: &FeeSchedule,
+        user_stake: Option<&UserStake>,
+        agent: Option<&AIAgent>,
+        now: i64,
+    ) -> Result<Self, ClientError> {
+        let mut summary = PositionSummary { now, ..PositionSummary::default() };
+        if let Some(user_stake) = user_stake {
+            summary.staked_amount = user_stake.staked_amount;
+            summary.pending_rewards =
+                pending_rewards(user_stake, platform_config, now).ok_or(ClientError::Overflow("pending rewards"))?;
+        }
+        if let Some(agent) = agent {
+            let (net, fee) =
+                claimable_rewards(agent, fee_schedule, now).ok_or(ClientError::Overflow("claimable rewards"))?;
+            summary.agent_staked_amount = agent.staked_amount;
+            summary.claimable_rewards = net;
+            summary.claim_fee = fee;
+            summary.cooldown_remaining = cooldown_remaining(agent, platform_config, now);
+        }
+        Ok(summary)
+    }
+}
+
+// A fetched account, as its owner and data
+type Fetched = Option<(Pubkey, Vec<u8>)>;
+
+// Decode a program account, `None` if it does not exist
+fn decode_optional<T: AccountDeserialize>(address: &Pubkey, account: Fetched) -> Result<Option<T>, ClientError> {
+    let Some((owner, data)) = account else {
+        return Ok(None);
+    };
+    check_owner(address, &owner)?;
+    decode_account(address, &data).map(Some)
+}
+
+fn decode_required<T: AccountDeserialize>(address: &Pubkey, account: Fetched) -> Result<T, ClientError> {
+    decode_optional(address, account)?.ok_or(ClientError::AccountNotFound(*address))
+}
+
+/// Fetch `user`'s position in one getMultipleAccounts call. The clock sysvar is
+/// fetched alongside the accounts, so every figure is for the same slot.
+pub fn fetch_position_summary(rpc: &RpcClient, user: &Pubkey) -> Result<PositionSummary, ClientError> {
+    let addresses = [
+        pda::platform_config().0,
+        pda::fee_schedule().0,
+        pda::user_stake(user).0,
+        pda::legacy_ai_agent(user).0,
+        sysvar::clock::ID,
+    ];
+    let mut fetched = rpc
+        .get_multiple_accounts_with_commitment(&addresses, rpc.commitment())?
+        .value
+        .into_iter()
+        .map(|account| account.map(|account| (account.owner, account.data)));
+    let mut next = || fetched.next().flatten();
+
+    let platform_config: PlatformConfig = decode_required(&addresses[0], next())?;
+    let fee_schedule: FeeSchedule = decode_required(&addresses[1], next())?;
+    let user_stake: Option<UserStake> = decode_optional(&addresses[2], next())?;
+    let agent: Option<AIAgent> = decode_optional(&addresses[3], next())?;
+    let (_, clock_data) = next().ok_or(ClientError::AccountNotFound(sysvar::clock::ID))?;
+    let clock: Clock =
+        bincode::deserialize(&clock_data).map_err(|error| ClientError::Decode(sysvar::clock::ID, error.to_string()))?;
+
+    PositionSummary::compute(&platform_config, &fee_schedule, user_stake.as_ref(), agent.as_ref(), clock.unix_timestamp)
+}
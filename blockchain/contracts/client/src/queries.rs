@@ -0,0 +1,128 @@
+//! getProgramAccounts queries. Filters are built from the layout constants the
+//! program exports, so integrators never hand-count byte offsets. Listing asks
+//! for an empty data slice, and the matching accounts are then fetched in
+//! getMultipleAccounts pages.
+
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::Discriminator;
+use ontora_ai::state::{AiAgent, Proposal, StakeIndexEntry, PROPOSAL_ACTIVE};
+use ontora_ai::AIAgent;
+use solana_account_decoder::{UiAccountEncoding, UiDataSliceConfig};
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::commitment_config::CommitmentConfig;
+
+use crate::accounts::{decode_account, decode_zero_copy};
+use crate::error::ClientError;
+
+// Largest batch getMultipleAccounts accepts
+pub(crate) const MULTIPLE_ACCOUNTS_PAGE: usize = 100;
+
+pub(crate) fn discriminator_filter<T: Discriminator>() -> RpcFilterType {
+    RpcFilterType::Memcmp(Memcmp::new_raw_bytes(0, T::DISCRIMINATOR.to_vec()))
+}
+
+/// Filters matching every agent.
+pub fn agents_filters() -> Vec<RpcFilterType> {
+    vec![discriminator_filter::<AiAgent>()]
+}
+
+/// Filters matching every legacy agent, the accounts `distribute_rewards` pays.
+pub fn legacy_agents_filters() -> Vec<RpcFilterType> {
+    vec![discriminator_filter::<AIAgent>()]
+}
+
+/// Filters matching the agents registered by `owner`.
+pub fn agents_by_owner_filters(owner: &Pubkey) -> Vec<RpcFilterType> {
+    vec![
+        discriminator_filter::<AiAgent>(),
+        RpcFilterType::Memcmp(Memcmp::new_raw_bytes(AiAgent::OWNER_OFFSET, owner.to_bytes().to_vec())),
+    ]
+}
+
+/// Filters matching the stake index entries of `user`, one per agent staked on.
+pub fn stakes_by_user_filters(user: &Pubkey) -> Vec<RpcFilterType> {
+    vec![
+        discriminator_filter::<StakeIndexEntry>(),
+        RpcFilterType::Memcmp(Memcmp::new_raw_bytes(StakeIndexEntry::USER_OFFSET, user.to_bytes().to_vec())),
+    ]
+}
+
+/// Filters matching the proposals still open for voting.
+pub fn active_proposals_filters() -> Vec<RpcFilterType> {
+    vec![
+        discriminator_filter::<Proposal>(),
+        RpcFilterType::Memcmp(Memcmp::new_raw_bytes(Proposal::STATUS_OFFSET, vec![PROPOSAL_ACTIVE])),
+    ]
+}
+
+// Lists the program accounts matching `filters`; the empty data slice keeps
+// the listing itself from transferring any account data
+pub(crate) fn address_scan_config(
+    filters: Vec<RpcFilterType>,
+    commitment: CommitmentConfig,
+) -> RpcProgramAccountsConfig {
+    RpcProgramAccountsConfig {
+        filters: Some(filters),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            data_slice: Some(UiDataSliceConfig { offset: 0, length: 0 }),
+            commitment: Some(commitment),
+            ..RpcAccountInfoConfig::default()
+        },
+        ..RpcProgramAccountsConfig::default()
+    }
+}
+
+// Addresses of the program accounts matching `filters`
+fn matching_addresses(rpc: &RpcClient, filters: Vec<RpcFilterType>) -> Result<Vec<Pubkey>, ClientError> {
+    let config = address_scan_config(filters, rpc.commitment());
+    let accounts = rpc.get_program_accounts_with_config(&ontora_ai::ID, config)?;
+    Ok(accounts.into_iter().map(|(address, _)| address).collect())
+}
+
+fn fetch_matching<T>(
+    rpc: &RpcClient,
+    filters: Vec<RpcFilterType>,
+    decode: impl Fn(&Pubkey, &[u8]) -> Result<T, ClientError>,
+) -> Result<Vec<(Pubkey, T)>, ClientError> {
+    let addresses = matching_addresses(rpc, filters)?;
+    let mut accounts = Vec::with_capacity(addresses.len());
+    for page in addresses.chunks(MULTIPLE_ACCOUNTS_PAGE) {
+        let fetched = rpc.get_multiple_accounts_with_commitment(page, rpc.commitment())?.value;
+        for (address, account) in page.iter().zip(fetched) {
+            // Closed between the listing and the fetch
+            let Some(account) = account else {
+                continue;
+            };
+            accounts.push((*address, decode(address, &account.data)?));
+        }
+    }
+    Ok(accounts)
+}
+
+/// Every agent, with its address.
+pub fn list_agents(rpc: &RpcClient) -> Result<Vec<(Pubkey, AiAgent)>, ClientError> {
+    fetch_matching(rpc, agents_filters(), decode_account)
+}
+
+/// Every legacy agent, with its address.
+pub fn list_legacy_agents(rpc: &RpcClient) -> Result<Vec<(Pubkey, AIAgent)>, ClientError> {
+    fetch_matching(rpc, legacy_agents_filters(), decode_account)
+}
+
+/// Every agent registered by `owner`, with its address.
+pub fn list_agents_by_owner(rpc: &RpcClient, owner: &Pubkey) -> Result<Vec<(Pubkey, AiAgent)>, ClientError> {
+    fetch_matching(rpc, agents_by_owner_filters(owner), decode_account)
+}
+
+/// Every open stake of `user`, one index entry per agent, with its address.
+pub fn list_stakes_by_user(rpc: &RpcClient, user: &Pubkey) -> Result<Vec<(Pubkey, StakeIndexEntry)>, ClientError> {
+    fetch_matching(rpc, stakes_by_user_filters(user), decode_account)
+}
+
+/// Every proposal still open for voting, with its address.
+pub fn list_active_proposals(rpc: &RpcClient) -> Result<Vec<(Pubkey, Proposal)>, ClientError> {
+    fetch_matching(rpc, active_proposals_filters(), decode_zero_copy)
+}
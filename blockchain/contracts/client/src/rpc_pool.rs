@@ -0,0 +1,508 @@
+//! A pool of RPC endpoints behind one `RpcClient`. Each endpoint has its own
+//! token-bucket rate limit, and each request goes to the healthiest endpoint
+//! with a token to spare. A read that is rate limited, times out or finds the
+//! endpoint down is retried on another endpoint; a send is not, since it may
+//! still land. An endpoint failing `failure_threshold` times in a row leaves
+//! the rotation until, after `recovery_interval`, one read probes it and its
+//! answer decides whether it comes back.
+//!
+//! The pool derefs to its `RpcClient`, so the fetch helpers take it as they
+//! take a client:
+//!
+//!     let pool = RpcPool::new(
+//!         vec![Endpoint::new(primary_url, 10.0), Endpoint::new(fallback_url, 4.0)],
+//!         CommitmentConfig::confirmed(),
+//!         PoolPolicy::default(),
+//!     );
+//!     let stake = fetch_user_stake(&pool, &user)?;
+//!     for endpoint in pool.health() { .. }
+
+use std::io;
+use std::ops::Deref;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use futures::future::BoxFuture;
+use serde_json::Value;
+use solana_client::client_error::{ClientError as RpcClientError, Result as RpcResult};
+use solana_client::rpc_client::{RpcClient, RpcClientConfig};
+use solana_client::rpc_custom_error::{
+    JSON_RPC_SERVER_ERROR_NODE_UNHEALTHY, JSON_RPC_SERVER_ERROR_SEND_TRANSACTION_PREFLIGHT_FAILURE,
+};
+use solana_client::rpc_request::{RpcError, RpcRequest, RpcResponseErrorData};
+use solana_client::rpc_sender::{RpcSender, RpcTransportStats};
+use solana_sdk::commitment_config::CommitmentConfig;
+
+// Weight of the latest answer in an endpoint's latency average
+const LATENCY_WEIGHT: f64 = 0.2;
+
+/// One RPC endpoint and its rate limit.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Endpoint {
+    pub url: String,
+    /// Sustained rate the endpoint allows.
+    pub requests_per_second: f64,
+    /// Requests that may go out at once after a quiet spell.
+    pub burst: u32,
+}
+
+impl Endpoint {
+    /// An endpoint allowing a burst of one second's requests.
+    pub fn new(url: impl Into<String>, requests_per_second: f64) -> Self {
+        Endpoint { url: url.into(), requests_per_second, burst: requests_per_second.ceil().max(1.0) as u32 }
+    }
+
+    /// One endpoint per URL of a comma-separated list, each allowed
+    /// `requests_per_second`.
+    pub fn parse_list(urls: &str, requests_per_second: f64) -> Vec<Self> {
+        urls.split(',')
+            .map(str::trim)
+            .filter(|url| !url.is_empty())
+            .map(|url| Endpoint::new(url, requests_per_second))
+            .collect()
+    }
+}
+
+/// When endpoints leave and rejoin the rotation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PoolPolicy {
+    /// Consecutive failures that mark an endpoint unhealthy.
+    pub failure_threshold: u32,
+    /// How long an unhealthy endpoint rests before a read probes it.
+    pub recovery_interval: Duration,
+    /// Endpoints a read tries before giving up.
+    pub max_attempts: u32,
+    /// Per-request timeout of HTTP endpoints.
+    pub timeout: Duration,
+}
+
+impl Default for PoolPolicy {
+    fn default() -> Self {
+        PoolPolicy {
+            failure_threshold: 3,
+            recovery_interval: Duration::from_secs(10),
+            max_attempts: 3,
+            timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+/// How one request to one endpoint failed.
+#[derive(Debug)]
+pub enum TransportError {
+    /// The endpoint answered 429 Too Many Requests.
+    RateLimited,
+    /// No answer within the timeout.
+    Timeout,
+    /// The endpoint could not be reached, or reported itself unable to serve.
+    Unavailable(String),
+    /// The endpoint answered with an error any endpoint would give, such as a
+    /// failed preflight; it counts as an answer, not a failure.
+    Rpc(RpcClientError),
+}
+
+/// Sends one JSON-RPC request to one endpoint, so tests can stand in for a
+/// flaky endpoint.
+pub trait RpcTransport: Send + Sync {
+    fn send(&self, request: RpcRequest, params: Value) -> BoxFuture<'_, Result<Value, TransportError>>;
+}
+
+/// JSON-RPC over HTTP. Unlike the `RpcClient`'s own sender it doesn't wait
+/// out a 429, so the pool can move the request elsewhere at once.
+pub struct HttpTransport {
+    url: String,
+    client: reqwest::Client,
+    next_id: AtomicU64,
+}
+
+impl HttpTransport {
+    pub fn new(url: impl Into<String>, timeout: Duration) -> Self {
+        let client = reqwest::Client::builder().timeout(timeout).build().expect("the HTTP client builds");
+        HttpTransport { url: url.into(), client, next_id: AtomicU64::new(1) }
+    }
+}
+
+fn request_error(error: reqwest::Error) -> TransportError {
+    if error.is_timeout() {
+        TransportError::Timeout
+    } else {
+        TransportError::Unavailable(error.to_string())
+    }
+}
+
+// An error answer, typed as the `RpcClient`'s own sender types it so a failed
+// preflight reads the same through the pool
+fn answer_error(error: &Value) -> TransportError {
+    let code = error["code"].as_i64().unwrap_or_default();
+    let message = error["message"].as_str().unwrap_or_default().to_string();
+    let data = match code {
+        JSON_RPC_SERVER_ERROR_NODE_UNHEALTHY => return TransportError::Unavailable(message),
+        JSON_RPC_SERVER_ERROR_SEND_TRANSACTION_PREFLIGHT_FAILURE => serde_json::from_value(error["data"].clone())
+            .map_or(RpcResponseErrorData::Empty, RpcResponseErrorData::SendTransactionPreflightFailure),
+        _ => RpcResponseErrorData::Empty,
+    };
+    TransportError::Rpc(RpcError::RpcResponseError { code, message, data }.into())
+}
+
+impl RpcTransport for HttpTransport {
+    fn send(&self, request: RpcRequest, params: Value) -> BoxFuture<'_, Result<Value, TransportError>> {
+        let body = request.build_request_json(self.next_id.fetch_add(1, Ordering::Relaxed), params).to_string();
+        Box::pin(async move {
+            let response = self
+                .client
+                .post(&self.url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .body(body)
+                .send()
+                .await
+                .map_err(request_error)?;
+            let status = response.status();
+            if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+                return Err(TransportError::RateLimited);
+            }
+            if !status.is_success() {
+                return Err(TransportError::Unavailable(status.to_string()));
+            }
+            let bytes = response.bytes().await.map_err(request_error)?;
+            let mut answer: Value = serde_json::from_slice(&bytes)
+                .map_err(|error| TransportError::Rpc(RpcError::ParseError(error.to_string()).into()))?;
+            if answer["error"].is_object() {
+                return Err(answer_error(&answer["error"]));
+            }
+            Ok(answer["result"].take())
+        })
+    }
+}
+
+/// Where an endpoint stands in the rotation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EndpointState {
+    Healthy,
+    /// Out of rotation until its recovery probe.
+    Unhealthy,
+    /// A recovery probe is in flight.
+    Probing,
+}
+
+/// One endpoint's health, as `RpcPool::health` reports it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EndpointHealth {
+    pub url: String,
+    pub state: EndpointState,
+    pub requests: u64,
+    /// Requests that were rate limited, timed out or found the endpoint down.
+    pub failures: u64,
+    pub rate_limited: u64,
+    pub consecutive_failures: u32,
+    /// Moving average over answered requests; `None` before the first answer.
+    pub latency: Option<Duration>,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Circuit {
+    Closed,
+    Open { until: Instant },
+    HalfOpen,
+}
+
+struct TokenBucket {
+    tokens: f64,
+    capacity: f64,
+    rate: f64,
+    refilled: Instant,
+}
+
+impl TokenBucket {
+    fn new(endpoint: &Endpoint, now: Instant) -> Self {
+        let capacity = endpoint.burst.max(1) as f64;
+        TokenBucket { tokens: capacity, capacity, rate: endpoint.requests_per_second, refilled: now }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.refilled).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.capacity);
+        self.refilled = now;
+    }
+
+    // Time until a token is free; zero if one is
+    fn wait(&self) -> Duration {
+        if self.tokens >= 1.0 {
+            return Duration::ZERO;
+        }
+        Duration::from_secs_f64((1.0 - self.tokens) / self.rate.max(f64::MIN_POSITIVE)).min(Duration::from_secs(3600))
+    }
+}
+
+struct Slot {
+    bucket: TokenBucket,
+    circuit: Circuit,
+    requests: u64,
+    failures: u64,
+    rate_limited: u64,
+    consecutive_failures: u32,
+    latency: Option<Duration>,
+}
+
+impl Slot {
+    fn health(&self, url: &str) -> EndpointHealth {
+        let state = match self.circuit {
+            Circuit::Closed => EndpointState::Healthy,
+            Circuit::Open { .. } => EndpointState::Unhealthy,
+            Circuit::HalfOpen => EndpointState::Probing,
+        };
+        EndpointHealth {
+            url: url.to_string(),
+            state,
+            requests: self.requests,
+            failures: self.failures,
+            rate_limited: self.rate_limited,
+            consecutive_failures: self.consecutive_failures,
+            latency: self.latency,
+        }
+    }
+}
+
+enum Pick {
+    Ready(usize),
+    Wait(Duration),
+    Exhausted,
+}
+
+struct Shared {
+    urls: Vec<String>,
+    transports: Vec<Box<dyn RpcTransport>>,
+    slots: Mutex<Vec<Slot>>,
+    stats: Mutex<RpcTransportStats>,
+    policy: PoolPolicy,
+}
+
+// Requests that change state; a timed-out one may still take effect, so it is
+// never sent twice
+fn is_idempotent(request: RpcRequest) -> bool {
+    !matches!(request, RpcRequest::SendTransaction | RpcRequest::RequestAirdrop)
+}
+
+fn io_error(kind: io::ErrorKind, message: String) -> RpcClientError {
+    io::Error::new(kind, message).into()
+}
+
+// Pool failures surface as I/O errors, which `is_transient` retries
+fn client_error(url: &str, error: TransportError) -> RpcClientError {
+    match error {
+        TransportError::RateLimited => io_error(io::ErrorKind::Other, format!("{} rate limited the request", url)),
+        TransportError::Timeout => io_error(io::ErrorKind::TimedOut, format!("{} did not answer in time", url)),
+        TransportError::Unavailable(reason) => io_error(io::ErrorKind::Other, format!("{}: {}", url, reason)),
+        TransportError::Rpc(error) => error,
+    }
+}
+
+impl Shared {
+    fn slots(&self) -> MutexGuard<'_, Vec<Slot>> {
+        self.slots.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    // The endpoint to send to next, skipping `tried`, with its token taken: a
+    // read first probes an endpoint due for recovery, then every request goes
+    // to the healthy endpoint with the fewest recent failures and lowest latency
+    fn pick(&self, tried: &[bool], read: bool) -> Pick {
+        let now = Instant::now();
+        let mut slots = self.slots();
+        for slot in slots.iter_mut() {
+            slot.bucket.refill(now);
+        }
+        let mut probe = None;
+        let mut healthy = Vec::new();
+        for (index, slot) in slots.iter().enumerate().filter(|(index, _)| !tried[*index]) {
+            match slot.circuit {
+                Circuit::Open { until } if read && until <= now && slot.bucket.wait().is_zero() => {
+                    probe = probe.or(Some(index));
+                }
+                Circuit::Closed => healthy.push(index),
+                _ => {}
+            }
+        }
+        if let Some(index) = probe {
+            slots[index].circuit = Circuit::HalfOpen;
+            slots[index].bucket.tokens -= 1.0;
+            return Pick::Ready(index);
+        }
+        healthy.sort_by_key(|&index| (slots[index].consecutive_failures, slots[index].latency.unwrap_or_default()));
+        match healthy.iter().find(|&&index| slots[index].bucket.wait().is_zero()) {
+            Some(&index) => {
+                slots[index].bucket.tokens -= 1.0;
+                Pick::Ready(index)
+            }
+            None => healthy.iter().map(|&index| slots[index].bucket.wait()).min().map_or(Pick::Exhausted, Pick::Wait),
+        }
+    }
+
+    fn record(&self, index: usize, result: &Result<Value, TransportError>, elapsed: Duration) {
+        let now = Instant::now();
+        let policy = &self.policy;
+        let mut slots = self.slots();
+        let slot = &mut slots[index];
+        slot.requests += 1;
+        match result {
+            Ok(_) | Err(TransportError::Rpc(_)) => {
+                slot.consecutive_failures = 0;
+                slot.circuit = Circuit::Closed;
+                slot.latency = Some(match slot.latency {
+                    Some(average) => average.mul_f64(1.0 - LATENCY_WEIGHT) + elapsed.mul_f64(LATENCY_WEIGHT),
+                    None => elapsed,
+                });
+            }
+            Err(error) => {
+                slot.failures += 1;
+                slot.consecutive_failures += 1;
+                if let TransportError::RateLimited = error {
+                    // Whatever the bucket thinks, the endpoint wants a pause
+                    slot.rate_limited += 1;
+                    slot.bucket.tokens = slot.bucket.tokens.min(0.0);
+                }
+                let failed_probe = matches!(slot.circuit, Circuit::HalfOpen);
+                if failed_probe || slot.consecutive_failures >= policy.failure_threshold.max(1) {
+                    slot.circuit = Circuit::Open { until: now + policy.recovery_interval };
+                }
+            }
+        }
+    }
+
+    async fn send(&self, request: RpcRequest, params: Value) -> RpcResult<Value> {
+        let started = Instant::now();
+        let read = is_idempotent(request);
+        let attempts = if read { self.policy.max_attempts.max(1) } else { 1 };
+        let mut tried = vec![false; self.transports.len()];
+        let mut last_error = None;
+        let mut waited = Duration::ZERO;
+        for _ in 0..attempts {
+            let index = loop {
+                match self.pick(&tried, read) {
+                    Pick::Ready(index) => break Some(index),
+                    Pick::Wait(wait) => {
+                        tokio::time::sleep(wait).await;
+                        waited += wait;
+                    }
+                    Pick::Exhausted => break None,
+                }
+            };
+            let Some(index) = index else {
+                break;
+            };
+            tried[index] = true;
+            let sent = Instant::now();
+            let result = self.transports[index].send(request, params.clone()).await;
+            self.record(index, &result, sent.elapsed());
+            match result {
+                Ok(value) => {
+                    self.count(started, waited);
+                    return Ok(value);
+                }
+                Err(error) => {
+                    let answered = matches!(error, TransportError::Rpc(_));
+                    last_error = Some(client_error(&self.urls[index], error));
+                    if answered {
+                        break;
+                    }
+                }
+            }
+        }
+        self.count(started, waited);
+        Err(last_error.unwrap_or_else(|| io_error(io::ErrorKind::Other, "no healthy RPC endpoint".to_string())))
+    }
+
+    fn count(&self, started: Instant, waited: Duration) {
+        let mut stats = self.stats.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        stats.request_count += 1;
+        stats.elapsed_time += started.elapsed();
+        stats.rate_limited_time += waited;
+    }
+}
+
+struct PoolSender(Arc<Shared>);
+
+#[async_trait]
+impl RpcSender for PoolSender {
+    async fn send(&self, request: RpcRequest, params: Value) -> RpcResult<Value> {
+        self.0.send(request, params).await
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        self.0.stats.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+    }
+
+    fn url(&self) -> String {
+        self.0.urls.join(",")
+    }
+}
+
+/// Endpoints behind one `RpcClient`; see the module docs.
+pub struct RpcPool {
+    client: RpcClient,
+    shared: Arc<Shared>,
+}
+
+impl RpcPool {
+    /// A pool sending over HTTP to `endpoints`, the first preferred while
+    /// their health is equal.
+    pub fn new(endpoints: Vec<Endpoint>, commitment: CommitmentConfig, policy: PoolPolicy) -> Self {
+        let endpoints = endpoints
+            .into_iter()
+            .map(|endpoint| {
+                let transport: Box<dyn RpcTransport> = Box::new(HttpTransport::new(&endpoint.url, policy.timeout));
+                (endpoint, transport)
+            })
+            .collect();
+        Self::with_transports(endpoints, commitment, policy)
+    }
+
+    /// A pool sending to each endpoint through its own transport.
+    pub fn with_transports(
+        endpoints: Vec<(Endpoint, Box<dyn RpcTransport>)>,
+        commitment: CommitmentConfig,
+        policy: PoolPolicy,
+    ) -> Self {
+        assert!(!endpoints.is_empty(), "an RPC pool needs an endpoint");
+        let now = Instant::now();
+        let mut urls = Vec::new();
+        let (slots, transports) = endpoints
+            .into_iter()
+            .map(|(endpoint, transport)| {
+                urls.push(endpoint.url.clone());
+                let slot = Slot {
+                    bucket: TokenBucket::new(&endpoint, now),
+                    circuit: Circuit::Closed,
+                    requests: 0,
+                    failures: 0,
+                    rate_limited: 0,
+                    consecutive_failures: 0,
+                    latency: None,
+                };
+                (slot, transport)
+            })
+            .unzip();
+        let shared = Arc::new(Shared {
+            urls,
+            transports,
+            slots: Mutex::new(slots),
+            stats: Mutex::new(RpcTransportStats::default()),
+            policy,
+        });
+        let client = RpcClient::new_sender(PoolSender(shared.clone()), RpcClientConfig::with_commitment(commitment));
+        RpcPool { client, shared }
+    }
+
+    /// Every endpoint's health, in the order given.
+    pub fn health(&self) -> Vec<EndpointHealth> {
+        self.shared.slots().iter().zip(&self.shared.urls).map(|(slot, url)| slot.health(url)).collect()
+    }
+}
+
+impl Deref for RpcPool {
+    type Target = RpcClient;
+
+    fn deref(&self) -> &RpcClient {
+        &self.client
+    }
+}
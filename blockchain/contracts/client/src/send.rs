@@ -0,0 +1,259 @@
+//! Sending with retries. `send_and_confirm_with_retry` resubmits after
+//! transient RPC errors with jittered backoff, re-signs with a fresh
+//! blockhash once the old one expires, and never re-signs while an earlier
+//! signature could still land: before each new signature it checks whether
+//! any earlier one did, so a slow confirmation can't turn into a double stake.
+//!
+//!     match send_and_confirm_with_retry(&rpc, &builder, &[&user], &RetryPolicy::default())? {
+//!         SendOutcome::Confirmed(signature) | SendOutcome::AlreadyProcessed(signature) => { .. }
+//!         SendOutcome::Expired => { .. }
+//!         SendOutcome::FailedOnChain(error) => { .. }
+//!     }
+
+use std::time::Duration;
+
+use solana_client::client_error::ClientErrorKind;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::hash::Hash;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::{TransactionError, VersionedTransaction};
+
+use crate::error::ClientError;
+use crate::signer::OntoraSigner;
+use crate::transaction::{TransactionRpc, TxBuilder};
+
+/// How a retried send ended.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SendOutcome {
+    /// The transaction landed while its blockhash was valid.
+    Confirmed(Signature),
+    /// An earlier submission landed, found before re-signing or reported by
+    /// the cluster as a duplicate. Its effects apply; don't send again.
+    AlreadyProcessed(Signature),
+    /// Every blockhash expired without any submission landing.
+    Expired,
+    /// The transaction landed and failed.
+    FailedOnChain(TransactionError),
+}
+
+/// Where a signature stands on the cluster.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LandingStatus {
+    NotFound,
+    /// Landed, but not yet at the client's commitment.
+    Processed,
+    /// Landed at the client's commitment, with its result.
+    Committed(Result<(), TransactionError>),
+}
+
+/// The RPC calls a retried send makes, so tests can stand in for a cluster.
+pub trait SendRpc: TransactionRpc {
+    /// A fresh blockhash and the last block height it is valid at.
+    fn latest_blockhash(&self) -> Result<(Hash, u64), ClientError>;
+    fn submit(&self, transaction: &VersionedTransaction) -> Result<Signature, ClientError>;
+    fn landing_status(&self, signature: &Signature) -> Result<LandingStatus, ClientError>;
+    fn block_height(&self) -> Result<u64, ClientError>;
+}
+
+impl SendRpc for RpcClient {
+    fn latest_blockhash(&self) -> Result<(Hash, u64), ClientError> {
+        Ok(self.get_latest_blockhash_with_commitment(self.commitment())?)
+    }
+
+    fn submit(&self, transaction: &VersionedTransaction) -> Result<Signature, ClientError> {
+        Ok(self.send_transaction(transaction)?)
+    }
+
+    fn landing_status(&self, signature: &Signature) -> Result<LandingStatus, ClientError> {
+        let status = self.get_signature_statuses_with_history(&[*signature])?.value.pop().flatten();
+        Ok(match status {
+            None => LandingStatus::NotFound,
+            Some(status) if status.satisfies_commitment(self.commitment()) => match status.err {
+                None => LandingStatus::Committed(Ok(())),
+                Some(error) => LandingStatus::Committed(Err(error)),
+            },
+            Some(_) => LandingStatus::Processed,
+        })
+    }
+
+    fn block_height(&self) -> Result<u64, ClientError> {
+        Ok(self.get_block_height()?)
+    }
+}
+
+/// Limits and pacing of `send_and_confirm_with_retry`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Blockhashes to sign with before giving up as `Expired`.
+    pub max_blockhashes: u32,
+    /// Retries of each RPC call after a transient error.
+    pub max_rpc_retries: u32,
+    /// First retry delay; it doubles per retry, plus up to half again of jitter.
+    pub base_backoff: Duration,
+    pub max_backoff: Duration,
+    /// Delay between confirmation polls.
+    pub poll_interval: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_blockhashes: 3,
+            max_rpc_retries: 5,
+            base_backoff: Duration::from_millis(250),
+            max_backoff: Duration::from_secs(4),
+            poll_interval: Duration::from_millis(500),
+        }
+    }
+}
+
+impl RetryPolicy {
+    // Delay before retry `attempt` (from 0). The jitter is drawn from `seed`, so
+    // clients retrying different transactions spread out.
+    fn backoff(&self, attempt: u32, seed: u64) -> Duration {
+        let delay = self.base_backoff.saturating_mul(1 << attempt.min(16)).min(self.max_backoff);
+        let mixed = (seed ^ attempt as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15) >> 54;
+        delay + delay * mixed as u32 / 2048
+    }
+
+    // Run `call`, retrying transient errors
+    fn retry<T>(&self, seed: u64, mut call: impl FnMut() -> Result<T, ClientError>) -> Result<T, ClientError> {
+        let mut attempt = 0;
+        loop {
+            match call() {
+                Err(error) if is_transient(&error) && attempt < self.max_rpc_retries => {
+                    std::thread::sleep(self.backoff(attempt, seed));
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+/// Whether `error` is a network failure worth retrying, as opposed to a
+/// rejection that would recur.
+pub fn is_transient(error: &ClientError) -> bool {
+    match error {
+        ClientError::Rpc(error) => matches!(error.kind(), ClientErrorKind::Io(_) | ClientErrorKind::Reqwest(_)),
+        _ => false,
+    }
+}
+
+fn transaction_error(error: &ClientError) -> Option<TransactionError> {
+    match error {
+        ClientError::Rpc(error) => error.get_transaction_error(),
+        _ => None,
+    }
+}
+
+fn seed_of(signature: &Signature) -> u64 {
+    let mut bytes = [0; 8];
+    bytes.copy_from_slice(&signature.as_ref()[..8]);
+    u64::from_le_bytes(bytes)
+}
+
+enum Landed {
+    Outcome(SendOutcome),
+    InFlight,
+    Nothing,
+}
+
+// Whether any of `sent` has landed, the newest counting as confirmed
+fn landed(rpc: &impl SendRpc, sent: &[Signature], policy: &RetryPolicy) -> Result<Landed, ClientError> {
+    let mut landed = Landed::Nothing;
+    for (index, signature) in sent.iter().enumerate() {
+        let outcome = match policy.retry(seed_of(signature), || rpc.landing_status(signature))? {
+            LandingStatus::NotFound => continue,
+            LandingStatus::Processed => {
+                landed = Landed::InFlight;
+                continue;
+            }
+            LandingStatus::Committed(Err(error)) => SendOutcome::FailedOnChain(error),
+            LandingStatus::Committed(Ok(())) if index + 1 == sent.len() => SendOutcome::Confirmed(*signature),
+            LandingStatus::Committed(Ok(())) => SendOutcome::AlreadyProcessed(*signature),
+        };
+        return Ok(Landed::Outcome(outcome));
+    }
+    Ok(landed)
+}
+
+// Poll until a signature commits, or until `last_valid_block_height` has
+// passed with none in flight. Returns `None` in the latter case.
+pub(crate) fn wait(
+    rpc: &impl SendRpc,
+    sent: &[Signature],
+    last_valid_block_height: u64,
+    policy: &RetryPolicy,
+) -> Result<Option<SendOutcome>, ClientError> {
+    let seed = seed_of(&sent[sent.len() - 1]);
+    loop {
+        // Read the height first: a signature not found after its blockhash
+        // expired can no longer land
+        let height = policy.retry(seed, || rpc.block_height())?;
+        match landed(rpc, sent, policy)? {
+            Landed::Outcome(outcome) => return Ok(Some(outcome)),
+            Landed::Nothing if height > last_valid_block_height => return Ok(None),
+            Landed::Nothing | Landed::InFlight => {}
+        }
+        std::thread::sleep(policy.poll_interval);
+    }
+}
+
+// Every signature in `sent` has expired unseen. Look once more before signing
+// a new one or giving up, since status lookups can lag the chain
+fn recheck(rpc: &impl SendRpc, sent: &[Signature], policy: &RetryPolicy) -> Result<Option<SendOutcome>, ClientError> {
+    if sent.is_empty() {
+        return Ok(None);
+    }
+    Ok(wait(rpc, sent, 0, policy)?.map(|outcome| match outcome {
+        SendOutcome::Confirmed(signature) => SendOutcome::AlreadyProcessed(signature),
+        outcome => outcome,
+    }))
+}
+
+/// Send the transaction `builder` describes and wait until it commits,
+/// re-signing with a fresh blockhash at most `policy.max_blockhashes` times.
+pub fn send_and_confirm_with_retry<S: OntoraSigner + ?Sized>(
+    rpc: &impl SendRpc,
+    builder: &TxBuilder,
+    signers: &[&S],
+    policy: &RetryPolicy,
+) -> Result<SendOutcome, ClientError> {
+    let mut sent: Vec<Signature> = Vec::new();
+    for round in 0..policy.max_blockhashes {
+        let (blockhash, last_valid_block_height) = policy.retry(round as u64, || rpc.latest_blockhash())?;
+        if let Some(outcome) = recheck(rpc, &sent, policy)? {
+            return Ok(outcome);
+        }
+
+        let transaction = builder.transaction(rpc, signers, blockhash)?;
+        let signature = transaction.signatures[0];
+        sent.push(signature);
+        match policy.retry(seed_of(&signature), || rpc.submit(&transaction)) {
+            Ok(_) => {}
+            Err(error) => match transaction_error(&error) {
+                Some(TransactionError::AlreadyProcessed) => return Ok(SendOutcome::AlreadyProcessed(signature)),
+                // Expired before it was sent; it can't land, so sign again
+                Some(TransactionError::BlockhashNotFound) => continue,
+                _ => return Err(error),
+            },
+        }
+        if let Some(outcome) = wait(rpc, &sent, last_valid_block_height, policy)? {
+            return Ok(outcome);
+        }
+    }
+    Ok(recheck(rpc, &sent, policy)?.unwrap_or(SendOutcome::Expired))
+}
+
+impl TxBuilder {
+    /// `send_and_confirm_with_retry` for this builder.
+    pub fn send_with_retry<S: OntoraSigner + ?Sized>(
+        &self,
+        rpc: &impl SendRpc,
+        signers: &[&S],
+        policy: &RetryPolicy,
+    ) -> Result<SendOutcome, ClientError> {
+        send_and_confirm_with_retry(rpc, self, signers, policy)
+    }
+}
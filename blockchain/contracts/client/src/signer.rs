@@ -0,0 +1,307 @@
+//! Signers. `TxBuilder` signs through `OntoraSigner`, so treasury and admin
+//! transactions can be signed by a keypair in memory, a remote signing
+//! service, or a Ledger over USB (with the `ledger` feature). Transactions
+//! whose signers are not all at hand gather their signatures one at a time
+//! in a `PartialTransaction`, which travels between them as base64.
+//!
+//!     let signer = load_signer(&"ledger://?key=0".parse()?)?;
+//!     let transaction = builder.transaction(&rpc, &[signer.as_ref()], blockhash)?;
+//!
+//!     let mut partial = builder.partial_transaction(&rpc, blockhash)?;
+//!     partial.sign(&first)?;
+//!     let mut partial = PartialTransaction::from_base64(&partial.to_base64())?;
+//!     partial.sign(&second)?;
+//!     let transaction = partial.into_transaction()?;
+//!
+//! The trait stays out of the crate root: its methods share their names with
+//! `solana_sdk::signer::Signer`'s, so importing both would make every
+//! `keypair.pubkey()` ambiguous.
+
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use solana_sdk::message::VersionedMessage;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{read_keypair_file, Keypair, Signature, Signer};
+use solana_sdk::transaction::VersionedTransaction;
+
+use crate::error::ClientError;
+
+const REMOTE_SIGNER_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Something that holds a key and signs messages with it.
+pub trait OntoraSigner {
+    fn pubkey(&self) -> Pubkey;
+    /// Signs the serialized message of a transaction.
+    fn sign_message(&self, message: &[u8]) -> Result<Signature, ClientError>;
+}
+
+impl OntoraSigner for Keypair {
+    fn pubkey(&self) -> Pubkey {
+        Signer::pubkey(self)
+    }
+
+    fn sign_message(&self, message: &[u8]) -> Result<Signature, ClientError> {
+        Signer::try_sign_message(self, message).map_err(|error| ClientError::Signer(error.to_string()))
+    }
+}
+
+/// A signing service reached over HTTP. `GET <url>/pubkey` answers its public
+/// key, and `POST <url>/sign` with a message as the body answers the
+/// signature, both as base58 text. Signatures are checked before use, so a
+/// misbehaving service fails the signing instead of the transaction.
+#[derive(Clone, Debug)]
+pub struct RemoteSigner {
+    url: String,
+    pubkey: Pubkey,
+    client: reqwest::blocking::Client,
+}
+
+impl RemoteSigner {
+    /// Connects to the service at `url` and asks it for its public key.
+    pub fn connect(url: &str) -> Result<Self, ClientError> {
+        let client = reqwest::blocking::Client::builder()
+            .timeout(REMOTE_SIGNER_TIMEOUT)
+            .build()
+            .map_err(|error| ClientError::Signer(error.to_string()))?;
+        let url = url.trim_end_matches('/').to_string();
+        let text = remote_text(client.get(format!("{}/pubkey", url)))?;
+        let pubkey = Pubkey::from_str(text.trim())
+            .map_err(|_| ClientError::Signer(format!("{} answered {:?}, not a public key", url, text)))?;
+        Ok(RemoteSigner { url, pubkey, client })
+    }
+}
+
+// The body of a successful response to `request`
+fn remote_text(request: reqwest::blocking::RequestBuilder) -> Result<String, ClientError> {
+    let response = request.send().map_err(|error| ClientError::Signer(error.to_string()))?;
+    let status = response.status();
+    let text = response.text().map_err(|error| ClientError::Signer(error.to_string()))?;
+    if !status.is_success() {
+        return Err(ClientError::Signer(format!("remote signer answered {}: {}", status, text.trim())));
+    }
+    Ok(text)
+}
+
+impl OntoraSigner for RemoteSigner {
+    fn pubkey(&self) -> Pubkey {
+        self.pubkey
+    }
+
+    fn sign_message(&self, message: &[u8]) -> Result<Signature, ClientError> {
+        let request = self
+            .client
+            .post(format!("{}/sign", self.url))
+            .header("Content-Type", "application/octet-stream")
+            .body(message.to_vec());
+        let text = remote_text(request)?;
+        let signature = Signature::from_str(text.trim())
+            .map_err(|_| ClientError::Signer(format!("remote signer answered {:?}, not a signature", text)))?;
+        if !signature.verify(self.pubkey.as_ref(), message) {
+            return Err(ClientError::Signer(format!("remote signer's signature does not verify for {}", self.pubkey)));
+        }
+        Ok(signature)
+    }
+}
+
+/// A Ledger over USB, signing with the key at `44'/501'/<account>'/<change>'`.
+/// Every signature waits for approval on the device.
+#[cfg(feature = "ledger")]
+pub struct LedgerSigner {
+    keypair: solana_remote_wallet::remote_keypair::RemoteKeypair,
+}
+
+#[cfg(feature = "ledger")]
+impl LedgerSigner {
+    /// Connects to the first Ledger found, with the Solana app open.
+    pub fn connect(account: Option<u32>, change: Option<u32>) -> Result<Self, ClientError> {
+        use solana_remote_wallet::locator::Locator;
+        use solana_remote_wallet::remote_keypair::generate_remote_keypair;
+        use solana_remote_wallet::remote_wallet::maybe_wallet_manager;
+        use solana_sdk::derivation_path::DerivationPath;
+
+        let ledger_error = |error: &dyn std::fmt::Display| ClientError::Signer(format!("Ledger: {}", error));
+        let manager = maybe_wallet_manager()
+            .map_err(|error| ledger_error(&error))?
+            .ok_or_else(|| ClientError::Signer("no Ledger connected".to_string()))?;
+        let locator = Locator::new_from_path("usb://ledger").map_err(|error| ledger_error(&error))?;
+        let derivation_path = DerivationPath::new_bip44(account, change);
+        let keypair = generate_remote_keypair(locator, derivation_path, &manager, false, "ledger")
+            .map_err(|error| ledger_error(&error))?;
+        Ok(LedgerSigner { keypair })
+    }
+}
+
+#[cfg(feature = "ledger")]
+impl OntoraSigner for LedgerSigner {
+    fn pubkey(&self) -> Pubkey {
+        Signer::pubkey(&self.keypair)
+    }
+
+    fn sign_message(&self, message: &[u8]) -> Result<Signature, ClientError> {
+        Signer::try_sign_message(&self.keypair, message).map_err(|error| ClientError::Signer(error.to_string()))
+    }
+}
+
+/// Where a signer comes from, as a URI: a keypair file's path (optionally as
+/// `file://<path>`), `ledger://` with the derivation as `?key=<account>` or
+/// `?key=<account>/<change>`, or the `http://` or `https://` URL of a remote
+/// signer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SignerSource {
+    Keypair(PathBuf),
+    Ledger { account: Option<u32>, change: Option<u32> },
+    Remote(String),
+}
+
+impl FromStr for SignerSource {
+    type Err = String;
+
+    fn from_str(uri: &str) -> Result<Self, Self::Err> {
+        if let Some(rest) = uri.strip_prefix("ledger://") {
+            let rest = rest.trim_start_matches('/');
+            let key = match rest.strip_prefix('?') {
+                None if rest.is_empty() => None,
+                Some(query) => Some(query.strip_prefix("key=").ok_or_else(|| format!("`{}`: expected ?key=", uri))?),
+                None => return Err(format!("`{}`: expected ledger:// or ledger://?key=<account>[/<change>]", uri)),
+            };
+            let parse = |part: &str| part.parse().map_err(|_| format!("`{}`: `{}` is not a number", uri, part));
+            let (account, change) = match key.map(|key| key.split_once('/').unwrap_or((key, ""))) {
+                None => (None, None),
+                Some((account, "")) => (Some(parse(account)?), None),
+                Some((account, change)) => (Some(parse(account)?), Some(parse(change)?)),
+            };
+            return Ok(SignerSource::Ledger { account, change });
+        }
+        if uri.starts_with("http://") || uri.starts_with("https://") {
+            return Ok(SignerSource::Remote(uri.to_string()));
+        }
+        if uri.is_empty() {
+            return Err("empty signer".to_string());
+        }
+        Ok(SignerSource::Keypair(PathBuf::from(uri.strip_prefix("file://").unwrap_or(uri))))
+    }
+}
+
+/// Opens the signer `source` names.
+pub fn load_signer(source: &SignerSource) -> Result<Box<dyn OntoraSigner>, ClientError> {
+    match source {
+        SignerSource::Keypair(path) => {
+            let keypair = read_keypair_file(path)
+                .map_err(|error| ClientError::Signer(format!("could not read {}: {}", path.display(), error)))?;
+            Ok(Box::new(keypair))
+        }
+        #[cfg(feature = "ledger")]
+        SignerSource::Ledger { account, change } => Ok(Box::new(LedgerSigner::connect(*account, *change)?)),
+        #[cfg(not(feature = "ledger"))]
+        SignerSource::Ledger { .. } => Err(ClientError::Signer("ledger:// needs the `ledger` feature".to_string())),
+        SignerSource::Remote(url) => Ok(Box::new(RemoteSigner::connect(url)?)),
+    }
+}
+
+/// A transaction gathering its signatures, one signer at a time.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PartialTransaction {
+    // Unsigned slots hold the default signature
+    transaction: VersionedTransaction,
+}
+
+impl PartialTransaction {
+    pub fn new(message: VersionedMessage) -> Self {
+        let signatures = vec![Signature::default(); message.header().num_required_signatures as usize];
+        PartialTransaction { transaction: VersionedTransaction { signatures, message } }
+    }
+
+    pub fn message(&self) -> &VersionedMessage {
+        &self.transaction.message
+    }
+
+    /// Keys the message needs signatures from, the fee payer first.
+    pub fn required_signers(&self) -> &[Pubkey] {
+        let keys = self.transaction.message.static_account_keys();
+        &keys[..self.transaction.signatures.len().min(keys.len())]
+    }
+
+    /// Required signers that have not signed yet.
+    pub fn missing_signers(&self) -> Vec<Pubkey> {
+        let signed = self.transaction.signatures.iter();
+        self.required_signers()
+            .iter()
+            .zip(signed)
+            .filter(|(_, signature)| **signature == Signature::default())
+            .map(|(key, _)| *key)
+            .collect()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.missing_signers().is_empty()
+    }
+
+    // Slot of `pubkey`'s signature
+    fn position(&self, pubkey: &Pubkey) -> Result<usize, ClientError> {
+        self.required_signers()
+            .iter()
+            .position(|key| key == pubkey)
+            .ok_or_else(|| ClientError::Signer(format!("{} is not a signer of this transaction", pubkey)))
+    }
+
+    pub fn sign(&mut self, signer: &(impl OntoraSigner + ?Sized)) -> Result<(), ClientError> {
+        let position = self.position(&signer.pubkey())?;
+        let signature = signer.sign_message(&self.transaction.message.serialize())?;
+        self.transaction.signatures[position] = signature;
+        Ok(())
+    }
+
+    /// Adds `pubkey`'s signature made elsewhere, after checking it against the
+    /// message.
+    pub fn add_signature(&mut self, pubkey: &Pubkey, signature: Signature) -> Result<(), ClientError> {
+        let position = self.position(pubkey)?;
+        if !signature.verify(pubkey.as_ref(), &self.transaction.message.serialize()) {
+            return Err(ClientError::Signer(format!("signature does not verify for {}", pubkey)));
+        }
+        self.transaction.signatures[position] = signature;
+        Ok(())
+    }
+
+    /// The signed transaction; fails while any signer is missing.
+    pub fn into_transaction(self) -> Result<VersionedTransaction, ClientError> {
+        let missing = self.missing_signers();
+        if !missing.is_empty() {
+            let missing: Vec<String> = missing.iter().map(Pubkey::to_string).collect();
+            return Err(ClientError::Signer(format!("missing signatures from {}", missing.join(", "))));
+        }
+        Ok(self.transaction)
+    }
+
+    /// The message and the signatures so far, to pass to the next signer.
+    pub fn to_base64(&self) -> String {
+        STANDARD.encode(bincode::serialize(&self.transaction).expect("transactions serialize"))
+    }
+
+    pub fn from_base64(encoded: &str) -> Result<Self, ClientError> {
+        let malformed =
+            |error: &dyn std::fmt::Display| ClientError::Signer(format!("malformed transaction: {}", error));
+        let bytes = STANDARD.decode(encoded.trim()).map_err(|error| malformed(&error))?;
+        let transaction: VersionedTransaction = bincode::deserialize(&bytes).map_err(|error| malformed(&error))?;
+        if transaction.signatures.len() != transaction.message.header().num_required_signatures as usize {
+            return Err(malformed(&"signature count does not match the message"));
+        }
+        Ok(PartialTransaction { transaction })
+    }
+}
+
+/// Signs `message` with every one of `signers`, which must be exactly the
+/// signers it requires.
+pub fn sign_transaction<S: OntoraSigner + ?Sized>(
+    message: VersionedMessage,
+    signers: &[&S],
+) -> Result<VersionedTransaction, ClientError> {
+    let mut partial = PartialTransaction::new(message);
+    for signer in signers {
+        partial.sign(*signer)?;
+    }
+    partial.into_transaction()
+}
@@ -0,0 +1,292 @@
+//! Pre-flight summaries. `simulate_and_summarize` simulates a transaction
+//! with the post-state of every account it writes, and reports what sending
+//! it would do: token balance changes, `UserStake` and `RewardPool` changes,
+//! the events it would emit, its fee and compute units, and the error it
+//! would fail with.
+//!
+//!     let summary = builder.simulate_and_summarize(&rpc)?;
+//!     if let Some(error) = &summary.error { .. }
+//!     for delta in &summary.token_deltas { println!("{} {:+}", delta.account, delta.change()); }
+//!
+//! Accounts resolved through a lookup table are not watched, and the
+//! pre-state is read just before the simulation, so a write landing in
+//! between shows up in the deltas.
+
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::{AccountDeserialize, Discriminator};
+use ontora_ai::state::UserStake;
+use ontora_ai::RewardPool;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcSimulateTransactionAccountsConfig, RpcSimulateTransactionConfig};
+use solana_sdk::account::Account;
+use solana_sdk::compute_budget;
+use solana_sdk::hash::Hash;
+use solana_sdk::message::VersionedMessage;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::{TransactionError, VersionedTransaction};
+
+use crate::accounts::decode_account;
+use crate::error::ClientError;
+use crate::events::{decode_events, OntoraEvent};
+use crate::pda;
+use crate::transaction::{TransactionRpc, TxBuilder, DEFAULT_INSTRUCTION_UNITS, MAX_COMPUTE_UNIT_LIMIT};
+
+/// Base fee charged per signature, in lamports.
+pub const LAMPORTS_PER_SIGNATURE: u64 = 5_000;
+// Compute budget instruction tags, as the runtime reads them
+const SET_COMPUTE_UNIT_LIMIT: u8 = 2;
+const SET_COMPUTE_UNIT_PRICE: u8 = 3;
+const MICRO_LAMPORTS_PER_LAMPORT: u128 = 1_000_000;
+
+/// What a simulation reported.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Simulation {
+    pub error: Option<TransactionError>,
+    pub logs: Vec<String>,
+    pub units_consumed: Option<u64>,
+    /// State after the transaction of each requested address, in order.
+    pub accounts: Vec<Option<Account>>,
+}
+
+/// The RPC calls a summary makes, so tests can stand in for a cluster.
+pub trait SimulateRpc: TransactionRpc {
+    /// Current state of `addresses`, `None` for those that don't exist.
+    fn accounts(&self, addresses: &[Pubkey]) -> Result<Vec<Option<Account>>, ClientError>;
+    /// Simulates `transaction` without checking its signatures or blockhash,
+    /// returning the state of `addresses` after it.
+    fn simulate(&self, transaction: &VersionedTransaction, addresses: &[Pubkey]) -> Result<Simulation, ClientError>;
+}
+
+impl SimulateRpc for RpcClient {
+    fn accounts(&self, addresses: &[Pubkey]) -> Result<Vec<Option<Account>>, ClientError> {
+        Ok(self.get_multiple_accounts_with_commitment(addresses, self.commitment())?.value)
+    }
+
+    fn simulate(&self, transaction: &VersionedTransaction, addresses: &[Pubkey]) -> Result<Simulation, ClientError> {
+        let config = RpcSimulateTransactionConfig {
+            sig_verify: false,
+            replace_recent_blockhash: true,
+            commitment: Some(self.commitment()),
+            accounts: Some(RpcSimulateTransactionAccountsConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                addresses: addresses.iter().map(Pubkey::to_string).collect(),
+            }),
+            ..RpcSimulateTransactionConfig::default()
+        };
+        let result = self.simulate_transaction_with_config(transaction, config)?.value;
+        let accounts = match result.accounts {
+            Some(accounts) => accounts
+                .into_iter()
+                .zip(addresses)
+                .map(|(account, address)| match account {
+                    None => Ok(None),
+                    Some(account) => account
+                        .decode()
+                        .map(Some)
+                        .ok_or_else(|| ClientError::Decode(*address, "undecodable simulated account".to_string())),
+                })
+                .collect::<Result<_, _>>()?,
+            None => return Err(ClientError::Simulation("no account states reported".to_string())),
+        };
+        Ok(Simulation {
+            error: result.err,
+            logs: result.logs.unwrap_or_default(),
+            units_consumed: result.units_consumed,
+            accounts,
+        })
+    }
+}
+
+/// A token account's balance before and after the transaction; 0 where
+/// the account does not exist.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TokenDelta {
+    pub account: Pubkey,
+    pub mint: Pubkey,
+    pub owner: Pubkey,
+    pub before: u64,
+    pub after: u64,
+}
+
+impl TokenDelta {
+    pub fn change(&self) -> i128 {
+        self.after as i128 - self.before as i128
+    }
+}
+
+/// A `UserStake` before and after the transaction; zeros where it does not
+/// exist.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StakeDelta {
+    pub address: Pubkey,
+    pub user: Pubkey,
+    pub staked_before: u64,
+    pub staked_after: u64,
+    pub rewards_before: u64,
+    pub rewards_after: u64,
+}
+
+/// The reward pool's recorded total before and after the transaction.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PoolDelta {
+    pub total_rewards_before: u64,
+    pub total_rewards_after: u64,
+}
+
+/// What sending a transaction would do. Only accounts the transaction
+/// changes are listed.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TxSummary {
+    /// Fee the payer would be charged, in lamports.
+    pub fee: u64,
+    pub units_consumed: Option<u64>,
+    /// The error the transaction would fail with; nothing else applies then.
+    pub error: Option<TransactionError>,
+    pub logs: Vec<String>,
+    pub events: Vec<OntoraEvent>,
+    pub token_deltas: Vec<TokenDelta>,
+    pub stake_deltas: Vec<StakeDelta>,
+    pub reward_pool: Option<PoolDelta>,
+}
+
+/// Fee `message` pays: the base fee for each signature, plus its compute-unit
+/// limit at its compute-unit price.
+pub fn transaction_fee(message: &VersionedMessage) -> u64 {
+    let keys = message.static_account_keys();
+    let (mut limit, mut price, mut instructions) = (None, 0u64, 0u64);
+    for instruction in message.instructions() {
+        let data = &instruction.data;
+        if *instruction.program_id(keys) != compute_budget::ID {
+            instructions += 1;
+            continue;
+        }
+        match data.first() {
+            Some(&SET_COMPUTE_UNIT_LIMIT) if data.len() >= 5 => {
+                limit = Some(u32::from_le_bytes(data[1..5].try_into().expect("four bytes")));
+            }
+            Some(&SET_COMPUTE_UNIT_PRICE) if data.len() >= 9 => {
+                price = u64::from_le_bytes(data[1..9].try_into().expect("eight bytes"));
+            }
+            _ => {}
+        }
+    }
+    let default_limit = (instructions * DEFAULT_INSTRUCTION_UNITS as u64).min(MAX_COMPUTE_UNIT_LIMIT as u64);
+    let limit = limit.map_or(default_limit, |limit| limit.min(MAX_COMPUTE_UNIT_LIMIT) as u64);
+    let priority = (price as u128 * limit as u128).div_ceil(MICRO_LAMPORTS_PER_LAMPORT) as u64;
+    message.header().num_required_signatures as u64 * LAMPORTS_PER_SIGNATURE + priority
+}
+
+// Accounts of `message` it may write, the ones a summary watches
+fn written_accounts(message: &VersionedMessage) -> Vec<Pubkey> {
+    let keys = message.static_account_keys();
+    (0..keys.len()).filter(|&index| message.is_maybe_writable(index)).map(|index| keys[index]).collect()
+}
+
+fn token_amount(account: &Option<Account>) -> Option<spl_token::state::Account> {
+    let account = account.as_ref().filter(|account| account.owner == spl_token::ID)?;
+    spl_token::state::Account::unpack(&account.data).ok()
+}
+
+fn program_account<T: AccountDeserialize + Discriminator>(
+    address: &Pubkey,
+    account: &Option<Account>,
+) -> Result<Option<T>, ClientError> {
+    match account {
+        Some(account) if account.owner == ontora_ai::ID && account.data.starts_with(&T::DISCRIMINATOR) => {
+            decode_account(address, &account.data).map(Some)
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Summarizes `simulation` of `message`, given the state of `addresses`
+/// before it, for callers that simulate themselves.
+pub fn summarize(
+    message: &VersionedMessage,
+    addresses: &[Pubkey],
+    before: &[Option<Account>],
+    simulation: Simulation,
+) -> Result<TxSummary, ClientError> {
+    let mut summary = TxSummary {
+        fee: transaction_fee(message),
+        units_consumed: simulation.units_consumed,
+        events: decode_events(&simulation.logs),
+        logs: simulation.logs,
+        ..TxSummary::default()
+    };
+    if simulation.error.is_some() {
+        summary.error = simulation.error;
+        return Ok(summary);
+    }
+    if simulation.accounts.len() != addresses.len() || before.len() != addresses.len() {
+        return Err(ClientError::Simulation("account states do not match the addresses".to_string()));
+    }
+
+    let reward_pool = pda::reward_pool().0;
+    for ((address, before), after) in addresses.iter().zip(before).zip(&simulation.accounts) {
+        let (token_before, token_after) = (token_amount(before), token_amount(after));
+        if let Some(token) = token_after.or(token_before) {
+            let delta = TokenDelta {
+                account: *address,
+                mint: token.mint,
+                owner: token.owner,
+                before: token_before.map_or(0, |token| token.amount),
+                after: token_after.map_or(0, |token| token.amount),
+            };
+            if delta.before != delta.after {
+                summary.token_deltas.push(delta);
+            }
+            continue;
+        }
+        if *address == reward_pool {
+            let total = |pool: Option<RewardPool>| pool.map_or(0, |pool| pool.total_rewards);
+            let delta = PoolDelta {
+                total_rewards_before: total(program_account(address, before)?),
+                total_rewards_after: total(program_account(address, after)?),
+            };
+            if delta.total_rewards_before != delta.total_rewards_after {
+                summary.reward_pool = Some(delta);
+            }
+            continue;
+        }
+        let stake_before: Option<UserStake> = program_account(address, before)?;
+        let stake_after: Option<UserStake> = program_account(address, after)?;
+        if let Some(user) = stake_after.as_ref().or(stake_before.as_ref()).map(|stake| stake.user) {
+            let delta = StakeDelta {
+                address: *address,
+                user,
+                staked_before: stake_before.as_ref().map_or(0, |stake| stake.staked_amount),
+                staked_after: stake_after.as_ref().map_or(0, |stake| stake.staked_amount),
+                rewards_before: stake_before.as_ref().map_or(0, |stake| stake.accumulated_rewards),
+                rewards_after: stake_after.as_ref().map_or(0, |stake| stake.accumulated_rewards),
+            };
+            if (delta.staked_before, delta.rewards_before) != (delta.staked_after, delta.rewards_after) {
+                summary.stake_deltas.push(delta);
+            }
+        }
+    }
+    Ok(summary)
+}
+
+/// Simulates `transaction` and summarizes what sending it would do. Its
+/// signatures and blockhash are not checked, so it may be unsigned.
+pub fn simulate_and_summarize(
+    rpc: &impl SimulateRpc,
+    transaction: &VersionedTransaction,
+) -> Result<TxSummary, ClientError> {
+    let addresses = written_accounts(&transaction.message);
+    let before = rpc.accounts(&addresses)?;
+    let simulation = rpc.simulate(transaction, &addresses)?;
+    summarize(&transaction.message, &addresses, &before, simulation)
+}
+
+impl TxBuilder {
+    /// Build the transaction unsigned and summarize what sending it would do.
+    pub fn simulate_and_summarize(&self, rpc: &impl SimulateRpc) -> Result<TxSummary, ClientError> {
+        let message = self.message(&self.build(rpc)?, Hash::default())?;
+        let signatures = vec![Signature::default(); message.header().num_required_signatures as usize];
+        simulate_and_summarize(rpc, &VersionedTransaction { signatures, message })
+    }
+}
@@ -0,0 +1,274 @@
+//! Typed account subscriptions over `accountSubscribe`. Each write to the
+//! account is decoded into the program's type and delivered, with the slot it
+//! landed in, on a channel the caller reads at its own pace. A notification for
+//! a slot already delivered is dropped, so a write the node reports twice
+//! arrives once. When the websocket drops, the subscription resubscribes and
+//! fetches the account once, so a write missed while disconnected still shows
+//! up as the current state.
+//!
+//!     let mut stake = subscribe_user_stake(&WsAccountPubsub::new(ws_url, rpc), &user);
+//!     while let Some(change) = stake.recv().await {
+//!         let AccountChange { slot, value } = change?;
+//!     }
+
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::{AccountDeserialize, ZeroCopy};
+use futures::channel::oneshot;
+use futures::stream::{BoxStream, StreamExt};
+use ontora_ai::state::{AiAgent, Proposal, UserStake};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::nonblocking::pubsub_client::PubsubClient;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::RpcAccountInfoConfig;
+use solana_sdk::account::Account;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tokio::task::JoinHandle;
+
+use crate::accounts::{check_owner, decode_account, decode_zero_copy};
+use crate::error::ClientError;
+use crate::pda;
+
+/// How long a subscription waits to resubscribe after its websocket drops.
+pub const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// One write to an account as the node reports it; `None` once the account
+/// is closed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AccountWrite {
+    pub slot: u64,
+    pub account: Option<Account>,
+}
+
+/// Where subscriptions read account writes from; implemented by
+/// `WsAccountPubsub`, and by tests replaying writes without a cluster.
+pub trait AccountPubsub: Clone + Send + Sync + 'static {
+    /// The writes to `address` from now on. The stream ends when the
+    /// subscription is dropped by the node or the connection.
+    fn account_writes(
+        &self,
+        address: Pubkey,
+    ) -> impl Future<Output = Result<BoxStream<'static, AccountWrite>, ClientError>> + Send;
+
+    /// The account's current state and the slot it was read at.
+    fn current(&self, address: Pubkey) -> impl Future<Output = Result<AccountWrite, ClientError>> + Send;
+
+    fn reconnect_delay(&self) -> Duration {
+        RECONNECT_DELAY
+    }
+}
+
+/// `accountSubscribe` on a websocket endpoint, with the fetches after a
+/// disconnect made through `rpc` at its commitment.
+#[derive(Clone)]
+pub struct WsAccountPubsub {
+    ws_url: String,
+    rpc: Arc<RpcClient>,
+}
+
+impl WsAccountPubsub {
+    pub fn new(ws_url: impl Into<String>, rpc: Arc<RpcClient>) -> Self {
+        WsAccountPubsub { ws_url: ws_url.into(), rpc }
+    }
+}
+
+fn subscription_error(error: impl std::fmt::Display) -> ClientError {
+    ClientError::Subscription(error.to_string())
+}
+
+// The node reports a closed account as an empty one owned by the system program
+fn written_account(account: Account) -> Option<Account> {
+    (account.lamports > 0).then_some(account)
+}
+
+impl AccountPubsub for WsAccountPubsub {
+    async fn account_writes(&self, address: Pubkey) -> Result<BoxStream<'static, AccountWrite>, ClientError> {
+        let config = RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            commitment: Some(self.rpc.commitment()),
+            ..RpcAccountInfoConfig::default()
+        };
+        let ws_url = self.ws_url.clone();
+        let (writes, received) = unbounded_channel();
+        let (subscribed, ready) = oneshot::channel();
+        // The notification stream borrows the client, so both live in a task
+        // forwarding writes until either side goes away
+        tokio::spawn(async move {
+            let client = match PubsubClient::new(&ws_url).await {
+                Ok(client) => client,
+                Err(error) => {
+                    let _ = subscribed.send(Err(subscription_error(error)));
+                    return;
+                }
+            };
+            let (mut notifications, unsubscribe) = match client.account_subscribe(&address, Some(config)).await {
+                Ok(subscription) => subscription,
+                Err(error) => {
+                    let _ = subscribed.send(Err(subscription_error(error)));
+                    return;
+                }
+            };
+            if subscribed.send(Ok(())).is_err() {
+                return;
+            }
+            while let Some(notification) = notifications.next().await {
+                let Some(account) = notification.value.decode::<Account>() else { continue };
+                let write = AccountWrite { slot: notification.context.slot, account: written_account(account) };
+                if writes.send(write).is_err() {
+                    break;
+                }
+            }
+            drop(notifications);
+            unsubscribe().await;
+        });
+        ready.await.map_err(|_| subscription_error("subscription task stopped"))??;
+        Ok(futures::stream::unfold(received, |mut received| async move {
+            received.recv().await.map(|write| (write, received))
+        })
+        .boxed())
+    }
+
+    async fn current(&self, address: Pubkey) -> Result<AccountWrite, ClientError> {
+        let response = self.rpc.get_account_with_commitment(&address, self.rpc.commitment()).await?;
+        Ok(AccountWrite { slot: response.context.slot, account: response.value.and_then(written_account) })
+    }
+}
+
+/// An account's state after a write; `value` is `None` once it is closed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AccountChange<T> {
+    pub slot: u64,
+    pub value: Option<T>,
+}
+
+/// Decoded changes to one account, delivered until the subscription is
+/// dropped. Errors (an account that fails to decode, a failed fetch after a
+/// disconnect) are delivered in line and the subscription carries on.
+pub struct Subscription<T> {
+    address: Pubkey,
+    changes: UnboundedReceiver<Result<AccountChange<T>, ClientError>>,
+    task: JoinHandle<()>,
+}
+
+impl<T> Subscription<T> {
+    pub fn address(&self) -> Pubkey {
+        self.address
+    }
+
+    /// The next change, waiting for one if none is pending.
+    pub async fn recv(&mut self) -> Option<Result<AccountChange<T>, ClientError>> {
+        self.changes.recv().await
+    }
+}
+
+impl<T> Drop for Subscription<T> {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+type Decoder<T> = fn(&Pubkey, &[u8]) -> Result<T, ClientError>;
+
+fn decode_change<T>(
+    address: &Pubkey,
+    write: AccountWrite,
+    decode: Decoder<T>,
+) -> Result<AccountChange<T>, ClientError> {
+    let value = match write.account {
+        Some(account) => {
+            check_owner(address, &account.owner)?;
+            Some(decode(address, &account.data)?)
+        }
+        None => None,
+    };
+    Ok(AccountChange { slot: write.slot, value })
+}
+
+// Forwards `address`'s writes to `changes`, resubscribing whenever the stream
+// ends, until the subscriber goes away
+async fn run_subscription<P: AccountPubsub, T>(
+    pubsub: P,
+    address: Pubkey,
+    decode: Decoder<T>,
+    changes: UnboundedSender<Result<AccountChange<T>, ClientError>>,
+) {
+    let mut last_slot = None;
+    // Delivers `write` unless its slot was already delivered; false once the subscriber is gone
+    let mut deliver = |write: AccountWrite| {
+        if last_slot.is_some_and(|last| write.slot <= last) {
+            return !changes.is_closed();
+        }
+        last_slot = Some(write.slot);
+        changes.send(decode_change(&address, write, decode)).is_ok()
+    };
+    let mut resubscribing = false;
+    loop {
+        match pubsub.account_writes(address).await {
+            Ok(mut writes) => {
+                // Subscribed before fetching, so no write falls in between
+                if resubscribing {
+                    let delivered = match pubsub.current(address).await {
+                        Ok(write) => deliver(write),
+                        Err(error) => changes.send(Err(error)).is_ok(),
+                    };
+                    if !delivered {
+                        return;
+                    }
+                }
+                while let Some(write) = writes.next().await {
+                    if !deliver(write) {
+                        return;
+                    }
+                }
+            }
+            Err(error) => {
+                if changes.send(Err(error)).is_err() {
+                    return;
+                }
+            }
+        }
+        resubscribing = true;
+        tokio::time::sleep(pubsub.reconnect_delay()).await;
+    }
+}
+
+fn subscribe<P: AccountPubsub, T: Send + 'static>(pubsub: &P, address: Pubkey, decode: Decoder<T>) -> Subscription<T> {
+    let (sender, changes) = unbounded_channel();
+    let task = tokio::spawn(run_subscription(pubsub.clone(), address, decode, sender));
+    Subscription { address, changes, task }
+}
+
+/// Subscribe to any Borsh account of the program. Must be called within a
+/// Tokio runtime.
+pub fn subscribe_account<P, T>(pubsub: &P, address: &Pubkey) -> Subscription<T>
+where
+    P: AccountPubsub,
+    T: AccountDeserialize + Send + 'static,
+{
+    subscribe(pubsub, *address, decode_account::<T>)
+}
+
+/// Subscribe to any zero-copy account of the program. Must be called within
+/// a Tokio runtime.
+pub fn subscribe_zero_copy<P, T>(pubsub: &P, address: &Pubkey) -> Subscription<T>
+where
+    P: AccountPubsub,
+    T: ZeroCopy + Send + 'static,
+{
+    subscribe(pubsub, *address, decode_zero_copy::<T>)
+}
+
+pub fn subscribe_user_stake<P: AccountPubsub>(pubsub: &P, user: &Pubkey) -> Subscription<UserStake> {
+    subscribe_account(pubsub, &pda::user_stake(user).0)
+}
+
+pub fn subscribe_agent<P: AccountPubsub>(pubsub: &P, owner: &Pubkey, agent_id: u64) -> Subscription<AiAgent> {
+    subscribe_account(pubsub, &pda::ai_agent(owner, agent_id).0)
+}
+
+pub fn subscribe_proposal<P: AccountPubsub>(pubsub: &P, proposal_id: u64) -> Subscription<Proposal> {
+    subscribe_zero_copy(pubsub, &pda::proposal(proposal_id).0)
+}
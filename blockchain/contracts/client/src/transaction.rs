@@ -0,0 +1,325 @@
+//! Transaction assembly with a compute budget. `TxBuilder` prepends
+//! `SetComputeUnitLimit` and `SetComputeUnitPrice` to the caller's
+//! instructions, so transactions request only the units they need and can
+//! outbid the fee market during congestion. With an address lookup table
+//! configured it emits v0 transactions, resolving every account the table
+//! holds through it.
+//!
+//!     let signature = TxBuilder::new(user.pubkey())
+//!         .instruction(build_claim_rewards(params))
+//!         .priority_fee(PriorityFee::Percentile(75))
+//!         .send(&rpc, &[&user])?;
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::str::FromStr;
+
+use anchor_lang::solana_program::hash::hash;
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::RpcSimulateTransactionConfig;
+use solana_sdk::address_lookup_table::AddressLookupTableAccount;
+use solana_sdk::compute_budget::ComputeBudgetInstruction;
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::message::{v0, Message, VersionedMessage};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::VersionedTransaction;
+
+use crate::error::ClientError;
+use crate::signer::{sign_transaction, OntoraSigner, PartialTransaction};
+
+// Per-instruction budgets the program's compute-unit test holds it to
+const COMPUTE_BUDGETS: &str = include_str!("../../tests/compute_budgets.txt");
+// Measurements may exceed their budget by this much before that test fails
+const BUDGET_TOLERANCE_PERCENT: u64 = 10;
+/// Limit assumed for an instruction without a budget: the runtime's default.
+pub const DEFAULT_INSTRUCTION_UNITS: u32 = 200_000;
+/// Most compute units a transaction may request.
+pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+/// Headroom added to simulated usage, since state can change before landing.
+pub const SIMULATION_MARGIN_PERCENT: u64 = 20;
+// getRecentPrioritizationFees accepts at most this many accounts
+const MAX_FEE_ACCOUNTS: usize = 128;
+
+/// Where the compute-unit limit comes from.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ComputeLimit {
+    /// Sum of the per-instruction budgets, with room for their tolerance.
+    #[default]
+    Table,
+    /// Units consumed in a simulation, plus `SIMULATION_MARGIN_PERCENT`.
+    Simulate,
+    /// A fixed number of units.
+    Fixed(u32),
+}
+
+/// How the compute-unit price is chosen, in micro-lamports per unit.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PriorityFee {
+    /// No price instruction; the transaction pays the base fee only.
+    #[default]
+    None,
+    Fixed(u64),
+    /// This percentile (0-100) of the fees recently paid to write the same accounts.
+    Percentile(u8),
+}
+
+impl FromStr for ComputeLimit {
+    type Err = String;
+
+    /// `table`, `simulate`, or a number of units.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "table" => Ok(ComputeLimit::Table),
+            "simulate" => Ok(ComputeLimit::Simulate),
+            units => match units.parse() {
+                Ok(units) if units <= MAX_COMPUTE_UNIT_LIMIT => Ok(ComputeLimit::Fixed(units)),
+                _ => Err(format!("expected `table`, `simulate` or at most {} units", MAX_COMPUTE_UNIT_LIMIT)),
+            },
+        }
+    }
+}
+
+impl FromStr for PriorityFee {
+    type Err = String;
+
+    /// `none`, a price in micro-lamports, or a percentile such as `p75`.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if value == "none" {
+            return Ok(PriorityFee::None);
+        }
+        if let Some(percentile) = value.strip_prefix('p') {
+            return match percentile.parse() {
+                Ok(percentile) if percentile <= 100 => Ok(PriorityFee::Percentile(percentile)),
+                _ => Err(format!("percentile `{}` is not between p0 and p100", value)),
+            };
+        }
+        value.parse().map(PriorityFee::Fixed).map_err(|_| {
+            format!("expected `none`, micro-lamports per unit or a percentile such as `p75`, got `{}`", value)
+        })
+    }
+}
+
+/// The RPC calls the builder makes, so tests can stand in for a cluster.
+pub trait TransactionRpc {
+    /// Prioritization fees paid in recent slots by transactions writing `accounts`.
+    fn recent_prioritization_fees(&self, accounts: &[Pubkey]) -> Result<Vec<u64>, ClientError>;
+    /// Compute units `transaction` consumes, simulated without signatures.
+    fn simulate_units(&self, transaction: &VersionedTransaction) -> Result<u64, ClientError>;
+}
+
+impl TransactionRpc for RpcClient {
+    fn recent_prioritization_fees(&self, accounts: &[Pubkey]) -> Result<Vec<u64>, ClientError> {
+        let fees = self.get_recent_prioritization_fees(accounts)?;
+        Ok(fees.into_iter().map(|fee| fee.prioritization_fee).collect())
+    }
+
+    fn simulate_units(&self, transaction: &VersionedTransaction) -> Result<u64, ClientError> {
+        let config = RpcSimulateTransactionConfig {
+            sig_verify: false,
+            replace_recent_blockhash: true,
+            commitment: Some(self.commitment()),
+            ..RpcSimulateTransactionConfig::default()
+        };
+        let simulation = self.simulate_transaction_with_config(transaction, config)?.value;
+        if let Some(error) = simulation.err {
+            let logs = simulation.logs.unwrap_or_default().join("\n");
+            return Err(ClientError::Simulation(format!("{}\n{}", error, logs)));
+        }
+        simulation.units_consumed.ok_or_else(|| ClientError::Simulation("no units reported".to_string()))
+    }
+}
+
+// Anchor's instruction discriminator for `name`
+fn sighash(name: &str) -> [u8; 8] {
+    let mut discriminator = [0; 8];
+    discriminator.copy_from_slice(&hash(format!("global:{}", name).as_bytes()).to_bytes()[..8]);
+    discriminator
+}
+
+fn budget_table() -> BTreeMap<[u8; 8], u32> {
+    COMPUTE_BUDGETS
+        .lines()
+        .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let (name, units) = line.split_once(' ')?;
+            Some((sighash(name), units.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+/// Compute units to request for `instruction`: its budget plus the
+/// tolerance the program is tested against, or the runtime default if it
+/// has none.
+pub fn instruction_budget(instruction: &Instruction) -> u32 {
+    if instruction.program_id != ontora_ai::ID || instruction.data.len() < 8 {
+        return DEFAULT_INSTRUCTION_UNITS;
+    }
+    match budget_table().get(&instruction.data[..8]) {
+        Some(&units) => (units as u64 * (100 + BUDGET_TOLERANCE_PERCENT) / 100) as u32,
+        None => DEFAULT_INSTRUCTION_UNITS,
+    }
+}
+
+/// `units` consumed in a simulation plus `SIMULATION_MARGIN_PERCENT`, capped
+/// at the most a transaction may request.
+pub fn limit_with_margin(units: u64) -> u32 {
+    let limit = units.saturating_mul(100 + SIMULATION_MARGIN_PERCENT) / 100;
+    limit.min(MAX_COMPUTE_UNIT_LIMIT as u64) as u32
+}
+
+/// The `percentile` of `fees` by nearest rank; 0 when there are none.
+pub fn fee_percentile(fees: &[u64], percentile: u8) -> u64 {
+    let mut fees = fees.to_vec();
+    fees.sort_unstable();
+    match fees.len() {
+        0 => 0,
+        count => fees[(count - 1) * percentile.min(100) as usize / 100],
+    }
+}
+
+/// Builds transactions with a compute-unit limit and price prepended.
+#[derive(Clone, Debug)]
+pub struct TxBuilder {
+    payer: Pubkey,
+    instructions: Vec<Instruction>,
+    compute_limit: ComputeLimit,
+    priority_fee: PriorityFee,
+    lookup_tables: Vec<AddressLookupTableAccount>,
+}
+
+impl TxBuilder {
+    pub fn new(payer: Pubkey) -> Self {
+        TxBuilder {
+            payer,
+            instructions: Vec::new(),
+            compute_limit: ComputeLimit::default(),
+            priority_fee: PriorityFee::default(),
+            lookup_tables: Vec::new(),
+        }
+    }
+
+    pub fn instruction(mut self, instruction: Instruction) -> Self {
+        self.instructions.push(instruction);
+        self
+    }
+
+    pub fn instructions(mut self, instructions: impl IntoIterator<Item = Instruction>) -> Self {
+        self.instructions.extend(instructions);
+        self
+    }
+
+    pub fn compute_limit(mut self, compute_limit: ComputeLimit) -> Self {
+        self.compute_limit = compute_limit;
+        self
+    }
+
+    pub fn priority_fee(mut self, priority_fee: PriorityFee) -> Self {
+        self.priority_fee = priority_fee;
+        self
+    }
+
+    /// Resolve accounts through `table`, which makes the transaction v0.
+    pub fn lookup_table(mut self, table: AddressLookupTableAccount) -> Self {
+        self.lookup_tables.push(table);
+        self
+    }
+
+    // A legacy message, or v0 when lookup tables are configured
+    pub(crate) fn message(
+        &self,
+        instructions: &[Instruction],
+        blockhash: Hash,
+    ) -> Result<VersionedMessage, ClientError> {
+        if self.lookup_tables.is_empty() {
+            let message = Message::new_with_blockhash(instructions, Some(&self.payer), &blockhash);
+            return Ok(VersionedMessage::Legacy(message));
+        }
+        let message = v0::Message::try_compile(&self.payer, instructions, &self.lookup_tables, blockhash)
+            .map_err(|error| ClientError::Build(error.to_string()))?;
+        Ok(VersionedMessage::V0(message))
+    }
+
+    // Accounts the instructions write, which set the local fee market they bid in
+    fn writable_accounts(&self) -> Vec<Pubkey> {
+        let writable: BTreeSet<Pubkey> = self
+            .instructions
+            .iter()
+            .flat_map(|instruction| instruction.accounts.iter())
+            .filter(|meta| meta.is_writable)
+            .map(|meta| meta.pubkey)
+            .collect();
+        writable.into_iter().take(MAX_FEE_ACCOUNTS).collect()
+    }
+
+    fn unit_price(&self, rpc: &impl TransactionRpc) -> Result<u64, ClientError> {
+        match self.priority_fee {
+            PriorityFee::None => Ok(0),
+            PriorityFee::Fixed(price) => Ok(price),
+            PriorityFee::Percentile(percentile) => {
+                let fees = rpc.recent_prioritization_fees(&self.writable_accounts())?;
+                Ok(fee_percentile(&fees, percentile))
+            }
+        }
+    }
+
+    fn unit_limit(&self, rpc: &impl TransactionRpc, price: Option<Instruction>) -> Result<u32, ClientError> {
+        match self.compute_limit {
+            ComputeLimit::Fixed(units) => Ok(units),
+            ComputeLimit::Table => {
+                let units: u64 = self.instructions.iter().map(|ix| instruction_budget(ix) as u64).sum();
+                Ok(units.min(MAX_COMPUTE_UNIT_LIMIT as u64) as u32)
+            }
+            ComputeLimit::Simulate => {
+                // Simulate the transaction as it will be sent, under the largest limit
+                let mut instructions = vec![ComputeBudgetInstruction::set_compute_unit_limit(MAX_COMPUTE_UNIT_LIMIT)];
+                instructions.extend(price);
+                instructions.extend(self.instructions.iter().cloned());
+                let message = self.message(&instructions, Hash::default())?;
+                let signatures = vec![Signature::default(); message.header().num_required_signatures as usize];
+                let transaction = VersionedTransaction { signatures, message };
+                Ok(limit_with_margin(rpc.simulate_units(&transaction)?))
+            }
+        }
+    }
+
+    /// The instructions to send: the compute-unit limit, the price unless it
+    /// is zero, then the caller's instructions.
+    pub fn build(&self, rpc: &impl TransactionRpc) -> Result<Vec<Instruction>, ClientError> {
+        let price = match self.unit_price(rpc)? {
+            0 => None,
+            price => Some(ComputeBudgetInstruction::set_compute_unit_price(price)),
+        };
+        let limit = self.unit_limit(rpc, price.clone())?;
+        let mut instructions = vec![ComputeBudgetInstruction::set_compute_unit_limit(limit)];
+        instructions.extend(price);
+        instructions.extend(self.instructions.iter().cloned());
+        Ok(instructions)
+    }
+
+    /// Build the transaction and sign it with `signers` over `blockhash`. To
+    /// sign with signers of different kinds, pass them as `&dyn OntoraSigner`.
+    pub fn transaction<S: OntoraSigner + ?Sized>(
+        &self,
+        rpc: &impl TransactionRpc,
+        signers: &[&S],
+        blockhash: Hash,
+    ) -> Result<VersionedTransaction, ClientError> {
+        sign_transaction(self.message(&self.build(rpc)?, blockhash)?, signers)
+    }
+
+    /// Build the transaction unsigned, for signers that sign one at a time.
+    pub fn partial_transaction(
+        &self,
+        rpc: &impl TransactionRpc,
+        blockhash: Hash,
+    ) -> Result<PartialTransaction, ClientError> {
+        Ok(PartialTransaction::new(self.message(&self.build(rpc)?, blockhash)?))
+    }
+
+    /// Build, sign with `signers` and send, waiting for confirmation.
+    pub fn send<S: OntoraSigner + ?Sized>(&self, rpc: &RpcClient, signers: &[&S]) -> Result<Signature, ClientError> {
+        let transaction = self.transaction(rpc, signers, rpc.get_latest_blockhash()?)?;
+        Ok(rpc.send_and_confirm_transaction(&transaction)?)
+    }
+}
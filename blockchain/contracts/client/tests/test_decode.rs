@@ -0,0 +1,411 @@
+// test_decode.rs
+// Tests for the transaction decoder. One transaction per program instruction is
+// built with its `build_*` function, serialized and base64-encoded as a wallet
+// hands it over, decoded, and its report compared with
+// tests/golden/decoded_transactions.txt. Addresses in the report are replaced by
+// the keys and PDAs they were built from, so the snapshot reads as the accounts
+// each instruction names. Regenerate it after an intended format change with
+//     UPDATE_DECODE_GOLDENS=1 cargo test --test test_decode
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use ontora_ai::state::{AdminAction, ProposalAction, Role, PAUSE_ALL, PAUSE_GOVERNANCE, PAUSE_WITHDRAWALS};
+use ontora_ai::MIGRATED_AGENT_ID;
+use ontora_client::*;
+use solana_sdk::address_lookup_table::AddressLookupTableAccount;
+use solana_sdk::compute_budget::ComputeBudgetInstruction;
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::message::{v0, VersionedMessage};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::{Transaction, VersionedTransaction};
+
+const GOLDEN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden/decoded_transactions.txt");
+const DECIMALS: u8 = 9;
+
+// Fixed, recognizable key: every byte is `seed`
+fn key(seed: u8) -> Pubkey {
+    Pubkey::new_from_array([seed; 32])
+}
+
+// The base64 text of an unsigned legacy transaction paid by key(1)
+fn serialized(instructions: &[Instruction]) -> String {
+    let transaction = VersionedTransaction::from(Transaction::new_with_payer(instructions, Some(&key(1))));
+    STANDARD.encode(bincode::serialize(&transaction).unwrap())
+}
+
+// What the addresses in the reports were built from
+fn labels() -> Vec<(Pubkey, String)> {
+    let mut labels: Vec<(Pubkey, String)> = (1..=14).map(|seed| (key(seed), format!("key({})", seed))).collect();
+    labels.extend([
+        (ontora_ai::ID, "ontora_ai::ID".to_string()),
+        (pda::platform_config().0, "platform_config".to_string()),
+        (pda::reward_pool().0, "reward_pool".to_string()),
+        (pda::fee_schedule().0, "fee_schedule".to_string()),
+        (pda::treasury().0, "treasury".to_string()),
+        (pda::treasury_vault(&key(2)).0, "treasury_vault(key(2))".to_string()),
+        (pda::staking_vault().0, "staking_vault".to_string()),
+        (pda::reward_vault().0, "reward_vault".to_string()),
+        (pda::legacy_ai_agent(&key(1)).0, "legacy_ai_agent(key(1))".to_string()),
+        (pda::pending_withdrawal(&key(1)).0, "pending_withdrawal(key(1))".to_string()),
+        (pda::reward_vesting(&key(1)).0, "reward_vesting(key(1))".to_string()),
+        (pda::legacy_proposal(&key(1)).0, "legacy_proposal(key(1))".to_string()),
+        (pda::legacy_proposal(&key(5)).0, "legacy_proposal(key(5))".to_string()),
+        (pda::proposal(3).0, "proposal(3)".to_string()),
+        (pda::proposal(4).0, "proposal(4)".to_string()),
+        (pda::proposal_vote(3, &key(1)).0, "proposal_vote(3, key(1))".to_string()),
+        (pda::ai_agent(&key(5), 7).0, "ai_agent(key(5), 7)".to_string()),
+        (pda::legacy_ai_agent(&key(5)).0, "legacy_ai_agent(key(5))".to_string()),
+        (pda::ai_agent(&key(5), MIGRATED_AGENT_ID).0, "ai_agent(key(5), MIGRATED_AGENT_ID)".to_string()),
+        (pda::user_stake(&key(5)).0, "user_stake(key(5))".to_string()),
+        (pda::stake_index(&key(5), MIGRATED_AGENT_ID).0, "stake_index(key(5), MIGRATED_AGENT_ID)".to_string()),
+        (pda::user_stake(&key(1)).0, "user_stake(key(1))".to_string()),
+        (pda::stake_index(&key(1), 7).0, "stake_index(key(1), 7)".to_string()),
+        (pda::metadata(&key(1), 7).0, "metadata(key(1), 7)".to_string()),
+        (pda::admin_multisig().0, "admin_multisig".to_string()),
+        (pda::admin_action(0).0, "admin_action(0)".to_string()),
+        (pda::governance_realm().0, "governance_realm".to_string()),
+    ]);
+    labels
+}
+
+// One transaction per program instruction, then one mixing in other programs
+// and an instruction the program does not have
+fn transactions() -> Vec<Vec<Instruction>> {
+    let (user, mint, token_account, vault, owner, crank) = (key(1), key(2), key(3), key(4), key(5), key(6));
+    let (governance, oracle) = (key(10), key(12));
+    let instructions = vec![
+        build_initialize_reward_pool(InitializeRewardPoolParams { payer: user, initial_rewards: 10_000_000_000_000 }),
+        build_fund_reward_pool(FundRewardPoolParams {
+            funder: user,
+            funder_token_account: token_account,
+            reward_mint: mint,
+            amount: 250_000_000_000,
+        }),
+        build_register_ai_agent(RegisterAiAgentParams {
+            user,
+            user_token_account: token_account,
+            staking_vault: vault,
+            stake_amount: 1_500_000_000,
+        }),
+        build_increase_stake(IncreaseStakeParams {
+            user,
+            user_token_account: token_account,
+            staking_vault: vault,
+            amount: 250_000_000,
+        }),
+        build_unstake_ai_agent(UnstakeAiAgentParams {
+            user,
+            user_token_account: token_account,
+            staking_vault: vault,
+            stake_mint: mint,
+        }),
+        build_unstake_partial(UnstakePartialParams {
+            user,
+            user_token_account: token_account,
+            staking_vault: vault,
+            stake_mint: mint,
+            amount: 500_000_000,
+        }),
+        build_emergency_unstake(EmergencyUnstakeParams {
+            user,
+            user_token_account: token_account,
+            staking_vault: vault,
+            stake_mint: mint,
+        }),
+        build_request_unstake(RequestUnstakeParams { user, amount: 750_000_000 }),
+        build_claim_unstake(ClaimUnstakeParams {
+            user,
+            user_token_account: token_account,
+            staking_vault: vault,
+            stake_mint: mint,
+        }),
+        build_close_agent(CloseAgentParams { owner: user }),
+        build_distribute_rewards(DistributeRewardsParams { crank, agent_owner: user }),
+        build_distribute_rewards_batch(DistributeRewardsBatchParams {
+            crank,
+            agents: vec![key(8), key(7)],
+            close_epoch: true,
+        }),
+        build_claim_rewards(ClaimRewardsParams {
+            user,
+            user_token_account: token_account,
+            reward_vault: vault,
+            reward_mint: mint,
+        }),
+        build_release_vested(ReleaseVestedParams {
+            user,
+            user_token_account: token_account,
+            reward_vault: vault,
+            reward_mint: mint,
+        }),
+        build_create_proposal(CreateProposalParams {
+            user,
+            description: "Raise the reward rate".to_string(),
+            duration: 86_400,
+        }),
+        build_vote_proposal(VoteProposalParams { user, proposal_creator: owner, in_favor: true }),
+        build_initialize_platform(InitializePlatformParams {
+            admin: user,
+            reward_rate_bps: 500,
+            min_stake_amount: 1_000_000_000,
+            epoch_duration: 604_800,
+            cooldown_seconds: 86_400,
+            epoch_emission: 100_000_000_000,
+        }),
+        build_initialize_vaults(InitializeVaultsParams { admin: user, stake_mint: mint }),
+        build_update_platform_config(UpdatePlatformConfigParams {
+            admin: user,
+            reward_rate_bps: 250,
+            min_stake_amount: 5_000_000_000,
+            epoch_duration: 3_600,
+            cooldown_seconds: 43_200,
+            epoch_emission: 50_000_000_000,
+            max_stake_per_agent: 500_000_000_000,
+            vesting_seconds: 2_592_000,
+            vote_unit: 100_000_000_000,
+            quorum_bps: 1_000,
+            approval_threshold_bps: 5_000,
+            update_metadata: "Q3 rates".to_string(),
+        }),
+        build_migrate_platform_config(MigratePlatformConfigParams { admin: user }),
+        build_migrate_account(MigrateAccountParams { account: key(9), payer: user }),
+        build_migrate_agent_v1(MigrateAgentV1Params {
+            admin: user,
+            owner,
+            stake_mint: mint,
+            staking_vault: token_account,
+            platform_vault: vault,
+        }),
+        build_get_version(),
+        build_create_governance_proposal(CreateGovernanceProposalParams {
+            creator: user,
+            proposal_id: 3,
+            title: "Rate cut".to_string(),
+            description: "Halve the reward rate".to_string(),
+            voting_duration: 259_200,
+            options: vec!["yes".to_string(), "no".to_string()],
+            action: ProposalAction::UpdateRewardRate { bps: 50 },
+        }),
+        build_cast_vote(CastVoteParams { voter: user, proposal_id: 3, vote_option: 1 }),
+        build_finalize_proposal(FinalizeProposalParams { caller: crank, proposal_id: 3 }),
+        build_execute_proposal(ExecuteProposalParams { executor: crank, proposal_id: 3 }),
+        build_set_governance_enabled(SetGovernanceEnabledParams {
+            admin: user,
+            enabled: false,
+            active_proposal_ids: vec![3, 4],
+        }),
+        build_propose_admin(ProposeAdminParams { admin: user, new_admin: owner }),
+        build_accept_admin(AcceptAdminParams { new_admin: owner }),
+        build_cancel_admin_transfer(CancelAdminTransferParams { admin: user }),
+        build_initialize_treasury(InitializeTreasuryParams { authority: user, governance }),
+        build_initialize_fee_schedule(InitializeFeeScheduleParams {
+            admin: user,
+            governance,
+            rates_bps: [10, 20, 30, 500],
+        }),
+        build_update_fee_schedule(UpdateFeeScheduleParams { authority: governance, rates_bps: [5, 10, 15, 250] }),
+        build_treasury_balance(TreasuryBalanceParams { mint }),
+        build_treasury_transfer(TreasuryTransferParams { governance, mint, recipient: key(11), amount: 2_500_000_000 }),
+        build_set_pause_flags(SetPauseFlagsParams { authority: crank, flags: PAUSE_WITHDRAWALS | PAUSE_GOVERNANCE }),
+        build_clear_pause_flags(ClearPauseFlagsParams { admin: user, flags: PAUSE_ALL }),
+        build_set_role(SetRoleParams { admin: user, role: Role::Oracle, holder: oracle }),
+        build_renounce_role(RenounceRoleParams { holder: oracle, role: Role::Oracle }),
+        build_update_performance_score(UpdatePerformanceScoreParams {
+            oracle,
+            agent_owner: owner,
+            agent_id: 7,
+            score: 87,
+        }),
+        build_register_agent(RegisterAgentParams {
+            owner,
+            agent_id: 7,
+            name: "Atlas".to_string(),
+            description: "Market making agent".to_string(),
+        }),
+        build_update_agent_metadata(UpdateAgentMetadataParams {
+            owner,
+            agent_id: 7,
+            name: "Atlas v2".to_string(),
+            description: "Market making and hedging".to_string(),
+        }),
+        build_stake_on_agent(StakeOnAgentParams {
+            user,
+            user_token_account: token_account,
+            platform_vault: vault,
+            agent_owner: owner,
+            agent_id: 7,
+            amount: 1_500_000_000,
+            beneficiary: None,
+        }),
+        build_unstake_from_agent(UnstakeFromAgentParams {
+            user,
+            user_token_account: token_account,
+            platform_vault: vault,
+            stake_mint: mint,
+            agent_owner: owner,
+            agent_id: 7,
+            amount: 500_000_000,
+        }),
+        build_claim_stake_rewards(ClaimStakeRewardsParams {
+            user,
+            user_token_account: token_account,
+            platform_vault: vault,
+            reward_mint: mint,
+        }),
+        build_create_metadata(CreateMetadataParams {
+            authority: user,
+            entity_id: 7,
+            data: "ipfs://agent-7".to_string(),
+        }),
+        build_update_metadata(UpdateMetadataParams {
+            authority: user,
+            entity_id: 7,
+            data: "ipfs://agent-7-v2".to_string(),
+        }),
+        build_initialize_admin_multisig(InitializeAdminMultisigParams {
+            admin: user,
+            signers: vec![owner, crank, oracle],
+            threshold: 2,
+        }),
+        build_propose_admin_action(ProposeAdminActionParams {
+            proposer: owner,
+            action_id: 0,
+            action: AdminAction::SetRole { role: Role::Crank, holder: crank },
+        }),
+        build_approve_admin_action(ApproveAdminActionParams { signer: crank, action_id: 0 }),
+        build_execute_admin_action(ExecuteAdminActionParams { executor: oracle, action_id: 0 }),
+        build_execute_initialize_vaults(ExecuteInitializeVaultsParams {
+            executor: oracle,
+            action_id: 0,
+            stake_mint: mint,
+        }),
+        build_set_governance_realm(SetGovernanceRealmParams { admin: user, realm: key(13), governance: key(14) }),
+    ];
+    let mut transactions: Vec<Vec<Instruction>> = instructions.into_iter().map(|ix| vec![ix]).collect();
+
+    let unknown = Instruction { program_id: ontora_ai::ID, accounts: Vec::new(), data: vec![0; 8] };
+    let role = build_set_role(SetRoleParams { admin: governance, role: Role::Pauser, holder: oracle });
+    transactions.push(vec![
+        ComputeBudgetInstruction::set_compute_unit_limit(200_000),
+        unknown,
+        with_governance_realm(role),
+    ]);
+    transactions
+}
+
+// Test the report of one transaction per instruction against the snapshot
+#[test]
+fn test_reports_match_golden() {
+    let labels = labels();
+    let mut reports = Vec::new();
+    for instructions in transactions() {
+        let transaction = parse_transaction(&serialized(&instructions)).unwrap();
+        let mut report = decode_transaction(&transaction, &[]).unwrap().report(Some(DECIMALS));
+        for (address, label) in &labels {
+            report = report.replace(&address.to_string(), label);
+        }
+        reports.push(report);
+    }
+    let rendered = reports.join("\n\n") + "\n";
+
+    if std::env::var_os("UPDATE_DECODE_GOLDENS").is_some() {
+        std::fs::write(GOLDEN_PATH, &rendered).unwrap();
+        return;
+    }
+    let golden = std::fs::read_to_string(GOLDEN_PATH).unwrap_or_default();
+    assert_eq!(rendered, golden, "decoded reports changed; if intended, regenerate with UPDATE_DECODE_GOLDENS=1");
+}
+
+// Test that every instruction decodes to the accounts it was built with, in
+// order, and that token amounts are left in base units without decimals
+#[test]
+fn test_decoded_accounts_match_instructions() {
+    for instructions in transactions() {
+        let transaction = parse_transaction(&serialized(&instructions)).unwrap();
+        let decoded = decode_transaction(&transaction, &[]).unwrap();
+        assert_eq!(decoded.fee_payer, key(1));
+        assert_eq!(decoded.instructions.len(), instructions.len());
+        for (instruction, reviewed) in instructions.iter().zip(&decoded.instructions) {
+            assert_eq!(reviewed.program_id(), instruction.program_id);
+            if let ReviewedInstruction::Decoded(decoded) = reviewed {
+                let keys: Vec<Pubkey> = decoded.accounts.iter().map(|(_, meta)| meta.pubkey).collect();
+                let expected: Vec<Pubkey> = instruction.accounts.iter().map(|meta| meta.pubkey).collect();
+                assert_eq!(keys, expected, "{}", decoded.name);
+            }
+        }
+    }
+
+    let stake = build_stake_on_agent(StakeOnAgentParams {
+        user: key(1),
+        user_token_account: key(3),
+        platform_vault: key(4),
+        agent_owner: key(5),
+        agent_id: 7,
+        amount: 1_500_000_000,
+        beneficiary: None,
+    });
+    let decoded = decode_instruction(&stake).unwrap();
+    let args = [
+        ("agent_id", ArgValue::Unsigned(7)),
+        ("amount", ArgValue::Tokens(1_500_000_000)),
+        ("beneficiary", ArgValue::Key(key(1))),
+    ];
+    assert_eq!(decoded.args, args);
+    let transaction = parse_transaction(&serialized(&[stake])).unwrap();
+    let report = decode_transaction(&transaction, &[]).unwrap().report(None);
+    assert!(report.contains("stake_on_agent: agent_id=7, amount=1500000000, "));
+}
+
+// Test that a v0 transaction's accounts are resolved through its lookup table,
+// and that decoding it without the table fails
+#[test]
+fn test_lookup_table_accounts_resolved() {
+    let stake = build_stake_on_agent(StakeOnAgentParams {
+        user: key(1),
+        user_token_account: key(3),
+        platform_vault: key(4),
+        agent_owner: key(5),
+        agent_id: 7,
+        amount: 1_000_000_000,
+        beneficiary: None,
+    });
+    let table = AddressLookupTableAccount {
+        key: key(20),
+        addresses: vec![pda::platform_config().0, pda::ai_agent(&key(5), 7).0, key(4)],
+    };
+    let message = v0::Message::try_compile(&key(1), &[stake.clone()], &[table.clone()], Hash::default()).unwrap();
+    assert_eq!(message.address_table_lookups.len(), 1);
+    let transaction = VersionedTransaction {
+        signatures: vec![Signature::default()],
+        message: VersionedMessage::V0(message),
+    };
+    let parsed = parse_transaction(&STANDARD.encode(bincode::serialize(&transaction).unwrap())).unwrap();
+
+    let decoded = decode_transaction(&parsed, &[table]).unwrap();
+    let expected = decode_instruction(&stake).unwrap();
+    assert_eq!(decoded.instructions, [ReviewedInstruction::Decoded(expected)]);
+    assert!(matches!(decode_transaction(&parsed, &[]), Err(ClientError::Instruction(_))));
+}
+
+// Test that text which is not a base64 transaction is refused, and that data
+// too short or too long for its instruction is reported rather than dropped
+#[test]
+fn test_malformed_input() {
+    assert!(matches!(parse_transaction("not base64!"), Err(ClientError::Instruction(_))));
+    assert!(matches!(parse_transaction(&STANDARD.encode([1, 2, 3])), Err(ClientError::Instruction(_))));
+
+    let mut role = build_set_role(SetRoleParams { admin: key(1), role: Role::Crank, holder: key(2) });
+    role.data.truncate(20);
+    let mut short = build_propose_admin(ProposeAdminParams { admin: key(1), new_admin: key(2) });
+    short.accounts.pop();
+    let transaction = parse_transaction(&serialized(&[role, short])).unwrap();
+    let decoded = decode_transaction(&transaction, &[]).unwrap();
+    for reviewed in &decoded.instructions {
+        assert!(matches!(reviewed, ReviewedInstruction::Undecoded { .. }), "{:?}", reviewed);
+    }
+    let report = decoded.report(Some(DECIMALS));
+    assert!(report.contains("not decoded, set_role arguments"));
+    assert!(report.contains("not decoded, propose_admin takes 2 accounts, the instruction has 1"));
+}
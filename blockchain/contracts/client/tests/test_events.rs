@@ -0,0 +1,297 @@
+// test_events.rs
+// Event decoding tests. A run of platform, agent and governance instructions is
+// processed by program-test and every event is decoded back from the captured logs,
+// the way a `logsSubscribe` notification delivers them; the rest feed hand-written
+// logs through the decoders to cover every event and the lines they must skip.
+
+use anchor_lang::{AccountSerialize, Discriminator, Event};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use ontora_ai::events::*;
+use ontora_ai::state::{ProposalAction, Role, UserStake};
+use ontora_client::*;
+use solana_client::rpc_response::RpcTransactionLogs;
+use solana_program_test::*;
+use solana_sdk::account::Account;
+use solana_sdk::instruction::{Instruction, InstructionError};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::rent::Rent;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::system_instruction;
+use solana_sdk::transaction::{Transaction, TransactionError};
+use solana_transaction_status::{
+    EncodedConfirmedTransactionWithStatusMeta, EncodedTransaction, EncodedTransactionWithStatusMeta,
+    TransactionStatusMeta,
+};
+
+const LAMPORTS: u64 = 10_000_000_000;
+
+struct Harness {
+    ctx: ProgramTestContext,
+}
+
+impl Harness {
+    async fn start() -> Self {
+        let test = ProgramTest::new("ontora_ai", ontora_ai::ID, processor!(ontora_ai::entry));
+        Harness { ctx: test.start_with_context().await }
+    }
+
+    // Process `instructions` paid by the context payer; returns the transaction's logs
+    // as a log subscription would deliver them
+    async fn send(&mut self, instructions: &[Instruction], signers: &[&Keypair]) -> RpcTransactionLogs {
+        let mut all_signers = vec![&self.ctx.payer];
+        all_signers.extend_from_slice(signers);
+        let tx = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.ctx.payer.pubkey()),
+            &all_signers,
+            self.ctx.last_blockhash,
+        );
+        let signature = tx.signatures[0].to_string();
+        let processed = self.ctx.banks_client.process_transaction_with_metadata(tx).await.unwrap();
+        processed.result.unwrap();
+        let logs = processed.metadata.expect("program-test records metadata").log_messages;
+        RpcTransactionLogs { signature, err: None, logs }
+    }
+
+    async fn new_user(&mut self) -> Keypair {
+        let user = Keypair::new();
+        let transfer = system_instruction::transfer(&self.ctx.payer.pubkey(), &user.pubkey(), LAMPORTS);
+        self.send(&[transfer], &[]).await;
+        user
+    }
+
+    // Give `user` a stake account holding `staked_amount`, as if they had staked it
+    fn seed_stake(&mut self, user: &Pubkey, staked_amount: u64) {
+        let (address, bump) = pda::user_stake(user);
+        let mut stake = UserStake::default();
+        stake.init(*user, bump);
+        stake.staked_amount = staked_amount;
+        let mut data = Vec::new();
+        stake.try_serialize(&mut data).unwrap();
+        data.resize(UserStake::SPACE, 0);
+        let lamports = Rent::default().minimum_balance(data.len());
+        let account = Account { lamports, data, owner: ontora_ai::ID, executable: false, rent_epoch: 0 };
+        self.ctx.set_account(&address, &account.into());
+    }
+}
+
+// The lines a top-level invocation of the program writes around `sol_log_data` calls
+fn invocation_logs(payloads: &[&str]) -> Vec<String> {
+    let mut logs = vec![format!("Program {} invoke [1]", ontora_ai::ID)];
+    logs.extend(payloads.iter().map(|payload| format!("Program data: {}", payload)));
+    logs.push(format!("Program {} success", ontora_ai::ID));
+    logs
+}
+
+// Each event decoded from `logs`, checked to re-encode to a payload the program logged
+fn decode_checked(logs: &RpcTransactionLogs) -> Vec<OntoraEvent> {
+    let events = decode_rpc_logs(logs);
+    assert_eq!(Ok(events.clone()), parse_logs(&ontora_ai::ID, &logs.logs));
+    for event in &events {
+        let line = format!("Program data: {}", STANDARD.encode(event.data()));
+        assert!(logs.logs.contains(&line), "{} does not round-trip", event.name());
+    }
+    events
+}
+
+// Test that the events a run of instructions emits decode from the captured logs, and
+// re-encode to the exact bytes the program logged
+#[tokio::test]
+async fn test_round_trips_emitted_events() {
+    let mut harness = Harness::start().await;
+    let (admin, owner, voter) = (harness.new_user().await, harness.new_user().await, harness.new_user().await);
+    let initialize = build_initialize_platform(InitializePlatformParams {
+        admin: admin.pubkey(),
+        reward_rate_bps: 100,
+        min_stake_amount: 1_000_000,
+        epoch_duration: 86_400,
+        cooldown_seconds: 86_400,
+        epoch_emission: 100_000,
+    });
+    harness.send(&[initialize], &[&admin]).await;
+
+    let admin_session = [
+        build_set_role(SetRoleParams { admin: admin.pubkey(), role: Role::Pauser, holder: admin.pubkey() }),
+        build_set_role(SetRoleParams { admin: admin.pubkey(), role: Role::Oracle, holder: admin.pubkey() }),
+        build_initialize_fee_schedule(InitializeFeeScheduleParams {
+            admin: admin.pubkey(),
+            governance: admin.pubkey(),
+            rates_bps: [0, 50, 100, 0],
+        }),
+        build_update_platform_config(UpdatePlatformConfigParams {
+            admin: admin.pubkey(),
+            reward_rate_bps: 200,
+            min_stake_amount: 2_000_000,
+            epoch_duration: 43_200,
+            cooldown_seconds: 86_400,
+            epoch_emission: 100_000,
+            max_stake_per_agent: 0,
+            vesting_seconds: 0,
+            vote_unit: 1_000_000,
+            quorum_bps: 2_000,
+            approval_threshold_bps: 6_000,
+            update_metadata: "Halve the epoch".to_string(),
+        }),
+        build_set_pause_flags(SetPauseFlagsParams { authority: admin.pubkey(), flags: 1 }),
+        build_clear_pause_flags(ClearPauseFlagsParams { admin: admin.pubkey(), flags: 1 }),
+        build_propose_admin(ProposeAdminParams { admin: admin.pubkey(), new_admin: owner.pubkey() }),
+        build_cancel_admin_transfer(CancelAdminTransferParams { admin: admin.pubkey() }),
+    ];
+    let mut events = decode_checked(&harness.send(&admin_session, &[&admin]).await);
+    match &events[3] {
+        OntoraEvent::PlatformUpdated(event) => {
+            assert_eq!((event.old_reward_rate, event.new_reward_rate), (100, 200));
+            assert_eq!(event.update_metadata, "Halve the epoch");
+        }
+        other => panic!("unexpected event {:?}", other),
+    }
+
+    let agent = [
+        build_register_agent(RegisterAgentParams {
+            owner: owner.pubkey(),
+            agent_id: 5,
+            name: "Atlas".to_string(),
+            description: "Market-making agent".to_string(),
+        }),
+        build_update_agent_metadata(UpdateAgentMetadataParams {
+            owner: owner.pubkey(),
+            agent_id: 5,
+            name: "Atlas II".to_string(),
+            description: "Market-making agent, retrained".to_string(),
+        }),
+    ];
+    events.extend(decode_checked(&harness.send(&agent, &[&owner]).await));
+    let score = build_update_performance_score(UpdatePerformanceScoreParams {
+        oracle: admin.pubkey(),
+        agent_owner: owner.pubkey(),
+        agent_id: 5,
+        score: 64,
+    });
+    events.extend(decode_checked(&harness.send(&[score], &[&admin]).await));
+
+    let proposal = [
+        build_create_governance_proposal(CreateGovernanceProposalParams {
+            creator: owner.pubkey(),
+            proposal_id: 0,
+            title: "Lower fees".to_string(),
+            description: "Drop the claim fee to 0.25%".to_string(),
+            voting_duration: 3_600,
+            options: vec!["Yes".to_string(), "No".to_string()],
+            action: ProposalAction::NoOp,
+        }),
+        build_cast_vote(CastVoteParams { voter: voter.pubkey(), proposal_id: 0, vote_option: 0 }),
+    ];
+    harness.seed_stake(&voter.pubkey(), 1_000_000);
+    events.extend(decode_checked(&harness.send(&proposal, &[&owner, &voter]).await));
+    let toggles = [false, true].map(|enabled| {
+        build_set_governance_enabled(SetGovernanceEnabledParams {
+            admin: admin.pubkey(),
+            enabled,
+            active_proposal_ids: vec![0],
+        })
+    });
+    events.extend(decode_checked(&harness.send(&toggles, &[&admin]).await));
+
+    let names: Vec<&str> = events.iter().map(OntoraEvent::name).collect();
+    assert_eq!(
+        names,
+        [
+            "RoleUpdated",
+            "RoleUpdated",
+            "FeeScheduleUpdated",
+            "PlatformUpdated",
+            "PauseFlagsUpdated",
+            "PauseFlagsUpdated",
+            "AdminTransferProposed",
+            "AdminTransferCancelled",
+            "AgentRegistered",
+            "AgentUpdated",
+            "PerformanceScoreUpdated",
+            "AgentUpdated",
+            "ProposalCreated",
+            "VoteCast",
+            "GovernanceSuspended",
+            "GovernanceResumed",
+        ]
+    );
+}
+
+// Test that every event in events.rs decodes from its logged bytes and encodes back
+#[test]
+fn test_every_event_round_trips() {
+    assert_eq!(OntoraEvent::DISCRIMINATORS.len(), OntoraEvent::NAMES.len());
+    for (discriminator, name) in OntoraEvent::DISCRIMINATORS.iter().zip(OntoraEvent::NAMES) {
+        // Zeroes decode as every field's empty value; the surplus is ignored
+        let mut bytes = discriminator.to_vec();
+        bytes.extend_from_slice(&[0; 256]);
+        let event = OntoraEvent::decode(&bytes).unwrap().unwrap();
+        assert_eq!(event.name(), *name);
+        let data = event.data();
+        assert!(bytes.starts_with(&data));
+
+        let logs = invocation_logs(&[&STANDARD.encode(&data)]);
+        assert_eq!(decode_events(&logs), vec![event]);
+    }
+}
+
+// Test that unknown discriminators and lines the RPC truncated or cut short are
+// skipped, and that a failed transaction has no events
+#[test]
+fn test_skips_what_cannot_be_decoded() {
+    let event = VoteCast {
+        schema_version: VoteCast::SCHEMA_VERSION,
+        proposal_id: 2,
+        voter: Pubkey::new_unique(),
+        vote_option: 1,
+        vote_weight: 40,
+        timestamp: 1_700_000_000,
+    };
+    let encoded = STANDARD.encode(event.data());
+    let mut cut_short = VoteCast::discriminator().to_vec();
+    cut_short.extend_from_slice(&[2, 0, 0]);
+    let unknown = STANDARD.encode([9; 40]);
+    let cut_short = STANDARD.encode(cut_short);
+    let mut logs = invocation_logs(&[&unknown, &cut_short, &encoded[..encoded.len() - 7], "not*base64", &encoded]);
+    logs.insert(logs.len() - 1, "Log truncated".to_string());
+    assert_eq!(decode_events(&logs), vec![OntoraEvent::VoteCast(event.clone())]);
+
+    let mut notification = RpcTransactionLogs { signature: String::new(), err: None, logs };
+    assert_eq!(decode_rpc_logs(&notification).len(), 1);
+    notification.err = Some(TransactionError::InstructionError(0, InstructionError::Custom(6000)));
+    assert!(decode_rpc_logs(&notification).is_empty());
+}
+
+// Test decoding a confirmed transaction as getTransaction returns it
+#[test]
+fn test_decodes_confirmed_transaction() {
+    let event = GovernanceResumed {
+        schema_version: GovernanceResumed::SCHEMA_VERSION,
+        admin: Pubkey::new_unique(),
+        resumed_proposals: 3,
+        timestamp: 1_700_000_000,
+    };
+    let confirmed = |meta: Option<TransactionStatusMeta>| EncodedConfirmedTransactionWithStatusMeta {
+        slot: 42,
+        transaction: EncodedTransactionWithStatusMeta {
+            transaction: EncodedTransaction::LegacyBinary(String::new()),
+            meta: meta.map(Into::into),
+            version: None,
+        },
+        block_time: None,
+    };
+    let logs = invocation_logs(&[&STANDARD.encode(event.data())]);
+    let meta = TransactionStatusMeta { log_messages: Some(logs), ..TransactionStatusMeta::default() };
+
+    let decoded = decode_transaction_events(&confirmed(Some(meta.clone())));
+    assert_eq!(decoded, vec![OntoraEvent::GovernanceResumed(event)]);
+
+    let failed = TransactionStatusMeta {
+        status: Err(TransactionError::InstructionError(0, InstructionError::Custom(6000))),
+        ..meta.clone()
+    };
+    assert!(decode_transaction_events(&confirmed(Some(failed))).is_empty());
+    let without_logs = TransactionStatusMeta { log_messages: None, ..meta };
+    assert!(decode_transaction_events(&confirmed(Some(without_logs))).is_empty());
+    assert!(decode_transaction_events(&confirmed(None)).is_empty());
+}
@@ -0,0 +1,156 @@
+// test_history.rs
+// Wallet timeline tests. Synthetic events from two overlapping sources are built
+// into a timeline; each event must appear once, in time order, with the running
+// stake and claimed rewards after it, and missing events must surface as gaps
+// against the events themselves and against the stake index entries on chain.
+
+use std::collections::BTreeMap;
+
+use anchor_lang::solana_program::pubkey::Pubkey;
+use ontora_ai::events::{RewardClaimed, StakeDeposited, StakeWithdrawn, VoteCast};
+use ontora_ai::state::StakeIndexEntry;
+use ontora_client::{on_chain_stakes, wallet_events, Activity, Gap, OntoraEvent, Timeline, WalletEvent};
+use solana_sdk::signature::Signature;
+
+const TIMESTAMP: i64 = 1_700_000_000;
+
+fn event(offset: i64, activity: Activity) -> WalletEvent {
+    WalletEvent { signature: Signature::new_unique(), index: 0, timestamp: TIMESTAMP + offset, activity }
+}
+
+fn stake(offset: i64, agent_id: u64, amount: u64) -> WalletEvent {
+    event(offset, Activity::Stake { agent_id, amount })
+}
+
+fn unstake(offset: i64, agent_id: u64, amount: u64) -> WalletEvent {
+    event(offset, Activity::Unstake { agent_id, amount })
+}
+
+fn claim(offset: i64, agent_id: u64, amount: u64) -> WalletEvent {
+    event(offset, Activity::Claim { agent_id, amount })
+}
+
+fn balances(timeline: &Timeline) -> Vec<(u64, u64)> {
+    timeline.entries.iter().map(|entry| (entry.staked, entry.claimed)).collect()
+}
+
+// Test that events from overlapping sources are kept once, ordered by time, with running balances
+#[test]
+fn test_build_orders_and_dedups() {
+    let events = [
+        stake(0, 1, 500),
+        stake(10, 2, 300),
+        claim(20, 1, 25),
+        unstake(30, 1, 200),
+        event(40, Activity::Vote { proposal_id: 4, option: 1, weight: 600 }),
+        claim(50, 3, 5),
+    ];
+    // The database has the first four events, the transactions the last four
+    let database = events[..4].to_vec();
+    let transactions = events[2..].to_vec();
+    let timeline = Timeline::build(transactions.into_iter().chain(database));
+
+    let ordered: Vec<&WalletEvent> = timeline.entries.iter().map(|entry| &entry.event).collect();
+    assert_eq!(ordered, events.iter().collect::<Vec<_>>());
+    assert_eq!(balances(&timeline), [(500, 0), (800, 0), (800, 25), (600, 25), (600, 25), (600, 30)]);
+    assert_eq!((timeline.staked(), timeline.claimed()), (600, 30));
+    assert_eq!(timeline.staked_by_agent, BTreeMap::from([(1, 300), (2, 300)]));
+    assert!(timeline.gaps().is_empty());
+}
+
+// Test that events of the same time keep the order they were given in
+#[test]
+fn test_build_keeps_order_of_ties() {
+    let events = [stake(0, 1, 100), unstake(0, 1, 100), stake(0, 1, 40)];
+    let timeline = Timeline::build(events.to_vec());
+    assert_eq!(balances(&timeline), [(100, 0), (0, 0), (40, 0)]);
+    assert!(timeline.gaps().is_empty());
+}
+
+// Test that an unstake beyond the events' stake is a gap, and the stake saturates at zero
+#[test]
+fn test_missing_deposits() {
+    let timeline = Timeline::build([stake(0, 1, 100), unstake(10, 1, 250), stake(20, 1, 50)]);
+    assert_eq!(balances(&timeline), [(100, 0), (0, 0), (50, 0)]);
+    let gap = Gap::MissingDeposits { agent_id: 1, missing: 150 };
+    assert_eq!(timeline.entries[1].gap, Some(gap));
+    assert_eq!(timeline.gaps(), [gap]);
+    assert_eq!(gap.to_string(), "150 more unstaked from agent 1 than the events staked");
+}
+
+// Test that the final stake is reconciled per agent against the stake index entries
+#[test]
+fn test_reconcile() {
+    let timeline = Timeline::build([stake(0, 1, 100), stake(10, 2, 300), unstake(20, 2, 300)]);
+    let entry = |agent_id, staked_amount| {
+        (Pubkey::new_unique(), StakeIndexEntry { agent_id, staked_amount, ..StakeIndexEntry::default() })
+    };
+    assert!(timeline.reconcile(&on_chain_stakes(&[entry(1, 100), entry(2, 0)])).is_empty());
+
+    let gaps = timeline.reconcile(&on_chain_stakes(&[entry(1, 80), entry(3, 40)]));
+    assert_eq!(
+        gaps,
+        [
+            Gap::Unreconciled { agent_id: 1, timeline: 100, on_chain: 80 },
+            Gap::Unreconciled { agent_id: 3, timeline: 0, on_chain: 40 },
+        ]
+    );
+    assert_eq!(gaps[0].to_string(), "agent 1: the events leave 100 staked, the chain holds 80");
+}
+
+// Test that only the wallet's events of a transaction are taken, at their position in it
+#[test]
+fn test_wallet_events() {
+    let (wallet, other, signature) = (Pubkey::new_unique(), Pubkey::new_unique(), Signature::new_unique());
+    let deposited = |user| {
+        let event = StakeDeposited {
+            schema_version: StakeDeposited::SCHEMA_VERSION,
+            user,
+            agent_id: 1,
+            amount: 70,
+            timestamp: TIMESTAMP,
+            staking_duration: 0,
+        };
+        OntoraEvent::StakeDeposited(event)
+    };
+    let events = [
+        deposited(other),
+        deposited(wallet),
+        OntoraEvent::StakeWithdrawn(StakeWithdrawn {
+            schema_version: StakeWithdrawn::SCHEMA_VERSION,
+            user: wallet,
+            agent_id: 1,
+            amount: 20,
+            timestamp: TIMESTAMP,
+        }),
+        OntoraEvent::RewardClaimed(RewardClaimed {
+            schema_version: RewardClaimed::SCHEMA_VERSION,
+            user: wallet,
+            agent_id: 2,
+            reward_amount: 9,
+            timestamp: TIMESTAMP,
+        }),
+        OntoraEvent::VoteCast(VoteCast {
+            schema_version: VoteCast::SCHEMA_VERSION,
+            proposal_id: 3,
+            voter: wallet,
+            timestamp: TIMESTAMP,
+            vote_option: 2,
+            vote_weight: 50,
+        }),
+    ];
+
+    let found = wallet_events(&wallet, &signature, &events);
+    let summary: Vec<(usize, Activity)> = found.iter().map(|event| (event.index, event.activity)).collect();
+    assert_eq!(
+        summary,
+        [
+            (1, Activity::Stake { agent_id: 1, amount: 70 }),
+            (2, Activity::Unstake { agent_id: 1, amount: 20 }),
+            (3, Activity::Claim { agent_id: 2, amount: 9 }),
+            (4, Activity::Vote { proposal_id: 3, option: 2, weight: 50 }),
+        ]
+    );
+    assert!(found.iter().all(|event| event.signature == signature));
+    assert_eq!(wallet_events(&other, &signature, &events).len(), 1);
+}
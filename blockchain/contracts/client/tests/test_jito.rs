@@ -0,0 +1,317 @@
+// test_jito.rs
+// Tests for `send_bundle_with_fallback`, built with the `jito` feature. A mock block
+// engine stands in for Jito: it records each bundle and, as scripted, lands it whole on a
+// mock cluster, accepts it and lets it expire, or refuses the connection. The mock
+// cluster lands every transaction submitted over RPC unless told to fail one.
+
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+use ontora_client::*;
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::{Instruction, InstructionError};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature, Signer};
+use solana_sdk::system_instruction::SystemInstruction;
+use solana_sdk::system_program;
+use solana_sdk::transaction::{TransactionError, VersionedTransaction};
+
+const VALIDITY: u64 = 3;
+const TIP: u64 = 10_000;
+
+#[derive(Default)]
+struct MockCluster {
+    height: Cell<u64>,
+    landed: RefCell<HashSet<Signature>>,
+    failed: RefCell<HashMap<Signature, TransactionError>>,
+    // Indexes of the RPC submissions that fail on chain
+    failing: Vec<usize>,
+    // Transactions submitted over RPC, in order
+    submitted: RefCell<Vec<VersionedTransaction>>,
+}
+
+impl TransactionRpc for MockCluster {
+    fn recent_prioritization_fees(&self, _: &[Pubkey]) -> Result<Vec<u64>, ClientError> {
+        unreachable!("no percentile fee is configured")
+    }
+
+    fn simulate_units(&self, _: &VersionedTransaction) -> Result<u64, ClientError> {
+        unreachable!("every limit is fixed")
+    }
+}
+
+impl SendRpc for MockCluster {
+    fn latest_blockhash(&self) -> Result<(Hash, u64), ClientError> {
+        Ok((Hash::new_unique(), self.height.get() + VALIDITY))
+    }
+
+    fn submit(&self, transaction: &VersionedTransaction) -> Result<Signature, ClientError> {
+        let signature = transaction.signatures[0];
+        let mut submitted = self.submitted.borrow_mut();
+        if self.failing.contains(&submitted.len()) {
+            let error = TransactionError::InstructionError(1, InstructionError::Custom(6001));
+            self.failed.borrow_mut().insert(signature, error);
+        } else {
+            self.landed.borrow_mut().insert(signature);
+        }
+        submitted.push(transaction.clone());
+        Ok(signature)
+    }
+
+    fn landing_status(&self, signature: &Signature) -> Result<LandingStatus, ClientError> {
+        if self.landed.borrow().contains(signature) {
+            return Ok(LandingStatus::Committed(Ok(())));
+        }
+        Ok(match self.failed.borrow().get(signature) {
+            Some(error) => LandingStatus::Committed(Err(error.clone())),
+            None => LandingStatus::NotFound,
+        })
+    }
+
+    fn block_height(&self) -> Result<u64, ClientError> {
+        self.height.set(self.height.get() + 1);
+        Ok(self.height.get())
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Engine {
+    Lands,
+    // Accepts bundles that never land
+    Drops,
+    Down,
+}
+
+struct MockEngine<'a> {
+    cluster: &'a MockCluster,
+    mode: Engine,
+    tip_floor: u64,
+    bundles: RefCell<Vec<Vec<VersionedTransaction>>>,
+    floor_requests: RefCell<Vec<u8>>,
+}
+
+impl<'a> MockEngine<'a> {
+    fn new(cluster: &'a MockCluster, mode: Engine) -> Self {
+        MockEngine { cluster, mode, tip_floor: 0, bundles: RefCell::default(), floor_requests: RefCell::default() }
+    }
+}
+
+impl BlockEngine for MockEngine<'_> {
+    fn send_bundle(&self, transactions: &[VersionedTransaction]) -> Result<String, ClientError> {
+        if self.mode == Engine::Down {
+            return Err(ClientError::Bundle("connection refused".to_string()));
+        }
+        self.bundles.borrow_mut().push(transactions.to_vec());
+        if self.mode == Engine::Lands {
+            self.cluster.landed.borrow_mut().extend(transactions.iter().map(|transaction| transaction.signatures[0]));
+        }
+        Ok(format!("bundle-{}", self.bundles.borrow().len()))
+    }
+
+    fn tip_floor(&self, percentile: u8) -> Result<u64, ClientError> {
+        self.floor_requests.borrow_mut().push(percentile);
+        Ok(self.tip_floor)
+    }
+}
+
+fn policy() -> RetryPolicy {
+    RetryPolicy {
+        base_backoff: Duration::ZERO,
+        max_backoff: Duration::ZERO,
+        poll_interval: Duration::ZERO,
+        ..RetryPolicy::default()
+    }
+}
+
+// Distribution batches of one agent each, the last closing the epoch
+fn batches(crank: &Keypair, count: usize) -> Vec<TxBuilder> {
+    (0..count)
+        .map(|index| {
+            let batch = build_distribute_rewards_batch(DistributeRewardsBatchParams {
+                crank: crank.pubkey(),
+                agents: vec![Pubkey::new_unique()],
+                close_epoch: index + 1 == count,
+            });
+            TxBuilder::new(crank.pubkey()).instruction(batch).compute_limit(ComputeLimit::Fixed(40_000))
+        })
+        .collect()
+}
+
+// The transaction's instructions other than the compute budget's
+fn program_instructions(transaction: &VersionedTransaction) -> Vec<Instruction> {
+    let keys = transaction.message.static_account_keys();
+    transaction
+        .message
+        .instructions()
+        .iter()
+        .filter(|instruction| keys[instruction.program_id_index as usize] != solana_sdk::compute_budget::ID)
+        .map(|instruction| Instruction {
+            program_id: keys[instruction.program_id_index as usize],
+            accounts: Vec::new(),
+            data: instruction.data.clone(),
+        })
+        .collect()
+}
+
+fn batch_data(builder: &TxBuilder, cluster: &MockCluster) -> Vec<u8> {
+    let instructions = builder.build(cluster).unwrap();
+    instructions.last().unwrap().data.clone()
+}
+
+fn send(
+    cluster: &MockCluster,
+    engine: &MockEngine,
+    builders: &[TxBuilder],
+    crank: &Keypair,
+    budget: &mut TipBudget,
+) -> Result<BundleOutcome, ClientError> {
+    send_bundle_with_fallback(cluster, engine, builders, &[crank], budget, &policy())
+}
+
+// Test that a bundle holds the batches in order and then the tip: a transfer from the
+// crank to a Jito tip account, all signed with one blockhash, and that the budget is
+// only charged once it lands
+#[test]
+fn test_bundle_composition_and_tip_placement() {
+    let crank = Keypair::new();
+    let cluster = MockCluster::default();
+    let engine = MockEngine::new(&cluster, Engine::Lands);
+    let builders = batches(&crank, 3);
+    let mut budget = TipBudget::new(TipStrategy::Fixed(TIP), 50_000, 25_000);
+    let outcome = send(&cluster, &engine, &builders, &crank, &mut budget).unwrap();
+
+    let bundles = engine.bundles.borrow();
+    assert_eq!(bundles.len(), 1);
+    let bundle = &bundles[0];
+    assert_eq!(bundle.len(), 4);
+    for (transaction, builder) in bundle.iter().zip(&builders) {
+        assert_eq!(program_instructions(transaction)[0].data, batch_data(builder, &cluster));
+    }
+    let blockhash = bundle[0].message.recent_blockhash();
+    assert!(bundle.iter().all(|transaction| transaction.message.recent_blockhash() == blockhash));
+    assert!(bundle.iter().all(|transaction| transaction.message.static_account_keys()[0] == crank.pubkey()));
+    assert!(bundle.iter().all(|transaction| transaction.verify_with_results().iter().all(|valid| *valid)));
+
+    let tip = &bundle[3];
+    let tip_instructions = program_instructions(tip);
+    assert_eq!(tip_instructions.len(), 1);
+    assert_eq!(tip_instructions[0].program_id, system_program::ID);
+    let transfer: SystemInstruction = bincode::deserialize(&tip_instructions[0].data).unwrap();
+    assert_eq!(transfer, SystemInstruction::Transfer { lamports: TIP });
+    let tip_account = tip.message.static_account_keys()[1];
+    assert!(JITO_TIP_ACCOUNTS.contains(&tip_account));
+
+    assert_eq!(outcome.route, Route::Bundle { id: "bundle-1".to_string(), tip: TIP, tip_account });
+    let confirmed: Vec<SendOutcome> = bundle[..3].iter().map(|tx| SendOutcome::Confirmed(tx.signatures[0])).collect();
+    assert_eq!(outcome.outcomes, confirmed);
+    assert_eq!(budget.remaining, 25_000 - TIP);
+    assert!(cluster.submitted.borrow().is_empty());
+    drop(bundles);
+
+    // Room for four batches besides the tip, and no fewer than one
+    assert!(matches!(send(&cluster, &engine, &batches(&crank, 5), &crank, &mut budget), Err(ClientError::Build(_))));
+    assert!(matches!(send(&cluster, &engine, &[], &crank, &mut budget), Err(ClientError::Build(_))));
+    assert_eq!(send(&cluster, &engine, &batches(&crank, 4), &crank, &mut budget).unwrap().outcomes.len(), 4);
+    assert_eq!(engine.bundles.borrow()[1].len(), MAX_BUNDLE_TRANSACTIONS);
+}
+
+// Test each tip strategy's amount, the cap on it, and how strategies are written
+#[test]
+fn test_tip_strategies() {
+    let cluster = MockCluster::default();
+    let mut engine = MockEngine::new(&cluster, Engine::Lands);
+    engine.tip_floor = 30_000;
+
+    let budget = |strategy| TipBudget::new(strategy, 20_000, 1_000_000);
+    assert_eq!(budget(TipStrategy::Fixed(TIP)).tip(&engine, 3).unwrap(), TIP);
+    assert_eq!(budget(TipStrategy::Fixed(TIP * 5)).tip(&engine, 3).unwrap(), 20_000);
+    assert_eq!(budget(TipStrategy::PerTransaction(2_500)).tip(&engine, 3).unwrap(), 7_500);
+    assert_eq!(budget(TipStrategy::Percentile(75)).tip(&engine, 3).unwrap(), 20_000);
+    assert_eq!(*engine.floor_requests.borrow(), [75]);
+    engine.tip_floor = 4_000;
+    assert_eq!(budget(TipStrategy::Percentile(50)).tip(&engine, 3).unwrap(), 4_000);
+
+    // The tip sent follows the strategy
+    let crank = Keypair::new();
+    let mut per_batch = budget(TipStrategy::PerTransaction(2_500));
+    let outcome = send(&cluster, &engine, &batches(&crank, 2), &crank, &mut per_batch).unwrap();
+    assert!(matches!(outcome.route, Route::Bundle { tip: 5_000, .. }));
+    assert_eq!(per_batch.remaining, 1_000_000 - 5_000);
+
+    assert_eq!("10000".parse(), Ok(TipStrategy::Fixed(10_000)));
+    assert_eq!("2500/tx".parse(), Ok(TipStrategy::PerTransaction(2_500)));
+    assert_eq!("p95".parse(), Ok(TipStrategy::Percentile(95)));
+    assert!("p101".parse::<TipStrategy>().is_err());
+    assert!("lots".parse::<TipStrategy>().is_err());
+    assert!("/tx".parse::<TipStrategy>().is_err());
+}
+
+// Test that batches go over RPC, in order, when the block engine can't be reached, and
+// that the budget is left alone
+#[test]
+fn test_falls_back_when_engine_unavailable() {
+    let crank = Keypair::new();
+    let cluster = MockCluster::default();
+    let engine = MockEngine::new(&cluster, Engine::Down);
+    let builders = batches(&crank, 3);
+    let mut budget = TipBudget::new(TipStrategy::Fixed(TIP), 50_000, 25_000);
+    let outcome = send(&cluster, &engine, &builders, &crank, &mut budget).unwrap();
+
+    let unavailable = matches!(outcome.route, Route::Rpc(Fallback::Unavailable(reason)) if reason.contains("refused"));
+    assert!(unavailable);
+    let submitted = cluster.submitted.borrow();
+    assert_eq!(submitted.len(), 3);
+    for (transaction, builder) in submitted.iter().zip(&builders) {
+        assert_eq!(program_instructions(transaction)[0].data, batch_data(builder, &cluster));
+    }
+    let confirmed: Vec<SendOutcome> = submitted.iter().map(|tx| SendOutcome::Confirmed(tx.signatures[0])).collect();
+    assert_eq!(outcome.outcomes, confirmed);
+    // No tip went over RPC
+    assert!(submitted.iter().all(|transaction| program_instructions(transaction)[0].program_id == ontora_ai::ID));
+    assert_eq!(budget.remaining, 25_000);
+}
+
+// Test that a tip the budget can't cover sends the batches over RPC without asking the
+// block engine, once earlier bundles have spent it
+#[test]
+fn test_falls_back_when_budget_exhausted() {
+    let crank = Keypair::new();
+    let cluster = MockCluster::default();
+    let engine = MockEngine::new(&cluster, Engine::Lands);
+    let mut budget = TipBudget::new(TipStrategy::Fixed(TIP), 50_000, 15_000);
+
+    let first = send(&cluster, &engine, &batches(&crank, 2), &crank, &mut budget).unwrap();
+    assert!(matches!(first.route, Route::Bundle { .. }));
+    let second = send(&cluster, &engine, &batches(&crank, 2), &crank, &mut budget).unwrap();
+    assert_eq!(second.route, Route::Rpc(Fallback::BudgetExhausted { tip: TIP, remaining: 5_000 }));
+    assert_eq!(engine.bundles.borrow().len(), 1);
+    assert_eq!(cluster.submitted.borrow().len(), 2);
+    assert!(second.outcomes.iter().all(|outcome| matches!(outcome, SendOutcome::Confirmed(_))));
+    assert_eq!(budget.remaining, 5_000);
+}
+
+// Test that a bundle accepted but expired unlanded is sent again over RPC without
+// charging its tip, and that the RPC fallback stops at the first batch that fails
+#[test]
+fn test_falls_back_when_bundle_expires() {
+    let crank = Keypair::new();
+    let cluster = MockCluster::default();
+    let engine = MockEngine::new(&cluster, Engine::Drops);
+    let mut budget = TipBudget::new(TipStrategy::Fixed(TIP), 50_000, 25_000);
+    let outcome = send(&cluster, &engine, &batches(&crank, 3), &crank, &mut budget).unwrap();
+
+    assert_eq!(outcome.route, Route::Rpc(Fallback::Expired));
+    assert_eq!(engine.bundles.borrow().len(), 1);
+    // The bundle's blockhash had expired before the batches went over RPC
+    assert!(cluster.height.get() > VALIDITY);
+    assert_eq!(cluster.submitted.borrow().len(), 3);
+    assert_eq!(outcome.outcomes.len(), 3);
+    assert_eq!(budget.remaining, 25_000);
+
+    let cluster = MockCluster { failing: vec![1], ..MockCluster::default() };
+    let engine = MockEngine::new(&cluster, Engine::Down);
+    let outcome = send(&cluster, &engine, &batches(&crank, 3), &crank, &mut budget).unwrap();
+    assert_eq!(cluster.submitted.borrow().len(), 2);
+    assert!(matches!(outcome.outcomes[..], [SendOutcome::Confirmed(_), SendOutcome::FailedOnChain(_)]));
+}
@@ -0,0 +1,224 @@
+// test_lookup_table.rs
+// Integration test for v0 transactions. One user spreads stake across 20 agents in a
+// single transaction: as a legacy transaction its account keys alone overflow the
+// packet, and with the platform, agent and stake accounts resolved through an address
+// lookup table it fits and lands under program-test.
+
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::AccountDeserialize;
+use ontora_ai::state::{StakeIndexEntry, UserStake};
+use ontora_client::*;
+use solana_program_test::*;
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::message::VersionedMessage;
+use solana_sdk::packet::PACKET_DATA_SIZE;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::slot_hashes::SlotHashes;
+use solana_sdk::system_instruction;
+use solana_sdk::sysvar::clock::Clock;
+use solana_sdk::transaction::{Transaction, VersionedTransaction};
+
+const LAMPORTS: u64 = 10_000_000_000;
+const MINT_DECIMALS: u8 = 9;
+const AGENTS: u64 = 20;
+const STAKE: u64 = 1_000_000_000;
+
+// The builder only reaches the cluster for percentile fees and simulation, which
+// these transactions don't use
+struct Offline;
+
+impl TransactionRpc for Offline {
+    fn recent_prioritization_fees(&self, _: &[Pubkey]) -> Result<Vec<u64>, ClientError> {
+        unreachable!("no percentile fee is configured")
+    }
+
+    fn simulate_units(&self, _: &VersionedTransaction) -> Result<u64, ClientError> {
+        unreachable!("no simulated limit is configured")
+    }
+}
+
+struct Harness {
+    ctx: ProgramTestContext,
+}
+
+impl Harness {
+    async fn start() -> Self {
+        let test = ProgramTest::new("ontora_ai", ontora_ai::ID, processor!(ontora_ai::entry));
+        Harness { ctx: test.start_with_context().await }
+    }
+
+    async fn send(&mut self, instructions: &[Instruction], signers: &[&Keypair]) {
+        let mut all_signers = vec![&self.ctx.payer];
+        all_signers.extend_from_slice(signers);
+        let tx = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.ctx.payer.pubkey()),
+            &all_signers,
+            self.ctx.last_blockhash,
+        );
+        self.ctx.banks_client.process_transaction(tx).await.unwrap();
+    }
+
+    async fn new_user(&mut self) -> Keypair {
+        let user = Keypair::new();
+        let transfer = system_instruction::transfer(&self.ctx.payer.pubkey(), &user.pubkey(), LAMPORTS);
+        self.send(&[transfer], &[]).await;
+        user
+    }
+
+    async fn data(&mut self, address: &Pubkey) -> Vec<u8> {
+        self.ctx.banks_client.get_account(*address).await.unwrap().expect("account exists").data
+    }
+
+    async fn account<T: AccountDeserialize>(&mut self, address: &Pubkey) -> T {
+        let data = self.data(address).await;
+        decode_account(address, &data).unwrap()
+    }
+
+    async fn slot(&mut self) -> u64 {
+        self.ctx.banks_client.get_sysvar::<Clock>().await.unwrap().slot
+    }
+
+    async fn next_slot(&mut self) {
+        let slot = self.slot().await;
+        self.ctx.warp_to_slot(slot + 1).unwrap();
+        self.ctx.last_blockhash = self.ctx.banks_client.get_latest_blockhash().await.unwrap();
+    }
+
+    // A mint controlled by `authority`, with `amount` in `user`'s token account and the
+    // platform's vaults, created by `authority` as the platform admin
+    async fn mint(&mut self, authority: &Keypair, user: &Pubkey, amount: u64) -> (Pubkey, Pubkey, Pubkey) {
+        let mint = Keypair::new();
+        let rent = self.ctx.banks_client.get_rent().await.unwrap();
+        let user_tokens = spl_associated_token_account::get_associated_token_address(user, &mint.pubkey());
+        let payer = self.ctx.payer.pubkey();
+        let instructions = [
+            system_instruction::create_account(
+                &payer,
+                &mint.pubkey(),
+                rent.minimum_balance(spl_token::state::Mint::LEN),
+                spl_token::state::Mint::LEN as u64,
+                &spl_token::ID,
+            ),
+            spl_token::instruction::initialize_mint(
+                &spl_token::ID,
+                &mint.pubkey(),
+                &authority.pubkey(),
+                None,
+                MINT_DECIMALS,
+            )
+            .unwrap(),
+            spl_associated_token_account::instruction::create_associated_token_account(
+                &payer,
+                user,
+                &mint.pubkey(),
+                &spl_token::ID,
+            ),
+            build_initialize_vaults(InitializeVaultsParams { admin: authority.pubkey(), stake_mint: mint.pubkey() }),
+            spl_token::instruction::mint_to(
+                &spl_token::ID,
+                &mint.pubkey(),
+                &user_tokens,
+                &authority.pubkey(),
+                &[],
+                amount,
+            )
+            .unwrap(),
+        ];
+        self.send(&instructions, &[&mint, authority]).await;
+        (mint.pubkey(), user_tokens, pda::staking_vault().0)
+    }
+
+    // Create a lookup table holding `addresses`, usable from the next slot
+    async fn lookup_table(&mut self, authority: &Keypair, addresses: &[Pubkey]) -> Pubkey {
+        // The table address derives from a recent slot, which must be in SlotHashes
+        let recent_slot = self.slot().await;
+        self.ctx.set_sysvar(&SlotHashes::new(&[(recent_slot, Hash::new_unique())]));
+        let (create, table) = build_create_lookup_table(&authority.pubkey(), &authority.pubkey(), recent_slot);
+        self.send(&[create], &[authority]).await;
+        for extend in build_extend_lookup_table(&table, &authority.pubkey(), &authority.pubkey(), addresses) {
+            self.send(&[extend], &[authority]).await;
+        }
+        self.next_slot().await;
+        table
+    }
+}
+
+// Test that a 20-agent stake batch only fits in a packet with a lookup table, and that
+// the v0 transaction stakes on every agent
+#[tokio::test]
+async fn test_twenty_agent_batch_needs_lookup_table() {
+    let mut harness = Harness::start().await;
+    let (admin, owner, user) = (harness.new_user().await, harness.new_user().await, harness.new_user().await);
+    let initialize = build_initialize_platform(InitializePlatformParams {
+        admin: admin.pubkey(),
+        reward_rate_bps: 100,
+        min_stake_amount: 1_000_000,
+        epoch_duration: 86_400,
+        cooldown_seconds: 86_400,
+        epoch_emission: 100_000,
+    });
+    harness.send(&[initialize], &[&admin]).await;
+
+    let agent_ids: Vec<u64> = (0..AGENTS).collect();
+    for ids in agent_ids.chunks(5) {
+        let registers: Vec<Instruction> = ids
+            .iter()
+            .map(|&agent_id| {
+                build_register_agent(RegisterAgentParams {
+                    owner: owner.pubkey(),
+                    agent_id,
+                    name: format!("Agent {}", agent_id),
+                    description: "Batch target".to_string(),
+                })
+            })
+            .collect();
+        harness.send(&registers, &[&owner]).await;
+    }
+    let (mint, user_tokens, platform_vault) = harness.mint(&admin, &user.pubkey(), AGENTS * STAKE).await;
+
+    let agents: Vec<(Pubkey, u64)> = agent_ids.iter().map(|&agent_id| (owner.pubkey(), agent_id)).collect();
+    let mut addresses = platform_lookup_addresses(&[mint]);
+    addresses.extend(agent_lookup_addresses(&agents));
+    addresses.extend(stake_lookup_addresses(&user.pubkey(), &agent_ids));
+    addresses.push(user_tokens);
+    assert!(addresses.len() > MAX_EXTEND_ADDRESSES);
+    let table_address = harness.lookup_table(&admin, &addresses).await;
+    let table = decode_lookup_table(&table_address, &harness.data(&table_address).await).unwrap();
+    assert_eq!(table.addresses, addresses);
+
+    let stakes = agent_ids.iter().map(|&agent_id| {
+        build_stake_on_agent(StakeOnAgentParams {
+            user: user.pubkey(),
+            user_token_account: user_tokens,
+            platform_vault,
+            agent_owner: owner.pubkey(),
+            agent_id,
+            amount: STAKE,
+            beneficiary: None,
+        })
+    });
+    let builder = TxBuilder::new(user.pubkey()).instructions(stakes);
+    let blockhash = harness.ctx.last_blockhash;
+
+    let legacy = builder.transaction(&Offline, &[&user], blockhash).unwrap();
+    assert!(matches!(legacy.message, VersionedMessage::Legacy(_)));
+    assert!(bincode::serialize(&legacy).unwrap().len() > PACKET_DATA_SIZE);
+
+    let versioned = builder.lookup_table(table).transaction(&Offline, &[&user], blockhash).unwrap();
+    let VersionedMessage::V0(message) = &versioned.message else {
+        panic!("a lookup table makes the transaction v0");
+    };
+    assert_eq!(message.address_table_lookups.len(), 1);
+    assert!(bincode::serialize(&versioned).unwrap().len() <= PACKET_DATA_SIZE);
+    harness.ctx.banks_client.process_transaction(versioned).await.unwrap();
+
+    let position: UserStake = harness.account(&pda::user_stake(&user.pubkey()).0).await;
+    assert_eq!(position.staked_amount, AGENTS * STAKE);
+    for agent_id in agent_ids {
+        let entry: StakeIndexEntry = harness.account(&pda::stake_index(&user.pubkey(), agent_id).0).await;
+        assert_eq!(entry.staked_amount, STAKE);
+    }
+}
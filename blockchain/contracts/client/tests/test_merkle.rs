@@ -0,0 +1,80 @@
+// test_merkle.rs
+// Airdrop tree tests. A 100-entry list is written as CSV, parsed back and built
+// into a tree; every entry's proof from the proofs file must lead to the root, and
+// a proof must fail for another amount, another wallet or a tampered sibling.
+
+use anchor_lang::solana_program::hash::{hashv, Hash};
+use anchor_lang::solana_program::pubkey::Pubkey;
+use ontora_client::{
+    find_proof, leaf_hash, node_hash, parse_airdrop_csv, verify_proof, AirdropEntry, AirdropTree, ClientError,
+};
+
+fn airdrop_list(count: u64) -> Vec<AirdropEntry> {
+    (1..=count).map(|index| AirdropEntry { wallet: Pubkey::new_unique(), amount: index * 1_000 }).collect()
+}
+
+fn csv(entries: &[AirdropEntry]) -> String {
+    let lines: String = entries.iter().map(|entry| format!("{},{}\n", entry.wallet, entry.amount)).collect();
+    format!("wallet,amount\n{}", lines)
+}
+
+// Test that every entry of a 100-entry list proves against the root from the proofs file
+#[test]
+fn test_proofs_round_trip() {
+    let entries = airdrop_list(100);
+    let parsed = parse_airdrop_csv(&csv(&entries)).unwrap();
+    assert_eq!(parsed, entries);
+    let tree = AirdropTree::new(parsed).unwrap();
+    let proofs: serde_json::Value = serde_json::from_str(&tree.proofs_json().to_string()).unwrap();
+    let root: Hash = proofs["root"].as_str().unwrap().parse().unwrap();
+    assert_eq!(root, tree.root());
+
+    for entry in &entries {
+        let (found, proof) = find_proof(&proofs, &entry.wallet).unwrap();
+        assert_eq!(found, *entry);
+        // 100 leaves take seven levels above them, the last odd ones carried up without a sibling
+        assert!(proof.len() <= 7);
+        assert!(verify_proof(&root, entry, &proof), "{} does not prove", entry.wallet);
+    }
+    assert!(matches!(find_proof(&proofs, &Pubkey::new_unique()), Err(ClientError::Airdrop(_))));
+}
+
+// Test that a proof fails for anything but the entry it was made for
+#[test]
+fn test_proofs_reject_other_entries() {
+    let entries = airdrop_list(5);
+    let tree = AirdropTree::new(entries.clone()).unwrap();
+    let proof = tree.proof(2);
+    assert!(verify_proof(&tree.root(), &entries[2], &proof));
+    assert!(!verify_proof(&tree.root(), &AirdropEntry { amount: entries[2].amount + 1, ..entries[2] }, &proof));
+    assert!(!verify_proof(&tree.root(), &entries[3], &proof));
+    let mut tampered = proof.clone();
+    tampered[0] = Hash::new_unique();
+    assert!(!verify_proof(&tree.root(), &entries[2], &tampered));
+}
+
+// Test the hashing: domain-separated leaves, and nodes over their children in ascending order
+#[test]
+fn test_hashing() {
+    let entry = AirdropEntry { wallet: Pubkey::new_unique(), amount: 42 };
+    assert_eq!(leaf_hash(&entry), hashv(&[&[0], entry.wallet.as_ref(), &42u64.to_le_bytes()]));
+    let (a, b) = (Hash::new_unique(), Hash::new_unique());
+    assert_eq!(node_hash(&a, &b), node_hash(&b, &a));
+    let (low, high) = if a < b { (a, b) } else { (b, a) };
+    assert_eq!(node_hash(&a, &b), hashv(&[&[1], low.as_ref(), high.as_ref()]));
+
+    let single = AirdropTree::new(vec![entry]).unwrap();
+    assert_eq!(single.root(), leaf_hash(&entry));
+    assert!(single.proof(0).is_empty());
+}
+
+// Test that malformed lines, duplicate wallets and empty lists are refused
+#[test]
+fn test_invalid_lists() {
+    let wallet = Pubkey::new_unique();
+    assert!(matches!(parse_airdrop_csv(&format!("{},10\n{},ten\n", wallet, wallet)), Err(ClientError::Airdrop(_))));
+    let twice = parse_airdrop_csv(&format!("{},10\n\n{},20\n", wallet, wallet)).unwrap();
+    assert_eq!(twice.len(), 2);
+    assert!(matches!(AirdropTree::new(twice), Err(ClientError::Airdrop(message)) if message.contains("twice")));
+    assert!(matches!(AirdropTree::new(Vec::new()), Err(ClientError::Airdrop(_))));
+}
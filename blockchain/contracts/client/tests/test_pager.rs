@@ -0,0 +1,207 @@
+// test_pager.rs
+// Tests for the agent and proposal pagers against 50 agents and 50 proposals seeded
+// into program-test. Program-test has no getProgramAccounts, so the account source
+// scans the seeded addresses itself, reporting each twice and out of order; the pagers
+// must still page through every matching account once, in address order, and cope
+// with accounts that change between the scan and their page.
+
+use std::sync::Mutex;
+
+use anchor_lang::{AccountSerialize, Discriminator};
+use futures::TryStreamExt;
+use ontora_ai::state::{AiAgent, Proposal, AI_AGENT_VERSION, PROPOSAL_ACTIVE, PROPOSAL_APPROVED, PROPOSAL_VERSION};
+use ontora_client::*;
+use solana_client::rpc_filter::RpcFilterType;
+use solana_program_test::*;
+use solana_sdk::account::{Account, AccountSharedData};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::rent::Rent;
+
+const SEEDED: u64 = 50;
+
+// Fixed, recognizable key: every byte is `seed`
+fn key(seed: u8) -> Pubkey {
+    Pubkey::new_from_array([seed; 32])
+}
+
+fn agent_owner(agent_id: u64) -> Pubkey {
+    key(1 + (agent_id % 3) as u8)
+}
+
+fn proposal_creator(proposal_id: u64) -> Pubkey {
+    key(10 + (proposal_id % 2) as u8)
+}
+
+// Every third proposal is active, the rest approved or rejected
+fn proposal_status(proposal_id: u64) -> u8 {
+    (proposal_id % 3) as u8
+}
+
+fn program_account(data: Vec<u8>) -> Account {
+    Account {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner: ontora_ai::ID,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+fn agent_account(agent_id: u64) -> Account {
+    let agent = AiAgent {
+        version: AI_AGENT_VERSION,
+        agent_id,
+        owner: agent_owner(agent_id),
+        name: format!("Agent {agent_id}"),
+        ..AiAgent::default()
+    };
+    let mut data = Vec::new();
+    agent.try_serialize(&mut data).unwrap();
+    program_account(data)
+}
+
+fn proposal_account(proposal_id: u64, status: u8) -> Account {
+    let mut proposal: Proposal = bytemuck::Zeroable::zeroed();
+    proposal.version = PROPOSAL_VERSION;
+    proposal.status = status;
+    proposal.id = proposal_id;
+    proposal.creator = proposal_creator(proposal_id);
+    let mut data = Proposal::discriminator().to_vec();
+    data.extend_from_slice(bytemuck::bytes_of(&proposal));
+    program_account(data)
+}
+
+fn agent_address(agent_id: u64) -> Pubkey {
+    pda::ai_agent(&agent_owner(agent_id), agent_id).0
+}
+
+fn proposal_address(proposal_id: u64) -> Pubkey {
+    pda::proposal(proposal_id).0
+}
+
+// The seeded accounts, read through the banks client
+struct BanksSource {
+    banks: BanksClient,
+    addresses: Vec<Pubkey>,
+    // Size of every getMultipleAccounts request
+    requests: Mutex<Vec<usize>>,
+}
+
+impl AccountSource for BanksSource {
+    async fn matching_addresses(&self, filters: Vec<RpcFilterType>) -> Result<Vec<Pubkey>, ClientError> {
+        let mut banks = self.banks.clone();
+        let mut matching = Vec::new();
+        for address in self.addresses.iter().rev().chain(&self.addresses) {
+            let Some(account) = banks.get_account(*address).await.unwrap() else {
+                continue;
+            };
+            let matches = filters.iter().all(|filter| match filter {
+                RpcFilterType::Memcmp(memcmp) => memcmp.bytes_match(&account.data),
+                _ => false,
+            });
+            if account.owner == ontora_ai::ID && matches {
+                matching.push(*address);
+            }
+        }
+        Ok(matching)
+    }
+
+    async fn multiple_accounts(&self, addresses: &[Pubkey]) -> Result<Vec<Option<Account>>, ClientError> {
+        self.requests.lock().unwrap().push(addresses.len());
+        let mut banks = self.banks.clone();
+        let mut accounts = Vec::with_capacity(addresses.len());
+        for address in addresses {
+            accounts.push(banks.get_account(*address).await.unwrap());
+        }
+        Ok(accounts)
+    }
+}
+
+async fn start() -> (ProgramTestContext, BanksSource) {
+    let mut test = ProgramTest::new("ontora_ai", ontora_ai::ID, processor!(ontora_ai::entry));
+    let mut addresses = Vec::new();
+    for id in 0..SEEDED {
+        test.add_account(agent_address(id), agent_account(id));
+        test.add_account(proposal_address(id), proposal_account(id, proposal_status(id)));
+        addresses.extend([agent_address(id), proposal_address(id)]);
+    }
+    let ctx = test.start_with_context().await;
+    let source = BanksSource { banks: ctx.banks_client.clone(), addresses, requests: Mutex::default() };
+    (ctx, source)
+}
+
+fn sorted(mut addresses: Vec<Pubkey>) -> Vec<Pubkey> {
+    addresses.sort();
+    addresses
+}
+
+// Test that the agent stream yields every seeded agent once, in address order, a
+// page of getMultipleAccounts at a time, and that the owner filter is applied
+#[tokio::test]
+async fn test_agent_stream_is_complete_and_ordered() {
+    let (_ctx, source) = start().await;
+
+    let agents: Vec<(Pubkey, AiAgent)> =
+        AgentPager::new(&source, AgentFilter::default()).page_size(7).into_stream().try_collect().await.unwrap();
+    let addresses: Vec<Pubkey> = agents.iter().map(|(address, _)| *address).collect();
+    assert_eq!(addresses, sorted((0..SEEDED).map(agent_address).collect()));
+    assert!(agents.iter().all(|(address, agent)| *address == agent_address(agent.agent_id)));
+    let requests = source.requests.lock().unwrap().clone();
+    assert_eq!(requests.len(), 8);
+    assert!(requests.iter().all(|&size| size <= 7));
+
+    let owner = agent_owner(0);
+    let owned: Vec<(Pubkey, AiAgent)> =
+        AgentPager::new(&source, AgentFilter { owner: Some(owner) }).into_stream().try_collect().await.unwrap();
+    let expected: Vec<Pubkey> = (0..SEEDED).filter(|id| agent_owner(*id) == owner).map(agent_address).collect();
+    assert_eq!(owned.len(), 17);
+    assert_eq!(owned.iter().map(|(address, _)| *address).collect::<Vec<_>>(), sorted(expected));
+}
+
+// Test paging active proposals while some change: one finalized and one closed
+// before their page are left out, one updated after its page does not come back,
+// and a pager resumed from the cursor finishes the listing without overlap
+#[tokio::test]
+async fn test_proposal_pages_survive_changes_mid_pagination() {
+    let (mut ctx, source) = start().await;
+    let active: Vec<u64> = (0..SEEDED).filter(|id| proposal_status(*id) == PROPOSAL_ACTIVE).collect();
+    let addresses = sorted(active.iter().copied().map(proposal_address).collect());
+    let proposal_id = |address: &Pubkey| active.iter().copied().find(|id| proposal_address(*id) == *address).unwrap();
+    let filter = ProposalFilter { status: Some(PROPOSAL_ACTIVE), creator: None };
+    let mut pager = ProposalPager::new(&source, filter.clone()).page_size(5);
+
+    let first = pager.next_page().await.unwrap().unwrap();
+    assert_eq!(first.iter().map(|(address, _)| *address).collect::<Vec<_>>(), addresses[..5]);
+
+    // Finalize and close two proposals of the next page, and update one already paged
+    let (finalized, closed, updated) = (addresses[5], addresses[7], addresses[0]);
+    let finalized_account = proposal_account(proposal_id(&finalized), PROPOSAL_APPROVED);
+    ctx.set_account(&finalized, &AccountSharedData::from(finalized_account));
+    ctx.set_account(&closed, &AccountSharedData::new(1, 0, &solana_sdk::system_program::ID));
+    let updated_account = proposal_account(proposal_id(&updated), PROPOSAL_ACTIVE);
+    ctx.set_account(&updated, &AccountSharedData::from(updated_account));
+
+    let second = pager.next_page().await.unwrap().unwrap();
+    let second_addresses: Vec<Pubkey> = second.iter().map(|(address, _)| *address).collect();
+    assert_eq!(second_addresses, [addresses[6], addresses[8], addresses[9]]);
+    let cursor = pager.cursor().unwrap();
+    assert_eq!(cursor, addresses[9]);
+    let rest: Vec<(Pubkey, Proposal)> =
+        ProposalPager::new(&source, filter).start_after(cursor).into_stream().try_collect().await.unwrap();
+
+    let listed: Vec<Pubkey> = first.iter().chain(&second).chain(&rest).map(|(address, _)| *address).collect();
+    let changed = [finalized, closed];
+    let expected: Vec<Pubkey> = addresses.iter().copied().filter(|address| !changed.contains(address)).collect();
+    assert_eq!(listed, expected);
+    assert!(first.iter().chain(&second).chain(&rest).all(|(_, proposal)| proposal.status == PROPOSAL_ACTIVE));
+
+    // Status and creator filters combine
+    let creator = proposal_creator(0);
+    let filter = ProposalFilter { status: Some(PROPOSAL_ACTIVE), creator: Some(creator) };
+    let created: Vec<(Pubkey, Proposal)> =
+        ProposalPager::new(&source, filter).into_stream().try_collect().await.unwrap();
+    let expected: Vec<Pubkey> =
+        expected.into_iter().filter(|address| proposal_creator(proposal_id(address)) == creator).collect();
+    assert!(!expected.is_empty());
+    assert_eq!(created.iter().map(|(address, _)| *address).collect::<Vec<_>>(), expected);
+}
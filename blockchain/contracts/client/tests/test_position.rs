@@ -0,0 +1,311 @@
+// test_position.rs
+// Cross-checks of the off-chain position math against the program. Each prediction is
+// made through `fetch_position_summary`, over an RPC client mocked with the accounts
+// program-test holds, and the matching instruction is sent right after at the same
+// clock: the claim must pay exactly the predicted amount and fee, and the unstake must
+// fail until the predicted cooldown has run out.
+
+use std::collections::HashMap;
+
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::solana_program::sysvar;
+use anchor_lang::AccountDeserialize;
+use ontora_ai::error::OntoraError;
+use ontora_ai::state::{FeeSchedule, FeeType, PlatformConfig, Role, UserStake};
+use ontora_ai::AIAgent;
+use ontora_client::*;
+use solana_account_decoder::{UiAccount, UiAccountEncoding};
+use solana_client::mock_sender::Mocks;
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_request::RpcRequest;
+use solana_program_test::*;
+use solana_sdk::instruction::{Instruction, InstructionError};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::system_instruction;
+use solana_sdk::sysvar::clock::Clock;
+use solana_sdk::transaction::{Transaction, TransactionError};
+
+const LAMPORTS: u64 = 10_000_000_000;
+const MINT_DECIMALS: u8 = 9;
+const STAKE: u64 = 3_000_000_070;
+const CLAIM_FEE_BPS: u16 = 333;
+const WEEK: i64 = 604_800;
+
+type SendResult = Result<(), TransactionError>;
+
+struct Harness {
+    ctx: ProgramTestContext,
+}
+
+// A legacy agent staked by `user`, on a platform cranked by `admin`
+struct Position {
+    admin: Keypair,
+    user: Keypair,
+    mint: Pubkey,
+    user_tokens: Pubkey,
+    staking_vault: Pubkey,
+    reward_vault: Pubkey,
+}
+
+impl Harness {
+    async fn start() -> Self {
+        let test = ProgramTest::new("ontora_ai", ontora_ai::ID, processor!(ontora_ai::entry));
+        Harness { ctx: test.start_with_context().await }
+    }
+
+    async fn send(&mut self, instructions: &[Instruction], signers: &[&Keypair]) -> SendResult {
+        let mut all_signers = vec![&self.ctx.payer];
+        all_signers.extend_from_slice(signers);
+        let tx = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.ctx.payer.pubkey()),
+            &all_signers,
+            self.ctx.last_blockhash,
+        );
+        self.ctx.banks_client.process_transaction(tx).await.map_err(|error| error.unwrap())
+    }
+
+    async fn new_user(&mut self) -> Keypair {
+        let user = Keypair::new();
+        let transfer = system_instruction::transfer(&self.ctx.payer.pubkey(), &user.pubkey(), LAMPORTS);
+        self.send(&[transfer], &[]).await.unwrap();
+        user
+    }
+
+    async fn account<T: AccountDeserialize>(&mut self, address: &Pubkey) -> T {
+        let account = self.ctx.banks_client.get_account(*address).await.unwrap().expect("account exists");
+        decode_account(address, &account.data).unwrap()
+    }
+
+    async fn token_balance(&mut self, account: &Pubkey) -> u64 {
+        let account = self.ctx.banks_client.get_account(*account).await.unwrap().expect("account exists");
+        spl_token::state::Account::unpack(&account.data).unwrap().amount
+    }
+
+    // Create `owner`'s associated token account for `mint`, holding `amount` minted by `authority`
+    async fn token_account(&mut self, owner: &Pubkey, mint: &Pubkey, authority: &Keypair, amount: u64) -> Pubkey {
+        let account = spl_associated_token_account::get_associated_token_address(owner, mint);
+        let create = spl_associated_token_account::instruction::create_associated_token_account(
+            &self.ctx.payer.pubkey(),
+            owner,
+            mint,
+            &spl_token::ID,
+        );
+        if amount == 0 {
+            self.send(&[create], &[]).await.unwrap();
+            return account;
+        }
+        let mint_to =
+            spl_token::instruction::mint_to(&spl_token::ID, mint, &account, &authority.pubkey(), &[], amount).unwrap();
+        self.send(&[create, mint_to], &[authority]).await.unwrap();
+        account
+    }
+
+    async fn warp_seconds(&mut self, seconds: i64) {
+        let mut clock: Clock = self.ctx.banks_client.get_sysvar().await.unwrap();
+        self.ctx.warp_to_slot(clock.slot + 1).unwrap();
+        clock.slot += 1;
+        clock.unix_timestamp += seconds;
+        self.ctx.set_sysvar(&clock);
+        self.ctx.last_blockhash = self.ctx.banks_client.get_latest_blockhash().await.unwrap();
+    }
+
+    // An RPC client whose getMultipleAccounts answers with the current program-test state
+    async fn rpc(&mut self, addresses: &[Pubkey]) -> RpcClient {
+        let mut accounts = Vec::new();
+        for address in addresses {
+            let account = self.ctx.banks_client.get_account(*address).await.unwrap();
+            accounts.push(
+                account.map(|account| UiAccount::encode(address, &account, UiAccountEncoding::Base64, None, None)),
+            );
+        }
+        let response = serde_json::json!({ "context": { "slot": 1 }, "value": accounts });
+        let mocks: Mocks = HashMap::from([(RpcRequest::GetMultipleAccounts, response)]);
+        RpcClient::new_mock_with_mocks("succeeds".to_string(), mocks)
+    }
+
+    async fn summary(&mut self, user: &Pubkey) -> PositionSummary {
+        let addresses = [
+            pda::platform_config().0,
+            pda::fee_schedule().0,
+            pda::user_stake(user).0,
+            pda::legacy_ai_agent(user).0,
+            sysvar::clock::ID,
+        ];
+        let rpc = self.rpc(&addresses).await;
+        fetch_position_summary(&rpc, user).unwrap()
+    }
+
+    // Initialize the platform with a claim fee, fund the reward vault, and stake a legacy agent
+    async fn position(&mut self) -> Position {
+        let admin = self.new_user().await;
+        let user = self.new_user().await;
+        let mut rates_bps = [0; FeeType::COUNT];
+        rates_bps[FeeType::Claim.index()] = CLAIM_FEE_BPS;
+        let mut instructions = vec![build_initialize_platform(InitializePlatformParams {
+            admin: admin.pubkey(),
+            reward_rate_bps: 100,
+            min_stake_amount: 1_000_000,
+            epoch_duration: 86_400,
+            cooldown_seconds: 86_400,
+            epoch_emission: 100_000,
+        })];
+        instructions.push(build_set_role(SetRoleParams {
+            admin: admin.pubkey(),
+            role: Role::Crank,
+            holder: admin.pubkey(),
+        }));
+        instructions.push(build_initialize_treasury(InitializeTreasuryParams {
+            authority: admin.pubkey(),
+            governance: admin.pubkey(),
+        }));
+        instructions.push(build_initialize_fee_schedule(InitializeFeeScheduleParams {
+            admin: admin.pubkey(),
+            governance: admin.pubkey(),
+            rates_bps,
+        }));
+        instructions.push(build_initialize_reward_pool(InitializeRewardPoolParams {
+            payer: admin.pubkey(),
+            initial_rewards: 10 * STAKE,
+        }));
+        self.send(&instructions, &[&admin]).await.unwrap();
+
+        let mint = Keypair::new();
+        let rent = self.ctx.banks_client.get_rent().await.unwrap();
+        let create_mint = [
+            system_instruction::create_account(
+                &self.ctx.payer.pubkey(),
+                &mint.pubkey(),
+                rent.minimum_balance(spl_token::state::Mint::LEN),
+                spl_token::state::Mint::LEN as u64,
+                &spl_token::ID,
+            ),
+            spl_token::instruction::initialize_mint(
+                &spl_token::ID,
+                &mint.pubkey(),
+                &admin.pubkey(),
+                None,
+                MINT_DECIMALS,
+            )
+            .unwrap(),
+        ];
+        self.send(&create_mint, &[&mint]).await.unwrap();
+        let mint = mint.pubkey();
+
+        let vaults = build_initialize_vaults(InitializeVaultsParams { admin: admin.pubkey(), stake_mint: mint });
+        let reward_vault = pda::reward_vault().0;
+        let fund =
+            spl_token::instruction::mint_to(&spl_token::ID, &mint, &reward_vault, &admin.pubkey(), &[], 10 * STAKE)
+                .unwrap();
+        self.send(&[vaults, fund], &[&admin]).await.unwrap();
+        let user_tokens = self.token_account(&user.pubkey(), &mint, &admin, STAKE).await;
+        let staking_vault = self.token_account(&pda::legacy_ai_agent(&user.pubkey()).0, &mint, &admin, 0).await;
+        let register = build_register_ai_agent(RegisterAiAgentParams {
+            user: user.pubkey(),
+            user_token_account: user_tokens,
+            staking_vault,
+            stake_amount: STAKE,
+        });
+        self.send(&[register], &[&user]).await.unwrap();
+        Position { admin, user, mint, user_tokens, staking_vault, reward_vault }
+    }
+}
+
+fn custom_error(result: SendResult) -> Option<u32> {
+    match result {
+        Err(TransactionError::InstructionError(_, InstructionError::Custom(code))) => Some(code),
+        _ => None,
+    }
+}
+
+// Test that a claim sent right after the prediction pays the predicted net and fee
+#[tokio::test]
+async fn test_claim_matches_prediction() {
+    let mut harness = Harness::start().await;
+    let position = harness.position().await;
+    let user = position.user.pubkey();
+
+    harness.warp_seconds(WEEK).await;
+    let distribute =
+        build_distribute_rewards(DistributeRewardsParams { crank: position.admin.pubkey(), agent_owner: user });
+    harness.send(&[distribute], &[&position.admin]).await.unwrap();
+    let accrued = harness.account::<AIAgent>(&pda::legacy_ai_agent(&user).0).await.accumulated_rewards;
+
+    let predicted = harness.summary(&user).await;
+    assert_eq!(predicted.claimable_rewards + predicted.claim_fee, accrued);
+    // The stake is chosen so the fee has a remainder to round away
+    assert_ne!(accrued * CLAIM_FEE_BPS as u64 % 10_000, 0);
+
+    let claim = build_claim_rewards(ClaimRewardsParams {
+        user,
+        user_token_account: position.user_tokens,
+        reward_vault: position.reward_vault,
+        reward_mint: position.mint,
+    });
+    harness.send(&[claim], &[&position.user]).await.unwrap();
+    assert_eq!(harness.token_balance(&position.user_tokens).await, predicted.claimable_rewards);
+    assert_eq!(harness.token_balance(&pda::treasury_vault(&position.mint).0).await, predicted.claim_fee);
+
+    let after = harness.summary(&user).await;
+    assert_eq!((after.claimable_rewards, after.claim_fee), (0, 0));
+}
+
+// Test that the unstake fails one second before the predicted cooldown end and
+// succeeds at it
+#[tokio::test]
+async fn test_unstake_matches_cooldown_prediction() {
+    let mut harness = Harness::start().await;
+    let position = harness.position().await;
+    let user = position.user.pubkey();
+    let unstake = build_unstake_ai_agent(UnstakeAiAgentParams {
+        user,
+        user_token_account: position.user_tokens,
+        staking_vault: position.staking_vault,
+        stake_mint: position.mint,
+    });
+
+    let predicted = harness.summary(&user).await;
+    assert_eq!(predicted.agent_staked_amount, STAKE);
+    assert!(predicted.cooldown_remaining > 1);
+
+    harness.warp_seconds(predicted.cooldown_remaining - 1).await;
+    assert_eq!(harness.summary(&user).await.cooldown_remaining, 1);
+    let result = harness.send(&[unstake.clone()], &[&position.user]).await;
+    assert_eq!(custom_error(result), Some(u32::from(OntoraError::CooldownNotCompleted)));
+
+    harness.warp_seconds(1).await;
+    assert_eq!(harness.summary(&user).await.cooldown_remaining, 0);
+    harness.send(&[unstake], &[&position.user]).await.unwrap();
+    assert_eq!(harness.token_balance(&position.user_tokens).await, STAKE);
+}
+
+// Test the pending reward math on a `UserStake`, including a missing stake and overflow
+#[test]
+fn test_pending_rewards() {
+    let mut config = PlatformConfig::default();
+    config.init(Pubkey::default(), 100, 1, 86_400, 86_400, 100_000, 1_000, 255);
+    config.last_reward_timestamp = 1_000;
+    let mut stake = UserStake::default();
+    stake.init(Pubkey::new_unique(), 255);
+    stake.staked_amount = 5_000_000_099;
+    stake.accumulated_rewards = 7;
+    stake.last_reward_claim = 1_000;
+
+    // Rewards accrue by the second, 1% of the stake per epoch, and round down
+    let now = 1_000 + 86_400 * 5 / 2;
+    assert_eq!(pending_rewards(&stake, &config, now), Some(7 + 125_000_002));
+    assert_eq!(pending_rewards(&stake, &config, 999), Some(7));
+
+    let fees = FeeSchedule::default();
+    let summary = PositionSummary::compute(&config, &fees, Some(&stake), None, now).unwrap();
+    assert_eq!((summary.staked_amount, summary.pending_rewards), (stake.staked_amount, 7 + 125_000_002));
+    assert_eq!((summary.agent_staked_amount, summary.cooldown_remaining), (0, 0));
+
+    stake.accumulated_rewards = u64::MAX;
+    assert_eq!(pending_rewards(&stake, &config, now), None);
+    assert!(matches!(
+        PositionSummary::compute(&config, &fees, Some(&stake), None, now),
+        Err(ClientError::Overflow(_))
+    ));
+}
@@ -0,0 +1,222 @@
+// test_queries.rs
+// Tests for the getProgramAccounts filters. The layout offsets they use are derived
+// again from serialized accounts, so a field that moves breaks this test rather than
+// silently emptying every listing, and each filter set is matched against accounts the
+// program actually wrote under program-test.
+
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::{AccountSerialize, Discriminator};
+use ontora_ai::state::{AiAgent, FeeType, Proposal, ProposalAction, StakeIndexEntry, PROPOSAL_ACTIVE};
+use ontora_ai::{AIAgent, LEGACY_AI_AGENT_VERSION};
+use ontora_client::*;
+use solana_client::rpc_filter::RpcFilterType;
+use solana_program_test::*;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::system_instruction;
+use solana_sdk::transaction::Transaction;
+
+const LAMPORTS: u64 = 10_000_000_000;
+const STAKE: u64 = 3_000_000_000;
+
+// Fixed, recognizable key: every byte is `seed`
+fn key(seed: u8) -> Pubkey {
+    Pubkey::new_from_array([seed; 32])
+}
+
+// Offset of `needle` in `data`, which must contain it exactly once
+fn offset_of(data: &[u8], needle: &[u8]) -> usize {
+    let offsets: Vec<usize> =
+        (0..=data.len() - needle.len()).filter(|&start| &data[start..start + needle.len()] == needle).collect();
+    assert_eq!(offsets.len(), 1, "expected exactly one match");
+    offsets[0]
+}
+
+fn account_bytes<T: AccountSerialize>(value: &T) -> Vec<u8> {
+    let mut data = Vec::new();
+    value.try_serialize(&mut data).unwrap();
+    data
+}
+
+fn matches(filters: &[RpcFilterType], data: &[u8]) -> bool {
+    filters.iter().all(|filter| match filter {
+        RpcFilterType::Memcmp(memcmp) => memcmp.bytes_match(data),
+        _ => false,
+    })
+}
+
+struct Harness {
+    ctx: ProgramTestContext,
+}
+
+impl Harness {
+    async fn start() -> Self {
+        let test = ProgramTest::new("ontora_ai", ontora_ai::ID, processor!(ontora_ai::entry));
+        Harness { ctx: test.start_with_context().await }
+    }
+
+    async fn send(&mut self, instructions: &[Instruction], signers: &[&Keypair]) {
+        let mut all_signers = vec![&self.ctx.payer];
+        all_signers.extend_from_slice(signers);
+        let tx = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.ctx.payer.pubkey()),
+            &all_signers,
+            self.ctx.last_blockhash,
+        );
+        self.ctx.banks_client.process_transaction(tx).await.unwrap();
+    }
+
+    async fn new_user(&mut self) -> Keypair {
+        let user = Keypair::new();
+        let transfer = system_instruction::transfer(&self.ctx.payer.pubkey(), &user.pubkey(), LAMPORTS);
+        self.send(&[transfer], &[]).await;
+        user
+    }
+
+    async fn data(&mut self, address: &Pubkey) -> Vec<u8> {
+        self.ctx.banks_client.get_account(*address).await.unwrap().expect("account exists").data
+    }
+
+    // A mint with a funded token account for `user` and the platform's vaults, created by
+    // `authority` as the platform admin
+    async fn mint(&mut self, authority: &Keypair, user: &Pubkey) -> (Pubkey, Pubkey, Pubkey) {
+        let mint = Keypair::new();
+        let rent = self.ctx.banks_client.get_rent().await.unwrap();
+        let user_tokens = spl_associated_token_account::get_associated_token_address(user, &mint.pubkey());
+        let payer = self.ctx.payer.pubkey();
+        let instructions = [
+            system_instruction::create_account(
+                &payer,
+                &mint.pubkey(),
+                rent.minimum_balance(spl_token::state::Mint::LEN),
+                spl_token::state::Mint::LEN as u64,
+                &spl_token::ID,
+            ),
+            spl_token::instruction::initialize_mint(&spl_token::ID, &mint.pubkey(), &authority.pubkey(), None, 9)
+                .unwrap(),
+            spl_associated_token_account::instruction::create_associated_token_account(
+                &payer,
+                user,
+                &mint.pubkey(),
+                &spl_token::ID,
+            ),
+            build_initialize_vaults(InitializeVaultsParams { admin: authority.pubkey(), stake_mint: mint.pubkey() }),
+            spl_token::instruction::mint_to(&spl_token::ID, &mint.pubkey(), &user_tokens, &authority.pubkey(), &[], STAKE)
+                .unwrap(),
+        ];
+        self.send(&instructions, &[&mint, authority]).await;
+        (mint.pubkey(), user_tokens, pda::staking_vault().0)
+    }
+}
+
+// Test that the exported offsets match the fields' positions in serialized accounts
+#[test]
+fn test_layout_offsets_match_serialized_accounts() {
+    let agent = AiAgent {
+        agent_id: 0x0101_0101_0101_0101,
+        owner: key(0xA5),
+        name: "Atlas".to_string(),
+        ..AiAgent::default()
+    };
+    assert_eq!(offset_of(&account_bytes(&agent), key(0xA5).as_ref()), AiAgent::OWNER_OFFSET);
+
+    let entry = StakeIndexEntry { user: key(0x5C), agent_id: 7, ..StakeIndexEntry::default() };
+    assert_eq!(offset_of(&account_bytes(&entry), key(0x5C).as_ref()), StakeIndexEntry::USER_OFFSET);
+
+    let mut proposal: Proposal = bytemuck::Zeroable::zeroed();
+    proposal.status = 0xE7;
+    proposal.creator = key(0xC7);
+    let body = bytemuck::bytes_of(&proposal);
+    assert_eq!(8 + offset_of(body, &[0xE7]), Proposal::STATUS_OFFSET);
+    assert_eq!(8 + offset_of(body, key(0xC7).as_ref()), Proposal::CREATOR_OFFSET);
+}
+
+// Test each filter set against accounts written by the program, and against
+// accounts it must not match
+#[tokio::test]
+async fn test_filters_match_program_accounts() {
+    let mut harness = Harness::start().await;
+    let (admin, owner, user) = (harness.new_user().await, harness.new_user().await, harness.new_user().await);
+    let initialize = build_initialize_platform(InitializePlatformParams {
+        admin: admin.pubkey(),
+        reward_rate_bps: 100,
+        min_stake_amount: 1_000_000,
+        epoch_duration: 86_400,
+        cooldown_seconds: 86_400,
+        epoch_emission: 100_000,
+    });
+    let treasury = build_initialize_treasury(InitializeTreasuryParams {
+        authority: admin.pubkey(),
+        governance: admin.pubkey(),
+    });
+    let fees = build_initialize_fee_schedule(InitializeFeeScheduleParams {
+        admin: admin.pubkey(),
+        governance: admin.pubkey(),
+        rates_bps: [0; FeeType::COUNT],
+    });
+    harness.send(&[initialize, treasury, fees], &[&admin]).await;
+
+    let register = build_register_agent(RegisterAgentParams {
+        owner: owner.pubkey(),
+        agent_id: 4,
+        name: "Atlas".to_string(),
+        description: "Market-making agent".to_string(),
+    });
+    harness.send(&[register], &[&owner]).await;
+    let (_, user_tokens, platform_vault) = harness.mint(&admin, &user.pubkey()).await;
+    let stake = build_stake_on_agent(StakeOnAgentParams {
+        user: user.pubkey(),
+        user_token_account: user_tokens,
+        platform_vault,
+        agent_owner: owner.pubkey(),
+        agent_id: 4,
+        amount: STAKE,
+        beneficiary: None,
+    });
+    let create = build_create_governance_proposal(CreateGovernanceProposalParams {
+        creator: user.pubkey(),
+        proposal_id: 0,
+        title: "Raise reward rate".to_string(),
+        description: "Raise the reward rate to 6%".to_string(),
+        voting_duration: 3_600,
+        options: vec!["Yes".to_string(), "No".to_string()],
+        action: ProposalAction::UpdateRewardRate { bps: 600 },
+    });
+    harness.send(&[stake, create], &[&user]).await;
+
+    let agent = harness.data(&pda::ai_agent(&owner.pubkey(), 4).0).await;
+    let entry = harness.data(&pda::stake_index(&user.pubkey(), 4).0).await;
+    let proposal = harness.data(&pda::proposal(0).0).await;
+    let user_stake = harness.data(&pda::user_stake(&user.pubkey()).0).await;
+
+    assert!(matches(&agents_filters(), &agent));
+    assert!(matches(&agents_by_owner_filters(&owner.pubkey()), &agent));
+    assert!(!matches(&agents_by_owner_filters(&user.pubkey()), &agent));
+    assert!(matches(&stakes_by_user_filters(&user.pubkey()), &entry));
+    assert!(!matches(&stakes_by_user_filters(&owner.pubkey()), &entry));
+    assert!(matches(&active_proposals_filters(), &proposal));
+    assert_eq!(proposal[Proposal::STATUS_OFFSET], PROPOSAL_ACTIVE);
+
+    // The discriminator keeps other account types out
+    assert!(!matches(&stakes_by_user_filters(&user.pubkey()), &user_stake));
+    assert!(!matches(&agents_by_owner_filters(&owner.pubkey()), &entry));
+    assert!(!matches(&agents_filters(), &user_stake));
+    assert_eq!(&entry[..8], &StakeIndexEntry::discriminator());
+
+    // Legacy agents have their own discriminator
+    let legacy_agent = account_bytes(&AIAgent {
+        version: LEGACY_AI_AGENT_VERSION,
+        owner: owner.pubkey(),
+        staked_amount: STAKE,
+        last_stake_time: 0,
+        accumulated_rewards: 0,
+        is_active: true,
+        bump: 255,
+        last_reward_epoch: 0,
+    });
+    assert!(matches(&legacy_agents_filters(), &legacy_agent));
+    assert!(!matches(&legacy_agents_filters(), &agent));
+    assert!(!matches(&agents_filters(), &legacy_agent));
+}
@@ -0,0 +1,595 @@
+// test_round_trip.rs
+// Round-trip tests for the client crate: every instruction is built with its
+// `build_*` function, processed by program-test, and the accounts it touched are
+// decoded with the crate's own decoders. A builder that drifts from the program's
+// account list fails here with a constraint error instead of in an integration.
+// Every PDA the program creates is also checked against the `pda` helper for it,
+// by address and by the bump the program stored, so the helpers and the seeds
+// constraints can't drift apart either.
+
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::{AccountDeserialize, AccountSerialize, AnchorDeserialize, ZeroCopy};
+use ontora_ai::error::OntoraError;
+use ontora_ai::state::*;
+use ontora_ai::{AIAgent, GovernanceProposal, RewardPool};
+use ontora_client::*;
+use solana_program_test::*;
+use solana_sdk::account::Account;
+use solana_sdk::instruction::{Instruction, InstructionError};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::rent::Rent;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::system_instruction;
+use solana_sdk::sysvar::clock::Clock;
+use solana_sdk::transaction::{Transaction, TransactionError};
+
+const LAMPORTS: u64 = 10_000_000_000;
+const MINT_DECIMALS: u8 = 9;
+const STAKE: u64 = 3_000_000_000;
+const EPOCH_EMISSION: u64 = 100_000;
+
+type SendResult = Result<Vec<u8>, TransactionError>;
+
+struct Harness {
+    ctx: ProgramTestContext,
+}
+
+// A platform initialized through the builders, with every role held by `admin`
+struct Platform {
+    admin: Keypair,
+    mint: Pubkey,
+    platform_vault: Pubkey,
+}
+
+impl Harness {
+    async fn start() -> Self {
+        let test = ProgramTest::new("ontora_ai", ontora_ai::ID, processor!(ontora_ai::entry));
+        Harness { ctx: test.start_with_context().await }
+    }
+
+    // Process `instructions` paid by the context payer; returns the return data, if any
+    async fn send(&mut self, instructions: &[Instruction], signers: &[&Keypair]) -> SendResult {
+        let mut all_signers = vec![&self.ctx.payer];
+        all_signers.extend_from_slice(signers);
+        let tx = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.ctx.payer.pubkey()),
+            &all_signers,
+            self.ctx.last_blockhash,
+        );
+        let processed = self.ctx.banks_client.process_transaction_with_metadata(tx).await.unwrap();
+        processed.result?;
+        Ok(processed.metadata.and_then(|metadata| metadata.return_data).map(|data| data.data).unwrap_or_default())
+    }
+
+    async fn new_user(&mut self) -> Keypair {
+        let user = Keypair::new();
+        let transfer = system_instruction::transfer(&self.ctx.payer.pubkey(), &user.pubkey(), LAMPORTS);
+        self.send(&[transfer], &[]).await.unwrap();
+        user
+    }
+
+    // Give `user` a stake account holding `staked_amount`, as if they had staked it
+    fn seed_stake(&mut self, user: &Pubkey, staked_amount: u64) {
+        let (address, bump) = pda::user_stake(user);
+        let mut stake = UserStake::default();
+        stake.init(*user, bump);
+        stake.staked_amount = staked_amount;
+        let mut data = Vec::new();
+        stake.try_serialize(&mut data).unwrap();
+        data.resize(UserStake::SPACE, 0);
+        let lamports = Rent::default().minimum_balance(data.len());
+        let account = Account { lamports, data, owner: ontora_ai::ID, executable: false, rent_epoch: 0 };
+        self.ctx.set_account(&address, &account.into());
+    }
+
+    async fn data(&mut self, address: &Pubkey) -> Vec<u8> {
+        self.ctx.banks_client.get_account(*address).await.unwrap().expect("account exists").data
+    }
+
+    async fn account<T: AccountDeserialize>(&mut self, address: &Pubkey) -> T {
+        let data = self.data(address).await;
+        decode_account(address, &data).unwrap()
+    }
+
+    async fn zero_copy<T: ZeroCopy>(&mut self, address: &Pubkey) -> T {
+        let data = self.data(address).await;
+        decode_zero_copy(address, &data).unwrap()
+    }
+
+    async fn create_mint(&mut self, authority: &Pubkey) -> Pubkey {
+        let mint = Keypair::new();
+        let rent = self.ctx.banks_client.get_rent().await.unwrap();
+        let instructions = [
+            system_instruction::create_account(
+                &self.ctx.payer.pubkey(),
+                &mint.pubkey(),
+                rent.minimum_balance(spl_token::state::Mint::LEN),
+                spl_token::state::Mint::LEN as u64,
+                &spl_token::ID,
+            ),
+            spl_token::instruction::initialize_mint(&spl_token::ID, &mint.pubkey(), authority, None, MINT_DECIMALS)
+                .unwrap(),
+        ];
+        self.send(&instructions, &[&mint]).await.unwrap();
+        mint.pubkey()
+    }
+
+    // Create `owner`'s associated token account for `mint`, holding `amount` minted by `authority`
+    async fn token_account(&mut self, owner: &Pubkey, mint: &Pubkey, authority: &Keypair, amount: u64) -> Pubkey {
+        let account = spl_associated_token_account::get_associated_token_address(owner, mint);
+        let create = spl_associated_token_account::instruction::create_associated_token_account(
+            &self.ctx.payer.pubkey(),
+            owner,
+            mint,
+            &spl_token::ID,
+        );
+        if amount == 0 {
+            self.send(&[create], &[]).await.unwrap();
+            return account;
+        }
+        let mint_to =
+            spl_token::instruction::mint_to(&spl_token::ID, mint, &account, &authority.pubkey(), &[], amount).unwrap();
+        self.send(&[create, mint_to], &[authority]).await.unwrap();
+        account
+    }
+
+    async fn token_balance(&mut self, account: &Pubkey) -> u64 {
+        let data = self.data(account).await;
+        spl_token::state::Account::unpack(&data).unwrap().amount
+    }
+
+    async fn warp_seconds(&mut self, seconds: i64) {
+        let mut clock: Clock = self.ctx.banks_client.get_sysvar().await.unwrap();
+        self.ctx.warp_to_slot(clock.slot + 1).unwrap();
+        clock.slot += 1;
+        clock.unix_timestamp += seconds;
+        self.ctx.set_sysvar(&clock);
+        self.ctx.last_blockhash = self.ctx.banks_client.get_latest_blockhash().await.unwrap();
+    }
+
+    // Initialize the platform, roles, treasury and fee schedule, and a mint with the platform's vaults
+    async fn platform(&mut self, rates_bps: [u16; FeeType::COUNT]) -> Platform {
+        let admin = self.new_user().await;
+        let mut instructions = vec![build_initialize_platform(InitializePlatformParams {
+            admin: admin.pubkey(),
+            reward_rate_bps: 100,
+            min_stake_amount: 1_000_000,
+            epoch_duration: 86_400,
+            cooldown_seconds: 86_400,
+            epoch_emission: EPOCH_EMISSION,
+        })];
+        for role in [Role::Crank, Role::Oracle, Role::Pauser, Role::Treasury] {
+            instructions.push(build_set_role(SetRoleParams {
+                admin: admin.pubkey(),
+                role,
+                holder: admin.pubkey(),
+            }));
+        }
+        instructions.push(build_initialize_treasury(InitializeTreasuryParams {
+            authority: admin.pubkey(),
+            governance: admin.pubkey(),
+        }));
+        instructions.push(build_initialize_fee_schedule(InitializeFeeScheduleParams {
+            admin: admin.pubkey(),
+            governance: admin.pubkey(),
+            rates_bps,
+        }));
+        self.send(&instructions, &[&admin]).await.unwrap();
+
+        let mint = self.create_mint(&admin.pubkey()).await;
+        let vaults = build_initialize_vaults(InitializeVaultsParams { admin: admin.pubkey(), stake_mint: mint });
+        self.send(&[vaults], &[&admin]).await.unwrap();
+        let config: PlatformConfig = self.account(&pda::platform_config().0).await;
+        assert_eq!(config.stake_mint, mint);
+        assert_eq!((config.staking_vault, config.reward_vault), (pda::staking_vault().0, pda::reward_vault().0));
+        let platform_vault = config.staking_vault;
+        Platform { admin, mint, platform_vault }
+    }
+}
+
+fn custom_error(result: SendResult) -> Option<u32> {
+    match result {
+        Err(TransactionError::InstructionError(_, InstructionError::Custom(code))) => Some(code),
+        _ => None,
+    }
+}
+
+// Test the platform, role, fee, pause, admin-transfer, version and migration builders
+#[tokio::test]
+async fn test_platform_builders_round_trip() {
+    let mut harness = Harness::start().await;
+    let platform = harness.platform([0, 50, 100, 0]).await;
+    let admin = platform.admin;
+    let config_address = pda::platform_config().0;
+
+    let config: PlatformConfig = harness.account(&config_address).await;
+    assert_eq!(config.admin, admin.pubkey());
+    assert_eq!(config.epoch_duration, 86_400);
+    for role in [Role::Crank, Role::Oracle, Role::Pauser, Role::Treasury] {
+        assert!(config.has_role(role, &admin.pubkey()));
+    }
+    let treasury: Treasury = harness.account(&pda::treasury().0).await;
+    assert_eq!(treasury.governance, admin.pubkey());
+    let fees: FeeSchedule = harness.account(&pda::fee_schedule().0).await;
+    assert_eq!(fees.rates_bps, [0, 50, 100, 0]);
+    assert_eq!(config.bump, pda::platform_config().1);
+    assert_eq!(treasury.bump, pda::treasury().1);
+    assert_eq!(fees.bump, pda::fee_schedule().1);
+
+    let update_fees = build_update_fee_schedule(UpdateFeeScheduleParams {
+        authority: admin.pubkey(),
+        rates_bps: [0, 100, 200, 0],
+    });
+    let update_config = build_update_platform_config(UpdatePlatformConfigParams {
+        admin: admin.pubkey(),
+        reward_rate_bps: 250,
+        min_stake_amount: 2_000_000,
+        epoch_duration: 172_800,
+        cooldown_seconds: 86_400,
+        epoch_emission: 100_000,
+        max_stake_per_agent: 0,
+        vesting_seconds: 0,
+        vote_unit: DEFAULT_VOTE_UNIT,
+        quorum_bps: DEFAULT_QUORUM_BPS,
+        approval_threshold_bps: DEFAULT_APPROVAL_THRESHOLD_BPS,
+        update_metadata: "raise rewards".to_string(),
+    });
+    harness.send(&[update_fees, update_config], &[&admin]).await.unwrap();
+    let fees: FeeSchedule = harness.account(&pda::fee_schedule().0).await;
+    assert_eq!(fees.pending_rates_bps, [0, 100, 200, 0]);
+    let config: PlatformConfig = harness.account(&config_address).await;
+    assert_eq!((config.reward_rate_bps, config.min_stake_amount, config.epoch_duration), (250, 2_000_000, 172_800));
+
+    let pause = build_set_pause_flags(SetPauseFlagsParams { authority: admin.pubkey(), flags: PAUSE_CLAIMS });
+    harness.send(&[pause], &[&admin]).await.unwrap();
+    assert_eq!(harness.account::<PlatformConfig>(&config_address).await.pause_flags, PAUSE_CLAIMS);
+    let unpause = build_clear_pause_flags(ClearPauseFlagsParams { admin: admin.pubkey(), flags: PAUSE_CLAIMS });
+    harness.send(&[unpause], &[&admin]).await.unwrap();
+    assert_eq!(harness.account::<PlatformConfig>(&config_address).await.pause_flags, 0);
+
+    let renounce = build_renounce_role(RenounceRoleParams { holder: admin.pubkey(), role: Role::Crank });
+    harness.send(&[renounce], &[&admin]).await.unwrap();
+    assert!(!harness.account::<PlatformConfig>(&config_address).await.has_role(Role::Crank, &admin.pubkey()));
+
+    let version = harness.send(&[build_get_version()], &[]).await.unwrap();
+    let version = VersionInfo::try_from_slice(&version).unwrap();
+    assert_eq!(version.program_version, PROGRAM_VERSION);
+    assert_eq!(version.platform_config_version, PLATFORM_CONFIG_VERSION);
+
+    // Both migrations reach the layout check, which rejects an account already current
+    let migrate_config = build_migrate_platform_config(MigratePlatformConfigParams { admin: admin.pubkey() });
+    let result = harness.send(&[migrate_config], &[&admin]).await;
+    assert_eq!(custom_error(result), Some(u32::from(OntoraError::AlreadyMigrated)));
+    let migrate_account = build_migrate_account(MigrateAccountParams { account: config_address, payer: admin.pubkey() });
+    let result = harness.send(&[migrate_account], &[&admin]).await;
+    assert_eq!(custom_error(result), Some(u32::from(OntoraError::AlreadyMigrated)));
+
+    let (first_candidate, new_admin) = (Keypair::new(), harness.new_user().await);
+    let propose = build_propose_admin(ProposeAdminParams { admin: admin.pubkey(), new_admin: first_candidate.pubkey() });
+    harness.send(&[propose], &[&admin]).await.unwrap();
+    assert_eq!(harness.account::<PlatformConfig>(&config_address).await.pending_admin, first_candidate.pubkey());
+    let cancel = build_cancel_admin_transfer(CancelAdminTransferParams { admin: admin.pubkey() });
+    harness.send(&[cancel], &[&admin]).await.unwrap();
+    assert_eq!(harness.account::<PlatformConfig>(&config_address).await.pending_admin, Pubkey::default());
+
+    let propose = build_propose_admin(ProposeAdminParams { admin: admin.pubkey(), new_admin: new_admin.pubkey() });
+    let accept = build_accept_admin(AcceptAdminParams { new_admin: new_admin.pubkey() });
+    harness.send(&[propose, accept], &[&admin, &new_admin]).await.unwrap();
+    assert_eq!(harness.account::<PlatformConfig>(&config_address).await.admin, new_admin.pubkey());
+}
+
+// Test the agent, staking and treasury builders
+#[tokio::test]
+async fn test_agent_and_staking_builders_round_trip() {
+    let mut harness = Harness::start().await;
+    let platform = harness.platform([0, 100, 0, 0]).await;
+    let admin = &platform.admin;
+    let (owner, user) = (harness.new_user().await, harness.new_user().await);
+    let agent_id = 7;
+
+    let register = build_register_agent(RegisterAgentParams {
+        owner: owner.pubkey(),
+        agent_id,
+        name: "Atlas".to_string(),
+        description: "Market-making agent".to_string(),
+    });
+    let update = build_update_agent_metadata(UpdateAgentMetadataParams {
+        owner: owner.pubkey(),
+        agent_id,
+        name: "Atlas II".to_string(),
+        description: "Arbitrage agent".to_string(),
+    });
+    harness.send(&[register, update], &[&owner]).await.unwrap();
+    let score = build_update_performance_score(UpdatePerformanceScoreParams {
+        oracle: admin.pubkey(),
+        agent_owner: owner.pubkey(),
+        agent_id,
+        score: 87,
+    });
+    harness.send(&[score], &[admin]).await.unwrap();
+    let agent: AiAgent = harness.account(&pda::ai_agent(&owner.pubkey(), agent_id).0).await;
+    assert_eq!((agent.agent_id, agent.owner), (agent_id, owner.pubkey()));
+    assert_eq!((agent.name.as_str(), agent.description.as_str()), ("Atlas II", "Arbitrage agent"));
+    assert_eq!(agent.performance_score, 87);
+    assert_eq!(agent.bump, pda::ai_agent(&owner.pubkey(), agent_id).1);
+
+    let user_tokens = harness.token_account(&user.pubkey(), &platform.mint, admin, STAKE).await;
+    let stake = build_stake_on_agent(StakeOnAgentParams {
+        user: user.pubkey(),
+        user_token_account: user_tokens,
+        platform_vault: platform.platform_vault,
+        agent_owner: owner.pubkey(),
+        agent_id,
+        amount: STAKE,
+        beneficiary: None,
+    });
+    harness.send(&[stake], &[&user]).await.unwrap();
+    let position: UserStake = harness.account(&pda::user_stake(&user.pubkey()).0).await;
+    assert_eq!((position.user, position.staked_amount), (user.pubkey(), STAKE));
+    let entry: StakeIndexEntry = harness.account(&pda::stake_index(&user.pubkey(), agent_id).0).await;
+    assert_eq!((entry.user, entry.agent_id, entry.staked_amount), (user.pubkey(), agent_id, STAKE));
+    assert_eq!(position.bump, pda::user_stake(&user.pubkey()).1);
+    assert_eq!(entry.bump, pda::stake_index(&user.pubkey(), agent_id).1);
+
+    // Unstaking a third pays a 1% fee into the treasury vault
+    let unstake = build_unstake_from_agent(UnstakeFromAgentParams {
+        user: user.pubkey(),
+        user_token_account: user_tokens,
+        platform_vault: platform.platform_vault,
+        stake_mint: platform.mint,
+        agent_owner: owner.pubkey(),
+        agent_id,
+        amount: STAKE / 3,
+    });
+    harness.send(&[unstake], &[&user]).await.unwrap();
+    let fee = STAKE / 3 / 100;
+    assert_eq!(harness.account::<UserStake>(&pda::user_stake(&user.pubkey()).0).await.staked_amount, STAKE - STAKE / 3);
+    assert_eq!(harness.account::<AiAgent>(&pda::ai_agent(&owner.pubkey(), agent_id).0).await.staked_amount, STAKE - STAKE / 3);
+    assert_eq!(harness.token_balance(&user_tokens).await, STAKE / 3 - fee);
+    assert_eq!(harness.token_balance(&pda::treasury_vault(&platform.mint).0).await, fee);
+
+    let balance = harness.send(&[build_treasury_balance(TreasuryBalanceParams { mint: platform.mint })], &[]).await;
+    assert_eq!(u64::try_from_slice(&balance.unwrap()).unwrap(), fee);
+
+    let recipient = harness.token_account(&admin.pubkey(), &platform.mint, admin, 0).await;
+    let transfer = build_treasury_transfer(TreasuryTransferParams {
+        governance: admin.pubkey(),
+        mint: platform.mint,
+        recipient,
+        amount: fee / 2,
+    });
+    harness.send(&[transfer], &[admin]).await.unwrap();
+    assert_eq!(harness.token_balance(&recipient).await, fee / 2);
+    assert_eq!(harness.account::<Treasury>(&pda::treasury().0).await.total_outflows, fee / 2);
+}
+
+// Test the builders of the legacy single-agent staking, rewards and proposal flow
+#[tokio::test]
+async fn test_legacy_builders_round_trip() {
+    let mut harness = Harness::start().await;
+    let platform = harness.platform([0; FeeType::COUNT]).await;
+    let admin = &platform.admin;
+    let user = harness.new_user().await;
+    let initial_rewards = 1_000 * STAKE;
+
+    let pool = build_initialize_reward_pool(InitializeRewardPoolParams { payer: admin.pubkey(), initial_rewards });
+    harness.send(&[pool], &[admin]).await.unwrap();
+    let reward_vault = pda::reward_vault().0;
+    let mint_to = spl_token::instruction::mint_to(
+        &spl_token::ID,
+        &platform.mint,
+        &reward_vault,
+        &admin.pubkey(),
+        &[],
+        initial_rewards,
+    )
+    .unwrap();
+    harness.send(&[mint_to], &[admin]).await.unwrap();
+    let pool: RewardPool = harness.account(&pda::reward_pool().0).await;
+    assert_eq!((pool.total_rewards, pool.bump), (initial_rewards, pda::reward_pool().1));
+
+    let user_tokens = harness.token_account(&user.pubkey(), &platform.mint, admin, STAKE).await;
+    let agent = pda::legacy_ai_agent(&user.pubkey()).0;
+    let staking_vault = harness.token_account(&agent, &platform.mint, admin, 0).await;
+    let register = build_register_ai_agent(RegisterAiAgentParams {
+        user: user.pubkey(),
+        user_token_account: user_tokens,
+        staking_vault,
+        stake_amount: STAKE,
+    });
+    harness.send(&[register], &[&user]).await.unwrap();
+    let legacy: AIAgent = harness.account(&agent).await;
+    assert_eq!((legacy.owner, legacy.staked_amount), (user.pubkey(), STAKE));
+    assert_eq!(legacy.bump, pda::legacy_ai_agent(&user.pubkey()).1);
+
+    let create = build_create_proposal(CreateProposalParams {
+        user: user.pubkey(),
+        description: "Lower the cooldown".to_string(),
+        duration: 86_400,
+    });
+    let vote = build_vote_proposal(VoteProposalParams {
+        user: user.pubkey(),
+        proposal_creator: user.pubkey(),
+        in_favor: true,
+    });
+    harness.send(&[create, vote], &[&user]).await.unwrap();
+    let proposal: GovernanceProposal = harness.account(&pda::legacy_proposal(&user.pubkey()).0).await;
+    assert_eq!((proposal.proposer, proposal.description.as_str()), (user.pubkey(), "Lower the cooldown"));
+    assert_eq!((proposal.yes_votes, proposal.no_votes), (STAKE / 100, 0));
+    assert_eq!(proposal.bump, pda::legacy_proposal(&user.pubkey()).1);
+
+    // Rewards are gated once per week for the pool, and the only staker takes the whole emission
+    harness.warp_seconds(604_800).await;
+    let distribute = build_distribute_rewards(DistributeRewardsParams { crank: admin.pubkey(), agent_owner: user.pubkey() });
+    harness.send(&[distribute], &[admin]).await.unwrap();
+    assert_eq!(harness.account::<AIAgent>(&agent).await.accumulated_rewards, EPOCH_EMISSION);
+    harness.warp_seconds(604_800).await;
+    let batch = build_distribute_rewards_batch(DistributeRewardsBatchParams {
+        crank: admin.pubkey(),
+        agents: vec![agent],
+        close_epoch: true,
+    });
+    harness.send(&[batch], &[admin]).await.unwrap();
+    assert_eq!(harness.account::<AIAgent>(&agent).await.accumulated_rewards, 2 * EPOCH_EMISSION);
+
+    let claim = build_claim_rewards(ClaimRewardsParams {
+        user: user.pubkey(),
+        user_token_account: user_tokens,
+        reward_vault,
+        reward_mint: platform.mint,
+    });
+    harness.send(&[claim], &[&user]).await.unwrap();
+    assert_eq!(harness.token_balance(&user_tokens).await, 2 * EPOCH_EMISSION);
+    assert_eq!(harness.account::<AIAgent>(&agent).await.accumulated_rewards, 0);
+
+    let unstake = build_unstake_ai_agent(UnstakeAiAgentParams {
+        user: user.pubkey(),
+        user_token_account: user_tokens,
+        staking_vault,
+        stake_mint: platform.mint,
+    });
+    harness.send(&[unstake], &[&user]).await.unwrap();
+    assert_eq!(harness.account::<AIAgent>(&agent).await.staked_amount, 0);
+    assert_eq!(harness.token_balance(&user_tokens).await, STAKE + 2 * EPOCH_EMISSION);
+}
+
+// Test the governance builders, including the proposal ids passed to the toggle
+#[tokio::test]
+async fn test_governance_builders_round_trip() {
+    let mut harness = Harness::start().await;
+    let platform = harness.platform([0; FeeType::COUNT]).await;
+    let admin = &platform.admin;
+    let (creator, voter) = (harness.new_user().await, harness.new_user().await);
+    let proposal_id = harness.account::<PlatformConfig>(&pda::platform_config().0).await.proposal_count;
+
+    let create = build_create_governance_proposal(CreateGovernanceProposalParams {
+        creator: creator.pubkey(),
+        proposal_id,
+        title: "Raise reward rate".to_string(),
+        description: "Raise the reward rate to 6%".to_string(),
+        voting_duration: 3_600,
+        options: vec!["Yes".to_string(), "No".to_string(), "Abstain".to_string()],
+        action: ProposalAction::UpdateRewardRate { bps: 600 },
+    });
+    harness.send(&[create], &[&creator]).await.unwrap();
+    harness.seed_stake(&voter.pubkey(), DEFAULT_VOTE_UNIT);
+    let vote = build_cast_vote(CastVoteParams { voter: voter.pubkey(), proposal_id, vote_option: 0 });
+    harness.send(&[vote], &[&voter]).await.unwrap();
+    let proposal: Proposal = harness.zero_copy(&pda::proposal(proposal_id).0).await;
+    assert_eq!((proposal.id, proposal.creator, proposal.option_count), (proposal_id, creator.pubkey(), 3));
+    assert_eq!(proposal.votes[..3], [1, 0, 0]);
+    assert_eq!(proposal.bump, pda::proposal(proposal_id).1);
+
+    let disable = build_set_governance_enabled(SetGovernanceEnabledParams {
+        admin: admin.pubkey(),
+        enabled: false,
+        active_proposal_ids: vec![proposal_id],
+    });
+    harness.send(&[disable], &[admin]).await.unwrap();
+    assert_eq!(harness.zero_copy::<Proposal>(&pda::proposal(proposal_id).0).await.status, PROPOSAL_SUSPENDED);
+    let enable = build_set_governance_enabled(SetGovernanceEnabledParams {
+        admin: admin.pubkey(),
+        enabled: true,
+        active_proposal_ids: vec![proposal_id],
+    });
+    harness.send(&[enable], &[admin]).await.unwrap();
+    assert_eq!(harness.zero_copy::<Proposal>(&pda::proposal(proposal_id).0).await.status, PROPOSAL_ACTIVE);
+
+    harness.warp_seconds(3_601).await;
+    let finalize = build_finalize_proposal(FinalizeProposalParams { caller: voter.pubkey(), proposal_id });
+    harness.send(&[finalize], &[&voter]).await.unwrap();
+    assert_eq!(harness.zero_copy::<Proposal>(&pda::proposal(proposal_id).0).await.status, PROPOSAL_APPROVED);
+    assert_eq!(harness.account::<PlatformConfig>(&pda::platform_config().0).await.active_proposal_count, 0);
+
+    let execute = build_execute_proposal(ExecuteProposalParams { executor: creator.pubkey(), proposal_id });
+    harness.send(&[execute], &[&creator]).await.unwrap();
+    assert_eq!(harness.zero_copy::<Proposal>(&pda::proposal(proposal_id).0).await.status, PROPOSAL_EXECUTED);
+    assert_eq!(harness.account::<PlatformConfig>(&pda::platform_config().0).await.reward_rate_bps, 600);
+}
+
+// Test the metadata builders
+#[tokio::test]
+async fn test_metadata_builders_round_trip() {
+    let mut harness = Harness::start().await;
+    let authority = harness.new_user().await;
+    let address = pda::metadata(&authority.pubkey(), 16).0;
+
+    let create = build_create_metadata(CreateMetadataParams {
+        authority: authority.pubkey(),
+        entity_id: 16,
+        data: "{\"model\":\"atlas-v2\"}".to_string(),
+    });
+    harness.send(&[create], &[&authority]).await.unwrap();
+    assert_eq!(harness.account::<Metadata>(&address).await.data, "{\"model\":\"atlas-v2\"}");
+
+    let update = build_update_metadata(UpdateMetadataParams {
+        authority: authority.pubkey(),
+        entity_id: 16,
+        data: "{\"model\":\"atlas-v3\",\"tier\":2}".to_string(),
+    });
+    harness.send(&[update], &[&authority]).await.unwrap();
+    let metadata: Metadata = harness.account(&address).await;
+    assert_eq!((metadata.entity_id, metadata.data.as_str()), (16, "{\"model\":\"atlas-v3\",\"tier\":2}"));
+    assert_eq!(metadata.bump, pda::metadata(&authority.pubkey(), 16).1);
+}
+
+// Test the admin multisig builders from initialization to execution
+#[tokio::test]
+async fn test_multisig_builders_round_trip() {
+    let mut harness = Harness::start().await;
+    let platform = harness.platform([0; FeeType::COUNT]).await;
+    let admin = &platform.admin;
+    let (second, third) = (harness.new_user().await, harness.new_user().await);
+    let multisig_address = pda::admin_multisig().0;
+
+    let initialize = build_initialize_admin_multisig(InitializeAdminMultisigParams {
+        admin: admin.pubkey(),
+        signers: vec![admin.pubkey(), second.pubkey(), third.pubkey()],
+        threshold: 2,
+    });
+    harness.send(&[initialize], &[admin]).await.unwrap();
+    let multisig: AdminMultisig = harness.account(&multisig_address).await;
+    assert_eq!((multisig.signers.len(), multisig.threshold, multisig.proposal_count), (3, 2, 0));
+    assert_eq!(multisig.bump, pda::admin_multisig().1);
+    assert_eq!(harness.account::<PlatformConfig>(&pda::platform_config().0).await.admin, multisig_address);
+
+    let action_id = multisig.proposal_count;
+    let propose = build_propose_admin_action(ProposeAdminActionParams {
+        proposer: admin.pubkey(),
+        action_id,
+        action: AdminAction::SetPauseFlags { pause_flags: PAUSE_DEPOSITS },
+    });
+    harness.send(&[propose], &[admin]).await.unwrap();
+    let approve = build_approve_admin_action(ApproveAdminActionParams { signer: second.pubkey(), action_id });
+    harness.send(&[approve], &[&second]).await.unwrap();
+    let action: AdminActionProposal = harness.account(&pda::admin_action(action_id).0).await;
+    assert_eq!(action.approvals, vec![admin.pubkey(), second.pubkey()]);
+    assert_eq!(action.bump, pda::admin_action(action_id).1);
+
+    let execute = build_execute_admin_action(ExecuteAdminActionParams { executor: third.pubkey(), action_id });
+    harness.send(&[execute], &[&third]).await.unwrap();
+    assert!(harness.account::<AdminActionProposal>(&pda::admin_action(action_id).0).await.executed);
+    assert_eq!(harness.account::<PlatformConfig>(&pda::platform_config().0).await.pause_flags, PAUSE_DEPOSITS);
+}
+
+// Test the governance realm builder against a stand-in spl-governance account
+#[tokio::test]
+async fn test_governance_realm_builder_round_trip() {
+    let mut harness = Harness::start().await;
+    let platform = harness.platform([0; FeeType::COUNT]).await;
+    let admin = &platform.admin;
+    let (realm, governance, governance_program) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let mut data = vec![GOVERNANCE_ACCOUNT_TYPES[0]];
+    data.extend_from_slice(realm.as_ref());
+    let account = Account { lamports: 1_000_000, data, owner: governance_program, executable: false, rent_epoch: 0 };
+    harness.ctx.set_account(&governance, &account.into());
+
+    let set = build_set_governance_realm(SetGovernanceRealmParams { admin: admin.pubkey(), realm, governance });
+    harness.send(&[set], &[admin]).await.unwrap();
+    let recorded: GovernanceRealm = harness.account(&pda::governance_realm().0).await;
+    assert_eq!((recorded.realm, recorded.governance), (realm, governance));
+    assert_eq!(recorded.governance_program, governance_program);
+    assert_eq!(recorded.native_treasury, native_treasury(&governance, &governance_program));
+    assert_eq!(recorded.bump, pda::governance_realm().1);
+}
@@ -0,0 +1,210 @@
+// test_rpc_pool.rs
+// Tests for `RpcPool`. Mock transports stand in for the endpoints: each answers
+// getSlot, getAccountInfo for the platform config and sendTransaction, or fails
+// the way it is currently told to, and counts the requests that reach it.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anchor_lang::AccountSerialize;
+use futures::future::BoxFuture;
+use ontora_ai::state::PlatformConfig;
+use ontora_client::*;
+use serde_json::{json, Value};
+use solana_account_decoder::{UiAccount, UiAccountEncoding};
+use solana_client::rpc_request::RpcRequest;
+use solana_sdk::account::Account;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+
+const SLOT: u64 = 4_242;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Behavior {
+    Answer,
+    RateLimited,
+    Timeout,
+}
+
+#[derive(Clone)]
+struct MockEndpoint {
+    behavior: Arc<Mutex<Behavior>>,
+    calls: Arc<AtomicU32>,
+    platform_config: Value,
+}
+
+impl MockEndpoint {
+    fn new(behavior: Behavior) -> Self {
+        MockEndpoint {
+            behavior: Arc::new(Mutex::new(behavior)),
+            calls: Arc::new(AtomicU32::new(0)),
+            platform_config: Value::Null,
+        }
+    }
+
+    fn set(&self, behavior: Behavior) {
+        *self.behavior.lock().unwrap() = behavior;
+    }
+
+    fn calls(&self) -> u32 {
+        self.calls.load(Ordering::SeqCst)
+    }
+}
+
+impl RpcTransport for MockEndpoint {
+    fn send(&self, request: RpcRequest, _: Value) -> BoxFuture<'_, Result<Value, TransportError>> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        let behavior = *self.behavior.lock().unwrap();
+        Box::pin(async move {
+            match behavior {
+                Behavior::RateLimited => Err(TransportError::RateLimited),
+                Behavior::Timeout => Err(TransportError::Timeout),
+                Behavior::Answer => Ok(match request {
+                    RpcRequest::GetSlot => json!(SLOT),
+                    RpcRequest::GetAccountInfo => self.platform_config.clone(),
+                    RpcRequest::SendTransaction => json!("signature"),
+                    _ => Value::Null,
+                }),
+            }
+        })
+    }
+}
+
+fn start_pool(endpoints: &[(&str, f64, &MockEndpoint)], policy: PoolPolicy) -> RpcPool {
+    let endpoints = endpoints
+        .iter()
+        .map(|(url, rate, mock)| {
+            let transport: Box<dyn RpcTransport> = Box::new((*mock).clone());
+            (Endpoint::new(*url, *rate), transport)
+        })
+        .collect();
+    RpcPool::with_transports(endpoints, CommitmentConfig::confirmed(), policy)
+}
+
+fn health(pool: &RpcPool, url: &str) -> EndpointHealth {
+    pool.health().into_iter().find(|health| health.url == url).unwrap()
+}
+
+// getAccountInfo's answer for a platform config administered by `admin`
+fn platform_config_answer(admin: Pubkey) -> Value {
+    let mut config = PlatformConfig::default();
+    config.init(admin, 100, 1_000, 86_400, 86_400, 100_000, 1_000, pda::platform_config().1);
+    let mut data = Vec::new();
+    config.try_serialize(&mut data).unwrap();
+    let account = Account { lamports: 1_000_000, data, owner: ontora_ai::ID, executable: false, rent_epoch: 0 };
+    let encoded = UiAccount::encode(&pda::platform_config().0, &account, UiAccountEncoding::Base64, None, None);
+    json!({ "context": { "slot": SLOT }, "value": encoded })
+}
+
+// Test that a rate-limited read is answered by the next endpoint
+#[test]
+fn test_read_fails_over_after_rate_limit() {
+    let (limited, backup) = (MockEndpoint::new(Behavior::RateLimited), MockEndpoint::new(Behavior::Answer));
+    let pool = start_pool(&[("limited", 100.0, &limited), ("backup", 100.0, &backup)], PoolPolicy::default());
+
+    assert_eq!(pool.get_slot().unwrap(), SLOT);
+    assert_eq!((limited.calls(), backup.calls()), (1, 1));
+    let limited = health(&pool, "limited");
+    assert_eq!((limited.requests, limited.failures, limited.rate_limited), (1, 1, 1));
+    assert_eq!(limited.state, EndpointState::Healthy);
+    assert_eq!(health(&pool, "backup").consecutive_failures, 0);
+}
+
+// Test that a timed-out send is reported, not repeated on another endpoint
+#[test]
+fn test_send_is_not_retried() {
+    let (slow, backup) = (MockEndpoint::new(Behavior::Timeout), MockEndpoint::new(Behavior::Answer));
+    let pool = start_pool(&[("slow", 100.0, &slow), ("backup", 100.0, &backup)], PoolPolicy::default());
+
+    let result = pool.send::<String>(RpcRequest::SendTransaction, json!(["transaction"]));
+    let error = ClientError::from(result.unwrap_err());
+    assert!(is_transient(&error), "{}", error);
+    assert_eq!((slow.calls(), backup.calls()), (1, 0));
+}
+
+// Test that consecutive failures take an endpoint out of rotation
+#[test]
+fn test_consecutive_failures_mark_endpoint_unhealthy() {
+    let down = MockEndpoint::new(Behavior::Timeout);
+    let recovery_interval = Duration::from_secs(60);
+    let policy = PoolPolicy { failure_threshold: 3, recovery_interval, ..PoolPolicy::default() };
+    let pool = start_pool(&[("down", 100.0, &down)], policy);
+
+    for attempt in 1..=3 {
+        assert!(pool.get_slot().is_err());
+        assert_eq!(health(&pool, "down").consecutive_failures, attempt);
+    }
+    assert_eq!(health(&pool, "down").state, EndpointState::Unhealthy);
+    // Failing fast now, without reaching the endpoint
+    assert!(pool.get_slot().is_err());
+    assert_eq!(down.calls(), 3);
+}
+
+// Test that an unhealthy endpoint is probed once its recovery interval passes,
+// and rejoins the rotation only if the probe succeeds
+#[test]
+fn test_unhealthy_endpoint_recovers_through_probe() {
+    let (flaky, backup) = (MockEndpoint::new(Behavior::Timeout), MockEndpoint::new(Behavior::Answer));
+    let recovery_interval = Duration::from_millis(50);
+    let policy = PoolPolicy { failure_threshold: 1, recovery_interval, ..PoolPolicy::default() };
+    let pool = start_pool(&[("flaky", 100.0, &flaky), ("backup", 100.0, &backup)], policy);
+
+    assert_eq!(pool.get_slot().unwrap(), SLOT);
+    assert_eq!(health(&pool, "flaky").state, EndpointState::Unhealthy);
+    assert_eq!(pool.get_slot().unwrap(), SLOT);
+    assert_eq!((flaky.calls(), backup.calls()), (1, 2));
+
+    // A failed probe fails over, and the endpoint rests again
+    std::thread::sleep(recovery_interval);
+    assert_eq!(pool.get_slot().unwrap(), SLOT);
+    assert_eq!((flaky.calls(), backup.calls()), (2, 3));
+    assert_eq!(health(&pool, "flaky").state, EndpointState::Unhealthy);
+    assert_eq!(pool.get_slot().unwrap(), SLOT);
+    assert_eq!(flaky.calls(), 2);
+
+    flaky.set(Behavior::Answer);
+    std::thread::sleep(recovery_interval);
+    assert_eq!(pool.get_slot().unwrap(), SLOT);
+    assert_eq!(flaky.calls(), 3);
+    let recovered = health(&pool, "flaky");
+    assert_eq!((recovered.state, recovered.consecutive_failures), (EndpointState::Healthy, 0));
+    assert_eq!((recovered.requests, recovered.failures), (3, 2));
+    assert!(recovered.latency.is_some());
+}
+
+// Test that requests beyond an endpoint's rate go to another endpoint, or
+// wait for a token when there is none
+#[test]
+fn test_rate_limits_spread_and_pace_requests() {
+    let (slow, fast) = (MockEndpoint::new(Behavior::Answer), MockEndpoint::new(Behavior::Answer));
+    let pool = start_pool(&[("slow", 1.0, &slow), ("fast", 100.0, &fast)], PoolPolicy::default());
+    for _ in 0..5 {
+        pool.get_slot().unwrap();
+    }
+    assert_eq!((slow.calls(), fast.calls()), (1, 4));
+
+    let only = MockEndpoint::new(Behavior::Answer);
+    let pool = start_pool(&[("only", 20.0, &only)], PoolPolicy::default());
+    let started = Instant::now();
+    for _ in 0..25 {
+        pool.get_slot().unwrap();
+    }
+    // A burst of 20, then five more at 20 per second
+    assert!(started.elapsed() >= Duration::from_millis(200), "{:?}", started.elapsed());
+    assert_eq!(only.calls(), 25);
+}
+
+// Test that the fetch helpers take a pool in place of a client
+#[test]
+fn test_fetch_helpers_accept_pool() {
+    let admin = Pubkey::new_unique();
+    let mut flaky = MockEndpoint::new(Behavior::RateLimited);
+    let mut backup = MockEndpoint::new(Behavior::Answer);
+    flaky.platform_config = platform_config_answer(admin);
+    backup.platform_config = platform_config_answer(admin);
+    let pool = start_pool(&[("flaky", 100.0, &flaky), ("backup", 100.0, &backup)], PoolPolicy::default());
+
+    assert_eq!(fetch_platform_config(&pool).unwrap().admin, admin);
+    assert_eq!((flaky.calls(), backup.calls()), (1, 1));
+}
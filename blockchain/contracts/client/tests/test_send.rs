@@ -0,0 +1,229 @@
+// test_send.rs
+// Tests for `send_and_confirm_with_retry`. A mock cluster stands in for the RPC: each
+// block height read advances one block, blockhashes stay valid for `VALIDITY` blocks,
+// sends can be scripted to fail, and each distinct transaction it accepts lands (or
+// not) as scripted, visible from its nth status lookup on.
+
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, VecDeque};
+use std::io;
+use std::time::Duration;
+
+use ontora_client::*;
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature, Signer};
+use solana_sdk::transaction::{TransactionError, VersionedTransaction};
+
+const VALIDITY: u64 = 3;
+
+enum Landing {
+    Never,
+    // Visible from the `lookups`th status lookup of the signature
+    After { lookups: u32, result: Result<(), TransactionError> },
+}
+
+#[derive(Default)]
+struct MockCluster {
+    height: Cell<u64>,
+    blockhashes: RefCell<Vec<Hash>>,
+    send_errors: RefCell<VecDeque<ClientError>>,
+    landings: RefCell<VecDeque<Landing>>,
+    // Every send call, including failed ones
+    sent: RefCell<Vec<Signature>>,
+    statuses: RefCell<HashMap<Signature, (Landing, u32)>>,
+}
+
+impl MockCluster {
+    fn new(landings: Vec<Landing>) -> Self {
+        MockCluster { landings: RefCell::new(landings.into()), ..MockCluster::default() }
+    }
+
+    fn failing_sends(self, errors: Vec<ClientError>) -> Self {
+        self.send_errors.replace(errors.into());
+        self
+    }
+
+    fn distinct_sent(&self) -> Vec<Signature> {
+        let mut distinct = self.sent.borrow().clone();
+        distinct.dedup();
+        distinct
+    }
+}
+
+impl TransactionRpc for MockCluster {
+    fn recent_prioritization_fees(&self, _: &[Pubkey]) -> Result<Vec<u64>, ClientError> {
+        unreachable!("no percentile fee is configured")
+    }
+
+    fn simulate_units(&self, _: &VersionedTransaction) -> Result<u64, ClientError> {
+        unreachable!("no simulated limit is configured")
+    }
+}
+
+impl SendRpc for MockCluster {
+    fn latest_blockhash(&self) -> Result<(Hash, u64), ClientError> {
+        let blockhash = Hash::new_unique();
+        self.blockhashes.borrow_mut().push(blockhash);
+        Ok((blockhash, self.height.get() + VALIDITY))
+    }
+
+    fn submit(&self, transaction: &VersionedTransaction) -> Result<Signature, ClientError> {
+        let signature = transaction.signatures[0];
+        self.sent.borrow_mut().push(signature);
+        if let Some(error) = self.send_errors.borrow_mut().pop_front() {
+            return Err(error);
+        }
+        let mut statuses = self.statuses.borrow_mut();
+        if !statuses.contains_key(&signature) {
+            let landing = self.landings.borrow_mut().pop_front().expect("a landing for every transaction");
+            statuses.insert(signature, (landing, 0));
+        }
+        Ok(signature)
+    }
+
+    fn landing_status(&self, signature: &Signature) -> Result<LandingStatus, ClientError> {
+        let mut statuses = self.statuses.borrow_mut();
+        let Some((landing, lookups_so_far)) = statuses.get_mut(signature) else {
+            return Ok(LandingStatus::NotFound);
+        };
+        *lookups_so_far += 1;
+        Ok(match landing {
+            Landing::After { lookups, result } if *lookups_so_far >= *lookups => {
+                LandingStatus::Committed(result.clone())
+            }
+            _ => LandingStatus::NotFound,
+        })
+    }
+
+    fn block_height(&self) -> Result<u64, ClientError> {
+        self.height.set(self.height.get() + 1);
+        Ok(self.height.get())
+    }
+}
+
+fn policy() -> RetryPolicy {
+    RetryPolicy {
+        base_backoff: Duration::ZERO,
+        max_backoff: Duration::ZERO,
+        poll_interval: Duration::ZERO,
+        ..RetryPolicy::default()
+    }
+}
+
+fn send(cluster: &MockCluster, user: &Keypair) -> Result<SendOutcome, ClientError> {
+    let claim = build_claim_rewards(ClaimRewardsParams {
+        user: user.pubkey(),
+        user_token_account: Pubkey::new_unique(),
+        reward_vault: Pubkey::new_unique(),
+        reward_mint: Pubkey::new_unique(),
+    });
+    let builder = TxBuilder::new(user.pubkey()).instruction(claim);
+    send_and_confirm_with_retry(cluster, &builder, &[user], &policy())
+}
+
+fn landed(lookups: u32) -> Landing {
+    Landing::After { lookups, result: Ok(()) }
+}
+
+fn timeout() -> ClientError {
+    ClientError::Rpc(io::Error::new(io::ErrorKind::TimedOut, "timed out").into())
+}
+
+fn rejected(error: TransactionError) -> ClientError {
+    ClientError::Rpc(error.into())
+}
+
+// Test that a transaction landing within its blockhash's lifetime is confirmed after a
+// single send
+#[test]
+fn test_confirms_first_send() {
+    let user = Keypair::new();
+    let cluster = MockCluster::new(vec![landed(2)]);
+    let outcome = send(&cluster, &user).unwrap();
+    assert_eq!(outcome, SendOutcome::Confirmed(cluster.sent.borrow()[0]));
+    assert_eq!(cluster.sent.borrow().len(), 1);
+    assert_eq!(cluster.blockhashes.borrow().len(), 1);
+}
+
+// Test that transient send errors are retried with the same signed transaction, and
+// that errors which would recur are returned as they are
+#[test]
+fn test_retries_transient_errors() {
+    let user = Keypair::new();
+    let cluster = MockCluster::new(vec![landed(1)]).failing_sends(vec![timeout(), timeout()]);
+    let outcome = send(&cluster, &user).unwrap();
+    assert_eq!(cluster.sent.borrow().len(), 3);
+    assert_eq!(cluster.distinct_sent().len(), 1);
+    assert_eq!(outcome, SendOutcome::Confirmed(cluster.sent.borrow()[0]));
+    assert!(is_transient(&timeout()));
+
+    let cluster = MockCluster::new(vec![]).failing_sends((0..6).map(|_| timeout()).collect());
+    assert!(matches!(send(&cluster, &user), Err(error) if is_transient(&error)));
+    assert_eq!(cluster.sent.borrow().len(), 1 + policy().max_rpc_retries as usize);
+
+    let broke = rejected(TransactionError::InsufficientFundsForFee);
+    assert!(!is_transient(&broke));
+    let cluster = MockCluster::new(vec![]).failing_sends(vec![broke]);
+    assert!(send(&cluster, &user).is_err());
+    assert_eq!(cluster.sent.borrow().len(), 1);
+}
+
+// Test that a transaction that never lands is re-signed with a fresh blockhash once the
+// old one expires, and that an unknown blockhash at send time re-signs straight away
+#[test]
+fn test_resigns_after_expiry() {
+    let user = Keypair::new();
+    let cluster = MockCluster::new(vec![Landing::Never, landed(1)]);
+    let outcome = send(&cluster, &user).unwrap();
+    let sent = cluster.distinct_sent();
+    assert_eq!(sent.len(), 2);
+    assert_eq!(outcome, SendOutcome::Confirmed(sent[1]));
+    assert_eq!(cluster.blockhashes.borrow().len(), 2);
+    // The first blockhash had expired before the second signature went out
+    assert!(cluster.height.get() > VALIDITY);
+
+    let unknown = rejected(TransactionError::BlockhashNotFound);
+    let cluster = MockCluster::new(vec![landed(1)]).failing_sends(vec![unknown]);
+    let outcome = send(&cluster, &user).unwrap();
+    let sent = cluster.distinct_sent();
+    assert_eq!(sent.len(), 2);
+    assert_eq!(outcome, SendOutcome::Confirmed(sent[1]));
+}
+
+// Test the duplicate-landing race: the original transaction only shows up after its
+// blockhash expired, and is found by the check before re-signing instead of being sent
+// a second time
+#[test]
+fn test_late_landing_is_not_resent() {
+    let user = Keypair::new();
+    // Polls at heights 1 to 4 miss it, and height 4 is past its last valid height of 3;
+    // the fifth lookup, before re-signing, finds it
+    let cluster = MockCluster::new(vec![landed(5)]);
+    let outcome = send(&cluster, &user).unwrap();
+    assert_eq!(cluster.sent.borrow().len(), 1);
+    assert_eq!(outcome, SendOutcome::AlreadyProcessed(cluster.sent.borrow()[0]));
+
+    // The cluster itself can report the duplicate
+    let cluster = MockCluster::new(vec![]).failing_sends(vec![rejected(TransactionError::AlreadyProcessed)]);
+    let outcome = send(&cluster, &user).unwrap();
+    assert_eq!(outcome, SendOutcome::AlreadyProcessed(cluster.sent.borrow()[0]));
+}
+
+// Test that a transaction failing on chain is reported as such and not resent, and that
+// sending gives up once every blockhash has expired
+#[test]
+fn test_failure_and_expiry() {
+    let user = Keypair::new();
+    let error = TransactionError::InstructionError(1, InstructionError::Custom(6001));
+    let cluster = MockCluster::new(vec![Landing::After { lookups: 2, result: Err(error.clone()) }]);
+    assert_eq!(send(&cluster, &user).unwrap(), SendOutcome::FailedOnChain(error));
+    assert_eq!(cluster.sent.borrow().len(), 1);
+
+    let rounds = policy().max_blockhashes as usize;
+    let cluster = MockCluster::new((0..rounds).map(|_| Landing::Never).collect());
+    assert_eq!(send(&cluster, &user).unwrap(), SendOutcome::Expired);
+    assert_eq!(cluster.distinct_sent().len(), rounds);
+    assert_eq!(cluster.blockhashes.borrow().len(), rounds);
+}
@@ -0,0 +1,209 @@
+// test_signer.rs
+// Tests for the signer abstraction: transactions signed through `OntoraSigner` by
+// signers of different kinds, a `PartialTransaction` carried between two signers as
+// base64, signer URIs, and a `RemoteSigner` against a signing service served from a
+// local socket. The Ledger signer needs a device and is not covered here.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::str::FromStr;
+use std::thread;
+
+use ontora_client::signer::OntoraSigner;
+use ontora_client::*;
+use solana_sdk::hash::Hash;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature};
+use solana_sdk::system_instruction;
+use solana_sdk::transaction::VersionedTransaction;
+
+// Stands in for the RPC; the table limit and no price never call it
+struct Offline;
+
+impl TransactionRpc for Offline {
+    fn recent_prioritization_fees(&self, _: &[Pubkey]) -> Result<Vec<u64>, ClientError> {
+        unreachable!("no price is set")
+    }
+
+    fn simulate_units(&self, _: &VersionedTransaction) -> Result<u64, ClientError> {
+        unreachable!("the limit comes from the table")
+    }
+}
+
+// A signer that keeps its key elsewhere, as a hardware wallet would
+struct Device {
+    keypair: Keypair,
+    approve: bool,
+}
+
+impl OntoraSigner for Device {
+    fn pubkey(&self) -> Pubkey {
+        self.keypair.pubkey()
+    }
+
+    fn sign_message(&self, message: &[u8]) -> Result<Signature, ClientError> {
+        if !self.approve {
+            return Err(ClientError::Signer("rejected on the device".to_string()));
+        }
+        self.keypair.sign_message(message)
+    }
+}
+
+// A transfer out of `treasury`, paid for by `payer`, so it needs both signatures
+fn treasury_transfer(payer: &Pubkey, treasury: &Pubkey) -> TxBuilder {
+    TxBuilder::new(*payer).instruction(system_instruction::transfer(treasury, &Pubkey::new_unique(), 1_000))
+}
+
+// Test that a keypair and a device sign one transaction side by side, and that the
+// result matches signing with the keypairs alone
+#[test]
+fn test_signs_with_mixed_signers() {
+    let payer = Keypair::new();
+    let treasury = Device { keypair: Keypair::new(), approve: true };
+    let builder = treasury_transfer(&payer.pubkey(), &treasury.pubkey());
+    let blockhash = Hash::new_unique();
+
+    let signers: [&dyn OntoraSigner; 2] = [&payer, &treasury];
+    let transaction = builder.transaction(&Offline, &signers, blockhash).unwrap();
+    assert!(transaction.verify_with_results().iter().all(|verified| *verified));
+    let with_keypairs = builder.transaction(&Offline, &[&payer, &treasury.keypair], blockhash).unwrap();
+    assert_eq!(transaction, with_keypairs);
+
+    // A signer the message does not name, one it names left out, and a refusal all fail
+    let stranger = Keypair::new();
+    assert!(matches!(builder.transaction(&Offline, &[&payer, &stranger], blockhash), Err(ClientError::Signer(_))));
+    assert!(matches!(builder.transaction(&Offline, &[&payer], blockhash), Err(ClientError::Signer(_))));
+    let refusing = Device { keypair: treasury.keypair.insecure_clone(), approve: false };
+    let signers: [&dyn OntoraSigner; 2] = [&payer, &refusing];
+    assert!(matches!(builder.transaction(&Offline, &signers, blockhash), Err(ClientError::Signer(_))));
+}
+
+// Test that a partial transaction gathers its signatures one signer at a time, across
+// a base64 round trip, and only completes once both have signed
+#[test]
+fn test_partial_transaction_round_trip() {
+    let payer = Keypair::new();
+    let treasury = Keypair::new();
+    let builder = treasury_transfer(&payer.pubkey(), &treasury.pubkey());
+    let blockhash = Hash::new_unique();
+
+    let mut partial = builder.partial_transaction(&Offline, blockhash).unwrap();
+    assert_eq!(partial.required_signers(), [payer.pubkey(), treasury.pubkey()]);
+    assert_eq!(partial.missing_signers(), [payer.pubkey(), treasury.pubkey()]);
+    partial.sign(&treasury).unwrap();
+    assert_eq!(partial.missing_signers(), [payer.pubkey()]);
+    assert!(matches!(partial.clone().into_transaction(), Err(ClientError::Signer(_))));
+
+    // The payer signs on another machine, from the encoded transaction
+    let mut received = PartialTransaction::from_base64(&partial.to_base64()).unwrap();
+    assert_eq!(received, partial);
+    assert!(matches!(received.sign(&Keypair::new()), Err(ClientError::Signer(_))));
+    received.sign(&payer).unwrap();
+    assert!(received.is_complete());
+    let transaction = received.into_transaction().unwrap();
+    assert_eq!(transaction, builder.transaction(&Offline, &[&payer, &treasury], blockhash).unwrap());
+
+    assert!(matches!(PartialTransaction::from_base64("not base64!"), Err(ClientError::Signer(_))));
+}
+
+// Test that signatures made elsewhere are checked against the message before use
+#[test]
+fn test_add_signature_verifies() {
+    let payer = Keypair::new();
+    let treasury = Keypair::new();
+    let builder = treasury_transfer(&payer.pubkey(), &treasury.pubkey());
+    let mut partial = builder.partial_transaction(&Offline, Hash::new_unique()).unwrap();
+    let message = partial.message().serialize();
+
+    let forged = payer.sign_message(&message).unwrap();
+    assert!(matches!(partial.add_signature(&treasury.pubkey(), forged), Err(ClientError::Signer(_))));
+    let stale = treasury.sign_message(b"another message").unwrap();
+    assert!(matches!(partial.add_signature(&treasury.pubkey(), stale), Err(ClientError::Signer(_))));
+    assert_eq!(partial.missing_signers(), [payer.pubkey(), treasury.pubkey()]);
+
+    partial.add_signature(&treasury.pubkey(), treasury.sign_message(&message).unwrap()).unwrap();
+    assert_eq!(partial.missing_signers(), [payer.pubkey()]);
+}
+
+// Test that signer URIs name keypair files, Ledger derivations and remote services
+#[test]
+fn test_parses_signer_sources() {
+    let parse = |uri: &str| SignerSource::from_str(uri);
+    assert_eq!(parse("crank.json"), Ok(SignerSource::Keypair("crank.json".into())));
+    assert_eq!(parse("file:///keys/admin.json"), Ok(SignerSource::Keypair("/keys/admin.json".into())));
+    assert_eq!(parse("ledger://"), Ok(SignerSource::Ledger { account: None, change: None }));
+    assert_eq!(parse("ledger://?key=2"), Ok(SignerSource::Ledger { account: Some(2), change: None }));
+    assert_eq!(parse("ledger://?key=2/1"), Ok(SignerSource::Ledger { account: Some(2), change: Some(1) }));
+    let url = "https://signer.internal:8443";
+    assert_eq!(parse(url), Ok(SignerSource::Remote(url.to_string())));
+
+    for invalid in ["", "ledger://nano", "ledger://?account=2", "ledger://?key=x", "ledger://?key=2/y"] {
+        assert!(parse(invalid).is_err(), "{:?} parsed", invalid);
+    }
+}
+
+// Serves a signing service for `keypair` on a local port, answering `connections`
+// requests; `honest` false answers signatures over the wrong message
+fn serve_signer(keypair: Keypair, honest: bool, connections: usize) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    thread::spawn(move || {
+        for stream in listener.incoming().take(connections) {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let mut length = 0;
+            loop {
+                let mut header = String::new();
+                reader.read_line(&mut header).unwrap();
+                if header.trim().is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = header.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        length = value.trim().parse().unwrap();
+                    }
+                }
+            }
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+
+            let answer = if request_line.starts_with("GET /pubkey") {
+                keypair.pubkey().to_string()
+            } else if honest {
+                keypair.sign_message(&body).unwrap().to_string()
+            } else {
+                keypair.sign_message(b"something else").unwrap().to_string()
+            };
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                answer.len(),
+                answer
+            );
+            stream.write_all(response.as_bytes()).unwrap();
+        }
+    });
+    url
+}
+
+// Test that a remote signer signs a transaction like the keypair it holds, and that a
+// service answering bad signatures fails the signing
+#[test]
+fn test_remote_signer() {
+    let payer = Keypair::new();
+    let treasury = Keypair::new();
+    let builder = treasury_transfer(&payer.pubkey(), &treasury.pubkey());
+    let blockhash = Hash::new_unique();
+    let expected = builder.transaction(&Offline, &[&payer, &treasury], blockhash).unwrap();
+
+    let url = serve_signer(treasury.insecure_clone(), true, 2);
+    let remote = load_signer(&SignerSource::from_str(&url).unwrap()).unwrap();
+    assert_eq!(remote.pubkey(), treasury.pubkey());
+    let signers: [&dyn OntoraSigner; 2] = [&payer, remote.as_ref()];
+    assert_eq!(builder.transaction(&Offline, &signers, blockhash).unwrap(), expected);
+
+    let dishonest = RemoteSigner::connect(&serve_signer(treasury.insecure_clone(), false, 2)).unwrap();
+    let signers: [&dyn OntoraSigner; 2] = [&payer, &dishonest];
+    assert!(matches!(builder.transaction(&Offline, &signers, blockhash), Err(ClientError::Signer(_))));
+}
@@ -0,0 +1,286 @@
+// test_simulate.rs
+// Tests for pre-flight summaries. Banks cannot report the accounts a simulation
+// wrote, so these run the compiled program in LiteSVM, which can: each transaction
+// is summarized, then sent, and the summary is compared with what sending it did.
+//
+// Build the program before running them:
+//     cargo build-sbf
+
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::AccountDeserialize;
+use litesvm::LiteSVM;
+use ontora_ai::state::UserStake;
+use ontora_ai::RewardPool;
+use ontora_client::*;
+use solana_sdk::account::Account;
+use solana_sdk::compute_budget::ComputeBudgetInstruction;
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::message::{Message, VersionedMessage};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::system_instruction;
+use solana_sdk::transaction::{Transaction, VersionedTransaction};
+
+// The program binary produced by `cargo build-sbf`
+const PROGRAM_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../target/deploy/ontora_ai.so");
+const LAMPORTS: u64 = 10_000_000_000;
+const TOKENS: u64 = 10_000_000_000;
+const STAKE: u64 = 3_000_000_000;
+
+struct Svm {
+    svm: LiteSVM,
+    payer: Keypair,
+}
+
+// A user holding TOKENS of a mint, and an agent to stake them on
+struct Staking {
+    user: Keypair,
+    agent_owner: Pubkey,
+    user_tokens: Pubkey,
+    platform_vault: Pubkey,
+}
+
+impl Staking {
+    fn stake(&self, amount: u64) -> Instruction {
+        build_stake_on_agent(StakeOnAgentParams {
+            user: self.user.pubkey(),
+            user_token_account: self.user_tokens,
+            platform_vault: self.platform_vault,
+            agent_owner: self.agent_owner,
+            agent_id: 1,
+            amount,
+            beneficiary: None,
+        })
+    }
+}
+
+impl TransactionRpc for Svm {
+    fn recent_prioritization_fees(&self, _: &[Pubkey]) -> Result<Vec<u64>, ClientError> {
+        Ok(Vec::new())
+    }
+
+    fn simulate_units(&self, transaction: &VersionedTransaction) -> Result<u64, ClientError> {
+        match self.svm.simulate_transaction(transaction.clone()) {
+            Ok(info) => Ok(info.meta.compute_units_consumed),
+            Err(failed) => Err(ClientError::Simulation(failed.err.to_string())),
+        }
+    }
+}
+
+impl SimulateRpc for Svm {
+    fn accounts(&self, addresses: &[Pubkey]) -> Result<Vec<Option<Account>>, ClientError> {
+        Ok(addresses.iter().map(|address| self.svm.get_account(address)).collect())
+    }
+
+    fn simulate(&self, transaction: &VersionedTransaction, addresses: &[Pubkey]) -> Result<Simulation, ClientError> {
+        let info = match self.svm.simulate_transaction(transaction.clone()) {
+            Ok(info) => info,
+            Err(failed) => {
+                return Ok(Simulation {
+                    error: Some(failed.err),
+                    logs: failed.meta.logs,
+                    units_consumed: Some(failed.meta.compute_units_consumed),
+                    accounts: Vec::new(),
+                })
+            }
+        };
+        // Accounts the transaction did not load are as they were
+        let accounts = addresses
+            .iter()
+            .map(|address| {
+                let written = info.post_accounts.iter().find(|(key, _)| key == address);
+                match written {
+                    Some((_, account)) => Some(Account::from(account.clone())).filter(|account| account.lamports > 0),
+                    None => self.svm.get_account(address),
+                }
+            })
+            .collect();
+        Ok(Simulation {
+            error: None,
+            logs: info.meta.logs,
+            units_consumed: Some(info.meta.compute_units_consumed),
+            accounts,
+        })
+    }
+}
+
+impl Svm {
+    fn start() -> Self {
+        let mut svm = LiteSVM::new();
+        svm.add_program_from_file(ontora_ai::ID, PROGRAM_PATH)
+            .unwrap_or_else(|error| panic!("failed to load {}: {} (run `cargo build-sbf` first)", PROGRAM_PATH, error));
+        let payer = Keypair::new();
+        svm.airdrop(&payer.pubkey(), LAMPORTS * 10).unwrap();
+        Svm { svm, payer }
+    }
+
+    // `instructions` through the builder, paid by the payer and signed by `signers`
+    fn transaction(&self, instructions: &[Instruction], signers: &[&Keypair]) -> VersionedTransaction {
+        let mut all_signers = vec![&self.payer];
+        all_signers.extend_from_slice(signers);
+        TxBuilder::new(self.payer.pubkey())
+            .instructions(instructions.iter().cloned())
+            .transaction(self, &all_signers, self.svm.latest_blockhash())
+            .unwrap()
+    }
+
+    fn send(&mut self, instructions: &[Instruction], signers: &[&Keypair]) {
+        let transaction = self.transaction(instructions, signers);
+        self.svm.send_transaction(transaction).unwrap();
+        self.svm.expire_blockhash();
+    }
+
+    fn new_user(&mut self) -> Keypair {
+        let user = Keypair::new();
+        self.svm.airdrop(&user.pubkey(), LAMPORTS).unwrap();
+        user
+    }
+
+    fn token_balance(&self, account: &Pubkey) -> u64 {
+        spl_token::state::Account::unpack(&self.svm.get_account(account).unwrap().data).unwrap().amount
+    }
+
+    fn decode<T: AccountDeserialize>(&self, address: &Pubkey) -> T {
+        decode_account(address, &self.svm.get_account(address).unwrap().data).unwrap()
+    }
+
+    // Initialize the platform, register an agent, and fund a user to stake on it
+    fn staking(&mut self) -> Staking {
+        let (admin, owner, user) = (self.new_user(), self.new_user(), self.new_user());
+        let initialize = build_initialize_platform(InitializePlatformParams {
+            admin: admin.pubkey(),
+            reward_rate_bps: 100,
+            min_stake_amount: 1_000_000,
+            epoch_duration: 86_400,
+            cooldown_seconds: 86_400,
+            epoch_emission: 100_000,
+        });
+        let register = build_register_agent(RegisterAgentParams {
+            owner: owner.pubkey(),
+            agent_id: 1,
+            name: "Atlas".to_string(),
+            description: "Market maker".to_string(),
+        });
+        self.send(&[initialize, register], &[&admin, &owner]);
+
+        let mint = Keypair::new();
+        let rent = self.svm.minimum_balance_for_rent_exemption(spl_token::state::Mint::LEN);
+        let user_tokens = spl_associated_token_account::get_associated_token_address(&user.pubkey(), &mint.pubkey());
+        let payer = self.payer.pubkey();
+        let create_ata = |owner: &Pubkey| {
+            spl_associated_token_account::instruction::create_associated_token_account(
+                &payer,
+                owner,
+                &mint.pubkey(),
+                &spl_token::ID,
+            )
+        };
+        let instructions = [
+            system_instruction::create_account(
+                &payer,
+                &mint.pubkey(),
+                rent,
+                spl_token::state::Mint::LEN as u64,
+                &spl_token::ID,
+            ),
+            spl_token::instruction::initialize_mint(&spl_token::ID, &mint.pubkey(), &admin.pubkey(), None, 9).unwrap(),
+            create_ata(&user.pubkey()),
+            build_initialize_vaults(InitializeVaultsParams { admin: admin.pubkey(), stake_mint: mint.pubkey() }),
+            spl_token::instruction::mint_to(&spl_token::ID, &mint.pubkey(), &user_tokens, &admin.pubkey(), &[], TOKENS)
+                .unwrap(),
+        ];
+        self.send(&instructions, &[&mint, &admin]);
+
+        Staking { user, agent_owner: owner.pubkey(), user_tokens, platform_vault: pda::staking_vault().0 }
+    }
+}
+
+// Test that a stake's summary lists the token movements, the position change, the
+// event and the fee, and that sending the transaction does exactly that
+#[test]
+fn test_stake_summary_matches_execution() {
+    let mut svm = Svm::start();
+    let staking = svm.staking();
+    let (user, user_tokens, platform_vault) = (&staking.user, staking.user_tokens, staking.platform_vault);
+    let transaction = svm.transaction(&[staking.stake(STAKE)], &[user]);
+
+    let summary = simulate_and_summarize(&svm, &transaction).unwrap();
+    assert_eq!(summary.error, None);
+    assert!(summary.units_consumed.unwrap() > 0);
+    assert!(summary.events.iter().any(|event| event.name() == "StakeDeposited"));
+    let mut deltas = summary.token_deltas.clone();
+    deltas.sort_by_key(|delta| delta.change());
+    let changes: Vec<(Pubkey, i128)> = deltas.iter().map(|delta| (delta.account, delta.change())).collect();
+    assert_eq!(changes, [(user_tokens, -(STAKE as i128)), (platform_vault, STAKE as i128)]);
+    let user_stake = pda::user_stake(&user.pubkey()).0;
+    assert_eq!(summary.stake_deltas.len(), 1);
+    assert_eq!((summary.stake_deltas[0].address, summary.stake_deltas[0].user), (user_stake, user.pubkey()));
+    assert_eq!(summary.reward_pool, None);
+
+    let payer_before = svm.svm.get_balance(&svm.payer.pubkey()).unwrap();
+    svm.svm.send_transaction(transaction).unwrap();
+    assert_eq!(payer_before - svm.svm.get_balance(&svm.payer.pubkey()).unwrap(), summary.fee);
+    for delta in &summary.token_deltas {
+        assert_eq!(svm.token_balance(&delta.account), delta.after);
+    }
+    let position: UserStake = svm.decode(&user_stake);
+    assert_eq!(summary.stake_deltas[0].staked_after, position.staked_amount);
+    assert_eq!(summary.stake_deltas[0].rewards_after, position.accumulated_rewards);
+    assert_eq!(position.staked_amount, STAKE);
+}
+
+// Test that the reward pool's change is summarized, and that a failing transaction
+// reports its error and nothing else, and changes nothing when sent
+#[test]
+fn test_pool_and_failure_summaries() {
+    let mut svm = Svm::start();
+    let staking = svm.staking();
+    let user = &staking.user;
+
+    let initialize =
+        build_initialize_reward_pool(InitializeRewardPoolParams { payer: user.pubkey(), initial_rewards: 5_000 });
+    let transaction = svm.transaction(&[initialize], &[user]);
+    let summary = simulate_and_summarize(&svm, &transaction).unwrap();
+    assert_eq!(summary.reward_pool, Some(PoolDelta { total_rewards_before: 0, total_rewards_after: 5_000 }));
+    svm.svm.send_transaction(transaction).unwrap();
+    svm.svm.expire_blockhash();
+    assert_eq!(svm.decode::<RewardPool>(&pda::reward_pool().0).total_rewards, 5_000);
+
+    // Staking more than the user holds
+    let transaction = svm.transaction(&[staking.stake(TOKENS + 1)], &[user]);
+    let summary = simulate_and_summarize(&svm, &transaction).unwrap();
+    assert!(summary.error.is_some());
+    assert!(!summary.logs.is_empty());
+    assert!(summary.token_deltas.is_empty() && summary.stake_deltas.is_empty() && summary.events.is_empty());
+    assert!(svm.svm.send_transaction(transaction).is_err());
+    assert_eq!(svm.token_balance(&staking.user_tokens), TOKENS);
+}
+
+// Test the fee of messages with and without a compute-unit price
+#[test]
+fn test_transaction_fee() {
+    let payer = Pubkey::new_unique();
+    let transfer = system_instruction::transfer(&payer, &Pubkey::new_unique(), 1);
+    let message = |instructions: &[Instruction]| {
+        VersionedMessage::Legacy(Message::new_with_blockhash(instructions, Some(&payer), &Hash::default()))
+    };
+    assert_eq!(transaction_fee(&message(&[transfer.clone()])), LAMPORTS_PER_SIGNATURE);
+
+    // 300,000 units at 1,500 micro-lamports is 450 lamports
+    let priced = [
+        ComputeBudgetInstruction::set_compute_unit_limit(300_000),
+        ComputeBudgetInstruction::set_compute_unit_price(1_500),
+        transfer.clone(),
+    ];
+    assert_eq!(transaction_fee(&message(&priced)), LAMPORTS_PER_SIGNATURE + 450);
+    // Without a limit, each instruction is budgeted the default, rounded up to a lamport
+    let unlimited = [ComputeBudgetInstruction::set_compute_unit_price(1), transfer.clone(), transfer];
+    assert_eq!(transaction_fee(&message(&unlimited)), LAMPORTS_PER_SIGNATURE + 1);
+
+    let two_signers = Transaction::new_unsigned(Message::new(
+        &[system_instruction::transfer(&Pubkey::new_unique(), &Pubkey::new_unique(), 1)],
+        Some(&payer),
+    ));
+    assert_eq!(transaction_fee(&VersionedMessage::Legacy(two_signers.message)), 2 * LAMPORTS_PER_SIGNATURE);
+}
@@ -0,0 +1,350 @@
+// test_subscribe.rs
+// Tests for the typed account subscriptions. program-test makes the state changes,
+// one slot apart, and the account after each is recorded as the write a node would
+// notify. A replaying pubsub stands in for the websocket: each subscription replays
+// its batch of writes and then ends, as a dropped connection does, and the gap-filling
+// fetch answers with whatever the test says the account currently holds.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::AccountSerialize;
+use futures::stream::{self, BoxStream, StreamExt};
+use ontora_ai::state::{AiAgent, Proposal, ProposalAction, UserStake, DEFAULT_VOTE_UNIT};
+use ontora_client::*;
+use solana_program_test::*;
+use solana_sdk::account::Account;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::rent::Rent;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::system_instruction;
+use solana_sdk::sysvar::clock::Clock;
+use solana_sdk::transaction::Transaction;
+
+const LAMPORTS: u64 = 10_000_000_000;
+const STAKE: u64 = 3_000_000_000;
+const AGENT_ID: u64 = 7;
+// How long to wait for a change that should not come
+const QUIET: Duration = Duration::from_millis(100);
+
+// What one subscription replays: its writes, or the error subscribing fails with
+type Session = Result<Vec<AccountWrite>, String>;
+
+#[derive(Clone, Default)]
+struct ReplayPubsub {
+    sessions: Arc<Mutex<VecDeque<Session>>>,
+    current: Arc<Mutex<Option<AccountWrite>>>,
+}
+
+impl ReplayPubsub {
+    fn new(sessions: Vec<Session>, current: Option<AccountWrite>) -> Self {
+        ReplayPubsub { sessions: Arc::new(Mutex::new(sessions.into())), current: Arc::new(Mutex::new(current)) }
+    }
+}
+
+impl AccountPubsub for ReplayPubsub {
+    async fn account_writes(&self, _: Pubkey) -> Result<BoxStream<'static, AccountWrite>, ClientError> {
+        match self.sessions.lock().unwrap().pop_front() {
+            Some(Ok(writes)) => Ok(stream::iter(writes).boxed()),
+            Some(Err(error)) => Err(ClientError::Subscription(error)),
+            // Out of sessions: connected, with nothing more to report
+            None => Ok(stream::pending().boxed()),
+        }
+    }
+
+    async fn current(&self, address: Pubkey) -> Result<AccountWrite, ClientError> {
+        self.current.lock().unwrap().clone().ok_or(ClientError::AccountNotFound(address))
+    }
+
+    fn reconnect_delay(&self) -> Duration {
+        Duration::ZERO
+    }
+}
+
+struct Harness {
+    ctx: ProgramTestContext,
+}
+
+impl Harness {
+    async fn start() -> Self {
+        let test = ProgramTest::new("ontora_ai", ontora_ai::ID, processor!(ontora_ai::entry));
+        Harness { ctx: test.start_with_context().await }
+    }
+
+    async fn send(&mut self, instructions: &[Instruction], signers: &[&Keypair]) {
+        let mut all_signers = vec![&self.ctx.payer];
+        all_signers.extend_from_slice(signers);
+        let blockhash = self.ctx.banks_client.get_latest_blockhash().await.unwrap();
+        let payer = self.ctx.payer.pubkey();
+        let tx = Transaction::new_signed_with_payer(instructions, Some(&payer), &all_signers, blockhash);
+        self.ctx.banks_client.process_transaction(tx).await.unwrap();
+    }
+
+    async fn new_user(&mut self) -> Keypair {
+        let user = Keypair::new();
+        let transfer = system_instruction::transfer(&self.ctx.payer.pubkey(), &user.pubkey(), LAMPORTS);
+        self.send(&[transfer], &[]).await;
+        user
+    }
+
+    // Give `user` a stake account holding `staked_amount`, as if they had staked it
+    fn seed_stake(&mut self, user: &Pubkey, staked_amount: u64) {
+        let (address, bump) = pda::user_stake(user);
+        let mut stake = UserStake::default();
+        stake.init(*user, bump);
+        stake.staked_amount = staked_amount;
+        let mut data = Vec::new();
+        stake.try_serialize(&mut data).unwrap();
+        data.resize(UserStake::SPACE, 0);
+        let lamports = Rent::default().minimum_balance(data.len());
+        let account = Account { lamports, data, owner: ontora_ai::ID, executable: false, rent_epoch: 0 };
+        self.ctx.set_account(&address, &account.into());
+    }
+
+    // Move to the next slot, so the next change lands in a slot of its own
+    async fn next_slot(&mut self) {
+        let clock: Clock = self.ctx.banks_client.get_sysvar().await.unwrap();
+        self.ctx.warp_to_slot(clock.slot + 1).unwrap();
+    }
+
+    // The account at `address` as a node would notify it now
+    async fn write(&mut self, address: &Pubkey) -> AccountWrite {
+        let clock: Clock = self.ctx.banks_client.get_sysvar().await.unwrap();
+        AccountWrite { slot: clock.slot, account: self.ctx.banks_client.get_account(*address).await.unwrap() }
+    }
+
+    // Initialize the platform with `admin` and a mint with the platform's vaults
+    async fn platform(&mut self, admin: &Keypair) -> (Pubkey, Pubkey) {
+        let initialize = build_initialize_platform(InitializePlatformParams {
+            admin: admin.pubkey(),
+            reward_rate_bps: 100,
+            min_stake_amount: 1_000_000,
+            epoch_duration: 86_400,
+            cooldown_seconds: 86_400,
+            epoch_emission: 100_000,
+        });
+        self.send(&[initialize], &[admin]).await;
+
+        let mint = Keypair::new();
+        let rent = self.ctx.banks_client.get_rent().await.unwrap();
+        let create_mint = [
+            system_instruction::create_account(
+                &self.ctx.payer.pubkey(),
+                &mint.pubkey(),
+                rent.minimum_balance(spl_token::state::Mint::LEN),
+                spl_token::state::Mint::LEN as u64,
+                &spl_token::ID,
+            ),
+            spl_token::instruction::initialize_mint(&spl_token::ID, &mint.pubkey(), &admin.pubkey(), None, 9).unwrap(),
+        ];
+        self.send(&create_mint, &[&mint]).await;
+        let stake_mint = mint.pubkey();
+        let vaults = build_initialize_vaults(InitializeVaultsParams { admin: admin.pubkey(), stake_mint });
+        self.send(&[vaults], &[admin]).await;
+        (mint.pubkey(), pda::staking_vault().0)
+    }
+
+    // Create `owner`'s associated token account for `mint`, holding `amount` minted by `authority`
+    async fn token_account(&mut self, owner: &Pubkey, mint: &Pubkey, authority: &Keypair, amount: u64) -> Pubkey {
+        let account = spl_associated_token_account::get_associated_token_address(owner, mint);
+        let mut instructions = vec![spl_associated_token_account::instruction::create_associated_token_account(
+            &self.ctx.payer.pubkey(),
+            owner,
+            mint,
+            &spl_token::ID,
+        )];
+        if amount > 0 {
+            let authority = authority.pubkey();
+            let mint_to = spl_token::instruction::mint_to(&spl_token::ID, mint, &account, &authority, &[], amount);
+            instructions.push(mint_to.unwrap());
+        }
+        self.send(&instructions, &[authority]).await;
+        account
+    }
+}
+
+fn register_agent(owner: &Keypair, name: &str) -> Instruction {
+    build_register_agent(RegisterAgentParams {
+        owner: owner.pubkey(),
+        agent_id: AGENT_ID,
+        name: name.to_string(),
+        description: "Market-making agent".to_string(),
+    })
+}
+
+fn rename_agent(owner: &Keypair, name: &str) -> Instruction {
+    build_update_agent_metadata(UpdateAgentMetadataParams {
+        owner: owner.pubkey(),
+        agent_id: AGENT_ID,
+        name: name.to_string(),
+        description: "Market-making agent".to_string(),
+    })
+}
+
+// The changes a subscription delivers next, failing on an error
+async fn next_changes<T>(subscription: &mut Subscription<T>, count: usize) -> Vec<AccountChange<T>> {
+    let mut changes = Vec::new();
+    for _ in 0..count {
+        changes.push(subscription.recv().await.expect("subscription is open").unwrap());
+    }
+    changes
+}
+
+async fn assert_quiet<T>(subscription: &mut Subscription<T>) {
+    assert!(tokio::time::timeout(QUIET, subscription.recv()).await.is_err(), "unexpected change");
+}
+
+// Test that agent writes arrive decoded and in order, that a write notified twice
+// arrives once, and that one missed while disconnected arrives through the fetch
+// made on resubscribing
+#[tokio::test]
+async fn test_agent_subscription_survives_disconnect() {
+    let mut harness = Harness::start().await;
+    let owner = harness.new_user().await;
+    let agent = pda::ai_agent(&owner.pubkey(), AGENT_ID).0;
+    let mut writes = Vec::new();
+    for (slot, name) in ["Atlas", "Atlas II", "Atlas III", "Atlas IV"].into_iter().enumerate() {
+        let instruction = if slot == 0 { register_agent(&owner, name) } else { rename_agent(&owner, name) };
+        harness.send(&[instruction], &[&owner]).await;
+        writes.push(harness.write(&agent).await);
+        harness.next_slot().await;
+    }
+
+    // The third write lands while the websocket is down; the node sends the fourth twice
+    let pubsub = ReplayPubsub::new(
+        vec![
+            Ok(vec![writes[0].clone(), writes[0].clone(), writes[1].clone()]),
+            Ok(vec![writes[3].clone(), writes[3].clone()]),
+        ],
+        Some(writes[2].clone()),
+    );
+    let mut subscription = subscribe_agent(&pubsub, &owner.pubkey(), AGENT_ID);
+    assert_eq!(subscription.address(), agent);
+
+    let changes = next_changes(&mut subscription, 4).await;
+    let names: Vec<(u64, String)> =
+        changes.into_iter().map(|change| (change.slot, change.value.unwrap().name)).collect();
+    let expected_names = ["Atlas", "Atlas II", "Atlas III", "Atlas IV"].map(String::from);
+    let expected: Vec<(u64, String)> = writes.iter().map(|write| write.slot).zip(expected_names).collect();
+    assert_eq!(names, expected);
+    // Later fetches find nothing newer than what was delivered
+    assert_quiet(&mut subscription).await;
+}
+
+// Test a stake position through its first deposit and two top-ups
+#[tokio::test]
+async fn test_user_stake_subscription_decodes_each_deposit() {
+    let mut harness = Harness::start().await;
+    let (admin, owner, user) = (harness.new_user().await, harness.new_user().await, harness.new_user().await);
+    let (mint, platform_vault) = harness.platform(&admin).await;
+    harness.send(&[register_agent(&owner, "Atlas")], &[&owner]).await;
+    let user_tokens = harness.token_account(&user.pubkey(), &mint, &admin, 3 * STAKE).await;
+
+    let position = pda::user_stake(&user.pubkey()).0;
+    let mut writes = Vec::new();
+    for amount in [STAKE, STAKE / 2, STAKE / 4] {
+        harness.next_slot().await;
+        let stake = build_stake_on_agent(StakeOnAgentParams {
+            user: user.pubkey(),
+            user_token_account: user_tokens,
+            platform_vault,
+            agent_owner: owner.pubkey(),
+            agent_id: AGENT_ID,
+            amount,
+            beneficiary: None,
+        });
+        harness.send(&[stake], &[&user]).await;
+        writes.push(harness.write(&position).await);
+    }
+
+    let pubsub = ReplayPubsub::new(vec![Ok(writes.clone())], None);
+    let mut subscription = subscribe_user_stake(&pubsub, &user.pubkey());
+    let staked: Vec<(u64, u64)> = next_changes(&mut subscription, 3)
+        .await
+        .into_iter()
+        .map(|change| {
+            let stake: UserStake = change.value.unwrap();
+            assert_eq!(stake.user, user.pubkey());
+            (change.slot, stake.staked_amount)
+        })
+        .collect();
+    let expected = [STAKE, STAKE + STAKE / 2, STAKE + STAKE / 2 + STAKE / 4];
+    assert_eq!(staked, writes.iter().map(|write| write.slot).zip(expected).collect::<Vec<_>>());
+}
+
+// Test a zero-copy proposal's tallies as votes come in, across a resubscription
+// that fails once before it succeeds
+#[tokio::test]
+async fn test_proposal_subscription_resubscribes_after_failure() {
+    let mut harness = Harness::start().await;
+    let admin = harness.new_user().await;
+    harness.platform(&admin).await;
+    let proposal = pda::proposal(0).0;
+
+    let create = build_create_governance_proposal(CreateGovernanceProposalParams {
+        creator: admin.pubkey(),
+        proposal_id: 0,
+        title: "Raise reward rate".to_string(),
+        description: "Raise the reward rate to 6%".to_string(),
+        voting_duration: 3_600,
+        options: vec!["Yes".to_string(), "No".to_string()],
+        action: ProposalAction::UpdateRewardRate { bps: 600 },
+    });
+    harness.send(&[create], &[&admin]).await;
+    let mut writes = vec![harness.write(&proposal).await];
+    for vote_option in [0, 1, 0] {
+        harness.next_slot().await;
+        let voter = harness.new_user().await;
+        harness.seed_stake(&voter.pubkey(), DEFAULT_VOTE_UNIT);
+        let vote = build_cast_vote(CastVoteParams { voter: voter.pubkey(), proposal_id: 0, vote_option });
+        harness.send(&[vote], &[&voter]).await;
+        writes.push(harness.write(&proposal).await);
+    }
+
+    let pubsub = ReplayPubsub::new(
+        vec![Ok(writes[..2].to_vec()), Err("connection refused".to_string()), Ok(writes[3..].to_vec())],
+        Some(writes[2].clone()),
+    );
+    let mut subscription = subscribe_proposal(&pubsub, 0);
+    let tallies = |change: AccountChange<Proposal>| change.value.unwrap().votes[..2].to_vec();
+    let changes = next_changes(&mut subscription, 2).await;
+    assert_eq!(changes.into_iter().map(tallies).collect::<Vec<_>>(), [vec![0, 0], vec![1, 0]]);
+
+    // The failed attempt is reported in line, and the next one carries on
+    let failed = subscription.recv().await.unwrap();
+    assert!(matches!(failed, Err(ClientError::Subscription(ref message)) if message == "connection refused"));
+    let changes = next_changes(&mut subscription, 2).await;
+    assert_eq!(changes.iter().map(|change| change.slot).collect::<Vec<_>>(), [writes[2].slot, writes[3].slot]);
+    assert_eq!(changes.into_iter().map(tallies).collect::<Vec<_>>(), [vec![1, 1], vec![2, 1]]);
+}
+
+// Test that a write that does not decode is reported without ending the
+// subscription, and that a closed account is delivered as None
+#[tokio::test]
+async fn test_undecodable_and_closed_writes() {
+    let owner = Pubkey::new_unique();
+    let agent = pda::ai_agent(&owner, AGENT_ID).0;
+    let account = |owner, data| Some(Account { lamports: 1, data, owner, executable: false, rent_epoch: 0 });
+    let mut valid = Vec::new();
+    AiAgent { owner, agent_id: AGENT_ID, ..AiAgent::default() }.try_serialize(&mut valid).unwrap();
+
+    let pubsub = ReplayPubsub::new(
+        vec![Ok(vec![
+            AccountWrite { slot: 10, account: account(ontora_ai::ID, vec![1, 2, 3]) },
+            AccountWrite { slot: 11, account: account(Pubkey::new_unique(), valid.clone()) },
+            AccountWrite { slot: 12, account: account(ontora_ai::ID, valid) },
+            AccountWrite { slot: 13, account: None },
+        ])],
+        None,
+    );
+    let mut subscription = subscribe_agent(&pubsub, &owner, AGENT_ID);
+    assert!(matches!(subscription.recv().await.unwrap(), Err(ClientError::Decode(address, _)) if address == agent));
+    assert!(matches!(subscription.recv().await.unwrap(), Err(ClientError::WrongOwner(address, _)) if address == agent));
+    let changes = next_changes(&mut subscription, 2).await;
+    assert_eq!((changes[0].slot, changes[0].value.as_ref().map(|agent| agent.agent_id)), (12, Some(AGENT_ID)));
+    assert_eq!((changes[1].slot, changes[1].value.is_none()), (13, true));
+    assert_quiet(&mut subscription).await;
+}
@@ -0,0 +1,143 @@
+// test_transaction.rs
+// Tests for the compute budget `TxBuilder` prepends. A mock stands in for the RPC
+// calls: it serves recent prioritization fees and a fixed simulated unit count, and
+// records what it was asked, so the prepended instructions can be checked exactly.
+
+use std::cell::RefCell;
+
+use ontora_client::*;
+use solana_sdk::compute_budget::{self, ComputeBudgetInstruction};
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::transaction::VersionedTransaction;
+
+#[derive(Default)]
+struct MockRpc {
+    fees: Vec<u64>,
+    units: u64,
+    fee_accounts: RefCell<Vec<Pubkey>>,
+    simulated: RefCell<Vec<VersionedTransaction>>,
+}
+
+impl TransactionRpc for MockRpc {
+    fn recent_prioritization_fees(&self, accounts: &[Pubkey]) -> Result<Vec<u64>, ClientError> {
+        self.fee_accounts.replace(accounts.to_vec());
+        Ok(self.fees.clone())
+    }
+
+    fn simulate_units(&self, transaction: &VersionedTransaction) -> Result<u64, ClientError> {
+        self.simulated.borrow_mut().push(transaction.clone());
+        Ok(self.units)
+    }
+}
+
+fn claim(user: Pubkey) -> Instruction {
+    build_claim_rewards(ClaimRewardsParams {
+        user,
+        user_token_account: Pubkey::new_unique(),
+        reward_vault: Pubkey::new_unique(),
+        reward_mint: Pubkey::new_unique(),
+    })
+}
+
+fn distribute(crank: Pubkey) -> Instruction {
+    build_distribute_rewards(DistributeRewardsParams { crank, agent_owner: Pubkey::new_unique() })
+}
+
+// Test that the table limit sums the budgets of tests/compute_budgets.txt plus their
+// 10% tolerance, and falls back to the runtime default for other programs
+#[test]
+fn test_table_limit() {
+    let user = Pubkey::new_unique();
+    assert_eq!(instruction_budget(&claim(user)), 55_000 * 110 / 100);
+    assert_eq!(instruction_budget(&distribute(user)), 20_000 * 110 / 100);
+    let transfer = spl_token::instruction::transfer(&spl_token::ID, &user, &user, &user, &[], 1).unwrap();
+    assert_eq!(instruction_budget(&transfer), DEFAULT_INSTRUCTION_UNITS);
+
+    let rpc = MockRpc::default();
+    let built = TxBuilder::new(user).instruction(claim(user)).instruction(distribute(user)).build(&rpc).unwrap();
+    assert_eq!(built.len(), 3);
+    assert_eq!(built[0], ComputeBudgetInstruction::set_compute_unit_limit(60_500 + 22_000));
+    assert_eq!(built[1].program_id, ontora_ai::ID);
+    assert!(rpc.simulated.borrow().is_empty());
+
+    // A transaction can't ask for more than the runtime maximum
+    let built = TxBuilder::new(user).instructions(vec![transfer; 8]).build(&rpc).unwrap();
+    assert_eq!(built[0], ComputeBudgetInstruction::set_compute_unit_limit(MAX_COMPUTE_UNIT_LIMIT));
+}
+
+// Test that a simulated limit adds the safety margin to the units consumed, and that
+// the simulation ran the instructions as they will be sent
+#[test]
+fn test_simulated_limit_has_margin() {
+    let user = Pubkey::new_unique();
+    let rpc = MockRpc { units: 41_234, ..MockRpc::default() };
+    let builder = TxBuilder::new(user)
+        .instruction(claim(user))
+        .compute_limit(ComputeLimit::Simulate)
+        .priority_fee(PriorityFee::Fixed(7_000));
+    let built = builder.build(&rpc).unwrap();
+
+    let limit = limit_with_margin(41_234);
+    assert_eq!(limit, 41_234 * 120 / 100);
+    assert!(limit as u64 > rpc.units);
+    assert_eq!(built[0], ComputeBudgetInstruction::set_compute_unit_limit(limit));
+    assert_eq!(built[1], ComputeBudgetInstruction::set_compute_unit_price(7_000));
+
+    let simulated = rpc.simulated.borrow();
+    assert_eq!(simulated.len(), 1);
+    let message = &simulated[0].message;
+    assert_eq!(message.static_account_keys()[0], user);
+    assert_eq!(message.instructions().len(), 3);
+    assert_eq!(*message.instructions()[0].program_id(message.static_account_keys()), compute_budget::ID);
+
+    assert_eq!(limit_with_margin(u64::MAX), MAX_COMPUTE_UNIT_LIMIT);
+    let fixed = TxBuilder::new(user).instruction(claim(user)).compute_limit(ComputeLimit::Fixed(90_000));
+    assert_eq!(fixed.build(&rpc).unwrap()[0], ComputeBudgetInstruction::set_compute_unit_limit(90_000));
+}
+
+// Test the percentile price, read from the fees paid on the writable accounts, and that
+// no price instruction is sent without a fee
+#[test]
+fn test_priority_fee_strategies() {
+    let user = Pubkey::new_unique();
+    let instruction = claim(user);
+    let rpc = MockRpc { fees: vec![100, 0, 30, 10, 90, 20, 50, 40, 60, 80, 70], ..MockRpc::default() };
+
+    let built = TxBuilder::new(user).instruction(instruction.clone()).priority_fee(PriorityFee::Percentile(75));
+    let built = built.build(&rpc).unwrap();
+    assert_eq!(built[1], ComputeBudgetInstruction::set_compute_unit_price(70));
+    let mut writable: Vec<Pubkey> =
+        instruction.accounts.iter().filter(|meta| meta.is_writable).map(|meta| meta.pubkey).collect();
+    writable.sort();
+    writable.dedup();
+    assert_eq!(*rpc.fee_accounts.borrow(), writable);
+
+    assert_eq!(fee_percentile(&rpc.fees, 0), 0);
+    assert_eq!(fee_percentile(&rpc.fees, 50), 50);
+    assert_eq!(fee_percentile(&rpc.fees, 100), 100);
+    assert_eq!(fee_percentile(&[], 90), 0);
+
+    let built = TxBuilder::new(user).instruction(instruction.clone()).build(&rpc).unwrap();
+    assert_eq!(built.len(), 2);
+    assert_eq!(built[1], instruction);
+    let quiet = MockRpc::default();
+    let built = TxBuilder::new(user).instruction(instruction).priority_fee(PriorityFee::Percentile(75));
+    assert_eq!(built.build(&quiet).unwrap().len(), 2);
+}
+
+// Test the strategies' string forms, as the CLI and config files spell them
+#[test]
+fn test_parse_strategies() {
+    assert_eq!("table".parse(), Ok(ComputeLimit::Table));
+    assert_eq!("simulate".parse(), Ok(ComputeLimit::Simulate));
+    assert_eq!("250000".parse(), Ok(ComputeLimit::Fixed(250_000)));
+    assert!("1400001".parse::<ComputeLimit>().is_err());
+    assert!("lots".parse::<ComputeLimit>().is_err());
+
+    assert_eq!("none".parse(), Ok(PriorityFee::None));
+    assert_eq!("5000".parse(), Ok(PriorityFee::Fixed(5_000)));
+    assert_eq!("p75".parse(), Ok(PriorityFee::Percentile(75)));
+    assert!("p101".parse::<PriorityFee>().is_err());
+    assert!("high".parse::<PriorityFee>().is_err());
+}
@@ -0,0 +1,18 @@
+use thiserror::Error;
+
+/// Errors from running the crank.
+#[derive(Debug, Error)]
+pub enum CrankError {
+    #[error("RPC request failed: {0}")]
+    Rpc(#[from] solana_client::client_error::ClientError),
+    #[error(transparent)]
+    Client(#[from] ontora_client::ClientError),
+    #[error("could not read keypair {0}: {1}")]
+    Keypair(String, String),
+    #[error("state file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("malformed state file: {0}")]
+    State(String),
+    #[error("batch {index} did not land: {reason}")]
+    Batch { index: usize, reason: String },
+}
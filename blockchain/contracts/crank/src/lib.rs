@@ -0,0 +1,21 @@
+//! The reward distribution crank: every epoch, pays each staked legacy agent
+//! through `distribute_rewards_batch` transactions sized to the runtime's
+//! limits, and records the agents paid in a local state file so a restarted
+//! crank resumes the epoch instead of paying anyone twice.
+//!
+//!     let mut state = EpochState::load(&path, reward_pool.last_updated)?;
+//!     let epoch = ontora_ai::reward_epoch(clock.unix_timestamp);
+//!     let pending = pending_agents(&list_legacy_agents(&rpc)?, &state.paid, epoch);
+//!     for batch in plan_batches(&pending, &BatchLimits::default(), table.as_ref()) {
+//!         // send batch.instruction(&crank), then
+//!         state.record(&batch.agents);
+//!         state.store(&path)?;
+//!     }
+
+pub mod error;
+pub mod planner;
+pub mod state;
+
+pub use error::CrankError;
+pub use planner::{pending_agents, plan_batches, Batch, BatchLimits, MAX_TRANSACTION_ACCOUNTS};
+pub use state::EpochState;
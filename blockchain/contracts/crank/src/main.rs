@@ -0,0 +1,224 @@
+// main.rs
+// Distributes the epoch's rewards to every staked legacy agent. Run it from a timer:
+// until the epoch opens it exits without sending anything, and once it has, it pays the
+// agents in batches and closes the epoch with the last one. The agents paid so far are
+// kept in the state file, so a run that stopped part way resumes with the rest.
+//
+//     ontora-crank --keypair crank.json --lookup-table <TABLE> --priority-fee p75
+//     ontora-crank --keypair crank.json --dry-run
+//
+// Built with the `jito` feature, `--block-engine` sends the batches as Jito bundles of up
+// to four with a tip, so they aren't outbid near the epoch boundary. A bundle that can't
+// be sent or doesn't land, or whose tip would overrun `--tip-budget`, is sent over RPC.
+//
+//     ontora-crank --keypair crank.json --block-engine https://mainnet.block-engine.jito.wtf --tip p75
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use clap::Parser;
+#[cfg(feature = "jito")]
+use ontora_client::{send_bundle_with_fallback, JitoClient, Route, TipBudget, TipStrategy, MAX_BUNDLE_TRANSACTIONS};
+use ontora_client::{
+    fetch_lookup_table, fetch_reward_pool, list_legacy_agents, send_and_confirm_with_retry, ClientError,
+    ComputeLimit, PriorityFee, RetryPolicy, SendOutcome, TxBuilder,
+};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::account::from_account;
+use solana_sdk::clock::Clock;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+#[cfg(feature = "jito")]
+use solana_sdk::signature::Keypair;
+use solana_sdk::signature::{read_keypair_file, Signer};
+use solana_sdk::sysvar;
+
+use ontora_crank::{pending_agents, plan_batches, Batch, BatchLimits, CrankError, EpochState};
+
+const DEFAULT_RPC_URL: &str = "http://127.0.0.1:8899";
+
+/// Pay the epoch's rewards to every staked legacy agent (crank role).
+#[derive(Debug, Parser)]
+#[command(name = "ontora-crank", version)]
+struct Cli {
+    /// Keypair holding the crank role; it signs and pays for every batch
+    #[arg(long, short = 'k', env = "ONTORA_KEYPAIR")]
+    keypair: PathBuf,
+    /// RPC endpoint of the cluster
+    #[arg(long, short = 'u', env = "ONTORA_RPC_URL", default_value = DEFAULT_RPC_URL)]
+    url: String,
+    /// Where the agents paid in the current epoch are recorded
+    #[arg(long, env = "ONTORA_CRANK_STATE", default_value = "ontora-crank.state")]
+    state_file: PathBuf,
+    /// Address lookup table holding the agents, so more of them fit in a transaction
+    #[arg(long)]
+    lookup_table: Option<Pubkey>,
+    /// Compute-unit price: `none`, micro-lamports per unit, or a percentile of recent fees such as `p75`
+    #[arg(long, env = "ONTORA_PRIORITY_FEE", default_value = "none")]
+    priority_fee: PriorityFee,
+    /// Compute units budgeted for each agent in a batch
+    #[arg(long, default_value_t = ontora_crank::planner::DEFAULT_UNITS_PER_AGENT)]
+    units_per_agent: u32,
+    /// Print the planned batches without sending them
+    #[arg(long)]
+    dry_run: bool,
+    #[cfg(feature = "jito")]
+    #[command(flatten)]
+    jito: JitoArgs,
+}
+
+#[cfg(feature = "jito")]
+#[derive(Debug, clap::Args)]
+struct JitoArgs {
+    /// Jito block engine to send the batches to as bundles, such as https://mainnet.block-engine.jito.wtf
+    #[arg(long, env = "ONTORA_BLOCK_ENGINE")]
+    block_engine: Option<String>,
+    /// Tip per bundle: lamports, lamports per batch such as `2500/tx`, or a percentile of landed tips such as `p50`
+    #[arg(long, env = "ONTORA_TIP", default_value = "p50")]
+    tip: TipStrategy,
+    /// Most lamports one bundle tips
+    #[arg(long, default_value_t = 100_000)]
+    max_tip: u64,
+    /// Lamports this run may spend on tips; once spent, batches go over RPC
+    #[arg(long, default_value_t = 1_000_000)]
+    tip_budget: u64,
+}
+
+// Record a batch that landed, or fail the run on one that didn't
+fn settle(
+    cli: &Cli,
+    state: &mut EpochState,
+    index: usize,
+    batch: &Batch,
+    outcome: SendOutcome,
+) -> Result<(), CrankError> {
+    let reason = match outcome {
+        SendOutcome::Confirmed(signature) | SendOutcome::AlreadyProcessed(signature) => {
+            state.record(&batch.agents);
+            state.store(&cli.state_file)?;
+            println!("batch {} landed {}", index + 1, signature);
+            return Ok(());
+        }
+        SendOutcome::Expired => "expired before it landed".to_string(),
+        SendOutcome::FailedOnChain(error) => format!("failed: {}", error),
+    };
+    Err(CrankError::Batch { index: index + 1, reason })
+}
+
+// Send the batches in bundles of up to four through `block_engine`
+#[cfg(feature = "jito")]
+fn send_bundles(
+    cli: &Cli,
+    rpc: &RpcClient,
+    crank: &Keypair,
+    block_engine: &str,
+    batches: &[Batch],
+    builders: &[TxBuilder],
+    state: &mut EpochState,
+) -> Result<(), CrankError> {
+    let engine = JitoClient::new(block_engine);
+    let mut budget = TipBudget::new(cli.jito.tip, cli.jito.max_tip, cli.jito.tip_budget);
+    let per_bundle = MAX_BUNDLE_TRANSACTIONS - 1;
+    for (chunk, builders) in builders.chunks(per_bundle).enumerate() {
+        let first = chunk * per_bundle;
+        let sent = send_bundle_with_fallback(rpc, &engine, builders, &[crank], &mut budget, &RetryPolicy::default())?;
+        let last = first + builders.len();
+        match &sent.route {
+            Route::Bundle { id, tip, tip_account } => {
+                let tipped = format!("tipping {} lamports to {}", tip, tip_account);
+                println!("batches {}-{} landed as bundle {}, {}", first + 1, last, id, tipped)
+            }
+            Route::Rpc(fallback) => println!("batches {}-{} sent over RPC: {:?}", first + 1, last, fallback),
+        }
+        for (offset, outcome) in sent.outcomes.into_iter().enumerate() {
+            settle(cli, state, first + offset, &batches[first + offset], outcome)?;
+        }
+    }
+    println!("{} lamports of the tip budget left", budget.remaining);
+    Ok(())
+}
+
+fn execute(cli: &Cli) -> Result<(), CrankError> {
+    let crank = read_keypair_file(&cli.keypair)
+        .map_err(|error| CrankError::Keypair(cli.keypair.display().to_string(), error.to_string()))?;
+    let rpc = RpcClient::new_with_commitment(cli.url.clone(), CommitmentConfig::confirmed());
+
+    let reward_pool = fetch_reward_pool(&rpc)?;
+    let clock: Clock = from_account(&rpc.get_account(&sysvar::clock::ID)?)
+        .ok_or_else(|| ClientError::Decode(sysvar::clock::ID, "not a clock".to_string()))?;
+    let epoch = ontora_ai::reward_epoch(clock.unix_timestamp);
+    if epoch < reward_pool.open_epoch() {
+        let opens_at = ontora_ai::REWARD_GENESIS + reward_pool.open_epoch() as i64 * ontora_ai::EPOCH_DURATION;
+        println!("the epoch opens in {}s; nothing to distribute yet", opens_at - clock.unix_timestamp);
+        if !cli.dry_run {
+            return Ok(());
+        }
+    }
+
+    let mut state = EpochState::load(&cli.state_file, reward_pool.last_updated)?;
+    let agents = list_legacy_agents(&rpc)?;
+    let pending = pending_agents(&agents, &state.paid, epoch);
+    let table = cli.lookup_table.map(|address| fetch_lookup_table(&rpc, &address)).transpose()?;
+    let limits = BatchLimits { units_per_agent: cli.units_per_agent, ..BatchLimits::default() };
+    let batches = plan_batches(&pending, &limits, table.as_ref());
+    println!(
+        "epoch after {}: {} agents, {} paid already, {} to pay in {} batches",
+        reward_pool.last_updated,
+        agents.len(),
+        state.paid.len(),
+        pending.len(),
+        batches.len()
+    );
+
+    for (index, batch) in batches.iter().enumerate() {
+        let closes = if batch.close_epoch { ", closes the epoch" } else { "" };
+        println!(
+            "batch {}/{}: {} agents, {} units{}",
+            index + 1,
+            batches.len(),
+            batch.agents.len(),
+            batch.compute_units,
+            closes
+        );
+        if cli.dry_run {
+            batch.agents.iter().for_each(|agent| println!("    {}", agent));
+        }
+    }
+    if cli.dry_run {
+        return Ok(());
+    }
+
+    let builders: Vec<TxBuilder> = batches
+        .iter()
+        .map(|batch| {
+            let builder = TxBuilder::new(crank.pubkey())
+                .instruction(batch.instruction(&crank.pubkey()))
+                .compute_limit(ComputeLimit::Fixed(batch.compute_units))
+                .priority_fee(cli.priority_fee);
+            match &table {
+                Some(table) => builder.lookup_table(table.clone()),
+                None => builder,
+            }
+        })
+        .collect();
+    #[cfg(feature = "jito")]
+    if let Some(block_engine) = &cli.jito.block_engine {
+        return send_bundles(cli, &rpc, &crank, block_engine, &batches, &builders, &mut state);
+    }
+    for (index, (batch, builder)) in batches.iter().zip(&builders).enumerate() {
+        let outcome = send_and_confirm_with_retry(&rpc, builder, &[&crank], &RetryPolicy::default())?;
+        settle(cli, &mut state, index, batch, outcome)?;
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match execute(&cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("error: {}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,140 @@
+//! Batch planning. Each `distribute_rewards_batch` transaction carries as many
+//! agents as fit three limits at once: the accounts a transaction may lock,
+//! the compute units it may request, and the packet size. An agent resolved
+//! through the lookup table costs two bytes of the packet; one listed in full
+//! costs its whole key.
+
+use std::collections::{BTreeSet, HashSet};
+
+use ontora_ai::AIAgent;
+use ontora_client::{build_distribute_rewards_batch, DistributeRewardsBatchParams, MAX_COMPUTE_UNIT_LIMIT};
+use solana_sdk::address_lookup_table::AddressLookupTableAccount;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::packet::PACKET_DATA_SIZE;
+use solana_sdk::pubkey::Pubkey;
+
+/// Accounts one transaction may lock, programs included: the runtime's limit.
+pub const MAX_TRANSACTION_ACCOUNTS: usize = 64;
+/// Units of a batch without agents, with both compute budget instructions.
+pub const DEFAULT_BASE_UNITS: u32 = 20_000;
+/// Units each agent adds to a batch.
+pub const DEFAULT_UNITS_PER_AGENT: u32 = 8_000;
+
+// Accounts every batch locks besides its agents: the crank, the platform
+// config, the reward pool, the program and the compute budget program
+const FIXED_ACCOUNTS: usize = 5;
+// Serialized size of a legacy batch transaction without agents: the signature,
+// header, fixed accounts, blockhash, and the three instructions
+const FIXED_BYTES: usize = 297;
+// What naming one lookup table adds: the version prefix, the table's key and
+// its two index lists
+const LOOKUP_TABLE_BYTES: usize = 36;
+// An agent listed in full: its key, and its index in the instruction
+const LISTED_AGENT_BYTES: usize = 33;
+// An agent in the lookup table: its index in the table, and in the instruction
+const LOOKED_UP_AGENT_BYTES: usize = 2;
+
+/// Limits a batch transaction is planned against.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BatchLimits {
+    pub max_accounts: usize,
+    /// Serialized transaction size.
+    pub max_bytes: usize,
+    pub max_compute_units: u32,
+    pub base_units: u32,
+    pub units_per_agent: u32,
+}
+
+impl Default for BatchLimits {
+    fn default() -> Self {
+        BatchLimits {
+            max_accounts: MAX_TRANSACTION_ACCOUNTS,
+            max_bytes: PACKET_DATA_SIZE,
+            max_compute_units: MAX_COMPUTE_UNIT_LIMIT,
+            base_units: DEFAULT_BASE_UNITS,
+            units_per_agent: DEFAULT_UNITS_PER_AGENT,
+        }
+    }
+}
+
+impl BatchLimits {
+    /// Compute units a batch of `agents` agents requests.
+    pub fn units(&self, agents: usize) -> u32 {
+        let units = self.base_units as u64 + self.units_per_agent as u64 * agents as u64;
+        units.min(u32::MAX as u64) as u32
+    }
+}
+
+/// One `distribute_rewards_batch` transaction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Batch {
+    /// Agents to pay, in ascending address order.
+    pub agents: Vec<Pubkey>,
+    /// Compute-unit limit to request.
+    pub compute_units: u32,
+    /// Whether this batch closes the epoch; only the last one does.
+    pub close_epoch: bool,
+}
+
+impl Batch {
+    pub fn instruction(&self, crank: &Pubkey) -> Instruction {
+        build_distribute_rewards_batch(DistributeRewardsBatchParams {
+            crank: *crank,
+            agents: self.agents.clone(),
+            close_epoch: self.close_epoch,
+        })
+    }
+}
+
+/// Agents still to be paid in `epoch`, the current reward epoch: those with
+/// stake, other than `paid` and those the chain already records as paid in it.
+/// An agent without stake would be paid nothing, so it isn't worth its compute.
+pub fn pending_agents(agents: &[(Pubkey, AIAgent)], paid: &BTreeSet<Pubkey>, epoch: u64) -> Vec<Pubkey> {
+    agents
+        .iter()
+        .filter(|(address, agent)| {
+            agent.staked_amount > 0 && agent.last_reward_epoch != epoch && !paid.contains(address)
+        })
+        .map(|(address, _)| *address)
+        .collect()
+}
+
+/// Splits `agents` into batches in ascending address order, each as large as
+/// `limits` allow when sent through `lookup_table`. The last batch closes the
+/// epoch; with no agents left, it is the only batch and pays no one.
+pub fn plan_batches(
+    agents: &[Pubkey],
+    limits: &BatchLimits,
+    lookup_table: Option<&AddressLookupTableAccount>,
+) -> Vec<Batch> {
+    let in_table: HashSet<&Pubkey> = lookup_table.map(|table| table.addresses.iter().collect()).unwrap_or_default();
+    let fixed_bytes = FIXED_BYTES + if lookup_table.is_some() { LOOKUP_TABLE_BYTES } else { 0 };
+    let mut agents = agents.to_vec();
+    agents.sort_unstable();
+    agents.dedup();
+
+    let batch = |agents: Vec<Pubkey>| Batch { compute_units: limits.units(agents.len()), agents, close_epoch: false };
+    let mut batches = Vec::new();
+    let mut current = Vec::new();
+    let mut bytes = fixed_bytes;
+    for agent in agents {
+        let agent_bytes = if in_table.contains(&agent) { LOOKED_UP_AGENT_BYTES } else { LISTED_AGENT_BYTES };
+        let fits = FIXED_ACCOUNTS + current.len() < limits.max_accounts
+            && bytes + agent_bytes <= limits.max_bytes
+            && limits.units(current.len() + 1) <= limits.max_compute_units;
+        // A batch always takes at least one agent, even past limits that leave room for none
+        if !fits && !current.is_empty() {
+            batches.push(batch(std::mem::take(&mut current)));
+            bytes = fixed_bytes;
+        }
+        current.push(agent);
+        bytes += agent_bytes;
+    }
+    if !current.is_empty() || batches.is_empty() {
+        batches.push(batch(current));
+    }
+    if let Some(last) = batches.last_mut() {
+        last.close_epoch = true;
+    }
+    batches
+}
@@ -0,0 +1,63 @@
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::str::FromStr;
+
+use solana_sdk::pubkey::Pubkey;
+
+use crate::error::CrankError;
+
+/// Progress through one epoch's distribution. The file holds the epoch on its
+/// first line and one paid agent per line after it.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EpochState {
+    /// The reward pool's `last_updated` the epoch opened after; the closing
+    /// batch moves it, which starts the next epoch.
+    pub epoch: i64,
+    /// Agents paid so far in the epoch.
+    pub paid: BTreeSet<Pubkey>,
+}
+
+impl EpochState {
+    pub fn new(epoch: i64) -> Self {
+        EpochState { epoch, paid: BTreeSet::new() }
+    }
+
+    /// Reads the progress stored at `path` for `epoch`. Nothing stored, or
+    /// progress through an earlier epoch, starts `epoch` afresh.
+    pub fn load(path: &Path, epoch: i64) -> Result<Self, CrankError> {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(EpochState::new(epoch)),
+            Err(error) => return Err(error.into()),
+        };
+        let mut lines = contents.lines().map(str::trim).filter(|line| !line.is_empty());
+        let stored: i64 = match lines.next() {
+            Some(line) => line.parse().map_err(|_| CrankError::State(format!("epoch {:?}", line)))?,
+            None => return Ok(EpochState::new(epoch)),
+        };
+        if stored != epoch {
+            return Ok(EpochState::new(epoch));
+        }
+        let paid = lines
+            .map(|line| Pubkey::from_str(line).map_err(|_| CrankError::State(format!("agent {:?}", line))))
+            .collect::<Result<_, _>>()?;
+        Ok(EpochState { epoch, paid })
+    }
+
+    pub fn record(&mut self, agents: &[Pubkey]) {
+        self.paid.extend(agents.iter().copied());
+    }
+
+    /// Writes the progress to `path`, through a temporary file renamed over
+    /// it, so a crash mid-write can't lose the agents already recorded.
+    pub fn store(&self, path: &Path) -> Result<(), CrankError> {
+        let mut contents = format!("{}\n", self.epoch);
+        for agent in &self.paid {
+            contents.push_str(&format!("{}\n", agent));
+        }
+        let temporary = path.with_extension("tmp");
+        std::fs::write(&temporary, contents)?;
+        std::fs::rename(&temporary, path)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,185 @@
+// test_planner.rs
+// Tests for the crank's batch planner and state file. The planner runs against 150
+// synthetic agents; the batches it plans are checked for composition and limits, and
+// the largest ones are built into signed transactions to check the packet size
+// estimate against the real serialization.
+
+use std::collections::BTreeSet;
+
+use ontora_ai::{AIAgent, RewardPool, LEGACY_AI_AGENT_VERSION};
+use ontora_client::{ClientError, ComputeLimit, PriorityFee, TransactionRpc, TxBuilder};
+use ontora_crank::*;
+use solana_sdk::address_lookup_table::AddressLookupTableAccount;
+use solana_sdk::hash::Hash;
+use solana_sdk::packet::PACKET_DATA_SIZE;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::VersionedTransaction;
+
+const AGENTS: usize = 150;
+
+// Stands in for the RPC; a fixed limit and price never call it
+struct Offline;
+
+impl TransactionRpc for Offline {
+    fn recent_prioritization_fees(&self, _: &[Pubkey]) -> Result<Vec<u64>, ClientError> {
+        unreachable!("the price is fixed")
+    }
+
+    fn simulate_units(&self, _: &VersionedTransaction) -> Result<u64, ClientError> {
+        unreachable!("the limit is fixed")
+    }
+}
+
+fn synthetic_agents() -> Vec<Pubkey> {
+    (0..AGENTS).map(|_| Pubkey::new_unique()).collect()
+}
+
+fn table_of(addresses: &[Pubkey]) -> AddressLookupTableAccount {
+    AddressLookupTableAccount { key: Pubkey::new_unique(), addresses: addresses.to_vec() }
+}
+
+// The size `batch` serializes to, with a compute-unit price so every instruction is present
+fn serialized_size(batch: &Batch, table: Option<&AddressLookupTableAccount>) -> usize {
+    let crank = Keypair::new();
+    let mut builder = TxBuilder::new(crank.pubkey())
+        .instruction(batch.instruction(&crank.pubkey()))
+        .compute_limit(ComputeLimit::Fixed(batch.compute_units))
+        .priority_fee(PriorityFee::Fixed(1_000));
+    if let Some(table) = table {
+        builder = builder.lookup_table(table.clone());
+    }
+    let transaction = builder.transaction(&Offline, &[&crank], Hash::new_unique()).unwrap();
+    bincode::serialize(&transaction).unwrap().len()
+}
+
+// Batches in order pay every agent once, each batch in ascending address order,
+// with the limit its size calls for, and only the last closes the epoch
+fn assert_covers(batches: &[Batch], agents: &[Pubkey], limits: &BatchLimits) {
+    let planned: Vec<Pubkey> = batches.iter().flat_map(|batch| batch.agents.iter().copied()).collect();
+    let mut expected = agents.to_vec();
+    expected.sort();
+    assert_eq!(planned, expected);
+    for (index, batch) in batches.iter().enumerate() {
+        assert_eq!(batch.compute_units, limits.base_units + limits.units_per_agent * batch.agents.len() as u32);
+        assert!(batch.compute_units <= limits.max_compute_units);
+        assert_eq!(batch.close_epoch, index == batches.len() - 1);
+    }
+}
+
+// Test that with every agent in the lookup table, the account lock limit sizes the
+// batches: 59 agents beside the 5 accounts every batch locks
+#[test]
+fn test_plans_batches_through_lookup_table() {
+    let agents = synthetic_agents();
+    let table = table_of(&agents);
+    let limits = BatchLimits::default();
+    let batches = plan_batches(&agents, &limits, Some(&table));
+
+    let sizes: Vec<usize> = batches.iter().map(|batch| batch.agents.len()).collect();
+    assert_eq!(sizes, [59, 59, 32]);
+    assert_covers(&batches, &agents, &limits);
+
+    let transaction_accounts = |batch: &Batch| batch.instruction(&Pubkey::new_unique()).accounts.len() + 2;
+    assert_eq!(transaction_accounts(&batches[0]), MAX_TRANSACTION_ACCOUNTS);
+    assert!(serialized_size(&batches[0], Some(&table)) <= PACKET_DATA_SIZE);
+}
+
+// Test that agents listed in full are sized by the packet: the planned batch fits,
+// and one more agent would not
+#[test]
+fn test_plans_batches_by_packet_size() {
+    let agents = synthetic_agents();
+    let limits = BatchLimits::default();
+    let batches = plan_batches(&agents, &limits, None);
+
+    let sizes: Vec<usize> = batches.iter().map(|batch| batch.agents.len()).collect();
+    assert_eq!(sizes, [28, 28, 28, 28, 28, 10]);
+    assert_covers(&batches, &agents, &limits);
+
+    let full = &batches[0];
+    assert!(serialized_size(full, None) <= PACKET_DATA_SIZE);
+    let mut larger = full.clone();
+    larger.agents.push(batches[1].agents[0]);
+    assert!(serialized_size(&larger, None) > PACKET_DATA_SIZE);
+
+    // Agents missing from the table are listed in full, so a partial table lands between
+    let table = table_of(&agents[..AGENTS / 2]);
+    let batches = plan_batches(&agents, &limits, Some(&table));
+    assert_covers(&batches, &agents, &limits);
+    assert!((3..6).contains(&batches.len()));
+    for batch in &batches {
+        assert!(serialized_size(batch, Some(&table)) <= PACKET_DATA_SIZE);
+    }
+}
+
+// Test that costly agents make the compute limit the binding one
+#[test]
+fn test_plans_batches_by_compute_units() {
+    let agents = synthetic_agents();
+    let limits = BatchLimits { units_per_agent: 100_000, ..BatchLimits::default() };
+    let batches = plan_batches(&agents, &limits, Some(&table_of(&agents)));
+
+    // (1,400,000 - 20,000) / 100,000 agents per batch
+    assert!(batches[..batches.len() - 1].iter().all(|batch| batch.agents.len() == 13));
+    assert_eq!(batches.len(), 12);
+    assert_covers(&batches, &agents, &limits);
+}
+
+// Test that a resumed epoch plans only the agents with stake left to pay, and that an
+// epoch with none left still gets its closing batch
+#[test]
+fn test_resumes_from_state_file() {
+    let agents: Vec<(Pubkey, AIAgent)> = synthetic_agents()
+        .into_iter()
+        .enumerate()
+        .map(|(index, address)| {
+            // Every tenth agent has unstaked
+            let staked_amount = if index % 10 == 0 { 0 } else { 1_000_000_000 };
+            let agent = AIAgent {
+                version: LEGACY_AI_AGENT_VERSION,
+                owner: Pubkey::new_unique(),
+                staked_amount,
+                last_stake_time: 0,
+                accumulated_rewards: 0,
+                is_active: staked_amount > 0,
+                bump: 255,
+                last_reward_epoch: 0,
+            };
+            (address, agent)
+        })
+        .collect();
+    let path = std::env::temp_dir().join(format!("ontora-crank-state-{}", Pubkey::new_unique()));
+    let epoch = 1_700_000_000;
+    let open_epoch = RewardPool { total_rewards: 0, last_updated: epoch, bump: 255 }.open_epoch();
+
+    // The first run paid the first batch before stopping
+    let mut state = EpochState::load(&path, epoch).unwrap();
+    assert!(state.paid.is_empty());
+    let pending = pending_agents(&agents, &state.paid, open_epoch);
+    assert_eq!(pending.len(), 135);
+    let limits = BatchLimits::default();
+    let first = plan_batches(&pending, &limits, None);
+    state.record(&first[0].agents);
+    state.store(&path).unwrap();
+
+    // The restarted run plans the rest, exactly as the first run would have
+    let mut state = EpochState::load(&path, epoch).unwrap();
+    assert_eq!(state.paid, first[0].agents.iter().copied().collect::<BTreeSet<_>>());
+    let resumed = plan_batches(&pending_agents(&agents, &state.paid, open_epoch), &limits, None);
+    assert_eq!(resumed, first[1..]);
+
+    // Once every agent is paid, a batch paying no one closes the epoch
+    resumed.iter().for_each(|batch| state.record(&batch.agents));
+    let closing = plan_batches(&pending_agents(&agents, &state.paid, open_epoch), &limits, None);
+    assert_eq!(closing, [Batch { agents: Vec::new(), compute_units: limits.base_units, close_epoch: true }]);
+
+    // Agents the chain records as paid in the open epoch are left out, as after a lost state file
+    let mut agents = agents;
+    agents[1].1.last_reward_epoch = open_epoch;
+    assert_eq!(pending_agents(&agents, &BTreeSet::new(), open_epoch).len(), 134);
+
+    // Progress through an earlier epoch is dropped
+    assert_eq!(EpochState::load(&path, epoch + 604_800).unwrap(), EpochState::new(epoch + 604_800));
+    std::fs::remove_file(&path).unwrap();
+}
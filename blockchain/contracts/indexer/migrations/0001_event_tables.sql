@@ -0,0 +1,99 @@
+-- One row per indexed event, keyed by where it was emitted, so a replayed
+-- event is written once. Written for both SQLite and Postgres: integers are
+-- BIGINT, public keys and signatures base58 TEXT. Amounts and ids above
+-- i64::MAX are stored as i64::MAX.
+
+CREATE TABLE stakes (
+    signature TEXT NOT NULL,
+    event_index BIGINT NOT NULL,
+    slot BIGINT NOT NULL,
+    -- 'deposit' or 'withdrawal'
+    kind TEXT NOT NULL,
+    user_pubkey TEXT NOT NULL,
+    agent_id BIGINT NOT NULL,
+    amount BIGINT NOT NULL,
+    staking_duration BIGINT,
+    timestamp BIGINT NOT NULL,
+    PRIMARY KEY (signature, event_index)
+);
+CREATE INDEX stakes_user ON stakes (user_pubkey);
+CREATE INDEX stakes_agent ON stakes (agent_id);
+
+CREATE TABLE claims (
+    signature TEXT NOT NULL,
+    event_index BIGINT NOT NULL,
+    slot BIGINT NOT NULL,
+    user_pubkey TEXT NOT NULL,
+    agent_id BIGINT NOT NULL,
+    amount BIGINT NOT NULL,
+    timestamp BIGINT NOT NULL,
+    PRIMARY KEY (signature, event_index)
+);
+CREATE INDEX claims_user ON claims (user_pubkey);
+
+CREATE TABLE proposals (
+    signature TEXT NOT NULL,
+    event_index BIGINT NOT NULL,
+    slot BIGINT NOT NULL,
+    -- 'created' or 'finalized'; the other kind's columns are NULL
+    kind TEXT NOT NULL,
+    proposal_id BIGINT NOT NULL,
+    creator TEXT,
+    title TEXT,
+    voting_duration BIGINT,
+    result BIGINT,
+    vote_summary TEXT,
+    timestamp BIGINT NOT NULL,
+    PRIMARY KEY (signature, event_index)
+);
+CREATE INDEX proposals_id ON proposals (proposal_id);
+
+CREATE TABLE votes (
+    signature TEXT NOT NULL,
+    event_index BIGINT NOT NULL,
+    slot BIGINT NOT NULL,
+    proposal_id BIGINT NOT NULL,
+    voter TEXT NOT NULL,
+    vote_option BIGINT NOT NULL,
+    vote_weight BIGINT NOT NULL,
+    timestamp BIGINT NOT NULL,
+    PRIMARY KEY (signature, event_index)
+);
+CREATE INDEX votes_proposal ON votes (proposal_id);
+
+CREATE TABLE agents (
+    signature TEXT NOT NULL,
+    event_index BIGINT NOT NULL,
+    slot BIGINT NOT NULL,
+    -- 'registered' or 'updated'
+    kind TEXT NOT NULL,
+    agent_id BIGINT NOT NULL,
+    owner TEXT NOT NULL,
+    metadata TEXT NOT NULL,
+    timestamp BIGINT NOT NULL,
+    PRIMARY KEY (signature, event_index)
+);
+CREATE INDEX agents_id ON agents (agent_id);
+
+-- Prices published by the oracle program, recorded under the symbol their
+-- feed is registered for
+CREATE TABLE price_updates (
+    signature TEXT NOT NULL,
+    event_index BIGINT NOT NULL,
+    slot BIGINT NOT NULL,
+    symbol TEXT NOT NULL,
+    feed TEXT NOT NULL,
+    price BIGINT NOT NULL,
+    confidence BIGINT NOT NULL,
+    round BIGINT NOT NULL,
+    timestamp BIGINT NOT NULL,
+    PRIMARY KEY (signature, event_index)
+);
+CREATE INDEX price_updates_symbol ON price_updates (symbol, slot);
+
+-- The newest finalized transaction written to the tables above; a single row
+CREATE TABLE indexer_cursor (
+    id BIGINT PRIMARY KEY,
+    slot BIGINT NOT NULL,
+    signature TEXT NOT NULL
+);
@@ -0,0 +1,22 @@
+-- Reward distributions, for the reward pool totals the API reports
+CREATE TABLE reward_distributions (
+    signature TEXT NOT NULL,
+    event_index BIGINT NOT NULL,
+    slot BIGINT NOT NULL,
+    authority TEXT NOT NULL,
+    total_amount BIGINT NOT NULL,
+    eligible_count BIGINT NOT NULL,
+    timestamp BIGINT NOT NULL,
+    PRIMARY KEY (signature, event_index)
+);
+
+-- Off-chain curation of agents: the program records neither a category nor
+-- a verified mark, so operators maintain them here. The indexer never writes
+-- this table; agents without a row are uncategorized and unverified.
+CREATE TABLE agent_profiles (
+    agent_id BIGINT PRIMARY KEY,
+    category TEXT,
+    -- 1 when verified, 0 otherwise
+    verified BIGINT NOT NULL DEFAULT 0
+);
+CREATE INDEX agent_profiles_category ON agent_profiles (category);
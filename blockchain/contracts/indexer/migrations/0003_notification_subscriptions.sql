@@ -0,0 +1,25 @@
+-- Webhook notification rules: POST to `url` when an event of one of
+-- `event_types` concerns `wallet`
+CREATE TABLE subscriptions (
+    id TEXT PRIMARY KEY,
+    wallet TEXT NOT NULL,
+    -- Comma-separated event names, e.g. 'RewardClaimed,ProposalFinalized'
+    event_types TEXT NOT NULL,
+    -- Smallest amount that notifies, for events that carry one; NULL for any
+    min_amount BIGINT,
+    url TEXT NOT NULL,
+    -- HMAC key deliveries are signed with
+    secret TEXT NOT NULL,
+    created_at BIGINT NOT NULL
+);
+CREATE INDEX subscriptions_wallet ON subscriptions (wallet);
+
+-- Notifications given up on after their retries ran out
+CREATE TABLE dead_letters (
+    id TEXT PRIMARY KEY,
+    subscription_id TEXT NOT NULL,
+    url TEXT NOT NULL,
+    body TEXT NOT NULL,
+    reason TEXT NOT NULL,
+    failed_at BIGINT NOT NULL
+);
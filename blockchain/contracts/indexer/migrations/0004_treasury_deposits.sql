@@ -0,0 +1,14 @@
+-- Protocol fees paid into the treasury. The event names no payer: the fee's
+-- payer is the user of the stake or claim row with the same signature.
+CREATE TABLE treasury_deposits (
+    signature TEXT NOT NULL,
+    event_index BIGINT NOT NULL,
+    slot BIGINT NOT NULL,
+    -- 'unstake', 'claim' or 'subscription'
+    source TEXT NOT NULL,
+    mint TEXT NOT NULL,
+    amount BIGINT NOT NULL,
+    timestamp BIGINT NOT NULL,
+    PRIMARY KEY (signature, event_index)
+);
+CREATE INDEX treasury_deposits_signature ON treasury_deposits (signature);
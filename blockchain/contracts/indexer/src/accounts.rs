@@ -0,0 +1,72 @@
+//! Decoding of raw program-owned account data into the program's state
+//! structs, keyed by Anchor discriminator.
+
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::Discriminator;
+use ontora_ai::state::{
+    AdminActionProposal, AdminMultisig, AiAgent, FeeSchedule, GovernanceRealm, Metadata, PlatformConfig, Proposal,
+    StakeIndexEntry, Treasury, UserStake,
+};
+use ontora_ai::{AIAgent, GovernanceProposal, PendingWithdrawal, RewardPool};
+use ontora_client::{decode_account, decode_zero_copy, ClientError};
+
+macro_rules! program_accounts {
+    (borsh { $($borsh:ident($borsh_ty:ty),)* } zero_copy { $($zero_copy:ident($zero_copy_ty:ty),)* }) => {
+        /// Any account of the program, decoded.
+        // Proposals dwarf the other accounts, but a decoded account only lives
+        // until it is diffed against the next one
+        #[allow(clippy::large_enum_variant)]
+        #[derive(Clone)]
+        pub enum ProgramAccount {
+            $($borsh($borsh_ty),)*
+            $($zero_copy($zero_copy_ty),)*
+        }
+
+        impl ProgramAccount {
+            /// Decodes `data` by its discriminator. `None` when the discriminator
+            /// is not one of the program's accounts, or the data is too short to
+            /// have one.
+            pub fn decode(address: &Pubkey, data: &[u8]) -> Result<Option<Self>, ClientError> {
+                let Some(discriminator) = data.get(..8) else {
+                    return Ok(None);
+                };
+                $(if discriminator == <$borsh_ty as Discriminator>::discriminator() {
+                    return decode_account(address, data).map(|account| Some(ProgramAccount::$borsh(account)));
+                })*
+                $(if discriminator == <$zero_copy_ty as Discriminator>::discriminator() {
+                    return decode_zero_copy(address, data).map(|account| Some(ProgramAccount::$zero_copy(account)));
+                })*
+                Ok(None)
+            }
+
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $(ProgramAccount::$borsh(_) => stringify!($borsh),)*
+                    $(ProgramAccount::$zero_copy(_) => stringify!($zero_copy),)*
+                }
+            }
+        }
+    };
+}
+
+program_accounts! {
+    borsh {
+        PlatformConfig(PlatformConfig),
+        FeeSchedule(FeeSchedule),
+        Treasury(Treasury),
+        GovernanceRealm(GovernanceRealm),
+        RewardPool(RewardPool),
+        AiAgent(AiAgent),
+        LegacyAiAgent(AIAgent),
+        PendingWithdrawal(PendingWithdrawal),
+        UserStake(UserStake),
+        StakeIndexEntry(StakeIndexEntry),
+        LegacyProposal(GovernanceProposal),
+        Metadata(Metadata),
+        AdminMultisig(AdminMultisig),
+        AdminActionProposal(AdminActionProposal),
+    }
+    zero_copy {
+        Proposal(Proposal),
+    }
+}
@@ -0,0 +1,341 @@
+// service.rs
+// Delivers changes to the program's accounts as signed webhooks: snapshots every
+// program-owned account, then diffs each write the account stream delivers against
+// the last known state and POSTs the changes (stake moved, proposal status changed, ...)
+// to every configured URL, retrying failed deliveries with backoff. Platform health
+// is exported for Prometheus at /metrics. With a database, it also notifies the
+// wallet subscriptions the API server manages of their finalized events.
+//
+//     WEBHOOK_URLS=https://example.com/hook WEBHOOK_SECRET=<SECRET> cargo run --bin ontora-indexer-service
+//
+// Environment:
+//     ONTORA_PROGRAM_ID      deployed program id (default: the program's declared id)
+//     INDEXER_RPC_URL        comma-separated RPC endpoints, failed over in turn (default: http://127.0.0.1:8899)
+//     INDEXER_RPC_RATE       requests per second allowed on each RPC endpoint (default: 10)
+//     INDEXER_WS_URL         pubsub endpoint for programSubscribe (default: ws://127.0.0.1:8900)
+//     INDEXER_GEYSER_URL     Yellowstone gRPC endpoint, used instead of pubsub when set
+//                            (requires the `geyser` feature)
+//     INDEXER_GEYSER_TOKEN   x-token for the gRPC endpoint, if it needs one
+//     WEBHOOK_URLS           comma-separated endpoints to deliver to
+//     WEBHOOK_SECRET         HMAC key deliveries are signed with
+//     METRICS_ADDR           address /metrics is served on (default: 0.0.0.0:9464)
+//     METRICS_RPC_URL        comma-separated RPC endpoints the metrics scrape (default: INDEXER_RPC_URL)
+//     METRICS_SCRAPE_SECS    seconds between scrapes of the platform accounts (default: 15)
+//     INDEXER_DATABASE_URL   indexer database holding notification subscriptions; none are sent when unset
+//
+// `monitor` runs it as the reward pool's runway monitor instead: every interval it reads
+// the platform config and reward pool and alerts when the runway in epochs falls below
+// its thresholds, when the reward vault no longer holds what the pool records, and, with
+// a price feed, when the rewards left are worth too little or the feed goes stale. Each
+// alert is sent once when it fires and once when it recovers.
+//
+//     WEBHOOK_URLS=https://example.com/alerts WEBHOOK_SECRET=<SECRET> cargo run --bin ontora-indexer-service -- monitor
+//
+// Environment, besides the RPC and webhook settings above:
+//     SLACK_WEBHOOK_URLS       comma-separated Slack-compatible incoming webhooks, sent `{"text": ...}`
+//     MONITOR_INTERVAL_SECS    seconds between checks (default: 60)
+//     MONITOR_WARNING_EPOCHS   runway, in epochs, below which a warning fires (default: 8)
+//     MONITOR_CRITICAL_EPOCHS  runway below which it turns critical (default: 2)
+//     MONITOR_MAX_DIVERGENCE   base units the vault may differ from the pool by (default: 0)
+//     MONITOR_PRICE_SYMBOL     price feed, read from INDEXER_DATABASE_URL, the pool is valued in USD with
+//     MONITOR_MIN_USD          USD value of the rewards left below which a warning fires
+//     MONITOR_PRICE_DECIMALS   decimals the feed's prices are scaled by (default: 8)
+//     MONITOR_TOKEN_DECIMALS   decimals of the reward mint (default: 9)
+//     MONITOR_MAX_FEED_AGE     seconds after which the feed's price counts as stale (default: 3600)
+
+use std::cell::RefCell;
+use std::net::TcpListener;
+use std::rc::Rc;
+use std::str::FromStr;
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use solana_client::pubsub_client::PubsubClient;
+use solana_client::rpc_config::{RpcTransactionLogsConfig, RpcTransactionLogsFilter};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+
+use ontora_client::{Endpoint, PoolPolicy, RpcPool};
+use ontora_indexer::{
+    alert_payload, fetch_pool_state, fetch_program_accounts, metrics, program_subscribe, settle, slack_payload,
+    AccountTracker, AccountUpdate, DeliveryPolicy, FeedPrice, Indexer, IndexerError, Metrics, Monitor, Notifier,
+    SqlSink, Thresholds, UsdThreshold, WebhookDispatcher, WebhookEndpoint,
+};
+
+const DEFAULT_RPC_URL: &str = "http://127.0.0.1:8899";
+const DEFAULT_RPC_RATE: f64 = 10.0;
+const DEFAULT_WS_URL: &str = "ws://127.0.0.1:8900";
+const DEFAULT_METRICS_ADDR: &str = "0.0.0.0:9464";
+const DEFAULT_SCRAPE_INTERVAL_SECS: u64 = 15;
+const DEFAULT_MONITOR_INTERVAL_SECS: u64 = 60;
+
+// Longest wait for an account write before retrying due deliveries
+const IDLE_INTERVAL: Duration = Duration::from_secs(1);
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+// How often matched notifications are checked against the finalized chain
+const SETTLE_INTERVAL: Duration = Duration::from_secs(5);
+
+#[cfg(feature = "geyser")]
+fn subscribe(ws_url: &str, program_id: &Pubkey) -> Result<Receiver<AccountUpdate>, IndexerError> {
+    match std::env::var("INDEXER_GEYSER_URL") {
+        Ok(endpoint) => {
+            let x_token = std::env::var("INDEXER_GEYSER_TOKEN").ok();
+            ontora_indexer::geyser_subscribe(&endpoint, x_token, program_id)
+        }
+        Err(_) => program_subscribe(ws_url, program_id),
+    }
+}
+
+#[cfg(not(feature = "geyser"))]
+fn subscribe(ws_url: &str, program_id: &Pubkey) -> Result<Receiver<AccountUpdate>, IndexerError> {
+    if std::env::var("INDEXER_GEYSER_URL").is_ok() {
+        return Err(IndexerError::Stream("INDEXER_GEYSER_URL needs the `geyser` feature".to_string()));
+    }
+    program_subscribe(ws_url, program_id)
+}
+
+fn rpc_pool(urls: &str) -> Result<RpcPool, String> {
+    let rate = match std::env::var("INDEXER_RPC_RATE") {
+        Ok(rate) => rate.parse().map_err(|_| format!("invalid INDEXER_RPC_RATE {}", rate))?,
+        Err(_) => DEFAULT_RPC_RATE,
+    };
+    let endpoints = Endpoint::parse_list(urls, rate);
+    if endpoints.is_empty() {
+        return Err(format!("no RPC endpoint in {:?}", urls));
+    }
+    Ok(RpcPool::new(endpoints, CommitmentConfig::confirmed(), PoolPolicy::default()))
+}
+
+// Serve /metrics, scrape the platform accounts every interval, and count the
+// events and failures of every transaction mentioning the program
+fn start_metrics(program_id: &Pubkey, rpc_url: &str, ws_url: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let addr = std::env::var("METRICS_ADDR").unwrap_or_else(|_| DEFAULT_METRICS_ADDR.to_string());
+    let rpc = rpc_pool(&std::env::var("METRICS_RPC_URL").unwrap_or_else(|_| rpc_url.to_string()))?;
+    let interval = match std::env::var("METRICS_SCRAPE_SECS") {
+        Ok(secs) => secs.parse().map_err(|_| format!("invalid METRICS_SCRAPE_SECS {}", secs))?,
+        Err(_) => DEFAULT_SCRAPE_INTERVAL_SECS,
+    };
+    let metrics = Arc::new(Mutex::new(Metrics::new()));
+
+    let listener = TcpListener::bind(&addr)?;
+    let served = metrics.clone();
+    std::thread::spawn(move || {
+        if let Err(error) = metrics::serve(listener, served) {
+            eprintln!("metrics server stopped: {}", error);
+        }
+    });
+
+    let scraped = metrics.clone();
+    std::thread::spawn(move || {
+        loop {
+            if let Err(error) = metrics::scrape(&rpc, &scraped) {
+                eprintln!("metrics scrape failed: {}", error);
+            }
+            std::thread::sleep(Duration::from_secs(interval));
+        }
+    });
+
+    let (subscription, notifications) = PubsubClient::logs_subscribe(
+        ws_url,
+        RpcTransactionLogsFilter::Mentions(vec![program_id.to_string()]),
+        RpcTransactionLogsConfig { commitment: Some(CommitmentConfig::confirmed()) },
+    )?;
+    std::thread::spawn(move || {
+        let _subscription = subscription;
+        for notification in notifications {
+            metrics.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).observe_transaction(&notification.value);
+        }
+        eprintln!("log subscription closed; event counters stopped");
+    });
+    eprintln!("serving metrics on {}", addr);
+    Ok(())
+}
+
+// Match every event from now on against the subscriptions in the database,
+// and deliver the matches once their slot is final
+fn notify(
+    database_url: &str,
+    program_id: &Pubkey,
+    rpc_url: &str,
+    ws_url: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let transport = reqwest::blocking::Client::builder().timeout(WEBHOOK_TIMEOUT).build()?;
+    let notifier = Rc::new(RefCell::new(Notifier::connect(database_url, transport, DeliveryPolicy::default())?));
+    let mut indexer = Indexer::new(*program_id, None).with_sink(notifier.clone());
+    let rpc = rpc_pool(rpc_url)?;
+    let (_subscription, notifications) = PubsubClient::logs_subscribe(
+        ws_url,
+        RpcTransactionLogsFilter::Mentions(vec![program_id.to_string()]),
+        RpcTransactionLogsConfig { commitment: Some(CommitmentConfig::confirmed()) },
+    )?;
+
+    let mut last_settle = Instant::now();
+    loop {
+        let now = Instant::now();
+        let timeout = notifier.borrow().next_due().map_or(IDLE_INTERVAL, |due| due.saturating_duration_since(now));
+        match notifications.recv_timeout(timeout.min(IDLE_INTERVAL)) {
+            Ok(notification) if notification.value.err.is_none() => {
+                let signature = Signature::from_str(&notification.value.signature)?;
+                indexer.ingest(notification.context.slot, signature, &notification.value.logs)?;
+            }
+            Ok(_) | Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return Err(IndexerError::SubscriptionClosed.into()),
+        }
+        if last_settle.elapsed() >= SETTLE_INTERVAL {
+            settle(&rpc, &mut indexer)?;
+            last_settle = Instant::now();
+        }
+        notifier.borrow_mut().dispatch(Instant::now())?;
+    }
+}
+
+// `name` parsed, or `default` when it is unset
+fn env_or<T: FromStr>(name: &str, default: T) -> Result<T, String> {
+    match std::env::var(name) {
+        Ok(value) => value.parse().map_err(|_| format!("invalid {} {}", name, value)),
+        Err(_) => Ok(default),
+    }
+}
+
+fn thresholds() -> Result<Thresholds, String> {
+    let defaults = Thresholds::default();
+    let usd = match std::env::var("MONITOR_MIN_USD") {
+        Ok(_) => Some(UsdThreshold {
+            min_usd: env_or("MONITOR_MIN_USD", 0.0)?,
+            price_decimals: env_or("MONITOR_PRICE_DECIMALS", 8)?,
+            token_decimals: env_or("MONITOR_TOKEN_DECIMALS", 9)?,
+            max_feed_age_seconds: env_or("MONITOR_MAX_FEED_AGE", 3_600)?,
+        }),
+        Err(_) => None,
+    };
+    Ok(Thresholds {
+        runway_warning_epochs: env_or("MONITOR_WARNING_EPOCHS", defaults.runway_warning_epochs)?,
+        runway_critical_epochs: env_or("MONITOR_CRITICAL_EPOCHS", defaults.runway_critical_epochs)?,
+        max_vault_divergence: env_or("MONITOR_MAX_DIVERGENCE", defaults.max_vault_divergence)?,
+        usd,
+    })
+}
+
+// Check the reward pool every interval and deliver each alert that fires or recovers
+fn monitor(rpc_url: &str, endpoints: Vec<WebhookEndpoint>) -> Result<(), Box<dyn std::error::Error>> {
+    let interval = Duration::from_secs(env_or("MONITOR_INTERVAL_SECS", DEFAULT_MONITOR_INTERVAL_SECS)?);
+    let thresholds = thresholds()?;
+    let prices = match (&thresholds.usd, std::env::var("MONITOR_PRICE_SYMBOL")) {
+        (Some(_), Ok(symbol)) => {
+            let url =
+                std::env::var("INDEXER_DATABASE_URL").map_err(|_| "MONITOR_PRICE_SYMBOL needs INDEXER_DATABASE_URL")?;
+            Some((SqlSink::connect(&url)?, symbol))
+        }
+        (Some(_), Err(_)) => return Err("MONITOR_MIN_USD needs MONITOR_PRICE_SYMBOL".into()),
+        (None, _) => None,
+    };
+    let slack: Vec<WebhookEndpoint> = std::env::var("SLACK_WEBHOOK_URLS")
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|url| !url.is_empty())
+        .map(|url| WebhookEndpoint { url: url.to_string(), secret: Vec::new() })
+        .collect();
+
+    let rpc = rpc_pool(rpc_url)?;
+    let transport = reqwest::blocking::Client::builder().timeout(WEBHOOK_TIMEOUT).build()?;
+    let mut dispatcher = WebhookDispatcher::new(transport, Vec::new(), DeliveryPolicy::default());
+    let mut monitor = Monitor::new(thresholds);
+    let mut next_check = Instant::now();
+    loop {
+        if Instant::now() >= next_check {
+            next_check += interval;
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() as i64;
+            let price = match &prices {
+                Some((database, symbol)) => database
+                    .latest_price(symbol)?
+                    .map(|update| FeedPrice { price: update.price, timestamp: update.timestamp }),
+                None => None,
+            };
+            // A failed fetch leaves every alert as it was until the next check
+            match fetch_pool_state(&rpc, price, now) {
+                Ok(state) => {
+                    for alert in monitor.evaluate(&state) {
+                        eprintln!("{}", slack_payload(&alert)["text"]);
+                        for endpoint in &endpoints {
+                            dispatcher.enqueue_to(endpoint, alert_payload(&alert).to_string());
+                        }
+                        for endpoint in &slack {
+                            dispatcher.enqueue_to(endpoint, slack_payload(&alert).to_string());
+                        }
+                    }
+                }
+                Err(error) => eprintln!("reward pool check failed: {}", error),
+            }
+        }
+        dispatcher.dispatch(Instant::now());
+        for dead in dispatcher.take_dead_letters() {
+            eprintln!("gave up delivering to {} ({}): {}", dead.url, dead.reason, dead.body);
+        }
+        let wake = dispatcher.next_due().map_or(next_check, |due| due.min(next_check));
+        std::thread::sleep(wake.saturating_duration_since(Instant::now()));
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let program_id = match std::env::var("ONTORA_PROGRAM_ID") {
+        Ok(id) => Pubkey::from_str(&id).map_err(|error| format!("invalid ONTORA_PROGRAM_ID {}: {}", id, error))?,
+        Err(_) => ontora_ai::ID,
+    };
+    let rpc_url = std::env::var("INDEXER_RPC_URL").unwrap_or_else(|_| DEFAULT_RPC_URL.to_string());
+    let ws_url = std::env::var("INDEXER_WS_URL").unwrap_or_else(|_| DEFAULT_WS_URL.to_string());
+    let secret = std::env::var("WEBHOOK_SECRET").map_err(|_| "WEBHOOK_SECRET is not set")?.into_bytes();
+    let endpoints: Vec<WebhookEndpoint> = std::env::var("WEBHOOK_URLS")
+        .map_err(|_| "WEBHOOK_URLS is not set")?
+        .split(',')
+        .map(str::trim)
+        .filter(|url| !url.is_empty())
+        .map(|url| WebhookEndpoint { url: url.to_string(), secret: secret.clone() })
+        .collect();
+    if std::env::args().nth(1).as_deref() == Some("monitor") {
+        return monitor(&rpc_url, endpoints);
+    }
+
+    start_metrics(&program_id, &rpc_url, &ws_url)?;
+    if let Ok(database_url) = std::env::var("INDEXER_DATABASE_URL") {
+        let (rpc_url, ws_url) = (rpc_url.clone(), ws_url.clone());
+        std::thread::spawn(move || {
+            if let Err(error) = notify(&database_url, &program_id, &rpc_url, &ws_url) {
+                eprintln!("notifications stopped: {}", error);
+            }
+        });
+    }
+    let rpc = rpc_pool(&rpc_url)?;
+    let transport = reqwest::blocking::Client::builder().timeout(WEBHOOK_TIMEOUT).build()?;
+    let mut dispatcher = WebhookDispatcher::new(transport, endpoints, DeliveryPolicy::default());
+    let mut tracker = AccountTracker::new(program_id);
+
+    // Subscribe before snapshotting so no write lands in between; writes older
+    // than the snapshot are dropped as stale
+    let updates = subscribe(&ws_url, &program_id)?;
+    for update in fetch_program_accounts(&rpc, &program_id)? {
+        if let Err(error) = tracker.seed(&update) {
+            eprintln!("skipping {}: {}", update.address, error);
+        }
+    }
+    eprintln!("tracking {} accounts", tracker.len());
+
+    loop {
+        let now = Instant::now();
+        let timeout = dispatcher.next_due().map_or(IDLE_INTERVAL, |due| due.saturating_duration_since(now));
+        match updates.recv_timeout(timeout.min(IDLE_INTERVAL)) {
+            Ok(update) => match tracker.apply(&update) {
+                Ok(diffs) => diffs.iter().for_each(|diff| dispatcher.enqueue(diff)),
+                Err(error) => eprintln!("skipping {}: {}", update.address, error),
+            },
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return Err(IndexerError::Stream("closed".to_string()).into()),
+        }
+        dispatcher.dispatch(Instant::now());
+        for dead in dispatcher.take_dead_letters() {
+            eprintln!("gave up delivering to {} ({}): {}", dead.url, dead.reason, dead.body);
+        }
+    }
+}
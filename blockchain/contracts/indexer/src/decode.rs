@@ -0,0 +1,5 @@
+//! Event decoding lives in the client crate, so integrators and the indexer
+//! share one `OntoraEvent`. The indexer uses the strict `parse_logs`: an event
+//! it can't decode is an error rather than a gap in the index.
+
+pub use ontora_client::events::{parse_logs, DecodeError, OntoraEvent};
@@ -0,0 +1,169 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use anchor_lang::solana_program::pubkey::Pubkey;
+use ontora_client::ClientError;
+
+use crate::accounts::ProgramAccount;
+use crate::stream::AccountUpdate;
+
+/// A change to one account that webhook consumers are told about.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AccountChange {
+    /// An account of the program appeared.
+    Created,
+    /// The account was closed.
+    Closed,
+    /// A user's stake grew: their total (`agent_id` of `None`, from `UserStake`)
+    /// or their stake on one agent (from `StakeIndexEntry`).
+    StakeIncreased { user: Pubkey, agent_id: Option<u64>, previous: u64, current: u64 },
+    StakeDecreased { user: Pubkey, agent_id: Option<u64>, previous: u64, current: u64 },
+    TotalStakedChanged { previous: u64, current: u64 },
+    ProposalStatusChanged { proposal_id: u64, previous: u8, current: u8 },
+    PerformanceScoreChanged { owner: Pubkey, agent_id: u64, previous: u64, current: u64 },
+    RewardPoolChanged { previous: u64, current: u64 },
+}
+
+/// A change, and the account update it was found in.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AccountDiff {
+    pub slot: u64,
+    pub address: Pubkey,
+    /// `ProgramAccount::name` of the account.
+    pub account_type: &'static str,
+    pub change: AccountChange,
+}
+
+// (user, agent id, amount) of a stake-holding account
+fn stake(account: &ProgramAccount) -> Option<(Pubkey, Option<u64>, u64)> {
+    match account {
+        ProgramAccount::UserStake(stake) => Some((stake.user, None, stake.staked_amount)),
+        ProgramAccount::StakeIndexEntry(entry) => Some((entry.user, Some(entry.agent_id), entry.staked_amount)),
+        _ => None,
+    }
+}
+
+/// The changes from `previous` to `current`, `None` being an account that
+/// does not exist. Created and closed stake accounts also report their stake
+/// moving from or to zero.
+pub fn diff(previous: Option<&ProgramAccount>, current: Option<&ProgramAccount>) -> Vec<AccountChange> {
+    let mut changes = Vec::new();
+    match (previous, current) {
+        (None, Some(_)) => changes.push(AccountChange::Created),
+        (Some(_), None) => changes.push(AccountChange::Closed),
+        _ => {}
+    }
+
+    let (before, after) = (previous.and_then(stake), current.and_then(stake));
+    if let Some((user, agent_id, _)) = after.or(before) {
+        let (previous, current) = (before.map_or(0, |stake| stake.2), after.map_or(0, |stake| stake.2));
+        match current.cmp(&previous) {
+            Ordering::Greater => changes.push(AccountChange::StakeIncreased { user, agent_id, previous, current }),
+            Ordering::Less => changes.push(AccountChange::StakeDecreased { user, agent_id, previous, current }),
+            Ordering::Equal => {}
+        }
+    }
+
+    match (previous, current) {
+        (Some(ProgramAccount::PlatformConfig(before)), Some(ProgramAccount::PlatformConfig(after)))
+            if before.total_staked != after.total_staked =>
+        {
+            changes.push(AccountChange::TotalStakedChanged {
+                previous: before.total_staked,
+                current: after.total_staked,
+            });
+        }
+        (Some(ProgramAccount::Proposal(before)), Some(ProgramAccount::Proposal(after)))
+            if before.status != after.status =>
+        {
+            changes.push(AccountChange::ProposalStatusChanged {
+                proposal_id: after.id,
+                previous: before.status,
+                current: after.status,
+            });
+        }
+        (Some(ProgramAccount::AiAgent(before)), Some(ProgramAccount::AiAgent(after)))
+            if before.performance_score != after.performance_score =>
+        {
+            changes.push(AccountChange::PerformanceScoreChanged {
+                owner: after.owner,
+                agent_id: after.agent_id,
+                previous: before.performance_score,
+                current: after.performance_score,
+            });
+        }
+        (Some(ProgramAccount::RewardPool(before)), Some(ProgramAccount::RewardPool(after)))
+            if before.total_rewards != after.total_rewards =>
+        {
+            changes.push(AccountChange::RewardPoolChanged {
+                previous: before.total_rewards,
+                current: after.total_rewards,
+            });
+        }
+        _ => {}
+    }
+    changes
+}
+
+/// The last known state of every program account, which each update is
+/// diffed against.
+///
+/// Streams can deliver an account's updates out of order across slots, so an
+/// update from a slot older than the known state is dropped as stale.
+pub struct AccountTracker {
+    program_id: Pubkey,
+    accounts: HashMap<Pubkey, (u64, ProgramAccount)>,
+}
+
+impl AccountTracker {
+    pub fn new(program_id: Pubkey) -> Self {
+        AccountTracker { program_id, accounts: HashMap::new() }
+    }
+
+    /// Number of accounts known.
+    pub fn len(&self) -> usize {
+        self.accounts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty()
+    }
+
+    pub fn get(&self, address: &Pubkey) -> Option<&ProgramAccount> {
+        self.accounts.get(address).map(|(_, account)| account)
+    }
+
+    /// Records `update` as known state without reporting changes, as for the
+    /// accounts fetched at startup.
+    pub fn seed(&mut self, update: &AccountUpdate) -> Result<(), ClientError> {
+        self.apply(update).map(|_| ())
+    }
+
+    /// Records `update` and returns what changed. Updates to accounts that
+    /// are not the program's, or are stale, change nothing.
+    pub fn apply(&mut self, update: &AccountUpdate) -> Result<Vec<AccountDiff>, ClientError> {
+        let known = self.accounts.get(&update.address);
+        if known.is_some_and(|(slot, _)| update.slot < *slot) {
+            return Ok(Vec::new());
+        }
+        let current = if update.lamports == 0 || update.owner != self.program_id {
+            None
+        } else {
+            ProgramAccount::decode(&update.address, &update.data)?
+        };
+        let Some(account_type) = current.as_ref().or(known.map(|(_, account)| account)).map(ProgramAccount::name)
+        else {
+            return Ok(Vec::new());
+        };
+
+        let diffs = diff(known.map(|(_, account)| account), current.as_ref())
+            .into_iter()
+            .map(|change| AccountDiff { slot: update.slot, address: update.address, account_type, change })
+            .collect();
+        match current {
+            Some(account) => self.accounts.insert(update.address, (update.slot, account)),
+            None => self.accounts.remove(&update.address),
+        };
+        Ok(diffs)
+    }
+}
@@ -0,0 +1,30 @@
+use thiserror::Error;
+
+use crate::decode::DecodeError;
+
+/// Errors from running the indexer against a cluster.
+#[derive(Debug, Error)]
+pub enum IndexerError {
+    #[error("RPC request failed: {0}")]
+    Rpc(#[from] solana_client::client_error::ClientError),
+    #[error("log subscription failed: {0}")]
+    Pubsub(#[from] solana_client::pubsub_client::PubsubClientError),
+    #[error("log subscription closed")]
+    SubscriptionClosed,
+    #[error("account stream failed: {0}")]
+    Stream(String),
+    #[error(transparent)]
+    Decode(#[from] DecodeError),
+    #[error(transparent)]
+    Account(#[from] ontora_client::ClientError),
+    #[error("invalid signature: {0}")]
+    Signature(#[from] solana_sdk::signature::ParseSignatureError),
+    #[error("malformed cursor {0:?}")]
+    Cursor(String),
+    #[error("cursor file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("database: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("database migration: {0}")]
+    Migration(#[from] sqlx::migrate::MigrateError),
+}
@@ -0,0 +1,151 @@
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+use std::str::FromStr;
+
+use anchor_lang::solana_program::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+
+use crate::decode::{parse_logs, DecodeError};
+use crate::error::IndexerError;
+use crate::sink::{EventRecord, EventSink};
+
+// How far below the finalized slot delivered transactions are remembered, so
+// one seen by both the backfill and the subscription is applied only once
+const SEEN_RETENTION_SLOTS: u64 = 1_000;
+
+/// The newest finalized transaction the indexer has handed to its sinks. A
+/// restarted indexer backfills every transaction after it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Cursor {
+    pub slot: u64,
+    pub signature: Signature,
+}
+
+impl Cursor {
+    /// Reads the cursor stored at `path`; `None` when nothing was stored yet.
+    pub fn load(path: &Path) -> Result<Option<Self>, IndexerError> {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(error.into()),
+        };
+        let mut fields = contents.split_whitespace();
+        match (fields.next(), fields.next()) {
+            (Some(slot), Some(signature)) => Ok(Some(Cursor {
+                slot: slot.parse().map_err(|_| IndexerError::Cursor(contents.clone()))?,
+                signature: Signature::from_str(signature)?,
+            })),
+            _ => Err(IndexerError::Cursor(contents)),
+        }
+    }
+
+    pub fn store(&self, path: &Path) -> Result<(), IndexerError> {
+        std::fs::write(path, format!("{} {}\n", self.slot, self.signature))?;
+        Ok(())
+    }
+}
+
+// A transaction whose events were applied but whose slot is not final yet
+struct PendingTransaction {
+    signature: Signature,
+    records: Vec<EventRecord>,
+}
+
+/// Feeds decoded events to its sinks and retracts the ones whose slot does not
+/// make it onto the finalized chain.
+///
+/// Transactions are ingested at `confirmed` commitment, in the order they are
+/// delivered. `settle` then compares the pending slots at or below the
+/// finalized slot against the finalized blocks.
+pub struct Indexer {
+    program_id: Pubkey,
+    sinks: Vec<Box<dyn EventSink>>,
+    pending: BTreeMap<u64, Vec<PendingTransaction>>,
+    seen: HashSet<(u64, Signature)>,
+    cursor: Option<Cursor>,
+}
+
+impl Indexer {
+    pub fn new(program_id: Pubkey, cursor: Option<Cursor>) -> Self {
+        Indexer {
+            program_id,
+            sinks: Vec::new(),
+            pending: BTreeMap::new(),
+            seen: HashSet::new(),
+            cursor,
+        }
+    }
+
+    pub fn with_sink(mut self, sink: impl EventSink + 'static) -> Self {
+        self.sinks.push(Box::new(sink));
+        self
+    }
+
+    pub fn cursor(&self) -> Option<Cursor> {
+        self.cursor
+    }
+
+    /// Lowest slot with events that are not final yet.
+    pub fn lowest_pending_slot(&self) -> Option<u64> {
+        self.pending.keys().next().copied()
+    }
+
+    /// Decodes the events in one successful transaction's logs and applies them
+    /// to every sink. Returns how many were applied: zero when the transaction
+    /// was already ingested in this slot.
+    pub fn ingest(&mut self, slot: u64, signature: Signature, logs: &[String]) -> Result<usize, DecodeError> {
+        if self.seen.contains(&(slot, signature)) {
+            return Ok(0);
+        }
+        let records: Vec<EventRecord> = parse_logs(&self.program_id, logs)?
+            .into_iter()
+            .enumerate()
+            .map(|(index, event)| EventRecord { slot, signature, index, event })
+            .collect();
+        for record in &records {
+            for sink in &mut self.sinks {
+                sink.apply(record);
+            }
+        }
+        self.seen.insert((slot, signature));
+        let applied = records.len();
+        self.pending.entry(slot).or_default().push(PendingTransaction { signature, records });
+        Ok(applied)
+    }
+
+    /// Settles every pending slot at or below `finalized_slot`. Slots listed in
+    /// `finalized_blocks` become final and advance the cursor; the rest were
+    /// skipped by the finalized chain, so their events are retracted, newest
+    /// first. `finalized_blocks` must cover every pending slot up to
+    /// `finalized_slot`.
+    pub fn settle(&mut self, finalized_slot: u64, finalized_blocks: &[u64]) {
+        let unsettled = self.pending.split_off(&(finalized_slot + 1));
+        let settled = std::mem::replace(&mut self.pending, unsettled);
+        let finalized: HashSet<u64> = finalized_blocks.iter().copied().collect();
+
+        for (slot, transactions) in settled.iter().rev() {
+            if finalized.contains(slot) {
+                continue;
+            }
+            for transaction in transactions.iter().rev() {
+                for record in transaction.records.iter().rev() {
+                    for sink in &mut self.sinks {
+                        sink.retract(record);
+                    }
+                }
+                // The transaction may land again in another slot
+                self.seen.remove(&(*slot, transaction.signature));
+            }
+        }
+        for (slot, transactions) in &settled {
+            if let (true, Some(last)) = (finalized.contains(slot), transactions.last()) {
+                self.cursor = Some(Cursor { slot: *slot, signature: last.signature });
+            }
+        }
+
+        self.seen.retain(|(slot, _)| slot + SEEN_RETENTION_SLOTS >= finalized_slot);
+        for sink in &mut self.sinks {
+            sink.finalize(finalized_slot);
+        }
+    }
+}
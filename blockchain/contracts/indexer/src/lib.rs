@@ -0,0 +1,58 @@
+//! Event indexer for the Ontora AI program: decodes the events the program
+//! emits from transaction logs and hands them to pluggable sinks, retracting
+//! the ones whose slot does not make it onto the finalized chain.
+//!
+//!     let mut indexer = Indexer::new(program_id, cursor).with_sink(MemorySink::new());
+//!     indexer.ingest(slot, signature, &logs)?;
+//!     settle(&rpc, &mut indexer)?;
+//!
+//! `SqlSink` keeps the indexed events in SQLite or Postgres, along with the
+//! cursor to resume from. `Notifier` matches events against the wallet
+//! subscriptions kept in the same database and delivers them as webhooks.
+//!
+//! The webhook service works from account writes instead: it diffs each
+//! program account against its last known state and delivers the changes.
+//!
+//!     for diff in tracker.apply(&update)? {
+//!         dispatcher.enqueue(&diff);
+//!     }
+//!     dispatcher.dispatch(Instant::now());
+//!
+//! Its `monitor` mode watches the reward pool instead, alerting through the
+//! same dispatcher when its runway runs low (see `monitor`).
+
+pub mod accounts;
+pub mod decode;
+pub mod diff;
+pub mod error;
+pub mod indexer;
+pub mod metrics;
+pub mod monitor;
+pub mod notify;
+pub mod sink;
+pub mod source;
+pub mod store;
+pub mod stream;
+pub mod webhook;
+
+pub use accounts::ProgramAccount;
+pub use decode::{parse_logs, DecodeError, OntoraEvent};
+pub use diff::{diff, AccountChange, AccountDiff, AccountTracker};
+pub use error::IndexerError;
+pub use indexer::{Cursor, Indexer};
+pub use metrics::{reward_pool_runway, Metrics};
+pub use monitor::{
+    alert_payload, fetch_pool_state, slack_payload, Alert, AlertStatus, Condition, FeedPrice, Monitor, PoolState,
+    Severity, Thresholds, UsdThreshold,
+};
+pub use notify::{Notifier, Subscription, NOTIFICATION_EVENTS};
+pub use sink::{Aggregates, EventRecord, EventSink, MemorySink, StdoutSink};
+pub use source::{backfill, settle, LoggedTransaction};
+pub use store::{PriceUpdate, SqlSink, EVENT_TABLES};
+#[cfg(feature = "geyser")]
+pub use stream::geyser_subscribe;
+pub use stream::{fetch_program_accounts, program_subscribe, AccountUpdate};
+pub use webhook::{
+    payload, sign, verify, DeadLetter, DeliveryPolicy, WebhookDispatcher, WebhookEndpoint, WebhookTransport,
+    SIGNATURE_HEADER, TIMESTAMP_HEADER,
+};
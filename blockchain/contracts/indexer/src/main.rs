@@ -0,0 +1,106 @@
+// main.rs
+// Streams the program's events to stdout, and to a database when one is configured:
+// subscribes to the logs of every transaction mentioning the program, backfills what
+// happened since the stored cursor, and retracts events whose slot does not make it onto
+// the finalized chain.
+//
+//     ONTORA_PROGRAM_ID=<PROGRAM_ID> cargo run --bin ontora-indexer
+//
+// Environment:
+//     ONTORA_PROGRAM_ID      deployed program id (default: the program's declared id)
+//     INDEXER_RPC_URL        comma-separated RPC endpoints, failed over in turn (default: http://127.0.0.1:8899)
+//     INDEXER_RPC_RATE       requests per second allowed on each RPC endpoint (default: 10)
+//     INDEXER_WS_URL         pubsub endpoint (default: ws://127.0.0.1:8900)
+//     INDEXER_CURSOR_PATH    where the last finalized signature is kept (default: indexer-cursor.txt)
+//     INDEXER_DATABASE_URL   SQLite or Postgres database to store events in; its cursor replaces the file's
+
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::mpsc::RecvTimeoutError;
+use std::time::{Duration, Instant};
+
+use solana_client::pubsub_client::PubsubClient;
+use solana_client::rpc_config::{RpcTransactionLogsConfig, RpcTransactionLogsFilter};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+
+use ontora_client::{Endpoint, PoolPolicy, RpcPool};
+use ontora_indexer::{backfill, settle, Cursor, Indexer, IndexerError, SqlSink, StdoutSink};
+
+const DEFAULT_RPC_URL: &str = "http://127.0.0.1:8899";
+const DEFAULT_RPC_RATE: f64 = 10.0;
+const DEFAULT_WS_URL: &str = "ws://127.0.0.1:8900";
+const DEFAULT_CURSOR_PATH: &str = "indexer-cursor.txt";
+
+// How often pending slots are checked against the finalized chain
+const SETTLE_INTERVAL: Duration = Duration::from_secs(5);
+
+fn rpc_pool(urls: &str) -> Result<RpcPool, String> {
+    let rate = match std::env::var("INDEXER_RPC_RATE") {
+        Ok(rate) => rate.parse().map_err(|_| format!("invalid INDEXER_RPC_RATE {}", rate))?,
+        Err(_) => DEFAULT_RPC_RATE,
+    };
+    let endpoints = Endpoint::parse_list(urls, rate);
+    if endpoints.is_empty() {
+        return Err(format!("no RPC endpoint in {:?}", urls));
+    }
+    Ok(RpcPool::new(endpoints, CommitmentConfig::confirmed(), PoolPolicy::default()))
+}
+
+fn settle_and_store(rpc: &RpcPool, indexer: &mut Indexer, cursor_path: &Path) -> Result<(), IndexerError> {
+    settle(rpc, indexer)?;
+    if let Some(cursor) = indexer.cursor() {
+        cursor.store(cursor_path)?;
+    }
+    Ok(())
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let program_id = match std::env::var("ONTORA_PROGRAM_ID") {
+        Ok(id) => Pubkey::from_str(&id).map_err(|error| format!("invalid ONTORA_PROGRAM_ID {}: {}", id, error))?,
+        Err(_) => ontora_ai::ID,
+    };
+    let rpc_url = std::env::var("INDEXER_RPC_URL").unwrap_or_else(|_| DEFAULT_RPC_URL.to_string());
+    let ws_url = std::env::var("INDEXER_WS_URL").unwrap_or_else(|_| DEFAULT_WS_URL.to_string());
+    let cursor_path = PathBuf::from(std::env::var("INDEXER_CURSOR_PATH").unwrap_or_else(|_| DEFAULT_CURSOR_PATH.to_string()));
+
+    let rpc = rpc_pool(&rpc_url)?;
+    let database = std::env::var("INDEXER_DATABASE_URL").ok().map(|url| SqlSink::connect(&url)).transpose()?;
+    let cursor = match &database {
+        Some(database) => database.cursor()?,
+        None => Cursor::load(&cursor_path)?,
+    };
+    let mut indexer = Indexer::new(program_id, cursor).with_sink(StdoutSink);
+    if let Some(database) = database {
+        indexer = indexer.with_sink(database);
+    }
+
+    // Subscribe before backfilling so nothing lands in between; transactions
+    // delivered by both are applied once
+    let (_subscription, notifications) = PubsubClient::logs_subscribe(
+        &ws_url,
+        RpcTransactionLogsFilter::Mentions(vec![program_id.to_string()]),
+        RpcTransactionLogsConfig { commitment: Some(CommitmentConfig::confirmed()) },
+    )?;
+    for transaction in backfill(&rpc, &program_id, cursor.map(|cursor| cursor.signature))? {
+        indexer.ingest(transaction.slot, transaction.signature, &transaction.logs)?;
+    }
+    settle_and_store(&rpc, &mut indexer, &cursor_path)?;
+
+    let mut last_settle = Instant::now();
+    loop {
+        match notifications.recv_timeout(SETTLE_INTERVAL) {
+            Ok(notification) if notification.value.err.is_none() => {
+                let signature = Signature::from_str(&notification.value.signature)?;
+                indexer.ingest(notification.context.slot, signature, &notification.value.logs)?;
+            }
+            Ok(_) | Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return Err(IndexerError::SubscriptionClosed.into()),
+        }
+        if last_settle.elapsed() >= SETTLE_INTERVAL {
+            settle_and_store(&rpc, &mut indexer, &cursor_path)?;
+            last_settle = Instant::now();
+        }
+    }
+}
@@ -0,0 +1,272 @@
+//! Prometheus metrics of platform health. Gauges come from accounts fetched
+//! every scrape interval; counters from the events and failed transactions a
+//! log subscription delivers, and the health of each RPC endpoint from the
+//! pool the scrape goes through. `render` writes the text exposition format
+//! that `serve` answers `/metrics` with.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::{BufRead, BufReader, Write as _};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use ontora_ai::math::epoch_rewards;
+use ontora_ai::state::{AiAgent, PlatformConfig};
+use ontora_ai::RewardPool;
+use ontora_client::{
+    decode_rpc_logs, fetch_platform_config, fetch_reward_pool, list_agents, ClientError, EndpointHealth, EndpointState,
+    OntoraEvent, RpcPool,
+};
+use solana_client::rpc_response::RpcTransactionLogs;
+
+// Gauges read from the platform's accounts at the last successful scrape
+#[derive(Clone, Debug, Default, PartialEq)]
+struct Gauges {
+    total_staked: u64,
+    reward_pool_balance: u64,
+    emission_per_epoch: u64,
+    active_proposals: u64,
+    // Unset until an agent's score has been posted
+    feed_staleness_seconds: Option<i64>,
+    scraped_at: i64,
+}
+
+/// Current values of every series.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Metrics {
+    gauges: Option<Gauges>,
+    events: BTreeMap<&'static str, u64>,
+    rewards_claimed: u64,
+    failed_transactions: u64,
+    scrape_errors: u64,
+    rpc_endpoints: Vec<EndpointHealth>,
+}
+
+/// Epochs of emission the reward pool covers: its balance over the rewards
+/// the current stake earns per epoch. Infinite while nothing is emitted.
+pub fn reward_pool_runway(balance: u64, emission_per_epoch: u64) -> f64 {
+    if emission_per_epoch == 0 {
+        return f64::INFINITY;
+    }
+    balance as f64 / emission_per_epoch as f64
+}
+
+// A sample value as the exposition format spells it
+fn sample(value: f64) -> String {
+    if value == f64::INFINITY {
+        "+Inf".to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the gauges from freshly fetched accounts. `now` is the unix time
+    /// of the fetch; feed staleness is measured from the newest score any
+    /// agent received.
+    pub fn observe_accounts(
+        &mut self,
+        config: &PlatformConfig,
+        reward_pool: &RewardPool,
+        agents: &[AiAgent],
+        now: i64,
+    ) {
+        let newest_score = agents.iter().map(|agent| agent.score_updated_at).filter(|at| *at > 0).max();
+        self.gauges = Some(Gauges {
+            total_staked: config.total_staked,
+            reward_pool_balance: reward_pool.total_rewards,
+            emission_per_epoch: epoch_rewards(config.total_staked, config.reward_rate_bps, 1).unwrap_or(u64::MAX),
+            active_proposals: config.active_proposal_count,
+            feed_staleness_seconds: newest_score.map(|at| now.saturating_sub(at).max(0)),
+            scraped_at: now,
+        });
+    }
+
+    /// Counts a scrape that failed; the gauges keep their last values.
+    pub fn observe_scrape_error(&mut self) {
+        self.scrape_errors += 1;
+    }
+
+    /// Replaces the RPC endpoints' health with the pool's latest report.
+    pub fn observe_rpc_health(&mut self, endpoints: Vec<EndpointHealth>) {
+        self.rpc_endpoints = endpoints;
+    }
+
+    pub fn observe_event(&mut self, event: &OntoraEvent) {
+        *self.events.entry(event.name()).or_default() += 1;
+        if let OntoraEvent::RewardClaimed(event) = event {
+            self.rewards_claimed = self.rewards_claimed.saturating_add(event.reward_amount);
+        }
+    }
+
+    /// Counts a transaction from the log subscription: a failure, or each
+    /// event it emitted.
+    pub fn observe_transaction(&mut self, logs: &RpcTransactionLogs) {
+        if logs.err.is_some() {
+            self.failed_transactions += 1;
+            return;
+        }
+        for event in decode_rpc_logs(logs) {
+            self.observe_event(&event);
+        }
+    }
+
+    /// Every series in the Prometheus text exposition format. Gauges are left
+    /// out until the first successful scrape, RPC series until the first
+    /// scrape of any kind.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        if let Some(gauges) = &self.gauges {
+            let values = [
+                ("ontora_total_staked", "Tokens staked across the platform.", gauges.total_staked as f64),
+                ("ontora_reward_pool_balance", "Rewards left in the reward pool.", gauges.reward_pool_balance as f64),
+                (
+                    "ontora_reward_emission_per_epoch",
+                    "Rewards the current stake earns per epoch at the current rate.",
+                    gauges.emission_per_epoch as f64,
+                ),
+                (
+                    "ontora_reward_pool_runway_epochs",
+                    "Epochs of emission the reward pool covers.",
+                    reward_pool_runway(gauges.reward_pool_balance, gauges.emission_per_epoch),
+                ),
+                ("ontora_active_proposals", "Governance proposals open for voting.", gauges.active_proposals as f64),
+                (
+                    "ontora_last_scrape_timestamp_seconds",
+                    "Unix time the platform accounts were last fetched.",
+                    gauges.scraped_at as f64,
+                ),
+            ];
+            for (name, help, value) in values {
+                header(&mut out, name, "gauge", help);
+                let _ = writeln!(out, "{} {}", name, sample(value));
+            }
+            if let Some(staleness) = gauges.feed_staleness_seconds {
+                let name = "ontora_feed_staleness_seconds";
+                header(&mut out, name, "gauge", "Seconds since the oracle last posted an agent performance score.");
+                let _ = writeln!(out, "{} {}", name, staleness);
+            }
+        }
+
+        header(&mut out, "ontora_events_total", "counter", "Program events decoded, by event.");
+        for (event, count) in &self.events {
+            let _ = writeln!(out, "ontora_events_total{{event=\"{}\"}} {}", event, count);
+        }
+        let counters = [
+            ("ontora_rewards_claimed_total", "Rewards paid out by claims.", self.rewards_claimed),
+            (
+                "ontora_failed_transactions_total",
+                "Failed transactions mentioning the program.",
+                self.failed_transactions,
+            ),
+            ("ontora_scrape_errors_total", "Failed fetches of the platform accounts.", self.scrape_errors),
+        ];
+        for (name, help, value) in counters {
+            header(&mut out, name, "counter", help);
+            let _ = writeln!(out, "{} {}", name, value);
+        }
+
+        if self.rpc_endpoints.is_empty() {
+            return out;
+        }
+        let series: [(&str, &str, &str, fn(&EndpointHealth) -> u64); 4] = [
+            ("ontora_rpc_up", "gauge", "Whether the RPC endpoint is in rotation.", |endpoint| {
+                u64::from(endpoint.state == EndpointState::Healthy)
+            }),
+            (
+                "ontora_rpc_requests_total",
+                "counter",
+                "Requests sent to the RPC endpoint.",
+                |endpoint| endpoint.requests,
+            ),
+            (
+                "ontora_rpc_failures_total",
+                "counter",
+                "Requests to the RPC endpoint that were rate limited, timed out or found it down.",
+                |endpoint| endpoint.failures,
+            ),
+            (
+                "ontora_rpc_rate_limited_total",
+                "counter",
+                "Requests the RPC endpoint rate limited.",
+                |endpoint| endpoint.rate_limited,
+            ),
+        ];
+        for (name, kind, help, value) in series {
+            header(&mut out, name, kind, help);
+            for endpoint in &self.rpc_endpoints {
+                let _ = writeln!(out, "{}{{endpoint=\"{}\"}} {}", name, endpoint.url, value(endpoint));
+            }
+        }
+        out
+    }
+}
+
+/// Fetches the platform config, reward pool and agents through `rpc` and
+/// updates the gauges, counting a scrape error if any fetch fails. The pool's
+/// endpoint health is recorded either way.
+pub fn scrape(rpc: &RpcPool, metrics: &Mutex<Metrics>) -> Result<(), ClientError> {
+    let fetched = fetch_platform_config(rpc).and_then(|config| {
+        let reward_pool = fetch_reward_pool(rpc)?;
+        let agents: Vec<AiAgent> = list_agents(rpc)?.into_iter().map(|(_, agent)| agent).collect();
+        Ok((config, reward_pool, agents))
+    });
+    let mut metrics = metrics.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    metrics.observe_rpc_health(rpc.health());
+    match fetched {
+        Ok((config, reward_pool, agents)) => {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() as i64;
+            metrics.observe_accounts(&config, &reward_pool, &agents, now);
+            Ok(())
+        }
+        Err(error) => {
+            metrics.observe_scrape_error();
+            Err(error)
+        }
+    }
+}
+
+/// Answers `GET /metrics` on `listener` with the rendered metrics, and 404
+/// otherwise, until the listener fails.
+pub fn serve(listener: TcpListener, metrics: Arc<Mutex<Metrics>>) -> std::io::Result<()> {
+    for stream in listener.incoming() {
+        let mut stream = stream?;
+        let mut request_line = String::new();
+        let mut reader = BufReader::new(stream.try_clone()?);
+        if reader.read_line(&mut request_line).is_err() {
+            continue;
+        }
+        // Drain the headers; the request has no body worth reading
+        let mut line = String::new();
+        while reader.read_line(&mut line).map(|read| read > 2).unwrap_or(false) {
+            line.clear();
+        }
+
+        let mut parts = request_line.split_whitespace();
+        let response = match (parts.next(), parts.next()) {
+            (Some("GET"), Some("/metrics")) => {
+                let body = metrics.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).render();
+                format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\n\
+                     Connection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                )
+            }
+            _ => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string(),
+        };
+        // A client that hung up early is its own problem
+        let _ = stream.write_all(response.as_bytes());
+    }
+    Ok(())
+}
@@ -0,0 +1,280 @@
+//! Reward pool runway alerts. Each check reads the pool's state, evaluates
+//! every condition against the thresholds, and reports only what changed: an
+//! alert fires when a condition starts failing or its severity changes, and a
+//! recovery is sent when it passes again. A condition that cannot be evaluated
+//! in a check (no price to value the pool in USD, say) keeps its state.
+//!
+//!     let mut monitor = Monitor::new(Thresholds::default());
+//!     for alert in monitor.evaluate(&fetch_pool_state(&rpc, price, now)?) {
+//!         dispatcher.enqueue_to(&endpoint, alert_payload(&alert).to_string());
+//!     }
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use anchor_lang::solana_program::pubkey::Pubkey;
+use ontora_ai::math::epoch_rewards;
+use ontora_client::{fetch_platform_config, fetch_reward_pool, ClientError, RpcPool};
+use serde_json::{json, Value};
+
+use crate::metrics::reward_pool_runway;
+
+/// The pool's state at one check.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PoolState {
+    pub total_staked: u64,
+    pub reward_rate_bps: u64,
+    /// The reward pool's `total_rewards`.
+    pub total_rewards: u64,
+    /// Tokens in the reward vault; `None` before the vault is created.
+    pub vault_balance: Option<u64>,
+    /// Latest price of the feed USD thresholds are valued with.
+    pub price: Option<FeedPrice>,
+    /// Unix time of the check.
+    pub now: i64,
+}
+
+impl PoolState {
+    /// Rewards the current stake earns per epoch at the current rate.
+    pub fn emission_per_epoch(&self) -> u64 {
+        epoch_rewards(self.total_staked, self.reward_rate_bps, 1).unwrap_or(u64::MAX)
+    }
+
+    pub fn runway_epochs(&self) -> f64 {
+        reward_pool_runway(self.total_rewards, self.emission_per_epoch())
+    }
+}
+
+/// A price as the oracle published it, scaled by `UsdThreshold::price_decimals`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FeedPrice {
+    pub price: i64,
+    pub timestamp: i64,
+}
+
+/// A floor on the USD value of the rewards left.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UsdThreshold {
+    pub min_usd: f64,
+    pub price_decimals: u8,
+    /// Decimals of the reward mint.
+    pub token_decimals: u8,
+    /// Age past which the price no longer counts.
+    pub max_feed_age_seconds: i64,
+}
+
+/// When each condition fails.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Thresholds {
+    pub runway_warning_epochs: f64,
+    pub runway_critical_epochs: f64,
+    /// Most the vault balance may differ from `total_rewards`, in base units.
+    pub max_vault_divergence: u64,
+    pub usd: Option<UsdThreshold>,
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Thresholds { runway_warning_epochs: 8.0, runway_critical_epochs: 2.0, max_vault_divergence: 0, usd: None }
+    }
+}
+
+/// What an alert is about.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Condition {
+    LowRunway,
+    VaultDivergence,
+    LowUsdValue,
+    StaleFeed,
+}
+
+impl Condition {
+    pub fn name(self) -> &'static str {
+        match self {
+            Condition::LowRunway => "low_runway",
+            Condition::VaultDivergence => "vault_divergence",
+            Condition::LowUsdValue => "low_usd_value",
+            Condition::StaleFeed => "stale_feed",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Critical,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Warning => "warning",
+            Severity::Critical => "critical",
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AlertStatus {
+    Firing,
+    Resolved,
+}
+
+/// A change in a condition: it fired, changed severity, or recovered. A
+/// recovery carries the severity it recovered from.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Alert {
+    pub condition: Condition,
+    pub status: AlertStatus,
+    pub severity: Severity,
+    pub message: String,
+    pub timestamp: i64,
+}
+
+// The outcome of evaluating one condition: its severity if failing, and what was found
+struct Reading {
+    failing: Option<Severity>,
+    message: String,
+}
+
+// Whole tokens in `amount` base units of a mint with `decimals`
+fn whole(amount: u64, decimals: u8) -> f64 {
+    amount as f64 / 10f64.powi(decimals as i32)
+}
+
+// Every condition `state` lets `thresholds` evaluate, with what was found
+fn readings(state: &PoolState, thresholds: &Thresholds) -> BTreeMap<Condition, Reading> {
+    let mut readings = BTreeMap::new();
+    let runway = state.runway_epochs();
+    let failing = if runway < thresholds.runway_critical_epochs {
+        Some(Severity::Critical)
+    } else if runway < thresholds.runway_warning_epochs {
+        Some(Severity::Warning)
+    } else {
+        None
+    };
+    let message = format!(
+        "reward pool runway is {:.1} epochs: {} rewards left, {} emitted per epoch",
+        runway,
+        state.total_rewards,
+        state.emission_per_epoch()
+    );
+    readings.insert(Condition::LowRunway, Reading { failing, message });
+
+    if let Some(balance) = state.vault_balance {
+        let divergence = balance.abs_diff(state.total_rewards);
+        let failing = (divergence > thresholds.max_vault_divergence).then_some(Severity::Critical);
+        let message = format!("reward vault holds {}, the pool records {}", balance, state.total_rewards);
+        readings.insert(Condition::VaultDivergence, Reading { failing, message });
+    }
+
+    if let Some(usd) = &thresholds.usd {
+        let fresh = state.price.filter(|price| state.now - price.timestamp <= usd.max_feed_age_seconds);
+        let message = match state.price {
+            Some(price) => format!("the price feed last updated {} seconds ago", state.now - price.timestamp),
+            None => "the price feed has no price".to_string(),
+        };
+        let failing = fresh.is_none().then_some(Severity::Warning);
+        readings.insert(Condition::StaleFeed, Reading { failing, message });
+        // Without a fresh price the pool's value is unknown, so its alert stays as it was
+        if let Some(price) = fresh {
+            let value = whole(state.total_rewards, usd.token_decimals) * price.price as f64
+                / 10f64.powi(usd.price_decimals as i32);
+            let failing = (value < usd.min_usd).then_some(Severity::Warning);
+            let message = format!("rewards left are worth ${:.2}, the floor is ${:.2}", value, usd.min_usd);
+            readings.insert(Condition::LowUsdValue, Reading { failing, message });
+        }
+    }
+    readings
+}
+
+/// Tracks which conditions are failing across checks, so each change is
+/// reported once.
+#[derive(Clone, Debug)]
+pub struct Monitor {
+    thresholds: Thresholds,
+    failing: BTreeMap<Condition, Severity>,
+}
+
+impl Monitor {
+    pub fn new(thresholds: Thresholds) -> Self {
+        Monitor { thresholds, failing: BTreeMap::new() }
+    }
+
+    /// Conditions failing as of the last check, with their severity.
+    pub fn failing(&self) -> &BTreeMap<Condition, Severity> {
+        &self.failing
+    }
+
+    /// Evaluates `state` and returns the alerts it changes: conditions that
+    /// started failing or changed severity, and those that recovered.
+    pub fn evaluate(&mut self, state: &PoolState) -> Vec<Alert> {
+        let mut alerts = Vec::new();
+        for (condition, reading) in readings(state, &self.thresholds) {
+            let alert = |status, severity| Alert {
+                condition,
+                status,
+                severity,
+                message: reading.message.clone(),
+                timestamp: state.now,
+            };
+            match (self.failing.get(&condition).copied(), reading.failing) {
+                (previous, Some(severity)) if previous != Some(severity) => {
+                    self.failing.insert(condition, severity);
+                    alerts.push(alert(AlertStatus::Firing, severity));
+                }
+                (Some(previous), None) => {
+                    self.failing.remove(&condition);
+                    alerts.push(alert(AlertStatus::Resolved, previous));
+                }
+                _ => {}
+            }
+        }
+        alerts
+    }
+}
+
+/// The JSON body of a generic alert webhook.
+pub fn alert_payload(alert: &Alert) -> Value {
+    json!({
+        "alert": alert.condition.name(),
+        "status": match alert.status {
+            AlertStatus::Firing => "firing",
+            AlertStatus::Resolved => "resolved",
+        },
+        "severity": alert.severity.to_string(),
+        "message": alert.message,
+        "timestamp": alert.timestamp,
+    })
+}
+
+/// The body of a Slack-compatible incoming webhook.
+pub fn slack_payload(alert: &Alert) -> Value {
+    let text = match alert.status {
+        AlertStatus::Firing => format!("[{}] {}: {}", alert.severity, alert.condition.name(), alert.message),
+        AlertStatus::Resolved => format!("[resolved] {}: {}", alert.condition.name(), alert.message),
+    };
+    json!({ "text": text })
+}
+
+/// Fetches the platform config and reward pool through `rpc`, and the
+/// balance of the reward vault once the admin has created it.
+pub fn fetch_pool_state(rpc: &RpcPool, price: Option<FeedPrice>, now: i64) -> Result<PoolState, ClientError> {
+    let config = fetch_platform_config(rpc)?;
+    let reward_pool = fetch_reward_pool(rpc)?;
+    let vault_balance = match config.reward_vault {
+        vault if vault == Pubkey::default() => None,
+        vault => {
+            let balance = rpc.get_token_account_balance(&vault)?;
+            Some(balance.amount.parse().map_err(|_| ClientError::Decode(vault, "token amount".to_string()))?)
+        }
+    };
+    Ok(PoolState {
+        total_staked: config.total_staked,
+        reward_rate_bps: config.reward_rate_bps,
+        total_rewards: reward_pool.total_rewards,
+        vault_balance,
+        price,
+        now,
+    })
+}
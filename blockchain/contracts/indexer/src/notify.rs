@@ -0,0 +1,327 @@
+//! Webhook notifications for the wallets events concern. Subscriptions are
+//! rows in the indexer database, managed through the API server; the
+//! `Notifier` sink matches each event against the subscriptions of the wallet
+//! it concerns as it streams in, and POSTs the matches once their slot is
+//! final, retrying with backoff and recording the ones given up on.
+//!
+//!     let notifier = Rc::new(RefCell::new(Notifier::connect(url, transport, DeliveryPolicy::default())?));
+//!     let mut indexer = Indexer::new(program_id, None).with_sink(notifier.clone());
+//!     // ingest and settle, then
+//!     notifier.borrow_mut().dispatch(Instant::now())?;
+//!
+//! Deliveries are signed like account-change webhooks (see `webhook`), with
+//! the subscription's own secret.
+
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use anchor_lang::solana_program::pubkey::Pubkey;
+use serde_json::{json, Value};
+use solana_sdk::signature::Signature;
+use sqlx::any::AnyRow;
+use sqlx::{AnyPool, Row};
+use tokio::runtime::Runtime;
+
+use crate::decode::OntoraEvent;
+use crate::error::IndexerError;
+use crate::sink::{EventRecord, EventSink};
+use crate::store;
+use crate::webhook::{DeliveryPolicy, WebhookDispatcher, WebhookEndpoint, WebhookTransport};
+
+/// Events a subscription can ask for. The program emits no slashing event,
+/// and a `PerformanceScoreUpdated` names the agent account rather than its
+/// owner, so neither can be matched to a wallet.
+pub const NOTIFICATION_EVENTS: [&str; 4] = ["RewardClaimed", "StakeDeposited", "StakeWithdrawn", "ProposalFinalized"];
+
+/// One notification rule.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Subscription {
+    pub id: String,
+    pub wallet: Pubkey,
+    /// Names from `NOTIFICATION_EVENTS`.
+    pub event_types: Vec<String>,
+    /// Smallest amount that notifies, for events that carry an amount.
+    pub min_amount: Option<u64>,
+    pub url: String,
+    pub secret: String,
+    pub created_at: i64,
+}
+
+impl Subscription {
+    /// Whether an event named `event` with `amount` (if it carries one)
+    /// notifies this subscription. Its wallet is matched beforehand.
+    pub fn matches(&self, event: &str, amount: Option<u64>) -> bool {
+        let threshold = match (self.min_amount, amount) {
+            (Some(min_amount), Some(amount)) => amount >= min_amount,
+            _ => true,
+        };
+        threshold && self.event_types.iter().any(|name| name == event)
+    }
+}
+
+// Columns are BIGINT on both backends; larger values are clamped
+fn int(value: u64) -> i64 {
+    i64::try_from(value).unwrap_or(i64::MAX)
+}
+
+fn subscription(row: &AnyRow) -> Result<Subscription, sqlx::Error> {
+    let wallet: String = row.try_get("wallet")?;
+    let event_types: String = row.try_get("event_types")?;
+    let min_amount: Option<i64> = row.try_get("min_amount")?;
+    Ok(Subscription {
+        id: row.try_get("id")?,
+        wallet: Pubkey::from_str(&wallet).map_err(|error| sqlx::Error::Decode(Box::new(error)))?,
+        event_types: event_types.split(',').filter(|name| !name.is_empty()).map(str::to_string).collect(),
+        min_amount: min_amount.map(|amount| amount.max(0) as u64),
+        url: row.try_get("url")?,
+        secret: row.try_get("secret")?,
+        created_at: row.try_get("created_at")?,
+    })
+}
+
+const SUBSCRIPTION_COLUMNS: &str = "id, wallet, event_types, min_amount, url, secret, created_at";
+
+pub async fn insert_subscription(pool: &AnyPool, subscription: &Subscription) -> Result<(), sqlx::Error> {
+    sqlx::query(&format!(
+        "INSERT INTO subscriptions ({SUBSCRIPTION_COLUMNS}) VALUES ($1, $2, $3, $4, $5, $6, $7)"
+    ))
+    .bind(subscription.id.clone())
+    .bind(subscription.wallet.to_string())
+    .bind(subscription.event_types.join(","))
+    .bind(subscription.min_amount.map(int))
+    .bind(subscription.url.clone())
+    .bind(subscription.secret.clone())
+    .bind(subscription.created_at)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Replaces the rule of the subscription with `subscription.id`; its wallet,
+/// secret and creation time stay. Returns whether it exists.
+pub async fn update_subscription(pool: &AnyPool, subscription: &Subscription) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("UPDATE subscriptions SET event_types = $1, min_amount = $2, url = $3 WHERE id = $4")
+        .bind(subscription.event_types.join(","))
+        .bind(subscription.min_amount.map(int))
+        .bind(subscription.url.clone())
+        .bind(subscription.id.clone())
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Returns whether the subscription existed.
+pub async fn delete_subscription(pool: &AnyPool, id: &str) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM subscriptions WHERE id = $1").bind(id).execute(pool).await?;
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn get_subscription(pool: &AnyPool, id: &str) -> Result<Option<Subscription>, sqlx::Error> {
+    let sql = format!("SELECT {SUBSCRIPTION_COLUMNS} FROM subscriptions WHERE id = $1");
+    let row = sqlx::query(&sql).bind(id).fetch_optional(pool).await?;
+    row.as_ref().map(subscription).transpose()
+}
+
+/// Up to `limit` subscriptions with an id after `after`, in id order, of
+/// `wallet` or of every wallet.
+pub async fn list_subscriptions(
+    pool: &AnyPool,
+    wallet: Option<&Pubkey>,
+    after: Option<&str>,
+    limit: i64,
+) -> Result<Vec<Subscription>, sqlx::Error> {
+    let wallet_condition = if wallet.is_some() { "wallet = $3" } else { "$3 = ''" };
+    let sql = format!(
+        "SELECT {SUBSCRIPTION_COLUMNS} FROM subscriptions WHERE id > $1 AND {wallet_condition} ORDER BY id LIMIT $2"
+    );
+    let rows = sqlx::query(&sql)
+        .bind(after.unwrap_or_default())
+        .bind(limit)
+        .bind(wallet.map(Pubkey::to_string).unwrap_or_default())
+        .fetch_all(pool)
+        .await?;
+    rows.iter().map(subscription).collect()
+}
+
+/// Dead letters of `subscription_id`, oldest first.
+pub async fn list_dead_letters(pool: &AnyPool, subscription_id: &str) -> Result<Vec<Value>, sqlx::Error> {
+    let rows = sqlx::query(
+        "SELECT id, url, body, reason, failed_at FROM dead_letters WHERE subscription_id = $1 ORDER BY failed_at, id",
+    )
+    .bind(subscription_id)
+    .fetch_all(pool)
+    .await?;
+    rows.iter()
+        .map(|row| {
+            let body: String = row.try_get("body")?;
+            Ok(json!({
+                "id": row.try_get::<String, _>("id")?,
+                "url": row.try_get::<String, _>("url")?,
+                "body": serde_json::from_str::<Value>(&body).unwrap_or(Value::String(body)),
+                "reason": row.try_get::<String, _>("reason")?,
+                "failedAt": row.try_get::<i64, _>("failed_at")?,
+            }))
+        })
+        .collect()
+}
+
+fn unix_time() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs() as i64)
+}
+
+/// The JSON body delivered to `subscription` for `record`. `id` is the same
+/// on every attempt, so receivers can drop duplicates.
+pub fn notification(subscription: &Subscription, record: &EventRecord) -> Value {
+    let details = match &record.event {
+        OntoraEvent::RewardClaimed(event) => json!({ "agent_id": event.agent_id, "amount": event.reward_amount }),
+        OntoraEvent::StakeDeposited(event) => json!({ "agent_id": event.agent_id, "amount": event.amount }),
+        OntoraEvent::StakeWithdrawn(event) => json!({ "agent_id": event.agent_id, "amount": event.amount }),
+        OntoraEvent::ProposalFinalized(event) => json!({
+            "proposal_id": event.proposal_id,
+            "result": event.result,
+            "vote_summary": event.vote_summary,
+            "reason": event.reason,
+        }),
+        _ => Value::Null,
+    };
+    json!({
+        "id": format!("{}-{}-{}", record.signature, record.index, subscription.id),
+        "subscription": subscription.id,
+        "wallet": subscription.wallet.to_string(),
+        "event": record.event.name(),
+        "slot": record.slot,
+        "signature": record.signature.to_string(),
+        "details": details,
+    })
+}
+
+/// Matches events against the subscriptions in the indexer database and
+/// delivers the notifications.
+///
+/// Matches are held until their slot is final, so a retracted event never
+/// notifies. A failed database read drops the event's notifications.
+pub struct Notifier<T> {
+    runtime: Runtime,
+    pool: AnyPool,
+    dispatcher: WebhookDispatcher<T>,
+    // Matched notifications of records that are not final yet, by slot
+    pending: BTreeMap<u64, Vec<(Signature, usize, WebhookEndpoint, String)>>,
+}
+
+impl<T: WebhookTransport> Notifier<T> {
+    /// Connects to the indexer database at `url` (`sqlite:` or `postgres:`).
+    pub fn connect(url: &str, transport: T, policy: DeliveryPolicy) -> Result<Self, IndexerError> {
+        let (runtime, pool) = store::open(url)?;
+        let dispatcher = WebhookDispatcher::new(transport, Vec::new(), policy);
+        Ok(Notifier { runtime, pool, dispatcher, pending: BTreeMap::new() })
+    }
+
+    pub fn subscribe(&self, subscription: &Subscription) -> Result<(), IndexerError> {
+        Ok(self.runtime.block_on(insert_subscription(&self.pool, subscription))?)
+    }
+
+    /// Notifications matched but not final yet, and final but not delivered.
+    pub fn pending(&self) -> usize {
+        self.pending.values().map(Vec::len).sum::<usize>() + self.dispatcher.pending()
+    }
+
+    pub fn next_due(&self) -> Option<Instant> {
+        self.dispatcher.next_due()
+    }
+
+    /// Delivers every notification due at `now`, and records the ones given
+    /// up on in `dead_letters`. Returns how many were accepted.
+    pub fn dispatch(&mut self, now: Instant) -> Result<usize, IndexerError> {
+        let accepted = self.dispatcher.dispatch(now);
+        for dead in self.dispatcher.take_dead_letters() {
+            let body: Value = serde_json::from_str(&dead.body).unwrap_or_default();
+            let query = sqlx::query(
+                "INSERT INTO dead_letters (id, subscription_id, url, body, reason, failed_at) \
+                 VALUES ($1, $2, $3, $4, $5, $6) ON CONFLICT (id) DO NOTHING",
+            )
+            .bind(body["id"].as_str().unwrap_or_default().to_string())
+            .bind(body["subscription"].as_str().unwrap_or_default().to_string())
+            .bind(dead.url)
+            .bind(dead.body)
+            .bind(dead.reason)
+            .bind(unix_time());
+            self.runtime.block_on(query.execute(&self.pool))?;
+        }
+        Ok(accepted)
+    }
+
+    pub fn dead_letters(&self, subscription_id: &str) -> Result<Vec<Value>, IndexerError> {
+        Ok(self.runtime.block_on(list_dead_letters(&self.pool, subscription_id))?)
+    }
+
+    // The wallet `event` concerns, and the amount it carries
+    fn subject(&self, event: &OntoraEvent) -> Result<Option<(Pubkey, Option<u64>)>, IndexerError> {
+        let subject = match event {
+            OntoraEvent::RewardClaimed(event) => (event.user, Some(event.reward_amount)),
+            OntoraEvent::StakeDeposited(event) => (event.user, Some(event.amount)),
+            OntoraEvent::StakeWithdrawn(event) => (event.user, Some(event.amount)),
+            OntoraEvent::ProposalFinalized(event) => {
+                // The creator is only on the proposal's creation event
+                let query = sqlx::query_scalar(
+                    "SELECT creator FROM proposals WHERE proposal_id = $1 AND kind = 'created' LIMIT 1",
+                )
+                .bind(int(event.proposal_id));
+                let creator: Option<Option<String>> = self.runtime.block_on(query.fetch_optional(&self.pool))?;
+                match creator.flatten().map(|creator| Pubkey::from_str(&creator)) {
+                    Some(Ok(creator)) => (creator, None),
+                    _ => return Ok(None),
+                }
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(subject))
+    }
+
+    fn matches(&self, record: &EventRecord) -> Result<Vec<Subscription>, IndexerError> {
+        let Some((wallet, amount)) = self.subject(&record.event)? else {
+            return Ok(Vec::new());
+        };
+        let sql = format!("SELECT {SUBSCRIPTION_COLUMNS} FROM subscriptions WHERE wallet = $1");
+        let rows = self.runtime.block_on(sqlx::query(&sql).bind(wallet.to_string()).fetch_all(&self.pool))?;
+        let mut subscriptions = Vec::new();
+        for row in &rows {
+            let subscription = subscription(row)?;
+            if subscription.matches(record.event.name(), amount) {
+                subscriptions.push(subscription);
+            }
+        }
+        Ok(subscriptions)
+    }
+}
+
+impl<T: WebhookTransport> EventSink for Notifier<T> {
+    fn apply(&mut self, record: &EventRecord) {
+        let subscriptions = match self.matches(record) {
+            Ok(subscriptions) => subscriptions,
+            Err(error) => {
+                eprintln!("no notifications for {}#{}: {}", record.signature, record.index, error);
+                return;
+            }
+        };
+        for subscription in subscriptions {
+            let secret = subscription.secret.as_bytes().to_vec();
+            let endpoint = WebhookEndpoint { url: subscription.url.clone(), secret };
+            let body = notification(&subscription, record).to_string();
+            self.pending.entry(record.slot).or_default().push((record.signature, record.index, endpoint, body));
+        }
+    }
+
+    fn retract(&mut self, record: &EventRecord) {
+        if let Some(pending) = self.pending.get_mut(&record.slot) {
+            pending.retain(|(signature, index, _, _)| (signature, *index) != (&record.signature, record.index));
+        }
+    }
+
+    fn finalize(&mut self, slot: u64) {
+        let unsettled = self.pending.split_off(&(slot + 1));
+        for (_, _, endpoint, body) in std::mem::replace(&mut self.pending, unsettled).into_values().flatten() {
+            self.dispatcher.enqueue_to(&endpoint, body);
+        }
+    }
+}
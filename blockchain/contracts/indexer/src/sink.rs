@@ -0,0 +1,175 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use anchor_lang::solana_program::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+
+use crate::decode::OntoraEvent;
+
+/// One decoded event and where it was emitted.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EventRecord {
+    pub slot: u64,
+    pub signature: Signature,
+    /// Position of the event among those the transaction emitted.
+    pub index: usize,
+    pub event: OntoraEvent,
+}
+
+/// Destination of indexed events.
+///
+/// Events arrive at `confirmed` commitment and are applied right away. When
+/// their slot later turns out not to be on the finalized chain, every record
+/// applied from it is retracted, newest first. Records from a slot that is
+/// finalized are never retracted.
+pub trait EventSink {
+    fn apply(&mut self, record: &EventRecord);
+    fn retract(&mut self, record: &EventRecord);
+    /// Every record at or below `slot` is now final.
+    fn finalize(&mut self, _slot: u64) {}
+}
+
+impl<S: EventSink> EventSink for Rc<RefCell<S>> {
+    fn apply(&mut self, record: &EventRecord) {
+        self.borrow_mut().apply(record);
+    }
+
+    fn retract(&mut self, record: &EventRecord) {
+        self.borrow_mut().retract(record);
+    }
+
+    fn finalize(&mut self, slot: u64) {
+        self.borrow_mut().finalize(slot);
+    }
+}
+
+/// Prints one line per applied or retracted event.
+#[derive(Default)]
+pub struct StdoutSink;
+
+impl EventSink for StdoutSink {
+    fn apply(&mut self, record: &EventRecord) {
+        println!("+ {} {}#{} {:?}", record.slot, record.signature, record.index, record.event);
+    }
+
+    fn retract(&mut self, record: &EventRecord) {
+        println!("- {} {}#{} {}", record.slot, record.signature, record.index, record.event.name());
+    }
+
+    fn finalize(&mut self, slot: u64) {
+        println!("= finalized through slot {slot}");
+    }
+}
+
+/// Running totals over the indexed events.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Aggregates {
+    /// Number of events seen, by event name.
+    pub event_counts: BTreeMap<&'static str, u64>,
+    /// Metadata of each registered agent, by (owner, agent id).
+    pub agents: BTreeMap<(Pubkey, u64), String>,
+    /// Latest performance score, by agent account.
+    pub performance_scores: BTreeMap<Pubkey, u64>,
+    /// Amount currently staked, by agent id.
+    pub stake_by_agent: BTreeMap<u64, u64>,
+    /// Amount currently staked, by user.
+    pub stake_by_user: BTreeMap<Pubkey, u64>,
+    /// Rewards claimed, by user.
+    pub rewards_claimed: BTreeMap<Pubkey, u64>,
+    /// Vote weight cast, by (proposal id, option).
+    pub votes: BTreeMap<(u64, u8), u64>,
+    /// Result of each finalized proposal, by proposal id.
+    pub proposal_results: BTreeMap<u64, u8>,
+    /// Treasury deposits, by (mint, `FeeSource::index`).
+    pub treasury_deposits: BTreeMap<(Pubkey, usize), u64>,
+    /// Treasury withdrawals, by mint.
+    pub treasury_withdrawals: BTreeMap<Pubkey, u64>,
+}
+
+impl Aggregates {
+    fn add(&mut self, event: &OntoraEvent) {
+        *self.event_counts.entry(event.name()).or_default() += 1;
+        match event {
+            OntoraEvent::AgentRegistered(event) => {
+                self.agents.insert((event.owner, event.agent_id), event.metadata.clone());
+            }
+            OntoraEvent::AgentUpdated(event) => {
+                self.agents.insert((event.owner, event.agent_id), event.new_metadata.clone());
+            }
+            OntoraEvent::PerformanceScoreUpdated(event) => {
+                self.performance_scores.insert(event.agent, event.new_score);
+            }
+            OntoraEvent::StakeDeposited(event) => {
+                *self.stake_by_agent.entry(event.agent_id).or_default() += event.amount;
+                *self.stake_by_user.entry(event.user).or_default() += event.amount;
+            }
+            OntoraEvent::StakeWithdrawn(event) => {
+                let agent = self.stake_by_agent.entry(event.agent_id).or_default();
+                *agent = agent.saturating_sub(event.amount);
+                let user = self.stake_by_user.entry(event.user).or_default();
+                *user = user.saturating_sub(event.amount);
+            }
+            OntoraEvent::RewardClaimed(event) => {
+                *self.rewards_claimed.entry(event.user).or_default() += event.reward_amount;
+            }
+            OntoraEvent::VoteCast(event) => {
+                *self.votes.entry((event.proposal_id, event.vote_option)).or_default() += event.vote_weight;
+            }
+            OntoraEvent::ProposalFinalized(event) => {
+                self.proposal_results.insert(event.proposal_id, event.result);
+            }
+            OntoraEvent::TreasuryDeposit(event) => {
+                *self.treasury_deposits.entry((event.mint, event.source.index())).or_default() += event.amount;
+            }
+            OntoraEvent::TreasuryWithdrawal(event) => {
+                *self.treasury_withdrawals.entry(event.mint).or_default() += event.amount;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Keeps every applied record in memory and aggregates over them.
+///
+/// Several aggregates keep only the latest value, which cannot be undone from
+/// the retracted record alone, so a retraction drops the record and rebuilds
+/// the aggregates from the ones that remain.
+#[derive(Default)]
+pub struct MemorySink {
+    records: Vec<EventRecord>,
+    aggregates: Aggregates,
+}
+
+impl MemorySink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn records(&self) -> &[EventRecord] {
+        &self.records
+    }
+
+    pub fn aggregates(&self) -> &Aggregates {
+        &self.aggregates
+    }
+}
+
+impl EventSink for MemorySink {
+    fn apply(&mut self, record: &EventRecord) {
+        self.aggregates.add(&record.event);
+        self.records.push(record.clone());
+    }
+
+    fn retract(&mut self, record: &EventRecord) {
+        let before = self.records.len();
+        self.records.retain(|kept| (kept.slot, kept.signature, kept.index) != (record.slot, record.signature, record.index));
+        if self.records.len() == before {
+            return;
+        }
+        self.aggregates = Aggregates::default();
+        for kept in &self.records {
+            self.aggregates.add(&kept.event);
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use std::str::FromStr;
+
+use anchor_lang::solana_program::pubkey::Pubkey;
+use solana_client::rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient};
+use solana_client::rpc_config::RpcTransactionConfig;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::signature::Signature;
+use solana_transaction_status::UiTransactionEncoding;
+
+use crate::error::IndexerError;
+use crate::indexer::Indexer;
+
+// Largest page getSignaturesForAddress returns
+const SIGNATURE_PAGE: usize = 1_000;
+
+/// A successful transaction that mentions the program, with its logs.
+#[derive(Clone, Debug)]
+pub struct LoggedTransaction {
+    pub slot: u64,
+    pub signature: Signature,
+    pub logs: Vec<String>,
+}
+
+/// Every successful transaction mentioning `program_id` after `until`, oldest
+/// first, at `confirmed` commitment. `None` backfills the program's whole history.
+pub fn backfill(
+    rpc: &RpcClient,
+    program_id: &Pubkey,
+    until: Option<Signature>,
+) -> Result<Vec<LoggedTransaction>, IndexerError> {
+    let mut statuses = Vec::new();
+    let mut before = None;
+    loop {
+        let config = GetConfirmedSignaturesForAddress2Config {
+            before,
+            until,
+            limit: Some(SIGNATURE_PAGE),
+            commitment: Some(CommitmentConfig::confirmed()),
+        };
+        let page = rpc.get_signatures_for_address_with_config(program_id, config)?;
+        let last_page = page.len() < SIGNATURE_PAGE;
+        before = page.last().map(|status| Signature::from_str(&status.signature)).transpose()?;
+        statuses.extend(page);
+        if last_page || before.is_none() {
+            break;
+        }
+    }
+
+    let config = RpcTransactionConfig {
+        encoding: Some(UiTransactionEncoding::Json),
+        commitment: Some(CommitmentConfig::confirmed()),
+        max_supported_transaction_version: Some(0),
+    };
+    let mut transactions = Vec::new();
+    for status in statuses.into_iter().rev().filter(|status| status.err.is_none()) {
+        let signature = Signature::from_str(&status.signature)?;
+        let transaction = rpc.get_transaction_with_config(&signature, config)?;
+        let logs = transaction
+            .transaction
+            .meta
+            .and_then(|meta| Option::<Vec<String>>::from(meta.log_messages))
+            .unwrap_or_default();
+        transactions.push(LoggedTransaction { slot: transaction.slot, signature, logs });
+    }
+    Ok(transactions)
+}
+
+/// Settles the indexer's pending slots against the cluster's finalized chain.
+pub fn settle(rpc: &RpcClient, indexer: &mut Indexer) -> Result<(), IndexerError> {
+    let Some(lowest) = indexer.lowest_pending_slot() else {
+        return Ok(());
+    };
+    let finalized_slot = rpc.get_slot_with_commitment(CommitmentConfig::finalized())?;
+    if lowest > finalized_slot {
+        return Ok(());
+    }
+    let blocks = rpc.get_blocks_with_commitment(lowest, Some(finalized_slot), CommitmentConfig::finalized())?;
+    indexer.settle(finalized_slot, &blocks);
+    Ok(())
+}
@@ -0,0 +1,339 @@
+//! Persistent event storage. `SqlSink` writes each indexed event to a
+//! database table, one row per event keyed by (signature, event index), so a
+//! replayed batch is written once. It runs the same queries on SQLite and
+//! Postgres; the URL picks the backend.
+//!
+//!     let sink = SqlSink::connect("sqlite://indexer.db?mode=rwc")?;
+//!     let cursor = sink.cursor()?;
+//!     let mut indexer = Indexer::new(program_id, cursor).with_sink(sink);
+//!
+//! The schema lives in `migrations/` and is applied on connect.
+
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use anchor_lang::solana_program::pubkey::Pubkey;
+use ontora_ai::state::FeeSource;
+use solana_sdk::signature::Signature;
+use sqlx::any::{AnyArguments, AnyPoolOptions};
+use sqlx::migrate::Migrator;
+use sqlx::query::Query;
+use sqlx::{Any, AnyPool, Row};
+use tokio::runtime::Runtime;
+
+use crate::decode::OntoraEvent;
+use crate::error::IndexerError;
+use crate::indexer::Cursor;
+use crate::sink::{EventRecord, EventSink};
+
+/// The schema in `migrations/`, for services that read the database and may
+/// start before the indexer has created it.
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+/// Tables `SqlSink` writes events to.
+pub const EVENT_TABLES: [&str; 8] = [
+    "stakes",
+    "claims",
+    "proposals",
+    "votes",
+    "agents",
+    "price_updates",
+    "reward_distributions",
+    "treasury_deposits",
+];
+
+// The one row of `indexer_cursor`
+const CURSOR_ROW: i64 = 1;
+
+/// A price the oracle program published, under the symbol its feed is
+/// registered for.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PriceUpdate {
+    pub slot: u64,
+    pub signature: Signature,
+    /// Position of the event among those the transaction emitted.
+    pub index: usize,
+    pub symbol: String,
+    pub feed: Pubkey,
+    pub price: i64,
+    pub confidence: u64,
+    pub round: u64,
+    pub timestamp: i64,
+}
+
+// Columns are BIGINT on both backends; larger values are clamped
+fn int(value: u64) -> i64 {
+    i64::try_from(value).unwrap_or(i64::MAX)
+}
+
+/// Connects a synchronous sink to `url` (`sqlite:` or `postgres:`): a runtime
+/// to block on and a one-connection pool, with the schema brought up to date.
+pub(crate) fn open(url: &str) -> Result<(Runtime, AnyPool), IndexerError> {
+    sqlx::any::install_default_drivers();
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(sqlx::Error::Io)?;
+    // Writes are sequential, and every connection to `sqlite::memory:`
+    // opens a database of its own, so one connection is kept for good
+    let options = AnyPoolOptions::new().max_connections(1).idle_timeout(None).max_lifetime(None);
+    let pool = runtime.block_on(options.connect(url))?;
+    runtime.block_on(MIGRATOR.run(&pool))?;
+    Ok((runtime, pool))
+}
+
+/// Writes indexed events to SQLite or Postgres.
+///
+/// `EventSink` cannot fail, so a failed write panics. The stored cursor only
+/// moves past records once they are final and written, so a restarted indexer
+/// resuming from it replays whatever was lost.
+pub struct SqlSink {
+    runtime: Runtime,
+    pool: AnyPool,
+    // Last transaction applied in each slot that is not final yet
+    pending: BTreeMap<u64, Signature>,
+}
+
+impl SqlSink {
+    /// Connects to `url` (`sqlite:` or `postgres:`) and brings its schema up to
+    /// date.
+    pub fn connect(url: &str) -> Result<Self, IndexerError> {
+        let (runtime, pool) = open(url)?;
+        Ok(SqlSink { runtime, pool, pending: BTreeMap::new() })
+    }
+
+    /// The stored resume point; `None` before any record was finalized.
+    pub fn cursor(&self) -> Result<Option<Cursor>, IndexerError> {
+        let query = sqlx::query("SELECT slot, signature FROM indexer_cursor WHERE id = $1").bind(CURSOR_ROW);
+        let Some(row) = self.runtime.block_on(query.fetch_optional(&self.pool))? else {
+            return Ok(None);
+        };
+        let slot: i64 = row.try_get("slot")?;
+        let signature: String = row.try_get("signature")?;
+        Ok(Some(Cursor { slot: slot as u64, signature: Signature::from_str(&signature)? }))
+    }
+
+    /// Number of rows in each of `EVENT_TABLES`.
+    pub fn row_counts(&self) -> Result<BTreeMap<&'static str, i64>, IndexerError> {
+        let mut counts = BTreeMap::new();
+        for table in EVENT_TABLES {
+            let query = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {table}"));
+            counts.insert(table, self.runtime.block_on(query.fetch_one(&self.pool))?);
+        }
+        Ok(counts)
+    }
+
+    /// Records a price from the oracle program. Like events, recording one
+    /// twice writes it once.
+    pub fn record_price(&mut self, update: &PriceUpdate) -> Result<(), IndexerError> {
+        let query = sqlx::query(
+            "INSERT INTO price_updates \
+             (signature, event_index, slot, symbol, feed, price, confidence, round, timestamp) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) ON CONFLICT (signature, event_index) DO NOTHING",
+        )
+        .bind(update.signature.to_string())
+        .bind(update.index as i64)
+        .bind(int(update.slot))
+        .bind(update.symbol.clone())
+        .bind(update.feed.to_string())
+        .bind(update.price)
+        .bind(int(update.confidence))
+        .bind(int(update.round))
+        .bind(update.timestamp);
+        self.runtime.block_on(query.execute(&self.pool))?;
+        Ok(())
+    }
+
+    /// The latest price recorded for `symbol`.
+    pub fn latest_price(&self, symbol: &str) -> Result<Option<PriceUpdate>, IndexerError> {
+        let query = sqlx::query(
+            "SELECT signature, event_index, slot, symbol, feed, price, confidence, round, timestamp \
+             FROM price_updates WHERE symbol = $1 ORDER BY slot DESC, event_index DESC LIMIT 1",
+        )
+        .bind(symbol);
+        let Some(row) = self.runtime.block_on(query.fetch_optional(&self.pool))? else {
+            return Ok(None);
+        };
+        let signature: String = row.try_get("signature")?;
+        let feed: String = row.try_get("feed")?;
+        let index: i64 = row.try_get("event_index")?;
+        let slot: i64 = row.try_get("slot")?;
+        let confidence: i64 = row.try_get("confidence")?;
+        let round: i64 = row.try_get("round")?;
+        Ok(Some(PriceUpdate {
+            slot: slot as u64,
+            signature: Signature::from_str(&signature)?,
+            index: index as usize,
+            symbol: row.try_get("symbol")?,
+            feed: Pubkey::from_str(&feed).map_err(|error| sqlx::Error::Decode(Box::new(error)))?,
+            price: row.try_get("price")?,
+            confidence: confidence as u64,
+            round: round as u64,
+            timestamp: row.try_get("timestamp")?,
+        }))
+    }
+
+    fn execute(&self, query: Query<'_, Any, AnyArguments<'_>>) {
+        if let Err(error) = self.runtime.block_on(query.execute(&self.pool)) {
+            panic!("writing to the event database failed: {error}");
+        }
+    }
+}
+
+// The table `event` is stored in; `None` for events that are not stored
+fn table(event: &OntoraEvent) -> Option<&'static str> {
+    match event {
+        OntoraEvent::StakeDeposited(_) | OntoraEvent::StakeWithdrawn(_) => Some("stakes"),
+        OntoraEvent::RewardClaimed(_) => Some("claims"),
+        OntoraEvent::ProposalCreated(_) | OntoraEvent::ProposalFinalized(_) => Some("proposals"),
+        OntoraEvent::VoteCast(_) => Some("votes"),
+        OntoraEvent::AgentRegistered(_) | OntoraEvent::AgentUpdated(_) => Some("agents"),
+        OntoraEvent::RewardDistributed(_) => Some("reward_distributions"),
+        OntoraEvent::TreasuryDeposit(_) => Some("treasury_deposits"),
+        _ => None,
+    }
+}
+
+// The upsert writing `event`; its key and slot are the first three parameters
+fn insert_sql(event: &OntoraEvent) -> Option<String> {
+    let (columns, values) = match event {
+        OntoraEvent::StakeDeposited(_) | OntoraEvent::StakeWithdrawn(_) => {
+            ("kind, user_pubkey, agent_id, amount, staking_duration, timestamp", "$4, $5, $6, $7, $8, $9")
+        }
+        OntoraEvent::RewardClaimed(_) => ("user_pubkey, agent_id, amount, timestamp", "$4, $5, $6, $7"),
+        OntoraEvent::ProposalCreated(_) => {
+            ("kind, proposal_id, creator, title, voting_duration, timestamp", "$4, $5, $6, $7, $8, $9")
+        }
+        OntoraEvent::ProposalFinalized(_) => {
+            ("kind, proposal_id, result, vote_summary, timestamp", "$4, $5, $6, $7, $8")
+        }
+        OntoraEvent::VoteCast(_) => {
+            ("proposal_id, voter, vote_option, vote_weight, timestamp", "$4, $5, $6, $7, $8")
+        }
+        OntoraEvent::AgentRegistered(_) | OntoraEvent::AgentUpdated(_) => {
+            ("kind, agent_id, owner, metadata, timestamp", "$4, $5, $6, $7, $8")
+        }
+        OntoraEvent::RewardDistributed(_) => {
+            ("authority, total_amount, eligible_count, timestamp", "$4, $5, $6, $7")
+        }
+        OntoraEvent::TreasuryDeposit(_) => ("source, mint, amount, timestamp", "$4, $5, $6, $7"),
+        _ => return None,
+    };
+    Some(format!(
+        "INSERT INTO {} (signature, event_index, slot, {columns}) VALUES ($1, $2, $3, {values}) \
+         ON CONFLICT (signature, event_index) DO NOTHING",
+        table(event)?
+    ))
+}
+
+fn fee_source(source: FeeSource) -> &'static str {
+    match source {
+        FeeSource::Unstake => "unstake",
+        FeeSource::Claim => "claim",
+        FeeSource::Subscription => "subscription",
+    }
+}
+
+// Binds `record` to the query `insert_sql` made for its event
+fn bind_record<'q>(query: Query<'q, Any, AnyArguments<'q>>, record: &EventRecord) -> Query<'q, Any, AnyArguments<'q>> {
+    let query = query.bind(record.signature.to_string()).bind(record.index as i64).bind(int(record.slot));
+    match &record.event {
+        OntoraEvent::StakeDeposited(event) => query
+            .bind("deposit")
+            .bind(event.user.to_string())
+            .bind(int(event.agent_id))
+            .bind(int(event.amount))
+            .bind(Some(int(event.staking_duration)))
+            .bind(event.timestamp),
+        OntoraEvent::StakeWithdrawn(event) => query
+            .bind("withdrawal")
+            .bind(event.user.to_string())
+            .bind(int(event.agent_id))
+            .bind(int(event.amount))
+            .bind(None::<i64>)
+            .bind(event.timestamp),
+        OntoraEvent::RewardClaimed(event) => query
+            .bind(event.user.to_string())
+            .bind(int(event.agent_id))
+            .bind(int(event.reward_amount))
+            .bind(event.timestamp),
+        OntoraEvent::ProposalCreated(event) => query
+            .bind("created")
+            .bind(int(event.proposal_id))
+            .bind(event.creator.to_string())
+            .bind(event.title.clone())
+            .bind(int(event.voting_duration))
+            .bind(event.timestamp),
+        OntoraEvent::ProposalFinalized(event) => query
+            .bind("finalized")
+            .bind(int(event.proposal_id))
+            .bind(event.result as i64)
+            .bind(event.vote_summary.clone())
+            .bind(event.timestamp),
+        OntoraEvent::VoteCast(event) => query
+            .bind(int(event.proposal_id))
+            .bind(event.voter.to_string())
+            .bind(event.vote_option as i64)
+            .bind(int(event.vote_weight))
+            .bind(event.timestamp),
+        OntoraEvent::AgentRegistered(event) => query
+            .bind("registered")
+            .bind(int(event.agent_id))
+            .bind(event.owner.to_string())
+            .bind(event.metadata.clone())
+            .bind(event.timestamp),
+        OntoraEvent::AgentUpdated(event) => query
+            .bind("updated")
+            .bind(int(event.agent_id))
+            .bind(event.owner.to_string())
+            .bind(event.new_metadata.clone())
+            .bind(event.timestamp),
+        OntoraEvent::RewardDistributed(event) => query
+            .bind(event.authority.to_string())
+            .bind(int(event.total_amount))
+            .bind(int(event.eligible_count))
+            .bind(event.timestamp),
+        OntoraEvent::TreasuryDeposit(event) => query
+            .bind(fee_source(event.source))
+            .bind(event.mint.to_string())
+            .bind(int(event.amount))
+            .bind(event.timestamp),
+        _ => query,
+    }
+}
+
+impl EventSink for SqlSink {
+    fn apply(&mut self, record: &EventRecord) {
+        if let Some(sql) = insert_sql(&record.event) {
+            self.execute(bind_record(sqlx::query(&sql), record));
+            self.pending.insert(record.slot, record.signature);
+        }
+    }
+
+    fn retract(&mut self, record: &EventRecord) {
+        let Some(table) = table(&record.event) else {
+            return;
+        };
+        let sql = format!("DELETE FROM {table} WHERE signature = $1 AND event_index = $2");
+        self.execute(sqlx::query(&sql).bind(record.signature.to_string()).bind(record.index as i64));
+        // Records are retracted a whole slot at a time
+        self.pending.remove(&record.slot);
+    }
+
+    fn finalize(&mut self, slot: u64) {
+        let unsettled = self.pending.split_off(&(slot + 1));
+        let settled = std::mem::replace(&mut self.pending, unsettled);
+        let Some((&cursor_slot, signature)) = settled.iter().next_back() else {
+            return;
+        };
+        self.execute(
+            sqlx::query(
+                "INSERT INTO indexer_cursor (id, slot, signature) VALUES ($1, $2, $3) \
+                 ON CONFLICT (id) DO UPDATE SET slot = excluded.slot, signature = excluded.signature",
+            )
+            .bind(CURSOR_ROW)
+            .bind(int(cursor_slot))
+            .bind(signature.to_string()),
+        );
+    }
+}
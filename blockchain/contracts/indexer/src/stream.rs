@@ -0,0 +1,189 @@
+//! Account-change streams: the program's accounts as they are written, from
+//! an RPC `programSubscribe` or, with the `geyser` feature, a Yellowstone
+//! gRPC endpoint.
+
+use std::str::FromStr;
+use std::sync::mpsc::{self, Receiver};
+
+use anchor_lang::solana_program::pubkey::Pubkey;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::pubsub_client::PubsubClient;
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_response::{Response, RpcKeyedAccount};
+use solana_sdk::account::Account;
+use solana_sdk::commitment_config::CommitmentConfig;
+
+use crate::error::IndexerError;
+
+/// An account's state as of `slot`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AccountUpdate {
+    pub slot: u64,
+    pub address: Pubkey,
+    pub lamports: u64,
+    pub owner: Pubkey,
+    pub data: Vec<u8>,
+}
+
+impl AccountUpdate {
+    pub fn new(slot: u64, address: Pubkey, account: Account) -> Self {
+        AccountUpdate { slot, address, lamports: account.lamports, owner: account.owner, data: account.data }
+    }
+}
+
+fn account_config() -> RpcAccountInfoConfig {
+    RpcAccountInfoConfig {
+        encoding: Some(UiAccountEncoding::Base64),
+        commitment: Some(CommitmentConfig::confirmed()),
+        ..RpcAccountInfoConfig::default()
+    }
+}
+
+/// Every account `program_id` owns, at `confirmed` commitment. They are
+/// stamped with the slot read before fetching them, so no later update is
+/// mistaken for stale.
+pub fn fetch_program_accounts(rpc: &RpcClient, program_id: &Pubkey) -> Result<Vec<AccountUpdate>, IndexerError> {
+    let slot = rpc.get_slot_with_commitment(CommitmentConfig::confirmed())?;
+    let config = RpcProgramAccountsConfig { account_config: account_config(), ..RpcProgramAccountsConfig::default() };
+    let accounts = rpc.get_program_accounts_with_config(program_id, config)?;
+    Ok(accounts.into_iter().map(|(address, account)| AccountUpdate::new(slot, address, account)).collect())
+}
+
+fn keyed_update(notification: Response<RpcKeyedAccount>) -> Option<AccountUpdate> {
+    let address = Pubkey::from_str(&notification.value.pubkey).ok()?;
+    let account = notification.value.account.decode::<Account>()?;
+    Some(AccountUpdate::new(notification.context.slot, address, account))
+}
+
+/// Subscribes to writes of every account `program_id` owns over RPC pubsub, at
+/// `confirmed` commitment. The channel closes when the subscription does.
+pub fn program_subscribe(ws_url: &str, program_id: &Pubkey) -> Result<Receiver<AccountUpdate>, IndexerError> {
+    let config = RpcProgramAccountsConfig {
+        account_config: account_config(),
+        with_context: Some(true),
+        ..RpcProgramAccountsConfig::default()
+    };
+    let (subscription, notifications) = PubsubClient::program_subscribe(ws_url, program_id, Some(config))?;
+    let (sender, updates) = mpsc::channel();
+    std::thread::spawn(move || {
+        // Owned here so the subscription lives as long as the forwarding
+        let _subscription = subscription;
+        for notification in notifications {
+            let Some(update) = keyed_update(notification) else {
+                continue;
+            };
+            if sender.send(update).is_err() {
+                break;
+            }
+        }
+    });
+    Ok(updates)
+}
+
+#[cfg(feature = "geyser")]
+pub use geyser::geyser_subscribe;
+
+#[cfg(feature = "geyser")]
+mod geyser {
+    use std::collections::HashMap;
+    use std::sync::mpsc::{self, Receiver, Sender};
+
+    use anchor_lang::solana_program::pubkey::Pubkey;
+    use futures::StreamExt;
+    use yellowstone_grpc_client::GeyserGrpcClient;
+    use yellowstone_grpc_proto::prelude::subscribe_update::UpdateOneof;
+    use yellowstone_grpc_proto::prelude::{CommitmentLevel, SubscribeRequest, SubscribeRequestFilterAccounts};
+
+    use super::AccountUpdate;
+    use crate::error::IndexerError;
+
+    // Streams account updates into `sender` until the stream fails or the
+    // receiver is dropped. `ready` learns whether the subscription started.
+    async fn forward(
+        endpoint: String,
+        x_token: Option<String>,
+        program_id: Pubkey,
+        sender: Sender<AccountUpdate>,
+        ready: Sender<Result<(), String>>,
+    ) -> Result<(), String> {
+        let request = SubscribeRequest {
+            accounts: HashMap::from([(
+                "ontora".to_string(),
+                SubscribeRequestFilterAccounts { owner: vec![program_id.to_string()], ..Default::default() },
+            )]),
+            commitment: Some(CommitmentLevel::Confirmed as i32),
+            ..Default::default()
+        };
+        let subscribed = async {
+            let mut client = GeyserGrpcClient::connect(endpoint, x_token, None).map_err(|error| error.to_string())?;
+            client.subscribe_with_request(Some(request)).await.map_err(|error| error.to_string())
+        };
+        let (_requests, mut messages) = match subscribed.await {
+            Ok(stream) => {
+                let _ = ready.send(Ok(()));
+                stream
+            }
+            Err(error) => {
+                let _ = ready.send(Err(error.clone()));
+                return Err(error);
+            }
+        };
+
+        while let Some(message) = messages.next().await {
+            let message = message.map_err(|status| status.to_string())?;
+            let Some(UpdateOneof::Account(update)) = message.update_oneof else {
+                continue;
+            };
+            let Some(account) = update.account else {
+                continue;
+            };
+            let (Ok(address), Ok(owner)) =
+                (Pubkey::try_from(account.pubkey.as_slice()), Pubkey::try_from(account.owner.as_slice()))
+            else {
+                continue;
+            };
+            let update = AccountUpdate {
+                slot: update.slot,
+                address,
+                lamports: account.lamports,
+                owner,
+                data: account.data,
+            };
+            if sender.send(update).is_err() {
+                return Ok(());
+            }
+        }
+        Err("stream ended".to_string())
+    }
+
+    /// Subscribes to writes of every account `program_id` owns through a
+    /// Yellowstone gRPC endpoint, at `confirmed` commitment. The channel closes
+    /// when the stream does.
+    pub fn geyser_subscribe(
+        endpoint: &str,
+        x_token: Option<String>,
+        program_id: &Pubkey,
+    ) -> Result<Receiver<AccountUpdate>, IndexerError> {
+        let (sender, updates) = mpsc::channel();
+        let (ready, started) = mpsc::channel();
+        let (endpoint, program_id) = (endpoint.to_string(), *program_id);
+        std::thread::spawn(move || {
+            let runtime = match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+                Ok(runtime) => runtime,
+                Err(error) => {
+                    let _ = ready.send(Err(error.to_string()));
+                    return;
+                }
+            };
+            if let Err(error) = runtime.block_on(forward(endpoint, x_token, program_id, sender, ready)) {
+                eprintln!("geyser stream closed: {}", error);
+            }
+        });
+        match started.recv() {
+            Ok(Ok(())) => Ok(updates),
+            Ok(Err(error)) => Err(IndexerError::Stream(error)),
+            Err(_) => Err(IndexerError::Stream("stream thread exited".to_string())),
+        }
+    }
+}
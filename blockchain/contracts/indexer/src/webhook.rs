@@ -0,0 +1,284 @@
+//! Webhook delivery of account changes. Each change is POSTed as JSON to
+//! every configured endpoint, signed with the endpoint's secret, and retried
+//! with backoff until the endpoint accepts it or the attempts run out.
+//!
+//! Receivers check a delivery with `verify`:
+//!
+//!     let timestamp = headers[TIMESTAMP_HEADER].parse()?;
+//!     if !verify(secret, timestamp, &body, &headers[SIGNATURE_HEADER]) { reject }
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use hmac::{Hmac, Mac};
+use serde_json::{json, Value};
+use sha2::Sha256;
+
+use crate::diff::{AccountChange, AccountDiff};
+
+/// Header carrying the hex HMAC-SHA256 signature of a delivery.
+pub const SIGNATURE_HEADER: &str = "X-Ontora-Signature";
+/// Header carrying the unix time a delivery was signed at.
+pub const TIMESTAMP_HEADER: &str = "X-Ontora-Timestamp";
+
+type HmacSha256 = Hmac<Sha256>;
+
+fn mac(secret: &[u8], timestamp: u64, body: &str) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(secret).expect("HMAC takes keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    mac
+}
+
+/// Signature of `body` sent at `timestamp`: the hex HMAC-SHA256 of
+/// `"{timestamp}.{body}"` under `secret`. Covering the timestamp lets
+/// receivers reject replayed deliveries.
+pub fn sign(secret: &[u8], timestamp: u64, body: &str) -> String {
+    hex::encode(mac(secret, timestamp, body).finalize().into_bytes())
+}
+
+/// Whether `signature` is `sign(secret, timestamp, body)`, compared in
+/// constant time.
+pub fn verify(secret: &[u8], timestamp: u64, body: &str, signature: &str) -> bool {
+    match hex::decode(signature) {
+        Ok(signature) => mac(secret, timestamp, body).verify_slice(&signature).is_ok(),
+        Err(_) => false,
+    }
+}
+
+fn change_json(change: &AccountChange) -> Value {
+    match change {
+        AccountChange::Created => json!({ "type": "created" }),
+        AccountChange::Closed => json!({ "type": "closed" }),
+        AccountChange::StakeIncreased { user, agent_id, previous, current } => json!({
+            "type": "stake_increased",
+            "user": user.to_string(),
+            "agent_id": agent_id,
+            "previous": previous,
+            "current": current,
+        }),
+        AccountChange::StakeDecreased { user, agent_id, previous, current } => json!({
+            "type": "stake_decreased",
+            "user": user.to_string(),
+            "agent_id": agent_id,
+            "previous": previous,
+            "current": current,
+        }),
+        AccountChange::TotalStakedChanged { previous, current } => json!({
+            "type": "total_staked_changed",
+            "previous": previous,
+            "current": current,
+        }),
+        AccountChange::ProposalStatusChanged { proposal_id, previous, current } => json!({
+            "type": "proposal_status_changed",
+            "proposal_id": proposal_id,
+            "previous": previous,
+            "current": current,
+        }),
+        AccountChange::PerformanceScoreChanged { owner, agent_id, previous, current } => json!({
+            "type": "performance_score_changed",
+            "owner": owner.to_string(),
+            "agent_id": agent_id,
+            "previous": previous,
+            "current": current,
+        }),
+        AccountChange::RewardPoolChanged { previous, current } => json!({
+            "type": "reward_pool_changed",
+            "previous": previous,
+            "current": current,
+        }),
+    }
+}
+
+/// The JSON body delivered for `diff`. `id` is the same on every attempt and
+/// every endpoint, so receivers can drop duplicates.
+pub fn payload(id: &str, diff: &AccountDiff) -> Value {
+    json!({
+        "id": id,
+        "slot": diff.slot,
+        "account": diff.address.to_string(),
+        "account_type": diff.account_type,
+        "change": change_json(&diff.change),
+    })
+}
+
+/// Where deliveries go.
+#[derive(Clone, Debug)]
+pub struct WebhookEndpoint {
+    pub url: String,
+    pub secret: Vec<u8>,
+}
+
+/// The HTTP POST a dispatcher delivers with, so tests can stand in for it.
+pub trait WebhookTransport {
+    /// POSTs `body` as JSON with the extra `headers`; returns the response
+    /// status, or why no response came.
+    fn post(&self, url: &str, headers: &[(&str, String)], body: &str) -> Result<u16, String>;
+}
+
+impl WebhookTransport for reqwest::blocking::Client {
+    fn post(&self, url: &str, headers: &[(&str, String)], body: &str) -> Result<u16, String> {
+        let mut request = reqwest::blocking::Client::post(self, url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body.to_string());
+        for (name, value) in headers {
+            request = request.header(*name, value);
+        }
+        request.send().map(|response| response.status().as_u16()).map_err(|error| error.to_string())
+    }
+}
+
+/// Limits and pacing of webhook retries.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DeliveryPolicy {
+    /// Attempts per delivery before it is given up as a dead letter.
+    pub max_attempts: u32,
+    /// Delay before the first retry; it doubles per retry.
+    pub base_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for DeliveryPolicy {
+    fn default() -> Self {
+        DeliveryPolicy {
+            max_attempts: 8,
+            base_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(300),
+        }
+    }
+}
+
+/// A delivery that was given up on.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DeadLetter {
+    pub url: String,
+    pub body: String,
+    pub reason: String,
+}
+
+struct Delivery {
+    body: String,
+    attempts: u32,
+    due: Instant,
+}
+
+// Why an attempt failed, and whether trying again could help
+enum Failure {
+    Retry(String),
+    Reject(String),
+}
+
+/// Queues of deliveries, one per endpoint, each delivered in order: a failed
+/// delivery holds back the ones behind it until it succeeds or is given up.
+///
+/// A 2xx response accepts a delivery. Network errors, 408, 429 and 5xx are
+/// retried with backoff; other responses reject it at once.
+pub struct WebhookDispatcher<T> {
+    transport: T,
+    endpoints: Vec<WebhookEndpoint>,
+    queues: Vec<VecDeque<Delivery>>,
+    policy: DeliveryPolicy,
+    dead_letters: Vec<DeadLetter>,
+    sequence: u64,
+}
+
+impl<T: WebhookTransport> WebhookDispatcher<T> {
+    pub fn new(transport: T, endpoints: Vec<WebhookEndpoint>, policy: DeliveryPolicy) -> Self {
+        let queues = endpoints.iter().map(|_| VecDeque::new()).collect();
+        WebhookDispatcher { transport, endpoints, queues, policy, dead_letters: Vec::new(), sequence: 0 }
+    }
+
+    /// Queues `diff` for every endpoint, due right away.
+    pub fn enqueue(&mut self, diff: &AccountDiff) {
+        let id = format!("{}-{}-{}", diff.slot, diff.address, self.sequence);
+        self.sequence += 1;
+        let body = payload(&id, diff).to_string();
+        let now = Instant::now();
+        for queue in &mut self.queues {
+            queue.push_back(Delivery { body: body.clone(), attempts: 0, due: now });
+        }
+    }
+
+    /// Queues `body` for `endpoint` alone, due right away. An endpoint the
+    /// dispatcher does not know yet gets a queue of its own.
+    pub fn enqueue_to(&mut self, endpoint: &WebhookEndpoint, body: String) {
+        let known = |known: &WebhookEndpoint| known.url == endpoint.url && known.secret == endpoint.secret;
+        let index = self.endpoints.iter().position(known).unwrap_or_else(|| {
+            self.endpoints.push(endpoint.clone());
+            self.queues.push(VecDeque::new());
+            self.endpoints.len() - 1
+        });
+        self.queues[index].push_back(Delivery { body, attempts: 0, due: Instant::now() });
+    }
+
+    /// Deliveries still queued across endpoints.
+    pub fn pending(&self) -> usize {
+        self.queues.iter().map(VecDeque::len).sum()
+    }
+
+    /// When the earliest queued delivery is due.
+    pub fn next_due(&self) -> Option<Instant> {
+        self.queues.iter().filter_map(|queue| queue.front()).map(|delivery| delivery.due).min()
+    }
+
+    /// Deliveries given up on since the last call.
+    pub fn take_dead_letters(&mut self) -> Vec<DeadLetter> {
+        std::mem::take(&mut self.dead_letters)
+    }
+
+    fn attempt(&self, endpoint: &WebhookEndpoint, body: &str) -> Result<(), Failure> {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        let headers = [
+            (SIGNATURE_HEADER, sign(&endpoint.secret, timestamp, body)),
+            (TIMESTAMP_HEADER, timestamp.to_string()),
+        ];
+        match self.transport.post(&endpoint.url, &headers, body) {
+            Ok(200..=299) => Ok(()),
+            Ok(status @ (408 | 429 | 500..=599)) => Err(Failure::Retry(format!("status {}", status))),
+            Ok(status) => Err(Failure::Reject(format!("status {}", status))),
+            Err(error) => Err(Failure::Retry(error)),
+        }
+    }
+
+    fn backoff(&self, attempts: u32) -> Duration {
+        self.policy.base_backoff.saturating_mul(1 << (attempts - 1).min(16)).min(self.policy.max_backoff)
+    }
+
+    /// Attempts every delivery due at `now`, in order per endpoint. Returns
+    /// how many were accepted.
+    pub fn dispatch(&mut self, now: Instant) -> usize {
+        let mut accepted = 0;
+        for index in 0..self.endpoints.len() {
+            while let Some(delivery) = self.queues[index].front() {
+                if delivery.due > now {
+                    break;
+                }
+                let endpoint = &self.endpoints[index];
+                let reason = match self.attempt(endpoint, &delivery.body) {
+                    Ok(()) => {
+                        self.queues[index].pop_front();
+                        accepted += 1;
+                        continue;
+                    }
+                    Err(Failure::Reject(reason)) => reason,
+                    Err(Failure::Retry(reason)) => {
+                        let attempts = delivery.attempts + 1;
+                        if attempts < self.policy.max_attempts {
+                            let due = now + self.backoff(attempts);
+                            let delivery = self.queues[index].front_mut().expect("front was just read");
+                            delivery.attempts = attempts;
+                            delivery.due = due;
+                            break;
+                        }
+                        reason
+                    }
+                };
+                let delivery = self.queues[index].pop_front().expect("front was just read");
+                let url = self.endpoints[index].url.clone();
+                self.dead_letters.push(DeadLetter { url, body: delivery.body, reason });
+            }
+        }
+        accepted
+    }
+}
@@ -0,0 +1,263 @@
+// test_decode.rs
+// Decoder tests: instructions built with ontora-client are processed by program-test,
+// and the logs each transaction produced are fed through `parse_logs`, the same path
+// the indexer takes for logs delivered by the websocket subscription.
+
+use anchor_lang::{AccountSerialize, Discriminator, Event};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use ontora_ai::events::*;
+use ontora_ai::state::{ProposalAction, Role, UserStake, DEFAULT_VOTE_UNIT};
+use ontora_client::*;
+use ontora_indexer::{parse_logs, DecodeError, OntoraEvent};
+use solana_program_test::*;
+use solana_sdk::account::Account;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::rent::Rent;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::system_instruction;
+use solana_sdk::transaction::Transaction;
+
+const LAMPORTS: u64 = 10_000_000_000;
+
+struct Harness {
+    ctx: ProgramTestContext,
+}
+
+impl Harness {
+    async fn start() -> Self {
+        let test = ProgramTest::new("ontora_ai", ontora_ai::ID, processor!(ontora_ai::entry));
+        Harness { ctx: test.start_with_context().await }
+    }
+
+    // Process `instructions` paid by the context payer; returns the transaction's logs
+    async fn send(&mut self, instructions: &[Instruction], signers: &[&Keypair]) -> Vec<String> {
+        let mut all_signers = vec![&self.ctx.payer];
+        all_signers.extend_from_slice(signers);
+        let tx = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.ctx.payer.pubkey()),
+            &all_signers,
+            self.ctx.last_blockhash,
+        );
+        let processed = self.ctx.banks_client.process_transaction_with_metadata(tx).await.unwrap();
+        processed.result.unwrap();
+        processed.metadata.expect("program-test records metadata").log_messages
+    }
+
+    async fn new_user(&mut self) -> Keypair {
+        let user = Keypair::new();
+        let transfer = system_instruction::transfer(&self.ctx.payer.pubkey(), &user.pubkey(), LAMPORTS);
+        self.send(&[transfer], &[]).await;
+        user
+    }
+
+    // Give `user` a stake account holding `staked_amount`, as if they had staked it
+    fn seed_stake(&mut self, user: &Pubkey, staked_amount: u64) {
+        let (address, bump) = pda::user_stake(user);
+        let mut stake = UserStake::default();
+        stake.init(*user, bump);
+        stake.staked_amount = staked_amount;
+        let mut data = Vec::new();
+        stake.try_serialize(&mut data).unwrap();
+        data.resize(UserStake::SPACE, 0);
+        let lamports = Rent::default().minimum_balance(data.len());
+        let account = Account { lamports, data, owner: ontora_ai::ID, executable: false, rent_epoch: 0 };
+        self.ctx.set_account(&address, &account.into());
+    }
+}
+
+fn names(events: &[OntoraEvent]) -> Vec<&'static str> {
+    events.iter().map(OntoraEvent::name).collect()
+}
+
+// The lines a top-level invocation of `program_id` writes around one `sol_log_data` call
+fn invocation_logs(program_id: &Pubkey, data: &[u8]) -> Vec<String> {
+    vec![
+        format!("Program {} invoke [1]", program_id),
+        format!("Program data: {}", STANDARD.encode(data)),
+        format!("Program {} success", program_id),
+    ]
+}
+
+// Test that the platform, role, fee and agent events decode with their fields
+#[tokio::test]
+async fn test_decodes_platform_and_agent_events() {
+    let mut harness = Harness::start().await;
+    let (admin, owner) = (harness.new_user().await, harness.new_user().await);
+
+    let initialize = build_initialize_platform(InitializePlatformParams {
+        admin: admin.pubkey(),
+        reward_rate_bps: 100,
+        min_stake_amount: 1_000_000,
+        epoch_duration: 86_400,
+        cooldown_seconds: 86_400,
+        epoch_emission: 100_000,
+    });
+    let role = build_set_role(SetRoleParams { admin: admin.pubkey(), role: Role::Oracle, holder: admin.pubkey() });
+    let fees = build_initialize_fee_schedule(InitializeFeeScheduleParams {
+        admin: admin.pubkey(),
+        governance: admin.pubkey(),
+        rates_bps: [0, 50, 100, 0],
+    });
+    let logs = harness.send(&[initialize, role, fees], &[&admin]).await;
+    let events = parse_logs(&ontora_ai::ID, &logs).unwrap();
+    assert_eq!(names(&events), ["RoleUpdated", "FeeScheduleUpdated"]);
+    match &events[0] {
+        OntoraEvent::RoleUpdated(event) => {
+            assert_eq!(event.schema_version, RoleUpdated::SCHEMA_VERSION);
+            assert_eq!((event.role, event.new_holder, event.authority), (Role::Oracle, admin.pubkey(), admin.pubkey()));
+        }
+        other => panic!("unexpected event {:?}", other),
+    }
+    match &events[1] {
+        OntoraEvent::FeeScheduleUpdated(event) => {
+            assert_eq!((event.rates_bps, event.authority), ([0, 50, 100, 0], admin.pubkey()));
+        }
+        other => panic!("unexpected event {:?}", other),
+    }
+
+    let register = build_register_agent(RegisterAgentParams {
+        owner: owner.pubkey(),
+        agent_id: 3,
+        name: "Atlas".to_string(),
+        description: "Market-making agent".to_string(),
+    });
+    let logs = harness.send(&[register], &[&owner]).await;
+    let events = parse_logs(&ontora_ai::ID, &logs).unwrap();
+    match events.as_slice() {
+        [OntoraEvent::AgentRegistered(event)] => {
+            assert_eq!((event.agent_id, event.owner), (3, owner.pubkey()));
+            assert!(event.metadata.contains("Atlas"));
+        }
+        other => panic!("unexpected events {:?}", other),
+    }
+
+    let score = build_update_performance_score(UpdatePerformanceScoreParams {
+        oracle: admin.pubkey(),
+        agent_owner: owner.pubkey(),
+        agent_id: 3,
+        score: 87,
+    });
+    let logs = harness.send(&[score], &[&admin]).await;
+    let events = parse_logs(&ontora_ai::ID, &logs).unwrap();
+    assert_eq!(names(&events), ["PerformanceScoreUpdated", "AgentUpdated"]);
+    match &events[0] {
+        OntoraEvent::PerformanceScoreUpdated(event) => {
+            assert_eq!(event.agent, pda::ai_agent(&owner.pubkey(), 3).0);
+            assert_eq!((event.previous_score, event.new_score), (0, 87));
+        }
+        other => panic!("unexpected event {:?}", other),
+    }
+}
+
+// Test that the governance events decode with their fields
+#[tokio::test]
+async fn test_decodes_governance_events() {
+    let mut harness = Harness::start().await;
+    let (admin, creator, voter) = (harness.new_user().await, harness.new_user().await, harness.new_user().await);
+    let initialize = build_initialize_platform(InitializePlatformParams {
+        admin: admin.pubkey(),
+        reward_rate_bps: 100,
+        min_stake_amount: 1_000_000,
+        epoch_duration: 86_400,
+        cooldown_seconds: 86_400,
+        epoch_emission: 100_000,
+    });
+    harness.send(&[initialize], &[&admin]).await;
+
+    let create = build_create_governance_proposal(CreateGovernanceProposalParams {
+        creator: creator.pubkey(),
+        proposal_id: 0,
+        title: "Raise reward rate".to_string(),
+        description: "Raise the reward rate to 6%".to_string(),
+        voting_duration: 3_600,
+        options: vec!["Yes".to_string(), "No".to_string()],
+        action: ProposalAction::UpdateRewardRate { bps: 600 },
+    });
+    harness.seed_stake(&voter.pubkey(), 3 * DEFAULT_VOTE_UNIT);
+    let vote = build_cast_vote(CastVoteParams { voter: voter.pubkey(), proposal_id: 0, vote_option: 1 });
+    let logs = harness.send(&[create, vote], &[&creator, &voter]).await;
+    let events = parse_logs(&ontora_ai::ID, &logs).unwrap();
+    match events.as_slice() {
+        [OntoraEvent::ProposalCreated(created), OntoraEvent::VoteCast(vote)] => {
+            assert_eq!((created.proposal_id, created.creator), (0, creator.pubkey()));
+            assert_eq!((created.title.as_str(), created.voting_duration), ("Raise reward rate", 3_600));
+            assert_eq!((vote.proposal_id, vote.voter), (0, voter.pubkey()));
+            assert_eq!((vote.vote_option, vote.vote_weight), (1, 3));
+        }
+        other => panic!("unexpected events {:?}", other),
+    }
+}
+
+// Test that the decoder covers every event declared in events.rs
+#[test]
+fn test_every_event_is_decodable() {
+    let source = include_str!("../../src/events.rs");
+    assert_eq!(source.matches("#[event]").count(), OntoraEvent::NAMES.len());
+    for name in OntoraEvent::NAMES {
+        assert!(source.contains(&format!("pub struct {} {{", name)), "{} is not in events.rs", name);
+    }
+
+    let event = PauseFlagsUpdated {
+        schema_version: PauseFlagsUpdated::SCHEMA_VERSION,
+        authority: Pubkey::new_unique(),
+        previous_flags: 0,
+        new_flags: 4,
+    };
+    let decoded = OntoraEvent::decode(&event.data()).unwrap().unwrap();
+    assert_eq!(decoded, OntoraEvent::PauseFlagsUpdated(event.clone()));
+
+    // Fields appended by a newer schema version are ignored
+    let mut extended = event.data();
+    extended.extend_from_slice(&[7; 16]);
+    assert_eq!(OntoraEvent::decode(&extended).unwrap().unwrap(), OntoraEvent::PauseFlagsUpdated(event));
+}
+
+// Test that only data logged while the program itself is running is decoded
+#[test]
+fn test_ignores_data_from_other_programs() {
+    let event = GovernanceResumed {
+        schema_version: GovernanceResumed::SCHEMA_VERSION,
+        admin: Pubkey::new_unique(),
+        resumed_proposals: 2,
+        timestamp: 1_700_000_000,
+    };
+    let other_program = Pubkey::new_unique();
+
+    let logs = invocation_logs(&other_program, &event.data());
+    assert_eq!(parse_logs(&ontora_ai::ID, &logs), Ok(vec![]));
+
+    // The program invoked by another program, which logs the same bytes after the call returns
+    let logs = vec![
+        format!("Program {} invoke [1]", other_program),
+        "Program log: invoke the indexer".to_string(),
+        format!("Program {} invoke [2]", ontora_ai::ID),
+        format!("Program data: {}", STANDARD.encode(event.data())),
+        format!("Program {} consumed 2000 of 190000 compute units", ontora_ai::ID),
+        format!("Program {} success", ontora_ai::ID),
+        format!("Program data: {}", STANDARD.encode(event.data())),
+        format!("Program {} success", other_program),
+    ];
+    assert_eq!(parse_logs(&ontora_ai::ID, &logs), Ok(vec![OntoraEvent::GovernanceResumed(event)]));
+}
+
+// Test that unknown discriminators are skipped and truncated events are errors
+#[test]
+fn test_rejects_malformed_event_data() {
+    let logs = invocation_logs(&ontora_ai::ID, &[9; 40]);
+    assert_eq!(parse_logs(&ontora_ai::ID, &logs), Ok(vec![]));
+
+    let mut truncated = VoteCast::discriminator().to_vec();
+    truncated.extend_from_slice(&[1, 0, 0]);
+    let logs = invocation_logs(&ontora_ai::ID, &truncated);
+    assert!(matches!(
+        parse_logs(&ontora_ai::ID, &logs),
+        Err(DecodeError::Event { name: "VoteCast", .. })
+    ));
+
+    let mut logs = invocation_logs(&ontora_ai::ID, &[]);
+    logs[1] = "Program data: not*base64".to_string();
+    assert!(matches!(parse_logs(&ontora_ai::ID, &logs), Err(DecodeError::Base64(_))));
+}
@@ -0,0 +1,214 @@
+// test_metrics.rs
+// Metrics tests. Synthetic platform accounts and log notifications are fed to
+// `Metrics`, and the rendered text exposition is checked series by series, then
+// fetched over HTTP from `serve` the way Prometheus scrapes it. A scrape through
+// an RPC pool whose endpoints are down checks the per-endpoint series.
+
+use std::future::Future;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+use anchor_lang::Event;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use ontora_ai::events::{RewardClaimed, StakeDeposited};
+use ontora_ai::state::{AiAgent, PlatformConfig};
+use ontora_ai::RewardPool;
+use ontora_client::{Endpoint, PoolPolicy, RpcPool, RpcTransport, TransportError};
+use ontora_indexer::{metrics, reward_pool_runway, Metrics};
+use serde_json::Value;
+use solana_client::rpc_request::RpcRequest;
+use solana_client::rpc_response::RpcTransactionLogs;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::instruction::InstructionError;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::transaction::TransactionError;
+
+const NOW: i64 = 1_700_000_000;
+
+// A log notification for a transaction in which the program emitted `events`
+fn notification(events: &[Vec<u8>], err: Option<TransactionError>) -> RpcTransactionLogs {
+    let mut logs = vec![format!("Program {} invoke [1]", ontora_ai::ID)];
+    logs.extend(events.iter().map(|data| format!("Program data: {}", STANDARD.encode(data))));
+    logs.push(format!("Program {} success", ontora_ai::ID));
+    RpcTransactionLogs { signature: String::new(), err, logs }
+}
+
+fn deposit(amount: u64) -> Vec<u8> {
+    StakeDeposited {
+        schema_version: StakeDeposited::SCHEMA_VERSION,
+        user: Pubkey::new_unique(),
+        agent_id: 1,
+        amount,
+        timestamp: NOW,
+        staking_duration: 0,
+    }
+    .data()
+}
+
+fn claim(reward_amount: u64) -> Vec<u8> {
+    RewardClaimed {
+        schema_version: RewardClaimed::SCHEMA_VERSION,
+        user: Pubkey::new_unique(),
+        agent_id: 1,
+        reward_amount,
+        timestamp: NOW,
+    }
+    .data()
+}
+
+// Metrics after one scrape of a platform with 2,000,000 staked at 1% per epoch, a pool
+// of 50,000 and an agent scored 90 seconds ago, and three transactions
+fn observed() -> Metrics {
+    let mut metrics = Metrics::new();
+    let config = PlatformConfig {
+        total_staked: 2_000_000,
+        reward_rate_bps: 100,
+        active_proposal_count: 3,
+        ..PlatformConfig::default()
+    };
+    let reward_pool = RewardPool { total_rewards: 50_000, last_updated: NOW, bump: 255 };
+    let agents = [
+        AiAgent { score_updated_at: NOW - 400, ..AiAgent::default() },
+        AiAgent { score_updated_at: NOW - 90, ..AiAgent::default() },
+        // Never scored
+        AiAgent::default(),
+    ];
+    metrics.observe_accounts(&config, &reward_pool, &agents, NOW);
+
+    metrics.observe_transaction(&notification(&[deposit(500), deposit(700)], None));
+    metrics.observe_transaction(&notification(&[claim(40), claim(60)], None));
+    let failed = TransactionError::InstructionError(0, InstructionError::Custom(6001));
+    metrics.observe_transaction(&notification(&[deposit(900)], Some(failed)));
+    metrics
+}
+
+// The sample lines of `exposition`, without HELP and TYPE comments
+fn samples(exposition: &str) -> Vec<&str> {
+    exposition.lines().filter(|line| !line.starts_with('#')).collect()
+}
+
+// Test that gauges derived from the accounts and counters derived from events render
+// as the expected series, each preceded by its HELP and TYPE
+#[test]
+fn test_renders_expected_series() {
+    let exposition = observed().render();
+    assert_eq!(
+        samples(&exposition),
+        [
+            "ontora_total_staked 2000000",
+            "ontora_reward_pool_balance 50000",
+            "ontora_reward_emission_per_epoch 20000",
+            "ontora_reward_pool_runway_epochs 2.5",
+            "ontora_active_proposals 3",
+            "ontora_last_scrape_timestamp_seconds 1700000000",
+            "ontora_feed_staleness_seconds 90",
+            "ontora_events_total{event=\"RewardClaimed\"} 2",
+            "ontora_events_total{event=\"StakeDeposited\"} 2",
+            "ontora_rewards_claimed_total 100",
+            "ontora_failed_transactions_total 1",
+            "ontora_scrape_errors_total 0",
+        ]
+    );
+    for (name, kind) in [("ontora_reward_pool_runway_epochs", "gauge"), ("ontora_events_total", "counter")] {
+        let help = format!("# HELP {} ", name);
+        let type_line = format!("# TYPE {} {}", name, kind);
+        let lines: Vec<&str> = exposition.lines().collect();
+        let at = lines.iter().position(|line| line.starts_with(&help)).unwrap();
+        assert_eq!(lines[at + 1], type_line);
+    }
+    assert!(exposition.ends_with('\n'));
+}
+
+// Test the runway edge cases, and that gauges only appear once a scrape succeeded
+#[test]
+fn test_runway_and_missing_gauges() {
+    assert_eq!(reward_pool_runway(50_000, 20_000), 2.5);
+    assert_eq!(reward_pool_runway(50_000, 0), f64::INFINITY);
+    assert_eq!(reward_pool_runway(0, 20_000), 0.0);
+
+    let mut metrics = Metrics::new();
+    metrics.observe_scrape_error();
+    assert_eq!(
+        samples(&metrics.render()),
+        ["ontora_rewards_claimed_total 0", "ontora_failed_transactions_total 0", "ontora_scrape_errors_total 1"]
+    );
+
+    // Nothing staked emits nothing, and no agent has been scored yet
+    let reward_pool = RewardPool { total_rewards: 10, last_updated: NOW, bump: 255 };
+    metrics.observe_accounts(&PlatformConfig::default(), &reward_pool, &[AiAgent::default()], NOW);
+    let exposition = metrics.render();
+    assert!(samples(&exposition).contains(&"ontora_reward_pool_runway_epochs +Inf"));
+    assert!(!exposition.contains("ontora_feed_staleness_seconds"));
+}
+
+// Test that `serve` answers /metrics with the rendered exposition and anything else with 404
+#[test]
+fn test_serves_metrics_endpoint() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let shared = Arc::new(Mutex::new(observed()));
+    let served = shared.clone();
+    std::thread::spawn(move || metrics::serve(listener, served));
+
+    let get = |path: &str| {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(stream, "GET {} HTTP/1.1\r\nHost: {}\r\nAccept: text/plain\r\n\r\n", path, addr).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    };
+    let response = get("/metrics");
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    assert!(head.starts_with("HTTP/1.1 200 OK"));
+    assert!(head.contains("Content-Type: text/plain; version=0.0.4"));
+    assert_eq!(body, shared.lock().unwrap().render());
+    assert!(get("/").starts_with("HTTP/1.1 404"));
+}
+
+// An endpoint that fails every request as a rate limit or as an outage
+struct DownEndpoint {
+    rate_limited: bool,
+}
+
+impl RpcTransport for DownEndpoint {
+    fn send(
+        &self,
+        _: RpcRequest,
+        _: Value,
+    ) -> Pin<Box<dyn Future<Output = Result<Value, TransportError>> + Send + '_>> {
+        let error = match self.rate_limited {
+            true => TransportError::RateLimited,
+            false => TransportError::Unavailable("node is behind".to_string()),
+        };
+        Box::pin(async move { Err(error) })
+    }
+}
+
+// Test that a scrape through a pool whose endpoints are down counts a scrape
+// error and renders each endpoint's health
+#[test]
+fn test_scrape_renders_rpc_endpoint_health() {
+    let endpoints: Vec<(Endpoint, Box<dyn RpcTransport>)> = vec![
+        (Endpoint::new("https://limited.example", 100.0), Box::new(DownEndpoint { rate_limited: true })),
+        (Endpoint::new("https://down.example", 100.0), Box::new(DownEndpoint { rate_limited: false })),
+    ];
+    let policy = PoolPolicy { failure_threshold: 1, ..PoolPolicy::default() };
+    let pool = RpcPool::with_transports(endpoints, CommitmentConfig::confirmed(), policy);
+    let metrics = Mutex::new(Metrics::new());
+
+    assert!(metrics::scrape(&pool, &metrics).is_err());
+    let exposition = metrics.lock().unwrap().render();
+    let lines = samples(&exposition);
+    assert!(lines.contains(&"ontora_scrape_errors_total 1"));
+    for endpoint in ["limited", "down"] {
+        let up = format!("ontora_rpc_up{{endpoint=\"https://{}.example\"}} 0", endpoint);
+        let requests = format!("ontora_rpc_requests_total{{endpoint=\"https://{}.example\"}} 1", endpoint);
+        assert!(lines.contains(&up.as_str()) && lines.contains(&requests.as_str()), "{}", exposition);
+    }
+    assert!(lines.contains(&"ontora_rpc_rate_limited_total{endpoint=\"https://limited.example\"} 1"));
+    assert!(lines.contains(&"ontora_rpc_rate_limited_total{endpoint=\"https://down.example\"} 0"));
+    assert!(lines.contains(&"ontora_rpc_failures_total{endpoint=\"https://down.example\"} 1"));
+}
@@ -0,0 +1,148 @@
+// test_monitor.rs
+// Runway monitor tests. Synthetic pool states are fed to a `Monitor` check by
+// check, and the alerts each check returns are compared: a condition fires once,
+// is silent while it holds, fires again when its severity changes, and resolves
+// once when it passes. The webhook and Slack bodies are checked field by field.
+
+use ontora_indexer::{
+    alert_payload, slack_payload, Alert, AlertStatus, Condition, FeedPrice, Monitor, PoolState, Severity, Thresholds,
+    UsdThreshold,
+};
+use serde_json::json;
+
+const NOW: i64 = 1_700_000_000;
+
+// A pool emitting 10_000 per epoch, holding `total_rewards`
+fn pool(total_rewards: u64) -> PoolState {
+    PoolState { total_staked: 1_000_000, reward_rate_bps: 100, total_rewards, now: NOW, ..PoolState::default() }
+}
+
+// Valued with 3-decimal tokens and 8-decimal prices, floored at $230
+fn usd_thresholds() -> Thresholds {
+    Thresholds {
+        usd: Some(UsdThreshold { min_usd: 230.0, price_decimals: 8, token_decimals: 3, max_feed_age_seconds: 600 }),
+        ..Thresholds::default()
+    }
+}
+
+fn summary(alerts: &[Alert]) -> Vec<(Condition, AlertStatus, Severity)> {
+    alerts.iter().map(|alert| (alert.condition, alert.status, alert.severity)).collect()
+}
+
+// Test that a low runway fires once, escalates, and resolves with the severity it had
+#[test]
+fn test_runway_alert_lifecycle() {
+    let mut monitor = Monitor::new(Thresholds::default());
+    assert_eq!(pool(100_000).runway_epochs(), 10.0);
+    assert!(monitor.evaluate(&pool(100_000)).is_empty());
+
+    let warning = monitor.evaluate(&pool(50_000));
+    assert_eq!(summary(&warning), vec![(Condition::LowRunway, AlertStatus::Firing, Severity::Warning)]);
+    assert_eq!(warning[0].message, "reward pool runway is 5.0 epochs: 50000 rewards left, 10000 emitted per epoch");
+    assert_eq!(warning[0].timestamp, NOW);
+    assert!(monitor.evaluate(&pool(40_000)).is_empty(), "a condition still failing is not sent again");
+
+    let critical = monitor.evaluate(&pool(10_000));
+    assert_eq!(summary(&critical), vec![(Condition::LowRunway, AlertStatus::Firing, Severity::Critical)]);
+    assert_eq!(monitor.failing().get(&Condition::LowRunway), Some(&Severity::Critical));
+
+    let resolved = monitor.evaluate(&pool(200_000));
+    assert_eq!(summary(&resolved), vec![(Condition::LowRunway, AlertStatus::Resolved, Severity::Critical)]);
+    assert!(monitor.failing().is_empty());
+    assert!(monitor.evaluate(&pool(200_000)).is_empty());
+}
+
+// Test that a pool with no stake has an unlimited runway
+#[test]
+fn test_no_emission_never_fires() {
+    let mut monitor = Monitor::new(Thresholds::default());
+    let idle = PoolState { total_staked: 0, ..pool(0) };
+    assert_eq!(idle.runway_epochs(), f64::INFINITY);
+    assert!(monitor.evaluate(&idle).is_empty());
+}
+
+// Test that a vault holding other than the pool records is critical, within the tolerance
+#[test]
+fn test_vault_divergence() {
+    let mut monitor = Monitor::new(Thresholds { max_vault_divergence: 5, ..Thresholds::default() });
+    let watched = |balance| PoolState { vault_balance: Some(balance), ..pool(100_000) };
+    assert!(monitor.evaluate(&watched(100_005)).is_empty());
+
+    let diverged = monitor.evaluate(&watched(90_000));
+    assert_eq!(summary(&diverged), vec![(Condition::VaultDivergence, AlertStatus::Firing, Severity::Critical)]);
+    assert_eq!(diverged[0].message, "reward vault holds 90000, the pool records 100000");
+
+    // An unwatched vault is not evaluated, so the alert stays firing
+    assert!(monitor.evaluate(&pool(100_000)).is_empty());
+    let resolved = monitor.evaluate(&watched(100_000));
+    assert_eq!(summary(&resolved), vec![(Condition::VaultDivergence, AlertStatus::Resolved, Severity::Critical)]);
+}
+
+// Test that the USD floor is checked against fresh prices, and a stale feed leaves it as it was
+#[test]
+fn test_usd_value_and_stale_feed() {
+    let mut monitor = Monitor::new(usd_thresholds());
+    let priced = |total_rewards, timestamp| PoolState {
+        price: Some(FeedPrice { price: 250_000_000, timestamp }),
+        ..pool(total_rewards)
+    };
+    assert!(monitor.evaluate(&priced(100_000, NOW - 60)).is_empty(), "100 tokens at $2.50 clear the floor");
+
+    let low = monitor.evaluate(&priced(90_000, NOW));
+    assert_eq!(summary(&low), vec![(Condition::LowUsdValue, AlertStatus::Firing, Severity::Warning)]);
+    assert_eq!(low[0].message, "rewards left are worth $225.00, the floor is $230.00");
+
+    let lower = monitor.evaluate(&priced(70_000, NOW));
+    assert_eq!(summary(&lower), vec![(Condition::LowRunway, AlertStatus::Firing, Severity::Warning)]);
+
+    let stale = monitor.evaluate(&priced(100_000, NOW - 601));
+    assert_eq!(
+        summary(&stale),
+        vec![
+            (Condition::LowRunway, AlertStatus::Resolved, Severity::Warning),
+            (Condition::StaleFeed, AlertStatus::Firing, Severity::Warning),
+        ]
+    );
+    assert_eq!(stale[1].message, "the price feed last updated 601 seconds ago");
+    assert_eq!(monitor.failing().get(&Condition::LowUsdValue), Some(&Severity::Warning));
+
+    let missing = monitor.evaluate(&pool(100_000));
+    assert!(missing.is_empty(), "no price is still a stale feed");
+
+    let fresh = monitor.evaluate(&priced(100_000, NOW));
+    assert_eq!(
+        summary(&fresh),
+        vec![
+            (Condition::LowUsdValue, AlertStatus::Resolved, Severity::Warning),
+            (Condition::StaleFeed, AlertStatus::Resolved, Severity::Warning),
+        ]
+    );
+}
+
+// Test the generic webhook and Slack bodies of a firing and a resolved alert
+#[test]
+fn test_alert_payloads() {
+    let mut monitor = Monitor::new(Thresholds::default());
+    let firing = monitor.evaluate(&pool(10_000)).remove(0);
+    let resolved = monitor.evaluate(&pool(100_000)).remove(0);
+
+    assert_eq!(
+        alert_payload(&firing),
+        json!({
+            "alert": "low_runway",
+            "status": "firing",
+            "severity": "critical",
+            "message": "reward pool runway is 1.0 epochs: 10000 rewards left, 10000 emitted per epoch",
+            "timestamp": NOW,
+        })
+    );
+    assert_eq!(alert_payload(&resolved)["status"], "resolved");
+    assert_eq!(
+        slack_payload(&firing)["text"],
+        "[critical] low_runway: reward pool runway is 1.0 epochs: 10000 rewards left, 10000 emitted per epoch"
+    );
+    assert_eq!(
+        slack_payload(&resolved)["text"],
+        "[resolved] low_runway: reward pool runway is 10.0 epochs: 100000 rewards left, 10000 emitted per epoch"
+    );
+}
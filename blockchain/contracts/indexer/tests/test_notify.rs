@@ -0,0 +1,166 @@
+// test_notify.rs
+// Notification tests against an in-memory SQLite database. Claims and stakes of
+// several sizes stream through an indexer into the notifier, and a transport that
+// records every POST shows which subscriptions each one reached, and when.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use ontora_ai::events::{RewardClaimed, StakeDeposited};
+use ontora_indexer::*;
+use serde_json::Value;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+
+const TIMESTAMP: i64 = 1_700_000_000;
+
+// Records every POST and answers it with `status`
+#[derive(Clone)]
+struct Recorder {
+    status: u16,
+    posts: Rc<RefCell<Vec<(String, Value)>>>,
+}
+
+impl WebhookTransport for Recorder {
+    fn post(&self, url: &str, _headers: &[(&str, String)], body: &str) -> Result<u16, String> {
+        self.posts.borrow_mut().push((url.to_string(), serde_json::from_str(body).unwrap()));
+        Ok(self.status)
+    }
+}
+
+fn notifier(status: u16, policy: DeliveryPolicy) -> (Rc<RefCell<Notifier<Recorder>>>, Recorder) {
+    let recorder = Recorder { status, posts: Rc::default() };
+    let notifier = Notifier::connect("sqlite::memory:", recorder.clone(), policy).unwrap();
+    (Rc::new(RefCell::new(notifier)), recorder)
+}
+
+fn subscription(id: &str, wallet: Pubkey, event: &str, min_amount: Option<u64>) -> Subscription {
+    Subscription {
+        id: id.to_string(),
+        wallet,
+        event_types: vec![event.to_string()],
+        min_amount,
+        url: format!("https://example.com/{id}"),
+        secret: format!("secret-{id}"),
+        created_at: TIMESTAMP,
+    }
+}
+
+fn logs(events: &[OntoraEvent]) -> Vec<String> {
+    let mut logs = vec![format!("Program {} invoke [1]", ontora_ai::ID)];
+    logs.extend(events.iter().map(|event| format!("Program data: {}", STANDARD.encode(event.data()))));
+    logs.push(format!("Program {} success", ontora_ai::ID));
+    logs
+}
+
+fn claim(user: Pubkey, reward_amount: u64) -> OntoraEvent {
+    OntoraEvent::RewardClaimed(RewardClaimed {
+        schema_version: RewardClaimed::SCHEMA_VERSION,
+        user,
+        agent_id: 1,
+        reward_amount,
+        timestamp: TIMESTAMP,
+    })
+}
+
+fn deposit(user: Pubkey, amount: u64) -> OntoraEvent {
+    OntoraEvent::StakeDeposited(StakeDeposited {
+        schema_version: StakeDeposited::SCHEMA_VERSION,
+        user,
+        agent_id: 1,
+        amount,
+        timestamp: TIMESTAMP,
+        staking_duration: 86_400,
+    })
+}
+
+// Which subscription each post went to, with the amount it carried
+fn delivered(recorder: &Recorder) -> Vec<(String, u64)> {
+    let posts = recorder.posts.borrow();
+    let delivered = posts.iter().map(|(_, body)| {
+        (body["subscription"].as_str().unwrap().to_string(), body["details"]["amount"].as_u64().unwrap())
+    });
+    delivered.collect()
+}
+
+// Test that each event reaches the subscriptions of its wallet whose type and
+// minimum amount it meets, and only once its slot is final
+#[test]
+fn test_notifies_matching_subscriptions_once_final() {
+    let (notifier, recorder) = notifier(200, DeliveryPolicy::default());
+    let (wallet, other) = (Pubkey::new_unique(), Pubkey::new_unique());
+    for subscription in [
+        subscription("a", wallet, "RewardClaimed", Some(100)),
+        subscription("b", wallet, "RewardClaimed", Some(1_000)),
+        subscription("c", wallet, "StakeDeposited", None),
+        subscription("d", other, "RewardClaimed", None),
+    ] {
+        notifier.borrow().subscribe(&subscription).unwrap();
+    }
+
+    let mut indexer = Indexer::new(ontora_ai::ID, None).with_sink(notifier.clone());
+    let (first, second) = (Signature::new_unique(), Signature::new_unique());
+    indexer.ingest(10, first, &logs(&[claim(wallet, 500), claim(wallet, 5_000)])).unwrap();
+    indexer.ingest(11, second, &logs(&[claim(wallet, 50), deposit(wallet, 7)])).unwrap();
+    notifier.borrow_mut().dispatch(Instant::now()).unwrap();
+    assert!(recorder.posts.borrow().is_empty());
+    assert_eq!(notifier.borrow().pending(), 4);
+
+    indexer.settle(11, &[10, 11]);
+    assert_eq!(notifier.borrow_mut().dispatch(Instant::now()).unwrap(), 4);
+    let mut delivered = delivered(&recorder);
+    delivered.sort();
+    let expected = [("a", 500), ("a", 5_000), ("b", 5_000), ("c", 7)];
+    assert_eq!(delivered, expected.map(|(id, amount)| (id.to_string(), amount)));
+
+    let posts = recorder.posts.borrow();
+    let (url, body) = &posts[0];
+    assert_eq!(url, "https://example.com/a");
+    assert_eq!(body["id"], format!("{first}-0-a"));
+    assert_eq!(body["wallet"], wallet.to_string());
+    assert_eq!(body["event"], "RewardClaimed");
+    assert_eq!(body["slot"], 10);
+}
+
+// Test that an event from a slot the finalized chain skipped notifies nobody
+#[test]
+fn test_retracted_event_is_not_notified() {
+    let (notifier, recorder) = notifier(200, DeliveryPolicy::default());
+    let wallet = Pubkey::new_unique();
+    notifier.borrow().subscribe(&subscription("a", wallet, "RewardClaimed", None)).unwrap();
+
+    let mut indexer = Indexer::new(ontora_ai::ID, None).with_sink(notifier.clone());
+    indexer.ingest(10, Signature::new_unique(), &logs(&[claim(wallet, 5)])).unwrap();
+    indexer.ingest(11, Signature::new_unique(), &logs(&[claim(wallet, 9)])).unwrap();
+    indexer.settle(11, &[11]);
+    notifier.borrow_mut().dispatch(Instant::now()).unwrap();
+    assert_eq!(delivered(&recorder), [("a".to_string(), 9)]);
+    assert_eq!(notifier.borrow().pending(), 0);
+}
+
+// Test that a notification the endpoint keeps failing lands in `dead_letters`
+#[test]
+fn test_exhausted_notification_is_dead_lettered() {
+    let policy = DeliveryPolicy { max_attempts: 2, base_backoff: Duration::ZERO, max_backoff: Duration::ZERO };
+    let (notifier, recorder) = notifier(503, policy);
+    let wallet = Pubkey::new_unique();
+    notifier.borrow().subscribe(&subscription("a", wallet, "StakeDeposited", None)).unwrap();
+
+    let mut indexer = Indexer::new(ontora_ai::ID, None).with_sink(notifier.clone());
+    let signature = Signature::new_unique();
+    indexer.ingest(10, signature, &logs(&[deposit(wallet, 40)])).unwrap();
+    indexer.settle(10, &[10]);
+    assert_eq!(notifier.borrow_mut().dispatch(Instant::now()).unwrap(), 0);
+    assert!(notifier.borrow().dead_letters("a").unwrap().is_empty());
+    notifier.borrow_mut().dispatch(Instant::now()).unwrap();
+
+    assert_eq!(recorder.posts.borrow().len(), 2);
+    let dead_letters = notifier.borrow().dead_letters("a").unwrap();
+    assert_eq!(dead_letters.len(), 1);
+    assert_eq!(dead_letters[0]["id"], format!("{signature}-0-a"));
+    assert_eq!(dead_letters[0]["reason"], "status 503");
+    assert_eq!(dead_letters[0]["body"]["details"]["amount"], 40);
+}
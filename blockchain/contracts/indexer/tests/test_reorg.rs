@@ -0,0 +1,142 @@
+// test_reorg.rs
+// Settlement tests: events are ingested at confirmed commitment, then settled against a
+// finalized chain that skips some of their slots. Events from skipped slots must be
+// retracted from the sinks and never reach the cursor.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use anchor_lang::Event;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use ontora_ai::events::{StakeDeposited, StakeWithdrawn, VoteCast};
+use ontora_indexer::{Cursor, Indexer, MemorySink};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+
+// Logs of one transaction in which the program emitted `events`
+fn logs(events: &[Vec<u8>]) -> Vec<String> {
+    let mut logs = vec![format!("Program {} invoke [1]", ontora_ai::ID)];
+    logs.extend(events.iter().map(|data| format!("Program data: {}", STANDARD.encode(data))));
+    logs.push(format!("Program {} success", ontora_ai::ID));
+    logs
+}
+
+fn deposit(user: Pubkey, agent_id: u64, amount: u64) -> Vec<u8> {
+    StakeDeposited {
+        schema_version: StakeDeposited::SCHEMA_VERSION,
+        user,
+        agent_id,
+        amount,
+        timestamp: 1_700_000_000,
+        staking_duration: 0,
+    }
+    .data()
+}
+
+fn withdrawal(user: Pubkey, agent_id: u64, amount: u64) -> Vec<u8> {
+    StakeWithdrawn {
+        schema_version: StakeWithdrawn::SCHEMA_VERSION,
+        user,
+        agent_id,
+        amount,
+        timestamp: 1_700_000_000,
+    }
+    .data()
+}
+
+fn vote(voter: Pubkey, vote_option: u8, vote_weight: u64) -> Vec<u8> {
+    VoteCast {
+        schema_version: VoteCast::SCHEMA_VERSION,
+        proposal_id: 0,
+        voter,
+        timestamp: 1_700_000_000,
+        vote_option,
+        vote_weight,
+    }
+    .data()
+}
+
+fn indexer() -> (Indexer, Rc<RefCell<MemorySink>>) {
+    let sink = Rc::new(RefCell::new(MemorySink::new()));
+    (Indexer::new(ontora_ai::ID, None).with_sink(sink.clone()), sink)
+}
+
+// Test that events from a slot missing from the finalized chain are retracted
+#[test]
+fn test_retracts_events_from_skipped_slots() {
+    let (mut indexer, sink) = indexer();
+    let user = Pubkey::new_unique();
+    let (first, forked, last) = (Signature::new_unique(), Signature::new_unique(), Signature::new_unique());
+
+    assert_eq!(indexer.ingest(10, first, &logs(&[deposit(user, 1, 500)])), Ok(1));
+    assert_eq!(indexer.ingest(11, forked, &logs(&[deposit(user, 1, 300), vote(user, 0, 800)])), Ok(2));
+    assert_eq!(indexer.ingest(12, last, &logs(&[withdrawal(user, 1, 200)])), Ok(1));
+    assert_eq!(sink.borrow().aggregates().stake_by_agent[&1], 600);
+    assert_eq!(sink.borrow().aggregates().votes[&(0, 0)], 800);
+
+    indexer.settle(12, &[10, 12]);
+    let sink = sink.borrow();
+    let aggregates = sink.aggregates();
+    assert_eq!(aggregates.stake_by_agent[&1], 300);
+    assert_eq!(aggregates.stake_by_user[&user], 300);
+    assert!(aggregates.votes.is_empty());
+    assert_eq!((aggregates.event_counts["StakeDeposited"], aggregates.event_counts.get("VoteCast")), (1, None));
+    assert!(sink.records().iter().all(|record| record.signature != forked));
+    assert_eq!(indexer.cursor(), Some(Cursor { slot: 12, signature: last }));
+    assert_eq!(indexer.lowest_pending_slot(), None);
+}
+
+// Test that slots above the finalized slot stay pending and keep their events
+#[test]
+fn test_keeps_slots_above_the_finalized_slot_pending() {
+    let (mut indexer, sink) = indexer();
+    let user = Pubkey::new_unique();
+    let (settled, unsettled) = (Signature::new_unique(), Signature::new_unique());
+
+    indexer.ingest(20, settled, &logs(&[deposit(user, 2, 100)])).unwrap();
+    indexer.ingest(25, unsettled, &logs(&[deposit(user, 2, 50)])).unwrap();
+    indexer.settle(22, &[20, 21]);
+    assert_eq!(indexer.cursor(), Some(Cursor { slot: 20, signature: settled }));
+    assert_eq!(indexer.lowest_pending_slot(), Some(25));
+    assert_eq!(sink.borrow().aggregates().stake_by_agent[&2], 150);
+
+    indexer.settle(30, &[28]);
+    assert_eq!(sink.borrow().aggregates().stake_by_agent[&2], 100);
+    assert_eq!(indexer.cursor(), Some(Cursor { slot: 20, signature: settled }));
+}
+
+// Test that a transaction delivered twice is applied once, and that one re-included in
+// another slot survives the retraction of its dead fork
+#[test]
+fn test_deduplicates_and_follows_reincluded_transactions() {
+    let (mut indexer, sink) = indexer();
+    let user = Pubkey::new_unique();
+    let signature = Signature::new_unique();
+    let transaction = logs(&[deposit(user, 4, 700)]);
+
+    assert_eq!(indexer.ingest(40, signature, &transaction), Ok(1));
+    assert_eq!(indexer.ingest(40, signature, &transaction), Ok(0));
+    assert_eq!(sink.borrow().aggregates().stake_by_agent[&4], 700);
+
+    assert_eq!(indexer.ingest(42, signature, &transaction), Ok(1));
+    indexer.settle(42, &[41, 42]);
+    assert_eq!(sink.borrow().aggregates().stake_by_agent[&4], 700);
+    assert_eq!(sink.borrow().records().len(), 1);
+    assert_eq!(indexer.cursor(), Some(Cursor { slot: 42, signature }));
+}
+
+// Test that the cursor survives a restart through its file
+#[test]
+fn test_cursor_round_trips_through_file() {
+    let path = std::env::temp_dir().join(format!("ontora-indexer-cursor-{}", Pubkey::new_unique()));
+    assert_eq!(Cursor::load(&path).unwrap(), None);
+
+    let cursor = Cursor { slot: 1_234, signature: Signature::new_unique() };
+    cursor.store(&path).unwrap();
+    assert_eq!(Cursor::load(&path).unwrap(), Some(cursor));
+
+    std::fs::write(&path, "not a cursor").unwrap();
+    assert!(Cursor::load(&path).is_err());
+    std::fs::remove_file(&path).unwrap();
+}
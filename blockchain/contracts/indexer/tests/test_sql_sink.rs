@@ -0,0 +1,232 @@
+// test_sql_sink.rs
+// Tests for the SQL sink against an in-memory SQLite database: every stored event kind
+// lands in its table once however often it is replayed, retracted records are deleted,
+// and the cursor only moves to finalized transactions.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use ontora_ai::events::{
+    AgentRegistered, AgentUpdated, ProposalCreated, ProposalFinalized, RewardClaimed, RewardDistributed, RoleUpdated,
+    StakeDeposited, StakeWithdrawn, TreasuryDeposit, VoteCast,
+};
+use ontora_ai::state::{FeeSource, Role};
+use ontora_indexer::{Cursor, EventRecord, EventSink, Indexer, OntoraEvent, PriceUpdate, SqlSink};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+
+const TIMESTAMP: i64 = 1_700_000_000;
+
+fn sink() -> SqlSink {
+    SqlSink::connect("sqlite::memory:").unwrap()
+}
+
+fn counts(sink: &SqlSink, expected: [i64; 8]) -> BTreeMap<&'static str, i64> {
+    let tables = [
+        "stakes",
+        "claims",
+        "proposals",
+        "votes",
+        "agents",
+        "price_updates",
+        "reward_distributions",
+        "treasury_deposits",
+    ];
+    let counts = sink.row_counts().unwrap();
+    let expected: BTreeMap<_, _> = tables.into_iter().zip(expected).collect();
+    assert_eq!(counts, expected);
+    counts
+}
+
+// One event of every stored kind, plus one that is not stored, across two transactions
+fn batch() -> Vec<EventRecord> {
+    let user = Pubkey::new_unique();
+    let events = [
+        OntoraEvent::AgentRegistered(AgentRegistered {
+            schema_version: AgentRegistered::SCHEMA_VERSION,
+            agent_id: 1,
+            owner: user,
+            timestamp: TIMESTAMP,
+            metadata: "{\"name\":\"scout\"}".to_string(),
+        }),
+        OntoraEvent::AgentUpdated(AgentUpdated {
+            schema_version: AgentUpdated::SCHEMA_VERSION,
+            agent_id: 1,
+            owner: user,
+            timestamp: TIMESTAMP,
+            new_metadata: "{\"name\":\"scout v2\"}".to_string(),
+        }),
+        OntoraEvent::StakeDeposited(StakeDeposited {
+            schema_version: StakeDeposited::SCHEMA_VERSION,
+            user,
+            agent_id: 1,
+            amount: 500,
+            timestamp: TIMESTAMP,
+            staking_duration: 86_400,
+        }),
+        OntoraEvent::StakeWithdrawn(StakeWithdrawn {
+            schema_version: StakeWithdrawn::SCHEMA_VERSION,
+            user,
+            agent_id: 1,
+            // Above i64::MAX, stored clamped
+            amount: u64::MAX,
+            timestamp: TIMESTAMP,
+        }),
+        OntoraEvent::RewardClaimed(RewardClaimed {
+            schema_version: RewardClaimed::SCHEMA_VERSION,
+            user,
+            agent_id: 1,
+            reward_amount: 25,
+            timestamp: TIMESTAMP,
+        }),
+        OntoraEvent::ProposalCreated(ProposalCreated {
+            schema_version: ProposalCreated::SCHEMA_VERSION,
+            proposal_id: 0,
+            creator: user,
+            timestamp: TIMESTAMP,
+            title: "Raise the reward rate".to_string(),
+            voting_duration: 3_600,
+        }),
+        OntoraEvent::VoteCast(VoteCast {
+            schema_version: VoteCast::SCHEMA_VERSION,
+            proposal_id: 0,
+            voter: user,
+            timestamp: TIMESTAMP,
+            vote_option: 1,
+            vote_weight: 500,
+        }),
+        OntoraEvent::ProposalFinalized(ProposalFinalized {
+            schema_version: ProposalFinalized::SCHEMA_VERSION,
+            proposal_id: 0,
+            timestamp: TIMESTAMP,
+            result: 1,
+            vote_summary: "yes 500, no 0".to_string(),
+            reason: 0,
+        }),
+        OntoraEvent::RewardDistributed(RewardDistributed {
+            schema_version: RewardDistributed::SCHEMA_VERSION,
+            authority: user,
+            timestamp: TIMESTAMP,
+            total_amount: 1_000,
+            eligible_count: 3,
+        }),
+        OntoraEvent::TreasuryDeposit(TreasuryDeposit {
+            schema_version: TreasuryDeposit::SCHEMA_VERSION,
+            source: FeeSource::Claim,
+            mint: Pubkey::new_unique(),
+            amount: 5,
+            timestamp: TIMESTAMP,
+        }),
+        OntoraEvent::RoleUpdated(RoleUpdated {
+            schema_version: RoleUpdated::SCHEMA_VERSION,
+            role: Role::Crank,
+            previous_holder: Pubkey::default(),
+            new_holder: user,
+            authority: user,
+        }),
+    ];
+    let (first, second) = (Signature::new_unique(), Signature::new_unique());
+    events
+        .into_iter()
+        .enumerate()
+        .map(|(position, event)| {
+            let (slot, signature, index) = match position {
+                0..=3 => (10, first, position),
+                _ => (11, second, position - 4),
+            };
+            EventRecord { slot, signature, index, event }
+        })
+        .collect()
+}
+
+// Test that replaying a batch writes nothing new
+#[test]
+fn test_replayed_batch_is_written_once() {
+    let mut sink = sink();
+    counts(&sink, [0; 8]);
+
+    let batch = batch();
+    for record in &batch {
+        sink.apply(record);
+    }
+    let written = counts(&sink, [2, 1, 2, 1, 2, 0, 1, 1]);
+    for record in &batch {
+        sink.apply(record);
+    }
+    assert_eq!(sink.row_counts().unwrap(), written);
+}
+
+// Logs of one transaction in which the program emitted `events`
+fn logs(events: &[OntoraEvent]) -> Vec<String> {
+    let mut logs = vec![format!("Program {} invoke [1]", ontora_ai::ID)];
+    logs.extend(events.iter().map(|event| format!("Program data: {}", STANDARD.encode(event.data()))));
+    logs.push(format!("Program {} success", ontora_ai::ID));
+    logs
+}
+
+fn claim(user: Pubkey, reward_amount: u64) -> OntoraEvent {
+    OntoraEvent::RewardClaimed(RewardClaimed {
+        schema_version: RewardClaimed::SCHEMA_VERSION,
+        user,
+        agent_id: 1,
+        reward_amount,
+        timestamp: TIMESTAMP,
+    })
+}
+
+// Test that records from a skipped slot are deleted, that the cursor follows the
+// finalized transactions, and that an indexer restarted from it writes no duplicates
+#[test]
+fn test_settles_and_resumes() {
+    let sink = Rc::new(RefCell::new(sink()));
+    let user = Pubkey::new_unique();
+    let (first, forked, last) = (Signature::new_unique(), Signature::new_unique(), Signature::new_unique());
+    let transactions = [(10, first, logs(&[claim(user, 5)])), (12, last, logs(&[claim(user, 7), claim(user, 9)]))];
+
+    let mut indexer = Indexer::new(ontora_ai::ID, None).with_sink(sink.clone());
+    indexer.ingest(10, first, &transactions[0].2).unwrap();
+    indexer.ingest(11, forked, &logs(&[claim(user, 3)])).unwrap();
+    indexer.ingest(12, last, &transactions[1].2).unwrap();
+    counts(&sink.borrow(), [0, 4, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(sink.borrow().cursor().unwrap(), None);
+
+    indexer.settle(11, &[10]);
+    counts(&sink.borrow(), [0, 3, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(sink.borrow().cursor().unwrap(), Some(Cursor { slot: 10, signature: first }));
+    indexer.settle(12, &[12]);
+    assert_eq!(sink.borrow().cursor().unwrap(), Some(Cursor { slot: 12, signature: last }));
+
+    // A restart resumes from the stored cursor and sees both transactions again
+    let cursor = sink.borrow().cursor().unwrap();
+    let mut restarted = Indexer::new(ontora_ai::ID, cursor).with_sink(sink.clone());
+    for (slot, signature, logs) in &transactions {
+        restarted.ingest(*slot, *signature, logs).unwrap();
+    }
+    restarted.settle(12, &[10, 12]);
+    counts(&sink.borrow(), [0, 3, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(sink.borrow().cursor().unwrap(), Some(Cursor { slot: 12, signature: last }));
+}
+
+// Test that a price recorded twice is stored once
+#[test]
+fn test_records_prices_once() {
+    let mut sink = sink();
+    let update = PriceUpdate {
+        slot: 40,
+        signature: Signature::new_unique(),
+        index: 0,
+        symbol: "SOL/USD".to_string(),
+        feed: Pubkey::new_unique(),
+        price: 14_250_000_000,
+        confidence: 1_000_000,
+        round: 3,
+        timestamp: TIMESTAMP,
+    };
+    sink.record_price(&update).unwrap();
+    sink.record_price(&update).unwrap();
+    sink.record_price(&PriceUpdate { index: 1, round: 4, ..update.clone() }).unwrap();
+    counts(&sink, [0, 0, 0, 0, 0, 2, 0, 0]);
+}
@@ -0,0 +1,269 @@
+// test_webhook.rs
+// Webhook service tests. Synthetic account writes go through the tracker, and the
+// changes it finds are delivered by the dispatcher over real HTTP to a local server
+// that records every request; the recorded payloads are checked against their
+// signatures, and scripted error responses exercise the retry queue.
+
+use std::collections::{HashMap, VecDeque};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anchor_lang::{AccountSerialize, Discriminator};
+use bytemuck::Zeroable;
+use ontora_ai::state::{Proposal, StakeIndexEntry, UserStake, PROPOSAL_ACTIVE, PROPOSAL_APPROVED};
+use ontora_indexer::*;
+use serde_json::{json, Value};
+use solana_sdk::pubkey::Pubkey;
+
+const SECRET: &[u8] = b"webhook-secret";
+const LAMPORTS: u64 = 2_000_000;
+
+// A request the server received: lowercased header names, and the body
+struct Captured {
+    headers: HashMap<String, String>,
+    body: String,
+}
+
+impl Captured {
+    fn json(&self) -> Value {
+        serde_json::from_str(&self.body).unwrap()
+    }
+
+    fn is_signed_with(&self, secret: &[u8]) -> bool {
+        let timestamp = self.headers[&TIMESTAMP_HEADER.to_lowercase()].parse().unwrap();
+        verify(secret, timestamp, &self.body, &self.headers[&SIGNATURE_HEADER.to_lowercase()])
+    }
+}
+
+// A local HTTP server answering each request with the next scripted status, then 200
+struct Server {
+    url: String,
+    requests: Arc<Mutex<Vec<Captured>>>,
+}
+
+impl Server {
+    fn start(statuses: Vec<u16>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let captured = requests.clone();
+        let mut statuses = VecDeque::from(statuses);
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut headers = HashMap::new();
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                loop {
+                    line.clear();
+                    reader.read_line(&mut line).unwrap();
+                    let Some((name, value)) = line.trim_end().split_once(':') else {
+                        break;
+                    };
+                    headers.insert(name.to_lowercase(), value.trim().to_string());
+                }
+                let mut body = vec![0; headers["content-length"].parse().unwrap()];
+                reader.read_exact(&mut body).unwrap();
+                captured.lock().unwrap().push(Captured { headers, body: String::from_utf8(body).unwrap() });
+
+                let status = statuses.pop_front().unwrap_or(200);
+                let head = format!("HTTP/1.1 {} Scripted\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status);
+                stream.write_all(head.as_bytes()).unwrap();
+            }
+        });
+        Server { url, requests }
+    }
+
+    fn requests(&self) -> std::sync::MutexGuard<'_, Vec<Captured>> {
+        self.requests.lock().unwrap()
+    }
+}
+
+fn borsh_update<T: AccountSerialize>(slot: u64, address: Pubkey, account: &T) -> AccountUpdate {
+    let mut data = Vec::new();
+    account.try_serialize(&mut data).unwrap();
+    AccountUpdate { slot, address, lamports: LAMPORTS, owner: ontora_ai::ID, data }
+}
+
+fn user_stake(slot: u64, address: Pubkey, user: Pubkey, staked_amount: u64) -> AccountUpdate {
+    borsh_update(slot, address, &UserStake { user, staked_amount, ..UserStake::default() })
+}
+
+fn proposal(slot: u64, address: Pubkey, id: u64, status: u8) -> AccountUpdate {
+    let mut proposal = Proposal::zeroed();
+    proposal.id = id;
+    proposal.status = status;
+    let mut data = Proposal::discriminator().to_vec();
+    data.extend_from_slice(bytemuck::bytes_of(&proposal));
+    AccountUpdate { slot, address, lamports: LAMPORTS, owner: ontora_ai::ID, data }
+}
+
+fn dispatcher(urls: &[&str], policy: DeliveryPolicy) -> WebhookDispatcher<reqwest::blocking::Client> {
+    let endpoints = urls.iter().map(|url| WebhookEndpoint { url: url.to_string(), secret: SECRET.to_vec() }).collect();
+    WebhookDispatcher::new(reqwest::blocking::Client::new(), endpoints, policy)
+}
+
+fn immediate_retries(max_attempts: u32) -> DeliveryPolicy {
+    DeliveryPolicy { max_attempts, base_backoff: Duration::ZERO, max_backoff: Duration::ZERO }
+}
+
+// Dispatch until nothing is queued, failing after `rounds` rounds
+fn drain(dispatcher: &mut WebhookDispatcher<reqwest::blocking::Client>, rounds: usize) {
+    for _ in 0..rounds {
+        if dispatcher.pending() == 0 {
+            return;
+        }
+        dispatcher.dispatch(Instant::now());
+    }
+    assert_eq!(dispatcher.pending(), 0, "deliveries left after {} rounds", rounds);
+}
+
+// Test that writes are diffed against the known state: stake and proposal changes
+// are reported, stale and foreign writes are not, and closing reports the stake leaving
+#[test]
+fn test_tracker_diffs_account_writes() {
+    let mut tracker = AccountTracker::new(ontora_ai::ID);
+    let (user, stake, ballot) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    tracker.seed(&user_stake(10, stake, user, 100)).unwrap();
+    tracker.seed(&proposal(10, ballot, 4, PROPOSAL_ACTIVE)).unwrap();
+    assert_eq!(tracker.len(), 2);
+
+    let diffs = tracker.apply(&user_stake(12, stake, user, 250)).unwrap();
+    let increased = AccountChange::StakeIncreased { user, agent_id: None, previous: 100, current: 250 };
+    assert_eq!(diffs, vec![AccountDiff { slot: 12, address: stake, account_type: "UserStake", change: increased }]);
+    // Delivered late, from before the write just applied
+    assert!(tracker.apply(&user_stake(11, stake, user, 50)).unwrap().is_empty());
+    // Rewritten without a tracked field changing
+    assert!(tracker.apply(&proposal(12, ballot, 4, PROPOSAL_ACTIVE)).unwrap().is_empty());
+
+    let changes = |diffs: Vec<AccountDiff>| diffs.into_iter().map(|diff| diff.change).collect::<Vec<_>>();
+    let diffs = tracker.apply(&proposal(13, ballot, 4, PROPOSAL_APPROVED)).unwrap();
+    let approved = AccountChange::ProposalStatusChanged {
+        proposal_id: 4,
+        previous: PROPOSAL_ACTIVE,
+        current: PROPOSAL_APPROVED,
+    };
+    assert_eq!(changes(diffs), [approved]);
+
+    let entry = Pubkey::new_unique();
+    let staked = StakeIndexEntry { user, agent_id: 7, staked_amount: 150, ..StakeIndexEntry::default() };
+    assert_eq!(
+        changes(tracker.apply(&borsh_update(14, entry, &staked)).unwrap()),
+        [
+            AccountChange::Created,
+            AccountChange::StakeIncreased { user, agent_id: Some(7), previous: 0, current: 150 },
+        ]
+    );
+    let closed = AccountUpdate { slot: 15, address: entry, lamports: 0, owner: Pubkey::default(), data: Vec::new() };
+    assert_eq!(
+        changes(tracker.apply(&closed).unwrap()),
+        [
+            AccountChange::Closed,
+            AccountChange::StakeDecreased { user, agent_id: Some(7), previous: 150, current: 0 },
+        ]
+    );
+    assert!(tracker.get(&entry).is_none());
+
+    // Accounts the program doesn't own, and data that isn't one of its accounts
+    let foreign = AccountUpdate { owner: Pubkey::new_unique(), ..user_stake(16, Pubkey::new_unique(), user, 9) };
+    assert!(tracker.apply(&foreign).unwrap().is_empty());
+    let unknown = AccountUpdate { data: vec![1; 40], ..user_stake(16, entry, user, 9) };
+    assert!(tracker.apply(&unknown).unwrap().is_empty());
+    assert_eq!(tracker.len(), 2);
+}
+
+// Test that every change reaches every endpoint as a signed JSON payload, in order,
+// with failed deliveries retried before the ones queued behind them
+#[test]
+fn test_delivers_signed_payloads_with_retries() {
+    let flaky = Server::start(vec![503, 500]);
+    let steady = Server::start(vec![]);
+    let mut dispatcher = dispatcher(&[&flaky.url, &steady.url], immediate_retries(5));
+    let mut tracker = AccountTracker::new(ontora_ai::ID);
+    let (user, stake, ballot) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    tracker.seed(&user_stake(10, stake, user, 100)).unwrap();
+    tracker.seed(&proposal(10, ballot, 4, PROPOSAL_ACTIVE)).unwrap();
+
+    for update in [user_stake(12, stake, user, 250), proposal(13, ballot, 4, PROPOSAL_APPROVED)] {
+        for diff in tracker.apply(&update).unwrap() {
+            dispatcher.enqueue(&diff);
+        }
+    }
+    assert_eq!(dispatcher.pending(), 4);
+    drain(&mut dispatcher, 10);
+    assert!(dispatcher.take_dead_letters().is_empty());
+
+    let expected = [
+        json!({
+            "slot": 12,
+            "account": stake.to_string(),
+            "account_type": "UserStake",
+            "change": {
+                "type": "stake_increased",
+                "user": user.to_string(),
+                "agent_id": null,
+                "previous": 100,
+                "current": 250,
+            },
+        }),
+        json!({
+            "slot": 13,
+            "account": ballot.to_string(),
+            "account_type": "Proposal",
+            "change": { "type": "proposal_status_changed", "proposal_id": 4, "previous": 0, "current": 1 },
+        }),
+    ];
+    let without_id = |mut payload: Value| {
+        payload.as_object_mut().unwrap().remove("id");
+        payload
+    };
+    for (server, attempts) in [(&flaky, 4), (&steady, 2)] {
+        let requests = server.requests();
+        assert_eq!(requests.len(), attempts);
+        assert!(requests.iter().all(|request| request.is_signed_with(SECRET)));
+        assert!(requests.iter().all(|request| request.headers["content-type"] == "application/json"));
+        // The first payload was retried twice, and was the only one in the meantime
+        let bodies: Vec<Value> = requests.iter().map(Captured::json).collect();
+        let delivered = &bodies[attempts - 2..];
+        assert_eq!(delivered.iter().cloned().map(without_id).collect::<Vec<_>>(), expected);
+        assert!(bodies[..attempts - 1].iter().all(|body| *body == bodies[0]));
+    }
+    // Each change keeps its id across attempts and endpoints
+    assert_eq!(flaky.requests()[0].json()["id"], steady.requests()[0].json()["id"]);
+    assert_ne!(steady.requests()[0].json()["id"], steady.requests()[1].json()["id"]);
+
+    let request = &steady.requests()[0];
+    assert!(!request.is_signed_with(b"another-secret"));
+    let tampered = Captured { headers: request.headers.clone(), body: request.body.replace("250", "2500") };
+    assert!(!tampered.is_signed_with(SECRET));
+}
+
+// Test that rejected deliveries and deliveries out of attempts become dead letters
+// without holding back the rest of the queue
+#[test]
+fn test_gives_up_on_rejected_and_exhausted_deliveries() {
+    let server = Server::start(vec![400, 503, 503]);
+    let mut dispatcher = dispatcher(&[&server.url], immediate_retries(2));
+    let mut tracker = AccountTracker::new(ontora_ai::ID);
+    let user = Pubkey::new_unique();
+    for (slot, address) in [(1, Pubkey::new_unique()), (2, Pubkey::new_unique()), (3, Pubkey::new_unique())] {
+        for diff in tracker.apply(&user_stake(slot, address, user, 0)).unwrap() {
+            dispatcher.enqueue(&diff);
+        }
+    }
+    assert_eq!(dispatcher.pending(), 3);
+    drain(&mut dispatcher, 10);
+
+    let dead = dispatcher.take_dead_letters();
+    assert_eq!(dead.iter().map(|letter| letter.reason.as_str()).collect::<Vec<_>>(), ["status 400", "status 503"]);
+    assert!(dead.iter().all(|letter| letter.url == server.url));
+    let requests = server.requests();
+    // One attempt for the rejection, two for the one that ran out, one for the last
+    assert_eq!(requests.len(), 4);
+    assert_eq!(requests[0].json(), serde_json::from_str::<Value>(&dead[0].body).unwrap());
+    assert_eq!(requests[3].json()["slot"], 3);
+    assert!(dispatcher.take_dead_letters().is_empty());
+}
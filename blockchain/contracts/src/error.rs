@@ -1,9 +1,44 @@
 use anchor_lang::prelude::*;
 
-/// Custom error types for the Nivaro AI platform.
+/// Custom error types for the Ontora AI platform.
 /// Each error has a unique code and a descriptive message for debugging and user feedback.
+///
+/// This is the program's only error enum. On-chain codes are the discriminant plus
+/// Anchor's 6000 offset. Codes 0-5 keep the values the legacy staking and voting
+/// instructions in lib.rs have always returned. The former `ErrorCode` enums in
+/// instructions.rs and state.rs collided with those same 6000-6003 codes, so their
+/// variants were folded into the canonical ones below:
+/// - `Unauthorized` -> `UnauthorizedUser` (6103), or `UnauthorizedAdmin` (6102) on admin checks
+/// - `InvalidStakeAmount` -> `InvalidStakeAmount` (6300), or `ArithmeticError` (6600) on overflow
+/// - `MetadataTooLarge` -> `MetadataTooLarge` (6502)
+/// - `NoRewardsToClaim` -> `NoRewardsAvailable` (6304)
+/// - `TooManyAgents` -> `TooManyAgents` (6308)
 #[error_code]
 pub enum OntoraError {
+    /// Error when a stake amount is zero (legacy staking instructions).
+    #[msg("Insufficient stake amount")]
+    InsufficientStake = 0,
+
+    /// Error when unstaking or claiming before the cooldown has elapsed.
+    #[msg("Staking cooldown not completed")]
+    CooldownNotCompleted = 1,
+
+    /// Error when the signer does not own the AI agent.
+    #[msg("Invalid AI agent owner")]
+    InvalidOwner = 2,
+
+    /// Error when voting on a proposal that is closed.
+    #[msg("Governance proposal already active")]
+    ProposalActive = 3,
+
+    /// Error when the voter has no stake backing their vote.
+    #[msg("Insufficient voting power")]
+    InsufficientVotingPower = 4,
+
+    /// Error when the reward pool cannot cover a payout.
+    #[msg("Reward pool depleted")]
+    RewardPoolDepleted = 5,
+
     /// Error when the platform is already initialized.
     #[msg("Platform is already initialized.")]
     AlreadyInitialized = 100,
@@ -20,6 +55,30 @@ pub enum OntoraError {
     #[msg("Unauthorized: Caller does not have required permissions.")]
     UnauthorizedUser = 103,
 
+    /// Error when accepting or cancelling an admin transfer that was never proposed.
+    #[msg("No admin transfer is pending.")]
+    NoPendingAdmin = 104,
+
+    /// Error when the proposed admin is the default key or already the admin.
+    #[msg("Invalid pending admin.")]
+    InvalidPendingAdmin = 105,
+
+    /// Error when the caller is not one of the admin multisig signers.
+    #[msg("Unauthorized: Caller is not a multisig signer.")]
+    NotMultisigSigner = 106,
+
+    /// Error when the requested operation group is paused.
+    #[msg("This operation is currently paused.")]
+    PlatformPaused = 107,
+
+    /// Error when migrating a platform configuration that is already at the current version.
+    #[msg("Platform configuration is already at the current version.")]
+    AlreadyMigrated = 108,
+
+    /// Error when the caller does not hold the role an instruction requires.
+    #[msg("Unauthorized: Caller does not hold the required role.")]
+    MissingRole = 109,
+
     /// Error when an AI agent is already registered with the given ID.
     #[msg("AI agent is already registered with this ID.")]
     AgentAlreadyRegistered = 200,
@@ -32,6 +91,14 @@ pub enum OntoraError {
     #[msg("Invalid AI agent metadata provided.")]
     InvalidAgentMetadata = 202,
 
+    /// Error when adding stake to an agent that is no longer active.
+    #[msg("AI agent is not active.")]
+    AgentInactive = 204,
+
+    /// Error when closing an agent that still holds stake or unclaimed rewards.
+    #[msg("AI agent still holds stake or unclaimed rewards.")]
+    AgentNotEmpty = 205,
+
     /// Error when the stake amount is zero or negative.
     #[msg("Stake amount must be greater than zero.")]
     InvalidStakeAmount = 300,
@@ -64,6 +131,22 @@ pub enum OntoraError {
     #[msg("Unstake amount exceeds staked balance.")]
     InvalidUnstakeAmount = 307,
 
+    /// Error when a user already stakes on the maximum number of agents.
+    #[msg("Too many agents staked by user.")]
+    TooManyAgents = 308,
+
+    /// Error when an agent has already been paid in the current reward epoch.
+    #[msg("Agent already rewarded this epoch.")]
+    AlreadyDistributedThisEpoch = 309,
+
+    /// Error when a legacy agent's owner already has a withdrawal waiting to be claimed.
+    #[msg("A withdrawal is already pending.")]
+    WithdrawalPending = 310,
+
+    /// Error when a stake would take an agent past the platform's stake cap per agent.
+    #[msg("Stake would exceed the agent's stake cap.")]
+    AgentStakeCapExceeded = 311,
+
     /// Error when the provided vote weight or option is invalid.
     #[msg("Invalid vote weight or option provided.")]
     InvalidVote = 400,
@@ -84,6 +167,50 @@ pub enum OntoraError {
     #[msg("Governance action is not allowed at this time.")]
     GovernanceActionNotAllowed = 404,
 
+    /// Error when an admin action proposal has passed its expiry.
+    #[msg("Admin action proposal has expired.")]
+    AdminActionExpired = 405,
+
+    /// Error when an admin action has fewer approvals than the threshold.
+    #[msg("Admin action has not reached the approval threshold.")]
+    ThresholdNotMet = 406,
+
+    /// Error when an admin action was already executed.
+    #[msg("Admin action was already executed.")]
+    AdminActionExecuted = 407,
+
+    /// Error when a governance instruction runs while governance is disabled.
+    #[msg("Governance is disabled.")]
+    GovernanceDisabled = 408,
+
+    /// Error when a proposal is not in the status the instruction requires.
+    #[msg("Proposal is not active.")]
+    ProposalNotActive = 409,
+
+    /// Error when toggling governance without passing every unfinalized proposal.
+    #[msg("Every unfinalized proposal must be passed to toggle governance.")]
+    ActiveProposalsRemaining = 410,
+
+    /// Error when finalizing a proposal that already has a result.
+    #[msg("Proposal has already been finalized.")]
+    ProposalAlreadyFinalized = 411,
+
+    /// Error when finalizing a proposal before its voting period has ended.
+    #[msg("Voting period has not ended yet.")]
+    VotingPeriodNotEnded = 412,
+
+    /// Error when executing a proposal that was not approved.
+    #[msg("Proposal was not approved.")]
+    ProposalNotApproved = 413,
+
+    /// Error when executing a proposal whose action was already applied.
+    #[msg("Proposal has already been executed.")]
+    ProposalAlreadyExecuted = 414,
+
+    /// Error when an admin action is executed through the instruction for another kind of action.
+    #[msg("Admin action cannot be executed by this instruction.")]
+    InvalidAdminAction = 415,
+
     /// Error when the platform configuration parameters are invalid.
     #[msg("Invalid platform configuration parameters.")]
     InvalidConfig = 500,
@@ -96,6 +223,46 @@ pub enum OntoraError {
     #[msg("Metadata size exceeds the maximum allowed limit.")]
     MetadataTooLarge = 502,
 
+    /// Error when the multisig signer set or threshold is invalid.
+    #[msg("Invalid multisig signers or threshold.")]
+    InvalidMultisigConfig = 503,
+
+    /// Error when pause flags contain unknown bits.
+    #[msg("Invalid pause flags.")]
+    InvalidPauseFlags = 504,
+
+    /// Error when a fee rate exceeds the hard cap for its fee type.
+    #[msg("Fee rate exceeds the maximum for its fee type.")]
+    InvalidFeeRate = 505,
+
+    /// Error when a name or title exceeds its byte limit.
+    #[msg("Name exceeds the maximum length in bytes.")]
+    NameTooLong = 506,
+
+    /// Error when a description exceeds its byte limit.
+    #[msg("Description exceeds the maximum length in bytes.")]
+    DescriptionTooLong = 507,
+
+    /// Error when a required description is empty.
+    #[msg("Description must not be empty.")]
+    DescriptionEmpty = 508,
+
+    /// Error when a ballot has more options than the account can hold.
+    #[msg("Too many proposal options.")]
+    TooManyOptions = 509,
+
+    /// Error when a voting duration is zero or negative.
+    #[msg("Duration must be greater than zero.")]
+    InvalidDuration = 510,
+
+    /// Error when a mint differs from the stake mint pinned on the platform configuration.
+    #[msg("Mint does not match the platform's stake mint.")]
+    InvalidMint = 511,
+
+    /// Error when a vault is not the one recorded on the platform configuration.
+    #[msg("Vault does not match the platform's vault.")]
+    InvalidVault = 512,
+
     /// Error when an arithmetic operation overflows or underflows.
     #[msg("Arithmetic overflow or underflow occurred.")]
     ArithmeticError = 600,
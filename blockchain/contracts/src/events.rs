@@ -1,9 +1,63 @@
 use anchor_lang::prelude::*;
+use crate::state::{FeeSource, FeeType, ProposalAction, Role};
+
+/// Declares each event's current `SCHEMA_VERSION`, which it carries as its
+/// first field. Layouts are append-only: existing fields never move or change
+/// type, new fields go at the end, and the event's version is bumped when they
+/// do. `tests/golden/event_layouts.txt` pins every layout.
+macro_rules! schema_versions {
+    ($($event:ident = $version:literal,)*) => {
+        $(
+            impl $event {
+                pub const SCHEMA_VERSION: u8 = $version;
+            }
+        )*
+    };
+}
+
+schema_versions! {
+    PlatformInitialized = 1,
+    PlatformUpdated = 9,
+    AgentRegistered = 1,
+    AgentUpdated = 1,
+    StakeDeposited = 1,
+    StakeWithdrawn = 1,
+    RewardClaimed = 1,
+    ProposalCreated = 1,
+    VoteCast = 1,
+    ProposalFinalized = 2,
+    RewardDistributed = 1,
+    AdminTransferProposed = 1,
+    AdminTransferAccepted = 1,
+    AdminTransferCancelled = 1,
+    AdminMultisigInitialized = 1,
+    AdminActionProposed = 1,
+    AdminActionApproved = 1,
+    AdminActionExecuted = 1,
+    PlatformConfigMigrated = 1,
+    AccountMigrated = 1,
+    RoleUpdated = 1,
+    PerformanceScoreUpdated = 1,
+    GovernanceSuspended = 1,
+    GovernanceResumed = 1,
+    PauseFlagsUpdated = 1,
+    FeeScheduleUpdated = 1,
+    TreasuryDeposit = 1,
+    TreasuryWithdrawal = 1,
+    GovernanceRealmSet = 1,
+    RewardPoolFunded = 1,
+    PenaltyCharged = 1,
+    UnstakeRequested = 1,
+    ProposalExecuted = 1,
+}
 
 /// Event definitions for on-chain logging and tracking of Nivaro AI platform activities.
 /// These events are emitted during key contract operations to provide transparency and enable off-chain tracking.
 #[event]
+#[derive(Clone, Debug, PartialEq)]
 pub struct PlatformInitialized {
+    /// Layout version of this event (see the schema versions above).
+    pub schema_version: u8,
     /// The authority (admin) who initialized the platform.
     pub authority: Pubkey,
     /// The timestamp when the platform was initialized.
@@ -13,7 +67,10 @@ pub struct PlatformInitialized {
 }
 
 #[event]
+#[derive(Clone, Debug, PartialEq)]
 pub struct PlatformUpdated {
+    /// Layout version of this event (see the schema versions above).
+    pub schema_version: u8,
     /// The authority (admin) who updated the platform configuration.
     pub authority: Pubkey,
     /// The timestamp when the update occurred.
@@ -22,10 +79,51 @@ pub struct PlatformUpdated {
     pub new_reward_rate: u64,
     /// Any additional metadata or notes about the update (e.g., reason or version).
     pub update_metadata: String,
+    /// The reward rate (in basis points) before the update.
+    pub old_reward_rate: u64,
+    /// The minimum stake amount before the update.
+    pub old_min_stake_amount: u64,
+    /// The minimum stake amount after the update.
+    pub new_min_stake_amount: u64,
+    /// The epoch duration (in seconds) before the update.
+    pub old_epoch_duration: i64,
+    /// The epoch duration (in seconds) after the update.
+    pub new_epoch_duration: i64,
+    /// The unstaking cooldown (in seconds) before the update.
+    pub old_cooldown_seconds: i64,
+    /// The unstaking cooldown (in seconds) after the update.
+    pub new_cooldown_seconds: i64,
+    /// The rewards shared among the legacy agents per epoch before the update.
+    pub old_epoch_emission: u64,
+    /// The rewards shared among the legacy agents per epoch after the update.
+    pub new_epoch_emission: u64,
+    /// The stake cap per agent (0 = unlimited) before the update.
+    pub old_max_stake_per_agent: u64,
+    /// The stake cap per agent (0 = unlimited) after the update.
+    pub new_max_stake_per_agent: u64,
+    /// The vesting period of claimed rewards (in seconds, 0 = none) before the update.
+    pub old_vesting_seconds: i64,
+    /// The vesting period of claimed rewards (in seconds, 0 = none) after the update.
+    pub new_vesting_seconds: i64,
+    /// The stake per vote before the update.
+    pub old_vote_unit: u64,
+    /// The stake per vote after the update.
+    pub new_vote_unit: u64,
+    /// The proposal quorum (in basis points of the stake) before the update.
+    pub old_quorum_bps: u16,
+    /// The proposal quorum (in basis points of the stake) after the update.
+    pub new_quorum_bps: u16,
+    /// The winning option's required share of the votes (in basis points) before the update.
+    pub old_approval_threshold_bps: u16,
+    /// The winning option's required share of the votes (in basis points) after the update.
+    pub new_approval_threshold_bps: u16,
 }
 
 #[event]
+#[derive(Clone, Debug, PartialEq)]
 pub struct AgentRegistered {
+    /// Layout version of this event (see the schema versions above).
+    pub schema_version: u8,
     /// The unique ID of the AI agent.
     pub agent_id: u64,
     /// The owner of the AI agent.
@@ -37,7 +135,10 @@ pub struct AgentRegistered {
 }
 
 #[event]
+#[derive(Clone, Debug, PartialEq)]
 pub struct AgentUpdated {
+    /// Layout version of this event (see the schema versions above).
+    pub schema_version: u8,
     /// The unique ID of the AI agent.
     pub agent_id: u64,
     /// The owner of the AI agent.
@@ -49,7 +150,10 @@ pub struct AgentUpdated {
 }
 
 #[event]
+#[derive(Clone, Debug, PartialEq)]
 pub struct StakeDeposited {
+    /// Layout version of this event (see the schema versions above).
+    pub schema_version: u8,
     /// The user who deposited the stake.
     pub user: Pubkey,
     /// The unique ID of the AI agent staked on.
@@ -63,7 +167,10 @@ pub struct StakeDeposited {
 }
 
 #[event]
+#[derive(Clone, Debug, PartialEq)]
 pub struct StakeWithdrawn {
+    /// Layout version of this event (see the schema versions above).
+    pub schema_version: u8,
     /// The user who withdrew the stake.
     pub user: Pubkey,
     /// The unique ID of the AI agent staked on.
@@ -75,7 +182,10 @@ pub struct StakeWithdrawn {
 }
 
 #[event]
+#[derive(Clone, Debug, PartialEq)]
 pub struct RewardClaimed {
+    /// Layout version of this event (see the schema versions above).
+    pub schema_version: u8,
     /// The user who claimed the reward.
     pub user: Pubkey,
     /// The unique ID of the AI agent associated with the reward.
@@ -87,7 +197,10 @@ pub struct RewardClaimed {
 }
 
 #[event]
+#[derive(Clone, Debug, PartialEq)]
 pub struct ProposalCreated {
+    /// Layout version of this event (see the schema versions above).
+    pub schema_version: u8,
     /// The unique ID of the governance proposal.
     pub proposal_id: u64,
     /// The creator of the proposal.
@@ -101,7 +214,10 @@ pub struct ProposalCreated {
 }
 
 #[event]
+#[derive(Clone, Debug, PartialEq)]
 pub struct VoteCast {
+    /// Layout version of this event (see the schema versions above).
+    pub schema_version: u8,
     /// The unique ID of the governance proposal.
     pub proposal_id: u64,
     /// The user who cast the vote.
@@ -115,7 +231,10 @@ pub struct VoteCast {
 }
 
 #[event]
+#[derive(Clone, Debug, PartialEq)]
 pub struct ProposalFinalized {
+    /// Layout version of this event (see the schema versions above).
+    pub schema_version: u8,
     /// The unique ID of the governance proposal.
     pub proposal_id: u64,
     /// The timestamp when the proposal was finalized.
@@ -124,10 +243,15 @@ pub struct ProposalFinalized {
     pub result: u8,
     /// Total votes for each option (serialized as a string for simplicity).
     pub vote_summary: String,
+    /// Why the proposal was approved or rejected (one of the `FINALIZED_*` values).
+    pub reason: u8,
 }
 
 #[event]
+#[derive(Clone, Debug, PartialEq)]
 pub struct RewardDistributed {
+    /// Layout version of this event (see the schema versions above).
+    pub schema_version: u8,
     /// The authority or system account that triggered the distribution.
     pub authority: Pubkey,
     /// The timestamp when the reward distribution occurred.
@@ -137,3 +261,320 @@ pub struct RewardDistributed {
     /// The number of eligible users or agents who received rewards.
     pub eligible_count: u64,
 }
+
+#[event]
+#[derive(Clone, Debug, PartialEq)]
+pub struct AdminTransferProposed {
+    /// Layout version of this event (see the schema versions above).
+    pub schema_version: u8,
+    /// The current admin who nominated a successor.
+    pub admin: Pubkey,
+    /// The nominated admin, who must accept before gaining access.
+    pub pending_admin: Pubkey,
+    /// The timestamp when the transfer was proposed.
+    pub timestamp: i64,
+}
+
+#[event]
+#[derive(Clone, Debug, PartialEq)]
+pub struct AdminTransferAccepted {
+    /// Layout version of this event (see the schema versions above).
+    pub schema_version: u8,
+    /// The admin whose access ended with this transfer.
+    pub previous_admin: Pubkey,
+    /// The admin now controlling the platform configuration.
+    pub new_admin: Pubkey,
+    /// The timestamp when the transfer was accepted.
+    pub timestamp: i64,
+}
+
+#[event]
+#[derive(Clone, Debug, PartialEq)]
+pub struct AdminTransferCancelled {
+    /// Layout version of this event (see the schema versions above).
+    pub schema_version: u8,
+    /// The admin who cancelled the transfer.
+    pub admin: Pubkey,
+    /// The nominee whose pending transfer was cancelled.
+    pub cancelled_admin: Pubkey,
+    /// The timestamp when the transfer was cancelled.
+    pub timestamp: i64,
+}
+
+#[event]
+#[derive(Clone, Debug, PartialEq)]
+pub struct AdminMultisigInitialized {
+    /// Layout version of this event (see the schema versions above).
+    pub schema_version: u8,
+    /// The multisig account that now holds the admin role.
+    pub multisig: Pubkey,
+    /// The multisig signers.
+    pub signers: Vec<Pubkey>,
+    /// Approvals required to execute an action.
+    pub threshold: u8,
+}
+
+#[event]
+#[derive(Clone, Debug, PartialEq)]
+pub struct AdminActionProposed {
+    /// Layout version of this event (see the schema versions above).
+    pub schema_version: u8,
+    /// The unique ID of the admin action proposal.
+    pub proposal_id: u64,
+    /// The signer who proposed the action.
+    pub proposer: Pubkey,
+    /// The timestamp after which the proposal expires.
+    pub expires_at: i64,
+}
+
+#[event]
+#[derive(Clone, Debug, PartialEq)]
+pub struct AdminActionApproved {
+    /// Layout version of this event (see the schema versions above).
+    pub schema_version: u8,
+    /// The unique ID of the admin action proposal.
+    pub proposal_id: u64,
+    /// The signer who approved.
+    pub signer: Pubkey,
+    /// Number of distinct approvals so far.
+    pub approvals: u8,
+}
+
+#[event]
+#[derive(Clone, Debug, PartialEq)]
+pub struct AdminActionExecuted {
+    /// Layout version of this event (see the schema versions above).
+    pub schema_version: u8,
+    /// The unique ID of the admin action proposal.
+    pub proposal_id: u64,
+    /// The signer who executed the action.
+    pub executor: Pubkey,
+    /// The timestamp when the action was executed.
+    pub timestamp: i64,
+}
+
+#[event]
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlatformConfigMigrated {
+    /// Layout version of this event (see the schema versions above).
+    pub schema_version: u8,
+    /// The admin who paid for and ran the migration.
+    pub admin: Pubkey,
+    /// The layout version before the migration.
+    pub from_version: u16,
+    /// The layout version after the migration.
+    pub to_version: u16,
+    /// The program version stamped onto the config.
+    pub program_version: [u16; 3],
+}
+
+/// Event emitted when `migrate_account` upgrades an account to its current layout.
+#[event]
+#[derive(Clone, Debug, PartialEq)]
+pub struct AccountMigrated {
+    /// Layout version of this event (see the schema versions above).
+    pub schema_version: u8,
+    /// The migrated account.
+    pub account: Pubkey,
+    /// The account's Anchor discriminator, identifying its type.
+    pub discriminator: [u8; 8],
+    /// The layout version before the migration.
+    pub from_version: u16,
+    /// The layout version after the migration.
+    pub to_version: u16,
+    /// The signer who paid for any growth.
+    pub payer: Pubkey,
+}
+
+#[event]
+#[derive(Clone, Debug, PartialEq)]
+pub struct RoleUpdated {
+    /// Layout version of this event (see the schema versions above).
+    pub schema_version: u8,
+    /// The role that changed hands.
+    pub role: Role,
+    /// The previous holder (default key if the role was renounced).
+    pub previous_holder: Pubkey,
+    /// The new holder (default key when renounced).
+    pub new_holder: Pubkey,
+    /// The admin who rotated the role, or the holder who renounced it.
+    pub authority: Pubkey,
+}
+
+#[event]
+#[derive(Clone, Debug, PartialEq)]
+pub struct PerformanceScoreUpdated {
+    /// Layout version of this event (see the schema versions above).
+    pub schema_version: u8,
+    /// The agent account that was scored.
+    pub agent: Pubkey,
+    /// The score before the update.
+    pub previous_score: u64,
+    /// The score after the update.
+    pub new_score: u64,
+    /// The timestamp of the update.
+    pub timestamp: i64,
+}
+
+#[event]
+#[derive(Clone, Debug, PartialEq)]
+pub struct GovernanceSuspended {
+    /// Layout version of this event (see the schema versions above).
+    pub schema_version: u8,
+    /// The admin who disabled governance.
+    pub admin: Pubkey,
+    /// The number of in-flight proposals moved to Suspended.
+    pub suspended_proposals: u64,
+    /// The timestamp governance was disabled.
+    pub timestamp: i64,
+}
+
+#[event]
+#[derive(Clone, Debug, PartialEq)]
+pub struct GovernanceResumed {
+    /// Layout version of this event (see the schema versions above).
+    pub schema_version: u8,
+    /// The admin who re-enabled governance.
+    pub admin: Pubkey,
+    /// The number of suspended proposals reactivated with extended deadlines.
+    pub resumed_proposals: u64,
+    /// The timestamp governance was re-enabled.
+    pub timestamp: i64,
+}
+
+#[event]
+#[derive(Clone, Debug, PartialEq)]
+pub struct PauseFlagsUpdated {
+    /// Layout version of this event (see the schema versions above).
+    pub schema_version: u8,
+    /// The pauser or admin who changed the flags.
+    pub authority: Pubkey,
+    /// The pause bitfield before the change.
+    pub previous_flags: u8,
+    /// The pause bitfield after the change.
+    pub new_flags: u8,
+}
+
+#[event]
+#[derive(Clone, Debug, PartialEq)]
+pub struct FeeScheduleUpdated {
+    /// Layout version of this event (see the schema versions above).
+    pub schema_version: u8,
+    /// The admin or governance key that changed the schedule.
+    pub authority: Pubkey,
+    /// The new rates, indexed by `FeeType` (basis points).
+    pub rates_bps: [u16; FeeType::COUNT],
+    /// The timestamp the new rates take effect (the next epoch boundary).
+    pub effective_at: i64,
+}
+
+#[event]
+#[derive(Clone, Debug, PartialEq)]
+pub struct TreasuryDeposit {
+    /// Layout version of this event (see the schema versions above).
+    pub schema_version: u8,
+    /// The instruction category the fee came from.
+    pub source: FeeSource,
+    /// The mint of the deposited tokens.
+    pub mint: Pubkey,
+    /// The amount deposited (in token units).
+    pub amount: u64,
+    /// The timestamp of the deposit.
+    pub timestamp: i64,
+}
+
+#[event]
+#[derive(Clone, Debug, PartialEq)]
+pub struct TreasuryWithdrawal {
+    /// Layout version of this event (see the schema versions above).
+    pub schema_version: u8,
+    /// The governance key that approved the outflow.
+    pub governance: Pubkey,
+    /// The mint of the withdrawn tokens.
+    pub mint: Pubkey,
+    /// The recipient token account.
+    pub recipient: Pubkey,
+    /// The amount withdrawn (in token units).
+    pub amount: u64,
+    /// The timestamp of the withdrawal.
+    pub timestamp: i64,
+}
+
+#[event]
+#[derive(Clone, Debug, PartialEq)]
+pub struct GovernanceRealmSet {
+    /// Layout version of this event (see the schema versions above).
+    pub schema_version: u8,
+    /// The admin who authorized the governance.
+    pub admin: Pubkey,
+    /// The spl-governance realm the governance belongs to.
+    pub realm: Pubkey,
+    /// The governance account now authorized to execute admin instructions.
+    pub governance: Pubkey,
+    /// The native treasury PDA that signs for the governance.
+    pub native_treasury: Pubkey,
+}
+
+#[event]
+#[derive(Clone, Debug, PartialEq)]
+pub struct RewardPoolFunded {
+    /// Layout version of this event (see the schema versions above).
+    pub schema_version: u8,
+    /// The account that funded the pool.
+    pub funder: Pubkey,
+    /// The amount added (in token units).
+    pub amount: u64,
+    /// The pool's total rewards after the top-up.
+    pub total_rewards: u64,
+    /// The timestamp of the top-up.
+    pub timestamp: i64,
+}
+
+#[event]
+#[derive(Clone, Debug, PartialEq)]
+pub struct PenaltyCharged {
+    /// Layout version of this event (see the schema versions above).
+    pub schema_version: u8,
+    /// The user who left before the cooldown ended.
+    pub user: Pubkey,
+    /// The ID of the agent the stake was withdrawn from.
+    pub agent_id: u64,
+    /// The penalty kept for the reward pool (in token units).
+    pub amount: u64,
+    /// The penalty rate applied (in basis points).
+    pub penalty_bps: u16,
+    /// The timestamp of the withdrawal.
+    pub timestamp: i64,
+}
+
+#[event]
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnstakeRequested {
+    /// Layout version of this event (see the schema versions above).
+    pub schema_version: u8,
+    /// The user who requested the withdrawal.
+    pub user: Pubkey,
+    /// The ID of the agent the stake is withdrawn from.
+    pub agent_id: u64,
+    /// The amount taken out of the stake (in token units).
+    pub amount: u64,
+    /// When the withdrawal can be claimed.
+    pub release_time: i64,
+    /// The timestamp of the request.
+    pub timestamp: i64,
+}
+
+#[event]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProposalExecuted {
+    /// Layout version of this event (see the schema versions above).
+    pub schema_version: u8,
+    /// The unique ID of the governance proposal.
+    pub proposal_id: u64,
+    /// The account that executed the approved proposal (anyone may).
+    pub executor: Pubkey,
+    /// The change applied to the platform configuration.
+    pub action: ProposalAction,
+    /// The timestamp of the execution.
+    pub timestamp: i64,
+}
@@ -0,0 +1,94 @@
+use anchor_lang::prelude::*;
+use crate::error::OntoraError;
+use crate::events::FeeScheduleUpdated;
+use crate::pda::{FEE_SCHEDULE_SEED, PLATFORM_CONFIG_SEED};
+use crate::state::*;
+
+/// Context for creating the fee schedule.
+#[derive(Accounts)]
+pub struct InitializeFeeSchedule<'info> {
+    /// The platform configuration; only its admin may create the schedule.
+    #[account(
+        seeds = [PLATFORM_CONFIG_SEED],
+        bump = platform_config.bump,
+        has_one = admin @ OntoraError::UnauthorizedAdmin
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    /// The fee schedule account to be initialized.
+    #[account(
+        init,
+        payer = admin,
+        space = FeeSchedule::SPACE,
+        seeds = [FEE_SCHEDULE_SEED],
+        bump
+    )]
+    pub fee_schedule: Account<'info, FeeSchedule>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    /// The system program for account initialization.
+    pub system_program: Program<'info, System>,
+}
+
+/// Instruction to create the fee schedule. The initial rates apply immediately
+/// since no fee has been charged under any earlier schedule.
+pub fn initialize_fee_schedule(
+    ctx: Context<InitializeFeeSchedule>,
+    governance: Pubkey,
+    rates_bps: [u16; FeeType::COUNT],
+) -> Result<()> {
+    require!(FeeSchedule::validate_rates(&rates_bps), OntoraError::InvalidFeeRate);
+
+    ctx.accounts
+        .fee_schedule
+        .init(governance, rates_bps, ctx.bumps.fee_schedule);
+
+    emit!(FeeScheduleUpdated {
+        schema_version: FeeScheduleUpdated::SCHEMA_VERSION,
+        authority: ctx.accounts.admin.key(),
+        rates_bps,
+        effective_at: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+/// Context for changing fee rates.
+#[derive(Accounts)]
+pub struct UpdateFeeSchedule<'info> {
+    /// The platform configuration, for its admin and epoch duration.
+    #[account(seeds = [PLATFORM_CONFIG_SEED], bump = platform_config.bump)]
+    pub platform_config: Account<'info, PlatformConfig>,
+    /// The fee schedule being updated.
+    #[account(
+        mut,
+        seeds = [FEE_SCHEDULE_SEED],
+        bump = fee_schedule.bump,
+        constraint = authority.key() == platform_config.admin
+            || authority.key() == fee_schedule.governance @ OntoraError::UnauthorizedUser
+    )]
+    pub fee_schedule: Account<'info, FeeSchedule>,
+    /// The platform admin or the schedule's governance key.
+    pub authority: Signer<'info>,
+}
+
+/// Instruction to stage new fee rates. They take effect at the next epoch
+/// boundary so nobody is repriced mid-epoch; a second update before then
+/// replaces the staged rates.
+pub fn update_fee_schedule(ctx: Context<UpdateFeeSchedule>, rates_bps: [u16; FeeType::COUNT]) -> Result<()> {
+    require!(FeeSchedule::validate_rates(&rates_bps), OntoraError::InvalidFeeRate);
+
+    let now = Clock::get()?.unix_timestamp;
+    let fee_schedule = &mut ctx.accounts.fee_schedule;
+    let effective_at = FeeSchedule::next_epoch_boundary(now, ctx.accounts.platform_config.epoch_duration);
+
+    // Anything staged for an epoch that has already started is in effect now
+    fee_schedule.settle(now);
+    fee_schedule.schedule(rates_bps, effective_at);
+
+    emit!(FeeScheduleUpdated {
+        schema_version: FeeScheduleUpdated::SCHEMA_VERSION,
+        authority: ctx.accounts.authority.key(),
+        rates_bps,
+        effective_at,
+    });
+    Ok(())
+}
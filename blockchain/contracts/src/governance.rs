@@ -1,37 +1,44 @@
 use anchor_lang::prelude::*;
-use crate::state::{PlatformConfig, Proposal, UserStake};
-use crate::events::{ProposalCreated, VoteCast, ProposalFinalized};
+use crate::state::{
+    PlatformConfig, Proposal, ProposalAction, UserStake, VoteRecord, PAUSE_GOVERNANCE, PROPOSAL_ACTIVE,
+    PROPOSAL_APPROVED, PROPOSAL_APPROVE_OPTION, PROPOSAL_EXECUTED, PROPOSAL_VERSION,
+};
+use crate::pda::{PLATFORM_CONFIG_SEED, PROPOSAL_SEED, PROPOSAL_VOTE_SEED, USER_STAKE_SEED};
+use crate::events::{
+    GovernanceResumed, GovernanceSuspended, ProposalCreated, ProposalExecuted, ProposalFinalized, VoteCast,
+};
 use crate::error::OntoraError;
 
 /// Context for creating a new governance proposal.
 #[derive(Accounts)]
-pub struct CreateProposal<'info> {
+pub struct CreateGovernanceProposal<'info> {
     /// The creator of the proposal, must have staked tokens to propose.
     #[account(mut)]
     pub creator: Signer<'info>,
     /// The platform configuration account to ensure governance is enabled.
-    #[account(has_one = authority @ NivaroError::UnauthorizedAccess)]
+    #[account(mut, seeds = [PLATFORM_CONFIG_SEED], bump = platform_config.bump)]
     pub platform_config: Account<'info, PlatformConfig>,
     /// The proposal account to be initialized.
     #[account(
         init,
         payer = creator,
         space = Proposal::LEN,
-        seeds = [b"proposal", platform_config.proposal_count.to_le_bytes().as_ref()],
+        seeds = [PROPOSAL_SEED, platform_config.proposal_count.to_le_bytes().as_ref()],
         bump
     )]
-    pub proposal: Account<'info, Proposal>,
+    pub proposal: AccountLoader<'info, Proposal>,
     /// The system program for account initialization.
     pub system_program: Program<'info, System>,
 }
 
-impl<'info> CreateProposal<'info> {
+impl<'info> CreateGovernanceProposal<'info> {
     /// Validates that the creator has sufficient stake to create a proposal.
     pub fn validate(&self) -> Result<()> {
         // Check if governance is enabled in platform config.
         if !self.platform_config.governance_enabled {
             return err!(OntoraError::GovernanceDisabled);
         }
+        self.platform_config.require_not_paused(PAUSE_GOVERNANCE)?;
         // Placeholder for stake check (assumes a separate stake account or logic).
         // In a real implementation, check if creator has staked tokens.
         Ok(())
@@ -40,44 +47,50 @@ impl<'info> CreateProposal<'info> {
 
 /// Instruction to create a new governance proposal.
 pub fn create_proposal(
-    ctx: Context<CreateProposal>,
+    ctx: Context<CreateGovernanceProposal>,
     title: String,
     description: String,
     voting_duration: u64,
     options: Vec<String>,
+    action: ProposalAction,
 ) -> Result<()> {
     // Validate the input and context.
     ctx.accounts.validate()?;
 
-    // Ensure the title and description are within size limits.
-    if title.len() > 100 || description.len() > 1000 {
-        return err!(OntoraError::InvalidInput);
-    }
-    if options.len() < 2 || options.len() > 10 {
-        return err!(OntoraError::InvalidVoteOptions);
-    }
+    // Ensure the strings and ballot fit the space reserved for the proposal, and the action
+    // could be applied.
+    Proposal::validate_inputs(&title, &description, &options, voting_duration)?;
+    action.validate()?;
 
     let clock = Clock::get()?;
-    let proposal = &mut ctx.accounts.proposal;
+    let mut proposal = ctx.accounts.proposal.load_init()?;
     let platform_config = &mut ctx.accounts.platform_config;
 
     // Initialize the proposal data.
+    proposal.version = PROPOSAL_VERSION;
     proposal.id = platform_config.proposal_count;
     proposal.creator = ctx.accounts.creator.key();
-    proposal.title = title.clone();
-    proposal.description = description;
-    proposal.options = options.clone();
-    proposal.votes = vec![0; options.len()];
+    proposal.set_contents(&title, &description, &options);
+    proposal.set_action(&action)?;
     proposal.start_time = clock.unix_timestamp;
-    proposal.end_time = clock.unix_timestamp + voting_duration as i64;
-    proposal.status = 0; // 0 = Active
-    proposal.bump = *ctx.bumps.get("proposal").unwrap();
+    proposal.end_time = clock.unix_timestamp.checked_add(voting_duration as i64).ok_or(OntoraError::InvalidDuration)?;
+    proposal.status = PROPOSAL_ACTIVE;
+    proposal.suspended_at = 0;
+    proposal.bump = ctx.bumps.proposal;
+    // Fix the quorum base and thresholds for the life of the proposal.
+    proposal.snapshot_total_staked = platform_config.total_staked;
+    proposal.snapshot_slot = clock.slot;
+    proposal.quorum_bps = platform_config.quorum_bps;
+    proposal.approval_threshold_bps = platform_config.approval_threshold_bps;
+    proposal.vote_unit = platform_config.vote_unit;
 
-    // Increment the proposal counter in platform config.
+    // Increment the proposal counter and the count of unfinalized proposals.
     platform_config.proposal_count += 1;
+    platform_config.active_proposal_count += 1;
 
     // Emit an event for proposal creation.
     emit!(ProposalCreated {
+        schema_version: ProposalCreated::SCHEMA_VERSION,
         proposal_id: proposal.id,
         creator: proposal.creator,
         timestamp: clock.unix_timestamp,
@@ -90,37 +103,50 @@ pub fn create_proposal(
 
 /// Context for casting a vote on a proposal.
 #[derive(Accounts)]
+#[instruction(proposal_id: u64)]
 pub struct CastVote<'info> {
     /// The voter, must have staked tokens to vote.
     #[account(mut)]
     pub voter: Signer<'info>,
     /// The platform configuration account to ensure governance is enabled.
-    #[account(has_one = authority @ OntoraError::UnauthorizedAccess)]
+    #[account(seeds = [PLATFORM_CONFIG_SEED], bump = platform_config.bump)]
     pub platform_config: Account<'info, PlatformConfig>,
     /// The proposal account to vote on.
-    #[account(mut, seeds = [b"proposal", proposal.id.to_le_bytes().as_ref()], bump = proposal.bump)]
-    pub proposal: Account<'info, Proposal>,
-    /// The user's stake account to determine voting power (optional placeholder).
-    #[account(mut)]
-    pub user_stake: Option<Account<'info, UserStake>>,
+    #[account(mut, seeds = [PROPOSAL_SEED, proposal_id.to_le_bytes().as_ref()], bump = proposal.load()?.bump)]
+    pub proposal: AccountLoader<'info, Proposal>,
+    /// The voter's stake account, whose stake weighs the vote.
+    #[account(seeds = [USER_STAKE_SEED, voter.key().as_ref()], bump = user_stake.bump)]
+    pub user_stake: Account<'info, UserStake>,
+    /// The record of the voter's vote, created by their first vote on the proposal.
+    #[account(
+        init_if_needed,
+        payer = voter,
+        space = VoteRecord::SPACE,
+        seeds = [PROPOSAL_VOTE_SEED, proposal_id.to_le_bytes().as_ref(), voter.key().as_ref()],
+        bump
+    )]
+    pub vote_record: Account<'info, VoteRecord>,
     /// The system program for account operations.
     pub system_program: Program<'info, System>,
 }
 
 impl<'info> CastVote<'info> {
-    /// Validates that the voter can cast a vote.
-    pub fn validate(&self) -> Result<()> {
+    /// Validates that the voter can cast a vote on the loaded proposal.
+    pub fn validate(&self, proposal: &Proposal) -> Result<()> {
         // Check if governance is enabled.
         if !self.platform_config.governance_enabled {
             return err!(OntoraError::GovernanceDisabled);
         }
+        self.platform_config.require_not_paused(PAUSE_GOVERNANCE)?;
         // Check if the proposal is active.
         let clock = Clock::get()?;
-        if self.proposal.status != 0 || clock.unix_timestamp < self.proposal.start_time || clock.unix_timestamp > self.proposal.end_time {
+        if proposal.status != PROPOSAL_ACTIVE || clock.unix_timestamp < proposal.start_time || clock.unix_timestamp > proposal.end_time {
             return err!(OntoraError::ProposalNotActive);
         }
-        // Placeholder for checking if the voter has already voted.
-        // In a real implementation, track votes per user to prevent double voting.
+        // Each wallet votes once per proposal.
+        if self.vote_record.has_voted() {
+            return err!(OntoraError::AlreadyVoted);
+        }
         Ok(())
     }
 }
@@ -131,34 +157,38 @@ pub fn cast_vote(
     proposal_id: u64,
     vote_option: u8,
 ) -> Result<()> {
+    let mut proposal = ctx.accounts.proposal.load_mut()?;
     // Validate the input and context.
-    ctx.accounts.validate()?;
+    ctx.accounts.validate(&proposal)?;
 
-    let proposal = &mut ctx.accounts.proposal;
-    // Ensure the proposal ID matches (redundant but for clarity).
-    if proposal.id != proposal_id {
-        return err!(OntoraError::InvalidProposal);
-    }
     // Ensure the vote option is valid.
-    if vote_option as usize >= proposal.options.len() {
-        return err!(OntoraError::InvalidVoteOption);
+    if vote_option >= proposal.option_count {
+        return err!(OntoraError::InvalidVote);
     }
 
     let clock = Clock::get()?;
-    // Calculate voting weight (placeholder logic).
-    // In a real implementation, derive weight from staked amount in user_stake.
-    let vote_weight = 1; // Simplified for now.
+    // Weigh the vote by the voter's stake, one vote per `vote_unit`, if it predates the proposal.
+    let vote_weight = ctx.accounts.user_stake.proposal_voting_power(&ctx.accounts.platform_config, &proposal);
+    require!(vote_weight > 0, OntoraError::InsufficientVotingPower);
 
     // Record the vote.
-    proposal.votes[vote_option as usize] += vote_weight;
+    proposal.add_votes(vote_option, vote_weight)?;
+    let vote_record = &mut ctx.accounts.vote_record;
+    vote_record.proposal_id = proposal_id;
+    vote_record.voter = ctx.accounts.voter.key();
+    vote_record.vote_option = vote_option;
+    vote_record.vote_weight = vote_weight;
+    vote_record.voted_at = clock.unix_timestamp;
+    vote_record.bump = ctx.bumps.vote_record;
 
     // Emit an event for vote casting.
     emit!(VoteCast {
+        schema_version: VoteCast::SCHEMA_VERSION,
         proposal_id,
         voter: ctx.accounts.voter.key(),
         timestamp: clock.unix_timestamp,
         vote_option,
-        vote_weight: vote_weight as u64,
+        vote_weight,
     });
 
     Ok(())
@@ -166,33 +196,35 @@ pub fn cast_vote(
 
 /// Context for finalizing a proposal after voting ends.
 #[derive(Accounts)]
+#[instruction(proposal_id: u64)]
 pub struct FinalizeProposal<'info> {
     /// The caller who finalizes the proposal (can be anyone since it's permissionless).
     #[account(mut)]
     pub caller: Signer<'info>,
     /// The platform configuration account to ensure governance is enabled.
-    #[account(has_one = authority @ OntoraError::UnauthorizedAccess)]
+    #[account(mut, seeds = [PLATFORM_CONFIG_SEED], bump = platform_config.bump)]
     pub platform_config: Account<'info, PlatformConfig>,
     /// The proposal account to finalize.
-    #[account(mut, seeds = [b"proposal", proposal.id.to_le_bytes().as_ref()], bump = proposal.bump)]
-    pub proposal: Account<'info, Proposal>,
+    #[account(mut, seeds = [PROPOSAL_SEED, proposal_id.to_le_bytes().as_ref()], bump = proposal.load()?.bump)]
+    pub proposal: AccountLoader<'info, Proposal>,
     /// The system program for account operations.
     pub system_program: Program<'info, System>,
 }
 
 impl<'info> FinalizeProposal<'info> {
-    /// Validates that the proposal can be finalized.
-    pub fn validate(&self) -> Result<()> {
+    /// Validates that the loaded proposal can be finalized.
+    pub fn validate(&self, proposal: &Proposal) -> Result<()> {
         // Check if governance is enabled.
         if !self.platform_config.governance_enabled {
             return err!(OntoraError::GovernanceDisabled);
         }
+        self.platform_config.require_not_paused(PAUSE_GOVERNANCE)?;
         // Check if the proposal is still active and voting period has ended.
         let clock = Clock::get()?;
-        if self.proposal.status != 0 {
+        if proposal.status != PROPOSAL_ACTIVE {
             return err!(OntoraError::ProposalAlreadyFinalized);
         }
-        if clock.unix_timestamp <= self.proposal.end_time {
+        if clock.unix_timestamp <= proposal.end_time {
             return err!(OntoraError::VotingPeriodNotEnded);
         }
         Ok(())
@@ -204,45 +236,168 @@ pub fn finalize_proposal(
     ctx: Context<FinalizeProposal>,
     proposal_id: u64,
 ) -> Result<()> {
+    let mut proposal = ctx.accounts.proposal.load_mut()?;
     // Validate the input and context.
-    ctx.accounts.validate()?;
-
-    let proposal = &mut ctx.accounts.proposal;
-    // Ensure the proposal ID matches.
-    if proposal.id != proposal_id {
-        return err!(OntoraError::InvalidProposal);
-    }
+    ctx.accounts.validate(&proposal)?;
 
     let clock = Clock::get()?;
-    // Determine the winning option (highest votes).
-    let mut max_votes = 0;
-    let mut winning_option = 0;
-    for (index, &votes) in proposal.votes.iter().enumerate() {
-        if votes > max_votes {
-            max_votes = votes;
-            winning_option = index as u8;
-        }
-    }
-
-    // Update proposal status (1 = Approved if there's a clear winner, 2 = Rejected if no votes or tied).
-    proposal.status = if max_votes > 0 { 1 } else { 2 };
+    // Approved only with quorum, a single leading option, and that option at the threshold.
+    let vote_unit = proposal.effective_vote_unit(&ctx.accounts.platform_config);
+    let (status, reason, winning_option) = proposal.outcome(vote_unit);
+    proposal.status = status;
+    proposal.winning_option = winning_option;
+    ctx.accounts.platform_config.active_proposal_count -= 1;
 
     // Serialize vote summary as a string for the event (simplified).
-    let vote_summary = format!("{:?}", proposal.votes);
+    let vote_summary = format!("{:?}", proposal.tallies());
 
     // Emit an event for proposal finalization.
     emit!(ProposalFinalized {
+        schema_version: ProposalFinalized::SCHEMA_VERSION,
         proposal_id,
         timestamp: clock.unix_timestamp,
         result: proposal.status,
         vote_summary,
+        reason,
     });
 
-    // Optionally, trigger platform updates if the proposal is approved.
-    // This is a placeholder; in a real implementation, apply changes based on proposal content.
-    if proposal.status == 1 {
+    // An approved proposal's action is applied separately, by `execute_proposal`.
+    if proposal.status == PROPOSAL_APPROVED {
         msg!("Proposal {} approved with option {}", proposal_id, winning_option);
     }
 
     Ok(())
 }
+
+/// Context for executing an approved proposal.
+#[derive(Accounts)]
+pub struct ExecuteProposal<'info> {
+    /// The caller who executes the proposal (can be anyone once it is approved).
+    pub executor: Signer<'info>,
+    /// The platform configuration the proposal's action changes.
+    #[account(mut, seeds = [PLATFORM_CONFIG_SEED], bump = platform_config.bump)]
+    pub platform_config: Account<'info, PlatformConfig>,
+    /// The approved proposal.
+    #[account(
+        mut,
+        seeds = [PROPOSAL_SEED, proposal.load()?.id.to_le_bytes().as_ref()],
+        bump = proposal.load()?.bump
+    )]
+    pub proposal: AccountLoader<'info, Proposal>,
+}
+
+impl<'info> ExecuteProposal<'info> {
+    /// Validates that the loaded proposal can be executed.
+    pub fn validate(&self, proposal: &Proposal) -> Result<()> {
+        // Check if governance is enabled.
+        if !self.platform_config.governance_enabled {
+            return err!(OntoraError::GovernanceDisabled);
+        }
+        self.platform_config.require_not_paused(PAUSE_GOVERNANCE)?;
+        // Only a proposal approved by its approving option runs, and only once.
+        if proposal.status == PROPOSAL_EXECUTED {
+            return err!(OntoraError::ProposalAlreadyExecuted);
+        }
+        if proposal.status != PROPOSAL_APPROVED || proposal.winning_option != PROPOSAL_APPROVE_OPTION {
+            return err!(OntoraError::ProposalNotApproved);
+        }
+        Ok(())
+    }
+}
+
+/// Instruction to apply an approved proposal's action to the platform configuration.
+pub fn execute_proposal(ctx: Context<ExecuteProposal>) -> Result<()> {
+    let mut proposal = ctx.accounts.proposal.load_mut()?;
+    // Validate the input and context.
+    ctx.accounts.validate(&proposal)?;
+
+    let clock = Clock::get()?;
+    // The action is checked again against the config as it is now, which may have changed
+    // since the proposal was created.
+    let action = proposal.action()?;
+    action.apply(&mut ctx.accounts.platform_config, clock.unix_timestamp)?;
+    proposal.status = PROPOSAL_EXECUTED;
+
+    // Emit an event for proposal execution.
+    emit!(ProposalExecuted {
+        schema_version: ProposalExecuted::SCHEMA_VERSION,
+        proposal_id: proposal.id,
+        executor: ctx.accounts.executor.key(),
+        action,
+        timestamp: clock.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+/// Context for enabling or disabling governance.
+#[derive(Accounts)]
+pub struct SetGovernanceEnabled<'info> {
+    /// The platform configuration holding the governance switch.
+    #[account(
+        mut,
+        seeds = [PLATFORM_CONFIG_SEED],
+        bump = platform_config.bump,
+        has_one = admin @ OntoraError::UnauthorizedAdmin
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    /// The platform admin.
+    pub admin: Signer<'info>,
+    // remaining_accounts: every unfinalized proposal, writable.
+}
+
+/// Instruction to toggle governance. Disabling moves every in-flight proposal
+/// to Suspended, which blocks voting but keeps the tallies; re-enabling resumes
+/// them with deadlines pushed back by the time they spent suspended. All
+/// unfinalized proposals must be passed so none is left running or stranded.
+pub fn set_governance_enabled<'info>(
+    ctx: Context<'_, '_, 'info, 'info, SetGovernanceEnabled<'info>>,
+    enabled: bool,
+) -> Result<()> {
+    let platform_config = &mut ctx.accounts.platform_config;
+    if platform_config.governance_enabled == enabled {
+        return err!(OntoraError::GovernanceActionNotAllowed);
+    }
+    if ctx.remaining_accounts.len() as u64 != platform_config.active_proposal_count {
+        return err!(OntoraError::ActiveProposalsRemaining);
+    }
+
+    let clock = Clock::get()?;
+    for info in ctx.remaining_accounts.iter() {
+        let loader = AccountLoader::<Proposal>::try_from(info)?;
+        let mut proposal = loader.load_mut()?;
+        let expected = Pubkey::create_program_address(
+            &[PROPOSAL_SEED, proposal.id.to_le_bytes().as_ref(), &[proposal.bump]],
+            &crate::ID,
+        )
+        .map_err(|_| OntoraError::InvalidAccount)?;
+        require_keys_eq!(expected, info.key(), OntoraError::InvalidAccount);
+
+        // A proposal passed twice fails here on its second pass, since its status already changed.
+        if enabled {
+            proposal.resume(clock.unix_timestamp)?;
+        } else {
+            proposal.suspend(clock.unix_timestamp)?;
+        }
+    }
+
+    platform_config.governance_enabled = enabled;
+    let count = ctx.remaining_accounts.len() as u64;
+    if enabled {
+        emit!(GovernanceResumed {
+            schema_version: GovernanceResumed::SCHEMA_VERSION,
+            admin: ctx.accounts.admin.key(),
+            resumed_proposals: count,
+            timestamp: clock.unix_timestamp,
+        });
+    } else {
+        emit!(GovernanceSuspended {
+            schema_version: GovernanceSuspended::SCHEMA_VERSION,
+            admin: ctx.accounts.admin.key(),
+            suspended_proposals: count,
+            timestamp: clock.unix_timestamp,
+        });
+    }
+
+    Ok(())
+}
@@ -1,7 +1,19 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+use crate::error::OntoraError;
+use crate::events::{
+    AdminTransferAccepted, AdminTransferCancelled, AdminTransferProposed, AgentRegistered, AgentUpdated,
+    PauseFlagsUpdated, PerformanceScoreUpdated, PlatformConfigMigrated, PlatformUpdated, RoleUpdated, StakeDeposited,
+    StakeWithdrawn,
+};
+use crate::migration::upgrade_account;
+use crate::pda::{
+    AI_AGENT_SEED, FEE_SCHEDULE_SEED, METADATA_SEED, PLATFORM_CONFIG_SEED, PROPOSAL_VOTE_SEED, REWARD_POOL_SEED,
+    REWARD_VAULT_SEED, STAKE_INDEX_SEED, STAKING_VAULT_SEED, TREASURY_SEED, TREASURY_VAULT_SEED, USER_STAKE_SEED,
+};
+use crate::realms::require_authority;
 use crate::state::*;
-use crate::ErrorCode;
+use crate::treasury::route_fee;
 
 // Initialize the platform configuration
 #[derive(Accounts)]
@@ -10,7 +22,7 @@ pub struct InitializePlatform<'info> {
         init,
         payer = admin,
         space = PlatformConfig::SPACE,
-        seeds = [b"platform-config"],
+        seeds = [PLATFORM_CONFIG_SEED],
         bump
     )]
     pub platform_config: Account<'info, PlatformConfig>,
@@ -24,17 +36,24 @@ pub fn initialize_platform(
     reward_rate_bps: u64,
     min_stake_amount: u64,
     epoch_duration: i64,
+    cooldown_seconds: i64,
+    epoch_emission: u64,
 ) -> Result<()> {
     let platform_config = &mut ctx.accounts.platform_config;
     let bump = ctx.bumps.platform_config;
 
+    PlatformConfig::validate_params(reward_rate_bps, min_stake_amount, epoch_duration, cooldown_seconds)?;
     platform_config.init(
         ctx.accounts.admin.key(),
         reward_rate_bps,
         min_stake_amount,
         epoch_duration,
+        cooldown_seconds,
+        epoch_emission,
         bump,
     );
+    // Rewards accrue from the platform's creation
+    platform_config.last_reward_timestamp = Clock::get()?.unix_timestamp;
 
     msg!("Platform initialized with admin: {}", ctx.accounts.admin.key());
     Ok(())
@@ -45,39 +64,428 @@ pub fn initialize_platform(
 pub struct UpdatePlatformConfig<'info> {
     #[account(
         mut,
-        seeds = [b"platform-config"],
-        bump = platform_config.bump,
-        has_one = admin @ ErrorCode::Unauthorized
+        seeds = [PLATFORM_CONFIG_SEED],
+        bump = platform_config.bump
     )]
     pub platform_config: Account<'info, PlatformConfig>,
+    /// The admin, or the native treasury of the governance set by
+    /// `set_governance_realm` (see `require_authority`).
     #[account(mut)]
     pub admin: Signer<'info>,
+    // remaining_accounts: the governance realm, when the governance executes.
 }
 
 pub fn update_platform_config(
     ctx: Context<UpdatePlatformConfig>,
-    reward_rate_bps: u64,
-    min_stake_amount: u64,
-    epoch_duration: i64,
+    params: ConfigParams,
+    update_metadata: String,
 ) -> Result<()> {
+    require!(
+        update_metadata.len() <= MAX_UPDATE_METADATA_LENGTH,
+        OntoraError::MetadataTooLarge
+    );
+    require_authority(
+        &ctx.accounts.platform_config.admin,
+        &ctx.accounts.admin,
+        ctx.remaining_accounts,
+        OntoraError::UnauthorizedAdmin,
+    )?;
+    let platform_config = &mut ctx.accounts.platform_config;
+    let previous = platform_config.params();
+
+    let now = Clock::get()?.unix_timestamp;
+    platform_config.update_params(params, now)?;
+
+    emit!(PlatformUpdated {
+        schema_version: PlatformUpdated::SCHEMA_VERSION,
+        authority: ctx.accounts.admin.key(),
+        timestamp: now,
+        new_reward_rate: params.reward_rate_bps,
+        update_metadata,
+        old_reward_rate: previous.reward_rate_bps,
+        old_min_stake_amount: previous.min_stake_amount,
+        new_min_stake_amount: params.min_stake_amount,
+        old_epoch_duration: previous.epoch_duration,
+        new_epoch_duration: params.epoch_duration,
+        old_cooldown_seconds: previous.cooldown_seconds,
+        new_cooldown_seconds: params.cooldown_seconds,
+        old_epoch_emission: previous.epoch_emission,
+        new_epoch_emission: params.epoch_emission,
+        old_max_stake_per_agent: previous.max_stake_per_agent,
+        new_max_stake_per_agent: params.max_stake_per_agent,
+        old_vesting_seconds: previous.vesting_seconds,
+        new_vesting_seconds: params.vesting_seconds,
+        old_vote_unit: previous.vote_unit,
+        new_vote_unit: params.vote_unit,
+        old_quorum_bps: previous.quorum_bps,
+        new_quorum_bps: params.quorum_bps,
+        old_approval_threshold_bps: previous.approval_threshold_bps,
+        new_approval_threshold_bps: params.approval_threshold_bps,
+    });
+    Ok(())
+}
+
+// Grow an older platform config to the current layout and stamp the program
+// version (admin only). Taken as an
+// unchecked account because an old layout fails to deserialize until reallocated.
+#[derive(Accounts)]
+pub struct MigratePlatformConfig<'info> {
+    /// CHECK: PDA and owner are checked here; admin is checked after the realloc
+    #[account(mut, seeds = [PLATFORM_CONFIG_SEED], bump, owner = crate::ID)]
+    pub platform_config: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn migrate_platform_config(ctx: Context<MigratePlatformConfig>) -> Result<()> {
+    // Fields appended since the account was created are zero-filled by the realloc
+    let from_version = upgrade_account::<PlatformConfig>(
+        &ctx.accounts.platform_config.to_account_info(),
+        &ctx.accounts.admin,
+        &ctx.accounts.system_program,
+    )?;
+
+    emit!(PlatformConfigMigrated {
+        schema_version: PlatformConfigMigrated::SCHEMA_VERSION,
+        admin: ctx.accounts.admin.key(),
+        from_version,
+        to_version: PLATFORM_CONFIG_VERSION,
+        program_version: PROGRAM_VERSION,
+    });
+    Ok(())
+}
+
+// Create the staking and reward vaults (admin only). Both are token accounts at
+// program PDAs: the platform config holds the staking vault and the reward pool
+// the reward vault, so the program signs every transfer out of them. The mint
+// must match the config's stake mint once one is pinned.
+#[derive(Accounts)]
+pub struct InitializeVaults<'info> {
+    #[account(
+        mut,
+        seeds = [PLATFORM_CONFIG_SEED],
+        bump = platform_config.bump,
+        has_one = admin @ OntoraError::UnauthorizedAdmin,
+        constraint = platform_config.stake_mint == Pubkey::default()
+            || platform_config.stake_mint == stake_mint.key() @ OntoraError::InvalidMint
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    /// CHECK: only the address is used, as the reward vault's authority; the pool may not exist yet
+    #[account(seeds = [REWARD_POOL_SEED], bump)]
+    pub reward_pool: UncheckedAccount<'info>,
+    pub stake_mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = admin,
+        seeds = [STAKING_VAULT_SEED],
+        bump,
+        token::mint = stake_mint,
+        token::authority = platform_config
+    )]
+    pub staking_vault: Account<'info, TokenAccount>,
+    #[account(
+        init,
+        payer = admin,
+        seeds = [REWARD_VAULT_SEED],
+        bump,
+        token::mint = stake_mint,
+        token::authority = reward_pool
+    )]
+    pub reward_vault: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn initialize_vaults(ctx: Context<InitializeVaults>) -> Result<()> {
+    let platform_config = &mut ctx.accounts.platform_config;
+    platform_config.stake_mint = ctx.accounts.stake_mint.key();
+    platform_config.staking_vault = ctx.accounts.staking_vault.key();
+    platform_config.reward_vault = ctx.accounts.reward_vault.key();
+
+    msg!("Vaults initialized for mint: {}", platform_config.stake_mint);
+    Ok(())
+}
+
+// Report the program and layout versions (no accounts)
+#[derive(Accounts)]
+pub struct GetVersion {}
+
+pub fn get_version(_ctx: Context<GetVersion>) -> Result<VersionInfo> {
+    Ok(VersionInfo {
+        program_version: PROGRAM_VERSION,
+        min_client_version: MIN_CLIENT_VERSION,
+        platform_config_version: PLATFORM_CONFIG_VERSION,
+    })
+}
+
+// Nominate a new admin (current admin only). The current admin keeps full
+// access until the nominee accepts.
+#[derive(Accounts)]
+pub struct ProposeAdmin<'info> {
+    #[account(
+        mut,
+        seeds = [PLATFORM_CONFIG_SEED],
+        bump = platform_config.bump,
+        has_one = admin @ OntoraError::UnauthorizedAdmin
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    pub admin: Signer<'info>,
+}
+
+pub fn propose_admin(ctx: Context<ProposeAdmin>, new_admin: Pubkey) -> Result<()> {
     let platform_config = &mut ctx.accounts.platform_config;
+    require!(
+        new_admin != Pubkey::default() && new_admin != platform_config.admin,
+        OntoraError::InvalidPendingAdmin
+    );
 
-    platform_config.reward_rate_bps = reward_rate_bps;
-    platform_config.min_stake_amount = min_stake_amount;
-    platform_config.epoch_duration = epoch_duration;
+    platform_config.pending_admin = new_admin;
 
-    msg!("Platform config updated by admin: {}", ctx.accounts.admin.key());
+    emit!(AdminTransferProposed {
+        schema_version: AdminTransferProposed::SCHEMA_VERSION,
+        admin: platform_config.admin,
+        pending_admin: new_admin,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+// Complete an admin transfer (pending admin only)
+#[derive(Accounts)]
+pub struct AcceptAdmin<'info> {
+    #[account(
+        mut,
+        seeds = [PLATFORM_CONFIG_SEED],
+        bump = platform_config.bump,
+        constraint = platform_config.has_pending_admin() @ OntoraError::NoPendingAdmin,
+        constraint = platform_config.pending_admin == new_admin.key() @ OntoraError::UnauthorizedAdmin
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    pub new_admin: Signer<'info>,
+}
+
+pub fn accept_admin(ctx: Context<AcceptAdmin>) -> Result<()> {
+    let platform_config = &mut ctx.accounts.platform_config;
+    let previous_admin = platform_config.admin;
+
+    platform_config.admin = platform_config.pending_admin;
+    platform_config.pending_admin = Pubkey::default();
+
+    emit!(AdminTransferAccepted {
+        schema_version: AdminTransferAccepted::SCHEMA_VERSION,
+        previous_admin,
+        new_admin: platform_config.admin,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+// Withdraw a pending nomination (current admin only)
+#[derive(Accounts)]
+pub struct CancelAdminTransfer<'info> {
+    #[account(
+        mut,
+        seeds = [PLATFORM_CONFIG_SEED],
+        bump = platform_config.bump,
+        has_one = admin @ OntoraError::UnauthorizedAdmin,
+        constraint = platform_config.has_pending_admin() @ OntoraError::NoPendingAdmin
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    pub admin: Signer<'info>,
+}
+
+pub fn cancel_admin_transfer(ctx: Context<CancelAdminTransfer>) -> Result<()> {
+    let platform_config = &mut ctx.accounts.platform_config;
+    let cancelled_admin = platform_config.pending_admin;
+
+    platform_config.pending_admin = Pubkey::default();
+
+    emit!(AdminTransferCancelled {
+        schema_version: AdminTransferCancelled::SCHEMA_VERSION,
+        admin: platform_config.admin,
+        cancelled_admin,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+// Set pause bits (pauser role only). The pauser can only add bits.
+#[derive(Accounts)]
+pub struct SetPauseFlags<'info> {
+    #[account(
+        mut,
+        seeds = [PLATFORM_CONFIG_SEED],
+        bump = platform_config.bump,
+        constraint = platform_config.has_role(Role::Pauser, &authority.key()) @ OntoraError::MissingRole
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    pub authority: Signer<'info>,
+}
+
+pub fn set_pause_flags(ctx: Context<SetPauseFlags>, flags: u8) -> Result<()> {
+    require!(flags != 0 && flags & !PAUSE_ALL == 0, OntoraError::InvalidPauseFlags);
+    let platform_config = &mut ctx.accounts.platform_config;
+    let previous_flags = platform_config.pause_flags;
+
+    platform_config.pause_flags |= flags;
+
+    emit!(PauseFlagsUpdated {
+        schema_version: PauseFlagsUpdated::SCHEMA_VERSION,
+        authority: ctx.accounts.authority.key(),
+        previous_flags,
+        new_flags: platform_config.pause_flags,
+    });
+    Ok(())
+}
+
+// Clear pause bits (admin only)
+#[derive(Accounts)]
+pub struct ClearPauseFlags<'info> {
+    #[account(
+        mut,
+        seeds = [PLATFORM_CONFIG_SEED],
+        bump = platform_config.bump,
+        has_one = admin @ OntoraError::UnauthorizedAdmin
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    pub admin: Signer<'info>,
+}
+
+pub fn clear_pause_flags(ctx: Context<ClearPauseFlags>, flags: u8) -> Result<()> {
+    require!(flags != 0 && flags & !PAUSE_ALL == 0, OntoraError::InvalidPauseFlags);
+    let platform_config = &mut ctx.accounts.platform_config;
+    let previous_flags = platform_config.pause_flags;
+
+    platform_config.pause_flags &= !flags;
+
+    emit!(PauseFlagsUpdated {
+        schema_version: PauseFlagsUpdated::SCHEMA_VERSION,
+        authority: ctx.accounts.admin.key(),
+        previous_flags,
+        new_flags: platform_config.pause_flags,
+    });
+    Ok(())
+}
+
+// Assign a delegated role to a new holder (admin only)
+#[derive(Accounts)]
+pub struct SetRole<'info> {
+    #[account(
+        mut,
+        seeds = [PLATFORM_CONFIG_SEED],
+        bump = platform_config.bump
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    /// The admin, or the native treasury of the governance set by `set_governance_realm`.
+    pub admin: Signer<'info>,
+    // remaining_accounts: the governance realm, when the governance executes.
+}
+
+pub fn set_role(ctx: Context<SetRole>, role: Role, holder: Pubkey) -> Result<()> {
+    require_authority(
+        &ctx.accounts.platform_config.admin,
+        &ctx.accounts.admin,
+        ctx.remaining_accounts,
+        OntoraError::UnauthorizedAdmin,
+    )?;
+    let platform_config = &mut ctx.accounts.platform_config;
+    let previous_holder = platform_config.role_holder(role);
+
+    platform_config.set_role_holder(role, holder);
+
+    emit!(RoleUpdated {
+        schema_version: RoleUpdated::SCHEMA_VERSION,
+        role,
+        previous_holder,
+        new_holder: holder,
+        authority: ctx.accounts.admin.key(),
+    });
+    Ok(())
+}
+
+// Give up a role (current holder only). The role stays vacant until the admin reassigns it.
+#[derive(Accounts)]
+#[instruction(role: Role)]
+pub struct RenounceRole<'info> {
+    #[account(
+        mut,
+        seeds = [PLATFORM_CONFIG_SEED],
+        bump = platform_config.bump,
+        constraint = platform_config.has_role(role, &holder.key()) @ OntoraError::MissingRole
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    pub holder: Signer<'info>,
+}
+
+pub fn renounce_role(ctx: Context<RenounceRole>, role: Role) -> Result<()> {
+    ctx.accounts.platform_config.set_role_holder(role, Pubkey::default());
+
+    emit!(RoleUpdated {
+        schema_version: RoleUpdated::SCHEMA_VERSION,
+        role,
+        previous_holder: ctx.accounts.holder.key(),
+        new_holder: Pubkey::default(),
+        authority: ctx.accounts.holder.key(),
+    });
+    Ok(())
+}
+
+// Post an agent's performance score (oracle role only)
+#[derive(Accounts)]
+pub struct UpdatePerformanceScore<'info> {
+    #[account(
+        seeds = [PLATFORM_CONFIG_SEED],
+        bump = platform_config.bump,
+        constraint = platform_config.has_role(Role::Oracle, &oracle.key()) @ OntoraError::MissingRole
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    #[account(
+        mut,
+        seeds = [AI_AGENT_SEED, ai_agent.owner.as_ref(), &ai_agent.agent_id.to_le_bytes()],
+        bump = ai_agent.bump
+    )]
+    pub ai_agent: Account<'info, AiAgent>,
+    pub oracle: Signer<'info>,
+}
+
+pub fn update_performance_score(ctx: Context<UpdatePerformanceScore>, score: u64) -> Result<()> {
+    ctx.accounts.platform_config.require_not_paused(PAUSE_ORACLE_UPDATES)?;
+    let ai_agent = &mut ctx.accounts.ai_agent;
+    let previous_score = ai_agent.performance_score;
+
+    let timestamp = Clock::get()?.unix_timestamp;
+    ai_agent.performance_score = score;
+    ai_agent.score_updated_at = timestamp;
+
+    emit!(PerformanceScoreUpdated {
+        schema_version: PerformanceScoreUpdated::SCHEMA_VERSION,
+        agent: ai_agent.key(),
+        previous_score,
+        new_score: score,
+        timestamp,
+    });
+    emit!(AgentUpdated {
+        schema_version: AgentUpdated::SCHEMA_VERSION,
+        agent_id: ai_agent.agent_id,
+        owner: ai_agent.owner,
+        timestamp,
+        new_metadata: ai_agent.compact_metadata(),
+    });
     Ok(())
 }
 
 // Register a new AI agent
 #[derive(Accounts)]
+#[instruction(agent_id: u64)]
 pub struct RegisterAiAgent<'info> {
     #[account(
         init,
         payer = owner,
         space = AiAgent::SPACE,
-        seeds = [b"ai-agent", owner.key().as_ref(), &agent_id.to_le_bytes()],
+        seeds = [AI_AGENT_SEED, owner.key().as_ref(), &agent_id.to_le_bytes()],
         bump
     )]
     pub ai_agent: Account<'info, AiAgent>,
@@ -96,9 +504,8 @@ pub fn register_ai_agent(
     let bump = ctx.bumps.ai_agent;
     let clock = Clock::get()?;
 
-    // Validate input lengths
-    require!(name.len() <= MAX_NAME_LENGTH, ErrorCode::MetadataTooLarge);
-    require!(description.len() <= MAX_DESCRIPTION_LENGTH, ErrorCode::MetadataTooLarge);
+    // Validate input byte lengths
+    AiAgent::validate_metadata(&name, &description)?;
 
     ai_agent.init(
         agent_id,
@@ -109,39 +516,92 @@ pub fn register_ai_agent(
         bump,
     );
 
-    msg!("AI Agent registered: ID {} by owner {}", agent_id, ctx.accounts.owner.key());
+    emit!(AgentRegistered {
+        schema_version: AgentRegistered::SCHEMA_VERSION,
+        agent_id,
+        owner: ai_agent.owner,
+        timestamp: clock.unix_timestamp,
+        metadata: ai_agent.compact_metadata(),
+    });
     Ok(())
 }
 
-// Stake tokens on an AI agent
+// Update an AI agent's name and description
 #[derive(Accounts)]
+#[instruction(agent_id: u64)]
+pub struct UpdateAgentMetadata<'info> {
+    #[account(
+        mut,
+        seeds = [AI_AGENT_SEED, owner.key().as_ref(), &agent_id.to_le_bytes()],
+        bump = ai_agent.bump,
+        has_one = owner @ OntoraError::UnauthorizedUser
+    )]
+    pub ai_agent: Account<'info, AiAgent>,
+    pub owner: Signer<'info>,
+}
+
+pub fn update_agent_metadata(
+    ctx: Context<UpdateAgentMetadata>,
+    agent_id: u64,
+    name: String,
+    description: String,
+) -> Result<()> {
+    // Validate input byte lengths
+    AiAgent::validate_metadata(&name, &description)?;
+
+    let ai_agent = &mut ctx.accounts.ai_agent;
+    ai_agent.name = name;
+    ai_agent.description = description;
+
+    emit!(AgentUpdated {
+        schema_version: AgentUpdated::SCHEMA_VERSION,
+        agent_id,
+        owner: ai_agent.owner,
+        timestamp: Clock::get()?.unix_timestamp,
+        new_metadata: ai_agent.compact_metadata(),
+    });
+    Ok(())
+}
+
+// Stake tokens on an AI agent. The position belongs to `beneficiary`, who alone can
+// unstake it and claim its rewards; the signer funds it and pays for its accounts.
+#[derive(Accounts)]
+#[instruction(agent_id: u64, amount: u64, beneficiary: Pubkey)]
 pub struct StakeOnAgent<'info> {
     #[account(
         mut,
-        seeds = [b"platform-config"],
+        seeds = [PLATFORM_CONFIG_SEED],
         bump = platform_config.bump
     )]
     pub platform_config: Account<'info, PlatformConfig>,
     #[account(
         mut,
-        seeds = [b"ai-agent", user.key().as_ref(), &agent_id.to_le_bytes()],
-        bump = ai_agent.bump,
-        has_one = owner @ ErrorCode::Unauthorized
+        seeds = [AI_AGENT_SEED, ai_agent.owner.as_ref(), &agent_id.to_le_bytes()],
+        bump = ai_agent.bump
     )]
     pub ai_agent: Account<'info, AiAgent>,
     #[account(
         init_if_needed,
         payer = user,
         space = UserStake::SPACE,
-        seeds = [b"user-stake", user.key().as_ref()],
+        seeds = [USER_STAKE_SEED, beneficiary.as_ref()],
         bump
     )]
     pub user_stake: Account<'info, UserStake>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = StakeIndexEntry::SPACE,
+        seeds = [STAKE_INDEX_SEED, beneficiary.as_ref(), &agent_id.to_le_bytes()],
+        bump
+    )]
+    pub stake_index: Account<'info, StakeIndexEntry>,
+    /// The funder: signs the token transfer and pays for new accounts.
     #[account(mut)]
     pub user: Signer<'info>,
     #[account(mut)]
     pub user_token_account: Account<'info, TokenAccount>,
-    #[account(mut)]
+    #[account(mut, address = platform_config.staking_vault @ OntoraError::InvalidVault)]
     pub platform_vault: Account<'info, TokenAccount>,
     pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
@@ -151,32 +611,51 @@ pub fn stake_on_agent(
     ctx: Context<StakeOnAgent>,
     agent_id: u64,
     amount: u64,
+    beneficiary: Pubkey,
 ) -> Result<()> {
     let platform_config = &mut ctx.accounts.platform_config;
     let ai_agent = &mut ctx.accounts.ai_agent;
     let user_stake = &mut ctx.accounts.user_stake;
     let clock = Clock::get()?;
 
+    platform_config.require_not_paused(PAUSE_DEPOSITS)?;
+
     // Validate stake amount
-    require!(amount >= platform_config.min_stake_amount, ErrorCode::InvalidStakeAmount);
+    require!(amount >= platform_config.min_stake_amount, OntoraError::InvalidStakeAmount);
+    let agent_stake = ai_agent.staked_amount.checked_add(amount).ok_or(OntoraError::ArithmeticError)?;
+    require!(
+        platform_config.max_stake_per_agent == 0 || agent_stake <= platform_config.max_stake_per_agent,
+        OntoraError::AgentStakeCapExceeded
+    );
 
-    // Initialize user stake if newly created
+    // Initialize the beneficiary's stake if newly created
     if user_stake.user == Pubkey::default() {
-        user_stake.init(ctx.accounts.user.key(), ctx.bumps.user_stake);
+        user_stake.init(beneficiary, ctx.bumps.user_stake);
     }
 
-    // Add agent to user's staked agents list
-    user_stake.add_staked_agent(agent_id)?;
-
-    // Update stake amounts
-    user_stake.staked_amount = user_stake.staked_amount.checked_add(amount).ok_or(ErrorCode::InvalidStakeAmount)?;
-    ai_agent.staked_amount = ai_agent.staked_amount.checked_add(amount).ok_or(ErrorCode::InvalidStakeAmount)?;
-    platform_config.total_staked = platform_config.total_staked.checked_add(amount).ok_or(ErrorCode::InvalidStakeAmount)?;
+    // Record the beneficiary's first stake on this agent
+    let stake_index = &mut ctx.accounts.stake_index;
+    if stake_index.user == Pubkey::default() {
+        stake_index.init(beneficiary, agent_id, clock.unix_timestamp, ctx.bumps.stake_index);
+    }
 
-    // Update timestamps
-    user_stake.last_stake_update = clock.unix_timestamp;
+    // Update stake amounts; the user's old stake is settled first so the new
+    // stake only earns from now
+    stake_index.staked_amount = stake_index.staked_amount.checked_add(amount).ok_or(OntoraError::ArithmeticError)?;
+    let acc_reward_per_share = platform_config.accrue(clock.unix_timestamp)?;
+    let staked_amount = user_stake.staked_amount.checked_add(amount).ok_or(OntoraError::ArithmeticError)?;
+    user_stake.settle(staked_amount, acc_reward_per_share)?;
+    ai_agent.staked_amount = agent_stake;
+    platform_config.total_staked = platform_config.total_staked.checked_add(amount).ok_or(OntoraError::ArithmeticError)?;
+
+    // Update timestamps; stake funded by someone else does not count as the beneficiary's change
+    if ctx.accounts.user.key() == beneficiary {
+        user_stake.record_own_change(clock.unix_timestamp);
+    } else {
+        user_stake.record_funded(amount, clock.unix_timestamp)?;
+    }
 
-    // Transfer tokens from user to platform vault
+    // Transfer tokens from the funder to the platform vault
     let cpi_accounts = Transfer {
         from: ctx.accounts.user_token_account.to_account_info(),
         to: ctx.accounts.platform_vault.to_account_info(),
@@ -186,73 +665,237 @@ pub fn stake_on_agent(
     let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
     token::transfer(cpi_ctx, amount)?;
 
-    msg!("User {} staked {} on agent {}", ctx.accounts.user.key(), amount, agent_id);
+    emit!(StakeDeposited {
+        schema_version: StakeDeposited::SCHEMA_VERSION,
+        user: beneficiary,
+        agent_id,
+        amount,
+        timestamp: clock.unix_timestamp,
+        staking_duration: 0,
+    });
+    msg!("User {} staked {} on agent {} for {}", ctx.accounts.user.key(), amount, agent_id, beneficiary);
     Ok(())
 }
 
-// Claim accumulated rewards
+// Withdraw tokens staked on an AI agent
 #[derive(Accounts)]
-pub struct ClaimRewards<'info> {
+#[instruction(agent_id: u64)]
+pub struct UnstakeFromAgent<'info> {
     #[account(
         mut,
-        seeds = [b"platform-config"],
+        seeds = [PLATFORM_CONFIG_SEED],
         bump = platform_config.bump
     )]
     pub platform_config: Account<'info, PlatformConfig>,
     #[account(
         mut,
-        seeds = [b"user-stake", user.key().as_ref()],
+        seeds = [AI_AGENT_SEED, ai_agent.owner.as_ref(), &agent_id.to_le_bytes()],
+        bump = ai_agent.bump
+    )]
+    pub ai_agent: Account<'info, AiAgent>,
+    #[account(
+        mut,
+        seeds = [USER_STAKE_SEED, user.key().as_ref()],
         bump = user_stake.bump,
-        has_one = user @ ErrorCode::Unauthorized
+        has_one = user @ OntoraError::UnauthorizedUser
     )]
     pub user_stake: Account<'info, UserStake>,
+    #[account(
+        mut,
+        seeds = [STAKE_INDEX_SEED, user.key().as_ref(), &agent_id.to_le_bytes()],
+        bump = stake_index.bump,
+        has_one = user @ OntoraError::UnauthorizedUser
+    )]
+    pub stake_index: Account<'info, StakeIndexEntry>,
     #[account(mut)]
     pub user: Signer<'info>,
     #[account(mut)]
     pub user_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        address = platform_config.staking_vault @ OntoraError::InvalidVault,
+        token::mint = stake_mint,
+        token::authority = platform_config
+    )]
+    pub platform_vault: Account<'info, TokenAccount>,
+    pub stake_mint: Account<'info, Mint>,
+    #[account(mut, seeds = [FEE_SCHEDULE_SEED], bump = fee_schedule.bump)]
+    pub fee_schedule: Account<'info, FeeSchedule>,
+    #[account(mut, seeds = [TREASURY_SEED], bump = treasury.bump)]
+    pub treasury: Account<'info, Treasury>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        seeds = [TREASURY_VAULT_SEED, stake_mint.key().as_ref()],
+        bump,
+        token::mint = stake_mint,
+        token::authority = treasury
+    )]
+    pub treasury_vault: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn unstake_from_agent(
+    ctx: Context<UnstakeFromAgent>,
+    agent_id: u64,
+    amount: u64,
+) -> Result<()> {
+    let clock = Clock::get()?;
+    ctx.accounts.platform_config.require_not_paused(PAUSE_WITHDRAWALS)?;
+    require!(
+        amount > 0 && amount <= ctx.accounts.stake_index.staked_amount,
+        OntoraError::InvalidStakeAmount
+    );
+
+    // Update stake amounts
+    let stake_index = &mut ctx.accounts.stake_index;
+    stake_index.staked_amount -= amount;
+    // The withdrawn tokens keep what they earned up to now and stop earning
+    let platform_config = &mut ctx.accounts.platform_config;
+    let acc_reward_per_share = platform_config.accrue(clock.unix_timestamp)?;
+    let user_stake = &mut ctx.accounts.user_stake;
+    let staked_amount = user_stake.staked_amount.checked_sub(amount).ok_or(OntoraError::ArithmeticError)?;
+    user_stake.settle(staked_amount, acc_reward_per_share)?;
+    user_stake.record_own_change(clock.unix_timestamp);
+    let ai_agent = &mut ctx.accounts.ai_agent;
+    ai_agent.staked_amount = ai_agent.staked_amount.checked_sub(amount).ok_or(OntoraError::ArithmeticError)?;
+    platform_config.total_staked = platform_config.total_staked.checked_sub(amount).ok_or(OntoraError::ArithmeticError)?;
+
+    // Route the unstake fee to the treasury; the user receives the rest. The
+    // vault is owned by the platform config PDA, which signs both transfers.
+    let seeds = &[PLATFORM_CONFIG_SEED, &[ctx.accounts.platform_config.bump]];
+    let signer = &[&seeds[..]];
+    ctx.accounts.fee_schedule.settle(clock.unix_timestamp);
+    let (net, fee) = ctx.accounts.fee_schedule.apply_fee(amount, FeeType::Unstake)?;
+    route_fee(
+        &ctx.accounts.token_program,
+        ctx.accounts.platform_vault.to_account_info(),
+        ctx.accounts.platform_config.to_account_info(),
+        signer,
+        &mut ctx.accounts.treasury,
+        &ctx.accounts.treasury_vault,
+        FeeSource::Unstake,
+        fee,
+    )?;
+
+    // Transfer the rest from platform vault to user
+    let cpi_accounts = Transfer {
+        from: ctx.accounts.platform_vault.to_account_info(),
+        to: ctx.accounts.user_token_account.to_account_info(),
+        authority: ctx.accounts.platform_config.to_account_info(),
+    };
+    let cpi_program = ctx.accounts.token_program.to_account_info();
+    let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+    token::transfer(cpi_ctx, net)?;
+
+    // A full exit closes the index entry and returns its rent to the user
+    if ctx.accounts.stake_index.staked_amount == 0 {
+        ctx.accounts.stake_index.close(ctx.accounts.user.to_account_info())?;
+    }
+
+    emit!(StakeWithdrawn {
+        schema_version: StakeWithdrawn::SCHEMA_VERSION,
+        user: ctx.accounts.user.key(),
+        agent_id,
+        amount,
+        timestamp: clock.unix_timestamp,
+    });
+    msg!("User {} unstaked {} from agent {}", ctx.accounts.user.key(), amount, agent_id);
+    Ok(())
+}
+
+// Claim a user stake's accumulated rewards
+#[derive(Accounts)]
+pub struct ClaimStakeRewards<'info> {
+    #[account(
+        mut,
+        seeds = [PLATFORM_CONFIG_SEED],
+        bump = platform_config.bump
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    #[account(
+        mut,
+        seeds = [USER_STAKE_SEED, user.key().as_ref()],
+        bump = user_stake.bump,
+        has_one = user @ OntoraError::UnauthorizedUser
+    )]
+    pub user_stake: Account<'info, UserStake>,
+    #[account(mut)]
+    pub user: Signer<'info>,
     #[account(mut)]
+    pub user_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        address = platform_config.staking_vault @ OntoraError::InvalidVault,
+        token::mint = reward_mint,
+        token::authority = platform_config
+    )]
     pub platform_vault: Account<'info, TokenAccount>,
+    pub reward_mint: Account<'info, Mint>,
+    #[account(mut, seeds = [FEE_SCHEDULE_SEED], bump = fee_schedule.bump)]
+    pub fee_schedule: Account<'info, FeeSchedule>,
+    #[account(mut, seeds = [TREASURY_SEED], bump = treasury.bump)]
+    pub treasury: Account<'info, Treasury>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        seeds = [TREASURY_VAULT_SEED, reward_mint.key().as_ref()],
+        bump,
+        token::mint = reward_mint,
+        token::authority = treasury
+    )]
+    pub treasury_vault: Account<'info, TokenAccount>,
     pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
 }
 
-pub fn claim_rewards(ctx: Context<ClaimRewards>) -> Result<()> {
+pub fn claim_stake_rewards(ctx: Context<ClaimStakeRewards>) -> Result<()> {
     let platform_config = &mut ctx.accounts.platform_config;
     let user_stake = &mut ctx.accounts.user_stake;
     let clock = Clock::get()?;
 
-    // Calculate elapsed epochs since last claim
+    platform_config.require_not_paused(PAUSE_CLAIMS)?;
+
+    // Claims are allowed once per epoch
     let elapsed_time = clock.unix_timestamp - user_stake.last_reward_claim;
-    let elapsed_epochs = elapsed_time / platform_config.epoch_duration;
-    if elapsed_epochs <= 0 {
-        return err!(ErrorCode::NoRewardsToClaim);
+    if elapsed_time < platform_config.epoch_duration {
+        return err!(OntoraError::NoRewardsAvailable);
     }
 
-    // Calculate rewards based on staked amount and reward rate
-    let reward_per_epoch = (user_stake.staked_amount as u128)
-        .checked_mul(platform_config.reward_rate_bps as u128)
-        .ok_or(ErrorCode::InvalidStakeAmount)?
-        .checked_div(10000) // Convert basis points to percentage
-        .ok_or(ErrorCode::InvalidStakeAmount)? as u64;
-    let total_reward = reward_per_epoch
-        .checked_mul(elapsed_epochs as u64)
-        .ok_or(ErrorCode::InvalidStakeAmount)?;
-
-    // Update accumulated rewards and reset claim timestamp
-    user_stake.accumulated_rewards = user_stake.accumulated_rewards
-        .checked_add(total_reward)
-        .ok_or(ErrorCode::InvalidStakeAmount)?;
+    // Claim everything pending and reset the accumulated balance
+    let acc_reward_per_share = platform_config.accrue(clock.unix_timestamp)?;
+    let staked_amount = user_stake.staked_amount;
+    user_stake.settle(staked_amount, acc_reward_per_share)?;
     let reward_to_claim = user_stake.accumulated_rewards;
     user_stake.accumulated_rewards = 0;
     user_stake.last_reward_claim = clock.unix_timestamp;
 
+    // Route the claim fee to the treasury; the user receives the rest. The
+    // vault is owned by the platform config PDA, which signs both transfers.
+    let seeds = &[PLATFORM_CONFIG_SEED, &[ctx.accounts.platform_config.bump]];
+    let signer = &[&seeds[..]];
+    ctx.accounts.fee_schedule.settle(clock.unix_timestamp);
+    let (reward_to_claim, fee) = ctx.accounts.fee_schedule.apply_fee(reward_to_claim, FeeType::Claim)?;
+    route_fee(
+        &ctx.accounts.token_program,
+        ctx.accounts.platform_vault.to_account_info(),
+        ctx.accounts.platform_config.to_account_info(),
+        signer,
+        &mut ctx.accounts.treasury,
+        &ctx.accounts.treasury_vault,
+        FeeSource::Claim,
+        fee,
+    )?;
+
     // Transfer rewards from platform vault to user
     let cpi_accounts = Transfer {
         from: ctx.accounts.platform_vault.to_account_info(),
         to: ctx.accounts.user_token_account.to_account_info(),
-        authority: ctx.accounts.platform_vault.to_account_info(), // Assumes vault has delegated authority or program owns it
+        authority: ctx.accounts.platform_config.to_account_info(),
     };
     let cpi_program = ctx.accounts.token_program.to_account_info();
-    let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+    let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
     token::transfer(cpi_ctx, reward_to_claim)?;
 
     msg!("User {} claimed rewards: {}", ctx.accounts.user.key(), reward_to_claim);
@@ -264,15 +907,15 @@ pub fn claim_rewards(ctx: Context<ClaimRewards>) -> Result<()> {
 pub struct VoteOnProposal<'info> {
     #[account(
         mut,
-        seeds = [b"platform-config"],
+        seeds = [PLATFORM_CONFIG_SEED],
         bump = platform_config.bump
     )]
     pub platform_config: Account<'info, PlatformConfig>,
     #[account(
         mut,
-        seeds = [b"user-stake", voter.key().as_ref()],
+        seeds = [USER_STAKE_SEED, voter.key().as_ref()],
         bump = user_stake.bump,
-        has_one = voter @ ErrorCode::Unauthorized
+        has_one = voter @ OntoraError::UnauthorizedUser
     )]
     pub user_stake: Account<'info, UserStake>,
     #[account(mut)]
@@ -281,7 +924,7 @@ pub struct VoteOnProposal<'info> {
         init_if_needed,
         payer = voter,
         space = Metadata::SPACE,
-        seeds = [b"proposal-vote", &proposal_id.to_le_bytes(), voter.key().as_ref()],
+        seeds = [PROPOSAL_VOTE_SEED, &proposal_id.to_le_bytes(), voter.key().as_ref()],
         bump
     )]
     pub vote_record: Account<'info, Metadata>,
@@ -297,8 +940,10 @@ pub fn vote_on_proposal(
     let vote_record = &mut ctx.accounts.vote_record;
     let clock = Clock::get()?;
 
+    ctx.accounts.platform_config.require_not_paused(PAUSE_GOVERNANCE)?;
+
     // Ensure user has staked tokens to have voting power
-    require!(user_stake.staked_amount > 0, ErrorCode::InvalidStakeAmount);
+    require!(user_stake.staked_amount > 0, OntoraError::InvalidStakeAmount);
 
     // Record the vote (simplified as metadata)
     let vote_data = format!("Vote: {}", if in_favor { "Yes" } else { "No" });
@@ -313,15 +958,54 @@ pub fn vote_on_proposal(
     Ok(())
 }
 
-// Custom error for reward claiming
-#[error_code]
-pub enum ErrorCode {
-    #[msg("Unauthorized access.")]
-    Unauthorized,
-    #[msg("Invalid stake amount.")]
-    InvalidStakeAmount,
-    #[msg("Metadata data too large.")]
-    MetadataTooLarge,
-    #[msg("No rewards available to claim.")]
-    NoRewardsToClaim,
+// Create a metadata account for an entity, sized to its payload
+#[derive(Accounts)]
+#[instruction(entity_id: u64, data: String)]
+pub struct CreateMetadata<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = Metadata::space_for(data.len()),
+        seeds = [METADATA_SEED, authority.key().as_ref(), &entity_id.to_le_bytes()],
+        bump
+    )]
+    pub metadata: Account<'info, Metadata>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn create_metadata(ctx: Context<CreateMetadata>, entity_id: u64, data: String) -> Result<()> {
+    Metadata::validate_data(&data)?;
+    let clock = Clock::get()?;
+    ctx.accounts.metadata.init(entity_id, data, clock.unix_timestamp, ctx.bumps.metadata);
+    Ok(())
+}
+
+// Replace an entity's metadata, resizing the account to the new payload. The
+// authority pays the rent for growth and is refunded the excess on shrink.
+#[derive(Accounts)]
+#[instruction(entity_id: u64, data: String)]
+pub struct UpdateMetadata<'info> {
+    #[account(
+        mut,
+        seeds = [METADATA_SEED, authority.key().as_ref(), &entity_id.to_le_bytes()],
+        bump = metadata.bump,
+        realloc = Metadata::space_for(data.len()),
+        realloc::payer = authority,
+        realloc::zero = false
+    )]
+    pub metadata: Account<'info, Metadata>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn update_metadata(ctx: Context<UpdateMetadata>, _entity_id: u64, data: String) -> Result<()> {
+    // Checked here as well as sized above; an oversized payload reverts the realloc
+    Metadata::validate_data(&data)?;
+    let metadata = &mut ctx.accounts.metadata;
+    metadata.data = data;
+    metadata.updated_at = Clock::get()?.unix_timestamp;
+    Ok(())
 }
@@ -2,46 +2,198 @@ use anchor_lang::prelude::*;
 use anchor_lang::solana_program::clock::Clock;
 use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
 
+use crate::error::OntoraError;
+use crate::pda::{
+    FEE_SCHEDULE_SEED, LEGACY_AI_AGENT_SEED, PENDING_WITHDRAWAL_SEED, PLATFORM_CONFIG_SEED, PROPOSAL_SEED,
+    REWARD_POOL_SEED, REWARD_VESTING_SEED, TREASURY_SEED, TREASURY_VAULT_SEED,
+};
+
+pub mod error;
+pub mod events;
+pub mod fees;
+pub mod governance;
+pub mod instructions;
+pub mod math;
+pub mod migration;
+pub mod multisig;
+pub mod pda;
+pub mod realms;
+pub mod state;
+pub mod treasury;
+
+pub use fees::{InitializeFeeSchedule, UpdateFeeSchedule};
+pub use governance::{CastVote, CreateGovernanceProposal, ExecuteProposal, FinalizeProposal, SetGovernanceEnabled};
+pub use instructions::{
+    AcceptAdmin, CancelAdminTransfer, ClaimStakeRewards, ClearPauseFlags, CreateMetadata, GetVersion,
+    InitializePlatform, InitializeVaults, MigratePlatformConfig, ProposeAdmin, RegisterAiAgent, RenounceRole,
+    SetPauseFlags, SetRole, StakeOnAgent, UnstakeFromAgent, UpdateAgentMetadata, UpdateMetadata,
+    UpdatePerformanceScore, UpdatePlatformConfig, VoteOnProposal,
+};
+pub use migration::{MigrateAccount, MigrateAgentV1, Versioned, AI_AGENT_V1, MIGRATED_AGENT_ID, MIGRATED_AGENT_NAME};
+pub use multisig::{
+    ApproveAdminAction, ExecuteAdminAction, ExecuteInitializeVaults, InitializeAdminMultisig, ProposeAdminAction,
+};
+pub use realms::SetGovernanceRealm;
+pub use treasury::{InitializeTreasury, TreasuryBalance, TreasuryTransfer};
+
+// `#[program]` reaches each Accounts struct's generated client modules through the crate root
+pub(crate) use fees::{__client_accounts_initialize_fee_schedule, __client_accounts_update_fee_schedule};
+pub(crate) use governance::{
+    __client_accounts_cast_vote, __client_accounts_create_governance_proposal, __client_accounts_execute_proposal,
+    __client_accounts_finalize_proposal, __client_accounts_set_governance_enabled,
+};
+pub(crate) use instructions::{
+    __client_accounts_accept_admin, __client_accounts_cancel_admin_transfer, __client_accounts_claim_stake_rewards,
+    __client_accounts_clear_pause_flags, __client_accounts_create_metadata, __client_accounts_get_version,
+    __client_accounts_initialize_platform, __client_accounts_initialize_vaults,
+    __client_accounts_migrate_platform_config, __client_accounts_propose_admin, __client_accounts_renounce_role,
+    __client_accounts_set_pause_flags, __client_accounts_set_role, __client_accounts_stake_on_agent,
+    __client_accounts_unstake_from_agent, __client_accounts_update_agent_metadata, __client_accounts_update_metadata,
+    __client_accounts_update_performance_score, __client_accounts_update_platform_config,
+};
+pub(crate) use migration::{__client_accounts_migrate_account, __client_accounts_migrate_agent_v1};
+pub(crate) use multisig::{
+    __client_accounts_approve_admin_action, __client_accounts_execute_admin_action,
+    __client_accounts_execute_initialize_vaults, __client_accounts_initialize_admin_multisig,
+    __client_accounts_propose_admin_action,
+};
+pub(crate) use realms::__client_accounts_set_governance_realm;
+pub(crate) use treasury::{
+    __client_accounts_initialize_treasury, __client_accounts_treasury_balance, __client_accounts_treasury_transfer,
+};
+#[cfg(feature = "cpi")]
+pub(crate) use crate::{
+    fees::{__cpi_client_accounts_initialize_fee_schedule, __cpi_client_accounts_update_fee_schedule},
+    governance::{
+        __cpi_client_accounts_cast_vote, __cpi_client_accounts_create_governance_proposal,
+        __cpi_client_accounts_execute_proposal, __cpi_client_accounts_finalize_proposal,
+        __cpi_client_accounts_set_governance_enabled,
+    },
+    instructions::{
+        __cpi_client_accounts_accept_admin, __cpi_client_accounts_cancel_admin_transfer,
+        __cpi_client_accounts_claim_stake_rewards, __cpi_client_accounts_clear_pause_flags,
+        __cpi_client_accounts_create_metadata, __cpi_client_accounts_get_version,
+        __cpi_client_accounts_initialize_platform, __cpi_client_accounts_initialize_vaults,
+        __cpi_client_accounts_migrate_platform_config, __cpi_client_accounts_propose_admin,
+        __cpi_client_accounts_renounce_role, __cpi_client_accounts_set_pause_flags, __cpi_client_accounts_set_role,
+        __cpi_client_accounts_stake_on_agent, __cpi_client_accounts_unstake_from_agent,
+        __cpi_client_accounts_update_agent_metadata, __cpi_client_accounts_update_metadata,
+        __cpi_client_accounts_update_performance_score, __cpi_client_accounts_update_platform_config,
+    },
+    migration::{__cpi_client_accounts_migrate_account, __cpi_client_accounts_migrate_agent_v1},
+    multisig::{
+        __cpi_client_accounts_approve_admin_action, __cpi_client_accounts_execute_admin_action,
+        __cpi_client_accounts_execute_initialize_vaults, __cpi_client_accounts_initialize_admin_multisig,
+        __cpi_client_accounts_propose_admin_action,
+    },
+    realms::__cpi_client_accounts_set_governance_realm,
+    treasury::{
+        __cpi_client_accounts_initialize_treasury, __cpi_client_accounts_treasury_balance,
+        __cpi_client_accounts_treasury_transfer,
+    },
+};
+
 // Declare the program ID for the smart contract
 declare_id!("YourProgramIDHere"); // Replace with your actual program ID after deployment
 
 // Constants for staking and rewards
-const STAKING_COOLDOWN: i64 = 86400; // 24 hours in seconds for unstaking cooldown
-const REWARD_RATE: u64 = 100; // Reward rate per epoch (adjustable)
-const EPOCH_DURATION: i64 = 604800; // 7 days in seconds for reward epoch
-
-// Custom error codes for the program
-#[error_code]
-pub enum OntoraError {
-    #[msg("Insufficient stake amount")]
-    InsufficientStake,
-    #[msg("Staking cooldown not completed")]
-    CooldownNotCompleted,
-    #[msg("Invalid AI agent owner")]
-    InvalidOwner,
-    #[msg("Governance proposal already active")]
-    ProposalActive,
-    #[msg("Insufficient voting power")]
-    InsufficientVotingPower,
-    #[msg("Reward pool depleted")]
-    RewardPoolDepleted,
+pub const EPOCH_DURATION: i64 = 604800; // 7 days in seconds for reward epoch
+// Unix time reward epoch 0 starts at; each following epoch is EPOCH_DURATION long
+pub const REWARD_GENESIS: i64 = 0;
+
+// Index of the reward epoch `timestamp` falls in
+pub fn reward_epoch(timestamp: i64) -> u64 {
+    timestamp.saturating_sub(REWARD_GENESIS).div_euclid(EPOCH_DURATION).max(0) as u64
 }
 
+// Layout version of the legacy AIAgent; v1 accounts carried no version field
+pub const LEGACY_AI_AGENT_VERSION: u8 = 2;
+
 // Account structure for an AI Agent
 #[account]
+#[derive(InitSpace)]
 pub struct AIAgent {
+    pub version: u8, // Layout version (LEGACY_AI_AGENT_VERSION)
     pub owner: Pubkey, // Owner of the AI agent
     pub staked_amount: u64, // Amount of tokens staked
     pub last_stake_time: i64, // Timestamp of last staking action
     pub accumulated_rewards: u64, // Accumulated rewards for this agent
     pub is_active: bool, // Whether the agent is active
     pub bump: u8, // Bump seed for PDA derivation
+    pub last_reward_epoch: u64, // Reward epoch the agent was last paid in (0 if never)
+}
+
+impl AIAgent {
+    // Seconds until the platform's unstaking cooldown ends (0 once it has)
+    pub fn cooldown_remaining(&self, config: &state::PlatformConfig, now: i64) -> i64 {
+        math::cooldown_remaining(self.last_stake_time, config.cooldown_seconds, now)
+    }
+}
+
+// Stake requested out of a legacy agent with `request_unstake`, waiting in the
+// agent's vault for `claim_unstake`
+#[account]
+#[derive(InitSpace)]
+pub struct PendingWithdrawal {
+    pub owner: Pubkey, // Owner of the agent the stake was requested from
+    pub amount: u64, // Amount to pay out, before the unstake fee
+    pub release_time: i64, // When the withdrawal can be claimed
+    pub bump: u8, // Bump seed for PDA derivation
+}
+
+// Rewards a legacy agent's owner claimed while `vesting_seconds` was set. They
+// stay in the reward vault and vest linearly; `release_vested` pays what has.
+#[account]
+#[derive(InitSpace)]
+pub struct RewardVesting {
+    pub owner: Pubkey, // Whose rewards vest
+    pub total_amount: u64, // Rewards on the current schedule
+    pub released_amount: u64, // Of those, paid out so far
+    pub start_time: i64, // When the current schedule started
+    pub duration: i64, // Seconds the schedule vests over
+    pub bump: u8, // Bump seed for PDA derivation
+}
+
+impl RewardVesting {
+    // Vested on the schedule and not yet released
+    pub fn releasable(&self, now: i64) -> u64 {
+        math::vested_amount(self.total_amount, self.start_time, self.duration, now)
+            .saturating_sub(self.released_amount)
+    }
+
+    // Mark what has vested as released and return it
+    pub fn release(&mut self, now: i64) -> u64 {
+        let amount = self.releasable(now);
+        self.released_amount += amount;
+        amount
+    }
+
+    // Start a new schedule at `now` holding what had not vested plus `amount`.
+    // It ends at the amount-weighted mean of the old end and `now + duration`,
+    // so the unvested rest is not pushed out by a full period on every claim.
+    // Returns what had vested and not been released, for the caller to pay out.
+    pub fn extend(&mut self, amount: u64, now: i64, duration: i64) -> Result<u64> {
+        let vested = math::vested_amount(self.total_amount, self.start_time, self.duration, now);
+        let releasable = vested.saturating_sub(self.released_amount);
+        let unvested = self.total_amount - vested;
+        let remaining = math::cooldown_remaining(self.start_time, self.duration, now);
+        self.total_amount = unvested.checked_add(amount).ok_or(OntoraError::ArithmeticError)?;
+        self.released_amount = 0;
+        self.start_time = now;
+        self.duration = math::weighted_vesting_duration(unvested, remaining, amount, duration);
+        Ok(releasable)
+    }
 }
 
+// Max GovernanceProposal description size in bytes (not chars; a 4-byte emoji takes four)
+pub const MAX_GOVERNANCE_DESCRIPTION_LENGTH: usize = 200;
+
 // Account structure for Governance Proposal
 #[account]
+#[derive(InitSpace)]
 pub struct GovernanceProposal {
     pub proposer: Pubkey, // Who proposed this
+    #[max_len(MAX_GOVERNANCE_DESCRIPTION_LENGTH)]
     pub description: String, // Description of the proposal (limited length)
     pub yes_votes: u64, // Votes in favor
     pub no_votes: u64, // Votes against
@@ -51,15 +203,60 @@ pub struct GovernanceProposal {
     pub bump: u8, // Bump seed for PDA derivation
 }
 
+impl GovernanceProposal {
+    pub const MAX_DESCRIPTION_LENGTH: usize = MAX_GOVERNANCE_DESCRIPTION_LENGTH;
+
+    // Reject empty descriptions and ones that overflow the allocated space
+    pub fn validate_description(description: &str) -> Result<()> {
+        require!(!description.is_empty(), OntoraError::DescriptionEmpty);
+        require!(
+            description.len() <= Self::MAX_DESCRIPTION_LENGTH,
+            OntoraError::DescriptionTooLong
+        );
+        Ok(())
+    }
+}
+
 // Account structure for Reward Pool
 #[account]
+#[derive(InitSpace)]
 pub struct RewardPool {
     pub total_rewards: u64, // Total rewards available in the pool
-    pub last_updated: i64, // Last time the pool was updated
+    pub last_updated: i64, // When the pool was initialized or last closed an epoch
     pub bump: u8, // Bump seed for PDA derivation
 }
 
+impl RewardPool {
+    // First epoch the pool pays in: the one after the epoch it was initialized
+    // or last closed in. It is at least 1, so an agent never paid cannot match it.
+    pub fn open_epoch(&self) -> u64 {
+        reward_epoch(self.last_updated) + 1
+    }
+}
+
 // Program entrypoint and instructions
+//
+// Other programs compose with these over CPI: built with the `cpi` feature,
+// this crate has no entrypoint and Anchor generates `ontora_ai::cpi`, one
+// function per instruction with its accounts in `ontora_ai::cpi::accounts`
+// (tests/composer is a worked example).
+//
+// CPI-safe, with any signer a PDA the calling program signs for:
+// fund_reward_pool, register_ai_agent, increase_stake, unstake_ai_agent,
+// unstake_partial, emergency_unstake, request_unstake, claim_unstake,
+// claim_rewards, release_vested, create_proposal,
+// vote_proposal, register_agent, update_agent_metadata, stake_on_agent,
+// unstake_from_agent, claim_stake_rewards,
+// create_metadata, update_metadata,
+// create_governance_proposal, cast_vote, finalize_proposal, and the views
+// get_version and treasury_balance, read through `cpi::Return`.
+//
+// Signer-only: the admin, role, multisig and treasury instructions check
+// their signer against a key the platform records, so a program can call
+// them only once made that authority. Those taking the admin also accept the
+// realm's native treasury, and then only over CPI (see
+// `realms::require_authority`). The one-time initializers are for the
+// deployer's keypair.
 #[program]
 pub mod ontora_ai {
     use super::*;
@@ -73,6 +270,31 @@ pub mod ontora_ai {
         Ok(())
     }
 
+    // Top up the reward pool; anyone may fund it
+    pub fn fund_reward_pool(ctx: Context<FundRewardPool>, amount: u64) -> Result<()> {
+        require!(amount > 0, OntoraError::InvalidStakeAmount);
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.funder_token_account.to_account_info(),
+            to: ctx.accounts.reward_vault.to_account_info(),
+            authority: ctx.accounts.funder.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+        token::transfer(cpi_ctx, amount)?;
+
+        let reward_pool = &mut ctx.accounts.reward_pool;
+        reward_pool.total_rewards = reward_pool.total_rewards.checked_add(amount).ok_or(OntoraError::ArithmeticError)?;
+
+        emit!(events::RewardPoolFunded {
+            schema_version: events::RewardPoolFunded::SCHEMA_VERSION,
+            funder: ctx.accounts.funder.key(),
+            amount,
+            total_rewards: reward_pool.total_rewards,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
     // Register a new AI agent with staking
     pub fn register_ai_agent(ctx: Context<RegisterAIAgent>, stake_amount: u64) -> Result<()> {
         let ai_agent = &mut ctx.accounts.ai_agent;
@@ -80,8 +302,13 @@ pub mod ontora_ai {
         let user_token_account = &ctx.accounts.user_token_account;
         let staking_vault = &ctx.accounts.staking_vault;
 
-        // Ensure stake amount is greater than zero
-        require!(stake_amount > 0, OntoraError::InsufficientStake);
+        ctx.accounts.platform_config.require_not_paused(state::PAUSE_DEPOSITS)?;
+
+        // Ensure the stake meets the platform minimum, so dust can't fill the registry
+        require!(
+            stake_amount >= ctx.accounts.platform_config.min_stake_amount,
+            OntoraError::InvalidStakeAmount
+        );
 
         // Transfer tokens from user to staking vault
         let cpi_accounts = Transfer {
@@ -94,49 +321,109 @@ pub mod ontora_ai {
         token::transfer(cpi_ctx, stake_amount)?;
 
         // Initialize AI agent data
+        let now = Clock::get()?.unix_timestamp;
         ai_agent.owner = ctx.accounts.user.key();
         ai_agent.staked_amount = stake_amount;
-        ai_agent.last_stake_time = Clock::get()?.unix_timestamp;
+        ai_agent.last_stake_time = now;
         ai_agent.accumulated_rewards = 0;
         ai_agent.is_active = true;
         ai_agent.bump = ctx.bumps.ai_agent;
+        ai_agent.version = LEGACY_AI_AGENT_VERSION;
+
+        // Legacy stake counts towards the total the epoch emission is shared over
+        let platform_config = &mut ctx.accounts.platform_config;
+        platform_config.total_staked =
+            platform_config.total_staked.checked_add(stake_amount).ok_or(OntoraError::ArithmeticError)?;
+        platform_config.total_agents =
+            platform_config.total_agents.checked_add(1).ok_or(OntoraError::ArithmeticError)?;
+
+        emit_legacy_deposit(ai_agent.owner, stake_amount, now);
+
+        Ok(())
+    }
+
+    // Add tokens to the stake of an active AI agent
+    pub fn increase_stake(ctx: Context<IncreaseStake>, amount: u64) -> Result<()> {
+        let ai_agent = &mut ctx.accounts.ai_agent;
+
+        ctx.accounts.platform_config.require_not_paused(state::PAUSE_DEPOSITS)?;
+
+        require!(amount > 0, OntoraError::InsufficientStake);
+        require!(ai_agent.is_active, OntoraError::AgentInactive);
+
+        // Transfer tokens from user to staking vault
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.user_token_account.to_account_info(),
+            to: ctx.accounts.staking_vault.to_account_info(),
+            authority: ctx.accounts.user.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+        token::transfer(cpi_ctx, amount)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        ai_agent.staked_amount = ai_agent.staked_amount.checked_add(amount).ok_or(OntoraError::ArithmeticError)?;
+        ai_agent.last_stake_time = now;
+        let platform_config = &mut ctx.accounts.platform_config;
+        platform_config.total_staked =
+            platform_config.total_staked.checked_add(amount).ok_or(OntoraError::ArithmeticError)?;
+
+        emit_legacy_deposit(ai_agent.owner, amount, now);
 
         Ok(())
     }
 
     // Unstake tokens from an AI agent
     pub fn unstake_ai_agent(ctx: Context<UnstakeAIAgent>) -> Result<()> {
-        let ai_agent = &mut ctx.accounts.ai_agent;
-        let token_program = &ctx.accounts.token_program;
-        let user_token_account = &ctx.accounts.user_token_account;
-        let staking_vault = &ctx.accounts.staking_vault;
-        let current_time = Clock::get()?.unix_timestamp;
+        let amount = ctx.accounts.ai_agent.staked_amount;
+        ctx.accounts.withdraw(amount)
+    }
 
-        // Check if cooldown period has passed
+    // Unstake part of an AI agent's stake; the agent stays active while any remains
+    pub fn unstake_partial(ctx: Context<UnstakeAIAgent>, amount: u64) -> Result<()> {
         require!(
-            current_time >= ai_agent.last_stake_time + STAKING_COOLDOWN,
-            OntoraError::CooldownNotCompleted
+            amount > 0 && amount <= ctx.accounts.ai_agent.staked_amount,
+            OntoraError::InvalidUnstakeAmount
         );
+        ctx.accounts.withdraw(amount)
+    }
 
-        // Check if the caller is the owner
-        require!(ai_agent.owner == ctx.accounts.user.key(), OntoraError::InvalidOwner);
+    // Withdraw an AI agent's whole stake before the cooldown ends. The fee
+    // schedule's emergency unstake penalty is kept back for the reward pool.
+    pub fn emergency_unstake(ctx: Context<EmergencyUnstake>) -> Result<()> {
+        ctx.accounts.withdraw()
+    }
 
-        // Transfer staked tokens back to user
-        let seeds = &[b"ai_agent", ai_agent.owner.as_ref(), &[ai_agent.bump]];
-        let signer = &[&seeds[..]];
-        let cpi_accounts = Transfer {
-            from: staking_vault.to_account_info(),
-            to: user_token_account.to_account_info(),
-            authority: ai_agent.to_account_info(),
-        };
-        let cpi_program = token_program.to_account_info();
-        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
-        token::transfer(cpi_ctx, ai_agent.staked_amount)?;
+    // Take part of an AI agent's stake out now and queue it for `claim_unstake`
+    // once the cooldown since the last stake change ends. The amount stops
+    // earning rewards and counting as voting power straight away.
+    pub fn request_unstake(ctx: Context<RequestUnstake>, amount: u64) -> Result<()> {
+        let bump = ctx.bumps.pending_withdrawal;
+        ctx.accounts.request(amount, bump)
+    }
 
-        // Update AI agent state
-        ai_agent.staked_amount = 0;
-        ai_agent.is_active = false;
-        ai_agent.last_stake_time = current_time;
+    // Pay a requested withdrawal, less the unstake fee, once it is released
+    pub fn claim_unstake(ctx: Context<ClaimUnstake>) -> Result<()> {
+        ctx.accounts.claim()
+    }
+
+    // Close an AI agent left empty by unstaking, refunding its rent to the owner,
+    // who can then register again
+    pub fn close_agent(ctx: Context<CloseAgent>) -> Result<()> {
+        let ai_agent = &ctx.accounts.ai_agent;
+        require!(
+            ai_agent.staked_amount == 0 && ai_agent.accumulated_rewards == 0 && !ai_agent.is_active,
+            OntoraError::AgentNotEmpty
+        );
+        // A requested withdrawal is paid out of the agent's vault, so it is claimed first
+        require!(ctx.accounts.pending_withdrawal.data_is_empty(), OntoraError::WithdrawalPending);
+        // An agent registered again starts from `last_reward_epoch` 0, so one paid this
+        // epoch stays open until the next, or it could be paid twice
+        let epoch = reward_epoch(Clock::get()?.unix_timestamp);
+        require!(ai_agent.last_reward_epoch != epoch, OntoraError::AlreadyDistributedThisEpoch);
+
+        // Agents registered before the counter existed were never counted
+        let platform_config = &mut ctx.accounts.platform_config;
+        platform_config.total_agents = platform_config.total_agents.saturating_sub(1);
 
         Ok(())
     }
@@ -147,21 +434,62 @@ pub mod ontora_ai {
         let ai_agent = &mut ctx.accounts.ai_agent;
         let current_time = Clock::get()?.unix_timestamp;
 
-        // Check if enough time has passed since last update (epoch duration)
-        require!(
-            current_time >= reward_pool.last_updated + EPOCH_DURATION,
-            OntoraError::CooldownNotCompleted
-        );
+        // Each agent is paid once per epoch, independently of the others
+        let epoch = reward_epoch(current_time);
+        require!(epoch >= reward_pool.open_epoch(), OntoraError::CooldownNotCompleted);
+        require!(ai_agent.last_reward_epoch != epoch, OntoraError::AlreadyDistributedThisEpoch);
 
-        // Calculate rewards based on staked amount and reward rate
-        let reward = ai_agent.staked_amount * REWARD_RATE / 1000; // Example: 0.1% of staked amount per epoch
-        require!(reward_pool.total_rewards >= reward, OntoraError::RewardPoolDepleted);
+        // The agent's share of the epoch emission, pro rata to the stake at the epoch's first payout
+        let config = &mut ctx.accounts.platform_config;
+        let reward = config.epoch_payout(epoch, ai_agent.staked_amount, reward_pool.total_rewards)?;
 
         // Update reward pool and agent rewards
-        reward_pool.total_rewards -= reward;
-        ai_agent.accumulated_rewards += reward;
-        reward_pool.last_updated = current_time;
+        reward_pool.total_rewards =
+            reward_pool.total_rewards.checked_sub(reward).ok_or(OntoraError::ArithmeticError)?;
+        ai_agent.accumulated_rewards = ai_agent
+            .accumulated_rewards
+            .checked_add(reward)
+            .ok_or(OntoraError::ArithmeticError)?;
+        ai_agent.last_reward_epoch = epoch;
+
+        Ok(())
+    }
+
+    // Distribute rewards to a batch of AI agents, passed as writable remaining
+    // accounts in ascending address order. One epoch's distribution can span
+    // transactions; a batch with `close_epoch` set ends it early, and an agent
+    // already paid in the epoch fails the batch.
+    pub fn distribute_rewards_batch(ctx: Context<DistributeRewardsBatch>, close_epoch: bool) -> Result<()> {
+        let config = &mut ctx.accounts.platform_config;
+        let reward_pool = &mut ctx.accounts.reward_pool;
+        let current_time = Clock::get()?.unix_timestamp;
+
+        let epoch = reward_epoch(current_time);
+        require!(epoch >= reward_pool.open_epoch(), OntoraError::CooldownNotCompleted);
+
+        let mut previous: Option<Pubkey> = None;
+        for info in ctx.remaining_accounts {
+            // Ascending addresses rule out paying an agent twice in one batch
+            require!(!matches!(previous, Some(key) if key >= *info.key), OntoraError::InvalidAccount);
+            require!(info.is_writable && *info.owner == crate::ID, OntoraError::InvalidAccount);
+            previous = Some(*info.key);
+
+            let mut ai_agent = AIAgent::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+            require!(ai_agent.last_reward_epoch != epoch, OntoraError::AlreadyDistributedThisEpoch);
+            let reward = config.epoch_payout(epoch, ai_agent.staked_amount, reward_pool.total_rewards)?;
+            reward_pool.total_rewards =
+                reward_pool.total_rewards.checked_sub(reward).ok_or(OntoraError::ArithmeticError)?;
+            ai_agent.accumulated_rewards = ai_agent
+                .accumulated_rewards
+                .checked_add(reward)
+                .ok_or(OntoraError::ArithmeticError)?;
+            ai_agent.last_reward_epoch = epoch;
+            ai_agent.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+        }
 
+        if close_epoch {
+            reward_pool.last_updated = current_time;
+        }
         Ok(())
     }
 
@@ -172,15 +500,43 @@ pub mod ontora_ai {
         let user_token_account = &ctx.accounts.user_token_account;
         let reward_vault = &ctx.accounts.reward_vault;
 
+        ctx.accounts.platform_config.require_not_paused(state::PAUSE_CLAIMS)?;
+
         // Check if the caller is the owner
         require!(ai_agent.owner == ctx.accounts.user.key(), OntoraError::InvalidOwner);
 
         // Check if there are rewards to claim
         require!(ai_agent.accumulated_rewards > 0, OntoraError::RewardPoolDepleted);
 
-        // Transfer rewards from vault to user
-        let seeds = &[b"reward_vault", &[ctx.accounts.reward_pool.bump]];
+        // Route the claim fee to the treasury; the user receives the rest
+        let current_time = Clock::get()?.unix_timestamp;
+        ctx.accounts.fee_schedule.settle(current_time);
+        let (net, fee) = ctx.accounts.fee_schedule.apply_fee(ai_agent.accumulated_rewards, state::FeeType::Claim)?;
+        let seeds = &[REWARD_POOL_SEED, &[ctx.accounts.reward_pool.bump]];
         let signer = &[&seeds[..]];
+        treasury::route_fee(
+            token_program,
+            reward_vault.to_account_info(),
+            ctx.accounts.reward_pool.to_account_info(),
+            signer,
+            &mut ctx.accounts.treasury,
+            &ctx.accounts.treasury_vault,
+            state::FeeSource::Claim,
+            fee,
+        )?;
+
+        // With vesting set, the rest joins the user's schedule, which pays out
+        // what had vested on it so far
+        let reward_vesting = &mut ctx.accounts.reward_vesting;
+        if reward_vesting.owner == Pubkey::default() {
+            reward_vesting.owner = ai_agent.owner;
+            reward_vesting.bump = ctx.bumps.reward_vesting;
+        }
+        let vesting_seconds = ctx.accounts.platform_config.vesting_seconds;
+        let payout =
+            if vesting_seconds > 0 { reward_vesting.extend(net, current_time, vesting_seconds)? } else { net };
+
+        // Transfer rewards from vault to user
         let cpi_accounts = Transfer {
             from: reward_vault.to_account_info(),
             to: user_token_account.to_account_info(),
@@ -188,7 +544,7 @@ pub mod ontora_ai {
         };
         let cpi_program = token_program.to_account_info();
         let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
-        token::transfer(cpi_ctx, ai_agent.accumulated_rewards)?;
+        token::transfer(cpi_ctx, payout)?;
 
         // Reset accumulated rewards
         ai_agent.accumulated_rewards = 0;
@@ -196,13 +552,21 @@ pub mod ontora_ai {
         Ok(())
     }
 
+    // Pay out the rewards vested on the caller's schedule since the last release
+    pub fn release_vested(ctx: Context<ReleaseVested>) -> Result<()> {
+        ctx.accounts.release()
+    }
+
     // Create a governance proposal
     pub fn create_proposal(ctx: Context<CreateProposal>, description: String, duration: i64) -> Result<()> {
         let proposal = &mut ctx.accounts.proposal;
         let current_time = Clock::get()?.unix_timestamp;
 
-        // Ensure description is not empty and within length limits (e.g., 200 characters)
-        require!(description.len() > 0 && description.len() <= 200, OntoraError::InvalidOwner);
+        ctx.accounts.platform_config.require_not_paused(state::PAUSE_GOVERNANCE)?;
+
+        // Ensure description is not empty and fits the account, and the vote lasts a while
+        GovernanceProposal::validate_description(&description)?;
+        require!(duration > 0, OntoraError::InvalidDuration);
 
         // Initialize proposal data
         proposal.proposer = ctx.accounts.user.key();
@@ -210,7 +574,7 @@ pub mod ontora_ai {
         proposal.yes_votes = 0;
         proposal.no_votes = 0;
         proposal.start_time = current_time;
-        proposal.end_time = current_time + duration;
+        proposal.end_time = current_time.checked_add(duration).ok_or(OntoraError::ArithmeticError)?;
         proposal.is_active = true;
         proposal.bump = ctx.bumps.proposal;
 
@@ -223,6 +587,8 @@ pub mod ontora_ai {
         let ai_agent = &ctx.accounts.ai_agent;
         let current_time = Clock::get()?.unix_timestamp;
 
+        ctx.accounts.platform_config.require_not_paused(state::PAUSE_GOVERNANCE)?;
+
         // Ensure proposal is active and voting period is ongoing
         require!(proposal.is_active, OntoraError::ProposalActive);
         require!(current_time < proposal.end_time, OntoraError::ProposalActive);
@@ -235,80 +601,812 @@ pub mod ontora_ai {
         require!(voting_power > 0, OntoraError::InsufficientVotingPower);
 
         // Record the vote
-        if in_favor {
-            proposal.yes_votes += voting_power;
-        } else {
-            proposal.no_votes += voting_power;
-        }
+        let votes = if in_favor { &mut proposal.yes_votes } else { &mut proposal.no_votes };
+        *votes = votes.checked_add(voting_power).ok_or(OntoraError::ArithmeticError)?;
 
         Ok(())
     }
+
+    // Create the platform configuration with its first admin
+    pub fn initialize_platform(
+        ctx: Context<InitializePlatform>,
+        reward_rate_bps: u64,
+        min_stake_amount: u64,
+        epoch_duration: i64,
+        cooldown_seconds: i64,
+        epoch_emission: u64,
+    ) -> Result<()> {
+        instructions::initialize_platform(
+            ctx,
+            reward_rate_bps,
+            min_stake_amount,
+            epoch_duration,
+            cooldown_seconds,
+            epoch_emission,
+        )
+    }
+
+    // Create the staking and reward vaults for the stake mint (admin only)
+    pub fn initialize_vaults(ctx: Context<InitializeVaults>) -> Result<()> {
+        instructions::initialize_vaults(ctx)
+    }
+
+    // Update platform parameters (admin or its governance realm)
+    pub fn update_platform_config(
+        ctx: Context<UpdatePlatformConfig>,
+        params: state::ConfigParams,
+        update_metadata: String,
+    ) -> Result<()> {
+        instructions::update_platform_config(ctx, params, update_metadata)
+    }
+
+    // Grow an older platform config to the current layout (admin pays the rent)
+    pub fn migrate_platform_config(ctx: Context<MigratePlatformConfig>) -> Result<()> {
+        instructions::migrate_platform_config(ctx)
+    }
+
+    // Upgrade any versioned account to its current layout (payer covers the rent)
+    pub fn migrate_account(ctx: Context<MigrateAccount>) -> Result<()> {
+        migration::migrate_account(ctx)
+    }
+
+    // Move a legacy AIAgent and its stake onto the unified agent layout (admin only)
+    pub fn migrate_agent_v1(ctx: Context<MigrateAgentV1>) -> Result<()> {
+        migration::migrate_agent_v1(ctx)
+    }
+
+    // Report the program version and the oldest supported client via return data
+    pub fn get_version(ctx: Context<GetVersion>) -> Result<state::VersionInfo> {
+        instructions::get_version(ctx)
+    }
+
+    // Open a multi-option governance proposal
+    pub fn create_governance_proposal(
+        ctx: Context<CreateGovernanceProposal>,
+        title: String,
+        description: String,
+        voting_duration: u64,
+        options: Vec<String>,
+        action: state::ProposalAction,
+    ) -> Result<()> {
+        governance::create_proposal(ctx, title, description, voting_duration, options, action)
+    }
+
+    // Vote for one option of a governance proposal
+    pub fn cast_vote(ctx: Context<CastVote>, proposal_id: u64, vote_option: u8) -> Result<()> {
+        governance::cast_vote(ctx, proposal_id, vote_option)
+    }
+
+    // Close voting on a governance proposal once its period has ended (permissionless)
+    pub fn finalize_proposal(ctx: Context<FinalizeProposal>, proposal_id: u64) -> Result<()> {
+        governance::finalize_proposal(ctx, proposal_id)
+    }
+
+    // Apply an approved governance proposal's action to the platform config, if option 0
+    // won (permissionless)
+    pub fn execute_proposal(ctx: Context<ExecuteProposal>) -> Result<()> {
+        governance::execute_proposal(ctx)
+    }
+
+    // Enable or disable governance, suspending or resuming in-flight proposals (admin only)
+    pub fn set_governance_enabled<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SetGovernanceEnabled<'info>>,
+        enabled: bool,
+    ) -> Result<()> {
+        governance::set_governance_enabled(ctx, enabled)
+    }
+
+    // Nominate a new platform admin (two-step transfer)
+    pub fn propose_admin(ctx: Context<ProposeAdmin>, new_admin: Pubkey) -> Result<()> {
+        instructions::propose_admin(ctx, new_admin)
+    }
+
+    // Accept a pending platform admin nomination
+    pub fn accept_admin(ctx: Context<AcceptAdmin>) -> Result<()> {
+        instructions::accept_admin(ctx)
+    }
+
+    // Cancel a pending platform admin nomination
+    pub fn cancel_admin_transfer(ctx: Context<CancelAdminTransfer>) -> Result<()> {
+        instructions::cancel_admin_transfer(ctx)
+    }
+
+    // Create the protocol treasury
+    pub fn initialize_treasury(ctx: Context<InitializeTreasury>, governance: Pubkey) -> Result<()> {
+        treasury::initialize_treasury(ctx, governance)
+    }
+
+    // Create the fee schedule (admin only)
+    pub fn initialize_fee_schedule(
+        ctx: Context<InitializeFeeSchedule>,
+        governance: Pubkey,
+        rates_bps: [u16; state::FeeType::COUNT],
+    ) -> Result<()> {
+        fees::initialize_fee_schedule(ctx, governance, rates_bps)
+    }
+
+    // Stage new fee rates for the next epoch (admin or governance)
+    pub fn update_fee_schedule(ctx: Context<UpdateFeeSchedule>, rates_bps: [u16; state::FeeType::COUNT]) -> Result<()> {
+        fees::update_fee_schedule(ctx, rates_bps)
+    }
+
+    // Report the treasury balance for one mint
+    pub fn treasury_balance(ctx: Context<TreasuryBalance>) -> Result<u64> {
+        treasury::treasury_balance(ctx)
+    }
+
+    // Governance-approved treasury outflow
+    pub fn treasury_transfer(ctx: Context<TreasuryTransfer>, amount: u64) -> Result<()> {
+        treasury::treasury_transfer(ctx, amount)
+    }
+
+    // Pause operation groups (pauser role)
+    pub fn set_pause_flags(ctx: Context<SetPauseFlags>, flags: u8) -> Result<()> {
+        instructions::set_pause_flags(ctx, flags)
+    }
+
+    // Unpause operation groups (admin only)
+    pub fn clear_pause_flags(ctx: Context<ClearPauseFlags>, flags: u8) -> Result<()> {
+        instructions::clear_pause_flags(ctx, flags)
+    }
+
+    // Assign a delegated role (admin or its governance realm)
+    pub fn set_role(ctx: Context<SetRole>, role: state::Role, holder: Pubkey) -> Result<()> {
+        instructions::set_role(ctx, role, holder)
+    }
+
+    // Give up a delegated role (current holder only)
+    pub fn renounce_role(ctx: Context<RenounceRole>, role: state::Role) -> Result<()> {
+        instructions::renounce_role(ctx, role)
+    }
+
+    // Post an agent performance score (oracle role)
+    pub fn update_performance_score(ctx: Context<UpdatePerformanceScore>, score: u64) -> Result<()> {
+        instructions::update_performance_score(ctx, score)
+    }
+
+    // Register an AI agent under its owner and id
+    pub fn register_agent(ctx: Context<RegisterAiAgent>, agent_id: u64, name: String, description: String) -> Result<()> {
+        instructions::register_ai_agent(ctx, agent_id, name, description)
+    }
+
+    // Update an AI agent's name and description
+    pub fn update_agent_metadata(
+        ctx: Context<UpdateAgentMetadata>,
+        agent_id: u64,
+        name: String,
+        description: String,
+    ) -> Result<()> {
+        instructions::update_agent_metadata(ctx, agent_id, name, description)
+    }
+
+    // Stake tokens on an AI agent for a beneficiary, opening its stake index entry on first stake
+    pub fn stake_on_agent(ctx: Context<StakeOnAgent>, agent_id: u64, amount: u64, beneficiary: Pubkey) -> Result<()> {
+        instructions::stake_on_agent(ctx, agent_id, amount, beneficiary)
+    }
+
+    // Withdraw tokens staked on an AI agent, closing the index entry on full exit
+    pub fn unstake_from_agent(ctx: Context<UnstakeFromAgent>, agent_id: u64, amount: u64) -> Result<()> {
+        instructions::unstake_from_agent(ctx, agent_id, amount)
+    }
+
+    // Pay out a user stake's rewards to its owner, at most once per epoch
+    pub fn claim_stake_rewards(ctx: Context<ClaimStakeRewards>) -> Result<()> {
+        instructions::claim_stake_rewards(ctx)
+    }
+
+    // Create an entity's metadata account sized to its payload
+    pub fn create_metadata(ctx: Context<CreateMetadata>, entity_id: u64, data: String) -> Result<()> {
+        instructions::create_metadata(ctx, entity_id, data)
+    }
+
+    // Replace an entity's metadata, growing or shrinking the account to fit
+    pub fn update_metadata(ctx: Context<UpdateMetadata>, entity_id: u64, data: String) -> Result<()> {
+        instructions::update_metadata(ctx, entity_id, data)
+    }
+
+    // Hand the admin role to a threshold multisig
+    pub fn initialize_admin_multisig(
+        ctx: Context<InitializeAdminMultisig>,
+        signers: Vec<Pubkey>,
+        threshold: u8,
+    ) -> Result<()> {
+        multisig::initialize_admin_multisig(ctx, signers, threshold)
+    }
+
+    // Propose an admin action for multisig approval
+    pub fn propose_admin_action(ctx: Context<ProposeAdminAction>, action: state::AdminAction) -> Result<()> {
+        multisig::propose_admin_action(ctx, action)
+    }
+
+    // Approve a pending admin action
+    pub fn approve_admin_action(ctx: Context<ApproveAdminAction>) -> Result<()> {
+        multisig::approve_admin_action(ctx)
+    }
+
+    // Execute an admin action that reached the approval threshold
+    pub fn execute_admin_action(ctx: Context<ExecuteAdminAction>) -> Result<()> {
+        multisig::execute_admin_action(ctx)
+    }
+
+    // Create the vaults of an approved `InitializeVaults` admin action
+    pub fn execute_initialize_vaults(ctx: Context<ExecuteInitializeVaults>) -> Result<()> {
+        multisig::execute_initialize_vaults(ctx)
+    }
+
+    // Let an spl-governance governance execute admin instructions (admin only)
+    pub fn set_governance_realm(ctx: Context<SetGovernanceRealm>, realm: Pubkey, governance: Pubkey) -> Result<()> {
+        realms::set_governance_realm(ctx, realm, governance)
+    }
 }
 
 // Context structs for instruction validation
 #[derive(Accounts)]
 pub struct InitializeRewardPool<'info> {
-    #[account(init, payer = user, space = 8 + 16 + 8, seeds = [b"reward_pool"], bump)]
+    #[account(init, payer = user, space = 8 + RewardPool::INIT_SPACE, seeds = [REWARD_POOL_SEED], bump)]
     pub reward_pool: Account<'info, RewardPool>,
     #[account(mut)]
     pub user: Signer<'info>,
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct FundRewardPool<'info> {
+    #[account(seeds = [PLATFORM_CONFIG_SEED], bump = platform_config.bump)]
+    pub platform_config: Account<'info, state::PlatformConfig>,
+    #[account(mut, seeds = [REWARD_POOL_SEED], bump = reward_pool.bump)]
+    pub reward_pool: Account<'info, RewardPool>,
+    // Held by the pool, which signs claims out of it
+    #[account(
+        mut,
+        address = platform_config.reward_vault @ OntoraError::InvalidVault,
+        token::mint = reward_mint,
+        token::authority = reward_pool
+    )]
+    pub reward_vault: Account<'info, TokenAccount>,
+    pub reward_mint: Account<'info, Mint>,
+    pub funder: Signer<'info>,
+    #[account(mut, token::mint = reward_mint)]
+    pub funder_token_account: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
 #[derive(Accounts)]
 pub struct RegisterAIAgent<'info> {
-    #[account(init, payer = user, space = 8 + 32 + 8 + 8 + 8 + 1 + 1, seeds = [b"ai_agent", user.key().as_ref()], bump)]
+    #[account(mut, seeds = [PLATFORM_CONFIG_SEED], bump = platform_config.bump)]
+    pub platform_config: Account<'info, state::PlatformConfig>,
+    #[account(
+        init,
+        payer = user,
+        space = 8 + AIAgent::INIT_SPACE,
+        seeds = [LEGACY_AI_AGENT_SEED, user.key().as_ref()],
+        bump
+    )]
     pub ai_agent: Account<'info, AIAgent>,
     #[account(mut)]
     pub user: Signer<'info>,
     #[account(mut)]
     pub user_token_account: Account<'info, TokenAccount>,
-    #[account(mut)]
+    // Held by the agent being registered, as in `increase_stake`
+    #[account(mut, token::authority = ai_agent)]
     pub staking_vault: Account<'info, TokenAccount>,
     pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct IncreaseStake<'info> {
+    #[account(mut, seeds = [PLATFORM_CONFIG_SEED], bump = platform_config.bump)]
+    pub platform_config: Account<'info, state::PlatformConfig>,
+    #[account(mut, seeds = [LEGACY_AI_AGENT_SEED, user.key().as_ref()], bump = ai_agent.bump)]
+    pub ai_agent: Account<'info, AIAgent>,
+    pub user: Signer<'info>,
+    #[account(mut)]
+    pub user_token_account: Account<'info, TokenAccount>,
+    // Held by the agent, so the stake recorded is the stake it can pay back
+    #[account(mut, token::authority = ai_agent)]
+    pub staking_vault: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
 #[derive(Accounts)]
 pub struct UnstakeAIAgent<'info> {
+    #[account(mut, seeds = [PLATFORM_CONFIG_SEED], bump = platform_config.bump)]
+    pub platform_config: Account<'info, state::PlatformConfig>,
     #[account(mut, has_one = owner @ OntoraError::InvalidOwner)]
     pub ai_agent: Account<'info, AIAgent>,
+    /// CHECK: only read for whether it exists; a one-shot unstake waits for a requested withdrawal to be claimed
+    #[account(seeds = [PENDING_WITHDRAWAL_SEED, ai_agent.owner.as_ref()], bump)]
+    pub pending_withdrawal: UncheckedAccount<'info>,
     #[account(mut)]
     pub user: Signer<'info>,
     #[account(mut)]
     pub user_token_account: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = stake_mint, token::authority = ai_agent)]
+    pub staking_vault: Account<'info, TokenAccount>,
+    pub stake_mint: Account<'info, Mint>,
+    #[account(mut, seeds = [FEE_SCHEDULE_SEED], bump = fee_schedule.bump)]
+    pub fee_schedule: Account<'info, state::FeeSchedule>,
+    #[account(mut, seeds = [TREASURY_SEED], bump = treasury.bump)]
+    pub treasury: Account<'info, state::Treasury>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        seeds = [TREASURY_VAULT_SEED, stake_mint.key().as_ref()],
+        bump,
+        token::mint = stake_mint,
+        token::authority = treasury
+    )]
+    pub treasury_vault: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+// Record a deposit into a legacy agent. Legacy agents have no id of their own;
+// events use MIGRATED_AGENT_ID, the one `migrate_agent_v1` later gives them.
+fn emit_legacy_deposit(user: Pubkey, amount: u64, timestamp: i64) {
+    emit!(events::StakeDeposited {
+        schema_version: events::StakeDeposited::SCHEMA_VERSION,
+        user,
+        agent_id: MIGRATED_AGENT_ID,
+        amount,
+        timestamp,
+        // No lockups yet
+        staking_duration: 0,
+    });
+}
+
+impl<'info> UnstakeAIAgent<'info> {
+    // Pay `amount` of the agent's stake, less the unstake fee, back to the owner
+    fn withdraw(&mut self, amount: u64) -> Result<()> {
+        let current_time = Clock::get()?.unix_timestamp;
+
+        self.platform_config.require_not_paused(state::PAUSE_WITHDRAWALS)?;
+
+        // Check if cooldown period has passed
+        require!(
+            self.ai_agent.cooldown_remaining(&self.platform_config, current_time) == 0,
+            OntoraError::CooldownNotCompleted
+        );
+
+        // Check if the caller is the owner
+        require!(self.ai_agent.owner == self.user.key(), OntoraError::InvalidOwner);
+        require!(self.pending_withdrawal.data_is_empty(), OntoraError::WithdrawalPending);
+
+        // Route the unstake fee to the treasury; the user receives the rest
+        self.fee_schedule.settle(current_time);
+        let (net, fee) = self.fee_schedule.apply_fee(amount, state::FeeType::Unstake)?;
+        let owner = self.ai_agent.owner;
+        let seeds = &[LEGACY_AI_AGENT_SEED, owner.as_ref(), &[self.ai_agent.bump]];
+        let signer = &[&seeds[..]];
+        treasury::route_fee(
+            &self.token_program,
+            self.staking_vault.to_account_info(),
+            self.ai_agent.to_account_info(),
+            signer,
+            &mut self.treasury,
+            &self.treasury_vault,
+            state::FeeSource::Unstake,
+            fee,
+        )?;
+
+        // Transfer staked tokens back to user
+        let cpi_accounts = Transfer {
+            from: self.staking_vault.to_account_info(),
+            to: self.user_token_account.to_account_info(),
+            authority: self.ai_agent.to_account_info(),
+        };
+        let cpi_program = self.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        token::transfer(cpi_ctx, net)?;
+
+        // Update AI agent state; the cooldown restarts and the withdrawal is
+        // recorded only when the stake changed
+        let ai_agent = &mut self.ai_agent;
+        ai_agent.staked_amount = ai_agent.staked_amount.checked_sub(amount).ok_or(OntoraError::ArithmeticError)?;
+        ai_agent.is_active = ai_agent.staked_amount > 0;
+        // Saturates so stake registered before the total counted legacy stake can still leave
+        let platform_config = &mut self.platform_config;
+        platform_config.total_staked = platform_config.total_staked.saturating_sub(amount);
+        if amount > 0 {
+            ai_agent.last_stake_time = current_time;
+            emit!(events::StakeWithdrawn {
+                schema_version: events::StakeWithdrawn::SCHEMA_VERSION,
+                user: owner,
+                agent_id: MIGRATED_AGENT_ID,
+                amount,
+                timestamp: current_time,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct EmergencyUnstake<'info> {
+    #[account(mut, seeds = [PLATFORM_CONFIG_SEED], bump = platform_config.bump)]
+    pub platform_config: Account<'info, state::PlatformConfig>,
+    #[account(mut, seeds = [LEGACY_AI_AGENT_SEED, user.key().as_ref()], bump = ai_agent.bump)]
+    pub ai_agent: Account<'info, AIAgent>,
+    pub user: Signer<'info>,
+    #[account(mut, token::mint = stake_mint)]
+    pub user_token_account: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = stake_mint, token::authority = ai_agent)]
+    pub staking_vault: Account<'info, TokenAccount>,
+    pub stake_mint: Account<'info, Mint>,
+    #[account(mut, seeds = [REWARD_POOL_SEED], bump = reward_pool.bump)]
+    pub reward_pool: Account<'info, RewardPool>,
+    #[account(mut, seeds = [FEE_SCHEDULE_SEED], bump = fee_schedule.bump)]
+    pub fee_schedule: Account<'info, state::FeeSchedule>,
+    // Held by the pool, so the penalty is paid out to the remaining stakers
+    #[account(
+        mut,
+        address = platform_config.reward_vault @ OntoraError::InvalidVault,
+        token::mint = stake_mint,
+        token::authority = reward_pool
+    )]
+    pub reward_vault: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+impl<'info> EmergencyUnstake<'info> {
+    // Pay the whole stake, less the emergency unstake penalty, back to the owner and
+    // move the penalty into the reward pool. The cooldown is not checked.
+    fn withdraw(&mut self) -> Result<()> {
+        let current_time = Clock::get()?.unix_timestamp;
+
+        self.platform_config.require_not_paused(state::PAUSE_WITHDRAWALS)?;
+
+        let amount = self.ai_agent.staked_amount;
+        require!(amount > 0, OntoraError::InvalidUnstakeAmount);
+        self.fee_schedule.settle(current_time);
+        let penalty_bps = self.fee_schedule.fee_bps(state::FeeType::EmergencyUnstake);
+        let (net, penalty) = self.fee_schedule.apply_fee(amount, state::FeeType::EmergencyUnstake)?;
+
+        let owner = self.ai_agent.owner;
+        let seeds = &[LEGACY_AI_AGENT_SEED, owner.as_ref(), &[self.ai_agent.bump]];
+        let signer = &[&seeds[..]];
+        if penalty > 0 {
+            let cpi_accounts = Transfer {
+                from: self.staking_vault.to_account_info(),
+                to: self.reward_vault.to_account_info(),
+                authority: self.ai_agent.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer);
+            token::transfer(cpi_ctx, penalty)?;
+            let reward_pool = &mut self.reward_pool;
+            reward_pool.total_rewards =
+                reward_pool.total_rewards.checked_add(penalty).ok_or(OntoraError::ArithmeticError)?;
+        }
+
+        let cpi_accounts = Transfer {
+            from: self.staking_vault.to_account_info(),
+            to: self.user_token_account.to_account_info(),
+            authority: self.ai_agent.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer);
+        token::transfer(cpi_ctx, net)?;
+
+        let ai_agent = &mut self.ai_agent;
+        ai_agent.staked_amount = 0;
+        ai_agent.is_active = false;
+        ai_agent.last_stake_time = current_time;
+        let platform_config = &mut self.platform_config;
+        platform_config.total_staked = platform_config.total_staked.saturating_sub(amount);
+
+        emit!(events::StakeWithdrawn {
+            schema_version: events::StakeWithdrawn::SCHEMA_VERSION,
+            user: owner,
+            agent_id: MIGRATED_AGENT_ID,
+            amount: net,
+            timestamp: current_time,
+        });
+        emit!(events::PenaltyCharged {
+            schema_version: events::PenaltyCharged::SCHEMA_VERSION,
+            user: owner,
+            agent_id: MIGRATED_AGENT_ID,
+            amount: penalty,
+            penalty_bps,
+            timestamp: current_time,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct RequestUnstake<'info> {
+    #[account(mut, seeds = [PLATFORM_CONFIG_SEED], bump = platform_config.bump)]
+    pub platform_config: Account<'info, state::PlatformConfig>,
+    #[account(mut, seeds = [LEGACY_AI_AGENT_SEED, user.key().as_ref()], bump = ai_agent.bump)]
+    pub ai_agent: Account<'info, AIAgent>,
+    // One withdrawal at a time: it exists until claimed
+    #[account(
+        init,
+        payer = user,
+        space = 8 + PendingWithdrawal::INIT_SPACE,
+        seeds = [PENDING_WITHDRAWAL_SEED, user.key().as_ref()],
+        bump
+    )]
+    pub pending_withdrawal: Account<'info, PendingWithdrawal>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> RequestUnstake<'info> {
+    // Move `amount` out of the agent's stake into the pending withdrawal; the
+    // tokens stay in the agent's vault until claimed
+    fn request(&mut self, amount: u64, bump: u8) -> Result<()> {
+        let current_time = Clock::get()?.unix_timestamp;
+
+        self.platform_config.require_not_paused(state::PAUSE_WITHDRAWALS)?;
+
+        let ai_agent = &mut self.ai_agent;
+        require!(amount > 0 && amount <= ai_agent.staked_amount, OntoraError::InvalidUnstakeAmount);
+        let release_time = ai_agent.last_stake_time.saturating_add(self.platform_config.cooldown_seconds);
+
+        ai_agent.staked_amount = ai_agent.staked_amount.checked_sub(amount).ok_or(OntoraError::ArithmeticError)?;
+        ai_agent.is_active = ai_agent.staked_amount > 0;
+        let platform_config = &mut self.platform_config;
+        platform_config.total_staked = platform_config.total_staked.saturating_sub(amount);
+
+        let pending_withdrawal = &mut self.pending_withdrawal;
+        pending_withdrawal.owner = ai_agent.owner;
+        pending_withdrawal.amount = amount;
+        pending_withdrawal.release_time = release_time;
+        pending_withdrawal.bump = bump;
+
+        emit!(events::UnstakeRequested {
+            schema_version: events::UnstakeRequested::SCHEMA_VERSION,
+            user: ai_agent.owner,
+            agent_id: MIGRATED_AGENT_ID,
+            amount,
+            release_time,
+            timestamp: current_time,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct ClaimUnstake<'info> {
+    #[account(seeds = [PLATFORM_CONFIG_SEED], bump = platform_config.bump)]
+    pub platform_config: Account<'info, state::PlatformConfig>,
+    #[account(seeds = [LEGACY_AI_AGENT_SEED, user.key().as_ref()], bump = ai_agent.bump)]
+    pub ai_agent: Account<'info, AIAgent>,
+    // Closed once paid, refunding its rent
+    #[account(
+        mut,
+        seeds = [PENDING_WITHDRAWAL_SEED, user.key().as_ref()],
+        bump = pending_withdrawal.bump,
+        close = user
+    )]
+    pub pending_withdrawal: Account<'info, PendingWithdrawal>,
     #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(mut, token::mint = stake_mint)]
+    pub user_token_account: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = stake_mint, token::authority = ai_agent)]
     pub staking_vault: Account<'info, TokenAccount>,
+    pub stake_mint: Account<'info, Mint>,
+    #[account(mut, seeds = [FEE_SCHEDULE_SEED], bump = fee_schedule.bump)]
+    pub fee_schedule: Account<'info, state::FeeSchedule>,
+    #[account(mut, seeds = [TREASURY_SEED], bump = treasury.bump)]
+    pub treasury: Account<'info, state::Treasury>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        seeds = [TREASURY_VAULT_SEED, stake_mint.key().as_ref()],
+        bump,
+        token::mint = stake_mint,
+        token::authority = treasury
+    )]
+    pub treasury_vault: Account<'info, TokenAccount>,
     pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ClaimUnstake<'info> {
+    // Pay the pending withdrawal, less the unstake fee, back to the owner
+    fn claim(&mut self) -> Result<()> {
+        let current_time = Clock::get()?.unix_timestamp;
+
+        self.platform_config.require_not_paused(state::PAUSE_WITHDRAWALS)?;
+
+        require!(current_time >= self.pending_withdrawal.release_time, OntoraError::CooldownNotCompleted);
+
+        // Route the unstake fee to the treasury; the user receives the rest
+        let amount = self.pending_withdrawal.amount;
+        self.fee_schedule.settle(current_time);
+        let (net, fee) = self.fee_schedule.apply_fee(amount, state::FeeType::Unstake)?;
+        let owner = self.ai_agent.owner;
+        let seeds = &[LEGACY_AI_AGENT_SEED, owner.as_ref(), &[self.ai_agent.bump]];
+        let signer = &[&seeds[..]];
+        treasury::route_fee(
+            &self.token_program,
+            self.staking_vault.to_account_info(),
+            self.ai_agent.to_account_info(),
+            signer,
+            &mut self.treasury,
+            &self.treasury_vault,
+            state::FeeSource::Unstake,
+            fee,
+        )?;
+
+        let cpi_accounts = Transfer {
+            from: self.staking_vault.to_account_info(),
+            to: self.user_token_account.to_account_info(),
+            authority: self.ai_agent.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer);
+        token::transfer(cpi_ctx, net)?;
+
+        emit!(events::StakeWithdrawn {
+            schema_version: events::StakeWithdrawn::SCHEMA_VERSION,
+            user: owner,
+            agent_id: MIGRATED_AGENT_ID,
+            amount,
+            timestamp: current_time,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct CloseAgent<'info> {
+    #[account(mut, seeds = [PLATFORM_CONFIG_SEED], bump = platform_config.bump)]
+    pub platform_config: Account<'info, state::PlatformConfig>,
+    #[account(
+        mut,
+        seeds = [LEGACY_AI_AGENT_SEED, owner.key().as_ref()],
+        bump = ai_agent.bump,
+        has_one = owner @ OntoraError::InvalidOwner,
+        close = owner
+    )]
+    pub ai_agent: Account<'info, AIAgent>,
+    /// CHECK: only read for whether it exists
+    #[account(seeds = [PENDING_WITHDRAWAL_SEED, owner.key().as_ref()], bump)]
+    pub pending_withdrawal: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
 }
 
 #[derive(Accounts)]
 pub struct DistributeRewards<'info> {
+    #[account(
+        mut,
+        seeds = [PLATFORM_CONFIG_SEED],
+        bump = platform_config.bump,
+        constraint = platform_config.has_role(state::Role::Crank, &crank.key()) @ OntoraError::MissingRole
+    )]
+    pub platform_config: Account<'info, state::PlatformConfig>,
     #[account(mut)]
     pub reward_pool: Account<'info, RewardPool>,
     #[account(mut)]
     pub ai_agent: Account<'info, AIAgent>,
-    pub user: Signer<'info>,
+    pub crank: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct DistributeRewardsBatch<'info> {
+    #[account(
+        mut,
+        seeds = [PLATFORM_CONFIG_SEED],
+        bump = platform_config.bump,
+        constraint = platform_config.has_role(state::Role::Crank, &crank.key()) @ OntoraError::MissingRole
+    )]
+    pub platform_config: Account<'info, state::PlatformConfig>,
+    #[account(mut)]
+    pub reward_pool: Account<'info, RewardPool>,
+    pub crank: Signer<'info>,
+    // remaining_accounts: the batch's AIAgent accounts, writable, in ascending address order.
 }
 
 #[derive(Accounts)]
 pub struct ClaimRewards<'info> {
+    #[account(seeds = [PLATFORM_CONFIG_SEED], bump = platform_config.bump)]
+    pub platform_config: Account<'info, state::PlatformConfig>,
     #[account(mut)]
     pub ai_agent: Account<'info, AIAgent>,
-    #[account(mut)]
+    #[account(mut, seeds = [REWARD_POOL_SEED], bump = reward_pool.bump)]
     pub reward_pool: Account<'info, RewardPool>,
     #[account(mut)]
     pub user: Signer<'info>,
     #[account(mut)]
     pub user_token_account: Account<'info, TokenAccount>,
-    #[account(mut)]
+    // Held by the pool, which signs the payout with its own seeds
+    #[account(
+        mut,
+        address = platform_config.reward_vault @ OntoraError::InvalidVault,
+        token::mint = reward_mint,
+        token::authority = reward_pool
+    )]
     pub reward_vault: Account<'info, TokenAccount>,
+    pub reward_mint: Account<'info, Mint>,
+    #[account(mut, seeds = [FEE_SCHEDULE_SEED], bump = fee_schedule.bump)]
+    pub fee_schedule: Account<'info, state::FeeSchedule>,
+    #[account(mut, seeds = [TREASURY_SEED], bump = treasury.bump)]
+    pub treasury: Account<'info, state::Treasury>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        seeds = [TREASURY_VAULT_SEED, reward_mint.key().as_ref()],
+        bump,
+        token::mint = reward_mint,
+        token::authority = treasury
+    )]
+    pub treasury_vault: Account<'info, TokenAccount>,
+    // The user's vesting schedule, created on their first claim whether or not
+    // vesting is set
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + RewardVesting::INIT_SPACE,
+        seeds = [REWARD_VESTING_SEED, user.key().as_ref()],
+        bump
+    )]
+    pub reward_vesting: Account<'info, RewardVesting>,
     pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseVested<'info> {
+    #[account(seeds = [PLATFORM_CONFIG_SEED], bump = platform_config.bump)]
+    pub platform_config: Account<'info, state::PlatformConfig>,
+    #[account(mut, seeds = [REWARD_VESTING_SEED, user.key().as_ref()], bump = reward_vesting.bump)]
+    pub reward_vesting: Account<'info, RewardVesting>,
+    #[account(seeds = [REWARD_POOL_SEED], bump = reward_pool.bump)]
+    pub reward_pool: Account<'info, RewardPool>,
+    pub user: Signer<'info>,
+    #[account(mut, token::mint = reward_mint)]
+    pub user_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        address = platform_config.reward_vault @ OntoraError::InvalidVault,
+        token::mint = reward_mint,
+        token::authority = reward_pool
+    )]
+    pub reward_vault: Account<'info, TokenAccount>,
+    pub reward_mint: Account<'info, Mint>,
+    pub token_program: Program<'info, Token>,
+}
+
+impl<'info> ReleaseVested<'info> {
+    // Pay the vested part of the schedule not yet released, out of the reward vault
+    fn release(&mut self) -> Result<()> {
+        self.platform_config.require_not_paused(state::PAUSE_CLAIMS)?;
+
+        let amount = self.reward_vesting.release(Clock::get()?.unix_timestamp);
+        require!(amount > 0, OntoraError::NoRewardsAvailable);
+
+        let seeds = &[REWARD_POOL_SEED, &[self.reward_pool.bump]];
+        let signer = &[&seeds[..]];
+        let cpi_accounts = Transfer {
+            from: self.reward_vault.to_account_info(),
+            to: self.user_token_account.to_account_info(),
+            authority: self.reward_pool.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(self.token_program.to_account_info(), cpi_accounts, signer);
+        token::transfer(cpi_ctx, amount)
+    }
 }
 
 #[derive(Accounts)]
 pub struct CreateProposal<'info> {
-    #[account(init, payer = user, space = 8 + 32 + 200 + 8 + 8 + 8 + 8 + 1 + 1, seeds = [b"proposal", user.key().as_ref()], bump)]
+    #[account(seeds = [PLATFORM_CONFIG_SEED], bump = platform_config.bump)]
+    pub platform_config: Account<'info, state::PlatformConfig>,
+    #[account(
+        init,
+        payer = user,
+        space = 8 + GovernanceProposal::INIT_SPACE,
+        seeds = [PROPOSAL_SEED, user.key().as_ref()],
+        bump
+    )]
     pub proposal: Account<'info, GovernanceProposal>,
     #[account(mut)]
     pub user: Signer<'info>,
@@ -317,6 +1415,8 @@ pub struct CreateProposal<'info> {
 
 #[derive(Accounts)]
 pub struct VoteProposal<'info> {
+    #[account(seeds = [PLATFORM_CONFIG_SEED], bump = platform_config.bump)]
+    pub platform_config: Account<'info, state::PlatformConfig>,
     #[account(mut)]
     pub proposal: Account<'info, GovernanceProposal>,
     #[account(has_one = owner @ OntoraError::InvalidOwner)]
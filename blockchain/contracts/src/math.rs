@@ -0,0 +1,109 @@
+use anchor_lang::prelude::*;
+use crate::error::OntoraError;
+
+/// Basis points in a whole (100%).
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+/// `amount * bps / 10_000`, rounded down. `None` only if the result overflows
+/// u64, which needs `bps` above 100%.
+pub fn bps_of(amount: u64, bps: u64) -> Option<u64> {
+    let product = (amount as u128).checked_mul(bps as u128)?;
+    u64::try_from(product / BPS_DENOMINATOR as u128).ok()
+}
+
+/// Whole epochs from `since` to `now`; a clock behind `since` counts as zero.
+/// `None` for a non-positive epoch duration or an overflowing span.
+pub fn elapsed_epochs(since: i64, now: i64, epoch_duration: i64) -> Option<u64> {
+    if epoch_duration <= 0 {
+        return None;
+    }
+    let elapsed = now.checked_sub(since)?;
+    Some((elapsed / epoch_duration).max(0) as u64)
+}
+
+/// Reward for `epochs` epochs on `staked` at `rate_bps` per epoch. The
+/// per-epoch reward rounds down before it is multiplied.
+pub fn epoch_rewards(staked: u64, rate_bps: u64, epochs: u64) -> Option<u64> {
+    bps_of(staked, rate_bps)?.checked_mul(epochs)
+}
+
+/// Fixed-point scale of `PlatformConfig::acc_reward_per_share`.
+pub const ACC_PRECISION: u128 = 1_000_000_000_000;
+
+/// Growth of the reward per staked token, scaled by `ACC_PRECISION`, over
+/// `elapsed` seconds at `rate_bps` per epoch. Accrues by the second and rounds
+/// down; a clock behind counts as zero. `None` for a non-positive epoch
+/// duration or an overflow.
+pub fn reward_per_share_growth(rate_bps: u64, elapsed: i64, epoch_duration: i64) -> Option<u128> {
+    if epoch_duration <= 0 {
+        return None;
+    }
+    let scaled = (rate_bps as u128).checked_mul(ACC_PRECISION)?.checked_mul(elapsed.max(0) as u128)?;
+    Some(scaled / (BPS_DENOMINATOR as u128 * epoch_duration as u128))
+}
+
+/// Rewards `staked` has earned since its `reward_debt` was set, where the debt
+/// is the stake times the accumulator at that time. Rounds down. `None` on an
+/// overflow, or for a debt above what the stake has accrued.
+pub fn accrued_rewards(staked: u64, acc_reward_per_share: u128, reward_debt: u128) -> Option<u64> {
+    let accrued = (staked as u128).checked_mul(acc_reward_per_share)?.checked_sub(reward_debt)?;
+    u64::try_from(accrued / ACC_PRECISION).ok()
+}
+
+/// Split `amount` into (net, fee) at `fee_bps`. The fee rounds down, so
+/// rounding always favors the user, and net + fee == amount. `None` if the
+/// rate is above 100%.
+pub fn split_fee(amount: u64, fee_bps: u16) -> Option<(u64, u64)> {
+    let fee = bps_of(amount, fee_bps as u64)?;
+    Some((amount.checked_sub(fee)?, fee))
+}
+
+/// An agent's share of one epoch's `emission`: `emission * staked /
+/// total_staked`, computed in u128 and rounded down, so the shares of all
+/// agents never add up to more than `emission` and the dust stays in the
+/// pool. Nothing for an agent without stake or a platform without any. Fails
+/// if the pool can't cover the share.
+pub fn pro_rata_payout(emission: u64, staked: u64, total_staked: u64, pool_balance: u64) -> Result<u64> {
+    if staked == 0 || total_staked == 0 {
+        return Ok(0);
+    }
+    // A stake above the total would take more than the whole emission
+    let reward = emission as u128 * staked.min(total_staked) as u128 / total_staked as u128;
+    let reward = u64::try_from(reward).map_err(|_| OntoraError::ArithmeticError)?;
+    require!(reward <= pool_balance, OntoraError::RewardPoolDepleted);
+    Ok(reward)
+}
+
+/// Seconds from `now` until `cooldown` seconds after `since`, never negative.
+/// Saturates instead of overflowing, so any clock value is safe to pass.
+pub fn cooldown_remaining(since: i64, cooldown: i64, now: i64) -> i64 {
+    since.saturating_add(cooldown).saturating_sub(now).max(0)
+}
+
+/// Part of `total` vested by `now` on a schedule of `duration` seconds from
+/// `start`, linearly by the second and rounded down. Nothing vests on a clock behind `start`, and
+/// everything at once for a non-positive duration.
+pub fn vested_amount(total: u64, start: i64, duration: i64, now: i64) -> u64 {
+    let elapsed = now.saturating_sub(start);
+    if duration <= 0 || elapsed >= duration {
+        return total;
+    }
+    if elapsed <= 0 {
+        return 0;
+    }
+    // elapsed < duration, so the share is below `total` and fits in u64
+    (total as u128 * elapsed as u128 / duration as u128) as u64
+}
+
+/// Seconds from now to the amount-weighted end of two tranches: `held` ending
+/// `held_remaining` seconds out and `added` ending `duration` seconds out.
+/// Rounded up; `duration` when both are empty.
+pub fn weighted_vesting_duration(held: u64, held_remaining: i64, added: u64, duration: i64) -> i64 {
+    let total = held as i128 + added as i128;
+    if total == 0 {
+        return duration;
+    }
+    let weighted = held as i128 * held_remaining as i128 + added as i128 * duration as i128;
+    // A weighted mean of the two durations, so it fits in i64
+    ((weighted + total - 1) / total) as i64
+}
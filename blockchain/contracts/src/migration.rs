@@ -0,0 +1,414 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_lang::Discriminator;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+use crate::error::OntoraError;
+use crate::events::AccountMigrated;
+use crate::pda::{AI_AGENT_SEED, LEGACY_AI_AGENT_SEED, PLATFORM_CONFIG_SEED, STAKE_INDEX_SEED, USER_STAKE_SEED};
+use crate::state::*;
+use crate::{AIAgent, LEGACY_AI_AGENT_VERSION};
+
+/// A program account whose layout carries a version and can be upgraded in place.
+/// `migrate_account` dispatches on the discriminator to one of these implementations
+/// and handles the realloc and rent top-up for all of them.
+pub trait Versioned: Discriminator {
+    /// Layout version written by this build.
+    const CURRENT_VERSION: u16;
+    /// Size of the current layout, discriminator included.
+    const CURRENT_SPACE: usize;
+    /// Byte offset of the version field, discriminator included.
+    const VERSION_OFFSET: usize;
+
+    /// Rewrite `data` from its stored layout to the current one. `data` has
+    /// already been grown to at least `CURRENT_SPACE` with the new tail
+    /// zero-filled; `stored_len` is its length before growing. Returns the
+    /// version migrated from, or None if the account is already current.
+    fn migrate_in_place(data: &mut [u8], stored_len: usize) -> Result<Option<u16>>;
+
+    /// Key that must pay for a migration of the (migrated) account in `data`,
+    /// if it is restricted.
+    fn migration_authority(_data: &[u8]) -> Result<Option<Pubkey>> {
+        Ok(None)
+    }
+}
+
+/// Migrate an account whose only change so far is the leading `version: u8`.
+/// Accounts created before it are exactly one byte short of the current size,
+/// so their fields shift right by one and the version is written in front.
+fn insert_version_prefix(data: &mut [u8], stored_len: usize, current_space: usize, current_version: u8) -> Option<u16> {
+    if stored_len >= current_space {
+        return None;
+    }
+    data.copy_within(8..stored_len, 9);
+    data[8] = current_version;
+    Some(0)
+}
+
+impl Versioned for PlatformConfig {
+    // PlatformConfig predates the leading-version convention; its u16 version sits after `pauser`.
+    const CURRENT_VERSION: u16 = PLATFORM_CONFIG_VERSION;
+    const CURRENT_SPACE: usize = PlatformConfig::SPACE;
+    const VERSION_OFFSET: usize = 8 + 32 + 8 + 8 + 8 + 8 + 8 + 1 + 32 + 1 + 32;
+
+    fn migrate_in_place(data: &mut [u8], _stored_len: usize) -> Result<Option<u16>> {
+        // Appended fields are zero-filled, so the grown bytes already decode.
+        let mut platform_config = PlatformConfig::try_deserialize(&mut &data[..])?;
+        let from_version = platform_config.migrate(Clock::get()?.unix_timestamp);
+        if from_version.is_some() {
+            platform_config.try_serialize(&mut &mut data[..])?;
+        }
+        Ok(from_version)
+    }
+
+    fn migration_authority(data: &[u8]) -> Result<Option<Pubkey>> {
+        let platform_config = PlatformConfig::try_deserialize(&mut &data[..])?;
+        Ok(Some(platform_config.admin))
+    }
+}
+
+/// Size of a v1 `AiAgent`, before `score_updated_at` was appended.
+const AI_AGENT_V1_SPACE: usize = AiAgent::SPACE - 8;
+
+impl Versioned for AiAgent {
+    const CURRENT_VERSION: u16 = AI_AGENT_VERSION as u16;
+    const CURRENT_SPACE: usize = AiAgent::SPACE;
+    const VERSION_OFFSET: usize = 8;
+
+    fn migrate_in_place(data: &mut [u8], stored_len: usize) -> Result<Option<u16>> {
+        // v2 appended `score_updated_at`, which decodes as 0 from the zero-filled growth.
+        if stored_len == AI_AGENT_V1_SPACE - 1 {
+            return Ok(insert_version_prefix(data, stored_len, Self::CURRENT_SPACE, AI_AGENT_VERSION));
+        }
+        match data[Self::VERSION_OFFSET] {
+            1 => {
+                data[Self::VERSION_OFFSET] = AI_AGENT_VERSION;
+                Ok(Some(1))
+            }
+            AI_AGENT_VERSION => Ok(None),
+            _ => err!(OntoraError::InvalidAccount),
+        }
+    }
+}
+
+/// Size of a v1 legacy `AIAgent`, before the version and `last_reward_epoch`.
+const LEGACY_AI_AGENT_V1_SPACE: usize = 8 + AIAgent::INIT_SPACE - 1 - 8;
+
+impl Versioned for AIAgent {
+    const CURRENT_VERSION: u16 = LEGACY_AI_AGENT_VERSION as u16;
+    const CURRENT_SPACE: usize = 8 + AIAgent::INIT_SPACE;
+    const VERSION_OFFSET: usize = 8;
+
+    fn migrate_in_place(data: &mut [u8], stored_len: usize) -> Result<Option<u16>> {
+        // v1 had neither the version nor `last_reward_epoch`, which decodes as
+        // 0 (never paid) from the zero-filled growth.
+        if stored_len == LEGACY_AI_AGENT_V1_SPACE {
+            let migrated = insert_version_prefix(data, stored_len, Self::CURRENT_SPACE, LEGACY_AI_AGENT_VERSION);
+            return Ok(migrated.map(|_| AI_AGENT_V1));
+        }
+        match data[Self::VERSION_OFFSET] {
+            LEGACY_AI_AGENT_VERSION => Ok(None),
+            _ => err!(OntoraError::InvalidAccount),
+        }
+    }
+}
+
+/// Size of a v1 `UserStake`, whose `staked_agents: Vec<u64>` held up to 10 ids.
+const USER_STAKE_V1_SPACE: usize = 8 + 1 + 32 + 8 + 8 + (4 + 8 * 10) + 8 + 8 + 1;
+
+impl Versioned for UserStake {
+    const CURRENT_VERSION: u16 = USER_STAKE_VERSION as u16;
+    const CURRENT_SPACE: usize = UserStake::SPACE;
+    const VERSION_OFFSET: usize = 8;
+
+    fn migrate_in_place(data: &mut [u8], stored_len: usize) -> Result<Option<u16>> {
+        // Pre-versioning accounts are one byte short of v1, so their fields start at 8.
+        let (from_version, fields_start) = if stored_len == USER_STAKE_V1_SPACE - 1 {
+            (0, 8)
+        } else {
+            match data[Self::VERSION_OFFSET] {
+                1 => (1, 9),
+                // v3 appended `reward_debt`, which decodes as 0 from the zero-filled growth:
+                // the stake has earned everything accrued since the platform's accumulator started
+                from_version @ (2..=3) => {
+                    carry_stake_changed_at(data);
+                    data[Self::VERSION_OFFSET] = USER_STAKE_VERSION;
+                    return Ok(Some(from_version as u16));
+                }
+                USER_STAKE_VERSION => return Ok(None),
+                _ => return err!(OntoraError::InvalidAccount),
+            }
+        };
+
+        // Drop `staked_agents` from between the leading and trailing fields; the
+        // account keeps its old size. Memberships are rebuilt as StakeIndexEntry
+        // PDAs by the next stake on each agent.
+        let head_len = 32 + 8 + 8; // user, staked_amount, accumulated_rewards
+        let tail_len = 8 + 8 + 1; // last_stake_update, last_reward_claim, bump
+        let vec_start = fields_start + head_len;
+        let agent_count = u32::from_le_bytes(data[vec_start..vec_start + 4].try_into().unwrap()) as usize;
+        let tail_start = vec_start + 4 + 8 * agent_count;
+        require!(tail_start + tail_len <= stored_len, OntoraError::InvalidAccount);
+
+        data.copy_within(fields_start..vec_start, 9);
+        data.copy_within(tail_start..tail_start + tail_len, 9 + head_len);
+        // Clear what the list left behind where `reward_debt` now sits
+        data[9 + head_len + tail_len..Self::CURRENT_SPACE].fill(0);
+        carry_stake_changed_at(data);
+        data[Self::VERSION_OFFSET] = USER_STAKE_VERSION;
+        Ok(Some(from_version))
+    }
+}
+
+/// Byte offsets of `UserStake.last_stake_update` and of `stake_changed_at`, appended in v4.
+const USER_STAKE_LAST_UPDATE_OFFSET: usize = 8 + 1 + 32 + 8 + 8;
+const USER_STAKE_CHANGED_AT_OFFSET: usize = USER_STAKE_LAST_UPDATE_OFFSET + 8 + 8 + 1 + 16;
+
+/// Take every stake change before v4 as the user's own, as the stake others funded was
+/// not told apart: a stake updated after a proposal opened keeps no vote on it.
+fn carry_stake_changed_at(data: &mut [u8]) {
+    data.copy_within(
+        USER_STAKE_LAST_UPDATE_OFFSET..USER_STAKE_LAST_UPDATE_OFFSET + 8,
+        USER_STAKE_CHANGED_AT_OFFSET,
+    );
+}
+
+impl Versioned for Proposal {
+    const CURRENT_VERSION: u16 = PROPOSAL_VERSION as u16;
+    const CURRENT_SPACE: usize = Proposal::LEN;
+    const VERSION_OFFSET: usize = 8;
+
+    fn migrate_in_place(data: &mut [u8], _stored_len: usize) -> Result<Option<u16>> {
+        // v2 appended the stake snapshot, quorum and threshold, which decode as 0 from the
+        // zero-filled growth: a proposal created before them passes on any untied vote. v3
+        // appended the snapshot slot, likewise left 0, and v4 the action, whose zero bytes
+        // decode as NoOp, and the winning option. v5 appended the vote unit; left 0, the
+        // proposal is weighed at the platform's current one.
+        match data[Self::VERSION_OFFSET] {
+            from_version @ (1..=4) => {
+                data[Self::VERSION_OFFSET] = PROPOSAL_VERSION;
+                Ok(Some(from_version as u16))
+            }
+            PROPOSAL_VERSION => Ok(None),
+            _ => err!(OntoraError::InvalidAccount),
+        }
+    }
+}
+
+/// Grow `account` to `T::CURRENT_SPACE` (the payer covers the rent difference)
+/// and migrate it to the current layout. Returns the version migrated from.
+pub fn upgrade_account<'info, T: Versioned>(
+    account: &AccountInfo<'info>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+) -> Result<u16> {
+    let stored_len = account.data_len();
+    if stored_len < T::CURRENT_SPACE {
+        let rent_needed = Rent::get()?
+            .minimum_balance(T::CURRENT_SPACE)
+            .saturating_sub(account.lamports());
+        if rent_needed > 0 {
+            let cpi_accounts = system_program::Transfer {
+                from: payer.to_account_info(),
+                to: account.clone(),
+            };
+            let cpi_ctx = CpiContext::new(system_program.to_account_info(), cpi_accounts);
+            system_program::transfer(cpi_ctx, rent_needed)?;
+        }
+        account.realloc(T::CURRENT_SPACE, true)?;
+    }
+
+    let from_version = {
+        let mut data = account.try_borrow_mut_data()?;
+        T::migrate_in_place(&mut data, stored_len)?
+    }
+    .ok_or(OntoraError::AlreadyMigrated)?;
+
+    let authority = {
+        let data = account.try_borrow_data()?;
+        T::migration_authority(&data)?
+    };
+    if let Some(authority) = authority {
+        require_keys_eq!(authority, payer.key(), OntoraError::UnauthorizedAdmin);
+    }
+    Ok(from_version)
+}
+
+/// Context for migrating any versioned program account. The account is taken
+/// unchecked because an old layout fails to deserialize until it is migrated.
+#[derive(Accounts)]
+pub struct MigrateAccount<'info> {
+    /// CHECK: owner is checked here; the type is identified by its discriminator
+    #[account(mut, owner = crate::ID)]
+    pub account: UncheckedAccount<'info>,
+    /// Pays the rent for any growth; must be the admin for the platform config.
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// The system program for the rent top-up.
+    pub system_program: Program<'info, System>,
+}
+
+/// Instruction to upgrade a `PlatformConfig`, `AiAgent`, `UserStake`,
+/// `Proposal`, or legacy `AIAgent` to its current layout.
+pub fn migrate_account(ctx: Context<MigrateAccount>) -> Result<()> {
+    let account = ctx.accounts.account.to_account_info();
+    let payer = &ctx.accounts.payer;
+    let system_program = &ctx.accounts.system_program;
+
+    let discriminator: [u8; 8] = {
+        let data = account.try_borrow_data()?;
+        data.get(..8)
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or(OntoraError::InvalidAccount)?
+    };
+    let (from_version, to_version) = if discriminator == PlatformConfig::discriminator() {
+        (upgrade_account::<PlatformConfig>(&account, payer, system_program)?, PlatformConfig::CURRENT_VERSION)
+    } else if discriminator == AiAgent::discriminator() {
+        (upgrade_account::<AiAgent>(&account, payer, system_program)?, AiAgent::CURRENT_VERSION)
+    } else if discriminator == UserStake::discriminator() {
+        (upgrade_account::<UserStake>(&account, payer, system_program)?, UserStake::CURRENT_VERSION)
+    } else if discriminator == Proposal::discriminator() {
+        (upgrade_account::<Proposal>(&account, payer, system_program)?, Proposal::CURRENT_VERSION)
+    } else if discriminator == AIAgent::discriminator() {
+        (upgrade_account::<AIAgent>(&account, payer, system_program)?, AIAgent::CURRENT_VERSION)
+    } else {
+        return err!(OntoraError::InvalidAccount);
+    };
+
+    emit!(AccountMigrated {
+        schema_version: AccountMigrated::SCHEMA_VERSION,
+        account: account.key(),
+        discriminator,
+        from_version,
+        to_version,
+        payer: payer.key(),
+    });
+    Ok(())
+}
+
+/// Id a legacy agent's owner finds it under once migrated. Owners pick the ids
+/// of the agents they register, counting up from 0, so the top id is free.
+pub const MIGRATED_AGENT_ID: u64 = u64::MAX;
+/// Name given to a migrated legacy agent, which had none.
+pub const MIGRATED_AGENT_NAME: &str = "Legacy agent";
+/// Layout version of the first legacy `AIAgent`, which carried none.
+pub const AI_AGENT_V1: u16 = 1;
+
+/// Context for moving a legacy `AIAgent` and its stake onto the unified
+/// layout (admin only). The owner's stake becomes theirs on the migrated agent.
+#[derive(Accounts)]
+pub struct MigrateAgentV1<'info> {
+    #[account(
+        mut,
+        seeds = [PLATFORM_CONFIG_SEED],
+        bump = platform_config.bump,
+        has_one = admin @ OntoraError::UnauthorizedAdmin
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    /// The legacy agent. It stays open, emptied, so its unclaimed rewards can still be claimed.
+    #[account(mut, seeds = [LEGACY_AI_AGENT_SEED, legacy_agent.owner.as_ref()], bump = legacy_agent.bump)]
+    pub legacy_agent: Account<'info, AIAgent>,
+    /// The agent it becomes; its existence is what stops a second migration.
+    #[account(
+        init,
+        payer = admin,
+        space = AiAgent::SPACE,
+        seeds = [AI_AGENT_SEED, legacy_agent.owner.as_ref(), &MIGRATED_AGENT_ID.to_le_bytes()],
+        bump
+    )]
+    pub ai_agent: Account<'info, AiAgent>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = UserStake::SPACE,
+        seeds = [USER_STAKE_SEED, legacy_agent.owner.as_ref()],
+        bump
+    )]
+    pub user_stake: Account<'info, UserStake>,
+    #[account(
+        init,
+        payer = admin,
+        space = StakeIndexEntry::SPACE,
+        seeds = [STAKE_INDEX_SEED, legacy_agent.owner.as_ref(), &MIGRATED_AGENT_ID.to_le_bytes()],
+        bump
+    )]
+    pub stake_index: Account<'info, StakeIndexEntry>,
+    /// The vault the legacy agent's stake was deposited into.
+    #[account(mut, token::mint = stake_mint, token::authority = legacy_agent)]
+    pub staking_vault: Account<'info, TokenAccount>,
+    #[account(
+        mut,
+        address = platform_config.staking_vault @ OntoraError::InvalidVault,
+        token::mint = stake_mint,
+        token::authority = platform_config
+    )]
+    pub platform_vault: Account<'info, TokenAccount>,
+    pub stake_mint: Account<'info, Mint>,
+    /// The platform admin, who pays for the new accounts.
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Instruction to migrate a legacy agent: an `AiAgent` under `MIGRATED_AGENT_ID`
+/// takes over its owner, stake and stake time, the owner's `UserStake` and a
+/// stake index entry record the stake as theirs, and the staked tokens move to
+/// the platform vault. An agent already unstaked has nothing to migrate.
+pub fn migrate_agent_v1(ctx: Context<MigrateAgentV1>) -> Result<()> {
+    let legacy_agent = &ctx.accounts.legacy_agent;
+    require!(legacy_agent.is_active && legacy_agent.staked_amount > 0, OntoraError::AlreadyMigrated);
+    let (owner, amount, staked_at) = (legacy_agent.owner, legacy_agent.staked_amount, legacy_agent.last_stake_time);
+
+    let ai_agent = &mut ctx.accounts.ai_agent;
+    ai_agent.init(
+        MIGRATED_AGENT_ID,
+        owner,
+        MIGRATED_AGENT_NAME.to_string(),
+        String::new(),
+        staked_at,
+        ctx.bumps.ai_agent,
+    );
+    ai_agent.staked_amount = amount;
+
+    let user_stake = &mut ctx.accounts.user_stake;
+    if user_stake.user == Pubkey::default() {
+        user_stake.init(owner, ctx.bumps.user_stake);
+    }
+    // The moved stake earns on the user stake from now
+    let acc_reward_per_share = ctx.accounts.platform_config.accrue(Clock::get()?.unix_timestamp)?;
+    let staked_amount = user_stake.staked_amount.checked_add(amount).ok_or(OntoraError::ArithmeticError)?;
+    user_stake.settle(staked_amount, acc_reward_per_share)?;
+    user_stake.last_stake_update = user_stake.last_stake_update.max(staked_at);
+    user_stake.stake_changed_at = user_stake.stake_changed_at.max(staked_at);
+
+    let stake_index = &mut ctx.accounts.stake_index;
+    stake_index.init(owner, MIGRATED_AGENT_ID, staked_at, ctx.bumps.stake_index);
+    stake_index.staked_amount = amount;
+
+    // The stake moves within `total_staked`, which already counts legacy stake
+
+    // The legacy agent PDA owns its vault and signs the move
+    let seeds = &[LEGACY_AI_AGENT_SEED, owner.as_ref(), &[legacy_agent.bump]];
+    let signer = &[&seeds[..]];
+    let cpi_accounts = Transfer {
+        from: ctx.accounts.staking_vault.to_account_info(),
+        to: ctx.accounts.platform_vault.to_account_info(),
+        authority: ctx.accounts.legacy_agent.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi_accounts, signer);
+    token::transfer(cpi_ctx, amount)?;
+
+    let legacy_agent = &mut ctx.accounts.legacy_agent;
+    legacy_agent.staked_amount = 0;
+    legacy_agent.is_active = false;
+
+    emit!(AccountMigrated {
+        schema_version: AccountMigrated::SCHEMA_VERSION,
+        account: legacy_agent.key(),
+        discriminator: AIAgent::discriminator(),
+        from_version: legacy_agent.version.into(),
+        to_version: AiAgent::CURRENT_VERSION,
+        payer: ctx.accounts.admin.key(),
+    });
+    Ok(())
+}
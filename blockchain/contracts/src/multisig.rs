@@ -0,0 +1,391 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, Token, TokenAccount};
+use crate::error::OntoraError;
+use crate::events::{
+    AdminActionApproved, AdminActionExecuted, AdminActionProposed, AdminMultisigInitialized,
+    AdminTransferProposed, PauseFlagsUpdated, PlatformUpdated, RoleUpdated,
+};
+use crate::pda::{
+    ADMIN_ACTION_SEED, ADMIN_MULTISIG_SEED, PLATFORM_CONFIG_SEED, REWARD_POOL_SEED, REWARD_VAULT_SEED,
+    STAKING_VAULT_SEED,
+};
+use crate::state::*;
+
+/// Context for handing the platform admin role to a threshold multisig.
+#[derive(Accounts)]
+pub struct InitializeAdminMultisig<'info> {
+    /// The platform configuration whose admin becomes the multisig PDA.
+    #[account(
+        mut,
+        seeds = [PLATFORM_CONFIG_SEED],
+        bump = platform_config.bump,
+        has_one = admin @ OntoraError::UnauthorizedAdmin
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    /// The multisig account to be initialized.
+    #[account(
+        init,
+        payer = admin,
+        space = AdminMultisig::SPACE,
+        seeds = [ADMIN_MULTISIG_SEED],
+        bump
+    )]
+    pub admin_multisig: Account<'info, AdminMultisig>,
+    /// The current single-key admin, giving up the role.
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    /// The system program for account initialization.
+    pub system_program: Program<'info, System>,
+}
+
+/// Instruction to create the admin multisig and make it the platform admin.
+/// From then on admin operations only execute through approved proposals.
+pub fn initialize_admin_multisig(
+    ctx: Context<InitializeAdminMultisig>,
+    signers: Vec<Pubkey>,
+    threshold: u8,
+) -> Result<()> {
+    require!(
+        AdminMultisig::validate_config(&signers, threshold),
+        OntoraError::InvalidMultisigConfig
+    );
+
+    let admin_multisig = &mut ctx.accounts.admin_multisig;
+    admin_multisig.init(signers.clone(), threshold, ctx.bumps.admin_multisig);
+
+    let platform_config = &mut ctx.accounts.platform_config;
+    platform_config.admin = admin_multisig.key();
+    platform_config.pending_admin = Pubkey::default();
+
+    emit!(AdminMultisigInitialized {
+        schema_version: AdminMultisigInitialized::SCHEMA_VERSION,
+        multisig: admin_multisig.key(),
+        signers,
+        threshold,
+    });
+
+    Ok(())
+}
+
+/// Context for proposing an admin action.
+#[derive(Accounts)]
+pub struct ProposeAdminAction<'info> {
+    /// The multisig the proposal belongs to.
+    #[account(
+        mut,
+        seeds = [ADMIN_MULTISIG_SEED],
+        bump = admin_multisig.bump,
+        constraint = admin_multisig.is_signer(&proposer.key()) @ OntoraError::NotMultisigSigner
+    )]
+    pub admin_multisig: Account<'info, AdminMultisig>,
+    /// The proposal account to be initialized.
+    #[account(
+        init,
+        payer = proposer,
+        space = AdminActionProposal::SPACE,
+        seeds = [ADMIN_ACTION_SEED, admin_multisig.proposal_count.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub proposal: Account<'info, AdminActionProposal>,
+    /// The proposing signer; their proposal counts as their approval.
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+    /// The system program for account initialization.
+    pub system_program: Program<'info, System>,
+}
+
+/// Instruction to propose an admin action. The proposal expires after `ADMIN_ACTION_TTL`.
+pub fn propose_admin_action(ctx: Context<ProposeAdminAction>, action: AdminAction) -> Result<()> {
+    let clock = Clock::get()?;
+    let admin_multisig = &mut ctx.accounts.admin_multisig;
+    let proposal = &mut ctx.accounts.proposal;
+
+    proposal.multisig = admin_multisig.key();
+    proposal.id = admin_multisig.proposal_count;
+    proposal.action = action;
+    proposal.proposer = ctx.accounts.proposer.key();
+    proposal.approvals = vec![ctx.accounts.proposer.key()];
+    proposal.expires_at = clock.unix_timestamp + ADMIN_ACTION_TTL;
+    proposal.executed = false;
+    proposal.bump = ctx.bumps.proposal;
+
+    admin_multisig.proposal_count = admin_multisig
+        .proposal_count
+        .checked_add(1)
+        .ok_or(OntoraError::ArithmeticError)?;
+
+    emit!(AdminActionProposed {
+        schema_version: AdminActionProposed::SCHEMA_VERSION,
+        proposal_id: proposal.id,
+        proposer: proposal.proposer,
+        expires_at: proposal.expires_at,
+    });
+
+    Ok(())
+}
+
+/// Context for approving an admin action.
+#[derive(Accounts)]
+pub struct ApproveAdminAction<'info> {
+    /// The multisig the proposal belongs to.
+    #[account(
+        seeds = [ADMIN_MULTISIG_SEED],
+        bump = admin_multisig.bump,
+        constraint = admin_multisig.is_signer(&signer.key()) @ OntoraError::NotMultisigSigner
+    )]
+    pub admin_multisig: Account<'info, AdminMultisig>,
+    /// The proposal being approved.
+    #[account(
+        mut,
+        seeds = [ADMIN_ACTION_SEED, proposal.id.to_le_bytes().as_ref()],
+        bump = proposal.bump,
+        constraint = proposal.multisig == admin_multisig.key() @ OntoraError::InvalidAccount
+    )]
+    pub proposal: Account<'info, AdminActionProposal>,
+    /// The approving signer.
+    pub signer: Signer<'info>,
+}
+
+/// Instruction to approve an admin action. A repeated approval by the same signer is a no-op.
+pub fn approve_admin_action(ctx: Context<ApproveAdminAction>) -> Result<()> {
+    let clock = Clock::get()?;
+    let proposal = &mut ctx.accounts.proposal;
+
+    require!(!proposal.executed, OntoraError::AdminActionExecuted);
+    require!(!proposal.is_expired(clock.unix_timestamp), OntoraError::AdminActionExpired);
+
+    if proposal.approve(ctx.accounts.signer.key()) {
+        emit!(AdminActionApproved {
+            schema_version: AdminActionApproved::SCHEMA_VERSION,
+            proposal_id: proposal.id,
+            signer: ctx.accounts.signer.key(),
+            approvals: proposal.approvals.len() as u8,
+        });
+    }
+
+    Ok(())
+}
+
+/// Context for executing an approved admin action.
+#[derive(Accounts)]
+pub struct ExecuteAdminAction<'info> {
+    /// The platform configuration the action applies to; must be administered by the multisig.
+    #[account(
+        mut,
+        seeds = [PLATFORM_CONFIG_SEED],
+        bump = platform_config.bump,
+        constraint = platform_config.admin == admin_multisig.key() @ OntoraError::UnauthorizedAdmin
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    /// The multisig the proposal belongs to.
+    #[account(
+        seeds = [ADMIN_MULTISIG_SEED],
+        bump = admin_multisig.bump,
+        constraint = admin_multisig.is_signer(&executor.key()) @ OntoraError::NotMultisigSigner
+    )]
+    pub admin_multisig: Account<'info, AdminMultisig>,
+    /// The proposal being executed.
+    #[account(
+        mut,
+        seeds = [ADMIN_ACTION_SEED, proposal.id.to_le_bytes().as_ref()],
+        bump = proposal.bump,
+        constraint = proposal.multisig == admin_multisig.key() @ OntoraError::InvalidAccount
+    )]
+    pub proposal: Account<'info, AdminActionProposal>,
+    /// Any multisig signer may trigger execution once the threshold is met.
+    pub executor: Signer<'info>,
+}
+
+/// Instruction to execute an admin action once it has `threshold` distinct approvals.
+pub fn execute_admin_action(ctx: Context<ExecuteAdminAction>) -> Result<()> {
+    let clock = Clock::get()?;
+    let threshold = ctx.accounts.admin_multisig.threshold;
+    let proposal = &mut ctx.accounts.proposal;
+    require_executable(proposal, threshold, clock.unix_timestamp)?;
+
+    let platform_config = &mut ctx.accounts.platform_config;
+    match proposal.action.clone() {
+        AdminAction::UpdateConfig {
+            reward_rate_bps,
+            min_stake_amount,
+            epoch_duration,
+        } => {
+            let params = ConfigParams { reward_rate_bps, min_stake_amount, epoch_duration, ..platform_config.params() };
+            update_params(platform_config, params, proposal.id, clock.unix_timestamp)?;
+        }
+        AdminAction::UpdateParams { params } => {
+            update_params(platform_config, params, proposal.id, clock.unix_timestamp)?;
+        }
+        AdminAction::SetPauseFlags { pause_flags } => {
+            require!(pause_flags & !PAUSE_ALL == 0, OntoraError::InvalidPauseFlags);
+            let previous_flags = platform_config.pause_flags;
+            platform_config.pause_flags = pause_flags;
+            emit!(PauseFlagsUpdated {
+                schema_version: PauseFlagsUpdated::SCHEMA_VERSION,
+                authority: platform_config.admin,
+                previous_flags,
+                new_flags: pause_flags,
+            });
+        }
+        AdminAction::SetRole { role, holder } => {
+            let previous_holder = platform_config.role_holder(role);
+            platform_config.set_role_holder(role, holder);
+            emit!(RoleUpdated {
+                schema_version: RoleUpdated::SCHEMA_VERSION,
+                role,
+                previous_holder,
+                new_holder: holder,
+                authority: platform_config.admin,
+            });
+        }
+        AdminAction::ProposeAdmin { new_admin } => {
+            require!(
+                new_admin != Pubkey::default() && new_admin != platform_config.admin,
+                OntoraError::InvalidPendingAdmin
+            );
+            platform_config.pending_admin = new_admin;
+            emit!(AdminTransferProposed {
+                schema_version: AdminTransferProposed::SCHEMA_VERSION,
+                admin: platform_config.admin,
+                pending_admin: new_admin,
+                timestamp: clock.unix_timestamp,
+            });
+        }
+        // Needs the vault accounts, which `execute_initialize_vaults` takes
+        AdminAction::InitializeVaults { .. } => return err!(OntoraError::InvalidAdminAction),
+    }
+    mark_executed(proposal, ctx.accounts.executor.key(), clock.unix_timestamp);
+
+    Ok(())
+}
+
+// Check that a proposal is still open and has `threshold` approvals
+fn require_executable(proposal: &AdminActionProposal, threshold: u8, now: i64) -> Result<()> {
+    require!(!proposal.executed, OntoraError::AdminActionExecuted);
+    require!(!proposal.is_expired(now), OntoraError::AdminActionExpired);
+    require!(proposal.is_executable(threshold, now), OntoraError::ThresholdNotMet);
+    Ok(())
+}
+
+fn mark_executed(proposal: &mut AdminActionProposal, executor: Pubkey, now: i64) {
+    proposal.executed = true;
+    emit!(AdminActionExecuted {
+        schema_version: AdminActionExecuted::SCHEMA_VERSION,
+        proposal_id: proposal.id,
+        executor,
+        timestamp: now,
+    });
+}
+
+// Apply `params` to the config and log the old and new values
+fn update_params(platform_config: &mut PlatformConfig, params: ConfigParams, proposal_id: u64, now: i64) -> Result<()> {
+    let previous = platform_config.params();
+    platform_config.update_params(params, now)?;
+    emit!(PlatformUpdated {
+        schema_version: PlatformUpdated::SCHEMA_VERSION,
+        authority: platform_config.admin,
+        timestamp: now,
+        new_reward_rate: params.reward_rate_bps,
+        update_metadata: format!("admin action {}", proposal_id),
+        old_reward_rate: previous.reward_rate_bps,
+        old_min_stake_amount: previous.min_stake_amount,
+        new_min_stake_amount: params.min_stake_amount,
+        old_epoch_duration: previous.epoch_duration,
+        new_epoch_duration: params.epoch_duration,
+        old_cooldown_seconds: previous.cooldown_seconds,
+        new_cooldown_seconds: params.cooldown_seconds,
+        old_epoch_emission: previous.epoch_emission,
+        new_epoch_emission: params.epoch_emission,
+        old_max_stake_per_agent: previous.max_stake_per_agent,
+        new_max_stake_per_agent: params.max_stake_per_agent,
+        old_vesting_seconds: previous.vesting_seconds,
+        new_vesting_seconds: params.vesting_seconds,
+        old_vote_unit: previous.vote_unit,
+        new_vote_unit: params.vote_unit,
+        old_quorum_bps: previous.quorum_bps,
+        new_quorum_bps: params.quorum_bps,
+        old_approval_threshold_bps: previous.approval_threshold_bps,
+        new_approval_threshold_bps: params.approval_threshold_bps,
+    });
+    Ok(())
+}
+
+/// Context for executing an approved `AdminAction::InitializeVaults`: the multisig's
+/// counterpart of `initialize_vaults`, with the executor paying for the vaults.
+#[derive(Accounts)]
+pub struct ExecuteInitializeVaults<'info> {
+    /// The platform configuration the vaults are pinned on; must be administered by the multisig.
+    #[account(
+        mut,
+        seeds = [PLATFORM_CONFIG_SEED],
+        bump = platform_config.bump,
+        constraint = platform_config.admin == admin_multisig.key() @ OntoraError::UnauthorizedAdmin,
+        constraint = platform_config.stake_mint == Pubkey::default()
+            || platform_config.stake_mint == stake_mint.key() @ OntoraError::InvalidMint
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    /// The multisig the proposal belongs to.
+    #[account(
+        seeds = [ADMIN_MULTISIG_SEED],
+        bump = admin_multisig.bump,
+        constraint = admin_multisig.is_signer(&executor.key()) @ OntoraError::NotMultisigSigner
+    )]
+    pub admin_multisig: Account<'info, AdminMultisig>,
+    /// The proposal being executed.
+    #[account(
+        mut,
+        seeds = [ADMIN_ACTION_SEED, proposal.id.to_le_bytes().as_ref()],
+        bump = proposal.bump,
+        constraint = proposal.multisig == admin_multisig.key() @ OntoraError::InvalidAccount
+    )]
+    pub proposal: Account<'info, AdminActionProposal>,
+    /// CHECK: only the address is used, as the reward vault's authority; the pool may not exist yet
+    #[account(seeds = [REWARD_POOL_SEED], bump)]
+    pub reward_pool: UncheckedAccount<'info>,
+    /// The mint the action names.
+    pub stake_mint: Account<'info, Mint>,
+    #[account(
+        init,
+        payer = executor,
+        seeds = [STAKING_VAULT_SEED],
+        bump,
+        token::mint = stake_mint,
+        token::authority = platform_config
+    )]
+    pub staking_vault: Account<'info, TokenAccount>,
+    #[account(
+        init,
+        payer = executor,
+        seeds = [REWARD_VAULT_SEED],
+        bump,
+        token::mint = stake_mint,
+        token::authority = reward_pool
+    )]
+    pub reward_vault: Account<'info, TokenAccount>,
+    /// Any multisig signer may trigger execution once the threshold is met.
+    #[account(mut)]
+    pub executor: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Instruction to create the vaults an approved `AdminAction::InitializeVaults` names.
+pub fn execute_initialize_vaults(ctx: Context<ExecuteInitializeVaults>) -> Result<()> {
+    let clock = Clock::get()?;
+    let threshold = ctx.accounts.admin_multisig.threshold;
+    let proposal = &mut ctx.accounts.proposal;
+    require_executable(proposal, threshold, clock.unix_timestamp)?;
+    let AdminAction::InitializeVaults { stake_mint } = proposal.action else {
+        return err!(OntoraError::InvalidAdminAction);
+    };
+    require_keys_eq!(stake_mint, ctx.accounts.stake_mint.key(), OntoraError::InvalidMint);
+
+    let platform_config = &mut ctx.accounts.platform_config;
+    platform_config.stake_mint = stake_mint;
+    platform_config.staking_vault = ctx.accounts.staking_vault.key();
+    platform_config.reward_vault = ctx.accounts.reward_vault.key();
+    mark_executed(proposal, ctx.accounts.executor.key(), clock.unix_timestamp);
+
+    msg!("Vaults initialized for mint: {}", stake_mint);
+    Ok(())
+}
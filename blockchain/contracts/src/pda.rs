@@ -0,0 +1,152 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{associated_token, token};
+
+/// Seed of the platform configuration.
+pub const PLATFORM_CONFIG_SEED: &[u8] = b"platform-config";
+/// Seed of the fee schedule.
+pub const FEE_SCHEDULE_SEED: &[u8] = b"fees";
+/// Seed of the protocol treasury.
+pub const TREASURY_SEED: &[u8] = b"treasury";
+/// Seed prefix of the treasury's token vault for one mint.
+pub const TREASURY_VAULT_SEED: &[u8] = b"treasury-vault";
+/// Seed of the vault holding the stakes on agents.
+pub const STAKING_VAULT_SEED: &[u8] = b"staking-vault";
+/// Seed of the vault the reward pool pays rewards from.
+pub const REWARD_VAULT_SEED: &[u8] = b"reward-vault";
+/// Seed of the legacy reward pool.
+pub const REWARD_POOL_SEED: &[u8] = b"reward_pool";
+/// Seed prefix of an agent registered with `register_agent`.
+pub const AI_AGENT_SEED: &[u8] = b"ai-agent";
+/// Seed prefix of the single agent registered with the legacy `register_ai_agent`.
+pub const LEGACY_AI_AGENT_SEED: &[u8] = b"ai_agent";
+/// Seed prefix of the withdrawal a legacy agent's owner requested with `request_unstake`.
+pub const PENDING_WITHDRAWAL_SEED: &[u8] = b"pending-withdrawal";
+/// Seed prefix of the schedule a legacy agent's owner's claimed rewards vest on.
+pub const REWARD_VESTING_SEED: &[u8] = b"reward-vesting";
+/// Seed prefix of a user's stake position.
+pub const USER_STAKE_SEED: &[u8] = b"user-stake";
+/// Seed prefix of a (user, agent) stake index entry.
+pub const STAKE_INDEX_SEED: &[u8] = b"stake-index";
+/// Seed prefix of a governance proposal, by id, and of a legacy proposal, by creator.
+pub const PROPOSAL_SEED: &[u8] = b"proposal";
+/// Seed prefix of a voter's record on a proposal.
+pub const PROPOSAL_VOTE_SEED: &[u8] = b"proposal-vote";
+/// Seed prefix of an entity's metadata account.
+pub const METADATA_SEED: &[u8] = b"metadata";
+/// Seed of the admin multisig.
+pub const ADMIN_MULTISIG_SEED: &[u8] = b"admin-multisig";
+/// Seed prefix of an admin action proposed to the multisig.
+pub const ADMIN_ACTION_SEED: &[u8] = b"admin-action";
+/// Seed of the external governance authorized to execute admin instructions.
+pub const GOVERNANCE_REALM_SEED: &[u8] = b"governance-realm";
+
+/// The platform configuration.
+pub fn platform_config() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[PLATFORM_CONFIG_SEED], &crate::ID)
+}
+
+/// The fee schedule.
+pub fn fee_schedule() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[FEE_SCHEDULE_SEED], &crate::ID)
+}
+
+/// The protocol treasury.
+pub fn treasury() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[TREASURY_SEED], &crate::ID)
+}
+
+/// The treasury's token vault for `mint`.
+pub fn treasury_vault(mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[TREASURY_VAULT_SEED, mint.as_ref()], &crate::ID)
+}
+
+/// The vault holding the stakes on agents, held by the platform configuration.
+pub fn staking_vault() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[STAKING_VAULT_SEED], &crate::ID)
+}
+
+/// The vault the reward pool pays rewards from, held by the reward pool.
+pub fn reward_vault() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[REWARD_VAULT_SEED], &crate::ID)
+}
+
+/// The legacy reward pool.
+pub fn reward_pool() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[REWARD_POOL_SEED], &crate::ID)
+}
+
+/// Agent `agent_id` of `owner`.
+pub fn ai_agent(owner: &Pubkey, agent_id: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[AI_AGENT_SEED, owner.as_ref(), &agent_id.to_le_bytes()], &crate::ID)
+}
+
+/// The legacy agent of `owner`.
+pub fn legacy_ai_agent(owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[LEGACY_AI_AGENT_SEED, owner.as_ref()], &crate::ID)
+}
+
+/// The withdrawal `owner` requested from their legacy agent.
+pub fn pending_withdrawal(owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[PENDING_WITHDRAWAL_SEED, owner.as_ref()], &crate::ID)
+}
+
+/// The schedule `owner`'s claimed legacy rewards vest on.
+pub fn reward_vesting(owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[REWARD_VESTING_SEED, owner.as_ref()], &crate::ID)
+}
+
+/// The stake position of `user`.
+pub fn user_stake(user: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[USER_STAKE_SEED, user.as_ref()], &crate::ID)
+}
+
+/// The index entry of `user`'s stake on agent `agent_id`.
+pub fn stake_index(user: &Pubkey, agent_id: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[STAKE_INDEX_SEED, user.as_ref(), &agent_id.to_le_bytes()], &crate::ID)
+}
+
+/// Governance proposal `proposal_id`.
+pub fn proposal(proposal_id: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[PROPOSAL_SEED, &proposal_id.to_le_bytes()], &crate::ID)
+}
+
+/// The legacy proposal opened by `creator`.
+pub fn legacy_proposal(creator: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[PROPOSAL_SEED, creator.as_ref()], &crate::ID)
+}
+
+/// The record of `voter`'s vote on proposal `proposal_id`.
+pub fn proposal_vote(proposal_id: u64, voter: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[PROPOSAL_VOTE_SEED, &proposal_id.to_le_bytes(), voter.as_ref()], &crate::ID)
+}
+
+/// Metadata of entity `entity_id` under `authority`.
+pub fn metadata(authority: &Pubkey, entity_id: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[METADATA_SEED, authority.as_ref(), &entity_id.to_le_bytes()], &crate::ID)
+}
+
+/// The admin multisig.
+pub fn admin_multisig() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[ADMIN_MULTISIG_SEED], &crate::ID)
+}
+
+/// Admin action `action_id` proposed to the multisig.
+pub fn admin_action(action_id: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[ADMIN_ACTION_SEED, &action_id.to_le_bytes()], &crate::ID)
+}
+
+/// The external governance authorized to execute admin instructions.
+pub fn governance_realm() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[GOVERNANCE_REALM_SEED], &crate::ID)
+}
+
+// Legacy agent vaults are associated token accounts of the agent, so they are
+// derived under the associated token program rather than this one.
+fn associated_vault(owner: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[owner.as_ref(), token::ID.as_ref(), mint.as_ref()], &associated_token::ID)
+}
+
+/// The vault `owner`'s legacy agent holds its stake of `mint` in.
+pub fn legacy_staking_vault(owner: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    associated_vault(&legacy_ai_agent(owner).0, mint)
+}
@@ -0,0 +1,90 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{get_stack_height, TRANSACTION_LEVEL_STACK_HEIGHT};
+use crate::error::OntoraError;
+use crate::events::GovernanceRealmSet;
+use crate::pda::{GOVERNANCE_REALM_SEED, PLATFORM_CONFIG_SEED};
+use crate::state::*;
+
+/// Context for authorizing an spl-governance (Realms) governance as an
+/// executor of admin instructions.
+#[derive(Accounts)]
+pub struct SetGovernanceRealm<'info> {
+    #[account(
+        seeds = [PLATFORM_CONFIG_SEED],
+        bump = platform_config.bump,
+        has_one = admin @ OntoraError::UnauthorizedAdmin
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    /// The recorded governance; setting it again replaces the previous one.
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = GovernanceRealm::SPACE,
+        seeds = [GOVERNANCE_REALM_SEED],
+        bump
+    )]
+    pub governance_realm: Account<'info, GovernanceRealm>,
+    /// CHECK: the spl-governance governance account; its type and realm are
+    /// checked in the handler, and its owner is taken as the governance program.
+    pub governance: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    /// The system program for account initialization.
+    pub system_program: Program<'info, System>,
+}
+
+/// Instruction to let `governance` of `realm` execute admin instructions
+/// through its native treasury, alongside the admin.
+pub fn set_governance_realm(ctx: Context<SetGovernanceRealm>, realm: Pubkey, governance: Pubkey) -> Result<()> {
+    let info = &ctx.accounts.governance;
+    require_keys_eq!(info.key(), governance, OntoraError::InvalidAccount);
+    // spl-governance accounts lead with their account type, and governance
+    // accounts follow it with their realm
+    let data = info.try_borrow_data()?;
+    require!(
+        data.len() >= 33 && GOVERNANCE_ACCOUNT_TYPES.contains(&data[0]) && data[1..33] == realm.to_bytes(),
+        OntoraError::InvalidAccount
+    );
+    require!(*info.owner != crate::ID && *info.owner != System::id(), OntoraError::InvalidAccount);
+
+    let governance_realm = &mut ctx.accounts.governance_realm;
+    governance_realm.init(realm, governance, *info.owner, ctx.bumps.governance_realm);
+
+    emit!(GovernanceRealmSet {
+        schema_version: GovernanceRealmSet::SCHEMA_VERSION,
+        admin: ctx.accounts.admin.key(),
+        realm,
+        governance,
+        native_treasury: governance_realm.native_treasury,
+    });
+    Ok(())
+}
+
+/// Checks that `signer` is `authority`, or else the native treasury of the
+/// governance recorded by `set_governance_realm`, passed as the first
+/// remaining account. The treasury is a PDA of the governance program, so its
+/// signature proves that program is the caller; the call must also be a CPI,
+/// as proposal execution always is. Fails with `error` otherwise.
+pub fn require_authority(
+    authority: &Pubkey,
+    signer: &Signer,
+    remaining_accounts: &[AccountInfo],
+    error: OntoraError,
+) -> Result<()> {
+    if signer.key() == *authority {
+        return Ok(());
+    }
+    let Some(info) = remaining_accounts.first() else {
+        return Err(error.into());
+    };
+    require_keys_eq!(*info.owner, crate::ID, OntoraError::InvalidAccount);
+    let governance_realm = GovernanceRealm::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+    let expected = Pubkey::create_program_address(&[GOVERNANCE_REALM_SEED, &[governance_realm.bump]], &crate::ID)
+        .map_err(|_| OntoraError::InvalidAccount)?;
+    require_keys_eq!(info.key(), expected, OntoraError::InvalidAccount);
+    require_keys_eq!(signer.key(), governance_realm.native_treasury, error);
+    if get_stack_height() <= TRANSACTION_LEVEL_STACK_HEIGHT {
+        return Err(error.into());
+    }
+    Ok(())
+}
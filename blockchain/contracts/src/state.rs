@@ -3,11 +3,141 @@ use anchor_lang::prelude::*;
 // Constants for maximum sizes to prevent excessive memory allocation
 pub const MAX_NAME_LENGTH: usize = 32;
 pub const MAX_DESCRIPTION_LENGTH: usize = 256;
-pub const MAX_AGENTS_PER_USER: usize = 10;
+// Hard ceiling on `Metadata.data`; accounts are sized to their payload up to this
+pub const MAX_METADATA_LENGTH: usize = 2048;
+pub const MAX_MULTISIG_SIGNERS: usize = 7;
+// Seconds an admin action proposal stays open for approvals and execution
+pub const ADMIN_ACTION_TTL: i64 = 3 * 86400;
+
+// Bounds on platform parameters, enforced at initialization and on every update
+pub const MAX_REWARD_RATE_BPS: u64 = 10_000;
+pub const MIN_EPOCH_DURATION: i64 = 3600; // 1 hour
+pub const MAX_EPOCH_DURATION: i64 = 365 * 86400; // 1 year
+pub const MAX_COOLDOWN_SECONDS: i64 = 30 * 86400; // 30 days
+// Unstaking cooldown of configs created before it was configurable
+pub const DEFAULT_COOLDOWN_SECONDS: i64 = 86400; // 24 hours
+pub const MAX_VESTING_SECONDS: i64 = 365 * 86400; // 1 year
+// Stake per vote a new config starts with
+pub const DEFAULT_VOTE_UNIT: u64 = 100_000_000_000; // 100 tokens at 9 decimals
+// Proposal quorum and approval threshold a new config starts with
+pub const DEFAULT_QUORUM_BPS: u16 = 1000; // 10% of the stake must vote
+pub const DEFAULT_APPROVAL_THRESHOLD_BPS: u16 = 5000; // the winner needs half the votes
+pub const MAX_GOVERNANCE_BPS: u16 = 10_000;
+pub const MAX_UPDATE_METADATA_LENGTH: usize = 128;
+
+// Hard caps per fee type, enforced on every fee schedule change
+pub const MAX_STAKE_FEE_BPS: u16 = 500; // 5%
+pub const MAX_UNSTAKE_FEE_BPS: u16 = 500; // 5%
+pub const MAX_CLAIM_FEE_BPS: u16 = 1000; // 10%
+pub const MAX_EMERGENCY_PENALTY_BPS: u16 = 2000; // 20%
+
+// Governance proposal limits
+pub const MAX_PROPOSAL_TITLE_LENGTH: usize = 100;
+pub const MAX_PROPOSAL_DESCRIPTION_LENGTH: usize = 1000;
+pub const MAX_PROPOSAL_OPTIONS: usize = 10;
+pub const MAX_PROPOSAL_OPTION_LENGTH: usize = 32;
+pub const MAX_VOTING_DURATION: u64 = 90 * 86400; // 90 days
+
+// `Proposal.status` values
+pub const PROPOSAL_ACTIVE: u8 = 0;
+pub const PROPOSAL_APPROVED: u8 = 1;
+pub const PROPOSAL_REJECTED: u8 = 2;
+// Frozen while governance is disabled: no voting, tallies kept
+pub const PROPOSAL_SUSPENDED: u8 = 3;
+// Approved and its action applied by `execute_proposal`
+pub const PROPOSAL_EXECUTED: u8 = 4;
+// Bytes `Proposal.action` holds: the largest ProposalAction, a variant byte and an 8-byte value
+pub const PROPOSAL_ACTION_LEN: usize = 9;
+// Ballot option that carries out a proposal's action: `execute_proposal` runs only if it won
+pub const PROPOSAL_APPROVE_OPTION: u8 = 0;
+
+// `ProposalFinalized.reason` values: why a proposal was approved or rejected
+pub const FINALIZED_APPROVED: u8 = 0;
+// Fewer votes than `quorum_bps` of the stake snapshotted at creation (or none at all)
+pub const FINALIZED_NO_QUORUM: u8 = 1;
+// Two or more options share the highest tally
+pub const FINALIZED_TIE: u8 = 2;
+// The winning option's share of the votes is under `approval_threshold_bps`
+pub const FINALIZED_BELOW_THRESHOLD: u8 = 3;
+
+// Pause flag bits on `PlatformConfig.pause_flags`
+pub const PAUSE_DEPOSITS: u8 = 1 << 0;
+pub const PAUSE_WITHDRAWALS: u8 = 1 << 1;
+pub const PAUSE_CLAIMS: u8 = 1 << 2;
+pub const PAUSE_GOVERNANCE: u8 = 1 << 3;
+pub const PAUSE_ORACLE_UPDATES: u8 = 1 << 4;
+pub const PAUSE_ALL: u8 = PAUSE_DEPOSITS | PAUSE_WITHDRAWALS | PAUSE_CLAIMS | PAUSE_GOVERNANCE | PAUSE_ORACLE_UPDATES;
+
+// Current `PlatformConfig` layout version. Accounts created before the field
+// existed read back 0 once grown by `migrate_platform_config`.
+pub const PLATFORM_CONFIG_VERSION: u16 = 15;
+
+// Current layout versions of the accounts that carry a leading `version: u8`.
+// Accounts created before the field existed are one byte short and read as 0.
+pub const AI_AGENT_VERSION: u8 = 2;
+// UserStake v2 dropped the `staked_agents` list in favour of StakeIndexEntry PDAs;
+// v3 appended `reward_debt`; v4 appended the owner's last change and the stake others funded since.
+pub const USER_STAKE_VERSION: u8 = 4;
+// Proposal v2 appended the stake snapshot and the quorum and threshold it was created under;
+// v3 appended the slot of the snapshot; v4 appended the action executing it applies;
+// v5 appended the vote unit it was created under.
+pub const PROPOSAL_VERSION: u8 = 5;
+
+// Semantic version of this program build, taken from the crate version at compile time
+pub const PROGRAM_VERSION: [u16; 3] = [
+    parse_version_part(env!("CARGO_PKG_VERSION_MAJOR")),
+    parse_version_part(env!("CARGO_PKG_VERSION_MINOR")),
+    parse_version_part(env!("CARGO_PKG_VERSION_PATCH")),
+];
+
+// Oldest client SDK version that understands this program's accounts and instructions
+pub const MIN_CLIENT_VERSION: [u16; 3] = [1, 0, 0];
+
+// Parse one decimal component of the crate version in a const context
+const fn parse_version_part(part: &str) -> u16 {
+    let bytes = part.as_bytes();
+    let mut value: u16 = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        value = value * 10 + (bytes[i] - b'0') as u16;
+        i += 1;
+    }
+    value
+}
+
+// Return data of `get_version`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VersionInfo {
+    pub program_version: [u16; 3],
+    pub min_client_version: [u16; 3],
+    pub platform_config_version: u16,
+}
+
+// Operational roles the admin delegates to separate keys
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub enum Role {
+    // Runs reward distribution
+    Crank,
+    // Posts agent performance scores
+    Oracle,
+    // Sets pause bits
+    Pauser,
+    // Manages the protocol treasury
+    Treasury,
+}
+
+// Holders of the delegated roles. The pauser predates this struct and stays a
+// top-level field on `PlatformConfig` to keep the layout append-only.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, InitSpace)]
+pub struct Roles {
+    pub crank: Pubkey,
+    pub oracle: Pubkey,
+    pub treasury: Pubkey,
+}
 
 // Global configuration account for the Nivaro AI platform
 #[account]
-#[derive(Default)]
+#[derive(Default, InitSpace)]
 pub struct PlatformConfig {
     // Platform administrator (can update settings)
     pub admin: Pubkey,
@@ -17,48 +147,912 @@ pub struct PlatformConfig {
     pub min_stake_amount: u64,
     // Epoch duration in seconds (e.g., 86400 for 1 day)
     pub epoch_duration: i64,
-    // Timestamp of the last reward distribution
+    // Timestamp `acc_reward_per_share` was last brought up to
     pub last_reward_timestamp: i64,
     // Total staked amount across the platform
     pub total_staked: u64,
     // Bump seed for PDA derivation
     pub bump: u8,
+    // Admin nominated by `propose_admin`; becomes admin once they accept (default = none)
+    pub pending_admin: Pubkey,
+    // Bitfield of paused operation groups (PAUSE_* constants)
+    pub pause_flags: u8,
+    // Key allowed to set pause bits (only the admin may clear them)
+    pub pauser: Pubkey,
+    // Account layout version (PLATFORM_CONFIG_VERSION when current)
+    pub version: u16,
+    // Crank, oracle, and treasury role holders (default = renounced)
+    pub roles: Roles,
+    // PROGRAM_VERSION of the build that last initialized or migrated this account
+    pub program_version: [u16; 3],
+    // Whether proposals can be created, voted on, and finalized
+    pub governance_enabled: bool,
+    // Number of proposals created (next proposal id)
+    pub proposal_count: u64,
+    // Proposals not yet finalized (active or suspended)
+    pub active_proposal_count: u64,
+    // Seconds after a legacy agent's last stake change before it can be unstaked
+    pub cooldown_seconds: i64,
+    // Rewards shared among the legacy agents each epoch, pro rata to their stake
+    pub epoch_emission: u64,
+    // Rewards earned per staked token since the platform started, scaled by ACC_PRECISION
+    pub acc_reward_per_share: u128,
+    // Legacy agents registered with `register_ai_agent` (from layout v9 on)
+    pub total_agents: u64,
+    // Mint of the staking and reward vaults, pinned by `initialize_vaults` (default = unset)
+    pub stake_mint: Pubkey,
+    // Vault of the stakes on agents, created by `initialize_vaults` (default = unset)
+    pub staking_vault: Pubkey,
+    // Vault the reward pool pays rewards from, created by `initialize_vaults` (default = unset)
+    pub reward_vault: Pubkey,
+    // Most a unified agent may hold in stake (0 = unlimited)
+    pub max_stake_per_agent: u64,
+    // Seconds claimed legacy rewards vest over before `release_vested` pays them (0 = paid at once)
+    pub vesting_seconds: i64,
+    // Stake that counts as one vote in `cast_vote`
+    pub vote_unit: u64,
+    // Share of `total_staked` that must vote for a proposal to pass (in basis points)
+    pub quorum_bps: u16,
+    // Share of the votes a proposal's winning option needs to pass (in basis points)
+    pub approval_threshold_bps: u16,
+    // Reward epoch the legacy payouts below were made in
+    pub payout_epoch: u64,
+    // `total_staked` at the epoch's first legacy payout, which every share that epoch is taken of
+    pub payout_total_staked: u64,
+    // Emission paid to legacy agents in `payout_epoch` so far, at most `epoch_emission`
+    pub payout_paid: u64,
 }
 
 impl PlatformConfig {
     // Initialize the platform configuration with default values
-    pub fn init(&mut self, admin: Pubkey, reward_rate_bps: u64, min_stake_amount: u64, epoch_duration: i64, bump: u8) {
+    pub fn init(
+        &mut self,
+        admin: Pubkey,
+        reward_rate_bps: u64,
+        min_stake_amount: u64,
+        epoch_duration: i64,
+        cooldown_seconds: i64,
+        epoch_emission: u64,
+        bump: u8,
+    ) {
         self.admin = admin;
         self.reward_rate_bps = reward_rate_bps;
         self.min_stake_amount = min_stake_amount;
         self.epoch_duration = epoch_duration;
+        self.cooldown_seconds = cooldown_seconds;
+        self.epoch_emission = epoch_emission;
         self.last_reward_timestamp = 0;
         self.total_staked = 0;
         self.bump = bump;
+        self.pending_admin = Pubkey::default();
+        self.pause_flags = 0;
+        self.pauser = admin;
+        self.version = PLATFORM_CONFIG_VERSION;
+        self.program_version = PROGRAM_VERSION;
+        self.roles = Roles {
+            crank: admin,
+            oracle: admin,
+            treasury: admin,
+        };
+        self.governance_enabled = true;
+        self.proposal_count = 0;
+        self.active_proposal_count = 0;
+        self.acc_reward_per_share = 0;
+        self.total_agents = 0;
+        self.stake_mint = Pubkey::default();
+        self.staking_vault = Pubkey::default();
+        self.reward_vault = Pubkey::default();
+        self.max_stake_per_agent = 0;
+        self.vesting_seconds = 0;
+        self.vote_unit = DEFAULT_VOTE_UNIT;
+        self.quorum_bps = DEFAULT_QUORUM_BPS;
+        self.approval_threshold_bps = DEFAULT_APPROVAL_THRESHOLD_BPS;
+        self.payout_epoch = 0;
+        self.payout_total_staked = 0;
+        self.payout_paid = 0;
+    }
+
+    // Fill fields added since `version` with safe defaults, bump to the current
+    // version, and stamp the running program version. Returns the previous
+    // layout version, or None if both the layout and the stamp are current.
+    pub fn migrate(&mut self, now: i64) -> Option<u16> {
+        if self.version >= PLATFORM_CONFIG_VERSION && self.program_version == PROGRAM_VERSION {
+            return None;
+        }
+        let from_version = self.version;
+
+        // Zero-filled by the realloc; only fields whose zero value is unsafe need care.
+        // Governance stays disabled after migrating from v3 until the admin enables it.
+        // The epoch emission stays 0 after migrating from v5, so no legacy rewards are
+        // distributed until the admin sets one. The agent count starts at 0 after
+        // migrating from v8; agents registered before it are not counted. The vaults
+        // stay unset after migrating from v9 until the admin runs `initialize_vaults`.
+        // Stakes on agents stay uncapped after migrating from v10, and claims stay
+        // liquid after migrating from v11. The payout bookkeeping of v15 starts with the
+        // next payout, as no reward epoch is 0.
+        if self.pauser == Pubkey::default() {
+            self.pauser = self.admin;
+        }
+        if from_version < 2 {
+            self.roles = Roles {
+                crank: self.admin,
+                oracle: self.admin,
+                treasury: self.admin,
+            };
+        }
+        if from_version < 5 {
+            self.cooldown_seconds = DEFAULT_COOLDOWN_SECONDS;
+        }
+        // The reward accumulator starts from zero at the migration
+        if from_version < 7 {
+            self.last_reward_timestamp = now;
+        }
+        if from_version < 13 {
+            self.vote_unit = DEFAULT_VOTE_UNIT;
+        }
+        if from_version < 14 {
+            self.quorum_bps = DEFAULT_QUORUM_BPS;
+            self.approval_threshold_bps = DEFAULT_APPROVAL_THRESHOLD_BPS;
+        }
+        self.version = PLATFORM_CONFIG_VERSION;
+        self.program_version = PROGRAM_VERSION;
+        Some(from_version)
+    }
+
+    pub fn is_paused(&self, flag: u8) -> bool {
+        self.pause_flags & flag != 0
+    }
+
+    // Fail with `PlatformPaused` when the operation group `flag` is paused
+    pub fn require_not_paused(&self, flag: u8) -> Result<()> {
+        require!(!self.is_paused(flag), crate::error::OntoraError::PlatformPaused);
+        Ok(())
+    }
+
+    // Check platform parameters against the bounds above
+    pub fn validate_params(
+        reward_rate_bps: u64,
+        min_stake_amount: u64,
+        epoch_duration: i64,
+        cooldown_seconds: i64,
+    ) -> Result<()> {
+        require!(
+            reward_rate_bps <= MAX_REWARD_RATE_BPS
+                && min_stake_amount > 0
+                && (MIN_EPOCH_DURATION..=MAX_EPOCH_DURATION).contains(&epoch_duration)
+                && (0..=MAX_COOLDOWN_SECONDS).contains(&cooldown_seconds),
+            crate::error::OntoraError::InvalidConfig
+        );
+        Ok(())
+    }
+
+    // Validate and apply a parameter update (shared by the admin and multisig paths).
+    // Rewards up to `now` accrue at the old rate first. A stake cap may not be below
+    // the minimum stake, a vote takes some stake, and governance shares are at most 100%.
+    pub fn update_params(&mut self, params: ConfigParams, now: i64) -> Result<()> {
+        Self::validate_params(
+            params.reward_rate_bps,
+            params.min_stake_amount,
+            params.epoch_duration,
+            params.cooldown_seconds,
+        )?;
+        require!(
+            (params.max_stake_per_agent == 0 || params.max_stake_per_agent >= params.min_stake_amount)
+                && (0..=MAX_VESTING_SECONDS).contains(&params.vesting_seconds)
+                && params.vote_unit > 0
+                && params.quorum_bps <= MAX_GOVERNANCE_BPS
+                && params.approval_threshold_bps <= MAX_GOVERNANCE_BPS,
+            crate::error::OntoraError::InvalidConfig
+        );
+        self.accrue(now)?;
+        self.reward_rate_bps = params.reward_rate_bps;
+        self.min_stake_amount = params.min_stake_amount;
+        self.epoch_duration = params.epoch_duration;
+        self.cooldown_seconds = params.cooldown_seconds;
+        self.epoch_emission = params.epoch_emission;
+        self.max_stake_per_agent = params.max_stake_per_agent;
+        self.vesting_seconds = params.vesting_seconds;
+        self.vote_unit = params.vote_unit;
+        self.quorum_bps = params.quorum_bps;
+        self.approval_threshold_bps = params.approval_threshold_bps;
+        Ok(())
+    }
+
+    // The reward per staked token at `now`, without recording it
+    pub fn reward_per_share_at(&self, now: i64) -> Result<u128> {
+        let elapsed = now.saturating_sub(self.last_reward_timestamp);
+        let growth = crate::math::reward_per_share_growth(self.reward_rate_bps, elapsed, self.epoch_duration)
+            .ok_or(crate::error::OntoraError::ArithmeticError)?;
+        Ok(self
+            .acc_reward_per_share
+            .checked_add(growth)
+            .ok_or(crate::error::OntoraError::ArithmeticError)?)
+    }
+
+    // Bring the reward accumulator up to `now` and return it. Must run before
+    // any stake changes or the rate does, so each share accrues at the stake
+    // and rate it was held at.
+    pub fn accrue(&mut self, now: i64) -> Result<u128> {
+        self.acc_reward_per_share = self.reward_per_share_at(now)?;
+        self.last_reward_timestamp = self.last_reward_timestamp.max(now);
+        Ok(self.acc_reward_per_share)
+    }
+
+    // A legacy agent's payout in reward epoch `epoch`: its share of the epoch emission,
+    // pro rata to `total_staked` as it stood at the epoch's first payout, so an agent that
+    // unstakes after being paid does not grow the shares of the rest. Stake added since can
+    // still take the epoch past its emission, so what is left of it caps each payout.
+    pub fn epoch_payout(&mut self, epoch: u64, staked: u64, pool_balance: u64) -> Result<u64> {
+        if self.payout_epoch != epoch {
+            self.payout_epoch = epoch;
+            self.payout_total_staked = self.total_staked;
+            self.payout_paid = 0;
+        }
+        let share =
+            crate::math::pro_rata_payout(self.epoch_emission, staked, self.payout_total_staked, pool_balance)?;
+        let reward = share.min(self.epoch_emission.saturating_sub(self.payout_paid));
+        self.payout_paid = self.payout_paid.checked_add(reward).ok_or(crate::error::OntoraError::ArithmeticError)?;
+        Ok(reward)
+    }
+
+    // The parameters `update_platform_config` sets, as they are now
+    pub fn params(&self) -> ConfigParams {
+        ConfigParams {
+            reward_rate_bps: self.reward_rate_bps,
+            min_stake_amount: self.min_stake_amount,
+            epoch_duration: self.epoch_duration,
+            cooldown_seconds: self.cooldown_seconds,
+            epoch_emission: self.epoch_emission,
+            max_stake_per_agent: self.max_stake_per_agent,
+            vesting_seconds: self.vesting_seconds,
+            vote_unit: self.vote_unit,
+            quorum_bps: self.quorum_bps,
+            approval_threshold_bps: self.approval_threshold_bps,
+        }
+    }
+
+    pub fn role_holder(&self, role: Role) -> Pubkey {
+        match role {
+            Role::Crank => self.roles.crank,
+            Role::Oracle => self.roles.oracle,
+            Role::Pauser => self.pauser,
+            Role::Treasury => self.roles.treasury,
+        }
+    }
+
+    pub fn set_role_holder(&mut self, role: Role, holder: Pubkey) {
+        match role {
+            Role::Crank => self.roles.crank = holder,
+            Role::Oracle => self.roles.oracle = holder,
+            Role::Pauser => self.pauser = holder,
+            Role::Treasury => self.roles.treasury = holder,
+        }
+    }
+
+    // Whether `key` holds `role` (a renounced role matches nobody)
+    pub fn has_role(&self, role: Role, key: &Pubkey) -> bool {
+        *key != Pubkey::default() && self.role_holder(role) == *key
+    }
+
+    // Whether an admin transfer is awaiting acceptance
+    pub fn has_pending_admin(&self) -> bool {
+        self.pending_admin != Pubkey::default()
+    }
+
+    // Space required for the account: discriminator plus the derived layout
+    pub const SPACE: usize = 8 + Self::INIT_SPACE;
+}
+
+// Threshold multisig that holds the platform admin role
+#[account]
+#[derive(Default, InitSpace)]
+pub struct AdminMultisig {
+    // Keys allowed to propose, approve, and execute admin actions
+    #[max_len(MAX_MULTISIG_SIGNERS)]
+    pub signers: Vec<Pubkey>,
+    // Approvals required before an action executes
+    pub threshold: u8,
+    // Number of proposals created (next proposal id)
+    pub proposal_count: u64,
+    // Bump seed for PDA derivation
+    pub bump: u8,
+}
+
+impl AdminMultisig {
+    // Initialize the multisig with its signer set
+    pub fn init(&mut self, signers: Vec<Pubkey>, threshold: u8, bump: u8) {
+        self.signers = signers;
+        self.threshold = threshold;
+        self.proposal_count = 0;
+        self.bump = bump;
+    }
+
+    // Check that the signer set is non-empty, unique, bounded, and the threshold reachable
+    pub fn validate_config(signers: &[Pubkey], threshold: u8) -> bool {
+        let unique = signers
+            .iter()
+            .enumerate()
+            .all(|(i, key)| !signers[..i].contains(key));
+        !signers.is_empty()
+            && signers.len() <= MAX_MULTISIG_SIGNERS
+            && unique
+            && threshold > 0
+            && threshold as usize <= signers.len()
+    }
+
+    pub fn is_signer(&self, key: &Pubkey) -> bool {
+        self.signers.contains(key)
+    }
+
+    // Space required for the account: discriminator plus the derived layout
+    pub const SPACE: usize = 8 + Self::INIT_SPACE;
+}
+
+// Every parameter `update_platform_config` sets
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, InitSpace)]
+pub struct ConfigParams {
+    pub reward_rate_bps: u64,
+    pub min_stake_amount: u64,
+    pub epoch_duration: i64,
+    pub cooldown_seconds: i64,
+    pub epoch_emission: u64,
+    pub max_stake_per_agent: u64,
+    pub vesting_seconds: i64,
+    pub vote_unit: u64,
+    pub quorum_bps: u16,
+    pub approval_threshold_bps: u16,
+}
+
+// Admin operations the multisig can execute against the platform config
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, InitSpace)]
+pub enum AdminAction {
+    // The parameters `update_platform_config` took when the multisig was added;
+    // the others are left as they are
+    UpdateConfig {
+        reward_rate_bps: u64,
+        min_stake_amount: u64,
+        epoch_duration: i64,
+    },
+    // Replace the pause bitfield (may clear bits, unlike the pauser)
+    SetPauseFlags { pause_flags: u8 },
+    // Rotate a delegated role
+    SetRole { role: Role, holder: Pubkey },
+    // Nominate a new admin through the two-step transfer
+    ProposeAdmin { new_admin: Pubkey },
+    // Replace every parameter `update_platform_config` sets
+    UpdateParams { params: ConfigParams },
+    // Create the staking and reward vaults for `stake_mint`, like `initialize_vaults`.
+    // Runs through `execute_initialize_vaults`, which takes the vault accounts.
+    InitializeVaults { stake_mint: Pubkey },
+}
+
+impl Default for AdminAction {
+    fn default() -> Self {
+        AdminAction::SetPauseFlags { pause_flags: 0 }
+    }
+}
+
+// A pending admin action collecting multisig approvals
+#[account]
+#[derive(Default, InitSpace)]
+pub struct AdminActionProposal {
+    // Multisig this proposal belongs to
+    pub multisig: Pubkey,
+    // Sequential proposal id within the multisig
+    pub id: u64,
+    // The action executed once approved
+    pub action: AdminAction,
+    // Signer who proposed the action
+    pub proposer: Pubkey,
+    // Signers who approved (deduplicated)
+    #[max_len(MAX_MULTISIG_SIGNERS)]
+    pub approvals: Vec<Pubkey>,
+    // Timestamp after which the proposal can no longer be approved or executed
+    pub expires_at: i64,
+    // Whether the action has been executed
+    pub executed: bool,
+    // Bump seed for PDA derivation
+    pub bump: u8,
+}
+
+impl AdminActionProposal {
+    // Record an approval; returns false when the signer already approved
+    pub fn approve(&mut self, signer: Pubkey) -> bool {
+        if self.approvals.contains(&signer) {
+            return false;
+        }
+        self.approvals.push(signer);
+        true
+    }
+
+    pub fn is_expired(&self, now: i64) -> bool {
+        now > self.expires_at
+    }
+
+    // Whether the action may execute now under the given threshold
+    pub fn is_executable(&self, threshold: u8, now: i64) -> bool {
+        !self.executed && !self.is_expired(now) && self.approvals.len() >= threshold as usize
+    }
+
+    // Space required for the account: discriminator plus the derived layout
+    pub const SPACE: usize = 8 + Self::INIT_SPACE;
+}
+
+// Instruction category a treasury deposit came from
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FeeSource {
+    Unstake,
+    Claim,
+    Subscription,
+}
+
+impl FeeSource {
+    pub const COUNT: usize = 3;
+
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
+
+// Fee categories priced by the `FeeSchedule`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FeeType {
+    Stake,
+    Unstake,
+    Claim,
+    // Penalty for unstaking before the cooldown ends
+    EmergencyUnstake,
+}
+
+impl FeeType {
+    pub const COUNT: usize = 4;
+    pub const ALL: [FeeType; FeeType::COUNT] = [
+        FeeType::Stake,
+        FeeType::Unstake,
+        FeeType::Claim,
+        FeeType::EmergencyUnstake,
+    ];
+
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    // Hard cap for this fee type
+    pub fn max_bps(self) -> u16 {
+        match self {
+            FeeType::Stake => MAX_STAKE_FEE_BPS,
+            FeeType::Unstake => MAX_UNSTAKE_FEE_BPS,
+            FeeType::Claim => MAX_CLAIM_FEE_BPS,
+            FeeType::EmergencyUnstake => MAX_EMERGENCY_PENALTY_BPS,
+        }
+    }
+}
+
+// Every protocol fee rate, in one place (seeds [b"fees"]). Changes are staged
+// in `pending_rates_bps` and take effect at the next epoch boundary.
+#[account]
+#[derive(Default, InitSpace)]
+pub struct FeeSchedule {
+    // Key that may update the schedule besides the platform admin
+    pub governance: Pubkey,
+    // Rates in effect, indexed by `FeeType` (basis points)
+    pub rates_bps: [u16; FeeType::COUNT],
+    // Rates that replace `rates_bps` at `pending_effective_at`
+    pub pending_rates_bps: [u16; FeeType::COUNT],
+    // Timestamp the pending rates take effect (0 = nothing pending)
+    pub pending_effective_at: i64,
+    // Bump seed for PDA derivation
+    pub bump: u8,
+}
+
+impl FeeSchedule {
+    // Initialize the schedule with rates that apply immediately
+    pub fn init(&mut self, governance: Pubkey, rates_bps: [u16; FeeType::COUNT], bump: u8) {
+        self.governance = governance;
+        self.rates_bps = rates_bps;
+        self.pending_rates_bps = [0; FeeType::COUNT];
+        self.pending_effective_at = 0;
+        self.bump = bump;
+    }
+
+    // Check every rate against its fee type's hard cap
+    pub fn validate_rates(rates_bps: &[u16; FeeType::COUNT]) -> bool {
+        FeeType::ALL
+            .iter()
+            .all(|fee_type| rates_bps[fee_type.index()] <= fee_type.max_bps())
+    }
+
+    // First epoch boundary strictly after `now`, with epochs aligned to the unix epoch
+    pub fn next_epoch_boundary(now: i64, epoch_duration: i64) -> i64 {
+        (now.div_euclid(epoch_duration) + 1) * epoch_duration
+    }
+
+    pub fn has_pending(&self) -> bool {
+        self.pending_effective_at != 0
+    }
+
+    // Stage new rates, replacing any change not yet in effect
+    pub fn schedule(&mut self, rates_bps: [u16; FeeType::COUNT], effective_at: i64) {
+        self.pending_rates_bps = rates_bps;
+        self.pending_effective_at = effective_at;
+    }
+
+    // Promote pending rates once their epoch has started; returns true if it did
+    pub fn settle(&mut self, now: i64) -> bool {
+        if !self.has_pending() || now < self.pending_effective_at {
+            return false;
+        }
+        self.rates_bps = self.pending_rates_bps;
+        self.pending_rates_bps = [0; FeeType::COUNT];
+        self.pending_effective_at = 0;
+        true
+    }
+
+    pub fn fee_bps(&self, fee_type: FeeType) -> u16 {
+        self.rates_bps[fee_type.index()]
+    }
+
+    // Split `amount` into (net, fee) at the current rate. The fee rounds down,
+    // so rounding always favors the user.
+    pub fn apply_fee(&self, amount: u64, fee_type: FeeType) -> Result<(u64, u64)> {
+        Ok(crate::math::split_fee(amount, self.fee_bps(fee_type)).ok_or(crate::error::OntoraError::ArithmeticError)?)
+    }
+
+    // Space required for the account: discriminator plus the derived layout
+    pub const SPACE: usize = 8 + Self::INIT_SPACE;
+}
+
+// Protocol treasury: owns one token vault per accepted mint (seeds
+// [b"treasury-vault", mint]) and is the only destination for protocol fees
+#[account]
+#[derive(Default, InitSpace)]
+pub struct Treasury {
+    // Key that approves outflows (the governance executor)
+    pub governance: Pubkey,
+    // Lifetime deposits per `FeeSource`, in raw token units across all mints
+    pub deposits_by_source: [u64; FeeSource::COUNT],
+    // Lifetime outflows, in raw token units across all mints
+    pub total_outflows: u64,
+    // Bump seed for PDA derivation
+    pub bump: u8,
+}
+
+impl Treasury {
+    // Initialize the treasury with its governance key
+    pub fn init(&mut self, governance: Pubkey, bump: u8) {
+        self.governance = governance;
+        self.deposits_by_source = [0; FeeSource::COUNT];
+        self.total_outflows = 0;
+        self.bump = bump;
+    }
+
+    pub fn record_deposit(&mut self, source: FeeSource, amount: u64) -> Result<()> {
+        let total = &mut self.deposits_by_source[source.index()];
+        *total = total
+            .checked_add(amount)
+            .ok_or(crate::error::OntoraError::ArithmeticError)?;
+        Ok(())
+    }
+
+    pub fn record_outflow(&mut self, amount: u64) -> Result<()> {
+        self.total_outflows = self
+            .total_outflows
+            .checked_add(amount)
+            .ok_or(crate::error::OntoraError::ArithmeticError)?;
+        Ok(())
+    }
+
+    // Space required for the account: discriminator plus the derived layout
+    pub const SPACE: usize = 8 + Self::INIT_SPACE;
+}
+
+// External spl-governance (Realms) governance authorized to execute admin
+// instructions (seeds [b"governance-realm"]). Its native treasury PDA signs
+// the proposal transactions the governance executes.
+#[account]
+#[derive(Default, InitSpace)]
+pub struct GovernanceRealm {
+    pub realm: Pubkey,
+    pub governance: Pubkey,
+    // Program that owns `governance`; only it can sign for `native_treasury`
+    pub governance_program: Pubkey,
+    pub native_treasury: Pubkey,
+    // Bump seed for PDA derivation
+    pub bump: u8,
+}
+
+impl GovernanceRealm {
+    pub fn init(&mut self, realm: Pubkey, governance: Pubkey, governance_program: Pubkey, bump: u8) {
+        self.realm = realm;
+        self.governance = governance;
+        self.governance_program = governance_program;
+        self.native_treasury = native_treasury(&governance, &governance_program);
+        self.bump = bump;
+    }
+
+    // Space required for the account: discriminator plus the derived layout
+    pub const SPACE: usize = 8 + Self::INIT_SPACE;
+}
+
+// Seed prefix spl-governance derives a governance's native treasury from
+pub const NATIVE_TREASURY_SEED: &[u8] = b"native-treasury";
+
+// spl-governance `GovernanceAccountType`s of governance accounts, V1 and V2
+pub const GOVERNANCE_ACCOUNT_TYPES: [u8; 8] = [3, 4, 9, 10, 18, 19, 20, 21];
+
+// The native treasury of `governance` under `governance_program`
+pub fn native_treasury(governance: &Pubkey, governance_program: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[NATIVE_TREASURY_SEED, governance.as_ref()], governance_program).0
+}
+
+// Change to the platform config a governance proposal makes once approved and executed.
+// NoOp comes first so the zero bytes of a proposal from before actions decode as it.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ProposalAction {
+    // Approval changes nothing
+    #[default]
+    NoOp,
+    UpdateRewardRate { bps: u64 },
+    UpdateMinStake { amount: u64 },
+    UpdateEpochDuration { seconds: i64 },
+}
+
+impl ProposalAction {
+    // Check the new value against the bounds `PlatformConfig::validate_params` holds
+    // every config to, so a proposal that could never execute is refused at creation
+    pub fn validate(&self) -> Result<()> {
+        let valid = match *self {
+            ProposalAction::NoOp => true,
+            ProposalAction::UpdateRewardRate { bps } => bps <= MAX_REWARD_RATE_BPS,
+            ProposalAction::UpdateMinStake { amount } => amount > 0,
+            ProposalAction::UpdateEpochDuration { seconds } => {
+                (MIN_EPOCH_DURATION..=MAX_EPOCH_DURATION).contains(&seconds)
+            }
+        };
+        require!(valid, crate::error::OntoraError::InvalidProposalParameters);
+        Ok(())
+    }
+
+    // Apply the change through `update_params`, which validates it against the config as it
+    // is now (a minimum stake above the stake cap fails) and accrues rewards up to `now` first
+    pub fn apply(&self, config: &mut PlatformConfig, now: i64) -> Result<()> {
+        let mut params = config.params();
+        match *self {
+            ProposalAction::NoOp => return Ok(()),
+            ProposalAction::UpdateRewardRate { bps } => params.reward_rate_bps = bps,
+            ProposalAction::UpdateMinStake { amount } => params.min_stake_amount = amount,
+            ProposalAction::UpdateEpochDuration { seconds } => params.epoch_duration = seconds,
+        }
+        config.update_params(params, now)
+    }
+}
+
+// Governance proposal (seeds [b"proposal", id]). Zero-copy so voting touches
+// only the tally it changes instead of (de)serializing every string. Fields are
+// ordered so the `repr(C)` layout has no implicit padding; strings are fixed
+// byte arrays with explicit lengths.
+#[account(zero_copy)]
+pub struct Proposal {
+    // Account layout version (PROPOSAL_VERSION when current)
+    pub version: u8,
+    // One of the PROPOSAL_* status values
+    pub status: u8,
+    // Bump seed for PDA derivation
+    pub bump: u8,
+    // Number of ballot options in use
+    pub option_count: u8,
+    // Bytes of `title` in use
+    pub title_len: u8,
+    pub _reserved: [u8; 1],
+    // Bytes of `description` in use
+    pub description_len: u16,
+    // Sequential id from `PlatformConfig.proposal_count`
+    pub id: u64,
+    // Account that created the proposal
+    pub creator: Pubkey,
+    // Timestamp voting opened
+    pub start_time: i64,
+    // Timestamp voting closes (extended by time spent suspended)
+    pub end_time: i64,
+    // Timestamp the proposal was suspended (0 unless suspended)
+    pub suspended_at: i64,
+    // Vote tally per option (first `option_count` in use)
+    pub votes: [u64; MAX_PROPOSAL_OPTIONS],
+    // Bytes of each option label in use
+    pub option_lens: [u8; MAX_PROPOSAL_OPTIONS],
+    // Ballot option labels (UTF-8)
+    pub options: [[u8; MAX_PROPOSAL_OPTION_LENGTH]; MAX_PROPOSAL_OPTIONS],
+    // Short title (UTF-8)
+    pub title: [u8; MAX_PROPOSAL_TITLE_LENGTH],
+    // Full description (UTF-8)
+    pub description: [u8; MAX_PROPOSAL_DESCRIPTION_LENGTH],
+    // Keeps the struct size a multiple of its 8-byte alignment
+    pub _padding: [u8; 2],
+    // `PlatformConfig.total_staked` when the proposal was created, the base of its quorum
+    pub snapshot_total_staked: u64,
+    // `PlatformConfig.quorum_bps` when the proposal was created
+    pub quorum_bps: u16,
+    // `PlatformConfig.approval_threshold_bps` when the proposal was created
+    pub approval_threshold_bps: u16,
+    // Keeps the struct size a multiple of its 8-byte alignment
+    pub _padding_v2: [u8; 4],
+    // Slot the proposal was created in, when `snapshot_total_staked` was taken
+    pub snapshot_slot: u64,
+    // Borsh-encoded ProposalAction that executing the approved proposal applies
+    pub action: [u8; PROPOSAL_ACTION_LEN],
+    // Option that led when the proposal was finalized
+    pub winning_option: u8,
+    // Keeps the struct size a multiple of its 8-byte alignment
+    pub _padding_v4: [u8; 6],
+    // `PlatformConfig.vote_unit` when the proposal was created (0 if created before v5)
+    pub vote_unit: u64,
+}
+
+impl Proposal {
+    // Byte offset of `status`, for getProgramAccounts filters
+    pub const STATUS_OFFSET: usize = 8 + 1;
+    // Byte offset of `creator`, for getProgramAccounts filters
+    pub const CREATOR_OFFSET: usize = 8 + 1 + 1 + 1 + 1 + 1 + 1 + 2 + 8;
+
+    // Check creation inputs against the fixed arrays above. Limits are in
+    // bytes, since strings are stored as UTF-8 and a 4-byte emoji takes four.
+    pub fn validate_inputs(title: &str, description: &str, options: &[String], voting_duration: u64) -> Result<()> {
+        use crate::error::OntoraError;
+
+        require!(title.len() <= MAX_PROPOSAL_TITLE_LENGTH, OntoraError::NameTooLong);
+        require!(!description.is_empty(), OntoraError::DescriptionEmpty);
+        require!(
+            description.len() <= MAX_PROPOSAL_DESCRIPTION_LENGTH,
+            OntoraError::DescriptionTooLong
+        );
+        require!(options.len() >= 2, OntoraError::InvalidProposalParameters);
+        require!(options.len() <= MAX_PROPOSAL_OPTIONS, OntoraError::TooManyOptions);
+        require!(
+            options.iter().all(|option| option.len() <= MAX_PROPOSAL_OPTION_LENGTH),
+            OntoraError::NameTooLong
+        );
+        require!((1..=MAX_VOTING_DURATION).contains(&voting_duration), OntoraError::InvalidDuration);
+        Ok(())
+    }
+
+    // Copy validated strings into the fixed arrays and reset the tallies
+    pub fn set_contents(&mut self, title: &str, description: &str, options: &[String]) {
+        self.title = [0; MAX_PROPOSAL_TITLE_LENGTH];
+        self.title[..title.len()].copy_from_slice(title.as_bytes());
+        self.title_len = title.len() as u8;
+
+        self.description = [0; MAX_PROPOSAL_DESCRIPTION_LENGTH];
+        self.description[..description.len()].copy_from_slice(description.as_bytes());
+        self.description_len = description.len() as u16;
+
+        self.options = [[0; MAX_PROPOSAL_OPTION_LENGTH]; MAX_PROPOSAL_OPTIONS];
+        self.option_lens = [0; MAX_PROPOSAL_OPTIONS];
+        for (index, option) in options.iter().enumerate() {
+            self.options[index][..option.len()].copy_from_slice(option.as_bytes());
+            self.option_lens[index] = option.len() as u8;
+        }
+        self.option_count = options.len() as u8;
+        self.votes = [0; MAX_PROPOSAL_OPTIONS];
+    }
+
+    pub fn title(&self) -> &str {
+        core::str::from_utf8(&self.title[..self.title_len as usize]).unwrap_or_default()
+    }
+
+    pub fn description(&self) -> &str {
+        core::str::from_utf8(&self.description[..self.description_len as usize]).unwrap_or_default()
+    }
+
+    pub fn option(&self, index: usize) -> &str {
+        core::str::from_utf8(&self.options[index][..self.option_lens[index] as usize]).unwrap_or_default()
+    }
+
+    pub fn action(&self) -> Result<ProposalAction> {
+        ProposalAction::deserialize(&mut &self.action[..])
+            .map_err(|_| crate::error::OntoraError::SerializationError.into())
+    }
+
+    pub fn set_action(&mut self, action: &ProposalAction) -> Result<()> {
+        self.action = [0; PROPOSAL_ACTION_LEN];
+        action
+            .serialize(&mut &mut self.action[..])
+            .map_err(|_| crate::error::OntoraError::SerializationError.into())
+    }
+
+    // Tallies of the options in use
+    pub fn tallies(&self) -> &[u64] {
+        &self.votes[..self.option_count as usize]
+    }
+
+    // Add `weight` votes to `option`'s tally, summed in u128 so only a tally past
+    // u64::MAX fails
+    pub fn add_votes(&mut self, option: u8, weight: u64) -> Result<()> {
+        let tally = &mut self.votes[option as usize];
+        let total = *tally as u128 + weight as u128;
+        *tally = u64::try_from(total).map_err(|_| crate::error::OntoraError::ArithmeticError)?;
+        Ok(())
+    }
+
+    // Stake per vote on this proposal: the vote unit it was created under, or the
+    // platform's current one for a proposal created before it was recorded
+    pub fn effective_vote_unit(&self, config: &PlatformConfig) -> u64 {
+        if self.vote_unit == 0 {
+            config.vote_unit
+        } else {
+            self.vote_unit
+        }
+    }
+
+    // Status and FINALIZED_* reason the tallies finalize to, with the leading option.
+    // Quorum weighs the votes at `vote_unit` of stake each against the snapshotted
+    // stake; a result exactly at quorum or at the threshold passes.
+    pub fn outcome(&self, vote_unit: u64) -> (u8, u8, u8) {
+        let tallies = self.tallies();
+        let (mut winning_option, mut max_votes, mut tied) = (0, 0, false);
+        for (index, &votes) in tallies.iter().enumerate() {
+            if votes > max_votes {
+                (winning_option, max_votes, tied) = (index as u8, votes, false);
+            } else if votes == max_votes {
+                tied = true;
+            }
+        }
+
+        let bps = MAX_GOVERNANCE_BPS as u128;
+        let total_votes: u128 = tallies.iter().map(|&votes| votes as u128).sum();
+        let voted_stake = total_votes.saturating_mul(vote_unit as u128).saturating_mul(bps);
+        let reason = if total_votes == 0 || voted_stake < self.snapshot_total_staked as u128 * self.quorum_bps as u128 {
+            FINALIZED_NO_QUORUM
+        } else if tied {
+            FINALIZED_TIE
+        } else if max_votes as u128 * bps < total_votes * self.approval_threshold_bps as u128 {
+            FINALIZED_BELOW_THRESHOLD
+        } else {
+            FINALIZED_APPROVED
+        };
+        let status = if reason == FINALIZED_APPROVED { PROPOSAL_APPROVED } else { PROPOSAL_REJECTED };
+        (status, reason, winning_option)
+    }
+
+    // Freeze an active proposal while governance is disabled
+    pub fn suspend(&mut self, now: i64) -> Result<()> {
+        require!(self.status == PROPOSAL_ACTIVE, crate::error::OntoraError::ProposalNotActive);
+        self.status = PROPOSAL_SUSPENDED;
+        self.suspended_at = now;
+        Ok(())
+    }
+
+    // Reactivate a suspended proposal, pushing its deadline back by the time it was suspended
+    pub fn resume(&mut self, now: i64) -> Result<()> {
+        require!(self.status == PROPOSAL_SUSPENDED, crate::error::OntoraError::ProposalNotActive);
+        let suspended_for = now.saturating_sub(self.suspended_at);
+        self.end_time = self
+            .end_time
+            .checked_add(suspended_for)
+            .ok_or(crate::error::OntoraError::ArithmeticError)?;
+        self.status = PROPOSAL_ACTIVE;
+        self.suspended_at = 0;
+        Ok(())
     }
 
     // Calculate space required for the account
-    pub const SPACE: usize = 8 + // discriminator
-        32 + // admin (Pubkey)
-        8 + // reward_rate_bps (u64)
-        8 + // min_stake_amount (u64)
-        8 + // epoch_duration (i64)
-        8 + // last_reward_timestamp (i64)
-        8 + // total_staked (u64)
-        1; // bump (u8)
+    pub const LEN: usize = 8 + core::mem::size_of::<Proposal>();
 }
 
 // AI Agent data structure to store agent-specific information
 #[account]
-#[derive(Default)]
+#[derive(Default, InitSpace)]
 pub struct AiAgent {
+    // Account layout version (AI_AGENT_VERSION when current)
+    pub version: u8,
     // Unique identifier for the agent
     pub agent_id: u64,
     // Owner of the agent (user who registered it)
     pub owner: Pubkey,
     // Name of the AI agent (e.g., "Ontora-Alpha")
+    #[max_len(MAX_NAME_LENGTH)]
     pub name: String,
     // Description or metadata about the agent's purpose
+    #[max_len(MAX_DESCRIPTION_LENGTH)]
     pub description: String,
     // Total amount staked on this agent
     pub staked_amount: u64,
@@ -68,11 +1062,37 @@ pub struct AiAgent {
     pub created_at: i64,
     // Bump seed for PDA derivation
     pub bump: u8,
+    // Timestamp of the last performance score update (0 until first scored)
+    pub score_updated_at: i64,
 }
 
 impl AiAgent {
+    // Byte offset of `owner`, for getProgramAccounts filters
+    pub const OWNER_OFFSET: usize = 8 + 1 + 8;
+
+    // Check name and description byte lengths against the space reserved in `SPACE`
+    pub fn validate_metadata(name: &str, description: &str) -> Result<()> {
+        require!(name.len() <= MAX_NAME_LENGTH, crate::error::OntoraError::NameTooLong);
+        require!(
+            description.len() <= MAX_DESCRIPTION_LENGTH,
+            crate::error::OntoraError::DescriptionTooLong
+        );
+        Ok(())
+    }
+
+    // Compact JSON description of the agent carried by AgentRegistered and AgentUpdated
+    pub fn compact_metadata(&self) -> String {
+        serde_json::json!({
+            "name": self.name,
+            "description": self.description,
+            "performance_score": self.performance_score,
+        })
+        .to_string()
+    }
+
     // Initialize a new AI agent with provided data
     pub fn init(&mut self, agent_id: u64, owner: Pubkey, name: String, description: String, created_at: i64, bump: u8) {
+        self.version = AI_AGENT_VERSION;
         self.agent_id = agent_id;
         self.owner = owner;
         self.name = name;
@@ -81,86 +1101,202 @@ impl AiAgent {
         self.performance_score = 0;
         self.created_at = created_at;
         self.bump = bump;
+        self.score_updated_at = 0;
     }
 
-    // Calculate space required for the account
-    pub const SPACE: usize = 8 + // discriminator
-        8 + // agent_id (u64)
-        32 + // owner (Pubkey)
-        4 + MAX_NAME_LENGTH + // name (String with max length)
-        4 + MAX_DESCRIPTION_LENGTH + // description (String with max length)
-        8 + // staked_amount (u64)
-        8 + // performance_score (u64)
-        8 + // created_at (i64)
-        1; // bump (u8)
-}
-
-// User staking data to track individual user balances and rewards
+    // Whether the performance score is more than an epoch old; an agent that has
+    // never been scored is measured from its registration
+    pub fn is_stale(&self, now: i64, config: &PlatformConfig) -> bool {
+        let last_update = self.score_updated_at.max(self.created_at);
+        now.saturating_sub(last_update) > config.epoch_duration
+    }
+
+    // Space required for the account: discriminator plus the derived layout
+    pub const SPACE: usize = 8 + Self::INIT_SPACE;
+}
+
+// User staking data to track individual user balances and rewards. The agents a
+// user has staked on are tracked by StakeIndexEntry PDAs, not in this account.
 #[account]
-#[derive(Default)]
+#[derive(Default, InitSpace)]
 pub struct UserStake {
+    // Account layout version (USER_STAKE_VERSION when current)
+    pub version: u8,
     // User public key (owner of this stake)
     pub user: Pubkey,
     // Total amount staked by the user
     pub staked_amount: u64,
     // Accumulated rewards (unclaimed)
     pub accumulated_rewards: u64,
-    // List of agent IDs the user has staked on
-    pub staked_agents: Vec<u64>,
     // Timestamp of the last stake update
     pub last_stake_update: i64,
     // Timestamp of the last reward claim
     pub last_reward_claim: i64,
     // Bump seed for PDA derivation
     pub bump: u8,
+    // Stake times the platform's `acc_reward_per_share` when the stake last
+    // changed or was settled; what it has earned since is the difference
+    pub reward_debt: u128,
+    // Timestamp the user last changed the stake themselves
+    pub stake_changed_at: i64,
+    // Stake others have placed for the user since `stake_changed_at`
+    pub funded_stake: u64,
+    // Timestamp of the latest stake others placed for the user
+    pub funded_at: i64,
 }
 
 impl UserStake {
     // Initialize a new user stake account
     pub fn init(&mut self, user: Pubkey, bump: u8) {
+        self.version = USER_STAKE_VERSION;
         self.user = user;
         self.staked_amount = 0;
         self.accumulated_rewards = 0;
-        self.staked_agents = Vec::new();
         self.last_stake_update = 0;
         self.last_reward_claim = 0;
         self.bump = bump;
+        self.reward_debt = 0;
+        self.stake_changed_at = 0;
+        self.funded_stake = 0;
+        self.funded_at = 0;
     }
 
-    // Add an agent ID to the user's staked agents list
-    pub fn add_staked_agent(&mut self, agent_id: u64) -> Result<()> {
-        if self.staked_agents.len() >= MAX_AGENTS_PER_USER {
-            return err!(ErrorCode::TooManyAgents);
-        }
-        if !self.staked_agents.contains(&agent_id) {
-            self.staked_agents.push(agent_id);
-        }
+    // Note a change the user made to their own stake, which absorbs what others funded
+    pub fn record_own_change(&mut self, now: i64) {
+        self.last_stake_update = now;
+        self.stake_changed_at = now;
+        self.funded_stake = 0;
+        self.funded_at = 0;
+    }
+
+    // Note `amount` of stake another account placed for the user
+    pub fn record_funded(&mut self, amount: u64, now: i64) -> Result<()> {
+        self.last_stake_update = now;
+        self.funded_stake = self.funded_stake.checked_add(amount).ok_or(crate::error::OntoraError::ArithmeticError)?;
+        self.funded_at = now;
         Ok(())
     }
 
-    // Remove an agent ID from the user's staked agents list
-    pub fn remove_staked_agent(&mut self, agent_id: u64) {
-        self.staked_agents.retain(|&id| id != agent_id);
+    // Unclaimed rewards at `now`: the accumulated balance plus what the stake
+    // has earned since it was last settled
+    pub fn pending_rewards(&self, config: &PlatformConfig, now: i64) -> Result<u64> {
+        let acc_reward_per_share = config.reward_per_share_at(now)?;
+        let reward = crate::math::accrued_rewards(self.staked_amount, acc_reward_per_share, self.reward_debt)
+            .ok_or(crate::error::OntoraError::ArithmeticError)?;
+        Ok(self
+            .accumulated_rewards
+            .checked_add(reward)
+            .ok_or(crate::error::OntoraError::ArithmeticError)?)
     }
 
-    // Calculate space required for the account
-    pub const SPACE: usize = 8 + // discriminator
-        32 + // user (Pubkey)
-        8 + // staked_amount (u64)
-        8 + // accumulated_rewards (u64)
-        4 + (8 * MAX_AGENTS_PER_USER) + // staked_agents (Vec<u64> with max length)
-        8 + // last_stake_update (i64)
-        8 + // last_reward_claim (i64)
-        1; // bump (u8)
+    // Credit what the stake has earned up to `acc_reward_per_share` (from
+    // `PlatformConfig::accrue`), then set it to `staked_amount`, which earns
+    // from here on
+    pub fn settle(&mut self, staked_amount: u64, acc_reward_per_share: u128) -> Result<()> {
+        let reward = crate::math::accrued_rewards(self.staked_amount, acc_reward_per_share, self.reward_debt)
+            .ok_or(crate::error::OntoraError::ArithmeticError)?;
+        self.accumulated_rewards =
+            self.accumulated_rewards.checked_add(reward).ok_or(crate::error::OntoraError::ArithmeticError)?;
+        self.staked_amount = staked_amount;
+        self.reward_debt = (staked_amount as u128)
+            .checked_mul(acc_reward_per_share)
+            .ok_or(crate::error::OntoraError::ArithmeticError)?;
+        Ok(())
+    }
+
+    // Governance weight: one vote per full `vote_unit` of stake, none below it
+    pub fn voting_power(&self, config: &PlatformConfig) -> u64 {
+        self.staked_amount.checked_div(config.vote_unit).unwrap_or(0)
+    }
+
+    // Governance weight on `proposal`: only stake predating the proposal votes on it.
+    // Otherwise tokens could be staked, voted, unstaked and staked again from another wallet,
+    // or for it, to vote twice. A stake the user changed after the proposal opened, a partial
+    // unstake included, has no vote on it; stake others funded since it opened is left out,
+    // so funding a user's stake cannot take their vote away.
+    pub fn proposal_voting_power(&self, config: &PlatformConfig, proposal: &Proposal) -> u64 {
+        if self.stake_changed_at >= proposal.start_time {
+            return 0;
+        }
+        let funded_since = if self.funded_at >= proposal.start_time { self.funded_stake } else { 0 };
+        self.staked_amount.saturating_sub(funded_since).checked_div(proposal.effective_vote_unit(config)).unwrap_or(0)
+    }
+
+    // Space required for the account: discriminator plus the derived layout
+    pub const SPACE: usize = 8 + Self::INIT_SPACE;
+}
+
+// Marks that a user has a stake on an agent; the account exists exactly while
+// the stake is open. PDA seeds: [b"stake-index", user, agent_id].
+#[account]
+#[derive(Default, InitSpace)]
+pub struct StakeIndexEntry {
+    // User public key; first after the discriminator so clients can memcmp on it
+    pub user: Pubkey,
+    // Agent the user has staked on
+    pub agent_id: u64,
+    // Amount the user has staked on this agent; the entry is closed at zero
+    pub staked_amount: u64,
+    // Timestamp of the first stake on this agent
+    pub staked_at: i64,
+    // Bump seed for PDA derivation
+    pub bump: u8,
+}
+
+impl StakeIndexEntry {
+    // Byte offset of `user`, for getProgramAccounts filters
+    pub const USER_OFFSET: usize = 8;
+
+    // Initialize an entry on the user's first stake on an agent
+    pub fn init(&mut self, user: Pubkey, agent_id: u64, staked_at: i64, bump: u8) {
+        self.user = user;
+        self.agent_id = agent_id;
+        self.staked_amount = 0;
+        self.staked_at = staked_at;
+        self.bump = bump;
+    }
+
+    // Space required for the account: discriminator plus the derived layout
+    pub const SPACE: usize = 8 + Self::INIT_SPACE;
+}
+
+// Marks that a voter has voted on a proposal; `cast_vote` creates it, so a wallet
+// votes once. PDA seeds: [b"proposal-vote", proposal_id, voter].
+#[account]
+#[derive(Default, InitSpace)]
+pub struct VoteRecord {
+    // Proposal voted on
+    pub proposal_id: u64,
+    // Wallet that voted (default = no vote yet)
+    pub voter: Pubkey,
+    // Option voted for
+    pub vote_option: u8,
+    // Votes the stake carried
+    pub vote_weight: u64,
+    // Timestamp of the vote
+    pub voted_at: i64,
+    // Bump seed for PDA derivation
+    pub bump: u8,
+}
+
+impl VoteRecord {
+    // Whether the voter has already voted through this record
+    pub fn has_voted(&self) -> bool {
+        self.voter != Pubkey::default()
+    }
+
+    // Space required for the account: discriminator plus the derived layout
+    pub const SPACE: usize = 8 + Self::INIT_SPACE;
 }
 
 // Metadata account for additional platform or agent-specific data
 #[account]
-#[derive(Default)]
+#[derive(Default, InitSpace)]
 pub struct Metadata {
     // Associated entity (e.g., agent ID or platform-wide metadata)
     pub entity_id: u64,
-    // Key-value pair or JSON-like data (stored as string)
+    // Key-value pair or JSON-like data (stored as string); INIT_SPACE is the ceiling
+    #[max_len(MAX_METADATA_LENGTH)]
     pub data: String,
     // Timestamp of last update
     pub updated_at: i64,
@@ -169,6 +1305,17 @@ pub struct Metadata {
 }
 
 impl Metadata {
+    // Reject payloads over the hard ceiling (in bytes)
+    pub fn validate_data(data: &str) -> Result<()> {
+        require!(data.len() <= MAX_METADATA_LENGTH, crate::error::OntoraError::MetadataTooLarge);
+        Ok(())
+    }
+
+    // Space for an account holding `data_len` bytes of data
+    pub const fn space_for(data_len: usize) -> usize {
+        8 + Self::INIT_SPACE - MAX_METADATA_LENGTH + data_len
+    }
+
     // Initialize metadata with provided data
     pub fn init(&mut self, entity_id: u64, data: String, updated_at: i64, bump: u8) {
         self.entity_id = entity_id;
@@ -177,21 +1324,6 @@ impl Metadata {
         self.bump = bump;
     }
 
-    // Calculate space required for the account
-    pub const SPACE: usize = 8 + // discriminator
-        8 + // entity_id (u64)
-        4 + MAX_DESCRIPTION_LENGTH + // data (String with max length)
-        8 + // updated_at (i64)
-        1; // bump (u8)
-}
-
-// Custom error codes for state management
-#[error_code]
-pub enum ErrorCode {
-    #[msg("Too many agents staked by user.")]
-    TooManyAgents,
-    #[msg("Invalid stake amount.")]
-    InvalidStakeAmount,
-    #[msg("Metadata data too large.")]
-    MetadataTooLarge,
+    // Calculate space required for a fixed-size record (e.g. vote records)
+    pub const SPACE: usize = Self::space_for(MAX_DESCRIPTION_LENGTH);
 }
@@ -0,0 +1,159 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+use crate::error::OntoraError;
+use crate::events::{TreasuryDeposit, TreasuryWithdrawal};
+use crate::pda::{PLATFORM_CONFIG_SEED, TREASURY_SEED, TREASURY_VAULT_SEED};
+use crate::realms::require_authority;
+use crate::state::*;
+
+/// Context for creating the protocol treasury.
+#[derive(Accounts)]
+pub struct InitializeTreasury<'info> {
+    /// The platform configuration; only the treasury role may create the treasury.
+    #[account(
+        seeds = [PLATFORM_CONFIG_SEED],
+        bump = platform_config.bump,
+        constraint = platform_config.has_role(Role::Treasury, &authority.key()) @ OntoraError::MissingRole
+    )]
+    pub platform_config: Account<'info, PlatformConfig>,
+    /// The treasury account to be initialized.
+    #[account(
+        init,
+        payer = authority,
+        space = Treasury::SPACE,
+        seeds = [TREASURY_SEED],
+        bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+    /// The treasury role holder, paying for the account.
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    /// The system program for account initialization.
+    pub system_program: Program<'info, System>,
+}
+
+/// Instruction to create the treasury with its governance key. Fee rates live
+/// on the `FeeSchedule`.
+pub fn initialize_treasury(ctx: Context<InitializeTreasury>, governance: Pubkey) -> Result<()> {
+    ctx.accounts.treasury.init(governance, ctx.bumps.treasury);
+    Ok(())
+}
+
+/// Move a fee into the treasury vault for `mint` and record it under `source`.
+/// Every fee path goes through here so deposits are always tagged.
+#[allow(clippy::too_many_arguments)]
+pub fn route_fee<'info>(
+    token_program: &Program<'info, Token>,
+    from: AccountInfo<'info>,
+    authority: AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+    treasury: &mut Account<'info, Treasury>,
+    treasury_vault: &Account<'info, TokenAccount>,
+    source: FeeSource,
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+
+    let cpi_accounts = Transfer {
+        from,
+        to: treasury_vault.to_account_info(),
+        authority,
+    };
+    let cpi_ctx = CpiContext::new_with_signer(token_program.to_account_info(), cpi_accounts, signer_seeds);
+    token::transfer(cpi_ctx, amount)?;
+
+    treasury.record_deposit(source, amount)?;
+
+    emit!(TreasuryDeposit {
+        schema_version: TreasuryDeposit::SCHEMA_VERSION,
+        source,
+        mint: treasury_vault.mint,
+        amount,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+/// Context for reading a treasury vault balance.
+#[derive(Accounts)]
+pub struct TreasuryBalance<'info> {
+    #[account(seeds = [TREASURY_SEED], bump = treasury.bump)]
+    pub treasury: Account<'info, Treasury>,
+    #[account(
+        seeds = [TREASURY_VAULT_SEED, treasury_vault.mint.as_ref()],
+        bump,
+        token::authority = treasury
+    )]
+    pub treasury_vault: Account<'info, TokenAccount>,
+}
+
+/// View returning the treasury's balance of the vault's mint via return data.
+pub fn treasury_balance(ctx: Context<TreasuryBalance>) -> Result<u64> {
+    Ok(ctx.accounts.treasury_vault.amount)
+}
+
+/// Context for a governance-approved treasury outflow.
+#[derive(Accounts)]
+pub struct TreasuryTransfer<'info> {
+    #[account(
+        mut,
+        seeds = [TREASURY_SEED],
+        bump = treasury.bump
+    )]
+    pub treasury: Account<'info, Treasury>,
+    pub mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        seeds = [TREASURY_VAULT_SEED, mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = treasury
+    )]
+    pub treasury_vault: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = mint)]
+    pub recipient: Account<'info, TokenAccount>,
+    /// The treasury's governance key, or the native treasury of the governance
+    /// set by `set_governance_realm`.
+    pub governance: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    // remaining_accounts: the governance realm, when the realm's governance executes.
+}
+
+/// Instruction for the only treasury outflow: a transfer approved by governance.
+pub fn treasury_transfer(ctx: Context<TreasuryTransfer>, amount: u64) -> Result<()> {
+    require_authority(
+        &ctx.accounts.treasury.governance,
+        &ctx.accounts.governance,
+        ctx.remaining_accounts,
+        OntoraError::UnauthorizedUser,
+    )?;
+    require!(amount > 0, OntoraError::InvalidStakeAmount);
+    require!(
+        ctx.accounts.treasury_vault.amount >= amount,
+        OntoraError::InsufficientBalance
+    );
+
+    let seeds = &[TREASURY_SEED, &[ctx.accounts.treasury.bump]];
+    let signer = &[&seeds[..]];
+    let cpi_accounts = Transfer {
+        from: ctx.accounts.treasury_vault.to_account_info(),
+        to: ctx.accounts.recipient.to_account_info(),
+        authority: ctx.accounts.treasury.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi_accounts, signer);
+    token::transfer(cpi_ctx, amount)?;
+
+    ctx.accounts.treasury.record_outflow(amount)?;
+
+    emit!(TreasuryWithdrawal {
+        schema_version: TreasuryWithdrawal::SCHEMA_VERSION,
+        governance: ctx.accounts.governance.key(),
+        mint: ctx.accounts.mint.key(),
+        recipient: ctx.accounts.recipient.key(),
+        amount,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
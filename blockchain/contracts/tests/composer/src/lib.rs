@@ -0,0 +1,107 @@
+//! Example of a program composing with Ontora AI over CPI. Each owner has a
+//! vault: a PDA of this program that holds tokens, stakes them on agents and
+//! votes on proposals with the stake's weight. The vault signs the Ontora
+//! instructions through `invoke_signed`, and pays for the stake accounts they
+//! open, so it needs lamports as well as tokens.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Token, TokenAccount};
+use ontora_ai::cpi::accounts::{CastVote, StakeOnAgent};
+use ontora_ai::program::OntoraAi;
+
+declare_id!("Composer111111111111111111111111111111111111");
+
+pub const VAULT_SEED: &[u8] = b"vault";
+
+/// The vault of `owner`.
+pub fn vault(owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[VAULT_SEED, owner.as_ref()], &crate::ID)
+}
+
+#[program]
+pub mod ontora_composer {
+    use super::*;
+
+    /// Stake `amount` of the vault's tokens on an agent
+    pub fn stake(ctx: Context<Stake>, agent_id: u64, amount: u64) -> Result<()> {
+        let owner = ctx.accounts.owner.key();
+        let seeds = &[VAULT_SEED, owner.as_ref(), &[ctx.bumps.vault]];
+        let signer = &[&seeds[..]];
+        let cpi_accounts = StakeOnAgent {
+            platform_config: ctx.accounts.platform_config.to_account_info(),
+            ai_agent: ctx.accounts.ai_agent.to_account_info(),
+            user_stake: ctx.accounts.user_stake.to_account_info(),
+            stake_index: ctx.accounts.stake_index.to_account_info(),
+            user: ctx.accounts.vault.to_account_info(),
+            user_token_account: ctx.accounts.vault_tokens.to_account_info(),
+            platform_vault: ctx.accounts.platform_vault.to_account_info(),
+            token_program: ctx.accounts.token_program.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.ontora_program.to_account_info(), cpi_accounts, signer);
+        ontora_ai::cpi::stake_on_agent(cpi_ctx, agent_id, amount, ctx.accounts.vault.key())
+    }
+
+    /// Vote for `vote_option` of a proposal, weighted by the vault's stake
+    pub fn vote(ctx: Context<Vote>, proposal_id: u64, vote_option: u8) -> Result<()> {
+        let owner = ctx.accounts.owner.key();
+        let seeds = &[VAULT_SEED, owner.as_ref(), &[ctx.bumps.vault]];
+        let signer = &[&seeds[..]];
+        let cpi_accounts = CastVote {
+            voter: ctx.accounts.vault.to_account_info(),
+            platform_config: ctx.accounts.platform_config.to_account_info(),
+            proposal: ctx.accounts.proposal.to_account_info(),
+            user_stake: ctx.accounts.user_stake.to_account_info(),
+            vote_record: ctx.accounts.vote_record.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.ontora_program.to_account_info(), cpi_accounts, signer);
+        ontora_ai::cpi::cast_vote(cpi_ctx, proposal_id, vote_option)
+    }
+}
+
+#[derive(Accounts)]
+pub struct Stake<'info> {
+    pub owner: Signer<'info>,
+    #[account(mut, seeds = [VAULT_SEED, owner.key().as_ref()], bump)]
+    pub vault: SystemAccount<'info>,
+    #[account(mut, token::authority = vault)]
+    pub vault_tokens: Account<'info, TokenAccount>,
+    /// CHECK: checked by ontora_ai
+    #[account(mut)]
+    pub platform_config: UncheckedAccount<'info>,
+    /// CHECK: checked by ontora_ai
+    #[account(mut)]
+    pub ai_agent: UncheckedAccount<'info>,
+    /// CHECK: checked by ontora_ai, which opens it on the vault's first stake
+    #[account(mut)]
+    pub user_stake: UncheckedAccount<'info>,
+    /// CHECK: checked by ontora_ai, which opens it on the vault's first stake on the agent
+    #[account(mut)]
+    pub stake_index: UncheckedAccount<'info>,
+    /// CHECK: checked by ontora_ai
+    #[account(mut)]
+    pub platform_vault: UncheckedAccount<'info>,
+    pub ontora_program: Program<'info, OntoraAi>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Vote<'info> {
+    pub owner: Signer<'info>,
+    #[account(mut, seeds = [VAULT_SEED, owner.key().as_ref()], bump)]
+    pub vault: SystemAccount<'info>,
+    /// CHECK: checked by ontora_ai
+    pub platform_config: UncheckedAccount<'info>,
+    /// CHECK: checked by ontora_ai
+    #[account(mut)]
+    pub proposal: UncheckedAccount<'info>,
+    /// CHECK: checked by ontora_ai against the vault
+    pub user_stake: UncheckedAccount<'info>,
+    /// CHECK: checked by ontora_ai, which opens it on the vault's vote
+    #[account(mut)]
+    pub vote_record: UncheckedAccount<'info>,
+    pub ontora_program: Program<'info, OntoraAi>,
+    pub system_program: Program<'info, System>,
+}
@@ -0,0 +1,193 @@
+// test_composer.rs
+// Runs the composer program next to Ontora AI under program-test: an owner's vault
+// PDA stakes on an agent and votes on a proposal through the composer's own
+// instructions, each a CPI the vault signs for with its seeds.
+
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::{AccountDeserialize, InstructionData, ToAccountMetas};
+use ontora_ai::state::{Proposal, ProposalAction, StakeIndexEntry, UserStake};
+use ontora_client::*;
+use solana_program_test::*;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::system_instruction;
+use solana_sdk::transaction::Transaction;
+
+const LAMPORTS: u64 = 10_000_000_000;
+const MIN_STAKE: u64 = 1_000_000;
+const STAKE: u64 = 3_000_000_000;
+const AGENT_ID: u64 = 4;
+
+struct Harness {
+    ctx: ProgramTestContext,
+}
+
+impl Harness {
+    async fn start() -> Self {
+        let mut test = ProgramTest::new("ontora_composer", ontora_composer::ID, processor!(ontora_composer::entry));
+        test.add_program("ontora_ai", ontora_ai::ID, processor!(ontora_ai::entry));
+        Harness { ctx: test.start_with_context().await }
+    }
+
+    async fn send(&mut self, instructions: &[Instruction], signers: &[&Keypair]) -> Result<(), BanksClientError> {
+        let mut all_signers = vec![&self.ctx.payer];
+        all_signers.extend_from_slice(signers);
+        let tx = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.ctx.payer.pubkey()),
+            &all_signers,
+            self.ctx.last_blockhash,
+        );
+        self.ctx.banks_client.process_transaction(tx).await
+    }
+
+    async fn fund(&mut self, address: &Pubkey) {
+        let transfer = system_instruction::transfer(&self.ctx.payer.pubkey(), address, LAMPORTS);
+        self.send(&[transfer], &[]).await.unwrap();
+    }
+
+    async fn new_user(&mut self) -> Keypair {
+        let user = Keypair::new();
+        self.fund(&user.pubkey()).await;
+        user
+    }
+
+    async fn data(&mut self, address: &Pubkey) -> Vec<u8> {
+        self.ctx.banks_client.get_account(*address).await.unwrap().expect("account exists").data
+    }
+
+    // A mint with `STAKE` tokens for `holder`, which may be a PDA, and the platform's vaults
+    // for it, created by `authority` as the platform admin
+    async fn mint(&mut self, authority: &Keypair, holder: &Pubkey) -> (Pubkey, Pubkey) {
+        let mint = Keypair::new();
+        let rent = self.ctx.banks_client.get_rent().await.unwrap();
+        let holder_tokens = spl_associated_token_account::get_associated_token_address(holder, &mint.pubkey());
+        let payer = self.ctx.payer.pubkey();
+        let instructions = [
+            system_instruction::create_account(
+                &payer,
+                &mint.pubkey(),
+                rent.minimum_balance(spl_token::state::Mint::LEN),
+                spl_token::state::Mint::LEN as u64,
+                &spl_token::ID,
+            ),
+            spl_token::instruction::initialize_mint(&spl_token::ID, &mint.pubkey(), &authority.pubkey(), None, 9)
+                .unwrap(),
+            spl_associated_token_account::instruction::create_associated_token_account(
+                &payer,
+                holder,
+                &mint.pubkey(),
+                &spl_token::ID,
+            ),
+            build_initialize_vaults(InitializeVaultsParams { admin: authority.pubkey(), stake_mint: mint.pubkey() }),
+            spl_token::instruction::mint_to(
+                &spl_token::ID,
+                &mint.pubkey(),
+                &holder_tokens,
+                &authority.pubkey(),
+                &[],
+                STAKE,
+            )
+            .unwrap(),
+        ];
+        self.send(&instructions, &[&mint, authority]).await.unwrap();
+        (holder_tokens, pda::staking_vault().0)
+    }
+}
+
+fn stake_instruction(
+    owner: &Pubkey,
+    vault: &Pubkey,
+    vault_tokens: Pubkey,
+    platform_vault: Pubkey,
+    agent_owner: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: ontora_composer::ID,
+        accounts: ontora_composer::accounts::Stake {
+            owner: *owner,
+            vault: *vault,
+            vault_tokens,
+            platform_config: pda::platform_config().0,
+            ai_agent: pda::ai_agent(agent_owner, AGENT_ID).0,
+            user_stake: pda::user_stake(vault).0,
+            stake_index: pda::stake_index(vault, AGENT_ID).0,
+            platform_vault,
+            ontora_program: ontora_ai::ID,
+            token_program: spl_token::ID,
+            system_program: solana_sdk::system_program::ID,
+        }
+        .to_account_metas(None),
+        data: ontora_composer::instruction::Stake { agent_id: AGENT_ID, amount: STAKE }.data(),
+    }
+}
+
+// Test that the vault stakes and then votes with its stake's weight, and that
+// nobody but its owner can make it sign
+#[tokio::test]
+async fn test_vault_stakes_and_votes_over_cpi() {
+    let mut harness = Harness::start().await;
+    let (admin, agent_owner, owner) = (harness.new_user().await, harness.new_user().await, harness.new_user().await);
+    let initialize = build_initialize_platform(InitializePlatformParams {
+        admin: admin.pubkey(),
+        reward_rate_bps: 100,
+        min_stake_amount: MIN_STAKE,
+        epoch_duration: 86_400,
+        cooldown_seconds: 86_400,
+        epoch_emission: 100_000_000,
+    });
+    let register = build_register_agent(RegisterAgentParams {
+        owner: agent_owner.pubkey(),
+        agent_id: AGENT_ID,
+        name: "Atlas".to_string(),
+        description: "Market-making agent".to_string(),
+    });
+    let create = build_create_governance_proposal(CreateGovernanceProposalParams {
+        creator: admin.pubkey(),
+        proposal_id: 0,
+        title: "Raise reward rate".to_string(),
+        description: "Raise the reward rate to 6%".to_string(),
+        voting_duration: 3_600,
+        options: vec!["Yes".to_string(), "No".to_string()],
+        action: ProposalAction::UpdateRewardRate { bps: 600 },
+    });
+    harness.send(&[initialize, create], &[&admin]).await.unwrap();
+    harness.send(&[register], &[&agent_owner]).await.unwrap();
+
+    let (vault, _) = ontora_composer::vault(&owner.pubkey());
+    harness.fund(&vault).await;
+    let (vault_tokens, platform_vault) = harness.mint(&admin, &vault).await;
+
+    // Someone else's signature does not open the owner's vault
+    let intruder = harness.new_user().await;
+    let stolen = stake_instruction(&intruder.pubkey(), &vault, vault_tokens, platform_vault, &agent_owner.pubkey());
+    assert!(harness.send(&[stolen], &[&intruder]).await.is_err());
+
+    let stake = stake_instruction(&owner.pubkey(), &vault, vault_tokens, platform_vault, &agent_owner.pubkey());
+    harness.send(&[stake], &[&owner]).await.unwrap();
+    let user_stake = UserStake::try_deserialize(&mut &harness.data(&pda::user_stake(&vault).0).await[..]).unwrap();
+    assert_eq!((user_stake.user, user_stake.staked_amount), (vault, STAKE));
+    let entry = StakeIndexEntry::try_deserialize(&mut &harness.data(&pda::stake_index(&vault, AGENT_ID).0).await[..]);
+    assert_eq!(entry.unwrap().staked_amount, STAKE);
+
+    let vote = Instruction {
+        program_id: ontora_composer::ID,
+        accounts: ontora_composer::accounts::Vote {
+            owner: owner.pubkey(),
+            vault,
+            platform_config: pda::platform_config().0,
+            proposal: pda::proposal(0).0,
+            user_stake: pda::user_stake(&vault).0,
+            vote_record: pda::proposal_vote(0, &vault).0,
+            ontora_program: ontora_ai::ID,
+            system_program: solana_sdk::system_program::ID,
+        }
+        .to_account_metas(None),
+        data: ontora_composer::instruction::Vote { proposal_id: 0, vote_option: 0 }.data(),
+    };
+    harness.send(&[vote], &[&owner]).await.unwrap();
+    let proposal_data = harness.data(&pda::proposal(0).0).await;
+    let proposal = decode_zero_copy::<Proposal>(&pda::proposal(0).0, &proposal_data).unwrap();
+    assert_eq!(proposal.votes[..2], [STAKE / MIN_STAKE, 0]);
+}
@@ -10,12 +10,12 @@ mod tests {
     use solana_sdk::transport::TransportError;
 
     // Import the program to test (adjust based on your program name).
+    use ontora_ai::pda;
     use ontora_ai::program::NivaroAi; 
     use ontora_ai::state::{PlatformConfig, Proposal, UserStake};
     use ontora_ai::instructions::{initialize_platform, stake_tokens, create_proposal, cast_vote, finalize_proposal};
 
     // Define constants for testing.
-    const PLATFORM_CONFIG_SEED: &[u8] = b"platform_config";
     const INITIAL_REWARD_RATE: u64 = 100; // Simplified reward rate for testing.
     const STAKE_AMOUNT: u64 = 1_000_000; // Simplified stake amount for testing (1M lamports).
     const VOTING_DURATION: u64 = 86_400; // 1 day in seconds for voting duration.
@@ -43,10 +43,7 @@ mod tests {
         let (mut context, payer, authority) = setup_test_env().await;
 
         // Derive the platform config PDA.
-        let (platform_config_pda, platform_config_bump) = Pubkey::find_program_address(
-            &[PLATFORM_CONFIG_SEED],
-            &ontora_ai::ID,
-        );
+        let (platform_config_pda, platform_config_bump) = pda::platform_config();
 
         // Call the initialize_platform instruction.
         let result = initialize_platform(
@@ -85,10 +82,7 @@ mod tests {
         let (mut context, payer, authority) = setup_test_env().await;
 
         // Derive the platform config PDA.
-        let (platform_config_pda, platform_config_bump) = Pubkey::find_program_address(
-            &[PLATFORM_CONFIG_SEED],
-            &ontora_ai::ID,
-        );
+        let (platform_config_pda, platform_config_bump) = pda::platform_config();
 
         // Initialize the platform first.
         initialize_platform(
@@ -105,10 +99,7 @@ mod tests {
         let user = Keypair::new();
 
         // Derive the user stake PDA.
-        let (user_stake_pda, user_stake_bump) = Pubkey::find_program_address(
-            &[b"user_stake", user.pubkey().as_ref()],
-            &ontora_ai::ID,
-        );
+        let (user_stake_pda, user_stake_bump) = pda::user_stake(&user.pubkey());
 
         // Call the stake_tokens instruction.
         let result = stake_tokens(
@@ -146,10 +137,7 @@ mod tests {
         let (mut context, payer, authority) = setup_test_env().await;
 
         // Derive the platform config PDA.
-        let (platform_config_pda, platform_config_bump) = Pubkey::find_program_address(
-            &[PLATFORM_CONFIG_SEED],
-            &ontora_ai::ID,
-        );
+        let (platform_config_pda, platform_config_bump) = pda::platform_config();
 
         // Initialize the platform first.
         initialize_platform(
@@ -167,10 +155,7 @@ mod tests {
 
         // Derive the proposal PDA (proposal ID 0 since it's the first proposal).
         let proposal_id = 0;
-        let (proposal_pda, proposal_bump) = Pubkey::find_program_address(
-            &[b"proposal", proposal_id.to_le_bytes().as_ref()],
-            &ontora_ai::ID,
-        );
+        let (proposal_pda, proposal_bump) = pda::proposal(proposal_id);
 
         // Call the create_proposal instruction.
         let title = "Test Proposal".to_string();
@@ -217,10 +202,7 @@ mod tests {
         let (mut context, payer, authority) = setup_test_env().await;
 
         // Derive the platform config PDA.
-        let (platform_config_pda, platform_config_bump) = Pubkey::find_program_address(
-            &[PLATFORM_CONFIG_SEED],
-            &ontora_ai::ID,
-        );
+        let (platform_config_pda, platform_config_bump) = pda::platform_config();
 
         // Initialize the platform first.
         initialize_platform(
@@ -236,10 +218,7 @@ mod tests {
         // Create a user keypair as the proposal creator.
         let creator = Keypair::new();
         let proposal_id = 0;
-        let (proposal_pda, proposal_bump) = Pubkey::find_program_address(
-            &[b"proposal", proposal_id.to_le_bytes().as_ref()],
-            &ontora_ai::ID,
-        );
+        let (proposal_pda, proposal_bump) = pda::proposal(proposal_id);
 
         // Create a proposal.
         let title = "Test Proposal".to_string();
@@ -297,10 +276,7 @@ mod tests {
         let (mut context, payer, authority) = setup_test_env().await;
 
         // Derive the platform config PDA.
-        let (platform_config_pda, platform_config_bump) = Pubkey::find_program_address(
-            &[PLATFORM_CONFIG_SEED],
-            &ontora_ai::ID,
-        );
+        let (platform_config_pda, platform_config_bump) = pda::platform_config();
 
         // Initialize the platform first.
         initialize_platform(
@@ -316,10 +292,7 @@ mod tests {
         // Create a user keypair as the proposal creator.
         let creator = Keypair::new();
         let proposal_id = 0;
-        let (proposal_pda, proposal_bump) = Pubkey::find_program_address(
-            &[b"proposal", proposal_id.to_le_bytes().as_ref()],
-            &ontora_ai::ID,
-        );
+        let (proposal_pda, proposal_bump) = pda::proposal(proposal_id);
 
         // Create a proposal with a short voting duration for testing.
         let title = "Test Proposal".to_string();
@@ -0,0 +1,129 @@
+// snapshot/mod.rs
+// This module saves the state a suite's expensive setup leaves behind, so later tests can
+// start from it instead of replaying the setup. A snapshot holds the accounts the setup
+// created, the keypairs that sign for them, and the clock; restoring preloads the accounts
+// into a fresh ProgramTest with `add_account` and warps the validator to the saved slot and
+// unix_timestamp.
+//
+// BanksClient cannot list a program's accounts, so the setup names the accounts to keep.
+// Snapshots are written under CARGO_TARGET_TMPDIR and carry a fingerprint of the test binary
+// (the program is compiled into it, as is the setup itself), the sbf build when there is one,
+// and the account layout goldens. A snapshot whose fingerprint no longer matches is ignored,
+// and the suite rebuilds it on its next run.
+// Test files using the snapshots also declare `mod test_setup;`.
+
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use anchor_client::solana_sdk::account::Account;
+use anchor_client::solana_sdk::signature::{Keypair, Signer};
+use anchor_client::Program;
+use anchor_lang::solana_program::clock::Clock;
+use anchor_lang::solana_program::hash::{hashv, Hash};
+use anchor_lang::solana_program::pubkey::Pubkey;
+use serde::{Deserialize, Serialize};
+
+use crate::test_setup::*;
+
+const SNAPSHOT_DIR: &str = concat!(env!("CARGO_TARGET_TMPDIR"), "/snapshots");
+const SBF_PROGRAM_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/target/deploy/ontora_ai.so");
+const ACCOUNT_LAYOUTS_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden/account_layouts.txt");
+
+// Accounts, signers and clock of a validator after a suite's setup
+#[derive(Serialize, Deserialize)]
+pub struct Snapshot {
+    pub fingerprint: [u8; 32],
+    pub genesis_clock: Clock,
+    pub clock: Clock,
+    pub accounts: Vec<(Pubkey, Account)>,
+    // Keypair bytes by the name the setup gave them
+    pub signers: Vec<(String, Vec<u8>)>,
+}
+
+// Hash of everything a snapshot's accounts depend on: the program and the setup (both in the
+// running test binary), the sbf build, and the account layouts. Computed once per run.
+pub fn fingerprint() -> Hash {
+    static FINGERPRINT: OnceLock<Hash> = OnceLock::new();
+    *FINGERPRINT.get_or_init(|| {
+        let inputs: Vec<Vec<u8>> = [
+            std::env::current_exe().expect("the test binary has a path"),
+            PathBuf::from(SBF_PROGRAM_PATH),
+            PathBuf::from(ACCOUNT_LAYOUTS_PATH),
+        ]
+        .iter()
+        .map(|path| std::fs::read(path).unwrap_or_default())
+        .collect();
+        hashv(&inputs.iter().map(Vec::as_slice).collect::<Vec<_>>())
+    })
+}
+
+fn snapshot_path(name: &str) -> PathBuf {
+    PathBuf::from(SNAPSHOT_DIR).join(format!("{}.bin", name))
+}
+
+impl Snapshot {
+    // Read `signers`' and `accounts`' current state and the clock from the validator.
+    // Accounts that do not exist are left out.
+    pub async fn capture(ctx: &mut TestContext, signers: &[(&str, &TestUser)], accounts: &[Pubkey]) -> Snapshot {
+        let addresses = signers.iter().map(|(_, user)| user.pubkey).chain(accounts.iter().copied());
+        let mut captured = Vec::new();
+        for address in addresses {
+            if let Some(account) = ctx.banks_client.get_account(address).await.unwrap() {
+                captured.push((address, account));
+            }
+        }
+        Snapshot {
+            fingerprint: fingerprint().to_bytes(),
+            genesis_clock: ctx.genesis_clock.clone(),
+            clock: get_clock(&mut ctx.banks_client).await,
+            accounts: captured,
+            signers: signers.iter().map(|(name, user)| (name.to_string(), user.keypair.to_bytes().to_vec())).collect(),
+        }
+    }
+
+    // Write the snapshot as `name`, replacing any earlier one. Suites run their tests in
+    // parallel, so it is written aside and renamed into place.
+    pub fn save(&self, name: &str) {
+        let path = snapshot_path(name);
+        std::fs::create_dir_all(SNAPSHOT_DIR).unwrap();
+        let staging = path.with_extension(format!("{}.tmp", std::process::id()));
+        std::fs::write(&staging, bincode::serialize(self).unwrap()).unwrap();
+        std::fs::rename(&staging, &path).unwrap();
+    }
+
+    // The snapshot saved as `name`, unless there is none or it was taken of another build
+    pub fn load(name: &str) -> Option<Snapshot> {
+        let bytes = std::fs::read(snapshot_path(name)).ok()?;
+        let snapshot: Snapshot = bincode::deserialize(&bytes).ok()?;
+        (snapshot.fingerprint == fingerprint().to_bytes()).then_some(snapshot)
+    }
+
+    // Start a validator holding the snapshot's accounts, at its slot and unix_timestamp
+    pub async fn restore(&self) -> (TestContext, Program) {
+        let mut test = program_test();
+        for (address, account) in &self.accounts {
+            test.add_account(*address, account.clone());
+        }
+        let (mut ctx, program) = start_test_context(test, id()).await;
+        if self.clock.slot > get_clock(&mut ctx.banks_client).await.slot {
+            ctx.program_context.warp_to_slot(self.clock.slot).unwrap();
+        }
+        let mut clock = get_clock(&mut ctx.banks_client).await;
+        clock.unix_timestamp = self.clock.unix_timestamp;
+        ctx.program_context.set_sysvar(&clock);
+        ctx.genesis_clock = self.genesis_clock.clone();
+        ctx.last_blockhash = ctx.banks_client.get_latest_blockhash().await.unwrap();
+        (ctx, program)
+    }
+
+    // The signer the setup saved as `name`
+    pub fn signer(&self, name: &str) -> TestUser {
+        let (_, bytes) = self
+            .signers
+            .iter()
+            .find(|(saved, _)| saved == name)
+            .unwrap_or_else(|| panic!("no signer {} in the snapshot", name));
+        let keypair = Keypair::from_bytes(bytes).unwrap();
+        TestUser { pubkey: keypair.pubkey(), keypair }
+    }
+}
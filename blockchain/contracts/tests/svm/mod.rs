@@ -0,0 +1,416 @@
+// svm/mod.rs
+// This module is an in-process test harness for the Ontora AI program built on LiteSVM.
+// It mirrors the helper API of test_setup.rs (users, funding, SPL tokens, platform
+// initialization, clock control, account reads) without starting a ProgramTest
+// validator per test, so the bulk of the suite runs synchronously and in milliseconds.
+//
+// LiteSVM executes the compiled program, so build it before running the tests:
+//     cargo build-sbf
+// Test files using the harness also declare `mod test_setup;` for the shared types and PDAs.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::clock::Clock;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::solana_program::system_instruction;
+use anchor_lang::solana_program::system_program;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use litesvm::types::{FailedTransactionMetadata, TransactionMetadata};
+use litesvm::LiteSVM;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::Transaction;
+
+use crate::test_setup::*;
+use ontora_ai::state::{FeeType, PlatformConfig, Role};
+
+mod scenario;
+pub use scenario::*;
+
+// The program binary produced by `cargo build-sbf`
+pub const PROGRAM_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/target/deploy/ontora_ai.so");
+
+pub type SvmResult = std::result::Result<TransactionMetadata, FailedTransactionMetadata>;
+
+// SvmContext holds the in-process VM and the fee payer for every transaction.
+// `genesis_clock` is the clock at startup, the origin `warp_seconds` maps time onto slots from.
+pub struct SvmContext {
+    pub svm: LiteSVM,
+    pub payer: Keypair,
+    pub genesis_clock: Clock,
+}
+
+// An agent registered through the single-agent `register_ai_agent`, with its owner's
+// token account and the vault holding its stake
+pub struct LegacyAgent {
+    pub owner: TestUser,
+    pub agent: Pubkey,
+    pub token_account: Pubkey,
+    pub staking_vault: Pubkey,
+}
+
+// Start a VM with the program, the SPL token programs, and a funded payer
+pub fn setup_svm() -> SvmContext {
+    let mut svm = LiteSVM::new();
+    svm.add_program_from_file(ontora_ai::ID, PROGRAM_PATH)
+        .unwrap_or_else(|error| panic!("failed to load {}: {} (run `cargo build-sbf` first)", PROGRAM_PATH, error));
+
+    let payer = Keypair::new();
+    svm.airdrop(&payer.pubkey(), INITIAL_LAMPORTS * 10).unwrap();
+    let genesis_clock = svm.get_sysvar::<Clock>();
+    SvmContext { svm, payer, genesis_clock }
+}
+
+// Build a program instruction from its Anchor accounts and arguments
+pub fn program_instruction(accounts: impl ToAccountMetas, args: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: ontora_ai::ID,
+        accounts: accounts.to_account_metas(None),
+        data: args.data(),
+    }
+}
+
+// Sign `instructions` with `signers`, pay with the context payer, and process them.
+// The blockhash is expired afterwards so an identical transaction can be sent again.
+pub fn send_transaction(ctx: &mut SvmContext, instructions: &[Instruction], signers: &[&Keypair]) -> SvmResult {
+    let mut all_signers = vec![&ctx.payer];
+    all_signers.extend_from_slice(signers);
+    let tx = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&ctx.payer.pubkey()),
+        &all_signers,
+        ctx.svm.latest_blockhash(),
+    );
+    let result = ctx.svm.send_transaction(tx);
+    ctx.svm.expire_blockhash();
+    result
+}
+
+// Extract the custom program error code a failed transaction returned, if any
+pub fn svm_error_code(result: &SvmResult) -> Option<u32> {
+    use solana_sdk::instruction::InstructionError;
+    use solana_sdk::transaction::TransactionError;
+
+    match result {
+        Err(failed) => match failed.err {
+            TransactionError::InstructionError(_, InstructionError::Custom(code)) => Some(code),
+            _ => None,
+        },
+        Ok(_) => None,
+    }
+}
+
+// Create a new test user holding INITIAL_LAMPORTS
+pub fn create_test_user(ctx: &mut SvmContext) -> TestUser {
+    let keypair = Keypair::new();
+    let pubkey = keypair.pubkey();
+    fund_account(ctx, &pubkey, INITIAL_LAMPORTS);
+    TestUser { keypair, pubkey }
+}
+
+// Fund an account with additional lamports
+pub fn fund_account(ctx: &mut SvmContext, account: &Pubkey, amount: u64) {
+    ctx.svm.airdrop(account, amount).unwrap();
+}
+
+// Get an account's balance in lamports
+pub fn get_account_balance(ctx: &SvmContext, account: &Pubkey) -> u64 {
+    ctx.svm.get_balance(account).unwrap_or(0)
+}
+
+// Deserialize an Anchor account
+pub fn fetch_account<T: AccountDeserialize>(ctx: &SvmContext, address: &Pubkey) -> T {
+    let account = ctx.svm.get_account(address).expect("account does not exist");
+    T::try_deserialize(&mut account.data.as_ref()).unwrap()
+}
+
+// Get the current clock
+pub fn get_clock(ctx: &SvmContext) -> Clock {
+    ctx.svm.get_sysvar::<Clock>()
+}
+
+// Advance the slot by `slots`
+pub fn advance_slot(ctx: &mut SvmContext, slots: u64) {
+    let slot = get_clock(ctx).slot;
+    ctx.svm.warp_to_slot(slot + slots);
+}
+
+// Overwrite the Clock sysvar's unix_timestamp, which `Clock::get()` in the program reads
+pub fn set_unix_timestamp(ctx: &mut SvmContext, unix_timestamp: i64) {
+    let mut clock = get_clock(ctx);
+    clock.unix_timestamp = unix_timestamp;
+    ctx.svm.set_sysvar(&clock);
+}
+
+// Move the VM `seconds` forward, as `warp_seconds` in test_setup.rs does: warp to the slot
+// that time falls in, then pin the clock's unix_timestamp. Returns the new unix_timestamp.
+pub fn warp_seconds(ctx: &mut SvmContext, seconds: i64) -> i64 {
+    let clock = get_clock(ctx);
+    let unix_timestamp = clock.unix_timestamp + seconds;
+    let slot = slot_at(&ctx.genesis_clock, unix_timestamp).max(clock.slot + 1);
+    ctx.svm.warp_to_slot(slot);
+    set_unix_timestamp(ctx, unix_timestamp);
+    unix_timestamp
+}
+
+// Move the VM forward one full platform epoch (`config.epoch_duration` seconds)
+pub fn warp_to_next_epoch(ctx: &mut SvmContext, config: &PlatformConfig) -> i64 {
+    warp_seconds(ctx, config.epoch_duration)
+}
+
+// Create an SPL mint with `authority` as its mint authority
+pub fn create_mint(ctx: &mut SvmContext, authority: &Pubkey) -> Pubkey {
+    let mint = Keypair::new();
+    let instructions = [
+        system_instruction::create_account(
+            &ctx.payer.pubkey(),
+            &mint.pubkey(),
+            ctx.svm.minimum_balance_for_rent_exemption(spl_token::state::Mint::LEN),
+            spl_token::state::Mint::LEN as u64,
+            &spl_token::ID,
+        ),
+        spl_token::instruction::initialize_mint(&spl_token::ID, &mint.pubkey(), authority, None, TEST_MINT_DECIMALS)
+            .unwrap(),
+    ];
+    send_transaction(ctx, &instructions, &[&mint]).unwrap();
+    mint.pubkey()
+}
+
+// Create the associated token account of `owner` for `mint`; PDAs may own one too
+pub fn create_token_account(ctx: &mut SvmContext, owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+    let instruction = spl_associated_token_account::instruction::create_associated_token_account(
+        &ctx.payer.pubkey(),
+        owner,
+        mint,
+        &spl_token::ID,
+    );
+    send_transaction(ctx, &[instruction], &[]).unwrap();
+    spl_associated_token_account::get_associated_token_address(owner, mint)
+}
+
+// Mint `amount` tokens into `account`
+pub fn mint_tokens(ctx: &mut SvmContext, mint: &Pubkey, authority: &TestUser, account: &Pubkey, amount: u64) {
+    let instruction =
+        spl_token::instruction::mint_to(&spl_token::ID, mint, account, &authority.pubkey, &[], amount).unwrap();
+    send_transaction(ctx, &[instruction], &[&authority.keypair]).unwrap();
+}
+
+// Create `owner`'s token account for the platform mint holding `amount` tokens
+pub fn create_funded_token_account(ctx: &mut SvmContext, platform: &TestPlatform, owner: &Pubkey, amount: u64) -> Pubkey {
+    let account = create_token_account(ctx, owner, &platform.mint);
+    if amount > 0 {
+        mint_tokens(ctx, &platform.mint, &platform.admin, &account, amount);
+    }
+    account
+}
+
+// Get a token account's balance
+pub fn get_token_balance(ctx: &SvmContext, account: &Pubkey) -> u64 {
+    let account = ctx.svm.get_account(account).expect("token account does not exist");
+    spl_token::state::Account::unpack(&account.data).unwrap().amount
+}
+
+// Register an AI agent owned by `owner` and return its PDA
+pub fn create_mock_ai_agent(ctx: &mut SvmContext, owner: &TestUser, agent_id: u64) -> Pubkey {
+    let ai_agent = ai_agent_pda(&ontora_ai::ID, &owner.pubkey, agent_id);
+    let instruction = program_instruction(
+        ontora_ai::accounts::RegisterAiAgent {
+            ai_agent,
+            owner: owner.pubkey,
+            system_program: system_program::ID,
+        },
+        ontora_ai::instruction::RegisterAgent {
+            agent_id,
+            name: format!("Agent {}", agent_id),
+            description: "Test agent".to_string(),
+        },
+    );
+    send_transaction(ctx, &[instruction], &[&owner.keypair]).unwrap();
+    ai_agent
+}
+
+// A new owner holding `amount` tokens, with the vault of the legacy agent they have yet to register
+pub fn prepare_legacy_agent(ctx: &mut SvmContext, platform: &TestPlatform, amount: u64) -> LegacyAgent {
+    let owner = create_test_user(ctx);
+    let token_account = create_funded_token_account(ctx, platform, &owner.pubkey, amount);
+    let agent = legacy_ai_agent_pda(&ontora_ai::ID, &owner.pubkey);
+    let staking_vault = create_token_account(ctx, &agent, &platform.mint);
+    LegacyAgent { owner, agent, token_account, staking_vault }
+}
+
+// Register a legacy agent for a new owner, staking `amount` tokens into its vault
+pub fn register_legacy_agent(ctx: &mut SvmContext, platform: &TestPlatform, amount: u64) -> LegacyAgent {
+    let staked = prepare_legacy_agent(ctx, platform, amount);
+    let instruction = register_legacy_instruction(platform, &staked, amount);
+    send_transaction(ctx, &[instruction], &[&staked.owner.keypair]).unwrap();
+    staked
+}
+
+// The `register_ai_agent` instruction for `staked`, for callers composing their own transaction
+pub fn register_legacy_instruction(platform: &TestPlatform, staked: &LegacyAgent, amount: u64) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::RegisterAIAgent {
+            platform_config: platform.platform_config,
+            ai_agent: staked.agent,
+            user: staked.owner.pubkey,
+            user_token_account: staked.token_account,
+            staking_vault: staked.staking_vault,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        },
+        ontora_ai::instruction::RegisterAiAgent { stake_amount: amount },
+    )
+}
+
+// The `increase_stake` instruction adding `amount` to `staked`'s stake
+pub fn increase_stake_instruction(platform: &TestPlatform, staked: &LegacyAgent, amount: u64) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::IncreaseStake {
+            platform_config: platform.platform_config,
+            ai_agent: staked.agent,
+            user: staked.owner.pubkey,
+            user_token_account: staked.token_account,
+            staking_vault: staked.staking_vault,
+            token_program: spl_token::ID,
+        },
+        ontora_ai::instruction::IncreaseStake { amount },
+    )
+}
+
+// The `request_unstake` instruction queueing `amount` of `staked`'s stake for withdrawal
+pub fn request_unstake_instruction(platform: &TestPlatform, staked: &LegacyAgent, amount: u64) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::RequestUnstake {
+            platform_config: platform.platform_config,
+            ai_agent: staked.agent,
+            pending_withdrawal: pending_withdrawal_pda(&ontora_ai::ID, &staked.owner.pubkey),
+            user: staked.owner.pubkey,
+            system_program: system_program::ID,
+        },
+        ontora_ai::instruction::RequestUnstake { amount },
+    )
+}
+
+// The `claim_unstake` instruction paying out `staked`'s requested withdrawal
+pub fn claim_unstake_instruction(platform: &TestPlatform, staked: &LegacyAgent) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::ClaimUnstake {
+            platform_config: platform.platform_config,
+            ai_agent: staked.agent,
+            pending_withdrawal: pending_withdrawal_pda(&ontora_ai::ID, &staked.owner.pubkey),
+            user: staked.owner.pubkey,
+            user_token_account: staked.token_account,
+            staking_vault: staked.staking_vault,
+            stake_mint: platform.mint,
+            fee_schedule: platform.fee_schedule,
+            treasury: platform.treasury,
+            treasury_vault: treasury_vault_pda(&ontora_ai::ID, &platform.mint),
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        },
+        ontora_ai::instruction::ClaimUnstake {},
+    )
+}
+
+// Initialize every platform account with a fresh admin, as `initialize_program` does
+// in test_setup.rs: the config with TEST_VOTE_UNIT, roles, treasury, a zero-fee schedule,
+// and a reward pool whose vault holds `initial_rewards` tokens
+pub fn initialize_program(ctx: &mut SvmContext, initial_rewards: u64) -> TestPlatform {
+    initialize_program_with_fees(ctx, initial_rewards, [0; FeeType::COUNT])
+}
+
+// `initialize_program` with a fee schedule charging `rates_bps` from the start
+pub fn initialize_program_with_fees(ctx: &mut SvmContext, initial_rewards: u64, rates_bps: [u16; FeeType::COUNT]) -> TestPlatform {
+    let program_id = ontora_ai::ID;
+    let admin = create_test_user(ctx);
+    let platform_config = platform_config_pda(&program_id);
+    let treasury = treasury_pda(&program_id);
+    let fee_schedule = fee_schedule_pda(&program_id);
+    let reward_pool = reward_pool_pda(&program_id);
+
+    let mut instructions = vec![program_instruction(
+        ontora_ai::accounts::InitializePlatform {
+            platform_config,
+            admin: admin.pubkey,
+            system_program: system_program::ID,
+        },
+        ontora_ai::instruction::InitializePlatform {
+            reward_rate_bps: 100,
+            min_stake_amount: TEST_STAKE_AMOUNT,
+            epoch_duration: 86_400,
+            cooldown_seconds: 86_400,
+            epoch_emission: TEST_EPOCH_EMISSION,
+        },
+    )];
+    for role in [Role::Crank, Role::Oracle, Role::Treasury] {
+        instructions.push(program_instruction(
+            ontora_ai::accounts::SetRole { platform_config, admin: admin.pubkey },
+            ontora_ai::instruction::SetRole { role, holder: admin.pubkey },
+        ));
+    }
+    instructions.push(program_instruction(
+        ontora_ai::accounts::UpdatePlatformConfig { platform_config, admin: admin.pubkey },
+        test_config_update(),
+    ));
+    instructions.push(program_instruction(
+        ontora_ai::accounts::InitializeTreasury {
+            platform_config,
+            treasury,
+            authority: admin.pubkey,
+            system_program: system_program::ID,
+        },
+        ontora_ai::instruction::InitializeTreasury { governance: admin.pubkey },
+    ));
+    instructions.push(program_instruction(
+        ontora_ai::accounts::InitializeFeeSchedule {
+            platform_config,
+            fee_schedule,
+            admin: admin.pubkey,
+            system_program: system_program::ID,
+        },
+        ontora_ai::instruction::InitializeFeeSchedule {
+            governance: admin.pubkey,
+            rates_bps,
+        },
+    ));
+    instructions.push(program_instruction(
+        ontora_ai::accounts::InitializeRewardPool {
+            reward_pool,
+            user: admin.pubkey,
+            system_program: system_program::ID,
+        },
+        ontora_ai::instruction::InitializeRewardPool { initial_rewards },
+    ));
+    send_transaction(ctx, &instructions, &[&admin.keypair]).unwrap();
+
+    let mint = create_mint(ctx, &admin.pubkey);
+    let (platform_vault, reward_vault) = (staking_vault_pda(&program_id), reward_vault_pda(&program_id));
+    let instruction = program_instruction(
+        ontora_ai::accounts::InitializeVaults {
+            platform_config,
+            reward_pool,
+            stake_mint: mint,
+            staking_vault: platform_vault,
+            reward_vault,
+            admin: admin.pubkey,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        },
+        ontora_ai::instruction::InitializeVaults {},
+    );
+    send_transaction(ctx, &[instruction], &[&admin.keypair]).unwrap();
+    let platform = TestPlatform {
+        admin,
+        platform_config,
+        fee_schedule,
+        treasury,
+        reward_pool,
+        mint,
+        platform_vault,
+        reward_vault,
+    };
+    if initial_rewards > 0 {
+        mint_tokens(ctx, &platform.mint, &platform.admin, &platform.reward_vault, initial_rewards);
+    }
+    platform
+}
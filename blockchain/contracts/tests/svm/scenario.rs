@@ -0,0 +1,550 @@
+// svm/scenario.rs
+// A builder for multi-user integration tests on the in-process harness. A scenario is an
+// ordered list of named steps (users, agents, stakes, epochs, proposals, and expectations)
+// that `run` submits one by one against a fresh platform, checking each expectation against
+// the decoded accounts and naming the failing step when one does not hold:
+//
+//     Scenario::new()
+//         .user("alice").with_tokens(1_000)
+//         .agent("gpt-agent").owned_by("alice")
+//         .stake("alice", "gpt-agent", 500)
+//         .expect_staked("gpt-agent", 500)
+//         .run();
+//
+// Rewards come from the single-agent flow: `stake_legacy` registers the user's own agent,
+// `distribute` cranks one epoch's payout to it, and `expect_rewards` reads what it accrued.
+
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::solana_program::system_program;
+use std::collections::HashMap;
+
+use super::*;
+use crate::test_setup::*;
+use ontora_ai::error::OntoraError;
+use ontora_ai::events::ProposalFinalized;
+use ontora_ai::state::{AiAgent, PlatformConfig, Proposal, ProposalAction};
+use ontora_ai::AIAgent;
+
+// Length of a reward epoch, matching EPOCH_DURATION in lib.rs
+pub const REWARD_EPOCH: i64 = 604_800;
+
+#[derive(Debug)]
+enum Action {
+    // `user` funds a stake that belongs to `beneficiary`
+    Stake { user: String, agent: String, amount: u64, beneficiary: String },
+    Unstake { user: String, agent: String, amount: u64 },
+    StakeLegacy { user: String, amount: u64 },
+    UnstakeLegacy { user: String },
+    Distribute { user: String },
+    Claim { user: String },
+    Propose { name: String, proposer: String, options: Vec<String>, voting_seconds: u64, action: ProposalAction },
+    Vote { user: String, proposal: String, option: u8 },
+    Finalize { proposal: String },
+    Execute { user: String, proposal: String },
+}
+
+#[derive(Debug)]
+enum Step {
+    User { name: String, tokens: u64 },
+    Agent { name: String, owner: Option<String> },
+    Act { action: Action, expected_error: Option<OntoraError> },
+    Advance { seconds: i64 },
+    ExpectRewards { user: String, amount: u64 },
+    ExpectStaked { agent: String, amount: u64 },
+    ExpectTokens { user: String, amount: u64 },
+    ExpectProposal { proposal: String, status: u8, tallies: Vec<u64> },
+    ExpectFinalized { proposal: String, reason: u8 },
+}
+
+// A user created by the scenario, with their token account and, once they have
+// staked through `stake_legacy`, their single-agent PDA and its vault
+pub struct ScenarioUser {
+    pub user: TestUser,
+    pub token_account: Pubkey,
+    pub legacy_agent: Option<(Pubkey, Pubkey)>,
+}
+
+// The state a scenario leaves behind, for assertions the steps do not cover
+pub struct ScenarioWorld {
+    pub ctx: SvmContext,
+    pub platform: TestPlatform,
+    pub users: HashMap<String, ScenarioUser>,
+    // Agent name to (PDA, agent id)
+    pub agents: HashMap<String, (Pubkey, u64)>,
+    // Proposal name to proposal id
+    pub proposals: HashMap<String, u64>,
+    // Proposal name to the event its finalization emitted
+    pub finalized: HashMap<String, ProposalFinalized>,
+}
+
+pub struct Scenario {
+    steps: Vec<Step>,
+    initial_rewards: u64,
+    epoch_seconds: i64,
+}
+
+impl Default for Scenario {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Scenario {
+    pub fn new() -> Self {
+        Scenario {
+            steps: Vec::new(),
+            initial_rewards: TEST_REWARD_POOL_AMOUNT,
+            epoch_seconds: REWARD_EPOCH,
+        }
+    }
+
+    // Tokens minted into the reward vault before the first step
+    pub fn with_reward_pool(mut self, amount: u64) -> Self {
+        self.initial_rewards = amount;
+        self
+    }
+
+    // Length of the epochs `advance_epochs` moves through (REWARD_EPOCH by default)
+    pub fn with_epoch_seconds(mut self, seconds: i64) -> Self {
+        self.epoch_seconds = seconds;
+        self
+    }
+
+    pub fn user(mut self, name: &str) -> Self {
+        self.steps.push(Step::User { name: name.to_string(), tokens: 0 });
+        self
+    }
+
+    // Fund the user declared last
+    pub fn with_tokens(mut self, amount: u64) -> Self {
+        match self.steps.last_mut() {
+            Some(Step::User { tokens, .. }) => *tokens = amount,
+            _ => panic!("with_tokens must follow user()"),
+        }
+        self
+    }
+
+    pub fn agent(mut self, name: &str) -> Self {
+        self.steps.push(Step::Agent { name: name.to_string(), owner: None });
+        self
+    }
+
+    // Register the agent declared last under `owner`
+    pub fn owned_by(mut self, owner: &str) -> Self {
+        match self.steps.last_mut() {
+            Some(Step::Agent { owner: slot, .. }) => *slot = Some(owner.to_string()),
+            _ => panic!("owned_by must follow agent()"),
+        }
+        self
+    }
+
+    pub fn stake(self, user: &str, agent: &str, amount: u64) -> Self {
+        self.stake_for(user, user, agent, amount)
+    }
+
+    // Stake `amount` of `funder`'s tokens on `agent` for `beneficiary`
+    pub fn stake_for(self, funder: &str, beneficiary: &str, agent: &str, amount: u64) -> Self {
+        let (user, beneficiary) = (funder.to_string(), beneficiary.to_string());
+        self.act(Action::Stake { user, agent: agent.to_string(), amount, beneficiary })
+    }
+
+    pub fn unstake(self, user: &str, agent: &str, amount: u64) -> Self {
+        self.act(Action::Unstake { user: user.to_string(), agent: agent.to_string(), amount })
+    }
+
+    // Register `user`'s single-agent stake of `amount` tokens
+    pub fn stake_legacy(self, user: &str, amount: u64) -> Self {
+        self.act(Action::StakeLegacy { user: user.to_string(), amount })
+    }
+
+    // Withdraw the whole of `user`'s single-agent stake
+    pub fn unstake_legacy(self, user: &str) -> Self {
+        self.act(Action::UnstakeLegacy { user: user.to_string() })
+    }
+
+    // Crank one epoch's reward payout to `user`'s single agent
+    pub fn distribute(self, user: &str) -> Self {
+        self.act(Action::Distribute { user: user.to_string() })
+    }
+
+    pub fn claim(self, user: &str) -> Self {
+        self.act(Action::Claim { user: user.to_string() })
+    }
+
+    pub fn propose(self, name: &str, proposer: &str, options: &[&str], voting_seconds: u64) -> Self {
+        self.act(Action::Propose {
+            name: name.to_string(),
+            proposer: proposer.to_string(),
+            options: options.iter().map(|option| option.to_string()).collect(),
+            voting_seconds,
+            action: ProposalAction::NoOp,
+        })
+    }
+
+    // Have the proposal declared last apply `action` once it passes
+    pub fn with_action(mut self, action: ProposalAction) -> Self {
+        match self.steps.last_mut() {
+            Some(Step::Act { action: Action::Propose { action: slot, .. }, .. }) => *slot = action,
+            _ => panic!("with_action must follow propose()"),
+        }
+        self
+    }
+
+    // Vote with the user's stake; a user who never staked has no stake account to vote with
+    pub fn vote(self, user: &str, proposal: &str, option: u8) -> Self {
+        self.act(Action::Vote { user: user.to_string(), proposal: proposal.to_string(), option })
+    }
+
+    pub fn finalize(self, proposal: &str) -> Self {
+        self.act(Action::Finalize { proposal: proposal.to_string() })
+    }
+
+    // Apply a passed proposal's action; any user may
+    pub fn execute(self, user: &str, proposal: &str) -> Self {
+        self.act(Action::Execute { user: user.to_string(), proposal: proposal.to_string() })
+    }
+
+    // Expect the action declared last to fail with `error`
+    pub fn fails_with(mut self, error: OntoraError) -> Self {
+        match self.steps.last_mut() {
+            Some(Step::Act { expected_error, .. }) => *expected_error = Some(error),
+            _ => panic!("fails_with must follow an action"),
+        }
+        self
+    }
+
+    pub fn advance_seconds(mut self, seconds: i64) -> Self {
+        self.steps.push(Step::Advance { seconds });
+        self
+    }
+
+    pub fn advance_epochs(self, epochs: i64) -> Self {
+        let seconds = self.epoch_seconds * epochs;
+        self.advance_seconds(seconds)
+    }
+
+    // Rewards accrued on `user`'s single agent and not yet claimed
+    pub fn expect_rewards(mut self, user: &str, amount: u64) -> Self {
+        self.steps.push(Step::ExpectRewards { user: user.to_string(), amount });
+        self
+    }
+
+    pub fn expect_staked(mut self, agent: &str, amount: u64) -> Self {
+        self.steps.push(Step::ExpectStaked { agent: agent.to_string(), amount });
+        self
+    }
+
+    pub fn expect_tokens(mut self, user: &str, amount: u64) -> Self {
+        self.steps.push(Step::ExpectTokens { user: user.to_string(), amount });
+        self
+    }
+
+    pub fn expect_proposal(mut self, proposal: &str, status: u8, tallies: &[u64]) -> Self {
+        self.steps.push(Step::ExpectProposal { proposal: proposal.to_string(), status, tallies: tallies.to_vec() });
+        self
+    }
+
+    // The FINALIZED_* reason the proposal's ProposalFinalized event gave
+    pub fn expect_finalized(mut self, proposal: &str, reason: u8) -> Self {
+        self.steps.push(Step::ExpectFinalized { proposal: proposal.to_string(), reason });
+        self
+    }
+
+    fn act(mut self, action: Action) -> Self {
+        self.steps.push(Step::Act { action, expected_error: None });
+        self
+    }
+
+    // Submit every step in order against a fresh platform
+    pub fn run(self) -> ScenarioWorld {
+        let mut ctx = setup_svm();
+        let platform = initialize_program(&mut ctx, self.initial_rewards);
+        let mut world = ScenarioWorld {
+            ctx,
+            platform,
+            users: HashMap::new(),
+            agents: HashMap::new(),
+            proposals: HashMap::new(),
+            finalized: HashMap::new(),
+        };
+        for (index, step) in self.steps.into_iter().enumerate() {
+            let description = format!("step {} ({:?})", index, step);
+            world.apply(step, &description);
+        }
+        world
+    }
+}
+
+impl ScenarioWorld {
+    pub fn user(&self, name: &str) -> &ScenarioUser {
+        self.users.get(name).unwrap_or_else(|| panic!("no user named {:?}", name))
+    }
+
+    pub fn agent(&self, name: &str) -> (Pubkey, u64) {
+        *self.agents.get(name).unwrap_or_else(|| panic!("no agent named {:?}", name))
+    }
+
+    pub fn proposal(&self, name: &str) -> Proposal {
+        let id = *self.proposals.get(name).unwrap_or_else(|| panic!("no proposal named {:?}", name));
+        let account = self.ctx.svm.get_account(&proposal_pda(&ontora_ai::ID, id)).unwrap();
+        read_zero_copy(&account.data)
+    }
+
+    fn legacy_agent(&self, user: &str) -> (Pubkey, Pubkey) {
+        self.user(user).legacy_agent.unwrap_or_else(|| panic!("{:?} has no single-agent stake", user))
+    }
+
+    fn apply(&mut self, step: Step, description: &str) {
+        match step {
+            Step::User { name, tokens } => {
+                let user = create_test_user(&mut self.ctx);
+                let token_account = create_funded_token_account(&mut self.ctx, &self.platform, &user.pubkey, tokens);
+                self.users.insert(name, ScenarioUser { user, token_account, legacy_agent: None });
+            }
+            Step::Agent { name, owner } => {
+                let owner = owner.unwrap_or_else(|| panic!("{}: agent needs owned_by()", description));
+                let agent_id = self.agents.len() as u64 + 1;
+                let pda = create_mock_ai_agent(&mut self.ctx, &self.users[&owner].user, agent_id);
+                self.agents.insert(name, (pda, agent_id));
+            }
+            Step::Act { action, expected_error } => {
+                let result = self.submit(action);
+                match expected_error {
+                    None => {
+                        if let Err(failed) = &result {
+                            panic!("{} failed: {:?}\n{}", description, failed.err, failed.meta.logs.join("\n"));
+                        }
+                    }
+                    Some(error) => {
+                        assert_eq!(svm_error_code(&result), Some(u32::from(error)), "{} should fail with {:?}", description, error);
+                    }
+                }
+            }
+            Step::Advance { seconds } => {
+                warp_seconds(&mut self.ctx, seconds);
+            }
+            Step::ExpectRewards { user, amount } => {
+                let (agent, _) = self.legacy_agent(&user);
+                let rewards = fetch_account::<AIAgent>(&self.ctx, &agent).accumulated_rewards;
+                assert_eq!(rewards, amount, "{}", description);
+            }
+            Step::ExpectStaked { agent, amount } => {
+                let (pda, _) = self.agent(&agent);
+                assert_eq!(fetch_account::<AiAgent>(&self.ctx, &pda).staked_amount, amount, "{}", description);
+            }
+            Step::ExpectTokens { user, amount } => {
+                let token_account = self.user(&user).token_account;
+                assert_eq!(get_token_balance(&self.ctx, &token_account), amount, "{}", description);
+            }
+            Step::ExpectProposal { proposal, status, tallies } => {
+                let proposal = self.proposal(&proposal);
+                assert_eq!(proposal.status, status, "{}", description);
+                assert_eq!(proposal.tallies(), tallies.as_slice(), "{}", description);
+            }
+            Step::ExpectFinalized { proposal, reason } => {
+                let event = self
+                    .finalized
+                    .get(&proposal)
+                    .unwrap_or_else(|| panic!("{}: {:?} was not finalized", description, proposal));
+                assert_eq!(event.reason, reason, "{}", description);
+            }
+        }
+    }
+
+    fn submit(&mut self, action: Action) -> SvmResult {
+        let program_id = ontora_ai::ID;
+        let platform = &self.platform;
+        let (instruction, signer) = match action {
+            Action::Stake { ref user, ref agent, amount, .. } | Action::Unstake { ref user, ref agent, amount } => {
+                let (ai_agent, agent_id) = self.agent(agent);
+                let staker = self.user(user);
+                let key = staker.user.pubkey;
+                let instruction = if let Action::Stake { ref beneficiary, .. } = action {
+                    let beneficiary = self.user(beneficiary).user.pubkey;
+                    program_instruction(
+                        ontora_ai::accounts::StakeOnAgent {
+                            platform_config: platform.platform_config,
+                            ai_agent,
+                            user_stake: user_stake_pda(&program_id, &beneficiary),
+                            stake_index: stake_index_pda(&program_id, &beneficiary, agent_id),
+                            user: key,
+                            user_token_account: staker.token_account,
+                            platform_vault: platform.platform_vault,
+                            token_program: spl_token::ID,
+                            system_program: system_program::ID,
+                        },
+                        ontora_ai::instruction::StakeOnAgent { agent_id, amount, beneficiary },
+                    )
+                } else {
+                    program_instruction(
+                        ontora_ai::accounts::UnstakeFromAgent {
+                            platform_config: platform.platform_config,
+                            ai_agent,
+                            user_stake: user_stake_pda(&program_id, &key),
+                            stake_index: stake_index_pda(&program_id, &key, agent_id),
+                            user: key,
+                            user_token_account: staker.token_account,
+                            platform_vault: platform.platform_vault,
+                            stake_mint: platform.mint,
+                            fee_schedule: platform.fee_schedule,
+                            treasury: platform.treasury,
+                            treasury_vault: treasury_vault_pda(&program_id, &platform.mint),
+                            token_program: spl_token::ID,
+                            system_program: system_program::ID,
+                        },
+                        ontora_ai::instruction::UnstakeFromAgent { agent_id, amount },
+                    )
+                };
+                (instruction, user.clone())
+            }
+            Action::StakeLegacy { ref user, amount } => {
+                let staker = self.user(user);
+                let agent = legacy_ai_agent_pda(&program_id, &staker.user.pubkey);
+                let token_account = staker.token_account;
+                let owner = staker.user.pubkey;
+                let mint = platform.mint;
+                let staking_vault = create_token_account(&mut self.ctx, &agent, &mint);
+                self.users.get_mut(user).unwrap().legacy_agent = Some((agent, staking_vault));
+                let instruction = program_instruction(
+                    ontora_ai::accounts::RegisterAIAgent {
+                        platform_config: self.platform.platform_config,
+                        ai_agent: agent,
+                        user: owner,
+                        user_token_account: token_account,
+                        staking_vault,
+                        token_program: spl_token::ID,
+                        system_program: system_program::ID,
+                    },
+                    ontora_ai::instruction::RegisterAiAgent { stake_amount: amount },
+                );
+                (instruction, user.clone())
+            }
+            Action::UnstakeLegacy { ref user } => {
+                let (agent, staking_vault) = self.legacy_agent(user);
+                let owner = self.user(user);
+                let instruction = program_instruction(
+                    ontora_ai::accounts::UnstakeAIAgent {
+                        platform_config: platform.platform_config,
+                        ai_agent: agent,
+                        pending_withdrawal: pending_withdrawal_pda(&program_id, &owner.user.pubkey),
+                        user: owner.user.pubkey,
+                        user_token_account: owner.token_account,
+                        staking_vault,
+                        stake_mint: platform.mint,
+                        fee_schedule: platform.fee_schedule,
+                        treasury: platform.treasury,
+                        treasury_vault: treasury_vault_pda(&program_id, &platform.mint),
+                        token_program: spl_token::ID,
+                        system_program: system_program::ID,
+                    },
+                    ontora_ai::instruction::UnstakeAiAgent {},
+                );
+                (instruction, user.clone())
+            }
+            Action::Distribute { ref user } => {
+                let (agent, _) = self.legacy_agent(user);
+                let instruction = program_instruction(
+                    ontora_ai::accounts::DistributeRewards {
+                        platform_config: platform.platform_config,
+                        reward_pool: platform.reward_pool,
+                        ai_agent: agent,
+                        crank: platform.admin.pubkey,
+                    },
+                    ontora_ai::instruction::DistributeRewards {},
+                );
+                return send_transaction(&mut self.ctx, &[instruction], &[&self.platform.admin.keypair]);
+            }
+            Action::Claim { ref user } => {
+                let (agent, _) = self.legacy_agent(user);
+                let claimer = self.user(user);
+                let instruction = program_instruction(
+                    ontora_ai::accounts::ClaimRewards {
+                        platform_config: platform.platform_config,
+                        ai_agent: agent,
+                        reward_pool: platform.reward_pool,
+                        user: claimer.user.pubkey,
+                        user_token_account: claimer.token_account,
+                        reward_vault: platform.reward_vault,
+                        reward_mint: platform.mint,
+                        fee_schedule: platform.fee_schedule,
+                        treasury: platform.treasury,
+                        treasury_vault: treasury_vault_pda(&program_id, &platform.mint),
+                        reward_vesting: reward_vesting_pda(&program_id, &claimer.user.pubkey),
+                        token_program: spl_token::ID,
+                        system_program: system_program::ID,
+                    },
+                    ontora_ai::instruction::ClaimRewards {},
+                );
+                (instruction, user.clone())
+            }
+            Action::Propose { name, ref proposer, options, voting_seconds, action } => {
+                let proposal_id = fetch_account::<PlatformConfig>(&self.ctx, &platform.platform_config).proposal_count;
+                self.proposals.insert(name.clone(), proposal_id);
+                let instruction = program_instruction(
+                    ontora_ai::accounts::CreateGovernanceProposal {
+                        creator: self.user(proposer).user.pubkey,
+                        platform_config: platform.platform_config,
+                        proposal: proposal_pda(&program_id, proposal_id),
+                        system_program: system_program::ID,
+                    },
+                    ontora_ai::instruction::CreateGovernanceProposal {
+                        title: name,
+                        description: "Scenario proposal".to_string(),
+                        voting_duration: voting_seconds,
+                        options,
+                        action,
+                    },
+                );
+                (instruction, proposer.clone())
+            }
+            Action::Vote { ref user, ref proposal, option } => {
+                let proposal_id = self.proposals[proposal];
+                let voter = self.user(user);
+                let instruction = program_instruction(
+                    ontora_ai::accounts::CastVote {
+                        voter: voter.user.pubkey,
+                        platform_config: platform.platform_config,
+                        proposal: proposal_pda(&program_id, proposal_id),
+                        user_stake: user_stake_pda(&program_id, &voter.user.pubkey),
+                        vote_record: vote_record_pda(&program_id, proposal_id, &voter.user.pubkey),
+                        system_program: system_program::ID,
+                    },
+                    ontora_ai::instruction::CastVote { proposal_id, vote_option: option },
+                );
+                (instruction, user.clone())
+            }
+            Action::Finalize { ref proposal } => {
+                let proposal_id = self.proposals[proposal];
+                let instruction = program_instruction(
+                    ontora_ai::accounts::FinalizeProposal {
+                        caller: platform.admin.pubkey,
+                        platform_config: platform.platform_config,
+                        proposal: proposal_pda(&program_id, proposal_id),
+                        system_program: system_program::ID,
+                    },
+                    ontora_ai::instruction::FinalizeProposal { proposal_id },
+                );
+                let result = send_transaction(&mut self.ctx, &[instruction], &[&self.platform.admin.keypair]);
+                if let Some(event) = result.as_ref().ok().and_then(|meta| parse_events(&meta.logs).pop()) {
+                    self.finalized.insert(proposal.clone(), event);
+                }
+                return result;
+            }
+            Action::Execute { ref user, ref proposal } => {
+                let proposal_id = self.proposals[proposal];
+                let instruction = program_instruction(
+                    ontora_ai::accounts::ExecuteProposal {
+                        executor: self.user(user).user.pubkey,
+                        platform_config: platform.platform_config,
+                        proposal: proposal_pda(&program_id, proposal_id),
+                    },
+                    ontora_ai::instruction::ExecuteProposal {},
+                );
+                (instruction, user.clone())
+            }
+        };
+
+        let signer = &self.users[&signer].user.keypair;
+        send_transaction(&mut self.ctx, &[instruction], &[signer])
+    }
+}
@@ -0,0 +1,258 @@
+// test_account_helpers.rs
+// This module contains boundary tests for the computed-field helpers on account
+// types: pending rewards and their settlement, voting power overall and on a proposal, proposal
+// actions, vote tallies and proposal outcomes, score staleness, and unstake cooldowns.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use bytemuck::Zeroable;
+
+// Import test setup utilities (assumes test_setup.rs is in the same directory)
+mod test_setup;
+use test_setup::*;
+
+use ontora_ai::error::OntoraError;
+use ontora_ai::state::{
+    AiAgent, ConfigParams, PlatformConfig, Proposal, ProposalAction, UserStake, DEFAULT_VOTE_UNIT, FINALIZED_APPROVED,
+    FINALIZED_BELOW_THRESHOLD, FINALIZED_NO_QUORUM, FINALIZED_TIE, MAX_REWARD_RATE_BPS, MIN_EPOCH_DURATION,
+    PROPOSAL_APPROVED, PROPOSAL_REJECTED,
+};
+use ontora_ai::{AIAgent, LEGACY_AI_AGENT_VERSION};
+
+const EPOCH: i64 = 86_400;
+const CREATED_AT: i64 = 1_700_000_000;
+
+fn config() -> PlatformConfig {
+    let mut config = PlatformConfig::default();
+    config.init(
+        Pubkey::new_unique(),
+        100,
+        TEST_STAKE_AMOUNT,
+        EPOCH,
+        86_400,
+        TEST_EPOCH_EMISSION,
+        255,
+    );
+    config.last_reward_timestamp = CREATED_AT;
+    config
+}
+
+fn stake(staked_amount: u64) -> UserStake {
+    let mut stake = UserStake::default();
+    stake.init(Pubkey::new_unique(), 255);
+    stake.staked_amount = staked_amount;
+    stake.last_reward_claim = CREATED_AT;
+    stake
+}
+
+// Test that rewards accrue by the second on top of the accumulated balance
+#[test]
+fn test_pending_rewards() {
+    let config = config();
+    let mut stake = stake(100 * TEST_STAKE_AMOUNT);
+    let per_epoch = TEST_STAKE_AMOUNT; // 1% of the stake
+
+    assert_eq!(stake.pending_rewards(&config, CREATED_AT).unwrap(), 0);
+    assert_eq!(stake.pending_rewards(&config, CREATED_AT + EPOCH / 2).unwrap(), per_epoch / 2);
+    assert_eq!(stake.pending_rewards(&config, CREATED_AT + EPOCH).unwrap(), per_epoch);
+    assert_eq!(stake.pending_rewards(&config, CREATED_AT + 3 * EPOCH).unwrap(), 3 * per_epoch);
+    // A clock behind the accumulator accrues nothing
+    assert_eq!(stake.pending_rewards(&config, CREATED_AT - EPOCH).unwrap(), 0);
+
+    stake.accumulated_rewards = 17;
+    assert_eq!(stake.pending_rewards(&config, CREATED_AT + EPOCH).unwrap(), per_epoch + 17);
+
+    stake.staked_amount = u64::MAX;
+    let error = stake.pending_rewards(&config, i64::MAX).unwrap_err();
+    assert_eq!(error, OntoraError::ArithmeticError.into());
+}
+
+// Test that settling credits what the old stake earned and the new stake only
+// earns from the settlement on
+#[test]
+fn test_settle() {
+    let mut config = config();
+    let mut stake = stake(0);
+    let acc_reward_per_share = config.accrue(CREATED_AT).unwrap();
+    stake.settle(TEST_STAKE_AMOUNT, acc_reward_per_share).unwrap();
+    assert_eq!(stake.accumulated_rewards, 0);
+
+    // Half an epoch on the first stake, then the stake doubles
+    let acc_reward_per_share = config.accrue(CREATED_AT + EPOCH / 2).unwrap();
+    stake.settle(2 * TEST_STAKE_AMOUNT, acc_reward_per_share).unwrap();
+    let first_half = TEST_STAKE_AMOUNT / 200;
+    assert_eq!(stake.accumulated_rewards, first_half);
+    assert_eq!(stake.pending_rewards(&config, CREATED_AT + EPOCH).unwrap(), first_half + 2 * first_half);
+
+    // A clock behind the accumulator neither rewinds it nor credits anything
+    assert_eq!(config.accrue(CREATED_AT).unwrap(), acc_reward_per_share);
+    assert_eq!(config.last_reward_timestamp, CREATED_AT + EPOCH / 2);
+}
+
+// Test that a rate change applies from when it is made, not to what already accrued
+#[test]
+fn test_rate_change_accrues_old_rate_first() {
+    let mut config = config();
+    let stake = stake(100 * TEST_STAKE_AMOUNT);
+    let per_epoch = TEST_STAKE_AMOUNT; // 1% of the stake
+
+    let params = ConfigParams { reward_rate_bps: 300, ..config.params() };
+    config.update_params(params, CREATED_AT + EPOCH).unwrap();
+    assert_eq!(stake.pending_rewards(&config, CREATED_AT + EPOCH).unwrap(), per_epoch);
+    assert_eq!(stake.pending_rewards(&config, CREATED_AT + 2 * EPOCH).unwrap(), per_epoch + 3 * per_epoch);
+}
+
+// Test that voting power counts whole vote units
+#[test]
+fn test_voting_power() {
+    let config = config();
+    assert_eq!(config.vote_unit, DEFAULT_VOTE_UNIT);
+    assert_eq!(stake(0).voting_power(&config), 0);
+    assert_eq!(stake(DEFAULT_VOTE_UNIT - 1).voting_power(&config), 0);
+    assert_eq!(stake(DEFAULT_VOTE_UNIT).voting_power(&config), 1);
+    assert_eq!(stake(3 * DEFAULT_VOTE_UNIT - 1).voting_power(&config), 2);
+
+    // A zero vote unit can't divide, so it grants no votes
+    let mut config = config;
+    config.vote_unit = 0;
+    assert_eq!(stake(DEFAULT_VOTE_UNIT).voting_power(&config), 0);
+}
+
+// Test that only a stake its user last changed before the proposal opened votes on it,
+// and stake others funded votes only if funded before the proposal opened
+#[test]
+fn test_proposal_voting_power() {
+    let config = config();
+    let mut proposal = Proposal::zeroed();
+    proposal.start_time = CREATED_AT;
+    let mut stake = stake(3 * DEFAULT_VOTE_UNIT);
+    stake.record_own_change(CREATED_AT - 1);
+    assert_eq!(stake.proposal_voting_power(&config, &proposal), 3);
+
+    // Funded before the proposal opened, then in the second it opened
+    let mut funded = stake.clone();
+    funded.staked_amount += DEFAULT_VOTE_UNIT;
+    funded.record_funded(DEFAULT_VOTE_UNIT, CREATED_AT - 1).unwrap();
+    assert_eq!(funded.proposal_voting_power(&config, &proposal), 4);
+    funded.staked_amount += 2 * DEFAULT_VOTE_UNIT;
+    funded.record_funded(2 * DEFAULT_VOTE_UNIT, CREATED_AT).unwrap();
+    assert_eq!(funded.last_stake_update, CREATED_AT);
+    assert_eq!(funded.proposal_voting_power(&config, &proposal), 3);
+
+    // Changed by its user in the second the proposal opened, or any time after
+    stake.record_own_change(CREATED_AT);
+    assert_eq!(stake.proposal_voting_power(&config, &proposal), 0);
+    stake.record_own_change(CREATED_AT + EPOCH);
+    assert_eq!(stake.proposal_voting_power(&config, &proposal), 0);
+}
+
+// Test that a proposal stores its action, which changes only its own parameter and is
+// checked against the config's bounds
+#[test]
+fn test_proposal_action() {
+    let mut proposal = Proposal::zeroed();
+    assert_eq!(proposal.action().unwrap(), ProposalAction::NoOp);
+    let action = ProposalAction::UpdateEpochDuration { seconds: 2 * EPOCH };
+    proposal.set_action(&action).unwrap();
+    assert_eq!(proposal.action().unwrap(), action);
+
+    let mut config = config();
+    let before = config.clone();
+    ProposalAction::NoOp.apply(&mut config, CREATED_AT).unwrap();
+    assert_eq!(config.epoch_duration, before.epoch_duration);
+    action.apply(&mut config, CREATED_AT).unwrap();
+    assert_eq!(config.epoch_duration, 2 * EPOCH);
+    assert_eq!((config.reward_rate_bps, config.min_stake_amount), (before.reward_rate_bps, before.min_stake_amount));
+
+    assert!(ProposalAction::UpdateRewardRate { bps: MAX_REWARD_RATE_BPS }.validate().is_ok());
+    let invalid = [
+        ProposalAction::UpdateRewardRate { bps: MAX_REWARD_RATE_BPS + 1 },
+        ProposalAction::UpdateMinStake { amount: 0 },
+        ProposalAction::UpdateEpochDuration { seconds: MIN_EPOCH_DURATION - 1 },
+    ];
+    for action in invalid {
+        assert_eq!(action.validate().unwrap_err(), OntoraError::InvalidProposalParameters.into());
+    }
+}
+
+// Test that a tally takes votes up to u64::MAX and fails past it, leaving it unchanged
+#[test]
+fn test_add_votes() {
+    let mut proposal = Proposal::zeroed();
+    proposal.add_votes(1, u64::MAX - 5).unwrap();
+    proposal.add_votes(1, 5).unwrap();
+    assert_eq!(proposal.votes[1], u64::MAX);
+    let error = proposal.add_votes(1, 1).unwrap_err();
+    assert_eq!(error, OntoraError::ArithmeticError.into());
+    assert_eq!((proposal.votes[0], proposal.votes[1]), (0, u64::MAX));
+}
+
+// Test the finalization rules at their boundaries: quorum, tie, then approval threshold
+#[test]
+fn test_proposal_outcome() {
+    let outcome = |votes: &[u64], snapshot_total_staked: u64| {
+        let mut proposal = Proposal::zeroed();
+        proposal.option_count = votes.len() as u8;
+        proposal.votes[..votes.len()].copy_from_slice(votes);
+        proposal.snapshot_total_staked = snapshot_total_staked;
+        proposal.quorum_bps = 2_000;
+        proposal.approval_threshold_bps = 6_000;
+        proposal.outcome(10)
+    };
+    // 20 votes of 10 stake each are exactly a fifth of 1_000, and 12 of them exactly 60%
+    assert_eq!(outcome(&[8, 12], 1_000), (PROPOSAL_APPROVED, FINALIZED_APPROVED, 1));
+    assert_eq!(outcome(&[8, 12], 1_001), (PROPOSAL_REJECTED, FINALIZED_NO_QUORUM, 1));
+    assert_eq!(outcome(&[9, 11], 1_000), (PROPOSAL_REJECTED, FINALIZED_BELOW_THRESHOLD, 1));
+    assert_eq!(outcome(&[10, 10], 1_000), (PROPOSAL_REJECTED, FINALIZED_TIE, 0));
+    // No votes never reach quorum, even with nothing staked
+    assert_eq!(outcome(&[0, 0], 0), (PROPOSAL_REJECTED, FINALIZED_NO_QUORUM, 0));
+}
+
+// Test that a proposal is weighed at its own vote unit, or the platform's if it has none
+#[test]
+fn test_proposal_effective_vote_unit() {
+    let config = config();
+    let mut proposal = Proposal::zeroed();
+    assert_eq!(proposal.effective_vote_unit(&config), DEFAULT_VOTE_UNIT);
+    proposal.vote_unit = DEFAULT_VOTE_UNIT * 10;
+    assert_eq!(proposal.effective_vote_unit(&config), DEFAULT_VOTE_UNIT * 10);
+}
+
+// Test that an agent goes stale one epoch after registration or its last score
+#[test]
+fn test_agent_is_stale() {
+    let config = config();
+    let mut agent = AiAgent::default();
+    agent.init(1, Pubkey::new_unique(), "Agent".to_string(), String::new(), CREATED_AT, 255);
+
+    assert!(!agent.is_stale(CREATED_AT, &config));
+    assert!(!agent.is_stale(CREATED_AT + EPOCH, &config));
+    assert!(agent.is_stale(CREATED_AT + EPOCH + 1, &config));
+
+    agent.score_updated_at = CREATED_AT + 5 * EPOCH;
+    assert!(!agent.is_stale(CREATED_AT + 6 * EPOCH, &config));
+    assert!(agent.is_stale(CREATED_AT + 6 * EPOCH + 1, &config));
+}
+
+// Test that the configured cooldown counts down to zero and stays there
+#[test]
+fn test_cooldown_remaining() {
+    let agent = AIAgent {
+        version: LEGACY_AI_AGENT_VERSION,
+        owner: Pubkey::new_unique(),
+        staked_amount: TEST_STAKE_AMOUNT,
+        last_stake_time: CREATED_AT,
+        accumulated_rewards: 0,
+        is_active: true,
+        bump: 255,
+        last_reward_epoch: 0,
+    };
+    let mut config = config();
+    assert_eq!(agent.cooldown_remaining(&config, CREATED_AT), 86_400);
+    assert_eq!(agent.cooldown_remaining(&config, CREATED_AT + 86_399), 1);
+    assert_eq!(agent.cooldown_remaining(&config, CREATED_AT + 86_400), 0);
+    assert_eq!(agent.cooldown_remaining(&config, i64::MAX), 0);
+
+    config.cooldown_seconds = 0;
+    assert_eq!(agent.cooldown_remaining(&config, CREATED_AT), 0);
+}
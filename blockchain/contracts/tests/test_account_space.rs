@@ -0,0 +1,158 @@
+// test_account_space.rs
+// This module checks the derived account sizes: every account type is built at
+// its largest (full strings, full vectors, the widest enum variant), serialized,
+// and must fit the space its `init` constraint allocates.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use bytemuck::Zeroable;
+
+// Import test setup utilities (assumes test_setup.rs is in the same directory)
+mod test_setup;
+use test_setup::*;
+
+use ontora_ai::state::{
+    AdminAction, AdminActionProposal, AdminMultisig, AiAgent, FeeSchedule, FeeType, Metadata, PlatformConfig,
+    Proposal, Role, StakeIndexEntry, Treasury, UserStake, VoteRecord, MAX_DESCRIPTION_LENGTH, MAX_METADATA_LENGTH,
+    MAX_MULTISIG_SIGNERS, MAX_NAME_LENGTH,
+};
+use ontora_ai::{AIAgent, GovernanceProposal, PendingWithdrawal, RewardPool, RewardVesting, LEGACY_AI_AGENT_VERSION};
+
+fn serialized_len<T: AccountSerialize>(value: &T) -> usize {
+    let mut data = Vec::new();
+    value.try_serialize(&mut data).unwrap();
+    data.len()
+}
+
+fn keys(count: usize) -> Vec<Pubkey> {
+    (0..count).map(|_| Pubkey::new_unique()).collect()
+}
+
+// Test that the fixed-size accounts serialize to exactly their derived space
+#[test]
+fn test_fixed_size_accounts_match_space() {
+    let mut config = PlatformConfig::default();
+    config.init(
+        Pubkey::new_unique(),
+        100,
+        TEST_STAKE_AMOUNT,
+        86_400,
+        86_400,
+        TEST_EPOCH_EMISSION,
+        255,
+    );
+    assert_eq!(serialized_len(&config), PlatformConfig::SPACE);
+
+    let mut fee_schedule = FeeSchedule::default();
+    fee_schedule.init(Pubkey::new_unique(), [1; FeeType::COUNT], 255);
+    assert_eq!(serialized_len(&fee_schedule), FeeSchedule::SPACE);
+
+    let mut treasury = Treasury::default();
+    treasury.init(Pubkey::new_unique(), 255);
+    assert_eq!(serialized_len(&treasury), Treasury::SPACE);
+
+    let mut stake = UserStake::default();
+    stake.init(Pubkey::new_unique(), 255);
+    assert_eq!(serialized_len(&stake), UserStake::SPACE);
+
+    let mut entry = StakeIndexEntry::default();
+    entry.init(Pubkey::new_unique(), u64::MAX, i64::MAX, 255);
+    assert_eq!(serialized_len(&entry), StakeIndexEntry::SPACE);
+
+    let vote = VoteRecord { voter: Pubkey::new_unique(), vote_weight: u64::MAX, ..Default::default() };
+    assert_eq!(serialized_len(&vote), VoteRecord::SPACE);
+
+    let pool = RewardPool { total_rewards: u64::MAX, last_updated: i64::MAX, bump: 255 };
+    assert_eq!(serialized_len(&pool), 8 + RewardPool::INIT_SPACE);
+
+    let agent = AIAgent {
+        version: LEGACY_AI_AGENT_VERSION,
+        owner: Pubkey::new_unique(),
+        staked_amount: u64::MAX,
+        last_stake_time: i64::MAX,
+        accumulated_rewards: u64::MAX,
+        is_active: true,
+        bump: 255,
+        last_reward_epoch: u64::MAX,
+    };
+    assert_eq!(serialized_len(&agent), 8 + AIAgent::INIT_SPACE);
+
+    let withdrawal =
+        PendingWithdrawal { owner: Pubkey::new_unique(), amount: u64::MAX, release_time: i64::MAX, bump: 255 };
+    assert_eq!(serialized_len(&withdrawal), 8 + PendingWithdrawal::INIT_SPACE);
+
+    let vesting = RewardVesting {
+        owner: Pubkey::new_unique(),
+        total_amount: u64::MAX,
+        released_amount: u64::MAX,
+        start_time: i64::MAX,
+        duration: i64::MAX,
+        bump: 255,
+    };
+    assert_eq!(serialized_len(&vesting), 8 + RewardVesting::INIT_SPACE);
+}
+
+// Test that accounts with strings and vectors fit their space when full
+#[test]
+fn test_maximal_accounts_fit_space() {
+    let mut agent = AiAgent::default();
+    agent.init(
+        u64::MAX,
+        Pubkey::new_unique(),
+        "n".repeat(MAX_NAME_LENGTH),
+        "d".repeat(MAX_DESCRIPTION_LENGTH),
+        i64::MAX,
+        255,
+    );
+    assert_eq!(serialized_len(&agent), AiAgent::SPACE);
+
+    let mut multisig = AdminMultisig::default();
+    multisig.init(keys(MAX_MULTISIG_SIGNERS), MAX_MULTISIG_SIGNERS as u8, 255);
+    assert_eq!(serialized_len(&multisig), AdminMultisig::SPACE);
+
+    // SetRole is the widest action variant
+    let proposal = AdminActionProposal {
+        multisig: Pubkey::new_unique(),
+        id: u64::MAX,
+        action: AdminAction::SetRole { role: Role::Treasury, holder: Pubkey::new_unique() },
+        proposer: Pubkey::new_unique(),
+        approvals: keys(MAX_MULTISIG_SIGNERS),
+        expires_at: i64::MAX,
+        executed: true,
+        bump: 255,
+    };
+    assert_eq!(serialized_len(&proposal), AdminActionProposal::SPACE);
+
+    let mut metadata = Metadata::default();
+    metadata.init(u64::MAX, "m".repeat(MAX_METADATA_LENGTH), i64::MAX, 255);
+    assert_eq!(serialized_len(&metadata), 8 + Metadata::INIT_SPACE);
+    assert_eq!(serialized_len(&metadata), Metadata::space_for(MAX_METADATA_LENGTH));
+    metadata.data = "m".repeat(MAX_DESCRIPTION_LENGTH);
+    assert_eq!(serialized_len(&metadata), Metadata::SPACE);
+
+    let governance_proposal = GovernanceProposal {
+        proposer: Pubkey::new_unique(),
+        description: "d".repeat(GovernanceProposal::MAX_DESCRIPTION_LENGTH),
+        yes_votes: u64::MAX,
+        no_votes: u64::MAX,
+        start_time: i64::MAX,
+        end_time: i64::MAX,
+        is_active: true,
+        bump: 255,
+    };
+    assert_eq!(serialized_len(&governance_proposal), 8 + GovernanceProposal::INIT_SPACE);
+}
+
+// Test that Proposal::LEN covers the zero-copy layout with no hidden padding
+#[test]
+fn test_proposal_len() {
+    let proposal = zero_copy_bytes(&Proposal::zeroed());
+    assert_eq!(proposal.len(), Proposal::LEN);
+
+    let header = 5 + 1 + 2 + 8 + 32 + 8 + 8 + 8;
+    let arrays = 8 * 10 + 10 + 32 * 10 + 100 + 1000 + 2;
+    let snapshot = 8 + 2 + 2 + 4 + 8;
+    let action = 9 + 7;
+    let vote_unit = 8;
+    assert_eq!(Proposal::LEN, 8 + header + arrays + snapshot + action + vote_unit);
+}
@@ -0,0 +1,157 @@
+// test_admin.rs
+// This module contains test cases for the two-step platform admin transfer:
+// proposing, accepting, and cancelling a transfer, and access after rotation.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_client::Program;
+use solana_program_test::*;
+
+// Import test setup utilities (assumes test_setup.rs is in the same directory)
+mod test_setup;
+use test_setup::*;
+
+use ontora_ai::state::{ConfigParams, PlatformConfig};
+
+async fn fetch_platform_config(banks_client: &mut BanksClient, platform_config: Pubkey) -> PlatformConfig {
+    let account = banks_client.get_account(platform_config).await.unwrap().unwrap();
+    PlatformConfig::try_deserialize(&mut account.data.as_ref()).unwrap()
+}
+
+async fn propose_admin(program: &Program, platform_config: Pubkey, admin: &TestUser, new_admin: Pubkey) -> std::result::Result<(), anchor_client::ClientError> {
+    program
+        .request()
+        .accounts(ontora_ai::accounts::ProposeAdmin {
+            platform_config,
+            admin: admin.pubkey,
+        })
+        .args(ontora_ai::instruction::ProposeAdmin { new_admin })
+        .signer(&admin.keypair)
+        .send()
+        .await
+        .map(|_| ())
+}
+
+async fn accept_admin(program: &Program, platform_config: Pubkey, new_admin: &TestUser) -> std::result::Result<(), anchor_client::ClientError> {
+    program
+        .request()
+        .accounts(ontora_ai::accounts::AcceptAdmin {
+            platform_config,
+            new_admin: new_admin.pubkey,
+        })
+        .args(ontora_ai::instruction::AcceptAdmin {})
+        .signer(&new_admin.keypair)
+        .send()
+        .await
+        .map(|_| ())
+}
+
+async fn update_config(program: &Program, platform_config: Pubkey, admin: &TestUser) -> std::result::Result<(), anchor_client::ClientError> {
+    program
+        .request()
+        .accounts(ontora_ai::accounts::UpdatePlatformConfig {
+            platform_config,
+            admin: admin.pubkey,
+        })
+        .args(ontora_ai::instruction::UpdatePlatformConfig {
+            params: ConfigParams {
+                reward_rate_bps: 200,
+                min_stake_amount: TEST_STAKE_AMOUNT,
+                epoch_duration: 86_400,
+                cooldown_seconds: 86_400,
+                epoch_emission: TEST_EPOCH_EMISSION,
+                max_stake_per_agent: 0,
+                vesting_seconds: 0,
+                vote_unit: TEST_VOTE_UNIT,
+                quorum_bps: TEST_QUORUM_BPS,
+                approval_threshold_bps: TEST_APPROVAL_THRESHOLD_BPS,
+            },
+            update_metadata: String::new(),
+        })
+        .signer(&admin.keypair)
+        .send()
+        .await
+        .map(|_| ())
+}
+
+// Test a full rotation: propose, accept, and the old admin losing access
+#[tokio::test]
+async fn test_admin_full_rotation() {
+    let (mut ctx, program) = setup_test_context().await;
+    let admin = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
+    let new_admin = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
+    let platform_config = initialize_test_platform(&program, &admin).await;
+
+    assert!(propose_admin(&program, platform_config, &admin, new_admin.pubkey).await.is_ok());
+
+    // The current admin keeps access until the nominee accepts
+    let config = fetch_platform_config(&mut ctx.banks_client, platform_config).await;
+    assert_eq!(config.admin, admin.pubkey);
+    assert_eq!(config.pending_admin, new_admin.pubkey);
+    assert!(update_config(&program, platform_config, &admin).await.is_ok());
+    assert!(update_config(&program, platform_config, &new_admin).await.is_err());
+
+    assert!(accept_admin(&program, platform_config, &new_admin).await.is_ok());
+
+    let config = fetch_platform_config(&mut ctx.banks_client, platform_config).await;
+    assert_eq!(config.admin, new_admin.pubkey);
+    assert_eq!(config.pending_admin, Pubkey::default());
+
+    // Old admin lost access; new admin has it
+    assert!(update_config(&program, platform_config, &admin).await.is_err());
+    assert!(update_config(&program, platform_config, &new_admin).await.is_ok());
+}
+
+// Test that only the nominated key can accept
+#[tokio::test]
+async fn test_accept_admin_by_wrong_key() {
+    let (mut ctx, program) = setup_test_context().await;
+    let admin = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
+    let new_admin = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
+    let attacker = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
+    let platform_config = initialize_test_platform(&program, &admin).await;
+
+    // Accepting with nothing pending fails
+    assert!(accept_admin(&program, platform_config, &new_admin).await.is_err());
+
+    propose_admin(&program, platform_config, &admin, new_admin.pubkey).await.unwrap();
+    assert!(accept_admin(&program, platform_config, &attacker).await.is_err());
+
+    let config = fetch_platform_config(&mut ctx.banks_client, platform_config).await;
+    assert_eq!(config.admin, admin.pubkey);
+    assert_eq!(config.pending_admin, new_admin.pubkey);
+}
+
+// Test cancelling a pending transfer
+#[tokio::test]
+async fn test_cancel_admin_transfer() {
+    let (mut ctx, program) = setup_test_context().await;
+    let admin = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
+    let new_admin = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
+    let platform_config = initialize_test_platform(&program, &admin).await;
+
+    propose_admin(&program, platform_config, &admin, new_admin.pubkey).await.unwrap();
+
+    // Only the current admin may cancel
+    let cancel_as = |signer: &TestUser| {
+        program
+            .request()
+            .accounts(ontora_ai::accounts::CancelAdminTransfer {
+                platform_config,
+                admin: signer.pubkey,
+            })
+            .args(ontora_ai::instruction::CancelAdminTransfer {})
+            .signer(&signer.keypair)
+            .send()
+    };
+    assert!(cancel_as(&new_admin).await.is_err());
+    assert!(cancel_as(&admin).await.is_ok());
+
+    let config = fetch_platform_config(&mut ctx.banks_client, platform_config).await;
+    assert_eq!(config.pending_admin, Pubkey::default());
+
+    // The cancelled nominee can no longer accept
+    assert!(accept_admin(&program, platform_config, &new_admin).await.is_err());
+    let config = fetch_platform_config(&mut ctx.banks_client, platform_config).await;
+    assert_eq!(config.admin, admin.pubkey);
+}
@@ -0,0 +1,159 @@
+// test_agent_events.rs
+// This module contains test cases for the agent lifecycle events: AgentRegistered
+// from registration, and AgentUpdated from metadata updates and performance scores.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::solana_program::system_program;
+use anchor_client::Program;
+use solana_program_test::*;
+use solana_sdk::signature::Signer;
+use solana_sdk::transaction::Transaction;
+
+// Import test setup utilities (assumes test_setup.rs is in the same directory)
+mod test_setup;
+use test_setup::*;
+
+use ontora_ai::events::{AgentRegistered, AgentUpdated};
+use ontora_ai::pda;
+use ontora_ai::state::Role;
+
+const AGENT_ID: u64 = 7;
+
+fn agent_pda(owner: &Pubkey, agent_id: u64) -> Pubkey {
+    Pubkey::find_program_address(&[pda::AI_AGENT_SEED, owner.as_ref(), &agent_id.to_le_bytes()], &id()).0
+}
+
+fn metadata_json(name: &str, description: &str, performance_score: u64) -> String {
+    serde_json::json!({
+        "name": name,
+        "description": description,
+        "performance_score": performance_score,
+    })
+    .to_string()
+}
+
+// Send `instructions` signed by `signer` and return the logs
+async fn send_with_logs(ctx: &mut TestContext, instructions: Vec<Instruction>, signer: &TestUser) -> Vec<String> {
+    let tx = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&signer.pubkey),
+        &[&signer.keypair],
+        ctx.banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    let result = ctx.banks_client.process_transaction_with_metadata(tx).await.unwrap();
+    assert!(result.result.is_ok());
+    result.metadata.unwrap().log_messages
+}
+
+async fn register_agent(ctx: &mut TestContext, program: &Program, owner: &TestUser) -> Vec<String> {
+    let instructions = program
+        .request()
+        .accounts(ontora_ai::accounts::RegisterAiAgent {
+            ai_agent: agent_pda(&owner.pubkey, AGENT_ID),
+            owner: owner.pubkey,
+            system_program: system_program::ID,
+        })
+        .args(ontora_ai::instruction::RegisterAgent {
+            agent_id: AGENT_ID,
+            name: "Ontora-Alpha".to_string(),
+            description: "Forecasting agent".to_string(),
+        })
+        .instructions()
+        .unwrap();
+    send_with_logs(ctx, instructions, owner).await
+}
+
+// Test that registration emits AgentRegistered with the agent's metadata
+#[tokio::test]
+async fn test_register_emits_agent_registered() {
+    let (mut ctx, program) = setup_test_context().await;
+    let owner = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
+
+    let logs = register_agent(&mut ctx, &program, &owner).await;
+    let events = parse_events::<AgentRegistered>(&logs);
+    assert_eq!(events.len(), 1);
+    let event = &events[0];
+    assert_eq!(event.schema_version, AgentRegistered::SCHEMA_VERSION);
+    assert_eq!(event.agent_id, AGENT_ID);
+    assert_eq!(event.owner, owner.pubkey);
+    assert!(event.timestamp > 0);
+    assert_eq!(event.metadata, metadata_json("Ontora-Alpha", "Forecasting agent", 0));
+}
+
+// Test that metadata updates and performance scores both emit AgentUpdated
+#[tokio::test]
+async fn test_updates_emit_agent_updated() {
+    let (mut ctx, program) = setup_test_context().await;
+    let admin = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
+    let owner = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
+    let oracle = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
+    let platform_config = initialize_test_platform(&program, &admin).await;
+    register_agent(&mut ctx, &program, &owner).await;
+    let ai_agent = agent_pda(&owner.pubkey, AGENT_ID);
+
+    // Owner renames the agent
+    let instructions = program
+        .request()
+        .accounts(ontora_ai::accounts::UpdateAgentMetadata { ai_agent, owner: owner.pubkey })
+        .args(ontora_ai::instruction::UpdateAgentMetadata {
+            agent_id: AGENT_ID,
+            name: "Ontora-Beta".to_string(),
+            description: "Forecasting agent, v2".to_string(),
+        })
+        .instructions()
+        .unwrap();
+    let logs = send_with_logs(&mut ctx, instructions, &owner).await;
+    let events = parse_events::<AgentUpdated>(&logs);
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].schema_version, AgentUpdated::SCHEMA_VERSION);
+    assert_eq!(events[0].agent_id, AGENT_ID);
+    assert_eq!(events[0].owner, owner.pubkey);
+    assert_eq!(events[0].new_metadata, metadata_json("Ontora-Beta", "Forecasting agent, v2", 0));
+
+    // The oracle posts a score
+    program
+        .request()
+        .accounts(ontora_ai::accounts::SetRole { platform_config, admin: admin.pubkey })
+        .args(ontora_ai::instruction::SetRole { role: Role::Oracle, holder: oracle.pubkey })
+        .signer(&admin.keypair)
+        .send()
+        .await
+        .unwrap();
+    let instructions = program
+        .request()
+        .accounts(ontora_ai::accounts::UpdatePerformanceScore {
+            platform_config,
+            ai_agent,
+            oracle: oracle.pubkey,
+        })
+        .args(ontora_ai::instruction::UpdatePerformanceScore { score: 93 })
+        .instructions()
+        .unwrap();
+    let logs = send_with_logs(&mut ctx, instructions, &oracle).await;
+    let events = parse_events::<AgentUpdated>(&logs);
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].agent_id, AGENT_ID);
+    assert_eq!(events[0].owner, owner.pubkey);
+    assert_eq!(events[0].new_metadata, metadata_json("Ontora-Beta", "Forecasting agent, v2", 93));
+
+    // Only the owner may update the metadata
+    let instructions = program
+        .request()
+        .accounts(ontora_ai::accounts::UpdateAgentMetadata { ai_agent, owner: oracle.pubkey })
+        .args(ontora_ai::instruction::UpdateAgentMetadata {
+            agent_id: AGENT_ID,
+            name: "Hijacked".to_string(),
+            description: String::new(),
+        })
+        .instructions()
+        .unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&oracle.keypair.pubkey()),
+        &[&oracle.keypair],
+        ctx.banks_client.get_latest_blockhash().await.unwrap(),
+    );
+    assert!(ctx.banks_client.process_transaction(tx).await.is_err());
+}
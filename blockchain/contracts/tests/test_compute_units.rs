@@ -0,0 +1,580 @@
+// test_compute_units.rs
+// This module benchmarks the compute units of each instruction. The compiled
+// program runs under solana-program-test (see `setup_sbf_test_context`, which
+// needs `cargo build-sbf` first), every instruction is simulated with an
+// explicit compute-unit limit, and the units consumed are checked against the
+// budget table in tests/compute_budgets.txt. A comparison report is printed;
+// see it with
+//     cargo test --test test_compute_units -- --nocapture
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::system_program;
+use anchor_lang::InstructionData;
+use anchor_client::Program;
+use solana_program_test::*;
+use solana_sdk::compute_budget::ComputeBudgetInstruction;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::Transaction;
+use std::collections::BTreeMap;
+
+// Import test setup utilities (assumes test_setup.rs is in the same directory)
+mod test_setup;
+use test_setup::*;
+
+use ontora_ai::pda;
+use ontora_ai::state::{ConfigParams, ProposalAction, Role};
+
+const BUDGETS_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/compute_budgets.txt");
+// Measurements may exceed their budget by this much before the test fails
+const BUDGET_TOLERANCE_PERCENT: u64 = 10;
+// Per-instruction default limit when a transaction sets none
+const DEFAULT_COMPUTE_UNIT_LIMIT: u64 = 200_000;
+// Limit requested for each measured transaction, so a regression past the
+// default still produces a number instead of a failed simulation
+const MEASUREMENT_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+const LEGACY_EPOCH_DURATION: i64 = 604_800; // Matches EPOCH_DURATION in lib.rs
+const COOLDOWN_SECONDS: i64 = 86_400; // Matches the cooldown the test platform is initialized with
+const VOTING_DURATION: u64 = 86_400;
+
+fn instructions(program: &Program, accounts: impl ToAccountMetas, args: impl InstructionData) -> Vec<Instruction> {
+    program.request().accounts(accounts).args(args).instructions().unwrap()
+}
+
+fn load_budgets() -> BTreeMap<String, u64> {
+    std::fs::read_to_string(BUDGETS_PATH)
+        .unwrap()
+        .lines()
+        .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let (name, units) = line.split_once(' ').expect("budget lines are `instruction units`");
+            (name.to_string(), units.trim().parse().expect("budgets are whole compute units"))
+        })
+        .collect()
+}
+
+// Units consumed by each instruction, in execution order
+#[derive(Default)]
+struct Measurements(Vec<(&'static str, u64)>);
+
+impl Measurements {
+    // Simulate `instructions` under the measurement limit and record the units
+    // they consume, then process them so the next step sees the new state. The
+    // first signer pays the fee.
+    async fn measure(&mut self, ctx: &mut TestContext, name: &'static str, instructions: Vec<Instruction>, signers: &[&Keypair]) {
+        let mut all = vec![ComputeBudgetInstruction::set_compute_unit_limit(MEASUREMENT_COMPUTE_UNIT_LIMIT)];
+        all.extend(instructions);
+        let blockhash = ctx.banks_client.get_latest_blockhash().await.unwrap();
+        let tx = Transaction::new_signed_with_payer(&all, Some(&signers[0].pubkey()), signers, blockhash);
+
+        let simulation = ctx.banks_client.simulate_transaction(tx.clone()).await.unwrap();
+        let details = simulation.simulation_details.unwrap();
+        if let Some(Err(error)) = simulation.result {
+            panic!("{} failed: {:?}\n{}", name, error, details.logs.join("\n"));
+        }
+        self.0.push((name, details.units_consumed));
+        ctx.banks_client.process_transaction(tx).await.unwrap();
+    }
+
+    fn report(&self, budgets: &BTreeMap<String, u64>) -> String {
+        let mut text = format!(
+            "{:<28} {:>9} {:>9} {:>10} {:>12}\n",
+            "instruction", "units", "budget", "of budget", "of 200k"
+        );
+        for (name, units) in &self.0 {
+            let budget = budgets.get(*name).copied().unwrap_or_default();
+            let of_budget = if budget == 0 { "-".to_string() } else { format!("{:.1}%", *units as f64 * 100.0 / budget as f64) };
+            text.push_str(&format!(
+                "{:<28} {:>9} {:>9} {:>10} {:>11.1}%\n",
+                name,
+                units,
+                budget,
+                of_budget,
+                *units as f64 * 100.0 / DEFAULT_COMPUTE_UNIT_LIMIT as f64
+            ));
+        }
+        text
+    }
+
+    // Instructions over budget (beyond the tolerance), missing a budget, or
+    // budgeted but never measured
+    fn violations(&self, budgets: &BTreeMap<String, u64>) -> Vec<String> {
+        let mut violations = Vec::new();
+        for (name, units) in &self.0 {
+            match budgets.get(*name) {
+                None => violations.push(format!("{} has no entry in compute_budgets.txt", name)),
+                Some(budget) if units * 100 > budget * (100 + BUDGET_TOLERANCE_PERCENT) => violations.push(format!(
+                    "{} used {} units, more than {}% over its budget of {}",
+                    name, units, BUDGET_TOLERANCE_PERCENT, budget
+                )),
+                Some(_) => {}
+            }
+        }
+        for name in budgets.keys() {
+            if !self.0.iter().any(|(measured, _)| measured == name) {
+                violations.push(format!("{} is budgeted but was not measured", name));
+            }
+        }
+        violations
+    }
+}
+
+// Test that every instruction stays within its compute-unit budget
+#[tokio::test]
+async fn test_compute_units_within_budget() {
+    let (mut ctx, program) = setup_sbf_test_context().await;
+    let program_id = program.id();
+    let platform = initialize_program(&mut ctx, &program, TEST_REWARD_POOL_AMOUNT).await;
+    let admin = &platform.admin;
+    let user = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
+    let token_account = create_funded_token_account(&mut ctx, &platform, &user.pubkey, 2 * TEST_STAKE_AMOUNT).await;
+    let treasury_vault = treasury_vault_pda(&program_id, &platform.mint);
+    let mut measurements = Measurements::default();
+
+    // Agents and staking
+    let agent = ai_agent_pda(&program_id, &user.pubkey, TEST_AI_AGENT_ID);
+    let user_stake = user_stake_pda(&program_id, &user.pubkey);
+    let stake_index = stake_index_pda(&program_id, &user.pubkey, TEST_AI_AGENT_ID);
+    let register = instructions(
+        &program,
+        ontora_ai::accounts::RegisterAiAgent { ai_agent: agent, owner: user.pubkey, system_program: system_program::ID },
+        ontora_ai::instruction::RegisterAgent {
+            agent_id: TEST_AI_AGENT_ID,
+            name: "Benchmark agent".to_string(),
+            description: "Agent registered by the compute-unit benchmark".to_string(),
+        },
+    );
+    measurements.measure(&mut ctx, "register_agent", register, &[&user.keypair]).await;
+
+    let update = instructions(
+        &program,
+        ontora_ai::accounts::UpdateAgentMetadata { ai_agent: agent, owner: user.pubkey },
+        ontora_ai::instruction::UpdateAgentMetadata {
+            agent_id: TEST_AI_AGENT_ID,
+            name: "Benchmark agent v2".to_string(),
+            description: "Agent renamed by the compute-unit benchmark".to_string(),
+        },
+    );
+    measurements.measure(&mut ctx, "update_agent_metadata", update, &[&user.keypair]).await;
+
+    let score = instructions(
+        &program,
+        ontora_ai::accounts::UpdatePerformanceScore {
+            platform_config: platform.platform_config,
+            ai_agent: agent,
+            oracle: admin.pubkey,
+        },
+        ontora_ai::instruction::UpdatePerformanceScore { score: 90 },
+    );
+    measurements.measure(&mut ctx, "update_performance_score", score, &[&admin.keypair]).await;
+
+    let stake = instructions(
+        &program,
+        ontora_ai::accounts::StakeOnAgent {
+            platform_config: platform.platform_config,
+            ai_agent: agent,
+            user_stake,
+            stake_index,
+            user: user.pubkey,
+            user_token_account: token_account,
+            platform_vault: platform.platform_vault,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        },
+        ontora_ai::instruction::StakeOnAgent {
+            agent_id: TEST_AI_AGENT_ID,
+            amount: TEST_STAKE_AMOUNT,
+            beneficiary: user.pubkey,
+        },
+    );
+    measurements.measure(&mut ctx, "stake_on_agent", stake, &[&user.keypair]).await;
+
+    let unstake = instructions(
+        &program,
+        ontora_ai::accounts::UnstakeFromAgent {
+            platform_config: platform.platform_config,
+            ai_agent: agent,
+            user_stake,
+            stake_index,
+            user: user.pubkey,
+            user_token_account: token_account,
+            platform_vault: platform.platform_vault,
+            stake_mint: platform.mint,
+            fee_schedule: platform.fee_schedule,
+            treasury: platform.treasury,
+            treasury_vault,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        },
+        ontora_ai::instruction::UnstakeFromAgent { agent_id: TEST_AI_AGENT_ID, amount: TEST_STAKE_AMOUNT / 2 },
+    );
+    measurements.measure(&mut ctx, "unstake_from_agent", unstake, &[&user.keypair]).await;
+
+    // Legacy agent rewards
+    let legacy_agent = legacy_ai_agent_pda(&program_id, &user.pubkey);
+    let staking_vault = create_token_account(&mut ctx, &legacy_agent, &platform.mint).await;
+    let register_legacy = instructions(
+        &program,
+        ontora_ai::accounts::RegisterAIAgent {
+            platform_config: platform.platform_config,
+            ai_agent: legacy_agent,
+            user: user.pubkey,
+            user_token_account: token_account,
+            staking_vault,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        },
+        ontora_ai::instruction::RegisterAiAgent { stake_amount: TEST_STAKE_AMOUNT },
+    );
+    measurements.measure(&mut ctx, "register_ai_agent", register_legacy, &[&user.keypair]).await;
+
+    let increase_stake = instructions(
+        &program,
+        ontora_ai::accounts::IncreaseStake {
+            platform_config: platform.platform_config,
+            ai_agent: legacy_agent,
+            user: user.pubkey,
+            user_token_account: token_account,
+            staking_vault,
+            token_program: spl_token::ID,
+        },
+        ontora_ai::instruction::IncreaseStake { amount: TEST_STAKE_AMOUNT / 4 },
+    );
+    measurements.measure(&mut ctx, "increase_stake", increase_stake, &[&user.keypair]).await;
+
+    let fund = instructions(
+        &program,
+        ontora_ai::accounts::FundRewardPool {
+            platform_config: platform.platform_config,
+            reward_pool: platform.reward_pool,
+            reward_vault: platform.reward_vault,
+            reward_mint: platform.mint,
+            funder: user.pubkey,
+            funder_token_account: token_account,
+            token_program: spl_token::ID,
+        },
+        ontora_ai::instruction::FundRewardPool { amount: TEST_STAKE_AMOUNT / 8 },
+    );
+    measurements.measure(&mut ctx, "fund_reward_pool", fund, &[&user.keypair]).await;
+
+    warp_seconds(&mut ctx, LEGACY_EPOCH_DURATION).await;
+    let distribute = instructions(
+        &program,
+        ontora_ai::accounts::DistributeRewards {
+            platform_config: platform.platform_config,
+            reward_pool: platform.reward_pool,
+            ai_agent: legacy_agent,
+            crank: admin.pubkey,
+        },
+        ontora_ai::instruction::DistributeRewards {},
+    );
+    measurements.measure(&mut ctx, "distribute_rewards", distribute, &[&admin.keypair]).await;
+
+    let claim = instructions(
+        &program,
+        ontora_ai::accounts::ClaimRewards {
+            platform_config: platform.platform_config,
+            ai_agent: legacy_agent,
+            reward_pool: platform.reward_pool,
+            user: user.pubkey,
+            user_token_account: token_account,
+            reward_vault: platform.reward_vault,
+            reward_mint: platform.mint,
+            fee_schedule: platform.fee_schedule,
+            treasury: platform.treasury,
+            treasury_vault,
+            reward_vesting: reward_vesting_pda(&program_id, &user.pubkey),
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        },
+        ontora_ai::instruction::ClaimRewards {},
+    );
+    measurements.measure(&mut ctx, "claim_rewards", claim, &[&user.keypair]).await;
+
+    let unstake_partial = instructions(
+        &program,
+        ontora_ai::accounts::UnstakeAIAgent {
+            platform_config: platform.platform_config,
+            ai_agent: legacy_agent,
+            pending_withdrawal: pending_withdrawal_pda(&program_id, &user.pubkey),
+            user: user.pubkey,
+            user_token_account: token_account,
+            staking_vault,
+            stake_mint: platform.mint,
+            fee_schedule: platform.fee_schedule,
+            treasury: platform.treasury,
+            treasury_vault,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        },
+        ontora_ai::instruction::UnstakePartial { amount: TEST_STAKE_AMOUNT / 2 },
+    );
+    measurements.measure(&mut ctx, "unstake_partial", unstake_partial, &[&user.keypair]).await;
+
+    let migrate = instructions(
+        &program,
+        ontora_ai::accounts::MigrateAgentV1 {
+            platform_config: platform.platform_config,
+            legacy_agent,
+            ai_agent: ai_agent_pda(&program_id, &user.pubkey, ontora_ai::MIGRATED_AGENT_ID),
+            user_stake,
+            stake_index: stake_index_pda(&program_id, &user.pubkey, ontora_ai::MIGRATED_AGENT_ID),
+            staking_vault,
+            platform_vault: platform.platform_vault,
+            stake_mint: platform.mint,
+            admin: admin.pubkey,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        },
+        ontora_ai::instruction::MigrateAgentV1 {},
+    );
+    measurements.measure(&mut ctx, "migrate_agent_v1", migrate, &[&admin.keypair]).await;
+
+    // A second legacy staker queues half their stake, leaves with the rest before
+    // the cooldown ends, and claims the half once it has
+    let leaver = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
+    let leaver_token_account =
+        create_funded_token_account(&mut ctx, &platform, &leaver.pubkey, TEST_STAKE_AMOUNT).await;
+    let leaver_agent = legacy_ai_agent_pda(&program_id, &leaver.pubkey);
+    let leaver_vault = create_token_account(&mut ctx, &leaver_agent, &platform.mint).await;
+    let register_leaver = instructions(
+        &program,
+        ontora_ai::accounts::RegisterAIAgent {
+            platform_config: platform.platform_config,
+            ai_agent: leaver_agent,
+            user: leaver.pubkey,
+            user_token_account: leaver_token_account,
+            staking_vault: leaver_vault,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        },
+        ontora_ai::instruction::RegisterAiAgent { stake_amount: TEST_STAKE_AMOUNT },
+    );
+    send_transaction(&mut ctx, &register_leaver, &[&leaver.keypair]).await.unwrap();
+    let leaver_withdrawal = pending_withdrawal_pda(&program_id, &leaver.pubkey);
+    let request_unstake = instructions(
+        &program,
+        ontora_ai::accounts::RequestUnstake {
+            platform_config: platform.platform_config,
+            ai_agent: leaver_agent,
+            pending_withdrawal: leaver_withdrawal,
+            user: leaver.pubkey,
+            system_program: system_program::ID,
+        },
+        ontora_ai::instruction::RequestUnstake { amount: TEST_STAKE_AMOUNT / 2 },
+    );
+    measurements.measure(&mut ctx, "request_unstake", request_unstake, &[&leaver.keypair]).await;
+    let emergency = instructions(
+        &program,
+        ontora_ai::accounts::EmergencyUnstake {
+            platform_config: platform.platform_config,
+            ai_agent: leaver_agent,
+            user: leaver.pubkey,
+            user_token_account: leaver_token_account,
+            staking_vault: leaver_vault,
+            stake_mint: platform.mint,
+            reward_pool: platform.reward_pool,
+            fee_schedule: platform.fee_schedule,
+            reward_vault: platform.reward_vault,
+            token_program: spl_token::ID,
+        },
+        ontora_ai::instruction::EmergencyUnstake {},
+    );
+    measurements.measure(&mut ctx, "emergency_unstake", emergency, &[&leaver.keypair]).await;
+
+    warp_seconds(&mut ctx, COOLDOWN_SECONDS).await;
+    let claim_unstake = instructions(
+        &program,
+        ontora_ai::accounts::ClaimUnstake {
+            platform_config: platform.platform_config,
+            ai_agent: leaver_agent,
+            pending_withdrawal: leaver_withdrawal,
+            user: leaver.pubkey,
+            user_token_account: leaver_token_account,
+            staking_vault: leaver_vault,
+            stake_mint: platform.mint,
+            fee_schedule: platform.fee_schedule,
+            treasury: platform.treasury,
+            treasury_vault,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        },
+        ontora_ai::instruction::ClaimUnstake {},
+    );
+    measurements.measure(&mut ctx, "claim_unstake", claim_unstake, &[&leaver.keypair]).await;
+    // The emergency unstake and the claim left the agent empty
+    let close_agent = instructions(
+        &program,
+        ontora_ai::accounts::CloseAgent {
+            platform_config: platform.platform_config,
+            ai_agent: leaver_agent,
+            pending_withdrawal: leaver_withdrawal,
+            owner: leaver.pubkey,
+        },
+        ontora_ai::instruction::CloseAgent {},
+    );
+    measurements.measure(&mut ctx, "close_agent", close_agent, &[&leaver.keypair]).await;
+
+    // Governance
+    let proposal = proposal_pda(&program_id, 0);
+    let create_proposal = instructions(
+        &program,
+        ontora_ai::accounts::CreateGovernanceProposal {
+            creator: user.pubkey,
+            platform_config: platform.platform_config,
+            proposal,
+            system_program: system_program::ID,
+        },
+        ontora_ai::instruction::CreateGovernanceProposal {
+            title: "Raise rewards".to_string(),
+            description: "Raise the reward rate to 2%".to_string(),
+            voting_duration: VOTING_DURATION,
+            options: vec!["Yes".to_string(), "No".to_string(), "Abstain".to_string()],
+            action: ProposalAction::UpdateRewardRate { bps: 200 },
+        },
+    );
+    measurements.measure(&mut ctx, "create_governance_proposal", create_proposal, &[&user.keypair]).await;
+
+    let vote = instructions(
+        &program,
+        ontora_ai::accounts::CastVote {
+            voter: user.pubkey,
+            platform_config: platform.platform_config,
+            proposal,
+            user_stake,
+            vote_record: vote_record_pda(&program_id, 0, &user.pubkey),
+            system_program: system_program::ID,
+        },
+        ontora_ai::instruction::CastVote { proposal_id: 0, vote_option: 0 },
+    );
+    measurements.measure(&mut ctx, "cast_vote", vote, &[&user.keypair]).await;
+
+    warp_seconds(&mut ctx, VOTING_DURATION as i64 + 1).await;
+    let finalize = instructions(
+        &program,
+        ontora_ai::accounts::FinalizeProposal {
+            caller: user.pubkey,
+            platform_config: platform.platform_config,
+            proposal,
+            system_program: system_program::ID,
+        },
+        ontora_ai::instruction::FinalizeProposal { proposal_id: 0 },
+    );
+    measurements.measure(&mut ctx, "finalize_proposal", finalize, &[&user.keypair]).await;
+    // The user's stake is all of the snapshot, so the proposal passed
+    let execute = instructions(
+        &program,
+        ontora_ai::accounts::ExecuteProposal {
+            executor: user.pubkey,
+            platform_config: platform.platform_config,
+            proposal,
+        },
+        ontora_ai::instruction::ExecuteProposal {},
+    );
+    measurements.measure(&mut ctx, "execute_proposal", execute, &[&user.keypair]).await;
+
+    // Metadata
+    let metadata = Pubkey::find_program_address(
+        &[pda::METADATA_SEED, user.pubkey.as_ref(), &TEST_AI_AGENT_ID.to_le_bytes()],
+        &program_id,
+    )
+    .0;
+    let create_metadata = instructions(
+        &program,
+        ontora_ai::accounts::CreateMetadata { metadata, authority: user.pubkey, system_program: system_program::ID },
+        ontora_ai::instruction::CreateMetadata { entity_id: TEST_AI_AGENT_ID, data: "x".repeat(256) },
+    );
+    measurements.measure(&mut ctx, "create_metadata", create_metadata, &[&user.keypair]).await;
+
+    let update_metadata = instructions(
+        &program,
+        ontora_ai::accounts::UpdateMetadata { metadata, authority: user.pubkey, system_program: system_program::ID },
+        ontora_ai::instruction::UpdateMetadata { entity_id: TEST_AI_AGENT_ID, data: "y".repeat(512) },
+    );
+    measurements.measure(&mut ctx, "update_metadata", update_metadata, &[&user.keypair]).await;
+
+    // Administration
+    let update_config = instructions(
+        &program,
+        ontora_ai::accounts::UpdatePlatformConfig { platform_config: platform.platform_config, admin: admin.pubkey },
+        ontora_ai::instruction::UpdatePlatformConfig {
+            params: ConfigParams {
+                reward_rate_bps: 200,
+                min_stake_amount: TEST_STAKE_AMOUNT,
+                epoch_duration: 86_400,
+                cooldown_seconds: 86_400,
+                epoch_emission: TEST_EPOCH_EMISSION,
+                max_stake_per_agent: 0,
+                vesting_seconds: 0,
+                vote_unit: TEST_VOTE_UNIT,
+                quorum_bps: TEST_QUORUM_BPS,
+                approval_threshold_bps: TEST_APPROVAL_THRESHOLD_BPS,
+            },
+            update_metadata: "Compute-unit benchmark".to_string(),
+        },
+    );
+    measurements.measure(&mut ctx, "update_platform_config", update_config, &[&admin.keypair]).await;
+
+    let propose_admin = instructions(
+        &program,
+        ontora_ai::accounts::ProposeAdmin { platform_config: platform.platform_config, admin: admin.pubkey },
+        ontora_ai::instruction::ProposeAdmin { new_admin: user.pubkey },
+    );
+    measurements.measure(&mut ctx, "propose_admin", propose_admin, &[&admin.keypair]).await;
+
+    let cancel_transfer = instructions(
+        &program,
+        ontora_ai::accounts::CancelAdminTransfer { platform_config: platform.platform_config, admin: admin.pubkey },
+        ontora_ai::instruction::CancelAdminTransfer {},
+    );
+    measurements.measure(&mut ctx, "cancel_admin_transfer", cancel_transfer, &[&admin.keypair]).await;
+
+    let set_role = instructions(
+        &program,
+        ontora_ai::accounts::SetRole { platform_config: platform.platform_config, admin: admin.pubkey },
+        ontora_ai::instruction::SetRole { role: Role::Pauser, holder: admin.pubkey },
+    );
+    measurements.measure(&mut ctx, "set_role", set_role, &[&admin.keypair]).await;
+
+    let pause = instructions(
+        &program,
+        ontora_ai::accounts::SetPauseFlags { platform_config: platform.platform_config, authority: admin.pubkey },
+        ontora_ai::instruction::SetPauseFlags { flags: 1 },
+    );
+    measurements.measure(&mut ctx, "set_pause_flags", pause, &[&admin.keypair]).await;
+
+    let unpause = instructions(
+        &program,
+        ontora_ai::accounts::ClearPauseFlags { platform_config: platform.platform_config, admin: admin.pubkey },
+        ontora_ai::instruction::ClearPauseFlags { flags: 1 },
+    );
+    measurements.measure(&mut ctx, "clear_pause_flags", unpause, &[&admin.keypair]).await;
+
+    // Views
+    let version = instructions(&program, ontora_ai::accounts::GetVersion {}, ontora_ai::instruction::GetVersion {});
+    measurements.measure(&mut ctx, "get_version", version, &[&user.keypair]).await;
+
+    let balance = instructions(
+        &program,
+        ontora_ai::accounts::TreasuryBalance { treasury: platform.treasury, treasury_vault },
+        ontora_ai::instruction::TreasuryBalance {},
+    );
+    measurements.measure(&mut ctx, "treasury_balance", balance, &[&user.keypair]).await;
+
+    let budgets = load_budgets();
+    println!("{}", measurements.report(&budgets));
+    let violations = measurements.violations(&budgets);
+    assert!(violations.is_empty(), "compute-unit budgets exceeded:\n{}", violations.join("\n"));
+}
+
+// Test the tolerance rule and the table bookkeeping without running the program
+#[test]
+fn test_budget_violations() {
+    let budgets = BTreeMap::from([("a".to_string(), 1_000), ("b".to_string(), 1_000)]);
+    let check = |measured: Vec<(&'static str, u64)>| Measurements(measured).violations(&budgets);
+
+    assert!(check(vec![("a", 1_000), ("b", 1_100)]).is_empty());
+    assert_eq!(check(vec![("a", 1_000), ("b", 1_101)]).len(), 1);
+    assert_eq!(check(vec![("a", 1_000)]), vec!["b is budgeted but was not measured".to_string()]);
+    assert_eq!(check(vec![("a", 1), ("b", 1), ("c", 1)]), vec!["c has no entry in compute_budgets.txt".to_string()]);
+}
@@ -0,0 +1,210 @@
+// test_errors.rs
+// This module pins the numeric error codes clients see: the enum-to-code mapping
+// itself, and one failing instruction from each program module.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::solana_program::system_program;
+use anchor_client::Program;
+use solana_program_test::*;
+
+// Import test setup utilities (assumes test_setup.rs is in the same directory)
+mod test_setup;
+use test_setup::*;
+
+use ontora_ai::error::OntoraError;
+use ontora_ai::pda;
+use ontora_ai::state::{FeeType, MAX_STAKE_FEE_BPS};
+
+// Assert that a request failed with exactly `expected`
+fn assert_error(result: std::result::Result<(), anchor_client::ClientError>, expected: OntoraError) {
+    let error = result.expect_err("instruction should have failed");
+    assert_eq!(custom_error_code(&error), Some(u32::from(expected)), "{:?}", error);
+}
+
+async fn setup_platform() -> (TestContext, Program, TestUser, Pubkey) {
+    let (mut ctx, program) = setup_test_context().await;
+    let admin = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
+    let platform_config = initialize_test_platform(&program, &admin).await;
+    (ctx, program, admin, platform_config)
+}
+
+// Test that codes clients already match on keep their values
+#[test]
+fn test_error_codes_are_stable() {
+    let expected = [
+        (OntoraError::InsufficientStake, 6000),
+        (OntoraError::CooldownNotCompleted, 6001),
+        (OntoraError::InvalidOwner, 6002),
+        (OntoraError::ProposalActive, 6003),
+        (OntoraError::InsufficientVotingPower, 6004),
+        (OntoraError::RewardPoolDepleted, 6005),
+        (OntoraError::UnauthorizedAdmin, 6102),
+        (OntoraError::UnauthorizedUser, 6103),
+        (OntoraError::MissingRole, 6109),
+        (OntoraError::AgentInactive, 6204),
+        (OntoraError::AgentNotEmpty, 6205),
+        (OntoraError::InvalidStakeAmount, 6300),
+        (OntoraError::NoRewardsAvailable, 6304),
+        (OntoraError::TooManyAgents, 6308),
+        (OntoraError::AlreadyDistributedThisEpoch, 6309),
+        (OntoraError::WithdrawalPending, 6310),
+        (OntoraError::AgentStakeCapExceeded, 6311),
+        (OntoraError::GovernanceActionNotAllowed, 6404),
+        (OntoraError::ProposalAlreadyFinalized, 6411),
+        (OntoraError::VotingPeriodNotEnded, 6412),
+        (OntoraError::ProposalNotApproved, 6413),
+        (OntoraError::ProposalAlreadyExecuted, 6414),
+        (OntoraError::InvalidAdminAction, 6415),
+        (OntoraError::MetadataTooLarge, 6502),
+        (OntoraError::InvalidMultisigConfig, 6503),
+        (OntoraError::InvalidPauseFlags, 6504),
+        (OntoraError::InvalidFeeRate, 6505),
+        (OntoraError::NameTooLong, 6506),
+        (OntoraError::DescriptionTooLong, 6507),
+        (OntoraError::DescriptionEmpty, 6508),
+        (OntoraError::TooManyOptions, 6509),
+        (OntoraError::InvalidDuration, 6510),
+        (OntoraError::InvalidMint, 6511),
+        (OntoraError::InvalidVault, 6512),
+        (OntoraError::ArithmeticError, 6600),
+    ];
+    for (error, code) in expected {
+        assert_eq!(u32::from(error), code);
+    }
+}
+
+// Test lib.rs: an empty proposal description
+#[tokio::test]
+async fn test_lib_error_code() {
+    let (_ctx, program, admin, platform_config) = setup_platform().await;
+    let proposal = Pubkey::find_program_address(&[pda::PROPOSAL_SEED, admin.pubkey.as_ref()], &program.id()).0;
+
+    let result = program
+        .request()
+        .accounts(ontora_ai::accounts::CreateProposal {
+            platform_config,
+            proposal,
+            user: admin.pubkey,
+            system_program: system_program::ID,
+        })
+        .args(ontora_ai::instruction::CreateProposal {
+            description: String::new(),
+            duration: 86_400,
+        })
+        .signer(&admin.keypair)
+        .send()
+        .await
+        .map(|_| ());
+    assert_error(result, OntoraError::DescriptionEmpty);
+}
+
+// Test instructions.rs: pause flags with no bits set
+#[tokio::test]
+async fn test_instructions_error_code() {
+    let (_ctx, program, admin, platform_config) = setup_platform().await;
+
+    let result = program
+        .request()
+        .accounts(ontora_ai::accounts::SetPauseFlags {
+            platform_config,
+            authority: admin.pubkey,
+        })
+        .args(ontora_ai::instruction::SetPauseFlags { flags: 0 })
+        .signer(&admin.keypair)
+        .send()
+        .await
+        .map(|_| ());
+    assert_error(result, OntoraError::InvalidPauseFlags);
+}
+
+// Test governance.rs: enabling governance that is already enabled
+#[tokio::test]
+async fn test_governance_error_code() {
+    let (_ctx, program, admin, platform_config) = setup_platform().await;
+
+    let result = program
+        .request()
+        .accounts(ontora_ai::accounts::SetGovernanceEnabled {
+            platform_config,
+            admin: admin.pubkey,
+        })
+        .args(ontora_ai::instruction::SetGovernanceEnabled { enabled: true })
+        .signer(&admin.keypair)
+        .send()
+        .await
+        .map(|_| ());
+    assert_error(result, OntoraError::GovernanceActionNotAllowed);
+}
+
+// Test fees.rs: a stake fee over its cap
+#[tokio::test]
+async fn test_fees_error_code() {
+    let (_ctx, program, admin, platform_config) = setup_platform().await;
+    let mut rates_bps = [0; FeeType::COUNT];
+    rates_bps[FeeType::Stake.index()] = MAX_STAKE_FEE_BPS + 1;
+
+    let result = program
+        .request()
+        .accounts(ontora_ai::accounts::InitializeFeeSchedule {
+            platform_config,
+            fee_schedule: Pubkey::find_program_address(&[pda::FEE_SCHEDULE_SEED], &program.id()).0,
+            admin: admin.pubkey,
+            system_program: system_program::ID,
+        })
+        .args(ontora_ai::instruction::InitializeFeeSchedule {
+            governance: admin.pubkey,
+            rates_bps,
+        })
+        .signer(&admin.keypair)
+        .send()
+        .await
+        .map(|_| ());
+    assert_error(result, OntoraError::InvalidFeeRate);
+}
+
+// Test treasury.rs: creating the treasury without the treasury role
+#[tokio::test]
+async fn test_treasury_error_code() {
+    let (mut ctx, program, _admin, platform_config) = setup_platform().await;
+    let outsider = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
+
+    let result = program
+        .request()
+        .accounts(ontora_ai::accounts::InitializeTreasury {
+            platform_config,
+            treasury: Pubkey::find_program_address(&[pda::TREASURY_SEED], &program.id()).0,
+            authority: outsider.pubkey,
+            system_program: system_program::ID,
+        })
+        .args(ontora_ai::instruction::InitializeTreasury { governance: outsider.pubkey })
+        .signer(&outsider.keypair)
+        .send()
+        .await
+        .map(|_| ());
+    assert_error(result, OntoraError::MissingRole);
+}
+
+// Test multisig.rs: a zero approval threshold
+#[tokio::test]
+async fn test_multisig_error_code() {
+    let (_ctx, program, admin, platform_config) = setup_platform().await;
+
+    let result = program
+        .request()
+        .accounts(ontora_ai::accounts::InitializeAdminMultisig {
+            platform_config,
+            admin_multisig: Pubkey::find_program_address(&[pda::ADMIN_MULTISIG_SEED], &program.id()).0,
+            admin: admin.pubkey,
+            system_program: system_program::ID,
+        })
+        .args(ontora_ai::instruction::InitializeAdminMultisig {
+            signers: vec![admin.pubkey],
+            threshold: 0,
+        })
+        .signer(&admin.keypair)
+        .send()
+        .await
+        .map(|_| ());
+    assert_error(result, OntoraError::InvalidMultisigConfig);
+}
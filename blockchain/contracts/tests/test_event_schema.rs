@@ -0,0 +1,163 @@
+// test_event_schema.rs
+// This module enforces the append-only event policy. Every event's Borsh
+// layout (field names, types, order) is read from src/events.rs and compared
+// with the snapshot in tests/golden/event_layouts.txt: existing fields may not
+// move or change type, and appending fields requires a SCHEMA_VERSION bump.
+// After an allowed change, regenerate the snapshot with
+//     UPDATE_EVENT_LAYOUTS=1 cargo test --test test_event_schema
+
+use std::collections::BTreeMap;
+
+const EVENTS_SOURCE: &str = include_str!("../src/events.rs");
+const GOLDEN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden/event_layouts.txt");
+
+#[derive(Debug, PartialEq)]
+struct EventLayout {
+    version: u8,
+    // (name, type) in declaration order
+    fields: Vec<(String, String)>,
+}
+
+// Layouts and schema versions of the #[event] structs in events.rs
+fn current_layouts() -> BTreeMap<String, EventLayout> {
+    let versions_start = EVENTS_SOURCE.find("schema_versions! {").expect("schema_versions! block");
+    let versions_end = versions_start + EVENTS_SOURCE[versions_start..].find('}').unwrap();
+    let versions: BTreeMap<&str, u8> = EVENTS_SOURCE[versions_start..versions_end]
+        .lines()
+        .filter_map(|line| line.trim().trim_end_matches(',').split_once(" = "))
+        .map(|(event, version)| (event, version.parse().unwrap()))
+        .collect();
+
+    let mut layouts = BTreeMap::new();
+    let mut lines = EVENTS_SOURCE.lines();
+    while let Some(line) = lines.next() {
+        if line.trim() != "#[event]" {
+            continue;
+        }
+        let name = lines
+            .next()
+            .and_then(|line| line.strip_prefix("pub struct "))
+            .and_then(|line| line.strip_suffix(" {"))
+            .expect("#[event] must be followed by `pub struct Name {`")
+            .to_string();
+        let fields = lines
+            .by_ref()
+            .take_while(|line| line.trim() != "}")
+            .filter_map(|line| line.trim().strip_prefix("pub "))
+            .map(|field| {
+                let (name, ty) = field.trim_end_matches(',').split_once(": ").unwrap();
+                (name.to_string(), ty.to_string())
+            })
+            .collect();
+        let version = *versions
+            .get(name.as_str())
+            .unwrap_or_else(|| panic!("{} has no entry in schema_versions!", name));
+        layouts.insert(name, EventLayout { version, fields });
+    }
+    layouts
+}
+
+fn parse_golden(text: &str) -> BTreeMap<String, EventLayout> {
+    let mut layouts = BTreeMap::new();
+    let mut current: Option<String> = None;
+    for line in text.lines().filter(|line| !line.trim().is_empty() && !line.starts_with('#')) {
+        if let Some(field) = line.strip_prefix("    ") {
+            let (name, ty) = field.split_once(": ").unwrap();
+            let event: &mut EventLayout = layouts.get_mut(current.as_ref().unwrap()).unwrap();
+            event.fields.push((name.to_string(), ty.to_string()));
+        } else {
+            let (name, version) = line.split_once(" v").unwrap();
+            layouts.insert(name.to_string(), EventLayout { version: version.parse().unwrap(), fields: Vec::new() });
+            current = Some(name.to_string());
+        }
+    }
+    layouts
+}
+
+fn render_golden(layouts: &BTreeMap<String, EventLayout>) -> String {
+    let mut text = String::from(
+        "# Borsh layout of every event, in field order. Append-only: see tests/test_event_schema.rs.\n",
+    );
+    for (name, layout) in layouts {
+        text.push_str(&format!("\n{} v{}\n", name, layout.version));
+        for (field, ty) in &layout.fields {
+            text.push_str(&format!("    {}: {}\n", field, ty));
+        }
+    }
+    text
+}
+
+// Reasons `current` is not an append-only evolution of `golden`
+fn compatibility_errors(golden: &BTreeMap<String, EventLayout>, current: &BTreeMap<String, EventLayout>) -> Vec<String> {
+    let mut errors = Vec::new();
+    for (name, before) in golden {
+        let Some(after) = current.get(name) else {
+            errors.push(format!("{} was removed", name));
+            continue;
+        };
+        if !after.fields.starts_with(&before.fields) {
+            errors.push(format!("{}: existing fields moved, changed type, or were removed", name));
+        } else if after.fields.len() > before.fields.len() && after.version <= before.version {
+            errors.push(format!("{}: fields were appended without bumping SCHEMA_VERSION past {}", name, before.version));
+        }
+        if after.version < before.version {
+            errors.push(format!("{}: SCHEMA_VERSION went backwards", name));
+        }
+    }
+    errors
+}
+
+// Test that every event leads with its schema version
+#[test]
+fn test_events_start_with_schema_version() {
+    for (name, layout) in current_layouts() {
+        assert_eq!(
+            layout.fields.first(),
+            Some(&("schema_version".to_string(), "u8".to_string())),
+            "{} must start with `schema_version: u8`",
+            name
+        );
+    }
+}
+
+// Test that event layouts only ever grow at the end, and match the snapshot
+#[test]
+fn test_event_layouts_are_append_only() {
+    let golden_text = std::fs::read_to_string(GOLDEN_PATH).unwrap_or_default();
+    let golden = parse_golden(&golden_text);
+    let current = current_layouts();
+
+    let errors = compatibility_errors(&golden, &current);
+    assert!(errors.is_empty(), "breaking event layout changes:\n{}", errors.join("\n"));
+
+    if std::env::var_os("UPDATE_EVENT_LAYOUTS").is_some() {
+        std::fs::write(GOLDEN_PATH, render_golden(&current)).unwrap();
+        return;
+    }
+    assert!(
+        golden == current,
+        "event layouts changed compatibly; regenerate the snapshot with UPDATE_EVENT_LAYOUTS=1"
+    );
+}
+
+// Test that the checker itself catches reordering, retyping, and unversioned appends
+#[test]
+fn test_compatibility_rules() {
+    let layout = |version: u8, fields: &[(&str, &str)]| EventLayout {
+        version,
+        fields: fields.iter().map(|(name, ty)| (name.to_string(), ty.to_string())).collect(),
+    };
+    let golden = BTreeMap::from([("Event".to_string(), layout(1, &[("schema_version", "u8"), ("a", "u64"), ("b", "Pubkey")]))]);
+    let check = |event: EventLayout| compatibility_errors(&golden, &BTreeMap::from([("Event".to_string(), event)]));
+
+    assert!(check(layout(1, &[("schema_version", "u8"), ("a", "u64"), ("b", "Pubkey")])).is_empty());
+    assert!(check(layout(2, &[("schema_version", "u8"), ("a", "u64"), ("b", "Pubkey"), ("c", "i64")])).is_empty());
+    // Appended without a version bump
+    assert_eq!(check(layout(1, &[("schema_version", "u8"), ("a", "u64"), ("b", "Pubkey"), ("c", "i64")])).len(), 1);
+    // Reordered
+    assert_eq!(check(layout(2, &[("schema_version", "u8"), ("b", "Pubkey"), ("a", "u64")])).len(), 1);
+    // Retyped
+    assert_eq!(check(layout(2, &[("schema_version", "u8"), ("a", "u32"), ("b", "Pubkey")])).len(), 1);
+    // Removed
+    assert_eq!(compatibility_errors(&golden, &BTreeMap::new()).len(), 1);
+}
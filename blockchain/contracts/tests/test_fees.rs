@@ -0,0 +1,137 @@
+// test_fees.rs
+// This module contains test cases for the fee schedule: per-type caps,
+// epoch-delayed rate changes, and the rounding of `apply_fee`.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::solana_program::system_program;
+use anchor_client::Program;
+use solana_program_test::*;
+
+// Import test setup utilities (assumes test_setup.rs is in the same directory)
+mod test_setup;
+use test_setup::*;
+
+use ontora_ai::state::{
+    FeeSchedule, FeeType, MAX_CLAIM_FEE_BPS, MAX_EMERGENCY_PENALTY_BPS, MAX_STAKE_FEE_BPS, MAX_UNSTAKE_FEE_BPS,
+};
+
+const MAX_RATES: [u16; FeeType::COUNT] = [
+    MAX_STAKE_FEE_BPS,
+    MAX_UNSTAKE_FEE_BPS,
+    MAX_CLAIM_FEE_BPS,
+    MAX_EMERGENCY_PENALTY_BPS,
+];
+
+async fn update_rates(program: &Program, platform_config: Pubkey, authority: &TestUser, rates_bps: [u16; FeeType::COUNT]) -> std::result::Result<(), anchor_client::ClientError> {
+    program
+        .request()
+        .accounts(ontora_ai::accounts::UpdateFeeSchedule {
+            platform_config,
+            fee_schedule: fee_schedule_pda(&program.id()),
+            authority: authority.pubkey,
+        })
+        .args(ontora_ai::instruction::UpdateFeeSchedule { rates_bps })
+        .signer(&authority.keypair)
+        .send()
+        .await
+        .map(|_| ())
+}
+
+// Test that each fee type is held to its own cap
+#[test]
+fn test_rates_capped_per_fee_type() {
+    assert!(FeeSchedule::validate_rates(&MAX_RATES));
+    assert!(FeeSchedule::validate_rates(&[0; FeeType::COUNT]));
+
+    for fee_type in FeeType::ALL {
+        let mut rates = MAX_RATES;
+        rates[fee_type.index()] += 1;
+        assert!(!FeeSchedule::validate_rates(&rates), "{:?} cap not enforced", fee_type);
+    }
+
+    // The emergency penalty cap does not leak into the other types
+    let mut rates = [0; FeeType::COUNT];
+    rates[FeeType::Stake.index()] = MAX_EMERGENCY_PENALTY_BPS;
+    assert!(!FeeSchedule::validate_rates(&rates));
+}
+
+// Test that staged rates only apply from the next epoch boundary
+#[test]
+fn test_rate_change_waits_for_epoch_boundary() {
+    let epoch = 86_400;
+    let now = 10 * epoch + 500;
+    let boundary = FeeSchedule::next_epoch_boundary(now, epoch);
+    assert_eq!(boundary, 11 * epoch);
+    assert_eq!(FeeSchedule::next_epoch_boundary(11 * epoch, epoch), 12 * epoch);
+
+    let mut schedule = FeeSchedule::default();
+    schedule.init(Pubkey::new_unique(), [0, 50, 100, 1000], 255);
+    schedule.schedule([0, 75, 100, 1000], boundary);
+
+    assert!(!schedule.settle(boundary - 1));
+    assert_eq!(schedule.fee_bps(FeeType::Unstake), 50);
+
+    assert!(schedule.settle(boundary));
+    assert_eq!(schedule.fee_bps(FeeType::Unstake), 75);
+    assert!(!schedule.has_pending());
+    assert!(!schedule.settle(boundary + epoch));
+}
+
+// Test that apply_fee rounds the fee down and conserves the amount
+#[test]
+fn test_apply_fee_rounding() {
+    let mut schedule = FeeSchedule::default();
+    schedule.init(Pubkey::new_unique(), MAX_RATES, 255);
+
+    // 0.5% of 199 is 0.995: the user keeps everything
+    schedule.rates_bps[FeeType::Unstake.index()] = 50;
+    assert_eq!(schedule.apply_fee(199, FeeType::Unstake).unwrap(), (199, 0));
+    assert_eq!(schedule.apply_fee(200, FeeType::Unstake).unwrap(), (199, 1));
+    assert_eq!(schedule.apply_fee(1_000_000, FeeType::Unstake).unwrap(), (995_000, 5_000));
+
+    // No overflow at the top of the range
+    let (net, fee) = schedule.apply_fee(u64::MAX, FeeType::Claim).unwrap();
+    assert_eq!(fee, u64::MAX / 10);
+    assert_eq!(net + fee, u64::MAX);
+
+    assert_eq!(schedule.apply_fee(0, FeeType::EmergencyUnstake).unwrap(), (0, 0));
+}
+
+// Test that updates are gated, capped, and staged rather than applied immediately
+#[tokio::test]
+async fn test_update_fee_schedule() {
+    let (mut ctx, program) = setup_test_context().await;
+    let admin = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
+    let governance = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
+    let outsider = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
+    let platform_config = initialize_test_platform(&program, &admin).await;
+
+    program
+        .request()
+        .accounts(ontora_ai::accounts::InitializeFeeSchedule {
+            platform_config,
+            fee_schedule: fee_schedule_pda(&program.id()),
+            admin: admin.pubkey,
+            system_program: system_program::ID,
+        })
+        .args(ontora_ai::instruction::InitializeFeeSchedule {
+            governance: governance.pubkey,
+            rates_bps: [0, 50, 100, 1000],
+        })
+        .signer(&admin.keypair)
+        .send()
+        .await
+        .unwrap();
+
+    assert!(update_rates(&program, platform_config, &outsider, [0, 60, 100, 1000]).await.is_err());
+    assert!(update_rates(&program, platform_config, &admin, [0, MAX_UNSTAKE_FEE_BPS + 1, 100, 1000]).await.is_err());
+    assert!(update_rates(&program, platform_config, &governance, [0, 60, 100, 1000]).await.is_ok());
+
+    let account = ctx.banks_client.get_account(fee_schedule_pda(&program.id())).await.unwrap().unwrap();
+    let schedule = FeeSchedule::try_deserialize(&mut account.data.as_ref()).unwrap();
+    let now = ctx.banks_client.get_sysvar::<Clock>().await.unwrap().unix_timestamp;
+    assert_eq!(schedule.fee_bps(FeeType::Unstake), 50);
+    assert_eq!(schedule.pending_rates_bps[FeeType::Unstake.index()], 60);
+    assert_eq!(schedule.pending_effective_at, FeeSchedule::next_epoch_boundary(now, 86_400));
+}
@@ -0,0 +1,397 @@
+// test_fuzz_entry.rs
+// This module is a deterministic structured fuzzer for the program entrypoint.
+// It feeds mutated instruction data and arbitrary account lists straight into
+// `ontora_ai::entry`, laid out in memory exactly as the BPF loader serializes
+// them, and checks that the processor returns a ProgramError rather than
+// panicking, and that no account grows past its realloc allowance or has its
+// header overwritten.
+//
+// Inputs come from valid encodings of every instruction plus the regression
+// corpus in tests/fuzz_corpus/ (hex files, `#` lines are comments). A panicking
+// input is written back to the corpus as crash-<hash>.hex so it is replayed on
+// every later run. FUZZ_ITERATIONS and FUZZ_SEED override the defaults.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::entrypoint::{deserialize, MAX_PERMITTED_DATA_INCREASE, NON_DUP_MARKER};
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::solana_program::system_program;
+use anchor_lang::InstructionData;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+
+use ontora_ai::pda;
+use ontora_ai::state::{AdminAction, ConfigParams, FeeType, PlatformConfig, ProposalAction, Role};
+
+const DEFAULT_ITERATIONS: usize = 5_000;
+const DEFAULT_SEED: u64 = 0x0a7a_f022;
+// Account lists each corpus input is replayed against
+const LAYOUTS_PER_CORPUS_INPUT: u64 = 16;
+const MAX_ACCOUNTS: usize = 20;
+const CORPUS_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fuzz_corpus");
+
+// One account as the fuzzer describes it, before serialization
+struct FuzzAccount {
+    key: Pubkey,
+    owner: Pubkey,
+    lamports: u64,
+    data: Vec<u8>,
+    is_signer: bool,
+    is_writable: bool,
+    executable: bool,
+}
+
+// Where an account's header fields landed in the serialized input
+struct AccountOffsets {
+    key: usize,
+    lamports: usize,
+    data_len: usize,
+}
+
+// Valid encodings of every instruction, with representative arguments
+fn seed_inputs() -> Vec<Vec<u8>> {
+    use ontora_ai::instruction as ix;
+
+    let key = Pubkey::new_unique();
+    let text = |len: usize| "x".repeat(len);
+    vec![
+        ix::InitializeRewardPool { initial_rewards: 1_000 }.data(),
+        ix::FundRewardPool { amount: 1_000 }.data(),
+        ix::RegisterAiAgent { stake_amount: 1_000 }.data(),
+        ix::IncreaseStake { amount: 1_000 }.data(),
+        ix::UnstakeAiAgent {}.data(),
+        ix::UnstakePartial { amount: 1_000 }.data(),
+        ix::EmergencyUnstake {}.data(),
+        ix::RequestUnstake { amount: 1_000 }.data(),
+        ix::ClaimUnstake {}.data(),
+        ix::CloseAgent {}.data(),
+        ix::DistributeRewards {}.data(),
+        ix::DistributeRewardsBatch { close_epoch: false }.data(),
+        ix::ClaimRewards {}.data(),
+        ix::ReleaseVested {}.data(),
+        ix::CreateProposal { description: text(32), duration: 3_600 }.data(),
+        ix::VoteProposal { in_favor: true }.data(),
+        ix::InitializePlatform {
+            reward_rate_bps: 100,
+            min_stake_amount: 1_000,
+            epoch_duration: 86_400,
+            cooldown_seconds: 86_400,
+            epoch_emission: 100_000_000,
+        }
+        .data(),
+        ix::InitializeVaults {}.data(),
+        ix::UpdatePlatformConfig {
+            params: ConfigParams {
+                reward_rate_bps: 100,
+                min_stake_amount: 1_000,
+                epoch_duration: 86_400,
+                cooldown_seconds: 86_400,
+                epoch_emission: 100_000_000,
+                max_stake_per_agent: 0,
+                vesting_seconds: 0,
+                vote_unit: 1_000,
+                quorum_bps: 1_000,
+                approval_threshold_bps: 5_000,
+            },
+            update_metadata: text(16),
+        }
+        .data(),
+        ix::MigratePlatformConfig {}.data(),
+        ix::MigrateAccount {}.data(),
+        ix::MigrateAgentV1 {}.data(),
+        ix::GetVersion {}.data(),
+        ix::CreateGovernanceProposal {
+            title: text(16),
+            description: text(64),
+            voting_duration: 3_600,
+            options: vec![text(4), text(4)],
+            action: ProposalAction::UpdateEpochDuration { seconds: 3_600 },
+        }
+        .data(),
+        ix::CastVote { proposal_id: 1, vote_option: 0 }.data(),
+        ix::FinalizeProposal { proposal_id: 1 }.data(),
+        ix::ExecuteProposal {}.data(),
+        ix::SetGovernanceEnabled { enabled: false }.data(),
+        ix::ProposeAdmin { new_admin: key }.data(),
+        ix::AcceptAdmin {}.data(),
+        ix::CancelAdminTransfer {}.data(),
+        ix::InitializeTreasury { governance: key }.data(),
+        ix::InitializeFeeSchedule { governance: key, rates_bps: [10; FeeType::COUNT] }.data(),
+        ix::UpdateFeeSchedule { rates_bps: [10; FeeType::COUNT] }.data(),
+        ix::TreasuryBalance {}.data(),
+        ix::TreasuryTransfer { amount: 1_000 }.data(),
+        ix::SetPauseFlags { flags: 1 }.data(),
+        ix::ClearPauseFlags { flags: 1 }.data(),
+        ix::SetRole { role: Role::Oracle, holder: key }.data(),
+        ix::RenounceRole { role: Role::Crank }.data(),
+        ix::UpdatePerformanceScore { score: 90 }.data(),
+        ix::RegisterAgent { agent_id: 1, name: text(8), description: text(32) }.data(),
+        ix::UpdateAgentMetadata { agent_id: 1, name: text(8), description: text(32) }.data(),
+        ix::StakeOnAgent { agent_id: 1, amount: 1_000, beneficiary: key }.data(),
+        ix::UnstakeFromAgent { agent_id: 1, amount: 1_000 }.data(),
+        ix::ClaimStakeRewards {}.data(),
+        ix::CreateMetadata { entity_id: 1, data: text(64) }.data(),
+        ix::UpdateMetadata { entity_id: 1, data: text(128) }.data(),
+        ix::InitializeAdminMultisig { signers: vec![key, Pubkey::new_unique()], threshold: 2 }.data(),
+        ix::ProposeAdminAction { action: AdminAction::SetPauseFlags { pause_flags: 0 } }.data(),
+        ix::ApproveAdminAction {}.data(),
+        ix::ExecuteAdminAction {}.data(),
+        ix::ExecuteInitializeVaults {}.data(),
+    ]
+}
+
+// Parse a corpus file: hex bytes, ignoring whitespace and `#` comment lines
+fn parse_corpus_file(text: &str) -> Vec<u8> {
+    let hex: String = text
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .flat_map(|line| line.chars().filter(|c| !c.is_whitespace()))
+        .collect();
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).expect("corpus files hold hex bytes"))
+        .collect()
+}
+
+fn corpus_inputs() -> Vec<(PathBuf, Vec<u8>)> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(CORPUS_DIR)
+        .map(|entries| entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()).collect())
+        .unwrap_or_default();
+    files.retain(|path| path.extension().is_some_and(|ext| ext == "hex"));
+    files.sort();
+    files
+        .into_iter()
+        .map(|path| {
+            let data = parse_corpus_file(&std::fs::read_to_string(&path).unwrap());
+            (path, data)
+        })
+        .collect()
+}
+
+// Apply a few random byte-level mutations, biased toward the places Borsh decoding breaks
+fn mutate(rng: &mut StdRng, input: &[u8], seeds: &[Vec<u8>]) -> Vec<u8> {
+    let mut data = input.to_vec();
+    for _ in 0..rng.gen_range(1..=4) {
+        match rng.gen_range(0..7) {
+            // Flip a bit
+            0 if !data.is_empty() => {
+                let index = rng.gen_range(0..data.len());
+                data[index] ^= 1 << rng.gen_range(0..8);
+            }
+            // Overwrite a byte
+            1 if !data.is_empty() => {
+                let index = rng.gen_range(0..data.len());
+                data[index] = rng.gen();
+            }
+            // Truncate
+            2 => data.truncate(rng.gen_range(0..=data.len())),
+            // Append random bytes
+            3 => data.extend((0..rng.gen_range(1..64)).map(|_| rng.gen::<u8>())),
+            // Plant a huge or boundary length prefix after the discriminator
+            4 if data.len() >= 12 => {
+                let index = rng.gen_range(8..=data.len() - 4);
+                let len = [u32::MAX, u32::MAX / 2, 0x8000_0000, 1 << 20, rng.gen()][rng.gen_range(0..5)];
+                data[index..index + 4].copy_from_slice(&len.to_le_bytes());
+            }
+            // Keep the arguments but swap in another instruction's discriminator
+            5 if data.len() >= 8 => {
+                let other = &seeds[rng.gen_range(0..seeds.len())];
+                data[..8].copy_from_slice(&other[..8]);
+            }
+            // Replace everything with random bytes
+            _ => data = (0..rng.gen_range(0..128)).map(|_| rng.gen::<u8>()).collect(),
+        }
+    }
+    data
+}
+
+// A random account list: a mix of well-known keys, fresh keys, program-owned
+// accounts holding real or garbage layouts, and signer/writable flags at random
+fn random_accounts(rng: &mut StdRng) -> Vec<FuzzAccount> {
+    let mut platform_config = Vec::new();
+    let mut config = PlatformConfig::default();
+    config.init(Pubkey::new_unique(), 100, 1_000, 86_400, 86_400, 100_000_000, 1_000, 255);
+    config.try_serialize(&mut platform_config).unwrap();
+
+    let mut known_keys = vec![
+        pda::platform_config().0,
+        pda::fee_schedule().0,
+        pda::treasury().0,
+        pda::reward_pool().0,
+        system_program::ID,
+        spl_token::ID,
+        ontora_ai::ID,
+    ];
+    let owners = [ontora_ai::ID, system_program::ID, spl_token::ID, Pubkey::new_unique()];
+
+    (0..rng.gen_range(0..=MAX_ACCOUNTS))
+        .map(|_| {
+            // Each key appears once, as the runtime would otherwise mark a duplicate
+            let key = if !known_keys.is_empty() && rng.gen_bool(0.3) {
+                known_keys.swap_remove(rng.gen_range(0..known_keys.len()))
+            } else {
+                Pubkey::new_unique()
+            };
+            let data = match rng.gen_range(0..4) {
+                0 => Vec::new(),
+                1 => platform_config.clone(),
+                2 => {
+                    let mut data = platform_config.clone();
+                    data.truncate(rng.gen_range(0..=data.len()));
+                    data
+                }
+                _ => (0..rng.gen_range(0..512)).map(|_| rng.gen::<u8>()).collect(),
+            };
+            FuzzAccount {
+                executable: key == system_program::ID || key == spl_token::ID || key == ontora_ai::ID,
+                key,
+                owner: owners[rng.gen_range(0..owners.len())],
+                lamports: rng.gen_range(0..10_000_000_000),
+                data,
+                is_signer: rng.gen_bool(0.5),
+                is_writable: rng.gen_bool(0.5),
+            }
+        })
+        .collect()
+}
+
+// Serialize the program input the way the BPF loader does (aligned layout),
+// into an 8-byte aligned buffer. Returns the buffer and each account's offsets.
+fn serialize_input(accounts: &[FuzzAccount], data: &[u8]) -> (Vec<u64>, Vec<AccountOffsets>) {
+    let mut bytes = Vec::new();
+    let mut offsets = Vec::new();
+    bytes.extend_from_slice(&(accounts.len() as u64).to_le_bytes());
+    for account in accounts {
+        bytes.extend_from_slice(&[NON_DUP_MARKER, account.is_signer as u8, account.is_writable as u8, account.executable as u8]);
+        // Original data length slot, filled in by `deserialize`
+        bytes.extend_from_slice(&[0; 4]);
+        let key = bytes.len();
+        bytes.extend_from_slice(account.key.as_ref());
+        bytes.extend_from_slice(account.owner.as_ref());
+        let lamports = bytes.len();
+        bytes.extend_from_slice(&account.lamports.to_le_bytes());
+        let data_len = bytes.len();
+        bytes.extend_from_slice(&(account.data.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(&account.data);
+        bytes.resize(bytes.len() + MAX_PERMITTED_DATA_INCREASE, 0);
+        bytes.resize(bytes.len().next_multiple_of(8), 0);
+        bytes.extend_from_slice(&u64::MAX.to_le_bytes()); // rent epoch
+        offsets.push(AccountOffsets { key, lamports, data_len });
+    }
+    bytes.extend_from_slice(&(data.len() as u64).to_le_bytes());
+    bytes.extend_from_slice(data);
+    bytes.extend_from_slice(ontora_ai::ID.as_ref());
+
+    let mut buffer = vec![0u64; bytes.len().div_ceil(8)];
+    // SAFETY: the u64 buffer holds at least bytes.len() bytes
+    unsafe { std::ptr::copy_nonoverlapping(bytes.as_ptr(), buffer.as_mut_ptr() as *mut u8, bytes.len()) };
+    (buffer, offsets)
+}
+
+fn read_u64(buffer: &[u64], offset: usize) -> u64 {
+    // SAFETY: offsets come from serialize_input for this buffer
+    unsafe { std::ptr::read_unaligned((buffer.as_ptr() as *const u8).add(offset) as *const u64) }
+}
+
+// Run one input through the entrypoint; Err describes a violated invariant
+fn run_case(accounts: &[FuzzAccount], data: &[u8]) -> std::result::Result<(), String> {
+    let (mut buffer, offsets) = serialize_input(accounts, data);
+    let input = buffer.as_mut_ptr() as *mut u8;
+
+    let outcome = panic::catch_unwind(AssertUnwindSafe(|| {
+        // SAFETY: `input` is laid out exactly as the loader serializes it
+        let (program_id, account_infos, instruction_data) = unsafe { deserialize(input) };
+        ontora_ai::entry(program_id, &account_infos, instruction_data)
+    }));
+    if outcome.is_err() {
+        return Err("the processor panicked".to_string());
+    }
+
+    let lamports_before: u128 = accounts.iter().map(|account| account.lamports as u128).sum();
+    let mut lamports_after = 0u128;
+    for (account, offset) in accounts.iter().zip(&offsets) {
+        let key = &buffer_bytes(&buffer)[offset.key..offset.key + 32];
+        if key != account.key.as_ref() {
+            return Err(format!("account {} had its key overwritten", account.key));
+        }
+        let data_len = read_u64(&buffer, offset.data_len) as usize;
+        if data_len > account.data.len() + MAX_PERMITTED_DATA_INCREASE {
+            return Err(format!("account {} grew to {} bytes, past its realloc allowance", account.key, data_len));
+        }
+        lamports_after += read_u64(&buffer, offset.lamports) as u128;
+    }
+    if lamports_after != lamports_before {
+        return Err("lamports were created or destroyed".to_string());
+    }
+    Ok(())
+}
+
+fn buffer_bytes(buffer: &[u64]) -> &[u8] {
+    // SAFETY: any u64 buffer is valid as bytes
+    unsafe { std::slice::from_raw_parts(buffer.as_ptr() as *const u8, buffer.len() * 8) }
+}
+
+// Record a failing input in the corpus so later runs replay it
+fn persist_crash(data: &[u8], layout_seed: u64, reason: &str) -> PathBuf {
+    let hash = data.iter().fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| (hash ^ *byte as u64).wrapping_mul(0x100_0000_01b3));
+    let path = Path::new(CORPUS_DIR).join(format!("crash-{:016x}.hex", hash));
+    let hex: String = data.iter().map(|byte| format!("{:02x}", byte)).collect();
+    std::fs::write(&path, format!("# {} (account layout seed {})\n{}\n", reason, layout_seed, hex)).unwrap();
+    path
+}
+
+fn env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+    std::env::var(name).ok().and_then(|value| value.parse().ok()).unwrap_or(default)
+}
+
+// Test that the seed encodings decode as real instructions and the corpus is readable
+#[test]
+fn test_corpus_is_well_formed() {
+    let seeds = seed_inputs();
+    let mut discriminators: Vec<&[u8]> = seeds.iter().map(|seed| &seed[..8]).collect();
+    discriminators.sort();
+    discriminators.dedup();
+    assert_eq!(discriminators.len(), seeds.len(), "every instruction needs its own seed");
+
+    let corpus = corpus_inputs();
+    assert!(!corpus.is_empty(), "the regression corpus is missing from {}", CORPUS_DIR);
+}
+
+// Test that the regression corpus and every seed, replayed against fixed account lists, never break the processor
+#[test]
+fn test_replay_corpus() {
+    let mut inputs = corpus_inputs();
+    inputs.extend(seed_inputs().into_iter().map(|data| (PathBuf::from("<seed>"), data)));
+
+    for (path, data) in inputs {
+        for layout_seed in 0..LAYOUTS_PER_CORPUS_INPUT {
+            let accounts = random_accounts(&mut StdRng::seed_from_u64(layout_seed));
+            if let Err(reason) = run_case(&accounts, &data) {
+                panic!("{} with account layout seed {}: {}", path.display(), layout_seed, reason);
+            }
+        }
+    }
+}
+
+// Test that mutated instructions against random accounts only ever produce errors, never panics
+#[test]
+fn test_fuzz_entrypoint() {
+    let iterations = env_or("FUZZ_ITERATIONS", DEFAULT_ITERATIONS);
+    let seed = env_or("FUZZ_SEED", DEFAULT_SEED);
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let seeds = seed_inputs();
+    let mut pool: Vec<Vec<u8>> = seeds.clone();
+    pool.extend(corpus_inputs().into_iter().map(|(_, data)| data));
+
+    for iteration in 0..iterations {
+        let data = mutate(&mut rng, &pool[rng.gen_range(0..pool.len())], &seeds);
+        let layout_seed = rng.gen();
+        let accounts = random_accounts(&mut StdRng::seed_from_u64(layout_seed));
+        if let Err(reason) = run_case(&accounts, &data) {
+            let path = persist_crash(&data, layout_seed, &reason);
+            panic!("iteration {} (FUZZ_SEED={}): {}; input saved to {}", iteration, seed, reason, path.display());
+        }
+    }
+}
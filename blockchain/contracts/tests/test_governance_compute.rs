@@ -0,0 +1,270 @@
+// test_governance_compute.rs
+// This module contains the governance proposal lifecycle against the zero-copy
+// Proposal account, and a compute-unit benchmark for cast_vote. With Borsh the
+// whole proposal was decoded and re-encoded on every vote, so the cost grew with
+// the title, description, and ballot; zero-copy voting must not.
+// Every test starts from the same open proposals, restored from a snapshot once the
+// first run has built them; see snapshot/mod.rs. The snapshot's speedup is measured by
+// an ignored benchmark:
+//     cargo test --test test_governance_compute -- --ignored --nocapture
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::solana_program::system_program;
+use anchor_client::Program;
+use solana_program_test::*;
+use solana_sdk::account::{Account as SolanaAccount, AccountSharedData};
+use solana_sdk::hash::Hash;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::Transaction;
+use std::time::{Duration, Instant};
+
+// Import test setup utilities (assumes test_setup.rs is in the same directory)
+mod snapshot;
+mod test_setup;
+use snapshot::Snapshot;
+use test_setup::*;
+
+use ontora_ai::error::OntoraError;
+use ontora_ai::pda;
+use ontora_ai::state::{
+    Proposal, ProposalAction, UserStake, DEFAULT_VOTE_UNIT, MAX_PROPOSAL_DESCRIPTION_LENGTH, MAX_PROPOSAL_OPTIONS,
+    MAX_PROPOSAL_OPTION_LENGTH, MAX_PROPOSAL_TITLE_LENGTH, PROPOSAL_ACTIVE, PROPOSAL_APPROVED,
+};
+
+const VOTING_DURATION: u64 = 86_400;
+// Upper bound for a single cast_vote, whatever the proposal's size
+const CAST_VOTE_CU_BUDGET: u64 = 30_000;
+// How far apart a minimal and a maximal proposal may be to vote on
+const CAST_VOTE_CU_SPREAD: u64 = 1_000;
+
+const SNAPSHOT: &str = "governance_voting";
+// Proposals the fixture leaves open
+const LIFECYCLE_PROPOSAL: u64 = 0;
+const MINIMAL_PROPOSAL: u64 = 1;
+const MAXIMAL_PROPOSAL: u64 = 2;
+const BENCH_RUNS: u32 = 5;
+
+async fn create_proposal(program: &Program, creator: &TestUser, proposal_id: u64, title: String, description: String, options: Vec<String>) {
+    program
+        .request()
+        .accounts(ontora_ai::accounts::CreateGovernanceProposal {
+            creator: creator.pubkey,
+            platform_config: platform_config_pda(&id()),
+            proposal: proposal_pda(&id(), proposal_id),
+            system_program: system_program::ID,
+        })
+        .args(ontora_ai::instruction::CreateGovernanceProposal {
+            title,
+            description,
+            voting_duration: VOTING_DURATION,
+            options,
+            action: ProposalAction::NoOp,
+        })
+        .signer(&creator.keypair)
+        .send()
+        .await
+        .unwrap();
+}
+
+fn cast_vote_tx(program: &Program, voter: &Keypair, proposal_id: u64, vote_option: u8, blockhash: Hash) -> Transaction {
+    let instructions = program
+        .request()
+        .accounts(ontora_ai::accounts::CastVote {
+            voter: voter.pubkey(),
+            platform_config: platform_config_pda(&id()),
+            proposal: proposal_pda(&id(), proposal_id),
+            user_stake: user_stake_pda(&id(), &voter.pubkey()),
+            vote_record: vote_record_pda(&id(), proposal_id, &voter.pubkey()),
+            system_program: system_program::ID,
+        })
+        .args(ontora_ai::instruction::CastVote { proposal_id, vote_option })
+        .instructions()
+        .unwrap();
+    Transaction::new_signed_with_payer(&instructions, Some(&voter.pubkey()), &[voter], blockhash)
+}
+
+async fn fetch_proposal(banks_client: &mut BanksClient, proposal_id: u64) -> Proposal {
+    let account = banks_client.get_account(proposal_pda(&id(), proposal_id)).await.unwrap().unwrap();
+    read_zero_copy(&account.data)
+}
+
+// Compute units a cast_vote on `proposal_id` would consume
+async fn cast_vote_units(ctx: &mut TestContext, program: &Program, voter: &TestUser, proposal_id: u64) -> u64 {
+    let blockhash = ctx.banks_client.get_latest_blockhash().await.unwrap();
+    let tx = cast_vote_tx(program, &voter.keypair, proposal_id, 0, blockhash);
+    let simulation = ctx.banks_client.simulate_transaction(tx).await.unwrap();
+    assert!(simulation.result.unwrap().is_ok());
+    simulation.simulation_details.unwrap().units_consumed
+}
+
+async fn finalize_proposal(program: &Program, caller: &TestUser, proposal_id: u64) -> std::result::Result<(), anchor_client::ClientError> {
+    program
+        .request()
+        .accounts(ontora_ai::accounts::FinalizeProposal {
+            caller: caller.pubkey,
+            platform_config: platform_config_pda(&id()),
+            proposal: proposal_pda(&id(), proposal_id),
+            system_program: system_program::ID,
+        })
+        .args(ontora_ai::instruction::FinalizeProposal { proposal_id })
+        .signer(&caller.keypair)
+        .send()
+        .await
+        .map(|_| ())
+}
+
+// Give `user` a stake account holding one vote's worth of stake
+fn seed_vote_stake(ctx: &mut TestContext, user: &Pubkey) -> Pubkey {
+    let (address, bump) = Pubkey::find_program_address(&[pda::USER_STAKE_SEED, user.as_ref()], &id());
+    let mut stake = UserStake::default();
+    stake.init(*user, bump);
+    stake.staked_amount = DEFAULT_VOTE_UNIT;
+    let mut data = Vec::new();
+    stake.try_serialize(&mut data).unwrap();
+    data.resize(UserStake::SPACE, 0);
+    let account = SolanaAccount {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner: id(),
+        executable: false,
+        rent_epoch: 0,
+    };
+    ctx.program_context.set_account(&address, &AccountSharedData::from(account));
+    address
+}
+
+// Initialize the platform, stake the admin's vote and open the fixture's proposals, and
+// snapshot the result
+async fn build_voting_fixture() -> (TestContext, Program, TestUser) {
+    let (mut ctx, program) = setup_test_context().await;
+    let admin = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
+    initialize_test_platform(&program, &admin).await;
+    let admin_stake = seed_vote_stake(&mut ctx, &admin.pubkey);
+
+    let options = vec!["Yes".to_string(), "No".to_string(), "Abstain".to_string()];
+    let (title, description) = ("Raise rewards".to_string(), "Raise the reward rate to 2%".to_string());
+    create_proposal(&program, &admin, LIFECYCLE_PROPOSAL, title, description, options).await;
+    let options = vec!["A".to_string(), "B".to_string()];
+    create_proposal(&program, &admin, MINIMAL_PROPOSAL, "T".to_string(), "D".to_string(), options).await;
+    create_proposal(
+        &program,
+        &admin,
+        MAXIMAL_PROPOSAL,
+        "t".repeat(MAX_PROPOSAL_TITLE_LENGTH),
+        "d".repeat(MAX_PROPOSAL_DESCRIPTION_LENGTH),
+        (0..MAX_PROPOSAL_OPTIONS).map(|i| i.to_string().repeat(MAX_PROPOSAL_OPTION_LENGTH)).collect(),
+    )
+    .await;
+
+    let mut accounts = vec![platform_config_pda(&id()), admin_stake];
+    for proposal_id in [LIFECYCLE_PROPOSAL, MINIMAL_PROPOSAL, MAXIMAL_PROPOSAL] {
+        accounts.push(proposal_pda(&id(), proposal_id));
+    }
+    Snapshot::capture(&mut ctx, &[("admin", &admin)], &accounts).await.save(SNAPSHOT);
+    (ctx, program, admin)
+}
+
+// The fixture every test starts from: the admin's platform, with one vote staked and proposals
+// open for voting
+async fn voting_fixture() -> (TestContext, Program, TestUser) {
+    match Snapshot::load(SNAPSHOT) {
+        Some(snapshot) => {
+            let (ctx, program) = snapshot.restore().await;
+            (ctx, program, snapshot.signer("admin"))
+        }
+        None => build_voting_fixture().await,
+    }
+}
+
+// Test creating, voting on, and finalizing a proposal
+#[tokio::test]
+async fn test_proposal_lifecycle() {
+    let (mut ctx, program, admin) = voting_fixture().await;
+
+    let proposal = fetch_proposal(&mut ctx.banks_client, LIFECYCLE_PROPOSAL).await;
+    assert_eq!(proposal.status, PROPOSAL_ACTIVE);
+    assert_eq!(proposal.title(), "Raise rewards");
+    assert_eq!(proposal.option(2), "Abstain");
+    assert_eq!(proposal.tallies(), &[0, 0, 0]);
+
+    // Options past the ballot are rejected
+    let blockhash = ctx.banks_client.get_latest_blockhash().await.unwrap();
+    let tx = cast_vote_tx(&program, &admin.keypair, LIFECYCLE_PROPOSAL, 3, blockhash);
+    assert!(ctx.banks_client.process_transaction(tx).await.is_err());
+
+    let tx = cast_vote_tx(&program, &admin.keypair, LIFECYCLE_PROPOSAL, 1, blockhash);
+    ctx.banks_client.process_transaction(tx).await.unwrap();
+    assert_eq!(fetch_proposal(&mut ctx.banks_client, LIFECYCLE_PROPOSAL).await.tallies(), &[0, 1, 0]);
+
+    // Finalize once the voting period is over
+    warp_seconds(&mut ctx, VOTING_DURATION as i64 + 1).await;
+    finalize_proposal(&program, &admin, LIFECYCLE_PROPOSAL).await.unwrap();
+    assert_eq!(fetch_proposal(&mut ctx.banks_client, LIFECYCLE_PROPOSAL).await.status, PROPOSAL_APPROVED);
+}
+
+// Test the voting deadline to the second: votes are accepted through end_time
+// inclusive, and finalizing only opens after it
+#[tokio::test]
+async fn test_proposal_deadline_boundary() {
+    let (mut ctx, program, admin) = voting_fixture().await;
+    let end_time = fetch_proposal(&mut ctx.banks_client, MINIMAL_PROPOSAL).await.end_time;
+
+    // At end_time voting is still open and finalizing is not
+    assert_eq!(warp_seconds(&mut ctx, VOTING_DURATION as i64).await, end_time);
+    let error = finalize_proposal(&program, &admin, MINIMAL_PROPOSAL).await.unwrap_err();
+    assert_eq!(custom_error_code(&error), Some(u32::from(OntoraError::VotingPeriodNotEnded)));
+    let tx = cast_vote_tx(&program, &admin.keypair, MINIMAL_PROPOSAL, 0, ctx.last_blockhash);
+    ctx.banks_client.process_transaction(tx).await.unwrap();
+
+    // One second later the reverse holds
+    warp_seconds(&mut ctx, 1).await;
+    let tx = cast_vote_tx(&program, &admin.keypair, MINIMAL_PROPOSAL, 1, ctx.last_blockhash);
+    assert!(ctx.banks_client.process_transaction(tx).await.is_err());
+    finalize_proposal(&program, &admin, MINIMAL_PROPOSAL).await.unwrap();
+
+    let proposal = fetch_proposal(&mut ctx.banks_client, MINIMAL_PROPOSAL).await;
+    assert_eq!(proposal.tallies(), &[1, 0]);
+    assert_eq!(proposal.status, PROPOSAL_APPROVED);
+}
+
+// Benchmark: voting costs the same on a minimal and a maximal proposal
+#[tokio::test]
+async fn test_cast_vote_compute_units() {
+    let (mut ctx, program, admin) = voting_fixture().await;
+
+    let minimal = cast_vote_units(&mut ctx, &program, &admin, MINIMAL_PROPOSAL).await;
+    let maximal = cast_vote_units(&mut ctx, &program, &admin, MAXIMAL_PROPOSAL).await;
+    println!("cast_vote compute units: minimal proposal {}, maximal proposal {}", minimal, maximal);
+
+    assert!(maximal <= CAST_VOTE_CU_BUDGET, "cast_vote used {} CU", maximal);
+    assert!(maximal.abs_diff(minimal) <= CAST_VOTE_CU_SPREAD);
+}
+
+// Benchmark: starting from the snapshot against building the fixture. Ignored by
+// default, as wall-clock timing is noisy under a parallel test run.
+#[tokio::test]
+#[ignore]
+async fn bench_snapshot_restore() {
+    let mut built = Duration::ZERO;
+    let mut restored = Duration::ZERO;
+    for _ in 0..BENCH_RUNS {
+        let started = Instant::now();
+        build_voting_fixture().await;
+        built += started.elapsed();
+
+        let started = Instant::now();
+        let snapshot = Snapshot::load(SNAPSHOT).expect("the fixture was just snapshotted");
+        let (mut ctx, _) = snapshot.restore().await;
+        restored += started.elapsed();
+        assert_eq!(get_clock(&mut ctx.banks_client).await.unix_timestamp, snapshot.clock.unix_timestamp);
+    }
+    let speedup = built.as_secs_f64() / restored.as_secs_f64();
+    println!(
+        "voting fixture: built in {:?}, restored in {:?} on average ({:.1}x)",
+        built / BENCH_RUNS,
+        restored / BENCH_RUNS,
+        speedup
+    );
+    assert!(speedup > 1.0, "restoring the snapshot was no faster than building the fixture");
+}
@@ -0,0 +1,264 @@
+// test_governance_realm.rs
+// This module contains test cases for the spl-governance (Realms) adapter. A mock
+// governance program stands in for spl-governance executing a proposal: it signs as
+// its native treasury PDA and invokes the admin-gated instruction it was handed.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::entrypoint::ProgramResult;
+use anchor_lang::solana_program::instruction::{Instruction, InstructionError};
+use anchor_lang::solana_program::program::invoke_signed;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::solana_program::system_instruction;
+use anchor_lang::InstructionData;
+use solana_program_test::*;
+use solana_sdk::account::Account as SolanaAccount;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::{Transaction, TransactionError};
+
+// Import test setup utilities (assumes test_setup.rs is in the same directory)
+mod test_setup;
+use test_setup::*;
+
+use ontora_ai::pda;
+use ontora_ai::state::{native_treasury, ConfigParams, GovernanceRealm, PlatformConfig, Role};
+
+const MOCK_GOVERNANCE_ID: Pubkey = Pubkey::new_from_array([7; 32]);
+// spl-governance's GovernanceV2 account type
+const GOVERNANCE_V2: u8 = 18;
+
+const UNAUTHORIZED_ADMIN: u32 = 6102;
+const INVALID_ACCOUNT: u32 = 6602;
+
+// Executes a proposal transaction: accounts are [governance, ontora program, ..the
+// instruction's accounts], the data is the instruction's. The governance's native
+// treasury signs, as spl-governance's does for executed proposals.
+fn mock_governance(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let governance = accounts[0].key;
+    let (treasury, bump) = Pubkey::find_program_address(&[b"native-treasury", governance.as_ref()], program_id);
+    let instruction = Instruction {
+        program_id: *accounts[1].key,
+        accounts: accounts[2..]
+            .iter()
+            .map(|info| AccountMeta {
+                pubkey: *info.key,
+                is_signer: info.is_signer || *info.key == treasury,
+                is_writable: info.is_writable,
+            })
+            .collect(),
+        data: data.to_vec(),
+    };
+    invoke_signed(&instruction, &accounts[1..], &[&[b"native-treasury", governance.as_ref(), &[bump]]])
+}
+
+fn program_account<T: AccountSerialize>(value: &T, space: usize) -> SolanaAccount {
+    let mut data = Vec::new();
+    value.try_serialize(&mut data).unwrap();
+    data.resize(space, 0);
+    SolanaAccount {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner: id(),
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+// A governance account of `realm`, laid out as spl-governance's: account type, then realm
+fn governance_account(realm: &Pubkey) -> SolanaAccount {
+    let mut data = vec![GOVERNANCE_V2];
+    data.extend_from_slice(realm.as_ref());
+    data.resize(236, 0);
+    SolanaAccount {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner: MOCK_GOVERNANCE_ID,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+fn governance_realm_pda() -> Pubkey {
+    Pubkey::find_program_address(&[pda::GOVERNANCE_REALM_SEED], &id()).0
+}
+
+fn set_governance_realm(admin: &Pubkey, realm: Pubkey, governance: Pubkey) -> Instruction {
+    Instruction {
+        program_id: id(),
+        accounts: ontora_ai::accounts::SetGovernanceRealm {
+            platform_config: platform_config_pda(&id()),
+            governance_realm: governance_realm_pda(),
+            governance,
+            admin: *admin,
+            system_program: anchor_lang::system_program::ID,
+        }
+        .to_account_metas(None),
+        data: ontora_ai::instruction::SetGovernanceRealm { realm, governance }.data(),
+    }
+}
+
+fn update_platform_config(admin: &Pubkey, reward_rate_bps: u64) -> Instruction {
+    Instruction {
+        program_id: id(),
+        accounts: ontora_ai::accounts::UpdatePlatformConfig {
+            platform_config: platform_config_pda(&id()),
+            admin: *admin,
+        }
+        .to_account_metas(None),
+        data: ontora_ai::instruction::UpdatePlatformConfig {
+            params: ConfigParams {
+                reward_rate_bps,
+                min_stake_amount: TEST_STAKE_AMOUNT,
+                epoch_duration: 86_400,
+                cooldown_seconds: 86_400,
+                epoch_emission: TEST_EPOCH_EMISSION,
+                max_stake_per_agent: 0,
+                vesting_seconds: 0,
+                vote_unit: TEST_VOTE_UNIT,
+                quorum_bps: TEST_QUORUM_BPS,
+                approval_threshold_bps: TEST_APPROVAL_THRESHOLD_BPS,
+            },
+            update_metadata: "Governance proposal".to_string(),
+        }
+        .data(),
+    }
+}
+
+fn set_role(admin: &Pubkey, role: Role, holder: Pubkey) -> Instruction {
+    Instruction {
+        program_id: id(),
+        accounts: ontora_ai::accounts::SetRole { platform_config: platform_config_pda(&id()), admin: *admin }
+            .to_account_metas(None),
+        data: ontora_ai::instruction::SetRole { role, holder }.data(),
+    }
+}
+
+// Append the governance realm account, as a proposal executing `instruction` would
+fn with_realm(mut instruction: Instruction) -> Instruction {
+    instruction.accounts.push(AccountMeta::new_readonly(governance_realm_pda(), false));
+    instruction
+}
+
+// Wrap `instruction` in a mock governance execution by `governance`
+fn execute(governance: &Pubkey, instruction: Instruction) -> Instruction {
+    let mut accounts = vec![AccountMeta::new_readonly(*governance, false), AccountMeta::new_readonly(id(), false)];
+    accounts.extend(instruction.accounts.into_iter().map(|meta| AccountMeta { is_signer: false, ..meta }));
+    Instruction { program_id: MOCK_GOVERNANCE_ID, accounts, data: instruction.data }
+}
+
+// Start a validator with a platform config administered by `admin`, the mock governance
+// program, and a governance account of `realm` for each of `governances`
+async fn setup(admin: &Keypair, realm: &Pubkey, governances: &[Pubkey]) -> ProgramTestContext {
+    let mut test = ProgramTest::new("ontora_ai", id(), processor!(ontora_ai::entry));
+    test.add_program("mock_governance", MOCK_GOVERNANCE_ID, processor!(mock_governance));
+    let (_, config_bump) = Pubkey::find_program_address(&[pda::PLATFORM_CONFIG_SEED], &id());
+    let mut config = PlatformConfig::default();
+    config.init(
+        admin.pubkey(),
+        100,
+        TEST_STAKE_AMOUNT,
+        86_400,
+        86_400,
+        TEST_EPOCH_EMISSION,
+        config_bump,
+    );
+    test.add_account(platform_config_pda(&id()), program_account(&config, PlatformConfig::SPACE));
+    for governance in governances {
+        test.add_account(*governance, governance_account(realm));
+    }
+    test.add_account(
+        admin.pubkey(),
+        SolanaAccount {
+            lamports: INITIAL_LAMPORTS,
+            ..Default::default()
+        },
+    );
+    test.start_with_context().await
+}
+
+async fn process(ctx: &mut ProgramTestContext, instruction: Instruction, signers: &[&Keypair]) -> Result<(), u32> {
+    let mut all_signers = vec![&ctx.payer];
+    all_signers.extend_from_slice(signers);
+    let tx = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&ctx.payer.pubkey()),
+        &all_signers,
+        ctx.last_blockhash,
+    );
+    ctx.banks_client.process_transaction(tx).await.map_err(|error| match error.unwrap() {
+        TransactionError::InstructionError(_, InstructionError::Custom(code)) => code,
+        other => panic!("unexpected error {:?}", other),
+    })
+}
+
+async fn platform_config(ctx: &mut ProgramTestContext) -> PlatformConfig {
+    let account = ctx.banks_client.get_account(platform_config_pda(&id())).await.unwrap().unwrap();
+    PlatformConfig::try_deserialize(&mut account.data.as_slice()).unwrap()
+}
+
+// Test that once the admin records a governance, a proposal executed by that
+// governance's native treasury updates the config and assigns roles
+#[tokio::test]
+async fn test_governance_executes_admin_instructions() {
+    let admin = Keypair::new();
+    let (realm, governance) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let mut ctx = setup(&admin, &realm, &[governance]).await;
+
+    process(&mut ctx, set_governance_realm(&admin.pubkey(), realm, governance), &[&admin]).await.unwrap();
+    let account = ctx.banks_client.get_account(governance_realm_pda()).await.unwrap().unwrap();
+    let recorded = GovernanceRealm::try_deserialize(&mut account.data.as_slice()).unwrap();
+    let treasury = native_treasury(&governance, &MOCK_GOVERNANCE_ID);
+    assert_eq!(recorded.realm, realm);
+    assert_eq!(recorded.governance, governance);
+    assert_eq!(recorded.governance_program, MOCK_GOVERNANCE_ID);
+    assert_eq!(recorded.native_treasury, treasury);
+
+    let update = with_realm(update_platform_config(&treasury, 250));
+    process(&mut ctx, execute(&governance, update), &[]).await.unwrap();
+    assert_eq!(platform_config(&mut ctx).await.reward_rate_bps, 250);
+
+    let oracle = Pubkey::new_unique();
+    let assign = with_realm(set_role(&treasury, Role::Oracle, oracle));
+    process(&mut ctx, execute(&governance, assign), &[]).await.unwrap();
+    assert_eq!(platform_config(&mut ctx).await.role_holder(Role::Oracle), oracle);
+
+    // The admin keeps its own access
+    process(&mut ctx, update_platform_config(&admin.pubkey(), 300), &[&admin]).await.unwrap();
+    assert_eq!(platform_config(&mut ctx).await.reward_rate_bps, 300);
+}
+
+// Test that only the admin records a governance, that it must belong to the realm,
+// and that other governances, missing realm accounts and direct calls are rejected
+#[tokio::test]
+async fn test_rejects_unrecorded_callers() {
+    let (admin, outsider) = (Keypair::new(), Keypair::new());
+    let (realm, governance, other) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let mut ctx = setup(&admin, &realm, &[governance, other]).await;
+    // Funded, so the outsider could pay for the realm account it is refused
+    let fund = system_instruction::transfer(&ctx.payer.pubkey(), &outsider.pubkey(), INITIAL_LAMPORTS);
+    process(&mut ctx, fund, &[]).await.unwrap();
+
+    let by_outsider = set_governance_realm(&outsider.pubkey(), realm, governance);
+    assert_eq!(process(&mut ctx, by_outsider, &[&outsider]).await, Err(UNAUTHORIZED_ADMIN));
+    let wrong_realm = set_governance_realm(&admin.pubkey(), Pubkey::new_unique(), governance);
+    assert_eq!(process(&mut ctx, wrong_realm, &[&admin]).await, Err(INVALID_ACCOUNT));
+    // A governance account other than the one named
+    let mut mismatched = set_governance_realm(&admin.pubkey(), realm, governance);
+    mismatched.accounts[2].pubkey = other;
+    assert_eq!(process(&mut ctx, mismatched, &[&admin]).await, Err(INVALID_ACCOUNT));
+    process(&mut ctx, set_governance_realm(&admin.pubkey(), realm, governance), &[&admin]).await.unwrap();
+
+    // A governance of the same program and realm that wasn't recorded
+    let other_treasury = native_treasury(&other, &MOCK_GOVERNANCE_ID);
+    let update = with_realm(update_platform_config(&other_treasury, 250));
+    assert_eq!(process(&mut ctx, execute(&other, update), &[]).await, Err(UNAUTHORIZED_ADMIN));
+
+    // The recorded governance without the realm account
+    let treasury = native_treasury(&governance, &MOCK_GOVERNANCE_ID);
+    let update = update_platform_config(&treasury, 250);
+    assert_eq!(process(&mut ctx, execute(&governance, update), &[]).await, Err(UNAUTHORIZED_ADMIN));
+
+    // An outsider presenting the realm account at the top level
+    let direct = with_realm(set_role(&outsider.pubkey(), Role::Pauser, outsider.pubkey()));
+    assert_eq!(process(&mut ctx, direct, &[&outsider]).await, Err(UNAUTHORIZED_ADMIN));
+    assert_eq!(platform_config(&mut ctx).await.reward_rate_bps, 100);
+}
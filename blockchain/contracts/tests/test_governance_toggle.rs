@@ -0,0 +1,182 @@
+// test_governance_toggle.rs
+// This module contains test cases for set_governance_enabled: suspending an
+// in-flight proposal on disable and resuming it with an extended deadline.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_client::Program;
+use solana_program_test::*;
+use solana_sdk::account::Account as SolanaAccount;
+use solana_sdk::commitment_config::CommitmentLevel;
+use solana_sdk::instruction::AccountMeta;
+use solana_sdk::signature::{Keypair, Signer};
+use bytemuck::Zeroable;
+use std::rc::Rc;
+
+// Import test setup utilities (assumes test_setup.rs is in the same directory)
+mod test_setup;
+use test_setup::*;
+
+use ontora_ai::pda;
+use ontora_ai::state::{PlatformConfig, Proposal, PROPOSAL_ACTIVE, PROPOSAL_SUSPENDED, PROPOSAL_VERSION};
+
+const START: i64 = 1_700_000_000;
+const VOTING_DURATION: i64 = 3 * 86_400;
+
+fn program_account<T: AccountSerialize>(value: &T, space: usize) -> SolanaAccount {
+    let mut data = Vec::new();
+    value.try_serialize(&mut data).unwrap();
+    data.resize(space, 0);
+    raw_account(data)
+}
+
+fn raw_account(data: Vec<u8>) -> SolanaAccount {
+    SolanaAccount {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner: id(),
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+fn proposal_pda(id_: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[pda::PROPOSAL_SEED, id_.to_le_bytes().as_ref()], &id())
+}
+
+// Start a validator holding a platform config with one active proposal (id 0)
+async fn setup_with_active_proposal(admin: &Keypair) -> (ProgramTestContext, Program, Pubkey, Pubkey) {
+    let mut test = ProgramTest::new("ontora_ai", id(), processor!(ontora_ai::entry));
+    let platform_config = platform_config_pda(&id());
+    let (_, config_bump) = Pubkey::find_program_address(&[pda::PLATFORM_CONFIG_SEED], &id());
+    let (proposal, proposal_bump) = proposal_pda(0);
+
+    let mut config = PlatformConfig::default();
+    config.init(
+        admin.pubkey(),
+        100,
+        TEST_STAKE_AMOUNT,
+        86_400,
+        86_400,
+        TEST_EPOCH_EMISSION,
+        config_bump,
+    );
+    config.proposal_count = 1;
+    config.active_proposal_count = 1;
+    test.add_account(platform_config, program_account(&config, PlatformConfig::SPACE));
+
+    let mut ballot = Proposal::zeroed();
+    ballot.version = PROPOSAL_VERSION;
+    ballot.creator = admin.pubkey();
+    ballot.set_contents(
+        "Raise reward rate",
+        "Raise the reward rate to 2%",
+        &["Yes".to_string(), "No".to_string()],
+    );
+    ballot.votes[..2].copy_from_slice(&[7, 3]);
+    ballot.start_time = START;
+    ballot.end_time = START + VOTING_DURATION;
+    ballot.status = PROPOSAL_ACTIVE;
+    ballot.bump = proposal_bump;
+    test.add_account(proposal, raw_account(zero_copy_bytes(&ballot)));
+    test.add_account(
+        admin.pubkey(),
+        SolanaAccount {
+            lamports: INITIAL_LAMPORTS,
+            ..Default::default()
+        },
+    );
+
+    let context = test.start_with_context().await;
+    set_time(&context, START + 86_400).await;
+    let program = Program::new(id(), Rc::new(context.banks_client.clone()), CommitmentLevel::Confirmed);
+    (context, program, platform_config, proposal)
+}
+
+async fn set_time(context: &ProgramTestContext, unix_timestamp: i64) {
+    let mut clock: Clock = context.banks_client.clone().get_sysvar().await.unwrap();
+    clock.unix_timestamp = unix_timestamp;
+    context.set_sysvar(&clock);
+}
+
+async fn fetch_proposal(context: &mut ProgramTestContext, proposal: Pubkey) -> Proposal {
+    let account = context.banks_client.get_account(proposal).await.unwrap().unwrap();
+    read_zero_copy(&account.data)
+}
+
+async fn set_governance(program: &Program, platform_config: Pubkey, admin: &Keypair, enabled: bool, proposals: &[Pubkey]) -> std::result::Result<(), anchor_client::ClientError> {
+    let mut request = program
+        .request()
+        .accounts(ontora_ai::accounts::SetGovernanceEnabled {
+            platform_config,
+            admin: admin.pubkey(),
+        });
+    for proposal in proposals {
+        request = request.accounts(AccountMeta::new(*proposal, false));
+    }
+    request
+        .args(ontora_ai::instruction::SetGovernanceEnabled { enabled })
+        .signer(admin)
+        .send()
+        .await
+        .map(|_| ())
+}
+
+// Test that suspend/resume keep tallies and extend the deadline by the suspension length
+#[test]
+fn test_suspend_and_resume_extend_deadline() {
+    let mut proposal = Proposal::zeroed();
+    proposal.set_contents("Title", "Body", &["Yes".to_string(), "No".to_string()]);
+    proposal.votes[..2].copy_from_slice(&[5, 1]);
+    proposal.end_time = START + VOTING_DURATION;
+    proposal.status = PROPOSAL_ACTIVE;
+
+    proposal.suspend(START + 100).unwrap();
+    assert_eq!(proposal.status, PROPOSAL_SUSPENDED);
+    assert!(proposal.suspend(START + 200).is_err());
+
+    proposal.resume(START + 100 + 3_600).unwrap();
+    assert_eq!(proposal.status, PROPOSAL_ACTIVE);
+    assert_eq!(proposal.end_time, START + VOTING_DURATION + 3_600);
+    assert_eq!(proposal.tallies(), &[5, 1]);
+    assert!(proposal.resume(START + 10_000).is_err());
+}
+
+// Test that disabling suspends the in-flight proposal and enabling resumes it
+#[tokio::test]
+async fn test_in_flight_proposal_suspended_and_resumed() {
+    let admin = Keypair::new();
+    let (mut context, program, platform_config, proposal) = setup_with_active_proposal(&admin).await;
+
+    // Disabling without passing the active proposal is refused
+    assert!(set_governance(&program, platform_config, &admin, false, &[]).await.is_err());
+
+    set_governance(&program, platform_config, &admin, false, &[proposal]).await.unwrap();
+    let suspended = fetch_proposal(&mut context, proposal).await;
+    assert_eq!(suspended.status, PROPOSAL_SUSPENDED);
+    assert_eq!(suspended.suspended_at, START + 86_400);
+    assert_eq!(suspended.tallies(), &[7, 3]);
+
+    // Two days later governance comes back; the deadline moves by exactly two days
+    set_time(&context, START + 3 * 86_400).await;
+    set_governance(&program, platform_config, &admin, true, &[proposal]).await.unwrap();
+    let resumed = fetch_proposal(&mut context, proposal).await;
+    assert_eq!(resumed.status, PROPOSAL_ACTIVE);
+    assert_eq!(resumed.end_time, START + VOTING_DURATION + 2 * 86_400);
+    assert_eq!(resumed.tallies(), &[7, 3]);
+
+    let account = context.banks_client.get_account(platform_config).await.unwrap().unwrap();
+    let config = PlatformConfig::try_deserialize(&mut account.data.as_ref()).unwrap();
+    assert!(config.governance_enabled);
+}
+
+// Test that only the admin toggles governance and a no-op toggle is refused
+#[tokio::test]
+async fn test_toggle_requires_admin_and_change() {
+    let admin = Keypair::new();
+    let (_context, program, platform_config, proposal) = setup_with_active_proposal(&admin).await;
+
+    let outsider = Keypair::new();
+    assert!(set_governance(&program, platform_config, &outsider, false, &[proposal]).await.is_err());
+    assert!(set_governance(&program, platform_config, &admin, true, &[proposal]).await.is_err());
+}
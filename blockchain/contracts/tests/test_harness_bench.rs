@@ -0,0 +1,106 @@
+// test_harness_bench.rs
+// This module benchmarks the in-process harness in svm/ against solana-program-test by
+// timing the same fixture (platform initialization, an agent, and a stake) on each, and
+// checks the harness is at least MIN_SPEEDUP times faster. Wall-clock timing is noisy
+// under a parallel test run, so the benchmark is ignored by default:
+//     cargo test --test test_harness_bench -- --ignored --nocapture
+
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::solana_program::system_program;
+use std::time::{Duration, Instant};
+
+mod svm;
+mod test_setup;
+
+use test_setup::{stake_index_pda, user_stake_pda, TEST_AI_AGENT_ID, TEST_STAKE_AMOUNT};
+
+const RUNS: u32 = 5;
+const MIN_SPEEDUP: f64 = 5.0;
+
+// The fixture on solana-program-test, as the suites used to build it
+async fn program_test_fixture() {
+    let (mut ctx, program) = test_setup::setup_test_context().await;
+    let platform = test_setup::initialize_program(&mut ctx, &program, 0).await;
+    let user = test_setup::create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
+    let token_account =
+        test_setup::create_funded_token_account(&mut ctx, &platform, &user.pubkey, TEST_STAKE_AMOUNT).await;
+    let agent = test_setup::create_mock_ai_agent(&program, &user, TEST_AI_AGENT_ID).await;
+
+    program
+        .request()
+        .accounts(stake_accounts(&program.id(), &platform, &user.pubkey, token_account, agent))
+        .args(ontora_ai::instruction::StakeOnAgent {
+            agent_id: TEST_AI_AGENT_ID,
+            amount: TEST_STAKE_AMOUNT,
+            beneficiary: user.pubkey,
+        })
+        .signer(&user.keypair)
+        .send()
+        .await
+        .unwrap();
+}
+
+// The same fixture on the in-process harness
+fn svm_fixture() {
+    let mut ctx = svm::setup_svm();
+    let platform = svm::initialize_program(&mut ctx, 0);
+    let user = svm::create_test_user(&mut ctx);
+    let token_account = svm::create_funded_token_account(&mut ctx, &platform, &user.pubkey, TEST_STAKE_AMOUNT);
+    let agent = svm::create_mock_ai_agent(&mut ctx, &user, TEST_AI_AGENT_ID);
+
+    let instruction = svm::program_instruction(
+        stake_accounts(&ontora_ai::ID, &platform, &user.pubkey, token_account, agent),
+        ontora_ai::instruction::StakeOnAgent {
+            agent_id: TEST_AI_AGENT_ID,
+            amount: TEST_STAKE_AMOUNT,
+            beneficiary: user.pubkey,
+        },
+    );
+    svm::send_transaction(&mut ctx, &[instruction], &[&user.keypair]).unwrap();
+}
+
+fn stake_accounts(
+    program_id: &Pubkey,
+    platform: &test_setup::TestPlatform,
+    user: &Pubkey,
+    user_token_account: Pubkey,
+    ai_agent: Pubkey,
+) -> ontora_ai::accounts::StakeOnAgent {
+    ontora_ai::accounts::StakeOnAgent {
+        platform_config: platform.platform_config,
+        ai_agent,
+        user_stake: user_stake_pda(program_id, user),
+        stake_index: stake_index_pda(program_id, user, TEST_AI_AGENT_ID),
+        user: *user,
+        user_token_account,
+        platform_vault: platform.platform_vault,
+        token_program: spl_token::ID,
+        system_program: system_program::ID,
+    }
+}
+
+// Benchmark both harnesses on the same fixture
+#[tokio::test]
+#[ignore]
+async fn bench_svm_harness_against_program_test() {
+    let mut program_test = Duration::ZERO;
+    let mut in_process = Duration::ZERO;
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        program_test_fixture().await;
+        program_test += start.elapsed();
+
+        let start = Instant::now();
+        svm_fixture();
+        in_process += start.elapsed();
+    }
+
+    let speedup = program_test.as_secs_f64() / in_process.as_secs_f64();
+    println!(
+        "solana-program-test: {:?}/run, svm harness: {:?}/run, speedup {:.1}x",
+        program_test / RUNS,
+        in_process / RUNS,
+        speedup
+    );
+    assert!(speedup >= MIN_SPEEDUP, "svm harness is only {:.1}x faster, expected {}x", speedup, MIN_SPEEDUP);
+}
@@ -0,0 +1,147 @@
+// test_input_validation.rs
+// This module contains test cases for string and ballot validation. Limits are
+// byte lengths, so the inputs here are built from 4-byte emoji that stay well
+// under each limit in chars while crossing it in bytes.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::solana_program::system_program;
+use anchor_client::Program;
+use bytemuck::Zeroable;
+use solana_program_test::*;
+
+// Import test setup utilities (assumes test_setup.rs is in the same directory)
+mod test_setup;
+use test_setup::*;
+
+use ontora_ai::error::OntoraError;
+use ontora_ai::pda;
+use ontora_ai::state::{
+    AiAgent, Proposal, MAX_DESCRIPTION_LENGTH, MAX_NAME_LENGTH, MAX_PROPOSAL_DESCRIPTION_LENGTH,
+    MAX_PROPOSAL_OPTIONS, MAX_PROPOSAL_OPTION_LENGTH, MAX_PROPOSAL_TITLE_LENGTH, MAX_VOTING_DURATION,
+};
+use ontora_ai::GovernanceProposal;
+
+const EMOJI: &str = "🚀";
+
+// The longest emoji string that fits in `max_bytes`, optionally one emoji over
+fn emoji_text(max_bytes: usize, over: bool) -> String {
+    let count = max_bytes / EMOJI.len() + usize::from(over);
+    EMOJI.repeat(count)
+}
+
+fn options(count: usize) -> Vec<String> {
+    (0..count).map(|i| format!("Option {}", i)).collect()
+}
+
+fn assert_rejected(result: Result<()>, expected: OntoraError) {
+    assert_eq!(result.unwrap_err(), expected.into());
+}
+
+// Test that agent names and descriptions are limited by bytes
+#[test]
+fn test_agent_metadata_byte_limits() {
+    assert_eq!(EMOJI.len(), 4);
+    let name = emoji_text(MAX_NAME_LENGTH, false);
+    let description = emoji_text(MAX_DESCRIPTION_LENGTH, false);
+    assert!(AiAgent::validate_metadata(&name, &description).is_ok());
+
+    // One emoji more is still far below the limit in chars
+    let long_name = emoji_text(MAX_NAME_LENGTH, true);
+    assert!(long_name.chars().count() < MAX_NAME_LENGTH);
+    assert_rejected(AiAgent::validate_metadata(&long_name, &description), OntoraError::NameTooLong);
+
+    let long_description = emoji_text(MAX_DESCRIPTION_LENGTH, true);
+    assert_rejected(AiAgent::validate_metadata(&name, &long_description), OntoraError::DescriptionTooLong);
+}
+
+// Test each proposal input near its byte limit
+#[test]
+fn test_proposal_input_byte_limits() {
+    let title = emoji_text(MAX_PROPOSAL_TITLE_LENGTH, false);
+    let description = emoji_text(MAX_PROPOSAL_DESCRIPTION_LENGTH, false);
+    let ballot = vec![emoji_text(MAX_PROPOSAL_OPTION_LENGTH, false); MAX_PROPOSAL_OPTIONS];
+    assert!(Proposal::validate_inputs(&title, &description, &ballot, 86_400).is_ok());
+
+    let long_title = emoji_text(MAX_PROPOSAL_TITLE_LENGTH, true);
+    assert_rejected(Proposal::validate_inputs(&long_title, &description, &ballot, 86_400), OntoraError::NameTooLong);
+
+    let long_description = emoji_text(MAX_PROPOSAL_DESCRIPTION_LENGTH, true);
+    assert_rejected(
+        Proposal::validate_inputs(&title, &long_description, &ballot, 86_400),
+        OntoraError::DescriptionTooLong,
+    );
+    assert_rejected(Proposal::validate_inputs(&title, "", &ballot, 86_400), OntoraError::DescriptionEmpty);
+
+    let mut long_option = ballot.clone();
+    long_option[0] = emoji_text(MAX_PROPOSAL_OPTION_LENGTH, true);
+    assert_rejected(Proposal::validate_inputs(&title, &description, &long_option, 86_400), OntoraError::NameTooLong);
+}
+
+// Test ballot size and duration checks
+#[test]
+fn test_proposal_ballot_and_duration() {
+    assert_rejected(
+        Proposal::validate_inputs("Title", "Body", &options(MAX_PROPOSAL_OPTIONS + 1), 86_400),
+        OntoraError::TooManyOptions,
+    );
+    assert_rejected(
+        Proposal::validate_inputs("Title", "Body", &options(1), 86_400),
+        OntoraError::InvalidProposalParameters,
+    );
+    assert_rejected(Proposal::validate_inputs("Title", "Body", &options(2), 0), OntoraError::InvalidDuration);
+    assert!(Proposal::validate_inputs("Title", "Body", &options(2), MAX_VOTING_DURATION).is_ok());
+    assert_rejected(
+        Proposal::validate_inputs("Title", "Body", &options(2), MAX_VOTING_DURATION + 1),
+        OntoraError::InvalidDuration,
+    );
+    assert_rejected(Proposal::validate_inputs("Title", "Body", &options(2), u64::MAX), OntoraError::InvalidDuration);
+}
+
+// Test that maximal emoji strings round-trip through the proposal's fixed arrays
+#[test]
+fn test_maximal_emoji_proposal_fits() {
+    let title = emoji_text(MAX_PROPOSAL_TITLE_LENGTH, false);
+    let description = emoji_text(MAX_PROPOSAL_DESCRIPTION_LENGTH, false);
+    let ballot = vec![emoji_text(MAX_PROPOSAL_OPTION_LENGTH, false); MAX_PROPOSAL_OPTIONS];
+
+    let mut proposal = Proposal::zeroed();
+    proposal.set_contents(&title, &description, &ballot);
+    assert_eq!(proposal.title(), title);
+    assert_eq!(proposal.description(), description);
+    assert!((0..MAX_PROPOSAL_OPTIONS).all(|index| proposal.option(index) == ballot[index]));
+}
+
+async fn create_legacy_proposal(program: &Program, platform_config: Pubkey, user: &TestUser, description: String) -> std::result::Result<(), anchor_client::ClientError> {
+    let proposal = Pubkey::find_program_address(&[pda::PROPOSAL_SEED, user.pubkey.as_ref()], &program.id()).0;
+
+    program
+        .request()
+        .accounts(ontora_ai::accounts::CreateProposal {
+            platform_config,
+            proposal,
+            user: user.pubkey,
+            system_program: system_program::ID,
+        })
+        .args(ontora_ai::instruction::CreateProposal { description, duration: 86_400 })
+        .signer(&user.keypair)
+        .send()
+        .await
+        .map(|_| ())
+}
+
+// Test that the legacy proposal instruction stores a full emoji description and rejects one more
+#[tokio::test]
+async fn test_legacy_proposal_emoji_description() {
+    let (mut ctx, program) = setup_test_context().await;
+    let admin = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
+    let user = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
+    let platform_config = initialize_test_platform(&program, &admin).await;
+
+    let too_long = emoji_text(GovernanceProposal::MAX_DESCRIPTION_LENGTH, true);
+    let error = create_legacy_proposal(&program, platform_config, &user, too_long).await.unwrap_err();
+    assert_eq!(custom_error_code(&error), Some(u32::from(OntoraError::DescriptionTooLong)));
+
+    let fits = emoji_text(GovernanceProposal::MAX_DESCRIPTION_LENGTH, false);
+    assert!(create_legacy_proposal(&program, platform_config, &user, fits).await.is_ok());
+}
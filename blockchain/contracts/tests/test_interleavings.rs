@@ -0,0 +1,529 @@
+// test_interleavings.rs
+// This module covers stake, unstake, distribution, and claim operations that
+// interleave within one epoch: several instructions in one transaction, or
+// transactions in the same or adjacent slots. After every step the accounting
+// invariants are checked (vaults hold exactly what the accounts record, nothing
+// wrapped), and the TreasuryDeposit events a transaction emits must match the
+// fees the treasury recorded, in instruction order. Fees are non-zero here so
+// every unstake and claim leaves an event behind.
+// The tests run on the in-process harness in svm/.
+
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::solana_program::system_program;
+use litesvm::types::TransactionMetadata;
+use solana_sdk::signature::Keypair;
+
+// Shared types and PDAs from test_setup.rs, helpers from the in-process harness
+mod svm;
+mod test_setup;
+use svm::*;
+use test_setup::{
+    ai_agent_pda, legacy_ai_agent_pda, parse_events, reward_vesting_pda, stake_index_pda, treasury_vault_pda,
+    user_stake_pda, TestPlatform, TestUser, TEST_EPOCH_EMISSION, TEST_REWARD_POOL_AMOUNT, TEST_STAKE_AMOUNT,
+};
+
+use ontora_ai::error::OntoraError;
+use ontora_ai::events::TreasuryDeposit;
+use ontora_ai::state::{AiAgent, FeeSource, FeeType, PlatformConfig, StakeIndexEntry, Treasury, UserStake};
+use ontora_ai::{AIAgent, RewardPool};
+
+const EPOCH_DURATION: i64 = 604_800; // Matches EPOCH_DURATION in lib.rs
+const EPOCH_REWARD: u64 = TEST_EPOCH_EMISSION; // What a lone staker is paid per epoch
+const UNSTAKE_FEE_BPS: u16 = 250;
+const CLAIM_FEE_BPS: u16 = 100;
+const AGENT_ID: u64 = 1;
+
+fn fee(amount: u64, bps: u16) -> u64 {
+    amount * bps as u64 / 10_000
+}
+
+// A legacy agent's share of the epoch emission with `total` staked across the platform
+fn share(staked: u64, total: u64) -> u64 {
+    (TEST_EPOCH_EMISSION as u128 * staked as u128 / total as u128) as u64
+}
+
+fn setup() -> (SvmContext, TestPlatform) {
+    let mut ctx = setup_svm();
+    let mut rates = [0; FeeType::COUNT];
+    rates[FeeType::Unstake.index()] = UNSTAKE_FEE_BPS;
+    rates[FeeType::Claim.index()] = CLAIM_FEE_BPS;
+    let platform = initialize_program_with_fees(&mut ctx, TEST_REWARD_POOL_AMOUNT, rates);
+    (ctx, platform)
+}
+
+// A user staking through `stake_on_agent`, on the agent they registered themselves
+struct Staker {
+    user: TestUser,
+    token_account: Pubkey,
+    agent: Pubkey,
+}
+
+fn new_staker(ctx: &mut SvmContext, platform: &TestPlatform, tokens: u64) -> Staker {
+    let user = create_test_user(ctx);
+    let token_account = create_funded_token_account(ctx, platform, &user.pubkey, tokens);
+    let agent = create_mock_ai_agent(ctx, &user, AGENT_ID);
+    Staker { user, token_account, agent }
+}
+
+fn stake_ix(platform: &TestPlatform, user: &TestUser, token_account: Pubkey, amount: u64) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::StakeOnAgent {
+            platform_config: platform.platform_config,
+            ai_agent: ai_agent_pda(&ontora_ai::ID, &user.pubkey, AGENT_ID),
+            user_stake: user_stake_pda(&ontora_ai::ID, &user.pubkey),
+            stake_index: stake_index_pda(&ontora_ai::ID, &user.pubkey, AGENT_ID),
+            user: user.pubkey,
+            user_token_account: token_account,
+            platform_vault: platform.platform_vault,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        },
+        ontora_ai::instruction::StakeOnAgent { agent_id: AGENT_ID, amount, beneficiary: user.pubkey },
+    )
+}
+
+fn unstake_ix(platform: &TestPlatform, user: &TestUser, token_account: Pubkey, amount: u64) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::UnstakeFromAgent {
+            platform_config: platform.platform_config,
+            ai_agent: ai_agent_pda(&ontora_ai::ID, &user.pubkey, AGENT_ID),
+            user_stake: user_stake_pda(&ontora_ai::ID, &user.pubkey),
+            stake_index: stake_index_pda(&ontora_ai::ID, &user.pubkey, AGENT_ID),
+            user: user.pubkey,
+            user_token_account: token_account,
+            platform_vault: platform.platform_vault,
+            stake_mint: platform.mint,
+            fee_schedule: platform.fee_schedule,
+            treasury: platform.treasury,
+            treasury_vault: treasury_vault_pda(&ontora_ai::ID, &platform.mint),
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        },
+        ontora_ai::instruction::UnstakeFromAgent { agent_id: AGENT_ID, amount },
+    )
+}
+
+fn distribute_ix(platform: &TestPlatform, agent: &LegacyAgent) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::DistributeRewards {
+            platform_config: platform.platform_config,
+            reward_pool: platform.reward_pool,
+            ai_agent: agent.agent,
+            crank: platform.admin.pubkey,
+        },
+        ontora_ai::instruction::DistributeRewards {},
+    )
+}
+
+fn claim_ix(platform: &TestPlatform, agent: &LegacyAgent) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::ClaimRewards {
+            platform_config: platform.platform_config,
+            ai_agent: agent.agent,
+            reward_pool: platform.reward_pool,
+            user: agent.owner.pubkey,
+            user_token_account: agent.token_account,
+            reward_vault: platform.reward_vault,
+            reward_mint: platform.mint,
+            fee_schedule: platform.fee_schedule,
+            treasury: platform.treasury,
+            treasury_vault: treasury_vault_pda(&ontora_ai::ID, &platform.mint),
+            reward_vesting: reward_vesting_pda(&ontora_ai::ID, &agent.owner.pubkey),
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        },
+        ontora_ai::instruction::ClaimRewards {},
+    )
+}
+
+fn pending_rewards(ctx: &SvmContext, agent: &LegacyAgent) -> u64 {
+    fetch_account::<AIAgent>(ctx, &agent.agent).accumulated_rewards
+}
+
+fn pool_balance(ctx: &SvmContext, platform: &TestPlatform) -> u64 {
+    fetch_account::<RewardPool>(ctx, &platform.reward_pool).total_rewards
+}
+
+fn total_staked(ctx: &SvmContext, platform: &TestPlatform) -> u64 {
+    fetch_account::<PlatformConfig>(ctx, &platform.platform_config).total_staked
+}
+
+// The treasury vault only exists once the first fee has been routed
+fn treasury_vault_balance(ctx: &SvmContext, platform: &TestPlatform) -> u64 {
+    let vault = treasury_vault_pda(&ontora_ai::ID, &platform.mint);
+    ctx.svm.get_account(&vault).map_or(0, |_| get_token_balance(ctx, &vault))
+}
+
+// The accounting invariants every interleaving must preserve. `legacy` and
+// `agents` must cover every agent on the platform.
+fn assert_invariants(ctx: &SvmContext, platform: &TestPlatform, legacy: &[&LegacyAgent], agents: &[Pubkey]) {
+    let config = fetch_account::<PlatformConfig>(ctx, &platform.platform_config);
+    let pool = fetch_account::<RewardPool>(ctx, &platform.reward_pool);
+    let treasury = fetch_account::<Treasury>(ctx, &platform.treasury);
+
+    // The stake vault and the legacy stake hold exactly the recorded stake, which the agents split between them
+    let legacy_stake: u64 = legacy.iter().map(|agent| fetch_account::<AIAgent>(ctx, &agent.agent).staked_amount).sum();
+    assert_eq!(get_token_balance(ctx, &platform.platform_vault) + legacy_stake, config.total_staked);
+    let agent_stake: u64 = agents.iter().map(|agent| fetch_account::<AiAgent>(ctx, agent).staked_amount).sum();
+    assert_eq!(agent_stake + legacy_stake, config.total_staked);
+
+    // The reward vault holds the undistributed pool plus every pending reward,
+    // and each legacy vault holds its agent's stake
+    let pending: u64 = legacy.iter().map(|agent| pending_rewards(ctx, agent)).sum();
+    assert_eq!(get_token_balance(ctx, &platform.reward_vault), pool.total_rewards + pending);
+    for agent in legacy {
+        let staked = fetch_account::<AIAgent>(ctx, &agent.agent).staked_amount;
+        assert_eq!(get_token_balance(ctx, &agent.staking_vault), staked);
+    }
+
+    // The treasury vault holds every recorded fee
+    let deposits: u64 = treasury.deposits_by_source.iter().sum();
+    assert_eq!(treasury_vault_balance(ctx, platform), deposits - treasury.total_outflows);
+
+    // An underflow would surface as a value near u64::MAX
+    for value in [config.total_staked, pool.total_rewards, pending, deposits] {
+        assert!(value < u64::MAX / 2, "balance {} looks like a wrapped subtraction", value);
+    }
+}
+
+// Send `instructions` and check that the TreasuryDeposit events they emit are
+// `expected`, in order, and add up to what the treasury recorded per source
+fn send_and_check_fees(
+    ctx: &mut SvmContext,
+    platform: &TestPlatform,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+    expected: &[(FeeSource, u64)],
+) -> TransactionMetadata {
+    let before = fetch_account::<Treasury>(ctx, &platform.treasury);
+    let meta = send_transaction(ctx, instructions, signers).unwrap();
+    let after = fetch_account::<Treasury>(ctx, &platform.treasury);
+
+    let deposits: Vec<(FeeSource, u64)> = parse_events::<TreasuryDeposit>(&meta.logs)
+        .into_iter()
+        .map(|event| (event.source, event.amount))
+        .collect();
+    assert_eq!(deposits, expected);
+    for source in [FeeSource::Unstake, FeeSource::Claim, FeeSource::Subscription] {
+        let emitted: u64 = deposits.iter().filter(|(from, _)| *from == source).map(|(_, amount)| amount).sum();
+        let recorded = after.deposits_by_source[source.index()] - before.deposits_by_source[source.index()];
+        assert_eq!(emitted, recorded, "{:?} events do not match the treasury", source);
+    }
+    meta
+}
+
+// Two legacy agents staking `TEST_STAKE_AMOUNT` and twice that, each credited
+// its share of one epoch's emission: 33_333_333 and 66_666_666
+fn two_rewarded_agents(ctx: &mut SvmContext, platform: &TestPlatform) -> (LegacyAgent, LegacyAgent) {
+    let first = register_legacy_agent(ctx, platform, TEST_STAKE_AMOUNT);
+    let second = register_legacy_agent(ctx, platform, 2 * TEST_STAKE_AMOUNT);
+    warp_seconds(ctx, EPOCH_DURATION);
+    send_transaction(ctx, &[distribute_ix(platform, &first)], &[&platform.admin.keypair]).unwrap();
+    send_transaction(ctx, &[distribute_ix(platform, &second)], &[&platform.admin.keypair]).unwrap();
+    (first, second)
+}
+
+// Test a partial unstake and a claim by the same user in one transaction
+#[test]
+fn test_claim_right_after_partial_unstake() {
+    let (mut ctx, platform) = setup();
+    let legacy = register_legacy_agent(&mut ctx, &platform, TEST_STAKE_AMOUNT);
+    mint_tokens(&mut ctx, &platform.mint, &platform.admin, &legacy.token_account, 2 * TEST_STAKE_AMOUNT);
+    let agent = create_mock_ai_agent(&mut ctx, &legacy.owner, AGENT_ID);
+    let stake = stake_ix(&platform, &legacy.owner, legacy.token_account, 2 * TEST_STAKE_AMOUNT);
+    send_transaction(&mut ctx, &[stake], &[&legacy.owner.keypair]).unwrap();
+    warp_seconds(&mut ctx, EPOCH_DURATION);
+    send_transaction(&mut ctx, &[distribute_ix(&platform, &legacy)], &[&platform.admin.keypair]).unwrap();
+    assert_invariants(&ctx, &platform, &[&legacy], &[agent]);
+    // The legacy stake is a third of the platform's
+    let reward = share(TEST_STAKE_AMOUNT, 3 * TEST_STAKE_AMOUNT);
+
+    let unstaked = TEST_STAKE_AMOUNT / 2;
+    send_and_check_fees(
+        &mut ctx,
+        &platform,
+        &[unstake_ix(&platform, &legacy.owner, legacy.token_account, unstaked), claim_ix(&platform, &legacy)],
+        &[&legacy.owner.keypair],
+        &[(FeeSource::Unstake, fee(unstaked, UNSTAKE_FEE_BPS)), (FeeSource::Claim, fee(reward, CLAIM_FEE_BPS))],
+    );
+
+    // 500_000_000 - 12_500_000 unstaked plus 33_333_333 - 333_333 claimed
+    assert_eq!(get_token_balance(&ctx, &legacy.token_account), 520_500_000);
+    assert_eq!(total_staked(&ctx, &platform), 3 * TEST_STAKE_AMOUNT - unstaked);
+    assert_eq!(pending_rewards(&ctx, &legacy), 0);
+    assert_eq!(pool_balance(&ctx, &platform), TEST_REWARD_POOL_AMOUNT - reward);
+    assert_invariants(&ctx, &platform, &[&legacy], &[agent]);
+}
+
+// Test two users claiming in the same transaction
+#[test]
+fn test_two_users_claim_in_one_transaction() {
+    let (mut ctx, platform) = setup();
+    let (first, second) = two_rewarded_agents(&mut ctx, &platform);
+
+    send_and_check_fees(
+        &mut ctx,
+        &platform,
+        &[claim_ix(&platform, &first), claim_ix(&platform, &second)],
+        &[&first.owner.keypair, &second.owner.keypair],
+        &[(FeeSource::Claim, 333_333), (FeeSource::Claim, 666_666)],
+    );
+
+    assert_eq!(get_token_balance(&ctx, &first.token_account), 33_000_000);
+    assert_eq!(get_token_balance(&ctx, &second.token_account), 66_000_000);
+    assert_eq!(pool_balance(&ctx, &platform), TEST_REWARD_POOL_AMOUNT - 99_999_999);
+    assert_eq!(treasury_vault_balance(&ctx, &platform), 999_999);
+    assert_invariants(&ctx, &platform, &[&first, &second], &[]);
+}
+
+// Test two users claiming in separate transactions landing in the same slot
+#[test]
+fn test_two_users_claim_in_same_slot() {
+    let (mut ctx, platform) = setup();
+    let (first, second) = two_rewarded_agents(&mut ctx, &platform);
+    let slot = get_clock(&ctx).slot;
+
+    let claim = claim_ix(&platform, &second);
+    send_and_check_fees(&mut ctx, &platform, &[claim], &[&second.owner.keypair], &[(FeeSource::Claim, 666_666)]);
+    assert_invariants(&ctx, &platform, &[&first, &second], &[]);
+    let claim = claim_ix(&platform, &first);
+    send_and_check_fees(&mut ctx, &platform, &[claim], &[&first.owner.keypair], &[(FeeSource::Claim, 333_333)]);
+
+    assert_eq!(get_clock(&ctx).slot, slot);
+    assert_eq!(get_token_balance(&ctx, &first.token_account), 33_000_000);
+    assert_eq!(get_token_balance(&ctx, &second.token_account), 66_000_000);
+    assert_eq!(get_token_balance(&ctx, &platform.reward_vault), TEST_REWARD_POOL_AMOUNT - 99_999_999);
+    assert_invariants(&ctx, &platform, &[&first, &second], &[]);
+}
+
+// Test a stake landing in the same transaction as a reward distribution: the
+// distribution shares the emission with the new stake, which is fully vaulted
+#[test]
+fn test_stake_in_same_transaction_as_distribution() {
+    let (mut ctx, platform) = setup();
+    let legacy = register_legacy_agent(&mut ctx, &platform, TEST_STAKE_AMOUNT);
+    warp_seconds(&mut ctx, EPOCH_DURATION);
+    let staker = new_staker(&mut ctx, &platform, 2 * TEST_STAKE_AMOUNT);
+
+    send_and_check_fees(
+        &mut ctx,
+        &platform,
+        &[stake_ix(&platform, &staker.user, staker.token_account, TEST_STAKE_AMOUNT), distribute_ix(&platform, &legacy)],
+        &[&staker.user.keypair, &platform.admin.keypair],
+        &[],
+    );
+
+    assert_eq!(pending_rewards(&ctx, &legacy), share(TEST_STAKE_AMOUNT, 2 * TEST_STAKE_AMOUNT));
+    assert_eq!(total_staked(&ctx, &platform), 2 * TEST_STAKE_AMOUNT);
+    assert_eq!(get_token_balance(&ctx, &staker.token_account), TEST_STAKE_AMOUNT);
+    assert_invariants(&ctx, &platform, &[&legacy], &[staker.agent]);
+}
+
+// Test registering an agent and distributing to it in one transaction.
+// Distribution pays on the stake present when it executes, so the new agent
+// earns the whole epoch's reward.
+#[test]
+fn test_register_and_distribute_in_one_transaction() {
+    let (mut ctx, platform) = setup();
+    warp_seconds(&mut ctx, EPOCH_DURATION);
+    let owner = create_test_user(&mut ctx);
+    let token_account = create_funded_token_account(&mut ctx, &platform, &owner.pubkey, TEST_STAKE_AMOUNT);
+    let agent = legacy_ai_agent_pda(&ontora_ai::ID, &owner.pubkey);
+    let staking_vault = create_token_account(&mut ctx, &agent, &platform.mint);
+    let legacy = LegacyAgent { owner, agent, token_account, staking_vault };
+
+    send_and_check_fees(
+        &mut ctx,
+        &platform,
+        &[register_legacy_instruction(&platform, &legacy, TEST_STAKE_AMOUNT), distribute_ix(&platform, &legacy)],
+        &[&legacy.owner.keypair, &platform.admin.keypair],
+        &[],
+    );
+
+    assert_eq!(pending_rewards(&ctx, &legacy), EPOCH_REWARD);
+    assert_eq!(get_token_balance(&ctx, &legacy.staking_vault), TEST_STAKE_AMOUNT);
+    assert_eq!(pool_balance(&ctx, &platform), TEST_REWARD_POOL_AMOUNT - EPOCH_REWARD);
+    assert_invariants(&ctx, &platform, &[&legacy], &[]);
+}
+
+// Test that two agents are paid in one transaction, and that paying one of them
+// again in the same epoch fails the whole transaction and keeps failing in the
+// next slot
+#[test]
+fn test_second_distribution_in_same_epoch() {
+    let (mut ctx, platform) = setup();
+    let first = register_legacy_agent(&mut ctx, &platform, TEST_STAKE_AMOUNT);
+    let second = register_legacy_agent(&mut ctx, &platform, TEST_STAKE_AMOUNT);
+    let third = register_legacy_agent(&mut ctx, &platform, 2 * TEST_STAKE_AMOUNT);
+    warp_seconds(&mut ctx, EPOCH_DURATION);
+
+    send_transaction(
+        &mut ctx,
+        &[distribute_ix(&platform, &first), distribute_ix(&platform, &second)],
+        &[&platform.admin.keypair],
+    )
+    .unwrap();
+    let reward = share(TEST_STAKE_AMOUNT, 4 * TEST_STAKE_AMOUNT);
+    assert_eq!((pending_rewards(&ctx, &first), pending_rewards(&ctx, &second)), (reward, reward));
+
+    let result = send_transaction(
+        &mut ctx,
+        &[distribute_ix(&platform, &third), distribute_ix(&platform, &first)],
+        &[&platform.admin.keypair],
+    );
+    assert_eq!(svm_error_code(&result), Some(u32::from(OntoraError::AlreadyDistributedThisEpoch)));
+    assert_eq!(pending_rewards(&ctx, &third), 0);
+    assert_eq!(pool_balance(&ctx, &platform), TEST_REWARD_POOL_AMOUNT - 2 * reward);
+
+    advance_slot(&mut ctx, 1);
+    let result = send_transaction(&mut ctx, &[distribute_ix(&platform, &second)], &[&platform.admin.keypair]);
+    assert_eq!(svm_error_code(&result), Some(u32::from(OntoraError::AlreadyDistributedThisEpoch)));
+
+    assert_eq!(pending_rewards(&ctx, &second), reward);
+    assert_eq!(pool_balance(&ctx, &platform), TEST_REWARD_POOL_AMOUNT - 2 * reward);
+    assert_invariants(&ctx, &platform, &[&first, &second, &third], &[]);
+}
+
+// Test staking and fully unstaking in one transaction: the index entry is
+// closed again and only the unstake fee is lost
+#[test]
+fn test_stake_and_full_unstake_in_one_transaction() {
+    let (mut ctx, platform) = setup();
+    let staker = new_staker(&mut ctx, &platform, 2 * TEST_STAKE_AMOUNT);
+
+    send_and_check_fees(
+        &mut ctx,
+        &platform,
+        &[stake_ix(&platform, &staker.user, staker.token_account, TEST_STAKE_AMOUNT), unstake_ix(&platform, &staker.user, staker.token_account, TEST_STAKE_AMOUNT)],
+        &[&staker.user.keypair],
+        &[(FeeSource::Unstake, 25_000_000)],
+    );
+
+    assert!(ctx.svm.get_account(&stake_index_pda(&ontora_ai::ID, &staker.user.pubkey, AGENT_ID)).is_none());
+    assert_eq!(fetch_account::<UserStake>(&ctx, &user_stake_pda(&ontora_ai::ID, &staker.user.pubkey)).staked_amount, 0);
+    assert_eq!(get_token_balance(&ctx, &staker.token_account), 1_975_000_000);
+    assert_eq!(total_staked(&ctx, &platform), 0);
+    assert_invariants(&ctx, &platform, &[], &[staker.agent]);
+}
+
+// Test a full unstake followed by a restake in the next slot: the index entry
+// is recreated with the new stake time
+#[test]
+fn test_full_unstake_then_restake_in_adjacent_slots() {
+    let (mut ctx, platform) = setup();
+    let staker = new_staker(&mut ctx, &platform, 2 * TEST_STAKE_AMOUNT);
+    let stake_index = stake_index_pda(&ontora_ai::ID, &staker.user.pubkey, AGENT_ID);
+    send_transaction(&mut ctx, &[stake_ix(&platform, &staker.user, staker.token_account, TEST_STAKE_AMOUNT)], &[&staker.user.keypair]).unwrap();
+
+    advance_slot(&mut ctx, 1);
+    send_and_check_fees(
+        &mut ctx,
+        &platform,
+        &[unstake_ix(&platform, &staker.user, staker.token_account, TEST_STAKE_AMOUNT)],
+        &[&staker.user.keypair],
+        &[(FeeSource::Unstake, 25_000_000)],
+    );
+    assert!(ctx.svm.get_account(&stake_index).is_none());
+    assert_invariants(&ctx, &platform, &[], &[staker.agent]);
+
+    let restaked_at = warp_seconds(&mut ctx, 1);
+    send_transaction(&mut ctx, &[stake_ix(&platform, &staker.user, staker.token_account, TEST_STAKE_AMOUNT)], &[&staker.user.keypair]).unwrap();
+
+    let entry = fetch_account::<StakeIndexEntry>(&ctx, &stake_index);
+    assert_eq!(entry.staked_amount, TEST_STAKE_AMOUNT);
+    assert_eq!(entry.staked_at, restaked_at);
+    assert_eq!(get_token_balance(&ctx, &staker.token_account), 975_000_000);
+    assert_eq!(total_staked(&ctx, &platform), TEST_STAKE_AMOUNT);
+    assert_invariants(&ctx, &platform, &[], &[staker.agent]);
+}
+
+// Test a claim followed by the next epoch's distribution in one transaction:
+// the claim pays the old epoch and the new reward stays pending
+#[test]
+fn test_claim_then_distribute_in_one_transaction() {
+    let (mut ctx, platform) = setup();
+    let legacy = register_legacy_agent(&mut ctx, &platform, TEST_STAKE_AMOUNT);
+    warp_seconds(&mut ctx, EPOCH_DURATION);
+    send_transaction(&mut ctx, &[distribute_ix(&platform, &legacy)], &[&platform.admin.keypair]).unwrap();
+    warp_seconds(&mut ctx, EPOCH_DURATION);
+
+    send_and_check_fees(
+        &mut ctx,
+        &platform,
+        &[claim_ix(&platform, &legacy), distribute_ix(&platform, &legacy)],
+        &[&legacy.owner.keypair, &platform.admin.keypair],
+        &[(FeeSource::Claim, 1_000_000)],
+    );
+
+    assert_eq!(get_token_balance(&ctx, &legacy.token_account), 99_000_000);
+    assert_eq!(pending_rewards(&ctx, &legacy), EPOCH_REWARD);
+    assert_eq!(pool_balance(&ctx, &platform), TEST_REWARD_POOL_AMOUNT - 2 * EPOCH_REWARD);
+    assert_eq!(get_token_balance(&ctx, &platform.reward_vault), TEST_REWARD_POOL_AMOUNT - EPOCH_REWARD);
+    assert_invariants(&ctx, &platform, &[&legacy], &[]);
+}
+
+// Test the next epoch's distribution followed by a claim in one transaction:
+// the claim pays both epochs
+#[test]
+fn test_distribute_then_claim_in_one_transaction() {
+    let (mut ctx, platform) = setup();
+    let legacy = register_legacy_agent(&mut ctx, &platform, TEST_STAKE_AMOUNT);
+    warp_seconds(&mut ctx, EPOCH_DURATION);
+    send_transaction(&mut ctx, &[distribute_ix(&platform, &legacy)], &[&platform.admin.keypair]).unwrap();
+    warp_seconds(&mut ctx, EPOCH_DURATION);
+
+    send_and_check_fees(
+        &mut ctx,
+        &platform,
+        &[distribute_ix(&platform, &legacy), claim_ix(&platform, &legacy)],
+        &[&platform.admin.keypair, &legacy.owner.keypair],
+        &[(FeeSource::Claim, 2_000_000)],
+    );
+
+    assert_eq!(get_token_balance(&ctx, &legacy.token_account), 198_000_000);
+    assert_eq!(pending_rewards(&ctx, &legacy), 0);
+    assert_eq!(get_token_balance(&ctx, &platform.reward_vault), TEST_REWARD_POOL_AMOUNT - 2 * EPOCH_REWARD);
+    assert_invariants(&ctx, &platform, &[&legacy], &[]);
+}
+
+// Test that claiming again in the same slot fails and moves nothing
+#[test]
+fn test_repeated_claim_in_same_slot() {
+    let (mut ctx, platform) = setup();
+    let legacy = register_legacy_agent(&mut ctx, &platform, TEST_STAKE_AMOUNT);
+    warp_seconds(&mut ctx, EPOCH_DURATION);
+    send_transaction(&mut ctx, &[distribute_ix(&platform, &legacy)], &[&platform.admin.keypair]).unwrap();
+    send_transaction(&mut ctx, &[claim_ix(&platform, &legacy)], &[&legacy.owner.keypair]).unwrap();
+
+    let result = send_transaction(&mut ctx, &[claim_ix(&platform, &legacy)], &[&legacy.owner.keypair]);
+    assert_eq!(svm_error_code(&result), Some(u32::from(OntoraError::RewardPoolDepleted)));
+    assert_eq!(get_token_balance(&ctx, &legacy.token_account), 99_000_000);
+    assert_eq!(treasury_vault_balance(&ctx, &platform), 1_000_000);
+    assert_invariants(&ctx, &platform, &[&legacy], &[]);
+}
+
+// Test that two partial unstakes in one transaction cannot together exceed the
+// stake: the second fails, the first is rolled back, and nothing wraps
+#[test]
+fn test_partial_unstakes_exceeding_stake_in_one_transaction() {
+    let (mut ctx, platform) = setup();
+    let staker = new_staker(&mut ctx, &platform, 2 * TEST_STAKE_AMOUNT);
+    send_transaction(&mut ctx, &[stake_ix(&platform, &staker.user, staker.token_account, 2 * TEST_STAKE_AMOUNT)], &[&staker.user.keypair]).unwrap();
+
+    let result = send_transaction(
+        &mut ctx,
+        &[
+            unstake_ix(&platform, &staker.user, staker.token_account, TEST_STAKE_AMOUNT),
+            unstake_ix(&platform, &staker.user, staker.token_account, TEST_STAKE_AMOUNT + 1),
+        ],
+        &[&staker.user.keypair],
+    );
+    assert_eq!(svm_error_code(&result), Some(u32::from(OntoraError::InvalidStakeAmount)));
+
+    let entry = fetch_account::<StakeIndexEntry>(&ctx, &stake_index_pda(&ontora_ai::ID, &staker.user.pubkey, AGENT_ID));
+    assert_eq!(entry.staked_amount, 2 * TEST_STAKE_AMOUNT);
+    assert_eq!(get_token_balance(&ctx, &staker.token_account), 0);
+    assert_eq!(treasury_vault_balance(&ctx, &platform), 0);
+    assert_invariants(&ctx, &platform, &[], &[staker.agent]);
+}
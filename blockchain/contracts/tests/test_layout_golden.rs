@@ -0,0 +1,367 @@
+// test_layout_golden.rs
+// This module snapshots the on-chain byte layouts. A canonical instance of
+// every account type and the args of every instruction are serialized and
+// compared, as hex, with tests/golden/account_layouts.txt and
+// tests/golden/instruction_layouts.txt. Each entry leads with its Anchor
+// discriminator. Field values are distinct so that swapping two fields of the
+// same type also shows up in the diff. An intended layout change needs a
+// migration; after adding one, regenerate the snapshots with
+//     UPDATE_LAYOUT_GOLDENS=1 cargo test --test test_layout_golden
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::{Discriminator, InstructionData};
+use bytemuck::Zeroable;
+use pretty_assertions::assert_eq;
+
+// Import test setup utilities (assumes test_setup.rs is in the same directory)
+mod test_setup;
+use test_setup::*;
+
+use ontora_ai::state::{
+    AdminAction, AdminActionProposal, AdminMultisig, AiAgent, ConfigParams, FeeSchedule, Metadata, PlatformConfig,
+    Proposal, ProposalAction, Role, Roles, StakeIndexEntry, Treasury, UserStake, VoteRecord, PROPOSAL_ACTIVE,
+    PROPOSAL_VERSION,
+};
+use ontora_ai::{AIAgent, GovernanceProposal, PendingWithdrawal, RewardPool, RewardVesting, LEGACY_AI_AGENT_VERSION};
+
+const ACCOUNT_GOLDEN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden/account_layouts.txt");
+const INSTRUCTION_GOLDEN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden/instruction_layouts.txt");
+
+// Bytes per hex row, so a diff points at the offset that moved
+const ROW_BYTES: usize = 32;
+
+// Fixed, recognizable key: every byte is `seed`
+fn key(seed: u8) -> Pubkey {
+    Pubkey::new_from_array([seed; 32])
+}
+
+fn account_bytes<T: AccountSerialize>(value: &T) -> Vec<u8> {
+    let mut data = Vec::new();
+    value.try_serialize(&mut data).unwrap();
+    data
+}
+
+fn canonical_accounts() -> Vec<(&'static str, [u8; 8], Vec<u8>)> {
+    let config = PlatformConfig {
+        admin: key(1),
+        reward_rate_bps: 500,
+        min_stake_amount: 1_000_000_000,
+        epoch_duration: 604_800,
+        last_reward_timestamp: 1_700_000_000,
+        total_staked: 42_000_000_000,
+        bump: 254,
+        pending_admin: key(2),
+        pause_flags: 0b101,
+        pauser: key(3),
+        version: 3,
+        roles: Roles { crank: key(4), oracle: key(5), treasury: key(6) },
+        program_version: [1, 2, 3],
+        governance_enabled: true,
+        proposal_count: 7,
+        active_proposal_count: 2,
+        cooldown_seconds: 86_400,
+        epoch_emission: 500_000_000,
+        acc_reward_per_share: 3_000_000_000_000_000,
+        total_agents: 19,
+        stake_mint: key(19),
+        staking_vault: key(20),
+        reward_vault: key(21),
+        max_stake_per_agent: 50_000_000_000,
+        vesting_seconds: 2_592_000,
+        vote_unit: 100_000_000_000,
+        quorum_bps: 1_000,
+        approval_threshold_bps: 5_000,
+        payout_epoch: 2_811,
+        payout_total_staked: 40_000_000_000,
+        payout_paid: 125_000_000,
+    };
+    let multisig = AdminMultisig { signers: vec![key(7), key(8), key(9)], threshold: 2, proposal_count: 11, bump: 253 };
+    let admin_action = AdminActionProposal {
+        multisig: key(10),
+        id: 12,
+        action: AdminAction::SetRole { role: Role::Oracle, holder: key(11) },
+        proposer: key(7),
+        approvals: vec![key(7), key(8)],
+        expires_at: 1_700_086_400,
+        executed: false,
+        bump: 252,
+    };
+    let fee_schedule = FeeSchedule {
+        governance: key(12),
+        rates_bps: [10, 20, 30, 40],
+        pending_rates_bps: [15, 25, 35, 45],
+        pending_effective_at: 1_700_604_800,
+        bump: 251,
+    };
+    let treasury = Treasury { governance: key(12), deposits_by_source: [100, 200, 300], total_outflows: 50, bump: 250 };
+
+    let mut proposal = Proposal::zeroed();
+    proposal.set_contents(
+        "Raise reward rate",
+        "Raise the reward rate to 6%",
+        &["Yes".to_string(), "No".to_string(), "Abstain".to_string()],
+    );
+    proposal.version = PROPOSAL_VERSION;
+    proposal.status = PROPOSAL_ACTIVE;
+    proposal.bump = 249;
+    proposal.id = 13;
+    proposal.creator = key(13);
+    proposal.start_time = 1_700_000_000;
+    proposal.end_time = 1_700_259_200;
+    proposal.votes[..3].copy_from_slice(&[5, 3, 1]);
+    proposal.snapshot_total_staked = 90_000_000_000;
+    proposal.quorum_bps = 1_000;
+    proposal.approval_threshold_bps = 5_000;
+    proposal.snapshot_slot = 250_000_000;
+    proposal.set_action(&ProposalAction::UpdateRewardRate { bps: 600 }).unwrap();
+    proposal.vote_unit = 100_000_000_000;
+
+    let agent = AiAgent {
+        version: 1,
+        agent_id: 14,
+        owner: key(14),
+        name: "Atlas".to_string(),
+        description: "Market-making agent".to_string(),
+        staked_amount: 3_000_000_000,
+        performance_score: 8_750,
+        created_at: 1_699_000_000,
+        bump: 248,
+        score_updated_at: 1_700_000_000,
+    };
+    let stake = UserStake {
+        version: 1,
+        user: key(15),
+        staked_amount: 2_000_000_000,
+        accumulated_rewards: 150_000_000,
+        last_stake_update: 1_699_500_000,
+        last_reward_claim: 1_699_800_000,
+        bump: 247,
+        reward_debt: 300_000_000_000_000_000_000,
+        stake_changed_at: 1_699_400_000,
+        funded_stake: 500_000_000,
+        funded_at: 1_699_500_000,
+    };
+    let entry = StakeIndexEntry {
+        user: key(15),
+        agent_id: 14,
+        staked_amount: 2_000_000_000,
+        staked_at: 1_699_500_000,
+        bump: 246,
+    };
+    let vote = VoteRecord {
+        proposal_id: 13,
+        voter: key(15),
+        vote_option: 1,
+        vote_weight: 20,
+        voted_at: 1_700_100_000,
+        bump: 239,
+    };
+    let metadata = Metadata {
+        entity_id: 16,
+        data: "{\"model\":\"atlas-v2\"}".to_string(),
+        updated_at: 1_700_000_000,
+        bump: 245,
+    };
+    let legacy_agent = AIAgent {
+        version: LEGACY_AI_AGENT_VERSION,
+        owner: key(17),
+        staked_amount: 5_000_000_000,
+        last_stake_time: 1_699_900_000,
+        accumulated_rewards: 500_000_000,
+        is_active: true,
+        bump: 244,
+        last_reward_epoch: 2_811,
+    };
+    let legacy_proposal = GovernanceProposal {
+        proposer: key(18),
+        description: "Lower the cooldown".to_string(),
+        yes_votes: 9,
+        no_votes: 4,
+        start_time: 1_700_000_000,
+        end_time: 1_700_604_800,
+        is_active: true,
+        bump: 243,
+    };
+    let pool = RewardPool { total_rewards: 1_000_000_000_000, last_updated: 1_700_000_000, bump: 242 };
+    let withdrawal =
+        PendingWithdrawal { owner: key(17), amount: 2_500_000_000, release_time: 1_699_986_400, bump: 241 };
+    let vesting = RewardVesting {
+        owner: key(23),
+        total_amount: 3_000_000_000,
+        released_amount: 750_000_000,
+        start_time: 1_700_000_000,
+        duration: 2_592_000,
+        bump: 240,
+    };
+
+    vec![
+        ("PlatformConfig", PlatformConfig::discriminator(), account_bytes(&config)),
+        ("AdminMultisig", AdminMultisig::discriminator(), account_bytes(&multisig)),
+        ("AdminActionProposal", AdminActionProposal::discriminator(), account_bytes(&admin_action)),
+        ("FeeSchedule", FeeSchedule::discriminator(), account_bytes(&fee_schedule)),
+        ("Treasury", Treasury::discriminator(), account_bytes(&treasury)),
+        ("Proposal", Proposal::discriminator(), zero_copy_bytes(&proposal)),
+        ("AiAgent", AiAgent::discriminator(), account_bytes(&agent)),
+        ("UserStake", UserStake::discriminator(), account_bytes(&stake)),
+        ("StakeIndexEntry", StakeIndexEntry::discriminator(), account_bytes(&entry)),
+        ("VoteRecord", VoteRecord::discriminator(), account_bytes(&vote)),
+        ("Metadata", Metadata::discriminator(), account_bytes(&metadata)),
+        ("AIAgent", AIAgent::discriminator(), account_bytes(&legacy_agent)),
+        ("GovernanceProposal", GovernanceProposal::discriminator(), account_bytes(&legacy_proposal)),
+        ("RewardPool", RewardPool::discriminator(), account_bytes(&pool)),
+        ("PendingWithdrawal", PendingWithdrawal::discriminator(), account_bytes(&withdrawal)),
+        ("RewardVesting", RewardVesting::discriminator(), account_bytes(&vesting)),
+    ]
+}
+
+fn canonical_instructions() -> Vec<(&'static str, [u8; 8], Vec<u8>)> {
+    use ontora_ai::instruction as ix;
+
+    macro_rules! entry {
+        ($name:ident $args:tt) => {
+            (stringify!($name), ix::$name::discriminator(), ix::$name $args.data())
+        };
+    }
+
+    vec![
+        entry!(InitializeRewardPool { initial_rewards: 1_000_000_000_000 }),
+        entry!(FundRewardPool { amount: 250_000_000_000 }),
+        entry!(RegisterAiAgent { stake_amount: 5_000_000_000 }),
+        entry!(IncreaseStake { amount: 1_000_000_000 }),
+        entry!(UnstakeAiAgent {}),
+        entry!(UnstakePartial { amount: 2_000_000_000 }),
+        entry!(EmergencyUnstake {}),
+        entry!(RequestUnstake { amount: 2_500_000_000 }),
+        entry!(ClaimUnstake {}),
+        entry!(CloseAgent {}),
+        entry!(DistributeRewards {}),
+        entry!(DistributeRewardsBatch { close_epoch: true }),
+        entry!(ClaimRewards {}),
+        entry!(ReleaseVested {}),
+        entry!(CreateProposal { description: "Lower the cooldown".to_string(), duration: 604_800 }),
+        entry!(VoteProposal { in_favor: true }),
+        entry!(InitializePlatform {
+            reward_rate_bps: 500,
+            min_stake_amount: 1_000_000_000,
+            epoch_duration: 604_800,
+            cooldown_seconds: 172_800,
+            epoch_emission: 1_000_000_000,
+        }),
+        entry!(InitializeVaults {}),
+        entry!(UpdatePlatformConfig {
+            params: ConfigParams {
+                reward_rate_bps: 600,
+                min_stake_amount: 2_000_000_000,
+                epoch_duration: 86_400,
+                cooldown_seconds: 43_200,
+                epoch_emission: 2_000_000_000,
+                max_stake_per_agent: 20_000_000_000,
+                vesting_seconds: 1_296_000,
+                vote_unit: 50_000_000_000,
+                quorum_bps: 1_500,
+                approval_threshold_bps: 6_000,
+            },
+            update_metadata: "bump rate".to_string(),
+        }),
+        entry!(MigratePlatformConfig {}),
+        entry!(MigrateAccount {}),
+        entry!(MigrateAgentV1 {}),
+        entry!(GetVersion {}),
+        entry!(CreateGovernanceProposal {
+            title: "Raise reward rate".to_string(),
+            description: "Raise the reward rate to 6%".to_string(),
+            voting_duration: 259_200,
+            options: vec!["Yes".to_string(), "No".to_string(), "Abstain".to_string()],
+            action: ProposalAction::UpdateRewardRate { bps: 600 },
+        }),
+        entry!(CastVote { proposal_id: 13, vote_option: 1 }),
+        entry!(FinalizeProposal { proposal_id: 13 }),
+        entry!(ExecuteProposal {}),
+        entry!(SetGovernanceEnabled { enabled: false }),
+        entry!(ProposeAdmin { new_admin: key(2) }),
+        entry!(AcceptAdmin {}),
+        entry!(CancelAdminTransfer {}),
+        entry!(InitializeTreasury { governance: key(12) }),
+        entry!(InitializeFeeSchedule { governance: key(12), rates_bps: [10, 20, 30, 40] }),
+        entry!(UpdateFeeSchedule { rates_bps: [15, 25, 35, 45] }),
+        entry!(TreasuryBalance {}),
+        entry!(TreasuryTransfer { amount: 75_000 }),
+        entry!(SetPauseFlags { flags: 0b100 }),
+        entry!(ClearPauseFlags { flags: 0b001 }),
+        entry!(SetRole { role: Role::Oracle, holder: key(5) }),
+        entry!(RenounceRole { role: Role::Crank }),
+        entry!(UpdatePerformanceScore { score: 8_750 }),
+        entry!(RegisterAgent { agent_id: 14, name: "Atlas".to_string(), description: "Market-making agent".to_string() }),
+        entry!(UpdateAgentMetadata { agent_id: 14, name: "Atlas II".to_string(), description: "Arbitrage agent".to_string() }),
+        entry!(StakeOnAgent { agent_id: 14, amount: 2_000_000_000, beneficiary: key(22) }),
+        entry!(UnstakeFromAgent { agent_id: 14, amount: 500_000_000 }),
+        entry!(ClaimStakeRewards {}),
+        entry!(CreateMetadata { entity_id: 16, data: "{\"model\":\"atlas-v2\"}".to_string() }),
+        entry!(UpdateMetadata { entity_id: 16, data: "{\"model\":\"atlas-v3\"}".to_string() }),
+        entry!(InitializeAdminMultisig { signers: vec![key(7), key(8), key(9)], threshold: 2 }),
+        entry!(ProposeAdminAction {
+            action: AdminAction::UpdateConfig { reward_rate_bps: 700, min_stake_amount: 3_000_000_000, epoch_duration: 1_209_600 },
+        }),
+        entry!(ApproveAdminAction {}),
+        entry!(ExecuteAdminAction {}),
+        entry!(ExecuteInitializeVaults {}),
+    ]
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn render_golden(header: &str, entries: &[(&'static str, [u8; 8], Vec<u8>)]) -> String {
+    let mut text = format!("# {}\n", header);
+    for (name, discriminator, bytes) in entries {
+        text.push_str(&format!("\n{} discriminator={} len={}\n", name, hex(discriminator), bytes.len()));
+        for (row, chunk) in bytes.chunks(ROW_BYTES).enumerate() {
+            text.push_str(&format!("    {:04x}: {}\n", row * ROW_BYTES, hex(chunk)));
+        }
+    }
+    text
+}
+
+// Compare against the snapshot, or rewrite it when UPDATE_LAYOUT_GOLDENS is set
+fn check_golden(path: &str, rendered: &str) {
+    if std::env::var_os("UPDATE_LAYOUT_GOLDENS").is_some() {
+        std::fs::write(path, rendered).unwrap();
+        return;
+    }
+    let golden = std::fs::read_to_string(path).unwrap_or_default();
+    assert_eq!(
+        golden, rendered,
+        "{} is out of date; if the layout change is intended, add a migration and regenerate with UPDATE_LAYOUT_GOLDENS=1",
+        path
+    );
+}
+
+// Test that every serialized entry leads with its own discriminator
+#[test]
+fn test_entries_lead_with_discriminator() {
+    for (name, discriminator, bytes) in canonical_accounts().into_iter().chain(canonical_instructions()) {
+        assert_eq!(&bytes[..8], &discriminator[..], "{} does not start with its discriminator", name);
+    }
+}
+
+// Test that account layouts and discriminators match the snapshot
+#[test]
+fn test_account_layouts_match_golden() {
+    let rendered = render_golden(
+        "Canonical account bytes (discriminator + Borsh or zero-copy body). See tests/test_layout_golden.rs.",
+        &canonical_accounts(),
+    );
+    check_golden(ACCOUNT_GOLDEN_PATH, &rendered);
+}
+
+// Test that instruction data layouts and discriminators match the snapshot
+#[test]
+fn test_instruction_layouts_match_golden() {
+    let rendered = render_golden(
+        "Canonical instruction data (discriminator + Borsh args). See tests/test_layout_golden.rs.",
+        &canonical_instructions(),
+    );
+    check_golden(INSTRUCTION_GOLDEN_PATH, &rendered);
+}
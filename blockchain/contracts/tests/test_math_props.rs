@@ -0,0 +1,194 @@
+// test_math_props.rs
+// This module contains property-based tests of the reward and fee math in
+// src/math.rs over the full input ranges. It only builds with the `proptests`
+// feature, so the default test run stays fast:
+//     cargo test --features proptests --test test_math_props
+// Failing cases shrink to a minimal counterexample, which proptest records in a
+// proptest-regressions file so later runs replay it first.
+#![cfg(feature = "proptests")]
+
+use anchor_lang::solana_program::pubkey::Pubkey;
+use proptest::prelude::*;
+
+use ontora_ai::error::OntoraError;
+use ontora_ai::math::{
+    bps_of, cooldown_remaining, elapsed_epochs, epoch_rewards, pro_rata_payout, split_fee, ACC_PRECISION,
+    BPS_DENOMINATOR,
+};
+use ontora_ai::state::{FeeSchedule, FeeType, PlatformConfig, UserStake, MAX_REWARD_RATE_BPS};
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(512))]
+
+    // None of the helpers panic, whatever the input
+    #[test]
+    fn no_input_panics(amount: u64, bps: u64, fee_bps: u16, since: i64, now: i64, duration: i64, epochs: u64) {
+        let _ = bps_of(amount, bps);
+        let _ = elapsed_epochs(since, now, duration);
+        let _ = epoch_rewards(amount, bps, epochs);
+        let _ = split_fee(amount, fee_bps);
+        let _ = pro_rata_payout(amount, bps, epochs, amount);
+        let _ = cooldown_remaining(since, duration, now);
+    }
+
+    // The user's net plus the fee is always exactly the gross amount
+    #[test]
+    fn fee_split_is_exact(amount: u64, fee_bps in 0..=BPS_DENOMINATOR as u16) {
+        let (net, fee) = split_fee(amount, fee_bps).unwrap();
+        prop_assert_eq!(net as u128 + fee as u128, amount as u128);
+        prop_assert!(fee as u128 * BPS_DENOMINATOR as u128 <= amount as u128 * fee_bps as u128);
+    }
+
+    // Rates above 100% are refused rather than underflowing
+    #[test]
+    fn fee_above_whole_is_refused(amount in 1..=u64::MAX, fee_bps in (BPS_DENOMINATOR as u16 + 1)..=u16::MAX) {
+        let fee_exceeds_amount = amount as u128 * fee_bps as u128 / BPS_DENOMINATOR as u128 > amount as u128;
+        prop_assert_eq!(split_fee(amount, fee_bps).is_none(), fee_exceeds_amount);
+    }
+
+    // The schedule's fee split matches the math module at every capped rate
+    #[test]
+    fn schedule_fee_matches_split(amount: u64, rate in 0..=FeeType::Unstake.max_bps()) {
+        let mut schedule = FeeSchedule::default();
+        let mut rates = [0; FeeType::COUNT];
+        rates[FeeType::Unstake.index()] = rate;
+        schedule.init(Pubkey::default(), rates, 255);
+        prop_assert_eq!(schedule.apply_fee(amount, FeeType::Unstake).unwrap(), split_fee(amount, rate).unwrap());
+    }
+
+    // Splitting an amount into parts and summing their rounded results loses at
+    // most one unit per extra part, and never gains
+    #[test]
+    fn splitting_loses_at_most_one_per_part(
+        parts in prop::collection::vec(0..=u64::MAX / 16, 1..16),
+        bps in 0..=BPS_DENOMINATOR,
+    ) {
+        let total: u64 = parts.iter().sum();
+        let whole = bps_of(total, bps).unwrap();
+        let summed: u64 = parts.iter().map(|part| bps_of(*part, bps).unwrap()).sum();
+        prop_assert!(summed <= whole);
+        prop_assert!(whole - summed < parts.len() as u64);
+    }
+
+    // Rewards for two spans of epochs add up to the reward for the combined span
+    #[test]
+    fn epoch_rewards_are_additive(staked: u64, rate_bps in 0..=MAX_REWARD_RATE_BPS, first in 0..1_000_000u64, second in 0..1_000_000u64) {
+        let combined = epoch_rewards(staked, rate_bps, first + second);
+        let split = epoch_rewards(staked, rate_bps, first)
+            .zip(epoch_rewards(staked, rate_bps, second))
+            .and_then(|(first, second)| first.checked_add(second));
+        prop_assert_eq!(combined, split);
+    }
+
+    // Elapsed epochs are the whole epochs in the span, and zero for a clock that went backwards
+    #[test]
+    fn elapsed_epochs_brackets_the_span(since in -(1i64 << 40)..(1i64 << 40), span in -(1i64 << 40)..(1i64 << 40), duration in 1..=1i64 << 32) {
+        let epochs = elapsed_epochs(since, since + span, duration).unwrap() as i64;
+        if span < 0 {
+            prop_assert_eq!(epochs, 0);
+        } else {
+            prop_assert!(epochs * duration <= span);
+            prop_assert!(span < (epochs + 1) * duration);
+        }
+    }
+
+    // A payout never exceeds the pool it is drawn from, nor the emission
+    #[test]
+    fn payouts_never_exceed_the_pool(emission: u64, staked: u64, total_staked: u64, pool: u64) {
+        match pro_rata_payout(emission, staked, total_staked, pool) {
+            Ok(reward) => {
+                prop_assert!(reward <= pool);
+                prop_assert!(reward <= emission);
+            }
+            Err(error) => {
+                // A share never exceeds the emission, so only a short pool fails
+                let owed = emission as u128 * staked.min(total_staked) as u128 / total_staked as u128;
+                prop_assert!(owed > pool as u128);
+                prop_assert_eq!(error, anchor_lang::error::Error::from(OntoraError::RewardPoolDepleted));
+            }
+        }
+    }
+
+    // The agents' shares of an epoch never add up to more than its emission,
+    // and an agent without stake gets nothing
+    #[test]
+    fn shares_never_exceed_the_emission(emission: u64, stakes in prop::collection::vec(0..=u64::MAX / 64, 1..32)) {
+        let total_staked: u64 = stakes.iter().sum();
+        let mut paid = 0u128;
+        for staked in &stakes {
+            let share = pro_rata_payout(emission, *staked, total_staked, u64::MAX).unwrap();
+            if *staked == 0 {
+                prop_assert_eq!(share, 0);
+            }
+            paid += share as u128;
+        }
+        prop_assert!(paid <= emission as u128);
+        // Each share rounds down by less than one unit
+        prop_assert!(paid + stakes.len() as u128 > emission as u128 || total_staked == 0);
+    }
+
+    // Pending rewards are the accumulated balance plus what the stake earned by
+    // the second since the accumulator started, or an arithmetic error; they
+    // never shrink as time passes
+    #[test]
+    fn pending_rewards_grow_with_time(
+        staked: u64,
+        accumulated: u64,
+        rate_bps in 0..=MAX_REWARD_RATE_BPS,
+        duration in 1..=1i64 << 32,
+        started in 0..(1i64 << 40),
+        elapsed in 0..(1i64 << 40),
+    ) {
+        let mut config = PlatformConfig::default();
+        config.init(Pubkey::default(), rate_bps, 1, duration, 86_400, 100_000_000, 1_000, 255);
+        config.last_reward_timestamp = started;
+        let mut stake = UserStake::default();
+        stake.init(Pubkey::default(), 255);
+        stake.staked_amount = staked;
+        stake.accumulated_rewards = accumulated;
+
+        let now = started + elapsed;
+        let per_epoch = rate_bps as u128 * ACC_PRECISION / BPS_DENOMINATOR as u128;
+        let growth = per_epoch * elapsed as u128 / duration as u128;
+        let expected = (staked as u128)
+            .checked_mul(growth)
+            .and_then(|reward| u64::try_from(reward / ACC_PRECISION).ok())
+            .and_then(|reward| reward.checked_add(accumulated));
+        match stake.pending_rewards(&config, now) {
+            Ok(pending) => {
+                prop_assert_eq!(Some(pending), expected);
+                if let Ok(later) = stake.pending_rewards(&config, now + duration) {
+                    prop_assert!(later >= pending);
+                }
+            }
+            Err(error) => {
+                prop_assert_eq!(expected, None);
+                prop_assert_eq!(error, anchor_lang::error::Error::from(OntoraError::ArithmeticError));
+            }
+        }
+    }
+
+    // Settling part way through never pays more than settling once at the end,
+    // and loses at most a unit to rounding
+    #[test]
+    fn settling_midway_pays_the_same(
+        staked in 0..=u64::MAX / 1_000,
+        rate_bps in 0..=MAX_REWARD_RATE_BPS,
+        duration in 1..=1i64 << 32,
+        first in 0..(1i64 << 24),
+        second in 0..(1i64 << 24),
+    ) {
+        let mut config = PlatformConfig::default();
+        config.init(Pubkey::default(), rate_bps, 1, duration, 86_400, 100_000_000, 1_000, 255);
+        let mut once = UserStake::default();
+        once.settle(staked, config.accrue(0).unwrap()).unwrap();
+        let mut twice = once.clone();
+
+        twice.settle(staked, config.accrue(first).unwrap()).unwrap();
+        let acc_reward_per_share = config.accrue(first + second).unwrap();
+        twice.settle(staked, acc_reward_per_share).unwrap();
+        once.settle(staked, acc_reward_per_share).unwrap();
+        prop_assert!(twice.accumulated_rewards <= once.accumulated_rewards);
+        prop_assert!(twice.accumulated_rewards + 1 >= once.accumulated_rewards);
+    }
+}
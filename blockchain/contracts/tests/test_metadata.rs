@@ -0,0 +1,113 @@
+// test_metadata.rs
+// This module contains test cases for metadata accounts that resize to their
+// payload. Transactions here are paid for by a separate fee payer, so the
+// authority's balance moves by exactly the rent difference.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::solana_program::system_program;
+use anchor_client::Program;
+use solana_program_test::*;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::Transaction;
+
+// Import test setup utilities (assumes test_setup.rs is in the same directory)
+mod test_setup;
+use test_setup::*;
+
+use ontora_ai::error::OntoraError;
+use ontora_ai::pda;
+use ontora_ai::state::{Metadata, MAX_DESCRIPTION_LENGTH, MAX_METADATA_LENGTH};
+
+const ENTITY_ID: u64 = 42;
+
+fn metadata_pda(authority: &Pubkey, entity_id: u64) -> Pubkey {
+    Pubkey::find_program_address(&[pda::METADATA_SEED, authority.as_ref(), &entity_id.to_le_bytes()], &id()).0
+}
+
+// Send a metadata instruction signed by `authority` with `fee_payer` covering the fee
+async fn send_metadata_tx(
+    ctx: &mut TestContext,
+    program: &Program,
+    authority: &TestUser,
+    data: String,
+    create: bool,
+) -> std::result::Result<(), BanksClientError> {
+    let metadata = metadata_pda(&authority.pubkey, ENTITY_ID);
+    let request = program.request();
+    let request = if create {
+        request
+            .accounts(ontora_ai::accounts::CreateMetadata {
+                metadata,
+                authority: authority.pubkey,
+                system_program: system_program::ID,
+            })
+            .args(ontora_ai::instruction::CreateMetadata { entity_id: ENTITY_ID, data })
+    } else {
+        request
+            .accounts(ontora_ai::accounts::UpdateMetadata {
+                metadata,
+                authority: authority.pubkey,
+                system_program: system_program::ID,
+            })
+            .args(ontora_ai::instruction::UpdateMetadata { entity_id: ENTITY_ID, data })
+    };
+    let instructions = request.instructions().unwrap();
+    let blockhash = ctx.banks_client.get_latest_blockhash().await.unwrap();
+    let signers: [&Keypair; 2] = [&ctx.payer, &authority.keypair];
+    let tx = Transaction::new_signed_with_payer(&instructions, Some(&ctx.payer.pubkey()), &signers, blockhash);
+    ctx.banks_client.process_transaction(tx).await
+}
+
+// Test that the payload ceiling is enforced in bytes
+#[test]
+fn test_metadata_ceiling() {
+    assert!(MAX_METADATA_LENGTH > MAX_DESCRIPTION_LENGTH);
+    assert_eq!(Metadata::space_for(MAX_DESCRIPTION_LENGTH), Metadata::SPACE);
+    assert!(Metadata::validate_data(&"m".repeat(MAX_METADATA_LENGTH)).is_ok());
+
+    // A full-ceiling payload can't fit in one transaction, so the limit is checked directly
+    let error = Metadata::validate_data(&"m".repeat(MAX_METADATA_LENGTH + 1)).unwrap_err();
+    assert_eq!(error, OntoraError::MetadataTooLarge.into());
+}
+
+// Test that updates grow and shrink the account, charging and refunding the rent difference
+#[tokio::test]
+async fn test_update_metadata_resizes_account() {
+    let (mut ctx, program) = setup_test_context().await;
+    let authority = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
+    let metadata = metadata_pda(&authority.pubkey, ENTITY_ID);
+    let rent = ctx.banks_client.get_rent().await.unwrap();
+
+    send_metadata_tx(&mut ctx, &program, &authority, "v1".to_string(), true).await.unwrap();
+    let account = ctx.banks_client.get_account(metadata).await.unwrap().unwrap();
+    assert_eq!(account.data.len(), Metadata::space_for(2));
+
+    // Grow past the old fixed size; the authority pays exactly the rent difference
+    let grown = "g".repeat(MAX_DESCRIPTION_LENGTH * 3);
+    let before = get_account_balance(&mut ctx.banks_client, &authority.pubkey).await;
+    send_metadata_tx(&mut ctx, &program, &authority, grown.clone(), false).await.unwrap();
+    let after = get_account_balance(&mut ctx.banks_client, &authority.pubkey).await;
+    let account = ctx.banks_client.get_account(metadata).await.unwrap().unwrap();
+    assert_eq!(account.data.len(), Metadata::space_for(grown.len()));
+    assert_eq!(
+        before - after,
+        rent.minimum_balance(Metadata::space_for(grown.len())) - rent.minimum_balance(Metadata::space_for(2))
+    );
+    let stored = Metadata::try_deserialize(&mut account.data.as_ref()).unwrap();
+    assert_eq!(stored.data, grown);
+
+    // Shrink back down; the excess rent is refunded to the authority
+    let before = after;
+    send_metadata_tx(&mut ctx, &program, &authority, "s".to_string(), false).await.unwrap();
+    let after = get_account_balance(&mut ctx.banks_client, &authority.pubkey).await;
+    let account = ctx.banks_client.get_account(metadata).await.unwrap().unwrap();
+    assert_eq!(account.data.len(), Metadata::space_for(1));
+    assert_eq!(account.lamports, rent.minimum_balance(Metadata::space_for(1)));
+    assert_eq!(
+        after - before,
+        rent.minimum_balance(Metadata::space_for(grown.len())) - rent.minimum_balance(Metadata::space_for(1))
+    );
+    let stored = Metadata::try_deserialize(&mut account.data.as_ref()).unwrap();
+    assert_eq!(stored.data, "s");
+}
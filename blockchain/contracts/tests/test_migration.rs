@@ -0,0 +1,465 @@
+// test_migration.rs
+// This module contains test cases for account versioning: golden layouts that
+// pin each type's version field, in-place upgrades of pre-versioning bytes,
+// the generic migrate_account instruction, and migrate_agent_v1 for legacy agents.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::solana_program::system_program;
+use anchor_client::Program;
+use solana_program_test::*;
+use solana_sdk::account::Account as SolanaAccount;
+use solana_sdk::commitment_config::CommitmentLevel;
+use solana_sdk::signature::{Keypair, Signer};
+use bytemuck::Zeroable;
+use std::rc::Rc;
+
+// Import test setup utilities (assumes test_setup.rs is in the same directory)
+mod test_setup;
+use test_setup::*;
+
+use ontora_ai::error::OntoraError;
+use ontora_ai::pda;
+use ontora_ai::state::{
+    AiAgent, PlatformConfig, Proposal, ProposalAction, StakeIndexEntry, UserStake, AI_AGENT_VERSION,
+    DEFAULT_QUORUM_BPS, PLATFORM_CONFIG_VERSION, PROPOSAL_VERSION, USER_STAKE_VERSION,
+};
+use ontora_ai::{AIAgent, Versioned, LEGACY_AI_AGENT_VERSION, MIGRATED_AGENT_ID};
+
+fn serialize<T: AccountSerialize>(value: &T) -> Vec<u8> {
+    let mut data = Vec::new();
+    value.try_serialize(&mut data).unwrap();
+    data
+}
+
+// Bytes of `value` as they were stored before the leading version byte existed
+fn pre_versioning_bytes<T: AccountSerialize>(value: &T, current_space: usize) -> Vec<u8> {
+    let mut data = serialize(value);
+    data.remove(8);
+    data.resize(current_space - 1, 0);
+    data
+}
+
+// Run `migrate_in_place` the way `upgrade_account` does: grow, zero-fill, migrate
+fn migrate_bytes<T: Versioned + AccountDeserialize>(mut data: Vec<u8>) -> (Option<u16>, T) {
+    let stored_len = data.len();
+    data.resize(T::CURRENT_SPACE.max(stored_len), 0);
+    let from_version = T::migrate_in_place(&mut data, stored_len).unwrap();
+    (from_version, T::try_deserialize(&mut data.as_ref()).unwrap())
+}
+
+// Size of a v1 AiAgent, before `score_updated_at` was appended
+const AI_AGENT_V1_SPACE: usize = AiAgent::SPACE - 8;
+
+// Size of a v1 UserStake, which still held up to 10 `staked_agents`
+const USER_STAKE_V1_SPACE: usize = 158;
+
+// Bytes of a v1 UserStake holding `agents`, or of a pre-versioning one
+fn legacy_user_stake_bytes(stake: &UserStake, agents: &[u64], versioned: bool) -> Vec<u8> {
+    let mut data = UserStake::discriminator().to_vec();
+    if versioned {
+        data.push(1);
+    }
+    (
+        stake.user,
+        stake.staked_amount,
+        stake.accumulated_rewards,
+        agents.to_vec(),
+        stake.last_stake_update,
+        stake.last_reward_claim,
+        stake.bump,
+    )
+        .serialize(&mut data)
+        .unwrap();
+    data.resize(USER_STAKE_V1_SPACE - usize::from(!versioned), 0);
+    data
+}
+
+fn sample_agent(owner: Pubkey) -> AiAgent {
+    let mut agent = AiAgent::default();
+    agent.init(7, owner, "Ontora-Alpha".to_string(), "Forecasting agent".to_string(), 1_700_000_000, 254);
+    agent.staked_amount = 5 * TEST_STAKE_AMOUNT;
+    agent.performance_score = 88;
+    agent
+}
+
+// Golden layouts: the version field must never move
+#[test]
+fn test_version_field_offsets() {
+    assert_eq!(<AiAgent as Versioned>::VERSION_OFFSET, 8);
+    assert_eq!(<UserStake as Versioned>::VERSION_OFFSET, 8);
+    assert_eq!(<Proposal as Versioned>::VERSION_OFFSET, 8);
+    assert_eq!(<PlatformConfig as Versioned>::VERSION_OFFSET, 146);
+    assert_eq!(<AIAgent as Versioned>::VERSION_OFFSET, 8);
+
+    let agent = serialize(&sample_agent(Pubkey::new_unique()));
+    assert_eq!(agent[8], AI_AGENT_VERSION);
+    assert_eq!(serialize(&legacy_agent(Pubkey::new_unique(), 1))[8], LEGACY_AI_AGENT_VERSION);
+
+    let mut stake = UserStake::default();
+    stake.init(Pubkey::new_unique(), 253);
+    assert_eq!(serialize(&stake)[8], USER_STAKE_VERSION);
+
+    let mut proposal = Proposal::zeroed();
+    proposal.version = PROPOSAL_VERSION;
+    proposal.id = u64::MAX;
+    let proposal = zero_copy_bytes(&proposal);
+    assert_eq!(proposal.len(), Proposal::LEN);
+    assert_eq!(proposal[8], PROPOSAL_VERSION);
+
+    let mut config = PlatformConfig::default();
+    config.init(
+        Pubkey::new_unique(),
+        100,
+        TEST_STAKE_AMOUNT,
+        86_400,
+        86_400,
+        TEST_EPOCH_EMISSION,
+        255,
+    );
+    let offset = <PlatformConfig as Versioned>::VERSION_OFFSET;
+    assert_eq!(serialize(&config)[offset..offset + 2], PLATFORM_CONFIG_VERSION.to_le_bytes());
+}
+
+// Test that pre-versioning bytes shift right and keep every field
+#[test]
+fn test_migrate_in_place_inserts_version() {
+    let agent = sample_agent(Pubkey::new_unique());
+    let (from_version, migrated) = migrate_bytes::<AiAgent>(pre_versioning_bytes(&agent, AI_AGENT_V1_SPACE));
+    assert_eq!(from_version, Some(0));
+    assert_eq!(migrated.version, AI_AGENT_VERSION);
+    assert_eq!(migrated.agent_id, agent.agent_id);
+    assert_eq!(migrated.owner, agent.owner);
+    assert_eq!(migrated.name, agent.name);
+    assert_eq!(migrated.description, agent.description);
+    assert_eq!(migrated.staked_amount, agent.staked_amount);
+    assert_eq!(migrated.performance_score, agent.performance_score);
+    assert_eq!(migrated.bump, agent.bump);
+    assert_eq!(migrated.score_updated_at, 0);
+
+    // A v1 agent only gains the trailing `score_updated_at`
+    let mut v1 = serialize(&agent);
+    v1[8] = 1;
+    v1.resize(AI_AGENT_V1_SPACE, 0);
+    let (from_version, migrated) = migrate_bytes::<AiAgent>(v1);
+    assert_eq!(from_version, Some(1));
+    assert_eq!(migrated.version, AI_AGENT_VERSION);
+    assert_eq!(migrated.description, agent.description);
+    assert_eq!(migrated.created_at, agent.created_at);
+    assert_eq!(migrated.score_updated_at, 0);
+
+    // A v1 legacy agent had neither the version nor `last_reward_epoch`, and was never paid in an epoch
+    let legacy = AIAgent { last_reward_epoch: 9, ..legacy_agent(Pubkey::new_unique(), TEST_STAKE_AMOUNT) };
+    let mut v1 = serialize(&legacy);
+    v1.remove(8);
+    v1.truncate(v1.len() - 8);
+    let (from_version, migrated) = migrate_bytes::<AIAgent>(v1);
+    assert_eq!(from_version, Some(1));
+    assert_eq!(migrated.version, LEGACY_AI_AGENT_VERSION);
+    assert_eq!(migrated.owner, legacy.owner);
+    assert_eq!(migrated.staked_amount, legacy.staked_amount);
+    assert_eq!(migrated.accumulated_rewards, legacy.accumulated_rewards);
+    assert_eq!(migrated.bump, legacy.bump);
+    assert_eq!(migrated.last_reward_epoch, 0);
+    assert_eq!(migrate_bytes::<AIAgent>(serialize(&legacy)).0, None);
+
+    // UserStake v0 and v1 both drop their staked_agents list on the way to v4
+    let mut stake = UserStake::default();
+    stake.init(Pubkey::new_unique(), 253);
+    stake.staked_amount = TEST_STAKE_AMOUNT;
+    stake.accumulated_rewards = 17;
+    stake.last_stake_update = 1_700_000_000;
+    stake.last_reward_claim = 1_700_000_100;
+    for (versioned, expected_from) in [(false, 0), (true, 1)] {
+        let data = legacy_user_stake_bytes(&stake, &[1, 2, 3], versioned);
+        let (from_version, migrated) = migrate_bytes::<UserStake>(data);
+        assert_eq!(from_version, Some(expected_from));
+        assert_eq!(migrated.version, USER_STAKE_VERSION);
+        assert_eq!(migrated.user, stake.user);
+        assert_eq!(migrated.staked_amount, stake.staked_amount);
+        assert_eq!(migrated.accumulated_rewards, stake.accumulated_rewards);
+        assert_eq!(migrated.last_stake_update, stake.last_stake_update);
+        assert_eq!(migrated.last_reward_claim, stake.last_reward_claim);
+        assert_eq!(migrated.bump, stake.bump);
+        assert_eq!(migrated.reward_debt, 0);
+        assert_eq!(migrated.stake_changed_at, stake.last_stake_update);
+    }
+    // A v2 stake only gains the trailing `reward_debt` and the v4 fields
+    let funded = UserStake { reward_debt: 99, stake_changed_at: 5, funded_stake: 6, funded_at: 7, ..stake.clone() };
+    let mut v2 = serialize(&funded);
+    v2[8] = 2;
+    v2.truncate(v2.len() - 16 - 24);
+    let (from_version, migrated) = migrate_bytes::<UserStake>(v2);
+    assert_eq!(from_version, Some(2));
+    assert_eq!(migrated.version, USER_STAKE_VERSION);
+    assert_eq!((migrated.staked_amount, migrated.last_reward_claim), (stake.staked_amount, stake.last_reward_claim));
+    assert_eq!(migrated.reward_debt, 0);
+    assert_eq!(migrated.stake_changed_at, stake.last_stake_update);
+    // A v3 stake keeps its `reward_debt` and takes its last update as its own change
+    let mut v3 = serialize(&funded);
+    v3[8] = 3;
+    v3.truncate(v3.len() - 24);
+    let (from_version, migrated) = migrate_bytes::<UserStake>(v3);
+    assert_eq!(from_version, Some(3));
+    assert_eq!(migrated.reward_debt, 99);
+    assert_eq!(migrated.stake_changed_at, stake.last_stake_update);
+    assert_eq!((migrated.funded_stake, migrated.funded_at), (0, 0));
+    let mut current = serialize(&stake);
+    current.resize(UserStake::SPACE, 0);
+    assert_eq!(migrate_bytes::<UserStake>(current).0, None);
+
+    // A v1 proposal gains a zero stake snapshot, quorum, threshold, snapshot slot and vote
+    // unit, no action, and keeps its tallies
+    let mut proposal = Proposal::zeroed();
+    proposal.version = 1;
+    proposal.id = 5;
+    proposal.votes[..2].copy_from_slice(&[3, 1]);
+    let mut data = zero_copy_bytes(&proposal);
+    let stored_len = Proposal::LEN - 48;
+    data[stored_len..].fill(0);
+    assert_eq!(Proposal::migrate_in_place(&mut data, stored_len).unwrap(), Some(1));
+    let migrated: Proposal = read_zero_copy(&data);
+    assert_eq!((migrated.version, migrated.id, migrated.votes), (PROPOSAL_VERSION, proposal.id, proposal.votes));
+    assert_eq!((migrated.snapshot_total_staked, migrated.quorum_bps, migrated.approval_threshold_bps), (0, 0, 0));
+    assert_eq!((migrated.snapshot_slot, migrated.vote_unit), (0, 0));
+    assert_eq!(migrated.action().unwrap(), ProposalAction::NoOp);
+    assert_eq!(Proposal::migrate_in_place(&mut data, Proposal::LEN).unwrap(), None);
+
+    // A v2 proposal keeps its snapshot and gains a zero snapshot slot
+    proposal.version = 2;
+    proposal.snapshot_total_staked = 90;
+    proposal.quorum_bps = DEFAULT_QUORUM_BPS;
+    let mut data = zero_copy_bytes(&proposal);
+    let stored_len = Proposal::LEN - 32;
+    data[stored_len..].fill(0);
+    assert_eq!(Proposal::migrate_in_place(&mut data, stored_len).unwrap(), Some(2));
+    let migrated: Proposal = read_zero_copy(&data);
+    assert_eq!(migrated.version, PROPOSAL_VERSION);
+    assert_eq!((migrated.snapshot_total_staked, migrated.quorum_bps), (90, DEFAULT_QUORUM_BPS));
+    assert_eq!(migrated.snapshot_slot, 0);
+
+    // A v3 proposal keeps its snapshot slot and executes as a no-op if it passes
+    proposal.version = 3;
+    proposal.snapshot_slot = 250_000_000;
+    let mut data = zero_copy_bytes(&proposal);
+    let stored_len = Proposal::LEN - 24;
+    data[stored_len..].fill(0);
+    assert_eq!(Proposal::migrate_in_place(&mut data, stored_len).unwrap(), Some(3));
+    let migrated: Proposal = read_zero_copy(&data);
+    assert_eq!((migrated.version, migrated.snapshot_slot), (PROPOSAL_VERSION, 250_000_000));
+    assert_eq!(migrated.action().unwrap(), ProposalAction::NoOp);
+
+    // A v4 proposal keeps its action and is weighed at the platform's vote unit
+    proposal.version = 4;
+    proposal.set_action(&ProposalAction::UpdateRewardRate { bps: 600 }).unwrap();
+    let mut data = zero_copy_bytes(&proposal);
+    let stored_len = Proposal::LEN - 8;
+    data[stored_len..].fill(0);
+    assert_eq!(Proposal::migrate_in_place(&mut data, stored_len).unwrap(), Some(4));
+    let migrated: Proposal = read_zero_copy(&data);
+    assert_eq!((migrated.version, migrated.vote_unit), (PROPOSAL_VERSION, 0));
+    assert_eq!(migrated.action().unwrap(), ProposalAction::UpdateRewardRate { bps: 600 });
+
+    // Current accounts are left alone
+    let mut current = serialize(&agent);
+    current.resize(AiAgent::SPACE, 0);
+    assert_eq!(migrate_bytes::<AiAgent>(current).0, None);
+}
+
+// Start a validator holding a pre-versioning AI agent and a funded payer
+async fn setup_legacy_agent(payer: &Keypair) -> (BanksClient, Program, Pubkey, AiAgent) {
+    let mut test = ProgramTest::new("ontora_ai", id(), processor!(ontora_ai::entry));
+    let agent = sample_agent(payer.pubkey());
+    let (agent_pda, _) = Pubkey::find_program_address(
+        &[pda::AI_AGENT_SEED, payer.pubkey().as_ref(), &agent.agent_id.to_le_bytes()],
+        &id(),
+    );
+    let data = pre_versioning_bytes(&agent, AI_AGENT_V1_SPACE);
+
+    test.add_account(
+        agent_pda,
+        SolanaAccount {
+            lamports: Rent::default().minimum_balance(data.len()),
+            data,
+            owner: id(),
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+    test.add_account(
+        payer.pubkey(),
+        SolanaAccount {
+            lamports: INITIAL_LAMPORTS,
+            data: vec![],
+            owner: system_program::ID,
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+
+    let (banks_client, _payer, _last_blockhash) = test.start().await;
+    let program = Program::new(id(), Rc::new(banks_client.clone()), CommitmentLevel::Confirmed);
+    (banks_client, program, agent_pda, agent)
+}
+
+async fn migrate_account(program: &Program, account: Pubkey, payer: &Keypair) -> std::result::Result<(), anchor_client::ClientError> {
+    program
+        .request()
+        .accounts(ontora_ai::accounts::MigrateAccount {
+            account,
+            payer: payer.pubkey(),
+            system_program: system_program::ID,
+        })
+        .args(ontora_ai::instruction::MigrateAccount {})
+        .signer(payer)
+        .send()
+        .await
+        .map(|_| ())
+}
+
+// Test that migrate_account grows a legacy agent, tops up rent, and refuses a second run
+#[tokio::test]
+async fn test_migrate_account_upgrades_legacy_agent() {
+    let payer = Keypair::new();
+    let (mut banks_client, program, agent_pda, agent) = setup_legacy_agent(&payer).await;
+
+    migrate_account(&program, agent_pda, &payer).await.unwrap();
+
+    let account = banks_client.get_account(agent_pda).await.unwrap().unwrap();
+    assert_eq!(account.data.len(), AiAgent::SPACE);
+    assert!(account.lamports >= Rent::default().minimum_balance(AiAgent::SPACE));
+    let migrated = AiAgent::try_deserialize(&mut account.data.as_ref()).unwrap();
+    assert_eq!(migrated.version, AI_AGENT_VERSION);
+    assert_eq!(migrated.name, agent.name);
+    assert_eq!(migrated.staked_amount, agent.staked_amount);
+
+    let error = migrate_account(&program, agent_pda, &payer).await.unwrap_err();
+    assert_eq!(custom_error_code(&error), Some(u32::from(OntoraError::AlreadyMigrated)));
+}
+
+// Test that current accounts and accounts of unknown types are refused
+#[tokio::test]
+async fn test_migrate_account_refuses_current_and_unknown() {
+    let (mut ctx, program) = setup_test_context().await;
+    let admin = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
+    let outsider = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
+    let platform_config = initialize_test_platform(&program, &admin).await;
+
+    // A current config has nothing to migrate, whoever asks
+    let error = migrate_account(&program, platform_config, &admin.keypair).await.unwrap_err();
+    assert_eq!(custom_error_code(&error), Some(u32::from(OntoraError::AlreadyMigrated)));
+
+    // Accounts of unknown types are rejected
+    let error = migrate_account(&program, outsider.pubkey, &outsider.keypair).await.unwrap_err();
+    assert_ne!(custom_error_code(&error), Some(u32::from(OntoraError::AlreadyMigrated)));
+}
+
+// A legacy agent of `owner` as `register_ai_agent` left it, staked and earning
+fn legacy_agent(owner: Pubkey, staked_amount: u64) -> AIAgent {
+    AIAgent {
+        version: LEGACY_AI_AGENT_VERSION,
+        owner,
+        staked_amount,
+        last_stake_time: 1_690_000_000,
+        accumulated_rewards: 37,
+        is_active: true,
+        bump: pda::legacy_ai_agent(&owner).1,
+        last_reward_epoch: 0,
+    }
+}
+
+async fn fetch<T: AccountDeserialize>(banks_client: &mut BanksClient, address: Pubkey) -> T {
+    let account = banks_client.get_account(address).await.unwrap().unwrap();
+    T::try_deserialize(&mut account.data.as_ref()).unwrap()
+}
+
+fn migrate_agent_v1(program: &Program, platform: &TestPlatform, owner: &Pubkey) -> ontora_ai::accounts::MigrateAgentV1 {
+    ontora_ai::accounts::MigrateAgentV1 {
+        platform_config: platform.platform_config,
+        legacy_agent: legacy_ai_agent_pda(&program.id(), owner),
+        ai_agent: ai_agent_pda(&program.id(), owner, MIGRATED_AGENT_ID),
+        user_stake: user_stake_pda(&program.id(), owner),
+        stake_index: stake_index_pda(&program.id(), owner, MIGRATED_AGENT_ID),
+        staking_vault: pda::legacy_staking_vault(owner, &platform.mint).0,
+        platform_vault: platform.platform_vault,
+        stake_mint: platform.mint,
+        admin: platform.admin.pubkey,
+        token_program: spl_token::ID,
+        system_program: system_program::ID,
+    }
+}
+
+// Test that migrate_agent_v1 carries each legacy agent's owner, stake and stake time onto
+// the unified layout, moves the staked tokens, and refuses a second run on the same agent
+#[tokio::test]
+async fn test_migrate_agent_v1_preserves_legacy_fields() {
+    let owners = [Pubkey::new_unique(), Pubkey::new_unique()];
+    let legacy = [legacy_agent(owners[0], 5 * TEST_STAKE_AMOUNT), legacy_agent(owners[1], 3)];
+    let mut test = program_test();
+    for agent in &legacy {
+        let data = serialize(agent);
+        assert_eq!(data.len(), 8 + AIAgent::INIT_SPACE);
+        test.add_account(
+            pda::legacy_ai_agent(&agent.owner).0,
+            SolanaAccount {
+                lamports: Rent::default().minimum_balance(data.len()),
+                data,
+                owner: id(),
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+    }
+    let (mut ctx, program) = start_test_context(test, id()).await;
+    let platform = initialize_program(&mut ctx, &program, 0).await;
+    for agent in &legacy {
+        let vault = create_token_account(&mut ctx, &pda::legacy_ai_agent(&agent.owner).0, &platform.mint).await;
+        mint_tokens(&mut ctx, &platform.mint, &platform.admin, &vault, agent.staked_amount).await;
+    }
+
+    for agent in &legacy {
+        program
+            .request()
+            .accounts(migrate_agent_v1(&program, &platform, &agent.owner))
+            .args(ontora_ai::instruction::MigrateAgentV1 {})
+            .signer(&platform.admin.keypair)
+            .send()
+            .await
+            .unwrap();
+
+        let banks_client = &mut ctx.banks_client;
+        let migrated: AiAgent = fetch(banks_client, ai_agent_pda(&program.id(), &agent.owner, MIGRATED_AGENT_ID)).await;
+        assert_eq!(migrated.version, AI_AGENT_VERSION);
+        assert_eq!(migrated.agent_id, MIGRATED_AGENT_ID);
+        assert_eq!(migrated.owner, agent.owner);
+        assert_eq!(migrated.staked_amount, agent.staked_amount);
+        assert_eq!(migrated.created_at, agent.last_stake_time);
+        assert_eq!(migrated.name, ontora_ai::MIGRATED_AGENT_NAME);
+        let stake: UserStake = fetch(banks_client, user_stake_pda(&program.id(), &agent.owner)).await;
+        assert_eq!((stake.user, stake.staked_amount), (agent.owner, agent.staked_amount));
+        assert_eq!(stake.last_stake_update, agent.last_stake_time);
+        let entry: StakeIndexEntry =
+            fetch(banks_client, stake_index_pda(&program.id(), &agent.owner, MIGRATED_AGENT_ID)).await;
+        assert_eq!((entry.agent_id, entry.staked_amount), (MIGRATED_AGENT_ID, agent.staked_amount));
+
+        // The emptied legacy agent keeps its rewards for a later claim
+        let emptied: AIAgent = fetch(banks_client, legacy_ai_agent_pda(&program.id(), &agent.owner)).await;
+        assert_eq!((emptied.staked_amount, emptied.is_active), (0, false));
+        assert_eq!(emptied.accumulated_rewards, agent.accumulated_rewards);
+        let vault = pda::legacy_staking_vault(&agent.owner, &platform.mint).0;
+        assert_eq!(get_token_balance(banks_client, &vault).await, 0);
+
+        let error = program
+            .request()
+            .accounts(migrate_agent_v1(&program, &platform, &agent.owner))
+            .args(ontora_ai::instruction::MigrateAgentV1 {})
+            .signer(&platform.admin.keypair)
+            .send()
+            .await;
+        assert!(error.is_err());
+    }
+    let staked: u64 = legacy.iter().map(|agent| agent.staked_amount).sum();
+    assert_eq!(get_token_balance(&mut ctx.banks_client, &platform.platform_vault).await, staked);
+    let config: PlatformConfig = fetch(&mut ctx.banks_client, platform.platform_config).await;
+    assert_eq!(config.total_staked, staked);
+}
@@ -0,0 +1,262 @@
+// test_multisig.rs
+// This module contains test cases for the threshold admin multisig: proposing,
+// approving, and executing admin actions, duplicate approvals, and expiry.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::solana_program::system_program;
+use anchor_client::Program;
+use solana_program_test::*;
+
+// Import test setup utilities (assumes test_setup.rs is in the same directory)
+mod test_setup;
+use test_setup::*;
+
+use ontora_ai::error::OntoraError;
+use ontora_ai::pda;
+use ontora_ai::state::{
+    AdminAction, AdminActionProposal, AdminMultisig, ConfigParams, PlatformConfig, ADMIN_ACTION_TTL, PAUSE_ALL,
+};
+
+fn admin_multisig_pda(program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[pda::ADMIN_MULTISIG_SEED], program_id).0
+}
+
+fn admin_action_pda(program_id: &Pubkey, proposal_id: u64) -> Pubkey {
+    Pubkey::find_program_address(&[pda::ADMIN_ACTION_SEED, &proposal_id.to_le_bytes()], program_id).0
+}
+
+// Initialize the platform and hand its admin role to a multisig over `signers`
+async fn setup_multisig(program: &Program, admin: &TestUser, signers: &[&TestUser], threshold: u8) -> Pubkey {
+    let platform_config = initialize_test_platform(program, admin).await;
+
+    program
+        .request()
+        .accounts(ontora_ai::accounts::InitializeAdminMultisig {
+            platform_config,
+            admin_multisig: admin_multisig_pda(&program.id()),
+            admin: admin.pubkey,
+            system_program: system_program::ID,
+        })
+        .args(ontora_ai::instruction::InitializeAdminMultisig {
+            signers: signers.iter().map(|s| s.pubkey).collect(),
+            threshold,
+        })
+        .signer(&admin.keypair)
+        .send()
+        .await
+        .unwrap();
+
+    platform_config
+}
+
+async fn propose(program: &Program, proposer: &TestUser, proposal_id: u64, action: AdminAction) {
+    program
+        .request()
+        .accounts(ontora_ai::accounts::ProposeAdminAction {
+            admin_multisig: admin_multisig_pda(&program.id()),
+            proposal: admin_action_pda(&program.id(), proposal_id),
+            proposer: proposer.pubkey,
+            system_program: system_program::ID,
+        })
+        .args(ontora_ai::instruction::ProposeAdminAction { action })
+        .signer(&proposer.keypair)
+        .send()
+        .await
+        .unwrap();
+}
+
+async fn approve(program: &Program, signer: &TestUser, proposal_id: u64) -> std::result::Result<(), anchor_client::ClientError> {
+    program
+        .request()
+        .accounts(ontora_ai::accounts::ApproveAdminAction {
+            admin_multisig: admin_multisig_pda(&program.id()),
+            proposal: admin_action_pda(&program.id(), proposal_id),
+            signer: signer.pubkey,
+        })
+        .args(ontora_ai::instruction::ApproveAdminAction {})
+        .signer(&signer.keypair)
+        .send()
+        .await
+        .map(|_| ())
+}
+
+async fn execute(program: &Program, platform_config: Pubkey, executor: &TestUser, proposal_id: u64) -> std::result::Result<(), anchor_client::ClientError> {
+    program
+        .request()
+        .accounts(ontora_ai::accounts::ExecuteAdminAction {
+            platform_config,
+            admin_multisig: admin_multisig_pda(&program.id()),
+            proposal: admin_action_pda(&program.id(), proposal_id),
+            executor: executor.pubkey,
+        })
+        .args(ontora_ai::instruction::ExecuteAdminAction {})
+        .signer(&executor.keypair)
+        .send()
+        .await
+        .map(|_| ())
+}
+
+async fn fetch<T: AccountDeserialize>(banks_client: &mut BanksClient, address: Pubkey) -> T {
+    let account = banks_client.get_account(address).await.unwrap().unwrap();
+    T::try_deserialize(&mut account.data.as_ref()).unwrap()
+}
+
+// Test a 2-of-3 multisig executing a config update
+#[tokio::test]
+async fn test_two_of_three_execution() {
+    let (mut ctx, program) = setup_test_context().await;
+    let admin = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
+    let alice = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
+    let bob = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
+    let carol = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
+    let platform_config = setup_multisig(&program, &admin, &[&alice, &bob, &carol], 2).await;
+
+    // The multisig now holds the admin role
+    let config: PlatformConfig = fetch(&mut ctx.banks_client, platform_config).await;
+    assert_eq!(config.admin, admin_multisig_pda(&program.id()));
+
+    propose(&program, &alice, 0, AdminAction::UpdateConfig {
+        reward_rate_bps: 250,
+        min_stake_amount: TEST_STAKE_AMOUNT,
+        epoch_duration: 86_400,
+    }).await;
+
+    // One approval (the proposer's) is not enough
+    assert!(execute(&program, platform_config, &alice, 0).await.is_err());
+
+    assert!(approve(&program, &bob, 0).await.is_ok());
+    assert!(execute(&program, platform_config, &carol, 0).await.is_ok());
+
+    let config: PlatformConfig = fetch(&mut ctx.banks_client, platform_config).await;
+    assert_eq!(config.reward_rate_bps, 250);
+
+    // An executed action cannot run twice
+    assert!(execute(&program, platform_config, &alice, 0).await.is_err());
+}
+
+// Test that the multisig can set the parameters added after UpdateConfig
+#[tokio::test]
+async fn test_update_params_execution() {
+    let (mut ctx, program) = setup_test_context().await;
+    let admin = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
+    let alice = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
+    let bob = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
+    let platform_config = setup_multisig(&program, &admin, &[&alice, &bob], 2).await;
+
+    let config: PlatformConfig = fetch(&mut ctx.banks_client, platform_config).await;
+    let params = ConfigParams {
+        cooldown_seconds: 3_600,
+        epoch_emission: 2 * TEST_EPOCH_EMISSION,
+        max_stake_per_agent: TEST_STAKE_AMOUNT * 5,
+        vesting_seconds: 604_800,
+        vote_unit: TEST_STAKE_AMOUNT / 4,
+        quorum_bps: 3_000,
+        approval_threshold_bps: 6_600,
+        ..config.params()
+    };
+    propose(&program, &alice, 0, AdminAction::UpdateParams { params }).await;
+    assert!(approve(&program, &bob, 0).await.is_ok());
+    assert!(execute(&program, platform_config, &bob, 0).await.is_ok());
+
+    let config: PlatformConfig = fetch(&mut ctx.banks_client, platform_config).await;
+    assert_eq!(config.params(), params);
+}
+
+// Test that the multisig creates the vaults through execute_initialize_vaults, and
+// that execute_admin_action refuses the action since it lacks the vault accounts
+#[tokio::test]
+async fn test_initialize_vaults_execution() {
+    let (mut ctx, program) = setup_test_context().await;
+    let admin = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
+    let alice = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
+    let bob = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
+    let platform_config = setup_multisig(&program, &admin, &[&alice, &bob], 2).await;
+    let stake_mint = create_mint(&mut ctx, &admin.pubkey).await;
+
+    propose(&program, &alice, 0, AdminAction::InitializeVaults { stake_mint }).await;
+    assert!(approve(&program, &bob, 0).await.is_ok());
+    let error = execute(&program, platform_config, &bob, 0).await.unwrap_err();
+    assert_eq!(custom_error_code(&error), Some(u32::from(OntoraError::InvalidAdminAction)));
+
+    let program_id = program.id();
+    let (staking_vault, reward_vault) = (staking_vault_pda(&program_id), reward_vault_pda(&program_id));
+    program
+        .request()
+        .accounts(ontora_ai::accounts::ExecuteInitializeVaults {
+            platform_config,
+            admin_multisig: admin_multisig_pda(&program_id),
+            proposal: admin_action_pda(&program_id, 0),
+            reward_pool: reward_pool_pda(&program_id),
+            stake_mint,
+            staking_vault,
+            reward_vault,
+            executor: bob.pubkey,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        })
+        .args(ontora_ai::instruction::ExecuteInitializeVaults {})
+        .signer(&bob.keypair)
+        .send()
+        .await
+        .unwrap();
+
+    let config: PlatformConfig = fetch(&mut ctx.banks_client, platform_config).await;
+    let vaults = (config.stake_mint, config.staking_vault, config.reward_vault);
+    assert_eq!(vaults, (stake_mint, staking_vault, reward_vault));
+    let proposal: AdminActionProposal = fetch(&mut ctx.banks_client, admin_action_pda(&program_id, 0)).await;
+    assert!(proposal.executed);
+}
+
+// Test that approving twice does not count twice
+#[tokio::test]
+async fn test_duplicate_approval_ignored() {
+    let (mut ctx, program) = setup_test_context().await;
+    let admin = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
+    let alice = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
+    let bob = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
+    let carol = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
+    let outsider = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
+    let platform_config = setup_multisig(&program, &admin, &[&alice, &bob, &carol], 2).await;
+
+    propose(&program, &alice, 0, AdminAction::SetPauseFlags { pause_flags: PAUSE_ALL }).await;
+
+    // Re-approving is accepted but ignored
+    assert!(approve(&program, &alice, 0).await.is_ok());
+    let proposal: AdminActionProposal = fetch(&mut ctx.banks_client, admin_action_pda(&program.id(), 0)).await;
+    assert_eq!(proposal.approvals, vec![alice.pubkey]);
+    assert!(execute(&program, platform_config, &alice, 0).await.is_err());
+
+    // Non-signers cannot approve
+    assert!(approve(&program, &outsider, 0).await.is_err());
+
+    let config: PlatformConfig = fetch(&mut ctx.banks_client, platform_config).await;
+    assert_eq!(config.pause_flags, 0);
+}
+
+// Test that an expired proposal can be neither approved nor executed
+#[test]
+fn test_expired_proposal() {
+    let created_at = 1_700_000_000;
+    let mut proposal = AdminActionProposal {
+        action: AdminAction::SetPauseFlags { pause_flags: PAUSE_ALL },
+        approvals: vec![Pubkey::new_unique()],
+        expires_at: created_at + ADMIN_ACTION_TTL,
+        ..Default::default()
+    };
+    assert!(proposal.approve(Pubkey::new_unique()));
+    assert!(proposal.is_executable(2, created_at + ADMIN_ACTION_TTL));
+    assert!(proposal.is_expired(created_at + ADMIN_ACTION_TTL + 1));
+    assert!(!proposal.is_executable(2, created_at + ADMIN_ACTION_TTL + 1));
+}
+
+// Test signer-set validation
+#[test]
+fn test_multisig_config_validation() {
+    let keys: Vec<Pubkey> = (0..8).map(|_| Pubkey::new_unique()).collect();
+    assert!(AdminMultisig::validate_config(&keys[..3], 2));
+    assert!(!AdminMultisig::validate_config(&keys[..3], 4));
+    assert!(!AdminMultisig::validate_config(&keys[..3], 0));
+    assert!(!AdminMultisig::validate_config(&keys, 2)); // more than 7 signers
+    assert!(!AdminMultisig::validate_config(&[keys[0], keys[0]], 1));
+}
@@ -0,0 +1,131 @@
+// test_pause.rs
+// This module contains test cases for granular pause flags and the pauser role:
+// each flag gating only its own operations, and the asymmetric set/clear permissions.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_client::Program;
+use solana_program_test::*;
+
+// Import test setup utilities (assumes test_setup.rs is in the same directory)
+mod test_setup;
+use test_setup::*;
+
+use ontora_ai::state::{
+    PlatformConfig, Role, PAUSE_ALL, PAUSE_CLAIMS, PAUSE_DEPOSITS, PAUSE_GOVERNANCE, PAUSE_ORACLE_UPDATES,
+    PAUSE_WITHDRAWALS,
+};
+
+const ALL_FLAGS: [u8; 5] = [PAUSE_DEPOSITS, PAUSE_WITHDRAWALS, PAUSE_CLAIMS, PAUSE_GOVERNANCE, PAUSE_ORACLE_UPDATES];
+
+async fn fetch_platform_config(banks_client: &mut BanksClient, platform_config: Pubkey) -> PlatformConfig {
+    let account = banks_client.get_account(platform_config).await.unwrap().unwrap();
+    PlatformConfig::try_deserialize(&mut account.data.as_ref()).unwrap()
+}
+
+async fn set_pauser(program: &Program, platform_config: Pubkey, admin: &TestUser, pauser: Pubkey) {
+    program
+        .request()
+        .accounts(ontora_ai::accounts::SetRole {
+            platform_config,
+            admin: admin.pubkey,
+        })
+        .args(ontora_ai::instruction::SetRole {
+            role: Role::Pauser,
+            holder: pauser,
+        })
+        .signer(&admin.keypair)
+        .send()
+        .await
+        .unwrap();
+}
+
+async fn set_flags(program: &Program, platform_config: Pubkey, authority: &TestUser, flags: u8) -> std::result::Result<(), anchor_client::ClientError> {
+    program
+        .request()
+        .accounts(ontora_ai::accounts::SetPauseFlags {
+            platform_config,
+            authority: authority.pubkey,
+        })
+        .args(ontora_ai::instruction::SetPauseFlags { flags })
+        .signer(&authority.keypair)
+        .send()
+        .await
+        .map(|_| ())
+}
+
+async fn clear_flags(program: &Program, platform_config: Pubkey, admin: &TestUser, flags: u8) -> std::result::Result<(), anchor_client::ClientError> {
+    program
+        .request()
+        .accounts(ontora_ai::accounts::ClearPauseFlags {
+            platform_config,
+            admin: admin.pubkey,
+        })
+        .args(ontora_ai::instruction::ClearPauseFlags { flags })
+        .signer(&admin.keypair)
+        .send()
+        .await
+        .map(|_| ())
+}
+
+// Test that each flag gates exactly its own operation group
+#[test]
+fn test_each_flag_gates_only_its_group() {
+    for flag in ALL_FLAGS {
+        let config = PlatformConfig {
+            pause_flags: flag,
+            ..Default::default()
+        };
+        for other in ALL_FLAGS {
+            assert_eq!(config.require_not_paused(other).is_err(), other == flag);
+        }
+    }
+
+    let config = PlatformConfig {
+        pause_flags: PAUSE_ALL,
+        ..Default::default()
+    };
+    assert!(ALL_FLAGS.iter().all(|flag| config.require_not_paused(*flag).is_err()));
+}
+
+// Test that the pauser can set bits but only the admin can clear them
+#[tokio::test]
+async fn test_pauser_sets_admin_clears() {
+    let (mut ctx, program) = setup_test_context().await;
+    let admin = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
+    let pauser = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
+    let outsider = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
+    let platform_config = initialize_test_platform(&program, &admin).await;
+    set_pauser(&program, platform_config, &admin, pauser.pubkey).await;
+
+    // Pause claims only; deposits stay open
+    assert!(set_flags(&program, platform_config, &pauser, PAUSE_CLAIMS).await.is_ok());
+    let config = fetch_platform_config(&mut ctx.banks_client, platform_config).await;
+    assert_eq!(config.pause_flags, PAUSE_CLAIMS);
+    assert!(config.require_not_paused(PAUSE_DEPOSITS).is_ok());
+
+    // Setting is additive
+    assert!(set_flags(&program, platform_config, &pauser, PAUSE_GOVERNANCE).await.is_ok());
+    let config = fetch_platform_config(&mut ctx.banks_client, platform_config).await;
+    assert_eq!(config.pause_flags, PAUSE_CLAIMS | PAUSE_GOVERNANCE);
+
+    // The pauser cannot clear, and outsiders cannot set
+    assert!(clear_flags(&program, platform_config, &pauser, PAUSE_CLAIMS).await.is_err());
+    assert!(set_flags(&program, platform_config, &outsider, PAUSE_DEPOSITS).await.is_err());
+
+    // The admin clears one bit and leaves the other
+    assert!(clear_flags(&program, platform_config, &admin, PAUSE_CLAIMS).await.is_ok());
+    let config = fetch_platform_config(&mut ctx.banks_client, platform_config).await;
+    assert_eq!(config.pause_flags, PAUSE_GOVERNANCE);
+}
+
+// Test that unknown bits are rejected
+#[tokio::test]
+async fn test_unknown_pause_bits_rejected() {
+    let (mut ctx, program) = setup_test_context().await;
+    let admin = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
+    let platform_config = initialize_test_platform(&program, &admin).await;
+
+    assert!(set_flags(&program, platform_config, &admin, 1 << 7).await.is_err());
+    assert!(set_flags(&program, platform_config, &admin, 0).await.is_err());
+}
@@ -0,0 +1,352 @@
+// test_platform_config.rs
+// This module contains test cases for the platform config account: the
+// PlatformUpdated event contents, the parameter bounds shared with
+// initialization, and the layout version migration.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_client::Program;
+use solana_program_test::*;
+use anchor_lang::solana_program::system_program;
+use solana_sdk::account::Account as SolanaAccount;
+use solana_sdk::commitment_config::CommitmentLevel;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::Transaction;
+use std::rc::Rc;
+
+// Import test setup utilities (assumes test_setup.rs is in the same directory)
+mod test_setup;
+use test_setup::*;
+
+use ontora_ai::events::PlatformUpdated;
+use ontora_ai::pda;
+use ontora_ai::state::{
+    ConfigParams, PlatformConfig, VersionInfo, DEFAULT_APPROVAL_THRESHOLD_BPS, DEFAULT_COOLDOWN_SECONDS,
+    DEFAULT_QUORUM_BPS, DEFAULT_VOTE_UNIT, MAX_COOLDOWN_SECONDS, MAX_EPOCH_DURATION, MAX_GOVERNANCE_BPS,
+    MAX_REWARD_RATE_BPS, MAX_VESTING_SECONDS, MIN_EPOCH_DURATION, PLATFORM_CONFIG_VERSION,
+};
+
+// Size of the original layout, ending at `bump`, before any fields were appended
+const LEGACY_CONFIG_SPACE: usize = 8 + 32 + 8 + 8 + 8 + 8 + 8 + 1;
+
+// Reward rate, minimum stake, epoch duration, cooldown, epoch emission, stake cap per
+// agent, reward vesting period, vote unit, quorum and approval threshold
+type Params = (u64, u64, i64, i64, u64, u64, i64, u64, u16, u16);
+
+// Send update_platform_config and return the transaction logs, or None if it failed
+async fn update_config(
+    ctx: &mut TestContext,
+    program: &Program,
+    platform_config: Pubkey,
+    admin: &TestUser,
+    params: Params,
+) -> Option<Vec<String>> {
+    let (
+        reward_rate_bps,
+        min_stake_amount,
+        epoch_duration,
+        cooldown_seconds,
+        epoch_emission,
+        max_stake_per_agent,
+        vesting_seconds,
+        vote_unit,
+        quorum_bps,
+        approval_threshold_bps,
+    ) = params;
+    let instructions = program
+        .request()
+        .accounts(ontora_ai::accounts::UpdatePlatformConfig {
+            platform_config,
+            admin: admin.pubkey,
+        })
+        .args(ontora_ai::instruction::UpdatePlatformConfig {
+            params: ConfigParams {
+                reward_rate_bps,
+                min_stake_amount,
+                epoch_duration,
+                cooldown_seconds,
+                epoch_emission,
+                max_stake_per_agent,
+                vesting_seconds,
+                vote_unit,
+                quorum_bps,
+                approval_threshold_bps,
+            },
+            update_metadata: "raise rewards for epoch 12".to_string(),
+        })
+        .instructions()
+        .unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&admin.pubkey),
+        &[&admin.keypair],
+        ctx.banks_client.get_latest_blockhash().await.unwrap(),
+    );
+
+    let result = ctx.banks_client.process_transaction_with_metadata(tx).await.unwrap();
+    match result.result {
+        Ok(()) => Some(result.metadata.unwrap().log_messages),
+        Err(_) => None,
+    }
+}
+
+// Test that a valid update emits PlatformUpdated with old and new values
+#[tokio::test]
+async fn test_update_emits_platform_updated() {
+    let (mut ctx, program) = setup_test_context().await;
+    let admin = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
+    let platform_config = initialize_test_platform(&program, &admin).await;
+
+    let (stake_cap, vote_unit) = (TEST_STAKE_AMOUNT * 5, TEST_STAKE_AMOUNT / 4);
+    let (emission, quorum_bps, approval_threshold_bps) = (2 * TEST_EPOCH_EMISSION, 3_000, 6_600);
+    let params = (
+        250,
+        TEST_STAKE_AMOUNT * 2,
+        7 * 86_400,
+        3_600,
+        emission,
+        stake_cap,
+        604_800,
+        vote_unit,
+        quorum_bps,
+        approval_threshold_bps,
+    );
+    let logs = update_config(&mut ctx, &program, platform_config, &admin, params).await.expect("update should succeed");
+
+    let events = parse_events::<PlatformUpdated>(&logs);
+    assert_eq!(events.len(), 1);
+    let event = &events[0];
+    assert_eq!(event.authority, admin.pubkey);
+    assert_eq!(event.old_reward_rate, 100);
+    assert_eq!(event.new_reward_rate, 250);
+    assert_eq!(event.old_min_stake_amount, TEST_STAKE_AMOUNT);
+    assert_eq!(event.new_min_stake_amount, TEST_STAKE_AMOUNT * 2);
+    assert_eq!(event.old_epoch_duration, 86_400);
+    assert_eq!(event.new_epoch_duration, 7 * 86_400);
+    assert_eq!((event.old_cooldown_seconds, event.new_cooldown_seconds), (86_400, 3_600));
+    assert_eq!((event.old_epoch_emission, event.new_epoch_emission), (TEST_EPOCH_EMISSION, 2 * TEST_EPOCH_EMISSION));
+    assert_eq!((event.old_max_stake_per_agent, event.new_max_stake_per_agent), (0, stake_cap));
+    assert_eq!((event.old_vesting_seconds, event.new_vesting_seconds), (0, 604_800));
+    assert_eq!((event.old_vote_unit, event.new_vote_unit), (DEFAULT_VOTE_UNIT, vote_unit));
+    assert_eq!((event.old_quorum_bps, event.new_quorum_bps), (DEFAULT_QUORUM_BPS, quorum_bps));
+    let thresholds = (event.old_approval_threshold_bps, event.new_approval_threshold_bps);
+    assert_eq!(thresholds, (DEFAULT_APPROVAL_THRESHOLD_BPS, approval_threshold_bps));
+    assert_eq!(event.update_metadata, "raise rewards for epoch 12");
+}
+
+// Test that each bound violation is rejected
+#[tokio::test]
+async fn test_update_rejects_out_of_bounds() {
+    let (mut ctx, program) = setup_test_context().await;
+    let admin = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
+    let platform_config = initialize_test_platform(&program, &admin).await;
+
+    const EMISSION: u64 = TEST_EPOCH_EMISSION;
+    const STAKE: u64 = TEST_STAKE_AMOUNT;
+    const UNIT: u64 = DEFAULT_VOTE_UNIT;
+    const QUORUM: u16 = DEFAULT_QUORUM_BPS;
+    const APPROVAL: u16 = DEFAULT_APPROVAL_THRESHOLD_BPS;
+    let cases = [
+        (MAX_REWARD_RATE_BPS + 1, STAKE, 86_400, 86_400, EMISSION, 0, 0, UNIT, QUORUM, APPROVAL),
+        (100, 0, 86_400, 86_400, EMISSION, 0, 0, UNIT, QUORUM, APPROVAL),
+        (100, STAKE, MIN_EPOCH_DURATION - 1, 86_400, EMISSION, 0, 0, UNIT, QUORUM, APPROVAL),
+        (100, STAKE, MAX_EPOCH_DURATION + 1, 86_400, EMISSION, 0, 0, UNIT, QUORUM, APPROVAL),
+        (100, STAKE, 86_400, -1, EMISSION, 0, 0, UNIT, QUORUM, APPROVAL),
+        (100, STAKE, 86_400, MAX_COOLDOWN_SECONDS + 1, EMISSION, 0, 0, UNIT, QUORUM, APPROVAL),
+        // A stake cap below the minimum stake
+        (100, STAKE, 86_400, 86_400, EMISSION, STAKE - 1, 0, UNIT, QUORUM, APPROVAL),
+        (100, STAKE, 86_400, 86_400, EMISSION, 0, -1, UNIT, QUORUM, APPROVAL),
+        (100, STAKE, 86_400, 86_400, EMISSION, 0, MAX_VESTING_SECONDS + 1, UNIT, QUORUM, APPROVAL),
+        // No stake counts as a vote
+        (100, STAKE, 86_400, 86_400, EMISSION, 0, 0, 0, QUORUM, APPROVAL),
+        // More than all of the stake or of the votes
+        (100, STAKE, 86_400, 86_400, EMISSION, 0, 0, UNIT, MAX_GOVERNANCE_BPS + 1, APPROVAL),
+        (100, STAKE, 86_400, 86_400, EMISSION, 0, 0, UNIT, QUORUM, MAX_GOVERNANCE_BPS + 1),
+    ];
+    for params in cases {
+        let logs = update_config(&mut ctx, &program, platform_config, &admin, params).await;
+        assert!(logs.is_none(), "accepted {:?}", params);
+    }
+
+    // The boundaries themselves are valid, and any epoch emission is
+    let params = (MAX_REWARD_RATE_BPS, 1, MIN_EPOCH_DURATION, 0, 0, 1, 0, 1, 0, 0);
+    let logs = update_config(&mut ctx, &program, platform_config, &admin, params).await;
+    assert!(logs.is_some());
+    let params = (
+        100,
+        1,
+        86_400,
+        MAX_COOLDOWN_SECONDS,
+        u64::MAX,
+        0,
+        MAX_VESTING_SECONDS,
+        u64::MAX,
+        MAX_GOVERNANCE_BPS,
+        MAX_GOVERNANCE_BPS,
+    );
+    let logs = update_config(&mut ctx, &program, platform_config, &admin, params).await;
+    assert!(logs.is_some());
+}
+
+async fn migrate_config(program: &Program, platform_config: Pubkey, admin: &Keypair) -> std::result::Result<(), anchor_client::ClientError> {
+    program
+        .request()
+        .accounts(ontora_ai::accounts::MigratePlatformConfig {
+            platform_config,
+            admin: admin.pubkey(),
+            system_program: system_program::ID,
+        })
+        .args(ontora_ai::instruction::MigratePlatformConfig {})
+        .signer(admin)
+        .send()
+        .await
+        .map(|_| ())
+}
+
+// Start a test validator holding a platform config in the original (pre-version) layout
+async fn setup_legacy_config(admin: &Keypair) -> (BanksClient, Program, Pubkey) {
+    let mut test = ProgramTest::new("ontora_ai", id(), processor!(ontora_ai::entry));
+    let platform_config = platform_config_pda(&id());
+    let (_, bump) = Pubkey::find_program_address(&[pda::PLATFORM_CONFIG_SEED], &id());
+
+    let legacy = PlatformConfig {
+        admin: admin.pubkey(),
+        reward_rate_bps: 100,
+        min_stake_amount: TEST_STAKE_AMOUNT,
+        epoch_duration: 86_400,
+        bump,
+        ..Default::default()
+    };
+    let mut data = Vec::new();
+    legacy.try_serialize(&mut data).unwrap();
+    data.truncate(LEGACY_CONFIG_SPACE);
+
+    test.add_account(
+        platform_config,
+        SolanaAccount {
+            lamports: Rent::default().minimum_balance(LEGACY_CONFIG_SPACE),
+            data,
+            owner: id(),
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+    test.add_account(
+        admin.pubkey(),
+        SolanaAccount {
+            lamports: INITIAL_LAMPORTS,
+            data: vec![],
+            owner: system_program::ID,
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+
+    let (banks_client, _payer, _last_blockhash) = test.start().await;
+    let program = Program::new(id(), Rc::new(banks_client.clone()), CommitmentLevel::Confirmed);
+    (banks_client, program, platform_config)
+}
+
+// The crate version Cargo compiled this test against
+fn cargo_version() -> [u16; 3] {
+    let parts: Vec<u16> = env!("CARGO_PKG_VERSION")
+        .split(['.', '-'])
+        .take(3)
+        .map(|part| part.parse().unwrap())
+        .collect();
+    [parts[0], parts[1], parts[2]]
+}
+
+// Test that get_version returns the Cargo version through return data
+#[tokio::test]
+async fn test_get_version_reports_cargo_version() {
+    let (mut ctx, program) = setup_test_context().await;
+    let instructions = program
+        .request()
+        .accounts(ontora_ai::accounts::GetVersion {})
+        .args(ontora_ai::instruction::GetVersion {})
+        .instructions()
+        .unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&ctx.payer.pubkey()),
+        &[&ctx.payer],
+        ctx.banks_client.get_latest_blockhash().await.unwrap(),
+    );
+
+    let simulation = ctx.banks_client.simulate_transaction(tx).await.unwrap();
+    let return_data = simulation.simulation_details.unwrap().return_data.unwrap();
+    assert_eq!(return_data.program_id, id());
+    let info = VersionInfo::try_from_slice(&return_data.data).unwrap();
+    assert_eq!(info.program_version, cargo_version());
+    assert_eq!(info.platform_config_version, PLATFORM_CONFIG_VERSION);
+}
+
+// Test that SPACE matches the serialized size of the struct
+#[test]
+fn test_platform_config_space_matches_layout() {
+    let mut data = Vec::new();
+    PlatformConfig::default().try_serialize(&mut data).unwrap();
+    assert_eq!(data.len(), PlatformConfig::SPACE);
+}
+
+// Test that a legacy config grows to SPACE, keeps its values, and gets safe defaults
+#[tokio::test]
+async fn test_migrate_legacy_config() {
+    let admin = Keypair::new();
+    let (mut banks_client, program, platform_config) = setup_legacy_config(&admin).await;
+
+    migrate_config(&program, platform_config, &admin).await.unwrap();
+
+    let account = banks_client.get_account(platform_config).await.unwrap().unwrap();
+    assert_eq!(account.data.len(), PlatformConfig::SPACE);
+    let config = PlatformConfig::try_deserialize(&mut account.data.as_ref()).unwrap();
+    assert_eq!(config.version, PLATFORM_CONFIG_VERSION);
+    assert_eq!(config.admin, admin.pubkey());
+    assert_eq!(config.reward_rate_bps, 100);
+    assert_eq!(config.min_stake_amount, TEST_STAKE_AMOUNT);
+    assert_eq!(config.pending_admin, Pubkey::default());
+    assert_eq!(config.pause_flags, 0);
+    assert_eq!(config.pauser, admin.pubkey());
+    assert_eq!(config.roles.crank, admin.pubkey());
+    assert_eq!(config.roles.oracle, admin.pubkey());
+    assert_eq!(config.roles.treasury, admin.pubkey());
+    assert_eq!(config.cooldown_seconds, DEFAULT_COOLDOWN_SECONDS);
+    assert_eq!(config.epoch_emission, 0);
+    assert_eq!(config.total_agents, 0);
+    assert_eq!(config.max_stake_per_agent, 0);
+    assert_eq!(config.vesting_seconds, 0);
+    assert_eq!(config.vote_unit, DEFAULT_VOTE_UNIT);
+    let governance = (config.quorum_bps, config.approval_threshold_bps);
+    assert_eq!(governance, (DEFAULT_QUORUM_BPS, DEFAULT_APPROVAL_THRESHOLD_BPS));
+    // The vaults wait for `initialize_vaults`
+    let unset = Pubkey::default();
+    assert_eq!((config.stake_mint, config.staking_vault, config.reward_vault), (unset, unset, unset));
+    // The reward accumulator starts at the migration
+    let now = banks_client.get_sysvar::<Clock>().await.unwrap().unix_timestamp;
+    assert_eq!((config.acc_reward_per_share, config.last_reward_timestamp), (0, now));
+    assert_eq!(config.program_version, cargo_version());
+
+    // A second run finds the account already current
+    assert!(migrate_config(&program, platform_config, &admin).await.is_err());
+}
+
+// Test that only the admin may migrate
+#[tokio::test]
+async fn test_migrate_requires_admin() {
+    let admin = Keypair::new();
+    let (_banks_client, program, platform_config) = setup_legacy_config(&admin).await;
+
+    let impostor = Keypair::new();
+    assert!(migrate_config(&program, platform_config, &impostor).await.is_err());
+}
+
+// Test that a freshly initialized config is already current
+#[tokio::test]
+async fn test_migrate_refuses_current_config() {
+    let (mut ctx, program) = setup_test_context().await;
+    let admin = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
+    let platform_config = initialize_test_platform(&program, &admin).await;
+
+    assert!(migrate_config(&program, platform_config, &admin.keypair).await.is_err());
+}
@@ -0,0 +1,203 @@
+// test_program_test_smoke.rs
+// This module keeps a small smoke suite on the full solana-program-test validator, so
+// the banks-client path of test_setup.rs stays covered while the staking and reward
+// suites run on the in-process harness in svm/. It also pays a claim out of the reward
+// vault through the real token program, as the pool's signer seeds must match on chain.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::system_program;
+use anchor_client::Program;
+use solana_program_test::*;
+
+// Import test setup utilities (assumes test_setup.rs is in the same directory)
+mod test_setup;
+use test_setup::*;
+
+use ontora_ai::error::OntoraError;
+use ontora_ai::state::AiAgent;
+use ontora_ai::AIAgent;
+
+const EPOCH_DURATION: i64 = 604_800; // Matches EPOCH_DURATION in lib.rs
+
+// Test staking and unstaking on an agent through the banks client
+#[tokio::test]
+async fn test_stake_and_unstake_round_trip() {
+    let (mut ctx, program) = setup_test_context().await;
+    let platform = initialize_program(&mut ctx, &program, 0).await;
+    let user = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
+    let token_account = create_funded_token_account(&mut ctx, &platform, &user.pubkey, TEST_STAKE_AMOUNT).await;
+    let agent = create_mock_ai_agent(&program, &user, TEST_AI_AGENT_ID).await;
+    let user_stake = user_stake_pda(&program.id(), &user.pubkey);
+    let stake_index = stake_index_pda(&program.id(), &user.pubkey, TEST_AI_AGENT_ID);
+
+    program
+        .request()
+        .accounts(ontora_ai::accounts::StakeOnAgent {
+            platform_config: platform.platform_config,
+            ai_agent: agent,
+            user_stake,
+            stake_index,
+            user: user.pubkey,
+            user_token_account: token_account,
+            platform_vault: platform.platform_vault,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        })
+        .args(ontora_ai::instruction::StakeOnAgent {
+            agent_id: TEST_AI_AGENT_ID,
+            amount: TEST_STAKE_AMOUNT,
+            beneficiary: user.pubkey,
+        })
+        .signer(&user.keypair)
+        .send()
+        .await
+        .unwrap();
+
+    let account = ctx.banks_client.get_account(agent).await.unwrap().unwrap();
+    assert_eq!(AiAgent::try_deserialize(&mut account.data.as_ref()).unwrap().staked_amount, TEST_STAKE_AMOUNT);
+    assert_eq!(get_token_balance(&mut ctx.banks_client, &platform.platform_vault).await, TEST_STAKE_AMOUNT);
+
+    program
+        .request()
+        .accounts(ontora_ai::accounts::UnstakeFromAgent {
+            platform_config: platform.platform_config,
+            ai_agent: agent,
+            user_stake,
+            stake_index,
+            user: user.pubkey,
+            user_token_account: token_account,
+            platform_vault: platform.platform_vault,
+            stake_mint: platform.mint,
+            fee_schedule: platform.fee_schedule,
+            treasury: platform.treasury,
+            treasury_vault: treasury_vault_pda(&program.id(), &platform.mint),
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        })
+        .args(ontora_ai::instruction::UnstakeFromAgent { agent_id: TEST_AI_AGENT_ID, amount: TEST_STAKE_AMOUNT })
+        .signer(&user.keypair)
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(get_token_balance(&mut ctx.banks_client, &token_account).await, TEST_STAKE_AMOUNT);
+}
+
+// A legacy agent staking TEST_STAKE_AMOUNT for a new owner: the owner, the agent,
+// and the owner's token account, emptied by the stake
+async fn register_legacy_agent(
+    ctx: &mut TestContext,
+    program: &Program,
+    platform: &TestPlatform,
+) -> (TestUser, Pubkey, Pubkey) {
+    let owner = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
+    let token_account = create_funded_token_account(ctx, platform, &owner.pubkey, TEST_STAKE_AMOUNT).await;
+    let agent = legacy_ai_agent_pda(&program.id(), &owner.pubkey);
+    let staking_vault = create_token_account(ctx, &agent, &platform.mint).await;
+
+    program
+        .request()
+        .accounts(ontora_ai::accounts::RegisterAIAgent {
+            platform_config: platform.platform_config,
+            ai_agent: agent,
+            user: owner.pubkey,
+            user_token_account: token_account,
+            staking_vault,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        })
+        .args(ontora_ai::instruction::RegisterAiAgent { stake_amount: TEST_STAKE_AMOUNT })
+        .signer(&owner.keypair)
+        .send()
+        .await
+        .unwrap();
+    (owner, agent, token_account)
+}
+
+async fn distribute_rewards(program: &Program, platform: &TestPlatform, agent: Pubkey) {
+    program
+        .request()
+        .accounts(ontora_ai::accounts::DistributeRewards {
+            platform_config: platform.platform_config,
+            reward_pool: platform.reward_pool,
+            ai_agent: agent,
+            crank: platform.admin.pubkey,
+        })
+        .args(ontora_ai::instruction::DistributeRewards {})
+        .signer(&platform.admin.keypair)
+        .send()
+        .await
+        .unwrap();
+}
+
+async fn claim_rewards(
+    program: &Program,
+    platform: &TestPlatform,
+    owner: &TestUser,
+    agent: Pubkey,
+    token_account: Pubkey,
+    reward_vault: Pubkey,
+) -> std::result::Result<(), anchor_client::ClientError> {
+    program
+        .request()
+        .accounts(ontora_ai::accounts::ClaimRewards {
+            platform_config: platform.platform_config,
+            ai_agent: agent,
+            reward_pool: platform.reward_pool,
+            user: owner.pubkey,
+            user_token_account: token_account,
+            reward_vault,
+            reward_mint: platform.mint,
+            fee_schedule: platform.fee_schedule,
+            treasury: platform.treasury,
+            treasury_vault: treasury_vault_pda(&program.id(), &platform.mint),
+            reward_vesting: reward_vesting_pda(&program.id(), &owner.pubkey),
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        })
+        .args(ontora_ai::instruction::ClaimRewards {})
+        .signer(&owner.keypair)
+        .send()
+        .await
+        .map(|_| ())
+}
+
+// Test an epoch's reward distribution after warping the validator clock
+#[tokio::test]
+async fn test_distribute_rewards_after_clock_warp() {
+    let (mut ctx, program) = setup_test_context().await;
+    let platform = initialize_program(&mut ctx, &program, TEST_REWARD_POOL_AMOUNT).await;
+    let (_owner, agent, _token_account) = register_legacy_agent(&mut ctx, &program, &platform).await;
+
+    warp_seconds(&mut ctx, EPOCH_DURATION).await;
+    distribute_rewards(&program, &platform, agent).await;
+
+    let account = ctx.banks_client.get_account(agent).await.unwrap().unwrap();
+    let rewards = AIAgent::try_deserialize(&mut account.data.as_ref()).unwrap().accumulated_rewards;
+    assert_eq!(rewards, TEST_EPOCH_EMISSION);
+}
+
+// Test that a claim moves the rewards out of the vault with a token transfer the reward
+// pool signs for, and that any vault but the one recorded on the config is refused
+#[tokio::test]
+async fn test_claim_rewards_transfers_from_vault() {
+    let (mut ctx, program) = setup_test_context().await;
+    let platform = initialize_program(&mut ctx, &program, TEST_REWARD_POOL_AMOUNT).await;
+    let (owner, agent, token_account) = register_legacy_agent(&mut ctx, &program, &platform).await;
+    warp_seconds(&mut ctx, EPOCH_DURATION).await;
+    distribute_rewards(&program, &platform, agent).await;
+    let reward = TEST_EPOCH_EMISSION;
+
+    // A vault of the reward mint held by anyone else can't stand in for the pool's
+    let other_vault = create_funded_token_account(&mut ctx, &platform, &owner.pubkey, reward).await;
+    let error = claim_rewards(&program, &platform, &owner, agent, token_account, other_vault).await.unwrap_err();
+    assert_eq!(custom_error_code(&error), Some(u32::from(OntoraError::InvalidVault)));
+    assert_eq!(get_token_balance(&mut ctx.banks_client, &other_vault).await, reward);
+
+    claim_rewards(&program, &platform, &owner, agent, token_account, platform.reward_vault).await.unwrap();
+    assert_eq!(get_token_balance(&mut ctx.banks_client, &token_account).await, reward);
+    let vault_balance = get_token_balance(&mut ctx.banks_client, &platform.reward_vault).await;
+    assert_eq!(vault_balance, TEST_REWARD_POOL_AMOUNT - reward);
+    let account = ctx.banks_client.get_account(agent).await.unwrap().unwrap();
+    assert_eq!(AIAgent::try_deserialize(&mut account.data.as_ref()).unwrap().accumulated_rewards, 0);
+}
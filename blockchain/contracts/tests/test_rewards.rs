@@ -1,400 +1,622 @@
-#[cfg(test)]
-mod tests {
-    use anchor_lang::prelude::*;
-    use anchor_client::solana_sdk::{
-        commitment_config::CommitmentConfig,
-        signature::{Keypair, Signer},
-        system_instruction,
-    };
-    use solana_program_test::*;
-    use solana_sdk::account::Account;
-    use solana_sdk::pubkey::Pubkey;
-    use solana_sdk::transport::TransportError;
-    use std::result::Result;
-
-    // Assuming a test_setup module exists in the same directory for environment setup
-    mod test_setup;
-    use test_setup::{
-        setup_test_context, create_test_user, fund_account, initialize_program,
-        get_account_balance,
-    };
+// test_rewards.rs
+// This module contains test cases for the reward pool: topping it up, per-epoch
+// distribution to staked AI agents, claiming from the reward vault, and the
+// failure cases of each.
+// The tests run on the in-process harness in svm/.
+
+use anchor_lang::solana_program::instruction::AccountMeta;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::solana_program::system_program;
+
+// Shared types and PDAs from test_setup.rs, helpers from the in-process harness
+mod svm;
+mod test_setup;
+use svm::*;
+use test_setup::{
+    parse_events, reward_vesting_pda, treasury_vault_pda, TestPlatform, TestUser, TEST_EPOCH_EMISSION,
+    TEST_REWARD_POOL_AMOUNT,
+};
+
+use ontora_ai::error::OntoraError;
+use ontora_ai::events::RewardPoolFunded;
+use ontora_ai::math::{vested_amount, weighted_vesting_duration};
+use ontora_ai::state::{ConfigParams, PlatformConfig};
+use ontora_ai::{reward_epoch, AIAgent, RewardPool, RewardVesting, REWARD_GENESIS};
+
+// Constants for reward testing
+const STAKE_AMOUNT: u64 = 1_000_000_000; // 1 SOL worth of tokens
+const EPOCH_DURATION: i64 = 604_800; // Matches EPOCH_DURATION in lib.rs
+const VESTING_SECONDS: i64 = 2 * EPOCH_DURATION;
+
+// An agent's share of the epoch emission with `total` staked across the platform
+fn share(staked: u64, total: u64) -> u64 {
+    (TEST_EPOCH_EMISSION as u128 * staked as u128 / total as u128) as u64
+}
 
-    // Constants for reward testing
-    const INITIAL_BALANCE: u64 = 10_000_000_000; // 10 SOL in lamports
-    const STAKE_AMOUNT: u64 = 1_000_000_000; // 1 SOL in lamports
-    const REWARD_RATE: u64 = 10; // 10% reward rate for simplicity
-    const REWARD_POOL_INITIAL: u64 = 5_000_000_000; // 5 SOL in lamports for reward pool
-
-    // Placeholder for program ID (replace with actual program ID from your project)
-    const PROGRAM_ID: Pubkey = Pubkey::new_unique();
-
-    // Test case 1: Successful reward calculation and distribution
-    #[tokio::test]
-    async fn test_successful_reward_calculation_and_distribution() {
-        let mut test_context = setup_test_context().await.unwrap();
-        let user = create_test_user(&mut test_context).await.unwrap();
-        let reward_pool = Keypair::new();
-        let user_stake_account = Keypair::new();
-
-        // Fund reward pool
-        fund_account(
-            &mut test_context,
-            &reward_pool.pubkey(),
-            REWARD_POOL_INITIAL,
-        )
-        .await
-        .unwrap();
-
-        // Initialize program and stake for user (placeholder logic)
-        initialize_program(&mut test_context, &PROGRAM_ID).await.unwrap();
-        stake_tokens(
-            &mut test_context,
-            &user,
-            &user_stake_account.pubkey(),
-            STAKE_AMOUNT,
-        )
-        .await
-        .unwrap();
-
-        // Simulate time passing or reward accrual (placeholder)
-        advance_time(&mut test_context, 86400).await.unwrap(); // 1 day
-
-        // Calculate expected reward (10% of staked amount for simplicity)
-        let expected_reward = STAKE_AMOUNT * REWARD_RATE / 100;
-
-        // Trigger reward distribution (placeholder instruction)
-        distribute_rewards(
-            &mut test_context,
-            &reward_pool.pubkey(),
-            &user_stake_account.pubkey(),
-        )
-        .await
-        .unwrap();
-
-        // Check user's pending rewards (placeholder account read)
-        let user_rewards = get_pending_rewards(&mut test_context, &user_stake_account.pubkey())
-            .await
-            .unwrap();
-        assert_eq!(user_rewards, expected_reward, "Reward calculation mismatch");
-    }
+fn distribute_rewards(ctx: &mut SvmContext, platform: &TestPlatform, agent: Pubkey) -> SvmResult {
+    let instruction = program_instruction(
+        ontora_ai::accounts::DistributeRewards {
+            platform_config: platform.platform_config,
+            reward_pool: platform.reward_pool,
+            ai_agent: agent,
+            crank: platform.admin.pubkey,
+        },
+        ontora_ai::instruction::DistributeRewards {},
+    );
+    send_transaction(ctx, &[instruction], &[&platform.admin.keypair])
+}
 
-    // Test case 2: Claim rewards successfully
-    #[tokio::test]
-    async fn test_successful_reward_claim() {
-        let mut test_context = setup_test_context().await.unwrap();
-        let user = create_test_user(&mut test_context).await.unwrap();
-        let reward_pool = Keypair::new();
-        let user_stake_account = Keypair::new();
-
-        // Fund reward pool and user stake
-        fund_account(
-            &mut test_context,
-            &reward_pool.pubkey(),
-            REWARD_POOL_INITIAL,
-        )
-        .await
-        .unwrap();
-        initialize_program(&mut test_context, &PROGRAM_ID).await.unwrap();
-        stake_tokens(
-            &mut test_context,
-            &user,
-            &user_stake_account.pubkey(),
-            STAKE_AMOUNT,
-        )
-        .await
-        .unwrap();
-
-        // Distribute rewards
-        advance_time(&mut test_context, 86400).await.unwrap();
-        distribute_rewards(
-            &mut test_context,
-            &reward_pool.pubkey(),
-            &user_stake_account.pubkey(),
-        )
-        .await
-        .unwrap();
-
-        let expected_reward = STAKE_AMOUNT * REWARD_RATE / 100;
-        let initial_balance = get_account_balance(&mut test_context, &user.pubkey())
-            .await
-            .unwrap();
-
-        // Claim rewards (placeholder instruction)
-        claim_rewards(
-            &mut test_context,
-            &user,
-            &user_stake_account.pubkey(),
-            &reward_pool.pubkey(),
-        )
-        .await
-        .unwrap();
-
-        // Verify balance update
-        let final_balance = get_account_balance(&mut test_context, &user.pubkey())
-            .await
-            .unwrap();
-        assert_eq!(
-            final_balance,
-            initial_balance + expected_reward,
-            "Balance not updated after claiming rewards"
-        );
-    }
+// Distribute to `agents`, passed as remaining accounts in the order given
+fn distribute_rewards_batch(
+    ctx: &mut SvmContext,
+    platform: &TestPlatform,
+    agents: &[Pubkey],
+    close_epoch: bool,
+) -> SvmResult {
+    let mut instruction = program_instruction(
+        ontora_ai::accounts::DistributeRewardsBatch {
+            platform_config: platform.platform_config,
+            reward_pool: platform.reward_pool,
+            crank: platform.admin.pubkey,
+        },
+        ontora_ai::instruction::DistributeRewardsBatch { close_epoch },
+    );
+    instruction.accounts.extend(agents.iter().map(|agent| AccountMeta::new(*agent, false)));
+    send_transaction(ctx, &[instruction], &[&platform.admin.keypair])
+}
 
-    // Test case 3: Edge case - Claim rewards with zero pending rewards
-    #[tokio::test]
-    async fn test_claim_rewards_with_zero_pending() {
-        let mut test_context = setup_test_context().await.unwrap();
-        let user = create_test_user(&mut test_context).await.unwrap();
-        let reward_pool = Keypair::new();
-        let user_stake_account = Keypair::new();
-
-        // Fund reward pool but no staking or distribution yet
-        fund_account(
-            &mut test_context,
-            &reward_pool.pubkey(),
-            REWARD_POOL_INITIAL,
-        )
-        .await
-        .unwrap();
-        initialize_program(&mut test_context, &PROGRAM_ID).await.unwrap();
-
-        // Attempt to claim rewards with no pending rewards
-        let result = claim_rewards(
-            &mut test_context,
-            &user,
-            &user_stake_account.pubkey(),
-            &reward_pool.pubkey(),
-        )
-        .await;
-
-        assert!(result.is_err(), "Claiming with zero rewards should fail");
-        if let Err(e) = result {
-            assert!(
-                e.to_string().contains("NoPendingRewards"),
-                "Expected NoPendingRewards error"
-            );
-        }
-    }
+fn fund_reward_pool(
+    ctx: &mut SvmContext,
+    platform: &TestPlatform,
+    funder: &TestUser,
+    funder_token_account: Pubkey,
+    amount: u64,
+) -> SvmResult {
+    fund_reward_vault(ctx, platform, platform.reward_vault, funder, funder_token_account, amount)
+}
 
-    // Test case 4: Edge case - Insufficient reward pool balance
-    #[tokio::test]
-    async fn test_distribute_rewards_insufficient_pool_balance() {
-        let mut test_context = setup_test_context().await.unwrap();
-        let user = create_test_user(&mut test_context).await.unwrap();
-        let reward_pool = Keypair::new();
-        let user_stake_account = Keypair::new();
-
-        // Fund reward pool with zero balance
-        fund_account(&mut test_context, &reward_pool.pubkey(), 0)
-            .await
-            .unwrap();
-        initialize_program(&mut test_context, &PROGRAM_ID).await.unwrap();
-        stake_tokens(
-            &mut test_context,
-            &user,
-            &user_stake_account.pubkey(),
-            STAKE_AMOUNT,
-        )
-        .await
-        .unwrap();
-
-        // Attempt to distribute rewards with empty pool
-        let result = distribute_rewards(
-            &mut test_context,
-            &reward_pool.pubkey(),
-            &user_stake_account.pubkey(),
-        )
-        .await;
-
-        assert!(
-            result.is_err(),
-            "Distributing rewards with empty pool should fail"
-        );
-        if let Err(e) = result {
-            assert!(
-                e.to_string().contains("InsufficientRewardPool"),
-                "Expected InsufficientRewardPool error"
-            );
-        }
-    }
+// `fund_reward_pool` into `reward_vault`, which need not be the platform's
+fn fund_reward_vault(
+    ctx: &mut SvmContext,
+    platform: &TestPlatform,
+    reward_vault: Pubkey,
+    funder: &TestUser,
+    funder_token_account: Pubkey,
+    amount: u64,
+) -> SvmResult {
+    let instruction = program_instruction(
+        ontora_ai::accounts::FundRewardPool {
+            platform_config: platform.platform_config,
+            reward_pool: platform.reward_pool,
+            reward_vault,
+            reward_mint: platform.mint,
+            funder: funder.pubkey,
+            funder_token_account,
+            token_program: spl_token::ID,
+        },
+        ontora_ai::instruction::FundRewardPool { amount },
+    );
+    send_transaction(ctx, &[instruction], &[&funder.keypair])
+}
 
-    // Test case 5: Edge case - Reward calculation overflow
-    #[tokio::test]
-    async fn test_reward_calculation_overflow() {
-        let mut test_context = setup_test_context().await.unwrap();
-        let user = create_test_user(&mut test_context).await.unwrap();
-        let reward_pool = Keypair::new();
-        let user_stake_account = Keypair::new();
-
-        // Fund reward pool
-        fund_account(
-            &mut test_context,
-            &reward_pool.pubkey(),
-            REWARD_POOL_INITIAL,
-        )
-        .await
-        .unwrap();
-        initialize_program(&mut test_context, &PROGRAM_ID).await.unwrap();
-
-        // Stake an extremely large amount to trigger overflow (placeholder)
-        let large_stake = u64::MAX / 2;
-        stake_tokens(
-            &mut test_context,
-            &user,
-            &user_stake_account.pubkey(),
-            large_stake,
-        )
-        .await
-        .unwrap();
-
-        // Attempt to distribute rewards with potential overflow
-        let result = distribute_rewards(
-            &mut test_context,
-            &reward_pool.pubkey(),
-            &user_stake_account.pubkey(),
-        )
-        .await;
-
-        assert!(result.is_err(), "Reward calculation overflow should fail");
-        if let Err(e) = result {
-            assert!(
-                e.to_string().contains("ArithmeticOverflow"),
-                "Expected ArithmeticOverflow error"
-            );
-        }
-    }
+fn claim_rewards(ctx: &mut SvmContext, platform: &TestPlatform, staked: &LegacyAgent) -> SvmResult {
+    let instruction = program_instruction(
+        ontora_ai::accounts::ClaimRewards {
+            platform_config: platform.platform_config,
+            ai_agent: staked.agent,
+            reward_pool: platform.reward_pool,
+            user: staked.owner.pubkey,
+            user_token_account: staked.token_account,
+            reward_vault: platform.reward_vault,
+            reward_mint: platform.mint,
+            fee_schedule: platform.fee_schedule,
+            treasury: platform.treasury,
+            treasury_vault: treasury_vault_pda(&ontora_ai::ID, &platform.mint),
+            reward_vesting: reward_vesting_pda(&ontora_ai::ID, &staked.owner.pubkey),
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        },
+        ontora_ai::instruction::ClaimRewards {},
+    );
+    send_transaction(ctx, &[instruction], &[&staked.owner.keypair])
+}
 
-    // Test case 6: Multiple users reward distribution
-    #[tokio::test]
-    async fn test_multiple_users_reward_distribution() {
-        let mut test_context = setup_test_context().await.unwrap();
-        let user1 = create_test_user(&mut test_context).await.unwrap();
-        let user2 = create_test_user(&mut test_context).await.unwrap();
-        let reward_pool = Keypair::new();
-        let user1_stake_account = Keypair::new();
-        let user2_stake_account = Keypair::new();
-
-        // Fund reward pool
-        fund_account(
-            &mut test_context,
-            &reward_pool.pubkey(),
-            REWARD_POOL_INITIAL,
-        )
-        .await
-        .unwrap();
-        initialize_program(&mut test_context, &PROGRAM_ID).await.unwrap();
-
-        // Stake for both users
-        stake_tokens(
-            &mut test_context,
-            &user1,
-            &user1_stake_account.pubkey(),
-            STAKE_AMOUNT,
-        )
-        .await
-        .unwrap();
-        stake_tokens(
-            &mut test_context,
-            &user2,
-            &user2_stake_account.pubkey(),
-            STAKE_AMOUNT,
-        )
-        .await
-        .unwrap();
-
-        // Simulate time passing
-        advance_time(&mut test_context, 86400).await.unwrap();
-
-        // Distribute rewards to both users
-        distribute_rewards(
-            &mut test_context,
-            &reward_pool.pubkey(),
-            &user1_stake_account.pubkey(),
-        )
-        .await
-        .unwrap();
-        distribute_rewards(
-            &mut test_context,
-            &reward_pool.pubkey(),
-            &user2_stake_account.pubkey(),
-        )
-        .await
-        .unwrap();
-
-        let expected_reward_per_user = STAKE_AMOUNT * REWARD_RATE / 100;
-
-        // Check pending rewards for both users
-        let user1_rewards = get_pending_rewards(&mut test_context, &user1_stake_account.pubkey())
-            .await
-            .unwrap();
-        let user2_rewards = get_pending_rewards(&mut test_context, &user2_stake_account.pubkey())
-            .await
-            .unwrap();
-
-        assert_eq!(
-            user1_rewards, expected_reward_per_user,
-            "User1 reward mismatch"
-        );
-        assert_eq!(
-            user2_rewards, expected_reward_per_user,
-            "User2 reward mismatch"
-        );
-    }
+fn get_pending_rewards(ctx: &SvmContext, agent: Pubkey) -> u64 {
+    fetch_account::<AIAgent>(ctx, &agent).accumulated_rewards
+}
 
-    // Placeholder helper functions (replace with actual program instructions)
-    async fn stake_tokens(
-        test_context: &mut ProgramTestContext,
-        user: &Keypair,
-        stake_account: &Pubkey,
-        amount: u64,
-    ) -> Result<(), TransportError> {
-        // Placeholder: Implement actual staking instruction call
-        println!("Staking {} lamports for user {:?}", amount, user.pubkey());
-        Ok(())
-    }
+fn set_vesting_seconds(ctx: &mut SvmContext, platform: &TestPlatform, vesting_seconds: i64) {
+    let config: PlatformConfig = fetch_account(ctx, &platform.platform_config);
+    let update = program_instruction(
+        ontora_ai::accounts::UpdatePlatformConfig {
+            platform_config: platform.platform_config,
+            admin: platform.admin.pubkey,
+        },
+        ontora_ai::instruction::UpdatePlatformConfig {
+            params: ConfigParams { vesting_seconds, ..config.params() },
+            update_metadata: String::new(),
+        },
+    );
+    send_transaction(ctx, &[update], &[&platform.admin.keypair]).unwrap();
+}
 
-    async fn distribute_rewards(
-        test_context: &mut ProgramTestContext,
-        reward_pool: &Pubkey,
-        stake_account: &Pubkey,
-    ) -> Result<(), TransportError> {
-        // Placeholder: Implement actual reward distribution instruction
-        println!(
-            "Distributing rewards from pool {:?} to stake account {:?}",
-            reward_pool, stake_account
-        );
-        Ok(())
-    }
+fn release_vested(ctx: &mut SvmContext, platform: &TestPlatform, staked: &LegacyAgent) -> SvmResult {
+    let instruction = program_instruction(
+        ontora_ai::accounts::ReleaseVested {
+            platform_config: platform.platform_config,
+            reward_vesting: reward_vesting_pda(&ontora_ai::ID, &staked.owner.pubkey),
+            reward_pool: platform.reward_pool,
+            user: staked.owner.pubkey,
+            user_token_account: staked.token_account,
+            reward_vault: platform.reward_vault,
+            reward_mint: platform.mint,
+            token_program: spl_token::ID,
+        },
+        ontora_ai::instruction::ReleaseVested {},
+    );
+    send_transaction(ctx, &[instruction], &[&staked.owner.keypair])
+}
+
+// Test case 1: Successful reward calculation and distribution
+#[test]
+fn test_successful_reward_calculation_and_distribution() {
+    let mut ctx = setup_svm();
+    let platform = initialize_program(&mut ctx, TEST_REWARD_POOL_AMOUNT);
+    let staked = register_legacy_agent(&mut ctx, &platform, STAKE_AMOUNT);
+
+    warp_seconds(&mut ctx, EPOCH_DURATION);
+    distribute_rewards(&mut ctx, &platform, staked.agent).unwrap();
+
+    // The only staker takes the whole emission
+    let expected_reward = TEST_EPOCH_EMISSION;
+    let user_rewards = get_pending_rewards(&ctx, staked.agent);
+    assert_eq!(user_rewards, expected_reward, "Reward calculation mismatch");
+}
 
-    async fn claim_rewards(
-        test_context: &mut ProgramTestContext,
-        user: &Keypair,
-        stake_account: &Pubkey,
-        reward_pool: &Pubkey,
-    ) -> Result<(), TransportError> {
-        // Placeholder: Implement actual reward claim instruction
-        println!(
-            "Claiming rewards for user {:?} from stake account {:?}",
-            user.pubkey(),
-            stake_account
-        );
-        Err(TransportError::Custom("NoPendingRewards".to_string()))
+// Test case 2: Claim rewards successfully
+#[test]
+fn test_successful_reward_claim() {
+    let mut ctx = setup_svm();
+    let platform = initialize_program(&mut ctx, TEST_REWARD_POOL_AMOUNT);
+    let staked = register_legacy_agent(&mut ctx, &platform, STAKE_AMOUNT);
+
+    warp_seconds(&mut ctx, EPOCH_DURATION);
+    distribute_rewards(&mut ctx, &platform, staked.agent).unwrap();
+
+    let expected_reward = TEST_EPOCH_EMISSION;
+    let initial_balance = get_token_balance(&ctx, &staked.token_account);
+
+    claim_rewards(&mut ctx, &platform, &staked).unwrap();
+
+    // The fixture charges no claim fee, so the whole reward arrives
+    let final_balance = get_token_balance(&ctx, &staked.token_account);
+    assert_eq!(final_balance, initial_balance + expected_reward, "Balance not updated after claiming rewards");
+    assert_eq!(
+        get_token_balance(&ctx, &platform.reward_vault),
+        TEST_REWARD_POOL_AMOUNT - expected_reward
+    );
+    assert_eq!(get_pending_rewards(&ctx, staked.agent), 0);
+}
+
+// Test case 3: Edge case - Claim rewards with zero pending rewards
+#[test]
+fn test_claim_rewards_with_zero_pending() {
+    let mut ctx = setup_svm();
+    let platform = initialize_program(&mut ctx, TEST_REWARD_POOL_AMOUNT);
+    let staked = register_legacy_agent(&mut ctx, &platform, STAKE_AMOUNT);
+
+    // Nothing has been distributed yet; claims report it as RewardPoolDepleted
+    let result = claim_rewards(&mut ctx, &platform, &staked);
+    assert_eq!(svm_error_code(&result), Some(u32::from(OntoraError::RewardPoolDepleted)));
+}
+
+// Test case 4: Edge case - Insufficient reward pool balance
+#[test]
+fn test_distribute_rewards_insufficient_pool_balance() {
+    let mut ctx = setup_svm();
+    let platform = initialize_program(&mut ctx, 0);
+    let staked = register_legacy_agent(&mut ctx, &platform, STAKE_AMOUNT);
+
+    warp_seconds(&mut ctx, EPOCH_DURATION);
+    let result = distribute_rewards(&mut ctx, &platform, staked.agent);
+    assert_eq!(svm_error_code(&result), Some(u32::from(OntoraError::RewardPoolDepleted)));
+}
+
+// Test case 5: Edge case - Reward calculation overflow. The emission times the stake
+// overflows u64, so the reward is computed in u128 and paid exactly.
+#[test]
+fn test_reward_calculation_overflow() {
+    let staked_amount = u64::MAX / 2;
+    let reward = share(staked_amount, staked_amount);
+    let mut ctx = setup_svm();
+    let platform = initialize_program(&mut ctx, reward);
+    let staked = register_legacy_agent(&mut ctx, &platform, staked_amount);
+
+    warp_seconds(&mut ctx, EPOCH_DURATION);
+    distribute_rewards(&mut ctx, &platform, staked.agent).unwrap();
+    let agent: AIAgent = fetch_account(&ctx, &staked.agent);
+    assert_eq!(agent.accumulated_rewards, reward);
+    let pool: RewardPool = fetch_account(&ctx, &platform.reward_pool);
+    assert_eq!(pool.total_rewards, 0);
+}
+
+// Test case 6: Multiple users reward distribution. Each agent is paid once per epoch,
+// whatever was paid to the others in it.
+#[test]
+fn test_multiple_users_reward_distribution() {
+    // Equal stakes split each epoch's emission in half
+    let reward = share(STAKE_AMOUNT, 2 * STAKE_AMOUNT);
+    Scenario::new()
+        .user("alice").with_tokens(STAKE_AMOUNT)
+        .user("bob").with_tokens(STAKE_AMOUNT)
+        .stake_legacy("alice", STAKE_AMOUNT)
+        .stake_legacy("bob", STAKE_AMOUNT)
+        .advance_epochs(1)
+        .distribute("alice")
+        .distribute("bob")
+        .distribute("alice").fails_with(OntoraError::AlreadyDistributedThisEpoch)
+        .distribute("bob").fails_with(OntoraError::AlreadyDistributedThisEpoch)
+        .expect_rewards("alice", reward)
+        .expect_rewards("bob", reward)
+        .advance_epochs(1)
+        .distribute("bob")
+        .distribute("alice")
+        .expect_rewards("alice", 2 * reward)
+        .expect_rewards("bob", 2 * reward)
+        .run();
+}
+
+// Test case 7: Distribution opens at the start of the epoch after the pool's, and each
+// later epoch starts on the count from REWARD_GENESIS, not from the last distribution
+#[test]
+fn test_distribution_epoch_boundary() {
+    let mut ctx = setup_svm();
+    let platform = initialize_program(&mut ctx, TEST_REWARD_POOL_AMOUNT);
+    let staked = register_legacy_agent(&mut ctx, &platform, STAKE_AMOUNT);
+    let now = get_clock(&ctx).unix_timestamp;
+    let open_epoch = fetch_account::<RewardPool>(&ctx, &platform.reward_pool).open_epoch();
+    assert_eq!(open_epoch, reward_epoch(now) + 1);
+    let boundary = REWARD_GENESIS + open_epoch as i64 * EPOCH_DURATION;
+
+    // One second before the boundary the pool's epoch has not ended
+    warp_seconds(&mut ctx, boundary - 1 - now);
+    let result = distribute_rewards(&mut ctx, &platform, staked.agent);
+    assert_eq!(svm_error_code(&result), Some(u32::from(OntoraError::CooldownNotCompleted)));
+
+    // At the boundary it has
+    warp_seconds(&mut ctx, 1);
+    distribute_rewards(&mut ctx, &platform, staked.agent).unwrap();
+    assert_eq!(get_pending_rewards(&ctx, staked.agent), TEST_EPOCH_EMISSION);
+    assert_eq!(fetch_account::<AIAgent>(&ctx, &staked.agent).last_reward_epoch, open_epoch);
+
+    // The agent waits for the next boundary, a whole epoch on
+    warp_seconds(&mut ctx, EPOCH_DURATION - 1);
+    let result = distribute_rewards(&mut ctx, &platform, staked.agent);
+    assert_eq!(svm_error_code(&result), Some(u32::from(OntoraError::AlreadyDistributedThisEpoch)));
+    warp_seconds(&mut ctx, 1);
+    distribute_rewards(&mut ctx, &platform, staked.agent).unwrap();
+}
+
+// Test case 8: One epoch's distribution spread over batches, closed by the last one
+#[test]
+fn test_batch_distribution_spans_transactions() {
+    let mut ctx = setup_svm();
+    let platform = initialize_program(&mut ctx, TEST_REWARD_POOL_AMOUNT);
+    let mut agents: Vec<Pubkey> =
+        (0..3).map(|_| register_legacy_agent(&mut ctx, &platform, STAKE_AMOUNT).agent).collect();
+    agents.sort();
+
+    warp_seconds(&mut ctx, EPOCH_DURATION);
+    distribute_rewards_batch(&mut ctx, &platform, &agents[..2], false).unwrap();
+    distribute_rewards_batch(&mut ctx, &platform, &agents[2..], true).unwrap();
+
+    let reward = share(STAKE_AMOUNT, 3 * STAKE_AMOUNT);
+    for agent in &agents {
+        assert_eq!(get_pending_rewards(&ctx, *agent), reward);
     }
+    // A third of the emission doesn't divide evenly; the dust stays in the pool
+    assert!(3 * reward < TEST_EPOCH_EMISSION);
+    let pool: RewardPool = fetch_account(&ctx, &platform.reward_pool);
+    assert_eq!(pool.total_rewards, TEST_REWARD_POOL_AMOUNT - 3 * reward);
+
+    // The closing batch ended the epoch
+    let result = distribute_rewards_batch(&mut ctx, &platform, &agents[..1], false);
+    assert_eq!(svm_error_code(&result), Some(u32::from(OntoraError::CooldownNotCompleted)));
+}
 
-    async fn get_pending_rewards(
-        test_context: &mut ProgramTestContext,
-        stake_account: &Pubkey,
-    ) -> Result<u64, TransportError> {
-        // Placeholder: Fetch pending rewards from account data
-        Ok(STAKE_AMOUNT * REWARD_RATE / 100)
+// Test case 9: Batches must list agents once each, in ascending address order
+#[test]
+fn test_batch_rejects_unordered_agents() {
+    let mut ctx = setup_svm();
+    let platform = initialize_program(&mut ctx, TEST_REWARD_POOL_AMOUNT);
+    let mut agents: Vec<Pubkey> =
+        (0..2).map(|_| register_legacy_agent(&mut ctx, &platform, STAKE_AMOUNT).agent).collect();
+    agents.sort();
+
+    warp_seconds(&mut ctx, EPOCH_DURATION);
+    let duplicated = [agents[0], agents[0]];
+    let result = distribute_rewards_batch(&mut ctx, &platform, &duplicated, true);
+    assert_eq!(svm_error_code(&result), Some(u32::from(OntoraError::InvalidAccount)));
+
+    let descending = [agents[1], agents[0]];
+    let result = distribute_rewards_batch(&mut ctx, &platform, &descending, true);
+    assert_eq!(svm_error_code(&result), Some(u32::from(OntoraError::InvalidAccount)));
+    assert_eq!(get_pending_rewards(&ctx, agents[0]), 0);
+}
+
+// Test case 10: Topping up a stake keeps the rewards it accrued, and later epochs pay on the new share
+#[test]
+fn test_increase_stake_keeps_accumulated_rewards() {
+    let mut ctx = setup_svm();
+    let platform = initialize_program(&mut ctx, TEST_REWARD_POOL_AMOUNT);
+    let staked = register_legacy_agent(&mut ctx, &platform, STAKE_AMOUNT);
+    register_legacy_agent(&mut ctx, &platform, STAKE_AMOUNT);
+    warp_seconds(&mut ctx, EPOCH_DURATION);
+    distribute_rewards(&mut ctx, &platform, staked.agent).unwrap();
+    let first_reward = share(STAKE_AMOUNT, 2 * STAKE_AMOUNT);
+
+    mint_tokens(&mut ctx, &platform.mint, &platform.admin, &staked.token_account, STAKE_AMOUNT);
+    let instruction = increase_stake_instruction(&platform, &staked, STAKE_AMOUNT);
+    send_transaction(&mut ctx, &[instruction], &[&staked.owner.keypair]).unwrap();
+    let agent: AIAgent = fetch_account(&ctx, &staked.agent);
+    assert_eq!((agent.staked_amount, agent.accumulated_rewards), (2 * STAKE_AMOUNT, first_reward));
+
+    warp_seconds(&mut ctx, EPOCH_DURATION);
+    distribute_rewards(&mut ctx, &platform, staked.agent).unwrap();
+    let second_reward = share(2 * STAKE_AMOUNT, 3 * STAKE_AMOUNT);
+    assert_eq!(get_pending_rewards(&ctx, staked.agent), first_reward + second_reward);
+}
+
+// Test case 11: Ten agents paid in one batch can't be paid again until the next epoch opens
+#[test]
+fn test_batch_distribution_pays_each_agent_once_per_epoch() {
+    let mut ctx = setup_svm();
+    let platform = initialize_program(&mut ctx, TEST_REWARD_POOL_AMOUNT);
+    let mut agents: Vec<Pubkey> =
+        (0..10).map(|_| register_legacy_agent(&mut ctx, &platform, STAKE_AMOUNT).agent).collect();
+    agents.sort();
+
+    warp_seconds(&mut ctx, EPOCH_DURATION);
+    distribute_rewards_batch(&mut ctx, &platform, &agents, false).unwrap();
+    let reward = share(STAKE_AMOUNT, 10 * STAKE_AMOUNT);
+    let open_epoch = fetch_account::<RewardPool>(&ctx, &platform.reward_pool).open_epoch();
+    for agent in &agents {
+        let agent: AIAgent = fetch_account(&ctx, agent);
+        assert_eq!((agent.accumulated_rewards, agent.last_reward_epoch), (reward, open_epoch));
     }
 
-    async fn advance_time(
-        test_context: &mut ProgramTestContext,
-        seconds: u64,
-    ) -> Result<(), TransportError> {
-        // Placeholder: Simulate time passing for reward accrual
-        println!("Advancing time by {} seconds", seconds);
-        Ok(())
+    // The epoch is still open, but neither instruction pays any of them again in it
+    let result = distribute_rewards_batch(&mut ctx, &platform, &agents[9..], false);
+    assert_eq!(svm_error_code(&result), Some(u32::from(OntoraError::AlreadyDistributedThisEpoch)));
+    let result = distribute_rewards(&mut ctx, &platform, agents[0]);
+    assert_eq!(svm_error_code(&result), Some(u32::from(OntoraError::AlreadyDistributedThisEpoch)));
+    distribute_rewards_batch(&mut ctx, &platform, &[], true).unwrap();
+    let pool: RewardPool = fetch_account(&ctx, &platform.reward_pool);
+    assert_eq!(pool.total_rewards, TEST_REWARD_POOL_AMOUNT - 10 * reward);
+    assert!(agents.iter().all(|agent| get_pending_rewards(&ctx, *agent) == reward));
+
+    // The next epoch pays them all again
+    warp_seconds(&mut ctx, EPOCH_DURATION);
+    distribute_rewards_batch(&mut ctx, &platform, &agents, true).unwrap();
+    assert!(agents.iter().all(|agent| get_pending_rewards(&ctx, *agent) == 2 * reward));
+}
+
+// Test case 12: Anyone can top up the pool, and a pool drained by distribution pays again once funded
+#[test]
+fn test_fund_reward_pool_after_depletion() {
+    let mut ctx = setup_svm();
+    let platform = initialize_program(&mut ctx, 0);
+    let staked = register_legacy_agent(&mut ctx, &platform, STAKE_AMOUNT);
+    let reward = TEST_EPOCH_EMISSION;
+    let funder = create_test_user(&mut ctx);
+    let funder_tokens = create_funded_token_account(&mut ctx, &platform, &funder.pubkey, 2 * reward);
+    let total_rewards = |ctx: &SvmContext| fetch_account::<RewardPool>(ctx, &platform.reward_pool).total_rewards;
+
+    let meta = fund_reward_pool(&mut ctx, &platform, &funder, funder_tokens, reward).unwrap();
+    let funded = RewardPoolFunded {
+        schema_version: RewardPoolFunded::SCHEMA_VERSION,
+        funder: funder.pubkey,
+        amount: reward,
+        total_rewards: reward,
+        timestamp: get_clock(&ctx).unix_timestamp,
+    };
+    assert_eq!(parse_events::<RewardPoolFunded>(&meta.logs), [funded]);
+    assert_eq!((total_rewards(&ctx), get_token_balance(&ctx, &platform.reward_vault)), (reward, reward));
+
+    // One epoch drains the pool, and the next can't be paid
+    warp_seconds(&mut ctx, EPOCH_DURATION);
+    distribute_rewards(&mut ctx, &platform, staked.agent).unwrap();
+    assert_eq!(total_rewards(&ctx), 0);
+    warp_seconds(&mut ctx, EPOCH_DURATION);
+    let result = distribute_rewards(&mut ctx, &platform, staked.agent);
+    assert_eq!(svm_error_code(&result), Some(u32::from(OntoraError::RewardPoolDepleted)));
+
+    // Funding it again lets the epoch be paid
+    let result = fund_reward_pool(&mut ctx, &platform, &funder, funder_tokens, 0);
+    assert_eq!(svm_error_code(&result), Some(u32::from(OntoraError::InvalidStakeAmount)));
+    fund_reward_pool(&mut ctx, &platform, &funder, funder_tokens, reward).unwrap();
+    assert_eq!(total_rewards(&ctx), reward);
+    distribute_rewards(&mut ctx, &platform, staked.agent).unwrap();
+    assert_eq!(total_rewards(&ctx), 0);
+    assert_eq!(get_pending_rewards(&ctx, staked.agent), 2 * reward);
+    assert_eq!(get_token_balance(&ctx, &platform.reward_vault), 2 * reward);
+}
+
+// Test case 13: Uneven stakes split the emission pro rata, and the rounding dust stays in the pool
+#[test]
+fn test_batch_distribution_is_pro_rata() {
+    let mut ctx = setup_svm();
+    let platform = initialize_program(&mut ctx, TEST_REWARD_POOL_AMOUNT);
+    let stakes = [STAKE_AMOUNT, 2 * STAKE_AMOUNT, 4 * STAKE_AMOUNT];
+    let mut agents: Vec<(Pubkey, u64)> =
+        stakes.iter().map(|stake| (register_legacy_agent(&mut ctx, &platform, *stake).agent, *stake)).collect();
+    agents.sort();
+
+    warp_seconds(&mut ctx, EPOCH_DURATION);
+    let addresses: Vec<Pubkey> = agents.iter().map(|(agent, _)| *agent).collect();
+    distribute_rewards_batch(&mut ctx, &platform, &addresses, true).unwrap();
+
+    let total: u64 = stakes.iter().sum();
+    for (agent, stake) in &agents {
+        assert_eq!(get_pending_rewards(&ctx, *agent), share(*stake, total));
     }
+    let paid: u64 = stakes.iter().map(|stake| share(*stake, total)).sum();
+    assert_eq!(paid, TEST_EPOCH_EMISSION - 1);
+    let pool: RewardPool = fetch_account(&ctx, &platform.reward_pool);
+    assert_eq!(pool.total_rewards, TEST_REWARD_POOL_AMOUNT - paid);
+}
+
+// Test case 14: Stake requested for withdrawal stops earning at once, though its tokens
+// stay in the agent's vault until claimed
+#[test]
+fn test_requested_unstake_stops_earning() {
+    let mut ctx = setup_svm();
+    let platform = initialize_program(&mut ctx, TEST_REWARD_POOL_AMOUNT);
+    let leaving = register_legacy_agent(&mut ctx, &platform, STAKE_AMOUNT);
+    let staying = register_legacy_agent(&mut ctx, &platform, STAKE_AMOUNT);
+    let instruction = request_unstake_instruction(&platform, &leaving, STAKE_AMOUNT / 2);
+    send_transaction(&mut ctx, &[instruction], &[&leaving.owner.keypair]).unwrap();
+
+    warp_seconds(&mut ctx, EPOCH_DURATION);
+    let mut agents = [leaving.agent, staying.agent];
+    agents.sort();
+    distribute_rewards_batch(&mut ctx, &platform, &agents, true).unwrap();
+
+    // The emission is shared over the stake left: a third to the leaving agent, two to the other
+    let total = 3 * STAKE_AMOUNT / 2;
+    assert_eq!(get_pending_rewards(&ctx, leaving.agent), share(STAKE_AMOUNT / 2, total));
+    assert_eq!(get_pending_rewards(&ctx, staying.agent), share(STAKE_AMOUNT, total));
+    assert_eq!(get_token_balance(&ctx, &leaving.staking_vault), STAKE_AMOUNT);
+}
+
+// Test case 15: With vesting set, a claim pays nothing at once and the reward is released
+// linearly over the vesting period
+#[test]
+fn test_vested_claim_releases_linearly() {
+    let mut ctx = setup_svm();
+    let platform = initialize_program(&mut ctx, TEST_REWARD_POOL_AMOUNT);
+    set_vesting_seconds(&mut ctx, &platform, VESTING_SECONDS);
+    let staked = register_legacy_agent(&mut ctx, &platform, STAKE_AMOUNT);
+    warp_seconds(&mut ctx, EPOCH_DURATION);
+    distribute_rewards(&mut ctx, &platform, staked.agent).unwrap();
+    let initial_balance = get_token_balance(&ctx, &staked.token_account);
+
+    claim_rewards(&mut ctx, &platform, &staked).unwrap();
+    assert_eq!(get_pending_rewards(&ctx, staked.agent), 0);
+    assert_eq!(get_token_balance(&ctx, &staked.token_account), initial_balance);
+    let result = release_vested(&mut ctx, &platform, &staked);
+    assert_eq!(svm_error_code(&result), Some(u32::from(OntoraError::NoRewardsAvailable)));
+
+    // A quarter of the way through, a quarter has vested
+    warp_seconds(&mut ctx, VESTING_SECONDS / 4);
+    release_vested(&mut ctx, &platform, &staked).unwrap();
+    let quarter = TEST_EPOCH_EMISSION / 4;
+    assert_eq!(get_token_balance(&ctx, &staked.token_account), initial_balance + quarter);
+
+    // Past the end the rest is released, and nothing after that
+    warp_seconds(&mut ctx, VESTING_SECONDS);
+    release_vested(&mut ctx, &platform, &staked).unwrap();
+    assert_eq!(get_token_balance(&ctx, &staked.token_account), initial_balance + TEST_EPOCH_EMISSION);
+    assert_eq!(get_token_balance(&ctx, &platform.reward_vault), TEST_REWARD_POOL_AMOUNT - TEST_EPOCH_EMISSION);
+    warp_seconds(&mut ctx, 1);
+    let result = release_vested(&mut ctx, &platform, &staked);
+    assert_eq!(svm_error_code(&result), Some(u32::from(OntoraError::NoRewardsAvailable)));
+}
+
+// Test case 16: A claim during a schedule pays what had vested and restarts the schedule
+// with the unvested rest plus the new reward, ending at their amount-weighted end
+#[test]
+fn test_overlapping_vested_claims_merge() {
+    let mut ctx = setup_svm();
+    let platform = initialize_program(&mut ctx, TEST_REWARD_POOL_AMOUNT);
+    set_vesting_seconds(&mut ctx, &platform, VESTING_SECONDS);
+    let staked = register_legacy_agent(&mut ctx, &platform, STAKE_AMOUNT);
+    warp_seconds(&mut ctx, EPOCH_DURATION);
+    distribute_rewards(&mut ctx, &platform, staked.agent).unwrap();
+    claim_rewards(&mut ctx, &platform, &staked).unwrap();
+    let initial_balance = get_token_balance(&ctx, &staked.token_account);
+
+    // Half the first reward has vested by the next epoch's claim
+    warp_seconds(&mut ctx, EPOCH_DURATION);
+    distribute_rewards(&mut ctx, &platform, staked.agent).unwrap();
+    claim_rewards(&mut ctx, &platform, &staked).unwrap();
+    let start = get_clock(&ctx).unix_timestamp;
+    let half = TEST_EPOCH_EMISSION / 2;
+    assert_eq!(get_token_balance(&ctx, &staked.token_account), initial_balance + half);
+    let vesting: RewardVesting = fetch_account(&ctx, &reward_vesting_pda(&ontora_ai::ID, &staked.owner.pubkey));
+    let schedule = (vesting.total_amount, vesting.released_amount, vesting.start_time, vesting.duration);
+    let remaining = VESTING_SECONDS - EPOCH_DURATION;
+    let duration = weighted_vesting_duration(half, remaining, TEST_EPOCH_EMISSION, VESTING_SECONDS);
+    assert_eq!(schedule, (half + TEST_EPOCH_EMISSION, 0, start, duration));
+
+    warp_seconds(&mut ctx, VESTING_SECONDS);
+    release_vested(&mut ctx, &platform, &staked).unwrap();
+    assert_eq!(get_token_balance(&ctx, &staked.token_account), initial_balance + 2 * TEST_EPOCH_EMISSION);
+}
+
+// Test case 17: Turning vesting off leaves a running schedule in place, and later claims pay at once
+#[test]
+fn test_vesting_off_pays_claims_at_once() {
+    let mut ctx = setup_svm();
+    let platform = initialize_program(&mut ctx, TEST_REWARD_POOL_AMOUNT);
+    set_vesting_seconds(&mut ctx, &platform, VESTING_SECONDS);
+    let staked = register_legacy_agent(&mut ctx, &platform, STAKE_AMOUNT);
+    warp_seconds(&mut ctx, EPOCH_DURATION);
+    distribute_rewards(&mut ctx, &platform, staked.agent).unwrap();
+    claim_rewards(&mut ctx, &platform, &staked).unwrap();
+    let initial_balance = get_token_balance(&ctx, &staked.token_account);
+
+    set_vesting_seconds(&mut ctx, &platform, 0);
+    warp_seconds(&mut ctx, EPOCH_DURATION);
+    distribute_rewards(&mut ctx, &platform, staked.agent).unwrap();
+    claim_rewards(&mut ctx, &platform, &staked).unwrap();
+    assert_eq!(get_token_balance(&ctx, &staked.token_account), initial_balance + TEST_EPOCH_EMISSION);
+
+    // The first reward keeps vesting on its schedule
+    release_vested(&mut ctx, &platform, &staked).unwrap();
+    assert_eq!(get_token_balance(&ctx, &staked.token_account), initial_balance + 3 * TEST_EPOCH_EMISSION / 2);
+}
+
+// Test case 18: Funding a pool-owned token account other than the platform's reward vault is refused
+#[test]
+fn test_fund_reward_pool_rejects_other_vault() {
+    let mut ctx = setup_svm();
+    let platform = initialize_program(&mut ctx, 0);
+    let funder = create_test_user(&mut ctx);
+    let funder_tokens = create_funded_token_account(&mut ctx, &platform, &funder.pubkey, TEST_EPOCH_EMISSION);
+    let other_vault = create_token_account(&mut ctx, &platform.reward_pool, &platform.mint);
+
+    let result = fund_reward_vault(&mut ctx, &platform, other_vault, &funder, funder_tokens, TEST_EPOCH_EMISSION);
+    assert_eq!(svm_error_code(&result), Some(u32::from(OntoraError::InvalidVault)));
+    assert_eq!(get_token_balance(&ctx, &funder_tokens), TEST_EPOCH_EMISSION);
+    assert_eq!(fetch_account::<RewardPool>(&ctx, &platform.reward_pool).total_rewards, 0);
+}
+
+// Test case 19: A second claim halfway through a schedule does not hold back the first
+// reward: by the end of the first schedule all of it has been paid
+#[test]
+fn test_second_claim_keeps_first_schedule_end() {
+    const DAY: i64 = 86_400;
+    let mut ctx = setup_svm();
+    let platform = initialize_program(&mut ctx, TEST_REWARD_POOL_AMOUNT);
+    set_vesting_seconds(&mut ctx, &platform, 30 * DAY);
+    let staked = register_legacy_agent(&mut ctx, &platform, STAKE_AMOUNT);
+    warp_seconds(&mut ctx, EPOCH_DURATION);
+    distribute_rewards(&mut ctx, &platform, staked.agent).unwrap();
+    claim_rewards(&mut ctx, &platform, &staked).unwrap();
+    let initial_balance = get_token_balance(&ctx, &staked.token_account);
+
+    warp_seconds(&mut ctx, 15 * DAY);
+    distribute_rewards(&mut ctx, &platform, staked.agent).unwrap();
+    claim_rewards(&mut ctx, &platform, &staked).unwrap();
+    let half = TEST_EPOCH_EMISSION / 2;
+    assert_eq!(get_token_balance(&ctx, &staked.token_account), initial_balance + half);
+
+    // Day 30 of the first schedule: the merged one ends on day 40
+    warp_seconds(&mut ctx, 15 * DAY);
+    release_vested(&mut ctx, &platform, &staked).unwrap();
+    let merged = vested_amount(half + TEST_EPOCH_EMISSION, 0, 25 * DAY, 15 * DAY);
+    let paid = get_token_balance(&ctx, &staked.token_account) - initial_balance;
+    assert_eq!(paid, half + merged);
+    assert!(paid >= TEST_EPOCH_EMISSION, "the first reward must be fully released by day 30");
 }
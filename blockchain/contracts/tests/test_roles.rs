@@ -0,0 +1,162 @@
+// test_roles.rs
+// This module contains test cases for role-based access control: each delegated
+// role gating only its own instructions, admin rotation, and renouncing a role.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::solana_program::system_program;
+use anchor_client::Program;
+use solana_program_test::*;
+
+// Import test setup utilities (assumes test_setup.rs is in the same directory)
+mod test_setup;
+use test_setup::*;
+
+use ontora_ai::pda;
+use ontora_ai::state::{PlatformConfig, Role, Roles, PAUSE_CLAIMS};
+
+const ALL_ROLES: [Role; 4] = [Role::Crank, Role::Oracle, Role::Pauser, Role::Treasury];
+
+async fn fetch_platform_config(banks_client: &mut BanksClient, platform_config: Pubkey) -> PlatformConfig {
+    let account = banks_client.get_account(platform_config).await.unwrap().unwrap();
+    PlatformConfig::try_deserialize(&mut account.data.as_ref()).unwrap()
+}
+
+async fn set_role(program: &Program, platform_config: Pubkey, signer: &TestUser, role: Role, holder: Pubkey) -> std::result::Result<(), anchor_client::ClientError> {
+    program
+        .request()
+        .accounts(ontora_ai::accounts::SetRole {
+            platform_config,
+            admin: signer.pubkey,
+        })
+        .args(ontora_ai::instruction::SetRole { role, holder })
+        .signer(&signer.keypair)
+        .send()
+        .await
+        .map(|_| ())
+}
+
+async fn renounce_role(program: &Program, platform_config: Pubkey, holder: &TestUser, role: Role) -> std::result::Result<(), anchor_client::ClientError> {
+    program
+        .request()
+        .accounts(ontora_ai::accounts::RenounceRole {
+            platform_config,
+            holder: holder.pubkey,
+        })
+        .args(ontora_ai::instruction::RenounceRole { role })
+        .signer(&holder.keypair)
+        .send()
+        .await
+        .map(|_| ())
+}
+
+async fn pause(program: &Program, platform_config: Pubkey, authority: &TestUser) -> std::result::Result<(), anchor_client::ClientError> {
+    program
+        .request()
+        .accounts(ontora_ai::accounts::SetPauseFlags {
+            platform_config,
+            authority: authority.pubkey,
+        })
+        .args(ontora_ai::instruction::SetPauseFlags { flags: PAUSE_CLAIMS })
+        .signer(&authority.keypair)
+        .send()
+        .await
+        .map(|_| ())
+}
+
+async fn initialize_treasury(program: &Program, platform_config: Pubkey, authority: &TestUser) -> std::result::Result<(), anchor_client::ClientError> {
+    let treasury = Pubkey::find_program_address(&[pda::TREASURY_SEED], &program.id()).0;
+    program
+        .request()
+        .accounts(ontora_ai::accounts::InitializeTreasury {
+            platform_config,
+            treasury,
+            authority: authority.pubkey,
+            system_program: system_program::ID,
+        })
+        .args(ontora_ai::instruction::InitializeTreasury {
+            governance: authority.pubkey,
+        })
+        .signer(&authority.keypair)
+        .send()
+        .await
+        .map(|_| ())
+}
+
+// Test that each holder matches only its own role and a renounced role matches nobody
+#[test]
+fn test_has_role_matches_only_holder() {
+    let keys: Vec<Pubkey> = (0..4).map(|_| Pubkey::new_unique()).collect();
+    let mut config = PlatformConfig {
+        pauser: keys[2],
+        roles: Roles {
+            crank: keys[0],
+            oracle: keys[1],
+            treasury: keys[3],
+        },
+        ..Default::default()
+    };
+
+    for (i, role) in ALL_ROLES.iter().enumerate() {
+        for (j, key) in keys.iter().enumerate() {
+            assert_eq!(config.has_role(*role, key), i == j);
+        }
+    }
+
+    config.set_role_holder(Role::Oracle, Pubkey::default());
+    assert!(!config.has_role(Role::Oracle, &Pubkey::default()));
+    assert!(!config.has_role(Role::Oracle, &keys[1]));
+}
+
+// Test that delegated keys can only act in their own role
+#[tokio::test]
+async fn test_roles_are_separated() {
+    let (mut ctx, program) = setup_test_context().await;
+    let admin = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
+    let pauser = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
+    let treasurer = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
+    let platform_config = initialize_test_platform(&program, &admin).await;
+
+    set_role(&program, platform_config, &admin, Role::Pauser, pauser.pubkey).await.unwrap();
+    set_role(&program, platform_config, &admin, Role::Treasury, treasurer.pubkey).await.unwrap();
+
+    // Neither the admin nor the treasurer may pause any more
+    assert!(pause(&program, platform_config, &admin).await.is_err());
+    assert!(pause(&program, platform_config, &treasurer).await.is_err());
+
+    // The pauser cannot create the treasury, nor can the admin
+    assert!(initialize_treasury(&program, platform_config, &pauser).await.is_err());
+    assert!(initialize_treasury(&program, platform_config, &admin).await.is_err());
+
+    assert!(pause(&program, platform_config, &pauser).await.is_ok());
+    assert!(initialize_treasury(&program, platform_config, &treasurer).await.is_ok());
+}
+
+// Test that only the admin rotates roles and a holder can renounce
+#[tokio::test]
+async fn test_admin_rotates_and_holder_renounces() {
+    let (mut ctx, program) = setup_test_context().await;
+    let admin = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
+    let crank = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
+    let replacement = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
+    let platform_config = initialize_test_platform(&program, &admin).await;
+
+    // Every role starts with the admin
+    let config = fetch_platform_config(&mut ctx.banks_client, platform_config).await;
+    assert!(ALL_ROLES.iter().all(|role| config.has_role(*role, &admin.pubkey)));
+
+    // A role holder cannot rotate roles
+    set_role(&program, platform_config, &admin, Role::Crank, crank.pubkey).await.unwrap();
+    assert!(set_role(&program, platform_config, &crank, Role::Crank, replacement.pubkey).await.is_err());
+
+    // The admin rotates the crank
+    set_role(&program, platform_config, &admin, Role::Crank, replacement.pubkey).await.unwrap();
+    let config = fetch_platform_config(&mut ctx.banks_client, platform_config).await;
+    assert_eq!(config.roles.crank, replacement.pubkey);
+
+    // Only the current holder can renounce
+    assert!(renounce_role(&program, platform_config, &crank, Role::Crank).await.is_err());
+    assert!(renounce_role(&program, platform_config, &replacement, Role::Crank).await.is_ok());
+    let config = fetch_platform_config(&mut ctx.banks_client, platform_config).await;
+    assert_eq!(config.roles.crank, Pubkey::default());
+}
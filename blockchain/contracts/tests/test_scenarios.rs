@@ -0,0 +1,410 @@
+// test_scenarios.rs
+// This module contains end-to-end scenarios spanning several users, agents, epochs, and
+// proposals, written with the Scenario builder in svm/scenario.rs. Stake that votes is placed
+// a second before the proposal opens, as only stake predating a proposal votes on it.
+
+// Shared types and PDAs from test_setup.rs, helpers from the in-process harness
+mod svm;
+mod test_setup;
+use svm::*;
+use test_setup::{TEST_EPOCH_EMISSION, TEST_STAKE_AMOUNT, TEST_VOTE_UNIT};
+
+use ontora_ai::error::OntoraError;
+use ontora_ai::state::{
+    PlatformConfig, ProposalAction, FINALIZED_APPROVED, FINALIZED_BELOW_THRESHOLD, FINALIZED_NO_QUORUM,
+    FINALIZED_TIE, PROPOSAL_ACTIVE, PROPOSAL_APPROVED, PROPOSAL_EXECUTED, PROPOSAL_REJECTED,
+};
+
+const VOTING_SECONDS: u64 = 3 * 86_400;
+// Votes one TEST_STAKE_AMOUNT of stake carries
+const VOTES_PER_STAKE: u64 = TEST_STAKE_AMOUNT / TEST_VOTE_UNIT;
+// One epoch's payout on a stake: its share of the epoch emission
+const fn epoch_reward(stake: u64, total_staked: u64) -> u64 {
+    (TEST_EPOCH_EMISSION as u128 * stake as u128 / total_staked as u128) as u64
+}
+
+// Two users, two agents, and one proposal: each voter's total stake across agents
+// counts one vote per vote unit, the one the proposal recorded when it opened
+#[test]
+fn test_stake_weighted_proposal_passes() {
+    let world = Scenario::new()
+        .user("alice").with_tokens(TEST_STAKE_AMOUNT)
+        .user("bob").with_tokens(3 * TEST_STAKE_AMOUNT)
+        .agent("gpt-agent").owned_by("alice")
+        .agent("claude-agent").owned_by("bob")
+        .stake("alice", "gpt-agent", TEST_STAKE_AMOUNT)
+        .stake("bob", "gpt-agent", TEST_STAKE_AMOUNT)
+        .stake("bob", "claude-agent", 2 * TEST_STAKE_AMOUNT)
+        .advance_seconds(1)
+        .propose("raise-rewards", "alice", &["keep", "raise"], VOTING_SECONDS)
+        .vote("alice", "raise-rewards", 0)
+        .vote("bob", "raise-rewards", 1)
+        .expect_proposal("raise-rewards", PROPOSAL_ACTIVE, &[VOTES_PER_STAKE, 3 * VOTES_PER_STAKE])
+        .finalize("raise-rewards").fails_with(OntoraError::VotingPeriodNotEnded)
+        .advance_seconds(VOTING_SECONDS as i64 + 1)
+        .vote("alice", "raise-rewards", 0).fails_with(OntoraError::ProposalNotActive)
+        .finalize("raise-rewards")
+        .expect_proposal("raise-rewards", PROPOSAL_APPROVED, &[VOTES_PER_STAKE, 3 * VOTES_PER_STAKE])
+        .expect_finalized("raise-rewards", FINALIZED_APPROVED)
+        .finalize("raise-rewards").fails_with(OntoraError::ProposalAlreadyFinalized)
+        .run();
+
+    assert_eq!(world.proposal("raise-rewards").vote_unit, TEST_VOTE_UNIT);
+}
+
+// A voter with ten times another's stake outvotes them ten to one
+#[test]
+fn test_vote_weight_is_proportional_to_stake() {
+    Scenario::new()
+        .user("alice").with_tokens(TEST_STAKE_AMOUNT)
+        .user("bob").with_tokens(10 * TEST_STAKE_AMOUNT)
+        .agent("gpt-agent").owned_by("alice")
+        .stake("alice", "gpt-agent", TEST_STAKE_AMOUNT)
+        .stake("bob", "gpt-agent", 10 * TEST_STAKE_AMOUNT)
+        .advance_seconds(1)
+        .propose("raise-rewards", "alice", &["keep", "raise"], VOTING_SECONDS)
+        .vote("alice", "raise-rewards", 0)
+        .vote("bob", "raise-rewards", 1)
+        .expect_proposal("raise-rewards", PROPOSAL_ACTIVE, &[VOTES_PER_STAKE, 10 * VOTES_PER_STAKE])
+        .run();
+}
+
+// A wallet that has withdrawn all its stake has no vote left to cast
+#[test]
+fn test_vote_without_stake_is_rejected() {
+    Scenario::new()
+        .with_reward_pool(0)
+        .user("alice").with_tokens(TEST_STAKE_AMOUNT)
+        .agent("gpt-agent").owned_by("alice")
+        .stake("alice", "gpt-agent", TEST_STAKE_AMOUNT)
+        .unstake("alice", "gpt-agent", TEST_STAKE_AMOUNT)
+        .propose("raise-rewards", "alice", &["keep", "raise"], VOTING_SECONDS)
+        .vote("alice", "raise-rewards", 0).fails_with(OntoraError::InsufficientVotingPower)
+        .expect_proposal("raise-rewards", PROPOSAL_ACTIVE, &[0, 0])
+        .run();
+}
+
+// A wallet votes once per proposal: a second vote, for either option, is refused and
+// leaves the tallies as the first vote left them
+#[test]
+fn test_second_vote_is_rejected() {
+    Scenario::new()
+        .user("alice").with_tokens(TEST_STAKE_AMOUNT)
+        .agent("gpt-agent").owned_by("alice")
+        .stake("alice", "gpt-agent", TEST_STAKE_AMOUNT)
+        .advance_seconds(1)
+        .propose("raise-rewards", "alice", &["keep", "raise"], VOTING_SECONDS)
+        .vote("alice", "raise-rewards", 1)
+        .vote("alice", "raise-rewards", 1).fails_with(OntoraError::AlreadyVoted)
+        .vote("alice", "raise-rewards", 0).fails_with(OntoraError::AlreadyVoted)
+        .expect_proposal("raise-rewards", PROPOSAL_ACTIVE, &[0, VOTES_PER_STAKE])
+        .run();
+}
+
+// Stake placed after a proposal opens has no vote on it, so tokens voted once cannot be
+// unstaked and staked again from a second wallet to vote twice; a stake that already voted
+// keeps its vote after leaving
+#[test]
+fn test_stake_after_proposal_has_no_voting_power() {
+    Scenario::new()
+        .with_reward_pool(0)
+        .user("alice").with_tokens(TEST_STAKE_AMOUNT)
+        .user("mallory").with_tokens(TEST_STAKE_AMOUNT)
+        .agent("gpt-agent").owned_by("alice")
+        .stake("mallory", "gpt-agent", TEST_STAKE_AMOUNT)
+        .advance_seconds(1)
+        .propose("raise-rewards", "alice", &["keep", "raise"], VOTING_SECONDS)
+        .stake("alice", "gpt-agent", TEST_STAKE_AMOUNT)
+        .vote("alice", "raise-rewards", 0).fails_with(OntoraError::InsufficientVotingPower)
+        .vote("mallory", "raise-rewards", 1)
+        // The tokens mallory voted with, moved to a second wallet
+        .user("mallory-2").with_tokens(TEST_STAKE_AMOUNT)
+        .unstake("mallory", "gpt-agent", TEST_STAKE_AMOUNT)
+        .stake("mallory-2", "gpt-agent", TEST_STAKE_AMOUNT)
+        .vote("mallory-2", "raise-rewards", 1).fails_with(OntoraError::InsufficientVotingPower)
+        .advance_seconds(1)
+        .vote("mallory-2", "raise-rewards", 1).fails_with(OntoraError::InsufficientVotingPower)
+        .expect_proposal("raise-rewards", PROPOSAL_ACTIVE, &[0, VOTES_PER_STAKE])
+        .run();
+}
+
+// Staking for another wallet after a proposal opens neither takes away the vote its own
+// stake has nor adds one: mallory cannot silence alice by funding her stake, and cannot
+// vote the same tokens twice by moving them onto a wallet that has not voted
+#[test]
+fn test_stake_funded_by_others_does_not_change_voting_power() {
+    Scenario::new()
+        .with_reward_pool(0)
+        .user("alice").with_tokens(TEST_STAKE_AMOUNT)
+        .user("bob").with_tokens(TEST_STAKE_AMOUNT)
+        .user("mallory").with_tokens(3 * TEST_STAKE_AMOUNT)
+        .agent("gpt-agent").owned_by("alice")
+        .stake("alice", "gpt-agent", TEST_STAKE_AMOUNT)
+        .stake("bob", "gpt-agent", TEST_STAKE_AMOUNT)
+        .stake("mallory", "gpt-agent", TEST_STAKE_AMOUNT)
+        .advance_seconds(1)
+        .propose("raise-rewards", "alice", &["keep", "raise"], VOTING_SECONDS)
+        .stake_for("mallory", "alice", "gpt-agent", TEST_STAKE_AMOUNT)
+        .vote("alice", "raise-rewards", 0)
+        .vote("mallory", "raise-rewards", 1)
+        .unstake("mallory", "gpt-agent", TEST_STAKE_AMOUNT)
+        .stake_for("mallory", "bob", "gpt-agent", TEST_STAKE_AMOUNT)
+        .vote("bob", "raise-rewards", 1)
+        .expect_proposal("raise-rewards", PROPOSAL_ACTIVE, &[VOTES_PER_STAKE, 2 * VOTES_PER_STAKE])
+        .run();
+}
+
+// A proposal nobody votes on is rejected once its period ends
+#[test]
+fn test_unvoted_proposal_is_rejected() {
+    Scenario::new()
+        .user("alice")
+        .propose("idle", "alice", &["yes", "no"], VOTING_SECONDS)
+        .advance_seconds(VOTING_SECONDS as i64 + 1)
+        .finalize("idle")
+        .expect_proposal("idle", PROPOSAL_REJECTED, &[0, 0])
+        .expect_finalized("idle", FINALIZED_NO_QUORUM)
+        .run();
+}
+
+// The quorum is a quarter of the stake at creation: one staker of four votes exactly
+// that much and carries the proposal
+#[test]
+fn test_proposal_at_quorum_passes() {
+    Scenario::new()
+        .user("alice").with_tokens(TEST_STAKE_AMOUNT)
+        .user("bob").with_tokens(3 * TEST_STAKE_AMOUNT)
+        .agent("gpt-agent").owned_by("alice")
+        .stake("alice", "gpt-agent", TEST_STAKE_AMOUNT)
+        .stake("bob", "gpt-agent", 3 * TEST_STAKE_AMOUNT)
+        .advance_seconds(1)
+        .propose("raise-rewards", "alice", &["keep", "raise"], VOTING_SECONDS)
+        .vote("alice", "raise-rewards", 1)
+        .advance_seconds(VOTING_SECONDS as i64 + 1)
+        .finalize("raise-rewards")
+        .expect_proposal("raise-rewards", PROPOSAL_APPROVED, &[0, VOTES_PER_STAKE])
+        .expect_finalized("raise-rewards", FINALIZED_APPROVED)
+        .run();
+}
+
+// One staker of five falls short of the quarter; stake withdrawn after creation does not
+// lower the quorum, which was fixed when the proposal opened
+#[test]
+fn test_proposal_below_quorum_is_rejected() {
+    Scenario::new()
+        .user("alice").with_tokens(TEST_STAKE_AMOUNT)
+        .user("bob").with_tokens(4 * TEST_STAKE_AMOUNT)
+        .agent("gpt-agent").owned_by("alice")
+        .stake("alice", "gpt-agent", TEST_STAKE_AMOUNT)
+        .stake("bob", "gpt-agent", 4 * TEST_STAKE_AMOUNT)
+        .advance_seconds(1)
+        .propose("raise-rewards", "alice", &["keep", "raise"], VOTING_SECONDS)
+        .vote("alice", "raise-rewards", 1)
+        .unstake("bob", "gpt-agent", 2 * TEST_STAKE_AMOUNT)
+        .advance_seconds(VOTING_SECONDS as i64 + 1)
+        .finalize("raise-rewards")
+        .expect_proposal("raise-rewards", PROPOSAL_REJECTED, &[0, VOTES_PER_STAKE])
+        .expect_finalized("raise-rewards", FINALIZED_NO_QUORUM)
+        .run();
+}
+
+// Equal stakes voting for different options leave no winner
+#[test]
+fn test_tied_proposal_is_rejected() {
+    Scenario::new()
+        .user("alice").with_tokens(TEST_STAKE_AMOUNT)
+        .user("bob").with_tokens(TEST_STAKE_AMOUNT)
+        .agent("gpt-agent").owned_by("alice")
+        .stake("alice", "gpt-agent", TEST_STAKE_AMOUNT)
+        .stake("bob", "gpt-agent", TEST_STAKE_AMOUNT)
+        .advance_seconds(1)
+        .propose("raise-rewards", "alice", &["keep", "raise"], VOTING_SECONDS)
+        .vote("alice", "raise-rewards", 0)
+        .vote("bob", "raise-rewards", 1)
+        .advance_seconds(VOTING_SECONDS as i64 + 1)
+        .finalize("raise-rewards")
+        .expect_proposal("raise-rewards", PROPOSAL_REJECTED, &[VOTES_PER_STAKE, VOTES_PER_STAKE])
+        .expect_finalized("raise-rewards", FINALIZED_TIE)
+        .run();
+}
+
+// A plurality short of the approval threshold (three fifths of the votes) is rejected
+#[test]
+fn test_plurality_below_threshold_is_rejected() {
+    Scenario::new()
+        .user("alice").with_tokens(2 * TEST_STAKE_AMOUNT)
+        .user("bob").with_tokens(TEST_STAKE_AMOUNT)
+        .user("carol").with_tokens(TEST_STAKE_AMOUNT)
+        .agent("gpt-agent").owned_by("alice")
+        .stake("alice", "gpt-agent", 2 * TEST_STAKE_AMOUNT)
+        .stake("bob", "gpt-agent", TEST_STAKE_AMOUNT)
+        .stake("carol", "gpt-agent", TEST_STAKE_AMOUNT)
+        .advance_seconds(1)
+        .propose("fees", "alice", &["cut", "keep", "raise"], VOTING_SECONDS)
+        .vote("alice", "fees", 0)
+        .vote("bob", "fees", 1)
+        .vote("carol", "fees", 2)
+        .advance_seconds(VOTING_SECONDS as i64 + 1)
+        .finalize("fees")
+        .expect_proposal("fees", PROPOSAL_REJECTED, &[2 * VOTES_PER_STAKE, VOTES_PER_STAKE, VOTES_PER_STAKE])
+        .expect_finalized("fees", FINALIZED_BELOW_THRESHOLD)
+        .run();
+}
+
+// A passed proposal's action reaches the config once anyone executes it, and only once
+#[test]
+fn test_executed_proposal_updates_config() {
+    let world = Scenario::new()
+        .user("alice").with_tokens(TEST_STAKE_AMOUNT)
+        .user("bob").with_tokens(TEST_STAKE_AMOUNT)
+        .user("carol")
+        .agent("gpt-agent").owned_by("alice")
+        .stake("alice", "gpt-agent", TEST_STAKE_AMOUNT)
+        .stake("bob", "gpt-agent", TEST_STAKE_AMOUNT)
+        .advance_seconds(1)
+        .propose("raise-rewards", "alice", &["yes", "no"], VOTING_SECONDS)
+        .with_action(ProposalAction::UpdateRewardRate { bps: 600 })
+        .vote("alice", "raise-rewards", 0)
+        .vote("bob", "raise-rewards", 0)
+        .execute("carol", "raise-rewards").fails_with(OntoraError::ProposalNotApproved)
+        .advance_seconds(VOTING_SECONDS as i64 + 1)
+        .finalize("raise-rewards")
+        .expect_finalized("raise-rewards", FINALIZED_APPROVED)
+        .execute("carol", "raise-rewards")
+        .expect_proposal("raise-rewards", PROPOSAL_EXECUTED, &[2 * VOTES_PER_STAKE, 0])
+        .execute("carol", "raise-rewards").fails_with(OntoraError::ProposalAlreadyExecuted)
+        .run();
+
+    let config: PlatformConfig = fetch_account(&world.ctx, &world.platform.platform_config);
+    assert_eq!(config.reward_rate_bps, 600);
+}
+
+// A rejected proposal's action never runs
+#[test]
+fn test_rejected_proposal_cannot_execute() {
+    let world = Scenario::new()
+        .user("alice")
+        .propose("longer-epochs", "alice", &["yes", "no"], VOTING_SECONDS)
+        .with_action(ProposalAction::UpdateEpochDuration { seconds: 2 * 86_400 })
+        .advance_seconds(VOTING_SECONDS as i64 + 1)
+        .finalize("longer-epochs")
+        .expect_finalized("longer-epochs", FINALIZED_NO_QUORUM)
+        .execute("alice", "longer-epochs").fails_with(OntoraError::ProposalNotApproved)
+        .run();
+
+    let config: PlatformConfig = fetch_account(&world.ctx, &world.platform.platform_config);
+    assert_eq!(config.epoch_duration, 86_400);
+}
+
+// A proposal that "no" carries finalizes with a clear winner, but its action does not run:
+// only option 0 approves an action
+#[test]
+fn test_proposal_won_by_no_cannot_execute() {
+    let world = Scenario::new()
+        .user("alice").with_tokens(TEST_STAKE_AMOUNT)
+        .user("bob").with_tokens(TEST_STAKE_AMOUNT)
+        .agent("gpt-agent").owned_by("alice")
+        .stake("alice", "gpt-agent", TEST_STAKE_AMOUNT)
+        .stake("bob", "gpt-agent", TEST_STAKE_AMOUNT)
+        .advance_seconds(1)
+        .propose("raise-rewards", "alice", &["yes", "no"], VOTING_SECONDS)
+        .with_action(ProposalAction::UpdateRewardRate { bps: 600 })
+        .vote("alice", "raise-rewards", 1)
+        .vote("bob", "raise-rewards", 1)
+        .advance_seconds(VOTING_SECONDS as i64 + 1)
+        .finalize("raise-rewards")
+        .expect_proposal("raise-rewards", PROPOSAL_APPROVED, &[0, 2 * VOTES_PER_STAKE])
+        .execute("bob", "raise-rewards").fails_with(OntoraError::ProposalNotApproved)
+        .expect_proposal("raise-rewards", PROPOSAL_APPROVED, &[0, 2 * VOTES_PER_STAKE])
+        .run();
+
+    assert_eq!(world.proposal("raise-rewards").winning_option, 1);
+    let config: PlatformConfig = fetch_account(&world.ctx, &world.platform.platform_config);
+    assert_eq!(config.reward_rate_bps, 100);
+}
+
+// An action the config could never take is refused when the proposal is created
+#[test]
+fn test_invalid_proposal_action_is_refused() {
+    Scenario::new()
+        .user("alice")
+        .propose("free-stake", "alice", &["yes", "no"], VOTING_SECONDS)
+        .with_action(ProposalAction::UpdateMinStake { amount: 0 })
+        .fails_with(OntoraError::InvalidProposalParameters)
+        .run();
+}
+
+// Two stakers over two epochs: each distribution pays one epoch's reward, claims move it
+// into the owner's wallet, and a second claim finds nothing left
+#[test]
+fn test_rewards_accrue_and_claim_over_two_epochs() {
+    let alice_stake = TEST_STAKE_AMOUNT;
+    let bob_stake = 2 * TEST_STAKE_AMOUNT;
+    let total = alice_stake + bob_stake;
+    let (alice_reward, bob_reward) = (epoch_reward(alice_stake, total), epoch_reward(bob_stake, total));
+    Scenario::new()
+        .user("alice").with_tokens(alice_stake)
+        .user("bob").with_tokens(bob_stake)
+        .stake_legacy("alice", alice_stake)
+        .stake_legacy("bob", bob_stake)
+        .advance_epochs(1)
+        .distribute("alice")
+        .advance_epochs(1)
+        .distribute("bob")
+        .advance_epochs(1)
+        .distribute("alice")
+        .expect_rewards("alice", 2 * alice_reward)
+        .expect_rewards("bob", bob_reward)
+        .claim("alice")
+        .claim("bob")
+        .expect_tokens("alice", 2 * alice_reward)
+        .expect_tokens("bob", bob_reward)
+        .expect_rewards("alice", 0)
+        .claim("alice").fails_with(OntoraError::RewardPoolDepleted)
+        .run();
+}
+
+// Shares of an epoch's emission are taken of the stake at its first payout: an agent that
+// unstakes once paid leaves the later shares as they were, and the epoch pays out no more
+// than its emission
+#[test]
+fn test_unstake_between_payouts_keeps_epoch_shares() {
+    let total = 4 * TEST_STAKE_AMOUNT;
+    Scenario::new()
+        .user("alice").with_tokens(TEST_STAKE_AMOUNT)
+        .user("bob").with_tokens(TEST_STAKE_AMOUNT)
+        .user("carol").with_tokens(2 * TEST_STAKE_AMOUNT)
+        .stake_legacy("alice", TEST_STAKE_AMOUNT)
+        .stake_legacy("bob", TEST_STAKE_AMOUNT)
+        .stake_legacy("carol", 2 * TEST_STAKE_AMOUNT)
+        .advance_epochs(1)
+        .distribute("alice")
+        .distribute("carol")
+        .unstake_legacy("carol")
+        .distribute("bob")
+        .expect_rewards("alice", epoch_reward(TEST_STAKE_AMOUNT, total))
+        .expect_rewards("bob", epoch_reward(TEST_STAKE_AMOUNT, total))
+        .expect_rewards("carol", epoch_reward(2 * TEST_STAKE_AMOUNT, total))
+        .run();
+}
+
+// Stakers exit an agent independently: partial and full unstakes return exactly what
+// was withdrawn, and nobody can withdraw more than they staked
+#[test]
+fn test_multi_staker_partial_exits() {
+    Scenario::new()
+        .with_reward_pool(0)
+        .user("alice").with_tokens(2 * TEST_STAKE_AMOUNT)
+        .user("bob").with_tokens(TEST_STAKE_AMOUNT)
+        .agent("gpt-agent").owned_by("alice")
+        .stake("alice", "gpt-agent", 2 * TEST_STAKE_AMOUNT)
+        .stake("bob", "gpt-agent", TEST_STAKE_AMOUNT)
+        .unstake("alice", "gpt-agent", TEST_STAKE_AMOUNT)
+        .expect_staked("gpt-agent", 2 * TEST_STAKE_AMOUNT)
+        .expect_tokens("alice", TEST_STAKE_AMOUNT)
+        .unstake("bob", "gpt-agent", 2 * TEST_STAKE_AMOUNT).fails_with(OntoraError::InvalidStakeAmount)
+        .unstake("bob", "gpt-agent", TEST_STAKE_AMOUNT)
+        .expect_staked("gpt-agent", TEST_STAKE_AMOUNT)
+        .expect_tokens("bob", TEST_STAKE_AMOUNT)
+        .run();
+}
@@ -1,9 +1,13 @@
 // test_setup.rs
 // This module provides setup utilities and helper functions for testing the Ontora AI Solana program.
-// It includes functions to initialize test environments, create mock accounts, and simulate user interactions.
+// It starts the program under solana-program-test, funds users and SPL token accounts, initializes
+// the platform accounts, derives PDAs, and moves the validator's slot and clock forward.
 
 use anchor_lang::prelude::*;
-use anchor_lang::solana_program::clock::Clock;
+use anchor_lang::solana_program::clock::{Clock, DEFAULT_MS_PER_SLOT};
+use anchor_lang::solana_program::hash::Hash;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program_pack::Pack;
 use anchor_lang::solana_program::pubkey::Pubkey;
 use anchor_lang::solana_program::system_instruction;
 use anchor_lang::solana_program::system_program;
@@ -15,6 +19,9 @@ use solana_program_test::*;
 use solana_sdk::commitment_config::CommitmentLevel;
 use std::rc::Rc;
 
+use ontora_ai::pda;
+use ontora_ai::state::{ConfigParams, FeeType, PlatformConfig, Role};
+
 // Assuming the program ID for Ontora AI (replace with actual program ID if needed)
 declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
 
@@ -22,23 +29,47 @@ declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
 pub const INITIAL_LAMPORTS: u64 = 10_000_000_000; // 10 SOL for test accounts
 pub const TEST_STAKE_AMOUNT: u64 = 1_000_000_000; // 1 SOL for staking in tests
 pub const TEST_AI_AGENT_ID: u64 = 1; // Mock AI agent ID for testing
+pub const TEST_REWARD_POOL_AMOUNT: u64 = 5_000_000_000; // Tokens minted into the reward vault
+pub const TEST_EPOCH_EMISSION: u64 = TEST_STAKE_AMOUNT / 10; // Rewards shared among the legacy agents per epoch
+pub const TEST_VOTE_UNIT: u64 = TEST_STAKE_AMOUNT / 10; // Stake per vote after initialize_program
+pub const TEST_QUORUM_BPS: u16 = 2_500; // A quarter of the stake must vote after initialize_program
+pub const TEST_APPROVAL_THRESHOLD_BPS: u16 = 6_000; // The winning option's share of the votes
+pub const TEST_MINT_DECIMALS: u8 = 9;
 
 // TestUser struct to represent a user in the test environment
-#[derive(Clone)]
 pub struct TestUser {
     pub keypair: Keypair,
     pub pubkey: Pubkey,
 }
 
-// TestContext struct to hold the test environment state
+// TestContext struct to hold the test environment state. `program_context` is the
+// validator itself, used to warp slots and override sysvars; `banks_client` talks to it.
+// `genesis_clock` is the clock at startup, the origin `warp_seconds` maps time onto slots from.
 pub struct TestContext {
     pub banks_client: BanksClient,
     pub payer: Keypair,
-    pub last_blockhash: [u8; 32],
+    pub last_blockhash: Hash,
+    pub program_context: ProgramTestContext,
+    pub genesis_clock: Clock,
+}
+
+// Accounts created by `initialize_program`. The admin holds every role and is the
+// mint authority of `mint`, which is both the stake and the reward token.
+pub struct TestPlatform {
+    pub admin: TestUser,
+    pub platform_config: Pubkey,
+    pub fee_schedule: Pubkey,
+    pub treasury: Pubkey,
+    pub reward_pool: Pubkey,
+    pub mint: Pubkey,
+    // Token account owned by the platform config, holding stake from `stake_on_agent`
+    pub platform_vault: Pubkey,
+    // Token account owned by the reward pool, paying out `claim_rewards`
+    pub reward_vault: Pubkey,
 }
 
 // Utility function to create a new test user with initial lamports
-pub async fn create_test_user(banks_client: &mut BanksClient, payer: &Keypair, last_blockhash: [u8; 32]) -> TestUser {
+pub async fn create_test_user(banks_client: &mut BanksClient, payer: &Keypair, last_blockhash: Hash) -> TestUser {
     let user_keypair = Keypair::new();
     let user_pubkey = user_keypair.pubkey();
 
@@ -50,7 +81,7 @@ pub async fn create_test_user(banks_client: &mut BanksClient, payer: &Keypair, l
             INITIAL_LAMPORTS,
         )],
         Some(&payer.pubkey()),
-        &[payer, &user_keypair],
+        &[payer],
         last_blockhash,
     );
 
@@ -68,13 +99,42 @@ pub async fn create_test_user(banks_client: &mut BanksClient, payer: &Keypair, l
 
 // Utility function to initialize the test context with a payer account
 pub async fn setup_test_context() -> (TestContext, Program) {
-    // Start the Solana test validator
-    let mut test = ProgramTest::new(
-        "ontora_ai", // Program name (adjust if different)
-        id(),        // Program ID
-        processor!(ontora_ai::entry), // Entry point (adjust based on your program)
+    start_test_context(program_test(), id()).await
+}
+
+// The validator `setup_test_context` starts, for callers preloading accounts into it first.
+// The SPL token and associated token programs are built in.
+pub fn program_test() -> ProgramTest {
+    ProgramTest::new(
+        "ontora_ai",
+        id(),
+        processor!(ontora_ai::entry),
+    )
+}
+
+// Same as `setup_test_context`, but runs the compiled program from target/deploy at its
+// declared ID, so compute units are metered as on chain. Run `cargo build-sbf` first.
+pub async fn setup_sbf_test_context() -> (TestContext, Program) {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/target/deploy/ontora_ai.so");
+    let elf = std::fs::read(path)
+        .unwrap_or_else(|error| panic!("failed to load {}: {} (run `cargo build-sbf` first)", path, error));
+
+    let mut test = ProgramTest::default();
+    test.add_account(
+        ontora_ai::ID,
+        Account {
+            lamports: Rent::default().minimum_balance(elf.len()),
+            data: elf,
+            owner: solana_sdk::bpf_loader::id(),
+            executable: true,
+            rent_epoch: 0,
+        },
     );
+    start_test_context(test, ontora_ai::ID).await
+}
 
+// Start `test` with a funded payer, with `program_id` as the program's ID
+pub async fn start_test_context(mut test: ProgramTest, program_id: Pubkey) -> (TestContext, Program) {
     // Add initial payer account with lamports
     let payer = Keypair::new();
     test.add_account(
@@ -88,12 +148,15 @@ pub async fn setup_test_context() -> (TestContext, Program) {
         },
     );
 
-    // Start the test environment
-    let (banks_client, _payer, last_blockhash) = test.start().await;
+    // Start the test environment, keeping the context so tests can warp it
+    let program_context = test.start_with_context().await;
+    let mut banks_client = program_context.banks_client.clone();
+    let last_blockhash = program_context.last_blockhash;
+    let genesis_clock = get_clock(&mut banks_client).await;
 
     // Create a program instance for interacting with the Solana program
     let program = Program::new(
-        id(),
+        program_id,
         Rc::new(banks_client.clone()),
         CommitmentLevel::Confirmed,
     );
@@ -103,52 +166,91 @@ pub async fn setup_test_context() -> (TestContext, Program) {
             banks_client,
             payer,
             last_blockhash,
+            program_context,
+            genesis_clock,
         },
         program,
     )
 }
 
+// Sign `instructions` with `signers`, pay with the context payer, and process them
+pub async fn send_transaction(
+    ctx: &mut TestContext,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+) -> std::result::Result<(), BanksClientError> {
+    let blockhash = ctx.banks_client.get_latest_blockhash().await.unwrap();
+    let mut all_signers = vec![&ctx.payer];
+    all_signers.extend_from_slice(signers);
+    let tx = Transaction::new_signed_with_payer(instructions, Some(&ctx.payer.pubkey()), &all_signers, blockhash);
+    ctx.banks_client.process_transaction(tx).await
+}
+
+// Utility function to get the current clock in the test environment
+pub async fn get_clock(banks_client: &mut BanksClient) -> Clock {
+    banks_client.get_sysvar::<Clock>().await.unwrap()
+}
+
 // Utility function to get the current slot (block height) in the test environment
 pub async fn get_current_slot(banks_client: &mut BanksClient) -> u64 {
-    banks_client
-        .get_sysvar::<Clock>()
-        .await
-        .unwrap()
-        .slot
+    get_clock(banks_client).await.slot
 }
 
 // Utility function to advance the slot by a specified number for testing time-dependent logic
-pub async fn advance_slot(banks_client: &mut BanksClient, slots: u64) {
-    let current_slot = get_current_slot(banks_client).await;
-    banks_client
-        .warp_to_slot(current_slot + slots)
-        .await
-        .unwrap();
+pub async fn advance_slot(ctx: &mut TestContext, slots: u64) {
+    let current_slot = get_current_slot(&mut ctx.banks_client).await;
+    ctx.program_context.warp_to_slot(current_slot + slots).unwrap();
+    ctx.last_blockhash = ctx.banks_client.get_latest_blockhash().await.unwrap();
 }
 
-// Utility function to create a mock AI agent account (adjust based on your program's state structure)
-pub async fn create_mock_ai_agent(
-    banks_client: &mut BanksClient,
-    program: &Program,
-    owner: &TestUser,
-    agent_id: u64,
-) -> Pubkey {
-    // Derive PDA for the AI agent account (adjust based on your program's PDA logic)
-    let (agent_pda, _bump) = Pubkey::find_program_address(
-        &[b"ai_agent", owner.pubkey.as_ref(), &agent_id.to_le_bytes()],
-        &program.id(),
-    );
+// Overwrite the Clock sysvar's unix_timestamp, which `Clock::get()` in the program reads.
+// Warping slots alone does not move the timestamp far enough for epoch-gated logic.
+pub async fn set_unix_timestamp(ctx: &mut TestContext, unix_timestamp: i64) {
+    let mut clock = get_clock(&mut ctx.banks_client).await;
+    clock.unix_timestamp = unix_timestamp;
+    ctx.program_context.set_sysvar(&clock);
+}
+
+// Slot the validator reaches `unix_timestamp` at, counting DEFAULT_MS_PER_SLOT from `genesis`
+pub fn slot_at(genesis: &Clock, unix_timestamp: i64) -> u64 {
+    let elapsed_ms = unix_timestamp.saturating_sub(genesis.unix_timestamp).max(0) as u64 * 1_000;
+    genesis.slot + elapsed_ms / DEFAULT_MS_PER_SLOT
+}
+
+// Move the validator `seconds` forward: warp to the slot that time falls in, then pin the
+// Clock sysvar's unix_timestamp to exactly `seconds` after the current one, so `Clock::get()`
+// in the program sees the jump to the second. Returns the new unix_timestamp.
+pub async fn warp_seconds(ctx: &mut TestContext, seconds: i64) -> i64 {
+    let clock = get_clock(&mut ctx.banks_client).await;
+    let unix_timestamp = clock.unix_timestamp + seconds;
+    let slot = slot_at(&ctx.genesis_clock, unix_timestamp).max(clock.slot + 1);
+    ctx.program_context.warp_to_slot(slot).unwrap();
+    set_unix_timestamp(ctx, unix_timestamp).await;
+    ctx.last_blockhash = ctx.banks_client.get_latest_blockhash().await.unwrap();
+    unix_timestamp
+}
+
+// Move the validator forward one full platform epoch (`config.epoch_duration` seconds)
+pub async fn warp_to_next_epoch(ctx: &mut TestContext, config: &PlatformConfig) -> i64 {
+    warp_seconds(ctx, config.epoch_duration).await
+}
 
-    // Mock instruction to initialize the AI agent (replace with actual instruction call)
-    // This is a placeholder; implement based on your program's instruction for creating an AI agent
-    let _result = program
+// Utility function to register an AI agent owned by `owner` and return its PDA
+pub async fn create_mock_ai_agent(program: &Program, owner: &TestUser, agent_id: u64) -> Pubkey {
+    let agent_pda = ai_agent_pda(&program.id(), &owner.pubkey, agent_id);
+
+    program
         .request()
-        .accounts(ontora_ai::accounts::InitializeAiAgent {
-            agent: agent_pda,
+        .accounts(ontora_ai::accounts::RegisterAiAgent {
+            ai_agent: agent_pda,
             owner: owner.pubkey,
             system_program: system_program::ID,
         })
-        .args(ontora_ai::instruction::InitializeAiAgent { agent_id })
+        .args(ontora_ai::instruction::RegisterAgent {
+            agent_id,
+            name: format!("Agent {}", agent_id),
+            description: "Test agent".to_string(),
+        })
         .signer(&owner.keypair)
         .send()
         .await
@@ -163,7 +265,7 @@ pub async fn fund_account(
     payer: &Keypair,
     account: &Pubkey,
     amount: u64,
-    last_blockhash: [u8; 32],
+    last_blockhash: Hash,
 ) {
     let tx = Transaction::new_signed_with_payer(
         &[system_instruction::transfer(
@@ -192,47 +294,326 @@ pub async fn get_account_balance(banks_client: &mut BanksClient, account: &Pubke
         .lamports
 }
 
-// Mock data for testing governance proposals (adjust based on your program's structure)
-pub struct MockProposal {
-    pub id: u64,
-    pub title: String,
-    pub description: String,
-    pub creator: Pubkey,
+// Create an SPL mint with `authority` as its mint authority
+pub async fn create_mint(ctx: &mut TestContext, authority: &Pubkey) -> Pubkey {
+    let mint = Keypair::new();
+    let rent = ctx.banks_client.get_rent().await.unwrap();
+    let instructions = [
+        system_instruction::create_account(
+            &ctx.payer.pubkey(),
+            &mint.pubkey(),
+            rent.minimum_balance(spl_token::state::Mint::LEN),
+            spl_token::state::Mint::LEN as u64,
+            &spl_token::ID,
+        ),
+        spl_token::instruction::initialize_mint(&spl_token::ID, &mint.pubkey(), authority, None, TEST_MINT_DECIMALS)
+            .unwrap(),
+    ];
+    send_transaction(ctx, &instructions, &[&mint]).await.unwrap();
+    mint.pubkey()
 }
 
-// Utility function to create a mock governance proposal (placeholder for actual instruction)
-pub async fn create_mock_proposal(
-    banks_client: &mut BanksClient,
-    program: &Program,
-    creator: &TestUser,
-    proposal_id: u64,
-) -> Pubkey {
-    // Derive PDA for the proposal account (adjust based on your program's PDA logic)
-    let (proposal_pda, _bump) = Pubkey::find_program_address(
-        &[b"proposal", &proposal_id.to_le_bytes()],
-        &program.id(),
+// Create the associated token account of `owner` for `mint`; PDAs may own one too
+pub async fn create_token_account(ctx: &mut TestContext, owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+    let instruction = spl_associated_token_account::instruction::create_associated_token_account(
+        &ctx.payer.pubkey(),
+        owner,
+        mint,
+        &spl_token::ID,
     );
+    send_transaction(ctx, &[instruction], &[]).await.unwrap();
+    spl_associated_token_account::get_associated_token_address(owner, mint)
+}
+
+// Mint `amount` tokens into `account`
+pub async fn mint_tokens(ctx: &mut TestContext, mint: &Pubkey, authority: &TestUser, account: &Pubkey, amount: u64) {
+    let instruction =
+        spl_token::instruction::mint_to(&spl_token::ID, mint, account, &authority.pubkey, &[], amount).unwrap();
+    send_transaction(ctx, &[instruction], &[&authority.keypair]).await.unwrap();
+}
+
+// Create `owner`'s token account for the platform mint holding `amount` tokens
+pub async fn create_funded_token_account(
+    ctx: &mut TestContext,
+    platform: &TestPlatform,
+    owner: &Pubkey,
+    amount: u64,
+) -> Pubkey {
+    let account = create_token_account(ctx, owner, &platform.mint).await;
+    if amount > 0 {
+        mint_tokens(ctx, &platform.mint, &platform.admin, &account, amount).await;
+    }
+    account
+}
+
+// Utility function to get a token account's balance
+pub async fn get_token_balance(banks_client: &mut BanksClient, account: &Pubkey) -> u64 {
+    let account = banks_client.get_account(*account).await.unwrap().unwrap();
+    spl_token::state::Account::unpack(&account.data).unwrap().amount
+}
 
-    // Mock instruction to create a proposal (replace with actual instruction call)
-    // This is a placeholder; implement based on your program's instruction for creating a proposal
-    let _result = program
+// Initialize every platform account with a fresh admin: the config with TEST_VOTE_UNIT, roles,
+// treasury, a zero-fee schedule, and a reward pool whose vault holds `initial_rewards` tokens
+pub async fn initialize_program(ctx: &mut TestContext, program: &Program, initial_rewards: u64) -> TestPlatform {
+    let admin = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
+    let platform_config = initialize_test_platform(program, &admin).await;
+
+    for role in [Role::Crank, Role::Oracle, Role::Treasury] {
+        program
+            .request()
+            .accounts(ontora_ai::accounts::SetRole { platform_config, admin: admin.pubkey })
+            .args(ontora_ai::instruction::SetRole { role, holder: admin.pubkey })
+            .signer(&admin.keypair)
+            .send()
+            .await
+            .unwrap();
+    }
+    program
         .request()
-        .accounts(ontora_ai::accounts::CreateProposal {
-            proposal: proposal_pda,
-            creator: creator.pubkey,
+        .accounts(ontora_ai::accounts::UpdatePlatformConfig { platform_config, admin: admin.pubkey })
+        .args(test_config_update())
+        .signer(&admin.keypair)
+        .send()
+        .await
+        .unwrap();
+
+    let treasury = treasury_pda(&program.id());
+    program
+        .request()
+        .accounts(ontora_ai::accounts::InitializeTreasury {
+            platform_config,
+            treasury,
+            authority: admin.pubkey,
             system_program: system_program::ID,
         })
-        .args(ontora_ai::instruction::CreateProposal {
-            id: proposal_id,
-            title: "Test Proposal".to_string(),
-            description: "A test proposal for Ontora AI".to_string(),
+        .args(ontora_ai::instruction::InitializeTreasury { governance: admin.pubkey })
+        .signer(&admin.keypair)
+        .send()
+        .await
+        .unwrap();
+
+    let fee_schedule = fee_schedule_pda(&program.id());
+    program
+        .request()
+        .accounts(ontora_ai::accounts::InitializeFeeSchedule {
+            platform_config,
+            fee_schedule,
+            admin: admin.pubkey,
+            system_program: system_program::ID,
         })
-        .signer(&creator.keypair)
+        .args(ontora_ai::instruction::InitializeFeeSchedule {
+            governance: admin.pubkey,
+            rates_bps: [0; FeeType::COUNT],
+        })
+        .signer(&admin.keypair)
         .send()
         .await
         .unwrap();
 
-    proposal_pda
+    let reward_pool = reward_pool_pda(&program.id());
+    program
+        .request()
+        .accounts(ontora_ai::accounts::InitializeRewardPool {
+            reward_pool,
+            user: admin.pubkey,
+            system_program: system_program::ID,
+        })
+        .args(ontora_ai::instruction::InitializeRewardPool { initial_rewards })
+        .signer(&admin.keypair)
+        .send()
+        .await
+        .unwrap();
+
+    let mint = create_mint(ctx, &admin.pubkey).await;
+    let (platform_vault, reward_vault) = (staking_vault_pda(&program.id()), reward_vault_pda(&program.id()));
+    program
+        .request()
+        .accounts(ontora_ai::accounts::InitializeVaults {
+            platform_config,
+            reward_pool,
+            stake_mint: mint,
+            staking_vault: platform_vault,
+            reward_vault,
+            admin: admin.pubkey,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        })
+        .args(ontora_ai::instruction::InitializeVaults {})
+        .signer(&admin.keypair)
+        .send()
+        .await
+        .unwrap();
+    let platform = TestPlatform {
+        admin,
+        platform_config,
+        fee_schedule,
+        treasury,
+        reward_pool,
+        mint,
+        platform_vault,
+        reward_vault,
+    };
+    if initial_rewards > 0 {
+        mint_tokens(ctx, &platform.mint, &platform.admin, &platform.reward_vault, initial_rewards).await;
+    }
+    platform
+}
+
+// Derive the platform config PDA
+pub fn platform_config_pda(program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[pda::PLATFORM_CONFIG_SEED], program_id).0
+}
+
+// Derive the fee schedule PDA
+pub fn fee_schedule_pda(program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[pda::FEE_SCHEDULE_SEED], program_id).0
+}
+
+// Derive the treasury PDA
+pub fn treasury_pda(program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[pda::TREASURY_SEED], program_id).0
 }
 
-// Add more utility functions as needed for staking, rewards, or other program-specific logic
+// Derive the treasury's token vault PDA for `mint`
+pub fn treasury_vault_pda(program_id: &Pubkey, mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[pda::TREASURY_VAULT_SEED, mint.as_ref()], program_id).0
+}
+
+// Derive the staking vault PDA
+pub fn staking_vault_pda(program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[pda::STAKING_VAULT_SEED], program_id).0
+}
+
+// Derive the reward vault PDA
+pub fn reward_vault_pda(program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[pda::REWARD_VAULT_SEED], program_id).0
+}
+
+// Derive the reward pool PDA
+pub fn reward_pool_pda(program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[pda::REWARD_POOL_SEED], program_id).0
+}
+
+// Derive the PDA of an agent registered with `register_agent`
+pub fn ai_agent_pda(program_id: &Pubkey, owner: &Pubkey, agent_id: u64) -> Pubkey {
+    Pubkey::find_program_address(&[pda::AI_AGENT_SEED, owner.as_ref(), &agent_id.to_le_bytes()], program_id).0
+}
+
+// Derive the PDA of an agent registered with the single-agent `register_ai_agent`
+pub fn legacy_ai_agent_pda(program_id: &Pubkey, owner: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[pda::LEGACY_AI_AGENT_SEED, owner.as_ref()], program_id).0
+}
+
+// Derive the PDA of the withdrawal a legacy agent's owner requested with `request_unstake`
+pub fn pending_withdrawal_pda(program_id: &Pubkey, owner: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[pda::PENDING_WITHDRAWAL_SEED, owner.as_ref()], program_id).0
+}
+
+// Derive the PDA holding a legacy agent owner's claimed rewards while they vest
+pub fn reward_vesting_pda(program_id: &Pubkey, owner: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[pda::REWARD_VESTING_SEED, owner.as_ref()], program_id).0
+}
+
+// Derive a user's stake PDA
+pub fn user_stake_pda(program_id: &Pubkey, user: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[pda::USER_STAKE_SEED, user.as_ref()], program_id).0
+}
+
+// Derive the stake index entry PDA of a (user, agent) pair
+pub fn stake_index_pda(program_id: &Pubkey, user: &Pubkey, agent_id: u64) -> Pubkey {
+    Pubkey::find_program_address(&[pda::STAKE_INDEX_SEED, user.as_ref(), &agent_id.to_le_bytes()], program_id).0
+}
+
+// Derive a governance proposal's PDA from its id
+pub fn proposal_pda(program_id: &Pubkey, proposal_id: u64) -> Pubkey {
+    Pubkey::find_program_address(&[pda::PROPOSAL_SEED, proposal_id.to_le_bytes().as_ref()], program_id).0
+}
+
+// Derive the PDA recording `voter`'s vote on a proposal
+pub fn vote_record_pda(program_id: &Pubkey, proposal_id: u64, voter: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[pda::PROPOSAL_VOTE_SEED, &proposal_id.to_le_bytes(), voter.as_ref()], program_id).0
+}
+
+// Initialize the platform with `admin` as its admin and return the config PDA
+pub async fn initialize_test_platform(program: &Program, admin: &TestUser) -> Pubkey {
+    let platform_config = platform_config_pda(&program.id());
+
+    program
+        .request()
+        .accounts(ontora_ai::accounts::InitializePlatform {
+            platform_config,
+            admin: admin.pubkey,
+            system_program: system_program::ID,
+        })
+        .args(ontora_ai::instruction::InitializePlatform {
+            reward_rate_bps: 100,
+            min_stake_amount: TEST_STAKE_AMOUNT,
+            epoch_duration: 86_400,
+            cooldown_seconds: 86_400,
+            epoch_emission: TEST_EPOCH_EMISSION,
+        })
+        .signer(&admin.keypair)
+        .send()
+        .await
+        .unwrap();
+
+    platform_config
+}
+
+// The update the fixtures apply after initializing the platform: the initial parameters,
+// with a vote unit test-sized stakes reach and the test quorum and approval threshold
+pub fn test_config_update() -> ontora_ai::instruction::UpdatePlatformConfig {
+    ontora_ai::instruction::UpdatePlatformConfig {
+        params: ConfigParams {
+            reward_rate_bps: 100,
+            min_stake_amount: TEST_STAKE_AMOUNT,
+            epoch_duration: 86_400,
+            cooldown_seconds: 86_400,
+            epoch_emission: TEST_EPOCH_EMISSION,
+            max_stake_per_agent: 0,
+            vesting_seconds: 0,
+            vote_unit: TEST_VOTE_UNIT,
+            quorum_bps: TEST_QUORUM_BPS,
+            approval_threshold_bps: TEST_APPROVAL_THRESHOLD_BPS,
+        },
+        update_metadata: String::new(),
+    }
+}
+
+// Decode every `T` event from a transaction's "Program data:" log lines
+pub fn parse_events<T: anchor_lang::Event + AnchorDeserialize>(logs: &[String]) -> Vec<T> {
+    use base64::Engine;
+
+    logs.iter()
+        .filter_map(|line| line.strip_prefix("Program data: "))
+        .filter_map(|data| base64::engine::general_purpose::STANDARD.decode(data).ok())
+        .filter(|bytes| bytes.len() >= 8 && bytes[..8] == T::discriminator())
+        .filter_map(|bytes| T::deserialize(&mut &bytes[8..]).ok())
+        .collect()
+}
+
+// Account bytes (discriminator + raw struct) of a zero-copy account
+pub fn zero_copy_bytes<T: anchor_lang::ZeroCopy>(value: &T) -> Vec<u8> {
+    let mut data = T::discriminator().to_vec();
+    data.extend_from_slice(bytemuck::bytes_of(value));
+    data
+}
+
+// Decode a zero-copy account from its bytes
+pub fn read_zero_copy<T: anchor_lang::ZeroCopy>(data: &[u8]) -> T {
+    bytemuck::pod_read_unaligned(&data[8..8 + std::mem::size_of::<T>()])
+}
+
+// Extract the custom program error code a failed transaction returned, if any
+pub fn custom_error_code(error: &anchor_client::ClientError) -> Option<u32> {
+    use solana_sdk::instruction::InstructionError;
+    use solana_sdk::transaction::TransactionError;
+
+    match error {
+        anchor_client::ClientError::SolanaClientError(error) => match error.get_transaction_error() {
+            Some(TransactionError::InstructionError(_, InstructionError::Custom(code))) => Some(code),
+            _ => None,
+        },
+        _ => None,
+    }
+}
@@ -0,0 +1,65 @@
+// test_snapshot.rs
+// This module tests the snapshots in snapshot/mod.rs: a restored validator holds the
+// captured accounts, signers and clock, and a snapshot taken of another build is not loaded.
+
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::AccountDeserialize;
+use solana_sdk::signature::Signer;
+
+mod snapshot;
+mod test_setup;
+use snapshot::Snapshot;
+use test_setup::*;
+
+use ontora_ai::state::{PlatformConfig, Role};
+
+// Test that restoring a snapshot brings back the accounts, the signers and the clock,
+// and that the restored validator keeps running from there
+#[tokio::test]
+async fn test_restore_round_trip() {
+    let (mut ctx, program) = setup_test_context().await;
+    let admin = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
+    let platform_config = initialize_test_platform(&program, &admin).await;
+    let unix_timestamp = warp_seconds(&mut ctx, 3 * 86_400).await;
+    let missing = Pubkey::new_unique();
+    Snapshot::capture(&mut ctx, &[("admin", &admin)], &[platform_config, missing]).await.save("round_trip");
+    let captured = ctx.banks_client.get_account(platform_config).await.unwrap().unwrap();
+
+    let snapshot = Snapshot::load("round_trip").unwrap();
+    assert_eq!(snapshot.accounts.len(), 2);
+    let (mut restored, program) = snapshot.restore().await;
+    assert_eq!(restored.banks_client.get_account(platform_config).await.unwrap().unwrap(), captured);
+    let clock = get_clock(&mut restored.banks_client).await;
+    assert_eq!((clock.slot, clock.unix_timestamp), (snapshot.clock.slot, unix_timestamp));
+    assert_eq!(restored.genesis_clock, ctx.genesis_clock);
+
+    // The saved admin still signs for the platform
+    let restored_admin = snapshot.signer("admin");
+    assert_eq!(restored_admin.pubkey, admin.keypair.pubkey());
+    program
+        .request()
+        .accounts(ontora_ai::accounts::SetRole { platform_config, admin: restored_admin.pubkey })
+        .args(ontora_ai::instruction::SetRole { role: Role::Crank, holder: restored_admin.pubkey })
+        .signer(&restored_admin.keypair)
+        .send()
+        .await
+        .unwrap();
+    let account = restored.banks_client.get_account(platform_config).await.unwrap().unwrap();
+    let config = PlatformConfig::try_deserialize(&mut account.data.as_ref()).unwrap();
+    assert_eq!(config.roles.crank, admin.pubkey);
+    assert_eq!(warp_seconds(&mut restored, 60).await, unix_timestamp + 60);
+}
+
+// Test that a snapshot whose fingerprint does not match this build is ignored
+#[tokio::test]
+async fn test_stale_snapshot_is_ignored() {
+    let (mut ctx, _) = setup_test_context().await;
+    let mut snapshot = Snapshot::capture(&mut ctx, &[], &[]).await;
+    snapshot.save("fresh");
+    assert!(Snapshot::load("fresh").is_some());
+
+    snapshot.fingerprint[0] ^= 1;
+    snapshot.save("stale");
+    assert!(Snapshot::load("stale").is_none());
+    assert!(Snapshot::load("never_saved").is_none());
+}
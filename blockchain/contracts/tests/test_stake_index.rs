@@ -0,0 +1,308 @@
+// test_stake_index.rs
+// This module contains test cases for StakeIndexEntry PDAs, which mark each
+// (user, agent) pair with an open stake: created on the first stake, found by
+// the user key the SDK's getStakedAgents filters on, and closed on full exit.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program_option::COption;
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::solana_program::system_program;
+use anchor_client::Program;
+use solana_program_test::*;
+use solana_sdk::account::Account as SolanaAccount;
+use solana_sdk::commitment_config::CommitmentLevel;
+use solana_sdk::signature::{Keypair, Signer};
+use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Import test setup utilities (assumes test_setup.rs is in the same directory)
+mod test_setup;
+use test_setup::*;
+
+use ontora_ai::error::OntoraError;
+use ontora_ai::pda::{
+    AI_AGENT_SEED, FEE_SCHEDULE_SEED, PLATFORM_CONFIG_SEED, TREASURY_SEED, TREASURY_VAULT_SEED, USER_STAKE_SEED,
+};
+use ontora_ai::state::{AiAgent, FeeSchedule, PlatformConfig, StakeIndexEntry, Treasury, UserStake};
+
+const AGENT_IDS: [u64; 2] = [1, 2];
+const UNSTAKE_FEE_BPS: u16 = 100;
+
+// Accounts of a validator preloaded with a configured platform and two agents
+struct StakingFixture {
+    banks_client: BanksClient,
+    program: Program,
+    user: Keypair,
+    mint: Pubkey,
+    user_token_account: Pubkey,
+    platform_vault: Pubkey,
+    agents: Vec<Pubkey>,
+}
+
+fn pda(seeds: &[&[u8]]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(seeds, &id())
+}
+
+fn program_account<T: AccountSerialize>(value: &T) -> SolanaAccount {
+    let mut data = Vec::new();
+    value.try_serialize(&mut data).unwrap();
+    SolanaAccount {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner: id(),
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+fn token_program_account<T: Pack>(value: T) -> SolanaAccount {
+    let mut data = vec![0; T::LEN];
+    T::pack(value, &mut data).unwrap();
+    SolanaAccount {
+        lamports: Rent::default().minimum_balance(T::LEN),
+        data,
+        owner: spl_token::ID,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+fn token_account(mint: Pubkey, owner: Pubkey, amount: u64) -> SolanaAccount {
+    token_program_account(spl_token::state::Account {
+        mint,
+        owner,
+        amount,
+        state: spl_token::state::AccountState::Initialized,
+        ..Default::default()
+    })
+}
+
+async fn setup_staking() -> StakingFixture {
+    let mut test = ProgramTest::new("ontora_ai", id(), processor!(ontora_ai::entry));
+    let user = Keypair::new();
+    let admin = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let user_token_account = Pubkey::new_unique();
+    let platform_vault = staking_vault_pda(&id());
+
+    let (platform_config_key, bump) = pda(&[PLATFORM_CONFIG_SEED]);
+    let mut platform_config = PlatformConfig::default();
+    platform_config.init(
+        admin,
+        100,
+        TEST_STAKE_AMOUNT,
+        86_400,
+        86_400,
+        TEST_EPOCH_EMISSION,
+        bump,
+    );
+    (platform_config.stake_mint, platform_config.staking_vault) = (mint, platform_vault);
+    test.add_account(platform_config_key, program_account(&platform_config));
+
+    let (fee_schedule_key, bump) = pda(&[FEE_SCHEDULE_SEED]);
+    let mut fee_schedule = FeeSchedule::default();
+    fee_schedule.init(admin, [0, UNSTAKE_FEE_BPS, 0, 0], bump);
+    test.add_account(fee_schedule_key, program_account(&fee_schedule));
+
+    let (treasury_key, bump) = pda(&[TREASURY_SEED]);
+    let mut treasury = Treasury::default();
+    treasury.init(admin, bump);
+    test.add_account(treasury_key, program_account(&treasury));
+
+    // Register the agents now so they aren't stale when the validator starts
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64;
+    let mut agents = Vec::new();
+    for agent_id in AGENT_IDS {
+        let (agent_key, bump) = pda(&[AI_AGENT_SEED, admin.as_ref(), &agent_id.to_le_bytes()]);
+        let mut agent = AiAgent::default();
+        agent.init(agent_id, admin, format!("Agent {}", agent_id), "Test agent".to_string(), now, bump);
+        test.add_account(agent_key, program_account(&agent));
+        agents.push(agent_key);
+    }
+
+    test.add_account(
+        mint,
+        token_program_account(spl_token::state::Mint {
+            mint_authority: COption::None,
+            supply: 10 * TEST_STAKE_AMOUNT,
+            decimals: 9,
+            is_initialized: true,
+            freeze_authority: COption::None,
+        }),
+    );
+    test.add_account(user_token_account, token_account(mint, user.pubkey(), 10 * TEST_STAKE_AMOUNT));
+    test.add_account(platform_vault, token_account(mint, platform_config_key, 0));
+    test.add_account(
+        user.pubkey(),
+        SolanaAccount {
+            lamports: INITIAL_LAMPORTS,
+            data: vec![],
+            owner: system_program::ID,
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+
+    let (banks_client, _payer, _last_blockhash) = test.start().await;
+    let program = Program::new(id(), Rc::new(banks_client.clone()), CommitmentLevel::Confirmed);
+    StakingFixture { banks_client, program, user, mint, user_token_account, platform_vault, agents }
+}
+
+async fn stake(fixture: &StakingFixture, agent_index: usize, amount: u64) {
+    let user = fixture.user.pubkey();
+    let agent_id = AGENT_IDS[agent_index];
+    fixture
+        .program
+        .request()
+        .accounts(ontora_ai::accounts::StakeOnAgent {
+            platform_config: platform_config_pda(&id()),
+            ai_agent: fixture.agents[agent_index],
+            user_stake: pda(&[USER_STAKE_SEED, user.as_ref()]).0,
+            stake_index: stake_index_pda(&id(), &user, agent_id),
+            user,
+            user_token_account: fixture.user_token_account,
+            platform_vault: fixture.platform_vault,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        })
+        .args(ontora_ai::instruction::StakeOnAgent { agent_id, amount, beneficiary: user })
+        .signer(&fixture.user)
+        .send()
+        .await
+        .unwrap();
+}
+
+async fn try_unstake(
+    fixture: &StakingFixture,
+    agent_index: usize,
+    amount: u64,
+) -> std::result::Result<(), anchor_client::ClientError> {
+    let user = fixture.user.pubkey();
+    let agent_id = AGENT_IDS[agent_index];
+    fixture
+        .program
+        .request()
+        .accounts(ontora_ai::accounts::UnstakeFromAgent {
+            platform_config: platform_config_pda(&id()),
+            ai_agent: fixture.agents[agent_index],
+            user_stake: pda(&[USER_STAKE_SEED, user.as_ref()]).0,
+            stake_index: stake_index_pda(&id(), &user, agent_id),
+            user,
+            user_token_account: fixture.user_token_account,
+            platform_vault: fixture.platform_vault,
+            stake_mint: fixture.mint,
+            fee_schedule: pda(&[FEE_SCHEDULE_SEED]).0,
+            treasury: pda(&[TREASURY_SEED]).0,
+            treasury_vault: pda(&[TREASURY_VAULT_SEED, fixture.mint.as_ref()]).0,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        })
+        .args(ontora_ai::instruction::UnstakeFromAgent { agent_id, amount })
+        .signer(&fixture.user)
+        .send()
+        .await
+        .map(|_| ())
+}
+
+async fn unstake(fixture: &StakingFixture, agent_index: usize, amount: u64) {
+    try_unstake(fixture, agent_index, amount).await.unwrap();
+}
+
+async fn fetch_agent(fixture: &mut StakingFixture, agent_index: usize) -> AiAgent {
+    let account = fixture.banks_client.get_account(fixture.agents[agent_index]).await.unwrap().unwrap();
+    AiAgent::try_deserialize(&mut account.data.as_ref()).unwrap()
+}
+
+async fn fetch_entry(fixture: &mut StakingFixture, agent_id: u64) -> Option<(Vec<u8>, StakeIndexEntry)> {
+    let account = fixture
+        .banks_client
+        .get_account(stake_index_pda(&id(), &fixture.user.pubkey(), agent_id))
+        .await
+        .unwrap()?;
+    let entry = StakeIndexEntry::try_deserialize(&mut account.data.as_ref()).unwrap();
+    Some((account.data, entry))
+}
+
+// Test that the first stake on each agent creates its entry and later stakes reuse it
+#[tokio::test]
+async fn test_stake_creates_index_entries() {
+    let mut fixture = setup_staking().await;
+    stake(&fixture, 0, TEST_STAKE_AMOUNT).await;
+    stake(&fixture, 0, TEST_STAKE_AMOUNT).await;
+    stake(&fixture, 1, 3 * TEST_STAKE_AMOUNT).await;
+
+    let user = fixture.user.pubkey();
+    for (agent_id, staked_amount) in [(AGENT_IDS[0], 2 * TEST_STAKE_AMOUNT), (AGENT_IDS[1], 3 * TEST_STAKE_AMOUNT)] {
+        let (data, entry) = fetch_entry(&mut fixture, agent_id).await.unwrap();
+        assert_eq!(data.len(), StakeIndexEntry::SPACE);
+        assert_eq!(entry.user, user);
+        assert_eq!(entry.agent_id, agent_id);
+        assert_eq!(entry.staked_amount, staked_amount);
+        assert!(entry.staked_at > 0);
+
+        // The bytes the SDK's getStakedAgents memcmp filter matches on
+        let offset = StakeIndexEntry::USER_OFFSET;
+        assert_eq!(&data[..8], &StakeIndexEntry::discriminator());
+        assert_eq!(&data[offset..offset + 32], user.as_ref());
+    }
+
+    // The user's stake no longer carries a list of agents
+    let account = fixture.banks_client.get_account(pda(&[USER_STAKE_SEED, user.as_ref()]).0).await.unwrap().unwrap();
+    assert_eq!(account.data.len(), UserStake::SPACE);
+    let user_stake = UserStake::try_deserialize(&mut account.data.as_ref()).unwrap();
+    assert_eq!(user_stake.staked_amount, 5 * TEST_STAKE_AMOUNT);
+}
+
+// Test that a partial unstake keeps the entry and a full exit closes it, refunding its rent
+#[tokio::test]
+async fn test_full_exit_closes_index_entry() {
+    let mut fixture = setup_staking().await;
+    stake(&fixture, 0, 2 * TEST_STAKE_AMOUNT).await;
+    stake(&fixture, 1, TEST_STAKE_AMOUNT).await;
+
+    unstake(&fixture, 0, TEST_STAKE_AMOUNT).await;
+    let (_, entry) = fetch_entry(&mut fixture, AGENT_IDS[0]).await.unwrap();
+    assert_eq!(entry.staked_amount, TEST_STAKE_AMOUNT);
+
+    let entry_key = stake_index_pda(&id(), &fixture.user.pubkey(), AGENT_IDS[0]);
+    let entry_rent = get_account_balance(&mut fixture.banks_client, &entry_key).await;
+    let before = get_account_balance(&mut fixture.banks_client, &fixture.user.pubkey()).await;
+    unstake(&fixture, 0, TEST_STAKE_AMOUNT).await;
+    let after = get_account_balance(&mut fixture.banks_client, &fixture.user.pubkey()).await;
+
+    assert!(fetch_entry(&mut fixture, AGENT_IDS[0]).await.is_none());
+    // The refund outweighs the transaction fee
+    assert!(after > before && after <= before + entry_rent);
+    // The stake on the other agent is untouched
+    assert!(fetch_entry(&mut fixture, AGENT_IDS[1]).await.is_some());
+
+    // Unstaked tokens come back less the unstake fee
+    let account = fixture.banks_client.get_account(fixture.user_token_account).await.unwrap().unwrap();
+    let token_account = spl_token::state::Account::unpack(&account.data).unwrap();
+    let fee = 2 * TEST_STAKE_AMOUNT * UNSTAKE_FEE_BPS as u64 / 10_000;
+    assert_eq!(token_account.amount, 10 * TEST_STAKE_AMOUNT - TEST_STAKE_AMOUNT - fee);
+}
+
+// Test that unstaking from one of two agents draws only on that agent's share of the stake
+#[tokio::test]
+async fn test_unstake_from_one_of_two_agents() {
+    let mut fixture = setup_staking().await;
+    stake(&fixture, 0, 2 * TEST_STAKE_AMOUNT).await;
+    stake(&fixture, 1, 3 * TEST_STAKE_AMOUNT).await;
+
+    // The user's total would cover it, the stake on the first agent does not
+    let error = try_unstake(&fixture, 0, 3 * TEST_STAKE_AMOUNT).await.expect_err("unstake should have failed");
+    assert_eq!(custom_error_code(&error), Some(u32::from(OntoraError::InvalidStakeAmount)), "{:?}", error);
+
+    unstake(&fixture, 0, TEST_STAKE_AMOUNT).await;
+    for (index, staked_amount) in [(0, TEST_STAKE_AMOUNT), (1, 3 * TEST_STAKE_AMOUNT)] {
+        let (_, entry) = fetch_entry(&mut fixture, AGENT_IDS[index]).await.unwrap();
+        assert_eq!(entry.staked_amount, staked_amount);
+        assert_eq!(fetch_agent(&mut fixture, index).await.staked_amount, staked_amount);
+    }
+    let user_stake = pda(&[USER_STAKE_SEED, fixture.user.pubkey().as_ref()]).0;
+    let account = fixture.banks_client.get_account(user_stake).await.unwrap().unwrap();
+    let user_stake = UserStake::try_deserialize(&mut account.data.as_ref()).unwrap();
+    assert_eq!(user_stake.staked_amount, 4 * TEST_STAKE_AMOUNT);
+}
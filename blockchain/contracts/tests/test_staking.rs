@@ -1,332 +1,1209 @@
 // test_staking.rs
 // This module contains test cases for staking functionality and edge cases in the Ontora AI Solana program.
-// It tests successful staking, unstaking, reward claiming, and various failure scenarios.
+// It tests successful staking and unstaking against SPL token accounts, various failure scenarios, and
+// the rewards a user stake earns across stake changes.
+// Legacy agent reward claims are covered in test_rewards.rs. The tests run on the in-process harness in svm/;
+// test_program_test_smoke.rs covers the same flow under solana-program-test.
 
-use anchor_lang::prelude::*;
+use anchor_lang::prelude::{AccountDeserialize, AccountSerialize};
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program_pack::Pack;
 use anchor_lang::solana_program::pubkey::Pubkey;
-use anchor_client::solana_sdk::signature::Keypair;
-use anchor_client::Program;
-use solana_program_test::*;
-use std::rc::Rc;
+use anchor_lang::solana_program::system_program;
 
-// Import test setup utilities (assumes test_setup.rs is in the same directory)
+// Shared types and PDAs from test_setup.rs, helpers from the in-process harness
+mod svm;
 mod test_setup;
-use test_setup::*;
+use svm::*;
+use test_setup::{
+    legacy_ai_agent_pda, parse_events, pending_withdrawal_pda, platform_config_pda, reward_pool_pda, reward_vault_pda,
+    stake_index_pda, staking_vault_pda, treasury_vault_pda, user_stake_pda, TestPlatform, TestUser, TEST_AI_AGENT_ID,
+    TEST_EPOCH_EMISSION, TEST_REWARD_POOL_AMOUNT, TEST_STAKE_AMOUNT,
+};
 
-// Assuming the program ID for Ontora AI (replace with actual program ID if needed)
-declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
+use ontora_ai::error::OntoraError;
+use ontora_ai::events::{PenaltyCharged, StakeDeposited, StakeWithdrawn, UnstakeRequested};
+use ontora_ai::state::{AiAgent, ConfigParams, FeeType, PlatformConfig, UserStake, MAX_EMERGENCY_PENALTY_BPS};
+use ontora_ai::{AIAgent, PendingWithdrawal, RewardPool, MIGRATED_AGENT_ID};
+
+const STAKING_COOLDOWN: i64 = 86_400; // The cooldown_seconds initialize_program sets
+const HALF_EPOCH: i64 = 43_200; // Half the epoch_duration initialize_program sets
+// What TEST_STAKE_AMOUNT earns in half an epoch at the 1% per epoch initialize_program sets
+const HALF_EPOCH_REWARD: u64 = TEST_STAKE_AMOUNT / 200;
+const EMERGENCY_PENALTY_BPS: u16 = 1_000; // Kept back by emergency_unstake
+
+// A funded staker: their lamports pay for the stake accounts, their tokens are staked
+struct Staker {
+    user: TestUser,
+    token_account: Pubkey,
+}
+
+fn setup_staking() -> (SvmContext, TestPlatform) {
+    setup_staking_with_penalty(EMERGENCY_PENALTY_BPS)
+}
+
+// `setup_staking` with a fee schedule charging `penalty_bps` on emergency unstakes
+fn setup_staking_with_penalty(penalty_bps: u16) -> (SvmContext, TestPlatform) {
+    let mut ctx = setup_svm();
+    let mut rates = [0; FeeType::COUNT];
+    rates[FeeType::EmergencyUnstake.index()] = penalty_bps;
+    let platform = initialize_program_with_fees(&mut ctx, 0, rates);
+    (ctx, platform)
+}
+
+fn create_staker(ctx: &mut SvmContext, platform: &TestPlatform, tokens: u64) -> Staker {
+    let user = create_test_user(ctx);
+    let token_account = create_funded_token_account(ctx, platform, &user.pubkey, tokens);
+    Staker { user, token_account }
+}
+
+fn stake(
+    ctx: &mut SvmContext,
+    platform: &TestPlatform,
+    staker: &Staker,
+    agent: Pubkey,
+    agent_id: u64,
+    amount: u64,
+) -> SvmResult {
+    let instruction = stake_instruction(platform, staker, staker.user.pubkey, agent, agent_id, amount);
+    send_transaction(ctx, &[instruction], &[&staker.user.keypair])
+}
+
+// `staker` funds a stake owned by `beneficiary`
+fn stake_instruction(
+    platform: &TestPlatform,
+    staker: &Staker,
+    beneficiary: Pubkey,
+    agent: Pubkey,
+    agent_id: u64,
+    amount: u64,
+) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::StakeOnAgent {
+            platform_config: platform.platform_config,
+            ai_agent: agent,
+            user_stake: user_stake_pda(&ontora_ai::ID, &beneficiary),
+            stake_index: stake_index_pda(&ontora_ai::ID, &beneficiary, agent_id),
+            user: staker.user.pubkey,
+            user_token_account: staker.token_account,
+            platform_vault: platform.platform_vault,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        },
+        ontora_ai::instruction::StakeOnAgent { agent_id, amount, beneficiary },
+    )
+}
+
+// A platform config without vaults, its admin, and a mint to create them for
+fn setup_without_vaults() -> (SvmContext, TestUser, Pubkey) {
+    let mut ctx = setup_svm();
+    let admin = create_test_user(&mut ctx);
+    let initialize = program_instruction(
+        ontora_ai::accounts::InitializePlatform {
+            platform_config: platform_config_pda(&ontora_ai::ID),
+            admin: admin.pubkey,
+            system_program: system_program::ID,
+        },
+        ontora_ai::instruction::InitializePlatform {
+            reward_rate_bps: 100,
+            min_stake_amount: TEST_STAKE_AMOUNT,
+            epoch_duration: 86_400,
+            cooldown_seconds: STAKING_COOLDOWN,
+            epoch_emission: TEST_EPOCH_EMISSION,
+        },
+    );
+    send_transaction(&mut ctx, &[initialize], &[&admin.keypair]).unwrap();
+    let mint = create_mint(&mut ctx, &admin.pubkey);
+    (ctx, admin, mint)
+}
+
+fn initialize_vaults(ctx: &mut SvmContext, admin: &TestUser, stake_mint: Pubkey) -> SvmResult {
+    let instruction = program_instruction(
+        ontora_ai::accounts::InitializeVaults {
+            platform_config: platform_config_pda(&ontora_ai::ID),
+            reward_pool: reward_pool_pda(&ontora_ai::ID),
+            stake_mint,
+            staking_vault: staking_vault_pda(&ontora_ai::ID),
+            reward_vault: reward_vault_pda(&ontora_ai::ID),
+            admin: admin.pubkey,
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        },
+        ontora_ai::instruction::InitializeVaults {},
+    );
+    send_transaction(ctx, &[instruction], &[&admin.keypair])
+}
+
+fn token_account(ctx: &SvmContext, address: &Pubkey) -> spl_token::state::Account {
+    spl_token::state::Account::unpack(&ctx.svm.get_account(address).expect("token account exists").data).unwrap()
+}
+
+fn unstake(
+    ctx: &mut SvmContext,
+    platform: &TestPlatform,
+    staker: &Staker,
+    agent: Pubkey,
+    agent_id: u64,
+    amount: u64,
+) -> SvmResult {
+    let instruction = unstake_instruction(platform, staker, staker.user.pubkey, agent, agent_id, amount);
+    send_transaction(ctx, &[instruction], &[&staker.user.keypair])
+}
+
+// `staker` withdraws from the stake of `position_owner` into their own token account
+fn unstake_instruction(
+    platform: &TestPlatform,
+    staker: &Staker,
+    position_owner: Pubkey,
+    agent: Pubkey,
+    agent_id: u64,
+    amount: u64,
+) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::UnstakeFromAgent {
+            platform_config: platform.platform_config,
+            ai_agent: agent,
+            user_stake: user_stake_pda(&ontora_ai::ID, &position_owner),
+            stake_index: stake_index_pda(&ontora_ai::ID, &position_owner, agent_id),
+            user: staker.user.pubkey,
+            user_token_account: staker.token_account,
+            platform_vault: platform.platform_vault,
+            stake_mint: platform.mint,
+            fee_schedule: platform.fee_schedule,
+            treasury: platform.treasury,
+            treasury_vault: treasury_vault_pda(&ontora_ai::ID, &platform.mint),
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        },
+        ontora_ai::instruction::UnstakeFromAgent { agent_id, amount },
+    )
+}
+
+// Pay out `staker`'s user stake rewards into their token account
+fn claim_stake_rewards(ctx: &mut SvmContext, platform: &TestPlatform, staker: &Staker) -> SvmResult {
+    let instruction = claim_stake_rewards_instruction(platform, staker);
+    send_transaction(ctx, &[instruction], &[&staker.user.keypair])
+}
+
+fn claim_stake_rewards_instruction(platform: &TestPlatform, staker: &Staker) -> Instruction {
+    program_instruction(
+        ontora_ai::accounts::ClaimStakeRewards {
+            platform_config: platform.platform_config,
+            user_stake: user_stake_pda(&ontora_ai::ID, &staker.user.pubkey),
+            user: staker.user.pubkey,
+            user_token_account: staker.token_account,
+            platform_vault: platform.platform_vault,
+            reward_mint: platform.mint,
+            fee_schedule: platform.fee_schedule,
+            treasury: platform.treasury,
+            treasury_vault: treasury_vault_pda(&ontora_ai::ID, &platform.mint),
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        },
+        ontora_ai::instruction::ClaimStakeRewards {},
+    )
+}
+
+// The accounts of `unstake_ai_agent` and `unstake_partial` for a legacy agent
+fn legacy_unstake_accounts(platform: &TestPlatform, staked: &LegacyAgent) -> ontora_ai::accounts::UnstakeAIAgent {
+    ontora_ai::accounts::UnstakeAIAgent {
+        platform_config: platform.platform_config,
+        ai_agent: staked.agent,
+        pending_withdrawal: pending_withdrawal_pda(&ontora_ai::ID, &staked.owner.pubkey),
+        user: staked.owner.pubkey,
+        user_token_account: staked.token_account,
+        staking_vault: staked.staking_vault,
+        stake_mint: platform.mint,
+        fee_schedule: platform.fee_schedule,
+        treasury: platform.treasury,
+        treasury_vault: treasury_vault_pda(&ontora_ai::ID, &platform.mint),
+        token_program: spl_token::ID,
+        system_program: system_program::ID,
+    }
+}
+
+// Withdraw a legacy agent's whole stake through `unstake_ai_agent`
+fn unstake_legacy(ctx: &mut SvmContext, platform: &TestPlatform, staked: &LegacyAgent) -> SvmResult {
+    let instruction =
+        program_instruction(legacy_unstake_accounts(platform, staked), ontora_ai::instruction::UnstakeAiAgent {});
+    send_transaction(ctx, &[instruction], &[&staked.owner.keypair])
+}
+
+// Withdraw `amount` of a legacy agent's stake through `unstake_partial`
+fn unstake_legacy_partial(
+    ctx: &mut SvmContext,
+    platform: &TestPlatform,
+    staked: &LegacyAgent,
+    amount: u64,
+) -> SvmResult {
+    let accounts = legacy_unstake_accounts(platform, staked);
+    let instruction = program_instruction(accounts, ontora_ai::instruction::UnstakePartial { amount });
+    send_transaction(ctx, &[instruction], &[&staked.owner.keypair])
+}
+
+// Withdraw a legacy agent's whole stake through `emergency_unstake`
+fn emergency_unstake(ctx: &mut SvmContext, platform: &TestPlatform, staked: &LegacyAgent) -> SvmResult {
+    let instruction = program_instruction(
+        ontora_ai::accounts::EmergencyUnstake {
+            platform_config: platform.platform_config,
+            ai_agent: staked.agent,
+            user: staked.owner.pubkey,
+            user_token_account: staked.token_account,
+            staking_vault: staked.staking_vault,
+            stake_mint: platform.mint,
+            reward_pool: platform.reward_pool,
+            fee_schedule: platform.fee_schedule,
+            reward_vault: platform.reward_vault,
+            token_program: spl_token::ID,
+        },
+        ontora_ai::instruction::EmergencyUnstake {},
+    );
+    send_transaction(ctx, &[instruction], &[&staked.owner.keypair])
+}
+
+// Close a legacy agent through `close_agent`
+fn close_agent(ctx: &mut SvmContext, platform: &TestPlatform, staked: &LegacyAgent) -> SvmResult {
+    let instruction = program_instruction(
+        ontora_ai::accounts::CloseAgent {
+            platform_config: platform.platform_config,
+            ai_agent: staked.agent,
+            pending_withdrawal: pending_withdrawal_pda(&ontora_ai::ID, &staked.owner.pubkey),
+            owner: staked.owner.pubkey,
+        },
+        ontora_ai::instruction::CloseAgent {},
+    );
+    send_transaction(ctx, &[instruction], &[&staked.owner.keypair])
+}
+
+// Change the platform's stake cap per agent, keeping its other parameters
+fn set_max_stake_per_agent(ctx: &mut SvmContext, platform: &TestPlatform, max_stake_per_agent: u64) {
+    let config: PlatformConfig = fetch_account(ctx, &platform.platform_config);
+    let update = program_instruction(
+        ontora_ai::accounts::UpdatePlatformConfig {
+            platform_config: platform.platform_config,
+            admin: platform.admin.pubkey,
+        },
+        ontora_ai::instruction::UpdatePlatformConfig {
+            params: ConfigParams { max_stake_per_agent, ..config.params() },
+            update_metadata: String::new(),
+        },
+    );
+    send_transaction(ctx, &[update], &[&platform.admin.keypair]).unwrap();
+}
+
+// Check an emergency unstake of TEST_STAKE_AMOUNT at `penalty_bps` paid `penalty` into the
+// reward pool and the rest to the owner, and logged both
+fn assert_emergency_unstake(
+    ctx: &mut SvmContext,
+    platform: &TestPlatform,
+    staked: &LegacyAgent,
+    penalty_bps: u16,
+    penalty: u64,
+) {
+    let pool_before: RewardPool = fetch_account(ctx, &platform.reward_pool);
+    let meta = emergency_unstake(ctx, platform, staked).unwrap();
+    let (user, now) = (staked.owner.pubkey, get_clock(ctx).unix_timestamp);
+
+    let net = TEST_STAKE_AMOUNT - penalty;
+    assert_eq!(get_token_balance(ctx, &staked.token_account), net);
+    assert_eq!(get_token_balance(ctx, &staked.staking_vault), 0);
+    assert_eq!(get_token_balance(ctx, &platform.reward_vault), penalty);
+    let pool: RewardPool = fetch_account(ctx, &platform.reward_pool);
+    assert_eq!(pool.total_rewards, pool_before.total_rewards + penalty);
+    let agent: AIAgent = fetch_account(ctx, &staked.agent);
+    assert_eq!((agent.staked_amount, agent.is_active), (0, false));
+    assert_eq!(fetch_account::<PlatformConfig>(ctx, &platform.platform_config).total_staked, 0);
+
+    let withdrawn = StakeWithdrawn {
+        schema_version: StakeWithdrawn::SCHEMA_VERSION,
+        user,
+        agent_id: MIGRATED_AGENT_ID,
+        amount: net,
+        timestamp: now,
+    };
+    assert_eq!(parse_events::<StakeWithdrawn>(&meta.logs), [withdrawn]);
+    let charged = PenaltyCharged {
+        schema_version: PenaltyCharged::SCHEMA_VERSION,
+        user,
+        agent_id: MIGRATED_AGENT_ID,
+        amount: penalty,
+        penalty_bps,
+        timestamp: now,
+    };
+    assert_eq!(parse_events::<PenaltyCharged>(&meta.logs), [charged]);
+}
+
+fn fetch_agent(ctx: &SvmContext, agent: Pubkey) -> AiAgent {
+    fetch_account(ctx, &agent)
+}
+
+fn fetch_user_stake(ctx: &SvmContext, staker: &Staker) -> UserStake {
+    fetch_account(ctx, &user_stake_pda(&ontora_ai::ID, &staker.user.pubkey))
+}
+
+// The staker's unclaimed rewards at the current clock
+fn pending_rewards(ctx: &SvmContext, platform: &TestPlatform, staker: &Staker) -> u64 {
+    let config: PlatformConfig = fetch_account(ctx, &platform.platform_config);
+    fetch_user_stake(ctx, staker).pending_rewards(&config, get_clock(ctx).unix_timestamp).unwrap()
+}
 
 // Test successful staking by a user
-#[tokio::test]
-async fn test_stake_success() {
-    let (mut ctx, program) = setup_test_context().await;
-    let user = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
-
-    // Create a mock AI agent for staking (adjust based on program logic)
-    let agent_pda = create_mock_ai_agent(&mut ctx.banks_client, &program, &user, TEST_AI_AGENT_ID).await;
-
-    // Get user's initial balance
-    let initial_balance = get_account_balance(&mut ctx.banks_client, &user.pubkey).await;
-
-    // Perform staking (replace with actual instruction call)
-    let stake_amount = TEST_STAKE_AMOUNT;
-    let result = program
-        .request()
-        .accounts(ontora_ai::accounts::Stake {
-            user: user.pubkey,
-            agent: agent_pda,
-            stake_account: derive_stake_account_pda(&user.pubkey, &agent_pda, &program.id()),
-            system_program: anchor_lang::solana_program::system_program::ID,
-        })
-        .args(ontora_ai::instruction::Stake {
-            amount: stake_amount,
-        })
-        .signer(&user.keypair)
-        .send()
-        .await;
-
-    // Assert the staking was successful
-    assert!(result.is_ok());
-
-    // Verify user's balance decreased by stake amount
-    let final_balance = get_account_balance(&mut ctx.banks_client, &user.pubkey).await;
-    assert_eq!(initial_balance - final_balance, stake_amount);
+#[test]
+fn test_stake_success() {
+    let (mut ctx, platform) = setup_staking();
+    let staker = create_staker(&mut ctx, &platform, 2 * TEST_STAKE_AMOUNT);
+    let agent_pda = create_mock_ai_agent(&mut ctx, &staker.user, TEST_AI_AGENT_ID);
+
+    stake(&mut ctx, &platform, &staker, agent_pda, TEST_AI_AGENT_ID, TEST_STAKE_AMOUNT).unwrap();
+
+    // The tokens moved from the user into the platform vault
+    assert_eq!(get_token_balance(&ctx, &staker.token_account), TEST_STAKE_AMOUNT);
+    assert_eq!(get_token_balance(&ctx, &platform.platform_vault), TEST_STAKE_AMOUNT);
+    assert_eq!(fetch_agent(&ctx, agent_pda).staked_amount, TEST_STAKE_AMOUNT);
 }
 
 // Test staking with insufficient funds
-#[tokio::test]
-async fn test_stake_insufficient_funds() {
-    let (mut ctx, program) = setup_test_context().await;
-    let user = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
-
-    // Create a mock AI agent for staking
-    let agent_pda = create_mock_ai_agent(&mut ctx.banks_client, &program, &user, TEST_AI_AGENT_ID).await;
-
-    // Attempt to stake more than the user's balance
-    let stake_amount = INITIAL_LAMPORTS * 2; // More than user's balance
-    let result = program
-        .request()
-        .accounts(ontora_ai::accounts::Stake {
-            user: user.pubkey,
-            agent: agent_pda,
-            stake_account: derive_stake_account_pda(&user.pubkey, &agent_pda, &program.id()),
-            system_program: anchor_lang::solana_program::system_program::ID,
-        })
-        .args(ontora_ai::instruction::Stake {
-            amount: stake_amount,
-        })
-        .signer(&user.keypair)
-        .send()
-        .await;
-
-    // Assert the staking fails due to insufficient funds
+#[test]
+fn test_stake_insufficient_funds() {
+    let (mut ctx, platform) = setup_staking();
+    let staker = create_staker(&mut ctx, &platform, TEST_STAKE_AMOUNT);
+    let agent_pda = create_mock_ai_agent(&mut ctx, &staker.user, TEST_AI_AGENT_ID);
+
+    // Attempt to stake more than the user's token balance
+    let result = stake(&mut ctx, &platform, &staker, agent_pda, TEST_AI_AGENT_ID, 2 * TEST_STAKE_AMOUNT);
     assert!(result.is_err());
+    assert_eq!(get_token_balance(&ctx, &staker.token_account), TEST_STAKE_AMOUNT);
 }
 
 // Test successful unstaking by a user
-#[tokio::test]
-async fn test_unstake_success() {
-    let (mut ctx, program) = setup_test_context().await;
-    let user = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
-
-    // Create a mock AI agent for staking
-    let agent_pda = create_mock_ai_agent(&mut ctx.banks_client, &program, &user, TEST_AI_AGENT_ID).await;
-
-    // Perform staking first
-    let stake_amount = TEST_STAKE_AMOUNT;
-    let _ = program
-        .request()
-        .accounts(ontora_ai::accounts::Stake {
-            user: user.pubkey,
-            agent: agent_pda,
-            stake_account: derive_stake_account_pda(&user.pubkey, &agent_pda, &program.id()),
-            system_program: anchor_lang::solana_program::system_program::ID,
-        })
-        .args(ontora_ai::instruction::Stake {
-            amount: stake_amount,
-        })
-        .signer(&user.keypair)
-        .send()
-        .await
-        .unwrap();
-
-    // Get user's initial balance after staking
-    let initial_balance = get_account_balance(&mut ctx.banks_client, &user.pubkey).await;
-
-    // Perform unstaking (replace with actual instruction call)
-    let result = program
-        .request()
-        .accounts(ontora_ai::accounts::Unstake {
-            user: user.pubkey,
-            agent: agent_pda,
-            stake_account: derive_stake_account_pda(&user.pubkey, &agent_pda, &program.id()),
-            system_program: anchor_lang::solana_program::system_program::ID,
-        })
-        .args(ontora_ai::instruction::Unstake {})
-        .signer(&user.keypair)
-        .send()
-        .await;
-
-    // Assert the unstaking was successful
-    assert!(result.is_ok());
-
-    // Verify user's balance increased (minus potential fees or penalties)
-    let final_balance = get_account_balance(&mut ctx.banks_client, &user.pubkey).await;
-    assert!(final_balance > initial_balance);
+#[test]
+fn test_unstake_success() {
+    let (mut ctx, platform) = setup_staking();
+    let staker = create_staker(&mut ctx, &platform, TEST_STAKE_AMOUNT);
+    let agent_pda = create_mock_ai_agent(&mut ctx, &staker.user, TEST_AI_AGENT_ID);
+    stake(&mut ctx, &platform, &staker, agent_pda, TEST_AI_AGENT_ID, TEST_STAKE_AMOUNT).unwrap();
+    assert_eq!(get_token_balance(&ctx, &staker.token_account), 0);
+
+    unstake(&mut ctx, &platform, &staker, agent_pda, TEST_AI_AGENT_ID, TEST_STAKE_AMOUNT).unwrap();
+
+    // The platform charges no unstake fee in tests, so the full stake comes back
+    assert_eq!(get_token_balance(&ctx, &staker.token_account), TEST_STAKE_AMOUNT);
+    assert_eq!(get_token_balance(&ctx, &platform.platform_vault), 0);
+    assert_eq!(fetch_agent(&ctx, agent_pda).staked_amount, 0);
 }
 
 // Test unstaking without prior staking
-#[tokio::test]
-async fn test_unstake_no_stake() {
-    let (mut ctx, program) = setup_test_context().await;
-    let user = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
-
-    // Create a mock AI agent for staking
-    let agent_pda = create_mock_ai_agent(&mut ctx.banks_client, &program, &user, TEST_AI_AGENT_ID).await;
-
-    // Attempt to unstake without staking first
-    let result = program
-        .request()
-        .accounts(ontora_ai::accounts::Unstake {
-            user: user.pubkey,
-            agent: agent_pda,
-            stake_account: derive_stake_account_pda(&user.pubkey, &agent_pda, &program.id()),
-            system_program: anchor_lang::solana_program::system_program::ID,
-        })
-        .args(ontora_ai::instruction::Unstake {})
-        .signer(&user.keypair)
-        .send()
-        .await;
-
-    // Assert the unstaking fails due to no prior stake
+#[test]
+fn test_unstake_no_stake() {
+    let (mut ctx, platform) = setup_staking();
+    let staker = create_staker(&mut ctx, &platform, TEST_STAKE_AMOUNT);
+    let agent_pda = create_mock_ai_agent(&mut ctx, &staker.user, TEST_AI_AGENT_ID);
+
+    // No stake account or index entry exists yet
+    let result = unstake(&mut ctx, &platform, &staker, agent_pda, TEST_AI_AGENT_ID, TEST_STAKE_AMOUNT);
     assert!(result.is_err());
 }
 
 // Test claiming rewards after staking
-#[tokio::test]
-async fn test_claim_rewards_success() {
-    let (mut ctx, program) = setup_test_context().await;
-    let user = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
-
-    // Create a mock AI agent for staking
-    let agent_pda = create_mock_ai_agent(&mut ctx.banks_client, &program, &user, TEST_AI_AGENT_ID).await;
-
-    // Perform staking
-    let stake_amount = TEST_STAKE_AMOUNT;
-    let _ = program
-        .request()
-        .accounts(ontora_ai::accounts::Stake {
-            user: user.pubkey,
-            agent: agent_pda,
-            stake_account: derive_stake_account_pda(&user.pubkey, &agent_pda, &program.id()),
-            system_program: anchor_lang::solana_program::system_program::ID,
-        })
-        .args(ontora_ai::instruction::Stake {
-            amount: stake_amount,
-        })
-        .signer(&user.keypair)
-        .send()
-        .await
-        .unwrap();
-
-    // Advance slots to simulate time passing for reward accrual
-    advance_slot(&mut ctx.banks_client, 100).await;
-
-    // Get user's initial balance before claiming rewards
-    let initial_balance = get_account_balance(&mut ctx.banks_client, &user.pubkey).await;
-
-    // Claim rewards (replace with actual instruction call)
-    let result = program
-        .request()
-        .accounts(ontora_ai::accounts::ClaimRewards {
-            user: user.pubkey,
-            agent: agent_pda,
-            stake_account: derive_stake_account_pda(&user.pubkey, &agent_pda, &program.id()),
-            system_program: anchor_lang::solana_program::system_program::ID,
-        })
-        .args(ontora_ai::instruction::ClaimRewards {})
-        .signer(&user.keypair)
-        .send()
-        .await;
-
-    // Assert the reward claim was successful
-    assert!(result.is_ok());
-
-    // Verify user's balance increased due to rewards
-    let final_balance = get_account_balance(&mut ctx.banks_client, &user.pubkey).await;
-    assert!(final_balance > initial_balance);
+#[test]
+fn test_claim_rewards_success() {
+    let (mut ctx, platform) = setup_staking();
+    let staker = create_staker(&mut ctx, &platform, TEST_STAKE_AMOUNT);
+    let agent_pda = create_mock_ai_agent(&mut ctx, &staker.user, TEST_AI_AGENT_ID);
+    stake(&mut ctx, &platform, &staker, agent_pda, TEST_AI_AGENT_ID, TEST_STAKE_AMOUNT).unwrap();
+
+    // Let half an epoch of rewards accrue, then claim them out of the platform vault
+    warp_seconds(&mut ctx, HALF_EPOCH);
+    claim_stake_rewards(&mut ctx, &platform, &staker).unwrap();
+
+    assert_eq!(get_token_balance(&ctx, &staker.token_account), HALF_EPOCH_REWARD);
+    assert_eq!(get_token_balance(&ctx, &platform.platform_vault), TEST_STAKE_AMOUNT - HALF_EPOCH_REWARD);
+    let position = fetch_user_stake(&ctx, &staker);
+    assert_eq!((position.accumulated_rewards, position.last_reward_claim), (0, get_clock(&ctx).unix_timestamp));
+
+    // A second claim within the epoch is refused
+    let result = claim_stake_rewards(&mut ctx, &platform, &staker);
+    assert_eq!(svm_error_code(&result), Some(u32::from(OntoraError::NoRewardsAvailable)));
 }
 
 // Test claiming rewards with no staking
-#[tokio::test]
-async fn test_claim_rewards_no_stake() {
-    let (mut ctx, program) = setup_test_context().await;
-    let user = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
-
-    // Create a mock AI agent for staking
-    let agent_pda = create_mock_ai_agent(&mut ctx.banks_client, &program, &user, TEST_AI_AGENT_ID).await;
-
-    // Attempt to claim rewards without staking
-    let result = program
-        .request()
-        .accounts(ontora_ai::accounts::ClaimRewards {
-            user: user.pubkey,
-            agent: agent_pda,
-            stake_account: derive_stake_account_pda(&user.pubkey, &agent_pda, &program.id()),
-            system_program: anchor_lang::solana_program::system_program::ID,
-        })
-        .args(ontora_ai::instruction::ClaimRewards {})
-        .signer(&user.keypair)
-        .send()
-        .await;
-
-    // Assert the reward claim fails due to no stake
-    assert!(result.is_err());
+#[test]
+fn test_claim_rewards_no_stake() {
+    let (mut ctx, platform) = setup_staking();
+    let staker = create_staker(&mut ctx, &platform, TEST_STAKE_AMOUNT);
+    create_mock_ai_agent(&mut ctx, &staker.user, TEST_AI_AGENT_ID);
+
+    // No user stake exists to claim from
+    let result = claim_stake_rewards(&mut ctx, &platform, &staker);
+    assert_eq!(svm_error_code(&result), Some(u32::from(anchor_lang::error::ErrorCode::AccountNotInitialized)));
+    assert_eq!(get_token_balance(&ctx, &staker.token_account), TEST_STAKE_AMOUNT);
 }
 
 // Test staking with invalid AI agent
-#[tokio::test]
-async fn test_stake_invalid_agent() {
-    let (mut ctx, program) = setup_test_context().await;
-    let user = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
+#[test]
+fn test_stake_invalid_agent() {
+    let (mut ctx, platform) = setup_staking();
+    let staker = create_staker(&mut ctx, &platform, TEST_STAKE_AMOUNT);
 
     // Use a random Pubkey as an invalid agent PDA
     let invalid_agent_pda = Pubkey::new_unique();
-
-    // Attempt to stake with an invalid agent
-    let stake_amount = TEST_STAKE_AMOUNT;
-    let result = program
-        .request()
-        .accounts(ontora_ai::accounts::Stake {
-            user: user.pubkey,
-            agent: invalid_agent_pda,
-            stake_account: derive_stake_account_pda(&user.pubkey, &invalid_agent_pda, &program.id()),
-            system_program: anchor_lang::solana_program::system_program::ID,
-        })
-        .args(ontora_ai::instruction::Stake {
-            amount: stake_amount,
-        })
-        .signer(&user.keypair)
-        .send()
-        .await;
-
-    // Assert the staking fails due to invalid agent
+    let result = stake(&mut ctx, &platform, &staker, invalid_agent_pda, TEST_AI_AGENT_ID, TEST_STAKE_AMOUNT);
     assert!(result.is_err());
 }
 
+// Test that initialize_vaults creates both vaults at their PDAs, under the config and the
+// reward pool, and records them with the mint on the config
+#[test]
+fn test_initialize_vaults() {
+    let (mut ctx, admin, mint) = setup_without_vaults();
+    initialize_vaults(&mut ctx, &admin, mint).unwrap();
+
+    let config: PlatformConfig = fetch_account(&ctx, &platform_config_pda(&ontora_ai::ID));
+    let (staking_vault, reward_vault) = (staking_vault_pda(&ontora_ai::ID), reward_vault_pda(&ontora_ai::ID));
+    assert_eq!((config.stake_mint, config.staking_vault, config.reward_vault), (mint, staking_vault, reward_vault));
+    let vault = token_account(&ctx, &staking_vault);
+    assert_eq!((vault.mint, vault.owner, vault.amount), (mint, platform_config_pda(&ontora_ai::ID), 0));
+    let vault = token_account(&ctx, &reward_vault);
+    assert_eq!((vault.mint, vault.owner, vault.amount), (mint, reward_pool_pda(&ontora_ai::ID), 0));
+
+    // The vaults exist now, so a second run fails
+    assert!(initialize_vaults(&mut ctx, &admin, mint).is_err());
+}
+
+// Test that only the admin may create the vaults, and only for the stake mint once one is set
+#[test]
+fn test_initialize_vaults_checks() {
+    let (mut ctx, admin, mint) = setup_without_vaults();
+    let stranger = create_test_user(&mut ctx);
+    let result = initialize_vaults(&mut ctx, &stranger, mint);
+    assert_eq!(svm_error_code(&result), Some(u32::from(OntoraError::UnauthorizedAdmin)));
+
+    // Pin the stake mint as if an earlier run had recorded it
+    let address = platform_config_pda(&ontora_ai::ID);
+    let mut account = ctx.svm.get_account(&address).unwrap();
+    let mut config = PlatformConfig::try_deserialize(&mut account.data.as_slice()).unwrap();
+    config.stake_mint = mint;
+    let mut data = Vec::new();
+    config.try_serialize(&mut data).unwrap();
+    account.data[..data.len()].copy_from_slice(&data);
+    ctx.svm.set_account(address, account).unwrap();
+
+    let other_mint = create_mint(&mut ctx, &admin.pubkey);
+    let result = initialize_vaults(&mut ctx, &admin, other_mint);
+    assert_eq!(svm_error_code(&result), Some(u32::from(OntoraError::InvalidMint)));
+    initialize_vaults(&mut ctx, &admin, mint).unwrap();
+}
+
+// Test that staking into any vault but the platform's is refused, including the
+// config's associated token account that served as the vault before
+#[test]
+fn test_stake_into_foreign_vault() {
+    let (mut ctx, platform) = setup_staking();
+    let staker = create_staker(&mut ctx, &platform, TEST_STAKE_AMOUNT);
+    let agent_pda = create_mock_ai_agent(&mut ctx, &staker.user, TEST_AI_AGENT_ID);
+    let foreign_vault = create_token_account(&mut ctx, &platform.platform_config, &platform.mint);
+
+    let user = staker.user.pubkey;
+    let mut instruction = stake_instruction(&platform, &staker, user, agent_pda, TEST_AI_AGENT_ID, TEST_STAKE_AMOUNT);
+    let vault = instruction.accounts.iter_mut().find(|meta| meta.pubkey == platform.platform_vault).unwrap();
+    vault.pubkey = foreign_vault;
+    let result = send_transaction(&mut ctx, &[instruction], &[&staker.user.keypair]);
+    assert_eq!(svm_error_code(&result), Some(u32::from(OntoraError::InvalidVault)));
+    assert_eq!(get_token_balance(&ctx, &staker.token_account), TEST_STAKE_AMOUNT);
+    assert_eq!(get_token_balance(&ctx, &foreign_vault), 0);
+}
+
+// Test that rewards are only claimed out of the platform's vault, not another token
+// account the platform config happens to own
+#[test]
+fn test_claim_from_foreign_vault() {
+    let (mut ctx, platform) = setup_staking();
+    let staker = create_staker(&mut ctx, &platform, TEST_STAKE_AMOUNT);
+    let agent_pda = create_mock_ai_agent(&mut ctx, &staker.user, TEST_AI_AGENT_ID);
+    stake(&mut ctx, &platform, &staker, agent_pda, TEST_AI_AGENT_ID, TEST_STAKE_AMOUNT).unwrap();
+    let foreign_vault = create_funded_token_account(&mut ctx, &platform, &platform.platform_config, TEST_STAKE_AMOUNT);
+    warp_seconds(&mut ctx, HALF_EPOCH);
+
+    let mut instruction = claim_stake_rewards_instruction(&platform, &staker);
+    let vault = instruction.accounts.iter_mut().find(|meta| meta.pubkey == platform.platform_vault).unwrap();
+    vault.pubkey = foreign_vault;
+    let result = send_transaction(&mut ctx, &[instruction], &[&staker.user.keypair]);
+    assert_eq!(svm_error_code(&result), Some(u32::from(OntoraError::InvalidVault)));
+    assert_eq!(get_token_balance(&ctx, &foreign_vault), TEST_STAKE_AMOUNT);
+    assert_eq!(get_token_balance(&ctx, &staker.token_account), 0);
+}
+
+// Test that a stake funded for another wallet is that wallet's position: the funder's
+// tokens move, the beneficiary earns on them and withdraws them into its own account
+#[test]
+fn test_stake_for_beneficiary() {
+    let (mut ctx, platform) = setup_staking();
+    let funder = create_staker(&mut ctx, &platform, TEST_STAKE_AMOUNT);
+    let beneficiary = create_staker(&mut ctx, &platform, 0);
+    let agent_pda = create_mock_ai_agent(&mut ctx, &funder.user, TEST_AI_AGENT_ID);
+
+    let owner = beneficiary.user.pubkey;
+    let instruction = stake_instruction(&platform, &funder, owner, agent_pda, TEST_AI_AGENT_ID, TEST_STAKE_AMOUNT);
+    let meta = send_transaction(&mut ctx, &[instruction], &[&funder.user.keypair]).unwrap();
+    assert_eq!(get_token_balance(&ctx, &funder.token_account), 0);
+    assert!(ctx.svm.get_account(&user_stake_pda(&ontora_ai::ID, &funder.user.pubkey)).is_none());
+    let position = fetch_user_stake(&ctx, &beneficiary);
+    assert_eq!((position.user, position.staked_amount), (owner, TEST_STAKE_AMOUNT));
+    let deposited = parse_events::<StakeDeposited>(&meta.logs);
+    assert_eq!(deposited.iter().map(|event| event.user).collect::<Vec<_>>(), [owner]);
+
+    warp_seconds(&mut ctx, HALF_EPOCH);
+    unstake(&mut ctx, &platform, &beneficiary, agent_pda, TEST_AI_AGENT_ID, TEST_STAKE_AMOUNT).unwrap();
+    assert_eq!(get_token_balance(&ctx, &beneficiary.token_account), TEST_STAKE_AMOUNT);
+    assert_eq!(fetch_user_stake(&ctx, &beneficiary).accumulated_rewards, HALF_EPOCH_REWARD);
+}
+
+// Test that the funder of a stake held for another wallet can't withdraw it, through
+// either its own accounts or the beneficiary's
+#[test]
+fn test_funder_cannot_unstake_for_beneficiary() {
+    let (mut ctx, platform) = setup_staking();
+    let funder = create_staker(&mut ctx, &platform, TEST_STAKE_AMOUNT);
+    let beneficiary = create_staker(&mut ctx, &platform, 0);
+    let agent_pda = create_mock_ai_agent(&mut ctx, &funder.user, TEST_AI_AGENT_ID);
+    let owner = beneficiary.user.pubkey;
+    let instruction = stake_instruction(&platform, &funder, owner, agent_pda, TEST_AI_AGENT_ID, TEST_STAKE_AMOUNT);
+    send_transaction(&mut ctx, &[instruction], &[&funder.user.keypair]).unwrap();
+
+    let result = unstake(&mut ctx, &platform, &funder, agent_pda, TEST_AI_AGENT_ID, TEST_STAKE_AMOUNT);
+    assert_eq!(svm_error_code(&result), Some(u32::from(anchor_lang::error::ErrorCode::AccountNotInitialized)));
+    let instruction = unstake_instruction(&platform, &funder, owner, agent_pda, TEST_AI_AGENT_ID, TEST_STAKE_AMOUNT);
+    let result = send_transaction(&mut ctx, &[instruction], &[&funder.user.keypair]);
+    assert_eq!(svm_error_code(&result), Some(u32::from(anchor_lang::error::ErrorCode::ConstraintSeeds)));
+    assert_eq!(get_token_balance(&ctx, &funder.token_account), 0);
+    assert_eq!(fetch_user_stake(&ctx, &beneficiary).staked_amount, TEST_STAKE_AMOUNT);
+}
+
+// Test that the rewards on a stake funded for another wallet are claimed by that wallet,
+// and that the funder has no position to claim them through
+#[test]
+fn test_beneficiary_claims_funded_stake_rewards() {
+    let (mut ctx, platform) = setup_staking();
+    let funder = create_staker(&mut ctx, &platform, TEST_STAKE_AMOUNT);
+    let beneficiary = create_staker(&mut ctx, &platform, 0);
+    let agent_pda = create_mock_ai_agent(&mut ctx, &funder.user, TEST_AI_AGENT_ID);
+    let owner = beneficiary.user.pubkey;
+    let instruction = stake_instruction(&platform, &funder, owner, agent_pda, TEST_AI_AGENT_ID, TEST_STAKE_AMOUNT);
+    send_transaction(&mut ctx, &[instruction], &[&funder.user.keypair]).unwrap();
+    warp_seconds(&mut ctx, HALF_EPOCH);
+
+    let result = claim_stake_rewards(&mut ctx, &platform, &funder);
+    assert_eq!(svm_error_code(&result), Some(u32::from(anchor_lang::error::ErrorCode::AccountNotInitialized)));
+
+    claim_stake_rewards(&mut ctx, &platform, &beneficiary).unwrap();
+    assert_eq!(get_token_balance(&ctx, &beneficiary.token_account), HALF_EPOCH_REWARD);
+    assert_eq!(get_token_balance(&ctx, &funder.token_account), 0);
+    let position = fetch_user_stake(&ctx, &beneficiary);
+    assert_eq!((position.staked_amount, position.accumulated_rewards), (TEST_STAKE_AMOUNT, 0));
+}
+
 // Test multiple users staking on the same AI agent
-#[tokio::test]
-async fn test_multiple_users_stake_same_agent() {
-    let (mut ctx, program) = setup_test_context().await;
-    let user1 = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
-    let user2 = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
-
-    // Create a mock AI agent for staking
-    let agent_pda = create_mock_ai_agent(&mut ctx.banks_client, &program, &user1, TEST_AI_AGENT_ID).await;
-
-    // User 1 stakes
-    let stake_amount = TEST_STAKE_AMOUNT;
-    let result1 = program
-        .request()
-        .accounts(ontora_ai::accounts::Stake {
-            user: user1.pubkey,
-            agent: agent_pda,
-            stake_account: derive_stake_account_pda(&user1.pubkey, &agent_pda, &program.id()),
-            system_program: anchor_lang::solana_program::system_program::ID,
-        })
-        .args(ontora_ai::instruction::Stake {
-            amount: stake_amount,
-        })
-        .signer(&user1.keypair)
-        .send()
-        .await;
-
-    // User 2 stakes on the same agent
-    let result2 = program
-        .request()
-        .accounts(ontora_ai::accounts::Stake {
-            user: user2.pubkey,
-            agent: agent_pda,
-            stake_account: derive_stake_account_pda(&user2.pubkey, &agent_pda, &program.id()),
-            system_program: anchor_lang::solana_program::system_program::ID,
-        })
-        .args(ontora_ai::instruction::Stake {
-            amount: stake_amount,
-        })
-        .signer(&user2.keypair)
-        .send()
-        .await;
-
-    // Assert both staking operations were successful
-    assert!(result1.is_ok());
-    assert!(result2.is_ok());
-}
-
-// Helper function to derive stake account PDA (adjust based on program logic)
-fn derive_stake_account_pda(user: &Pubkey, agent: &Pubkey, program_id: &Pubkey) -> Pubkey {
-    let (pda, _bump) = Pubkey::find_program_address(
-        &[b"stake_account", user.as_ref(), agent.as_ref()],
-        program_id,
+#[test]
+fn test_multiple_users_stake_same_agent() {
+    let world = Scenario::new()
+        .with_reward_pool(0)
+        .user("alice").with_tokens(TEST_STAKE_AMOUNT)
+        .user("bob").with_tokens(TEST_STAKE_AMOUNT)
+        .agent("gpt-agent").owned_by("alice")
+        .stake("alice", "gpt-agent", TEST_STAKE_AMOUNT)
+        .stake("bob", "gpt-agent", TEST_STAKE_AMOUNT)
+        .expect_staked("gpt-agent", 2 * TEST_STAKE_AMOUNT)
+        .expect_tokens("alice", 0)
+        .expect_tokens("bob", 0)
+        .run();
+
+    assert_eq!(get_token_balance(&world.ctx, &world.platform.platform_vault), 2 * TEST_STAKE_AMOUNT);
+}
+
+// Test that stakes from several users fill an agent exactly to the cap, after which
+// even the minimum stake is refused
+#[test]
+fn test_stake_up_to_agent_cap() {
+    let (mut ctx, platform) = setup_staking();
+    set_max_stake_per_agent(&mut ctx, &platform, 3 * TEST_STAKE_AMOUNT);
+    let first = create_staker(&mut ctx, &platform, 2 * TEST_STAKE_AMOUNT);
+    let second = create_staker(&mut ctx, &platform, 2 * TEST_STAKE_AMOUNT);
+    let agent_pda = create_mock_ai_agent(&mut ctx, &first.user, TEST_AI_AGENT_ID);
+
+    stake(&mut ctx, &platform, &first, agent_pda, TEST_AI_AGENT_ID, 2 * TEST_STAKE_AMOUNT).unwrap();
+    stake(&mut ctx, &platform, &second, agent_pda, TEST_AI_AGENT_ID, TEST_STAKE_AMOUNT).unwrap();
+    assert_eq!(fetch_agent(&ctx, agent_pda).staked_amount, 3 * TEST_STAKE_AMOUNT);
+
+    let result = stake(&mut ctx, &platform, &second, agent_pda, TEST_AI_AGENT_ID, TEST_STAKE_AMOUNT);
+    assert_eq!(svm_error_code(&result), Some(u32::from(OntoraError::AgentStakeCapExceeded)));
+    assert_eq!(get_token_balance(&ctx, &second.token_account), TEST_STAKE_AMOUNT);
+}
+
+// Test that a stake one unit past the cap is refused and leaves the agent empty
+#[test]
+fn test_stake_past_agent_cap() {
+    let (mut ctx, platform) = setup_staking();
+    set_max_stake_per_agent(&mut ctx, &platform, 2 * TEST_STAKE_AMOUNT);
+    let staker = create_staker(&mut ctx, &platform, 3 * TEST_STAKE_AMOUNT);
+    let agent_pda = create_mock_ai_agent(&mut ctx, &staker.user, TEST_AI_AGENT_ID);
+
+    let result = stake(&mut ctx, &platform, &staker, agent_pda, TEST_AI_AGENT_ID, 2 * TEST_STAKE_AMOUNT + 1);
+    assert_eq!(svm_error_code(&result), Some(u32::from(OntoraError::AgentStakeCapExceeded)));
+    assert_eq!(fetch_agent(&ctx, agent_pda).staked_amount, 0);
+    assert_eq!(get_token_balance(&ctx, &staker.token_account), 3 * TEST_STAKE_AMOUNT);
+}
+
+// Test that a cap of 0 leaves agents unlimited, including once a cap is lifted
+#[test]
+fn test_stake_without_agent_cap() {
+    let (mut ctx, platform) = setup_staking();
+    let staker = create_staker(&mut ctx, &platform, 10 * TEST_STAKE_AMOUNT);
+    let agent_pda = create_mock_ai_agent(&mut ctx, &staker.user, TEST_AI_AGENT_ID);
+    let config: PlatformConfig = fetch_account(&ctx, &platform.platform_config);
+    assert_eq!(config.max_stake_per_agent, 0);
+
+    stake(&mut ctx, &platform, &staker, agent_pda, TEST_AI_AGENT_ID, 5 * TEST_STAKE_AMOUNT).unwrap();
+    set_max_stake_per_agent(&mut ctx, &platform, 5 * TEST_STAKE_AMOUNT);
+    let result = stake(&mut ctx, &platform, &staker, agent_pda, TEST_AI_AGENT_ID, TEST_STAKE_AMOUNT);
+    assert_eq!(svm_error_code(&result), Some(u32::from(OntoraError::AgentStakeCapExceeded)));
+
+    set_max_stake_per_agent(&mut ctx, &platform, 0);
+    stake(&mut ctx, &platform, &staker, agent_pda, TEST_AI_AGENT_ID, 5 * TEST_STAKE_AMOUNT).unwrap();
+    assert_eq!(fetch_agent(&ctx, agent_pda).staked_amount, 10 * TEST_STAKE_AMOUNT);
+}
+
+// Test that the unstaking cooldown ends exactly cooldown_seconds after staking
+#[test]
+fn test_unstake_cooldown_boundary() {
+    let (mut ctx, platform) = setup_staking();
+    let staked = register_legacy_agent(&mut ctx, &platform, TEST_STAKE_AMOUNT);
+
+    warp_seconds(&mut ctx, STAKING_COOLDOWN - 1);
+    let result = unstake_legacy(&mut ctx, &platform, &staked);
+    assert_eq!(svm_error_code(&result), Some(u32::from(OntoraError::CooldownNotCompleted)));
+    assert_eq!(get_token_balance(&ctx, &staked.token_account), 0);
+
+    warp_seconds(&mut ctx, 1);
+    unstake_legacy(&mut ctx, &platform, &staked).unwrap();
+    assert_eq!(get_token_balance(&ctx, &staked.token_account), TEST_STAKE_AMOUNT);
+}
+
+// Test that unstaking follows the cooldown the admin configures, not the one at staking time
+#[test]
+fn test_unstake_configured_cooldown() {
+    let (mut ctx, platform) = setup_staking();
+    let staked = register_legacy_agent(&mut ctx, &platform, TEST_STAKE_AMOUNT);
+    let config: PlatformConfig = fetch_account(&ctx, &platform.platform_config);
+    let update = program_instruction(
+        ontora_ai::accounts::UpdatePlatformConfig {
+            platform_config: platform.platform_config,
+            admin: platform.admin.pubkey,
+        },
+        ontora_ai::instruction::UpdatePlatformConfig {
+            params: ConfigParams {
+                cooldown_seconds: 3_600,
+                max_stake_per_agent: 0,
+                vesting_seconds: 0,
+                ..config.params()
+            },
+            update_metadata: String::new(),
+        },
     );
-    pda
+    send_transaction(&mut ctx, &[update], &[&platform.admin.keypair]).unwrap();
+
+    warp_seconds(&mut ctx, 3_599);
+    let result = unstake_legacy(&mut ctx, &platform, &staked);
+    assert_eq!(svm_error_code(&result), Some(u32::from(OntoraError::CooldownNotCompleted)));
+
+    warp_seconds(&mut ctx, 1);
+    unstake_legacy(&mut ctx, &platform, &staked).unwrap();
+    assert_eq!(get_token_balance(&ctx, &staked.token_account), TEST_STAKE_AMOUNT);
+}
+
+// Test that a partial unstake of the whole balance empties and deactivates the agent,
+// and that no more than the balance can be unstaked
+#[test]
+fn test_unstake_partial_full_balance() {
+    let (mut ctx, platform) = setup_staking();
+    let staked = register_legacy_agent(&mut ctx, &platform, TEST_STAKE_AMOUNT);
+    warp_seconds(&mut ctx, STAKING_COOLDOWN);
+
+    let result = unstake_legacy_partial(&mut ctx, &platform, &staked, TEST_STAKE_AMOUNT + 1);
+    assert_eq!(svm_error_code(&result), Some(u32::from(OntoraError::InvalidUnstakeAmount)));
+
+    unstake_legacy_partial(&mut ctx, &platform, &staked, TEST_STAKE_AMOUNT).unwrap();
+    let agent: AIAgent = fetch_account(&ctx, &staked.agent);
+    assert_eq!((agent.staked_amount, agent.is_active), (0, false));
+    assert_eq!(get_token_balance(&ctx, &staked.token_account), TEST_STAKE_AMOUNT);
+    assert_eq!(get_token_balance(&ctx, &staked.staking_vault), 0);
+}
+
+// Test that a legacy unstake pays out of the agent's own vault only, not the platform's
+#[test]
+fn test_unstake_legacy_from_platform_vault() {
+    let (mut ctx, platform) = setup_staking();
+    let staker = create_staker(&mut ctx, &platform, TEST_STAKE_AMOUNT);
+    let agent_pda = create_mock_ai_agent(&mut ctx, &staker.user, TEST_AI_AGENT_ID);
+    stake(&mut ctx, &platform, &staker, agent_pda, TEST_AI_AGENT_ID, TEST_STAKE_AMOUNT).unwrap();
+    let staked = register_legacy_agent(&mut ctx, &platform, TEST_STAKE_AMOUNT);
+    warp_seconds(&mut ctx, STAKING_COOLDOWN);
+
+    let mut accounts = legacy_unstake_accounts(&platform, &staked);
+    accounts.staking_vault = platform.platform_vault;
+    let instruction = program_instruction(accounts, ontora_ai::instruction::UnstakeAiAgent {});
+    let result = send_transaction(&mut ctx, &[instruction], &[&staked.owner.keypair]);
+    assert_eq!(svm_error_code(&result), Some(u32::from(anchor_lang::error::ErrorCode::ConstraintTokenOwner)));
+    assert_eq!(get_token_balance(&ctx, &platform.platform_vault), TEST_STAKE_AMOUNT);
+    assert_eq!(get_token_balance(&ctx, &staked.token_account), 0);
+}
+
+// Test that unstaking zero fails and leaves the position and its cooldown as they were
+#[test]
+fn test_unstake_partial_zero() {
+    let (mut ctx, platform) = setup_staking();
+    let staked = register_legacy_agent(&mut ctx, &platform, TEST_STAKE_AMOUNT);
+    warp_seconds(&mut ctx, STAKING_COOLDOWN);
+    let before: AIAgent = fetch_account(&ctx, &staked.agent);
+
+    let result = unstake_legacy_partial(&mut ctx, &platform, &staked, 0);
+    assert_eq!(svm_error_code(&result), Some(u32::from(OntoraError::InvalidUnstakeAmount)));
+    let after: AIAgent = fetch_account(&ctx, &staked.agent);
+    assert_eq!((after.staked_amount, after.last_stake_time), (TEST_STAKE_AMOUNT, before.last_stake_time));
+    assert!(after.is_active);
+}
+
+// Test two partial unstakes in a row: the agent stays active, and each restarts the cooldown
+#[test]
+fn test_unstake_partial_sequential() {
+    let (mut ctx, platform) = setup_staking();
+    let staked = register_legacy_agent(&mut ctx, &platform, TEST_STAKE_AMOUNT);
+    let quarter = TEST_STAKE_AMOUNT / 4;
+
+    warp_seconds(&mut ctx, STAKING_COOLDOWN);
+    unstake_legacy_partial(&mut ctx, &platform, &staked, quarter).unwrap();
+    let agent: AIAgent = fetch_account(&ctx, &staked.agent);
+    assert_eq!((agent.staked_amount, agent.is_active), (TEST_STAKE_AMOUNT - quarter, true));
+    assert_eq!(agent.last_stake_time, get_clock(&ctx).unix_timestamp);
+
+    let result = unstake_legacy_partial(&mut ctx, &platform, &staked, quarter);
+    assert_eq!(svm_error_code(&result), Some(u32::from(OntoraError::CooldownNotCompleted)));
+
+    warp_seconds(&mut ctx, STAKING_COOLDOWN);
+    unstake_legacy_partial(&mut ctx, &platform, &staked, quarter).unwrap();
+    let agent: AIAgent = fetch_account(&ctx, &staked.agent);
+    assert_eq!((agent.staked_amount, agent.is_active), (TEST_STAKE_AMOUNT - 2 * quarter, true));
+    assert_eq!(get_token_balance(&ctx, &staked.token_account), 2 * quarter);
+    assert_eq!(get_token_balance(&ctx, &staked.staking_vault), TEST_STAKE_AMOUNT - 2 * quarter);
+}
+
+// Test that a top-up moves the tokens into the agent's vault, adds to its stake and
+// restarts the cooldown
+#[test]
+fn test_increase_stake() {
+    let (mut ctx, platform) = setup_staking();
+    let staked = register_legacy_agent(&mut ctx, &platform, TEST_STAKE_AMOUNT);
+    mint_tokens(&mut ctx, &platform.mint, &platform.admin, &staked.token_account, TEST_STAKE_AMOUNT);
+    warp_seconds(&mut ctx, STAKING_COOLDOWN);
+
+    let instruction = increase_stake_instruction(&platform, &staked, TEST_STAKE_AMOUNT);
+    send_transaction(&mut ctx, &[instruction], &[&staked.owner.keypair]).unwrap();
+    let agent: AIAgent = fetch_account(&ctx, &staked.agent);
+    assert_eq!((agent.staked_amount, agent.is_active), (2 * TEST_STAKE_AMOUNT, true));
+    assert_eq!(agent.last_stake_time, get_clock(&ctx).unix_timestamp);
+    assert_eq!(get_token_balance(&ctx, &staked.staking_vault), 2 * TEST_STAKE_AMOUNT);
+    assert_eq!(get_token_balance(&ctx, &staked.token_account), 0);
+
+    let result = unstake_legacy(&mut ctx, &platform, &staked);
+    assert_eq!(svm_error_code(&result), Some(u32::from(OntoraError::CooldownNotCompleted)));
+}
+
+// Test that a legacy registration of exactly the platform minimum is accepted and counted
+#[test]
+fn test_register_at_min_stake() {
+    let (mut ctx, platform) = setup_staking();
+    let staked = register_legacy_agent(&mut ctx, &platform, TEST_STAKE_AMOUNT);
+
+    let agent: AIAgent = fetch_account(&ctx, &staked.agent);
+    assert_eq!((agent.staked_amount, agent.is_active), (TEST_STAKE_AMOUNT, true));
+    let config: PlatformConfig = fetch_account(&ctx, &platform.platform_config);
+    assert_eq!((config.total_staked, config.total_agents), (TEST_STAKE_AMOUNT, 1));
+    register_legacy_agent(&mut ctx, &platform, 2 * TEST_STAKE_AMOUNT);
+    assert_eq!(fetch_account::<PlatformConfig>(&ctx, &platform.platform_config).total_agents, 2);
+}
+
+// Test that a legacy registration one lamport below the platform minimum is refused
+#[test]
+fn test_register_below_min_stake() {
+    let (mut ctx, platform) = setup_staking();
+    let staked = prepare_legacy_agent(&mut ctx, &platform, TEST_STAKE_AMOUNT);
+
+    let instruction = register_legacy_instruction(&platform, &staked, TEST_STAKE_AMOUNT - 1);
+    let result = send_transaction(&mut ctx, &[instruction], &[&staked.owner.keypair]);
+    assert_eq!(svm_error_code(&result), Some(u32::from(OntoraError::InvalidStakeAmount)));
+    assert!(ctx.svm.get_account(&staked.agent).is_none());
+    assert_eq!(get_token_balance(&ctx, &staked.token_account), TEST_STAKE_AMOUNT);
+    assert_eq!(fetch_account::<PlatformConfig>(&ctx, &platform.platform_config).total_agents, 0);
+}
+
+// Test that a legacy registration without the platform config account is refused
+#[test]
+fn test_register_without_config() {
+    let (mut ctx, platform) = setup_staking();
+    let staked = prepare_legacy_agent(&mut ctx, &platform, TEST_STAKE_AMOUNT);
+
+    let mut instruction = register_legacy_instruction(&platform, &staked, TEST_STAKE_AMOUNT);
+    instruction.accounts[0].pubkey = Pubkey::new_unique();
+    let result = send_transaction(&mut ctx, &[instruction], &[&staked.owner.keypair]);
+    assert_eq!(svm_error_code(&result), Some(u32::from(anchor_lang::error::ErrorCode::AccountNotInitialized)));
+    assert!(ctx.svm.get_account(&staked.agent).is_none());
+}
+
+// Test that a top-up of zero, or of an agent unstaked in full, is refused
+#[test]
+fn test_increase_stake_zero_and_inactive() {
+    let (mut ctx, platform) = setup_staking();
+    let staked = register_legacy_agent(&mut ctx, &platform, TEST_STAKE_AMOUNT);
+
+    let instruction = increase_stake_instruction(&platform, &staked, 0);
+    let result = send_transaction(&mut ctx, &[instruction], &[&staked.owner.keypair]);
+    assert_eq!(svm_error_code(&result), Some(u32::from(OntoraError::InsufficientStake)));
+
+    warp_seconds(&mut ctx, STAKING_COOLDOWN);
+    unstake_legacy(&mut ctx, &platform, &staked).unwrap();
+    let instruction = increase_stake_instruction(&platform, &staked, TEST_STAKE_AMOUNT);
+    let result = send_transaction(&mut ctx, &[instruction], &[&staked.owner.keypair]);
+    assert_eq!(svm_error_code(&result), Some(u32::from(OntoraError::AgentInactive)));
+    assert_eq!(fetch_account::<AIAgent>(&ctx, &staked.agent).staked_amount, 0);
+}
+
+// Test that registering, topping up and unstaking a legacy agent log StakeDeposited and
+// StakeWithdrawn with the owner, the amount and the cluster time
+#[test]
+fn test_legacy_stake_events() {
+    let (mut ctx, platform) = setup_staking();
+    let owner = create_test_user(&mut ctx);
+    let token_account = create_funded_token_account(&mut ctx, &platform, &owner.pubkey, 2 * TEST_STAKE_AMOUNT);
+    let agent = legacy_ai_agent_pda(&ontora_ai::ID, &owner.pubkey);
+    let staking_vault = create_token_account(&mut ctx, &agent, &platform.mint);
+    let staked = LegacyAgent { owner, agent, token_account, staking_vault };
+    let user = staked.owner.pubkey;
+    let deposited = |amount, timestamp| StakeDeposited {
+        schema_version: StakeDeposited::SCHEMA_VERSION,
+        user,
+        agent_id: MIGRATED_AGENT_ID,
+        amount,
+        timestamp,
+        staking_duration: 0,
+    };
+    let withdrawn = |amount, timestamp| StakeWithdrawn {
+        schema_version: StakeWithdrawn::SCHEMA_VERSION,
+        user,
+        agent_id: MIGRATED_AGENT_ID,
+        amount,
+        timestamp,
+    };
+
+    let instruction = register_legacy_instruction(&platform, &staked, TEST_STAKE_AMOUNT);
+    let meta = send_transaction(&mut ctx, &[instruction], &[&staked.owner.keypair]).unwrap();
+    let now = get_clock(&ctx).unix_timestamp;
+    assert_eq!(parse_events::<StakeDeposited>(&meta.logs), [deposited(TEST_STAKE_AMOUNT, now)]);
+
+    let now = warp_seconds(&mut ctx, 60);
+    let instruction = increase_stake_instruction(&platform, &staked, TEST_STAKE_AMOUNT);
+    let meta = send_transaction(&mut ctx, &[instruction], &[&staked.owner.keypair]).unwrap();
+    assert_eq!(parse_events::<StakeDeposited>(&meta.logs), [deposited(TEST_STAKE_AMOUNT, now)]);
+
+    let now = warp_seconds(&mut ctx, STAKING_COOLDOWN);
+    let meta = unstake_legacy_partial(&mut ctx, &platform, &staked, TEST_STAKE_AMOUNT / 2).unwrap();
+    assert_eq!(parse_events::<StakeWithdrawn>(&meta.logs), [withdrawn(TEST_STAKE_AMOUNT / 2, now)]);
+
+    let now = warp_seconds(&mut ctx, STAKING_COOLDOWN);
+    let meta = unstake_legacy(&mut ctx, &platform, &staked).unwrap();
+    assert_eq!(parse_events::<StakeWithdrawn>(&meta.logs), [withdrawn(3 * TEST_STAKE_AMOUNT / 2, now)]);
+    assert!(parse_events::<StakeDeposited>(&meta.logs).is_empty());
+}
+
+// Test that a 0 bps penalty lets the whole stake out during the cooldown
+#[test]
+fn test_emergency_unstake_without_penalty() {
+    let (mut ctx, platform) = setup_staking_with_penalty(0);
+    let staked = register_legacy_agent(&mut ctx, &platform, TEST_STAKE_AMOUNT);
+
+    assert_emergency_unstake(&mut ctx, &platform, &staked, 0, 0);
+}
+
+// Test that the maximum penalty keeps a fifth of the stake for the reward pool
+#[test]
+fn test_emergency_unstake_max_penalty() {
+    let (mut ctx, platform) = setup_staking_with_penalty(MAX_EMERGENCY_PENALTY_BPS);
+    let staked = register_legacy_agent(&mut ctx, &platform, TEST_STAKE_AMOUNT);
+    warp_seconds(&mut ctx, 60);
+
+    let penalty = TEST_STAKE_AMOUNT / 5;
+    assert_emergency_unstake(&mut ctx, &platform, &staked, MAX_EMERGENCY_PENALTY_BPS, penalty);
+}
+
+// Test that an emergency unstake after the cooldown still works and is still charged,
+// and that nothing is left to take out a second time
+#[test]
+fn test_emergency_unstake_after_cooldown() {
+    let (mut ctx, platform) = setup_staking();
+    let staked = register_legacy_agent(&mut ctx, &platform, TEST_STAKE_AMOUNT);
+    warp_seconds(&mut ctx, STAKING_COOLDOWN);
+
+    let penalty = TEST_STAKE_AMOUNT / 10;
+    assert_emergency_unstake(&mut ctx, &platform, &staked, EMERGENCY_PENALTY_BPS, penalty);
+
+    let result = emergency_unstake(&mut ctx, &platform, &staked);
+    assert_eq!(svm_error_code(&result), Some(u32::from(OntoraError::InvalidUnstakeAmount)));
+}
+
+// Test that a requested withdrawal leaves the stake at once, can't be claimed a second
+// before the cooldown since the last stake change ends, and pays out and closes at its end
+#[test]
+fn test_request_and_claim_unstake() {
+    let (mut ctx, platform) = setup_staking();
+    let staked = register_legacy_agent(&mut ctx, &platform, TEST_STAKE_AMOUNT);
+    let (user, staked_at) = (staked.owner.pubkey, get_clock(&ctx).unix_timestamp);
+    let withdrawal = pending_withdrawal_pda(&ontora_ai::ID, &user);
+
+    let now = warp_seconds(&mut ctx, 60);
+    let instruction = request_unstake_instruction(&platform, &staked, TEST_STAKE_AMOUNT / 4);
+    let meta = send_transaction(&mut ctx, &[instruction], &[&staked.owner.keypair]).unwrap();
+    let release_time = staked_at + STAKING_COOLDOWN;
+    let requested = UnstakeRequested {
+        schema_version: UnstakeRequested::SCHEMA_VERSION,
+        user,
+        agent_id: MIGRATED_AGENT_ID,
+        amount: TEST_STAKE_AMOUNT / 4,
+        release_time,
+        timestamp: now,
+    };
+    assert_eq!(parse_events::<UnstakeRequested>(&meta.logs), [requested]);
+    let pending: PendingWithdrawal = fetch_account(&ctx, &withdrawal);
+    assert_eq!((pending.owner, pending.amount, pending.release_time), (user, TEST_STAKE_AMOUNT / 4, release_time));
+    let agent: AIAgent = fetch_account(&ctx, &staked.agent);
+    assert_eq!((agent.staked_amount, agent.is_active), (3 * TEST_STAKE_AMOUNT / 4, true));
+    let config: PlatformConfig = fetch_account(&ctx, &platform.platform_config);
+    assert_eq!(config.total_staked, 3 * TEST_STAKE_AMOUNT / 4);
+    // The tokens wait in the agent's vault
+    assert_eq!(get_token_balance(&ctx, &staked.staking_vault), TEST_STAKE_AMOUNT);
+
+    warp_seconds(&mut ctx, release_time - now - 1);
+    let result = send_transaction(&mut ctx, &[claim_unstake_instruction(&platform, &staked)], &[&staked.owner.keypair]);
+    assert_eq!(svm_error_code(&result), Some(u32::from(OntoraError::CooldownNotCompleted)));
+
+    let now = warp_seconds(&mut ctx, 1);
+    let instruction = claim_unstake_instruction(&platform, &staked);
+    let meta = send_transaction(&mut ctx, &[instruction], &[&staked.owner.keypair]).unwrap();
+    let withdrawn = StakeWithdrawn {
+        schema_version: StakeWithdrawn::SCHEMA_VERSION,
+        user,
+        agent_id: MIGRATED_AGENT_ID,
+        amount: TEST_STAKE_AMOUNT / 4,
+        timestamp: now,
+    };
+    assert_eq!(parse_events::<StakeWithdrawn>(&meta.logs), [withdrawn]);
+    assert_eq!(get_token_balance(&ctx, &staked.token_account), TEST_STAKE_AMOUNT / 4);
+    assert_eq!(get_token_balance(&ctx, &staked.staking_vault), 3 * TEST_STAKE_AMOUNT / 4);
+    assert!(ctx.svm.get_account(&withdrawal).is_none());
+    assert_eq!(fetch_account::<AIAgent>(&ctx, &staked.agent).staked_amount, 3 * TEST_STAKE_AMOUNT / 4);
+}
+
+// Test that a one-shot unstake waits while a withdrawal is pending, that only one
+// withdrawal is pending at a time, and that requests are bounded by the stake
+#[test]
+fn test_request_unstake_blocks_one_shot_unstake() {
+    let (mut ctx, platform) = setup_staking();
+    let staked = register_legacy_agent(&mut ctx, &platform, TEST_STAKE_AMOUNT);
+    let request = |ctx: &mut SvmContext, amount| {
+        let instruction = request_unstake_instruction(&platform, &staked, amount);
+        send_transaction(ctx, &[instruction], &[&staked.owner.keypair])
+    };
+
+    let result = request(&mut ctx, 0);
+    assert_eq!(svm_error_code(&result), Some(u32::from(OntoraError::InvalidUnstakeAmount)));
+    let result = request(&mut ctx, TEST_STAKE_AMOUNT + 1);
+    assert_eq!(svm_error_code(&result), Some(u32::from(OntoraError::InvalidUnstakeAmount)));
+    request(&mut ctx, TEST_STAKE_AMOUNT / 2).unwrap();
+    assert!(request(&mut ctx, TEST_STAKE_AMOUNT / 4).is_err());
+
+    warp_seconds(&mut ctx, STAKING_COOLDOWN);
+    let result = unstake_legacy(&mut ctx, &platform, &staked);
+    assert_eq!(svm_error_code(&result), Some(u32::from(OntoraError::WithdrawalPending)));
+    let result = unstake_legacy_partial(&mut ctx, &platform, &staked, TEST_STAKE_AMOUNT / 4);
+    assert_eq!(svm_error_code(&result), Some(u32::from(OntoraError::WithdrawalPending)));
+
+    // Once claimed, the rest leaves the one-shot way
+    send_transaction(&mut ctx, &[claim_unstake_instruction(&platform, &staked)], &[&staked.owner.keypair]).unwrap();
+    unstake_legacy(&mut ctx, &platform, &staked).unwrap();
+    assert_eq!(get_token_balance(&ctx, &staked.token_account), TEST_STAKE_AMOUNT);
+    assert_eq!(get_token_balance(&ctx, &staked.staking_vault), 0);
+}
+
+// Test that a one-shot unstake with a withdrawal pending can't pass some other empty
+// account in place of the owner's PendingWithdrawal to skip the check
+#[test]
+fn test_one_shot_unstake_with_other_pending_withdrawal_account() {
+    let (mut ctx, platform) = setup_staking();
+    let staked = register_legacy_agent(&mut ctx, &platform, TEST_STAKE_AMOUNT);
+    let instruction = request_unstake_instruction(&platform, &staked, TEST_STAKE_AMOUNT / 2);
+    send_transaction(&mut ctx, &[instruction], &[&staked.owner.keypair]).unwrap();
+    warp_seconds(&mut ctx, STAKING_COOLDOWN);
+
+    let mut accounts = legacy_unstake_accounts(&platform, &staked);
+    accounts.pending_withdrawal = pending_withdrawal_pda(&ontora_ai::ID, &Pubkey::new_unique());
+    let instruction = program_instruction(accounts, ontora_ai::instruction::UnstakeAiAgent {});
+    let result = send_transaction(&mut ctx, &[instruction], &[&staked.owner.keypair]);
+    assert_eq!(svm_error_code(&result), Some(u32::from(anchor_lang::error::ErrorCode::ConstraintSeeds)));
+    assert_eq!(get_token_balance(&ctx, &staked.token_account), 0);
+}
+
+// Test that an agent closes only once its stake is withdrawn and claimed, refunding its
+// rent and the agent count, and that its owner can then register again
+#[test]
+fn test_close_agent_and_register_again() {
+    let (mut ctx, platform) = setup_staking();
+    let staked = register_legacy_agent(&mut ctx, &platform, TEST_STAKE_AMOUNT);
+    let result = close_agent(&mut ctx, &platform, &staked);
+    assert_eq!(svm_error_code(&result), Some(u32::from(OntoraError::AgentNotEmpty)));
+
+    // Requesting the whole stake empties the agent, but the withdrawal is paid from its vault
+    let instruction = request_unstake_instruction(&platform, &staked, TEST_STAKE_AMOUNT);
+    send_transaction(&mut ctx, &[instruction], &[&staked.owner.keypair]).unwrap();
+    let result = close_agent(&mut ctx, &platform, &staked);
+    assert_eq!(svm_error_code(&result), Some(u32::from(OntoraError::WithdrawalPending)));
+    warp_seconds(&mut ctx, STAKING_COOLDOWN);
+    let instruction = claim_unstake_instruction(&platform, &staked);
+    send_transaction(&mut ctx, &[instruction], &[&staked.owner.keypair]).unwrap();
+
+    let rent = get_account_balance(&ctx, &staked.agent);
+    let lamports = get_account_balance(&ctx, &staked.owner.pubkey);
+    close_agent(&mut ctx, &platform, &staked).unwrap();
+    assert_eq!(get_account_balance(&ctx, &staked.agent), 0);
+    assert_eq!(get_account_balance(&ctx, &staked.owner.pubkey), lamports + rent);
+    assert_eq!(fetch_account::<PlatformConfig>(&ctx, &platform.platform_config).total_agents, 0);
+
+    // The agent's vault outlives it and takes the new stake
+    let instruction = register_legacy_instruction(&platform, &staked, TEST_STAKE_AMOUNT);
+    send_transaction(&mut ctx, &[instruction], &[&staked.owner.keypair]).unwrap();
+    let agent: AIAgent = fetch_account(&ctx, &staked.agent);
+    assert_eq!((agent.staked_amount, agent.is_active), (TEST_STAKE_AMOUNT, true));
+    assert_eq!(get_token_balance(&ctx, &staked.staking_vault), TEST_STAKE_AMOUNT);
+    assert_eq!(fetch_account::<PlatformConfig>(&ctx, &platform.platform_config).total_agents, 1);
+}
+
+// Test that an unstaked agent with rewards it has not claimed stays open
+#[test]
+fn test_close_agent_with_pending_rewards() {
+    let mut ctx = setup_svm();
+    let platform = initialize_program(&mut ctx, TEST_REWARD_POOL_AMOUNT);
+    let staked = register_legacy_agent(&mut ctx, &platform, TEST_STAKE_AMOUNT);
+    warp_seconds(&mut ctx, REWARD_EPOCH);
+    let distribute = program_instruction(
+        ontora_ai::accounts::DistributeRewards {
+            platform_config: platform.platform_config,
+            reward_pool: platform.reward_pool,
+            ai_agent: staked.agent,
+            crank: platform.admin.pubkey,
+        },
+        ontora_ai::instruction::DistributeRewards {},
+    );
+    send_transaction(&mut ctx, &[distribute], &[&platform.admin.keypair]).unwrap();
+    unstake_legacy(&mut ctx, &platform, &staked).unwrap();
+
+    let agent: AIAgent = fetch_account(&ctx, &staked.agent);
+    assert_eq!((agent.staked_amount, agent.is_active), (0, false));
+    assert!(agent.accumulated_rewards > 0);
+    let result = close_agent(&mut ctx, &platform, &staked);
+    assert_eq!(svm_error_code(&result), Some(u32::from(OntoraError::AgentNotEmpty)));
+    assert_eq!(fetch_account::<PlatformConfig>(&ctx, &platform.platform_config).total_agents, 1);
+}
+
+// Test that an agent paid this epoch stays open until the next one, so it can't be
+// registered again and paid a second time in the same epoch
+#[test]
+fn test_close_agent_paid_this_epoch() {
+    let (mut ctx, platform) = setup_staking();
+    let staked = register_legacy_agent(&mut ctx, &platform, TEST_STAKE_AMOUNT);
+    let now = warp_seconds(&mut ctx, REWARD_EPOCH);
+    let distribute = program_instruction(
+        ontora_ai::accounts::DistributeRewards {
+            platform_config: platform.platform_config,
+            reward_pool: platform.reward_pool,
+            ai_agent: staked.agent,
+            crank: platform.admin.pubkey,
+        },
+        ontora_ai::instruction::DistributeRewards {},
+    );
+    // The pool is empty, so the payout leaves nothing to claim
+    send_transaction(&mut ctx, &[distribute], &[&platform.admin.keypair]).unwrap();
+    emergency_unstake(&mut ctx, &platform, &staked).unwrap();
+
+    let result = close_agent(&mut ctx, &platform, &staked);
+    assert_eq!(svm_error_code(&result), Some(u32::from(OntoraError::AlreadyDistributedThisEpoch)));
+    assert_eq!(fetch_account::<PlatformConfig>(&ctx, &platform.platform_config).total_agents, 1);
+
+    warp_seconds(&mut ctx, REWARD_EPOCH - now.rem_euclid(REWARD_EPOCH));
+    close_agent(&mut ctx, &platform, &staked).unwrap();
+    assert_eq!(get_account_balance(&ctx, &staked.agent), 0);
+}
+
+// Test that a top-up earns only from when it is staked: the first half epoch pays
+// on the original stake, the second on the doubled one
+#[test]
+fn test_rewards_after_stake_increase() {
+    let (mut ctx, platform) = setup_staking();
+    let staker = create_staker(&mut ctx, &platform, 2 * TEST_STAKE_AMOUNT);
+    let agent_pda = create_mock_ai_agent(&mut ctx, &staker.user, TEST_AI_AGENT_ID);
+    stake(&mut ctx, &platform, &staker, agent_pda, TEST_AI_AGENT_ID, TEST_STAKE_AMOUNT).unwrap();
+
+    warp_seconds(&mut ctx, HALF_EPOCH);
+    stake(&mut ctx, &platform, &staker, agent_pda, TEST_AI_AGENT_ID, TEST_STAKE_AMOUNT).unwrap();
+    assert_eq!(fetch_user_stake(&ctx, &staker).accumulated_rewards, HALF_EPOCH_REWARD);
+    assert_eq!(pending_rewards(&ctx, &platform, &staker), HALF_EPOCH_REWARD);
+
+    warp_seconds(&mut ctx, HALF_EPOCH);
+    assert_eq!(pending_rewards(&ctx, &platform, &staker), 3 * HALF_EPOCH_REWARD);
+}
+
+// Test that unstaked tokens keep what they earned while staked and stop earning
+#[test]
+fn test_rewards_after_partial_unstake() {
+    let (mut ctx, platform) = setup_staking();
+    let staker = create_staker(&mut ctx, &platform, 2 * TEST_STAKE_AMOUNT);
+    let agent_pda = create_mock_ai_agent(&mut ctx, &staker.user, TEST_AI_AGENT_ID);
+    stake(&mut ctx, &platform, &staker, agent_pda, TEST_AI_AGENT_ID, 2 * TEST_STAKE_AMOUNT).unwrap();
+
+    warp_seconds(&mut ctx, HALF_EPOCH);
+    unstake(&mut ctx, &platform, &staker, agent_pda, TEST_AI_AGENT_ID, TEST_STAKE_AMOUNT).unwrap();
+    let user_stake = fetch_user_stake(&ctx, &staker);
+    assert_eq!((user_stake.staked_amount, user_stake.accumulated_rewards), (TEST_STAKE_AMOUNT, 2 * HALF_EPOCH_REWARD));
+
+    warp_seconds(&mut ctx, HALF_EPOCH);
+    assert_eq!(pending_rewards(&ctx, &platform, &staker), 3 * HALF_EPOCH_REWARD);
+
+    // A full exit keeps the rewards earned and earns nothing more
+    unstake(&mut ctx, &platform, &staker, agent_pda, TEST_AI_AGENT_ID, TEST_STAKE_AMOUNT).unwrap();
+    warp_seconds(&mut ctx, HALF_EPOCH);
+    assert_eq!(pending_rewards(&ctx, &platform, &staker), 3 * HALF_EPOCH_REWARD);
+}
+
+// Test that two users staking the same amount at different times each earn only
+// for the time they have been staked
+#[test]
+fn test_rewards_for_different_entry_times() {
+    let (mut ctx, platform) = setup_staking();
+    let early = create_staker(&mut ctx, &platform, TEST_STAKE_AMOUNT);
+    let late = create_staker(&mut ctx, &platform, TEST_STAKE_AMOUNT);
+    let agent_pda = create_mock_ai_agent(&mut ctx, &early.user, TEST_AI_AGENT_ID);
+    stake(&mut ctx, &platform, &early, agent_pda, TEST_AI_AGENT_ID, TEST_STAKE_AMOUNT).unwrap();
+
+    warp_seconds(&mut ctx, HALF_EPOCH);
+    stake(&mut ctx, &platform, &late, agent_pda, TEST_AI_AGENT_ID, TEST_STAKE_AMOUNT).unwrap();
+    assert_eq!(pending_rewards(&ctx, &platform, &late), 0);
+
+    warp_seconds(&mut ctx, HALF_EPOCH);
+    assert_eq!(pending_rewards(&ctx, &platform, &early), 2 * HALF_EPOCH_REWARD);
+    assert_eq!(pending_rewards(&ctx, &platform, &late), HALF_EPOCH_REWARD);
 }
@@ -0,0 +1,70 @@
+// test_treasury.rs
+// This module contains test cases for the protocol treasury: per-source deposit
+// accounting and governance-gated outflows. Fee rates are covered in test_fees.rs.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::solana_program::system_program;
+use solana_program_test::*;
+
+// Import test setup utilities (assumes test_setup.rs is in the same directory)
+mod test_setup;
+use test_setup::*;
+
+use ontora_ai::state::{FeeSchedule, FeeSource, FeeType, Treasury};
+
+// Test that fees from two sources accumulate separately, followed by an outflow
+#[test]
+fn test_fees_from_two_sources_accumulate() {
+    let mut treasury = Treasury::default();
+    treasury.init(Pubkey::new_unique(), 255);
+    let mut fee_schedule = FeeSchedule::default();
+    fee_schedule.init(Pubkey::new_unique(), [0, 50, 100, 0], 255);
+
+    let (_, claim_fee) = fee_schedule.apply_fee(10_000, FeeType::Claim).unwrap();
+    let (_, unstake_fee) = fee_schedule.apply_fee(40_000, FeeType::Unstake).unwrap();
+    treasury.record_deposit(FeeSource::Claim, claim_fee).unwrap();
+    treasury.record_deposit(FeeSource::Unstake, unstake_fee).unwrap();
+    treasury.record_deposit(FeeSource::Claim, claim_fee).unwrap();
+
+    assert_eq!(treasury.deposits_by_source[FeeSource::Claim.index()], 200);
+    assert_eq!(treasury.deposits_by_source[FeeSource::Unstake.index()], 200);
+    assert_eq!(treasury.deposits_by_source[FeeSource::Subscription.index()], 0);
+
+    // Governance-approved outflow is tracked separately from deposits
+    treasury.record_outflow(150).unwrap();
+    assert_eq!(treasury.total_outflows, 150);
+    assert_eq!(treasury.deposits_by_source.iter().sum::<u64>(), 400);
+}
+
+// Test that treasury creation is limited to the treasury role
+#[tokio::test]
+async fn test_initialize_treasury() {
+    let (mut ctx, program) = setup_test_context().await;
+    let admin = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
+    let governance = create_test_user(&mut ctx.banks_client, &ctx.payer, ctx.last_blockhash).await;
+    let platform_config = initialize_test_platform(&program, &admin).await;
+
+    let initialize = |signer: &TestUser| {
+        program
+            .request()
+            .accounts(ontora_ai::accounts::InitializeTreasury {
+                platform_config,
+                treasury: treasury_pda(&program.id()),
+                authority: signer.pubkey,
+                system_program: system_program::ID,
+            })
+            .args(ontora_ai::instruction::InitializeTreasury {
+                governance: governance.pubkey,
+            })
+            .signer(&signer.keypair)
+            .send()
+    };
+
+    assert!(initialize(&governance).await.is_err());
+    assert!(initialize(&admin).await.is_ok());
+
+    let account = ctx.banks_client.get_account(treasury_pda(&program.id())).await.unwrap().unwrap();
+    let treasury = Treasury::try_deserialize(&mut account.data.as_ref()).unwrap();
+    assert_eq!(treasury.governance, governance.pubkey);
+}
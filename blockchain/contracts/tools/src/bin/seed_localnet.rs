@@ -0,0 +1,535 @@
+// seed_localnet.rs
+// Seeds a local validator with a demo platform for frontend development: the platform
+// config with its roles, treasury and fee schedule, a funded reward pool, five agents
+// with varied performance scores, three users with stakes, and one active proposal.
+//
+//     solana-test-validator --reset --bpf-program <PROGRAM_ID> target/deploy/ontora_ai.so
+//     ONTORA_PROGRAM_ID=<PROGRAM_ID> cargo run --bin seed-localnet
+//
+// Environment:
+//     ONTORA_PROGRAM_ID     deployed program id (default: the program's declared id)
+//     LOCALNET_RPC_URL      validator RPC endpoint (default: http://127.0.0.1:8899)
+//     SEED_MANIFEST_PATH    where the manifest is written (default: localnet-manifest.json)
+//
+// Every keypair is derived from a fixed label, so a rerun addresses the same accounts and
+// skips the ones that already exist. The manifest lists each address and the secret keys
+// the frontend signs with. Those keys are public by construction; use them on localnet only.
+
+use std::error::Error;
+use std::str::FromStr;
+use std::time::Instant;
+
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::solana_program::system_program;
+use anchor_lang::{AccountDeserialize, InstructionData, ToAccountMetas};
+use serde_json::{json, Value};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::hash::hashv;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{keypair_from_seed, Keypair, Signer};
+use solana_sdk::system_instruction;
+use solana_sdk::transaction::Transaction;
+
+use ontora_ai::pda::{
+    AI_AGENT_SEED, FEE_SCHEDULE_SEED, PLATFORM_CONFIG_SEED, PROPOSAL_SEED, REWARD_POOL_SEED, REWARD_VAULT_SEED,
+    STAKE_INDEX_SEED, STAKING_VAULT_SEED, TREASURY_SEED, USER_STAKE_SEED,
+};
+use ontora_ai::state::{AiAgent, FeeType, PlatformConfig, ProposalAction, Role};
+
+type SeedResult<T> = std::result::Result<T, Box<dyn Error>>;
+
+const DEFAULT_RPC_URL: &str = "http://127.0.0.1:8899";
+const DEFAULT_MANIFEST_PATH: &str = "localnet-manifest.json";
+
+// Prefix of every keypair seed, so the demo keys never collide with real ones
+const SEED_DOMAIN: &[u8] = b"ontora-localnet";
+
+const MINT_DECIMALS: u8 = 9;
+const TOKEN: u64 = 1_000_000_000;
+
+// The admin pays for everything and tops up the other keypairs
+const ADMIN_LAMPORTS: u64 = 100 * LAMPORTS_PER_SOL;
+const ACCOUNT_LAMPORTS: u64 = 2 * LAMPORTS_PER_SOL;
+
+const REWARD_POOL_TOKENS: u64 = 1_000_000 * TOKEN;
+const USER_TOKENS: u64 = 10_000 * TOKEN;
+const MIN_STAKE_AMOUNT: u64 = TOKEN;
+
+// [Stake, Unstake, Claim, EmergencyUnstake], so the treasury fills up during a demo
+const FEE_RATES_BPS: [u16; FeeType::COUNT] = [0, 50, 100, 0];
+
+// Name, description and performance score of each agent; agent ids start at 1
+const AGENTS: [(&str, &str, u64); 5] = [
+    ("Ontora-Alpha", "Market forecasting agent for SOL pairs", 92),
+    ("Ontora-Beta", "Sentiment analysis over social feeds", 78),
+    ("Ontora-Gamma", "On-chain liquidity monitor", 65),
+    ("Ontora-Delta", "Experimental arbitrage scout", 41),
+    ("Ontora-Epsilon", "Governance proposal summarizer", 88),
+];
+
+// (agent id, whole tokens) staked by each user
+const STAKES: [&[(u64, u64)]; 3] = [&[(1, 2_500), (2, 1_000)], &[(1, 750), (3, 4_000)], &[(4, 300), (5, 1_200)]];
+
+const PROPOSAL_TITLE: &str = "Raise the claim fee to 1.5%";
+const PROPOSAL_DESCRIPTION: &str = "Route more of each claim to the treasury to fund agent audits.";
+const PROPOSAL_OPTIONS: [&str; 3] = ["For", "Against", "Abstain"];
+const PROPOSAL_VOTING_SECONDS: u64 = 7 * 86_400;
+
+// Derive the keypair for `label`; the same label always yields the same key
+fn demo_keypair(label: &str) -> Keypair {
+    keypair_from_seed(&hashv(&[SEED_DOMAIN, label.as_bytes()]).to_bytes()).expect("a 32-byte seed is always valid")
+}
+
+fn pda(seeds: &[&[u8]], program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(seeds, program_id).0
+}
+
+fn keypair_json(keypair: &Keypair) -> Value {
+    json!({ "pubkey": keypair.pubkey().to_string(), "secret_key": keypair.to_bytes().to_vec() })
+}
+
+struct Platform {
+    platform_config: Pubkey,
+    fee_schedule: Pubkey,
+    treasury: Pubkey,
+    reward_pool: Pubkey,
+    mint: Keypair,
+    platform_vault: Pubkey,
+    reward_vault: Pubkey,
+}
+
+struct SeededAgent {
+    agent_id: u64,
+    owner: Keypair,
+    address: Pubkey,
+}
+
+struct SeededUser {
+    user: Keypair,
+    token_account: Pubkey,
+    user_stake: Pubkey,
+}
+
+struct Seeder {
+    rpc: RpcClient,
+    program_id: Pubkey,
+    admin: Keypair,
+}
+
+impl Seeder {
+    fn program_instruction(&self, accounts: impl ToAccountMetas, args: impl InstructionData) -> Instruction {
+        Instruction {
+            program_id: self.program_id,
+            accounts: accounts.to_account_metas(None),
+            data: args.data(),
+        }
+    }
+
+    // Send `instructions` paid by the admin and wait for confirmation; an empty batch is a no-op
+    fn send(&self, instructions: &[Instruction], signers: &[&Keypair]) -> SeedResult<()> {
+        if instructions.is_empty() {
+            return Ok(());
+        }
+        let mut all_signers = vec![&self.admin];
+        all_signers.extend_from_slice(signers);
+        let tx = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.admin.pubkey()),
+            &all_signers,
+            self.rpc.get_latest_blockhash()?,
+        );
+        self.rpc.send_and_confirm_transaction(&tx)?;
+        Ok(())
+    }
+
+    fn exists(&self, address: &Pubkey) -> SeedResult<bool> {
+        Ok(self.rpc.get_account_with_commitment(address, self.rpc.commitment())?.value.is_some())
+    }
+
+    fn fetch<T: AccountDeserialize>(&self, address: &Pubkey) -> SeedResult<T> {
+        let data = self.rpc.get_account_data(address)?;
+        T::try_deserialize(&mut data.as_slice()).map_err(|error| format!("failed to decode {}: {}", address, error).into())
+    }
+
+    fn token_balance(&self, account: &Pubkey) -> SeedResult<u64> {
+        match self.rpc.get_account_with_commitment(account, self.rpc.commitment())?.value {
+            Some(account) => Ok(spl_token::state::Account::unpack(&account.data)?.amount),
+            None => Ok(0),
+        }
+    }
+
+    // Instruction minting whatever `account` lacks to hold `target` tokens, if anything
+    fn top_up_tokens(&self, mint: &Pubkey, account: &Pubkey, target: u64) -> SeedResult<Option<Instruction>> {
+        let balance = self.token_balance(account)?;
+        if balance >= target {
+            return Ok(None);
+        }
+        let instruction = spl_token::instruction::mint_to(
+            &spl_token::ID,
+            mint,
+            account,
+            &self.admin.pubkey(),
+            &[],
+            target - balance,
+        )?;
+        Ok(Some(instruction))
+    }
+
+    // Airdrop to the admin, then move SOL from the admin to every keypair running low
+    fn fund(&self, accounts: &[Pubkey]) -> SeedResult<()> {
+        if self.rpc.get_balance(&self.admin.pubkey())? < ADMIN_LAMPORTS / 2 {
+            let signature = self.rpc.request_airdrop(&self.admin.pubkey(), ADMIN_LAMPORTS)?;
+            self.rpc.poll_for_signature(&signature)?;
+        }
+        let mut instructions = Vec::new();
+        for account in accounts {
+            let balance = self.rpc.get_balance(account)?;
+            if balance < ACCOUNT_LAMPORTS / 2 {
+                instructions.push(system_instruction::transfer(
+                    &self.admin.pubkey(),
+                    account,
+                    ACCOUNT_LAMPORTS - balance,
+                ));
+            }
+        }
+        self.send(&instructions, &[])
+    }
+
+    // The platform accounts are created in one transaction, so they exist all together or not at all
+    fn seed_platform(&self) -> SeedResult<Platform> {
+        let program_id = self.program_id;
+        let admin = self.admin.pubkey();
+        let mint = demo_keypair("mint");
+        let platform_config = pda(&[PLATFORM_CONFIG_SEED], &program_id);
+        let reward_pool = pda(&[REWARD_POOL_SEED], &program_id);
+        let platform = Platform {
+            platform_config,
+            fee_schedule: pda(&[FEE_SCHEDULE_SEED], &program_id),
+            treasury: pda(&[TREASURY_SEED], &program_id),
+            reward_pool,
+            platform_vault: pda(&[STAKING_VAULT_SEED], &program_id),
+            reward_vault: pda(&[REWARD_VAULT_SEED], &program_id),
+            mint,
+        };
+
+        if self.exists(&platform.platform_config)? {
+            let config = self.fetch::<PlatformConfig>(&platform.platform_config)?;
+            if config.admin != admin {
+                return Err(format!(
+                    "platform already initialized by admin {}; restart the validator with --reset",
+                    config.admin
+                )
+                .into());
+            }
+            println!("platform config exists, skipping initialization");
+        } else {
+            let mut instructions = vec![self.program_instruction(
+                ontora_ai::accounts::InitializePlatform {
+                    platform_config: platform.platform_config,
+                    admin,
+                    system_program: system_program::ID,
+                },
+                ontora_ai::instruction::InitializePlatform {
+                    reward_rate_bps: 100,
+                    min_stake_amount: MIN_STAKE_AMOUNT,
+                    epoch_duration: 86_400,
+                    cooldown_seconds: 86_400,
+                    epoch_emission: 100_000_000,
+                },
+            )];
+            for role in [Role::Crank, Role::Oracle, Role::Treasury] {
+                instructions.push(self.program_instruction(
+                    ontora_ai::accounts::SetRole { platform_config: platform.platform_config, admin },
+                    ontora_ai::instruction::SetRole { role, holder: admin },
+                ));
+            }
+            instructions.push(self.program_instruction(
+                ontora_ai::accounts::InitializeTreasury {
+                    platform_config: platform.platform_config,
+                    treasury: platform.treasury,
+                    authority: admin,
+                    system_program: system_program::ID,
+                },
+                ontora_ai::instruction::InitializeTreasury { governance: admin },
+            ));
+            instructions.push(self.program_instruction(
+                ontora_ai::accounts::InitializeFeeSchedule {
+                    platform_config: platform.platform_config,
+                    fee_schedule: platform.fee_schedule,
+                    admin,
+                    system_program: system_program::ID,
+                },
+                ontora_ai::instruction::InitializeFeeSchedule {
+                    governance: admin,
+                    rates_bps: FEE_RATES_BPS,
+                },
+            ));
+            instructions.push(self.program_instruction(
+                ontora_ai::accounts::InitializeRewardPool {
+                    reward_pool: platform.reward_pool,
+                    user: admin,
+                    system_program: system_program::ID,
+                },
+                ontora_ai::instruction::InitializeRewardPool { initial_rewards: REWARD_POOL_TOKENS },
+            ));
+            self.send(&instructions, &[])?;
+            println!("initialized platform config, roles, treasury, fee schedule and reward pool");
+        }
+
+        let mut instructions = Vec::new();
+        let mut signers = Vec::new();
+        if !self.exists(&platform.mint.pubkey())? {
+            let rent = self.rpc.get_minimum_balance_for_rent_exemption(spl_token::state::Mint::LEN)?;
+            instructions.push(system_instruction::create_account(
+                &admin,
+                &platform.mint.pubkey(),
+                rent,
+                spl_token::state::Mint::LEN as u64,
+                &spl_token::ID,
+            ));
+            instructions.push(spl_token::instruction::initialize_mint(
+                &spl_token::ID,
+                &platform.mint.pubkey(),
+                &admin,
+                None,
+                MINT_DECIMALS,
+            )?);
+            signers.push(&platform.mint);
+        }
+        if !self.exists(&platform.platform_vault)? {
+            instructions.push(self.program_instruction(
+                ontora_ai::accounts::InitializeVaults {
+                    platform_config: platform.platform_config,
+                    reward_pool: platform.reward_pool,
+                    stake_mint: platform.mint.pubkey(),
+                    staking_vault: platform.platform_vault,
+                    reward_vault: platform.reward_vault,
+                    admin,
+                    token_program: spl_token::ID,
+                    system_program: system_program::ID,
+                },
+                ontora_ai::instruction::InitializeVaults {},
+            ));
+        }
+        instructions.extend(self.top_up_tokens(&platform.mint.pubkey(), &platform.reward_vault, REWARD_POOL_TOKENS)?);
+        self.send(&instructions, &signers)?;
+        println!("mint {} and vaults ready", platform.mint.pubkey());
+        Ok(platform)
+    }
+
+    // Register each agent under its own owner, then set the scores that differ in one oracle transaction
+    fn seed_agents(&self, platform: &Platform) -> SeedResult<Vec<SeededAgent>> {
+        let mut agents = Vec::new();
+        let mut score_updates = Vec::new();
+        for (index, (name, description, score)) in AGENTS.iter().enumerate() {
+            let agent_id = index as u64 + 1;
+            let owner = demo_keypair(&format!("agent-owner-{}", agent_id));
+            let address = pda(
+                &[AI_AGENT_SEED, owner.pubkey().as_ref(), &agent_id.to_le_bytes()],
+                &self.program_id,
+            );
+            if !self.exists(&address)? {
+                let instruction = self.program_instruction(
+                    ontora_ai::accounts::RegisterAiAgent {
+                        ai_agent: address,
+                        owner: owner.pubkey(),
+                        system_program: system_program::ID,
+                    },
+                    ontora_ai::instruction::RegisterAgent {
+                        agent_id,
+                        name: name.to_string(),
+                        description: description.to_string(),
+                    },
+                );
+                self.send(&[instruction], &[&owner])?;
+                println!("registered agent {} ({})", agent_id, name);
+            }
+            if self.fetch::<AiAgent>(&address)?.performance_score != *score {
+                score_updates.push(self.program_instruction(
+                    ontora_ai::accounts::UpdatePerformanceScore {
+                        platform_config: platform.platform_config,
+                        ai_agent: address,
+                        oracle: self.admin.pubkey(),
+                    },
+                    ontora_ai::instruction::UpdatePerformanceScore { score: *score },
+                ));
+            }
+            agents.push(SeededAgent { agent_id, owner, address });
+        }
+        self.send(&score_updates, &[])?;
+        Ok(agents)
+    }
+
+    // Fund each user's token account and place the stakes that have no index entry yet
+    fn seed_users(&self, platform: &Platform, agents: &[SeededAgent]) -> SeedResult<Vec<SeededUser>> {
+        let mint = platform.mint.pubkey();
+        let mut users = Vec::new();
+        for (index, stakes) in STAKES.iter().enumerate() {
+            let user = demo_keypair(&format!("user-{}", index + 1));
+            let token_account = spl_associated_token_account::get_associated_token_address(&user.pubkey(), &mint);
+            let user_stake = pda(&[USER_STAKE_SEED, user.pubkey().as_ref()], &self.program_id);
+
+            let mut instructions = Vec::new();
+            let mut staking = false;
+            if !self.exists(&token_account)? {
+                instructions.push(spl_associated_token_account::instruction::create_associated_token_account(
+                    &self.admin.pubkey(),
+                    &user.pubkey(),
+                    &mint,
+                    &spl_token::ID,
+                ));
+                instructions.extend(self.top_up_tokens(&mint, &token_account, USER_TOKENS)?);
+            }
+            for &(agent_id, tokens) in stakes.iter() {
+                let stake_index = pda(
+                    &[STAKE_INDEX_SEED, user.pubkey().as_ref(), &agent_id.to_le_bytes()],
+                    &self.program_id,
+                );
+                if self.exists(&stake_index)? {
+                    continue;
+                }
+                let agent = &agents[agent_id as usize - 1];
+                instructions.push(self.program_instruction(
+                    ontora_ai::accounts::StakeOnAgent {
+                        platform_config: platform.platform_config,
+                        ai_agent: agent.address,
+                        user_stake,
+                        stake_index,
+                        user: user.pubkey(),
+                        user_token_account: token_account,
+                        platform_vault: platform.platform_vault,
+                        token_program: spl_token::ID,
+                        system_program: system_program::ID,
+                    },
+                    ontora_ai::instruction::StakeOnAgent {
+                        agent_id,
+                        amount: tokens * TOKEN,
+                        beneficiary: user.pubkey(),
+                    },
+                ));
+                staking = true;
+                println!("user-{} stakes {} tokens on agent {}", index + 1, tokens, agent_id);
+            }
+            // A keypair that signs none of the instructions may not sign the transaction
+            let signers: &[&Keypair] = if staking { &[&user] } else { &[] };
+            self.send(&instructions, signers)?;
+            users.push(SeededUser { user, token_account, user_stake });
+        }
+        Ok(users)
+    }
+
+    // Open the demo proposal unless any proposal exists already
+    fn seed_proposal(&self, platform: &Platform, creator: &Keypair) -> SeedResult<Pubkey> {
+        let proposal_id = 0u64;
+        let proposal = pda(&[PROPOSAL_SEED, &proposal_id.to_le_bytes()], &self.program_id);
+        if self.fetch::<PlatformConfig>(&platform.platform_config)?.proposal_count > proposal_id {
+            return Ok(proposal);
+        }
+        let instruction = self.program_instruction(
+            ontora_ai::accounts::CreateGovernanceProposal {
+                creator: creator.pubkey(),
+                platform_config: platform.platform_config,
+                proposal,
+                system_program: system_program::ID,
+            },
+            ontora_ai::instruction::CreateGovernanceProposal {
+                title: PROPOSAL_TITLE.to_string(),
+                description: PROPOSAL_DESCRIPTION.to_string(),
+                voting_duration: PROPOSAL_VOTING_SECONDS,
+                options: PROPOSAL_OPTIONS.iter().map(|option| option.to_string()).collect(),
+                // Fees are not a proposal action, so the demo proposal only signals
+                action: ProposalAction::NoOp,
+            },
+        );
+        self.send(&[instruction], &[creator])?;
+        println!("created proposal {}", proposal_id);
+        Ok(proposal)
+    }
+}
+
+fn manifest(
+    rpc_url: &str,
+    seeder: &Seeder,
+    platform: &Platform,
+    agents: &[SeededAgent],
+    users: &[SeededUser],
+    proposal: &Pubkey,
+) -> Value {
+    json!({
+        "rpc_url": rpc_url,
+        "program_id": seeder.program_id.to_string(),
+        "admin": keypair_json(&seeder.admin),
+        "mint": platform.mint.pubkey().to_string(),
+        "mint_decimals": MINT_DECIMALS,
+        "platform_config": platform.platform_config.to_string(),
+        "fee_schedule": platform.fee_schedule.to_string(),
+        "treasury": platform.treasury.to_string(),
+        "reward_pool": platform.reward_pool.to_string(),
+        "platform_vault": platform.platform_vault.to_string(),
+        "reward_vault": platform.reward_vault.to_string(),
+        "agents": agents.iter().zip(AGENTS.iter()).map(|(agent, (name, _, score))| json!({
+            "agent_id": agent.agent_id,
+            "name": name,
+            "performance_score": score,
+            "address": agent.address.to_string(),
+            "owner": keypair_json(&agent.owner),
+        })).collect::<Vec<_>>(),
+        "users": users.iter().zip(STAKES.iter()).map(|(user, stakes)| json!({
+            "user": keypair_json(&user.user),
+            "token_account": user.token_account.to_string(),
+            "user_stake": user.user_stake.to_string(),
+            "stakes": stakes.iter().map(|(agent_id, tokens)| json!({
+                "agent_id": agent_id,
+                "amount": tokens * TOKEN,
+            })).collect::<Vec<_>>(),
+        })).collect::<Vec<_>>(),
+        "proposals": [{
+            "proposal_id": 0,
+            "address": proposal.to_string(),
+            "title": PROPOSAL_TITLE,
+        }],
+    })
+}
+
+fn main() -> SeedResult<()> {
+    let started = Instant::now();
+    let program_id = match std::env::var("ONTORA_PROGRAM_ID") {
+        Ok(id) => Pubkey::from_str(&id).map_err(|error| format!("invalid ONTORA_PROGRAM_ID {}: {}", id, error))?,
+        Err(_) => ontora_ai::ID,
+    };
+    let rpc_url = std::env::var("LOCALNET_RPC_URL").unwrap_or_else(|_| DEFAULT_RPC_URL.to_string());
+    let manifest_path = std::env::var("SEED_MANIFEST_PATH").unwrap_or_else(|_| DEFAULT_MANIFEST_PATH.to_string());
+
+    let seeder = Seeder {
+        rpc: RpcClient::new_with_commitment(rpc_url.clone(), CommitmentConfig::confirmed()),
+        program_id,
+        admin: demo_keypair("admin"),
+    };
+    if !seeder.exists(&program_id)? {
+        return Err(format!("program {} is not deployed on {}", program_id, rpc_url).into());
+    }
+
+    let mut wallets: Vec<Pubkey> = (1..=AGENTS.len())
+        .map(|agent_id| demo_keypair(&format!("agent-owner-{}", agent_id)).pubkey())
+        .collect();
+    wallets.extend((1..=STAKES.len()).map(|user| demo_keypair(&format!("user-{}", user)).pubkey()));
+    seeder.fund(&wallets)?;
+
+    let platform = seeder.seed_platform()?;
+    let agents = seeder.seed_agents(&platform)?;
+    let users = seeder.seed_users(&platform, &agents)?;
+    let proposal = seeder.seed_proposal(&platform, &users[0].user)?;
+
+    let manifest = manifest(&rpc_url, &seeder, &platform, &agents, &users, &proposal);
+    std::fs::write(&manifest_path, serde_json::to_string_pretty(&manifest)?)?;
+    println!(
+        "seeded {} in {:.1}s; manifest written to {}",
+        rpc_url,
+        started.elapsed().as_secs_f64(),
+        manifest_path
+    );
+    Ok(())
+}
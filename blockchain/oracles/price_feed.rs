@@ -1,4 +1,11 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::ed25519_program;
+use anchor_lang::solana_program::hash::hash;
+use anchor_lang::system_program;
+use anchor_lang::solana_program::sysvar::instructions::{
+    load_current_index_checked, load_instruction_at_checked,
+};
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
 use chainlink_solana as chainlink;
 
 // Declare the program ID (replace with your actual program ID during deployment)
@@ -7,6 +14,44 @@ declare_id!("YourProgramIdHere11111111111111111111111111111111");
 // Constants for price feed configuration
 const MAX_DESCRIPTION_LEN: usize = 32;
 const STALE_PRICE_THRESHOLD: i64 = 3600; // 1 hour in seconds
+const MAX_SYMBOL_LEN: usize = 16;
+const MAX_PRICE_DEVIATION_BPS: i128 = 2000; // 20% max move between accepted updates
+const DEFAULT_EMA_ALPHA_BPS: u16 = 2000; // Weight of the newest observation in the EMA
+const BPS_DENOMINATOR: u16 = 10_000;
+const MAX_CONFIDENCE_BPS: u64 = 200; // Widest confidence interval accepted for conversions
+/// Decimals of the USD values returned by `convert_to_usd`
+pub const USD_DECIMALS: u8 = 6;
+pub const MAX_OPERATORS: usize = 10;
+/// Deviant finalized rounds a feed retains as slashing evidence
+pub const MAX_EVIDENCE_ROUNDS: usize = 16;
+pub const MAX_FEE_EXEMPT_PROGRAMS: usize = 16;
+
+/// Layout version of the `get_price` return data. Fields are append-only;
+/// bump this when appending so CPI consumers can detect what they received.
+pub const PRICE_VIEW_VERSION: u8 = 1;
+
+/// Layout version of `PriceFeedData`. Feeds created before the `version` field
+/// existed read back 0 and are treated as the v1 layout until migrated.
+pub const PRICE_FEED_VERSION: u8 = 3;
+
+// Ed25519 precompile instruction layout
+const ED25519_HEADER_LEN: usize = 2;
+const ED25519_OFFSETS_LEN: usize = 14;
+const ED25519_PUBKEY_LEN: usize = 32;
+const ED25519_SIGNATURE_LEN: usize = 64;
+
+// PDA seeds for the feed registry
+pub const ORACLE_CONFIG_SEED: &[u8] = b"oracle_config";
+pub const FEED_ENTRY_SEED: &[u8] = b"feed_entry";
+
+// PDA seeds for operator quorum accounts
+pub const OPERATOR_SET_SEED: &[u8] = b"operator_set";
+pub const PENDING_ROUND_SEED: &[u8] = b"pending_round";
+pub const OPERATOR_BOND_SEED: &[u8] = b"operator_bond";
+pub const BOND_VAULT_SEED: &[u8] = b"bond_vault";
+
+// PDA seed for the account collecting consumer access fees
+pub const FEE_VAULT_SEED: &[u8] = b"fee_vault";
 
 #[program]
 pub mod price_feed {
@@ -30,6 +75,14 @@ pub mod price_feed {
         price_feed.decimals = 0;
         price_feed.last_updated = 0;
         price_feed.is_initialized = true;
+        price_feed.operator_set = ctx.accounts.operator_set.key();
+        price_feed.confidence = 0;
+        price_feed.round = 0;
+        price_feed.heartbeat_secs = STALE_PRICE_THRESHOLD;
+        price_feed.stale = false;
+        price_feed.ema_price = 0;
+        price_feed.ema_alpha_bps = DEFAULT_EMA_ALPHA_BPS;
+        price_feed.version = PRICE_FEED_VERSION;
 
         emit!(PriceFeedInitialized {
             feed_id,
@@ -39,8 +92,36 @@ pub mod price_feed {
         Ok(())
     }
 
-    /// Update the price feed with the latest data from Chainlink oracle
-    pub fn update_price_feed(ctx: Context<UpdatePriceFeed>) -> Result<()> {
+    /// Submit an operator observation for the feed's next round. Once `threshold`
+    /// distinct operators agree within tolerance, their median becomes the live price.
+    pub fn update_price_feed(
+        ctx: Context<UpdatePriceFeed>,
+        price: i64,
+        decimals: u8,
+        timestamp: i64,
+    ) -> Result<()> {
+        let current_time = Clock::get()?.unix_timestamp;
+        let pending_round = &mut ctx.accounts.pending_round;
+        if pending_round.feed == Pubkey::default() {
+            pending_round.feed = ctx.accounts.price_feed.key();
+            pending_round.bump = ctx.bumps.pending_round;
+        }
+
+        ctx.accounts.operator_bond.assert_active(&ctx.accounts.operator_set)?;
+        accept_operator_submission(
+            &mut ctx.accounts.price_feed,
+            pending_round,
+            &ctx.accounts.operator_set,
+            ctx.accounts.operator.key(),
+            price,
+            decimals,
+            timestamp,
+            current_time,
+        )
+    }
+
+    /// Sync the price feed with the latest data from the Chainlink oracle
+    pub fn sync_chainlink_price(ctx: Context<SyncChainlinkPrice>) -> Result<()> {
         let price_feed = &mut ctx.accounts.price_feed;
         let chainlink_feed = &ctx.accounts.chainlink_feed;
         let chainlink_program = &ctx.accounts.chainlink_program;
@@ -62,127 +143,2195 @@ pub mod price_feed {
 
         let current_time = Clock::get()?.unix_timestamp;
         let updated_at = price_data.updated_at;
-        require!(
-            current_time - updated_at <= STALE_PRICE_THRESHOLD,
-            PriceFeedError::StalePriceData
-        );
+        let price = i64::try_from(price_data.answer)
+            .map_err(|_| error!(PriceFeedError::PriceOutOfRange))?;
+        validate_price_update(price_feed, price, updated_at, current_time)?;
 
-        // Update the price feed account with the latest data
-        price_feed.price = price_data.answer;
-        price_feed.decimals = price_data.decimals;
-        price_feed.last_updated = updated_at;
+        // Update the price feed account with the latest data (Chainlink reports no confidence)
+        price_feed.record_price(price, price_data.decimals, 0, updated_at);
 
-        emit!(PriceFeedUpdated {
-            feed_id: price_feed.feed_id,
+        emit!(PriceUpdated {
+            feed: price_feed.key(),
             price: price_feed.price,
-            updated_at: price_feed.last_updated,
+            confidence: price_feed.confidence,
+            round: price_feed.round,
+            timestamp: price_feed.last_updated,
         });
 
         Ok(())
     }
 
-    /// Read the current price from the price feed (view function, no state change)
-    pub fn get_price(ctx: Context<GetPrice>) -> Result<i128> {
+    /// Read the current price from the price feed. The result is written to return
+    /// data so other programs can consume it via `ontora_oracle::cpi::get_price`
+    /// without depending on the account layout. When the feed sets an access fee,
+    /// `payer` is charged into the fee vault unless the calling program is exempt.
+    pub fn get_price(ctx: Context<GetPrice>) -> Result<PriceView> {
         let price_feed = &ctx.accounts.price_feed;
         require!(
             price_feed.is_initialized,
             PriceFeedError::NotInitialized
         );
+        price_feed.assert_fresh(Clock::get()?.unix_timestamp)?;
+
+        charge_access_fee(
+            price_feed,
+            &mut ctx.accounts.fee_vault,
+            &ctx.accounts.payer,
+            &ctx.accounts.instructions_sysvar,
+            &ctx.accounts.system_program,
+        )?;
+
+        Ok(PriceView::from_feed(&ctx.accounts.price_feed))
+    }
+
+    /// Value `token_amount` (raw units of `token_mint`) in USD with `USD_DECIMALS`
+    /// decimals, returned via return data. Fails on a stale feed or one whose
+    /// confidence interval is too wide to price against. Charges the feed's access
+    /// fee exactly like `get_price`.
+    pub fn convert_to_usd(ctx: Context<ConvertToUsd>, token_amount: u64) -> Result<u64> {
+        let price_feed = &ctx.accounts.price_feed;
         require!(
-            Clock::get()?.unix_timestamp - price_feed.last_updated <= STALE_PRICE_THRESHOLD,
-            PriceFeedError::StalePriceData
+            price_feed.is_initialized,
+            PriceFeedError::NotInitialized
         );
+        price_feed.assert_fresh(Clock::get()?.unix_timestamp)?;
+        price_feed.assert_confident()?;
+
+        charge_access_fee(
+            price_feed,
+            &mut ctx.accounts.fee_vault,
+            &ctx.accounts.payer,
+            &ctx.accounts.instructions_sysvar,
+            &ctx.accounts.system_program,
+        )?;
 
-        Ok(price_feed.price)
+        token_amount_to_usd(
+            token_amount,
+            ctx.accounts.token_mint.decimals,
+            price_feed.price,
+            price_feed.decimals,
+        )
     }
-}
 
-#[derive(Accounts)]
-pub struct InitializePriceFeed<'info> {
-    #[account(
-        init,
-        payer = authority,
-        space = 8 + std::mem::size_of::<PriceFeedData>(),
-        seeds = [b"price_feed", authority.key().as_ref()],
-        bump
-    )]
-    pub price_feed: Account<'info, PriceFeedData>,
+    /// Submit an observation signed by one of the feed's operators. Anyone may relay
+    /// the payload; the transaction must include an Ed25519 verify instruction over
+    /// `signed_price_message(feed, price, decimals, timestamp)`. The observation
+    /// counts toward the round quorum exactly like a direct operator submission.
+    pub fn submit_signed_price(
+        ctx: Context<SubmitSignedPrice>,
+        price: i64,
+        decimals: u8,
+        timestamp: i64,
+        signature_meta: SignatureMeta,
+    ) -> Result<()> {
+        let instructions_sysvar = &ctx.accounts.instructions_sysvar;
 
-    #[account(mut)]
-    pub authority: Signer<'info>,
+        // The verify instruction must precede this one in the same transaction
+        let current_index = load_current_index_checked(instructions_sysvar)?;
+        require!(
+            signature_meta.ed25519_ix_index < current_index,
+            PriceFeedError::InvalidSignature
+        );
+        let ed25519_ix = load_instruction_at_checked(
+            signature_meta.ed25519_ix_index as usize,
+            instructions_sysvar,
+        )?;
+        require_keys_eq!(
+            ed25519_ix.program_id,
+            ed25519_program::ID,
+            PriceFeedError::InvalidSignature
+        );
 
-    pub system_program: Program<'info, System>,
-}
+        let message = signed_price_message(&ctx.accounts.price_feed.key(), price, decimals, timestamp);
+        verify_ed25519_instruction_data(&ed25519_ix.data, &signature_meta.operator, &message)?;
 
-#[derive(Accounts)]
-pub struct UpdatePriceFeed<'info> {
-    #[account(mut, has_one = feed_id)]
-    pub price_feed: Account<'info, PriceFeedData>,
+        let current_time = Clock::get()?.unix_timestamp;
+        let pending_round = &mut ctx.accounts.pending_round;
+        if pending_round.feed == Pubkey::default() {
+            pending_round.feed = ctx.accounts.price_feed.key();
+            pending_round.bump = ctx.bumps.pending_round;
+        }
 
-    #[account(mut)]
-    pub authority: Signer<'info>,
+        ctx.accounts.operator_bond.assert_active(&ctx.accounts.operator_set)?;
+        accept_operator_submission(
+            &mut ctx.accounts.price_feed,
+            pending_round,
+            &ctx.accounts.operator_set,
+            signature_meta.operator,
+            price,
+            decimals,
+            timestamp,
+            current_time,
+        )
+    }
 
-    /// Chainlink feed account (price data source)
-    pub chainlink_feed: UncheckedAccount<'info>,
+    /// Create an operator set with up to `MAX_OPERATORS` keys and an m-of-n threshold
+    pub fn initialize_operator_set(
+        ctx: Context<InitializeOperatorSet>,
+        operators: Vec<Pubkey>,
+        threshold: u8,
+        tolerance_bps: u16,
+        submission_window_secs: i64,
+    ) -> Result<()> {
+        validate_operator_config(&operators, threshold)?;
+        require!(
+            tolerance_bps <= BPS_DENOMINATOR,
+            PriceFeedError::InvalidOperatorConfig
+        );
+        require!(
+            submission_window_secs > 0 && submission_window_secs <= STALE_PRICE_THRESHOLD,
+            PriceFeedError::InvalidOperatorConfig
+        );
 
-    /// Chainlink program (for CPI to fetch price data)
-    pub chainlink_program: Program<'info, chainlink::program::Chainlink>,
+        let operator_set = &mut ctx.accounts.operator_set;
+        operator_set.authority = ctx.accounts.authority.key();
+        operator_set.operators = operators;
+        operator_set.threshold = threshold;
+        operator_set.tolerance_bps = tolerance_bps;
+        operator_set.submission_window_secs = submission_window_secs;
+        operator_set.bump = ctx.bumps.operator_set;
 
-    pub system_program: Program<'info, System>,
+        emit!(OperatorSetUpdated {
+            operator_set: operator_set.key(),
+            operators: operator_set.operators.clone(),
+            threshold,
+        });
+
+        Ok(())
+    }
+
+    /// Configure operator bonding: bond mint and minimum, slashing parameters,
+    /// the guardian allowed to slash, and the treasury receiving slashed bonds
+    #[allow(clippy::too_many_arguments)]
+    pub fn configure_bonding(
+        ctx: Context<ConfigureBonding>,
+        min_bond: u64,
+        slash_bps: u16,
+        slash_deviation_bps: u16,
+        unbonding_secs: i64,
+        guardian: Pubkey,
+    ) -> Result<()> {
+        require!(
+            min_bond > 0 && slash_bps > 0 && slash_bps <= BPS_DENOMINATOR,
+            PriceFeedError::InvalidBondConfig
+        );
+        require!(
+            slash_deviation_bps > 0 && unbonding_secs >= 0,
+            PriceFeedError::InvalidBondConfig
+        );
+
+        let operator_set = &mut ctx.accounts.operator_set;
+        operator_set.bond_mint = ctx.accounts.bond_mint.key();
+        operator_set.bond_vault = ctx.accounts.bond_vault.key();
+        operator_set.treasury = ctx.accounts.treasury.key();
+        operator_set.min_bond = min_bond;
+        operator_set.slash_bps = slash_bps;
+        operator_set.slash_deviation_bps = slash_deviation_bps;
+        operator_set.unbonding_secs = unbonding_secs;
+        operator_set.guardian = guardian;
+        Ok(())
+    }
+
+    /// Escrow an operator bond in the bond vault. Only keys listed in the operator
+    /// set may bond, and submissions are refused until the bond meets `min_bond`.
+    pub fn register_operator_bond(ctx: Context<RegisterOperatorBond>, amount: u64) -> Result<()> {
+        let operator_set = &ctx.accounts.operator_set;
+        let operator = ctx.accounts.operator.key();
+        require!(
+            operator_set.is_operator(&operator),
+            PriceFeedError::NotAnOperator
+        );
+
+        let operator_bond = &mut ctx.accounts.operator_bond;
+        require!(
+            operator_bond.deregistered_at == 0,
+            PriceFeedError::OperatorDeregistered
+        );
+        let new_amount = operator_bond
+            .amount
+            .checked_add(amount)
+            .ok_or(PriceFeedError::MathOverflow)?;
+        require!(
+            new_amount >= operator_set.min_bond,
+            PriceFeedError::InsufficientBond
+        );
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.operator_token_account.to_account_info(),
+            to: ctx.accounts.bond_vault.to_account_info(),
+            authority: ctx.accounts.operator.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+        token::transfer(cpi_ctx, amount)?;
+
+        if operator_bond.operator == Pubkey::default() {
+            operator_bond.operator = operator;
+            operator_bond.operator_set = operator_set.key();
+            operator_bond.registered_at = Clock::get()?.unix_timestamp;
+            operator_bond.bump = ctx.bumps.operator_bond;
+        }
+        operator_bond.amount = new_amount;
+
+        emit!(OperatorBonded {
+            operator,
+            amount,
+            total_bond: new_amount,
+        });
+
+        Ok(())
+    }
+
+    /// Slash an operator whose observation in a finalized round deviated from the
+    /// accepted median by more than `slash_deviation_bps`. Rounds with deviant
+    /// observations are retained as evidence for `unbonding_secs`.
+    pub fn slash_operator(ctx: Context<SlashOperator>, operator: Pubkey, evidence_round: u64) -> Result<()> {
+        let operator_set = &ctx.accounts.operator_set;
+        let pending_round = &mut ctx.accounts.pending_round;
+        let operator_bond = &mut ctx.accounts.operator_bond;
+
+        let (submitted_price, accepted_price) =
+            pending_round.slash_submission(evidence_round, operator, operator_set.slash_deviation_bps)?;
+
+        let slashed = slash_amount(operator_bond.amount, operator_set.slash_bps)?;
+        operator_bond.amount = operator_bond
+            .amount
+            .checked_sub(slashed)
+            .ok_or(PriceFeedError::MathOverflow)?;
+
+        let operator_set_key = operator_set.key();
+        let seeds = &[BOND_VAULT_SEED, operator_set_key.as_ref(), &[ctx.bumps.bond_vault_authority]];
+        let signer = &[&seeds[..]];
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.bond_vault.to_account_info(),
+            to: ctx.accounts.treasury.to_account_info(),
+            authority: ctx.accounts.bond_vault_authority.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi_accounts, signer);
+        token::transfer(cpi_ctx, slashed)?;
+
+        emit!(OperatorSlashed {
+            operator,
+            round: evidence_round,
+            submitted_price,
+            accepted_price,
+            slashed_amount: slashed,
+            remaining_bond: operator_bond.amount,
+        });
+
+        Ok(())
+    }
+
+    /// Leave the operator set and start the unbonding cooldown
+    pub fn deregister_operator(ctx: Context<DeregisterOperator>) -> Result<()> {
+        let operator_set = &mut ctx.accounts.operator_set;
+        let operator = ctx.accounts.operator.key();
+
+        operator_set.operators.retain(|key| *key != operator);
+        require!(
+            operator_set.threshold as usize <= operator_set.operators.len(),
+            PriceFeedError::InvalidOperatorConfig
+        );
+
+        let current_time = Clock::get()?.unix_timestamp;
+        ctx.accounts.operator_bond.deregistered_at = current_time;
+
+        emit!(OperatorDeregistered {
+            operator,
+            withdrawable_at: current_time.saturating_add(operator_set.unbonding_secs),
+        });
+
+        Ok(())
+    }
+
+    /// Return the remaining bond once deregistered and the cooldown has elapsed
+    pub fn withdraw_bond(ctx: Context<WithdrawBond>) -> Result<()> {
+        let operator_set = &ctx.accounts.operator_set;
+        let operator_bond = &ctx.accounts.operator_bond;
+        let current_time = Clock::get()?.unix_timestamp;
+
+        require!(
+            bond_withdrawable(operator_bond.deregistered_at, operator_set.unbonding_secs, current_time),
+            PriceFeedError::BondLocked
+        );
+
+        let operator_set_key = operator_set.key();
+        let seeds = &[BOND_VAULT_SEED, operator_set_key.as_ref(), &[ctx.bumps.bond_vault_authority]];
+        let signer = &[&seeds[..]];
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.bond_vault.to_account_info(),
+            to: ctx.accounts.operator_token_account.to_account_info(),
+            authority: ctx.accounts.bond_vault_authority.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi_accounts, signer);
+        token::transfer(cpi_ctx, operator_bond.amount)?;
+
+        emit!(BondWithdrawn {
+            operator: operator_bond.operator,
+            amount: operator_bond.amount,
+        });
+
+        Ok(())
+    }
+
+    /// Replace the operator list and threshold (operator set authority only)
+    pub fn set_operators(ctx: Context<SetOperators>, operators: Vec<Pubkey>, threshold: u8) -> Result<()> {
+        validate_operator_config(&operators, threshold)?;
+
+        let operator_set = &mut ctx.accounts.operator_set;
+        operator_set.operators = operators;
+        operator_set.threshold = threshold;
+
+        emit!(OperatorSetUpdated {
+            operator_set: operator_set.key(),
+            operators: operator_set.operators.clone(),
+            threshold,
+        });
+
+        Ok(())
+    }
+
+    /// Set the maximum interval between updates before the feed counts as stale
+    pub fn set_heartbeat(ctx: Context<ConfigureFeed>, heartbeat_secs: i64) -> Result<()> {
+        require!(
+            heartbeat_secs > 0 && heartbeat_secs <= STALE_PRICE_THRESHOLD,
+            PriceFeedError::InvalidHeartbeat
+        );
+        ctx.accounts.price_feed.heartbeat_secs = heartbeat_secs;
+        Ok(())
+    }
+
+    /// Set the EMA smoothing factor; alpha must be within (0, 10000] bps
+    pub fn set_ema_alpha(ctx: Context<ConfigureFeed>, ema_alpha_bps: u16) -> Result<()> {
+        require!(
+            ema_alpha_bps > 0 && ema_alpha_bps <= BPS_DENOMINATOR,
+            PriceFeedError::InvalidEmaAlpha
+        );
+        ctx.accounts.price_feed.ema_alpha_bps = ema_alpha_bps;
+        Ok(())
+    }
+
+    /// Set the lamports charged per `get_price` read (0 keeps the feed free)
+    pub fn set_access_fee(ctx: Context<ConfigureFeed>, access_fee_lamports: u64) -> Result<()> {
+        let price_feed = &mut ctx.accounts.price_feed;
+        price_feed.access_fee_lamports = access_fee_lamports;
+
+        emit!(AccessFeeUpdated {
+            feed: price_feed.key(),
+            access_fee_lamports,
+        });
+
+        Ok(())
+    }
+
+    /// Create the fee vault that collects consumer access fees (registry authority only)
+    pub fn initialize_fee_vault(ctx: Context<InitializeFeeVault>, treasury: Pubkey) -> Result<()> {
+        let fee_vault = &mut ctx.accounts.fee_vault;
+        fee_vault.treasury = treasury;
+        fee_vault.exempt_programs = Vec::new();
+        fee_vault.total_collected = 0;
+        fee_vault.bump = ctx.bumps.fee_vault;
+        Ok(())
+    }
+
+    /// Add or remove a consumer program from the access fee allowlist
+    pub fn set_fee_exemption(ctx: Context<ManageFeeVault>, program_id: Pubkey, exempt: bool) -> Result<()> {
+        let fee_vault = &mut ctx.accounts.fee_vault;
+        if exempt {
+            if !fee_vault.is_exempt(&program_id) {
+                require!(
+                    fee_vault.exempt_programs.len() < MAX_FEE_EXEMPT_PROGRAMS,
+                    PriceFeedError::TooManyExemptPrograms
+                );
+                fee_vault.exempt_programs.push(program_id);
+            }
+        } else {
+            fee_vault.exempt_programs.retain(|key| *key != program_id);
+        }
+
+        emit!(FeeExemptionUpdated { program_id, exempt });
+
+        Ok(())
+    }
+
+    /// Move collected access fees above the vault's rent reserve to the treasury
+    pub fn sweep_access_fees(ctx: Context<SweepAccessFees>) -> Result<()> {
+        let fee_vault_info = ctx.accounts.fee_vault.to_account_info();
+        let rent_reserve = Rent::get()?.minimum_balance(FeeVault::SPACE);
+        let amount = sweepable_lamports(fee_vault_info.lamports(), rent_reserve);
+        require!(amount > 0, PriceFeedError::NothingToSweep);
+
+        **fee_vault_info.try_borrow_mut_lamports()? -= amount;
+        **ctx.accounts.treasury.to_account_info().try_borrow_mut_lamports()? += amount;
+
+        emit!(AccessFeesSwept {
+            treasury: ctx.accounts.treasury.key(),
+            amount,
+        });
+
+        Ok(())
+    }
+
+    /// Permissionless: mark a feed stale once its heartbeat has been missed,
+    /// giving monitoring bots an on-chain signal
+    pub fn flag_stale(ctx: Context<FlagStale>) -> Result<()> {
+        let price_feed = &mut ctx.accounts.price_feed;
+        let current_time = Clock::get()?.unix_timestamp;
+
+        require!(
+            price_feed.is_initialized,
+            PriceFeedError::NotInitialized
+        );
+        require!(!price_feed.stale, PriceFeedError::AlreadyFlaggedStale);
+        require!(
+            price_feed.heartbeat_missed(current_time),
+            PriceFeedError::HeartbeatNotMissed
+        );
+
+        price_feed.stale = true;
+
+        emit!(FeedStaleDetected {
+            feed: price_feed.key(),
+            last_updated: price_feed.last_updated,
+            heartbeat_secs: price_feed.heartbeat_secs,
+            detected_at: current_time,
+            reporter: ctx.accounts.reporter.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Grow a feed created under an older layout to `PriceFeedData::SPACE`, zero-filling
+    /// appended fields, and stamp it with the current version. No-op when already current.
+    pub fn migrate_feed(ctx: Context<MigrateFeed>) -> Result<()> {
+        let feed_info = ctx.accounts.price_feed.to_account_info();
+
+        // Grow first: a v1 feed may not even have room for the version byte
+        if feed_info.data_len() < PriceFeedData::SPACE {
+            let rent_needed = Rent::get()?
+                .minimum_balance(PriceFeedData::SPACE)
+                .saturating_sub(feed_info.lamports());
+            if rent_needed > 0 {
+                let cpi_accounts = system_program::Transfer {
+                    from: ctx.accounts.authority.to_account_info(),
+                    to: feed_info.clone(),
+                };
+                let cpi_ctx = CpiContext::new(ctx.accounts.system_program.to_account_info(), cpi_accounts);
+                system_program::transfer(cpi_ctx, rent_needed)?;
+            }
+            feed_info.realloc(PriceFeedData::SPACE, true)?;
+        }
+
+        let mut price_feed = {
+            let data = feed_info.try_borrow_data()?;
+            PriceFeedData::try_deserialize(&mut &data[..])?
+        };
+        require_keys_eq!(
+            price_feed.operator_set,
+            ctx.accounts.operator_set.key(),
+            PriceFeedError::Unauthorized
+        );
+
+        let Some(from_version) = price_feed.migrate() else {
+            return Ok(());
+        };
+        {
+            let mut data = feed_info.try_borrow_mut_data()?;
+            price_feed.try_serialize(&mut &mut data[..])?;
+        }
+
+        emit!(FeedMigrated {
+            feed: feed_info.key(),
+            from_version,
+            to_version: PRICE_FEED_VERSION,
+        });
+
+        Ok(())
+    }
+
+    /// Initialize the oracle config that holds the authority managing the feed registry
+    pub fn initialize_oracle_config(ctx: Context<InitializeOracleConfig>) -> Result<()> {
+        let oracle_config = &mut ctx.accounts.oracle_config;
+        oracle_config.authority = ctx.accounts.authority.key();
+        oracle_config.bump = ctx.bumps.oracle_config;
+        Ok(())
+    }
+
+    /// Register a symbol -> feed mapping (e.g., "SOL/USD" -> price feed account).
+    /// The entry PDA is seeded by the normalized symbol hash, so registering the
+    /// same symbol twice fails at account creation.
+    pub fn register_feed(ctx: Context<RegisterFeed>, symbol: String, feed: Pubkey) -> Result<()> {
+        let normalized = normalize_symbol(&symbol)?;
+        let feed_entry = &mut ctx.accounts.feed_entry;
+
+        feed_entry.symbol_hash = symbol_hash(&normalized);
+        feed_entry.symbol = normalized;
+        feed_entry.feed = feed;
+        feed_entry.bump = ctx.bumps.feed_entry;
+
+        emit!(FeedRegistered {
+            symbol: feed_entry.symbol.clone(),
+            feed,
+        });
+
+        Ok(())
+    }
+
+    /// Point an existing symbol at a different feed account
+    pub fn update_feed_entry(ctx: Context<UpdateFeedEntry>, _symbol: String, feed: Pubkey) -> Result<()> {
+        let feed_entry = &mut ctx.accounts.feed_entry;
+        let old_feed = feed_entry.feed;
+        feed_entry.feed = feed;
+
+        emit!(FeedEntryUpdated {
+            symbol: feed_entry.symbol.clone(),
+            old_feed,
+            new_feed: feed,
+        });
+
+        Ok(())
+    }
+
+    /// Remove a symbol from the registry, returning the rent to the authority
+    pub fn remove_feed_entry(ctx: Context<RemoveFeedEntry>, _symbol: String) -> Result<()> {
+        emit!(FeedEntryRemoved {
+            symbol: ctx.accounts.feed_entry.symbol.clone(),
+            feed: ctx.accounts.feed_entry.feed,
+        });
+
+        Ok(())
+    }
+
+    /// Resolve a symbol to its feed account (view function, no state change)
+    pub fn resolve_feed(ctx: Context<ResolveFeed>, symbol: String) -> Result<Pubkey> {
+        resolve_feed_entry(&ctx.accounts.feed_entry, &symbol)
+    }
 }
 
-#[derive(Accounts)]
-pub struct GetPrice<'info> {
-    #[account(has_one = feed_id)]
-    pub price_feed: Account<'info, PriceFeedData>,
+/// Checks shared by every price update path: the observation must be fresh,
+/// strictly newer than the stored one (replay protection), and within the
+/// allowed deviation from the last accepted price.
+pub fn validate_price_update(
+    price_feed: &PriceFeedData,
+    price: i64,
+    timestamp: i64,
+    current_time: i64,
+) -> Result<()> {
+    // Zero or negative prices would invert or divide-by-zero downstream conversions
+    require!(price > 0, PriceFeedError::InvalidPrice);
+    require!(timestamp <= current_time, PriceFeedError::InvalidTimestamp);
+    require!(
+        current_time - timestamp <= STALE_PRICE_THRESHOLD,
+        PriceFeedError::StalePriceData
+    );
+    require!(
+        timestamp > price_feed.last_updated,
+        PriceFeedError::StaleTimestamp
+    );
+
+    // The first observation has nothing to deviate from
+    if price_feed.price > 0 {
+        let previous = price_feed.price as i128;
+        let deviation_bps = ((price as i128) - previous)
+            .checked_abs()
+            .and_then(|delta| delta.checked_mul(BPS_DENOMINATOR as i128))
+            .and_then(|scaled| scaled.checked_div(previous))
+            .ok_or(PriceFeedError::MathOverflow)?;
+        require!(
+            deviation_bps <= MAX_PRICE_DEVIATION_BPS,
+            PriceFeedError::PriceDeviationTooLarge
+        );
+    }
+
+    Ok(())
 }
 
-#[account]
-#[derive(Default)]
-pub struct PriceFeedData {
-    /// Chainlink feed ID (public key of the Chainlink price feed)
-    pub feed_id: Pubkey,
+/// Record one operator observation in the feed's pending round and publish the
+/// median once a quorum of matching observations exists.
+#[allow(clippy::too_many_arguments)]
+pub fn accept_operator_submission(
+    price_feed: &mut Account<PriceFeedData>,
+    pending_round: &mut PendingRound,
+    operator_set: &OperatorSet,
+    operator: Pubkey,
+    price: i64,
+    decimals: u8,
+    timestamp: i64,
+    current_time: i64,
+) -> Result<()> {
+    require!(
+        price_feed.is_initialized,
+        PriceFeedError::NotInitialized
+    );
+    require!(
+        operator_set.is_operator(&operator),
+        PriceFeedError::NotAnOperator
+    );
+    validate_price_update(price_feed, price, timestamp, current_time)?;
 
-    /// Description of the price feed (e.g., "SOL/USD")
-    pub description: String,
+    let next_round = price_feed.round.saturating_add(1);
+    if let Some(expired_count) = pending_round.open(next_round, decimals, current_time, operator_set.submission_window_secs) {
+        emit!(RoundExpired {
+            feed: price_feed.key(),
+            round: next_round,
+            submissions: expired_count,
+        });
+    }
+    require!(
+        pending_round.decimals == decimals,
+        PriceFeedError::DecimalsMismatch
+    );
+    pending_round.add_submission(operator, price, timestamp)?;
 
-    /// Latest price value from the oracle (raw, unscaled)
-    pub price: i128,
+    emit!(ObservationSubmitted {
+        feed: price_feed.key(),
+        operator,
+        round: next_round,
+        price,
+        timestamp,
+    });
 
-    /// Number of decimals for the price value
-    pub decimals: u8,
+    if let Some(quorum) = pending_round.quorum_price(operator_set.threshold, operator_set.tolerance_bps) {
+        price_feed.record_price(quorum.price, decimals, quorum.confidence, quorum.timestamp);
+        pending_round.finalize(next_round, quorum.price, current_time, operator_set);
 
-    /// Timestamp when the price was last updated
-    pub last_updated: i64,
+        emit!(PriceUpdated {
+            feed: price_feed.key(),
+            price: price_feed.price,
+            confidence: price_feed.confidence,
+            round: price_feed.round,
+            timestamp: price_feed.last_updated,
+        });
+    }
 
-    /// Flag to indicate if the price feed is initialized
-    pub is_initialized: bool,
+    Ok(())
 }
 
-#[event]
-pub struct PriceFeedInitialized {
-    pub feed_id: Pubkey,
-    pub description: String,
+/// Operator lists must be non-empty, bounded, duplicate-free, and satisfiable by the threshold
+pub fn validate_operator_config(operators: &[Pubkey], threshold: u8) -> Result<()> {
+    require!(
+        !operators.is_empty() && operators.len() <= MAX_OPERATORS,
+        PriceFeedError::InvalidOperatorConfig
+    );
+    require!(
+        threshold > 0 && threshold as usize <= operators.len(),
+        PriceFeedError::InvalidOperatorConfig
+    );
+    for (i, operator) in operators.iter().enumerate() {
+        require!(
+            !operators[i + 1..].contains(operator),
+            PriceFeedError::InvalidOperatorConfig
+        );
+    }
+    Ok(())
 }
 
-#[event]
-pub struct PriceFeedUpdated {
-    pub feed_id: Pubkey,
-    pub price: i128,
-    pub updated_at: i64,
+/// Whether an observation deviated from the accepted price by more than `bound_bps`
+pub fn is_deviant(submitted_price: i64, accepted_price: i64, bound_bps: u16) -> bool {
+    if accepted_price <= 0 {
+        return false;
+    }
+    let delta = ((submitted_price as i128) - (accepted_price as i128)).abs();
+    delta * (BPS_DENOMINATOR as i128) > (accepted_price as i128) * (bound_bps as i128)
 }
 
-#[error_code]
-pub enum PriceFeedError {
-    #[msg("Price feed is not initialized.")]
-    NotInitialized,
+/// Portion of a bond removed by one slash, rounded down
+pub fn slash_amount(bond: u64, slash_bps: u16) -> Result<u64> {
+    let slashed = (bond as u128)
+        .checked_mul(slash_bps as u128)
+        .ok_or(PriceFeedError::MathOverflow)?
+        / (BPS_DENOMINATOR as u128);
+    Ok(slashed as u64)
+}
 
-    #[msg("Invalid Chainlink feed ID.")]
-    InvalidFeedId,
+/// Bonds unlock `unbonding_secs` after deregistration
+pub fn bond_withdrawable(deregistered_at: i64, unbonding_secs: i64, current_time: i64) -> bool {
+    deregistered_at > 0 && current_time >= deregistered_at.saturating_add(unbonding_secs)
+}
 
-    #[msg("Price data is stale.")]
-    StalePriceData,
+/// Median of a non-empty price list; even-length lists average the middle pair
+pub fn median_price(prices: &[i64]) -> i64 {
+    let mut sorted = prices.to_vec();
+    sorted.sort_unstable();
+    let mid = sorted.len() / 2;
+    if sorted.len() % 2 == 0 {
+        (((sorted[mid - 1] as i128) + (sorted[mid] as i128)) / 2) as i64
+    } else {
+        sorted[mid]
+    }
+}
+
+/// Program that issued the transaction instruction currently executing. For a
+/// `get_price` CPI this is the consumer program; for a direct call it is the oracle.
+pub fn calling_program(instructions_sysvar: &AccountInfo) -> Result<Pubkey> {
+    let current_index = load_current_index_checked(instructions_sysvar)?;
+    let instruction = load_instruction_at_checked(current_index as usize, instructions_sysvar)?;
+    Ok(instruction.program_id)
+}
+
+/// Access fee owed by `consumer` for one read; allowlisted programs pay nothing
+pub fn access_fee_due(access_fee_lamports: u64, fee_vault: &FeeVault, consumer: &Pubkey) -> u64 {
+    if fee_vault.is_exempt(consumer) {
+        0
+    } else {
+        access_fee_lamports
+    }
+}
+
+/// Charge `payer` the feed's access fee for one read, unless the calling program is exempt
+pub fn charge_access_fee<'info>(
+    price_feed: &Account<'info, PriceFeedData>,
+    fee_vault: &mut Account<'info, FeeVault>,
+    payer: &Signer<'info>,
+    instructions_sysvar: &AccountInfo<'info>,
+    system_program: &Program<'info, System>,
+) -> Result<()> {
+    if price_feed.access_fee_lamports == 0 {
+        return Ok(());
+    }
+    let consumer = calling_program(instructions_sysvar)?;
+    let fee = access_fee_due(price_feed.access_fee_lamports, fee_vault, &consumer);
+    if fee == 0 {
+        return Ok(());
+    }
+
+    let cpi_accounts = system_program::Transfer {
+        from: payer.to_account_info(),
+        to: fee_vault.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new(system_program.to_account_info(), cpi_accounts);
+    system_program::transfer(cpi_ctx, fee)?;
+    fee_vault.total_collected = fee_vault.total_collected.saturating_add(fee);
+
+    emit!(AccessFeeCharged {
+        feed: price_feed.key(),
+        consumer,
+        payer: payer.key(),
+        fee,
+    });
+    Ok(())
+}
+
+/// Lamports the fee vault can release while staying rent exempt
+pub fn sweepable_lamports(balance: u64, rent_reserve: u64) -> u64 {
+    balance.saturating_sub(rent_reserve)
+}
+
+/// Multiply a raw token amount by a raw price without intermediate truncation.
+/// Every price x amount product in this program goes through here so a bad
+/// price or an oversized amount surfaces as an error rather than a silent wrap.
+pub fn checked_price_mul(amount: u64, price: i64) -> Result<u128> {
+    require!(price > 0, PriceFeedError::InvalidPrice);
+    (amount as u128)
+        .checked_mul(price as u128)
+        .ok_or_else(|| error!(PriceFeedError::MathOverflow))
+}
+
+/// USD value, with `USD_DECIMALS` decimals, of a raw token amount at a raw price.
+/// Exported so other programs (e.g. staking minimums) convert identically in-process.
+pub fn token_amount_to_usd(
+    token_amount: u64,
+    token_decimals: u8,
+    price: i64,
+    price_decimals: u8,
+) -> Result<u64> {
+    let value = checked_price_mul(token_amount, price)?;
+    let source_decimals = (token_decimals as u32) + (price_decimals as u32);
+    let target_decimals = USD_DECIMALS as u32;
+
+    let scaled = if source_decimals >= target_decimals {
+        let divisor = 10u128
+            .checked_pow(source_decimals - target_decimals)
+            .ok_or(PriceFeedError::MathOverflow)?;
+        value / divisor
+    } else {
+        let multiplier = 10u128
+            .checked_pow(target_decimals - source_decimals)
+            .ok_or(PriceFeedError::MathOverflow)?;
+        value
+            .checked_mul(multiplier)
+            .ok_or(PriceFeedError::MathOverflow)?
+    };
+    u64::try_from(scaled).map_err(|_| error!(PriceFeedError::MathOverflow))
+}
+
+/// One EMA step: ema + alpha * (price - ema), in fixed point with i128 intermediates.
+/// The result always lies between the previous EMA and the new price, so it fits in i64.
+pub fn next_ema(previous_ema: i64, price: i64, alpha_bps: u16) -> i64 {
+    let delta = (price as i128) - (previous_ema as i128);
+    let step = delta * (alpha_bps as i128) / (BPS_DENOMINATOR as i128);
+    ((previous_ema as i128) + step) as i64
+}
+
+/// Canonical signed payload: feed_pubkey || price (i64 LE) || decimals || timestamp (i64 LE)
+pub fn signed_price_message(feed: &Pubkey, price: i64, decimals: u8, timestamp: i64) -> Vec<u8> {
+    let mut message = Vec::with_capacity(32 + 8 + 1 + 8);
+    message.extend_from_slice(feed.as_ref());
+    message.extend_from_slice(&price.to_le_bytes());
+    message.push(decimals);
+    message.extend_from_slice(&timestamp.to_le_bytes());
+    message
+}
+
+/// Check that an Ed25519 precompile instruction verified exactly one signature
+/// by `expected_signer` over `expected_message`, with all data inline.
+/// The precompile itself rejects the transaction if the signature is invalid.
+pub fn verify_ed25519_instruction_data(
+    data: &[u8],
+    expected_signer: &Pubkey,
+    expected_message: &[u8],
+) -> Result<()> {
+    require!(
+        data.len() >= ED25519_HEADER_LEN + ED25519_OFFSETS_LEN,
+        PriceFeedError::InvalidSignature
+    );
+    require!(data[0] == 1, PriceFeedError::InvalidSignature);
+
+    let offsets = &data[ED25519_HEADER_LEN..ED25519_HEADER_LEN + ED25519_OFFSETS_LEN];
+    let read_u16 = |i: usize| u16::from_le_bytes([offsets[i], offsets[i + 1]]);
+    let signature_offset = read_u16(0) as usize;
+    let signature_ix_index = read_u16(2);
+    let pubkey_offset = read_u16(4) as usize;
+    let pubkey_ix_index = read_u16(6);
+    let message_offset = read_u16(8) as usize;
+    let message_size = read_u16(10) as usize;
+    let message_ix_index = read_u16(12);
+
+    // Signature, key, and message must live in the verify instruction itself
+    require!(
+        signature_ix_index == u16::MAX && pubkey_ix_index == u16::MAX && message_ix_index == u16::MAX,
+        PriceFeedError::InvalidSignature
+    );
+    require!(
+        signature_offset + ED25519_SIGNATURE_LEN <= data.len(),
+        PriceFeedError::InvalidSignature
+    );
+
+    let pubkey = data
+        .get(pubkey_offset..pubkey_offset + ED25519_PUBKEY_LEN)
+        .ok_or(PriceFeedError::InvalidSignature)?;
+    require!(
+        pubkey == expected_signer.as_ref(),
+        PriceFeedError::InvalidSignature
+    );
+
+    let message = data
+        .get(message_offset..message_offset + message_size)
+        .ok_or(PriceFeedError::InvalidSignature)?;
+    require!(
+        message == expected_message,
+        PriceFeedError::InvalidSignature
+    );
+
+    Ok(())
+}
+
+/// Normalize a user-supplied symbol so "sol/usd " and "SOL/USD" map to the same entry
+pub fn normalize_symbol(symbol: &str) -> Result<String> {
+    let normalized = symbol.trim().to_ascii_uppercase();
+    require!(!normalized.is_empty(), PriceFeedError::InvalidSymbol);
+    require!(
+        normalized.len() <= MAX_SYMBOL_LEN,
+        PriceFeedError::InvalidSymbol
+    );
+    Ok(normalized)
+}
+
+/// Hash of a normalized symbol, used as the `FeedEntry` PDA seed
+pub fn symbol_hash(normalized_symbol: &str) -> [u8; 32] {
+    hash(normalized_symbol.as_bytes()).to_bytes()
+}
+
+/// Derive the `FeedEntry` PDA for a symbol
+pub fn feed_entry_address(symbol: &str) -> Result<(Pubkey, u8)> {
+    let normalized = normalize_symbol(symbol)?;
+    Ok(Pubkey::find_program_address(
+        &[FEED_ENTRY_SEED, symbol_hash(&normalized).as_ref()],
+        &crate::ID,
+    ))
+}
+
+/// Consumer helper: resolve a symbol to a feed pubkey inside a transaction.
+/// Programs pass the `FeedEntry` account and the symbol they expect; the entry
+/// must be the PDA for that symbol, so a caller cannot substitute another asset's feed.
+pub fn resolve_feed_entry(feed_entry: &Account<FeedEntry>, symbol: &str) -> Result<Pubkey> {
+    let normalized = normalize_symbol(symbol)?;
+    let expected_hash = symbol_hash(&normalized);
+    require!(
+        feed_entry.symbol_hash == expected_hash,
+        PriceFeedError::SymbolMismatch
+    );
+
+    let expected_address = Pubkey::create_program_address(
+        &[FEED_ENTRY_SEED, expected_hash.as_ref(), &[feed_entry.bump]],
+        &crate::ID,
+    )
+    .map_err(|_| error!(PriceFeedError::SymbolMismatch))?;
+    require_keys_eq!(
+        feed_entry.key(),
+        expected_address,
+        PriceFeedError::SymbolMismatch
+    );
+
+    Ok(feed_entry.feed)
+}
+
+#[derive(Accounts)]
+pub struct InitializeOperatorSet<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = OperatorSet::SPACE,
+        seeds = [OPERATOR_SET_SEED, authority.key().as_ref()],
+        bump
+    )]
+    pub operator_set: Account<'info, OperatorSet>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ConfigureBonding<'info> {
+    #[account(
+        mut,
+        seeds = [OPERATOR_SET_SEED, authority.key().as_ref()],
+        bump = operator_set.bump,
+        has_one = authority @ PriceFeedError::Unauthorized
+    )]
+    pub operator_set: Account<'info, OperatorSet>,
+
+    pub bond_mint: Account<'info, Mint>,
+
+    /// CHECK: PDA signing for the bond vault
+    #[account(seeds = [BOND_VAULT_SEED, operator_set.key().as_ref()], bump)]
+    pub bond_vault_authority: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        token::mint = bond_mint,
+        token::authority = bond_vault_authority
+    )]
+    pub bond_vault: Account<'info, TokenAccount>,
+
+    /// Receives slashed bond amounts
+    #[account(token::mint = bond_mint)]
+    pub treasury: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterOperatorBond<'info> {
+    #[account(has_one = bond_vault)]
+    pub operator_set: Account<'info, OperatorSet>,
+
+    #[account(
+        init_if_needed,
+        payer = operator,
+        space = OperatorBond::SPACE,
+        seeds = [OPERATOR_BOND_SEED, operator_set.key().as_ref(), operator.key().as_ref()],
+        bump
+    )]
+    pub operator_bond: Account<'info, OperatorBond>,
+
+    #[account(mut)]
+    pub bond_vault: Account<'info, TokenAccount>,
+
+    #[account(mut, token::mint = operator_set.bond_mint)]
+    pub operator_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub operator: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(operator: Pubkey)]
+pub struct SlashOperator<'info> {
+    #[account(has_one = guardian @ PriceFeedError::Unauthorized, has_one = bond_vault, has_one = treasury)]
+    pub operator_set: Account<'info, OperatorSet>,
+
+    #[account(has_one = operator_set)]
+    pub price_feed: Account<'info, PriceFeedData>,
+
+    #[account(mut, seeds = [PENDING_ROUND_SEED, price_feed.key().as_ref()], bump = pending_round.bump)]
+    pub pending_round: Account<'info, PendingRound>,
+
+    #[account(
+        mut,
+        seeds = [OPERATOR_BOND_SEED, operator_set.key().as_ref(), operator.as_ref()],
+        bump = operator_bond.bump
+    )]
+    pub operator_bond: Account<'info, OperatorBond>,
+
+    /// CHECK: PDA signing for the bond vault
+    #[account(seeds = [BOND_VAULT_SEED, operator_set.key().as_ref()], bump)]
+    pub bond_vault_authority: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub bond_vault: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub treasury: Account<'info, TokenAccount>,
+
+    pub guardian: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct DeregisterOperator<'info> {
+    #[account(mut)]
+    pub operator_set: Account<'info, OperatorSet>,
+
+    #[account(
+        mut,
+        seeds = [OPERATOR_BOND_SEED, operator_set.key().as_ref(), operator.key().as_ref()],
+        bump = operator_bond.bump,
+        constraint = operator_bond.deregistered_at == 0 @ PriceFeedError::OperatorDeregistered
+    )]
+    pub operator_bond: Account<'info, OperatorBond>,
+
+    pub operator: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawBond<'info> {
+    #[account(has_one = bond_vault)]
+    pub operator_set: Account<'info, OperatorSet>,
+
+    #[account(
+        mut,
+        close = operator,
+        seeds = [OPERATOR_BOND_SEED, operator_set.key().as_ref(), operator.key().as_ref()],
+        bump = operator_bond.bump
+    )]
+    pub operator_bond: Account<'info, OperatorBond>,
+
+    /// CHECK: PDA signing for the bond vault
+    #[account(seeds = [BOND_VAULT_SEED, operator_set.key().as_ref()], bump)]
+    pub bond_vault_authority: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub bond_vault: Account<'info, TokenAccount>,
+
+    #[account(mut, token::mint = operator_set.bond_mint)]
+    pub operator_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub operator: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SetOperators<'info> {
+    #[account(
+        mut,
+        seeds = [OPERATOR_SET_SEED, authority.key().as_ref()],
+        bump = operator_set.bump,
+        has_one = authority @ PriceFeedError::Unauthorized
+    )]
+    pub operator_set: Account<'info, OperatorSet>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializePriceFeed<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = PriceFeedData::SPACE,
+        seeds = [b"price_feed", authority.key().as_ref()],
+        bump
+    )]
+    pub price_feed: Account<'info, PriceFeedData>,
+
+    /// Operators whose quorum publishes prices for this feed
+    pub operator_set: Account<'info, OperatorSet>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdatePriceFeed<'info> {
+    #[account(mut, has_one = operator_set)]
+    pub price_feed: Account<'info, PriceFeedData>,
+
+    pub operator_set: Account<'info, OperatorSet>,
+
+    #[account(
+        init_if_needed,
+        payer = operator,
+        space = PendingRound::SPACE,
+        seeds = [PENDING_ROUND_SEED, price_feed.key().as_ref()],
+        bump
+    )]
+    pub pending_round: Account<'info, PendingRound>,
+
+    #[account(
+        seeds = [OPERATOR_BOND_SEED, operator_set.key().as_ref(), operator.key().as_ref()],
+        bump = operator_bond.bump
+    )]
+    pub operator_bond: Account<'info, OperatorBond>,
+
+    #[account(mut)]
+    pub operator: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SyncChainlinkPrice<'info> {
+    #[account(mut, has_one = feed_id)]
+    pub price_feed: Account<'info, PriceFeedData>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// Chainlink feed account (price data source)
+    pub chainlink_feed: UncheckedAccount<'info>,
+
+    /// Chainlink program (for CPI to fetch price data)
+    pub chainlink_program: Program<'info, chainlink::program::Chainlink>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(price: i64, decimals: u8, timestamp: i64, signature_meta: SignatureMeta)]
+pub struct SubmitSignedPrice<'info> {
+    #[account(mut, has_one = operator_set)]
+    pub price_feed: Account<'info, PriceFeedData>,
+
+    pub operator_set: Account<'info, OperatorSet>,
+
+    #[account(
+        init_if_needed,
+        payer = relayer,
+        space = PendingRound::SPACE,
+        seeds = [PENDING_ROUND_SEED, price_feed.key().as_ref()],
+        bump
+    )]
+    pub pending_round: Account<'info, PendingRound>,
+
+    #[account(
+        seeds = [OPERATOR_BOND_SEED, operator_set.key().as_ref(), signature_meta.operator.as_ref()],
+        bump = operator_bond.bump
+    )]
+    pub operator_bond: Account<'info, OperatorBond>,
+
+    /// Anyone may relay a signed price
+    #[account(mut)]
+    pub relayer: Signer<'info>,
+
+    /// CHECK: address-constrained to the instructions sysvar
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ConfigureFeed<'info> {
+    #[account(mut, has_one = operator_set)]
+    pub price_feed: Account<'info, PriceFeedData>,
+
+    #[account(has_one = authority @ PriceFeedError::Unauthorized)]
+    pub operator_set: Account<'info, OperatorSet>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateFeed<'info> {
+    /// CHECK: may predate the current layout, so it is deserialized by `migrate_feed`
+    /// after realloc; ownership is checked here and the discriminator on read
+    #[account(mut, owner = crate::ID)]
+    pub price_feed: UncheckedAccount<'info>,
+
+    #[account(has_one = authority @ PriceFeedError::Unauthorized)]
+    pub operator_set: Account<'info, OperatorSet>,
+
+    /// Pays rent for the larger allocation
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FlagStale<'info> {
+    #[account(mut)]
+    pub price_feed: Account<'info, PriceFeedData>,
+
+    /// Anyone may report a missed heartbeat
+    pub reporter: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct GetPrice<'info> {
+    pub price_feed: Account<'info, PriceFeedData>,
+
+    #[account(mut, seeds = [FEE_VAULT_SEED], bump = fee_vault.bump)]
+    pub fee_vault: Account<'info, FeeVault>,
+
+    /// Pays the feed's access fee, if any
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: address constraint; used to identify the calling program
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ConvertToUsd<'info> {
+    pub price_feed: Account<'info, PriceFeedData>,
+
+    /// Mint of the token being valued; supplies its decimals
+    pub token_mint: Account<'info, Mint>,
+
+    #[account(mut, seeds = [FEE_VAULT_SEED], bump = fee_vault.bump)]
+    pub fee_vault: Account<'info, FeeVault>,
+
+    /// Pays the feed's access fee, if any
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: address constraint; used to identify the calling program
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeOracleConfig<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = OracleConfig::SPACE,
+        seeds = [ORACLE_CONFIG_SEED],
+        bump
+    )]
+    pub oracle_config: Account<'info, OracleConfig>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeFeeVault<'info> {
+    #[account(
+        seeds = [ORACLE_CONFIG_SEED],
+        bump = oracle_config.bump,
+        has_one = authority @ PriceFeedError::Unauthorized
+    )]
+    pub oracle_config: Account<'info, OracleConfig>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = FeeVault::SPACE,
+        seeds = [FEE_VAULT_SEED],
+        bump
+    )]
+    pub fee_vault: Account<'info, FeeVault>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ManageFeeVault<'info> {
+    #[account(
+        seeds = [ORACLE_CONFIG_SEED],
+        bump = oracle_config.bump,
+        has_one = authority @ PriceFeedError::Unauthorized
+    )]
+    pub oracle_config: Account<'info, OracleConfig>,
+
+    #[account(mut, seeds = [FEE_VAULT_SEED], bump = fee_vault.bump)]
+    pub fee_vault: Account<'info, FeeVault>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SweepAccessFees<'info> {
+    #[account(
+        seeds = [ORACLE_CONFIG_SEED],
+        bump = oracle_config.bump,
+        has_one = authority @ PriceFeedError::Unauthorized
+    )]
+    pub oracle_config: Account<'info, OracleConfig>,
+
+    #[account(mut, seeds = [FEE_VAULT_SEED], bump = fee_vault.bump, has_one = treasury)]
+    pub fee_vault: Account<'info, FeeVault>,
+
+    #[account(mut)]
+    pub treasury: SystemAccount<'info>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(symbol: String)]
+pub struct RegisterFeed<'info> {
+    #[account(
+        seeds = [ORACLE_CONFIG_SEED],
+        bump = oracle_config.bump,
+        has_one = authority @ PriceFeedError::Unauthorized
+    )]
+    pub oracle_config: Account<'info, OracleConfig>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = FeedEntry::SPACE,
+        seeds = [FEED_ENTRY_SEED, symbol_hash(&normalize_symbol(&symbol)?).as_ref()],
+        bump
+    )]
+    pub feed_entry: Account<'info, FeedEntry>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(symbol: String)]
+pub struct UpdateFeedEntry<'info> {
+    #[account(
+        seeds = [ORACLE_CONFIG_SEED],
+        bump = oracle_config.bump,
+        has_one = authority @ PriceFeedError::Unauthorized
+    )]
+    pub oracle_config: Account<'info, OracleConfig>,
+
+    #[account(
+        mut,
+        seeds = [FEED_ENTRY_SEED, symbol_hash(&normalize_symbol(&symbol)?).as_ref()],
+        bump = feed_entry.bump
+    )]
+    pub feed_entry: Account<'info, FeedEntry>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(symbol: String)]
+pub struct RemoveFeedEntry<'info> {
+    #[account(
+        seeds = [ORACLE_CONFIG_SEED],
+        bump = oracle_config.bump,
+        has_one = authority @ PriceFeedError::Unauthorized
+    )]
+    pub oracle_config: Account<'info, OracleConfig>,
+
+    #[account(
+        mut,
+        close = authority,
+        seeds = [FEED_ENTRY_SEED, symbol_hash(&normalize_symbol(&symbol)?).as_ref()],
+        bump = feed_entry.bump
+    )]
+    pub feed_entry: Account<'info, FeedEntry>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ResolveFeed<'info> {
+    pub feed_entry: Account<'info, FeedEntry>,
+}
+
+/// Global oracle configuration (registry authority)
+#[account]
+#[derive(Default)]
+pub struct OracleConfig {
+    /// Authority allowed to register, update, and remove feed entries
+    pub authority: Pubkey,
+
+    /// Bump seed for PDA derivation
+    pub bump: u8,
+}
+
+impl OracleConfig {
+    pub const SPACE: usize = 8 + // discriminator
+        32 + // authority
+        1; // bump
+}
+
+/// Collects consumer access fees until swept to the treasury
+#[account]
+#[derive(Default)]
+pub struct FeeVault {
+    /// Destination of `sweep_access_fees`
+    pub treasury: Pubkey,
+
+    /// Consumer programs that read feeds without paying the access fee
+    pub exempt_programs: Vec<Pubkey>,
+
+    /// Lifetime fees collected, in lamports
+    pub total_collected: u64,
+
+    /// Bump seed for PDA derivation
+    pub bump: u8,
+}
+
+impl FeeVault {
+    pub const SPACE: usize = 8 + // discriminator
+        32 + // treasury
+        4 + 32 * MAX_FEE_EXEMPT_PROGRAMS + // exempt_programs
+        8 + // total_collected
+        1; // bump
+
+    pub fn is_exempt(&self, program_id: &Pubkey) -> bool {
+        self.exempt_programs.contains(program_id)
+    }
+}
+
+/// Registry entry mapping a normalized asset symbol to its price feed account
+#[account]
+#[derive(Default)]
+pub struct FeedEntry {
+    /// sha256 of the normalized symbol (PDA seed)
+    pub symbol_hash: [u8; 32],
+
+    /// Normalized symbol (e.g., "SOL/USD")
+    pub symbol: String,
+
+    /// Price feed account for this symbol
+    pub feed: Pubkey,
+
+    /// Bump seed for PDA derivation
+    pub bump: u8,
+}
+
+impl FeedEntry {
+    pub const SPACE: usize = 8 + // discriminator
+        32 + // symbol_hash
+        4 + MAX_SYMBOL_LEN + // symbol
+        32 + // feed
+        1; // bump
+}
+
+#[account]
+#[derive(Default)]
+pub struct PriceFeedData {
+    /// Chainlink feed ID (public key of the Chainlink price feed)
+    pub feed_id: Pubkey,
+
+    /// Description of the price feed (e.g., "SOL/USD")
+    pub description: String,
+
+    /// Latest price value from the oracle (raw, unscaled)
+    pub price: i64,
+
+    /// Number of decimals for the price value
+    pub decimals: u8,
+
+    /// Timestamp when the price was last updated
+    pub last_updated: i64,
+
+    /// Flag to indicate if the price feed is initialized
+    pub is_initialized: bool,
+
+    /// Operator set whose quorum publishes prices for this feed
+    pub operator_set: Pubkey,
+
+    /// Confidence interval reported with the price (0 when the source has none)
+    pub confidence: u64,
+
+    /// Number of accepted updates
+    pub round: u64,
+
+    /// Maximum seconds between updates before the feed is considered stale
+    pub heartbeat_secs: i64,
+
+    /// Set by `flag_stale` when the heartbeat was missed; cleared by the next update
+    pub stale: bool,
+
+    /// Exponential moving average of accepted prices (same scale as `price`)
+    pub ema_price: i64,
+
+    /// Weight of each new observation in the EMA, in basis points
+    pub ema_alpha_bps: u16,
+
+    /// Account layout version, see `PRICE_FEED_VERSION`
+    pub version: u8,
+
+    /// Lamports charged per `get_price` read (0 = free)
+    pub access_fee_lamports: u64,
+}
+
+impl PriceFeedData {
+    // Serialized offsets. Everything after `description` is relative to the end
+    // of the string, since Borsh prefixes it with its length. Fields are
+    // append-only: the v1 tail must never move.
+    pub const OFFSET_FEED_ID: usize = 8;
+    pub const OFFSET_DESCRIPTION: usize = 40;
+    pub const TAIL_OFFSET_PRICE: usize = 0;
+    pub const TAIL_OFFSET_DECIMALS: usize = 8;
+    pub const TAIL_OFFSET_LAST_UPDATED: usize = 9;
+    pub const TAIL_OFFSET_IS_INITIALIZED: usize = 17;
+    pub const TAIL_OFFSET_OPERATOR_SET: usize = 18;
+    pub const TAIL_OFFSET_CONFIDENCE: usize = 50;
+    pub const TAIL_OFFSET_ROUND: usize = 58;
+    pub const TAIL_OFFSET_HEARTBEAT_SECS: usize = 66;
+    pub const TAIL_OFFSET_STALE: usize = 74;
+    pub const TAIL_OFFSET_EMA_PRICE: usize = 75;
+    pub const TAIL_OFFSET_EMA_ALPHA_BPS: usize = 83;
+    pub const V1_TAIL_LEN: usize = 85;
+    pub const TAIL_OFFSET_VERSION: usize = 85;
+    pub const TAIL_OFFSET_ACCESS_FEE_LAMPORTS: usize = 86;
+    pub const TAIL_LEN: usize = 94;
+
+    pub const SPACE: usize = Self::OFFSET_DESCRIPTION + 4 + MAX_DESCRIPTION_LEN + Self::TAIL_LEN;
+
+    /// Stamp the current layout version. Returns the previous version, or `None`
+    /// when the feed is already current. Appended fields rely on realloc zero-fill.
+    pub fn migrate(&mut self) -> Option<u8> {
+        if self.version >= PRICE_FEED_VERSION {
+            return None;
+        }
+        let from_version = self.version.max(1);
+        self.version = PRICE_FEED_VERSION;
+        Some(from_version)
+    }
+
+    /// Store an accepted observation and clear any stale flag
+    pub fn record_price(&mut self, price: i64, decimals: u8, confidence: u64, timestamp: i64) {
+        // The first observation seeds the EMA
+        self.ema_price = if self.round == 0 {
+            price
+        } else {
+            next_ema(self.ema_price, price, self.ema_alpha_bps)
+        };
+        self.price = price;
+        self.decimals = decimals;
+        self.confidence = confidence;
+        self.last_updated = timestamp;
+        self.round = self.round.saturating_add(1);
+        self.stale = false;
+    }
+
+    /// Whether more than `heartbeat_secs` have elapsed since the last update
+    pub fn heartbeat_missed(&self, current_time: i64) -> bool {
+        current_time.saturating_sub(self.last_updated) > self.heartbeat_secs
+    }
+
+    /// Fails with `ConfidenceTooWide` when the confidence interval exceeds `MAX_CONFIDENCE_BPS` of the price
+    pub fn assert_confident(&self) -> Result<()> {
+        require!(self.price > 0, PriceFeedError::InvalidPrice);
+        let max_confidence = (self.price as u128) * (MAX_CONFIDENCE_BPS as u128) / (BPS_DENOMINATOR as u128);
+        require!(
+            (self.confidence as u128) <= max_confidence,
+            PriceFeedError::ConfidenceTooWide
+        );
+        Ok(())
+    }
+
+    /// Fails with `StalePriceData` when the feed is flagged stale or its heartbeat was missed
+    pub fn assert_fresh(&self, current_time: i64) -> Result<()> {
+        let feed_stale = self.stale || self.heartbeat_missed(current_time);
+        require!(!feed_stale, PriceFeedError::StalePriceData);
+        Ok(())
+    }
+}
+
+// New fields may only be appended after the v1 tail
+const _: () = assert!(PriceFeedData::OFFSET_DESCRIPTION == PriceFeedData::OFFSET_FEED_ID + 32);
+const _: () = assert!(PriceFeedData::TAIL_OFFSET_EMA_ALPHA_BPS + 2 == PriceFeedData::V1_TAIL_LEN);
+const _: () = assert!(PriceFeedData::TAIL_OFFSET_VERSION >= PriceFeedData::V1_TAIL_LEN);
+const _: () = assert!(PriceFeedData::TAIL_OFFSET_ACCESS_FEE_LAMPORTS == PriceFeedData::TAIL_OFFSET_VERSION + 1);
+const _: () = assert!(PriceFeedData::TAIL_OFFSET_ACCESS_FEE_LAMPORTS + 8 == PriceFeedData::TAIL_LEN);
+
+/// m-of-n set of operators allowed to submit observations
+#[account]
+#[derive(Default)]
+pub struct OperatorSet {
+    /// Authority allowed to change operators and feed parameters
+    pub authority: Pubkey,
+
+    /// Operator keys (at most `MAX_OPERATORS`)
+    pub operators: Vec<Pubkey>,
+
+    /// Number of matching observations required to publish a price
+    pub threshold: u8,
+
+    /// Maximum distance from the round median, in bps, for an observation to count
+    pub tolerance_bps: u16,
+
+    /// Seconds after the first observation before a half-formed round expires
+    pub submission_window_secs: i64,
+
+    /// Bump seed for PDA derivation
+    pub bump: u8,
+
+    /// Mint operators post their bond in
+    pub bond_mint: Pubkey,
+
+    /// Token account escrowing all operator bonds
+    pub bond_vault: Pubkey,
+
+    /// Token account receiving slashed bonds
+    pub treasury: Pubkey,
+
+    /// Governance/guardian key allowed to slash
+    pub guardian: Pubkey,
+
+    /// Minimum bond required before an operator's submissions count
+    pub min_bond: u64,
+
+    /// Portion of the bond removed per slash, in bps
+    pub slash_bps: u16,
+
+    /// Deviation from the accepted median, in bps, beyond which a submission is slashable
+    pub slash_deviation_bps: u16,
+
+    /// Cooldown between deregistration and bond withdrawal
+    pub unbonding_secs: i64,
+}
+
+impl OperatorSet {
+    pub const SPACE: usize = 8 + // discriminator
+        32 + // authority
+        4 + 32 * MAX_OPERATORS + // operators
+        1 + // threshold
+        2 + // tolerance_bps
+        8 + // submission_window_secs
+        1 + // bump
+        32 + // bond_mint
+        32 + // bond_vault
+        32 + // treasury
+        32 + // guardian
+        8 + // min_bond
+        2 + // slash_bps
+        2 + // slash_deviation_bps
+        8; // unbonding_secs
+
+    pub fn is_operator(&self, key: &Pubkey) -> bool {
+        self.operators.contains(key)
+    }
+}
+
+/// Bond escrowed by one operator
+#[account]
+#[derive(Default)]
+pub struct OperatorBond {
+    pub operator: Pubkey,
+
+    pub operator_set: Pubkey,
+
+    /// Tokens currently escrowed (after slashes)
+    pub amount: u64,
+
+    pub registered_at: i64,
+
+    /// Deregistration time, 0 while active
+    pub deregistered_at: i64,
+
+    /// Bump seed for PDA derivation
+    pub bump: u8,
+}
+
+impl OperatorBond {
+    pub const SPACE: usize = 8 + // discriminator
+        32 + // operator
+        32 + // operator_set
+        8 + // amount
+        8 + // registered_at
+        8 + // deregistered_at
+        1; // bump
+
+    /// Active and bonded at or above the set's minimum
+    pub fn assert_active(&self, operator_set: &OperatorSet) -> Result<()> {
+        require!(
+            self.deregistered_at == 0,
+            PriceFeedError::OperatorDeregistered
+        );
+        require!(
+            self.amount >= operator_set.min_bond,
+            PriceFeedError::InsufficientBond
+        );
+        Ok(())
+    }
+}
+
+/// One operator's observation for a pending round
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct OperatorSubmission {
+    pub operator: Pubkey,
+    pub price: i64,
+    pub timestamp: i64,
+    /// Set once the operator is slashed for it as finalized-round evidence
+    pub slashed: bool,
+}
+
+/// Deviant observations of a finalized round, kept as slashing evidence
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq)]
+pub struct FinalizedRound {
+    pub round: u64,
+    pub accepted_price: i64,
+    pub finalized_at: i64,
+    /// Observations beyond `slash_deviation_bps` of `accepted_price`
+    pub submissions: Vec<OperatorSubmission>,
+}
+
+/// Price published from a quorum of matching observations
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct QuorumPrice {
+    pub price: i64,
+    /// Half the spread of the agreeing observations
+    pub confidence: u64,
+    /// Latest timestamp among the agreeing observations
+    pub timestamp: i64,
+}
+
+/// Observations collected for a feed's next round
+#[account]
+#[derive(Default)]
+pub struct PendingRound {
+    /// Feed this round belongs to
+    pub feed: Pubkey,
+
+    /// Feed round these observations would produce
+    pub round: u64,
+
+    /// Time of the first observation in this round
+    pub opened_at: i64,
+
+    /// Decimals shared by every observation in the round
+    pub decimals: u8,
+
+    /// Observations so far, one per operator
+    pub submissions: Vec<OperatorSubmission>,
+
+    /// Bump seed for PDA derivation
+    pub bump: u8,
+
+    /// Finalized rounds with deviant observations, oldest first, at most
+    /// `MAX_EVIDENCE_ROUNDS` and none older than the set's `unbonding_secs`
+    pub finalized_rounds: Vec<FinalizedRound>,
+}
+
+impl PendingRound {
+    pub const SPACE: usize = 8 + // discriminator
+        32 + // feed
+        8 + // round
+        8 + // opened_at
+        1 + // decimals
+        4 + (32 + 8 + 8 + 1) * MAX_OPERATORS + // submissions
+        1 + // bump
+        4 + (8 + 8 + 8 + 4 + (32 + 8 + 8 + 1) * MAX_OPERATORS) * MAX_EVIDENCE_ROUNDS; // finalized_rounds
+
+    /// Start collecting for `round` unless a live round is already open.
+    /// Returns the number of discarded observations when a stale round is reset.
+    pub fn open(&mut self, round: u64, decimals: u8, current_time: i64, window_secs: i64) -> Option<u32> {
+        let expired = current_time.saturating_sub(self.opened_at) > window_secs;
+        if self.round == round && !self.submissions.is_empty() && !expired {
+            return None;
+        }
+
+        let discarded = self.submissions.len() as u32;
+        self.round = round;
+        self.opened_at = current_time;
+        self.decimals = decimals;
+        self.submissions.clear();
+        (discarded > 0).then_some(discarded)
+    }
+
+    pub fn add_submission(&mut self, operator: Pubkey, price: i64, timestamp: i64) -> Result<()> {
+        require!(
+            !self.submissions.iter().any(|s| s.operator == operator),
+            PriceFeedError::DuplicateSubmission
+        );
+        require!(
+            self.submissions.len() < MAX_OPERATORS,
+            PriceFeedError::InvalidOperatorConfig
+        );
+        self.submissions.push(OperatorSubmission { operator, price, timestamp, slashed: false });
+        Ok(())
+    }
+
+    /// Median of the observations within `tolerance_bps` of the round median,
+    /// if at least `threshold` of them agree
+    pub fn quorum_price(&self, threshold: u8, tolerance_bps: u16) -> Option<QuorumPrice> {
+        if threshold == 0 || self.submissions.len() < threshold as usize {
+            return None;
+        }
+
+        let prices: Vec<i64> = self.submissions.iter().map(|s| s.price).collect();
+        let center = median_price(&prices) as i128;
+        if center <= 0 {
+            return None;
+        }
+
+        let agreeing: Vec<&OperatorSubmission> = self
+            .submissions
+            .iter()
+            .filter(|s| ((s.price as i128) - center).abs() * (BPS_DENOMINATOR as i128) <= center * (tolerance_bps as i128))
+            .collect();
+        if agreeing.len() < threshold as usize {
+            return None;
+        }
+
+        let agreeing_prices: Vec<i64> = agreeing.iter().map(|s| s.price).collect();
+        let min = *agreeing_prices.iter().min()?;
+        let max = *agreeing_prices.iter().max()?;
+        Some(QuorumPrice {
+            price: median_price(&agreeing_prices),
+            confidence: (((max as i128) - (min as i128)) / 2) as u64,
+            timestamp: agreeing.iter().map(|s| s.timestamp).max()?,
+        })
+    }
+
+    pub fn clear(&mut self) {
+        self.submissions.clear();
+        self.opened_at = 0;
+    }
+
+    /// Keep the round's deviant observations as slashing evidence and start fresh.
+    /// Evidence older than `unbonding_secs` is pruned, so a deregistered operator's
+    /// rounds stay slashable until its bond unlocks; past `MAX_EVIDENCE_ROUNDS`
+    /// live rounds the oldest is dropped.
+    pub fn finalize(&mut self, round: u64, accepted_price: i64, current_time: i64, operator_set: &OperatorSet) {
+        let submissions = std::mem::take(&mut self.submissions);
+        self.opened_at = 0;
+
+        self.finalized_rounds
+            .retain(|r| current_time.saturating_sub(r.finalized_at) <= operator_set.unbonding_secs);
+        let deviant: Vec<OperatorSubmission> = submissions
+            .into_iter()
+            .filter(|s| is_deviant(s.price, accepted_price, operator_set.slash_deviation_bps))
+            .collect();
+        if deviant.is_empty() {
+            return;
+        }
+        if self.finalized_rounds.len() == MAX_EVIDENCE_ROUNDS {
+            self.finalized_rounds.remove(0);
+        }
+        self.finalized_rounds.push(FinalizedRound {
+            round,
+            accepted_price,
+            finalized_at: current_time,
+            submissions: deviant,
+        });
+    }
+
+    /// Retained evidence for `round`, if any
+    pub fn evidence(&self, round: u64) -> Option<&FinalizedRound> {
+        self.finalized_rounds.iter().find(|r| r.round == round)
+    }
+
+    /// Mark `operator`'s deviant observation in retained round `round` as slashed and
+    /// return its price with the round's accepted price. The mark lives on the evidence
+    /// itself, so each observation is slashed at most once whatever else was slashed since.
+    pub fn slash_submission(&mut self, round: u64, operator: Pubkey, slash_deviation_bps: u16) -> Result<(i64, i64)> {
+        let evidence = self
+            .finalized_rounds
+            .iter_mut()
+            .find(|r| r.round == round)
+            .ok_or(PriceFeedError::EvidenceUnavailable)?;
+        let accepted_price = evidence.accepted_price;
+        let submission = evidence
+            .submissions
+            .iter_mut()
+            .find(|s| s.operator == operator)
+            .ok_or(PriceFeedError::EvidenceUnavailable)?;
+        require!(!submission.slashed, PriceFeedError::AlreadySlashed);
+        require!(
+            is_deviant(submission.price, accepted_price, slash_deviation_bps),
+            PriceFeedError::SubmissionWithinBounds
+        );
+        submission.slashed = true;
+        Ok((submission.price, accepted_price))
+    }
+}
+
+/// Price snapshot returned by `get_price`. This is the stable CPI interface:
+/// `version` always comes first and new fields are only ever appended.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct PriceView {
+    /// Layout version (`PRICE_VIEW_VERSION`)
+    pub version: u8,
+    pub price: i64,
+    pub decimals: u8,
+    pub confidence: u64,
+    pub last_updated: i64,
+    pub ema_price: i64,
+}
+
+impl PriceView {
+    pub fn from_feed(price_feed: &PriceFeedData) -> Self {
+        Self {
+            version: PRICE_VIEW_VERSION,
+            price: price_feed.price,
+            decimals: price_feed.decimals,
+            confidence: price_feed.confidence,
+            last_updated: price_feed.last_updated,
+            ema_price: price_feed.ema_price,
+        }
+    }
+}
+
+/// Locates the Ed25519 verify instruction accompanying a signed price
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct SignatureMeta {
+    /// Index of the Ed25519 verify instruction within the transaction
+    pub ed25519_ix_index: u16,
+
+    /// Operator whose signature the verify instruction carries
+    pub operator: Pubkey,
+}
+
+#[event]
+pub struct PriceFeedInitialized {
+    pub feed_id: Pubkey,
+    pub description: String,
+}
+
+#[event]
+pub struct PriceUpdated {
+    pub feed: Pubkey,
+    pub price: i64,
+    pub confidence: u64,
+    pub round: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ObservationSubmitted {
+    pub feed: Pubkey,
+    pub operator: Pubkey,
+    pub round: u64,
+    pub price: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RoundExpired {
+    pub feed: Pubkey,
+    pub round: u64,
+    pub submissions: u32,
+}
+
+#[event]
+pub struct OperatorSetUpdated {
+    pub operator_set: Pubkey,
+    pub operators: Vec<Pubkey>,
+    pub threshold: u8,
+}
+
+#[event]
+pub struct OperatorBonded {
+    pub operator: Pubkey,
+    pub amount: u64,
+    pub total_bond: u64,
+}
+
+#[event]
+pub struct OperatorSlashed {
+    pub operator: Pubkey,
+    pub round: u64,
+    pub submitted_price: i64,
+    pub accepted_price: i64,
+    pub slashed_amount: u64,
+    pub remaining_bond: u64,
+}
+
+#[event]
+pub struct OperatorDeregistered {
+    pub operator: Pubkey,
+    pub withdrawable_at: i64,
+}
+
+#[event]
+pub struct BondWithdrawn {
+    pub operator: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct AccessFeeCharged {
+    pub feed: Pubkey,
+    pub consumer: Pubkey,
+    pub payer: Pubkey,
+    pub fee: u64,
+}
+
+#[event]
+pub struct AccessFeeUpdated {
+    pub feed: Pubkey,
+    pub access_fee_lamports: u64,
+}
+
+#[event]
+pub struct FeeExemptionUpdated {
+    pub program_id: Pubkey,
+    pub exempt: bool,
+}
+
+#[event]
+pub struct AccessFeesSwept {
+    pub treasury: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct FeedMigrated {
+    pub feed: Pubkey,
+    pub from_version: u8,
+    pub to_version: u8,
+}
+
+#[event]
+pub struct FeedStaleDetected {
+    pub feed: Pubkey,
+    pub last_updated: i64,
+    pub heartbeat_secs: i64,
+    pub detected_at: i64,
+    pub reporter: Pubkey,
+}
+
+#[event]
+pub struct FeedRegistered {
+    pub symbol: String,
+    pub feed: Pubkey,
+}
+
+#[event]
+pub struct FeedEntryUpdated {
+    pub symbol: String,
+    pub old_feed: Pubkey,
+    pub new_feed: Pubkey,
+}
+
+#[event]
+pub struct FeedEntryRemoved {
+    pub symbol: String,
+    pub feed: Pubkey,
+}
+
+#[error_code]
+pub enum PriceFeedError {
+    #[msg("Price feed is not initialized.")]
+    NotInitialized,
+
+    #[msg("Invalid Chainlink feed ID.")]
+    InvalidFeedId,
+
+    #[msg("Price data is stale.")]
+    StalePriceData,
 
     #[msg("Description exceeds maximum length.")]
     DescriptionTooLong,
+
+    #[msg("Unauthorized oracle authority.")]
+    Unauthorized,
+
+    #[msg("Symbol is empty or exceeds maximum length.")]
+    InvalidSymbol,
+
+    #[msg("Feed entry does not match the requested symbol.")]
+    SymbolMismatch,
+
+    #[msg("Missing or invalid Ed25519 signature over the price payload.")]
+    InvalidSignature,
+
+    #[msg("Price timestamp is not newer than the stored price.")]
+    StaleTimestamp,
+
+    #[msg("Price timestamp is in the future.")]
+    InvalidTimestamp,
+
+    #[msg("Price deviates too far from the last accepted price.")]
+    PriceDeviationTooLarge,
+
+    #[msg("Oracle price does not fit in 64 bits.")]
+    PriceOutOfRange,
+
+    #[msg("Heartbeat must be positive and no longer than the staleness threshold.")]
+    InvalidHeartbeat,
+
+    #[msg("Feed heartbeat has not been missed.")]
+    HeartbeatNotMissed,
+
+    #[msg("Feed is already flagged stale.")]
+    AlreadyFlaggedStale,
+
+    #[msg("EMA alpha must be within (0, 10000] basis points.")]
+    InvalidEmaAlpha,
+
+    #[msg("Price must be greater than zero.")]
+    InvalidPrice,
+
+    #[msg("Arithmetic overflow in price math.")]
+    MathOverflow,
+
+    #[msg("Signer is not a registered operator for this feed.")]
+    NotAnOperator,
+
+    #[msg("Operator already submitted an observation for this round.")]
+    DuplicateSubmission,
+
+    #[msg("Operator list or threshold is invalid.")]
+    InvalidOperatorConfig,
+
+    #[msg("Observation decimals differ from the pending round.")]
+    DecimalsMismatch,
+
+    #[msg("Bonding parameters are invalid.")]
+    InvalidBondConfig,
+
+    #[msg("Operator bond is below the required minimum.")]
+    InsufficientBond,
+
+    #[msg("Operator has deregistered.")]
+    OperatorDeregistered,
+
+    #[msg("Bond is still within the unbonding cooldown.")]
+    BondLocked,
+
+    #[msg("No retained submission for this operator and round.")]
+    EvidenceUnavailable,
+
+    #[msg("Submission was within the allowed deviation.")]
+    SubmissionWithinBounds,
+
+    #[msg("Operator was already slashed for this round.")]
+    AlreadySlashed,
+
+    #[msg("Price confidence interval is too wide.")]
+    ConfidenceTooWide,
+
+    #[msg("Fee exemption allowlist is full.")]
+    TooManyExemptPrograms,
+
+    #[msg("No collected fees to sweep.")]
+    NothingToSweep,
 }
@@ -0,0 +1,208 @@
+// price_consumer.rs
+// Example external program that reads a price from the oracle via CPI, plus a
+// program-test that runs it against the oracle to check the returned view.
+
+use anchor_lang::prelude::*;
+use ontora_oracle::cpi::accounts::GetPrice;
+use ontora_oracle::program::PriceFeed;
+use ontora_oracle::{FeeVault, PriceFeedData, PriceView, PRICE_VIEW_VERSION};
+
+declare_id!("PriceConsumer111111111111111111111111111111");
+
+#[program]
+pub mod price_consumer {
+    use super::*;
+
+    /// CPI into the oracle, check the interface version, and require a minimum price
+    pub fn consume_price(ctx: Context<ConsumePrice>, min_price: i64) -> Result<()> {
+        let cpi_accounts = GetPrice {
+            price_feed: ctx.accounts.price_feed.to_account_info(),
+            fee_vault: ctx.accounts.fee_vault.to_account_info(),
+            payer: ctx.accounts.payer.to_account_info(),
+            instructions_sysvar: ctx.accounts.instructions_sysvar.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.oracle_program.to_account_info(), cpi_accounts);
+        let view: PriceView = ontora_oracle::cpi::get_price(cpi_ctx)?.get();
+
+        require!(view.version >= PRICE_VIEW_VERSION, ConsumerError::UnsupportedVersion);
+        require!(view.price >= min_price, ConsumerError::PriceTooLow);
+
+        msg!(
+            "price={} decimals={} confidence={} last_updated={}",
+            view.price,
+            view.decimals,
+            view.confidence,
+            view.last_updated
+        );
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct ConsumePrice<'info> {
+    pub price_feed: Account<'info, PriceFeedData>,
+    #[account(mut)]
+    pub fee_vault: Account<'info, FeeVault>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// CHECK: forwarded to the oracle, which checks the address
+    pub instructions_sysvar: UncheckedAccount<'info>,
+    pub oracle_program: Program<'info, PriceFeed>,
+    pub system_program: Program<'info, System>,
+}
+
+#[error_code]
+pub enum ConsumerError {
+    #[msg("Oracle returned an unsupported price view version.")]
+    UnsupportedVersion,
+
+    #[msg("Oracle price is below the requested minimum.")]
+    PriceTooLow,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::{AccountSerialize, InstructionData, ToAccountMetas};
+    use solana_program_test::*;
+    use solana_sdk::{
+        account::Account as SolanaAccount,
+        instruction::Instruction,
+        signature::Signer,
+        transaction::Transaction,
+    };
+
+    const TEST_TIMESTAMP: i64 = 1_630_000_000;
+
+    fn feed_account(price: i64, access_fee_lamports: u64) -> SolanaAccount {
+        let feed = PriceFeedData {
+            price,
+            decimals: 8,
+            confidence: 25,
+            last_updated: TEST_TIMESTAMP,
+            is_initialized: true,
+            heartbeat_secs: 3600,
+            ema_price: price,
+            round: 1,
+            access_fee_lamports,
+            ..Default::default()
+        };
+        program_account(&feed)
+    }
+
+    fn program_account<T: AccountSerialize>(value: &T) -> SolanaAccount {
+        let mut data = Vec::new();
+        value.try_serialize(&mut data).unwrap();
+        data.resize(1024, 0);
+
+        SolanaAccount {
+            lamports: 1_000_000_000,
+            data,
+            owner: ontora_oracle::ID,
+            executable: false,
+            rent_epoch: 0,
+        }
+    }
+
+    struct ConsumerRun {
+        result: std::result::Result<(), BanksClientError>,
+        fees_collected: u64,
+    }
+
+    async fn run_consumer(price: i64, min_price: i64) -> std::result::Result<(), BanksClientError> {
+        run_consumer_with_fee(price, min_price, 0, false).await.result
+    }
+
+    async fn run_consumer_with_fee(price: i64, min_price: i64, access_fee_lamports: u64, exempt: bool) -> ConsumerRun {
+        let mut program_test = ProgramTest::new("price_consumer", crate::ID, processor!(price_consumer::entry));
+        program_test.add_program("ontora_oracle", ontora_oracle::ID, processor!(ontora_oracle::entry));
+
+        let feed = Pubkey::new_unique();
+        program_test.add_account(feed, feed_account(price, access_fee_lamports));
+
+        let (fee_vault, bump) = Pubkey::find_program_address(&[ontora_oracle::FEE_VAULT_SEED], &ontora_oracle::ID);
+        let vault = FeeVault {
+            exempt_programs: if exempt { vec![crate::ID] } else { Vec::new() },
+            bump,
+            ..Default::default()
+        };
+        program_test.add_account(fee_vault, program_account(&vault));
+
+        let mut context = program_test.start_with_context().await;
+        let mut clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+        clock.unix_timestamp = TEST_TIMESTAMP + 10;
+        context.set_sysvar(&clock);
+
+        let ix = Instruction {
+            program_id: crate::ID,
+            accounts: crate::accounts::ConsumePrice {
+                price_feed: feed,
+                fee_vault,
+                payer: context.payer.pubkey(),
+                instructions_sysvar: solana_sdk::sysvar::instructions::ID,
+                oracle_program: ontora_oracle::ID,
+                system_program: solana_sdk::system_program::ID,
+            }
+            .to_account_metas(None),
+            data: crate::instruction::ConsumePrice { min_price }.data(),
+        };
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&context.payer.pubkey()),
+            &[&context.payer],
+            context.last_blockhash,
+        );
+        let result = context.banks_client.process_transaction(tx).await;
+        let vault_lamports = context.banks_client.get_balance(fee_vault).await.unwrap();
+
+        ConsumerRun {
+            result,
+            fees_collected: vault_lamports - 1_000_000_000,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_consumer_reads_price_via_cpi() {
+        assert!(run_consumer(50000_00000000, 40000_00000000).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_consumer_rejects_low_price() {
+        assert!(run_consumer(30000_00000000, 40000_00000000).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_consumer_pays_access_fee() {
+        let run = run_consumer_with_fee(50000_00000000, 40000_00000000, 5_000, false).await;
+        assert!(run.result.is_ok());
+        assert_eq!(run.fees_collected, 5_000);
+    }
+
+    #[tokio::test]
+    async fn test_allowlisted_consumer_reads_free() {
+        let run = run_consumer_with_fee(50000_00000000, 40000_00000000, 5_000, true).await;
+        assert!(run.result.is_ok());
+        assert_eq!(run.fees_collected, 0);
+    }
+
+    #[test]
+    fn test_price_view_layout_is_stable() {
+        let view = PriceView {
+            version: PRICE_VIEW_VERSION,
+            price: 1,
+            decimals: 2,
+            confidence: 3,
+            last_updated: 4,
+            ema_price: 5,
+        };
+        let bytes = view.try_to_vec().unwrap();
+
+        // version | price | decimals | confidence | last_updated must never move
+        assert_eq!(bytes[0], PRICE_VIEW_VERSION);
+        assert_eq!(&bytes[1..9], &1i64.to_le_bytes());
+        assert_eq!(bytes[9], 2);
+        assert_eq!(&bytes[10..18], &3u64.to_le_bytes());
+        assert_eq!(&bytes[18..26], &4i64.to_le_bytes());
+    }
+}
@@ -0,0 +1,104 @@
+// test_layout_golden.rs
+// Golden-file snapshot of the PriceFeedData account layout. Consumers read the
+// feed at fixed offsets, so a canonical instance is serialized and compared,
+// as hex, with tests/golden/account_layouts.txt. After an intended change (and
+// a `PRICE_FEED_VERSION` bump), regenerate the snapshot with
+//     UPDATE_LAYOUT_GOLDENS=1 cargo test --test test_layout_golden
+
+use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+use ontora_oracle::PriceFeedData;
+
+const GOLDEN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden/account_layouts.txt");
+
+// Bytes per hex row, so a diff points at the offset that moved
+const ROW_BYTES: usize = 32;
+
+// Every field holds a distinct value, so swapped fields show up too
+fn canonical_feed() -> PriceFeedData {
+    PriceFeedData {
+        feed_id: Pubkey::new_from_array([1; 32]),
+        description: "SOL/USD".to_string(),
+        price: 14_250_000_000,
+        decimals: 8,
+        last_updated: 1_700_000_000,
+        is_initialized: true,
+        operator_set: Pubkey::new_from_array([2; 32]),
+        confidence: 1_500_000,
+        round: 42,
+        heartbeat_secs: 60,
+        stale: false,
+        ema_price: 14_100_000_000,
+        ema_alpha_bps: 2_000,
+        version: 3,
+        access_fee_lamports: 5_000,
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn render_golden() -> String {
+    let mut data = Vec::new();
+    canonical_feed().try_serialize(&mut data).unwrap();
+
+    let mut text = String::from("# Canonical account bytes (discriminator + Borsh body). See tests/test_layout_golden.rs.\n");
+    text.push_str(&format!(
+        "\nPriceFeedData discriminator={} len={}\n",
+        hex(&PriceFeedData::discriminator()),
+        data.len()
+    ));
+    for (row, chunk) in data.chunks(ROW_BYTES).enumerate() {
+        text.push_str(&format!("    {:04x}: {}\n", row * ROW_BYTES, hex(chunk)));
+    }
+    text
+}
+
+// Lines that differ between the snapshot and the current rendering
+fn line_diff(golden: &str, current: &str) -> String {
+    let (golden, current): (Vec<&str>, Vec<&str>) = (golden.lines().collect(), current.lines().collect());
+    let mut diff = String::new();
+    for index in 0..golden.len().max(current.len()) {
+        let (before, after) = (golden.get(index), current.get(index));
+        if before != after {
+            before.into_iter().for_each(|line| diff.push_str(&format!("- {}\n", line)));
+            after.into_iter().for_each(|line| diff.push_str(&format!("+ {}\n", line)));
+        }
+    }
+    diff
+}
+
+// Test that the PriceFeedData layout and discriminator match the snapshot
+#[test]
+fn test_price_feed_layout_matches_golden() {
+    let rendered = render_golden();
+    if std::env::var_os("UPDATE_LAYOUT_GOLDENS").is_some() {
+        std::fs::write(GOLDEN_PATH, &rendered).unwrap();
+        return;
+    }
+    let golden = std::fs::read_to_string(GOLDEN_PATH).unwrap_or_default();
+    assert!(
+        golden == rendered,
+        "PriceFeedData layout changed; if intended, bump PRICE_FEED_VERSION and regenerate with \
+         UPDATE_LAYOUT_GOLDENS=1\n{}",
+        line_diff(&golden, &rendered)
+    );
+}
+
+// Test that the documented offsets point at the canonical field values
+#[test]
+fn test_price_feed_offsets_match_serialization() {
+    let feed = canonical_feed();
+    let mut data = Vec::new();
+    feed.try_serialize(&mut data).unwrap();
+    let tail = PriceFeedData::OFFSET_DESCRIPTION + 4 + feed.description.len();
+    let read_u64 = |offset: usize| u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap());
+
+    assert_eq!(data.len(), tail + PriceFeedData::TAIL_LEN);
+    assert_eq!(&data[PriceFeedData::OFFSET_FEED_ID..][..32], feed.feed_id.as_ref());
+    assert_eq!(read_u64(tail + PriceFeedData::TAIL_OFFSET_PRICE) as i64, feed.price);
+    assert_eq!(read_u64(tail + PriceFeedData::TAIL_OFFSET_ROUND), feed.round);
+    assert_eq!(data[tail + PriceFeedData::TAIL_OFFSET_VERSION], feed.version);
+    assert_eq!(read_u64(tail + PriceFeedData::TAIL_OFFSET_ACCESS_FEE_LAMPORTS), feed.access_fee_lamports);
+}
@@ -347,4 +347,726 @@ mod tests {
         }
         Ok((price_feed.price, price_feed.decimals))
     }
+
+    // Mock feed registry keyed by symbol hash (mirrors FeedEntry PDAs in price_feed.rs)
+    #[derive(Default)]
+    struct MockFeedRegistry {
+        authority: Pubkey,
+        entries: std::collections::HashMap<[u8; 32], Pubkey>,
+    }
+
+    fn register_feed(
+        registry: &mut MockFeedRegistry,
+        signer: &Pubkey,
+        symbol: &str,
+        feed: Pubkey,
+    ) -> Result<()> {
+        if *signer != registry.authority {
+            return Err(ProgramError::Custom(1005)); // Unauthorized error
+        }
+        let normalized = normalize_symbol(symbol)?;
+        let key = symbol_hash(&normalized);
+        if registry.entries.contains_key(&key) {
+            return Err(ProgramError::Custom(0)); // System program: account already in use
+        }
+        registry.entries.insert(key, feed);
+        Ok(())
+    }
+
+    fn resolve_feed(registry: &MockFeedRegistry, symbol: &str) -> Result<Pubkey> {
+        let normalized = normalize_symbol(symbol)?;
+        registry
+            .entries
+            .get(&symbol_hash(&normalized))
+            .copied()
+            .ok_or(ProgramError::Custom(1006)) // Missing entry
+    }
+
+    #[test]
+    fn test_feed_registry_register_and_resolve() {
+        let authority = Pubkey::new_unique();
+        let mut registry = MockFeedRegistry {
+            authority,
+            ..Default::default()
+        };
+        let sol_feed = Pubkey::new_unique();
+        let btc_feed = Pubkey::new_unique();
+
+        assert!(register_feed(&mut registry, &authority, "SOL/USD", sol_feed).is_ok());
+        assert!(register_feed(&mut registry, &authority, "BTC/USD", btc_feed).is_ok());
+
+        assert_eq!(resolve_feed(&registry, "SOL/USD").unwrap(), sol_feed);
+        assert_eq!(resolve_feed(&registry, "btc/usd ").unwrap(), btc_feed);
+    }
+
+    #[test]
+    fn test_feed_registry_duplicate_symbol_rejected() {
+        let authority = Pubkey::new_unique();
+        let mut registry = MockFeedRegistry {
+            authority,
+            ..Default::default()
+        };
+
+        assert!(register_feed(&mut registry, &authority, "SOL/USD", Pubkey::new_unique()).is_ok());
+
+        // Same symbol after normalization maps to the same PDA
+        let result = register_feed(&mut registry, &authority, " sol/usd", Pubkey::new_unique());
+        assert!(result.is_err());
+        assert_eq!(registry.entries.len(), 1);
+    }
+
+    #[test]
+    fn test_feed_registry_rejects_invalid_symbol() {
+        assert!(normalize_symbol("").is_err());
+        assert!(normalize_symbol("   ").is_err());
+        assert!(normalize_symbol("A_VERY_LONG_SYMBOL/USD").is_err());
+        assert_eq!(normalize_symbol(" eth/usd ").unwrap(), "ETH/USD");
+    }
+
+    // Build Ed25519 precompile instruction data with signature, key, and message inline
+    fn build_ed25519_ix_data(signer: &Pubkey, message: &[u8]) -> Vec<u8> {
+        let pubkey_offset: u16 = 2 + 14;
+        let signature_offset: u16 = pubkey_offset + 32;
+        let message_offset: u16 = signature_offset + 64;
+
+        let mut data = vec![1u8, 0u8];
+        for value in [
+            signature_offset,
+            u16::MAX,
+            pubkey_offset,
+            u16::MAX,
+            message_offset,
+            message.len() as u16,
+            u16::MAX,
+        ] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.extend_from_slice(signer.as_ref());
+        data.extend_from_slice(&[7u8; 64]); // Signature bytes are checked by the precompile
+        data.extend_from_slice(message);
+        data
+    }
+
+    // Mock of submit_signed_price for a 1-of-1 operator set: signature check
+    // followed by the shared validation
+    fn submit_signed_price(
+        feed_key: &Pubkey,
+        feed: &mut super::PriceFeedData,
+        ed25519_data: &[u8],
+        clock: &Clock,
+        price: i64,
+        decimals: u8,
+        timestamp: i64,
+    ) -> Result<()> {
+        let operator = feed.operator_set; // 1-of-1 set: the only operator key
+        let message = signed_price_message(feed_key, price, decimals, timestamp);
+        verify_ed25519_instruction_data(ed25519_data, &operator, &message)?;
+        validate_price_update(feed, price, timestamp, clock.unix_timestamp)?;
+
+        feed.record_price(price, decimals, 0, timestamp);
+        Ok(())
+    }
+
+    // Feed whose operator set is stood in for by the single operator key
+    fn signed_feed(operator: Pubkey) -> super::PriceFeedData {
+        super::PriceFeedData {
+            is_initialized: true,
+            operator_set: operator,
+            heartbeat_secs: 3600,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_submit_signed_price_valid_relay() {
+        let ctx = setup_test_context();
+        let feed_key = Pubkey::new_unique();
+        let mut feed = signed_feed(ctx.accounts.authority);
+        let timestamp = ctx.clock.unix_timestamp - 10;
+
+        let message = signed_price_message(&feed_key, 50000_00000000, 8, timestamp);
+        let data = build_ed25519_ix_data(&ctx.accounts.authority, &message);
+
+        let result = submit_signed_price(&feed_key, &mut feed, &data, &ctx.clock, 50000_00000000, 8, timestamp);
+        assert!(result.is_ok());
+        assert_eq!(feed.price, 50000_00000000);
+        assert_eq!(feed.decimals, 8);
+        assert_eq!(feed.last_updated, timestamp);
+    }
+
+    #[test]
+    fn test_submit_signed_price_tampered_price_byte() {
+        let ctx = setup_test_context();
+        let feed_key = Pubkey::new_unique();
+        let mut feed = signed_feed(ctx.accounts.authority);
+        let timestamp = ctx.clock.unix_timestamp - 10;
+
+        let message = signed_price_message(&feed_key, 50000_00000000, 8, timestamp);
+        let data = build_ed25519_ix_data(&ctx.accounts.authority, &message);
+
+        // Relayer flips the lowest price byte in the submitted arguments
+        let result = submit_signed_price(&feed_key, &mut feed, &data, &ctx.clock, 50000_00000001, 8, timestamp);
+        assert!(result.is_err());
+        assert_eq!(feed.price, 0);
+
+        // A signature by any key other than the update authority is rejected too
+        let forged = build_ed25519_ix_data(&Pubkey::new_unique(), &message);
+        let result = submit_signed_price(&feed_key, &mut feed, &forged, &ctx.clock, 50000_00000000, 8, timestamp);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_submit_signed_price_replayed_timestamp() {
+        let ctx = setup_test_context();
+        let feed_key = Pubkey::new_unique();
+        let mut feed = signed_feed(ctx.accounts.authority);
+        let first = ctx.clock.unix_timestamp - 20;
+        let older = ctx.clock.unix_timestamp - 30;
+
+        let message = signed_price_message(&feed_key, 50000_00000000, 8, first);
+        let data = build_ed25519_ix_data(&ctx.accounts.authority, &message);
+        assert!(submit_signed_price(&feed_key, &mut feed, &data, &ctx.clock, 50000_00000000, 8, first).is_ok());
+
+        // Same payload again
+        assert!(submit_signed_price(&feed_key, &mut feed, &data, &ctx.clock, 50000_00000000, 8, first).is_err());
+
+        // Validly signed but older observation
+        let message = signed_price_message(&feed_key, 50100_00000000, 8, older);
+        let data = build_ed25519_ix_data(&ctx.accounts.authority, &message);
+        assert!(submit_signed_price(&feed_key, &mut feed, &data, &ctx.clock, 50100_00000000, 8, older).is_err());
+        assert_eq!(feed.last_updated, first);
+    }
+
+    // Mock of flag_stale mirroring the checks in price_feed.rs
+    fn flag_stale(feed: &mut super::PriceFeedData, clock: &Clock) -> Result<()> {
+        if feed.stale {
+            return Err(ProgramError::Custom(1007)); // Already flagged error
+        }
+        if !feed.heartbeat_missed(clock.unix_timestamp) {
+            return Err(ProgramError::Custom(1008)); // Heartbeat not missed error
+        }
+        feed.stale = true;
+        Ok(())
+    }
+
+    #[test]
+    fn test_fresh_update_clears_stale_flag() {
+        let ctx = setup_test_context();
+        let mut feed = signed_feed(ctx.accounts.authority);
+        feed.heartbeat_secs = 60;
+        feed.record_price(100_000, 8, 0, ctx.clock.unix_timestamp - 120);
+
+        assert!(flag_stale(&mut feed, &ctx.clock).is_ok());
+        assert!(feed.stale);
+        assert!(feed.assert_fresh(ctx.clock.unix_timestamp).is_err());
+
+        feed.record_price(100_500, 8, 0, ctx.clock.unix_timestamp);
+        assert!(!feed.stale);
+        assert_eq!(feed.round, 2);
+        assert!(feed.assert_fresh(ctx.clock.unix_timestamp).is_ok());
+    }
+
+    #[test]
+    fn test_missed_heartbeat_allows_flagging() {
+        let mut ctx = setup_test_context();
+        let mut feed = signed_feed(ctx.accounts.authority);
+        feed.heartbeat_secs = 60;
+        feed.record_price(100_000, 8, 0, ctx.clock.unix_timestamp);
+
+        // Exactly at the heartbeat boundary the feed is still fresh
+        ctx.clock.unix_timestamp += 60;
+        assert!(feed.assert_fresh(ctx.clock.unix_timestamp).is_ok());
+
+        ctx.clock.unix_timestamp += 1;
+        assert!(feed.assert_fresh(ctx.clock.unix_timestamp).is_err());
+        assert!(flag_stale(&mut feed, &ctx.clock).is_ok());
+
+        // A second report adds nothing
+        assert!(flag_stale(&mut feed, &ctx.clock).is_err());
+    }
+
+    #[test]
+    fn test_premature_flag_stale_rejected() {
+        let ctx = setup_test_context();
+        let mut feed = signed_feed(ctx.accounts.authority);
+        feed.heartbeat_secs = 60;
+        feed.record_price(100_000, 8, 0, ctx.clock.unix_timestamp - 30);
+
+        assert!(flag_stale(&mut feed, &ctx.clock).is_err());
+        assert!(!feed.stale);
+    }
+
+    #[test]
+    fn test_ema_seeded_with_first_observation() {
+        let ctx = setup_test_context();
+        let mut feed = signed_feed(ctx.accounts.authority);
+        feed.ema_alpha_bps = 2000;
+
+        feed.record_price(100_000, 8, 0, ctx.clock.unix_timestamp);
+        assert_eq!(feed.ema_price, 100_000);
+    }
+
+    #[test]
+    fn test_ema_matches_reference_computation() {
+        let ctx = setup_test_context();
+        let mut feed = signed_feed(ctx.accounts.authority);
+        feed.ema_alpha_bps = 2500;
+
+        let prices: [i64; 8] = [100_000, 104_000, 99_000, 101_500, 110_000, 108_000, 95_000, 97_250];
+        let alpha = 0.25_f64;
+        let mut reference = prices[0] as f64;
+
+        for (i, price) in prices.iter().enumerate() {
+            feed.record_price(*price, 8, 0, ctx.clock.unix_timestamp + i as i64);
+            if i > 0 {
+                reference += alpha * (*price as f64 - reference);
+            }
+            // Integer truncation loses at most one unit per step
+            let drift = (feed.ema_price as f64 - reference).abs();
+            assert!(drift <= (i + 1) as f64, "step {}: ema {} vs reference {}", i, feed.ema_price, reference);
+        }
+    }
+
+    #[test]
+    fn test_ema_extremes_do_not_overflow() {
+        // Full alpha tracks spot exactly
+        assert_eq!(next_ema(100, i64::MAX, 10_000), i64::MAX);
+        // Large swings stay bounded by the two inputs
+        let ema = next_ema(i64::MIN + 1, i64::MAX, 5_000);
+        assert!(ema > i64::MIN + 1 && ema < i64::MAX);
+    }
+
+    #[test]
+    fn test_zero_price_rejected() {
+        let ctx = setup_test_context();
+        let feed = signed_feed(ctx.accounts.authority);
+
+        let result = validate_price_update(&feed, 0, ctx.clock.unix_timestamp, ctx.clock.unix_timestamp);
+        assert!(result.is_err());
+        assert!(checked_price_mul(1_000, 0).is_err());
+    }
+
+    #[test]
+    fn test_negative_price_rejected() {
+        let ctx = setup_test_context();
+        let feed_key = Pubkey::new_unique();
+        let mut feed = signed_feed(ctx.accounts.authority);
+        let timestamp = ctx.clock.unix_timestamp - 5;
+
+        let message = signed_price_message(&feed_key, -1, 8, timestamp);
+        let data = build_ed25519_ix_data(&ctx.accounts.authority, &message);
+
+        // A correctly signed negative price is still rejected
+        let result = submit_signed_price(&feed_key, &mut feed, &data, &ctx.clock, -1, 8, timestamp);
+        assert!(result.is_err());
+        assert_eq!(feed.price, 0);
+        assert!(checked_price_mul(1_000, -1).is_err());
+    }
+
+    #[test]
+    fn test_max_token_amount_at_high_price() {
+        // u64::MAX * i64::MAX fits in u128 (2^64 * 2^63 < 2^128)
+        let value = checked_price_mul(u64::MAX, i64::MAX).unwrap();
+        assert_eq!(value, (u64::MAX as u128) * (i64::MAX as u128));
+
+        let value = checked_price_mul(u64::MAX, 1_000_000_00000000).unwrap();
+        assert_eq!(value / 1_000_000_00000000, u64::MAX as u128);
+    }
+
+    fn operator_set(operators: &[Pubkey], threshold: u8) -> super::OperatorSet {
+        super::OperatorSet {
+            authority: Pubkey::new_unique(),
+            operators: operators.to_vec(),
+            threshold,
+            tolerance_bps: 100, // 1%
+            submission_window_secs: 60,
+            min_bond: 1_000,
+            slash_bps: 1000, // 10%
+            slash_deviation_bps: 500, // 5%
+            unbonding_secs: 86_400,
+            ..Default::default()
+        }
+    }
+
+    // Mock of the quorum path in accept_operator_submission
+    fn submit_observation(
+        feed: &mut super::PriceFeedData,
+        pending: &mut super::PendingRound,
+        set: &super::OperatorSet,
+        operator: Pubkey,
+        price: i64,
+        now: i64,
+    ) -> Result<()> {
+        if !set.is_operator(&operator) {
+            return Err(ProgramError::Custom(1009)); // Not an operator error
+        }
+        validate_price_update(feed, price, now, now)?;
+        pending.open(feed.round + 1, 8, now, set.submission_window_secs);
+        pending.add_submission(operator, price, now)?;
+        if let Some(quorum) = pending.quorum_price(set.threshold, set.tolerance_bps) {
+            feed.record_price(quorum.price, 8, quorum.confidence, quorum.timestamp);
+            pending.finalize(feed.round, quorum.price, now, set);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_operator_quorum_two_of_three() {
+        let ctx = setup_test_context();
+        let operators = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+        let set = operator_set(&operators, 2);
+        let mut feed = signed_feed(Pubkey::new_unique());
+        let mut pending = super::PendingRound::default();
+        let now = ctx.clock.unix_timestamp;
+
+        assert!(submit_observation(&mut feed, &mut pending, &set, operators[0], 100_000, now).is_ok());
+        assert_eq!(feed.price, 0, "one observation must not publish");
+
+        // The same operator cannot count twice
+        assert!(submit_observation(&mut feed, &mut pending, &set, operators[0], 100_000, now).is_err());
+
+        assert!(submit_observation(&mut feed, &mut pending, &set, operators[1], 100_400, now + 1).is_ok());
+        assert_eq!(feed.price, 100_200); // median of the agreeing pair
+        assert_eq!(feed.confidence, 200);
+        assert_eq!(feed.round, 1);
+        assert!(pending.submissions.is_empty());
+
+        // Outsiders are rejected
+        assert!(submit_observation(&mut feed, &mut pending, &set, Pubkey::new_unique(), 100_000, now + 2).is_err());
+    }
+
+    #[test]
+    fn test_operator_quorum_conflicting_observations() {
+        let ctx = setup_test_context();
+        let operators = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+        let set = operator_set(&operators, 2);
+        let mut feed = signed_feed(Pubkey::new_unique());
+        let mut pending = super::PendingRound::default();
+        let now = ctx.clock.unix_timestamp;
+
+        // 5% apart with a 1% tolerance: no quorum
+        assert!(submit_observation(&mut feed, &mut pending, &set, operators[0], 100_000, now).is_ok());
+        assert!(submit_observation(&mut feed, &mut pending, &set, operators[1], 105_000, now).is_ok());
+        assert_eq!(feed.price, 0);
+        assert_eq!(pending.submissions.len(), 2);
+
+        // A third observation agreeing with the first forms the quorum around them
+        assert!(submit_observation(&mut feed, &mut pending, &set, operators[2], 100_100, now).is_ok());
+        assert_eq!(feed.price, 100_050);
+    }
+
+    #[test]
+    fn test_operator_quorum_half_formed_round_expires() {
+        let ctx = setup_test_context();
+        let operators = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+        let set = operator_set(&operators, 2);
+        let mut feed = signed_feed(Pubkey::new_unique());
+        let mut pending = super::PendingRound::default();
+        let now = ctx.clock.unix_timestamp;
+
+        assert!(submit_observation(&mut feed, &mut pending, &set, operators[0], 100_000, now).is_ok());
+
+        // After the window the old observation is discarded instead of pairing up
+        let later = now + set.submission_window_secs + 1;
+        assert!(submit_observation(&mut feed, &mut pending, &set, operators[1], 100_050, later).is_ok());
+        assert_eq!(feed.price, 0);
+        assert_eq!(pending.submissions.len(), 1);
+        assert_eq!(pending.submissions[0].operator, operators[1]);
+    }
+
+    #[test]
+    fn test_operator_config_validation() {
+        let a = Pubkey::new_unique();
+        let b = Pubkey::new_unique();
+        assert!(validate_operator_config(&[a, b], 2).is_ok());
+        assert!(validate_operator_config(&[a, b], 3).is_err());
+        assert!(validate_operator_config(&[a, b], 0).is_err());
+        assert!(validate_operator_config(&[a, a], 1).is_err());
+        assert!(validate_operator_config(&[Pubkey::new_unique(); 11], 1).is_err());
+    }
+
+    // Mock of slash_operator against the retained evidence rounds
+    fn slash_operator(
+        set: &super::OperatorSet,
+        pending: &mut super::PendingRound,
+        bond: &mut super::OperatorBond,
+        evidence_round: u64,
+    ) -> Result<u64> {
+        pending.slash_submission(evidence_round, bond.operator, set.slash_deviation_bps)?;
+        let slashed = slash_amount(bond.amount, set.slash_bps)?;
+        bond.amount -= slashed;
+        Ok(slashed)
+    }
+
+    #[test]
+    fn test_bonded_registration_required_for_submissions() {
+        let operator = Pubkey::new_unique();
+        let set = operator_set(&[operator], 1);
+        let mut bond = super::OperatorBond {
+            operator,
+            amount: 999,
+            ..Default::default()
+        };
+
+        assert!(bond.assert_active(&set).is_err());
+        bond.amount = 1_000;
+        assert!(bond.assert_active(&set).is_ok());
+        bond.deregistered_at = 1;
+        assert!(bond.assert_active(&set).is_err());
+    }
+
+    #[test]
+    fn test_slash_on_deviant_round() {
+        let ctx = setup_test_context();
+        let operators = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+        let set = operator_set(&operators, 2);
+        let mut feed = signed_feed(Pubkey::new_unique());
+        let mut pending = super::PendingRound::default();
+        let now = ctx.clock.unix_timestamp;
+
+        // Operator 2 reports 10% off; the other two finalize the round
+        assert!(submit_observation(&mut feed, &mut pending, &set, operators[2], 110_000, now).is_ok());
+        assert!(submit_observation(&mut feed, &mut pending, &set, operators[0], 100_000, now).is_ok());
+        assert!(submit_observation(&mut feed, &mut pending, &set, operators[1], 100_000, now).is_ok());
+        assert_eq!(feed.round, 1);
+        assert_eq!(pending.evidence(1).unwrap().accepted_price, 100_000);
+
+        let mut deviant = super::OperatorBond { operator: operators[2], amount: 5_000, ..Default::default() };
+        assert_eq!(slash_operator(&set, &mut pending, &mut deviant, 1).unwrap(), 500);
+        assert_eq!(deviant.amount, 4_500);
+
+        // No double slashing for the same round
+        assert!(slash_operator(&set, &mut pending, &mut deviant, 1).is_err());
+
+        // Honest operators cannot be slashed
+        let mut honest = super::OperatorBond { operator: operators[0], amount: 5_000, ..Default::default() };
+        assert!(slash_operator(&set, &mut pending, &mut honest, 1).is_err());
+        assert_eq!(honest.amount, 5_000);
+    }
+
+    #[test]
+    fn test_slash_same_round_twice() {
+        let ctx = setup_test_context();
+        let operators = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+        let set = operator_set(&operators, 2);
+        let mut feed = signed_feed(Pubkey::new_unique());
+        let mut other_feed = signed_feed(Pubkey::new_unique());
+        let mut pending = super::PendingRound::default();
+        let mut other_pending = super::PendingRound::default();
+        let now = ctx.clock.unix_timestamp;
+
+        // Operator 2 deviates in rounds 1 and 2 of one feed and round 1 of another
+        for (i, t) in [now, now + 60].into_iter().enumerate() {
+            assert!(submit_observation(&mut feed, &mut pending, &set, operators[2], 110_000, t).is_ok());
+            assert!(submit_observation(&mut feed, &mut pending, &set, operators[0], 100_000, t).is_ok());
+            assert!(submit_observation(&mut feed, &mut pending, &set, operators[1], 100_000, t).is_ok());
+            assert_eq!(feed.round, i as u64 + 1);
+        }
+        assert!(submit_observation(&mut other_feed, &mut other_pending, &set, operators[2], 90_000, now).is_ok());
+        assert!(submit_observation(&mut other_feed, &mut other_pending, &set, operators[0], 100_000, now).is_ok());
+        assert!(submit_observation(&mut other_feed, &mut other_pending, &set, operators[1], 100_000, now).is_ok());
+
+        // Slashing round 2 in between does not make round 1 slashable again
+        let mut deviant = super::OperatorBond { operator: operators[2], amount: 10_000, ..Default::default() };
+        assert_eq!(slash_operator(&set, &mut pending, &mut deviant, 1).unwrap(), 1_000);
+        assert_eq!(slash_operator(&set, &mut pending, &mut deviant, 2).unwrap(), 900);
+        assert!(slash_operator(&set, &mut pending, &mut deviant, 1).is_err());
+        assert!(slash_operator(&set, &mut pending, &mut deviant, 2).is_err());
+        assert_eq!(deviant.amount, 8_100);
+        assert!(pending.evidence(1).unwrap().submissions[0].slashed);
+
+        // The other feed's round 1 is separate evidence against the same bond
+        assert_eq!(slash_operator(&set, &mut other_pending, &mut deviant, 1).unwrap(), 810);
+        assert!(slash_operator(&set, &mut other_pending, &mut deviant, 1).is_err());
+        assert_eq!(deviant.amount, 7_290);
+    }
+
+    #[test]
+    fn test_slash_evidence_retained_for_unbonding_period() {
+        let ctx = setup_test_context();
+        let operators = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+        let set = operator_set(&operators, 2);
+        let mut feed = signed_feed(Pubkey::new_unique());
+        let mut pending = super::PendingRound::default();
+        let now = ctx.clock.unix_timestamp;
+
+        // Round 1 has a deviant observation from operator 2
+        assert!(submit_observation(&mut feed, &mut pending, &set, operators[2], 110_000, now).is_ok());
+        assert!(submit_observation(&mut feed, &mut pending, &set, operators[0], 100_000, now).is_ok());
+        assert!(submit_observation(&mut feed, &mut pending, &set, operators[1], 100_000, now).is_ok());
+
+        // Clean rounds that follow do not displace it
+        for i in 1..=MAX_EVIDENCE_ROUNDS as i64 {
+            let t = now + i * 60;
+            assert!(submit_observation(&mut feed, &mut pending, &set, operators[0], 100_000, t).is_ok());
+            assert!(submit_observation(&mut feed, &mut pending, &set, operators[1], 100_000, t).is_ok());
+        }
+        assert_eq!(pending.finalized_rounds.len(), 1);
+
+        let mut deviant = super::OperatorBond { operator: operators[2], amount: 5_000, ..Default::default() };
+        assert_eq!(slash_operator(&set, &mut pending, &mut deviant.clone(), 1).unwrap(), 500);
+
+        // Once the unbonding period has passed the evidence is pruned
+        let later = now + set.unbonding_secs + 1;
+        assert!(submit_observation(&mut feed, &mut pending, &set, operators[0], 100_000, later).is_ok());
+        assert!(submit_observation(&mut feed, &mut pending, &set, operators[1], 100_000, later).is_ok());
+        assert!(pending.evidence(1).is_none());
+        assert!(slash_operator(&set, &mut pending, &mut deviant, 1).is_err());
+    }
+
+    #[test]
+    fn test_bond_withdrawal_timing() {
+        let unbonding = 86_400;
+        // Still registered: never withdrawable
+        assert!(!bond_withdrawable(0, unbonding, i64::MAX));
+
+        let deregistered_at = 1_630_000_000;
+        assert!(!bond_withdrawable(deregistered_at, unbonding, deregistered_at + unbonding - 1));
+        assert!(bond_withdrawable(deregistered_at, unbonding, deregistered_at + unbonding));
+    }
+
+    // Serialize a current feed and drop every field appended after v1
+    fn v1_feed_bytes(feed: &super::PriceFeedData) -> Vec<u8> {
+        let mut data = Vec::new();
+        feed.try_serialize(&mut data).unwrap();
+        data.truncate(data.len() - (super::PriceFeedData::TAIL_LEN - super::PriceFeedData::V1_TAIL_LEN));
+        data
+    }
+
+    #[test]
+    fn test_feed_layout_offsets() {
+        let feed = super::PriceFeedData {
+            description: "SOL/USD".to_string(),
+            price: 42,
+            ema_alpha_bps: 7,
+            version: PRICE_FEED_VERSION,
+            ..Default::default()
+        };
+        let mut data = Vec::new();
+        feed.try_serialize(&mut data).unwrap();
+
+        let tail = super::PriceFeedData::OFFSET_DESCRIPTION + 4 + feed.description.len();
+        let price_at = tail + super::PriceFeedData::TAIL_OFFSET_PRICE;
+        let alpha_at = tail + super::PriceFeedData::TAIL_OFFSET_EMA_ALPHA_BPS;
+        assert_eq!(&data[price_at..price_at + 8], &42i64.to_le_bytes());
+        assert_eq!(&data[alpha_at..alpha_at + 2], &7u16.to_le_bytes());
+        assert_eq!(data[tail + super::PriceFeedData::TAIL_OFFSET_VERSION], PRICE_FEED_VERSION);
+        assert_eq!(data.len(), tail + super::PriceFeedData::TAIL_LEN);
+    }
+
+    #[test]
+    fn test_migrate_v1_feed() {
+        let operator_set = Pubkey::new_unique();
+        let v1 = super::PriceFeedData {
+            feed_id: Pubkey::new_unique(),
+            description: "ABCDEFGHIJKLMNOPQRSTUVWXYZ012345".to_string(), // MAX_DESCRIPTION_LEN
+            price: 50000_00000000,
+            decimals: 8,
+            last_updated: 1_630_000_000,
+            is_initialized: true,
+            operator_set,
+            confidence: 25,
+            round: 12,
+            heartbeat_secs: 3600,
+            stale: false,
+            ema_price: 49900_00000000,
+            ema_alpha_bps: 2000,
+            version: 0,
+            access_fee_lamports: 0,
+        };
+        let mut data = v1_feed_bytes(&v1);
+
+        // The full-length v1 account has no room for the version byte
+        assert!(super::PriceFeedData::try_deserialize(&mut &data[..]).is_err());
+
+        // migrate_feed: realloc with zero fill, then stamp the version
+        data.resize(super::PriceFeedData::SPACE, 0);
+        let mut migrated = super::PriceFeedData::try_deserialize(&mut &data[..]).unwrap();
+        assert_eq!(migrated.migrate(), Some(1));
+
+        assert_eq!(migrated.version, PRICE_FEED_VERSION);
+        assert_eq!(migrated.feed_id, v1.feed_id);
+        assert_eq!(migrated.description, v1.description);
+        assert_eq!(migrated.price, v1.price);
+        assert_eq!(migrated.last_updated, v1.last_updated);
+        assert_eq!(migrated.operator_set, operator_set);
+        assert_eq!(migrated.round, v1.round);
+        assert_eq!(migrated.ema_price, v1.ema_price);
+        assert_eq!(migrated.ema_alpha_bps, v1.ema_alpha_bps);
+        assert_eq!(migrated.access_fee_lamports, 0);
+
+        // Already current: no-op
+        assert_eq!(migrated.migrate(), None);
+    }
+
+    #[test]
+    fn test_convert_to_usd_six_decimal_token() {
+        // 2.5 tokens (6 decimals) at $1.25 (8 decimals) = $3.125000
+        let usd = token_amount_to_usd(2_500_000, 6, 1_25000000, 8).unwrap();
+        assert_eq!(usd, 3_125_000);
+    }
+
+    #[test]
+    fn test_convert_to_usd_nine_decimal_token() {
+        // 1.5 SOL (9 decimals) at $150.00000000 = $225.000000
+        let usd = token_amount_to_usd(1_500_000_000, 9, 150_00000000, 8).unwrap();
+        assert_eq!(usd, 225_000_000);
+
+        // Dust rounds down
+        assert_eq!(token_amount_to_usd(1, 9, 150_00000000, 8).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_convert_to_usd_overflow() {
+        // The u128 product fits, but the scaled USD value does not fit in u64
+        assert!(token_amount_to_usd(u64::MAX, 6, i64::MAX, 8).is_err());
+        // Non-positive prices are rejected rather than converted
+        assert!(token_amount_to_usd(1_000_000, 6, 0, 8).is_err());
+    }
+
+    #[test]
+    fn test_convert_to_usd_requires_confident_price() {
+        let mut feed = super::PriceFeedData {
+            price: 100_00000000,
+            confidence: 2_00000000, // 2%, at the limit
+            ..Default::default()
+        };
+        assert!(feed.assert_confident().is_ok());
+
+        feed.confidence += 1;
+        assert!(feed.assert_confident().is_err());
+    }
+
+    #[test]
+    fn test_access_fee_charged() {
+        let vault = super::FeeVault::default();
+        let consumer = Pubkey::new_unique();
+        assert_eq!(access_fee_due(5_000, &vault, &consumer), 5_000);
+    }
+
+    #[test]
+    fn test_zero_fee_feed_is_free() {
+        let vault = super::FeeVault::default();
+        assert_eq!(access_fee_due(0, &vault, &Pubkey::new_unique()), 0);
+        assert_eq!(super::PriceFeedData::default().access_fee_lamports, 0);
+    }
+
+    #[test]
+    fn test_allowlisted_consumer_exempt() {
+        let exempt = Pubkey::new_unique();
+        let vault = super::FeeVault {
+            exempt_programs: vec![exempt],
+            ..Default::default()
+        };
+        assert_eq!(access_fee_due(5_000, &vault, &exempt), 0);
+        assert_eq!(access_fee_due(5_000, &vault, &Pubkey::new_unique()), 5_000);
+    }
+
+    #[test]
+    fn test_sweep_keeps_rent_reserve() {
+        let rent_reserve = 1_500_000;
+        assert_eq!(sweepable_lamports(rent_reserve + 25_000, rent_reserve), 25_000);
+        // Nothing collected yet
+        assert_eq!(sweepable_lamports(rent_reserve, rent_reserve), 0);
+    }
 }
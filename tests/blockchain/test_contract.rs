@@ -1,6 +1,7 @@
 use solana_program_test::*;
 use solana_sdk::{
     account::Account,
+    hash::Hash,
     instruction::{AccountMeta, Instruction},
     pubkey::Pubkey,
     signature::{Keypair, Signer},
@@ -11,7 +12,8 @@ use std::str::FromStr;
 use ontora_ai_program::processor::process_instruction;
 use ontora_ai_program::state::AiAgentState;
 
-async fn setup_test_environment() -> Result<(ProgramTest, Keypair, Pubkey), TransportError> {
+// Start the program once per test; `ProgramTest::start` consumes the builder
+async fn setup_test_environment() -> Result<(BanksClient, Keypair, Hash, Pubkey), TransportError> {
     let program_id = Pubkey::from_str("YourProgramIdHere11111111111111111111111111111").unwrap();
     let payer = Keypair::new();
     let mut program_test = ProgramTest::new(
@@ -31,15 +33,13 @@ async fn setup_test_environment() -> Result<(ProgramTest, Keypair, Pubkey), Tran
         },
     );
 
-    let (banks_client, payer, recent_blockhash) = program_test.start().await;
-    Ok((program_test, payer, program_id))
+    let (banks_client, _, recent_blockhash) = program_test.start().await;
+    Ok((banks_client, payer, recent_blockhash, program_id))
 }
 
 #[tokio::test]
 async fn test_initialize_ai_agent() {
-    let (program_test, payer, program_id) = setup_test_environment().await.unwrap();
-    let mut banks_client = program_test.start().await.0;
-    let recent_blockhash = program_test.start().await.2;
+    let (mut banks_client, payer, recent_blockhash, program_id) = setup_test_environment().await.unwrap();
 
     let agent_account = Keypair::new();
     let agent_account_pubkey = agent_account.pubkey();
@@ -80,9 +80,7 @@ async fn test_initialize_ai_agent() {
 
 #[tokio::test]
 async fn test_update_ai_agent_config() {
-    let (program_test, payer, program_id) = setup_test_environment().await.unwrap();
-    let mut banks_client = program_test.start().await.0;
-    let recent_blockhash = program_test.start().await.2;
+    let (mut banks_client, payer, recent_blockhash, program_id) = setup_test_environment().await.unwrap();
 
     let agent_account = Keypair::new();
     let agent_account_pubkey = agent_account.pubkey();
@@ -151,9 +149,7 @@ async fn test_update_ai_agent_config() {
 
 #[tokio::test]
 async fn test_unauthorized_access() {
-    let (program_test, payer, program_id) = setup_test_environment().await.unwrap();
-    let mut banks_client = program_test.start().await.0;
-    let recent_blockhash = program_test.start().await.2;
+    let (mut banks_client, payer, recent_blockhash, program_id) = setup_test_environment().await.unwrap();
 
     let agent_account = Keypair::new();
     let agent_account_pubkey = agent_account.pubkey();
@@ -211,9 +207,7 @@ async fn test_unauthorized_access() {
 
 #[tokio::test]
 async fn test_invalid_instruction_data() {
-    let (program_test, payer, program_id) = setup_test_environment().await.unwrap();
-    let mut banks_client = program_test.start().await.0;
-    let recent_blockhash = program_test.start().await.2;
+    let (mut banks_client, payer, recent_blockhash, program_id) = setup_test_environment().await.unwrap();
 
     let agent_account = Keypair::new();
     let agent_account_pubkey = agent_account.pubkey();
@@ -244,9 +238,7 @@ async fn test_invalid_instruction_data() {
 
 #[tokio::test]
 async fn test_multiple_agents_initialization() {
-    let (program_test, payer, program_id) = setup_test_environment().await.unwrap();
-    let mut banks_client = program_test.start().await.0;
-    let recent_blockhash = program_test.start().await.2;
+    let (mut banks_client, payer, recent_blockhash, program_id) = setup_test_environment().await.unwrap();
 
     let agent1_account = Keypair::new();
     let agent1_account_pubkey = agent1_account.pubkey();